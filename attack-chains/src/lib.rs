@@ -0,0 +1,118 @@
+//! # Attack Chains
+//!
+//! A scenario runner for exploits that span several patterns' programs.
+//!
+//! Each pattern's tests run its program alone, one instruction at a time,
+//! and stand in for anything outside it: pattern 15's attacker "has" 3,000
+//! SOL of flash-borrowed capital, and pattern 188's wallet "is" flash
+//! funded. Here those pieces are real. [`Chain`] loads several programs into
+//! one Mollusk instance and runs a list of instructions as one transaction:
+//!
+//! - every instruction sees the accounts the previous ones left
+//! - an instruction that names the instructions sysvar gets one describing
+//!   the whole transaction, with itself as the current instruction
+//! - if any instruction fails, none of the transaction's changes are kept
+//! - accounts left with zero lamports are deleted when it ends
+//!
+//! Accounts a transaction names but the ledger doesn't hold are passed as
+//! empty System Program accounts, as the runtime does.
+//!
+//! The ledger and the account fixtures are `common/test-harness`'s, the
+//! same ones the pattern tests use.
+
+use mollusk_svm::Mollusk;
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    sysvar,
+};
+use test_harness::{executable_account, instructions_sysvar, Accounts, Ledger};
+
+/// The instruction that failed a transaction, by index, and its error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionError {
+    pub index: usize,
+    pub error: InstructionError,
+}
+
+/// Programs loaded into one Mollusk instance, and the accounts they share.
+pub struct Chain {
+    pub mollusk: Mollusk,
+    ledger: Ledger,
+}
+
+impl Chain {
+    /// Load each `(program id, ELF name)` from `SBF_OUT_DIR`. The programs
+    /// and the System Program start in the ledger as executable accounts.
+    pub fn new(programs: &[(Pubkey, &str)]) -> Self {
+        let (first, rest) = programs.split_first().expect("at least one program");
+        let mut mollusk = Mollusk::new(&first.0, first.1);
+        for (id, name) in rest {
+            mollusk.add_program(id, name, &solana_sdk::bpf_loader_upgradeable::id());
+        }
+
+        let mut ledger = Ledger::new(vec![(
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        )]);
+        for (id, _) in programs {
+            ledger.set(
+                *id,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            );
+        }
+
+        Self { mollusk, ledger }
+    }
+
+    /// Add `account` at `key`, or replace the account already there.
+    pub fn set_account(&mut self, key: Pubkey, account: AccountSharedData) {
+        self.ledger.set(key, account);
+    }
+
+    /// The account at `key`, or an empty one if the ledger has none.
+    pub fn account(&self, key: &Pubkey) -> AccountSharedData {
+        self.ledger.get(key).cloned().unwrap_or_default()
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.account(key).lamports()
+    }
+
+    /// Run `ixs` as one transaction. On success the ledger keeps every
+    /// change; on failure it is left as it was.
+    pub fn transaction(&mut self, ixs: &[Instruction]) -> Result<(), TransactionError> {
+        let mut ledger = self.ledger.clone();
+
+        for (index, ix) in ixs.iter().enumerate() {
+            let accounts: Accounts = ix
+                .accounts
+                .iter()
+                .map(|meta| {
+                    let account = if meta.pubkey == sysvar::instructions::ID {
+                        instructions_sysvar(ixs, index as u16)
+                    } else {
+                        ledger.get(&meta.pubkey).cloned().unwrap_or_default()
+                    };
+                    (meta.pubkey, account)
+                })
+                .collect();
+
+            let result = self.mollusk.process_instruction(ix, &accounts);
+            if let Err(error) = result.raw_result {
+                return Err(TransactionError { index, error });
+            }
+            for (key, account) in result.resulting_accounts {
+                if key == sysvar::instructions::ID {
+                    continue;
+                }
+                ledger.set(key, account);
+            }
+        }
+
+        ledger.collect_garbage();
+        self.ledger = ledger;
+        Ok(())
+    }
+}
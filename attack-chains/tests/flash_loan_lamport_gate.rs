@@ -0,0 +1,282 @@
+// Mollusk tests for the attack chain of patterns 13 and 188
+/// # Attack Chain: Flash Loan → Lamport-Gated Access
+///
+/// Pattern 188's exploit test hands the attacker a wallet that "was" flash
+/// funded to 100 SOL. Here pattern 13's flash-loan pool funds it, with its
+/// secure, repayment-checked borrow, and the loan is repaid in the same
+/// transaction that passes the gate.
+///
+/// The flash pool holds 100 SOL at a 0.09% fee. The service's premium gate
+/// is a 100 SOL balance; its entitlement costs 0.5 SOL. The attacker has
+/// 1 SOL.
+///
+/// - Test 1: EXPLOIT — flash borrow, premium_action_vulnerable, repay, in
+///   one transaction: a premium call for the 0.09 SOL fee, and nothing paid
+///   to the treasury.
+/// - Test 2: SECURE — the same transaction with premium_action_secure fails
+///   at the gate, and the flash loan is undone with it.
+/// - Test 3: SANITY — purchase_entitlement then premium_action_secure pays
+///   the price to the treasury, with no flash loan needed.
+use attack_chains::{Chain, TransactionError};
+use solana_sdk::{
+    account::ReadableAccount,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    sysvar,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, system_account};
+
+const FLASH_ID: Pubkey = solana_sdk::pubkey!("6PznHVALBahrkdd7PSiptsX3ruZhDNK6rYwFGPv9Dpj2");
+const GATE_ID: Pubkey = solana_sdk::pubkey!("9qUVYrUmGCxz8oHaoZRXD7KiAhNGPY3wX9oJhC9e12Hf");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = SOL;
+
+/// Pattern 13's pool.
+const FLASH_LIQUIDITY: u64 = 100 * SOL;
+const FEE_BPS: u16 = 9;
+const FEE: u64 = FLASH_LIQUIDITY * FEE_BPS as u64 / 10_000;
+
+/// Pattern 188's service.
+const PRICE: u64 = SOL / 2;
+
+// Error codes
+const ACCOUNT_NOT_INITIALIZED: u32 = 3012; // Anchor: no entitlement
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize pattern 13's Pool with no loan open:
+/// [8 disc][32 admin][2 fee_bps][1 loan_pending][8 pending_repayment][1 bump]
+fn serialize_flash_pool(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(52);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&FEE_BPS.to_le_bytes());
+    data.push(0);
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize pattern 188's Service:
+/// [8 disc][32 treasury][8 price][8 premium_calls][1 bump]
+fn serialize_service(treasury: &Pubkey, premium_calls: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(57);
+    data.extend_from_slice(&account_discriminator("Service"));
+    data.extend_from_slice(treasury.as_ref());
+    data.extend_from_slice(&PRICE.to_le_bytes());
+    data.extend_from_slice(&premium_calls.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+struct Setup {
+    chain: Chain,
+    flash_pool: Pubkey,
+    service: Pubkey,
+    service_bump: u8,
+    treasury: Pubkey,
+    entitlement: Pubkey,
+    attacker: Pubkey,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mut chain = Chain::new(&[
+            (FLASH_ID, "flash_loan_introspection"),
+            (GATE_ID, "lamport_gate"),
+        ]);
+
+        let admin = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let (flash_pool, flash_bump) = Pubkey::find_program_address(&[b"pool"], &FLASH_ID);
+        let (service, service_bump) = Pubkey::find_program_address(&[b"service"], &GATE_ID);
+        let (entitlement, _) =
+            Pubkey::find_program_address(&[b"entitlement", attacker.as_ref()], &GATE_ID);
+
+        chain.set_account(
+            flash_pool,
+            owned_account(
+                &serialize_flash_pool(&admin, flash_bump),
+                RENT + FLASH_LIQUIDITY,
+                &FLASH_ID,
+            ),
+        );
+        chain.set_account(
+            service,
+            owned_account(
+                &serialize_service(&treasury, 0, service_bump),
+                RENT,
+                &GATE_ID,
+            ),
+        );
+        chain.set_account(treasury, system_account(RENT));
+        chain.set_account(attacker, system_account(WALLET));
+
+        Self {
+            chain,
+            flash_pool,
+            service,
+            service_bump,
+            treasury,
+            entitlement,
+            attacker,
+        }
+    }
+
+    /// Pattern 13: flash_borrow_secure(amount).
+    fn flash_borrow(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("flash_borrow_secure").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            FLASH_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.flash_pool, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            ],
+        )
+    }
+
+    /// Pattern 13: flash_repay(amount).
+    fn flash_repay(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("flash_repay").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            FLASH_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.flash_pool, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// Pattern 188: premium_action_vulnerable.
+    fn premium_vulnerable(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            GATE_ID,
+            &ix_discriminator("premium_action_vulnerable"),
+            vec![
+                AccountMeta::new(self.service, false),
+                AccountMeta::new_readonly(self.attacker, true),
+            ],
+        )
+    }
+
+    /// Pattern 188: premium_action_secure.
+    fn premium_secure(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            GATE_ID,
+            &ix_discriminator("premium_action_secure"),
+            vec![
+                AccountMeta::new(self.service, false),
+                AccountMeta::new_readonly(self.entitlement, false),
+                AccountMeta::new_readonly(self.attacker, true),
+            ],
+        )
+    }
+
+    /// Pattern 188: purchase_entitlement.
+    fn purchase_entitlement(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            GATE_ID,
+            &ix_discriminator("purchase_entitlement"),
+            vec![
+                AccountMeta::new_readonly(self.service, false),
+                AccountMeta::new(self.entitlement, false),
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn service_data(&self, premium_calls: u64) -> Vec<u8> {
+        serialize_service(&self.treasury, premium_calls, self.service_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_flash_loan_passes_balance_gate() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: one transaction
+    //   0. Pattern 13:  flash_borrow_secure(100 SOL)  wallet 1 → 101 SOL
+    //   1. Pattern 188: premium_action_vulnerable     101 SOL ≥ 100 SOL
+    //   2. Pattern 13:  flash_repay(100.09 SOL)
+    //
+    // Expected: SUCCEEDS. One premium call is recorded, the attacker paid
+    //           the 0.09 SOL fee, and the treasury got nothing.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let ixs = [
+        s.flash_borrow(FLASH_LIQUIDITY),
+        s.premium_vulnerable(),
+        s.flash_repay(FLASH_LIQUIDITY + FEE),
+    ];
+
+    assert_eq!(s.chain.transaction(&ixs), Ok(()));
+    assert_eq!(s.chain.account(&s.service).data(), s.service_data(1));
+    assert_eq!(s.chain.lamports(&s.attacker), WALLET - FEE);
+    assert_eq!(
+        s.chain.lamports(&s.flash_pool),
+        RENT + FLASH_LIQUIDITY + FEE
+    );
+    assert_eq!(s.chain.lamports(&s.treasury), RENT);
+}
+
+#[test]
+fn secure_entitlement_gate_breaks_chain() {
+    // -----------------------------------------------------------------------
+    // SECURE: the same transaction with premium_action_secure at step 1
+    //
+    // Expected: FAILS at instruction 1 with AccountNotInitialized (3012):
+    //           the attacker never bought an entitlement, and borrowed
+    //           lamports can't stand in for one. The flash loan reverts
+    //           with the rest of the transaction.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let ixs = [
+        s.flash_borrow(FLASH_LIQUIDITY),
+        s.premium_secure(),
+        s.flash_repay(FLASH_LIQUIDITY + FEE),
+    ];
+
+    assert_eq!(
+        s.chain.transaction(&ixs),
+        Err(TransactionError {
+            index: 1,
+            error: InstructionError::Custom(ACCOUNT_NOT_INITIALIZED),
+        })
+    );
+    assert_eq!(s.chain.account(&s.service).data(), s.service_data(0));
+    assert_eq!(s.chain.lamports(&s.attacker), WALLET);
+    assert_eq!(s.chain.lamports(&s.flash_pool), RENT + FLASH_LIQUIDITY);
+}
+
+#[test]
+fn sanity_purchased_entitlement_passes() {
+    // -----------------------------------------------------------------------
+    // SANITY: purchase_entitlement, then premium_action_secure, from the
+    //         attacker's own 1 SOL
+    //
+    // Expected: SUCCEEDS. The treasury receives the 0.5 SOL price and one
+    //           premium call is recorded.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let ixs = [s.purchase_entitlement(), s.premium_secure()];
+
+    assert_eq!(s.chain.transaction(&ixs), Ok(()));
+    assert_eq!(s.chain.account(&s.service).data(), s.service_data(1));
+    assert_eq!(s.chain.lamports(&s.treasury), RENT + PRICE);
+}
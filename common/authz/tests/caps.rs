@@ -0,0 +1,180 @@
+/// # Authz — capability tests
+///
+/// - A cap is only issued when the recorded authority signed
+/// - `AdminCap` is bound to the account it was verified against
+/// - `VaultWithdrawCap` moves lamports, and refuses to overdraw the vault or
+///   pay the vault itself
+use anchor_lang::prelude::*;
+use authz::{AdminCap, AuthzError, HasAuthority, VaultWithdrawCap};
+
+declare_id!("AuthzTest1111111111111111111111111111111111");
+
+#[account]
+pub struct Vault {
+    pub authority: Pubkey,
+}
+
+impl HasAuthority for Vault {
+    fn authority(&self) -> Pubkey {
+        self.authority
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Backing storage for one `AccountInfo`.
+struct Fixture {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+}
+
+impl Fixture {
+    fn vault(authority: Pubkey, lamports: u64) -> Self {
+        let mut data = Vec::new();
+        Vault { authority }.try_serialize(&mut data).unwrap();
+        Self {
+            key: Pubkey::new_unique(),
+            owner: ID,
+            lamports,
+            data,
+            is_signer: false,
+        }
+    }
+
+    fn wallet(key: Pubkey, lamports: u64, is_signer: bool) -> Self {
+        Self {
+            key,
+            owner: anchor_lang::system_program::ID,
+            lamports,
+            data: Vec::new(),
+            is_signer,
+        }
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            true,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            false,
+            0,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn admin_cap_requires_recorded_authority() {
+    let admin = Pubkey::new_unique();
+    let mut vault = Fixture::vault(admin, 0);
+    let mut attacker = Fixture::wallet(Pubkey::new_unique(), 0, true);
+    let mut signer = Fixture::wallet(admin, 0, true);
+
+    let vault_info = vault.info();
+    let vault_account = Account::<Vault>::try_from(&vault_info).unwrap();
+    let attacker_info = attacker.info();
+    let attacker_signer = Signer::try_from(&attacker_info).unwrap();
+    let signer_info = signer.info();
+    let admin_signer = Signer::try_from(&signer_info).unwrap();
+
+    assert_eq!(
+        AdminCap::verify(&vault_account, &attacker_signer).unwrap_err(),
+        AuthzError::WrongAuthority
+    );
+
+    let cap = AdminCap::verify(&vault_account, &admin_signer).unwrap();
+    assert_eq!(cap.authority(), admin);
+    assert_eq!(cap.scope(), vault_account.key());
+}
+
+#[test]
+fn admin_cap_is_bound_to_its_account() {
+    let admin = Pubkey::new_unique();
+    let mut vault = Fixture::vault(admin, 0);
+    let mut signer = Fixture::wallet(admin, 0, true);
+
+    let vault_info = vault.info();
+    let vault_account = Account::<Vault>::try_from(&vault_info).unwrap();
+    let signer_info = signer.info();
+    let admin_signer = Signer::try_from(&signer_info).unwrap();
+
+    let cap = AdminCap::verify(&vault_account, &admin_signer).unwrap();
+    assert_eq!(cap.require_scope(&vault_account.key()), Ok(()));
+    assert_eq!(
+        cap.require_scope(&Pubkey::new_unique()),
+        Err(AuthzError::WrongScope)
+    );
+}
+
+#[test]
+fn vault_cap_withdraws_for_authority_only() {
+    let authority = Pubkey::new_unique();
+    let mut vault = Fixture::vault(authority, 10);
+    let mut attacker = Fixture::wallet(Pubkey::new_unique(), 0, true);
+    let mut signer = Fixture::wallet(authority, 0, true);
+
+    let vault_info = vault.info();
+    let vault_account = Account::<Vault>::try_from(&vault_info).unwrap();
+    let attacker_info = attacker.info();
+    let attacker_signer = Signer::try_from(&attacker_info).unwrap();
+    let signer_info = signer.info();
+    let authority_signer = Signer::try_from(&signer_info).unwrap();
+
+    assert_eq!(
+        VaultWithdrawCap::verify(&vault_account, &attacker_signer).err(),
+        Some(AuthzError::WrongAuthority)
+    );
+
+    let cap = VaultWithdrawCap::verify(&vault_account, &authority_signer).unwrap();
+    assert_eq!(cap.vault(), vault_account.key());
+    cap.withdraw(&signer_info, 4).unwrap();
+    assert_eq!(vault_info.lamports(), 6);
+    assert_eq!(signer_info.lamports(), 4);
+}
+
+#[test]
+fn vault_cap_refuses_overdraw() {
+    let authority = Pubkey::new_unique();
+    let mut vault = Fixture::vault(authority, 10);
+    let mut signer = Fixture::wallet(authority, 0, true);
+
+    let vault_info = vault.info();
+    let vault_account = Account::<Vault>::try_from(&vault_info).unwrap();
+    let signer_info = signer.info();
+    let authority_signer = Signer::try_from(&signer_info).unwrap();
+
+    let cap = VaultWithdrawCap::verify(&vault_account, &authority_signer).unwrap();
+    assert_eq!(
+        cap.withdraw(&signer_info, 11),
+        Err(AuthzError::InsufficientFunds)
+    );
+    assert_eq!(vault_info.lamports(), 10);
+    assert_eq!(signer_info.lamports(), 0);
+}
+
+#[test]
+fn vault_cap_refuses_self_transfer() {
+    let authority = Pubkey::new_unique();
+    let mut vault = Fixture::vault(authority, 10);
+    let mut signer = Fixture::wallet(authority, 0, true);
+
+    let vault_info = vault.info();
+    let vault_account = Account::<Vault>::try_from(&vault_info).unwrap();
+    let signer_info = signer.info();
+    let authority_signer = Signer::try_from(&signer_info).unwrap();
+
+    let cap = VaultWithdrawCap::verify(&vault_account, &authority_signer).unwrap();
+    assert_eq!(cap.withdraw(&vault_info, 4), Err(AuthzError::SelfTransfer));
+    assert_eq!(vault_info.lamports(), 10);
+}
@@ -0,0 +1,82 @@
+//! # CPI Guard
+//!
+//! Checks on how the executing instruction was reached: by the transaction
+//! itself, or through CPI, and from which program.
+//!
+//! - [`require_top_level`]: the instruction is one of the transaction's
+//!   top-level instructions. Pattern 214's `flash_borrow_secure` uses it.
+//! - [`require_called_by`]: the instruction was invoked by CPI, directly
+//!   from a top-level instruction of a given program.
+//!
+//! ## Why the stack height
+//!
+//! The instructions sysvar describes the transaction's top-level
+//! instructions, not the call stack. Under CPI, "the current instruction"
+//! in the sysvar is the top-level instruction that started the chain, so
+//! "the current instruction belongs to this program" is just as true for a
+//! CPI the program made into itself (pattern 214). `get_stack_height()` is
+//! kept by the runtime: the transaction's instructions run at
+//! `TRANSACTION_LEVEL_STACK_HEIGHT` and each CPI one level deeper, whichever
+//! program makes it.
+//!
+//! [`require_called_by`] uses both. One level below the transaction, the
+//! caller is the current top-level instruction, so the sysvar names it.
+//! Further down, the caller is a CPI the sysvar doesn't record, and a
+//! trusted program at the top could have called a tunnelling program that
+//! called this one. Those calls are refused rather than attributed to the
+//! top-level program.
+//!
+//! A caller check proves which program made the call, not why. A program
+//! with a generic executor makes whatever calls its users ask for, so it
+//! vouches for everyone.
+//!
+//! Errors are [`CpiGuardError`], which callers map onto their own error codes.
+
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpiGuardError {
+    /// The instruction was reached through CPI.
+    NotTopLevel,
+    /// The instruction wasn't invoked by a top-level instruction: it is one,
+    /// or it's further down a chain of CPIs.
+    NotDirectCpi,
+    /// The top-level instruction that made the call belongs to another
+    /// program.
+    WrongCaller,
+    /// The account passed as the instructions sysvar isn't it.
+    InvalidSysvar,
+}
+
+/// Fails unless the executing instruction is a top-level instruction of the
+/// transaction.
+pub fn require_top_level() -> Result<(), CpiGuardError> {
+    if get_stack_height() != TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Err(CpiGuardError::NotTopLevel);
+    }
+    Ok(())
+}
+
+/// Fails unless the executing instruction was invoked by CPI directly from a
+/// top-level instruction of `program_id`. `instructions` must be the
+/// instructions sysvar.
+pub fn require_called_by(
+    instructions: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<(), CpiGuardError> {
+    if get_stack_height() != TRANSACTION_LEVEL_STACK_HEIGHT + 1 {
+        return Err(CpiGuardError::NotDirectCpi);
+    }
+    let index =
+        load_current_index_checked(instructions).map_err(|_| CpiGuardError::InvalidSysvar)?;
+    let caller = load_instruction_at_checked(index as usize, instructions)
+        .map_err(|_| CpiGuardError::InvalidSysvar)?;
+    if caller.program_id != *program_id {
+        return Err(CpiGuardError::WrongCaller);
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+/// # CPI Guard — call-site tests
+///
+/// Off-chain, `get_stack_height()` goes through the syscall stubs, so each
+/// test sets the height it runs at. The instructions sysvar is built by hand
+/// for the transaction the call is part of.
+///
+/// - `require_top_level` passes direct calls and refuses every CPI,
+///   including one from the program into itself
+/// - `require_called_by` passes a direct CPI from the named program, and
+///   refuses direct calls, other callers, calls tunnelled through another
+///   program, and a fake sysvar
+use std::cell::Cell;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::sysvar;
+use cpi_guard::{require_called_by, require_top_level, CpiGuardError};
+use test_harness::instructions_sysvar_data;
+
+thread_local! {
+    static STACK_HEIGHT: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Answers `sol_get_stack_height` with the calling thread's height, so
+/// tests running in parallel don't see each other's.
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_get_stack_height(&self) -> u64 {
+        STACK_HEIGHT.with(Cell::get)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Top-level instructions run at height 1, each CPI one deeper.
+fn at_height(height: u64) {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(Stubs));
+    });
+    STACK_HEIGHT.with(|cell| cell.set(height));
+}
+
+fn call(program_id: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(program_id, &[0], vec![])
+}
+
+/// Backing storage for the instructions sysvar's `AccountInfo`.
+struct Fixture {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+}
+
+impl Fixture {
+    fn instructions(ixs: &[Instruction], current: u16) -> Self {
+        Self {
+            key: sysvar::instructions::ID,
+            owner: sysvar::ID,
+            lamports: 0,
+            data: instructions_sysvar_data(ixs, current),
+        }
+    }
+
+    /// The same data at an address of the caller's choosing.
+    fn fake_instructions(ixs: &[Instruction], current: u16) -> Self {
+        Self {
+            key: Pubkey::new_unique(),
+            ..Self::instructions(ixs, current)
+        }
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            false,
+            false,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            false,
+            0,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn top_level_passes_direct_call() {
+    at_height(1);
+    assert_eq!(require_top_level(), Ok(()));
+}
+
+#[test]
+fn top_level_refuses_cpi() {
+    // One level down covers both a CPI from another program and one from
+    // this program into itself; the height is the same. Two levels down is
+    // a call tunnelled through a second program.
+    for height in [2, 3] {
+        at_height(height);
+        assert_eq!(require_top_level(), Err(CpiGuardError::NotTopLevel));
+    }
+}
+
+#[test]
+fn called_by_passes_direct_cpi_from_program() {
+    let trusted = Pubkey::new_unique();
+    let mut sysvar = Fixture::instructions(&[call(Pubkey::new_unique()), call(trusted)], 1);
+
+    at_height(2);
+    assert_eq!(require_called_by(&sysvar.info(), &trusted), Ok(()));
+}
+
+#[test]
+fn called_by_refuses_direct_call() {
+    let trusted = Pubkey::new_unique();
+    // At the top level, the sysvar's current instruction is the guarded one.
+    let mut sysvar = Fixture::instructions(&[call(Pubkey::new_unique())], 0);
+
+    at_height(1);
+    assert_eq!(
+        require_called_by(&sysvar.info(), &trusted),
+        Err(CpiGuardError::NotDirectCpi)
+    );
+}
+
+#[test]
+fn called_by_refuses_other_caller() {
+    let trusted = Pubkey::new_unique();
+    let attacker = Pubkey::new_unique();
+    let mut sysvar = Fixture::instructions(&[call(attacker)], 0);
+
+    at_height(2);
+    assert_eq!(
+        require_called_by(&sysvar.info(), &trusted),
+        Err(CpiGuardError::WrongCaller)
+    );
+}
+
+#[test]
+fn called_by_refuses_tunnelled_call() {
+    // trusted (top level) → attacker → guarded: the sysvar only shows the
+    // trusted program, but the caller is the attacker's.
+    let trusted = Pubkey::new_unique();
+    let mut sysvar = Fixture::instructions(&[call(trusted)], 0);
+
+    at_height(3);
+    assert_eq!(
+        require_called_by(&sysvar.info(), &trusted),
+        Err(CpiGuardError::NotDirectCpi)
+    );
+}
+
+#[test]
+fn called_by_refuses_fake_sysvar() {
+    let trusted = Pubkey::new_unique();
+    let mut fake = Fixture::fake_instructions(&[call(trusted)], 0);
+
+    at_height(2);
+    assert_eq!(
+        require_called_by(&fake.info(), &trusted),
+        Err(CpiGuardError::InvalidSysvar)
+    );
+}
@@ -0,0 +1,67 @@
+//! Program log capture for Mollusk tests, behind the `capture` feature.
+//!
+//! Mollusk doesn't return an instruction's logs, but the runtime also
+//! writes every program log line to the `log` crate, at debug level under
+//! [`STABLE_LOG`]. [`start`] installs a logger that keeps those lines for
+//! the calling thread; [`events`] parses what it kept. Tests run on
+//! parallel threads and each instruction runs on its caller's, so a test
+//! only sees its own lines.
+//!
+//! ```ignore
+//! demo_log::capture::start();
+//! mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+//! let events = demo_log::capture::events();
+//! assert_eq!(events[0].amount(), Some(5_000_000_000));
+//! ```
+
+use std::cell::RefCell;
+use std::sync::Once;
+
+use crate::{parse_logs, DemoEvent};
+
+/// The `log` target the runtime writes program log lines to.
+pub const STABLE_LOG: &str = "solana_runtime::message_processor::stable_log";
+
+thread_local! {
+    static LINES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Capture;
+
+impl log::Log for Capture {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == STABLE_LOG
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            LINES.with(|lines| lines.borrow_mut().push(record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture;
+
+/// Install the logger, once per process, and forget this thread's lines so
+/// far. Panics if another logger was installed first.
+pub fn start() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        log::set_logger(&CAPTURE).expect("another logger is installed");
+        log::set_max_level(log::LevelFilter::Debug);
+    });
+    LINES.with(|lines| lines.borrow_mut().clear());
+}
+
+/// This thread's log lines since [`start`] or the last call, as logged.
+pub fn lines() -> Vec<String> {
+    LINES.with(|lines| lines.borrow_mut().drain(..).collect())
+}
+
+/// The demo events among this thread's log lines since [`start`] or the
+/// last call.
+pub fn events() -> Vec<DemoEvent> {
+    parse_logs(&lines())
+}
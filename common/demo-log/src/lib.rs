@@ -0,0 +1,183 @@
+//! # Demo Log
+//!
+//! One log line format for every pattern program, so an exploit run can be
+//! read back as a trace instead of grepped for free-form strings:
+//!
+//! ```text
+//! Program log: demo: mode=vulnerable action=withdraw actor=7xKX… amount=5000000000
+//! ```
+//!
+//! Programs emit lines with [`demo_log!`]. Off-chain code turns program logs
+//! back into [`DemoEvent`]s with [`DemoEvent::parse`] or [`parse_logs`].
+//! Mollusk tests get the lines from `capture`, behind the `capture` feature.
+//!
+//! Values are written with `Display` and must not contain spaces. Pubkeys,
+//! integers, and bools are all fine.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "capture")]
+pub mod capture;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use solana_msg::msg;
+}
+
+/// Marks a program log line as a demo event. Kept in sync with
+/// [`demo_line!`].
+pub const PREFIX: &str = "demo: ";
+
+/// The runtime's prefix on `sol_log` output.
+const PROGRAM_LOG: &str = "Program log: ";
+
+/// Which side of the demonstration emitted the event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Ordinary state set-up shared by both paths (initialize, deposit, ...).
+    Setup,
+    /// The vulnerable instruction ran to completion.
+    Vulnerable,
+    /// The secure instruction ran to completion.
+    Secure,
+    /// An attacker-controlled program, e.g. a malicious CPI target.
+    Attacker,
+}
+
+impl Mode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mode::Setup => "setup",
+            Mode::Vulnerable => "vulnerable",
+            Mode::Secure => "secure",
+            Mode::Attacker => "attacker",
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Mode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "setup" => Ok(Mode::Setup),
+            "vulnerable" => Ok(Mode::Vulnerable),
+            "secure" => Ok(Mode::Secure),
+            "attacker" => Ok(Mode::Attacker),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Format a demo event line without logging it.
+///
+/// ```
+/// use demo_log::{demo_line, Mode};
+///
+/// let line = demo_line!(Mode::Secure, "withdraw", amount = 5u64);
+/// assert_eq!(line, "demo: mode=secure action=withdraw amount=5");
+/// ```
+#[macro_export]
+macro_rules! demo_line {
+    ($mode:expr, $action:literal $(, $key:ident = $value:expr)* $(,)?) => {
+        format!(
+            concat!("demo: mode={} action=", $action $(, " ", stringify!($key), "={}")*),
+            $mode $(, $value)*
+        )
+    };
+}
+
+/// Log a demo event: the mode, an action name, then `key = value` fields in
+/// the order given. By convention `actor` and `amount` come first when the
+/// event has them.
+///
+/// ```
+/// use demo_log::{demo_log, Mode};
+///
+/// let (payer, amount) = ("7xKX", 5_000_000_000u64);
+/// demo_log!(Mode::Vulnerable, "withdraw", actor = payer, amount = amount);
+/// ```
+#[macro_export]
+macro_rules! demo_log {
+    ($($args:tt)*) => {
+        $crate::__private::msg!(&$crate::demo_line!($($args)*))
+    };
+}
+
+/// A parsed demo log line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DemoEvent {
+    pub mode: Mode,
+    pub action: String,
+    /// Every field after `action`, in emitted order.
+    pub fields: Vec<(String, String)>,
+}
+
+impl DemoEvent {
+    /// Parse one log line, with or without the runtime's `Program log: `
+    /// prefix. Returns `None` for lines that aren't demo events.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.strip_prefix(PROGRAM_LOG).unwrap_or(line);
+        let mut pairs = line.strip_prefix(PREFIX)?.split(' ').map(|pair| {
+            pair.split_once('=')
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+        });
+
+        let mode = match pairs.next()?? {
+            (key, value) if key == "mode" => value.parse().ok()?,
+            _ => return None,
+        };
+        let action = match pairs.next()?? {
+            (key, value) if key == "action" => value,
+            _ => return None,
+        };
+        let fields = pairs.collect::<Option<Vec<_>>>()?;
+
+        Some(DemoEvent {
+            mode,
+            action,
+            fields,
+        })
+    }
+
+    /// The first value logged under `key`.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn actor(&self) -> Option<&str> {
+        self.field("actor")
+    }
+
+    pub fn amount(&self) -> Option<u64> {
+        self.field("amount")?.parse().ok()
+    }
+}
+
+impl fmt::Display for DemoEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{PREFIX}mode={} action={}", self.mode, self.action)?;
+        for (key, value) in &self.fields {
+            write!(f, " {key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Every demo event in a transaction's logs, in order. Other log lines are
+/// skipped.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Vec<DemoEvent> {
+    logs.iter()
+        .filter_map(|line| DemoEvent::parse(line.as_ref()))
+        .collect()
+}
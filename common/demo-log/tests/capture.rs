@@ -0,0 +1,51 @@
+#![cfg(feature = "capture")]
+/// # Demo Log — capture tests (`--features capture`)
+///
+/// - Lines the runtime logs under `STABLE_LOG` are kept and parsed
+/// - Other targets are ignored
+/// - Reading drains, and `start` forgets earlier lines
+/// - Each thread sees only its own lines
+use demo_log::capture::{self, STABLE_LOG};
+use demo_log::Mode;
+
+fn program_log(line: &str) {
+    log::debug!(target: STABLE_LOG, "Program log: {line}");
+}
+
+#[test]
+fn keeps_runtime_lines() {
+    capture::start();
+    log::debug!(target: STABLE_LOG, "Program 11111111111111111111111111111111 invoke [1]");
+    program_log("demo: mode=vulnerable action=withdraw amount=5");
+    log::debug!(target: "other", "Program log: demo: mode=secure action=withdraw amount=6");
+
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Vulnerable);
+    assert_eq!(events[0].amount(), Some(5));
+    assert!(capture::lines().is_empty());
+
+    program_log("demo: mode=setup action=deposit");
+    capture::start();
+    assert!(capture::events().is_empty());
+}
+
+#[test]
+fn threads_are_separate() {
+    capture::start();
+    program_log("demo: mode=secure action=withdraw amount=1");
+
+    std::thread::spawn(|| {
+        capture::start();
+        program_log("demo: mode=attacker action=hook amount=2");
+        assert_eq!(capture::events()[0].amount(), Some(2));
+    })
+    .join()
+    .unwrap();
+
+    let lines = capture::lines();
+    assert_eq!(
+        lines,
+        vec!["Program log: demo: mode=secure action=withdraw amount=1"]
+    );
+}
@@ -0,0 +1,67 @@
+/// # Demo Log — format and parse tests
+///
+/// - What `demo_line!` writes, `DemoEvent::parse` reads back
+/// - The runtime's `Program log: ` prefix is accepted
+/// - Non-demo and malformed lines are skipped, not misread
+use demo_log::{demo_line, parse_logs, DemoEvent, Mode};
+
+#[test]
+fn line_round_trips() {
+    let actor = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+    let line = demo_line!(
+        Mode::Vulnerable,
+        "withdraw",
+        actor = actor,
+        amount = 5_000_000_000u64,
+        signed = false,
+    );
+    assert_eq!(
+        line,
+        format!("demo: mode=vulnerable action=withdraw actor={actor} amount=5000000000 signed=false")
+    );
+
+    let event = DemoEvent::parse(&line).unwrap();
+    assert_eq!(event.mode, Mode::Vulnerable);
+    assert_eq!(event.action, "withdraw");
+    assert_eq!(event.actor(), Some(actor));
+    assert_eq!(event.amount(), Some(5_000_000_000));
+    assert_eq!(event.field("signed"), Some("false"));
+    assert_eq!(event.to_string(), line);
+}
+
+#[test]
+fn action_without_fields() {
+    let line = demo_line!(Mode::Setup, "initialize");
+    let event = DemoEvent::parse(&line).unwrap();
+    assert_eq!(event.mode, Mode::Setup);
+    assert_eq!(event.action, "initialize");
+    assert!(event.fields.is_empty());
+    assert_eq!(event.amount(), None);
+}
+
+#[test]
+fn program_log_prefix_is_stripped() {
+    let event =
+        DemoEvent::parse("Program log: demo: mode=secure action=claim amount=7").unwrap();
+    assert_eq!(event.mode, Mode::Secure);
+    assert_eq!(event.amount(), Some(7));
+}
+
+#[test]
+fn other_lines_are_skipped() {
+    let logs = [
+        "Program FNUhviqURhXejNitBzyz5p3RXLN8wyFKmZkB8QN2Ss3K invoke [1]",
+        "Program log: Instruction: ClaimVulnerable",
+        "Program log: demo: mode=vulnerable action=claim amount=1000000",
+        "Program log: demo: mode=sideways action=claim",
+        "Program log: demo: action=claim mode=secure",
+        "Program log: demo: mode=secure action=claim amount",
+        "Program log: demo: mode=attacker action=hook amount=1 forwarded=0",
+        "Program FNUhviqURhXejNitBzyz5p3RXLN8wyFKmZkB8QN2Ss3K success",
+    ];
+    let events = parse_logs(&logs);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].mode, Mode::Vulnerable);
+    assert_eq!(events[1].mode, Mode::Attacker);
+    assert_eq!(events[1].field("forwarded"), Some("0"));
+}
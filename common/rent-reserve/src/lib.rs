@@ -0,0 +1,43 @@
+//! # Rent Reserve
+//!
+//! How many lamports a program-owned account can pay out and stay alive.
+//!
+//! - [`withdrawable_lamports`]: the account's balance above the rent-exempt
+//!   minimum for its data. Pattern 219's `withdraw_all_secure` uses it.
+//! - [`require_rent_exempt`]: fails unless the account holds at least that
+//!   minimum. Pattern 43's `withdraw_secure` checks it after moving lamports.
+//!
+//! ## Why the minimum stays
+//!
+//! A data-bearing account's balance is two things: the lamports it holds
+//! for someone, and the rent-exempt minimum that keeps its data on chain.
+//! Only the first is anyone's to withdraw. At the end of a transaction the
+//! runtime deletes every account left with zero lamports, data and all, and
+//! rejects the transaction if an account is left with fewer lamports than
+//! the minimum but more than zero. Paying out `account.lamports()` does the
+//! first; paying out a share of it, rent included, risks the second.
+//!
+//! Pattern 186's `pay_out` and pattern 208's `pay` compute the same floor
+//! inline.
+
+use anchor_lang::prelude::{AccountInfo, ProgramError, Rent};
+use anchor_lang::solana_program::sysvar::Sysvar;
+
+/// The lamports `account` holds above the rent-exempt minimum for its
+/// current data length. Zero if it holds no more than the minimum.
+///
+/// Fails only if the rent sysvar can't be read.
+pub fn withdrawable_lamports(account: &AccountInfo) -> Result<u64, ProgramError> {
+    let reserve = Rent::get()?.minimum_balance(account.data_len());
+    Ok(account.lamports().saturating_sub(reserve))
+}
+
+/// Fail with `AccountNotRentExempt` unless `account` holds at least the
+/// rent-exempt minimum for its current data length. Call it after moving
+/// lamports out of, or reallocating, an account that must stay alive.
+pub fn require_rent_exempt(account: &AccountInfo) -> Result<(), ProgramError> {
+    if !Rent::get()?.is_exempt(account.lamports(), account.data_len()) {
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+/// # Rent Reserve — withdrawable lamports
+///
+/// Off-chain, `Rent::get()` goes through the syscall stubs, which are
+/// installed here to report the default rent.
+///
+/// - An account above the minimum can pay out exactly the excess
+/// - An account at or below the minimum can pay out nothing
+/// - The minimum grows with the account's data
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use rent_reserve::withdrawable_lamports;
+
+/// Answers `sol_get_rent_sysvar` with `Rent::default()`, which
+/// `Rent::get()` starts from.
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_get_rent_sysvar(&self, _var_addr: *mut u8) -> u64 {
+        0
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn install_stubs() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(Stubs));
+    });
+}
+
+/// Backing storage for a program-owned account's `AccountInfo`.
+struct Fixture {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+}
+
+impl Fixture {
+    fn new(lamports: u64, data_len: usize) -> Self {
+        Self {
+            key: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            lamports,
+            data: vec![0; data_len],
+        }
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            false,
+            true,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            false,
+            0,
+        )
+    }
+}
+
+fn minimum(data_len: usize) -> u64 {
+    Rent::default().minimum_balance(data_len)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn withdrawable_is_balance_above_minimum() {
+    install_stubs();
+    let mut account = Fixture::new(minimum(50) + 3_000_000_000, 50);
+
+    assert_eq!(withdrawable_lamports(&account.info()), Ok(3_000_000_000));
+}
+
+#[test]
+fn nothing_withdrawable_at_or_below_minimum() {
+    install_stubs();
+    for lamports in [minimum(50), minimum(50) - 1, 0] {
+        let mut account = Fixture::new(lamports, 50);
+        assert_eq!(withdrawable_lamports(&account.info()), Ok(0));
+    }
+}
+
+#[test]
+fn minimum_follows_data_length() {
+    install_stubs();
+    let lamports = minimum(50) + 1_000;
+    let mut small = Fixture::new(lamports, 50);
+    let mut large = Fixture::new(lamports, 500);
+
+    assert_eq!(withdrawable_lamports(&small.info()), Ok(1_000));
+    assert_eq!(withdrawable_lamports(&large.info()), Ok(0));
+}
@@ -0,0 +1,51 @@
+//! # Risk Limits
+//!
+//! Cap and LTV arithmetic shared by the reference programs. Plain `u64`
+//! math with no framework dependency, so Anchor and native programs can both
+//! use it and map [`LimitError`] onto their own error codes.
+//!
+//! Every check takes the running total, the increase, and the cap, and
+//! returns the new total — so the caller can't check one number and store
+//! another.
+
+/// Basis-point denominator: 10_000 = 100%.
+pub const BPS: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitError {
+    /// The increase would take the total past its cap.
+    CapExceeded,
+    /// The arithmetic itself overflowed `u64`.
+    Overflow,
+}
+
+/// `current + amount`, if it doesn't exceed `cap`. Reaching the cap exactly
+/// is allowed.
+pub fn checked_increase(current: u64, amount: u64, cap: u64) -> Result<u64, LimitError> {
+    let next = current.checked_add(amount).ok_or(LimitError::Overflow)?;
+    if next > cap {
+        return Err(LimitError::CapExceeded);
+    }
+    Ok(next)
+}
+
+/// How much more fits under `cap`. Zero if `current` is already at or past it
+/// (e.g. after the cap was lowered).
+pub fn headroom(current: u64, cap: u64) -> u64 {
+    cap.saturating_sub(current)
+}
+
+/// `amount * bps / BPS`, rounded down, computed in `u128`.
+pub fn apply_bps(amount: u64, bps: u64) -> Result<u64, LimitError> {
+    let scaled = (amount as u128) * (bps as u128) / (BPS as u128);
+    u64::try_from(scaled).map_err(|_| LimitError::Overflow)
+}
+
+/// The most that can be borrowed against `collateral` valued at `price_bps`
+/// (10_000 = 1:1) with loan-to-value `ltv_bps`.
+pub fn borrow_limit(collateral: u64, price_bps: u64, ltv_bps: u64) -> Result<u64, LimitError> {
+    apply_bps(apply_bps(collateral, price_bps)?, ltv_bps)
+}
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,39 @@
+//! JavaScript bindings for the checks above, enabled by the `wasm` feature.
+//!
+//! Amounts cross the boundary as `BigInt`. A [`LimitError`] is thrown as
+//! an `Error` whose message is the variant name, so the playground can
+//! show the same outcome the on-chain program maps to its error code.
+
+use wasm_bindgen::prelude::*;
+
+use crate::LimitError;
+
+impl From<LimitError> for JsValue {
+    fn from(err: LimitError) -> Self {
+        let name = match err {
+            LimitError::CapExceeded => "CapExceeded",
+            LimitError::Overflow => "Overflow",
+        };
+        JsError::new(name).into()
+    }
+}
+
+#[wasm_bindgen(js_name = checkedIncrease)]
+pub fn checked_increase(current: u64, amount: u64, cap: u64) -> Result<u64, LimitError> {
+    crate::checked_increase(current, amount, cap)
+}
+
+#[wasm_bindgen]
+pub fn headroom(current: u64, cap: u64) -> u64 {
+    crate::headroom(current, cap)
+}
+
+#[wasm_bindgen(js_name = applyBps)]
+pub fn apply_bps(amount: u64, bps: u64) -> Result<u64, LimitError> {
+    crate::apply_bps(amount, bps)
+}
+
+#[wasm_bindgen(js_name = borrowLimit)]
+pub fn borrow_limit(collateral: u64, price_bps: u64, ltv_bps: u64) -> Result<u64, LimitError> {
+    crate::borrow_limit(collateral, price_bps, ltv_bps)
+}
@@ -0,0 +1,46 @@
+/// # Risk Limits — boundary tests
+///
+/// - Increases up to the cap pass; one past it fails
+/// - Overflow is reported as overflow, not as a cap breach
+/// - Headroom never underflows
+/// - Borrow limits round down and survive u64-sized inputs
+use risk_limits::{apply_bps, borrow_limit, checked_increase, headroom, LimitError, BPS};
+
+#[test]
+fn increase_to_cap_boundary() {
+    assert_eq!(checked_increase(900, 100, 1_000), Ok(1_000));
+    assert_eq!(
+        checked_increase(900, 101, 1_000),
+        Err(LimitError::CapExceeded)
+    );
+    assert_eq!(checked_increase(1_000, 0, 1_000), Ok(1_000));
+    assert_eq!(checked_increase(0, 1, 0), Err(LimitError::CapExceeded));
+}
+
+#[test]
+fn increase_overflow_is_not_a_cap_breach() {
+    assert_eq!(
+        checked_increase(u64::MAX, 1, u64::MAX),
+        Err(LimitError::Overflow)
+    );
+}
+
+#[test]
+fn headroom_saturates() {
+    assert_eq!(headroom(400, 1_000), 600);
+    assert_eq!(headroom(1_000, 1_000), 0);
+    assert_eq!(headroom(1_500, 1_000), 0);
+}
+
+#[test]
+fn borrow_limit_rounds_down() {
+    assert_eq!(apply_bps(1_000, BPS), Ok(1_000));
+    assert_eq!(apply_bps(3, 5_000), Ok(1));
+    // 1_000 at 2x, 50% LTV
+    assert_eq!(borrow_limit(1_000, 2 * BPS, 5_000), Ok(1_000));
+    assert_eq!(borrow_limit(u64::MAX, BPS, 5_000), Ok(u64::MAX / 2));
+    assert_eq!(
+        borrow_limit(u64::MAX, 2 * BPS, BPS),
+        Err(LimitError::Overflow)
+    );
+}
@@ -0,0 +1,147 @@
+//! # Safe Math
+//!
+//! Valuation with the units in the types. Plain `u64`/`u128` math with no
+//! framework dependency; callers map [`ValuationError`] onto their own
+//! error codes.
+//!
+//! A raw token balance means nothing without its mint's decimals, and a
+//! price means nothing without the slot it was published in. Basket
+//! valuations go wrong when either is dropped along the way (patterns 34
+//! and 14), so neither type here can be built without them:
+//!
+//! - [`TokenAmount`]: a raw amount and its mint's decimals
+//! - [`UsdPrice`]: micro-USD per whole token, and its publish slot
+//! - [`QuoteValue`]: a micro-USD value, made only by [`quote`], which
+//!   prices one amount and refuses a price older than the caller's limit
+//! - [`value_basket`]: the micro-USD value of a list of holdings
+//!
+//! Amounts of different mints can't be compared: `TokenAmount` has no
+//! ordering, so `collateral >= debt` doesn't compile. Quote both and
+//! compare the `QuoteValue`s (pattern 226).
+//!
+//! What the amounts are is still the caller's choice. They should be the
+//! program's own records of what it holds, not token account balances that
+//! anyone can add to (pattern 220).
+
+/// Decimals of the micro-USD values [`value_basket`] returns.
+pub const USD_DECIMALS: u8 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValuationError {
+    /// A price was published more than the allowed number of slots ago.
+    StalePrice,
+    /// A mint's decimals are too large to scale by.
+    InvalidDecimals,
+    /// The arithmetic itself overflowed.
+    Overflow,
+}
+
+/// A raw token amount, in the smallest units of a mint with `decimals`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAmount {
+    raw: u64,
+    decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: u64, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    pub fn raw(&self) -> u64 {
+        self.raw
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+}
+
+/// The price of one whole token, in micro-USD, as published at `slot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsdPrice {
+    micro_usd: u64,
+    slot: u64,
+}
+
+impl UsdPrice {
+    pub fn new(micro_usd: u64, slot: u64) -> Self {
+        Self { micro_usd, slot }
+    }
+
+    pub fn micro_usd(&self) -> u64 {
+        self.micro_usd
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+}
+
+/// An amount of one asset and that asset's price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Holding {
+    pub amount: TokenAmount,
+    pub price: UsdPrice,
+}
+
+/// A value in micro-USD: the one unit in which amounts of different mints
+/// can be compared. Values are ordered; amounts aren't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct QuoteValue {
+    micro_usd: u64,
+}
+
+impl QuoteValue {
+    pub fn micro_usd(&self) -> u64 {
+        self.micro_usd
+    }
+
+    /// `bps` basis points of this value, rounded down: a loan-to-value
+    /// limit, say.
+    pub fn bps(&self, bps: u64) -> Result<QuoteValue, ValuationError> {
+        let micro_usd = (self.micro_usd as u128) * (bps as u128) / 10_000;
+        let micro_usd = u64::try_from(micro_usd).map_err(|_| ValuationError::Overflow)?;
+        Ok(QuoteValue { micro_usd })
+    }
+}
+
+/// The value of `amount` at `price`, at `current_slot`. Scaled by the
+/// amount's decimals and rounded down. Fails if the price was published
+/// more than `max_age_slots` before `current_slot`.
+pub fn quote(
+    amount: TokenAmount,
+    price: UsdPrice,
+    current_slot: u64,
+    max_age_slots: u64,
+) -> Result<QuoteValue, ValuationError> {
+    if current_slot.saturating_sub(price.slot) > max_age_slots {
+        return Err(ValuationError::StalePrice);
+    }
+    let scale = 10u128
+        .checked_pow(amount.decimals as u32)
+        .ok_or(ValuationError::InvalidDecimals)?;
+    let micro_usd = (amount.raw as u128) * (price.micro_usd as u128) / scale;
+    let micro_usd = u64::try_from(micro_usd).map_err(|_| ValuationError::Overflow)?;
+    Ok(QuoteValue { micro_usd })
+}
+
+/// The total micro-USD value of `holdings` at `current_slot`: the sum of
+/// each holding's [`quote`]. Fails if any of them does.
+pub fn value_basket(
+    holdings: &[Holding],
+    current_slot: u64,
+    max_age_slots: u64,
+) -> Result<u64, ValuationError> {
+    let mut total: u64 = 0;
+    for holding in holdings {
+        let value = quote(holding.amount, holding.price, current_slot, max_age_slots)?;
+        total = total
+            .checked_add(value.micro_usd)
+            .ok_or(ValuationError::Overflow)?;
+    }
+    Ok(total)
+}
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,66 @@
+//! JavaScript bindings for the valuation above, enabled by the `wasm`
+//! feature.
+//!
+//! Raw amounts, prices, and slots cross the boundary as `BigInt`, decimals
+//! as a number. A [`ValuationError`] is thrown as an `Error` whose message
+//! is the variant name.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Holding, TokenAmount, UsdPrice, ValuationError};
+
+impl From<ValuationError> for JsValue {
+    fn from(err: ValuationError) -> Self {
+        let name = match err {
+            ValuationError::StalePrice => "StalePrice",
+            ValuationError::InvalidDecimals => "InvalidDecimals",
+            ValuationError::Overflow => "Overflow",
+        };
+        JsError::new(name).into()
+    }
+}
+
+/// The micro-USD value of `raw` units of a mint with `decimals`, at
+/// `micro_usd` per whole token as published at `price_slot`.
+#[wasm_bindgen]
+pub fn quote(
+    raw: u64,
+    decimals: u8,
+    micro_usd: u64,
+    price_slot: u64,
+    current_slot: u64,
+    max_age_slots: u64,
+) -> Result<u64, ValuationError> {
+    let amount = TokenAmount::new(raw, decimals);
+    let price = UsdPrice::new(micro_usd, price_slot);
+    crate::quote(amount, price, current_slot, max_age_slots).map(|value| value.micro_usd())
+}
+
+/// Holdings to value together, as [`crate::value_basket`] does.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct Basket {
+    holdings: Vec<Holding>,
+}
+
+#[wasm_bindgen]
+impl Basket {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `raw` units of a mint with `decimals`, at `micro_usd` per whole
+    /// token as published at `slot`.
+    pub fn add(&mut self, raw: u64, decimals: u8, micro_usd: u64, slot: u64) {
+        self.holdings.push(Holding {
+            amount: TokenAmount::new(raw, decimals),
+            price: UsdPrice::new(micro_usd, slot),
+        });
+    }
+
+    /// The basket's total micro-USD value at `current_slot`.
+    pub fn value(&self, current_slot: u64, max_age_slots: u64) -> Result<u64, ValuationError> {
+        crate::value_basket(&self.holdings, current_slot, max_age_slots)
+    }
+}
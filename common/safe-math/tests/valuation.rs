@@ -0,0 +1,89 @@
+/// # Safe Math — basket valuation tests
+///
+/// - Each holding is scaled by its own decimals
+/// - A price one slot past the limit fails; at the limit it passes
+/// - Values round down, and overflow is reported, not wrapped
+/// - Quotes of different mints compare by value, not raw amount
+use safe_math::{quote, value_basket, Holding, TokenAmount, UsdPrice, ValuationError};
+
+const SLOT: u64 = 1_000;
+const MAX_AGE: u64 = 100;
+
+fn holding(raw: u64, decimals: u8, micro_usd: u64, slot: u64) -> Holding {
+    Holding {
+        amount: TokenAmount::new(raw, decimals),
+        price: UsdPrice::new(micro_usd, slot),
+    }
+}
+
+#[test]
+fn decimals_scale_each_holding() {
+    // 1,000 USDC (6 decimals) at $1, and 2 SOL (9 decimals) at $100
+    let basket = [
+        holding(1_000_000_000, 6, 1_000_000, SLOT),
+        holding(2_000_000_000, 9, 100_000_000, SLOT),
+    ];
+    assert_eq!(value_basket(&basket, SLOT, MAX_AGE), Ok(1_200_000_000));
+    assert_eq!(value_basket(&[], SLOT, MAX_AGE), Ok(0));
+}
+
+#[test]
+fn stale_price_boundary() {
+    let at_limit = [holding(1_000_000, 6, 1_000_000, SLOT - MAX_AGE)];
+    assert_eq!(value_basket(&at_limit, SLOT, MAX_AGE), Ok(1_000_000));
+
+    // One stale price fails the whole basket
+    let basket = [
+        holding(1_000_000, 6, 1_000_000, SLOT),
+        holding(1_000_000, 6, 1_000_000, SLOT - MAX_AGE - 1),
+    ];
+    assert_eq!(
+        value_basket(&basket, SLOT, MAX_AGE),
+        Err(ValuationError::StalePrice)
+    );
+}
+
+#[test]
+fn values_round_down() {
+    // 1 raw unit of a 9-decimal token at $100 is 0.1 micro-USD
+    assert_eq!(
+        value_basket(&[holding(1, 9, 100_000_000, SLOT)], SLOT, MAX_AGE),
+        Ok(0)
+    );
+}
+
+#[test]
+fn overflow_is_reported() {
+    let basket = [holding(u64::MAX, 0, 1, SLOT), holding(1, 0, 1, SLOT)];
+    assert_eq!(
+        value_basket(&basket, SLOT, MAX_AGE),
+        Err(ValuationError::Overflow)
+    );
+    assert_eq!(
+        value_basket(&[holding(u64::MAX, 0, 2, SLOT)], SLOT, MAX_AGE),
+        Err(ValuationError::Overflow)
+    );
+    assert_eq!(
+        value_basket(&[holding(1, 255, 1, SLOT)], SLOT, MAX_AGE),
+        Err(ValuationError::InvalidDecimals)
+    );
+}
+
+#[test]
+fn quotes_compare_by_value() {
+    // 1,000,000 BONK (5 decimals) at $0.00002 is $20; 100 USDC is $100.
+    // The raw BONK amount is the larger by a factor of 1,000.
+    let bonk = TokenAmount::new(100_000_000_000, 5);
+    let usdc = TokenAmount::new(100_000_000, 6);
+    let bonk_value = quote(bonk, UsdPrice::new(20, SLOT), SLOT, MAX_AGE).unwrap();
+    let usdc_value = quote(usdc, UsdPrice::new(1_000_000, SLOT), SLOT, MAX_AGE).unwrap();
+    assert_eq!(bonk_value.micro_usd(), 20_000_000);
+    assert!(bonk_value < usdc_value);
+
+    // 80% of $20, rounded down
+    assert_eq!(bonk_value.bps(8_000).unwrap().micro_usd(), 16_000_000);
+    assert_eq!(
+        quote(bonk, UsdPrice::new(20, SLOT - MAX_AGE - 1), SLOT, MAX_AGE),
+        Err(ValuationError::StalePrice)
+    );
+}
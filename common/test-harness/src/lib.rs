@@ -0,0 +1,236 @@
+//! # Test Harness
+//!
+//! The account ledger and fixtures the Mollusk tests share.
+//!
+//! Mollusk runs one instruction against the accounts it is handed and
+//! returns the resulting accounts; it keeps nothing between calls. A test
+//! that chains instructions, deposit then borrow then liquidate, has to
+//! carry the accounts forward itself. [`Ledger`] does that: [`Ledger::step`]
+//! runs an instruction against the accounts it names, checks the result,
+//! and on success keeps the resulting accounts for the next step. A failed
+//! step leaves the ledger as it was, as a failed transaction would.
+//!
+//! The fixtures build the accounts and instruction data an Anchor program
+//! expects: [`ix_discriminator`] and [`account_discriminator`] for the
+//! 8-byte prefixes, and [`owned_account`], [`system_account`] and
+//! [`executable_account`] for the accounts themselves. [`token`] packs SPL
+//! Token and Token-2022 accounts and mints, and [`instructions_sysvar`]
+//! describes a transaction to a program that introspects it.
+//!
+//! ```ignore
+//! let mut ledger = Ledger::new(vec![
+//!     (vault, owned_account(&vault_data, RENT, &PROGRAM_ID)),
+//!     (user, system_account(SOL)),
+//! ]);
+//! ledger.step(&mollusk, &deposit, &[Check::success()]);
+//! ledger.step(&mollusk, &withdraw, &[Check::err(ProgramError::Custom(6000))]);
+//! assert_eq!(ledger.lamports(&user), SOL - DEPOSIT);
+//! ```
+
+use mollusk_svm::{
+    result::{Check, InstructionResult},
+    Mollusk,
+};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount, WritableAccount},
+    instruction::Instruction,
+    pubkey::Pubkey,
+    sysvar,
+};
+
+pub mod token;
+
+/// Accounts as Mollusk takes them.
+pub type Accounts = Vec<(Pubkey, AccountSharedData)>;
+
+/// Accounts carried across the instructions of a test.
+#[derive(Debug, Clone, Default)]
+pub struct Ledger {
+    accounts: Accounts,
+    collect_garbage: bool,
+}
+
+impl Ledger {
+    pub fn new(accounts: Accounts) -> Self {
+        Self {
+            accounts,
+            collect_garbage: false,
+        }
+    }
+
+    /// Reset every account a successful step leaves with zero lamports to an
+    /// empty System Program account, as the runtime does at the end of a
+    /// transaction. Off by default, so a test can inspect what a close left
+    /// behind.
+    pub fn collecting_garbage(mut self) -> Self {
+        self.collect_garbage = true;
+        self
+    }
+
+    /// Add `account` at `key`, or replace the account already there.
+    pub fn set(&mut self, key: Pubkey, account: AccountSharedData) {
+        match self.accounts.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = account,
+            None => self.accounts.push((key, account)),
+        }
+    }
+
+    pub fn get(&self, key: &Pubkey) -> Option<&AccountSharedData> {
+        self.accounts
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, account)| account)
+    }
+
+    /// The account at `key`. Panics if the ledger has none.
+    pub fn account(&self, key: &Pubkey) -> &AccountSharedData {
+        self.get(key)
+            .unwrap_or_else(|| panic!("no account {key} in the ledger"))
+    }
+
+    /// The account at `key`, to edit between steps. Panics if the ledger has
+    /// none.
+    pub fn account_mut(&mut self, key: &Pubkey) -> &mut AccountSharedData {
+        self.accounts
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, account)| account)
+            .unwrap_or_else(|| panic!("no account {key} in the ledger"))
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.account(key).lamports()
+    }
+
+    pub fn data(&self, key: &Pubkey) -> &[u8] {
+        self.account(key).data()
+    }
+
+    /// The accounts `ix` names, in order, each once however many times it is
+    /// named. Panics if the ledger is missing one.
+    pub fn accounts_for(&self, ix: &Instruction) -> Accounts {
+        let mut accounts = Accounts::new();
+        for meta in &ix.accounts {
+            if accounts.iter().all(|(key, _)| *key != meta.pubkey) {
+                accounts.push((meta.pubkey, self.account(&meta.pubkey).clone()));
+            }
+        }
+        accounts
+    }
+
+    /// Run `ix` against the accounts it names and check the result. On
+    /// success the ledger takes the resulting accounts; on failure it is
+    /// left as it was.
+    pub fn step(
+        &mut self,
+        mollusk: &Mollusk,
+        ix: &Instruction,
+        checks: &[Check],
+    ) -> InstructionResult {
+        let accounts = self.accounts_for(ix);
+        let result = mollusk.process_and_validate_instruction(ix, &accounts, checks);
+        if result.program_result.is_ok() {
+            for (key, account) in &result.resulting_accounts {
+                let account = if self.collect_garbage && account.lamports() == 0 {
+                    AccountSharedData::default()
+                } else {
+                    account.clone()
+                };
+                if let Some(entry) = self.accounts.iter_mut().find(|(k, _)| k == key) {
+                    entry.1 = account;
+                }
+            }
+        }
+        result
+    }
+
+    /// Reset every account with zero lamports to an empty System Program
+    /// account, as the runtime does at the end of a transaction.
+    pub fn collect_garbage(&mut self) {
+        for (_, account) in self.accounts.iter_mut() {
+            if account.lamports() == 0 {
+                *account = AccountSharedData::default();
+            }
+        }
+    }
+}
+
+/// Adds each account, replacing any already at its key.
+impl Extend<(Pubkey, AccountSharedData)> for Ledger {
+    fn extend<I: IntoIterator<Item = (Pubkey, AccountSharedData)>>(&mut self, iter: I) {
+        for (key, account) in iter {
+            self.set(key, account);
+        }
+    }
+}
+
+/// The 8-byte prefix Anchor dispatches instruction `name` on.
+pub fn ix_discriminator(name: &str) -> [u8; 8] {
+    discriminator(&format!("global:{name}"))
+}
+
+/// The 8-byte prefix Anchor checks account type `name` by.
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    discriminator(&format!("account:{name}"))
+}
+
+fn discriminator(preimage: &str) -> [u8; 8] {
+    let hash = Sha256::digest(preimage.as_bytes());
+    let mut disc = [0u8; 8];
+    disc.copy_from_slice(&hash[..8]);
+    disc
+}
+
+/// An account holding `data`, owned by `owner`.
+pub fn owned_account(data: &[u8], lamports: u64, owner: &Pubkey) -> AccountSharedData {
+    let mut account = AccountSharedData::new(lamports, data.len(), owner);
+    account.set_data_from_slice(data);
+    account
+}
+
+/// A wallet: no data, owned by the System Program.
+pub fn system_account(lamports: u64) -> AccountSharedData {
+    AccountSharedData::new(lamports, 0, &solana_sdk::system_program::ID)
+}
+
+/// A program account, owned by its loader. Mollusk needs one for each
+/// program an instruction invokes through CPI.
+pub fn executable_account(owner: &Pubkey) -> AccountSharedData {
+    let mut account = AccountSharedData::new(1, 0, owner);
+    account.set_executable(true);
+    account
+}
+
+/// The instructions sysvar for a transaction of `ixs`, executing `ixs[current]`:
+/// [2 count][2 offset × count] then per instruction
+/// [2 account count][(1 flags)(32 pubkey) × accounts][32 program_id][2 data len][data],
+/// and finally [2 current index].
+pub fn instructions_sysvar(ixs: &[Instruction], current: u16) -> AccountSharedData {
+    owned_account(
+        &instructions_sysvar_data(ixs, current),
+        1_000_000,
+        &sysvar::id(),
+    )
+}
+
+/// The data of [`instructions_sysvar`], for tests that build the
+/// `AccountInfo` themselves.
+pub fn instructions_sysvar_data(ixs: &[Instruction], current: u16) -> Vec<u8> {
+    let mut data = (ixs.len() as u16).to_le_bytes().to_vec();
+    data.resize(2 + 2 * ixs.len(), 0);
+    for (i, ix) in ixs.iter().enumerate() {
+        let offset = data.len() as u16;
+        data[2 + 2 * i..4 + 2 * i].copy_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&(ix.accounts.len() as u16).to_le_bytes());
+        for meta in &ix.accounts {
+            data.push(meta.is_signer as u8 | (meta.is_writable as u8) << 1);
+            data.extend_from_slice(meta.pubkey.as_ref());
+        }
+        data.extend_from_slice(ix.program_id.as_ref());
+        data.extend_from_slice(&(ix.data.len() as u16).to_le_bytes());
+        data.extend_from_slice(&ix.data);
+    }
+    data.extend_from_slice(&current.to_le_bytes());
+    data
+}
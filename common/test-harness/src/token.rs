@@ -0,0 +1,130 @@
+//! SPL Token and Token-2022 account layouts.
+//!
+//! Tests that hand Mollusk a token account or a mint build its bytes here
+//! rather than depend on `spl-token`. [`TokenAccount`] and [`Mint`] pack the
+//! base layouts both token programs share; [`push_extension`] appends a
+//! Token-2022 extension after them.
+
+use solana_sdk::{account::AccountSharedData, pubkey::Pubkey, rent::Rent};
+
+use crate::owned_account;
+
+/// Size of a token account's base layout. Token-2022 pads a mint to this
+/// size too before its first extension.
+pub const ACCOUNT_LEN: usize = 165;
+
+/// Size of a mint's base layout.
+pub const MINT_LEN: usize = 82;
+
+/// Token-2022's account type byte, written after the base layout.
+pub const ACCOUNT_TYPE_MINT: u8 = 1;
+pub const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+/// `AccountState` values.
+pub const INITIALIZED: u8 = 1;
+pub const FROZEN: u8 = 2;
+
+/// A token account's fields (165 bytes):
+///   [32 mint][32 owner][8 amount][36 delegate][1 state][12 is_native]
+///   [8 delegated_amount][36 close_authority]
+#[derive(Debug, Clone)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// The delegate and its delegated amount.
+    pub delegate: Option<(Pubkey, u64)>,
+    pub state: u8,
+    pub close_authority: Option<Pubkey>,
+}
+
+impl TokenAccount {
+    /// An initialized account with no delegate or close authority.
+    pub fn new(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Self {
+        Self {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            delegate: None,
+            state: INITIALIZED,
+            close_authority: None,
+        }
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(ACCOUNT_LEN);
+        data.extend_from_slice(self.mint.as_ref());
+        data.extend_from_slice(self.owner.as_ref());
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        push_option(&mut data, self.delegate.as_ref().map(|(key, _)| key));
+        data.push(self.state);
+        data.extend_from_slice(&[0u8; 12]); // is_native: None
+        let delegated_amount = self.delegate.map_or(0, |(_, amount)| amount);
+        data.extend_from_slice(&delegated_amount.to_le_bytes());
+        push_option(&mut data, self.close_authority.as_ref());
+        data
+    }
+}
+
+/// A mint's fields (82 bytes):
+///   [36 mint_authority][8 supply][1 decimals][1 is_initialized]
+///   [36 freeze_authority]
+#[derive(Debug, Clone, Default)]
+pub struct Mint {
+    pub mint_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub freeze_authority: Option<Pubkey>,
+}
+
+impl Mint {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(MINT_LEN);
+        push_option(&mut data, self.mint_authority.as_ref());
+        data.extend_from_slice(&self.supply.to_le_bytes());
+        data.push(self.decimals);
+        data.push(1); // is_initialized
+        push_option(&mut data, self.freeze_authority.as_ref());
+        data
+    }
+}
+
+/// An initialized token account owned by `program`, holding the
+/// rent-exempt minimum.
+pub fn token_account(
+    program: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> AccountSharedData {
+    let data = TokenAccount::new(mint, owner, amount).pack();
+    owned_account(&data, Rent::default().minimum_balance(data.len()), program)
+}
+
+/// Append a Token-2022 extension entry, [2 type][2 length][value], to a
+/// packed mint or token account. Before the first entry, a mint is padded
+/// to [`ACCOUNT_LEN`] and either layout gets its account type byte.
+pub fn push_extension(data: &mut Vec<u8>, extension_type: u16, value: &[u8]) {
+    match data.len() {
+        MINT_LEN => {
+            data.resize(ACCOUNT_LEN, 0);
+            data.push(ACCOUNT_TYPE_MINT);
+        }
+        ACCOUNT_LEN => data.push(ACCOUNT_TYPE_ACCOUNT),
+        _ => {}
+    }
+    data.extend_from_slice(&extension_type.to_le_bytes());
+    data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    data.extend_from_slice(value);
+}
+
+/// A `COption<Pubkey>`: [4 tag][32 key].
+fn push_option(data: &mut Vec<u8>, key: Option<&Pubkey>) {
+    match key {
+        Some(key) => {
+            data.extend_from_slice(&1u32.to_le_bytes());
+            data.extend_from_slice(key.as_ref());
+        }
+        None => data.extend_from_slice(&[0u8; 36]),
+    }
+}
@@ -0,0 +1,216 @@
+/// # Test Harness — ledger and fixtures
+///
+/// The step tests run System Program transfers on `Mollusk::default()`,
+/// which has the builtins loaded and needs no program ELF.
+///
+/// - A successful step keeps the resulting accounts; a failed one keeps
+///   nothing
+/// - An instruction gets each account it names once, in order
+/// - Garbage collection empties zero-lamport accounts, and only those
+/// - Discriminators match Anchor's
+/// - Token layouts put each field at SPL Token's offset
+/// - The instructions sysvar indexes each instruction
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount, WritableAccount},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, instructions_sysvar, ix_discriminator,
+    owned_account, system_account,
+    token::{self, Mint, TokenAccount},
+    Ledger,
+};
+
+const SOL: u64 = 1_000_000_000;
+
+/// SystemError::ResultWithNegativeLamports
+const INSUFFICIENT_FUNDS: u32 = 1;
+
+/// SystemInstruction::Transfer: [4 variant = 2][8 lamports]
+fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction::new_with_bytes(
+        solana_sdk::system_program::ID,
+        &data,
+        vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)],
+    )
+}
+
+fn wallets() -> (Pubkey, Pubkey, Ledger) {
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ledger = Ledger::new(vec![(alice, system_account(SOL)), (bob, system_account(0))]);
+    (alice, bob, ledger)
+}
+
+#[test]
+fn step_keeps_success() {
+    let mollusk = Mollusk::default();
+    let (alice, bob, mut ledger) = wallets();
+
+    let ix = transfer(&alice, &bob, SOL / 4);
+    ledger.step(&mollusk, &ix, &[Check::success()]);
+    ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    assert_eq!(ledger.lamports(&alice), SOL / 2);
+    assert_eq!(ledger.lamports(&bob), SOL / 2);
+}
+
+#[test]
+fn failed_step_keeps_nothing() {
+    let mollusk = Mollusk::default();
+    let (alice, bob, mut ledger) = wallets();
+
+    let ix = transfer(&alice, &bob, 2 * SOL);
+    ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_FUNDS))],
+    );
+
+    assert_eq!(ledger.lamports(&alice), SOL);
+    assert_eq!(ledger.lamports(&bob), 0);
+}
+
+#[test]
+fn accounts_named_once_in_order() {
+    let (alice, bob, mut ledger) = wallets();
+    let program = Pubkey::new_unique();
+    ledger.set(
+        program,
+        executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+    );
+
+    let ix = Instruction::new_with_bytes(
+        program,
+        &[],
+        vec![
+            AccountMeta::new(bob, false),
+            AccountMeta::new(alice, true),
+            AccountMeta::new_readonly(bob, false),
+        ],
+    );
+    let keys: Vec<Pubkey> = ledger
+        .accounts_for(&ix)
+        .into_iter()
+        .map(|(k, _)| k)
+        .collect();
+    assert_eq!(keys, vec![bob, alice]);
+}
+
+#[test]
+#[should_panic(expected = "in the ledger")]
+fn missing_account_panics() {
+    let (alice, _, ledger) = wallets();
+    let stranger = Pubkey::new_unique();
+    ledger.accounts_for(&transfer(&alice, &stranger, 1));
+}
+
+#[test]
+fn set_replaces_or_adds() {
+    let (alice, _, mut ledger) = wallets();
+    ledger.set(alice, system_account(5));
+    assert_eq!(ledger.lamports(&alice), 5);
+
+    let carol = Pubkey::new_unique();
+    assert!(ledger.get(&carol).is_none());
+    ledger.set(carol, system_account(7));
+    assert_eq!(ledger.lamports(&carol), 7);
+
+    ledger.account_mut(&carol).set_lamports(8);
+    assert_eq!(ledger.lamports(&carol), 8);
+
+    let dave = Pubkey::new_unique();
+    ledger.extend([(carol, system_account(9)), (dave, system_account(10))]);
+    assert_eq!(ledger.lamports(&carol), 9);
+    assert_eq!(ledger.lamports(&dave), 10);
+}
+
+#[test]
+fn garbage_collection_empties_zero_lamports() {
+    let owner = Pubkey::new_unique();
+    let (closed, open) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut ledger = Ledger::new(vec![
+        (closed, owned_account(&[1, 2, 3], 0, &owner)),
+        (open, owned_account(&[4, 5, 6], 1, &owner)),
+    ]);
+
+    ledger.collect_garbage();
+
+    assert_eq!(*ledger.account(&closed), AccountSharedData::default());
+    assert_eq!(ledger.data(&open), &[4, 5, 6]);
+    assert_eq!(*ledger.account(&open).owner(), owner);
+}
+
+#[test]
+fn discriminators_match_anchor() {
+    // sha256("global:initialize")[..8], as in any Anchor IDL
+    assert_eq!(
+        ix_discriminator("initialize"),
+        [175, 175, 109, 31, 13, 152, 155, 237]
+    );
+    assert_ne!(account_discriminator("Vault"), ix_discriminator("Vault"));
+}
+
+#[test]
+fn token_layouts_match_spl() {
+    let (mint, owner, delegate) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let account = TokenAccount {
+        delegate: Some((delegate, 7)),
+        state: token::FROZEN,
+        ..TokenAccount::new(&mint, &owner, 5)
+    }
+    .pack();
+    assert_eq!(account.len(), token::ACCOUNT_LEN);
+    assert_eq!(&account[32..64], owner.as_ref());
+    assert_eq!(account[64..72], 5u64.to_le_bytes());
+    assert_eq!(&account[76..108], delegate.as_ref());
+    assert_eq!(account[108], token::FROZEN);
+    assert_eq!(account[121..129], 7u64.to_le_bytes());
+    assert_eq!(account[129..133], [0; 4]); // close_authority: None
+
+    let mut mint = Mint {
+        supply: 9,
+        decimals: 6,
+        ..Mint::default()
+    }
+    .pack();
+    assert_eq!(mint.len(), token::MINT_LEN);
+    assert_eq!(mint[36..44], 9u64.to_le_bytes());
+    assert_eq!(mint[44], 6);
+
+    token::push_extension(&mut mint, 12, delegate.as_ref());
+    token::push_extension(&mut mint, 6, &[token::FROZEN]);
+    assert_eq!(mint[token::ACCOUNT_LEN], token::ACCOUNT_TYPE_MINT);
+    assert_eq!(mint[166..170], [12, 0, 32, 0]);
+    assert_eq!(mint[202..207], [6, 0, 1, 0, token::FROZEN]);
+    assert_eq!(mint.len(), 207);
+}
+
+#[test]
+fn instructions_sysvar_indexes_each_instruction() {
+    let (program, key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ixs = [
+        Instruction::new_with_bytes(program, &[1, 2], vec![AccountMeta::new(key, true)]),
+        Instruction::new_with_bytes(program, &[3], vec![]),
+    ];
+    let sysvar = instructions_sysvar(&ixs, 1);
+    let data = sysvar.data();
+
+    assert_eq!(*sysvar.owner(), solana_sdk::sysvar::id());
+    assert_eq!(data[..2], 2u16.to_le_bytes());
+    let first = u16::from_le_bytes([data[2], data[3]]) as usize;
+    let second = u16::from_le_bytes([data[4], data[5]]) as usize;
+    assert_eq!(first, 6);
+    // [2 account count][1 flags][32 key][32 program][2 data len][2 data]
+    assert_eq!(data[first + 2], 0b11); // signer, writable
+    assert_eq!(second, first + 2 + 33 + 32 + 2 + 2);
+    assert_eq!(data[data.len() - 2..], 1u16.to_le_bytes());
+}
@@ -0,0 +1,91 @@
+//! # Timelock
+//!
+//! Slot arithmetic for changes that are queued now and take effect later.
+//! A privileged setter records the change and its [`ready_at`] slot instead
+//! of applying it; a separate instruction applies it once [`check_ready`]
+//! passes. Plain `u64` math with no framework dependency, like
+//! `risk-limits`, so callers keep the pending change in their own account
+//! type and map [`TimelockError`] onto their own error codes.
+//!
+//! A caller that stores the pending change as an `Option` of a type
+//! implementing [`Queued`] can let [`take_ready`] and [`cancel`] move it
+//! out, so a change can't be applied twice or after it was cancelled.
+//!
+//! ## Why a delay
+//!
+//! A setter that applies in the same transaction gives whoever holds the
+//! admin key, legitimately or not, an instant exploit: set the oracle, use
+//! it, all before anyone can react. With a delay, the queued change is on
+//! chain for everyone to see, and users can leave before it lands.
+//!
+//! ## Why a grace period
+//!
+//! A change nobody applied stays valid forever without one. Users who saw
+//! it queued months ago, and decided it was abandoned, wake up to it. After
+//! the grace period it has to be cancelled and queued again, in public.
+//!
+//! Pattern 224's `queue_*_secure`, `execute_change` and `cancel_change` use
+//! it.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelockError {
+    /// The change was queued, but its delay hasn't passed.
+    TooEarly,
+    /// The change's grace period has passed without it being applied.
+    Expired,
+    /// No change is queued.
+    NothingQueued,
+    /// `now + delay` overflowed `u64`.
+    Overflow,
+}
+
+/// A queued change, as the caller stores it.
+pub trait Queued {
+    /// The first slot the change may be applied in.
+    fn ready_at(&self) -> u64;
+}
+
+/// The first slot a change queued at `now` may be applied in.
+pub fn ready_at(now: u64, delay: u64) -> Result<u64, TimelockError> {
+    now.checked_add(delay).ok_or(TimelockError::Overflow)
+}
+
+/// Ok once `now` has reached `ready_at`. The change may be applied in
+/// exactly that slot.
+pub fn check_ready(now: u64, ready_at: u64) -> Result<(), TimelockError> {
+    if now < ready_at {
+        return Err(TimelockError::TooEarly);
+    }
+    Ok(())
+}
+
+/// Ok from `ready_at` through `ready_at + grace`, both included. A window
+/// that would end past `u64::MAX` never expires.
+pub fn check_window(now: u64, ready_at: u64, grace: u64) -> Result<(), TimelockError> {
+    check_ready(now, ready_at)?;
+    if now > ready_at.saturating_add(grace) {
+        return Err(TimelockError::Expired);
+    }
+    Ok(())
+}
+
+/// Move the pending change out of `pending` to be applied, if `now` is in
+/// its window. On error `pending` is left as it was: an expired change
+/// stays queued until it is cancelled or replaced.
+pub fn take_ready<T: Queued>(
+    pending: &mut Option<T>,
+    now: u64,
+    grace: u64,
+) -> Result<T, TimelockError> {
+    let queued = pending.as_ref().ok_or(TimelockError::NothingQueued)?;
+    check_window(now, queued.ready_at(), grace)?;
+    pending.take().ok_or(TimelockError::NothingQueued)
+}
+
+/// Move the pending change out of `pending` without applying it.
+pub fn cancel<T>(pending: &mut Option<T>) -> Result<T, TimelockError> {
+    pending.take().ok_or(TimelockError::NothingQueued)
+}
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
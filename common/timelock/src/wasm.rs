@@ -0,0 +1,93 @@
+//! JavaScript bindings for the delay arithmetic above, enabled by the
+//! `wasm` feature.
+//!
+//! Slots and values cross the boundary as `BigInt`. A [`TimelockError`] is
+//! thrown as an `Error` whose message is the variant name.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Queued, TimelockError};
+
+impl From<TimelockError> for JsValue {
+    fn from(err: TimelockError) -> Self {
+        let name = match err {
+            TimelockError::TooEarly => "TooEarly",
+            TimelockError::Expired => "Expired",
+            TimelockError::NothingQueued => "NothingQueued",
+            TimelockError::Overflow => "Overflow",
+        };
+        JsError::new(name).into()
+    }
+}
+
+#[wasm_bindgen(js_name = readyAt)]
+pub fn ready_at(now: u64, delay: u64) -> Result<u64, TimelockError> {
+    crate::ready_at(now, delay)
+}
+
+#[wasm_bindgen(js_name = checkReady)]
+pub fn check_ready(now: u64, ready_at: u64) -> Result<(), TimelockError> {
+    crate::check_ready(now, ready_at)
+}
+
+#[wasm_bindgen(js_name = checkWindow)]
+pub fn check_window(now: u64, ready_at: u64, grace: u64) -> Result<(), TimelockError> {
+    crate::check_window(now, ready_at, grace)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    value: u64,
+    ready_at: u64,
+}
+
+impl Queued for Pending {
+    fn ready_at(&self) -> u64 {
+        self.ready_at
+    }
+}
+
+/// One setting behind a timelock, such as pattern 224's fee: at most one
+/// change queued at a time, applied through [`crate::take_ready`].
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Queue {
+    delay: u64,
+    grace: u64,
+    pending: Option<Pending>,
+}
+
+#[wasm_bindgen]
+impl Queue {
+    #[wasm_bindgen(constructor)]
+    pub fn new(delay: u64, grace: u64) -> Self {
+        Self {
+            delay,
+            grace,
+            pending: None,
+        }
+    }
+
+    /// Queue `value` at `now`, replacing any change already queued.
+    pub fn queue(&mut self, value: u64, now: u64) -> Result<(), TimelockError> {
+        let ready_at = crate::ready_at(now, self.delay)?;
+        self.pending = Some(Pending { value, ready_at });
+        Ok(())
+    }
+
+    /// The first slot the queued change may be applied in, if there is one.
+    #[wasm_bindgen(getter, js_name = readyAt)]
+    pub fn pending_ready_at(&self) -> Option<u64> {
+        self.pending.map(|pending| pending.ready_at)
+    }
+
+    /// Take the queued value to apply, if `now` is in its window.
+    pub fn apply(&mut self, now: u64) -> Result<u64, TimelockError> {
+        crate::take_ready(&mut self.pending, now, self.grace).map(|pending| pending.value)
+    }
+
+    /// Drop the queued value without applying it.
+    pub fn cancel(&mut self) -> Result<u64, TimelockError> {
+        crate::cancel(&mut self.pending).map(|pending| pending.value)
+    }
+}
@@ -0,0 +1,97 @@
+/// # Timelock — queue lifecycle tests
+///
+/// - A queued change can't be applied before `ready_at`, and can at it
+/// - Past the grace period it's expired, and stays queued until cancelled
+/// - Cancelling removes it, and nothing is left to apply or cancel
+/// - Delays that overflow are reported; windows that would are open-ended
+use timelock::{cancel, check_ready, check_window, ready_at, take_ready, Queued, TimelockError};
+
+const QUEUED_AT: u64 = 1_000;
+const DELAY: u64 = 100;
+const GRACE: u64 = 50;
+
+/// A caller's pending change: a new fee, in basis points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FeeChange {
+    bps: u16,
+    ready_at: u64,
+}
+
+impl Queued for FeeChange {
+    fn ready_at(&self) -> u64 {
+        self.ready_at
+    }
+}
+
+fn queue(bps: u16) -> Option<FeeChange> {
+    let ready_at = ready_at(QUEUED_AT, DELAY).unwrap();
+    Some(FeeChange { bps, ready_at })
+}
+
+#[test]
+fn queue_execute_expire_cancel() {
+    let eta = QUEUED_AT + DELAY;
+
+    // Queued, then executed one slot early: rejected, still queued
+    let mut pending = queue(30);
+    assert_eq!(
+        take_ready(&mut pending, eta - 1, GRACE),
+        Err(TimelockError::TooEarly)
+    );
+    assert_eq!(pending, queue(30));
+
+    // Executed at its ETA: applied once, and gone
+    assert_eq!(
+        take_ready(&mut pending, eta, GRACE),
+        Ok(FeeChange {
+            bps: 30,
+            ready_at: eta
+        })
+    );
+    assert_eq!(pending, None);
+    assert_eq!(
+        take_ready(&mut pending, eta, GRACE),
+        Err(TimelockError::NothingQueued)
+    );
+
+    // Queued again, and executed one slot after the grace period: rejected,
+    // still queued
+    let mut pending = queue(40);
+    assert_eq!(
+        take_ready(&mut pending, eta + GRACE + 1, GRACE),
+        Err(TimelockError::Expired)
+    );
+    assert_eq!(pending, queue(40));
+
+    // Cancelled: removed without being applied, and nothing left
+    assert_eq!(cancel(&mut pending), Ok(queue(40).unwrap()));
+    assert_eq!(pending, None);
+    assert_eq!(
+        take_ready(&mut pending, eta, GRACE),
+        Err(TimelockError::NothingQueued)
+    );
+    assert_eq!(cancel(&mut pending), Err(TimelockError::NothingQueued));
+}
+
+#[test]
+fn window_boundaries() {
+    let eta = QUEUED_AT + DELAY;
+    assert_eq!(check_ready(eta - 1, eta), Err(TimelockError::TooEarly));
+    assert_eq!(check_ready(eta, eta), Ok(()));
+    assert_eq!(check_window(eta, eta, GRACE), Ok(()));
+    assert_eq!(check_window(eta + GRACE, eta, GRACE), Ok(()));
+    assert_eq!(
+        check_window(eta + GRACE + 1, eta, GRACE),
+        Err(TimelockError::Expired)
+    );
+    // No grace: only the ETA slot itself
+    assert_eq!(check_window(eta, eta, 0), Ok(()));
+    assert_eq!(check_window(eta + 1, eta, 0), Err(TimelockError::Expired));
+}
+
+#[test]
+fn overflow() {
+    assert_eq!(ready_at(u64::MAX, 1), Err(TimelockError::Overflow));
+    assert_eq!(ready_at(u64::MAX - 1, 1), Ok(u64::MAX));
+    assert_eq!(check_window(u64::MAX, u64::MAX - 1, u64::MAX), Ok(()));
+}
@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use authz::{AuthzError, HasAuthority, VaultWithdrawCap};
+use demo_log::{demo_log, Mode};
 
 declare_id!("HF33f3iZYeK7qz7AE1aWWGvQuxArTudNjKVseAhTYCRC");
 
@@ -25,7 +27,7 @@ pub mod missing_signer {
         let vault = &mut ctx.accounts.vault;
         vault.authority = ctx.accounts.authority.key();
         vault.balance = 0;
-        msg!("Vault initialized with authority: {}", vault.authority);
+        demo_log!(Mode::Setup, "initialize", actor = vault.authority);
         Ok(())
     }
 
@@ -48,7 +50,13 @@ pub mod missing_signer {
 
         let vault = &mut ctx.accounts.vault;
         vault.balance = vault.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;
-        msg!("Deposited {} lamports. New balance: {}", amount, vault.balance);
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount,
+            balance = vault.balance
+        );
         Ok(())
     }
 
@@ -85,7 +93,13 @@ pub mod missing_signer {
         **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= amount;
         **ctx.accounts.recipient.try_borrow_mut_lamports()? += amount;
 
-        msg!("VULNERABLE: Withdrew {} lamports without signature verification!", amount);
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.authority.key(),
+            amount = amount,
+            signed = ctx.accounts.authority.is_signer
+        );
         Ok(())
     }
 
@@ -105,8 +119,16 @@ pub mod missing_signer {
     // 1. `Signer<'info>` - Verifies signature exists
     // 2. `has_one = authority` - Verifies it's the vault's designated authority
     // Both checks are required for complete security.
+    //
+    // CAPABILITY:
+    // Lamports leave the vault only through `VaultWithdrawCap::withdraw`, and
+    // the cap can only be built from a `Signer` that matches `vault.authority`.
+    // A later instruction that drops either constraint still can't move funds
+    // without passing the same check.
     // ============================================================================
     pub fn withdraw_secure(ctx: Context<WithdrawSecure>, amount: u64) -> Result<()> {
+        let cap = VaultWithdrawCap::verify(&ctx.accounts.vault, &ctx.accounts.authority)
+            .map_err(authz_error)?;
         let vault = &mut ctx.accounts.vault;
 
         require!(vault.balance >= amount, ErrorCode::InsufficientFunds);
@@ -114,14 +136,28 @@ pub mod missing_signer {
         vault.balance = vault.balance.checked_sub(amount).ok_or(ErrorCode::Underflow)?;
 
         // Transfer lamports
-        **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= amount;
-        **ctx.accounts.recipient.try_borrow_mut_lamports()? += amount;
+        cap.withdraw(&ctx.accounts.recipient, amount).map_err(authz_error)?;
 
-        msg!("SECURE: Withdrew {} lamports with proper signature verification", amount);
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.authority.key(),
+            amount = amount
+        );
         Ok(())
     }
 }
 
+fn authz_error(err: AuthzError) -> Error {
+    match err {
+        AuthzError::WrongAuthority | AuthzError::WrongScope => ErrorCode::Unauthorized.into(),
+        AuthzError::InsufficientFunds => ErrorCode::InsufficientFunds.into(),
+        AuthzError::Overflow => ErrorCode::Overflow.into(),
+        AuthzError::AccountBorrowed => ProgramError::AccountBorrowFailed.into(),
+        AuthzError::SelfTransfer => ErrorCode::RecipientIsVault.into(),
+    }
+}
+
 // ============================================================================
 // Account Structures
 // ============================================================================
@@ -200,6 +236,12 @@ pub struct Vault {
     pub balance: u64,
 }
 
+impl HasAuthority for Vault {
+    fn authority(&self) -> Pubkey {
+        self.authority
+    }
+}
+
 #[error_code]
 pub enum ErrorCode {
     #[msg("Insufficient funds in vault")]
@@ -208,4 +250,8 @@ pub enum ErrorCode {
     Overflow,
     #[msg("Arithmetic underflow")]
     Underflow,
+    #[msg("Signer is not the vault authority")]
+    Unauthorized,
+    #[msg("Recipient is the vault")]
+    RecipientIsVault,
 }
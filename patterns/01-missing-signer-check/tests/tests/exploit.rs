@@ -6,14 +6,21 @@
 ///   signature succeeds, allowing anyone to drain the vault.
 /// - Test 2: Proves the FIX — calling `withdraw_secure` without the authority's signature
 ///   is rejected by Anchor's `Signer` constraint.
+/// - Test 3: SANITY — the authority's own signed withdrawal succeeds.
+/// - Test 4: SECURE — a signed withdrawal to the vault itself is rejected.
+///
+/// Each test also reads back the program's `demo_log!` events: a withdrawal
+/// that ran logs who was debited and how much, and a rejected one logs
+/// nothing.
+use demo_log::{capture, Mode};
 use mollusk_svm::{result::Check, Mollusk};
-use sha2::{Digest, Sha256};
 use solana_sdk::{
     account::AccountSharedData,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
 };
+use test_harness::{account_discriminator, ix_discriminator};
 
 /// The program ID must match what's in declare_id!() in the Anchor program.
 const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("HF33f3iZYeK7qz7AE1aWWGvQuxArTudNjKVseAhTYCRC");
@@ -22,26 +29,6 @@ const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("HF33f3iZYeK7qz7AE1aWWGvQuxArTudN
 // Helpers
 // ---------------------------------------------------------------------------
 
-/// Compute an Anchor instruction discriminator: sha256("global:{name}")[..8]
-fn ix_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("global:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
-/// Compute an Anchor account discriminator: sha256("account:{name}")[..8]
-fn account_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("account:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
 /// Serialize a Vault account the way Anchor stores it on-chain:
 ///   [8-byte discriminator][32-byte authority pubkey][8-byte balance (LE)]
 fn serialize_vault(authority: &Pubkey, balance: u64) -> Vec<u8> {
@@ -146,7 +133,17 @@ fn exploit_withdraw_without_signer() {
     );
 
     // The exploit should succeed — that's the whole point of the vulnerability.
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    // The program's own log records a withdrawal the authority never signed.
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Vulnerable);
+    assert_eq!(events[0].action, "withdraw");
+    assert_eq!(events[0].actor(), Some(authority.to_string().as_str()));
+    assert_eq!(events[0].amount(), Some(withdraw_amount));
+    assert_eq!(events[0].field("signed"), Some("false"));
 }
 
 #[test]
@@ -180,11 +177,15 @@ fn secure_rejects_unsigned_withdraw() {
     // Anchor's Signer<'info> type checks is_signer during account deserialization.
     // When it fails, Anchor returns error code 3010 (AccountNotSigner)
     // which maps to ProgramError::Custom(3010).
+    capture::start();
     mollusk.process_and_validate_instruction(
         &ix,
         &accounts,
         &[Check::err(ProgramError::Custom(3010))],
     );
+
+    // The handler never ran, so no withdrawal was logged.
+    assert!(capture::events().is_empty());
 }
 
 #[test]
@@ -213,5 +214,50 @@ fn secure_allows_signed_withdraw() {
     );
 
     // Legitimate withdrawal should succeed.
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Secure);
+    assert_eq!(events[0].action, "withdraw");
+    assert_eq!(events[0].actor(), Some(authority.to_string().as_str()));
+    assert_eq!(events[0].amount(), Some(withdraw_amount));
+}
+
+#[test]
+fn secure_rejects_withdraw_to_vault() {
+    // -----------------------------------------------------------------------
+    // SECURE: The authority signs withdraw_secure with the vault itself as
+    // the recipient.
+    //
+    // Expected: Transaction FAILS with RecipientIsVault (6004). The handler
+    // has already debited `vault.balance`, so a withdrawal that moved no
+    // lamports and succeeded would leave the vault holding lamports its
+    // balance no longer counts.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "missing_signer");
+
+    let withdraw_amount: u64 = 500_000;
+    let vault_lamports: u64 = 1_000_000;
+
+    let (vault_pda, authority, _recipient, accounts) =
+        setup_withdraw_accounts(vault_lamports, withdraw_amount);
+
+    let ix = build_withdraw_ix(
+        "withdraw_secure",
+        vault_pda,
+        authority,
+        vault_pda, // recipient is the vault
+        true,
+        withdraw_amount,
+    );
+
+    capture::start();
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(6004))],
+    );
+    assert!(capture::events().is_empty());
 }
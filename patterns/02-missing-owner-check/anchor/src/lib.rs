@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
 
 declare_id!("8anCcUkKVms75R4HYTnkbM6YGzAra9WTmXhNEY5RSaNw");
 
@@ -30,7 +31,12 @@ pub mod missing_owner {
         treasury.authority = ctx.accounts.authority.key();
         treasury.balance = amount;
         treasury.is_active = true;
-        msg!("Treasury initialized: authority={}, balance={}", treasury.authority, amount);
+        demo_log!(
+            Mode::Setup,
+            "initialize",
+            actor = treasury.authority,
+            amount = amount
+        );
         Ok(())
     }
 
@@ -68,10 +74,12 @@ pub mod missing_owner {
         // The program trusts all of this — but it could be completely fake
         require!(is_active, ErrorCode::TreasuryInactive);
 
-        msg!(
-            "VULNERABLE: Processed treasury — authority={}, balance={} (UNVERIFIED OWNER!)",
-            authority,
-            balance
+        demo_log!(
+            Mode::Vulnerable,
+            "process",
+            actor = authority,
+            amount = balance,
+            owner = ctx.accounts.treasury.owner
         );
         Ok(())
     }
@@ -100,10 +108,11 @@ pub mod missing_owner {
 
         require!(treasury.is_active, ErrorCode::TreasuryInactive);
 
-        msg!(
-            "SECURE: Processed treasury — authority={}, balance={} (OWNER VERIFIED)",
-            treasury.authority,
-            treasury.balance
+        demo_log!(
+            Mode::Secure,
+            "process",
+            actor = treasury.authority,
+            amount = treasury.balance
         );
         Ok(())
     }
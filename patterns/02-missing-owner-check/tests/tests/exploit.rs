@@ -7,14 +7,19 @@
 /// - Test 2: SECURE — the same fake account is rejected because Anchor's
 ///   `Account<'info, T>` verifies owner == program_id.
 /// - Test 3: SANITY — a legitimate treasury with correct owner is accepted.
+///
+/// Each test also reads back the program's `demo_log!` events, which record
+/// the balance the handler acted on and, in the vulnerable handler, who owns
+/// the account it came from.
+use demo_log::{capture, Mode};
 use mollusk_svm::{result::Check, Mollusk};
-use sha2::{Digest, Sha256};
 use solana_sdk::{
     account::AccountSharedData,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
 };
+use test_harness::{account_discriminator, ix_discriminator};
 
 const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8anCcUkKVms75R4HYTnkbM6YGzAra9WTmXhNEY5RSaNw");
 
@@ -22,24 +27,6 @@ const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8anCcUkKVms75R4HYTnkbM6YGzAra9WT
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn ix_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("global:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
-fn account_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("account:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
 /// Serialize a Treasury account:
 ///   [8-byte discriminator][32-byte authority][8-byte balance (LE)][1-byte is_active]
 fn serialize_treasury(authority: &Pubkey, balance: u64, is_active: bool) -> Vec<u8> {
@@ -98,7 +85,21 @@ fn exploit_fake_account_accepted() {
     ];
 
     // EXPLOIT: The program reads the fake data and trusts it.
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    // The program logged the attacker's invented balance, read from an
+    // account the System Program owns.
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Vulnerable);
+    assert_eq!(events[0].action, "process");
+    assert_eq!(events[0].actor(), Some(attacker.to_string().as_str()));
+    assert_eq!(events[0].amount(), Some(999_999_999));
+    assert_eq!(
+        events[0].field("owner"),
+        Some(solana_sdk::system_program::ID.to_string().as_str())
+    );
 }
 
 #[test]
@@ -141,11 +142,15 @@ fn secure_rejects_fake_account() {
 
     // Anchor error 3007 = AccountOwnedByWrongProgram
     // "The given account is owned by a different program than expected"
+    capture::start();
     mollusk.process_and_validate_instruction(
         &ix,
         &accounts,
         &[Check::err(ProgramError::Custom(3007))],
     );
+
+    // Rejected before the handler ran: nothing was processed.
+    assert!(capture::events().is_empty());
 }
 
 #[test]
@@ -182,5 +187,13 @@ fn secure_accepts_real_treasury() {
         (authority, authority_account),
     ];
 
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Secure);
+    assert_eq!(events[0].action, "process");
+    assert_eq!(events[0].actor(), Some(authority.to_string().as_str()));
+    assert_eq!(events[0].amount(), Some(1_000_000));
 }
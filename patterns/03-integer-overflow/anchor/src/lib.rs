@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
 
 declare_id!("3w5jyYEgbsnHjFcTUH9xdyH3KfN2YRppPCFUkskyYSxA");
 
@@ -30,7 +31,12 @@ pub mod integer_overflow {
         ledger.authority = ctx.accounts.authority.key();
         ledger.total_supply = initial_supply;
         ledger.user_balance = 0;
-        msg!("Ledger initialized: supply={}", initial_supply);
+        demo_log!(
+            Mode::Setup,
+            "initialize",
+            actor = ledger.authority,
+            supply = initial_supply
+        );
         Ok(())
     }
 
@@ -60,11 +66,13 @@ pub mod integer_overflow {
         ledger.total_supply = ledger.total_supply.wrapping_add(amount);
         ledger.user_balance = ledger.user_balance.wrapping_add(amount);
 
-        msg!(
-            "VULNERABLE MINT: amount={}, new_supply={}, new_balance={}",
-            amount,
-            ledger.total_supply,
-            ledger.user_balance
+        demo_log!(
+            Mode::Vulnerable,
+            "mint",
+            actor = ctx.accounts.authority.key(),
+            amount = amount,
+            supply = ledger.total_supply,
+            balance = ledger.user_balance
         );
         Ok(())
     }
@@ -76,11 +84,13 @@ pub mod integer_overflow {
         ledger.user_balance = ledger.user_balance.wrapping_sub(amount);
         ledger.total_supply = ledger.total_supply.wrapping_sub(amount);
 
-        msg!(
-            "VULNERABLE BURN: amount={}, new_supply={}, new_balance={}",
-            amount,
-            ledger.total_supply,
-            ledger.user_balance
+        demo_log!(
+            Mode::Vulnerable,
+            "burn",
+            actor = ctx.accounts.authority.key(),
+            amount = amount,
+            supply = ledger.total_supply,
+            balance = ledger.user_balance
         );
         Ok(())
     }
@@ -114,11 +124,13 @@ pub mod integer_overflow {
             .checked_add(amount)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
 
-        msg!(
-            "SECURE MINT: amount={}, new_supply={}, new_balance={}",
-            amount,
-            ledger.total_supply,
-            ledger.user_balance
+        demo_log!(
+            Mode::Secure,
+            "mint",
+            actor = ctx.accounts.authority.key(),
+            amount = amount,
+            supply = ledger.total_supply,
+            balance = ledger.user_balance
         );
         Ok(())
     }
@@ -137,11 +149,13 @@ pub mod integer_overflow {
             .checked_sub(amount)
             .ok_or(ErrorCode::ArithmeticUnderflow)?;
 
-        msg!(
-            "SECURE BURN: amount={}, new_supply={}, new_balance={}",
-            amount,
-            ledger.total_supply,
-            ledger.user_balance
+        demo_log!(
+            Mode::Secure,
+            "burn",
+            actor = ctx.accounts.authority.key(),
+            amount = amount,
+            supply = ledger.total_supply,
+            balance = ledger.user_balance
         );
         Ok(())
     }
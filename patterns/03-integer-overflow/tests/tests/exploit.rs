@@ -5,14 +5,18 @@
 /// - Underflow balance to u64::MAX (bypass insufficient funds)
 ///
 /// Tests prove checked_add/checked_sub catch both cases.
+///
+/// Each test also reads back the program's `demo_log!` events, which record
+/// the supply and balance the ledger was left with.
+use demo_log::{capture, DemoEvent, Mode};
 use mollusk_svm::{result::Check, Mollusk};
-use sha2::{Digest, Sha256};
 use solana_sdk::{
     account::AccountSharedData,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
 };
+use test_harness::{account_discriminator, ix_discriminator};
 
 const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("3w5jyYEgbsnHjFcTUH9xdyH3KfN2YRppPCFUkskyYSxA");
 
@@ -20,24 +24,6 @@ const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("3w5jyYEgbsnHjFcTUH9xdyH3KfN2YRpp
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn ix_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("global:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
-fn account_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("account:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
 /// Serialize a Ledger account:
 ///   [8-byte discriminator][32-byte authority][8-byte total_supply][8-byte user_balance]
 fn serialize_ledger(authority: &Pubkey, total_supply: u64, user_balance: u64) -> Vec<u8> {
@@ -87,6 +73,16 @@ fn setup_ledger(
     (ledger_key, authority, accounts)
 }
 
+/// The one event the last instruction logged, checked against `mode`/`action`.
+fn only_event(mode: Mode, action: &str) -> DemoEvent {
+    let mut events = capture::events();
+    assert_eq!(events.len(), 1, "{events:?}");
+    let event = events.remove(0);
+    assert_eq!(event.mode, mode);
+    assert_eq!(event.action, action);
+    event
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -106,7 +102,15 @@ fn exploit_overflow_supply_wraps_to_zero() {
     let (ledger, authority, accounts) = setup_ledger(u64::MAX, 0);
     let ix = build_ix("mint_vulnerable", ledger, authority, 1);
 
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    // The program logged the wrapped supply: one token minted, zero in supply.
+    let event = only_event(Mode::Vulnerable, "mint");
+    assert_eq!(event.actor(), Some(authority.to_string().as_str()));
+    assert_eq!(event.amount(), Some(1));
+    assert_eq!(event.field("supply"), Some("0"));
+    assert_eq!(event.field("balance"), Some("1"));
 }
 
 #[test]
@@ -126,7 +130,14 @@ fn exploit_underflow_balance_wraps_to_max() {
     let (ledger, authority, accounts) = setup_ledger(1000, 10);
     let ix = build_ix("burn_vulnerable", ledger, authority, 11);
 
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    // The program logged a balance of u64::MAX after burning 11 of 10 tokens.
+    let event = only_event(Mode::Vulnerable, "burn");
+    assert_eq!(event.amount(), Some(11));
+    assert_eq!(event.field("supply"), Some("989"));
+    assert_eq!(event.field("balance"), Some(u64::MAX.to_string().as_str()));
 }
 
 #[test]
@@ -145,11 +156,15 @@ fn secure_blocks_overflow() {
     let ix = build_ix("mint_secure", ledger, authority, 1);
 
     // Anchor custom error: ArithmeticOverflow = 6000 (first #[error_code] variant)
+    capture::start();
     mollusk.process_and_validate_instruction(
         &ix,
         &accounts,
         &[Check::err(ProgramError::Custom(6000))],
     );
+
+    // The handler failed before logging, so nothing was recorded.
+    assert!(capture::events().is_empty());
 }
 
 #[test]
@@ -168,11 +183,15 @@ fn secure_blocks_underflow() {
     let ix = build_ix("burn_secure", ledger, authority, 11);
 
     // Anchor custom error: ArithmeticUnderflow = 6001 (second #[error_code] variant)
+    capture::start();
     mollusk.process_and_validate_instruction(
         &ix,
         &accounts,
         &[Check::err(ProgramError::Custom(6001))],
     );
+
+    // The handler failed before logging, so nothing was recorded.
+    assert!(capture::events().is_empty());
 }
 
 #[test]
@@ -185,5 +204,11 @@ fn secure_allows_valid_mint() {
     let (ledger, authority, accounts) = setup_ledger(1000, 500);
     let ix = build_ix("mint_secure", ledger, authority, 100);
 
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    let event = only_event(Mode::Secure, "mint");
+    assert_eq!(event.amount(), Some(100));
+    assert_eq!(event.field("supply"), Some("1100"));
+    assert_eq!(event.field("balance"), Some("600"));
 }
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
 
 declare_id!("2P1GgtagVaYR8B6FhrPHdP4Mmy3pUFAZtSyeWFK293vg");
 
@@ -46,9 +47,11 @@ pub mod reinitialization {
         config.is_initialized = true;
         config.vault_balance = 0;
 
-        msg!(
-            "VULNERABLE INIT: authority set to {} (no re-init guard!)",
-            config.authority
+        demo_log!(
+            Mode::Vulnerable,
+            "initialize",
+            actor = config.authority,
+            guard = "none"
         );
         Ok(())
     }
@@ -76,9 +79,11 @@ pub mod reinitialization {
         config.is_initialized = true;
         config.vault_balance = 0;
 
-        msg!(
-            "SECURE INIT: authority set to {} (one-time only)",
-            config.authority
+        demo_log!(
+            Mode::Secure,
+            "initialize",
+            actor = config.authority,
+            guard = "is_initialized"
         );
         Ok(())
     }
@@ -92,9 +97,11 @@ pub mod reinitialization {
         config.is_initialized = true;
         config.vault_balance = 0;
 
-        msg!(
-            "ANCHOR INIT: authority set to {} (init constraint enforced)",
-            config.authority
+        demo_log!(
+            Mode::Secure,
+            "initialize",
+            actor = config.authority,
+            guard = "init"
         );
         Ok(())
     }
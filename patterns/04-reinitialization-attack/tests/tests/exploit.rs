@@ -6,14 +6,18 @@
 /// - Test 1: EXPLOIT — re-init overwrites the authority with attacker's key.
 /// - Test 2: SECURE — manual is_initialized guard blocks the re-init.
 /// - Test 3: SANITY — first-time secure init succeeds.
+///
+/// Each test also reads back the program's `demo_log!` events, which record
+/// the authority written and the guard that let the init through.
+use demo_log::{capture, Mode};
 use mollusk_svm::{result::Check, Mollusk};
-use sha2::{Digest, Sha256};
 use solana_sdk::{
     account::AccountSharedData,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
 };
+use test_harness::{account_discriminator, ix_discriminator};
 
 const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("2P1GgtagVaYR8B6FhrPHdP4Mmy3pUFAZtSyeWFK293vg");
 
@@ -21,24 +25,6 @@ const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("2P1GgtagVaYR8B6FhrPHdP4Mmy3pUFAZ
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn ix_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("global:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
-fn account_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("account:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
 /// Serialize a Config account:
 ///   [8-byte discriminator][32-byte authority][1-byte is_initialized][8-byte vault_balance]
 fn serialize_config(authority: &Pubkey, is_initialized: bool, vault_balance: u64) -> Vec<u8> {
@@ -97,7 +83,16 @@ fn exploit_reinit_overwrites_authority() {
     ];
 
     // Re-init succeeds — attacker now owns the config
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    // The program logged the attacker as the new authority, with no guard.
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Vulnerable);
+    assert_eq!(events[0].action, "initialize");
+    assert_eq!(events[0].actor(), Some(attacker.to_string().as_str()));
+    assert_eq!(events[0].field("guard"), Some("none"));
 }
 
 #[test]
@@ -137,11 +132,15 @@ fn secure_blocks_reinit() {
     ];
 
     // AlreadyInitialized = error code 6000 (first #[error_code] variant)
+    capture::start();
     mollusk.process_and_validate_instruction(
         &ix,
         &accounts,
         &[Check::err(ProgramError::Custom(6000))],
     );
+
+    // The guard rejected the call before anything was written or logged.
+    assert!(capture::events().is_empty());
 }
 
 #[test]
@@ -176,5 +175,13 @@ fn secure_allows_first_init() {
         (authority, authority_account),
     ];
 
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Secure);
+    assert_eq!(events[0].action, "initialize");
+    assert_eq!(events[0].actor(), Some(authority.to_string().as_str()));
+    assert_eq!(events[0].field("guard"), Some("is_initialized"));
 }
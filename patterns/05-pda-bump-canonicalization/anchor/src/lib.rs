@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
 
 declare_id!("x1rqubJg3BK9Q5FbHqaxSW4cU5toBeAQkWyw8cELaRm");
 
@@ -58,10 +59,12 @@ pub mod pda_bump {
         data_account.value = value;
         data_account.bump = bump; // Stores whatever bump was provided
 
-        msg!(
-            "VULNERABLE: Set value={} at bump={} (non-canonical bump accepted!)",
-            value,
-            bump
+        demo_log!(
+            Mode::Vulnerable,
+            "set_value",
+            actor = ctx.accounts.user.key(),
+            value = value,
+            bump = bump
         );
         Ok(())
     }
@@ -97,10 +100,12 @@ pub mod pda_bump {
         data_account.value = value;
         data_account.bump = canonical_bump; // Always stores canonical bump
 
-        msg!(
-            "SECURE: Set value={} at canonical bump={} (only valid bump accepted)",
-            value,
-            canonical_bump
+        demo_log!(
+            Mode::Secure,
+            "set_value",
+            actor = ctx.accounts.user.key(),
+            value = value,
+            bump = canonical_bump
         );
         Ok(())
     }
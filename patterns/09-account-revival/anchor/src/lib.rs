@@ -0,0 +1,264 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use demo_log::{demo_log, Mode};
+
+declare_id!("AHDWTKCqLBY7LnauhWnw436bdaA26nJNpQEr7u3FNPyg");
+
+/// # Account Revival Vulnerability
+///
+/// This program demonstrates closing an account by draining its lamports
+/// while leaving its data and owner untouched.
+///
+/// ## The Vulnerability
+/// The runtime only deletes a zero-lamport account at the end of the
+/// transaction. Until then the account keeps its data and is still owned by
+/// the program. If a later instruction in the same transaction sends the
+/// account rent again, it survives, and its old data is valid again. A prize
+/// ticket redeemed this way can be redeemed again, as often as the attacker
+/// likes.
+///
+/// ## Real-World Impact
+/// Anything that "closes" a one-shot record — reward tickets, vouchers,
+/// escrow receipts, claim records — can be revived and replayed, paying out
+/// again from the shared vault each time.
+///
+/// ## The Fix
+/// A close has three parts: move every lamport out, wipe the data, and hand
+/// the account back to the System Program. Anchor's `close = destination`
+/// constraint does all three after the handler returns.
+#[program]
+pub mod account_revival {
+    use super::*;
+
+    /// Create the vault PDA that pays prizes. The admin funds it with plain
+    /// system transfers.
+    pub fn init_vault(ctx: Context<InitVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.admin = ctx.accounts.admin.key();
+        vault.bump = ctx.bumps.vault;
+        Ok(())
+    }
+
+    /// Issue `owner` a ticket worth `prize` lamports.
+    pub fn issue_ticket(ctx: Context<IssueTicket>, prize: u64) -> Result<()> {
+        let ticket = &mut ctx.accounts.ticket;
+        ticket.owner = ctx.accounts.owner.key();
+        ticket.prize = prize;
+        ticket.bump = ctx.bumps.ticket;
+
+        demo_log!(
+            Mode::Setup,
+            "issue_ticket",
+            actor = ctx.accounts.admin.key(),
+            amount = prize,
+            owner = ticket.owner
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Ticket "closed" by draining its lamports only
+    // ============================================================================
+    // ISSUE: The ticket's rent goes to the owner, but its data and owner
+    //        program stay as they were. The account is only gone if it still
+    //        has zero lamports when the transaction ends.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker holds a 1 SOL ticket
+    //   2. In one transaction: redeem_vulnerable, then a system transfer
+    //      that sends the ticket its rent back
+    //   3. The ticket ends the transaction funded, program-owned, and with
+    //      the same data — it was never closed
+    //   4. The next transaction redeems it again; repeat until the vault
+    //      is empty
+    // ============================================================================
+    pub fn redeem_vulnerable(ctx: Context<RedeemVulnerable>) -> Result<()> {
+        let prize = ctx.accounts.ticket.prize;
+        pay_prize(&ctx.accounts.vault, &ctx.accounts.owner, prize)?;
+
+        // VULNERABLE: lamports out, data and owner left behind
+        let ticket = ctx.accounts.ticket.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+        **owner.try_borrow_mut_lamports()? = owner
+            .lamports()
+            .checked_add(ticket.lamports())
+            .ok_or(ErrorCode::Overflow)?;
+        **ticket.try_borrow_mut_lamports()? = 0;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "redeem",
+            actor = ctx.accounts.owner.key(),
+            amount = prize,
+            close = "lamports_only"
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Full manual close
+    // ============================================================================
+    // FIX: Drain the lamports, zero the data, assign the account to the
+    //      System Program and shrink it to zero bytes. A re-funded account
+    //      is then an empty system account, and `Account<Ticket>` rejects it.
+    //
+    // NOTE: Zeroing the data alone isn't enough in Anchor. On exit, Anchor
+    // writes every mutable `Account<T>` back to its data unless the account
+    // is closed (system-owned and empty), which would restore the ticket.
+    // ============================================================================
+    pub fn redeem_secure_manual(ctx: Context<RedeemVulnerable>) -> Result<()> {
+        let prize = ctx.accounts.ticket.prize;
+        pay_prize(&ctx.accounts.vault, &ctx.accounts.owner, prize)?;
+
+        let ticket = ctx.accounts.ticket.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+        // SECURE: 1. move every lamport out
+        **owner.try_borrow_mut_lamports()? = owner
+            .lamports()
+            .checked_add(ticket.lamports())
+            .ok_or(ErrorCode::Overflow)?;
+        **ticket.try_borrow_mut_lamports()? = 0;
+        // SECURE: 2. wipe the data
+        ticket.try_borrow_mut_data()?.fill(0);
+        // SECURE: 3. give the account back to the System Program
+        ticket.assign(&system_program::ID);
+        ticket.realloc(0, false)?;
+
+        demo_log!(
+            Mode::Secure,
+            "redeem",
+            actor = ctx.accounts.owner.key(),
+            amount = prize,
+            close = "manual"
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Anchor's `close = owner` constraint
+    // ============================================================================
+    // FIX: The same three steps as the manual close, run by Anchor after the
+    //      handler returns. Prefer this; it can't be half-written.
+    // ============================================================================
+    pub fn redeem_secure(ctx: Context<RedeemSecure>) -> Result<()> {
+        let prize = ctx.accounts.ticket.prize;
+        pay_prize(&ctx.accounts.vault, &ctx.accounts.owner, prize)?;
+
+        demo_log!(
+            Mode::Secure,
+            "redeem",
+            actor = ctx.accounts.owner.key(),
+            amount = prize,
+            close = "constraint"
+        );
+        Ok(())
+    }
+}
+
+fn pay_prize(vault: &Account<Vault>, owner: &Signer, prize: u64) -> Result<()> {
+    let vault = vault.to_account_info();
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(prize)
+        .ok_or(ErrorCode::VaultEmpty)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(prize)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitVault<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IssueTicket<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump, has_one = admin)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [b"ticket", owner.key().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+    /// CHECK: Ticket holder; only its key is recorded.
+    pub owner: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by redeem_vulnerable and redeem_secure_manual; the difference is
+/// in how the handler closes the ticket.
+#[derive(Accounts)]
+pub struct RedeemVulnerable<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"ticket", owner.key().as_ref()],
+        bump = ticket.bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// SECURE account structure - Anchor closes the ticket on exit
+#[derive(Accounts)]
+pub struct RedeemSecure<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"ticket", owner.key().as_ref()],
+        bump = ticket.bump,
+        close = owner // <-- lamports out, owner reassigned, data freed
+    )]
+    pub ticket: Account<'info, Ticket>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub admin: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Ticket {
+    pub owner: Pubkey, // 32 bytes
+    pub prize: u64,    //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Vault cannot cover the prize")]
+    VaultEmpty,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
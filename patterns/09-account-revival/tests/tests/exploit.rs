@@ -0,0 +1,203 @@
+/// # Pattern 9: Account Revival — Mollusk Exploit Tests
+///
+/// The attacker holds a ticket worth 1 SOL; the vault holds 3 SOL of
+/// prizes. Between redeems, each test does what the attacker's system
+/// transfer does in the same transaction: it sends the closed ticket its
+/// rent back.
+///
+/// - Test 1: EXPLOIT — a ticket "closed" by draining lamports is re-funded
+///   with its data intact and redeemed a second time.
+/// - Test 2: SECURE — after `close = owner` the re-funded ticket is an empty
+///   system account, and the second redeem fails.
+/// - Test 3: SECURE — the manual close (zero, assign, shrink) gives the
+///   same result.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount, WritableAccount},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, system_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("AHDWTKCqLBY7LnauhWnw436bdaA26nJNpQEr7u3FNPyg");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000; // above the rent-exempt minimum for both accounts
+
+// Anchor error codes
+const ACCOUNT_OWNED_BY_WRONG_PROGRAM: u32 = 3007;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Ticket account: [8 disc][32 owner][8 prize][1 bump]
+fn serialize_ticket(owner: &Pubkey, prize: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Ticket"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&prize.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The ticket PDA for `owner`. Returns (address, account, data).
+fn ticket(owner: &Pubkey, prize: u64) -> (Pubkey, AccountSharedData, Vec<u8>) {
+    let (address, bump) = Pubkey::find_program_address(&[b"ticket", owner.as_ref()], &PROGRAM_ID);
+    let data = serialize_ticket(owner, prize, bump);
+    let mut account = AccountSharedData::new(RENT, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account, data)
+}
+
+/// The vault PDA holding `prizes` lamports above rent.
+fn vault(prizes: u64) -> (Pubkey, AccountSharedData) {
+    let (address, bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+    let mut data = account_discriminator("Vault").to_vec();
+    data.extend_from_slice(Pubkey::new_unique().as_ref()); // admin
+    data.push(bump);
+    let mut account = AccountSharedData::new(RENT + prizes, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account)
+}
+
+fn redeem_ix(name: &str, vault: Pubkey, ticket: Pubkey, owner: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator(name),
+        vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new(ticket, false),
+            AccountMeta::new(owner, true),
+        ],
+    )
+}
+
+/// The attacker's follow-up system transfer: send the closed ticket its
+/// rent back. Whatever owner and data the close left behind are kept.
+fn refund(mut closed: AccountSharedData) -> AccountSharedData {
+    closed.set_lamports(RENT);
+    closed
+}
+
+/// Redeem with `name`, re-fund the ticket, and redeem again. The first
+/// redeem must close the ticket properly; the second must fail with 3007.
+fn assert_closed_ticket_stays_dead(name: &str) {
+    let mollusk = Mollusk::new(&PROGRAM_ID, "account_revival");
+    let attacker = Pubkey::new_unique();
+    let (ticket, ticket_account, _) = ticket(&attacker, SOL);
+    let (vault, vault_account) = vault(3 * SOL);
+
+    let result = mollusk.process_and_validate_instruction(
+        &redeem_ix(name, vault, ticket, attacker),
+        &[
+            (vault, vault_account),
+            (ticket, ticket_account),
+            (attacker, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&ticket)
+                .lamports(0)
+                .owner(&solana_sdk::system_program::ID)
+                .space(0)
+                .build(),
+            Check::account(&attacker).lamports(2 * RENT + SOL).build(),
+        ],
+    );
+    let vault_account = result.resulting_accounts[0].1.clone();
+    let revived = refund(result.resulting_accounts[1].1.clone());
+    assert!(revived.data().is_empty());
+
+    mollusk.process_and_validate_instruction(
+        &redeem_ix(name, vault, ticket, attacker),
+        &[
+            (vault, vault_account),
+            (ticket, revived),
+            (attacker, system_account(RENT)),
+        ],
+        &[Check::err(ProgramError::Custom(
+            ACCOUNT_OWNED_BY_WRONG_PROGRAM,
+        ))],
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_drained_ticket_revived_and_redeemed_twice() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker redeems their 1 SOL ticket with
+    // redeem_vulnerable, sends the ticket its rent back in the same
+    // transaction, and redeems it again in the next one.
+    //
+    // Expected: SUCCEEDS twice — the first redeem leaves the ticket at zero
+    //           lamports with its data untouched; after the refund the
+    //           second redeem pays another 1 SOL.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "account_revival");
+    let attacker = Pubkey::new_unique();
+    let (ticket, ticket_account, ticket_data) = ticket(&attacker, SOL);
+    let (vault, vault_account) = vault(3 * SOL);
+
+    let result = mollusk.process_and_validate_instruction(
+        &redeem_ix("redeem_vulnerable", vault, ticket, attacker),
+        &[
+            (vault, vault_account),
+            (ticket, ticket_account),
+            (attacker, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&ticket)
+                .lamports(0)
+                .owner(&PROGRAM_ID) // <-- still ours
+                .data(&ticket_data) // <-- still a valid ticket
+                .build(),
+            Check::account(&vault).lamports(RENT + 2 * SOL).build(),
+        ],
+    );
+    let vault_account = result.resulting_accounts[0].1.clone();
+    let revived = refund(result.resulting_accounts[1].1.clone());
+
+    // Next transaction: the same ticket pays out again
+    mollusk.process_and_validate_instruction(
+        &redeem_ix("redeem_vulnerable", vault, ticket, attacker),
+        &[
+            (vault, vault_account),
+            (ticket, revived),
+            (attacker, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(RENT + SOL).build(),
+            Check::account(&attacker).lamports(2 * RENT + SOL).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_close_constraint_blocks_revival() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same sequence through redeem_secure (`close = owner`).
+    //
+    // Expected: The first redeem pays 1 SOL and leaves the ticket empty and
+    //           system-owned. After the refund, the second FAILS with
+    //           AccountOwnedByWrongProgram (3007).
+    // -----------------------------------------------------------------------
+    assert_closed_ticket_stays_dead("redeem_secure");
+}
+
+#[test]
+fn secure_manual_close_blocks_revival() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same sequence through redeem_secure_manual, which zeroes
+    // the data, assigns the ticket to the System Program, and shrinks it.
+    //
+    // Expected: Same as the `close` constraint — 3007 on the second redeem.
+    // -----------------------------------------------------------------------
+    assert_closed_ticket_stays_dead("redeem_secure_manual");
+}
@@ -0,0 +1,321 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("EuubgdYBWZgZLrNdhJi515XePrksZCUhHXjkRtNGucNV");
+
+/// Written over the discriminator of a closed account. Older Anchor
+/// versions exported this value under the same name; 0.31 doesn't, so the
+/// program defines it.
+pub const CLOSED_ACCOUNT_DISCRIMINATOR: [u8; 8] = [255; 8];
+
+/// # Closed Account Cosplay
+///
+/// ## The Vulnerability
+/// A manual close that only drains lamports leaves the 8-byte discriminator
+/// and every field in place. Until the transaction ends, the account is
+/// still owned by the program and still deserializes as a live account. Any
+/// later instruction in the same transaction accepts it: here, a deposit
+/// receipt that was just redeemed is used as collateral for a loan.
+///
+/// ## Real-World Impact
+/// Receipts, positions, and claim records get "used up" by closing them.
+/// If the closed record still passes as live, it can back a loan, a vote,
+/// or a reward claim after the value behind it is gone. Any debt written to
+/// it disappears with the account.
+///
+/// ## The Fix
+/// When closing by hand, overwrite the discriminator with
+/// `CLOSED_ACCOUNT_DISCRIMINATOR`. `Account<T>` then fails to deserialize
+/// it, and hand-written loaders check for the marker before parsing.
+#[program]
+pub mod closed_account_cosplay {
+    use super::*;
+
+    /// Create the pool PDA that holds deposits and funds loans.
+    pub fn init_pool(ctx: Context<InitPool>) -> Result<()> {
+        ctx.accounts.pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    /// Move `amount` lamports into the pool and issue a receipt for them.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.owner = ctx.accounts.owner.key();
+        receipt.amount = amount;
+        receipt.borrowed = 0;
+        receipt.bump = ctx.bumps.receipt;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = receipt.owner,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Close drains lamports, discriminator left intact
+    // ============================================================================
+    // ISSUE: After redeem_vulnerable the receipt has zero lamports, but its
+    //        discriminator, owner, and amount are untouched. It is deleted
+    //        only when the transaction ends.
+    //
+    // ATTACK SCENARIO (one transaction):
+    //   1. Attacker holds a 2 SOL receipt with no loan against it
+    //   2. ix 1: redeem_vulnerable — 2 SOL back, receipt drained
+    //   3. ix 2: borrow_vulnerable with the same receipt — it still reads
+    //      as a 2 SOL deposit, so 1 SOL is lent against it
+    //   4. The transaction ends; the receipt and its debt are deleted
+    //   5. The attacker keeps 3 SOL for a 2 SOL deposit
+    // ============================================================================
+    pub fn redeem_vulnerable(ctx: Context<ReceiptAction>) -> Result<()> {
+        let receipt = &ctx.accounts.receipt;
+        require!(receipt.borrowed == 0, ErrorCode::OutstandingLoan);
+        let amount = receipt.amount;
+        pay_out(&ctx.accounts.pool, &ctx.accounts.owner, amount)?;
+
+        // VULNERABLE: lamports out, discriminator and fields left behind
+        let receipt = ctx.accounts.receipt.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+        **owner.try_borrow_mut_lamports()? = owner
+            .lamports()
+            .checked_add(receipt.lamports())
+            .ok_or(ErrorCode::Overflow)?;
+        **receipt.try_borrow_mut_lamports()? = 0;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "redeem",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            discriminator = "kept"
+        );
+        Ok(())
+    }
+
+    /// Lend up to half of the receipt's deposit.
+    pub fn borrow_vulnerable(ctx: Context<ReceiptAction>, amount: u64) -> Result<()> {
+        // VULNERABLE: a receipt closed earlier in this transaction still
+        // deserializes here
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.borrowed = checked_borrow(receipt, amount)?;
+        pay_out(&ctx.accounts.pool, &ctx.accounts.owner, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            receipt_lamports = ctx.accounts.receipt.get_lamports()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Close marks the account, reads refuse the mark
+    // ============================================================================
+    // FIX: The close zeroes the data and writes CLOSED_ACCOUNT_DISCRIMINATOR
+    //      over the first 8 bytes. `load_receipt` refuses that marker before
+    //      parsing, and `Account<Receipt>` fails on it too.
+    //
+    // NOTE: The receipt is an UncheckedAccount here on purpose. Anchor
+    // writes a mutable `Account<T>` back to its data on exit, which would
+    // put the old discriminator right back over the marker.
+    // ============================================================================
+    pub fn redeem_secure(ctx: Context<ReceiptActionSecure>) -> Result<()> {
+        let receipt = load_receipt(&ctx.accounts.receipt, &ctx.accounts.owner)?;
+        require!(receipt.borrowed == 0, ErrorCode::OutstandingLoan);
+        pay_out(&ctx.accounts.pool, &ctx.accounts.owner, receipt.amount)?;
+
+        // SECURE: drain, zero, and mark as closed
+        let info = ctx.accounts.receipt.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+        **owner.try_borrow_mut_lamports()? = owner
+            .lamports()
+            .checked_add(info.lamports())
+            .ok_or(ErrorCode::Overflow)?;
+        **info.try_borrow_mut_lamports()? = 0;
+        let mut data = info.try_borrow_mut_data()?;
+        data.fill(0);
+        data[..8].copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR);
+
+        demo_log!(
+            Mode::Secure,
+            "redeem",
+            actor = ctx.accounts.owner.key(),
+            amount = receipt.amount,
+            discriminator = "closed"
+        );
+        Ok(())
+    }
+
+    /// Lend up to half of the receipt's deposit, refusing closed receipts.
+    pub fn borrow_secure(ctx: Context<ReceiptActionSecure>, amount: u64) -> Result<()> {
+        let mut receipt = load_receipt(&ctx.accounts.receipt, &ctx.accounts.owner)?;
+        receipt.borrowed = checked_borrow(&receipt, amount)?;
+        pay_out(&ctx.accounts.pool, &ctx.accounts.owner, amount)?;
+        receipt.try_serialize(&mut &mut ctx.accounts.receipt.try_borrow_mut_data()?[..])?;
+
+        demo_log!(
+            Mode::Secure,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            receipt_lamports = ctx.accounts.receipt.get_lamports()
+        );
+        Ok(())
+    }
+}
+
+/// Read a receipt from a raw account. Closed receipts are refused before
+/// they're parsed.
+fn load_receipt(info: &AccountInfo, owner: &Signer) -> Result<Receipt> {
+    if info.owner != &crate::ID {
+        return Err(anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    let data = info.try_borrow_data()?;
+    // SECURE: a closed account is rejected by name
+    require!(
+        data.get(..8) != Some(&CLOSED_ACCOUNT_DISCRIMINATOR[..]),
+        ErrorCode::AccountClosed
+    );
+    let receipt = Receipt::try_deserialize(&mut &data[..])?;
+    require_keys_eq!(receipt.owner, owner.key(), ErrorCode::NotReceiptOwner);
+    Ok(receipt)
+}
+
+/// The receipt's new `borrowed` total, if `amount` stays within half the
+/// deposit.
+fn checked_borrow(receipt: &Receipt, amount: u64) -> Result<u64> {
+    let borrowed = receipt
+        .borrowed
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(borrowed <= receipt.amount / 2, ErrorCode::BorrowLimit);
+    Ok(borrowed)
+}
+
+fn pay_out(pool: &Account<Pool>, owner: &Signer, amount: u64) -> Result<()> {
+    let pool = pool.to_account_info();
+    **pool.try_borrow_mut_lamports()? = pool
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::PoolEmpty)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Receipt::INIT_SPACE,
+        seeds = [b"receipt", owner.key().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, Receipt>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// VULNERABLE account structure - shared by redeem_vulnerable and
+/// borrow_vulnerable. A drained receipt still deserializes here.
+#[derive(Accounts)]
+pub struct ReceiptAction<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"receipt", owner.key().as_ref()],
+        bump = receipt.bump
+    )]
+    pub receipt: Account<'info, Receipt>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// SECURE account structure - shared by redeem_secure and borrow_secure.
+/// The receipt is loaded by `load_receipt`.
+#[derive(Accounts)]
+pub struct ReceiptActionSecure<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    /// CHECK: Loaded by `load_receipt`, which checks the owner program, the
+    /// closed marker, the discriminator, and the receipt owner.
+    #[account(mut, seeds = [b"receipt", owner.key().as_ref()], bump)]
+    pub receipt: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Holds every deposit and pays out loans.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub bump: u8, // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Receipt {
+    pub owner: Pubkey, // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub borrowed: u64, //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Receipt has been closed")]
+    AccountClosed,
+    #[msg("Signer does not own this receipt")]
+    NotReceiptOwner,
+    #[msg("Repay the loan before redeeming")]
+    OutstandingLoan,
+    #[msg("Borrow exceeds half the deposit")]
+    BorrowLimit,
+    #[msg("Pool cannot cover the payout")]
+    PoolEmpty,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,263 @@
+/// # Pattern 10: Closed Account Cosplay — Mollusk Exploit Tests
+///
+/// The attacker holds a receipt for a 2 SOL deposit with no loan against it.
+/// Each test passes the receipt a close left behind straight into the next
+/// instruction, as a later instruction in the same transaction would see it.
+///
+/// - Test 1: EXPLOIT — after redeem_vulnerable the drained receipt still
+///   backs a 1 SOL loan.
+/// - Test 2: SECURE — after redeem_secure, borrow_secure refuses the closed
+///   receipt by name.
+/// - Test 3: SECURE — the closed marker also fails a plain `Account<Receipt>`
+///   in borrow_vulnerable.
+/// - Test 4: SANITY — borrow_secure lends against a live receipt.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, system_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("EuubgdYBWZgZLrNdhJi515XePrksZCUhHXjkRtNGucNV");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const DEPOSIT: u64 = 2 * SOL;
+const CLOSED_ACCOUNT_DISCRIMINATOR: [u8; 8] = [255; 8];
+
+// Error codes
+const ACCOUNT_DISCRIMINATOR_MISMATCH: u32 = 3002;
+const ACCOUNT_CLOSED: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Receipt account: [8 disc][32 owner][8 amount][8 borrowed][1 bump]
+fn serialize_receipt(owner: &Pubkey, amount: u64, borrowed: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(57);
+    data.extend_from_slice(&account_discriminator("Receipt"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&borrowed.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// What redeem_secure leaves behind: the marker, then zeroes.
+fn closed_receipt_data() -> Vec<u8> {
+    let mut data = vec![0u8; 57];
+    data[..8].copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR);
+    data
+}
+
+/// The attacker's receipt PDA for a 2 SOL deposit. Returns (address, account, bump).
+fn receipt(owner: &Pubkey) -> (Pubkey, AccountSharedData, u8) {
+    let (address, bump) = Pubkey::find_program_address(&[b"receipt", owner.as_ref()], &PROGRAM_ID);
+    let data = serialize_receipt(owner, DEPOSIT, 0, bump);
+    let mut account = AccountSharedData::new(RENT, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account, bump)
+}
+
+/// The pool PDA holding `deposits` lamports above rent.
+fn pool(deposits: u64) -> (Pubkey, AccountSharedData) {
+    let (address, bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+    let mut data = account_discriminator("Pool").to_vec();
+    data.push(bump);
+    let mut account = AccountSharedData::new(RENT + deposits, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account)
+}
+
+fn redeem_ix(name: &str, pool: Pubkey, receipt: Pubkey, owner: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator(name),
+        vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new(receipt, false),
+            AccountMeta::new(owner, true),
+        ],
+    )
+}
+
+fn borrow_ix(name: &str, pool: Pubkey, receipt: Pubkey, owner: Pubkey, amount: u64) -> Instruction {
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new(receipt, false),
+            AccountMeta::new(owner, true),
+        ],
+    )
+}
+
+/// Run `redeem` on a fresh receipt and pool (3 SOL of deposits). Returns the
+/// pool and receipt as the redeem left them.
+fn redeem(
+    mollusk: &Mollusk,
+    name: &str,
+    attacker: Pubkey,
+    closed_data: &[u8],
+) -> (Pubkey, AccountSharedData, Pubkey, AccountSharedData) {
+    let (receipt, receipt_account, _) = receipt(&attacker);
+    let (pool, pool_account) = pool(3 * SOL);
+
+    let result = mollusk.process_and_validate_instruction(
+        &redeem_ix(name, pool, receipt, attacker),
+        &[
+            (pool, pool_account),
+            (receipt, receipt_account),
+            (attacker, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&receipt)
+                .lamports(0)
+                .owner(&PROGRAM_ID)
+                .data(closed_data)
+                .build(),
+            Check::account(&attacker)
+                .lamports(2 * RENT + DEPOSIT)
+                .build(),
+        ],
+    );
+    (
+        pool,
+        result.resulting_accounts[0].1.clone(),
+        receipt,
+        result.resulting_accounts[1].1.clone(),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_redeemed_receipt_backs_a_loan() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: ix 1 redeems the 2 SOL receipt with redeem_vulnerable. ix 2
+    // borrows 1 SOL against the same receipt with borrow_vulnerable.
+    //
+    // Expected: Both SUCCEED — the drained receipt keeps its discriminator
+    //           and still reads as an unborrowed 2 SOL deposit. The loan is
+    //           recorded on an account with zero lamports, which the runtime
+    //           deletes when the transaction ends.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "closed_account_cosplay");
+    let attacker = Pubkey::new_unique();
+    let (_, _, bump) = receipt(&attacker);
+    let untouched = serialize_receipt(&attacker, DEPOSIT, 0, bump);
+
+    let (pool, pool_account, receipt, dead) =
+        redeem(&mollusk, "redeem_vulnerable", attacker, &untouched);
+
+    let indebted = serialize_receipt(&attacker, DEPOSIT, SOL, bump);
+    mollusk.process_and_validate_instruction(
+        &borrow_ix("borrow_vulnerable", pool, receipt, attacker, SOL),
+        &[
+            (pool, pool_account),
+            (receipt, dead),
+            (attacker, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&pool).lamports(RENT).build(), // 3 SOL paid out
+            Check::account(&receipt).lamports(0).data(&indebted).build(),
+            Check::account(&attacker).lamports(RENT + SOL).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_closed_receipt_refused_on_read() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same two instructions through redeem_secure and
+    // borrow_secure.
+    //
+    // Expected: The redeem SUCCEEDS and leaves CLOSED_ACCOUNT_DISCRIMINATOR
+    //           over zeroed data. The borrow FAILS with AccountClosed (6000).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "closed_account_cosplay");
+    let attacker = Pubkey::new_unique();
+
+    let (pool, pool_account, receipt, dead) =
+        redeem(&mollusk, "redeem_secure", attacker, &closed_receipt_data());
+
+    mollusk.process_and_validate_instruction(
+        &borrow_ix("borrow_secure", pool, receipt, attacker, SOL),
+        &[
+            (pool, pool_account),
+            (receipt, dead),
+            (attacker, system_account(RENT)),
+        ],
+        &[Check::err(ProgramError::Custom(ACCOUNT_CLOSED))],
+    );
+}
+
+#[test]
+fn secure_closed_marker_fails_typed_account() {
+    // -----------------------------------------------------------------------
+    // SECURE: A receipt closed by redeem_secure, passed to borrow_vulnerable,
+    // which takes a plain `Account<Receipt>` with no closed check.
+    //
+    // Expected: FAILS with AccountDiscriminatorMismatch (3002) — the marker
+    //           protects every reader, not just the ones that look for it.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "closed_account_cosplay");
+    let attacker = Pubkey::new_unique();
+
+    let (pool, pool_account, receipt, dead) =
+        redeem(&mollusk, "redeem_secure", attacker, &closed_receipt_data());
+
+    mollusk.process_and_validate_instruction(
+        &borrow_ix("borrow_vulnerable", pool, receipt, attacker, SOL),
+        &[
+            (pool, pool_account),
+            (receipt, dead),
+            (attacker, system_account(RENT)),
+        ],
+        &[Check::err(ProgramError::Custom(
+            ACCOUNT_DISCRIMINATOR_MISMATCH,
+        ))],
+    );
+}
+
+#[test]
+fn sanity_borrow_against_live_receipt_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: borrow_secure for 1 SOL against a live, unborrowed 2 SOL
+    // receipt.
+    //
+    // Expected: SUCCEEDS — the receipt records the loan and keeps its rent.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "closed_account_cosplay");
+    let owner = Pubkey::new_unique();
+    let (receipt, receipt_account, bump) = receipt(&owner);
+    let (pool, pool_account) = pool(3 * SOL);
+
+    let indebted = serialize_receipt(&owner, DEPOSIT, SOL, bump);
+    mollusk.process_and_validate_instruction(
+        &borrow_ix("borrow_secure", pool, receipt, owner, SOL),
+        &[
+            (pool, pool_account),
+            (receipt, receipt_account),
+            (owner, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&receipt)
+                .lamports(RENT)
+                .data(&indebted)
+                .build(),
+            Check::account(&owner).lamports(RENT + SOL).build(),
+        ],
+    );
+}
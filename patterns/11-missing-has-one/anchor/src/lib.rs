@@ -0,0 +1,303 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use demo_log::{demo_log, Mode};
+
+declare_id!("FkEeUJhbjgte3VowtF76sgyRmwdiF9NVpA3tMtwwLWNj");
+
+/// # Missing Relationship Check
+///
+/// This program demonstrates a lending market that accepts a position
+/// without checking which market the position belongs to.
+///
+/// ## The Vulnerability
+/// Anyone can create a market. Each market lists one collateral mint at a
+/// price, lends SOL from its own vault, and owns positions that record
+/// collateral in that mint's units. The vulnerable borrow takes a `market`
+/// and a `position` but never compares `position.market` with the market
+/// it was given. A position from the attacker's market, holding an
+/// attacker-minted token, is valued at the real market's price and paid
+/// out of the real market's vault.
+///
+/// ## Real-World Impact
+/// Lending protocols with many markets (reserves, pools, obligations) have
+/// lost funds this way. Every parent/child pair of accounts — market and
+/// position, pool and stake, vault and receipt — needs the child's stored
+/// parent key compared against the parent actually passed in.
+#[program]
+pub mod missing_has_one {
+    use super::*;
+
+    /// Create a market for `collateral_mint`, valued at `price` lamports
+    /// per base unit. Lenders fund the market PDA with plain transfers.
+    pub fn create_market(ctx: Context<CreateMarket>, price: u64, ltv_bps: u16) -> Result<()> {
+        require!(ltv_bps <= 10_000, ErrorCode::InvalidLtv);
+        let market = &mut ctx.accounts.market;
+        market.admin = ctx.accounts.admin.key();
+        market.collateral_mint = ctx.accounts.collateral_mint.key();
+        market.price = price;
+        market.ltv_bps = ltv_bps;
+        market.bump = ctx.bumps.market;
+        Ok(())
+    }
+
+    /// Create the signer's position in `market`.
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.market = ctx.accounts.market.key();
+        position.collateral = 0;
+        position.debt = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Move collateral tokens into the market's vault and credit the
+    /// position.
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.from.to_account_info(),
+                    mint: ctx.accounts.collateral_mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.collateral_mint.decimals,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.collateral = position
+            .collateral
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit_collateral",
+            actor = position.owner,
+            amount = amount,
+            market = position.market
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Position never tied to the market
+    // ============================================================================
+    // ISSUE: `position` is checked against the signer (`has_one = owner`)
+    //        but not against `market`. Its collateral is in whatever market
+    //        it was opened in.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker mints 1,000,000 units of a worthless token
+    //   2. Attacker creates their own market for it and deposits all of it
+    //   3. Attacker calls borrow_vulnerable on the real market, passing the
+    //      position from their own market
+    //   4. The handler values 1,000,000 units at the real market's price
+    //      (0.01 SOL each) and pays out the real market's vault
+    //
+    // WHY IT WORKS:
+    // Both accounts are genuine `Account<Position>` / `Account<Market>` owned
+    // by this program, so type and owner checks pass. Only the relationship
+    // between them is wrong, and nothing checks it.
+    // ============================================================================
+    pub fn borrow_vulnerable(ctx: Context<BorrowVulnerable>, amount: u64) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+        // VULNERABLE: position.market may be any market
+        position.debt = checked_borrow(market, position, amount)?;
+        pay_out(market, &ctx.accounts.owner, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            market = ctx.accounts.market.key(),
+            position_market = ctx.accounts.position.market
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: `has_one = market` on the position
+    // ============================================================================
+    // FIX: Anchor compares `position.market` with `market.key()` before the
+    //      handler runs. A position from any other market is rejected.
+    //
+    // GENERAL RULE:
+    // For every account that stores the key of another account in the same
+    // instruction, check the stored key against the one passed in. Use
+    // `has_one` when the field and the account share a name, and
+    // `constraint = child.parent == parent.key()` when they don't.
+    // ============================================================================
+    pub fn borrow_secure(ctx: Context<BorrowSecure>, amount: u64) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+        position.debt = checked_borrow(market, position, amount)?;
+        pay_out(market, &ctx.accounts.owner, amount)?;
+
+        demo_log!(
+            Mode::Secure,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            market = ctx.accounts.market.key(),
+            position_market = ctx.accounts.position.market
+        );
+        Ok(())
+    }
+}
+
+/// The position's new debt, if `amount` more stays within the market's
+/// loan-to-value limit.
+fn checked_borrow(market: &Market, position: &Position, amount: u64) -> Result<u64> {
+    let value = (position.collateral as u128)
+        .checked_mul(market.price as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    let limit = value * market.ltv_bps as u128 / 10_000;
+    let debt = position
+        .debt
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(debt as u128 <= limit, ErrorCode::BorrowLimit);
+    Ok(debt)
+}
+
+fn pay_out(market: &Account<Market>, owner: &Signer, amount: u64) -> Result<()> {
+    let market = market.to_account_info();
+    **market.try_borrow_mut_lamports()? = market
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientLiquidity)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market", admin.key().as_ref(), collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(has_one = collateral_mint)]
+    pub market: Account<'info, Market>,
+    #[account(mut, has_one = owner, has_one = market)]
+    pub position: Account<'info, Position>,
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = collateral_mint, token::authority = owner)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = market,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// VULNERABLE account structure - the position may belong to any market
+#[derive(Accounts)]
+pub struct BorrowVulnerable<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+
+    // VULNERABLE: tied to the signer, not to `market`
+    #[account(mut, has_one = owner)]
+    pub position: Account<'info, Position>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// SECURE account structure - the position must belong to `market`
+#[derive(Accounts)]
+pub struct BorrowSecure<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = market @ ErrorCode::WrongMarket // <-- position.market == market.key()
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// A lending market for one collateral mint. Its PDA holds the SOL it lends.
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub admin: Pubkey,           // 32 bytes
+    pub collateral_mint: Pubkey, // 32 bytes
+    pub price: u64,              //  8 bytes, lamports per collateral base unit
+    pub ltv_bps: u16,            //  2 bytes
+    pub bump: u8,                //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,   // 32 bytes
+    pub market: Pubkey,  // 32 bytes
+    pub collateral: u64, //  8 bytes, in the market's collateral units
+    pub debt: u64,       //  8 bytes, lamports
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Position belongs to a different market")]
+    WrongMarket,
+    #[msg("Borrow exceeds the position's limit")]
+    BorrowLimit,
+    #[msg("Market cannot cover the loan")]
+    InsufficientLiquidity,
+    #[msg("LTV cannot exceed 100%")]
+    InvalidLtv,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
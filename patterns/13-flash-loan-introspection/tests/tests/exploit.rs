@@ -0,0 +1,270 @@
+/// # Pattern 13: Flash Loan Repayment Introspection — Mollusk Exploit Tests
+///
+/// The pool lends its 100 SOL for a 0.09% fee. Mollusk runs one instruction
+/// at a time, so each test builds the instructions sysvar by hand to
+/// describe the transaction the borrow is part of.
+///
+/// - Test 1: EXPLOIT — flash_borrow_vulnerable pays out with no repay in the
+///   transaction.
+/// - Test 2: SECURE — flash_borrow_secure refuses when no repay follows.
+/// - Test 3: SECURE — repays that are short of the fee, sent to another
+///   program, or against another pool don't count.
+/// - Test 4: SANITY — with a matching repay later in the transaction, the
+///   secure borrow pays out.
+/// - Test 5: SANITY — flash_repay returns the loan plus fee and clears the
+///   pending flag.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar,
+};
+use test_harness::{
+    account_discriminator, executable_account, instructions_sysvar, ix_discriminator,
+    system_account,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("6PznHVALBahrkdd7PSiptsX3ruZhDNK6rYwFGPv9Dpj2");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const LIQUIDITY: u64 = 100 * SOL;
+const FEE_BPS: u16 = 9;
+const OWED: u64 = LIQUIDITY + LIQUIDITY * FEE_BPS as u64 / 10_000;
+
+// Error codes
+const MISSING_REPAYMENT: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account: [8 disc][32 admin][2 fee_bps][1 loan_pending][8 pending_repayment][1 bump]
+fn serialize_pool(admin: &Pubkey, pending_repayment: Option<u64>, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(52);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&FEE_BPS.to_le_bytes());
+    data.push(pending_repayment.is_some() as u8);
+    data.extend_from_slice(&pending_repayment.unwrap_or(0).to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The pool PDA holding LIQUIDITY above rent, with no loan open.
+/// Returns (address, account, bump).
+fn pool(admin: &Pubkey) -> (Pubkey, AccountSharedData, u8) {
+    let (address, bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+    let data = serialize_pool(admin, None, bump);
+    let mut account = AccountSharedData::new(RENT + LIQUIDITY, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account, bump)
+}
+
+fn borrow_ix(name: &str, pool: Pubkey, borrower: Pubkey, amount: u64) -> Instruction {
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    let mut accounts = vec![
+        AccountMeta::new(pool, false),
+        AccountMeta::new(borrower, true),
+    ];
+    if name == "flash_borrow_secure" {
+        accounts.push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+    }
+    Instruction::new_with_bytes(PROGRAM_ID, &data, accounts)
+}
+
+fn repay_ix(program_id: Pubkey, pool: Pubkey, borrower: Pubkey, amount: u64) -> Instruction {
+    let mut data = ix_discriminator("flash_repay").to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        program_id,
+        &data,
+        vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new(borrower, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    )
+}
+
+/// Run flash_borrow_secure for the whole pool as the first instruction of
+/// `[borrow, rest..]`.
+fn borrow_secure(
+    rest: &[Instruction],
+    pool: Pubkey,
+    pool_account: AccountSharedData,
+    borrower: Pubkey,
+    checks: &[Check],
+) {
+    let mollusk = Mollusk::new(&PROGRAM_ID, "flash_loan_introspection");
+    let borrow = borrow_ix("flash_borrow_secure", pool, borrower, LIQUIDITY);
+    let mut transaction = vec![borrow.clone()];
+    transaction.extend_from_slice(rest);
+
+    mollusk.process_and_validate_instruction(
+        &borrow,
+        &[
+            (pool, pool_account),
+            (borrower, system_account(RENT)),
+            (
+                sysvar::instructions::ID,
+                instructions_sysvar(&transaction, 0),
+            ),
+        ],
+        checks,
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_borrow_without_repay_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: A transaction with a single instruction,
+    // flash_borrow_vulnerable for the whole pool. No flash_repay follows.
+    //
+    // Expected: SUCCEEDS — the attacker keeps 100 SOL. The pool is left
+    //           with loan_pending set and a repayment nobody will make.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "flash_loan_introspection");
+    let admin = Pubkey::new_unique();
+    let attacker = Pubkey::new_unique();
+    let (pool, pool_account, bump) = pool(&admin);
+
+    mollusk.process_and_validate_instruction(
+        &borrow_ix("flash_borrow_vulnerable", pool, attacker, LIQUIDITY),
+        &[(pool, pool_account), (attacker, system_account(RENT))],
+        &[
+            Check::success(),
+            Check::account(&pool)
+                .lamports(RENT)
+                .data(&serialize_pool(&admin, Some(OWED), bump))
+                .build(),
+            Check::account(&attacker).lamports(RENT + LIQUIDITY).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_borrow_without_repayment() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same one-instruction transaction through
+    // flash_borrow_secure.
+    //
+    // Expected: FAILS with MissingRepayment (6000) — nothing after the
+    //           borrow in the instructions sysvar.
+    // -----------------------------------------------------------------------
+    let attacker = Pubkey::new_unique();
+    let (pool, pool_account, _) = pool(&Pubkey::new_unique());
+
+    borrow_secure(
+        &[],
+        pool,
+        pool_account,
+        attacker,
+        &[Check::err(ProgramError::Custom(MISSING_REPAYMENT))],
+    );
+}
+
+#[test]
+fn secure_rejects_lookalike_repayments() {
+    // -----------------------------------------------------------------------
+    // SECURE: Three transactions, each with a flash_repay that looks right
+    // but isn't:
+    //   - this program and pool, but only the principal (no fee)
+    //   - the right data and pool, sent to the attacker's own program
+    //   - this program, but against a different pool
+    //
+    // Expected: Each FAILS with MissingRepayment (6000).
+    // -----------------------------------------------------------------------
+    let attacker = Pubkey::new_unique();
+    let (pool, pool_account, _) = pool(&Pubkey::new_unique());
+    let attacker_program = Pubkey::new_unique();
+    let other_pool = Pubkey::new_unique();
+
+    for repay in [
+        repay_ix(PROGRAM_ID, pool, attacker, LIQUIDITY),
+        repay_ix(attacker_program, pool, attacker, OWED),
+        repay_ix(PROGRAM_ID, other_pool, attacker, OWED),
+    ] {
+        borrow_secure(
+            &[repay],
+            pool,
+            pool_account.clone(),
+            attacker,
+            &[Check::err(ProgramError::Custom(MISSING_REPAYMENT))],
+        );
+    }
+}
+
+#[test]
+fn sanity_borrow_with_repayment_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: [flash_borrow_secure(100 SOL), flash_repay(100.09 SOL)].
+    // This test runs the borrow.
+    //
+    // Expected: SUCCEEDS — 100 SOL paid out, 100.09 SOL recorded as owed.
+    // -----------------------------------------------------------------------
+    let admin = Pubkey::new_unique();
+    let borrower = Pubkey::new_unique();
+    let (pool, pool_account, bump) = pool(&admin);
+
+    borrow_secure(
+        &[repay_ix(PROGRAM_ID, pool, borrower, OWED)],
+        pool,
+        pool_account,
+        borrower,
+        &[
+            Check::success(),
+            Check::account(&pool)
+                .lamports(RENT)
+                .data(&serialize_pool(&admin, Some(OWED), bump))
+                .build(),
+            Check::account(&borrower).lamports(RENT + LIQUIDITY).build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_repay_closes_loan() {
+    // -----------------------------------------------------------------------
+    // SANITY: The second instruction of that transaction — flash_repay for
+    // 100.09 SOL against the pool the borrow left behind.
+    //
+    // Expected: SUCCEEDS — the pool is back to 100 SOL plus the fee, and
+    //           loan_pending is cleared.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "flash_loan_introspection");
+    let admin = Pubkey::new_unique();
+    let borrower = Pubkey::new_unique();
+    let (pool, _, bump) = pool(&admin);
+
+    let owing = serialize_pool(&admin, Some(OWED), bump);
+    let mut pool_account = AccountSharedData::new(RENT, owing.len(), &PROGRAM_ID);
+    pool_account.set_data_from_slice(&owing);
+
+    mollusk.process_and_validate_instruction(
+        &repay_ix(PROGRAM_ID, pool, borrower, OWED),
+        &[
+            (pool, pool_account),
+            (borrower, system_account(RENT + OWED)),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ],
+        &[
+            Check::success(),
+            Check::account(&pool)
+                .lamports(RENT + OWED)
+                .data(&serialize_pool(&admin, None, bump))
+                .build(),
+            Check::account(&borrower).lamports(RENT).build(),
+        ],
+    );
+}
@@ -0,0 +1,300 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+mod oracle;
+
+use oracle::PriceFeed;
+
+declare_id!("BN7YS27LaE9HNPm33KYH4Whd1D1gC7UFtEc83kgZzLhB");
+
+/// # Oracle Staleness and Confidence
+///
+/// This program demonstrates a collateralized debt market that values SOL
+/// collateral with a Pyth price account.
+///
+/// ## The Vulnerability
+/// A Pyth price account always holds a price. When publishers stop
+/// updating it — an outage, a halted market, a congested cluster — the last
+/// aggregate stays in the account. The vulnerable borrow reads that price
+/// and uses it. It never asks when the price was published, how uncertain
+/// it was, or whether the feed was trading at all.
+///
+/// ## Real-World Impact
+/// Oracle outages are when prices move most. A borrower who sees the feed
+/// frozen at a pre-crash price can borrow against collateral that is
+/// already worth a fraction of that, and the protocol absorbs the bad debt.
+/// Wide confidence intervals and non-trading statuses are Pyth's own
+/// signals that a price shouldn't be relied on.
+#[program]
+pub mod oracle_staleness {
+    use super::*;
+
+    /// Create a market priced by `price_feed`. Prices older than
+    /// `max_age_slots`, or with a confidence interval wider than
+    /// `max_conf_bps` of the price, are refused by the secure borrow.
+    pub fn create_market(
+        ctx: Context<CreateMarket>,
+        max_age_slots: u64,
+        max_conf_bps: u16,
+        ltv_bps: u16,
+    ) -> Result<()> {
+        require!(ltv_bps <= 10_000, ErrorCode::InvalidLtv);
+        let market = &mut ctx.accounts.market;
+        market.admin = ctx.accounts.admin.key();
+        market.price_feed = ctx.accounts.price_feed.key();
+        market.max_age_slots = max_age_slots;
+        market.max_conf_bps = max_conf_bps;
+        market.ltv_bps = ltv_bps;
+        market.bump = ctx.bumps.market;
+        Ok(())
+    }
+
+    /// Create the signer's position in `market`.
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.market = ctx.accounts.market.key();
+        position.collateral = 0;
+        position.debt = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Lock `amount` lamports of collateral in the position.
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.position.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.collateral = position
+            .collateral
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit_collateral",
+            actor = position.owner,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Whatever price the account holds
+    // ============================================================================
+    // ISSUE: The aggregate price is decoded and used as-is. Its publish
+    //        slot, confidence interval, and status are in the same account
+    //        and never read.
+    //
+    // ATTACK SCENARIO:
+    //   1. The SOL feed last published $200 at slot 1,000. Publishers go
+    //      quiet during a crash
+    //   2. At slot 10,000 SOL trades at $50. The account still says $200
+    //   3. Attacker deposits 10 SOL (worth $500) and borrows $1,000 against
+    //      it: 50% of the stale $2,000 valuation
+    //   4. The attacker walks away with $500 more than the collateral is
+    //      worth; the market keeps the bad debt
+    // ============================================================================
+    pub fn borrow_vulnerable(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let feed = PriceFeed::load(&ctx.accounts.price_feed.try_borrow_data()?)?;
+        // VULNERABLE: no age, confidence, or status check
+        let market = &ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+        position.debt = checked_borrow(market, position, &feed, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            price = feed.price,
+            pub_slot = feed.pub_slot
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Fresh, tight, trading prices only
+    // ============================================================================
+    // FIX: Before valuing anything, `PriceFeed::validate` requires:
+    //      - status == Trading
+    //      - current slot - publish slot <= market.max_age_slots
+    //      - conf <= price * market.max_conf_bps / 10_000
+    //
+    // WHY SLOTS:
+    // The aggregate's `pub_slot` is set by the oracle program when it
+    // aggregates. A publisher's timestamp is their own claim. Comparing
+    // slots measures staleness against the cluster's own clock.
+    //
+    // WHEN IT FAILS:
+    // Refusing to borrow during an outage is the intended behaviour.
+    // Borrowers retry once the feed updates.
+    // ============================================================================
+    pub fn borrow_secure(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let feed = PriceFeed::load(&ctx.accounts.price_feed.try_borrow_data()?)?;
+        let market = &ctx.accounts.market;
+        // SECURE: reject stale, uncertain, or halted prices
+        feed.validate(
+            Clock::get()?.slot,
+            market.max_age_slots,
+            market.max_conf_bps,
+        )?;
+        let position = &mut ctx.accounts.position;
+        position.debt = checked_borrow(market, position, &feed, amount)?;
+
+        demo_log!(
+            Mode::Secure,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            price = feed.price,
+            pub_slot = feed.pub_slot
+        );
+        Ok(())
+    }
+}
+
+/// Collateral value in micro-USD (6 decimals) at the feed's price.
+fn collateral_value(lamports: u64, feed: &PriceFeed) -> Result<u64> {
+    require!(feed.price > 0, ErrorCode::NonPositivePrice);
+    // lamports have 9 decimals, micro-USD 6, the price 10^expo
+    let exponent = feed.expo + 6 - 9;
+    let raw = lamports as u128 * feed.price as u128;
+    let scale = 10u128
+        .checked_pow(exponent.unsigned_abs())
+        .ok_or(ErrorCode::Overflow)?;
+    let value = if exponent >= 0 {
+        raw.checked_mul(scale).ok_or(ErrorCode::Overflow)?
+    } else {
+        raw / scale
+    };
+    u64::try_from(value).map_err(|_| error!(ErrorCode::Overflow))
+}
+
+/// The position's new debt, if `amount` more stays within the market's
+/// loan-to-value limit at the feed's price.
+fn checked_borrow(
+    market: &Market,
+    position: &Position,
+    feed: &PriceFeed,
+    amount: u64,
+) -> Result<u64> {
+    let value = collateral_value(position.collateral, feed)?;
+    let limit = value as u128 * market.ltv_bps as u128 / 10_000;
+    let debt = position
+        .debt
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(debt as u128 <= limit, ErrorCode::BorrowLimit);
+    Ok(debt)
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market", price_feed.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    /// CHECK: Stored as the market's oracle; decoded on every borrow.
+    pub price_feed: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(mut, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by both borrows: the feed is the market's own. What differs is
+/// how much of it the handler reads.
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(has_one = price_feed)]
+    pub market: Account<'info, Market>,
+    #[account(mut, has_one = owner, has_one = market)]
+    pub position: Account<'info, Position>,
+    /// CHECK: Must be the market's feed (`has_one`); decoded by `oracle`.
+    pub price_feed: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub admin: Pubkey,      // 32 bytes
+    pub price_feed: Pubkey, // 32 bytes, Pyth SOL/USD price account
+    pub max_age_slots: u64, //  8 bytes
+    pub max_conf_bps: u16,  //  2 bytes
+    pub ltv_bps: u16,       //  2 bytes
+    pub bump: u8,           //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,   // 32 bytes
+    pub market: Pubkey,  // 32 bytes
+    pub collateral: u64, //  8 bytes, lamports
+    pub debt: u64,       //  8 bytes, micro-USD
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Oracle price is older than the market allows")]
+    StalePrice,
+    #[msg("Oracle confidence interval is too wide")]
+    ConfidenceTooWide,
+    #[msg("Oracle price is not trading")]
+    PriceNotTrading,
+    #[msg("Not a Pyth v2 price account")]
+    InvalidPriceAccount,
+    #[msg("Oracle price is zero or negative")]
+    NonPositivePrice,
+    #[msg("Borrow exceeds the position's limit")]
+    BorrowLimit,
+    #[msg("LTV cannot exceed 100%")]
+    InvalidLtv,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,89 @@
+//! A minimal reader for Pyth's v2 price account.
+//!
+//! Only the fields this program needs are decoded, at their offsets in the
+//! on-chain layout (`pyth_sdk_solana::state::PriceAccount`). The SDK is built
+//! against solana-program 1.x, so reading the bytes directly keeps it out of
+//! an Anchor 0.31 build.
+//!
+//! Decoding proves the account is shaped like a price account. It says
+//! nothing about whether the price is usable; that's [`PriceFeed::validate`].
+
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+pub const MAGIC: u32 = 0xa1b2_c3d4;
+pub const VERSION: u32 = 2;
+pub const ACCOUNT_TYPE_PRICE: u32 = 3;
+/// `agg.status` while publishers are quoting normally. The others are
+/// Unknown (0), Halted (2), Auction (3), and Ignored (4).
+pub const STATUS_TRADING: u32 = 1;
+
+const OFFSET_MAGIC: usize = 0;
+const OFFSET_VERSION: usize = 4;
+const OFFSET_ACCOUNT_TYPE: usize = 8;
+const OFFSET_EXPO: usize = 20;
+const OFFSET_AGG_PRICE: usize = 208;
+const OFFSET_AGG_CONF: usize = 216;
+const OFFSET_AGG_STATUS: usize = 224;
+const OFFSET_AGG_PUB_SLOT: usize = 232;
+/// Everything up to the end of `agg`. The publisher components follow.
+const HEADER_LEN: usize = 240;
+
+/// The aggregate price of a Pyth price account.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceFeed {
+    /// Price in units of 10^expo.
+    pub price: i64,
+    /// Confidence interval, in the same units as `price`.
+    pub conf: u64,
+    pub expo: i32,
+    pub status: u32,
+    /// Slot in which this aggregate was published.
+    pub pub_slot: u64,
+}
+
+impl PriceFeed {
+    pub fn load(data: &[u8]) -> Result<Self> {
+        require!(data.len() >= HEADER_LEN, ErrorCode::InvalidPriceAccount);
+        require!(
+            read_u32(data, OFFSET_MAGIC) == MAGIC
+                && read_u32(data, OFFSET_VERSION) == VERSION
+                && read_u32(data, OFFSET_ACCOUNT_TYPE) == ACCOUNT_TYPE_PRICE,
+            ErrorCode::InvalidPriceAccount
+        );
+        Ok(Self {
+            price: read_u64(data, OFFSET_AGG_PRICE) as i64,
+            conf: read_u64(data, OFFSET_AGG_CONF),
+            expo: read_u32(data, OFFSET_EXPO) as i32,
+            status: read_u32(data, OFFSET_AGG_STATUS),
+            pub_slot: read_u64(data, OFFSET_AGG_PUB_SLOT),
+        })
+    }
+
+    /// Fails unless the price is trading, was published at most
+    /// `max_age_slots` before `current_slot`, and has a confidence interval
+    /// no wider than `max_conf_bps` of the price.
+    pub fn validate(&self, current_slot: u64, max_age_slots: u64, max_conf_bps: u16) -> Result<()> {
+        require_eq!(self.status, STATUS_TRADING, ErrorCode::PriceNotTrading);
+        require!(
+            current_slot.saturating_sub(self.pub_slot) <= max_age_slots,
+            ErrorCode::StalePrice
+        );
+        require!(self.price > 0, ErrorCode::NonPositivePrice);
+        // conf / price <= max_conf_bps / 10_000, without dividing
+        require!(
+            self.conf as u128 * 10_000 <= self.price as u128 * max_conf_bps as u128,
+            ErrorCode::ConfidenceTooWide
+        );
+        Ok(())
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
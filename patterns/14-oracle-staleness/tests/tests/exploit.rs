@@ -0,0 +1,371 @@
+/// # Pattern 14: Oracle Staleness and Confidence — Mollusk Exploit Tests
+///
+/// The market values SOL collateral with a Pyth price account, lends up to
+/// 50% of that value, and accepts prices up to 25 slots old with a
+/// confidence interval up to 2% of the price. The feed's last update said
+/// $200, published at slot 1,000. The price accounts are built by
+/// `MockPrice`, byte for byte in Pyth's v2 layout, so no mainnet data is
+/// needed.
+///
+/// - Test 1: EXPLOIT — at slot 10,000, borrow_vulnerable lends $1,000
+///   against 10 SOL at the frozen $200 price.
+/// - Test 2: SECURE — borrow_secure rejects the same price as stale.
+/// - Test 3: SECURE — borrow_secure rejects a fresh price whose confidence
+///   interval is 15% of the price.
+/// - Test 4: SECURE — borrow_secure rejects a fresh price from a halted
+///   feed.
+/// - Test 5: SANITY — borrow_secure lends against a fresh, tight, trading
+///   price.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, system_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("BN7YS27LaE9HNPm33KYH4Whd1D1gC7UFtEc83kgZzLhB");
+/// Pyth's mainnet oracle program, as the owner of the mock price accounts.
+const PYTH_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+
+const SOL: u64 = 1_000_000_000;
+const USD: u64 = 1_000_000; // debt is in micro-USD
+const RENT: u64 = 2_000_000;
+const LTV_BPS: u16 = 5_000;
+const MAX_AGE_SLOTS: u64 = 25;
+const MAX_CONF_BPS: u16 = 200;
+
+const EXPO: i32 = -8;
+const PRICE_200: i64 = 200 * 100_000_000; // $200 at expo -8
+const PUBLISHED_AT: u64 = 1_000;
+const COLLATERAL: u64 = 10 * SOL;
+
+// Pyth v2 price account
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+const PYTH_VERSION: u32 = 2;
+const PYTH_PRICE_ACCOUNT: u32 = 3;
+const PYTH_PRICE_ACCOUNT_LEN: usize = 3312; // 240-byte header + 32 components of 96
+const STATUS_TRADING: u32 = 1;
+const STATUS_HALTED: u32 = 2;
+
+// Error codes
+const STALE_PRICE: u32 = 6000;
+const CONFIDENCE_TOO_WIDE: u32 = 6001;
+const PRICE_NOT_TRADING: u32 = 6002;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// The aggregate fields of a Pyth price account. Everything else in the
+/// account is left zeroed.
+struct MockPrice {
+    price: i64,
+    conf: u64,
+    expo: i32,
+    status: u32,
+    pub_slot: u64,
+}
+
+impl MockPrice {
+    /// A trading price with a 0.1% confidence interval.
+    fn trading(price: i64, pub_slot: u64) -> Self {
+        Self {
+            price,
+            conf: price as u64 / 1_000,
+            expo: EXPO,
+            status: STATUS_TRADING,
+            pub_slot,
+        }
+    }
+
+    fn conf(mut self, conf: u64) -> Self {
+        self.conf = conf;
+        self
+    }
+
+    fn status(mut self, status: u32) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Serialize in Pyth's v2 `PriceAccount` layout:
+    /// [4 magic][4 ver][4 atype][4 size][4 ptype][4 expo] ... [208: agg.price]
+    /// [216: agg.conf][224: agg.status][228: agg.corp_act][232: agg.pub_slot]
+    fn data(&self) -> Vec<u8> {
+        let mut data = vec![0u8; PYTH_PRICE_ACCOUNT_LEN];
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[4..8].copy_from_slice(&PYTH_VERSION.to_le_bytes());
+        data[8..12].copy_from_slice(&PYTH_PRICE_ACCOUNT.to_le_bytes());
+        data[12..16].copy_from_slice(&(PYTH_PRICE_ACCOUNT_LEN as u32).to_le_bytes());
+        data[16..20].copy_from_slice(&1u32.to_le_bytes()); // ptype: Price
+        data[20..24].copy_from_slice(&self.expo.to_le_bytes());
+        data[32..40].copy_from_slice(&self.pub_slot.to_le_bytes()); // last_slot
+        data[208..216].copy_from_slice(&self.price.to_le_bytes());
+        data[216..224].copy_from_slice(&self.conf.to_le_bytes());
+        data[224..228].copy_from_slice(&self.status.to_le_bytes());
+        data[232..240].copy_from_slice(&self.pub_slot.to_le_bytes());
+        data
+    }
+
+    fn account(&self) -> AccountSharedData {
+        let data = self.data();
+        let mut account = AccountSharedData::new(RENT, data.len(), &PYTH_PROGRAM_ID);
+        account.set_data_from_slice(&data);
+        account
+    }
+}
+
+/// Serialize a Market account:
+/// [8 disc][32 admin][32 price_feed][8 max_age_slots][2 max_conf_bps][2 ltv_bps][1 bump]
+fn serialize_market(admin: &Pubkey, price_feed: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(85);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(price_feed.as_ref());
+    data.extend_from_slice(&MAX_AGE_SLOTS.to_le_bytes());
+    data.extend_from_slice(&MAX_CONF_BPS.to_le_bytes());
+    data.extend_from_slice(&LTV_BPS.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account: [8 disc][32 owner][32 market][8 collateral][8 debt][1 bump]
+fn serialize_position(
+    owner: &Pubkey,
+    market: &Pubkey,
+    collateral: u64,
+    debt: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(89);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(market.as_ref());
+    data.extend_from_slice(&collateral.to_le_bytes());
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn borrow_ix(name: &str, s: &Setup, amount: u64) -> Instruction {
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new_readonly(s.market, false),
+            AccountMeta::new(s.position, false),
+            AccountMeta::new_readonly(s.price_feed, false),
+            AccountMeta::new_readonly(s.borrower, true),
+        ],
+    )
+}
+
+/// A market on `price_feed`, and a borrower's position in it holding
+/// 10 SOL of collateral and no debt.
+struct Setup {
+    market: Pubkey,
+    market_account: AccountSharedData,
+    price_feed: Pubkey,
+    borrower: Pubkey,
+    position: Pubkey,
+    position_account: AccountSharedData,
+    position_bump: u8,
+}
+
+fn setup() -> Setup {
+    let admin = Pubkey::new_unique();
+    let price_feed = Pubkey::new_unique();
+    let (market, market_bump) =
+        Pubkey::find_program_address(&[b"market", price_feed.as_ref()], &PROGRAM_ID);
+
+    let borrower = Pubkey::new_unique();
+    let (position, position_bump) = Pubkey::find_program_address(
+        &[b"position", market.as_ref(), borrower.as_ref()],
+        &PROGRAM_ID,
+    );
+
+    Setup {
+        market,
+        market_account: owned_account(
+            &serialize_market(&admin, &price_feed, market_bump),
+            RENT,
+            &PROGRAM_ID,
+        ),
+        price_feed,
+        borrower,
+        position,
+        position_account: owned_account(
+            &serialize_position(&borrower, &market, COLLATERAL, 0, position_bump),
+            RENT + COLLATERAL,
+            &PROGRAM_ID,
+        ),
+        position_bump,
+    }
+}
+
+fn mollusk_at_slot(slot: u64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "oracle_staleness");
+    mollusk.sysvars.clock.slot = slot;
+    mollusk
+}
+
+fn run_borrow(
+    mollusk: &Mollusk,
+    name: &str,
+    s: Setup,
+    feed: &MockPrice,
+    amount: u64,
+    checks: &[Check],
+) {
+    mollusk.process_and_validate_instruction(
+        &borrow_ix(name, &s, amount),
+        &[
+            (s.market, s.market_account),
+            (s.position, s.position_account),
+            (s.price_feed, feed.account()),
+            (s.borrower, system_account(RENT)),
+        ],
+        checks,
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_stale_price_inflates_borrow_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Publishers stopped at slot 1,000 with SOL at $200. It's now
+    // slot 10,000 and SOL trades at $50, so 10 SOL is worth $500. The
+    // attacker borrows $1,000 through borrow_vulnerable.
+    //
+    // Expected: SUCCEEDS — the frozen price values the collateral at $2,000
+    //           and the 50% limit allows $1,000 of debt.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at_slot(10_000);
+    let s = setup();
+    let stale = MockPrice::trading(PRICE_200, PUBLISHED_AT);
+
+    let indebted = serialize_position(
+        &s.borrower,
+        &s.market,
+        COLLATERAL,
+        1_000 * USD,
+        s.position_bump,
+    );
+    let position = s.position;
+    run_borrow(
+        &mollusk,
+        "borrow_vulnerable",
+        s,
+        &stale,
+        1_000 * USD,
+        &[
+            Check::success(),
+            Check::account(&position).data(&indebted).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_stale_price() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same borrow, at the same slot, through borrow_secure.
+    //
+    // Expected: FAILS with StalePrice (6000) — the price is 9,000 slots old
+    //           and the market allows 25.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at_slot(10_000);
+    let s = setup();
+    let stale = MockPrice::trading(PRICE_200, PUBLISHED_AT);
+
+    run_borrow(
+        &mollusk,
+        "borrow_secure",
+        s,
+        &stale,
+        1_000 * USD,
+        &[Check::err(ProgramError::Custom(STALE_PRICE))],
+    );
+}
+
+#[test]
+fn secure_rejects_wide_confidence() {
+    // -----------------------------------------------------------------------
+    // SECURE: A price published this slot, but publishers disagree: $200
+    // ± $30. The market accepts at most ±2%.
+    //
+    // Expected: FAILS with ConfidenceTooWide (6001).
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at_slot(PUBLISHED_AT);
+    let s = setup();
+    let uncertain = MockPrice::trading(PRICE_200, PUBLISHED_AT).conf(30 * 100_000_000);
+
+    run_borrow(
+        &mollusk,
+        "borrow_secure",
+        s,
+        &uncertain,
+        1_000 * USD,
+        &[Check::err(ProgramError::Custom(CONFIDENCE_TOO_WIDE))],
+    );
+}
+
+#[test]
+fn secure_rejects_halted_feed() {
+    // -----------------------------------------------------------------------
+    // SECURE: A fresh, tight price, but the feed's status is Halted.
+    //
+    // Expected: FAILS with PriceNotTrading (6002).
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at_slot(PUBLISHED_AT);
+    let s = setup();
+    let halted = MockPrice::trading(PRICE_200, PUBLISHED_AT).status(STATUS_HALTED);
+
+    run_borrow(
+        &mollusk,
+        "borrow_secure",
+        s,
+        &halted,
+        1_000 * USD,
+        &[Check::err(ProgramError::Custom(PRICE_NOT_TRADING))],
+    );
+}
+
+#[test]
+fn sanity_fresh_price_borrow_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: A $200 price published 10 slots ago, trading, ±0.1%. The
+    // borrower takes $1,000 against 10 SOL through borrow_secure.
+    //
+    // Expected: SUCCEEDS — exactly the 50% limit, recorded as debt.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at_slot(PUBLISHED_AT + 10);
+    let s = setup();
+    let fresh = MockPrice::trading(PRICE_200, PUBLISHED_AT);
+
+    let indebted = serialize_position(
+        &s.borrower,
+        &s.market,
+        COLLATERAL,
+        1_000 * USD,
+        s.position_bump,
+    );
+    let position = s.position;
+    run_borrow(
+        &mollusk,
+        "borrow_secure",
+        s,
+        &fresh,
+        1_000 * USD,
+        &[
+            Check::success(),
+            Check::account(&position).data(&indebted).build(),
+        ],
+    );
+}
@@ -0,0 +1,512 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("2wheHSJBZuMQDeqeTHkr57j36tuiEcmsmwLnMfQHEzy9");
+
+/// Fixed-point scale for prices: lamports per token unit, times 10^12.
+pub const PRICE_SCALE: u128 = 1_000_000_000_000;
+/// The TWAP must cover at least this many seconds.
+pub const MIN_TWAP_WINDOW: i64 = 30 * 60;
+
+/// # AMM Spot-Price Manipulation
+///
+/// This program demonstrates a constant-product SOL/TOK pool next to a
+/// lending market that takes TOK as collateral and lends SOL.
+///
+/// ## The Vulnerability
+/// The vulnerable borrow values collateral at the pool's spot price,
+/// `sol_reserve / token_reserve`, read at the moment of the borrow. That
+/// ratio is whatever the last swap left it at. A borrower who swaps a large
+/// amount of SOL into the pool, borrows, and swaps back in one transaction
+/// sets the price their collateral is valued at.
+///
+/// ## Real-World Impact
+/// Flash loans make the capital for the skewing swap free, and the swap
+/// back returns it minus fees. Lending markets that read prices from
+/// shallow AMM pools have been drained this way repeatedly; Mango Markets
+/// lost $114M to collateral whose price was pushed up on thin markets.
+#[program]
+pub mod spot_price_manipulation {
+    use super::*;
+
+    /// Create the pool with `sol` lamports from the admin and a virtual
+    /// `tokens` reserve. Both TWAP observations start now.
+    pub fn init_pool(ctx: Context<InitPool>, sol: u64, tokens: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            sol,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.pool;
+        pool.admin = ctx.accounts.admin.key();
+        pool.sol_reserve = sol;
+        pool.token_reserve = tokens;
+        pool.price_cumulative = 0;
+        pool.last_update = now;
+        pool.observations = [Observation {
+            cumulative: 0,
+            timestamp: now,
+        }; 2];
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    /// Create the lending market for `pool`. Lenders fund the market PDA
+    /// with plain transfers.
+    pub fn init_market(ctx: Context<InitMarket>, ltv_bps: u16) -> Result<()> {
+        require!(ltv_bps <= 10_000, ErrorCode::InvalidLtv);
+        let market = &mut ctx.accounts.market;
+        market.pool = ctx.accounts.pool.key();
+        market.ltv_bps = ltv_bps;
+        market.bump = ctx.bumps.market;
+        Ok(())
+    }
+
+    /// Create the signer's position: a TOK balance, locked collateral, and
+    /// SOL debt.
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.pool = ctx.accounts.pool.key();
+        position.tokens = 0;
+        position.collateral = 0;
+        position.debt = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Swap `amount_in` lamports for TOK, credited to the position.
+    pub fn swap_sol_for_tokens(ctx: Context<Swap>, amount_in: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.pool;
+        pool.accrue(now)?;
+        let amount_out = swap_output(pool.sol_reserve, pool.token_reserve, amount_in)?;
+        pool.sol_reserve = pool
+            .sol_reserve
+            .checked_add(amount_in)
+            .ok_or(ErrorCode::Overflow)?;
+        pool.token_reserve -= amount_out;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+        let position = &mut ctx.accounts.position;
+        position.tokens = position
+            .tokens
+            .checked_add(amount_out)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "swap_sol_for_tokens",
+            actor = ctx.accounts.owner.key(),
+            amount = amount_in,
+            out = amount_out
+        );
+        Ok(())
+    }
+
+    /// Swap `amount_in` TOK from the position for lamports.
+    pub fn swap_tokens_for_sol(ctx: Context<Swap>, amount_in: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let position = &mut ctx.accounts.position;
+        position.tokens = position
+            .tokens
+            .checked_sub(amount_in)
+            .ok_or(ErrorCode::InsufficientTokens)?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.accrue(now)?;
+        let amount_out = swap_output(pool.token_reserve, pool.sol_reserve, amount_in)?;
+        pool.token_reserve = pool
+            .token_reserve
+            .checked_add(amount_in)
+            .ok_or(ErrorCode::Overflow)?;
+        pool.sol_reserve -= amount_out;
+
+        **ctx
+            .accounts
+            .pool
+            .to_account_info()
+            .try_borrow_mut_lamports()? -= amount_out;
+        **ctx.accounts.owner.try_borrow_mut_lamports()? += amount_out;
+
+        demo_log!(
+            Mode::Setup,
+            "swap_tokens_for_sol",
+            actor = ctx.accounts.owner.key(),
+            amount = amount_in,
+            out = amount_out
+        );
+        Ok(())
+    }
+
+    /// Lock `amount` TOK from the position's balance as collateral.
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.tokens = position
+            .tokens
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientTokens)?;
+        position.collateral = position
+            .collateral
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit_collateral",
+            actor = position.owner,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Start a new TWAP window. Anyone can call this once the newest
+    /// observation is at least `MIN_TWAP_WINDOW` old.
+    pub fn record_observation(ctx: Context<RecordObservation>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.pool.record(now)
+    }
+
+    // ============================================================================
+    // VULNERABLE: Collateral valued at the spot reserve ratio
+    // ============================================================================
+    // ISSUE: `sol_reserve / token_reserve` is the price the last swap left.
+    //        The borrower can be the one who made that swap, earlier in the
+    //        same transaction.
+    //
+    // ATTACK SCENARIO (one transaction):
+    //   1. Attacker flash-borrows 3,000 SOL and swaps it into the pool
+    //      (1,000 SOL / 100,000 TOK -> 4,000 SOL / 25,000 TOK). TOK's spot
+    //      price goes from 0.01 SOL to 0.16 SOL
+    //   2. Attacker borrows against 10,000 TOK of collateral. Worth 100 SOL
+    //      before the swap; valued at 1,600 SOL now, limit 800 SOL. The
+    //      market's entire 500 SOL is paid out
+    //   3. Attacker swaps the 75,000 TOK back for the 3,000 SOL and repays
+    //      the flash loan
+    //   4. The attacker keeps 500 SOL against 100 SOL of collateral
+    // ============================================================================
+    pub fn borrow_vulnerable(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        // VULNERABLE: the price any swap in this transaction can set
+        let price = ctx.accounts.pool.spot_price()?;
+        borrow(
+            &ctx.accounts.market,
+            &mut ctx.accounts.position,
+            &ctx.accounts.owner,
+            price,
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            price = price
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Collateral valued at a time-weighted average price
+    // ============================================================================
+    // FIX: The pool accumulates price x seconds before every swap. The TWAP
+    //      is the change in that accumulator since an observation at least
+    //      `MIN_TWAP_WINDOW` old, divided by the time between them.
+    //
+    // WHY A SAME-TRANSACTION SWAP DOESN'T MOVE IT:
+    // A swap accrues the old price up to now and then changes reserves. The
+    // new price starts accruing from now. At the moment of the borrow it has
+    // been in effect for zero seconds, so it contributes nothing. Holding a
+    // skewed price long enough to matter means leaving it open to arbitrage
+    // for a large part of the window.
+    //
+    // ALTERNATIVE:
+    // An external oracle (pattern 14) with staleness and confidence checks
+    // is the other standard source. Either way, never the spot ratio.
+    // ============================================================================
+    pub fn borrow_secure(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        // SECURE: average over at least MIN_TWAP_WINDOW
+        let price = ctx.accounts.pool.twap(now)?;
+        borrow(
+            &ctx.accounts.market,
+            &mut ctx.accounts.position,
+            &ctx.accounts.owner,
+            price,
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            price = price
+        );
+        Ok(())
+    }
+}
+
+/// Constant-product output for `amount_in`, with no fee.
+fn swap_output(reserve_in: u64, reserve_out: u64, amount_in: u64) -> Result<u64> {
+    let numerator = reserve_out as u128 * amount_in as u128;
+    let denominator = reserve_in as u128 + amount_in as u128;
+    require!(denominator > 0, ErrorCode::EmptyPool);
+    Ok((numerator / denominator) as u64)
+}
+
+/// Record `amount` more debt if the collateral covers it at `price`, then
+/// pay it out of the market.
+fn borrow(
+    market: &Account<Market>,
+    position: &mut Account<Position>,
+    owner: &Signer,
+    price: u128,
+    amount: u64,
+) -> Result<()> {
+    let value = position.collateral as u128 * price / PRICE_SCALE;
+    let limit = value * market.ltv_bps as u128 / 10_000;
+    let debt = position
+        .debt
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(debt as u128 <= limit, ErrorCode::BorrowLimit);
+    position.debt = debt;
+
+    let market = market.to_account_info();
+    **market.try_borrow_mut_lamports()? = market
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientLiquidity)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitMarket<'info> {
+    #[account(has_one = admin)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market", pool.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = owner, has_one = pool)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(mut, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordObservation<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+}
+
+/// Shared by both borrows. What differs is which price the handler reads
+/// from `pool`.
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = pool)]
+    pub market: Account<'info, Market>,
+    #[account(mut, has_one = owner, has_one = pool)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// A snapshot of the price accumulator.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct Observation {
+    pub cumulative: u128, // 16 bytes
+    pub timestamp: i64,   //  8 bytes
+}
+
+/// A constant-product SOL/TOK pool. Its PDA holds the SOL reserve; the TOK
+/// reserve is tracked here only.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub admin: Pubkey,                  // 32 bytes
+    pub sol_reserve: u64,               //  8 bytes, lamports
+    pub token_reserve: u64,             //  8 bytes, TOK base units
+    pub price_cumulative: u128,         // 16 bytes, sum of price x seconds
+    pub last_update: i64,               //  8 bytes
+    pub observations: [Observation; 2], // 48 bytes
+    pub bump: u8,                       //  1 byte
+}
+
+impl Pool {
+    /// Lamports per TOK unit, times `PRICE_SCALE`, at the current reserves.
+    pub fn spot_price(&self) -> Result<u128> {
+        require!(self.token_reserve > 0, ErrorCode::EmptyPool);
+        Ok(self.sol_reserve as u128 * PRICE_SCALE / self.token_reserve as u128)
+    }
+
+    /// The accumulator as of `now`, counting the current spot price for
+    /// the time since the last update.
+    fn cumulative_at(&self, now: i64) -> Result<u128> {
+        let elapsed = now.saturating_sub(self.last_update) as u128;
+        self.spot_price()?
+            .checked_mul(elapsed)
+            .and_then(|accrued| self.price_cumulative.checked_add(accrued))
+            .ok_or(error!(ErrorCode::Overflow))
+    }
+
+    /// Bring the accumulator up to `now`. Call before changing reserves.
+    fn accrue(&mut self, now: i64) -> Result<()> {
+        self.price_cumulative = self.cumulative_at(now)?;
+        self.last_update = now;
+        Ok(())
+    }
+
+    /// Overwrite the older observation, once the newer one is at least
+    /// `MIN_TWAP_WINDOW` old. The newer one then stays usable by `twap`
+    /// while the new window fills.
+    fn record(&mut self, now: i64) -> Result<()> {
+        self.accrue(now)?;
+        let (newest, oldest) = if self.observations[0].timestamp >= self.observations[1].timestamp {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+        require!(
+            now - self.observations[newest].timestamp >= MIN_TWAP_WINDOW,
+            ErrorCode::ObservationTooRecent
+        );
+        self.observations[oldest] = Observation {
+            cumulative: self.price_cumulative,
+            timestamp: now,
+        };
+        Ok(())
+    }
+
+    /// Average price since the newest observation at least
+    /// `MIN_TWAP_WINDOW` old.
+    pub fn twap(&self, now: i64) -> Result<u128> {
+        let start = self
+            .observations
+            .iter()
+            .filter(|obs| now - obs.timestamp >= MIN_TWAP_WINDOW)
+            .max_by_key(|obs| obs.timestamp)
+            .ok_or(ErrorCode::TwapUnavailable)?;
+        let elapsed = (now - start.timestamp) as u128;
+        Ok((self.cumulative_at(now)? - start.cumulative) / elapsed)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub pool: Pubkey, // 32 bytes
+    pub ltv_bps: u16, //  2 bytes
+    pub bump: u8,     //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,   // 32 bytes
+    pub pool: Pubkey,    // 32 bytes
+    pub tokens: u64,     //  8 bytes, free TOK
+    pub collateral: u64, //  8 bytes, locked TOK
+    pub debt: u64,       //  8 bytes, lamports
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Borrow exceeds the position's limit")]
+    BorrowLimit,
+    #[msg("No observation is old enough to cover the TWAP window")]
+    TwapUnavailable,
+    #[msg("The newest observation is younger than the TWAP window")]
+    ObservationTooRecent,
+    #[msg("Position does not hold enough tokens")]
+    InsufficientTokens,
+    #[msg("Pool has no liquidity")]
+    EmptyPool,
+    #[msg("Market cannot cover the loan")]
+    InsufficientLiquidity,
+    #[msg("LTV cannot exceed 100%")]
+    InvalidLtv,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,334 @@
+/// # Pattern 15: AMM Spot-Price Manipulation — Mollusk Exploit Tests
+///
+/// The pool holds 1,000 SOL and 100,000 TOK (0.01 SOL per TOK), with TWAP
+/// observations from an hour ago. The lending market holds 500 SOL and lends
+/// up to 50% of collateral value. The attacker has 10,000 TOK locked as
+/// collateral (100 SOL of value) and 3,000 SOL of flash-borrowed capital.
+/// Each test chains instructions in one clock second, as in a single
+/// transaction, feeding resulting accounts forward.
+///
+/// - Test 1: EXPLOIT — swap 3,000 SOL in, borrow_vulnerable the market's
+///   whole 500 SOL, swap back: the attacker ends 500 SOL up.
+/// - Test 2: SECURE — after the same swap, borrow_secure still values the
+///   collateral at 100 SOL and refuses 500 SOL.
+/// - Test 3: SANITY — after the same swap, borrow_secure lends the honest
+///   50 SOL limit.
+/// - Test 4: SECURE — ten minutes after the observations, the TWAP window
+///   isn't covered yet and borrow_secure refuses to price anything.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("2wheHSJBZuMQDeqeTHkr57j36tuiEcmsmwLnMfQHEzy9");
+
+const SOL: u64 = 1_000_000_000;
+const TOK: u64 = 1_000_000; // 6 decimals
+const RENT: u64 = 2_000_000;
+
+const SOL_RESERVE: u64 = 1_000 * SOL;
+const TOKEN_RESERVE: u64 = 100_000 * TOK;
+const LIQUIDITY: u64 = 500 * SOL;
+const LTV_BPS: u16 = 5_000;
+const COLLATERAL: u64 = 10_000 * TOK;
+const FLASH_LOAN: u64 = 3_000 * SOL;
+/// What the constant-product curve pays for FLASH_LOAN.
+const SWAP_OUT: u64 = 75_000 * TOK;
+/// Seconds between the pool's observations and the attack.
+const NOW: i64 = 3_600;
+
+// Error codes
+const BORROW_LIMIT: u32 = 6000;
+const TWAP_UNAVAILABLE: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account with no swaps since t = 0:
+/// [8 disc][32 admin][8 sol_reserve][8 token_reserve][16 price_cumulative]
+/// [8 last_update][2 x (16 cumulative, 8 timestamp) observations][1 bump]
+fn serialize_pool(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(129);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&SOL_RESERVE.to_le_bytes());
+    data.extend_from_slice(&TOKEN_RESERVE.to_le_bytes());
+    data.extend_from_slice(&0u128.to_le_bytes());
+    data.extend_from_slice(&0i64.to_le_bytes());
+    for _ in 0..2 {
+        data.extend_from_slice(&0u128.to_le_bytes());
+        data.extend_from_slice(&0i64.to_le_bytes());
+    }
+    data.push(bump);
+    data
+}
+
+/// Serialize a Market account: [8 disc][32 pool][2 ltv_bps][1 bump]
+fn serialize_market(pool: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(43);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(&LTV_BPS.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+/// [8 disc][32 owner][32 pool][8 tokens][8 collateral][8 debt][1 bump]
+fn serialize_position(
+    owner: &Pubkey,
+    pool: &Pubkey,
+    tokens: u64,
+    collateral: u64,
+    debt: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(97);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(&tokens.to_le_bytes());
+    data.extend_from_slice(&collateral.to_le_bytes());
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn mollusk_at(unix_timestamp: i64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "spot_price_manipulation");
+    mollusk.sysvars.clock.unix_timestamp = unix_timestamp;
+    mollusk
+}
+
+/// The pool, the market, and the attacker's position, tracked across
+/// chained instructions.
+struct World {
+    pool: Pubkey,
+    market: Pubkey,
+    attacker: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    ledger: Ledger,
+}
+
+impl World {
+    fn new() -> Self {
+        let admin = Pubkey::new_unique();
+        let (pool, pool_bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+        let (market, market_bump) =
+            Pubkey::find_program_address(&[b"market", pool.as_ref()], &PROGRAM_ID);
+        let attacker = Pubkey::new_unique();
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", pool.as_ref(), attacker.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                pool,
+                owned_account(&serialize_pool(&admin, pool_bump), RENT + SOL_RESERVE, &PROGRAM_ID),
+            ),
+            (
+                market,
+                owned_account(&serialize_market(&pool, market_bump), RENT + LIQUIDITY, &PROGRAM_ID),
+            ),
+            (
+                position,
+                owned_account(
+                    &serialize_position(&attacker, &pool, 0, COLLATERAL, 0, position_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                attacker,
+                AccountSharedData::new(RENT + FLASH_LOAN, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            pool,
+            market,
+            attacker,
+            position,
+            position_bump,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn swap(&self, name: &str, amount_in: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount_in.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn borrow(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.pool, false),
+                AccountMeta::new(self.market, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.attacker, true),
+            ],
+        )
+    }
+
+    fn position_data(&self, tokens: u64, debt: u64) -> Vec<u8> {
+        serialize_position(
+            &self.attacker,
+            &self.pool,
+            tokens,
+            COLLATERAL,
+            debt,
+            self.position_bump,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_swap_skews_spot_borrow_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: In one transaction the attacker:
+    //   1. swaps 3,000 SOL in: reserves go to 4,000 SOL / 25,000 TOK and
+    //      TOK's spot price from 0.01 to 0.16 SOL
+    //   2. borrows 500 SOL through borrow_vulnerable: 10,000 TOK now
+    //      "worth" 1,600 SOL, limit 800
+    //   3. swaps the 75,000 TOK back for the 3,000 SOL
+    //
+    // Expected: all three SUCCEED. The attacker ends with the flash loan
+    //           back plus 500 SOL, borrowed against 100 SOL of collateral.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(NOW);
+    let mut w = World::new();
+    let (market, position, attacker) = (w.market, w.position, w.attacker);
+
+    let ix = w.swap("swap_sol_for_tokens", FLASH_LOAN);
+    w.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let indebted = w.position_data(SWAP_OUT, LIQUIDITY);
+    let ix = w.borrow("borrow_vulnerable", LIQUIDITY);
+    w.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&market).lamports(RENT).build(),
+            Check::account(&position).data(&indebted).build(),
+        ],
+    );
+
+    let ix = w.swap("swap_tokens_for_sol", SWAP_OUT);
+    w.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&attacker)
+                .lamports(RENT + FLASH_LOAN + LIQUIDITY)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_twap_ignores_same_tx_swap() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same swap, then borrow_secure for 500 SOL.
+    //
+    // The swap accrued an hour of the old price and then moved the
+    // reserves. The new price has been in effect for zero seconds, so the
+    // TWAP is still 0.01 SOL per TOK.
+    //
+    // Expected: FAILS with BorrowLimit (6000) — 10,000 TOK is worth 100 SOL,
+    //           limit 50.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(NOW);
+    let mut w = World::new();
+
+    let ix = w.swap("swap_sol_for_tokens", FLASH_LOAN);
+    w.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = w.borrow("borrow_secure", LIQUIDITY);
+    w.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(BORROW_LIMIT))],
+    );
+}
+
+#[test]
+fn sanity_twap_borrow_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: After the same swap, borrow_secure for 50 SOL.
+    //
+    // Expected: SUCCEEDS — exactly the limit at the time-weighted price.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(NOW);
+    let mut w = World::new();
+    let (market, position) = (w.market, w.position);
+
+    let ix = w.swap("swap_sol_for_tokens", FLASH_LOAN);
+    w.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let indebted = w.position_data(SWAP_OUT, 50 * SOL);
+    let ix = w.borrow("borrow_secure", 50 * SOL);
+    w.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&market)
+                .lamports(RENT + LIQUIDITY - 50 * SOL)
+                .build(),
+            Check::account(&position).data(&indebted).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_twap_requires_window() {
+    // -----------------------------------------------------------------------
+    // SECURE: Ten minutes after the pool's observations, borrow_secure for
+    // 1 SOL.
+    //
+    // Expected: FAILS with TwapUnavailable (6001) — no observation is at
+    //           least 30 minutes old, so there's no average to use yet.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(600);
+    let mut w = World::new();
+
+    let ix = w.borrow("borrow_secure", SOL);
+    w.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(TWAP_UNAVAILABLE))],
+    );
+}
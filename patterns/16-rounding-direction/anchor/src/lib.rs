@@ -0,0 +1,337 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+mod math;
+
+use math::{mul_div, Rounding};
+
+declare_id!("8wiqW2TtkW85HGss7XkCfgXXYHmZ5S6BhgssNvYxUF6T");
+
+/// Shares have 6 decimals and SOL has 9, so the first deposit mints one
+/// share per 1,000 lamports.
+pub const LAMPORTS_PER_SHARE: u64 = 1_000;
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// # Rounding Direction
+///
+/// This program demonstrates a share vault whose withdrawal math rounds in
+/// the withdrawer's favor.
+///
+/// ## The Vulnerability
+/// Withdrawing `amount` lamports burns `amount * total_shares /
+/// total_assets` shares and charges `amount * fee_bps / 10_000` in fees.
+/// Both are integer divisions, and both truncate. Every withdrawal keeps
+/// the remainders: up to one share's worth of lamports that burn nothing,
+/// and up to one lamport of fee that's never charged.
+///
+/// ## Real-World Impact
+/// One remainder is dust. A withdrawal sized just under one share burns
+/// zero shares, and dozens of them fit in a transaction. The lamports come
+/// out of the vault while the share count stays put, so every other
+/// shareholder's shares are worth less. ERC-4626 vaults and lending pools
+/// have been drained one remainder at a time this way.
+#[program]
+pub mod rounding_direction {
+    use super::*;
+
+    pub fn init_vault(ctx: Context<InitVault>, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+        let vault = &mut ctx.accounts.vault;
+        vault.admin = ctx.accounts.admin.key();
+        vault.fee_bps = fee_bps;
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "init_vault",
+            actor = vault.admin,
+            fee_bps = fee_bps
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.vault = ctx.accounts.vault.key();
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Deposit `amount` lamports for shares. Shares minted round down: the
+    /// depositor gets no credit for a fraction of a share.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let shares = if vault.total_shares == 0 {
+            amount / LAMPORTS_PER_SHARE
+        } else {
+            mul_div(
+                amount,
+                vault.total_shares,
+                vault.total_assets,
+                Rounding::Down,
+            )?
+        };
+        require!(shares > 0, ErrorCode::ZeroShares);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        vault.total_assets = vault
+            .total_assets
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        vault.total_shares = vault
+            .total_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::Overflow)?;
+        let position = &mut ctx.accounts.position;
+        position.shares = position
+            .shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            shares = shares
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Shares burned and fee charged both round down
+    // ============================================================================
+    // ISSUE: `amount * total_shares / total_assets` and
+    //        `amount * fee_bps / 10_000` truncate. The withdrawer keeps both
+    //        remainders.
+    //
+    // ATTACK SCENARIO (1,000 lamports per share, 30 bps fee):
+    //   1. Attacker withdraws 999 lamports
+    //   2. Shares burned: 999 / 1,000 = 0. Fee: 2.997 → 2
+    //   3. The vault pays 997 lamports and burns nothing
+    //   4. Repeat. Dozens fit in one transaction, so each 5,000-lamport
+    //      signature returns tens of thousands of lamports, paid by the
+    //      other shareholders through a lower share price
+    //
+    // The attacker doesn't even need shares: burning zero passes a
+    // zero-share balance check.
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        // VULNERABLE: both divisions truncate toward the withdrawer
+        let shares =
+            (amount as u128 * vault.total_shares as u128 / vault.total_assets as u128) as u64;
+        let fee = (amount as u128 * vault.fee_bps as u128 / 10_000) as u64;
+
+        settle_withdraw(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.position,
+            &ctx.accounts.owner,
+            amount,
+            shares,
+            fee,
+        )?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            shares = shares,
+            fee = fee
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Every division rounds toward the vault
+    // ============================================================================
+    // FIX: The vault takes in shares and fees, so both round up. The
+    //      deposit path, which pays out shares, rounds down.
+    //
+    // WHO ABSORBS THE REMAINDER:
+    // | Quantity              | Direction | Who it's paid to |
+    // |-----------------------|-----------|------------------|
+    // | shares minted         | Down      | depositor        |
+    // | shares burned         | Up        | vault            |
+    // | fee charged           | Up        | vault            |
+    //
+    // A 999-lamport withdrawal now burns one full share and pays a
+    // 3-lamport fee. Dust withdrawals cost the withdrawer, never the vault.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        // SECURE: the remainder stays with the vault
+        let shares = mul_div(amount, vault.total_shares, vault.total_assets, Rounding::Up)?;
+        let fee = mul_div(amount, vault.fee_bps as u64, 10_000, Rounding::Up)?;
+
+        settle_withdraw(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.position,
+            &ctx.accounts.owner,
+            amount,
+            shares,
+            fee,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            shares = shares,
+            fee = fee
+        );
+        Ok(())
+    }
+}
+
+/// Burn `shares`, keep `fee` in the vault, and pay `amount - fee` to the
+/// owner.
+fn settle_withdraw(
+    vault: &mut Account<Vault>,
+    position: &mut Account<Position>,
+    owner: &Signer,
+    amount: u64,
+    shares: u64,
+    fee: u64,
+) -> Result<()> {
+    require!(shares <= position.shares, ErrorCode::InsufficientShares);
+    let payout = amount.checked_sub(fee).ok_or(ErrorCode::Overflow)?;
+
+    position.shares -= shares;
+    vault.total_shares = vault
+        .total_shares
+        .checked_sub(shares)
+        .ok_or(ErrorCode::Overflow)?;
+    vault.total_assets = vault
+        .total_assets
+        .checked_sub(payout)
+        .ok_or(ErrorCode::InsufficientAssets)?;
+
+    let vault = vault.to_account_info();
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(payout)
+        .ok_or(ErrorCode::InsufficientAssets)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(payout)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitVault<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner,
+        has_one = vault
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner,
+        has_one = vault
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub admin: Pubkey,     // 32 bytes
+    pub total_assets: u64, //  8 bytes, lamports
+    pub total_shares: u64, //  8 bytes
+    pub fee_bps: u16,      //  2 bytes
+    pub bump: u8,          //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey, // 32 bytes
+    pub vault: Pubkey, // 32 bytes
+    pub shares: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Position does not hold enough shares")]
+    InsufficientShares,
+    #[msg("Deposit is worth less than one share")]
+    ZeroShares,
+    #[msg("Vault does not hold enough assets")]
+    InsufficientAssets,
+    #[msg("Vault has no assets")]
+    EmptyVault,
+    #[msg("Fee cannot exceed 10%")]
+    InvalidFee,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
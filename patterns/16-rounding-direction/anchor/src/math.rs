@@ -0,0 +1,30 @@
+//! Multiply-then-divide with the rounding direction spelled out.
+//!
+//! Integer division drops the remainder, and whoever the result is paid to
+//! keeps it. For a vault that should always be the vault: the remaining
+//! shareholders, not the caller. Taking `Rounding` as an argument makes
+//! every call site say which way it goes.
+
+use anchor_lang::prelude::*;
+
+use crate::ErrorCode;
+
+/// Which way a division with a remainder goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Toward zero. For amounts the vault pays out or mints.
+    Down,
+    /// Away from zero. For amounts the vault takes in or burns.
+    Up,
+}
+
+/// `a * b / c`, computed in `u128` and rounded as asked.
+pub fn mul_div(a: u64, b: u64, c: u64, rounding: Rounding) -> Result<u64> {
+    require!(c > 0, ErrorCode::EmptyVault);
+    let product = a as u128 * b as u128;
+    let quotient = match rounding {
+        Rounding::Down => product / c as u128,
+        Rounding::Up => product.div_ceil(c as u128),
+    };
+    u64::try_from(quotient).map_err(|_| error!(ErrorCode::Overflow))
+}
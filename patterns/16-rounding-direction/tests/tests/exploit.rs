@@ -0,0 +1,261 @@
+/// # Pattern 16: Rounding Direction — Mollusk Exploit Tests
+///
+/// The vault holds 1,000 SOL against 1,000,000,000 shares (1,000 lamports per
+/// share) and charges a 30 bps withdrawal fee. Withdrawals chain through a
+/// ledger, the way a transaction full of them would run.
+///
+/// - Test 1: EXPLOIT — a position with zero shares makes 25 withdrawals of
+///   999 lamports through withdraw_vulnerable; each burns nothing.
+/// - Test 2: SECURE — the same zero-share position can't withdraw 999
+///   lamports through withdraw_secure.
+/// - Test 3: SECURE — a holder's 999-lamport withdrawal burns one whole share
+///   and pays a 3-lamport fee.
+/// - Test 4: SANITY — a whole-share withdrawal has no remainder, and both
+///   variants burn and charge the same.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8wiqW2TtkW85HGss7XkCfgXXYHmZ5S6BhgssNvYxUF6T");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const TOTAL_ASSETS: u64 = 1_000 * SOL;
+const TOTAL_SHARES: u64 = 1_000_000_000;
+const FEE_BPS: u16 = 30;
+/// One lamport short of a share.
+const DUST: u64 = 999;
+
+// Error codes
+const INSUFFICIENT_SHARES: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account:
+/// [8 disc][32 admin][8 total_assets][8 total_shares][2 fee_bps][1 bump]
+fn serialize_vault(admin: &Pubkey, total_assets: u64, total_shares: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(59);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&total_assets.to_le_bytes());
+    data.extend_from_slice(&total_shares.to_le_bytes());
+    data.extend_from_slice(&FEE_BPS.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account: [8 disc][32 owner][32 vault][8 shares][1 bump]
+fn serialize_position(owner: &Pubkey, vault: &Pubkey, shares: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(&shares.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The vault and one withdrawer's position.
+struct Vault {
+    admin: Pubkey,
+    vault: Pubkey,
+    vault_bump: u8,
+    owner: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    ledger: Ledger,
+}
+
+impl Vault {
+    fn new(shares: u64) -> Self {
+        let admin = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", vault.as_ref(), owner.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(&admin, TOTAL_ASSETS, TOTAL_SHARES, vault_bump),
+                    RENT + TOTAL_ASSETS,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                position,
+                owned_account(
+                    &serialize_position(&owner, &vault, shares, position_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                owner,
+                AccountSharedData::new(RENT, 0, &solana_sdk::system_program::ID),
+            ),
+        ];
+
+        Self {
+            admin,
+            vault,
+            vault_bump,
+            owner,
+            position,
+            position_bump,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn withdraw(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.owner, true),
+            ],
+        )
+    }
+
+    fn vault_data(&self, total_assets: u64, total_shares: u64) -> Vec<u8> {
+        serialize_vault(&self.admin, total_assets, total_shares, self.vault_bump)
+    }
+
+    fn position_data(&self, shares: u64) -> Vec<u8> {
+        serialize_position(&self.owner, &self.vault, shares, self.position_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_dust_withdrawals_burn_nothing_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: A position holding zero shares withdraws 999 lamports, 25
+    // times.
+    //
+    // Each withdrawal burns 999 / 1,000 = 0 shares, which the zero balance
+    // covers, and charges a 2-lamport fee instead of 2.997.
+    //
+    // Expected: all 25 SUCCEED. The attacker receives 25 × 997 lamports; the
+    //           vault's share count never moves, so every real share is
+    //           now worth slightly less.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "rounding_direction");
+    let mut v = Vault::new(0);
+    let (vault, position, owner) = (v.vault, v.position, v.owner);
+    let ix = v.withdraw("withdraw_vulnerable", DUST);
+
+    for n in 1..=25 {
+        let paid = n * (DUST - 2);
+        let vault_data = v.vault_data(TOTAL_ASSETS - paid, TOTAL_SHARES);
+        let position_data = v.position_data(0);
+        v.ledger.step(
+            &mollusk,
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&vault).data(&vault_data).build(),
+                Check::account(&position).data(&position_data).build(),
+                Check::account(&owner).lamports(RENT + paid).build(),
+            ],
+        );
+    }
+}
+
+#[test]
+fn secure_dust_withdrawal_needs_a_share() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same zero-share position withdraws 999 lamports through
+    // withdraw_secure.
+    //
+    // Expected: FAILS with InsufficientShares (6000) — 999 lamports rounds
+    //           up to one share, and the position has none.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "rounding_direction");
+    let mut v = Vault::new(0);
+    let ix = v.withdraw("withdraw_secure", DUST);
+
+    v.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_SHARES))],
+    );
+}
+
+#[test]
+fn secure_rounds_shares_and_fee_up() {
+    // -----------------------------------------------------------------------
+    // SECURE: A holder of 10 shares withdraws 999 lamports through
+    // withdraw_secure.
+    //
+    // Expected: SUCCEEDS — one whole share is burned and the fee is 3
+    //           lamports, so the holder receives 996. The vault keeps the
+    //           remainder of both divisions.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "rounding_direction");
+    let mut v = Vault::new(10);
+    let (vault, position, owner) = (v.vault, v.position, v.owner);
+    let ix = v.withdraw("withdraw_secure", DUST);
+    let vault_data = v.vault_data(TOTAL_ASSETS - 996, TOTAL_SHARES - 1);
+    let position_data = v.position_data(9);
+
+    v.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).data(&vault_data).build(),
+            Check::account(&position).data(&position_data).build(),
+            Check::account(&owner).lamports(RENT + 996).build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_exact_withdrawal_matches() {
+    // -----------------------------------------------------------------------
+    // SANITY: A holder of 10 shares withdraws exactly 10,000 lamports, once
+    // through each variant.
+    //
+    // Expected: both SUCCEED identically — 10 shares burned, a 30-lamport
+    //           fee, 9,970 lamports paid. Rounding only matters when
+    //           there's a remainder.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "rounding_direction");
+
+    for name in ["withdraw_vulnerable", "withdraw_secure"] {
+        let mut v = Vault::new(10);
+        let (vault, position, owner) = (v.vault, v.position, v.owner);
+        let ix = v.withdraw(name, 10_000);
+        let vault_data = v.vault_data(TOTAL_ASSETS - 9_970, TOTAL_SHARES - 10);
+        let position_data = v.position_data(0);
+
+        v.ledger.step(
+            &mollusk,
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&vault).data(&vault_data).build(),
+                Check::account(&position).data(&position_data).build(),
+                Check::account(&owner).lamports(RENT + 9_970).build(),
+            ],
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("HrnvpMfdPq7B5kG8CV3p9x9igDYKnrMMH7fxaFtxSDGf");
+
+/// A borrow index of 1.0. The index starts here and only grows.
+pub const INDEX_ONE: u64 = 1_000_000_000;
+pub const MAX_RATE_BPS: u16 = 10_000;
+
+/// # Division Before Multiplication
+///
+/// This program demonstrates a lending market whose interest accrual
+/// divides before it multiplies.
+///
+/// ## The Vulnerability
+/// Interest accrues through a borrow index. Each loan records the index at
+/// its last refresh, and a refresh rescales its debt by the index's growth:
+/// `debt * new_index / old_index`. The vulnerable refresh computes
+/// `debt / old_index * new_index`. The division runs first and truncates
+/// to whole index units — with the index near 1.0 and debt in lamports,
+/// that drops everything below one SOL before the growth is applied.
+///
+/// ## Real-World Impact
+/// A refresh is supposed to grow debt. This one can shrink it: a loan of
+/// 1.999999999 SOL refreshed at +10% owes 1.1 SOL, and a loan under
+/// 1 SOL owes nothing. Refresh is permissionless, so a borrower who sizes
+/// loans just under a whole index unit borrows for free, at the lenders'
+/// expense.
+#[program]
+pub mod division_before_multiplication {
+    use super::*;
+
+    pub fn init_market(ctx: Context<InitMarket>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.admin = ctx.accounts.admin.key();
+        market.borrow_index = INDEX_ONE;
+        market.bump = ctx.bumps.market;
+        demo_log!(Mode::Setup, "init_market", actor = market.admin);
+        Ok(())
+    }
+
+    /// Admin: grow the borrow index by `rate_bps` for the period just ended.
+    pub fn accrue(ctx: Context<Accrue>, rate_bps: u16) -> Result<()> {
+        require!(rate_bps <= MAX_RATE_BPS, ErrorCode::InvalidRate);
+        let market = &mut ctx.accounts.market;
+        market.borrow_index = scale(market.borrow_index, 10_000 + rate_bps as u64, 10_000)?;
+        demo_log!(
+            Mode::Setup,
+            "accrue",
+            actor = market.admin,
+            rate_bps = rate_bps,
+            index = market.borrow_index
+        );
+        Ok(())
+    }
+
+    /// Borrow `amount` lamports from the market into a new loan.
+    pub fn open_loan(ctx: Context<OpenLoan>, amount: u64) -> Result<()> {
+        let loan = &mut ctx.accounts.loan;
+        loan.owner = ctx.accounts.owner.key();
+        loan.market = ctx.accounts.market.key();
+        loan.debt = amount;
+        loan.index_snapshot = ctx.accounts.market.borrow_index;
+        loan.bump = ctx.bumps.loan;
+
+        let market = ctx.accounts.market.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+        **market.try_borrow_mut_lamports()? = market
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        **owner.try_borrow_mut_lamports()? = owner
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "open_loan",
+            actor = loan.owner,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Divides by the old index, then multiplies by the new one
+    // ============================================================================
+    // ISSUE: `debt / index_snapshot` truncates to whole index units before
+    //        the growth is applied. With the index around 1e9, every
+    //        lamport below a whole SOL of debt is dropped.
+    //
+    // ATTACK SCENARIO (index 1.0 → 1.1):
+    //   1. Borrower opens a loan of 1.999999999 SOL
+    //   2. After accrual, anyone calls refresh_vulnerable on it
+    //   3. 1,999,999,999 / 1,000,000,000 = 1; 1 × 1,100,000,000 = 1.1 SOL
+    //   4. The debt should be 2.2 SOL. A loan under 1 SOL becomes 0
+    //
+    // WHY IT SURVIVES REVIEW:
+    // It reads as the right formula, and tests with debt in whole SOL pass:
+    // 2 SOL / 1.0 × 1.1 is exactly 2.2 SOL either way.
+    // ============================================================================
+    pub fn refresh_vulnerable(ctx: Context<Refresh>) -> Result<()> {
+        let index = ctx.accounts.market.borrow_index;
+        let loan = &mut ctx.accounts.loan;
+        // VULNERABLE: the division throws away the remainder first
+        let debt = loan.debt / loan.index_snapshot * index;
+        let before = loan.debt;
+        loan.debt = debt;
+        loan.index_snapshot = index;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "refresh",
+            actor = loan.owner,
+            amount = debt,
+            before = before
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Multiplies first, in u128
+    // ============================================================================
+    // FIX: `debt * index / index_snapshot`, with the product in u128 so it
+    //      can't overflow, rounded up so the borrower owes any remainder.
+    //      The only precision lost is under one lamport.
+    //
+    // WHY u128:
+    // In u64, `debt * index` overflows once debt passes ~18 SOL at an index
+    // of 1.0. Multiplying first is only safe with a wider intermediate.
+    // ============================================================================
+    pub fn refresh_secure(ctx: Context<Refresh>) -> Result<()> {
+        let index = ctx.accounts.market.borrow_index;
+        let loan = &mut ctx.accounts.loan;
+        // SECURE: full precision until the single division at the end
+        let debt = scale(loan.debt, index, loan.index_snapshot)?;
+        let before = loan.debt;
+        loan.debt = debt;
+        loan.index_snapshot = index;
+
+        demo_log!(
+            Mode::Secure,
+            "refresh",
+            actor = loan.owner,
+            amount = debt,
+            before = before
+        );
+        Ok(())
+    }
+}
+
+/// `value * numerator / denominator` in u128, rounded up.
+fn scale(value: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    require!(denominator > 0, ErrorCode::InvalidIndex);
+    let scaled = (value as u128 * numerator as u128).div_ceil(denominator as u128);
+    u64::try_from(scaled).map_err(|_| error!(ErrorCode::Overflow))
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitMarket<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market"],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Accrue<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump, has_one = admin)]
+    pub market: Account<'info, Market>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenLoan<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [b"loan", market.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub loan: Account<'info, Loan>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Refresh is permissionless: anyone may bring any loan up to date.
+#[derive(Accounts)]
+pub struct Refresh<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(mut, has_one = market)]
+    pub loan: Account<'info, Loan>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub admin: Pubkey,     // 32 bytes
+    pub borrow_index: u64, //  8 bytes, INDEX_ONE = 1.0
+    pub bump: u8,          //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Loan {
+    pub owner: Pubkey,       // 32 bytes
+    pub market: Pubkey,      // 32 bytes
+    pub debt: u64,           //  8 bytes, lamports as of index_snapshot
+    pub index_snapshot: u64, //  8 bytes
+    pub bump: u8,            //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Borrow index is zero")]
+    InvalidIndex,
+    #[msg("Rate cannot exceed 100% per period")]
+    InvalidRate,
+    #[msg("Market cannot cover the loan")]
+    InsufficientLiquidity,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,163 @@
+/// # Pattern 17: Division Before Multiplication — Mollusk Exploit Tests
+///
+/// Every test refreshes one loan whose snapshot is the starting index
+/// (1.0) against a market index of 1.1: 10% interest. The loans' debts sit
+/// on and around whole index units, where the two formulas diverge.
+///
+/// - Test 1: EXPLOIT — 1.999999999 SOL refreshes to 1.1 SOL through
+///   refresh_vulnerable, instead of 2.2.
+/// - Test 2: EXPLOIT — 0.999999999 SOL refreshes to zero.
+/// - Test 3: SECURE — refresh_secure grows 1.999999999 SOL to
+///   2.199999999, rounding the remainder up.
+/// - Test 4: SECURE — a 1,000,000 SOL loan, whose u64 product would
+///   overflow, refreshes correctly.
+/// - Test 5: SANITY — a whole 2 SOL refreshes to 2.2 SOL through both,
+///   which is why round-number tests miss the bug.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("HrnvpMfdPq7B5kG8CV3p9x9igDYKnrMMH7fxaFtxSDGf");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const INDEX_ONE: u64 = 1_000_000_000;
+/// The market index after 10% interest.
+const INDEX_NOW: u64 = 1_100_000_000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Market account: [8 disc][32 admin][8 borrow_index][1 bump]
+fn serialize_market(admin: &Pubkey, borrow_index: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&borrow_index.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Loan account:
+/// [8 disc][32 owner][32 market][8 debt][8 index_snapshot][1 bump]
+fn serialize_loan(owner: &Pubkey, market: &Pubkey, debt: u64, index_snapshot: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(89);
+    data.extend_from_slice(&account_discriminator("Loan"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(market.as_ref());
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.extend_from_slice(&index_snapshot.to_le_bytes());
+    data.push(255);
+    data
+}
+
+/// Refresh a loan of `debt` lamports, snapshotted at INDEX_ONE, against a
+/// market at INDEX_NOW, and check the debt it ends with.
+fn refresh(name: &str, debt: u64, expected_debt: u64) {
+    let mollusk = Mollusk::new(&PROGRAM_ID, "division_before_multiplication");
+    let admin = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let loan = Pubkey::new_unique();
+    let (market, market_bump) = Pubkey::find_program_address(&[b"market"], &PROGRAM_ID);
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator(name),
+        vec![
+            AccountMeta::new_readonly(market, false),
+            AccountMeta::new(loan, false),
+        ],
+    );
+    let accounts = vec![
+        (
+            market,
+            owned_account(&serialize_market(&admin, INDEX_NOW, market_bump), RENT, &PROGRAM_ID),
+        ),
+        (
+            loan,
+            owned_account(&serialize_loan(&owner, &market, debt, INDEX_ONE), RENT, &PROGRAM_ID),
+        ),
+    ];
+    let refreshed = serialize_loan(&owner, &market, expected_debt, INDEX_NOW);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&loan).data(&refreshed).build(),
+        ],
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_refresh_truncates_debt_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: A loan of 1.999999999 SOL is refreshed through
+    // refresh_vulnerable after 10% interest.
+    //
+    // 1,999,999,999 / 1,000,000,000 = 1, then × 1,100,000,000.
+    //
+    // Expected: SUCCEEDS with debt 1.1 SOL. It should be 2.2; the refresh
+    //           forgave 1.1 SOL.
+    // -----------------------------------------------------------------------
+    refresh("refresh_vulnerable", 2 * SOL - 1, 1_100_000_000);
+}
+
+#[test]
+fn exploit_sub_unit_debt_erased_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: A loan of 0.999999999 SOL is refreshed through
+    // refresh_vulnerable.
+    //
+    // Expected: SUCCEEDS with debt 0. Every loan under one index unit is
+    //           free after its first refresh.
+    // -----------------------------------------------------------------------
+    refresh("refresh_vulnerable", SOL - 1, 0);
+}
+
+#[test]
+fn secure_refresh_keeps_precision() {
+    // -----------------------------------------------------------------------
+    // SECURE: The 1.999999999 SOL loan through refresh_secure.
+    //
+    // 1,999,999,999 × 1,100,000,000 / 1,000,000,000 = 2,199,999,998.9
+    //
+    // Expected: SUCCEEDS with debt 2,199,999,999. The fraction rounds up,
+    //           against the borrower.
+    // -----------------------------------------------------------------------
+    refresh("refresh_secure", 2 * SOL - 1, 2_199_999_999);
+}
+
+#[test]
+fn secure_large_debt_no_overflow() {
+    // -----------------------------------------------------------------------
+    // SECURE: A 1,000,000 SOL loan through refresh_secure.
+    //
+    // debt × index is 1.1e24, far past u64::MAX (1.8e19). Multiplying
+    // first needs the u128 intermediate.
+    //
+    // Expected: SUCCEEDS with debt 1,100,000 SOL.
+    // -----------------------------------------------------------------------
+    refresh("refresh_secure", 1_000_000 * SOL, 1_100_000 * SOL);
+}
+
+#[test]
+fn sanity_whole_units_match() {
+    // -----------------------------------------------------------------------
+    // SANITY: A loan of exactly 2 SOL, through each refresh.
+    //
+    // Expected: both SUCCEED with debt 2.2 SOL. With no remainder, the
+    //           order of operations doesn't matter.
+    // -----------------------------------------------------------------------
+    refresh("refresh_vulnerable", 2 * SOL, 2_200_000_000);
+    refresh("refresh_secure", 2 * SOL, 2_200_000_000);
+}
@@ -0,0 +1,221 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("BXYdjeZMq6KRAC9meHmhVC3MzkqE24D7teaxHX1SeCab");
+
+/// # Truncating Casts
+///
+/// This program demonstrates a grants treasury whose claim path narrows the
+/// requested amount with `as`.
+///
+/// ## The Vulnerability
+/// Each grant stores its limit and the amount claimed so far as `u32`
+/// lamports, which keeps the account small and comfortably covers grants of
+/// a few SOL. Claims arrive as `u64`. The vulnerable claim brings the
+/// request down to the grant's width with `amount as u32`, checks and books
+/// that, and then pays `amount`. `as` never fails: it keeps the low 32 bits
+/// and drops the rest.
+///
+/// ## Real-World Impact
+/// A claim of exactly 2^32 lamports (about 4.29 SOL) truncates to 0. It
+/// passes the limit check, adds nothing to `claimed`, and pays 4.29 SOL.
+/// Any multiple of 2^32, plus whatever the grant still allows, works the
+/// same way, so a grantee with a 0.1 SOL grant can empty the treasury.
+#[program]
+pub mod truncating_casts {
+    use super::*;
+
+    pub fn init_treasury(ctx: Context<InitTreasury>) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.admin = ctx.accounts.admin.key();
+        treasury.bump = ctx.bumps.treasury;
+        demo_log!(Mode::Setup, "init_treasury", actor = treasury.admin);
+        Ok(())
+    }
+
+    /// Admin: award `grantee` up to `limit` lamports.
+    pub fn create_grant(ctx: Context<CreateGrant>, limit: u32) -> Result<()> {
+        let grant = &mut ctx.accounts.grant;
+        grant.grantee = ctx.accounts.grantee.key();
+        grant.limit = limit;
+        grant.claimed = 0;
+        grant.bump = ctx.bumps.grant;
+        demo_log!(
+            Mode::Setup,
+            "create_grant",
+            actor = ctx.accounts.admin.key(),
+            grantee = grant.grantee,
+            limit = limit
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Narrows the request with `as`, pays the original
+    // ============================================================================
+    // ISSUE: `amount as u32` silently drops the high 32 bits. The limit
+    //        check and the `claimed` counter see the truncated value; the
+    //        lamport transfer uses the full `amount`.
+    //
+    // ATTACK SCENARIO (grant limit 1 SOL):
+    //   1. Grantee calls claim_vulnerable(4_294_967_296), which is 2^32
+    //   2. 2^32 as u32 = 0: the check `0 + 0 <= limit` passes
+    //   3. claimed += 0; the treasury pays 4.29 SOL
+    //   4. Repeat until the treasury is empty. The grant still shows 0 claimed
+    //
+    // WHY IT SURVIVES REVIEW:
+    // The cast reads as a type adjustment to make the comparison compile.
+    // Every realistic claim is far below 2^32 lamports and behaves exactly
+    // as intended.
+    // ============================================================================
+    pub fn claim_vulnerable(ctx: Context<Claim>, amount: u64) -> Result<()> {
+        let grant = &mut ctx.accounts.grant;
+        // VULNERABLE: keeps the low 32 bits of an attacker-chosen value
+        let booked = amount as u32;
+        let claimed = grant
+            .claimed
+            .checked_add(booked)
+            .ok_or(ErrorCode::OverLimit)?;
+        require!(claimed <= grant.limit, ErrorCode::OverLimit);
+        grant.claimed = claimed;
+
+        pay(&ctx.accounts.treasury, &ctx.accounts.grantee, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "claim",
+            actor = grant.grantee,
+            amount = amount,
+            booked = booked,
+            claimed = grant.claimed
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Converts with `try_into`, fails on values that don't fit
+    // ============================================================================
+    // FIX: `u32::try_from(amount)` returns an error for anything above
+    //      u32::MAX, surfaced as AmountTooLarge. The value that's checked,
+    //      booked, and paid is the same number.
+    // ============================================================================
+    pub fn claim_secure(ctx: Context<Claim>, amount: u64) -> Result<()> {
+        let grant = &mut ctx.accounts.grant;
+        // SECURE: a value that doesn't fit is an error, not a smaller value
+        let booked: u32 = amount.try_into().map_err(|_| ErrorCode::AmountTooLarge)?;
+        let claimed = grant
+            .claimed
+            .checked_add(booked)
+            .ok_or(ErrorCode::OverLimit)?;
+        require!(claimed <= grant.limit, ErrorCode::OverLimit);
+        grant.claimed = claimed;
+
+        pay(&ctx.accounts.treasury, &ctx.accounts.grantee, booked.into())?;
+        demo_log!(
+            Mode::Secure,
+            "claim",
+            actor = grant.grantee,
+            amount = booked,
+            claimed = grant.claimed
+        );
+        Ok(())
+    }
+}
+
+fn pay<'info>(
+    treasury: &Account<'info, Treasury>,
+    grantee: &Signer<'info>,
+    amount: u64,
+) -> Result<()> {
+    let treasury = treasury.to_account_info();
+    **treasury.try_borrow_mut_lamports()? = treasury
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    **grantee.try_borrow_mut_lamports()? = grantee
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitTreasury<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateGrant<'info> {
+    #[account(seeds = [b"treasury"], bump = treasury.bump, has_one = admin)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Grant::INIT_SPACE,
+        seeds = [b"grant", grantee.key().as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, Grant>,
+    /// CHECK: Only recorded as the grant's recipient.
+    pub grantee: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [b"grant", grantee.key().as_ref()],
+        bump = grant.bump,
+        has_one = grantee
+    )]
+    pub grant: Account<'info, Grant>,
+    #[account(mut)]
+    pub grantee: Signer<'info>,
+}
+
+/// Lamports held above rent fund the grants.
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub admin: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Grant {
+    pub grantee: Pubkey, // 32 bytes
+    pub limit: u32,      //  4 bytes, lamports (max ~4.29 SOL)
+    pub claimed: u32,    //  4 bytes
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount does not fit in a grant")]
+    AmountTooLarge,
+    #[msg("Claim exceeds the grant's limit")]
+    OverLimit,
+    #[msg("Treasury cannot cover the claim")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
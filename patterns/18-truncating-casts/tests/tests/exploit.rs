@@ -0,0 +1,226 @@
+/// # Pattern 18: Truncating Casts — Mollusk Exploit Tests
+///
+/// The treasury holds 100 SOL. The grantee's grant allows 1 SOL in total,
+/// stored as u32 lamports, and nothing has been claimed yet.
+///
+/// - Test 1: EXPLOIT — three claims of 2^32 lamports through
+///   claim_vulnerable each pay 4.29 SOL and book nothing.
+/// - Test 2: SECURE — claim_secure rejects the same amount as too large.
+/// - Test 3: SECURE — claim_secure still enforces the limit for amounts
+///   that fit.
+/// - Test 4: SANITY — a claim within the limit pays and books the same
+///   amount through both variants.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("BXYdjeZMq6KRAC9meHmhVC3MzkqE24D7teaxHX1SeCab");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const TREASURY: u64 = 100 * SOL;
+const LIMIT: u32 = 1_000_000_000;
+/// 2^32 lamports. As u32 it is 0.
+const WRAP: u64 = 1 << 32;
+
+// Error codes
+const AMOUNT_TOO_LARGE: u32 = 6000;
+const OVER_LIMIT: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Treasury account: [8 disc][32 admin][1 bump]
+fn serialize_treasury(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(&account_discriminator("Treasury"));
+    data.extend_from_slice(admin.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Grant account: [8 disc][32 grantee][4 limit][4 claimed][1 bump]
+fn serialize_grant(grantee: &Pubkey, claimed: u32, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Grant"));
+    data.extend_from_slice(grantee.as_ref());
+    data.extend_from_slice(&LIMIT.to_le_bytes());
+    data.extend_from_slice(&claimed.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The treasury and one grantee's grant.
+struct Grants {
+    treasury: Pubkey,
+    grantee: Pubkey,
+    grant: Pubkey,
+    grant_bump: u8,
+    ledger: Ledger,
+}
+
+impl Grants {
+    fn new() -> Self {
+        let admin = Pubkey::new_unique();
+        let grantee = Pubkey::new_unique();
+        let (treasury, treasury_bump) = Pubkey::find_program_address(&[b"treasury"], &PROGRAM_ID);
+        let (grant, grant_bump) =
+            Pubkey::find_program_address(&[b"grant", grantee.as_ref()], &PROGRAM_ID);
+
+        let ledger = vec![
+            (
+                treasury,
+                owned_account(
+                    &serialize_treasury(&admin, treasury_bump),
+                    RENT + TREASURY,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                grant,
+                owned_account(&serialize_grant(&grantee, 0, grant_bump), RENT, &PROGRAM_ID),
+            ),
+            (
+                grantee,
+                AccountSharedData::new(RENT, 0, &solana_sdk::system_program::ID),
+            ),
+        ];
+
+        Self {
+            treasury,
+            grantee,
+            grant,
+            grant_bump,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn claim(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(self.grant, false),
+                AccountMeta::new(self.grantee, true),
+            ],
+        )
+    }
+
+    fn grant_data(&self, claimed: u32) -> Vec<u8> {
+        serialize_grant(&self.grantee, claimed, self.grant_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_wrapped_claims_book_nothing_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The grantee calls claim_vulnerable(2^32) three times.
+    //
+    // 4,294,967,296 as u32 = 0. Each claim checks 0 + 0 <= 1 SOL, books 0,
+    // and pays the full 4.29 SOL.
+    //
+    // Expected: all three SUCCEED. The grantee has 12.88 SOL from a 1 SOL
+    //           grant, and the grant still shows nothing claimed.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "truncating_casts");
+    let mut g = Grants::new();
+    let (grant, grantee, treasury) = (g.grant, g.grantee, g.treasury);
+    let ix = g.claim("claim_vulnerable", WRAP);
+    let grant_data = g.grant_data(0);
+
+    for n in 1..=3 {
+        g.ledger.step(
+            &mollusk,
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&grant).data(&grant_data).build(),
+                Check::account(&grantee).lamports(RENT + n * WRAP).build(),
+                Check::account(&treasury)
+                    .lamports(RENT + TREASURY - n * WRAP)
+                    .build(),
+            ],
+        );
+    }
+}
+
+#[test]
+fn secure_rejects_amount_that_does_not_fit() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same 2^32-lamport claim through claim_secure.
+    //
+    // Expected: FAILS with AmountTooLarge (6000) — try_into refuses a value
+    //           above u32::MAX instead of wrapping it.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "truncating_casts");
+    let mut g = Grants::new();
+    let ix = g.claim("claim_secure", WRAP);
+
+    g.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(AMOUNT_TOO_LARGE))],
+    );
+}
+
+#[test]
+fn secure_enforces_limit() {
+    // -----------------------------------------------------------------------
+    // SECURE: A claim of 1 SOL + 1 lamport through claim_secure. It fits in
+    // u32, so the conversion succeeds and the limit check decides.
+    //
+    // Expected: FAILS with OverLimit (6001).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "truncating_casts");
+    let mut g = Grants::new();
+    let ix = g.claim("claim_secure", LIMIT as u64 + 1);
+
+    g.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(OVER_LIMIT))],
+    );
+}
+
+#[test]
+fn sanity_claim_within_limit() {
+    // -----------------------------------------------------------------------
+    // SANITY: A claim of 0.4 SOL, once through each variant.
+    //
+    // Expected: both SUCCEED identically — 0.4 SOL paid and 0.4 SOL booked.
+    //           Below 2^32 the cast changes nothing.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "truncating_casts");
+
+    for name in ["claim_vulnerable", "claim_secure"] {
+        let mut g = Grants::new();
+        let (grant, grantee) = (g.grant, g.grantee);
+        let ix = g.claim(name, 400_000_000);
+        let grant_data = g.grant_data(400_000_000);
+
+        g.ledger.step(
+            &mollusk,
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&grant).data(&grant_data).build(),
+                Check::account(&grantee)
+                    .lamports(RENT + 400_000_000)
+                    .build(),
+            ],
+        );
+    }
+}
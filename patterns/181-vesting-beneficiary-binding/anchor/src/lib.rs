@@ -0,0 +1,335 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("8tHoS6ezhKYDHGjc7oEuXZRp3tVzwtwG9mGPtEQCkUX7");
+
+/// # Vesting Beneficiary Binding
+///
+/// ## The Vulnerability
+/// A vesting schedule stores WHO the vested tokens belong to (`beneficiary`),
+/// but the claim instruction only checks that the destination token account
+/// is owned by whoever signed. The stored beneficiary is never compared to
+/// the signer, so any key can claim the vested amount into its own account.
+///
+/// ## Why It Matters
+/// Team allocations are usually held by a multisig. When the team rotates
+/// the multisig (a member leaves, keys are replaced), the schedule's
+/// beneficiary is updated to the new multisig — but an ex-member's key can
+/// still sign a claim and route the next unlock to a wallet they control.
+///
+/// ## The Fix
+/// Bind the claim to the schedule with `has_one = beneficiary` and require
+/// the destination token account to be owned by that beneficiary. Changing
+/// the beneficiary is an explicit two-step handover: the team proposes a
+/// new key, and the new key must sign to accept it.
+#[program]
+pub mod vesting_beneficiary {
+    use super::*;
+
+    /// Create a linear vesting schedule. The vault token account must
+    /// already be owned by the schedule PDA; the team funds it separately.
+    pub fn create_schedule(
+        ctx: Context<CreateSchedule>,
+        total_amount: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<()> {
+        require!(end_ts > start_ts, ErrorCode::InvalidSchedule);
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.team = ctx.accounts.team.key();
+        schedule.beneficiary = ctx.accounts.beneficiary.key();
+        schedule.pending_beneficiary = Pubkey::default();
+        schedule.mint = ctx.accounts.mint.key();
+        schedule.total_amount = total_amount;
+        schedule.claimed_amount = 0;
+        schedule.start_ts = start_ts;
+        schedule.end_ts = end_ts;
+        schedule.bump = ctx.bumps.schedule;
+
+        demo_log!(
+            Mode::Setup,
+            "create_schedule",
+            actor = ctx.accounts.team.key(),
+            amount = total_amount,
+            beneficiary = schedule.beneficiary
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Claim pays whoever signs
+    // ============================================================================
+    // ISSUE: The claim reads the vested amount from the schedule, but the
+    //        only identity check is "destination is owned by the signer".
+    //        The schedule's `beneficiary` field is never consulted.
+    //
+    // ATTACK SCENARIO:
+    //   1. The team allocation vests to the team multisig
+    //   2. The team rotates its multisig after a member leaves; the schedule's
+    //      beneficiary now points at the new multisig
+    //   3. The ex-member signs `claim_vulnerable` with their own token account
+    //   4. The program checks destination.owner == signer (true) and pays out
+    //   5. The team's unlock lands in the ex-member's wallet
+    // ============================================================================
+    pub fn claim_vulnerable(ctx: Context<ClaimVulnerable>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let amount = claimable_amount(&ctx.accounts.schedule, now)?;
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.claimed_amount = schedule
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        // VULNERABLE: destination is only bound to `claimer`, and `claimer`
+        // is never compared against `schedule.beneficiary`.
+        transfer_from_vault(
+            &ctx.accounts.schedule,
+            &ctx.accounts.vault,
+            &ctx.accounts.destination,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "claim",
+            actor = ctx.accounts.claimer.key(),
+            amount = amount,
+            beneficiary = ctx.accounts.schedule.beneficiary
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Claim is bound to the stored beneficiary
+    // ============================================================================
+    // FIX: `has_one = beneficiary` ties the signer to the schedule, and the
+    //      destination must be a token account of the schedule's mint owned by
+    //      that same beneficiary. An ex-member's key no longer matches
+    //      anything the schedule trusts.
+    // ============================================================================
+    pub fn claim_secure(ctx: Context<ClaimSecure>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let amount = claimable_amount(&ctx.accounts.schedule, now)?;
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.claimed_amount = schedule
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        transfer_from_vault(
+            &ctx.accounts.schedule,
+            &ctx.accounts.vault,
+            &ctx.accounts.destination,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "claim",
+            actor = ctx.accounts.beneficiary.key(),
+            amount = amount,
+            beneficiary = ctx.accounts.schedule.beneficiary
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Two-step beneficiary reassignment
+    // ============================================================================
+    // Rotating the beneficiary is a deliberate handover, never a side effect:
+    //   1. The team proposes the new beneficiary (stored as pending)
+    //   2. The new beneficiary signs `accept_beneficiary` to take over
+    // Until step 2, the old beneficiary keeps claiming rights, so a typo in
+    // step 1 can't strand the allocation at an unusable key.
+    // ============================================================================
+    pub fn propose_beneficiary(
+        ctx: Context<ProposeBeneficiary>,
+        new_beneficiary: Pubkey,
+    ) -> Result<()> {
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.pending_beneficiary = new_beneficiary;
+
+        demo_log!(
+            Mode::Setup,
+            "propose_beneficiary",
+            actor = ctx.accounts.team.key(),
+            beneficiary = new_beneficiary
+        );
+        Ok(())
+    }
+
+    pub fn accept_beneficiary(ctx: Context<AcceptBeneficiary>) -> Result<()> {
+        let schedule = &mut ctx.accounts.schedule;
+
+        require!(
+            schedule.pending_beneficiary != Pubkey::default(),
+            ErrorCode::NoPendingBeneficiary
+        );
+        require_keys_eq!(
+            schedule.pending_beneficiary,
+            ctx.accounts.new_beneficiary.key(),
+            ErrorCode::NotPendingBeneficiary
+        );
+
+        schedule.beneficiary = schedule.pending_beneficiary;
+        schedule.pending_beneficiary = Pubkey::default();
+
+        demo_log!(
+            Mode::Setup,
+            "accept_beneficiary",
+            actor = ctx.accounts.new_beneficiary.key(),
+            beneficiary = schedule.beneficiary
+        );
+        Ok(())
+    }
+}
+
+/// Linear vesting between `start_ts` and `end_ts`, minus what was already claimed.
+fn claimable_amount(schedule: &VestingSchedule, now: i64) -> Result<u64> {
+    let vested = if now <= schedule.start_ts {
+        0
+    } else if now >= schedule.end_ts {
+        schedule.total_amount
+    } else {
+        let elapsed = (now - schedule.start_ts) as u128;
+        let duration = (schedule.end_ts - schedule.start_ts) as u128;
+        (schedule.total_amount as u128 * elapsed / duration) as u64
+    };
+
+    let amount = vested
+        .checked_sub(schedule.claimed_amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(amount > 0, ErrorCode::NothingToClaim);
+    Ok(amount)
+}
+
+/// Transfer `amount` out of the vault, signing as the schedule PDA.
+fn transfer_from_vault<'info>(
+    schedule: &Account<'info, VestingSchedule>,
+    vault: &Account<'info, TokenAccount>,
+    destination: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[
+        b"schedule",
+        schedule.team.as_ref(),
+        schedule.mint.as_ref(),
+        &[schedule.bump],
+    ];
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: vault.to_account_info(),
+                to: destination.to_account_info(),
+                authority: schedule.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+    )
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateSchedule<'info> {
+    #[account(
+        init,
+        payer = team,
+        space = 8 + VestingSchedule::INIT_SPACE,
+        seeds = [b"schedule", team.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub schedule: Account<'info, VestingSchedule>,
+    #[account(token::mint = mint, token::authority = schedule)]
+    pub vault: Account<'info, TokenAccount>,
+    pub mint: Account<'info, Mint>,
+    /// CHECK: Only stored as the initial beneficiary.
+    pub beneficiary: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub team: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// VULNERABLE: destination is tied to the signer, not to the schedule
+#[derive(Accounts)]
+pub struct ClaimVulnerable<'info> {
+    #[account(mut)]
+    pub schedule: Account<'info, VestingSchedule>,
+    #[account(mut, token::authority = schedule)]
+    pub vault: Account<'info, TokenAccount>,
+    // VULNERABLE: "owned by the signer" is not "owned by the beneficiary"
+    #[account(mut, token::authority = claimer)]
+    pub destination: Account<'info, TokenAccount>,
+    pub claimer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// SECURE: signer and destination are both bound to `schedule.beneficiary`
+#[derive(Accounts)]
+pub struct ClaimSecure<'info> {
+    #[account(mut, has_one = beneficiary)]
+    pub schedule: Account<'info, VestingSchedule>,
+    #[account(mut, token::authority = schedule)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = schedule.mint,
+        token::authority = beneficiary
+    )]
+    pub destination: Account<'info, TokenAccount>,
+    pub beneficiary: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeBeneficiary<'info> {
+    #[account(mut, has_one = team)]
+    pub schedule: Account<'info, VestingSchedule>,
+    pub team: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptBeneficiary<'info> {
+    #[account(mut)]
+    pub schedule: Account<'info, VestingSchedule>,
+    pub new_beneficiary: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VestingSchedule {
+    pub team: Pubkey,                // 32 bytes — administers the grant
+    pub beneficiary: Pubkey,         // 32 bytes — the only key that may claim
+    pub pending_beneficiary: Pubkey, // 32 bytes — default() when no handover pending
+    pub mint: Pubkey,                // 32 bytes
+    pub total_amount: u64,           //  8 bytes
+    pub claimed_amount: u64,         //  8 bytes
+    pub start_ts: i64,               //  8 bytes
+    pub end_ts: i64,                 //  8 bytes
+    pub bump: u8,                    //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Nothing vested to claim")]
+    NothingToClaim,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Schedule end must be after start")]
+    InvalidSchedule,
+    #[msg("No beneficiary handover is pending")]
+    NoPendingBeneficiary,
+    #[msg("Signer is not the pending beneficiary")]
+    NotPendingBeneficiary,
+}
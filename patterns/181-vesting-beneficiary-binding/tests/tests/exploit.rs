@@ -0,0 +1,343 @@
+/// # Pattern 181: Vesting Beneficiary Binding — Mollusk Exploit Tests
+///
+/// A team allocation vests to the team multisig. After a multisig rotation,
+/// an ex-member's key is no longer the beneficiary — but the vulnerable
+/// claim never looks at the beneficiary at all.
+///
+/// - Test 1: EXPLOIT — the ex-member claims the unlock into their own account.
+/// - Test 2: SECURE — the same claim is rejected by `has_one = beneficiary`.
+/// - Test 3: SECURE — the real beneficiary cannot redirect to a foreign account.
+/// - Test 4: SANITY — the real beneficiary claims into their own account.
+/// - Test 5: SECURE — a proposed handover only completes when the new key signs.
+/// - Test 6: SANITY — propose + accept hands claiming rights to the new key.
+///
+/// These tests CPI into SPL Token, so `spl_token.so` must be in SBF_OUT_DIR
+/// (see the pattern README).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator};
+use test_harness::token::{token_account, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8tHoS6ezhKYDHGjc7oEuXZRp3tVzwtwG9mGPtEQCkUX7");
+const TOKEN_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// Fully vested: the clock is past `END_TS`.
+const START_TS: i64 = 1_000;
+const END_TS: i64 = 2_000;
+const NOW: i64 = 5_000;
+const TOTAL: u64 = 1_000_000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a VestingSchedule account:
+///   [8 disc][32 team][32 beneficiary][32 pending][32 mint]
+///   [8 total][8 claimed][8 start_ts][8 end_ts][1 bump]
+fn serialize_schedule(
+    team: &Pubkey,
+    beneficiary: &Pubkey,
+    pending: &Pubkey,
+    mint: &Pubkey,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(169);
+    data.extend_from_slice(&account_discriminator("VestingSchedule"));
+    data.extend_from_slice(team.as_ref());
+    data.extend_from_slice(beneficiary.as_ref());
+    data.extend_from_slice(pending.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&TOTAL.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&START_TS.to_le_bytes());
+    data.extend_from_slice(&END_TS.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "vesting_beneficiary");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk.sysvars.clock.unix_timestamp = NOW;
+    mollusk
+}
+
+/// Keys for a fully-vested team allocation after a multisig rotation.
+struct Scenario {
+    team: Pubkey,
+    mint: Pubkey,
+    schedule: Pubkey,
+    bump: u8,
+    vault: Pubkey,
+    /// The rotated team multisig — the current beneficiary.
+    new_multisig: Pubkey,
+    /// A key that left the team and is NOT the beneficiary.
+    ex_member: Pubkey,
+}
+
+impl Scenario {
+    fn new() -> Self {
+        let team = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (schedule, bump) = Pubkey::find_program_address(
+            &[b"schedule", team.as_ref(), mint.as_ref()],
+            &PROGRAM_ID,
+        );
+        Self {
+            team,
+            mint,
+            schedule,
+            bump,
+            vault: Pubkey::new_unique(),
+            new_multisig: Pubkey::new_unique(),
+            ex_member: Pubkey::new_unique(),
+        }
+    }
+
+    fn schedule_account(&self, beneficiary: &Pubkey, pending: &Pubkey) -> AccountSharedData {
+        let data = serialize_schedule(&self.team, beneficiary, pending, &self.mint, self.bump);
+        let mut account = AccountSharedData::new(5_000_000, data.len(), &PROGRAM_ID);
+        account.set_data_from_slice(&data);
+        account
+    }
+
+    /// Accounts for a claim where `claimer` signs and `destination` is owned by `dest_owner`.
+    fn claim_accounts(
+        &self,
+        claimer: &Pubkey,
+        destination: &Pubkey,
+        dest_owner: &Pubkey,
+    ) -> Vec<(Pubkey, AccountSharedData)> {
+        vec![
+            (self.schedule, self.schedule_account(&self.new_multisig, &Pubkey::default())),
+            (self.vault, token_account(&TOKEN_PROGRAM_ID, &self.mint, &self.schedule, TOTAL)),
+            (*destination, token_account(&TOKEN_PROGRAM_ID, &self.mint, dest_owner, 0)),
+            (*claimer, AccountSharedData::new(1_000_000, 0, &solana_sdk::system_program::ID)),
+            (TOKEN_PROGRAM_ID, executable_account(&solana_sdk::bpf_loader_upgradeable::id())),
+        ]
+    }
+
+    fn claim_ix(&self, name: &str, claimer: &Pubkey, destination: &Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.schedule, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new_readonly(*claimer, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_ex_member_claims_team_allocation() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The schedule's beneficiary is the new team multisig. An
+    // ex-member signs claim_vulnerable with a token account they own.
+    //
+    // The only check is destination.owner == signer, which the ex-member
+    // trivially satisfies. The full vested amount lands in their account.
+    //
+    // Expected: SUCCEEDS — the destination ends up holding TOTAL tokens.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+    let ex_member_ata = Pubkey::new_unique();
+
+    let ix = s.claim_ix("claim_vulnerable", &s.ex_member, &ex_member_ata);
+    let accounts = s.claim_accounts(&s.ex_member, &ex_member_ata, &s.ex_member);
+
+    let stolen = TokenAccount::new(&s.mint, &s.ex_member, TOTAL).pack();
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&ex_member_ata).data(&stolen).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_ex_member_claim() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same ex-member, same destination, secure instruction.
+    //
+    // `has_one = beneficiary` compares schedule.beneficiary (new multisig)
+    // with the signer (ex-member) and fails.
+    //
+    // Expected: FAILS with ConstraintHasOne (2001).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+    let ex_member_ata = Pubkey::new_unique();
+
+    let ix = s.claim_ix("claim_secure", &s.ex_member, &ex_member_ata);
+    let accounts = s.claim_accounts(&s.ex_member, &ex_member_ata, &s.ex_member);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(2001))],
+    );
+}
+
+#[test]
+fn secure_rejects_foreign_destination() {
+    // -----------------------------------------------------------------------
+    // SECURE: The real beneficiary signs, but the destination token account
+    // belongs to someone else (e.g. a compromised frontend swapped it).
+    //
+    // `token::authority = beneficiary` rejects the foreign account.
+    //
+    // Expected: FAILS with ConstraintTokenOwner (2015).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+    let foreign_ata = Pubkey::new_unique();
+
+    let ix = s.claim_ix("claim_secure", &s.new_multisig, &foreign_ata);
+    let accounts = s.claim_accounts(&s.new_multisig, &foreign_ata, &s.ex_member);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(2015))],
+    );
+}
+
+#[test]
+fn secure_allows_beneficiary_claim() {
+    // -----------------------------------------------------------------------
+    // SANITY: The current beneficiary claims into their own token account.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+    let multisig_ata = Pubkey::new_unique();
+
+    let ix = s.claim_ix("claim_secure", &s.new_multisig, &multisig_ata);
+    let accounts = s.claim_accounts(&s.new_multisig, &multisig_ata, &s.new_multisig);
+
+    let claimed = TokenAccount::new(&s.mint, &s.new_multisig, TOTAL).pack();
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&multisig_ata).data(&claimed).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_handover_requires_new_key_signature() {
+    // -----------------------------------------------------------------------
+    // SECURE: The team proposed the new multisig as beneficiary. The
+    // ex-member tries to accept the handover for themselves.
+    //
+    // Expected: FAILS with NotPendingBeneficiary (6004).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+    let old_multisig = Pubkey::new_unique();
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator("accept_beneficiary"),
+        vec![
+            AccountMeta::new(s.schedule, false),
+            AccountMeta::new_readonly(s.ex_member, true),
+        ],
+    );
+    let accounts = vec![
+        (s.schedule, s.schedule_account(&old_multisig, &s.new_multisig)),
+        (s.ex_member, AccountSharedData::new(1_000_000, 0, &solana_sdk::system_program::ID)),
+    ];
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(6004))],
+    );
+}
+
+#[test]
+fn secure_two_step_handover_moves_claim_rights() {
+    // -----------------------------------------------------------------------
+    // SANITY: Team proposes the new multisig, the new multisig accepts, and
+    // the schedule's beneficiary is updated with the pending slot cleared.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+    let old_multisig = Pubkey::new_unique();
+    let system_account = AccountSharedData::new(1_000_000, 0, &solana_sdk::system_program::ID);
+
+    // Step 1: team proposes the new multisig
+    let mut propose_data = ix_discriminator("propose_beneficiary").to_vec();
+    propose_data.extend_from_slice(s.new_multisig.as_ref());
+    let propose_ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &propose_data,
+        vec![
+            AccountMeta::new(s.schedule, false),
+            AccountMeta::new_readonly(s.team, true),
+        ],
+    );
+    let accounts = vec![
+        (s.schedule, s.schedule_account(&old_multisig, &Pubkey::default())),
+        (s.team, system_account.clone()),
+    ];
+    let proposed = mollusk.process_and_validate_instruction(
+        &propose_ix,
+        &accounts,
+        &[Check::success()],
+    );
+    let schedule_after_propose = proposed.resulting_accounts[0].1.clone();
+
+    // Step 2: the new multisig accepts
+    let accept_ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator("accept_beneficiary"),
+        vec![
+            AccountMeta::new(s.schedule, false),
+            AccountMeta::new_readonly(s.new_multisig, true),
+        ],
+    );
+    let accounts = vec![
+        (s.schedule, schedule_after_propose),
+        (s.new_multisig, system_account),
+    ];
+
+    let handed_over = serialize_schedule(
+        &s.team,
+        &s.new_multisig,
+        &Pubkey::default(),
+        &s.mint,
+        s.bump,
+    );
+    mollusk.process_and_validate_instruction(
+        &accept_ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&s.schedule).data(&handed_over).build(),
+        ],
+    );
+}
@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("5Gfze5kSZjUF2dN45uHYAxbhAZGrNThdVf3pAwm9tUjs");
+
+/// # Vesting Boundary Math (Cliff / Linear Off-by-One)
+///
+/// ## The Vulnerability
+/// Cliff + linear vesting has three boundaries: the start, the cliff, and
+/// the end. Each one is either inclusive or exclusive, and each one is a
+/// chance to be off by one. Two classic mistakes:
+///
+/// - **Cliff off-by-one**: `if slot <= cliff { return 0 }` — the beneficiary
+///   gets nothing AT the cliff slot, the exact moment the grant says the
+///   cliff portion unlocks.
+/// - **Truncated rate**: `per_slot = total / duration` is computed once and
+///   multiplied back up. The remainder `total % duration` is lost to
+///   truncation and no later slot ever vests it — it's stranded forever.
+///
+/// ## Why It Matters
+/// Neither bug lets an attacker steal, but both break the grant's contract.
+/// Stranded remainders accumulate across every schedule the program holds,
+/// and off-by-one cliffs are exactly the kind of thing an auditor's
+/// boundary test (cliff-1, cliff, end-1, end, end+1) catches in minutes.
+///
+/// ## The Fix
+/// Define the boundaries once, in one function, and test each edge:
+/// - `slot < cliff_slot` → nothing (the cliff is inclusive)
+/// - `cliff_slot <= slot < end_slot` → `total * (slot - start) / duration`
+/// - `slot >= end_slot` → `total` (the end is inclusive, no remainder)
+#[program]
+pub mod vesting_boundaries {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Exclusive cliff and truncated per-slot rate
+    // ============================================================================
+    // ISSUE 1: `slot <= cliff_slot` returns 0 AT the cliff.
+    // ISSUE 2: `total / duration` truncates; multiplying it back by the
+    //          elapsed slots never reaches `total`, even after the end.
+    //
+    // EXAMPLE (total = 1_000_003, duration = 1_000 slots):
+    //   per_slot         = 1_000 (the 0.003 is truncated away)
+    //   vested at end    = 1_000 * 1_000 = 1_000_000
+    //   vested at end+N  = 1_000_000 (elapsed is capped at duration)
+    //   3 lamports are unclaimable forever.
+    // ============================================================================
+    pub fn claim_vulnerable(ctx: Context<Claim>) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let vested = vested_vulnerable(&ctx.accounts.schedule, slot)?;
+        pay_out(ctx, vested, slot, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Boundaries defined precisely, full amount at the end
+    // ============================================================================
+    // FIX: Multiply before dividing (in u128), make the cliff inclusive, and
+    //      return `total` outright once the end slot is reached so rounding
+    //      can never strand a remainder.
+    // ============================================================================
+    pub fn claim_secure(ctx: Context<Claim>) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let vested = vested_secure(&ctx.accounts.schedule, slot)?;
+        pay_out(ctx, vested, slot, Mode::Secure)
+    }
+}
+
+/// VULNERABLE vesting curve — see the module docs for both bugs.
+fn vested_vulnerable(schedule: &VestingSchedule, slot: u64) -> Result<u64> {
+    // BUG: exclusive cliff — nothing vests AT the cliff slot
+    if slot <= schedule.cliff_slot {
+        return Ok(0);
+    }
+
+    let duration = schedule
+        .end_slot
+        .checked_sub(schedule.start_slot)
+        .ok_or(ErrorCode::InvalidSchedule)?;
+    let elapsed = slot.saturating_sub(schedule.start_slot).min(duration);
+
+    // BUG: the remainder `total % duration` is truncated away here
+    let per_slot = schedule.total_amount / duration;
+    Ok(per_slot * elapsed)
+}
+
+/// SECURE vesting curve.
+///
+/// Boundaries:
+/// - `slot <  cliff_slot` → 0
+/// - `slot >= cliff_slot` → linear amount accrued since `start_slot` (inclusive)
+/// - `slot >= end_slot`   → `total_amount` (inclusive, never truncated)
+fn vested_secure(schedule: &VestingSchedule, slot: u64) -> Result<u64> {
+    if slot < schedule.cliff_slot {
+        return Ok(0);
+    }
+    if slot >= schedule.end_slot {
+        return Ok(schedule.total_amount);
+    }
+
+    let duration = schedule
+        .end_slot
+        .checked_sub(schedule.start_slot)
+        .ok_or(ErrorCode::InvalidSchedule)?;
+    let elapsed = slot
+        .checked_sub(schedule.start_slot)
+        .ok_or(ErrorCode::InvalidSchedule)?;
+
+    // Multiply first, in u128, then divide once.
+    let vested = (schedule.total_amount as u128)
+        .checked_mul(elapsed as u128)
+        .ok_or(ErrorCode::Overflow)?
+        / duration as u128;
+    Ok(vested as u64)
+}
+
+/// Pay the not-yet-claimed part of `vested` from the schedule to the beneficiary.
+fn pay_out(ctx: Context<Claim>, vested: u64, slot: u64, mode: Mode) -> Result<()> {
+    let schedule = &mut ctx.accounts.schedule;
+    let amount = vested
+        .checked_sub(schedule.claimed_amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(amount > 0, ErrorCode::NothingToClaim);
+
+    schedule.claimed_amount = schedule
+        .claimed_amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+
+    **ctx.accounts.schedule.to_account_info().try_borrow_mut_lamports()? -= amount;
+    **ctx.accounts.beneficiary.try_borrow_mut_lamports()? += amount;
+
+    demo_log!(
+        mode,
+        "claim",
+        actor = ctx.accounts.beneficiary.key(),
+        amount = amount,
+        slot = slot
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut, has_one = beneficiary)]
+    pub schedule: Account<'info, VestingSchedule>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+}
+
+/// Lamports escrowed in this account (above rent) are the vesting grant.
+#[account]
+#[derive(InitSpace)]
+pub struct VestingSchedule {
+    pub beneficiary: Pubkey,  // 32 bytes
+    pub total_amount: u64,    //  8 bytes
+    pub claimed_amount: u64,  //  8 bytes
+    pub start_slot: u64,      //  8 bytes
+    pub cliff_slot: u64,      //  8 bytes
+    pub end_slot: u64,        //  8 bytes
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Nothing vested to claim")]
+    NothingToClaim,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Invalid vesting schedule")]
+    InvalidSchedule,
+}
@@ -0,0 +1,207 @@
+/// # Pattern 182: Vesting Boundary Math — Mollusk Exploit Tests
+///
+/// Schedule under test:
+///   start = 100, cliff = 200, end = 1_100 (duration 1_000 slots)
+///   total = 1_000_003 lamports (deliberately NOT divisible by the duration)
+///
+/// Every boundary is probed on a fresh schedule (nothing claimed yet):
+///
+/// | slot    | vulnerable       | secure                 |
+/// |---------|------------------|------------------------|
+/// | cliff-1 | 0                | 0                      |
+/// | cliff   | 0  (BUG)         | 100_000                |
+/// | end-1   | 999_000          | 999_002                |
+/// | end     | 1_000_000 (BUG)  | 1_000_003              |
+/// | end+1   | 1_000_000 (BUG)  | 1_000_003              |
+///
+/// A final pair of tests shows the 3-lamport remainder stranded forever by
+/// the vulnerable curve and released by the secure one.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("5Gfze5kSZjUF2dN45uHYAxbhAZGrNThdVf3pAwm9tUjs");
+
+const START: u64 = 100;
+const CLIFF: u64 = 200;
+const END: u64 = 1_100;
+const TOTAL: u64 = 1_000_003;
+
+/// Rent-exempt minimum for the 80-byte schedule account.
+const SCHEDULE_RENT: u64 = 1_447_680;
+const BENEFICIARY_LAMPORTS: u64 = 1_000_000;
+
+/// NothingToClaim = 6000 (first #[error_code] variant)
+const NOTHING_TO_CLAIM: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a VestingSchedule account:
+///   [8 disc][32 beneficiary][8 total][8 claimed][8 start][8 cliff][8 end]
+fn serialize_schedule(beneficiary: &Pubkey, claimed: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(80);
+    data.extend_from_slice(&account_discriminator("VestingSchedule"));
+    data.extend_from_slice(beneficiary.as_ref());
+    data.extend_from_slice(&TOTAL.to_le_bytes());
+    data.extend_from_slice(&claimed.to_le_bytes());
+    data.extend_from_slice(&START.to_le_bytes());
+    data.extend_from_slice(&CLIFF.to_le_bytes());
+    data.extend_from_slice(&END.to_le_bytes());
+    data
+}
+
+/// Run `name` at `slot` against a schedule with `claimed` already paid out.
+/// `expected` is either the lamports paid to the beneficiary or a custom error.
+fn claim_at(name: &str, slot: u64, claimed: u64, expected: Result<u64, u32>) {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "vesting_boundaries");
+    mollusk.sysvars.clock.slot = slot;
+
+    let beneficiary = Pubkey::new_unique();
+    let schedule = Pubkey::new_unique();
+
+    let data = serialize_schedule(&beneficiary, claimed);
+    let mut schedule_account =
+        AccountSharedData::new(SCHEDULE_RENT + TOTAL - claimed, data.len(), &PROGRAM_ID);
+    schedule_account.set_data_from_slice(&data);
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator(name),
+        vec![
+            AccountMeta::new(schedule, false),
+            AccountMeta::new(beneficiary, true),
+        ],
+    );
+    let accounts = vec![
+        (schedule, schedule_account),
+        (
+            beneficiary,
+            AccountSharedData::new(BENEFICIARY_LAMPORTS, 0, &solana_sdk::system_program::ID),
+        ),
+    ];
+
+    match expected {
+        Ok(paid) => {
+            mollusk.process_and_validate_instruction(
+                &ix,
+                &accounts,
+                &[
+                    Check::success(),
+                    Check::account(&beneficiary)
+                        .lamports(BENEFICIARY_LAMPORTS + paid)
+                        .build(),
+                ],
+            );
+        }
+        Err(code) => {
+            mollusk.process_and_validate_instruction(
+                &ix,
+                &accounts,
+                &[Check::err(ProgramError::Custom(code))],
+            );
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests — vulnerable curve
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_cliff_slot_vests_nothing() {
+    // -----------------------------------------------------------------------
+    // BUG: At exactly the cliff slot the grant says 10% has vested
+    // (100 of 1_000 slots elapsed), but `slot <= cliff` returns 0.
+    //
+    // Expected: FAILS with NothingToClaim — the beneficiary is short-changed.
+    // -----------------------------------------------------------------------
+    claim_at("claim_vulnerable", CLIFF, 0, Err(NOTHING_TO_CLAIM));
+}
+
+#[test]
+fn exploit_end_minus_one_underpays() {
+    // -----------------------------------------------------------------------
+    // BUG: per_slot = 1_000_003 / 1_000 = 1_000 (truncated).
+    // At end-1: 1_000 * 999 = 999_000 instead of 999_002.
+    // -----------------------------------------------------------------------
+    claim_at("claim_vulnerable", END - 1, 0, Ok(999_000));
+}
+
+#[test]
+fn exploit_end_strands_remainder() {
+    // -----------------------------------------------------------------------
+    // BUG: At the end slot the schedule should be fully vested, but
+    // 1_000 * 1_000 = 1_000_000 — 3 lamports short.
+    // -----------------------------------------------------------------------
+    claim_at("claim_vulnerable", END, 0, Ok(1_000_000));
+}
+
+#[test]
+fn exploit_after_end_still_strands_remainder() {
+    // -----------------------------------------------------------------------
+    // BUG: Waiting doesn't help — elapsed is capped at duration, so end+1
+    // pays exactly the same 1_000_000.
+    // -----------------------------------------------------------------------
+    claim_at("claim_vulnerable", END + 1, 0, Ok(1_000_000));
+}
+
+#[test]
+fn exploit_remainder_unclaimable_forever() {
+    // -----------------------------------------------------------------------
+    // BUG: The beneficiary already claimed the 1_000_000 the vulnerable curve
+    // allows. A million slots later, the last 3 lamports are still locked.
+    //
+    // Expected: FAILS with NothingToClaim — the remainder is stranded.
+    // -----------------------------------------------------------------------
+    claim_at("claim_vulnerable", END + 1_000_000, 1_000_000, Err(NOTHING_TO_CLAIM));
+}
+
+// ---------------------------------------------------------------------------
+// Tests — secure curve
+// ---------------------------------------------------------------------------
+
+#[test]
+fn secure_nothing_before_cliff() {
+    // cliff-1: the cliff is inclusive, so one slot before it nothing vests.
+    claim_at("claim_secure", CLIFF - 1, 0, Err(NOTHING_TO_CLAIM));
+}
+
+#[test]
+fn secure_unlocks_at_cliff() {
+    // cliff: 1_000_003 * 100 / 1_000 = 100_000 (floor of 100_000.3)
+    claim_at("claim_secure", CLIFF, 0, Ok(100_000));
+}
+
+#[test]
+fn secure_end_minus_one_is_linear() {
+    // end-1: 1_000_003 * 999 / 1_000 = 999_002 (floor of 999_002.997)
+    claim_at("claim_secure", END - 1, 0, Ok(999_002));
+}
+
+#[test]
+fn secure_fully_vested_at_end() {
+    // end: the end is inclusive — the full total, no truncation.
+    claim_at("claim_secure", END, 0, Ok(TOTAL));
+}
+
+#[test]
+fn secure_after_end_matches_end() {
+    // end+1: same as end.
+    claim_at("claim_secure", END + 1, 0, Ok(TOTAL));
+}
+
+#[test]
+fn secure_releases_remainder() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same position as `exploit_remainder_unclaimable_forever` —
+    // 1_000_000 already claimed. The secure curve pays the last 3 lamports.
+    // -----------------------------------------------------------------------
+    claim_at("claim_secure", END + 1_000_000, 1_000_000, Ok(3));
+}
@@ -0,0 +1,353 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use demo_log::{demo_log, Mode};
+
+declare_id!("DJTk3DokcqhcNhny1i6LuFUb8iMUUaHfiruuyZtyhSee");
+
+/// # CPI Balance Sandwich (Check → Foreign Code → Write)
+///
+/// ## The Vulnerability
+/// A deposit handler checks balances, then makes a CPI that runs code it
+/// doesn't control, then writes state based on what it checked BEFORE the
+/// CPI. Whatever the foreign code did in between — forward less, skim,
+/// forward nothing — is invisible to the state write.
+///
+/// ## Why It Matters
+/// Token-2022 made "foreign code in the middle of my transfer" normal: a
+/// mint's creator chooses its transfer hook, and every integrator's CPI
+/// runs it. A transfer hook can't move the transfer's tokens, though: it
+/// gets them read-only and can't call back into Token-2022. So this pattern
+/// stands in an asset-registered deposit hook the vault invokes directly:
+/// the asset creator picks the hook, the vault trusts it to move `amount`.
+///
+/// The attacker registers an asset whose hook forwards 1 lamport, deposits
+/// 1 SOL, and is credited with 1 SOL — then withdraws other users' funds
+/// against the phantom balance. The token vault falls the same way: the
+/// hook, approved as the depositor's delegate, sends the vault 1 token and
+/// skims the rest to a side account.
+///
+/// ## The Fix
+/// Never credit the amount you *asked for*. Re-read the balance after the
+/// CPI (for token accounts: `reload()`) and credit the delta.
+#[program]
+pub mod balance_sandwich {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Balance checked before the CPI, `amount` credited after
+    // ============================================================================
+    // ISSUE: The handler verifies the depositor CAN pay `amount`, runs the
+    //        asset's hook to move the funds, then credits `amount`. The hook
+    //        decides how much actually moves; the handler never looks.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker registers an asset whose hook forwards only 1 lamport
+    //   2. Attacker deposits 1_000_000 — the pre-CPI balance check passes
+    //   3. The hook moves 1 lamport into the vault
+    //   4. The position is credited 1_000_000
+    //   5. Attacker withdraws 1_000_000 of other depositors' lamports
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        // Balance check BEFORE the CPI
+        require!(
+            ctx.accounts.owner.lamports() >= amount,
+            ErrorCode::InsufficientFunds
+        );
+
+        invoke_hook(ctx.accounts, amount)?;
+
+        // VULNERABLE: state write trusts the pre-CPI view of the world
+        credit(&mut ctx.accounts.vault, &mut ctx.accounts.position, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            credited = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Measure the balance on both sides of the CPI, credit the delta
+    // ============================================================================
+    // FIX: Snapshot the vault balance, run the hook, re-read the balance, and
+    //      credit exactly what arrived. For SPL token vaults the re-read is
+    //      `ctx.accounts.vault_token.reload()?` — Anchor's deserialized copy
+    //      is stale after a CPI until you reload it.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        let before = ctx.accounts.vault.to_account_info().lamports();
+
+        invoke_hook(ctx.accounts, amount)?;
+
+        // SECURE: re-read after the CPI and credit only the delta
+        let after = ctx.accounts.vault.to_account_info().lamports();
+        let received = after.checked_sub(before).ok_or(ErrorCode::Overflow)?;
+        require!(received > 0, ErrorCode::NothingReceived);
+        credit(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.position,
+            received,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            credited = received
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Token balance checked before the CPI, `amount` credited after
+    // ============================================================================
+    // ISSUE: The same sandwich around an SPL token vault. The depositor has
+    //        approved the asset's hook as delegate, and the hook moves the
+    //        tokens in. The handler checks the depositor's balance, runs
+    //        the hook, and credits `amount`.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker registers a mint whose hook forwards 1 token to the
+    //      vault and skims the rest to a side account the attacker owns
+    //   2. Attacker approves the hook for 1_000_000 and deposits 1_000_000
+    //   3. The depositor's account is debited 1_000_000, so a check on
+    //      the sender's side would pass too
+    //   4. The vault's token account gains 1; the position is credited
+    //      1_000_000
+    // ============================================================================
+    pub fn deposit_token_vulnerable<'info>(
+        ctx: Context<'_, '_, '_, 'info, TokenDeposit<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        // Balance check BEFORE the CPI
+        require!(
+            ctx.accounts.owner_token.amount >= amount,
+            ErrorCode::InsufficientFunds
+        );
+
+        invoke_token_hook(ctx.accounts, ctx.remaining_accounts, amount)?;
+
+        // VULNERABLE: credits what was asked for, not what the vault holds
+        credit(&mut ctx.accounts.vault, &mut ctx.accounts.position, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit_token",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            credited = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Reload the vault's token account and credit the delta
+    // ============================================================================
+    // FIX: `vault_token` is deserialized when the instruction starts and
+    //      isn't refreshed by the CPI. Reload it after the hook returns and
+    //      credit exactly what it gained. Reading `amount` without the
+    //      reload would give the pre-CPI value and a delta of zero.
+    // ============================================================================
+    pub fn deposit_token_secure<'info>(
+        ctx: Context<'_, '_, '_, 'info, TokenDeposit<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let before = ctx.accounts.vault_token.amount;
+
+        invoke_token_hook(ctx.accounts, ctx.remaining_accounts, amount)?;
+
+        // SECURE: re-read the token account the CPI changed
+        ctx.accounts.vault_token.reload()?;
+        let received = ctx
+            .accounts
+            .vault_token
+            .amount
+            .checked_sub(before)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(received > 0, ErrorCode::NothingReceived);
+        credit(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.position,
+            received,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "deposit_token",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            credited = received
+        );
+        Ok(())
+    }
+}
+
+/// Add `amount` to the position and to the vault's total.
+fn credit(vault: &mut Vault, position: &mut Position, amount: u64) -> Result<()> {
+    position.deposited = position
+        .deposited
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    vault.total_deposits = vault
+        .total_deposits
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// CPI into the asset's registered deposit hook: `on_deposit(amount)`.
+fn invoke_hook(accounts: &Deposit, amount: u64) -> Result<()> {
+    let mut data = hash(b"global:on_deposit").to_bytes()[..8].to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    let ix = Instruction {
+        program_id: accounts.hook_program.key(),
+        accounts: vec![
+            AccountMeta::new(accounts.owner.key(), true),
+            AccountMeta::new(accounts.vault.key(), false),
+            AccountMeta::new_readonly(accounts.system_program.key(), false),
+        ],
+        data,
+    };
+    invoke(
+        &ix,
+        &[
+            accounts.owner.to_account_info(),
+            accounts.vault.to_account_info(),
+            accounts.system_program.to_account_info(),
+            accounts.hook_program.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// CPI into the mint's registered deposit hook: `on_token_deposit(amount)`
+/// with the depositor's and the vault's token accounts, followed by the
+/// extra accounts the caller supplies for the hook, the way Token-2022
+/// passes a transfer hook its extra accounts.
+fn invoke_token_hook<'info>(
+    accounts: &TokenDeposit<'info>,
+    extra: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    let mut data = hash(b"global:on_token_deposit").to_bytes()[..8].to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    let mut metas = vec![
+        AccountMeta::new(accounts.owner_token.key(), false),
+        AccountMeta::new(accounts.vault_token.key(), false),
+        AccountMeta::new_readonly(accounts.mint.key(), false),
+        AccountMeta::new_readonly(accounts.token_program.key(), false),
+    ];
+    metas.extend(extra.iter().map(|account| AccountMeta {
+        pubkey: account.key(),
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+    let mut infos = vec![
+        accounts.owner_token.to_account_info(),
+        accounts.vault_token.to_account_info(),
+        accounts.mint.to_account_info(),
+        accounts.token_program.to_account_info(),
+        accounts.hook_program.to_account_info(),
+    ];
+    infos.extend_from_slice(extra);
+
+    let ix = Instruction {
+        program_id: accounts.hook_program.key(),
+        accounts: metas,
+        data,
+    };
+    invoke(&ix, &infos)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"vault"], bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", owner.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: The hook registered for this vault's asset. Which program it
+    /// is was the asset creator's choice — what it does is not ours.
+    #[account(address = vault.hook_program)]
+    pub hook_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// A deposit into `mint`'s vault. The vault PDA owns `vault_token`; the
+/// hook's own accounts follow as remaining accounts.
+#[derive(Accounts)]
+pub struct TokenDeposit<'info> {
+    #[account(mut, seeds = [b"vault", mint.key().as_ref()], bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vault,
+        token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"position", mint.key().as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub owner_token: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: The hook registered for this mint. The depositor approved it
+    /// as delegate so it can move the deposit in.
+    #[account(address = vault.hook_program)]
+    pub hook_program: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Holds depositor lamports above rent, or for a token vault, owns the
+/// vault's token account; `total_deposits` is the ledger.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub hook_program: Pubkey, // 32 bytes
+    pub total_deposits: u64,  //  8 bytes
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,   // 32 bytes
+    pub deposited: u64,  //  8 bytes
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Insufficient funds")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Hook moved no funds into the vault")]
+    NothingReceived,
+}
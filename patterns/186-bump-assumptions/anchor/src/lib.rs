@@ -0,0 +1,178 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("CsmCXyF949jywDGKMRXQ1QvKnDe7rvT49aEAdTCqn2ae");
+
+/// # Bump Assumptions
+///
+/// ## The Vulnerability
+/// `find_program_address` searches bumps from 255 downward and returns the
+/// first one whose address is off the ed25519 curve. Each bump has roughly
+/// a 50% chance of landing on the curve, so the canonical bump is 255 for
+/// about half of all seed sets, 254 for a quarter, 253 for an eighth...
+///
+/// Code that hard-codes `255` and calls `create_program_address(...).unwrap()`
+/// works for every seed set the developer happened to test — and panics for
+/// the rest. Those users' funds are stuck behind an instruction that can
+/// never succeed for them.
+///
+/// ## Why It Matters
+/// It is a DoS that targets specific users by nothing more than their
+/// pubkey. It survives testing (one lucky test wallet is enough) and shows
+/// up in production as "withdraw fails for some people, always".
+///
+/// ## The Fix
+/// Derive once with `find_program_address` at init, store the canonical
+/// bump, and re-derive with the stored bump (`bump = profile.bump`).
+/// When deriving by hand, treat `create_program_address` as fallible.
+#[program]
+pub mod bump_assumptions {
+    use super::*;
+
+    /// Create the user's profile PDA at its canonical bump.
+    pub fn init_profile(ctx: Context<InitProfile>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.owner = ctx.accounts.owner.key();
+        profile.bump = ctx.bumps.profile;
+        demo_log!(
+            Mode::Setup,
+            "init_profile",
+            actor = profile.owner,
+            bump = profile.bump
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Assumes every profile lives at bump 255
+    // ============================================================================
+    // ISSUE: The handler re-derives the profile address with a hard-coded
+    //        bump of 255 and unwraps the result. For a user whose canonical
+    //        bump is lower, [b"profile", owner, 255] is ON the curve, so
+    //        `create_program_address` returns Err and the unwrap panics.
+    //
+    // ATTACK SCENARIO (no attacker needed — the bug is the attacker):
+    //   1. A user's seeds happen to have canonical bump 252
+    //   2. init_profile works — Anchor found the real bump
+    //   3. The user deposits lamports into their profile
+    //   4. Every withdraw_vulnerable call panics; the lamports are stuck
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<WithdrawVulnerable>, amount: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+
+        // VULNERABLE: "the bump is always 255" + unwrap on a fallible derivation
+        let expected =
+            Pubkey::create_program_address(&[b"profile", owner.as_ref(), &[255]], &crate::ID)
+                .unwrap();
+        require_keys_eq!(
+            ctx.accounts.profile.key(),
+            expected,
+            ErrorCode::InvalidProfile
+        );
+
+        pay_out(
+            &ctx.accounts.profile,
+            &ctx.accounts.owner,
+            amount,
+            Mode::Vulnerable,
+        )
+    }
+
+    // ============================================================================
+    // SECURE: Re-derive with the stored canonical bump
+    // ============================================================================
+    // FIX: The bump recorded at init is the one that worked. Anchor's
+    //      `seeds` + `bump = profile.bump` constraint re-derives with it and
+    //      returns ConstraintSeeds on mismatch instead of panicking.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<WithdrawSecure>, amount: u64) -> Result<()> {
+        pay_out(
+            &ctx.accounts.profile,
+            &ctx.accounts.owner,
+            amount,
+            Mode::Secure,
+        )
+    }
+}
+
+/// Move `amount` lamports out of the profile to its owner.
+fn pay_out<'info>(
+    profile: &Account<'info, Profile>,
+    owner: &Signer<'info>,
+    amount: u64,
+    mode: Mode,
+) -> Result<()> {
+    let profile_info = profile.to_account_info();
+    let rent_floor = Rent::get()?.minimum_balance(profile_info.data_len());
+    let available = profile_info.lamports().saturating_sub(rent_floor);
+    require!(amount <= available, ErrorCode::InsufficientFunds);
+
+    **profile_info.try_borrow_mut_lamports()? -= amount;
+    **owner.try_borrow_mut_lamports()? += amount;
+
+    demo_log!(
+        mode,
+        "withdraw",
+        actor = owner.key(),
+        amount = amount,
+        bump = profile.bump
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitProfile<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Profile::INIT_SPACE,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVulnerable<'info> {
+    // Address checked by hand in the handler
+    #[account(mut, has_one = owner)]
+    pub profile: Account<'info, Profile>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSecure<'info> {
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump = profile.bump,
+        has_one = owner
+    )]
+    pub profile: Account<'info, Profile>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Lamports held above rent belong to `owner`.
+#[account]
+#[derive(InitSpace)]
+pub struct Profile {
+    pub owner: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte — canonical, found once at init
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Profile address does not match the derived PDA")]
+    InvalidProfile,
+    #[msg("Insufficient funds")]
+    InsufficientFunds,
+}
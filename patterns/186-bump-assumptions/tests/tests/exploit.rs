@@ -0,0 +1,198 @@
+/// # Pattern 186: Bump Assumptions — Mollusk Exploit Tests
+///
+/// Users are picked by searching deterministic keys for a specific canonical
+/// bump of `[b"profile", owner]` — the way a real user base contains every
+/// bump whether or not the developer's test wallet did.
+///
+/// - Test 1: EXPLOIT — a user whose canonical bump is <= 252 can never
+///   withdraw through the hard-coded-255 path; the program panics.
+/// - Test 2: SECURE — the same user withdraws using the stored bump.
+/// - Test 3: SANITY — a bump-255 user passes the vulnerable path, which is
+///   exactly why the bug survives testing.
+/// - Test 4: SECURE — a profile stored under the wrong bump is rejected with
+///   ConstraintSeeds rather than a panic.
+use mollusk_svm::{result::Check, Mollusk};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("CsmCXyF949jywDGKMRXQ1QvKnDe7rvT49aEAdTCqn2ae");
+
+const PROFILE_LEN: usize = 8 + 32 + 1;
+const DEPOSIT: u64 = 5_000_000;
+const WITHDRAW: u64 = 1_000_000;
+const OWNER_LAMPORTS: u64 = 1_000_000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Profile account: [8 disc][32 owner][1 bump]
+fn serialize_profile(owner: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(PROFILE_LEN);
+    data.extend_from_slice(&account_discriminator("Profile"));
+    data.extend_from_slice(owner.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Grind deterministic user keys until one's profile PDA has a canonical
+/// bump satisfying `wanted`. Returns (owner, profile, canonical bump).
+fn user_with_bump(wanted: impl Fn(u8) -> bool) -> (Pubkey, Pubkey, u8) {
+    (0u32..)
+        .map(|i| {
+            let hash: [u8; 32] = Sha256::digest(format!("user-{i}").as_bytes()).into();
+            Pubkey::new_from_array(hash)
+        })
+        .find_map(|owner| {
+            let (profile, bump) =
+                Pubkey::find_program_address(&[b"profile", owner.as_ref()], &PROGRAM_ID);
+            wanted(bump).then_some((owner, profile, bump))
+        })
+        .unwrap()
+}
+
+/// Build a withdraw of WITHDRAW lamports from `owner`'s profile.
+/// Returns (instruction, accounts, profile_lamports).
+fn setup_withdraw(
+    mollusk: &Mollusk,
+    name: &str,
+    owner: Pubkey,
+    profile: Pubkey,
+    stored_bump: u8,
+) -> (Instruction, Vec<(Pubkey, AccountSharedData)>, u64) {
+    let profile_lamports = mollusk.sysvars.rent.minimum_balance(PROFILE_LEN) + DEPOSIT;
+    let profile_data = serialize_profile(&owner, stored_bump);
+    let mut profile_account =
+        AccountSharedData::new(profile_lamports, profile_data.len(), &PROGRAM_ID);
+    profile_account.set_data_from_slice(&profile_data);
+
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&WITHDRAW.to_le_bytes());
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(profile, false),
+            AccountMeta::new(owner, true),
+        ],
+    );
+
+    let accounts = vec![
+        (profile, profile_account),
+        (
+            owner,
+            AccountSharedData::new(OWNER_LAMPORTS, 0, &solana_sdk::system_program::ID),
+        ),
+    ];
+
+    (ix, accounts, profile_lamports)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_low_bump_user_locked_out() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: A user whose canonical bump is 252 or lower (about 1 in 8
+    // users) tries to withdraw their own lamports.
+    //
+    // [b"profile", owner, 255] is on the curve for them, so
+    // create_program_address returns Err and the handler's unwrap panics.
+    //
+    // Expected: FAILS with ProgramFailedToComplete — every time, for this
+    //           user, forever.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "bump_assumptions");
+    let (owner, profile, bump) = user_with_bump(|bump| bump <= 252);
+    let (ix, accounts, _) = setup_withdraw(&mollusk, "withdraw_vulnerable", owner, profile, bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::instruction_err(
+            InstructionError::ProgramFailedToComplete,
+        )],
+    );
+}
+
+#[test]
+fn secure_low_bump_user_withdraws() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same user. The secure path re-derives with the bump stored at
+    // init, which is the canonical one.
+    //
+    // Expected: SUCCEEDS — 1_000_000 lamports moved to the owner.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "bump_assumptions");
+    let (owner, profile, bump) = user_with_bump(|bump| bump <= 252);
+    let (ix, accounts, profile_lamports) =
+        setup_withdraw(&mollusk, "withdraw_secure", owner, profile, bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&profile)
+                .lamports(profile_lamports - WITHDRAW)
+                .build(),
+            Check::account(&owner)
+                .lamports(OWNER_LAMPORTS + WITHDRAW)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_bump_255_user_passes_vulnerable() {
+    // -----------------------------------------------------------------------
+    // SANITY: For a user whose canonical bump IS 255, the hard-coded path
+    // derives the right address. This is the wallet the developer tested
+    // with.
+    //
+    // Expected: SUCCEEDS — and hides the bug.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "bump_assumptions");
+    let (owner, profile, bump) = user_with_bump(|bump| bump == 255);
+    let (ix, accounts, _) = setup_withdraw(&mollusk, "withdraw_vulnerable", owner, profile, bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&owner)
+                .lamports(OWNER_LAMPORTS + WITHDRAW)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_wrong_stored_bump() {
+    // -----------------------------------------------------------------------
+    // SECURE: A profile whose stored bump doesn't derive its address (here:
+    // 255 stored for a low-bump user) is rejected by the seeds constraint —
+    // a clean error, not a panic.
+    //
+    // Expected: FAILS with ConstraintSeeds (2006).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "bump_assumptions");
+    let (owner, profile, _) = user_with_bump(|bump| bump <= 252);
+    let (ix, accounts, _) = setup_withdraw(&mollusk, "withdraw_secure", owner, profile, 255);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(2006))],
+    );
+}
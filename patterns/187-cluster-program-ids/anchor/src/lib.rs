@@ -0,0 +1,154 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey;
+use demo_log::{demo_log, Mode};
+
+declare_id!("CYZyDQ7F5JsQtLahcFLpk2T8PzXUJwTmFCiXn6jgdsJu");
+
+/// The price program this build is allowed to depend on, fixed at compile
+/// time per cluster. Build with `--features devnet` for the devnet id.
+#[cfg(not(feature = "devnet"))]
+pub const PRICE_PROGRAM_ID: Pubkey = pubkey!("FjpGVfbu1FwMmLi4GnQsBxBbtgbDDYT3aPaDtPcP7UTk");
+#[cfg(feature = "devnet")]
+pub const PRICE_PROGRAM_ID: Pubkey = pubkey!("6FE1nqFZPQJijcQBNYr3ZwQWAG9zetEmL75qQJMqtZEE");
+
+/// # Cluster Program IDs
+///
+/// ## The Vulnerability
+/// The protocol depends on an external price program. Its id is stored in a
+/// config account at init — taken from an instruction argument, never
+/// checked. Whatever the initializer typed is what every later instruction
+/// trusts, including the `owner` check on price accounts.
+///
+/// ## Why It Matters
+/// Deploy scripts are shared between clusters. One wrong environment
+/// variable and the mainnet config points at the devnet deployment — or at
+/// any program with the same account layout. Whoever can write price
+/// accounts under that program — on devnet, often anyone with a test-mode
+/// publisher — now sets mainnet prices. A malicious initializer gets the
+/// same result on purpose.
+///
+/// ## The Fix
+/// Pin the dependency at compile time, one constant per cluster behind a
+/// feature flag, and reject any other id at init. The wrong-cluster id then
+/// fails loudly on day one instead of quietly in production.
+#[program]
+pub mod cluster_ids {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Dependency id taken from the caller, unvalidated
+    // ============================================================================
+    // ISSUE: `price_program` is an argument. A devnet id, a look-alike
+    //        program, or an attacker's own program is stored just the same.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mainnet deploy script runs with the devnet PRICE_PROGRAM env var
+    //   2. Config stores the devnet price program id
+    //   3. Attacker publishes a price under the devnet program (test-mode feed)
+    //   4. `update_price` accepts it: its owner matches the stored id
+    //   5. Mainnet positions are valued at a price the attacker wrote
+    // ============================================================================
+    pub fn init_config_vulnerable(ctx: Context<InitConfig>, price_program: Pubkey) -> Result<()> {
+        // VULNERABLE: stored as given
+        write_config(ctx, price_program, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Dependency id must match this build's cluster constant
+    // ============================================================================
+    // FIX: `PRICE_PROGRAM_ID` is chosen at compile time by the `devnet`
+    //      feature. The argument only confirms what the operator thinks
+    //      they're deploying against; a mismatch aborts the init.
+    // ============================================================================
+    pub fn init_config_secure(ctx: Context<InitConfig>, price_program: Pubkey) -> Result<()> {
+        // SECURE: only this cluster's price program is accepted
+        require_keys_eq!(
+            price_program,
+            PRICE_PROGRAM_ID,
+            ErrorCode::WrongClusterDependency
+        );
+        write_config(ctx, price_program, Mode::Secure)
+    }
+
+    /// Read the latest price from an account owned by the configured price
+    /// program. Only as trustworthy as `config.price_program`.
+    pub fn update_price(ctx: Context<UpdatePrice>) -> Result<()> {
+        let data = ctx.accounts.price_account.try_borrow_data()?;
+        let price = data
+            .get(..8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ErrorCode::InvalidPriceAccount)?;
+        drop(data);
+
+        ctx.accounts.config.last_price = price;
+        demo_log!(
+            Mode::Setup,
+            "update_price",
+            price = price,
+            price_program = ctx.accounts.config.price_program
+        );
+        Ok(())
+    }
+}
+
+fn write_config(ctx: Context<InitConfig>, price_program: Pubkey, mode: Mode) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin = ctx.accounts.admin.key();
+    config.price_program = price_program;
+    config.last_price = 0;
+    config.bump = ctx.bumps.config;
+    demo_log!(
+        mode,
+        "init_config",
+        actor = config.admin,
+        price_program = price_program
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePrice<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    /// CHECK: Layout is [price: u64 LE, ...]; trusted because its owner is
+    /// the configured price program.
+    #[account(owner = config.price_program)]
+    pub price_account: UncheckedAccount<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,         // 32 bytes
+    pub price_program: Pubkey, // 32 bytes
+    pub last_price: u64,       //  8 bytes
+    pub bump: u8,              //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Dependency program id does not match this cluster's build")]
+    WrongClusterDependency,
+    #[msg("Price account data is malformed")]
+    InvalidPriceAccount,
+}
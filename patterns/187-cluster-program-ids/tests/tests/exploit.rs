@@ -0,0 +1,248 @@
+/// # Pattern 187: Cluster Program IDs — Mollusk Exploit Tests
+///
+/// The program is built for one cluster (mainnet by default, devnet with
+/// `--features devnet`). These tests follow the same feature, so each build
+/// is checked against its own pinned id and rejects the other cluster's.
+///
+/// - Test 1: EXPLOIT — the vulnerable init stores the other cluster's price
+///   program id.
+/// - Test 2: EXPLOIT — with that config, a price account written under the
+///   other cluster's program is accepted.
+/// - Test 3: SECURE — the secure init rejects the other cluster's id.
+/// - Test 4: SANITY — the secure init accepts this cluster's id.
+/// - Test 5: SECURE — a correctly pinned config rejects foreign price accounts.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("CYZyDQ7F5JsQtLahcFLpk2T8PzXUJwTmFCiXn6jgdsJu");
+
+const MAINNET_PRICE_PROGRAM: Pubkey =
+    solana_sdk::pubkey!("FjpGVfbu1FwMmLi4GnQsBxBbtgbDDYT3aPaDtPcP7UTk");
+const DEVNET_PRICE_PROGRAM: Pubkey =
+    solana_sdk::pubkey!("6FE1nqFZPQJijcQBNYr3ZwQWAG9zetEmL75qQJMqtZEE");
+
+/// The price program the program under test was built to trust...
+#[cfg(not(feature = "devnet"))]
+const THIS_CLUSTER: Pubkey = MAINNET_PRICE_PROGRAM;
+#[cfg(feature = "devnet")]
+const THIS_CLUSTER: Pubkey = DEVNET_PRICE_PROGRAM;
+
+/// ...and the one a swapped deploy config would hand it.
+#[cfg(not(feature = "devnet"))]
+const OTHER_CLUSTER: Pubkey = DEVNET_PRICE_PROGRAM;
+#[cfg(feature = "devnet")]
+const OTHER_CLUSTER: Pubkey = MAINNET_PRICE_PROGRAM;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Config account: [8 disc][32 admin][32 price_program][8 last_price][1 bump]
+fn serialize_config(admin: &Pubkey, price_program: &Pubkey, last_price: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(price_program.as_ref());
+    data.extend_from_slice(&last_price.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Build an init that passes `price_program` as the dependency id.
+/// Returns (instruction, accounts, config, admin).
+fn setup_init(
+    name: &str,
+    price_program: Pubkey,
+) -> (
+    Instruction,
+    Vec<(Pubkey, AccountSharedData)>,
+    Pubkey,
+    Pubkey,
+) {
+    let admin = Pubkey::new_unique();
+    let (config, _) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(price_program.as_ref());
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new(admin, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    );
+
+    let accounts = vec![
+        (config, AccountSharedData::default()),
+        (
+            admin,
+            AccountSharedData::new(1_000_000_000, 0, &solana_sdk::system_program::ID),
+        ),
+        (
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ),
+    ];
+
+    (ix, accounts, config, admin)
+}
+
+/// Build an update_price against a config that trusts `configured`, reading a
+/// price account owned by `price_owner` that says `price`.
+/// Returns (instruction, accounts, config, admin).
+fn setup_update(
+    configured: Pubkey,
+    price_owner: Pubkey,
+    price: u64,
+) -> (
+    Instruction,
+    Vec<(Pubkey, AccountSharedData)>,
+    Pubkey,
+    Pubkey,
+) {
+    let admin = Pubkey::new_unique();
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+
+    let config_data = serialize_config(&admin, &configured, 0, bump);
+    let mut config_account = AccountSharedData::new(1_000_000, config_data.len(), &PROGRAM_ID);
+    config_account.set_data_from_slice(&config_data);
+
+    let price_key = Pubkey::new_unique();
+    let mut price_account = AccountSharedData::new(1_000_000, 8, &price_owner);
+    price_account.set_data_from_slice(&price.to_le_bytes());
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator("update_price"),
+        vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(price_key, false),
+        ],
+    );
+
+    let accounts = vec![(config, config_account), (price_key, price_account)];
+
+    (ix, accounts, config, admin)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_wrong_cluster_id_stored() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The deploy script hands the vulnerable init the OTHER
+    // cluster's price program id.
+    //
+    // Expected: SUCCEEDS — the config now depends on the wrong program.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "cluster_ids");
+    let (ix, accounts, config, admin) = setup_init("init_config_vulnerable", OTHER_CLUSTER);
+
+    let (_, bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+    let swapped = serialize_config(&admin, &OTHER_CLUSTER, 0, bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&config).data(&swapped).build(),
+        ],
+    );
+}
+
+#[test]
+fn exploit_foreign_price_accepted() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: With the swapped config, an attacker writes a price of 1
+    // into an account owned by the other cluster's program (a devnet
+    // feed's test-mode publisher). The owner check compares against the
+    // stored id — and passes.
+    //
+    // Expected: SUCCEEDS — last_price is now the attacker's 1.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "cluster_ids");
+    let (ix, accounts, config, admin) = setup_update(OTHER_CLUSTER, OTHER_CLUSTER, 1);
+
+    let (_, bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+    let poisoned = serialize_config(&admin, &OTHER_CLUSTER, 1, bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&config).data(&poisoned).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_wrong_cluster_id() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same swapped id, secure init. The build's PRICE_PROGRAM_ID
+    // constant disagrees.
+    //
+    // Expected: FAILS with WrongClusterDependency (6000).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "cluster_ids");
+    let (ix, accounts, _, _) = setup_init("init_config_secure", OTHER_CLUSTER);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(6000))],
+    );
+}
+
+#[test]
+fn sanity_secure_accepts_cluster_id() {
+    // -----------------------------------------------------------------------
+    // SANITY: The id this build was compiled for is accepted.
+    //
+    // Expected: SUCCEEDS — config stores THIS_CLUSTER.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "cluster_ids");
+    let (ix, accounts, config, admin) = setup_init("init_config_secure", THIS_CLUSTER);
+
+    let (_, bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+    let pinned = serialize_config(&admin, &THIS_CLUSTER, 0, bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&config).data(&pinned).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_pinned_config_rejects_foreign_price() {
+    // -----------------------------------------------------------------------
+    // SECURE: A config pinned to THIS_CLUSTER. The attacker's price account
+    // is owned by the other cluster's program.
+    //
+    // Expected: FAILS with ConstraintOwner (2004).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "cluster_ids");
+    let (ix, accounts, _, _) = setup_update(THIS_CLUSTER, OTHER_CLUSTER, 1);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(2004))],
+    );
+}
@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("9qUVYrUmGCxz8oHaoZRXD7KiAhNGPY3wX9oJhC9e12Hf");
+
+/// Wallets holding at least this many lamports count as "premium" in the
+/// vulnerable gate (100 SOL).
+pub const PREMIUM_BALANCE: u64 = 100_000_000_000;
+
+/// # Lamport-Gated Access (Pay-to-Bypass)
+///
+/// ## The Vulnerability
+/// A premium feature is gated on `caller.lamports() >= PREMIUM_BALANCE`.
+/// A balance is not an entitlement: it says what the wallet holds at this
+/// instant, not what it has paid or committed. Inside one transaction an
+/// attacker can borrow SOL, pass the check, and repay — the wallet was
+/// "rich" for exactly as long as the gate looked.
+///
+/// ## Why It Matters
+/// Flash loans make any balance threshold free. Holder-only tiers, anti-sybil
+/// "must hold X" rules, and fee waivers all fall the same way — and so does
+/// the SPL token version (`token_account.amount >= X`).
+///
+/// ## The Fix
+/// Gate on state the program itself created when the user actually paid: an
+/// entitlement PDA written by an explicit purchase instruction that moves
+/// the payment to the treasury and records it.
+#[program]
+pub mod lamport_gate {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Premium access decided by wallet balance
+    // ============================================================================
+    // ISSUE: The gate reads `caller.lamports()`. Nothing is paid, nothing
+    //        is locked, and the balance can be borrowed for one transaction.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker's wallet holds 0.01 SOL
+    //   2. Instruction 1: flash-borrow 100 SOL into the wallet
+    //   3. Instruction 2: premium_action_vulnerable — balance check passes
+    //   4. Instruction 3: repay the flash loan
+    //   5. Premium feature used; attacker paid only the loan fee
+    // ============================================================================
+    pub fn premium_action_vulnerable(ctx: Context<PremiumVulnerable>) -> Result<()> {
+        // VULNERABLE: a balance is a snapshot, not a purchase
+        require!(
+            ctx.accounts.caller.lamports() >= PREMIUM_BALANCE,
+            ErrorCode::NotPremium
+        );
+
+        record_premium_call(&mut ctx.accounts.service, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Premium access requires an entitlement PDA
+    // ============================================================================
+    // FIX: The entitlement exists only if `purchase_entitlement` ran, which
+    //      moved the price to the treasury. Borrowed lamports can't create
+    //      it without being spent.
+    // ============================================================================
+    pub fn premium_action_secure(ctx: Context<PremiumSecure>) -> Result<()> {
+        // SECURE: the caller's Entitlement PDA is the gate — it must exist
+        record_premium_call(&mut ctx.accounts.service, Mode::Secure)
+    }
+
+    /// Pay the service price to the treasury and record the entitlement.
+    pub fn purchase_entitlement(ctx: Context<PurchaseEntitlement>) -> Result<()> {
+        let price = ctx.accounts.service.price;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            price,
+        )?;
+
+        let entitlement = &mut ctx.accounts.entitlement;
+        entitlement.user = ctx.accounts.user.key();
+        entitlement.paid = price;
+        entitlement.purchased_slot = Clock::get()?.slot;
+        entitlement.bump = ctx.bumps.entitlement;
+
+        demo_log!(
+            Mode::Setup,
+            "purchase_entitlement",
+            actor = entitlement.user,
+            amount = price
+        );
+        Ok(())
+    }
+}
+
+fn record_premium_call(service: &mut Account<Service>, mode: Mode) -> Result<()> {
+    service.premium_calls = service
+        .premium_calls
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    demo_log!(mode, "premium_call", calls = service.premium_calls);
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct PremiumVulnerable<'info> {
+    #[account(mut, seeds = [b"service"], bump = service.bump)]
+    pub service: Account<'info, Service>,
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PremiumSecure<'info> {
+    #[account(mut, seeds = [b"service"], bump = service.bump)]
+    pub service: Account<'info, Service>,
+    #[account(
+        seeds = [b"entitlement", caller.key().as_ref()],
+        bump = entitlement.bump,
+        constraint = entitlement.user == caller.key() @ ErrorCode::NotPremium
+    )]
+    pub entitlement: Account<'info, Entitlement>,
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseEntitlement<'info> {
+    #[account(seeds = [b"service"], bump = service.bump, has_one = treasury)]
+    pub service: Account<'info, Service>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Entitlement::INIT_SPACE,
+        seeds = [b"entitlement", user.key().as_ref()],
+        bump
+    )]
+    pub entitlement: Account<'info, Entitlement>,
+    /// CHECK: Receives the payment; must be the service's treasury.
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Service {
+    pub treasury: Pubkey,   // 32 bytes
+    pub price: u64,         //  8 bytes
+    pub premium_calls: u64, //  8 bytes
+    pub bump: u8,           //  1 byte
+}
+
+/// Proof of payment. Only `purchase_entitlement` creates one.
+#[account]
+#[derive(InitSpace)]
+pub struct Entitlement {
+    pub user: Pubkey,        // 32 bytes
+    pub paid: u64,           //  8 bytes
+    pub purchased_slot: u64, //  8 bytes
+    pub bump: u8,            //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Caller is not entitled to premium access")]
+    NotPremium,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,241 @@
+/// # Pattern 188: Lamport-Gated Access — Mollusk Exploit Tests
+///
+/// Mollusk runs one instruction at a time, so the flash loan is represented
+/// by its effect: the attacker's wallet holds 100 SOL at the moment the
+/// premium instruction runs, and nothing has been paid.
+///
+/// - Test 1: EXPLOIT — a flash-funded wallet passes the balance gate.
+/// - Test 2: SECURE — the same wallet without an entitlement is rejected.
+/// - Test 3: SANITY — purchase_entitlement moves the price to the treasury
+///   and records the payment.
+/// - Test 4: SANITY — an entitled user passes the secure gate with a small
+///   balance.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("9qUVYrUmGCxz8oHaoZRXD7KiAhNGPY3wX9oJhC9e12Hf");
+
+const PREMIUM_BALANCE: u64 = 100_000_000_000;
+const PRICE: u64 = 500_000_000;
+const SMALL_BALANCE: u64 = 10_000_000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Service account: [8 disc][32 treasury][8 price][8 premium_calls][1 bump]
+fn serialize_service(treasury: &Pubkey, premium_calls: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(57);
+    data.extend_from_slice(&account_discriminator("Service"));
+    data.extend_from_slice(treasury.as_ref());
+    data.extend_from_slice(&PRICE.to_le_bytes());
+    data.extend_from_slice(&premium_calls.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an Entitlement account: [8 disc][32 user][8 paid][8 purchased_slot][1 bump]
+fn serialize_entitlement(user: &Pubkey, paid: u64, purchased_slot: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(57);
+    data.extend_from_slice(&account_discriminator("Entitlement"));
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(&paid.to_le_bytes());
+    data.extend_from_slice(&purchased_slot.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The service PDA with zero premium calls so far. Returns (key, account, treasury).
+fn service_account() -> (Pubkey, AccountSharedData, Pubkey) {
+    let treasury = Pubkey::new_unique();
+    let (service, bump) = Pubkey::find_program_address(&[b"service"], &PROGRAM_ID);
+    let data = serialize_service(&treasury, 0, bump);
+    let mut account = AccountSharedData::new(1_000_000, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (service, account, treasury)
+}
+
+fn wallet(lamports: u64) -> AccountSharedData {
+    AccountSharedData::new(lamports, 0, &solana_sdk::system_program::ID)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_flash_funded_wallet_passes() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker's wallet was flash-funded to 100 SOL by the
+    // previous instruction in the transaction. It has paid nothing.
+    //
+    // Expected: SUCCEEDS — premium call recorded; the loan is repaid in
+    //           the next instruction.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "lamport_gate");
+    let (service, service_account, treasury) = service_account();
+    let attacker = Pubkey::new_unique();
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator("premium_action_vulnerable"),
+        vec![
+            AccountMeta::new(service, false),
+            AccountMeta::new_readonly(attacker, true),
+        ],
+    );
+
+    let (_, bump) = Pubkey::find_program_address(&[b"service"], &PROGRAM_ID);
+    let after = serialize_service(&treasury, 1, bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &[
+            (service, service_account),
+            (attacker, wallet(PREMIUM_BALANCE)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&service).data(&after).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_without_entitlement() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same flash-funded wallet. It never purchased, so its
+    // entitlement PDA doesn't exist.
+    //
+    // Expected: FAILS with AccountNotInitialized (3012).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "lamport_gate");
+    let (service, service_account, _) = service_account();
+    let attacker = Pubkey::new_unique();
+    let (entitlement, _) =
+        Pubkey::find_program_address(&[b"entitlement", attacker.as_ref()], &PROGRAM_ID);
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator("premium_action_secure"),
+        vec![
+            AccountMeta::new(service, false),
+            AccountMeta::new_readonly(entitlement, false),
+            AccountMeta::new_readonly(attacker, true),
+        ],
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &[
+            (service, service_account),
+            (entitlement, AccountSharedData::default()),
+            (attacker, wallet(PREMIUM_BALANCE)),
+        ],
+        &[Check::err(ProgramError::Custom(3012))],
+    );
+}
+
+#[test]
+fn sanity_purchase_records_payment() {
+    // -----------------------------------------------------------------------
+    // SANITY: A user buys the entitlement. The price moves to the treasury
+    // and the Entitlement PDA records it.
+    //
+    // Expected: SUCCEEDS — treasury +0.5 SOL, entitlement.paid == price.
+    // -----------------------------------------------------------------------
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "lamport_gate");
+    mollusk.warp_to_slot(42);
+    let (service, service_account, treasury) = service_account();
+    let user = Pubkey::new_unique();
+    let (entitlement, bump) =
+        Pubkey::find_program_address(&[b"entitlement", user.as_ref()], &PROGRAM_ID);
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator("purchase_entitlement"),
+        vec![
+            AccountMeta::new_readonly(service, false),
+            AccountMeta::new(entitlement, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(user, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    );
+
+    let recorded = serialize_entitlement(&user, PRICE, 42, bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &[
+            (service, service_account),
+            (entitlement, AccountSharedData::default()),
+            (treasury, wallet(1_000_000)),
+            (user, wallet(1_000_000_000)),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ],
+        &[
+            Check::success(),
+            Check::account(&treasury)
+                .lamports(1_000_000 + PRICE)
+                .build(),
+            Check::account(&entitlement).data(&recorded).build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_entitled_user_premium_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: A user who purchased earlier uses the premium feature. Their
+    // wallet holds 0.01 SOL — the balance doesn't matter, the entitlement
+    // does.
+    //
+    // Expected: SUCCEEDS — premium call recorded.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "lamport_gate");
+    let (service, service_account, treasury) = service_account();
+    let user = Pubkey::new_unique();
+    let (entitlement, bump) =
+        Pubkey::find_program_address(&[b"entitlement", user.as_ref()], &PROGRAM_ID);
+
+    let entitlement_data = serialize_entitlement(&user, PRICE, 42, bump);
+    let mut entitlement_account =
+        AccountSharedData::new(1_000_000, entitlement_data.len(), &PROGRAM_ID);
+    entitlement_account.set_data_from_slice(&entitlement_data);
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator("premium_action_secure"),
+        vec![
+            AccountMeta::new(service, false),
+            AccountMeta::new_readonly(entitlement, false),
+            AccountMeta::new_readonly(user, true),
+        ],
+    );
+
+    let (_, service_bump) = Pubkey::find_program_address(&[b"service"], &PROGRAM_ID);
+    let after = serialize_service(&treasury, 1, service_bump);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &[
+            (service, service_account),
+            (entitlement, entitlement_account),
+            (user, wallet(SMALL_BALANCE)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&service).data(&after).build(),
+        ],
+    );
+}
@@ -0,0 +1,303 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("8r56r2NmLKFR5XkknPwYvQX4KZyEGc4wbuCk5222Ho48");
+
+/// # Loan Extension Consent
+///
+/// ## The Vulnerability
+/// A term loan has a maturity date. After it passes, the lender may
+/// liquidate the collateral. The loan also has an `extend_loan` instruction
+/// — and the borrower can call it alone, with any new maturity. An
+/// underwater borrower just keeps extending, and the loan is never
+/// liquidatable.
+///
+/// ## Why It Matters
+/// Maturity is the lender's only enforcement lever. If one party can move
+/// it unilaterally, the loan's terms are whatever that party says they are.
+/// The lender's capital is locked against collateral they can never seize.
+///
+/// ## The Fix
+/// Extensions change the agreement, so both parties must agree: either the
+/// new maturity is within a maximum both signed up front (`max_maturity_ts`
+/// in the terms) and the loan hasn't matured yet, or the lender co-signs
+/// the extension. Repaid and liquidated loans can't be extended at all.
+#[program]
+pub mod loan_extension {
+    use super::*;
+
+    /// Open a loan. Both parties sign the terms; the lender pays the
+    /// `principal` to the borrower, who posts `collateral` lamports into the
+    /// loan PDA.
+    pub fn open_loan(
+        ctx: Context<OpenLoan>,
+        principal: u64,
+        collateral: u64,
+        maturity_ts: i64,
+        max_maturity_ts: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now < maturity_ts && maturity_ts <= max_maturity_ts,
+            ErrorCode::InvalidMaturity
+        );
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.lender.to_account_info(),
+                    to: ctx.accounts.borrower.to_account_info(),
+                },
+            ),
+            principal,
+        )?;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.borrower.to_account_info(),
+                    to: ctx.accounts.loan.to_account_info(),
+                },
+            ),
+            collateral,
+        )?;
+
+        let loan = &mut ctx.accounts.loan;
+        loan.lender = ctx.accounts.lender.key();
+        loan.borrower = ctx.accounts.borrower.key();
+        loan.principal = principal;
+        loan.maturity_ts = maturity_ts;
+        loan.max_maturity_ts = max_maturity_ts;
+        loan.status = LoanStatus::Active;
+        loan.bump = ctx.bumps.loan;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Borrower extends maturity alone, without limit
+    // ============================================================================
+    // ISSUE: Only the borrower signs. `new_maturity_ts` is unbounded — even
+    //        an already-overdue loan can be pushed to i64::MAX.
+    //
+    // ATTACK SCENARIO:
+    //   1. Loan matures at T; collateral falls below principal
+    //   2. At T + 1 the lender prepares to liquidate
+    //   3. Borrower front-runs with extend_loan_vulnerable(i64::MAX)
+    //   4. liquidate fails NotMatured — today and forever
+    // ============================================================================
+    pub fn extend_loan_vulnerable(
+        ctx: Context<ExtendVulnerable>,
+        new_maturity_ts: i64,
+    ) -> Result<()> {
+        let loan = &mut ctx.accounts.loan;
+        require!(loan.status == LoanStatus::Active, ErrorCode::LoanNotActive);
+        require!(
+            new_maturity_ts > loan.maturity_ts,
+            ErrorCode::InvalidMaturity
+        );
+
+        // VULNERABLE: borrower alone rewrites the loan's terms
+        loan.maturity_ts = new_maturity_ts;
+        demo_log!(
+            Mode::Vulnerable,
+            "extend_loan",
+            actor = ctx.accounts.borrower.key(),
+            maturity = new_maturity_ts
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Extensions stay within agreed terms or carry lender consent
+    // ============================================================================
+    // FIX: Up to `max_maturity_ts` (agreed by both parties at open) the
+    //      borrower may extend alone, but only before the loan matures.
+    //      Beyond it, or once the lender may already liquidate, the lender
+    //      must sign this transaction. A co-signed extension also raises the
+    //      cap, since the lender has agreed to new terms.
+    // ============================================================================
+    pub fn extend_loan_secure(ctx: Context<ExtendSecure>, new_maturity_ts: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let lender_signed = ctx.accounts.lender.is_signer;
+        let loan = &mut ctx.accounts.loan;
+        require!(loan.status == LoanStatus::Active, ErrorCode::LoanNotActive);
+        require!(
+            new_maturity_ts > loan.maturity_ts,
+            ErrorCode::InvalidMaturity
+        );
+
+        // SECURE: beyond the pre-agreed maximum, only with the lender's signature
+        if new_maturity_ts > loan.max_maturity_ts {
+            require!(lender_signed, ErrorCode::LenderConsentRequired);
+            loan.max_maturity_ts = new_maturity_ts;
+        }
+
+        // SECURE: an overdue loan belongs to the lender's liquidation; only
+        // they can give it more time
+        if !lender_signed {
+            require!(now < loan.maturity_ts, ErrorCode::LoanMatured);
+        }
+
+        loan.maturity_ts = new_maturity_ts;
+        demo_log!(
+            Mode::Secure,
+            "extend_loan",
+            actor = ctx.accounts.borrower.key(),
+            maturity = new_maturity_ts,
+            lender_signed = lender_signed
+        );
+        Ok(())
+    }
+
+    /// The borrower pays back the principal and takes the collateral back.
+    /// Allowed until the loan is liquidated, even after maturity.
+    pub fn repay(ctx: Context<Repay>) -> Result<()> {
+        let loan = &mut ctx.accounts.loan;
+        require!(loan.status == LoanStatus::Active, ErrorCode::LoanNotActive);
+        loan.status = LoanStatus::Repaid;
+        let principal = loan.principal;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.borrower.to_account_info(),
+                    to: ctx.accounts.lender.to_account_info(),
+                },
+            ),
+            principal,
+        )?;
+
+        let loan_info = ctx.accounts.loan.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(loan_info.data_len());
+        let collateral = loan_info.lamports().saturating_sub(rent_floor);
+        **loan_info.try_borrow_mut_lamports()? -= collateral;
+        **ctx.accounts.borrower.try_borrow_mut_lamports()? += collateral;
+
+        demo_log!(
+            Mode::Setup,
+            "repay",
+            actor = ctx.accounts.borrower.key(),
+            amount = principal
+        );
+        Ok(())
+    }
+
+    /// After maturity, the lender seizes the collateral.
+    pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let loan = &mut ctx.accounts.loan;
+        require!(loan.status == LoanStatus::Active, ErrorCode::LoanNotActive);
+        require!(now >= loan.maturity_ts, ErrorCode::NotMatured);
+        loan.status = LoanStatus::Liquidated;
+
+        let loan_info = loan.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(loan_info.data_len());
+        let collateral = loan_info.lamports().saturating_sub(rent_floor);
+        **loan_info.try_borrow_mut_lamports()? -= collateral;
+        **ctx.accounts.lender.try_borrow_mut_lamports()? += collateral;
+
+        demo_log!(
+            Mode::Setup,
+            "liquidate",
+            actor = ctx.accounts.lender.key(),
+            amount = collateral
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct OpenLoan<'info> {
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [b"loan", lender.key().as_ref(), borrower.key().as_ref()],
+        bump
+    )]
+    pub loan: Account<'info, Loan>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendVulnerable<'info> {
+    #[account(mut, has_one = borrower)]
+    pub loan: Account<'info, Loan>,
+    pub borrower: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendSecure<'info> {
+    #[account(mut, has_one = borrower, has_one = lender)]
+    pub loan: Account<'info, Loan>,
+    pub borrower: Signer<'info>,
+    /// CHECK: The loan's lender. Signer status is checked in the handler
+    /// when the extension goes beyond the agreed maximum or the loan has
+    /// already matured.
+    pub lender: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Repay<'info> {
+    #[account(mut, has_one = borrower, has_one = lender)]
+    pub loan: Account<'info, Loan>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    /// CHECK: The loan's lender, receiving the principal. Bound by `has_one`.
+    #[account(mut)]
+    pub lender: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    #[account(mut, has_one = lender)]
+    pub loan: Account<'info, Loan>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum LoanStatus {
+    Active,
+    Liquidated,
+    Repaid,
+}
+
+/// Collateral is the lamports held above rent.
+#[account]
+#[derive(InitSpace)]
+pub struct Loan {
+    pub lender: Pubkey,       // 32 bytes
+    pub borrower: Pubkey,     // 32 bytes
+    pub principal: u64,       //  8 bytes
+    pub maturity_ts: i64,     //  8 bytes
+    pub max_maturity_ts: i64, //  8 bytes — agreed by both parties at open
+    pub status: LoanStatus,   //  1 byte
+    pub bump: u8,             //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Loan is not active")]
+    LoanNotActive,
+    #[msg("Invalid maturity")]
+    InvalidMaturity,
+    #[msg("Extension beyond agreed terms requires the lender's signature")]
+    LenderConsentRequired,
+    #[msg("Loan has not matured")]
+    NotMatured,
+    #[msg("Loan has matured; extending it requires the lender's signature")]
+    LoanMatured,
+}
@@ -0,0 +1,448 @@
+/// # Pattern 189: Loan Extension Consent — Mollusk Exploit Tests
+///
+/// These tests walk the loan's state machine (Active → extended → Liquidated
+/// or Repaid) by feeding each instruction's resulting accounts into the next.
+///
+/// Loan terms: matures at 2_000, both parties agreed to at most 3_000.
+///
+/// - Test 1: EXPLOIT — an overdue borrower extends to i64::MAX alone; the
+///   lender's liquidation then fails.
+/// - Test 2: SECURE — a borrower-only extension past the agreed maximum is
+///   rejected.
+/// - Test 3: SECURE — an overdue borrower can't extend alone, even within
+///   the agreed maximum.
+/// - Test 4: SANITY — secure state machine: extend within terms, fail past
+///   them, no liquidation before the extended maturity, liquidate at it, no
+///   extension after liquidation.
+/// - Test 5: SANITY — a lender-co-signed extension past the maximum succeeds
+///   and binds the lender.
+/// - Test 6: SANITY — repayment returns the collateral and ends the loan: no
+///   extension or liquidation afterwards.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8r56r2NmLKFR5XkknPwYvQX4KZyEGc4wbuCk5222Ho48");
+
+const PRINCIPAL: u64 = 10_000_000_000;
+const COLLATERAL: u64 = 8_000_000_000;
+const LENDER_LAMPORTS: u64 = 1_000_000;
+const BORROWER_LAMPORTS: u64 = PRINCIPAL + 1_000_000;
+const MATURITY_TS: i64 = 2_000;
+const MAX_MATURITY_TS: i64 = 3_000;
+const LOAN_LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1;
+
+const STATUS_ACTIVE: u8 = 0;
+const STATUS_LIQUIDATED: u8 = 1;
+const STATUS_REPAID: u8 = 2;
+
+// ErrorCode discriminants
+const LOAN_NOT_ACTIVE: u32 = 6000;
+const LENDER_CONSENT_REQUIRED: u32 = 6002;
+const NOT_MATURED: u32 = 6003;
+const LOAN_MATURED: u32 = 6004;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+struct LoanState {
+    maturity_ts: i64,
+    max_maturity_ts: i64,
+    status: u8,
+}
+
+/// Serialize a Loan account:
+/// [8 disc][32 lender][32 borrower][8 principal][8 maturity_ts][8 max_maturity_ts][1 status][1 bump]
+fn serialize_loan(lender: &Pubkey, borrower: &Pubkey, state: &LoanState, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(LOAN_LEN);
+    data.extend_from_slice(&account_discriminator("Loan"));
+    data.extend_from_slice(lender.as_ref());
+    data.extend_from_slice(borrower.as_ref());
+    data.extend_from_slice(&PRINCIPAL.to_le_bytes());
+    data.extend_from_slice(&state.maturity_ts.to_le_bytes());
+    data.extend_from_slice(&state.max_maturity_ts.to_le_bytes());
+    data.push(state.status);
+    data.push(bump);
+    data
+}
+
+/// An open loan at its original terms, collateralised with COLLATERAL lamports.
+struct Scenario {
+    loan: Pubkey,
+    lender: Pubkey,
+    borrower: Pubkey,
+    bump: u8,
+    rent_floor: u64,
+    ledger: Ledger,
+}
+
+impl Scenario {
+    fn new(mollusk: &Mollusk) -> Self {
+        let lender = Pubkey::new_unique();
+        let borrower = Pubkey::new_unique();
+        let (loan, bump) = Pubkey::find_program_address(
+            &[b"loan", lender.as_ref(), borrower.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let terms = LoanState {
+            maturity_ts: MATURITY_TS,
+            max_maturity_ts: MAX_MATURITY_TS,
+            status: STATUS_ACTIVE,
+        };
+        let rent_floor = mollusk.sysvars.rent.minimum_balance(LOAN_LEN);
+        let loan_data = serialize_loan(&lender, &borrower, &terms, bump);
+        let mut loan_account =
+            AccountSharedData::new(rent_floor + COLLATERAL, loan_data.len(), &PROGRAM_ID);
+        loan_account.set_data_from_slice(&loan_data);
+
+        let ledger = vec![
+            (loan, loan_account),
+            (
+                lender,
+                AccountSharedData::new(LENDER_LAMPORTS, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                borrower,
+                AccountSharedData::new(BORROWER_LAMPORTS, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            loan,
+            lender,
+            borrower,
+            bump,
+            rent_floor,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn extend_vulnerable(&self, new_maturity_ts: i64) -> Instruction {
+        let mut data = ix_discriminator("extend_loan_vulnerable").to_vec();
+        data.extend_from_slice(&new_maturity_ts.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.loan, false),
+                AccountMeta::new_readonly(self.borrower, true),
+            ],
+        )
+    }
+
+    fn extend_secure(&self, new_maturity_ts: i64, lender_signs: bool) -> Instruction {
+        let mut data = ix_discriminator("extend_loan_secure").to_vec();
+        data.extend_from_slice(&new_maturity_ts.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.loan, false),
+                AccountMeta::new_readonly(self.borrower, true),
+                AccountMeta::new_readonly(self.lender, lender_signs),
+            ],
+        )
+    }
+
+    fn repay(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("repay"),
+            vec![
+                AccountMeta::new(self.loan, false),
+                AccountMeta::new(self.borrower, true),
+                AccountMeta::new(self.lender, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn liquidate(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("liquidate"),
+            vec![
+                AccountMeta::new(self.loan, false),
+                AccountMeta::new(self.lender, true),
+            ],
+        )
+    }
+
+    fn loan_data(&self, state: LoanState) -> Vec<u8> {
+        serialize_loan(&self.lender, &self.borrower, &state, self.bump)
+    }
+}
+
+fn mollusk_at(unix_timestamp: i64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "loan_extension");
+    mollusk.sysvars.clock.unix_timestamp = unix_timestamp;
+    mollusk
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_borrower_extends_past_liquidation() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: It's 2_500. The loan matured at 2_000 and the lender is
+    // about to liquidate. The borrower gets there first.
+    //
+    //   1. extend_loan_vulnerable(i64::MAX), borrower signs alone → OK
+    //   2. liquidate, lender signs                               → NotMatured
+    //
+    // Expected: the loan can never be liquidated.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(2_500);
+    let mut s = Scenario::new(&mollusk);
+    let loan = s.loan;
+
+    let forever = s.loan_data(LoanState {
+        maturity_ts: i64::MAX,
+        max_maturity_ts: MAX_MATURITY_TS,
+        status: STATUS_ACTIVE,
+    });
+    let ix = s.extend_vulnerable(i64::MAX);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&loan).data(&forever).build(),
+        ],
+    );
+
+    let ix = s.liquidate();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(NOT_MATURED))],
+    );
+}
+
+#[test]
+fn secure_rejects_unilateral_extension() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same overdue loan, same borrower-only extension, secure
+    // handler. i64::MAX is past the agreed 3_000 and the lender didn't sign.
+    //
+    // Expected: FAILS with LenderConsentRequired (6002).
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(2_500);
+    let mut s = Scenario::new(&mollusk);
+
+    let ix = s.extend_secure(i64::MAX, false);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(LENDER_CONSENT_REQUIRED))],
+    );
+}
+
+#[test]
+fn secure_rejects_overdue_extension() {
+    // -----------------------------------------------------------------------
+    // SECURE: It's 2_500 and the loan matured at 2_000. The borrower asks
+    // for 3_000, inside the agreed maximum, to stall the liquidation.
+    //
+    //   1. extend_loan_secure(3_000), borrower alone  → LoanMatured
+    //   2. liquidate                                  → OK
+    //
+    // Expected: the agreed maximum only covers extensions asked for in
+    // time; an overdue loan is the lender's to liquidate.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(2_500);
+    let mut s = Scenario::new(&mollusk);
+
+    let ix = s.extend_secure(MAX_MATURITY_TS, false);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(LOAN_MATURED))],
+    );
+
+    let ix = s.liquidate();
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+}
+
+#[test]
+fn sanity_secure_state_machine() {
+    // -----------------------------------------------------------------------
+    // SANITY: The secure loan through its whole life.
+    //
+    //   1. At 1_500, borrower extends to 3_000 (within agreed terms) → OK
+    //   2. Borrower extends to 4_000 alone              → LenderConsentRequired
+    //   3. At 2_500, lender liquidates                  → NotMatured
+    //   4. At 3_000, lender liquidates                  → OK, collateral to lender
+    //   5. Borrower tries to extend a liquidated loan   → LoanNotActive
+    // -----------------------------------------------------------------------
+    let mut mollusk = mollusk_at(1_500);
+    let mut s = Scenario::new(&mollusk);
+    let (loan, lender) = (s.loan, s.lender);
+    let rent_floor = s.rent_floor;
+
+    let at_cap = s.loan_data(LoanState {
+        maturity_ts: MAX_MATURITY_TS,
+        max_maturity_ts: MAX_MATURITY_TS,
+        status: STATUS_ACTIVE,
+    });
+    let ix = s.extend_secure(MAX_MATURITY_TS, false);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&loan).data(&at_cap).build(),
+        ],
+    );
+
+    let ix = s.extend_secure(4_000, false);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(LENDER_CONSENT_REQUIRED))],
+    );
+
+    // Past the original maturity, but not the extended one.
+    mollusk.sysvars.clock.unix_timestamp = 2_500;
+    let ix = s.liquidate();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(NOT_MATURED))],
+    );
+
+    mollusk.sysvars.clock.unix_timestamp = MAX_MATURITY_TS;
+    let liquidated = s.loan_data(LoanState {
+        maturity_ts: MAX_MATURITY_TS,
+        max_maturity_ts: MAX_MATURITY_TS,
+        status: STATUS_LIQUIDATED,
+    });
+    let ix = s.liquidate();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&loan)
+                .data(&liquidated)
+                .lamports(rent_floor)
+                .build(),
+            Check::account(&lender)
+                .lamports(LENDER_LAMPORTS + COLLATERAL)
+                .build(),
+        ],
+    );
+
+    let ix = s.extend_secure(3_500, true);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(LOAN_NOT_ACTIVE))],
+    );
+}
+
+#[test]
+fn sanity_lender_cosigned_extension() {
+    // -----------------------------------------------------------------------
+    // SANITY: Lender and borrower renegotiate. The lender co-signs an
+    // extension to 10_000, which also becomes the new agreed maximum.
+    //
+    //   1. extend_loan_secure(10_000), lender signs → OK
+    //   2. liquidate at 3_000                       → NotMatured
+    //
+    // Expected: the extension the lender agreed to binds the lender.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(3_000);
+    let mut s = Scenario::new(&mollusk);
+    let loan = s.loan;
+
+    let renegotiated = s.loan_data(LoanState {
+        maturity_ts: 10_000,
+        max_maturity_ts: 10_000,
+        status: STATUS_ACTIVE,
+    });
+    let ix = s.extend_secure(10_000, true);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&loan).data(&renegotiated).build(),
+        ],
+    );
+
+    let ix = s.liquidate();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(NOT_MATURED))],
+    );
+}
+
+#[test]
+fn sanity_repay_ends_loan() {
+    // -----------------------------------------------------------------------
+    // SANITY: The borrower repays at 1_500, before maturity.
+    //
+    //   1. repay                                       → OK, principal to
+    //                                                    lender, collateral back
+    //   2. Borrower extends the repaid loan            → LoanNotActive
+    //   3. At 2_500, lender liquidates the repaid loan → LoanNotActive
+    // -----------------------------------------------------------------------
+    let mut mollusk = mollusk_at(1_500);
+    let mut s = Scenario::new(&mollusk);
+    let (loan, lender, borrower) = (s.loan, s.lender, s.borrower);
+    let rent_floor = s.rent_floor;
+
+    let repaid = s.loan_data(LoanState {
+        maturity_ts: MATURITY_TS,
+        max_maturity_ts: MAX_MATURITY_TS,
+        status: STATUS_REPAID,
+    });
+    let ix = s.repay();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&loan)
+                .data(&repaid)
+                .lamports(rent_floor)
+                .build(),
+            Check::account(&lender)
+                .lamports(LENDER_LAMPORTS + PRINCIPAL)
+                .build(),
+            Check::account(&borrower)
+                .lamports(BORROWER_LAMPORTS - PRINCIPAL + COLLATERAL)
+                .build(),
+        ],
+    );
+
+    let ix = s.extend_secure(MAX_MATURITY_TS, false);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(LOAN_NOT_ACTIVE))],
+    );
+
+    mollusk.sysvars.clock.unix_timestamp = 2_500;
+    let ix = s.liquidate();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(LOAN_NOT_ACTIVE))],
+    );
+}
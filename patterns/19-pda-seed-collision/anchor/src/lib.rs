@@ -0,0 +1,299 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("Fvj8zLenvfuszSnN5rxfig4bVGMfnhuqAhb3P6kfaK7n");
+
+/// VULNERABLE: one prefix shared by pool vaults and user vaults.
+pub const VAULT_SEED: &[u8] = b"vault";
+/// SECURE: a literal prefix for each kind of vault.
+pub const POOL_VAULT_SEED: &[u8] = b"pool_vault";
+pub const USER_VAULT_SEED: &[u8] = b"user_vault";
+
+/// # PDA Seed Collision
+///
+/// This program demonstrates two kinds of vault whose PDAs are derived
+/// from the same seed prefix.
+///
+/// ## The Vulnerability
+/// A pool vault lives at `[b"vault", pool_id]`, where `pool_id` is any key
+/// the creator picks to name the pool. A user's vault lives at
+/// `[b"vault", user]`. Both are 32-byte keys after the same prefix, so the
+/// two address spaces are one. Nothing stops a creator from choosing a
+/// user's wallet as their `pool_id`: the pool vault is created at exactly
+/// the address where that user's vault belongs, with the creator as its
+/// authority.
+///
+/// ## Real-World Impact
+/// The user can no longer open their vault, because the address is taken.
+/// Worse, anyone paying the user derives the same address and deposits
+/// into it, and the attacker withdraws as its authority. The account is a
+/// real `Vault` owned by the program, so type checks don't help: the
+/// collision is in the addresses, not the data.
+#[program]
+pub mod pda_seed_collision {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Pool vaults and user vaults share one seed prefix
+    // ============================================================================
+    // ISSUE: `[b"vault", pool_id]` and `[b"vault", user]` are the same shape.
+    //        Any pool_id that equals a wallet address derives that wallet's
+    //        user vault.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker calls create_pool_vault_vulnerable(pool_id = victim)
+    //   2. The vault at [b"vault", victim] now exists, authority = attacker
+    //   3. The victim's open_user_vault_vulnerable fails: address in use
+    //   4. A payer deposits 5 SOL to the victim through deposit_vulnerable,
+    //      which derives [b"vault", victim] and finds a valid Vault
+    //   5. Attacker withdraws the 5 SOL
+    // ============================================================================
+    pub fn create_pool_vault_vulnerable(
+        ctx: Context<CreatePoolVaultVulnerable>,
+        pool_id: Pubkey,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.authority = ctx.accounts.creator.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Vulnerable,
+            "create_pool_vault",
+            actor = vault.authority,
+            pool_id = pool_id
+        );
+        Ok(())
+    }
+
+    pub fn open_user_vault_vulnerable(ctx: Context<OpenUserVaultVulnerable>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.authority = ctx.accounts.user.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(Mode::Vulnerable, "open_user_vault", actor = vault.authority);
+        Ok(())
+    }
+
+    pub fn deposit_vulnerable(ctx: Context<DepositVulnerable>, amount: u64) -> Result<()> {
+        // VULNERABLE: the address derived for `owner` may hold a pool vault
+        transfer_in(
+            &ctx.accounts.depositor,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount,
+            owner = ctx.accounts.owner.key(),
+            authority = ctx.accounts.vault.authority
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Each vault type has its own literal prefix
+    // ============================================================================
+    // FIX: Pool vaults derive from `[b"pool_vault", pool_id]` and user vaults
+    //      from `[b"user_vault", user]`. No choice of pool_id can reach an
+    //      address in the user namespace, so the same key can name a pool
+    //      and a user without the two ever meeting.
+    // ============================================================================
+    pub fn create_pool_vault_secure(
+        ctx: Context<CreatePoolVaultSecure>,
+        pool_id: Pubkey,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.authority = ctx.accounts.creator.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Secure,
+            "create_pool_vault",
+            actor = vault.authority,
+            pool_id = pool_id
+        );
+        Ok(())
+    }
+
+    pub fn open_user_vault_secure(ctx: Context<OpenUserVaultSecure>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.authority = ctx.accounts.user.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(Mode::Secure, "open_user_vault", actor = vault.authority);
+        Ok(())
+    }
+
+    pub fn deposit_secure(ctx: Context<DepositSecure>, amount: u64) -> Result<()> {
+        // SECURE: only a user vault can live under the user prefix
+        transfer_in(
+            &ctx.accounts.depositor,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount,
+            owner = ctx.accounts.owner.key(),
+            authority = ctx.accounts.vault.authority
+        );
+        Ok(())
+    }
+
+    /// The vault's authority withdraws `amount` lamports. Shared by both
+    /// kinds of vault.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let vault = ctx.accounts.vault.to_account_info();
+        let authority = ctx.accounts.authority.to_account_info();
+        **vault.try_borrow_mut_lamports()? = vault
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientFunds)?;
+        **authority.try_borrow_mut_lamports()? = authority
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = authority.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+fn transfer_in<'info>(
+    depositor: &Signer<'info>,
+    vault: &Account<'info, Vault>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            Transfer {
+                from: depositor.to_account_info(),
+                to: vault.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(pool_id: Pubkey)]
+pub struct CreatePoolVaultVulnerable<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [VAULT_SEED, pool_id.as_ref()], // <-- same shape as a user vault
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenUserVaultVulnerable<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [VAULT_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositVulnerable<'info> {
+    #[account(mut, seeds = [VAULT_SEED, owner.key().as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: The wallet being paid; only used to derive its vault.
+    pub owner: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_id: Pubkey)]
+pub struct CreatePoolVaultSecure<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [POOL_VAULT_SEED, pool_id.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenUserVaultSecure<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [USER_VAULT_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSecure<'info> {
+    #[account(mut, seeds = [USER_VAULT_SEED, owner.key().as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: The wallet being paid; only used to derive its vault.
+    pub owner: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, has_one = authority)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Used for both pool vaults and user vaults. Lamports above rent are the
+/// vault's balance.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub authority: Pubkey, // 32 bytes
+    pub bump: u8,          //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Vault cannot cover the withdrawal")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1 @@
+// Mollusk tests for Pattern 19
@@ -0,0 +1,301 @@
+/// # Pattern 19: PDA Seed Collision — Mollusk Exploit Tests
+///
+/// An attacker, a victim who hasn't opened a vault yet, and a payer who
+/// wants to send the victim 5 SOL. Each test runs the whole sequence
+/// through a ledger, starting with the attacker naming a pool after the
+/// victim's wallet.
+///
+/// - Test 1: EXPLOIT — the attacker's pool vault lands on the victim's
+///   user-vault address. The victim can't open theirs, the payer's deposit
+///   goes into the pool vault, and the attacker withdraws it.
+/// - Test 2: SECURE — with separate prefixes both vaults exist side by
+///   side, the deposit reaches the victim, and the attacker can't withdraw.
+/// - Test 3: SANITY — the victim withdraws the deposit from their own
+///   secure vault.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Accounts, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("Fvj8zLenvfuszSnN5rxfig4bVGMfnhuqAhb3P6kfaK7n");
+
+const SOL: u64 = 1_000_000_000;
+const WALLET: u64 = 10 * SOL;
+const DEPOSIT: u64 = 5 * SOL;
+const VAULT_LEN: usize = 41;
+
+// Error codes
+const CONSTRAINT_HAS_ONE: u32 = 2001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account: [8 disc][32 authority][1 bump]
+fn serialize_vault(authority: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(VAULT_LEN);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(authority.as_ref());
+    data.push(bump);
+    data
+}
+
+/// The three wallets and every vault address either namespace derives
+/// for the victim.
+struct World {
+    attacker: Pubkey,
+    victim: Pubkey,
+    payer: Pubkey,
+    ledger: Ledger,
+}
+
+impl World {
+    fn new() -> Self {
+        let attacker = Pubkey::new_unique();
+        let victim = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+
+        let mut ledger: Accounts = [attacker, victim, payer]
+            .into_iter()
+            .map(|key| {
+                (
+                    key,
+                    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID),
+                )
+            })
+            .collect();
+        for prefix in [&b"vault"[..], b"pool_vault", b"user_vault"] {
+            let (vault, _) = Self::derive(prefix, &victim);
+            ledger.push((vault, AccountSharedData::default()));
+        }
+        ledger.push((
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ));
+
+        Self {
+            attacker,
+            victim,
+            payer,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn derive(prefix: &[u8], key: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[prefix, key.as_ref()], &PROGRAM_ID)
+    }
+
+    /// The attacker creates a pool vault named after the victim's wallet.
+    fn create_pool_vault(&self, name: &str, prefix: &[u8]) -> Instruction {
+        let (vault, _) = Self::derive(prefix, &self.victim);
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(self.victim.as_ref()); // pool_id
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn open_user_vault(&self, name: &str, prefix: &[u8]) -> Instruction {
+        let (vault, _) = Self::derive(prefix, &self.victim);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(self.victim, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// The payer sends DEPOSIT to whatever vault `prefix` derives for the
+    /// victim.
+    fn deposit(&self, name: &str, prefix: &[u8]) -> Instruction {
+        let (vault, _) = Self::derive(prefix, &self.victim);
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&DEPOSIT.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(self.victim, false),
+                AccountMeta::new(self.payer, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn withdraw(&self, vault: Pubkey, authority: Pubkey) -> Instruction {
+        let mut data = ix_discriminator("withdraw").to_vec();
+        data.extend_from_slice(&DEPOSIT.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(authority, true),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_pool_vault_squats_user_address_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: create_pool_vault_vulnerable(pool_id = victim)
+    //   2. Victim: open_user_vault_vulnerable
+    //   3. Payer: deposit_vulnerable of 5 SOL to the victim
+    //   4. Attacker: withdraw 5 SOL from [b"vault", victim]
+    //
+    // Expected: 1 SUCCEEDS at the victim's user-vault address, with the
+    //           attacker as authority. 2 FAILS: the address is taken.
+    //           3 SUCCEEDS into the attacker's vault, and 4 SUCCEEDS.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "pda_seed_collision");
+    let mut w = World::new();
+    let attacker = w.attacker;
+    let (vault, bump) = World::derive(b"vault", &w.victim);
+    let rent = mollusk.sysvars.rent.minimum_balance(VAULT_LEN);
+
+    let squatted = serialize_vault(&w.attacker, bump);
+    w.ledger.step(
+        &mollusk,
+        &w.create_pool_vault("create_pool_vault_vulnerable", b"vault"),
+        &[
+            Check::success(),
+            Check::account(&vault).data(&squatted).build(),
+        ],
+    );
+
+    let ix = w.open_user_vault("open_user_vault_vulnerable", b"vault");
+    let result = mollusk.process_instruction(&ix, &w.ledger.accounts_for(&ix));
+    assert!(result.program_result.is_err());
+
+    w.ledger.step(
+        &mollusk,
+        &w.deposit("deposit_vulnerable", b"vault"),
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(rent + DEPOSIT).build(),
+        ],
+    );
+
+    w.ledger.step(
+        &mollusk,
+        &w.withdraw(vault, w.attacker),
+        &[
+            Check::success(),
+            Check::account(&attacker)
+                .lamports(WALLET - rent + DEPOSIT)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_prefixes_keep_types_apart() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same sequence through the secure instructions.
+    //
+    // Expected: the attacker's pool vault SUCCEEDS at
+    //           [b"pool_vault", victim], and the victim's vault SUCCEEDS at
+    //           [b"user_vault", victim]. The deposit lands in the victim's
+    //           vault; the attacker's withdrawal from it FAILS with
+    //           ConstraintHasOne (2001).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "pda_seed_collision");
+    let mut w = World::new();
+    let (pool_vault, pool_bump) = World::derive(b"pool_vault", &w.victim);
+    let (user_vault, user_bump) = World::derive(b"user_vault", &w.victim);
+    let rent = mollusk.sysvars.rent.minimum_balance(VAULT_LEN);
+    assert_ne!(pool_vault, user_vault);
+
+    let pool_data = serialize_vault(&w.attacker, pool_bump);
+    w.ledger.step(
+        &mollusk,
+        &w.create_pool_vault("create_pool_vault_secure", b"pool_vault"),
+        &[
+            Check::success(),
+            Check::account(&pool_vault).data(&pool_data).build(),
+        ],
+    );
+
+    let user_data = serialize_vault(&w.victim, user_bump);
+    w.ledger.step(
+        &mollusk,
+        &w.open_user_vault("open_user_vault_secure", b"user_vault"),
+        &[
+            Check::success(),
+            Check::account(&user_vault).data(&user_data).build(),
+        ],
+    );
+
+    w.ledger.step(
+        &mollusk,
+        &w.deposit("deposit_secure", b"user_vault"),
+        &[
+            Check::success(),
+            Check::account(&user_vault).lamports(rent + DEPOSIT).build(),
+        ],
+    );
+
+    w.ledger.step(
+        &mollusk,
+        &w.withdraw(user_vault, w.attacker),
+        &[Check::err(ProgramError::Custom(CONSTRAINT_HAS_ONE))],
+    );
+}
+
+#[test]
+fn sanity_owner_withdraws_deposit_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The victim opens their secure vault, the payer deposits 5 SOL,
+    // and the victim withdraws it.
+    //
+    // Expected: all SUCCEED — the victim ends 5 SOL up, less the vault's
+    //           rent.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "pda_seed_collision");
+    let mut w = World::new();
+    let victim = w.victim;
+    let (user_vault, _) = World::derive(b"user_vault", &w.victim);
+    let rent = mollusk.sysvars.rent.minimum_balance(VAULT_LEN);
+
+    w.ledger.step(
+        &mollusk,
+        &w.open_user_vault("open_user_vault_secure", b"user_vault"),
+        &[Check::success()],
+    );
+    w.ledger.step(
+        &mollusk,
+        &w.deposit("deposit_secure", b"user_vault"),
+        &[Check::success()],
+    );
+    w.ledger.step(
+        &mollusk,
+        &w.withdraw(user_vault, w.victim),
+        &[
+            Check::success(),
+            Check::account(&user_vault).lamports(rent).build(),
+            Check::account(&victim)
+                .lamports(WALLET - rent + DEPOSIT)
+                .build(),
+        ],
+    );
+}
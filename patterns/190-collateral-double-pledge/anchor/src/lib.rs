@@ -0,0 +1,259 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("7yJqxfVnvFW5PysfSSnXNEmrv7w8RjiqZwyF4X54M9FQ");
+
+/// Loan-to-value in basis points: borrow up to 70% of collateral.
+pub const LTV_BPS: u64 = 7_000;
+
+/// # Collateral Double-Pledge
+///
+/// ## The Vulnerability
+/// A lending program runs several markets for the same collateral mint.
+/// Registering collateral records the user's token account and its current
+/// balance in a per-market position — but the tokens stay where they are,
+/// and nothing marks the account as pledged. The same account, with the same
+/// tokens, registers in market A and market B. Each market lends against the
+/// full balance.
+///
+/// ## Why It Matters
+/// One set of collateral backs two (or ten) loans. When the price drops,
+/// every market tries to liquidate the same tokens; at most one gets them.
+/// The rest hold bad debt. And since the tokens never left the user's
+/// account, they can also be transferred away after borrowing.
+///
+/// ## The Fix
+/// Take custody: transfer the collateral into a token account owned by the
+/// market's PDA. Tokens in market A's custody can't also be in market B's.
+/// (An encumbrance flag is the weaker alternative — it only works if the
+/// token account is also frozen or delegated so it can't be emptied.)
+#[program]
+pub mod double_pledge {
+    use super::*;
+
+    /// Create a market for `mint`. `custody` must be a token account of
+    /// `mint` owned by the market PDA.
+    pub fn init_market(ctx: Context<InitMarket>, market_id: u8) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.mint = ctx.accounts.mint.key();
+        market.custody = ctx.accounts.custody.key();
+        market.market_id = market_id;
+        market.bump = ctx.bumps.market;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Collateral recorded by reference, never encumbered
+    // ============================================================================
+    // ISSUE: The position stores the token account's balance. The tokens
+    //        stay in the user's account, unmarked, free to be registered
+    //        again in another market — or moved.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker holds 1_000_000 collateral tokens in one account
+    //   2. register_collateral_vulnerable in market A → credited 1_000_000
+    //   3. register_collateral_vulnerable in market B → credited 1_000_000
+    //   4. Borrow 70% in A and 70% in B: 140% of the collateral's value
+    //   5. Default; each market can seize at most what's actually there
+    // ============================================================================
+    pub fn register_collateral_vulnerable(ctx: Context<RegisterVulnerable>) -> Result<()> {
+        let amount = ctx.accounts.collateral.amount;
+
+        // VULNERABLE: credit the balance, leave the tokens where they are
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.market = ctx.accounts.market.key();
+        position.collateral = amount;
+        position.borrowed = 0;
+        position.bump = ctx.bumps.position;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "register_collateral",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            market = ctx.accounts.market.market_id,
+            held_in = ctx.accounts.collateral.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Collateral moved into the market's PDA custody
+    // ============================================================================
+    // FIX: The position is credited only with what was transferred into the
+    //      market's custody account. A second market's deposit finds the
+    //      user's account empty and the token transfer fails.
+    // ============================================================================
+    pub fn deposit_collateral_secure(ctx: Context<DepositSecure>, amount: u64) -> Result<()> {
+        // SECURE: custody, not a reference
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.collateral.to_account_info(),
+                    to: ctx.accounts.custody.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.market = ctx.accounts.market.key();
+        position.collateral = amount;
+        position.borrowed = 0;
+        position.bump = ctx.bumps.position;
+
+        demo_log!(
+            Mode::Secure,
+            "deposit_collateral",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            market = ctx.accounts.market.market_id,
+            held_in = ctx.accounts.custody.key()
+        );
+        Ok(())
+    }
+
+    /// Borrow lamports from the market against the position's collateral.
+    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        let limit = (position.collateral as u128)
+            .checked_mul(LTV_BPS as u128)
+            .ok_or(ErrorCode::Overflow)?
+            / 10_000;
+        let borrowed = position
+            .borrowed
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(borrowed as u128 <= limit, ErrorCode::ExceedsBorrowLimit);
+        position.borrowed = borrowed;
+
+        **ctx
+            .accounts
+            .market
+            .to_account_info()
+            .try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.owner.try_borrow_mut_lamports()? += amount;
+
+        demo_log!(
+            Mode::Setup,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            market = ctx.accounts.market.market_id,
+            borrowed = borrowed,
+            limit = limit
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: u8)]
+pub struct InitMarket<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market", mint.key().as_ref(), &[market_id]],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = market)]
+    pub custody: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterVulnerable<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(token::mint = market.mint, token::authority = owner)]
+    pub collateral: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSecure<'info> {
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut, token::mint = market.mint, token::authority = owner)]
+    pub collateral: Account<'info, TokenAccount>,
+    #[account(mut, address = market.custody)]
+    pub custody: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner,
+        has_one = market
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Lamports held above rent are the market's lendable liquidity.
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub mint: Pubkey,    // 32 bytes
+    pub custody: Pubkey, // 32 bytes — token account owned by this PDA
+    pub market_id: u8,   //  1 byte
+    pub bump: u8,        //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,   // 32 bytes
+    pub market: Pubkey,  // 32 bytes
+    pub collateral: u64, //  8 bytes
+    pub borrowed: u64,   //  8 bytes
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Borrow exceeds the position's limit")]
+    ExceedsBorrowLimit,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
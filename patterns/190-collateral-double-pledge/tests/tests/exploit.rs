@@ -0,0 +1,342 @@
+/// # Pattern 190: Collateral Double-Pledge — Mollusk Exploit Tests
+///
+/// Two markets (A and B) lend lamports against the same collateral mint at
+/// 70% LTV. The attacker holds 1_000_000 collateral tokens in one account.
+/// Each test chains instructions, feeding resulting accounts forward.
+///
+/// - Test 1: EXPLOIT — the same token account registers in both markets;
+///   the attacker borrows 70% in each, 140% of the collateral.
+/// - Test 2: SECURE — after depositing into A's custody, depositing into B
+///   fails: the tokens are gone from the attacker's account.
+/// - Test 3: SANITY — a custodied position borrows up to its limit and no
+///   further.
+///
+/// These tests CPI into SPL Token, so `spl_token.so` must be in SBF_OUT_DIR
+/// (see the pattern README).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Accounts, Ledger};
+use test_harness::token::{token_account, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("7yJqxfVnvFW5PysfSSnXNEmrv7w8RjiqZwyF4X54M9FQ");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const COLLATERAL: u64 = 1_000_000;
+const BORROW_LIMIT: u64 = 700_000;
+const MARKET_LAMPORTS: u64 = 10_000_000_000;
+const OWNER_LAMPORTS: u64 = 1_000_000_000;
+
+/// SPL Token's `TokenError::InsufficientFunds`.
+const TOKEN_INSUFFICIENT_FUNDS: u32 = 1;
+const EXCEEDS_BORROW_LIMIT: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Market account: [8 disc][32 mint][32 custody][1 market_id][1 bump]
+fn serialize_market(mint: &Pubkey, custody: &Pubkey, market_id: u8, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(74);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(custody.as_ref());
+    data.push(market_id);
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account: [8 disc][32 owner][32 market][8 collateral][8 borrowed][1 bump]
+fn serialize_position(
+    owner: &Pubkey,
+    market: &Pubkey,
+    collateral: u64,
+    borrowed: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(89);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(market.as_ref());
+    data.extend_from_slice(&collateral.to_le_bytes());
+    data.extend_from_slice(&borrowed.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "double_pledge");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// One market's keys.
+#[derive(Clone, Copy)]
+struct MarketKeys {
+    market: Pubkey,
+    custody: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+}
+
+/// Markets A and B over the same mint, and one attacker-owned collateral
+/// account holding COLLATERAL tokens.
+struct Scenario {
+    mint: Pubkey,
+    owner: Pubkey,
+    collateral: Pubkey,
+    a: MarketKeys,
+    b: MarketKeys,
+    ledger: Ledger,
+}
+
+impl Scenario {
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let collateral = Pubkey::new_unique();
+
+        let mut ledger: Accounts = vec![
+            (
+                owner,
+                AccountSharedData::new(OWNER_LAMPORTS, 0, &solana_sdk::system_program::ID),
+            ),
+            (collateral, token_account(&TOKEN_PROGRAM_ID, &mint, &owner, COLLATERAL)),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+
+        let mut market_keys = |market_id: u8| {
+            let (market, bump) = Pubkey::find_program_address(
+                &[b"market", mint.as_ref(), &[market_id]],
+                &PROGRAM_ID,
+            );
+            let custody = Pubkey::new_unique();
+            let (position, position_bump) = Pubkey::find_program_address(
+                &[b"position", market.as_ref(), owner.as_ref()],
+                &PROGRAM_ID,
+            );
+
+            let market_data = serialize_market(&mint, &custody, market_id, bump);
+            let mut market_account =
+                AccountSharedData::new(MARKET_LAMPORTS, market_data.len(), &PROGRAM_ID);
+            market_account.set_data_from_slice(&market_data);
+
+            ledger.push((market, market_account));
+            ledger.push((custody, token_account(&TOKEN_PROGRAM_ID, &mint, &market, 0)));
+            ledger.push((position, AccountSharedData::default()));
+
+            MarketKeys {
+                market,
+                custody,
+                position,
+                position_bump,
+            }
+        };
+        let a = market_keys(0);
+        let b = market_keys(1);
+
+        Self {
+            mint,
+            owner,
+            collateral,
+            a,
+            b,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn register_vulnerable(&self, m: &MarketKeys) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("register_collateral_vulnerable"),
+            vec![
+                AccountMeta::new_readonly(m.market, false),
+                AccountMeta::new(m.position, false),
+                AccountMeta::new_readonly(self.collateral, false),
+                AccountMeta::new(self.owner, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn deposit_secure(&self, m: &MarketKeys, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("deposit_collateral_secure").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(m.market, false),
+                AccountMeta::new(m.position, false),
+                AccountMeta::new(self.collateral, false),
+                AccountMeta::new(m.custody, false),
+                AccountMeta::new(self.owner, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn borrow(&self, m: &MarketKeys, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("borrow").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(m.market, false),
+                AccountMeta::new(m.position, false),
+                AccountMeta::new(self.owner, true),
+            ],
+        )
+    }
+
+    fn position_data(&self, m: &MarketKeys, collateral: u64, borrowed: u64) -> Vec<u8> {
+        serialize_position(
+            &self.owner,
+            &m.market,
+            collateral,
+            borrowed,
+            m.position_bump,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_one_collateral_two_loans() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: One token account, 1_000_000 tokens.
+    //
+    //   1. register in market A → credited 1_000_000
+    //   2. register in market B → credited 1_000_000 (same tokens)
+    //   3. borrow 700_000 from A
+    //   4. borrow 700_000 from B
+    //
+    // Expected: all four SUCCEED — 1_400_000 borrowed against collateral
+    //           that supports 700_000, and the tokens never moved.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Scenario::new();
+    let (owner, collateral, mint) = (s.owner, s.collateral, s.mint);
+
+    for m in [s.a, s.b] {
+        let ix = s.register_vulnerable(&m);
+        let credited = s.position_data(&m, COLLATERAL, 0);
+        let position = m.position;
+        s.ledger.step(
+            &mollusk,
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&position).data(&credited).build(),
+            ],
+        );
+    }
+
+    for m in [s.a, s.b] {
+        let ix = s.borrow(&m, BORROW_LIMIT);
+        let market = m.market;
+        s.ledger.step(
+            &mollusk,
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&market)
+                    .lamports(MARKET_LAMPORTS - BORROW_LIMIT)
+                    .build(),
+            ],
+        );
+    }
+
+    // Both loans are out, and the collateral is still the attacker's.
+    let untouched = TokenAccount::new(&mint, &owner, COLLATERAL).pack();
+    assert_eq!(s.ledger.data(&collateral), untouched.as_slice());
+}
+
+#[test]
+fn secure_second_market_deposit_fails() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker deposits all 1_000_000 into market A's custody,
+    // then tries the same with market B.
+    //
+    //   1. deposit into A → OK, A's custody holds 1_000_000
+    //   2. deposit into B → the attacker's account is empty
+    //
+    // Expected: step 2 FAILS with SPL Token InsufficientFunds (custom 1).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Scenario::new();
+    let (mint, custody_a) = (s.mint, s.a.custody);
+    let market_a = s.a.market;
+
+    let in_custody = TokenAccount::new(&mint, &market_a, COLLATERAL).pack();
+    let ix = s.deposit_secure(&s.a, COLLATERAL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&custody_a).data(&in_custody).build(),
+        ],
+    );
+
+    let ix = s.deposit_secure(&s.b, COLLATERAL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(TOKEN_INSUFFICIENT_FUNDS))],
+    );
+}
+
+#[test]
+fn sanity_custodied_position_borrows_to_limit() {
+    // -----------------------------------------------------------------------
+    // SANITY: Deposit into A's custody, borrow the full 70%, then try 1 more.
+    //
+    // Expected: the deposit and the 700_000 borrow SUCCEED; the extra
+    //           lamport FAILS with ExceedsBorrowLimit (6000).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Scenario::new();
+    let position_a = s.a.position;
+
+    let ix = s.deposit_secure(&s.a, COLLATERAL);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let at_limit = s.position_data(&s.a, COLLATERAL, BORROW_LIMIT);
+    let ix = s.borrow(&s.a, BORROW_LIMIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position_a).data(&at_limit).build(),
+        ],
+    );
+
+    let ix = s.borrow(&s.a, 1);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(EXCEEDS_BORROW_LIMIT))],
+    );
+}
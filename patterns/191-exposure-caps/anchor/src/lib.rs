@@ -0,0 +1,381 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+use risk_limits::{borrow_limit, checked_increase, LimitError};
+
+declare_id!("A5Cranz5id2EdQj5AAMN1dfwjRhLF5fBTHaeM65qgHeW");
+
+/// Loan-to-value in basis points: borrow up to 50% of collateral value.
+pub const LTV_BPS: u64 = 5_000;
+
+/// # Exposure Caps
+///
+/// ## The Vulnerability
+/// A lending protocol lists markets and lets anyone deposit and borrow, with
+/// no limit on how big a market can get or how much can be borrowed from it.
+/// Every per-position check is correct. But when one market's price feed
+/// glitches, or one market has a bug, the loss is bounded only by what the
+/// market holds — and with no caps, that's as much as depositors put in.
+///
+/// ## Why It Matters
+/// Caps don't prevent bugs; they bound them. A new market with a thin oracle
+/// and a 1M SOL deposit limit can lose at most 1M SOL. The same market with
+/// no limit can lose the protocol.
+///
+/// ## The Fix
+/// Per-market deposit and borrow caps, and a global deposit (TVL) cap in the
+/// config, checked on every deposit and borrow. The checks live in the shared
+/// `risk-limits` crate so every program computes them the same way.
+#[program]
+pub mod exposure_caps {
+    use super::*;
+
+    pub fn init_config(ctx: Context<InitConfig>, global_deposit_cap: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.global_deposit_cap = global_deposit_cap;
+        config.total_deposits = 0;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    pub fn init_market(
+        ctx: Context<InitMarket>,
+        market_id: u8,
+        price_bps: u64,
+        deposit_cap: u64,
+        borrow_cap: u64,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.market_id = market_id;
+        market.price_bps = price_bps;
+        market.deposit_cap = deposit_cap;
+        market.borrow_cap = borrow_cap;
+        market.total_deposits = 0;
+        market.total_borrows = 0;
+        market.bump = ctx.bumps.market;
+        Ok(())
+    }
+
+    /// Stand-in for the market's oracle: the admin posts the collateral
+    /// price. A glitch here is what the caps have to survive.
+    pub fn set_price(ctx: Context<SetPrice>, price_bps: u64) -> Result<()> {
+        ctx.accounts.market.price_bps = price_bps;
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.market = ctx.accounts.market.key();
+        position.deposited = 0;
+        position.borrowed = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Deposits and borrows with no market or global limit
+    // ============================================================================
+    // ISSUE: Totals are tracked but never bounded. A market grows as large as
+    //        depositors make it, and a borrower can take everything in it.
+    //
+    // ATTACK SCENARIO:
+    //   1. A new market lists with a thin oracle; depositors put in 100 SOL
+    //   2. The oracle glitches: collateral priced at 1000x
+    //   3. Attacker deposits 1 SOL — now "worth" 1000 SOL
+    //   4. borrow_vulnerable(100 SOL) passes the LTV check
+    //   5. The market's entire liquidity is gone
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        transfer_in(&ctx, amount)?;
+
+        // VULNERABLE: totals grow without bound
+        let config = &mut ctx.accounts.config;
+        config.total_deposits = config
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let market = &mut ctx.accounts.market;
+        market.total_deposits = market
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        credit_position(&mut ctx.accounts.position, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            market_total = market.total_deposits
+        );
+        Ok(())
+    }
+
+    pub fn borrow_vulnerable(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let borrowed = check_ltv(&ctx.accounts.market, &ctx.accounts.position, amount)?;
+
+        // VULNERABLE: nothing limits the market's total exposure
+        let market = &mut ctx.accounts.market;
+        market.total_borrows = market
+            .total_borrows
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        ctx.accounts.position.borrowed = borrowed;
+        pay_out(&ctx, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            market_total = ctx.accounts.market.total_borrows
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Market deposit cap, market borrow cap, global deposit cap
+    // ============================================================================
+    // FIX: Each increase goes through `risk_limits::checked_increase`, which
+    //      returns the new total only if it stays within the cap. A glitched
+    //      price still passes the LTV check — but the most it can cost is
+    //      the market's borrow cap.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        // SECURE: both caps checked before any lamports move
+        let config = &mut ctx.accounts.config;
+        config.total_deposits =
+            checked_increase(config.total_deposits, amount, config.global_deposit_cap)
+                .map_err(|e| limit_error(e, ErrorCode::GlobalCapExceeded))?;
+        let market = &mut ctx.accounts.market;
+        market.total_deposits = checked_increase(market.total_deposits, amount, market.deposit_cap)
+            .map_err(|e| limit_error(e, ErrorCode::MarketCapExceeded))?;
+
+        transfer_in(&ctx, amount)?;
+        credit_position(&mut ctx.accounts.position, amount)?;
+
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            market_total = ctx.accounts.market.total_deposits
+        );
+        Ok(())
+    }
+
+    pub fn borrow_secure(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let borrowed = check_ltv(&ctx.accounts.market, &ctx.accounts.position, amount)?;
+
+        // SECURE: the market's total exposure is bounded regardless of price
+        let market = &mut ctx.accounts.market;
+        market.total_borrows = checked_increase(market.total_borrows, amount, market.borrow_cap)
+            .map_err(|e| limit_error(e, ErrorCode::BorrowCapExceeded))?;
+        ctx.accounts.position.borrowed = borrowed;
+        pay_out(&ctx, amount)?;
+
+        demo_log!(
+            Mode::Secure,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            market_total = ctx.accounts.market.total_borrows
+        );
+        Ok(())
+    }
+}
+
+fn transfer_in(ctx: &Context<Deposit>, amount: u64) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.owner.to_account_info(),
+                to: ctx.accounts.market.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+fn credit_position(position: &mut Position, amount: u64) -> Result<()> {
+    position.deposited = position
+        .deposited
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// Per-position LTV: both variants enforce it. It's only as good as the
+/// price it's given. Returns the position's new borrowed total.
+fn check_ltv(market: &Market, position: &Position, amount: u64) -> Result<u64> {
+    let limit = borrow_limit(position.deposited, market.price_bps, LTV_BPS)
+        .map_err(|_| ErrorCode::Overflow)?;
+    let borrowed = position
+        .borrowed
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(borrowed <= limit, ErrorCode::ExceedsBorrowLimit);
+    Ok(borrowed)
+}
+
+fn pay_out(ctx: &Context<Borrow>, amount: u64) -> Result<()> {
+    **ctx
+        .accounts
+        .market
+        .to_account_info()
+        .try_borrow_mut_lamports()? -= amount;
+    **ctx.accounts.owner.try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+fn limit_error(err: LimitError, cap_exceeded: ErrorCode) -> ErrorCode {
+    match err {
+        LimitError::CapExceeded => cap_exceeded,
+        LimitError::Overflow => ErrorCode::Overflow,
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u8)]
+pub struct InitMarket<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market".as_ref(), &[market_id]],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"market".as_ref(), &[market.market_id]], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(seeds = [b"market".as_ref(), &[market.market_id]], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"market".as_ref(), &[market.market_id]], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(mut, seeds = [b"market".as_ref(), &[market.market_id]], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,           // 32 bytes
+    pub global_deposit_cap: u64, //  8 bytes — protocol-wide TVL limit
+    pub total_deposits: u64,     //  8 bytes
+    pub bump: u8,                //  1 byte
+}
+
+/// Lamports held above rent are the market's lendable liquidity.
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub market_id: u8,       //  1 byte
+    pub price_bps: u64,      //  8 bytes — collateral value, 10_000 = 1:1
+    pub deposit_cap: u64,    //  8 bytes
+    pub borrow_cap: u64,     //  8 bytes
+    pub total_deposits: u64, //  8 bytes
+    pub total_borrows: u64,  //  8 bytes
+    pub bump: u8,            //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,  // 32 bytes
+    pub market: Pubkey, // 32 bytes
+    pub deposited: u64, //  8 bytes
+    pub borrowed: u64,  //  8 bytes
+    pub bump: u8,       //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Deposit would exceed the market's deposit cap")]
+    MarketCapExceeded,
+    #[msg("Deposit would exceed the protocol's global deposit cap")]
+    GlobalCapExceeded,
+    #[msg("Borrow would exceed the market's borrow cap")]
+    BorrowCapExceeded,
+    #[msg("Borrow exceeds the position's limit")]
+    ExceedsBorrowLimit,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,361 @@
+/// # Pattern 191: Exposure Caps — Mollusk Exploit Tests
+///
+/// One market, market 0. Other depositors have put in 100 SOL and the
+/// attacker 1 SOL, so the market holds 101 SOL. Caps: 200 SOL market
+/// deposits, 10 SOL market borrows, 1_000 SOL protocol-wide deposits.
+///
+/// - Test 1: EXPLOIT — after a 1000x price glitch, the attacker borrows
+///   100 SOL against 1 SOL: the market is drained.
+/// - Test 2: SECURE — the same borrow fails the 10 SOL borrow cap.
+/// - Test 3: SANITY — with the glitch, the secure borrow stops exactly at
+///   the cap: 10 SOL passes, one more lamport fails.
+/// - Test 4: EXPLOIT — a deposit past the market cap is accepted.
+/// - Test 5: SECURE — deposits fill the market cap exactly, then fail.
+/// - Test 6: SECURE — deposits fill the global cap exactly, then fail, even
+///   though the market still has room.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("A5Cranz5id2EdQj5AAMN1dfwjRhLF5fBTHaeM65qgHeW");
+
+const SOL: u64 = 1_000_000_000;
+const PRICE_1X: u64 = 10_000;
+const PRICE_GLITCH: u64 = 1_000 * PRICE_1X;
+const DEPOSIT_CAP: u64 = 200 * SOL;
+const BORROW_CAP: u64 = 10 * SOL;
+const GLOBAL_CAP: u64 = 1_000 * SOL;
+const OWNER_LAMPORTS: u64 = 500 * SOL;
+
+// ErrorCode discriminants
+const MARKET_CAP_EXCEEDED: u32 = 6000;
+const GLOBAL_CAP_EXCEEDED: u32 = 6001;
+const BORROW_CAP_EXCEEDED: u32 = 6002;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Config account: [8 disc][32 admin][8 global_deposit_cap][8 total_deposits][1 bump]
+fn serialize_config(admin: &Pubkey, global_cap: u64, total: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(57);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&global_cap.to_le_bytes());
+    data.extend_from_slice(&total.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Market account:
+///   [8 disc][1 market_id][8 price_bps][8 deposit_cap][8 borrow_cap]
+///   [8 total_deposits][8 total_borrows][1 bump]
+fn serialize_market(m: &MarketState, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(50);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.push(0);
+    data.extend_from_slice(&m.price_bps.to_le_bytes());
+    data.extend_from_slice(&DEPOSIT_CAP.to_le_bytes());
+    data.extend_from_slice(&BORROW_CAP.to_le_bytes());
+    data.extend_from_slice(&m.total_deposits.to_le_bytes());
+    data.extend_from_slice(&m.total_borrows.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account: [8 disc][32 owner][32 market][8 deposited][8 borrowed][1 bump]
+fn serialize_position(
+    owner: &Pubkey,
+    market: &Pubkey,
+    deposited: u64,
+    borrowed: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(89);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(market.as_ref());
+    data.extend_from_slice(&deposited.to_le_bytes());
+    data.extend_from_slice(&borrowed.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The variable part of the market's state.
+struct MarketState {
+    price_bps: u64,
+    total_deposits: u64,
+    total_borrows: u64,
+}
+
+/// 100 SOL from other depositors plus the attacker's 1 SOL.
+fn seeded_market(price_bps: u64) -> MarketState {
+    MarketState {
+        price_bps,
+        total_deposits: 101 * SOL,
+        total_borrows: 0,
+    }
+}
+
+struct Setup {
+    config: Pubkey,
+    market: Pubkey,
+    position: Pubkey,
+    owner: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// `protocol_deposits` is the config's running total across all markets.
+    fn new(market_state: MarketState, protocol_deposits: u64) -> Self {
+        let admin = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+        let (market, market_bump) = Pubkey::find_program_address(&[b"market", &[0]], &PROGRAM_ID);
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", market.as_ref(), owner.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                config,
+                owned_account(
+                    &serialize_config(&admin, GLOBAL_CAP, protocol_deposits, config_bump),
+                    10_000_000,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                market,
+                owned_account(
+                    &serialize_market(&market_state, market_bump),
+                    market_state.total_deposits - market_state.total_borrows,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                position,
+                owned_account(
+                    &serialize_position(&owner, &market, SOL, 0, position_bump),
+                    10_000_000,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                owner,
+                AccountSharedData::new(OWNER_LAMPORTS, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            config,
+            market,
+            position,
+            owner,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn deposit(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new(self.market, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.owner, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn borrow(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.market, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.owner, true),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_glitched_price_drains_market() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The price glitches to 1000x. The attacker's 1 SOL is "worth"
+    // 1_000 SOL, so at 50% LTV the position may borrow 500 SOL.
+    //
+    // Expected: borrow_vulnerable(100 SOL) SUCCEEDS — the market keeps only
+    //           1 SOL of the 101 SOL it held.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "exposure_caps");
+    let mut s = Setup::new(seeded_market(PRICE_GLITCH), 101 * SOL);
+    let (market, owner) = (s.market, s.owner);
+
+    let ix = s.borrow("borrow_vulnerable", 100 * SOL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&market).lamports(SOL).build(),
+            Check::account(&owner)
+                .lamports(OWNER_LAMPORTS + 100 * SOL)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_borrow_cap_bounds_glitch() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same glitch, same borrow, through borrow_secure.
+    //
+    // Expected: FAILS with BorrowCapExceeded (6002). The LTV check passed;
+    //           the cap is what stopped it.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "exposure_caps");
+    let mut s = Setup::new(seeded_market(PRICE_GLITCH), 101 * SOL);
+
+    let ix = s.borrow("borrow_secure", 100 * SOL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(BORROW_CAP_EXCEEDED))],
+    );
+}
+
+#[test]
+fn sanity_secure_borrow_stops_at_cap() {
+    // -----------------------------------------------------------------------
+    // SANITY: With the glitch still in place, borrow exactly the 10 SOL cap,
+    // then one lamport more.
+    //
+    // Expected: 10 SOL SUCCEEDS; the extra lamport FAILS with
+    //           BorrowCapExceeded (6002). The glitch cost at most the cap.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "exposure_caps");
+    let mut s = Setup::new(seeded_market(PRICE_GLITCH), 101 * SOL);
+    let market = s.market;
+
+    let ix = s.borrow("borrow_secure", BORROW_CAP);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&market)
+                .lamports(101 * SOL - BORROW_CAP)
+                .build(),
+        ],
+    );
+
+    let ix = s.borrow("borrow_secure", 1);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(BORROW_CAP_EXCEEDED))],
+    );
+}
+
+#[test]
+fn exploit_deposit_past_market_cap() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The market holds 101 SOL against a 200 SOL cap. A whale
+    // deposits 300 SOL through deposit_vulnerable.
+    //
+    // Expected: SUCCEEDS — the market grows to 401 SOL, twice its cap, and
+    //           all of it is exposed to the market's oracle.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "exposure_caps");
+    let mut s = Setup::new(seeded_market(PRICE_1X), 101 * SOL);
+    let market = s.market;
+
+    let ix = s.deposit("deposit_vulnerable", 300 * SOL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&market).lamports(401 * SOL).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_market_cap_boundary() {
+    // -----------------------------------------------------------------------
+    // SECURE: Fill the market to exactly its 200 SOL cap (99 SOL more), then
+    // deposit one more lamport.
+    //
+    // Expected: 99 SOL SUCCEEDS; the extra lamport FAILS with
+    //           MarketCapExceeded (6000).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "exposure_caps");
+    let mut s = Setup::new(seeded_market(PRICE_1X), 101 * SOL);
+    let market = s.market;
+
+    let ix = s.deposit("deposit_secure", DEPOSIT_CAP - 101 * SOL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&market).lamports(DEPOSIT_CAP).build(),
+        ],
+    );
+
+    let ix = s.deposit("deposit_secure", 1);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(MARKET_CAP_EXCEEDED))],
+    );
+}
+
+#[test]
+fn secure_global_cap_boundary() {
+    // -----------------------------------------------------------------------
+    // SECURE: Other markets have brought the protocol to 995 SOL of its
+    // 1_000 SOL global cap. This market has 99 SOL of room.
+    //
+    // Expected: 5 SOL SUCCEEDS; one more lamport FAILS with
+    //           GlobalCapExceeded (6001) — the global cap binds first.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "exposure_caps");
+    let mut s = Setup::new(seeded_market(PRICE_1X), GLOBAL_CAP - 5 * SOL);
+
+    let ix = s.deposit("deposit_secure", 5 * SOL);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.deposit("deposit_secure", 1);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(GLOBAL_CAP_EXCEEDED))],
+    );
+}
@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("13PPHGqwzjT1zDC3oKHjpvYXZpvrNkG69AhyMw6kGee4");
+
+/// # Aliased Admin Check
+///
+/// ## The Vulnerability
+/// The instruction has an admin check and a signer, but they belong to
+/// different accounts. The handler compares `config.admin` against the
+/// `admin` account's key. `admin` is an unchecked account, so anyone can pass
+/// the real admin's pubkey there without its signature. The signer is a
+/// separate `payer` account, and the attacker signs that one. The key check
+/// passes, the signer check passes, and nothing ties them together.
+///
+/// ## Why It Matters
+/// This is Pattern 01 (missing signer check) in disguise. Reviewers look for
+/// a `Signer` and for a key comparison, find both, and move on. The question
+/// they need to ask is whether the account that was compared is the account
+/// that signed.
+///
+/// ## The Fix
+/// Compare against the signer itself: `admin: Signer<'info>` with
+/// `has_one = admin` on the config. One account carries both the identity and
+/// the signature.
+#[program]
+pub mod aliased_admin {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Admin key checked on one account, signature on another
+    // ============================================================================
+    // ISSUE: `require_keys_eq!` validates `admin`, an UncheckedAccount. The
+    //        only `Signer` is `payer`, which is never compared to anything.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker reads config.admin from chain
+    //   2. Passes that pubkey as `admin` (unsigned) and themselves as `payer`
+    //   3. The key check passes: admin.key() == config.admin
+    //   4. The signer check passes: payer signed
+    //   5. The treasury is withdrawn to the attacker
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<WithdrawVulnerable>, amount: u64) -> Result<()> {
+        // VULNERABLE: right key, wrong account — `admin` never signed
+        require_keys_eq!(
+            ctx.accounts.config.admin,
+            ctx.accounts.admin.key(),
+            ErrorCode::Unauthorized
+        );
+
+        let treasury = ctx.accounts.config.to_account_info();
+        **treasury.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.recipient.try_borrow_mut_lamports()? += amount;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.payer.key(),
+            amount = amount,
+            admin = ctx.accounts.admin.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: The compared account is the signer
+    // ============================================================================
+    // FIX: `admin` is a `Signer`, and `has_one = admin` compares it against
+    //      config.admin. Only the real admin's signature gets through.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<WithdrawSecure>, amount: u64) -> Result<()> {
+        let treasury = ctx.accounts.config.to_account_info();
+        **treasury.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.recipient.try_borrow_mut_lamports()? += amount;
+
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.admin.key(),
+            amount = amount,
+            admin = ctx.accounts.admin.key()
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVulnerable<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    /// CHECK: Compared against config.admin in the handler.
+    pub admin: UncheckedAccount<'info>, // <-- checked key, no signature
+    pub payer: Signer<'info>, // <-- signature, never compared
+    /// CHECK: Receives lamports.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSecure<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>, // <-- checked key AND signature
+    /// CHECK: Receives lamports.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+/// Lamports held above rent are the protocol treasury.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Caller is not the admin")]
+    Unauthorized,
+}
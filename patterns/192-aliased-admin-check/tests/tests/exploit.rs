@@ -0,0 +1,208 @@
+/// # Pattern 192: Aliased Admin Check — Mollusk Exploit Tests
+///
+/// The config PDA holds a 10 SOL treasury; `config.admin` is the real admin.
+///
+/// - Test 1: EXPLOIT — the attacker passes the admin's pubkey unsigned and
+///   signs as `payer`; the treasury goes to the attacker.
+/// - Test 2: SECURE — the same unsigned admin is rejected by `Signer`.
+/// - Test 3: SECURE — the attacker signing in the admin slot fails
+///   `has_one`.
+/// - Test 4: SANITY — the real admin, signing, withdraws.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("13PPHGqwzjT1zDC3oKHjpvYXZpvrNkG69AhyMw6kGee4");
+
+const TREASURY: u64 = 10_000_000_000;
+const AMOUNT: u64 = 5_000_000_000;
+const WALLET: u64 = 1_000_000;
+
+// Anchor error codes
+const ACCOUNT_NOT_SIGNER: u32 = 3010;
+const CONSTRAINT_HAS_ONE: u32 = 2001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Config account: [8 disc][32 admin][1 bump]
+fn serialize_config(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.push(bump);
+    data
+}
+
+/// The config PDA holding the treasury. Returns (config, account, admin).
+fn config_account() -> (Pubkey, AccountSharedData, Pubkey) {
+    let admin = Pubkey::new_unique();
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+    let data = serialize_config(&admin, bump);
+    let mut account = AccountSharedData::new(TREASURY, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (config, account, admin)
+}
+
+fn wallet() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn amount_data(name: &str) -> Vec<u8> {
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&AMOUNT.to_le_bytes());
+    data
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_unsigned_admin_key_passes() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: `admin` = the real admin's pubkey, NOT signed.
+    //          `payer` = attacker, signed. `recipient` = attacker's wallet.
+    //
+    // Expected: SUCCEEDS — require_keys_eq! compared the right key on the
+    //           wrong account; 5 SOL moves to the attacker.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "aliased_admin");
+    let (config, config_account, admin) = config_account();
+    let attacker = Pubkey::new_unique();
+    let loot = Pubkey::new_unique();
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &amount_data("withdraw_vulnerable"),
+        vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(admin, false), // <-- not a signer
+            AccountMeta::new_readonly(attacker, true),
+            AccountMeta::new(loot, false),
+        ],
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &[
+            (config, config_account),
+            (admin, wallet()),
+            (attacker, wallet()),
+            (loot, wallet()),
+        ],
+        &[
+            Check::success(),
+            Check::account(&config).lamports(TREASURY - AMOUNT).build(),
+            Check::account(&loot).lamports(WALLET + AMOUNT).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_unsigned_admin() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same unsigned admin pubkey, through withdraw_secure.
+    //
+    // Expected: FAILS with AccountNotSigner (3010).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "aliased_admin");
+    let (config, config_account, admin) = config_account();
+    let loot = Pubkey::new_unique();
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &amount_data("withdraw_secure"),
+        vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(admin, false),
+            AccountMeta::new(loot, false),
+        ],
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &[
+            (config, config_account),
+            (admin, wallet()),
+            (loot, wallet()),
+        ],
+        &[Check::err(ProgramError::Custom(ACCOUNT_NOT_SIGNER))],
+    );
+}
+
+#[test]
+fn secure_rejects_attacker_signing_as_admin() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker moves their signature into the admin slot.
+    //
+    // Expected: FAILS with ConstraintHasOne (2001) — the signer isn't
+    //           config.admin.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "aliased_admin");
+    let (config, config_account, _admin) = config_account();
+    let attacker = Pubkey::new_unique();
+    let loot = Pubkey::new_unique();
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &amount_data("withdraw_secure"),
+        vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(attacker, true),
+            AccountMeta::new(loot, false),
+        ],
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &[
+            (config, config_account),
+            (attacker, wallet()),
+            (loot, wallet()),
+        ],
+        &[Check::err(ProgramError::Custom(CONSTRAINT_HAS_ONE))],
+    );
+}
+
+#[test]
+fn sanity_admin_withdraws_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The real admin signs and withdraws to a recipient.
+    //
+    // Expected: SUCCEEDS — 5 SOL moves from the treasury to the recipient.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "aliased_admin");
+    let (config, config_account, admin) = config_account();
+    let recipient = Pubkey::new_unique();
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &amount_data("withdraw_secure"),
+        vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(admin, true),
+            AccountMeta::new(recipient, false),
+        ],
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &[
+            (config, config_account),
+            (admin, wallet()),
+            (recipient, wallet()),
+        ],
+        &[
+            Check::success(),
+            Check::account(&config).lamports(TREASURY - AMOUNT).build(),
+            Check::account(&recipient).lamports(WALLET + AMOUNT).build(),
+        ],
+    );
+}
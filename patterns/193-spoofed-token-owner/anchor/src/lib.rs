@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use demo_log::{demo_log, Mode};
+
+declare_id!("FNUhviqURhXejNitBzyz5p3RXLN8wyFKmZkB8QN2Ss3K");
+
+/// Members must hold at least this many membership tokens to claim.
+pub const MIN_BALANCE: u64 = 1_000;
+
+/// # Spoofed Token Owner
+///
+/// ## The Vulnerability
+/// "Whoever owns a token account holding the membership mint may claim."
+/// The program takes the token account as an unchecked account and
+/// deserializes it as an SPL token account, then checks `mint`, `owner`, and
+/// `amount`. It never checks which program owns the account. An attacker
+/// deploys a program (or uses any account they control) whose data has the
+/// SPL layout: the right mint, their own wallet as owner, any amount they
+/// like.
+///
+/// ## Why It Matters
+/// This is Pattern 02 (missing owner check) and Pattern 06 (type cosplay)
+/// combined, in the token domain. Every field the program checks is real
+/// data, correctly parsed, but only the token program's own accounts make
+/// those fields mean anything. Token gating, holder airdrops, and
+/// governance weights all depend on it.
+///
+/// ## The Fix
+/// Take the account as `InterfaceAccount<'info, TokenAccount>`. Anchor
+/// checks that SPL Token or Token-2022 owns it before deserializing, so the
+/// `token::mint` and `token::authority` constraints read real balances.
+#[program]
+pub mod token_owner_spoof {
+    use super::*;
+
+    /// Create the config PDA, which also holds the reward pool.
+    pub fn initialize(ctx: Context<Initialize>, reward: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.member_mint = ctx.accounts.member_mint.key();
+        config.reward = reward;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Token account parsed without checking its owning program
+    // ============================================================================
+    // ISSUE: `TokenAccount::try_deserialize` checks only the layout. Any
+    //        account with 165 well-formed bytes "is" a token account.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker creates an account owned by their own program
+    //   2. Writes SPL layout bytes: mint = member_mint, owner = attacker,
+    //      amount = 1_000_000
+    //   3. Calls claim_vulnerable with it as `membership`
+    //   4. mint, owner, and amount checks all pass, and the reward is paid
+    //   5. Repeat from as many wallets as they like
+    // ============================================================================
+    pub fn claim_vulnerable(ctx: Context<ClaimVulnerable>) -> Result<()> {
+        let info = ctx.accounts.membership.to_account_info();
+        let data = info.try_borrow_data()?;
+        // VULNERABLE: deserializes whatever program owns the account
+        let membership = TokenAccount::try_deserialize(&mut &data[..])?;
+
+        require_keys_eq!(
+            membership.mint,
+            ctx.accounts.config.member_mint,
+            ErrorCode::NotAMember
+        );
+        require_keys_eq!(
+            membership.owner,
+            ctx.accounts.claimer.key(),
+            ErrorCode::NotAMember
+        );
+        require!(membership.amount >= MIN_BALANCE, ErrorCode::NotAMember);
+        drop(data);
+
+        pay_reward(&ctx.accounts.config, &ctx.accounts.claimer)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "claim",
+            actor = ctx.accounts.claimer.key(),
+            amount = ctx.accounts.config.reward,
+            held = membership.amount,
+            token_program = info.owner
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: InterfaceAccount checks the token program, then the fields
+    // ============================================================================
+    // FIX: `InterfaceAccount<TokenAccount>` rejects accounts not owned by
+    //      SPL Token or Token-2022 before any field is read. The mint,
+    //      authority, and balance constraints then read real balances.
+    // ============================================================================
+    pub fn claim_secure(ctx: Context<ClaimSecure>) -> Result<()> {
+        pay_reward(&ctx.accounts.config, &ctx.accounts.claimer)?;
+        demo_log!(
+            Mode::Secure,
+            "claim",
+            actor = ctx.accounts.claimer.key(),
+            amount = ctx.accounts.config.reward,
+            held = ctx.accounts.membership.amount
+        );
+        Ok(())
+    }
+}
+
+fn pay_reward<'info>(config: &Account<'info, Config>, claimer: &Signer<'info>) -> Result<()> {
+    let reward = config.reward;
+    **config.to_account_info().try_borrow_mut_lamports()? -= reward;
+    **claimer.try_borrow_mut_lamports()? += reward;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    pub member_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVulnerable<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    /// CHECK: VULNERABLE — parsed as a token account, owner never checked
+    pub membership: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSecure<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        token::mint = config.member_mint,
+        token::authority = claimer,
+        constraint = membership.amount >= MIN_BALANCE @ ErrorCode::NotAMember
+    )]
+    pub membership: InterfaceAccount<'info, TokenAccount>, // <-- owner program checked
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+}
+
+/// Lamports held above rent are the reward pool.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,       // 32 bytes
+    pub member_mint: Pubkey, // 32 bytes
+    pub reward: u64,         //  8 bytes
+    pub bump: u8,            //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Claimer does not hold enough membership tokens")]
+    NotAMember,
+}
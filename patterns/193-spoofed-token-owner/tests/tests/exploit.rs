@@ -0,0 +1,205 @@
+/// # Pattern 193: Spoofed Token Owner — Mollusk Exploit Tests
+///
+/// Members holding at least 1_000 membership tokens may claim a 1 SOL
+/// reward. No instruction here CPIs into SPL Token, so token accounts are
+/// plain 165-byte accounts whose owner we choose.
+///
+/// - Test 1: EXPLOIT — a token-shaped account owned by a fake program,
+///   claiming 1_000_000 tokens, gets the reward.
+/// - Test 2: SECURE — the same account is rejected: wrong owning program.
+/// - Test 3: SANITY — a real SPL Token account at the minimum claims.
+/// - Test 4: SECURE — a real SPL Token account below the minimum doesn't.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator};
+use test_harness::token::token_account;
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("FNUhviqURhXejNitBzyz5p3RXLN8wyFKmZkB8QN2Ss3K");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const REWARD: u64 = 1_000_000_000;
+const POOL: u64 = 10_000_000_000;
+const WALLET: u64 = 1_000_000;
+const MIN_BALANCE: u64 = 1_000;
+
+// Error codes
+const ACCOUNT_OWNED_BY_WRONG_PROGRAM: u32 = 3007;
+const NOT_A_MEMBER: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Config account: [8 disc][32 admin][32 member_mint][8 reward][1 bump]
+fn serialize_config(admin: &Pubkey, member_mint: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(member_mint.as_ref());
+    data.extend_from_slice(&REWARD.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The config PDA holding the reward pool. Returns (config, account, member_mint).
+fn config_account() -> (Pubkey, AccountSharedData, Pubkey) {
+    let admin = Pubkey::new_unique();
+    let member_mint = Pubkey::new_unique();
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+    let data = serialize_config(&admin, &member_mint, bump);
+    let mut account = AccountSharedData::new(POOL, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (config, account, member_mint)
+}
+
+fn claim_ix(name: &str, config: Pubkey, membership: Pubkey, claimer: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator(name),
+        vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(membership, false),
+            AccountMeta::new(claimer, true),
+        ],
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_fake_token_program_account_claims() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: `membership` has a valid SPL layout — member mint, attacker
+    // as owner, 1_000_000 tokens — but a fake program owns it.
+    //
+    // Expected: SUCCEEDS — every field check passes on bytes the attacker
+    //           wrote; the 1 SOL reward is paid.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "token_owner_spoof");
+    let (config, config_account, member_mint) = config_account();
+    let attacker = Pubkey::new_unique();
+    let fake_token_program = Pubkey::new_unique();
+    let membership = Pubkey::new_unique();
+
+    mollusk.process_and_validate_instruction(
+        &claim_ix("claim_vulnerable", config, membership, attacker),
+        &[
+            (config, config_account),
+            (
+                membership,
+                token_account(&fake_token_program, &member_mint, &attacker, 1_000_000),
+            ),
+            (
+                attacker,
+                AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID),
+            ),
+        ],
+        &[
+            Check::success(),
+            Check::account(&attacker).lamports(WALLET + REWARD).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_fake_token_program_account() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same fake-owned account, through claim_secure.
+    //
+    // Expected: FAILS with AccountOwnedByWrongProgram (3007) — rejected
+    //           before a single field is read.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "token_owner_spoof");
+    let (config, config_account, member_mint) = config_account();
+    let attacker = Pubkey::new_unique();
+    let fake_token_program = Pubkey::new_unique();
+    let membership = Pubkey::new_unique();
+
+    mollusk.process_and_validate_instruction(
+        &claim_ix("claim_secure", config, membership, attacker),
+        &[
+            (config, config_account),
+            (
+                membership,
+                token_account(&fake_token_program, &member_mint, &attacker, 1_000_000),
+            ),
+            (
+                attacker,
+                AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID),
+            ),
+        ],
+        &[Check::err(ProgramError::Custom(
+            ACCOUNT_OWNED_BY_WRONG_PROGRAM,
+        ))],
+    );
+}
+
+#[test]
+fn sanity_real_holder_claims_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: A real SPL Token account holding exactly MIN_BALANCE.
+    //
+    // Expected: SUCCEEDS — the member is paid 1 SOL.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "token_owner_spoof");
+    let (config, config_account, member_mint) = config_account();
+    let member = Pubkey::new_unique();
+    let membership = Pubkey::new_unique();
+
+    mollusk.process_and_validate_instruction(
+        &claim_ix("claim_secure", config, membership, member),
+        &[
+            (config, config_account),
+            (
+                membership,
+                token_account(&TOKEN_PROGRAM_ID, &member_mint, &member, MIN_BALANCE),
+            ),
+            (
+                member,
+                AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID),
+            ),
+        ],
+        &[
+            Check::success(),
+            Check::account(&config).lamports(POOL - REWARD).build(),
+            Check::account(&member).lamports(WALLET + REWARD).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_real_account_below_minimum() {
+    // -----------------------------------------------------------------------
+    // SECURE: A real SPL Token account one token short of MIN_BALANCE.
+    //
+    // Expected: FAILS with NotAMember (6000) — the balance constraint now
+    //           reads a real balance.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "token_owner_spoof");
+    let (config, config_account, member_mint) = config_account();
+    let member = Pubkey::new_unique();
+    let membership = Pubkey::new_unique();
+
+    mollusk.process_and_validate_instruction(
+        &claim_ix("claim_secure", config, membership, member),
+        &[
+            (config, config_account),
+            (
+                membership,
+                token_account(&TOKEN_PROGRAM_ID, &member_mint, &member, MIN_BALANCE - 1),
+            ),
+            (
+                member,
+                AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID),
+            ),
+        ],
+        &[Check::err(ProgramError::Custom(NOT_A_MEMBER))],
+    );
+}
@@ -0,0 +1,249 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        default_account_state::DefaultAccountState, BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::{AccountState, Mint as MintState},
+};
+use anchor_spl::token_interface::{
+    self, Mint, ThawAccount, TokenAccount, TokenInterface, TransferChecked,
+};
+use demo_log::{demo_log, Mode};
+
+declare_id!("J5xfPRxyBCczWL6nCMqUJi3KuZLV9C2UsTb7P4jMguoW");
+
+/// # Frozen-by-Default Mint
+///
+/// ## The Vulnerability
+/// A Token-2022 mint can carry the `DefaultAccountState` extension. With
+/// state `Frozen`, every token account created for the mint starts frozen,
+/// including the protocol's vault. Transfers into or out of a frozen
+/// account fail. Only the mint's freeze authority can thaw it. The
+/// vulnerable registration lists any mint without reading its extensions,
+/// so the market opens and every deposit into it fails.
+///
+/// ## Why It Matters
+/// It's a denial of service that needs no exploit transaction. A token
+/// issuer (or anyone who lists a token) creates the market, and it never
+/// works. If the issuer later thaws accounts selectively, they decide
+/// which users can deposit or withdraw.
+///
+/// ## The Fix
+/// Read the mint's extensions at registration. A frozen default is
+/// accepted only when the mint's freeze authority is the protocol's vault
+/// authority PDA. The market then records that its vault must be thawed,
+/// and `thaw_vault` does it. Any other frozen-default mint is rejected.
+#[program]
+pub mod frozen_default_mint {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Mint listed without reading its extensions
+    // ============================================================================
+    // ISSUE: `InterfaceAccount<Mint>` checks the owner and the base layout.
+    //        It says nothing about the extensions after it.
+    //
+    // ATTACK SCENARIO:
+    //   1. Issuer creates a Token-2022 mint with DefaultAccountState=Frozen
+    //      and keeps the freeze authority
+    //   2. The mint is registered; the market reports itself open
+    //   3. The vault token account is created — frozen
+    //   4. Every deposit fails with the token program's AccountFrozen error
+    //   5. The issuer thaws the accounts of whoever they choose
+    // ============================================================================
+    pub fn register_mint_vulnerable(ctx: Context<RegisterMint>) -> Result<()> {
+        // VULNERABLE: the mint's default account state is never read
+        write_market(ctx, false, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Frozen default accepted only with the freeze authority
+    // ============================================================================
+    // FIX: Unpack the mint with its extensions. If new accounts start
+    //      frozen, the protocol must hold the freeze authority so it can
+    //      thaw its own vault; otherwise the listing is refused.
+    // ============================================================================
+    pub fn register_mint_secure(ctx: Context<RegisterMint>) -> Result<()> {
+        let frozen = frozen_by_default(&ctx.accounts.mint.to_account_info())?;
+        if frozen {
+            // SECURE: only a protocol-controlled freeze authority can thaw
+            require!(
+                ctx.accounts.mint.freeze_authority
+                    == COption::Some(ctx.accounts.vault_authority.key()),
+                ErrorCode::FrozenByDefault
+            );
+        }
+        write_market(ctx, frozen, Mode::Secure)
+    }
+
+    /// Thaw the market's vault. Only needed, and only possible, for mints
+    /// whose freeze authority is the vault authority PDA.
+    pub fn thaw_vault(ctx: Context<ThawVault>) -> Result<()> {
+        require!(ctx.accounts.market.thaw_required, ErrorCode::NoThawRequired);
+
+        let mint = ctx.accounts.mint.key();
+        let seeds: &[&[u8]] = &[
+            b"vault_authority",
+            mint.as_ref(),
+            &[ctx.accounts.market.vault_authority_bump],
+        ];
+        token_interface::thaw_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            ThawAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        demo_log!(
+            Mode::Setup,
+            "thaw_vault",
+            mint = mint,
+            vault = ctx.accounts.vault.key()
+        );
+        Ok(())
+    }
+
+    /// Move tokens from the depositor into the market's vault. Fails inside
+    /// the token program if either account is frozen.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.from.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// Does the mint create new token accounts in the `Frozen` state?
+fn frozen_by_default(mint: &AccountInfo) -> Result<bool> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    Ok(mint
+        .get_extension::<DefaultAccountState>()
+        .map(|ext| ext.state == AccountState::Frozen as u8)
+        .unwrap_or(false))
+}
+
+fn write_market(ctx: Context<RegisterMint>, thaw_required: bool, mode: Mode) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.admin = ctx.accounts.admin.key();
+    market.mint = ctx.accounts.mint.key();
+    market.thaw_required = thaw_required;
+    market.vault_authority_bump = ctx.bumps.vault_authority;
+    market.bump = ctx.bumps.market;
+
+    demo_log!(
+        mode,
+        "register_mint",
+        actor = market.admin,
+        mint = market.mint,
+        thaw_required = thaw_required
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct RegisterMint<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market", mint.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: PDA that owns the vault and signs its thaw.
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ThawVault<'info> {
+    #[account(seeds = [b"market", mint.key().as_ref()], bump = market.bump, has_one = mint)]
+    pub market: Account<'info, Market>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA signer for the thaw.
+    #[account(
+        seeds = [b"vault_authority", mint.key().as_ref()],
+        bump = market.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(seeds = [b"market", mint.key().as_ref()], bump = market.bump, has_one = mint)]
+    pub market: Account<'info, Market>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = depositor)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Vault owner, matched by the token::authority constraint.
+    #[account(
+        seeds = [b"vault_authority", mint.key().as_ref()],
+        bump = market.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+    pub depositor: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub admin: Pubkey,            // 32 bytes
+    pub mint: Pubkey,             // 32 bytes
+    pub thaw_required: bool,      //  1 byte
+    pub vault_authority_bump: u8, //  1 byte
+    pub bump: u8,                 //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Mint freezes new accounts by default and the protocol cannot thaw them")]
+    FrozenByDefault,
+    #[msg("This market's vault does not need thawing")]
+    NoThawRequired,
+}
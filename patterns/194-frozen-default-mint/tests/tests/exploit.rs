@@ -0,0 +1,232 @@
+/// # Pattern 194: Frozen-by-Default Mint — Mollusk Exploit Tests
+///
+/// An issuer lists a Token-2022 mint whose `DefaultAccountState` extension
+/// is `Frozen`. Registration doesn't CPI into the token program, so the
+/// mints here are hand-built Token-2022 accounts.
+///
+/// - Test 1: EXPLOIT — the vulnerable registration opens a market for the
+///   frozen-by-default mint, with no thaw path.
+/// - Test 2: SECURE — the same mint is rejected: the issuer, not the
+///   protocol, holds the freeze authority.
+/// - Test 3: SANITY — a frozen-by-default mint whose freeze authority is the
+///   vault authority PDA registers, marked as needing a thaw.
+/// - Test 4: SANITY — a Token-2022 mint without the extension registers.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, owned_account};
+use test_harness::token::{push_extension, Mint, FROZEN};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("J5xfPRxyBCczWL6nCMqUJi3KuZLV9C2UsTb7P4jMguoW");
+const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+// Token-2022 layout constants
+const EXTENSION_DEFAULT_ACCOUNT_STATE: u16 = 6;
+
+// Error codes
+const FROZEN_BY_DEFAULT: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Market account:
+///   [8 disc][32 admin][32 mint][1 thaw_required][1 vault_authority_bump][1 bump]
+fn serialize_market(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    thaw_required: bool,
+    vault_authority_bump: u8,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(75);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.push(thaw_required as u8);
+    data.push(vault_authority_bump);
+    data.push(bump);
+    data
+}
+
+/// Serialize a Token-2022 mint with `freeze_authority`. With
+/// `default_frozen`, it carries a DefaultAccountState extension: [1 state]
+fn serialize_mint(freeze_authority: &Pubkey, default_frozen: bool) -> Vec<u8> {
+    let mut data = Mint {
+        mint_authority: Some(Pubkey::new_unique()),
+        supply: 1_000_000,
+        decimals: 6,
+        freeze_authority: Some(*freeze_authority),
+    }
+    .pack();
+    if default_frozen {
+        push_extension(&mut data, EXTENSION_DEFAULT_ACCOUNT_STATE, &[FROZEN]);
+    }
+    data
+}
+
+fn mint_account(freeze_authority: &Pubkey, default_frozen: bool) -> AccountSharedData {
+    owned_account(
+        &serialize_mint(freeze_authority, default_frozen),
+        10_000_000,
+        &TOKEN_2022_PROGRAM_ID,
+    )
+}
+
+/// PDAs for `mint`: (market, market_bump, vault_authority, vault_authority_bump)
+fn pdas(mint: &Pubkey) -> (Pubkey, u8, Pubkey, u8) {
+    let (market, market_bump) =
+        Pubkey::find_program_address(&[b"market", mint.as_ref()], &PROGRAM_ID);
+    let (vault_authority, vault_authority_bump) =
+        Pubkey::find_program_address(&[b"vault_authority", mint.as_ref()], &PROGRAM_ID);
+    (market, market_bump, vault_authority, vault_authority_bump)
+}
+
+/// Run `name` for `mint_account`. `Ok(thaw_required)` expects the market
+/// to open with that flag; `Err(code)` expects that custom error.
+fn register(
+    name: &str,
+    mint: Pubkey,
+    mint_account: AccountSharedData,
+    admin: Pubkey,
+    expected: Result<bool, u32>,
+) {
+    let mollusk = Mollusk::new(&PROGRAM_ID, "frozen_default_mint");
+    let (market, market_bump, vault_authority, vault_authority_bump) = pdas(&mint);
+    let opened = expected.map(|thaw_required| {
+        serialize_market(
+            &admin,
+            &mint,
+            thaw_required,
+            vault_authority_bump,
+            market_bump,
+        )
+    });
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator(name),
+        vec![
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new(admin, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &[
+            (market, AccountSharedData::default()),
+            (mint, mint_account),
+            (vault_authority, AccountSharedData::default()),
+            (
+                admin,
+                AccountSharedData::new(1_000_000_000, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ],
+        &match &opened {
+            Ok(data) => vec![Check::success(), Check::account(&market).data(data).build()],
+            Err(code) => vec![Check::err(ProgramError::Custom(*code))],
+        },
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_frozen_default_mint_registered() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The issuer's mint freezes every new token account, and the
+    // issuer keeps the freeze authority.
+    //
+    // Expected: SUCCEEDS — the market is open with thaw_required = false.
+    //           Its vault will be created frozen and nothing in the
+    //           protocol can thaw it; every deposit fails in Token-2022.
+    // -----------------------------------------------------------------------
+    let issuer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    register(
+        "register_mint_vulnerable",
+        mint,
+        mint_account(&issuer, true),
+        issuer,
+        Ok(false),
+    );
+}
+
+#[test]
+fn secure_rejects_frozen_default_mint() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same mint through register_mint_secure.
+    //
+    // Expected: FAILS with FrozenByDefault (6000) — new accounts start
+    //           frozen and only the issuer could thaw them.
+    // -----------------------------------------------------------------------
+    let issuer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    register(
+        "register_mint_secure",
+        mint,
+        mint_account(&issuer, true),
+        issuer,
+        Err(FROZEN_BY_DEFAULT),
+    );
+}
+
+#[test]
+fn sanity_protocol_held_freeze_authority_registers_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: A frozen-by-default mint whose freeze authority was handed to
+    // the market's vault authority PDA.
+    //
+    // Expected: SUCCEEDS — the market records thaw_required = true, so the
+    //           vault is thawed with `thaw_vault` before deposits open.
+    // -----------------------------------------------------------------------
+    let admin = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (_, _, vault_authority, _) = pdas(&mint);
+
+    register(
+        "register_mint_secure",
+        mint,
+        mint_account(&vault_authority, true),
+        admin,
+        Ok(true),
+    );
+}
+
+#[test]
+fn sanity_plain_mint_registers_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: A Token-2022 mint without DefaultAccountState.
+    //
+    // Expected: SUCCEEDS — thaw_required = false; new accounts start
+    //           initialized.
+    // -----------------------------------------------------------------------
+    let admin = Pubkey::new_unique();
+    let issuer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    register(
+        "register_mint_secure",
+        mint,
+        mint_account(&issuer, false),
+        admin,
+        Ok(false),
+    );
+}
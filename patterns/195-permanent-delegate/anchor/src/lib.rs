@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{permanent_delegate::get_permanent_delegate, StateWithExtensions},
+    state::Mint as MintState,
+};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use demo_log::{demo_log, Mode};
+
+declare_id!("3hKCm4rEX4aj1KStxx42kd1uHmoLY8nuFAP55GZhazpG");
+
+/// # Permanent Delegate Drain
+///
+/// ## The Vulnerability
+/// Token-2022's `PermanentDelegate` extension names a key that can transfer
+/// or burn tokens from *any* account of the mint, without the owner's
+/// signature. That includes accounts a program controls through a PDA. A
+/// protocol that accepts such a mint as collateral holds tokens that the
+/// delegate can take back whenever it likes.
+///
+/// ## Why It Matters
+/// The vault's balance and the protocol's books diverge the moment the
+/// delegate moves the tokens. Depositors still have their positions, loans
+/// taken against the collateral are still open, and nothing backs either.
+/// No bug in the protocol's own instructions is needed.
+///
+/// ## The Fix
+/// Read the mint's extensions when it's registered and refuse any mint that
+/// has a permanent delegate. A protocol that wants such tokens anyway has to
+/// treat the delegate as a counterparty and price it (haircuts, caps), not
+/// hold the tokens as if they were its own.
+#[program]
+pub mod permanent_delegate {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Collateral mint accepted without reading its extensions
+    // ============================================================================
+    // ISSUE: `InterfaceAccount<Mint>` validates the owner program and the
+    //        base mint. The permanent delegate lives in an extension after it.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker creates a Token-2022 mint with themselves as permanent
+    //      delegate and gets it listed as collateral
+    //   2. Users deposit; the vault holds their tokens
+    //   3. The attacker signs a transfer_checked from the vault to their
+    //      own account. Token-2022 accepts the permanent delegate in place
+    //      of the vault authority
+    //   4. The vault is empty; total_deposits still says it isn't
+    // ============================================================================
+    pub fn register_collateral_vulnerable(ctx: Context<RegisterCollateral>) -> Result<()> {
+        // VULNERABLE: a permanent delegate on the mint is never looked for
+        write_collateral(ctx, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Mints with a permanent delegate refused
+    // ============================================================================
+    // FIX: Unpack the mint with its extensions and reject it if a permanent
+    //      delegate is set. Tokens anyone else can move aren't custody.
+    // ============================================================================
+    pub fn register_collateral_secure(ctx: Context<RegisterCollateral>) -> Result<()> {
+        let delegate = permanent_delegate_of(&ctx.accounts.mint.to_account_info())?;
+        // SECURE: no key outside the protocol may move vault tokens
+        require!(delegate.is_none(), ErrorCode::PermanentDelegate);
+        write_collateral(ctx, Mode::Secure)
+    }
+
+    /// Move tokens from the depositor into the collateral vault.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.from.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        let collateral = &mut ctx.accounts.collateral;
+        collateral.total_deposits = collateral
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount,
+            total_deposits = collateral.total_deposits
+        );
+        Ok(())
+    }
+}
+
+/// The mint's permanent delegate, if it has one.
+fn permanent_delegate_of(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    Ok(get_permanent_delegate(&mint))
+}
+
+fn write_collateral(ctx: Context<RegisterCollateral>, mode: Mode) -> Result<()> {
+    let collateral = &mut ctx.accounts.collateral;
+    collateral.admin = ctx.accounts.admin.key();
+    collateral.mint = ctx.accounts.mint.key();
+    collateral.total_deposits = 0;
+    collateral.vault_authority_bump = ctx.bumps.vault_authority;
+    collateral.bump = ctx.bumps.collateral;
+
+    demo_log!(
+        mode,
+        "register_collateral",
+        actor = collateral.admin,
+        mint = collateral.mint
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct RegisterCollateral<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Collateral::INIT_SPACE,
+        seeds = [b"collateral", mint.key().as_ref()],
+        bump
+    )]
+    pub collateral: Account<'info, Collateral>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: PDA that owns the collateral vault.
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"collateral", mint.key().as_ref()],
+        bump = collateral.bump,
+        has_one = mint
+    )]
+    pub collateral: Account<'info, Collateral>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = depositor)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Vault owner, matched by the token::authority constraint.
+    #[account(
+        seeds = [b"vault_authority", mint.key().as_ref()],
+        bump = collateral.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+    pub depositor: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Collateral {
+    pub admin: Pubkey,            // 32 bytes
+    pub mint: Pubkey,             // 32 bytes
+    pub total_deposits: u64,      //  8 bytes
+    pub vault_authority_bump: u8, //  1 byte
+    pub bump: u8,                 //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Mint has a permanent delegate that can move tokens out of the vault")]
+    PermanentDelegate,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
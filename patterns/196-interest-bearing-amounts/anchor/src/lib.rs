@@ -0,0 +1,281 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        interest_bearing_mint::InterestBearingConfig, BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint as MintState,
+};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use demo_log::{demo_log, Mode};
+
+declare_id!("9fTXsfaGPY2LTMMEWgGzhRANG3ZRcxSwVbBfxTVuWw7L");
+
+/// # Interest-Bearing Amounts
+///
+/// ## The Vulnerability
+/// Token-2022's `InterestBearingConfig` extension never changes balances.
+/// Raw amounts stay fixed; wallets show `raw × e^(rate × time)`, the UI
+/// amount. The vulnerable deposit credits the depositor with that UI amount,
+/// converted back to base units, while withdrawals move raw tokens. Once the
+/// mint has accrued interest, every deposit is credited more tokens than it
+/// put in.
+///
+/// ## Why It Matters
+/// The gap grows with time, so the bug doesn't show in tests run right after
+/// the mint is created. In production, each deposit mints claims on other
+/// depositors' tokens; the last ones out find the vault short.
+///
+/// ## The Fix
+/// Store, compare, and transfer raw amounts only. Convert to UI amounts at
+/// the edges — display and pricing — and never write a converted amount
+/// back into protocol state.
+#[program]
+pub mod interest_bearing_amounts {
+    use super::*;
+
+    /// Create the signer's position for `mint` with nothing credited.
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.mint = ctx.accounts.mint.key();
+        position.amount = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Position credited in UI-scaled units
+    // ============================================================================
+    // ISSUE: `ui_amount_in_base_units` returns what a wallet displays for
+    //        `amount` right now. That number includes accrued interest, but
+    //        the vault only received `amount` raw tokens.
+    //
+    // ATTACK SCENARIO:
+    //   1. An interest-bearing mint at 5% has been live for 10 years; its
+    //      scale is e^0.5 ≈ 1.65
+    //   2. Attacker deposits 1,000 raw tokens; the position is credited 1,648
+    //   3. Attacker withdraws 1,648 raw tokens — 648 of them other
+    //      depositors'
+    //   4. Repeat; each round trip takes another 65%
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        transfer_in(&ctx, amount)?;
+
+        // VULNERABLE: a display value stored as a raw balance
+        let credited = ui_amount_in_base_units(
+            &ctx.accounts.mint.to_account_info(),
+            amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+        credit(&mut ctx.accounts.position, credited)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            credited = credited
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Position credited in raw units
+    // ============================================================================
+    // FIX: Credit exactly what the vault received. Interest-bearing display
+    //      values are for wallets and price feeds, not for balances.
+    //
+    // CONVERSION BOUNDARY:
+    //   - Stored and transferred: raw amounts, always
+    //   - Converted to UI amounts: only when showing a balance or pricing
+    //     one, with the timestamp it was computed at, and never stored
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        transfer_in(&ctx, amount)?;
+
+        // SECURE: raw in, raw credited
+        credit(&mut ctx.accounts.position, amount)?;
+
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            credited = amount
+        );
+        Ok(())
+    }
+
+    /// Debit `amount` from the position and send that many raw tokens out
+    /// of the vault.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientPosition)?;
+
+        let mint = ctx.accounts.mint.key();
+        let seeds: &[&[u8]] = &[
+            b"vault_authority",
+            mint.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.to.to_account_info(),
+                    authority: ctx.accounts.vault_authority.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            remaining = ctx.accounts.position.amount
+        );
+        Ok(())
+    }
+}
+
+fn transfer_in(ctx: &Context<Deposit>, amount: u64) -> Result<()> {
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.from.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )
+}
+
+fn credit(position: &mut Position, amount: u64) -> Result<()> {
+    position.amount = position
+        .amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// What a wallet shows for `amount` at `now`, scaled back to base units.
+/// Mints without the extension return `amount` unchanged.
+fn ui_amount_in_base_units(mint: &AccountInfo, amount: u64, now: i64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(config) = mint.get_extension::<InterestBearingConfig>() else {
+        return Ok(amount);
+    };
+    let decimals = mint.base.decimals;
+    let ui: f64 = config
+        .amount_to_ui_amount(amount, decimals, now)
+        .and_then(|ui| ui.parse().ok())
+        .ok_or(ErrorCode::Conversion)?;
+    Ok((ui * 10f64.powi(decimals as i32)) as u64)
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [b"position", mint.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Vault owner, matched by the token::authority constraint.
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [b"position", mint.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub to: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA signer for vault transfers.
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey, // 32 bytes
+    pub mint: Pubkey,  // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the position")]
+    InsufficientPosition,
+    #[msg("Could not convert the amount")]
+    Conversion,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,313 @@
+/// # Pattern 196: Interest-Bearing Amounts — Mollusk Exploit Tests
+///
+/// The mint is a Token-2022 interest-bearing mint at 5%, initialized at
+/// `T0`. Ten years later its UI scale is e^0.5 ≈ 1.6487. The vault already
+/// holds a victim's 1,000 tokens; the attacker deposits 1,000 more.
+///
+/// - Test 1: EXPLOIT — ten years in, deposit_vulnerable credits 1,648.72
+///   tokens for 1,000, and the attacker withdraws all of it.
+/// - Test 2: SECURE — deposit_secure credits 1,000; the same withdrawal
+///   fails.
+/// - Test 3: SANITY — at `T0` the scale is 1, and deposit_vulnerable credits
+///   exactly 1,000. The bug only shows once time has passed.
+///
+/// Deposits and withdrawals run in Token-2022, so `spl_token_2022.so` must
+/// be in SBF_OUT_DIR (see the pattern README).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, owned_account};
+use test_harness::token::{push_extension, token_account, Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("9fTXsfaGPY2LTMMEWgGzhRANG3ZRcxSwVbBfxTVuWw7L");
+const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const DECIMALS: u8 = 6;
+const DEPOSIT: u64 = 1_000_000_000; // 1,000 tokens
+
+/// Interest-bearing mint: 5% a year since `T0`
+const RATE_BPS: i16 = 500;
+const T0: i64 = 1_700_000_000;
+const TEN_YEARS: i64 = 315_567_360; // Token-2022 uses 365.24-day years
+/// DEPOSIT × e^0.5, truncated: what the vulnerable deposit credits
+const SCALED_DEPOSIT: u64 = 1_648_721_270;
+
+// Token-2022 layout constants
+const EXTENSION_INTEREST_BEARING_CONFIG: u16 = 10;
+
+// Error codes
+const INSUFFICIENT_POSITION: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Position account: [8 disc][32 owner][32 mint][8 amount][1 bump]
+fn serialize_position(owner: &Pubkey, mint: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Token-2022 mint with an InterestBearingConfig extension:
+///   [32 rate_authority][8 initialization_timestamp]
+///   [2 pre_update_average_rate][8 last_update_timestamp][2 current_rate]
+fn serialize_mint() -> Vec<u8> {
+    let mut data = Mint {
+        mint_authority: Some(Pubkey::new_unique()),
+        supply: 10_000_000_000,
+        decimals: DECIMALS,
+        ..Mint::default()
+    }
+    .pack();
+    let mut config = vec![0u8; 32]; // rate_authority: None
+    config.extend_from_slice(&T0.to_le_bytes());
+    config.extend_from_slice(&RATE_BPS.to_le_bytes());
+    config.extend_from_slice(&T0.to_le_bytes());
+    config.extend_from_slice(&RATE_BPS.to_le_bytes());
+    push_extension(&mut data, EXTENSION_INTEREST_BEARING_CONFIG, &config);
+    data
+}
+
+fn mint_account() -> AccountSharedData {
+    owned_account(&serialize_mint(), 10_000_000, &TOKEN_2022_PROGRAM_ID)
+}
+
+/// Mollusk with Token-2022 loaded and the clock at `now`.
+fn new_mollusk(now: i64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "interest_bearing_amounts");
+    mollusk.add_program(
+        &TOKEN_2022_PROGRAM_ID,
+        "spl_token_2022",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk.sysvars.clock.unix_timestamp = now;
+    mollusk
+}
+
+/// Keys for the depositor's position, wallet, and the shared vault.
+struct Scenario {
+    mint: Pubkey,
+    owner: Pubkey,
+    wallet: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    vault: Pubkey,
+    vault_authority: Pubkey,
+}
+
+impl Scenario {
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", mint.as_ref(), owner.as_ref()],
+            &PROGRAM_ID,
+        );
+        let (vault_authority, _) =
+            Pubkey::find_program_address(&[b"vault_authority", mint.as_ref()], &PROGRAM_ID);
+        Self {
+            mint,
+            owner,
+            wallet: Pubkey::new_unique(),
+            position,
+            position_bump,
+            vault: Pubkey::new_unique(),
+            vault_authority,
+        }
+    }
+
+    fn position_data(&self, amount: u64) -> Vec<u8> {
+        serialize_position(&self.owner, &self.mint, amount, self.position_bump)
+    }
+
+    fn position_account(&self, amount: u64) -> AccountSharedData {
+        let data = self.position_data(amount);
+        let mut account = AccountSharedData::new(2_000_000, data.len(), &PROGRAM_ID);
+        account.set_data_from_slice(&data);
+        account
+    }
+
+    fn vault_data(&self, amount: u64) -> Vec<u8> {
+        TokenAccount::new(&self.mint, &self.vault_authority, amount).pack()
+    }
+
+    /// Deposit DEPOSIT from the owner's wallet into a vault already holding
+    /// the victim's DEPOSIT. Returns the resulting position and vault.
+    fn deposit(
+        &self,
+        mollusk: &Mollusk,
+        name: &str,
+        credited: u64,
+    ) -> (AccountSharedData, AccountSharedData) {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&DEPOSIT.to_le_bytes());
+        let ix = Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.wallet, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new_readonly(self.vault_authority, false),
+                AccountMeta::new_readonly(self.owner, true),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+            ],
+        );
+
+        let position = self.position_data(credited);
+        let vault = self.vault_data(2 * DEPOSIT);
+        let result = mollusk.process_and_validate_instruction(
+            &ix,
+            &[
+                (self.position, self.position_account(0)),
+                (self.mint, mint_account()),
+                (
+                    self.wallet,
+                    token_account(&TOKEN_2022_PROGRAM_ID, &self.mint, &self.owner, DEPOSIT),
+                ),
+                (
+                    self.vault,
+                    token_account(
+                        &TOKEN_2022_PROGRAM_ID,
+                        &self.mint,
+                        &self.vault_authority,
+                        DEPOSIT,
+                    ),
+                ),
+                (self.vault_authority, AccountSharedData::default()),
+                (
+                    self.owner,
+                    AccountSharedData::new(1_000_000, 0, &solana_sdk::system_program::ID),
+                ),
+                (
+                    TOKEN_2022_PROGRAM_ID,
+                    executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+                ),
+            ],
+            &[
+                Check::success(),
+                Check::account(&self.position).data(&position).build(),
+                Check::account(&self.vault).data(&vault).build(),
+            ],
+        );
+        (
+            result.resulting_accounts[0].1.clone(),
+            result.resulting_accounts[3].1.clone(),
+        )
+    }
+
+    fn withdraw_ix(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("withdraw").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.wallet, false),
+                AccountMeta::new_readonly(self.vault_authority, false),
+                AccountMeta::new_readonly(self.owner, true),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn withdraw_accounts(
+        &self,
+        position: AccountSharedData,
+        vault: AccountSharedData,
+    ) -> Vec<(Pubkey, AccountSharedData)> {
+        vec![
+            (self.position, position),
+            (self.mint, mint_account()),
+            (self.vault, vault),
+            (self.wallet, token_account(&TOKEN_2022_PROGRAM_ID, &self.mint, &self.owner, 0)),
+            (self.vault_authority, AccountSharedData::default()),
+            (
+                self.owner,
+                AccountSharedData::new(1_000_000, 0, &solana_sdk::system_program::ID),
+            ),
+            (TOKEN_2022_PROGRAM_ID, executable_account(&solana_sdk::bpf_loader_upgradeable::id())),
+        ]
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_scaled_credit_withdrawn_as_raw() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Ten years after T0, the attacker deposits 1,000 tokens with
+    // deposit_vulnerable, then withdraws everything the position shows.
+    //
+    // Expected: Both SUCCEED — the position is credited 1,648.72 tokens,
+    //           and withdrawing them leaves the vault 648.72 short of the
+    //           victim's 1,000.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(T0 + TEN_YEARS);
+    let s = Scenario::new();
+
+    let (position, vault) = s.deposit(&mollusk, "deposit_vulnerable", SCALED_DEPOSIT);
+
+    let vault_after = s.vault_data(2 * DEPOSIT - SCALED_DEPOSIT);
+    let wallet_after = TokenAccount::new(&s.mint, &s.owner, SCALED_DEPOSIT).pack();
+    mollusk.process_and_validate_instruction(
+        &s.withdraw_ix(SCALED_DEPOSIT),
+        &s.withdraw_accounts(position, vault),
+        &[
+            Check::success(),
+            Check::account(&s.vault).data(&vault_after).build(),
+            Check::account(&s.wallet).data(&wallet_after).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_raw_credit_blocks_scaled_withdrawal() {
+    // -----------------------------------------------------------------------
+    // SECURE: Same time, same deposit, through deposit_secure. The attacker
+    // then asks for the scaled amount.
+    //
+    // Expected: The deposit credits exactly 1,000 tokens. The withdrawal of
+    //           1,648.72 FAILS with InsufficientPosition (6000).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(T0 + TEN_YEARS);
+    let s = Scenario::new();
+
+    let (position, vault) = s.deposit(&mollusk, "deposit_secure", DEPOSIT);
+
+    mollusk.process_and_validate_instruction(
+        &s.withdraw_ix(SCALED_DEPOSIT),
+        &s.withdraw_accounts(position, vault),
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_POSITION))],
+    );
+}
+
+#[test]
+fn sanity_no_gap_at_initialization() {
+    // -----------------------------------------------------------------------
+    // SANITY: deposit_vulnerable at T0, when no interest has accrued.
+    //
+    // Expected: SUCCEEDS and credits exactly 1,000 tokens — a test suite
+    //           that never moves the clock would pass the vulnerable code.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(T0);
+    let s = Scenario::new();
+
+    s.deposit(&mollusk, "deposit_vulnerable", DEPOSIT);
+}
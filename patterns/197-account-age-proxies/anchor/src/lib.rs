@@ -0,0 +1,271 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("8kUtxKe4KvJ1nDoCwSuNWPRNL45nLHwekpvHh5YUXAwE");
+
+/// Epochs a wallet must have "existed" for in the vulnerable check.
+pub const MIN_AGE_EPOCHS: u64 = 15;
+
+/// Seconds since registration before a member may claim (30 days).
+pub const MIN_AGE_SECONDS: i64 = 30 * 86_400;
+
+/// # Account Age Proxies
+///
+/// ## The Vulnerability
+/// An airdrop only pays "established" wallets, to keep sybils out. The
+/// vulnerable claim decides a wallet's age from its `rent_epoch`, reading it
+/// as the epoch the account was created. It isn't. `rent_epoch` is
+/// bookkeeping for rent collection, which no longer runs: depending on how
+/// the account was last written it holds 0, a stale epoch, or the `u64::MAX`
+/// rent-exempt sentinel. A wallet created one slot ago can read as older
+/// than the chain, and a wallet from genesis can read as not yet born.
+///
+/// ## Why It Matters
+/// Nothing about an account's metadata — `rent_epoch`, lamports, data
+/// length, address — records when it was created or who controls it.
+/// Sybil filters built on them either let every fresh wallet through or
+/// shut real users out, and attackers find out which long before users do.
+///
+/// ## The Fix
+/// Record the fact the program cares about in state it owns. A `register`
+/// instruction writes `registered_at` from the Clock sysvar into a member
+/// PDA, and claims measure age from that.
+#[program]
+pub mod account_age_proxy {
+    use super::*;
+
+    /// Create the airdrop. The admin funds the drop PDA with plain transfers.
+    pub fn init_drop(ctx: Context<InitDrop>, amount_per_claim: u64) -> Result<()> {
+        let drop = &mut ctx.accounts.drop;
+        drop.admin = ctx.accounts.admin.key();
+        drop.amount_per_claim = amount_per_claim;
+        drop.bump = ctx.bumps.drop;
+        Ok(())
+    }
+
+    /// Record when the signer registered. Age for the secure claim is
+    /// measured from here.
+    pub fn register(ctx: Context<Register>) -> Result<()> {
+        let member = &mut ctx.accounts.member;
+        member.wallet = ctx.accounts.wallet.key();
+        member.registered_at = Clock::get()?.unix_timestamp;
+        member.bump = ctx.bumps.member;
+
+        demo_log!(
+            Mode::Setup,
+            "register",
+            actor = member.wallet,
+            registered_at = member.registered_at
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Wallet age read from `rent_epoch`
+    // ============================================================================
+    // ISSUE: `rent_epoch` is not a creation epoch. With rent collection gone
+    //        it is 0, stale, or u64::MAX, and none of those depend on how
+    //        long the wallet has existed.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker generates 1,000 fresh keypairs and funds each with rent
+    //   2. Each wallet's rent_epoch reads as 0, so `epoch - rent_epoch` is
+    //      the current epoch — hundreds of epochs "old"
+    //   3. Every wallet passes the age check and claims
+    //
+    // AND THE REVERSE:
+    //   A real user's years-old, rent-exempt wallet carries rent_epoch =
+    //   u64::MAX. `epoch - u64::MAX` saturates to 0, so it is "too new".
+    // ============================================================================
+    pub fn claim_vulnerable(ctx: Context<ClaimVulnerable>) -> Result<()> {
+        let epoch = Clock::get()?.epoch;
+        // VULNERABLE: account metadata used as a creation date
+        let age = epoch.saturating_sub(ctx.accounts.wallet.rent_epoch);
+        require!(age >= MIN_AGE_EPOCHS, ErrorCode::TooNew);
+
+        pay_claim(
+            &ctx.accounts.drop,
+            &mut ctx.accounts.receipt,
+            &ctx.accounts.wallet,
+            ctx.bumps.receipt,
+        )?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "claim",
+            actor = ctx.accounts.wallet.key(),
+            amount = ctx.accounts.drop.amount_per_claim,
+            age_epochs = age
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Age measured from a registration the program recorded
+    // ============================================================================
+    // FIX: `member.registered_at` was written by `register` from the Clock
+    //      sysvar, into a PDA only this program can create. A wallet is as
+    //      old as its registration, no matter when the keypair was made.
+    //
+    // LIMITS:
+    // Registration is still free, so an attacker can register 1,000 wallets
+    // and wait. The waiting period only means sybils have to be planned in
+    // advance; pair it with a cost that doesn't scale for free (a deposit,
+    // stake, or attestation) when the drop is worth farming.
+    // ============================================================================
+    pub fn claim_secure(ctx: Context<ClaimSecure>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        // SECURE: age from program-owned state
+        let age = now.saturating_sub(ctx.accounts.member.registered_at);
+        require!(age >= MIN_AGE_SECONDS, ErrorCode::TooNew);
+
+        pay_claim(
+            &ctx.accounts.drop,
+            &mut ctx.accounts.receipt,
+            &ctx.accounts.wallet,
+            ctx.bumps.receipt,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "claim",
+            actor = ctx.accounts.wallet.key(),
+            amount = ctx.accounts.drop.amount_per_claim,
+            age_seconds = age
+        );
+        Ok(())
+    }
+}
+
+/// Record the claim and move `amount_per_claim` from the drop to the wallet.
+fn pay_claim(
+    drop: &Account<Drop>,
+    receipt: &mut Account<ClaimReceipt>,
+    wallet: &Signer,
+    bump: u8,
+) -> Result<()> {
+    receipt.wallet = wallet.key();
+    receipt.bump = bump;
+
+    let amount = drop.amount_per_claim;
+    let drop = drop.to_account_info();
+    **drop.try_borrow_mut_lamports()? = drop
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::DropEmpty)?;
+    **wallet.try_borrow_mut_lamports()? = wallet
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitDrop<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Drop::INIT_SPACE,
+        seeds = [b"drop"],
+        bump
+    )]
+    pub drop: Account<'info, Drop>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Register<'info> {
+    #[account(
+        init,
+        payer = wallet,
+        space = 8 + Member::INIT_SPACE,
+        seeds = [b"member", wallet.key().as_ref()],
+        bump
+    )]
+    pub member: Account<'info, Member>,
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// VULNERABLE account structure - the wallet vouches for its own age
+#[derive(Accounts)]
+pub struct ClaimVulnerable<'info> {
+    #[account(mut, seeds = [b"drop"], bump = drop.bump)]
+    pub drop: Account<'info, Drop>,
+    #[account(
+        init,
+        payer = wallet,
+        space = 8 + ClaimReceipt::INIT_SPACE,
+        seeds = [b"claimed", wallet.key().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, ClaimReceipt>,
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// SECURE account structure - age comes from the member PDA
+#[derive(Accounts)]
+pub struct ClaimSecure<'info> {
+    #[account(mut, seeds = [b"drop"], bump = drop.bump)]
+    pub drop: Account<'info, Drop>,
+    #[account(
+        seeds = [b"member", wallet.key().as_ref()],
+        bump = member.bump,
+        has_one = wallet
+    )]
+    pub member: Account<'info, Member>,
+    #[account(
+        init,
+        payer = wallet,
+        space = 8 + ClaimReceipt::INIT_SPACE,
+        seeds = [b"claimed", wallet.key().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, ClaimReceipt>,
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Drop {
+    pub admin: Pubkey,         // 32 bytes
+    pub amount_per_claim: u64, //  8 bytes
+    pub bump: u8,              //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Member {
+    pub wallet: Pubkey,     // 32 bytes
+    pub registered_at: i64, //  8 bytes
+    pub bump: u8,           //  1 byte
+}
+
+/// Exists once per wallet; its `init` stops a second claim.
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimReceipt {
+    pub wallet: Pubkey, // 32 bytes
+    pub bump: u8,       //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Wallet is not old enough to claim")]
+    TooNew,
+    #[msg("Airdrop has run out")]
+    DropEmpty,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,229 @@
+/// # Pattern 197: Account Age Proxies — Mollusk Exploit Tests
+///
+/// An airdrop pays 1 SOL per wallet, but only to wallets old enough to be
+/// real users. The clock sits at epoch 600.
+///
+/// - Test 1: EXPLOIT — a wallet created moments ago has rent_epoch 0 and
+///   passes claim_vulnerable's age check.
+/// - Test 2: SANITY — a rent-exempt wallet carrying the u64::MAX sentinel
+///   fails the same check, however old it is.
+/// - Test 3: SECURE — claim_secure rejects a member who registered a day ago.
+/// - Test 4: SANITY — claim_secure pays a member registered 31 days ago.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{AccountSharedData, WritableAccount},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8kUtxKe4KvJ1nDoCwSuNWPRNL45nLHwekpvHh5YUXAwE");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const AMOUNT: u64 = SOL;
+const FUNDS: u64 = 10 * SOL;
+const EPOCH: u64 = 600;
+const NOW: i64 = 1_700_000_000;
+const DAY: i64 = 86_400;
+
+// Error codes
+const TOO_NEW: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "account_age_proxy");
+    mollusk.sysvars.clock.epoch = EPOCH;
+    mollusk.sysvars.clock.unix_timestamp = NOW;
+    mollusk
+}
+
+/// The drop PDA holding FUNDS above rent: [8 disc][32 admin][8 amount_per_claim][1 bump]
+fn drop_account() -> (Pubkey, AccountSharedData) {
+    let (address, bump) = Pubkey::find_program_address(&[b"drop"], &PROGRAM_ID);
+    let mut data = account_discriminator("Drop").to_vec();
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(&AMOUNT.to_le_bytes());
+    data.push(bump);
+    let mut account = AccountSharedData::new(RENT + FUNDS, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account)
+}
+
+/// `wallet`'s member PDA: [8 disc][32 wallet][8 registered_at][1 bump]
+fn member_account(wallet: &Pubkey, registered_at: i64) -> (Pubkey, AccountSharedData) {
+    let (address, bump) = Pubkey::find_program_address(&[b"member", wallet.as_ref()], &PROGRAM_ID);
+    let mut data = account_discriminator("Member").to_vec();
+    data.extend_from_slice(wallet.as_ref());
+    data.extend_from_slice(&registered_at.to_le_bytes());
+    data.push(bump);
+    let mut account = AccountSharedData::new(RENT, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account)
+}
+
+fn receipt_address(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"claimed", wallet.as_ref()], &PROGRAM_ID).0
+}
+
+/// A system-owned wallet with `rent_epoch` set as the runtime might leave it.
+fn wallet(rent_epoch: u64) -> AccountSharedData {
+    let mut account = AccountSharedData::new(SOL, 0, &solana_sdk::system_program::ID);
+    account.set_rent_epoch(rent_epoch);
+    account
+}
+
+fn system_program() -> (Pubkey, AccountSharedData) {
+    (
+        solana_sdk::system_program::ID,
+        executable_account(&solana_sdk::native_loader::id()),
+    )
+}
+
+fn claim_vulnerable_ix(drop: Pubkey, wallet: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator("claim_vulnerable"),
+        vec![
+            AccountMeta::new(drop, false),
+            AccountMeta::new(receipt_address(&wallet), false),
+            AccountMeta::new(wallet, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    )
+}
+
+fn claim_secure_ix(drop: Pubkey, member: Pubkey, wallet: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &ix_discriminator("claim_secure"),
+        vec![
+            AccountMeta::new(drop, false),
+            AccountMeta::new_readonly(member, false),
+            AccountMeta::new(receipt_address(&wallet), false),
+            AccountMeta::new(wallet, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_fresh_wallet_passes_rent_epoch_age() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: A keypair generated for this claim, funded with 1 SOL. Its
+    // rent_epoch reads 0, so claim_vulnerable computes an age of 600 epochs.
+    //
+    // Expected: SUCCEEDS — the brand-new wallet is paid as "established".
+    //           Each of an attacker's fresh wallets would pass the same way.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk();
+    let (drop, drop_account) = drop_account();
+    let attacker = Pubkey::new_unique();
+
+    mollusk.process_and_validate_instruction(
+        &claim_vulnerable_ix(drop, attacker),
+        &[
+            (drop, drop_account),
+            (receipt_address(&attacker), AccountSharedData::default()),
+            (attacker, wallet(0)),
+            system_program(),
+        ],
+        &[
+            Check::success(),
+            Check::account(&drop)
+                .lamports(RENT + FUNDS - AMOUNT)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_exempt_wallet_reads_as_new_vulnerable() {
+    // -----------------------------------------------------------------------
+    // SANITY: A long-lived, rent-exempt wallet whose rent_epoch is the
+    // u64::MAX sentinel calls claim_vulnerable.
+    //
+    // Expected: FAILS with TooNew (6000) — `epoch - u64::MAX` saturates to
+    //           0. The field says nothing about age in either direction.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk();
+    let (drop, drop_account) = drop_account();
+    let user = Pubkey::new_unique();
+
+    mollusk.process_and_validate_instruction(
+        &claim_vulnerable_ix(drop, user),
+        &[
+            (drop, drop_account),
+            (receipt_address(&user), AccountSharedData::default()),
+            (user, wallet(u64::MAX)),
+            system_program(),
+        ],
+        &[Check::err(ProgramError::Custom(TOO_NEW))],
+    );
+}
+
+#[test]
+fn secure_recent_registration_rejected() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker's fresh wallet registered yesterday and calls
+    // claim_secure. Its rent_epoch is still 0.
+    //
+    // Expected: FAILS with TooNew (6000) — age is one day, measured from
+    //           the member PDA. rent_epoch is never read.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk();
+    let (drop, drop_account) = drop_account();
+    let attacker = Pubkey::new_unique();
+    let (member, member_account) = member_account(&attacker, NOW - DAY);
+
+    mollusk.process_and_validate_instruction(
+        &claim_secure_ix(drop, member, attacker),
+        &[
+            (drop, drop_account),
+            (member, member_account),
+            (receipt_address(&attacker), AccountSharedData::default()),
+            (attacker, wallet(0)),
+            system_program(),
+        ],
+        &[Check::err(ProgramError::Custom(TOO_NEW))],
+    );
+}
+
+#[test]
+fn sanity_established_member_claims_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: A user registered 31 days ago calls claim_secure. Their wallet
+    // carries the u64::MAX sentinel that failed the vulnerable check.
+    //
+    // Expected: SUCCEEDS — 1 SOL paid out of the drop.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk();
+    let (drop, drop_account) = drop_account();
+    let user = Pubkey::new_unique();
+    let (member, member_account) = member_account(&user, NOW - 31 * DAY);
+
+    mollusk.process_and_validate_instruction(
+        &claim_secure_ix(drop, member, user),
+        &[
+            (drop, drop_account),
+            (member, member_account),
+            (receipt_address(&user), AccountSharedData::default()),
+            (user, wallet(u64::MAX)),
+            system_program(),
+        ],
+        &[
+            Check::success(),
+            Check::account(&drop)
+                .lamports(RENT + FUNDS - AMOUNT)
+                .build(),
+        ],
+    );
+}
@@ -0,0 +1,239 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+use approval::AdminApproval;
+
+declare_id!("JBPd3sPVcKnStbQgjWwde2z8y4Vx4rw2VghKj8hnrHT3");
+
+/// # Helper Preconditions
+///
+/// ## The Vulnerability
+/// `transfer_from_vault` moves lamports out of the vault PDA and checks
+/// nothing; its doc comment says callers must already have verified the
+/// admin. The original `withdraw` does, through `has_one = admin`. A later
+/// `emergency_withdraw` reuses the helper with an account struct that has
+/// no such constraint, and anyone can call it.
+///
+/// ## Why It Matters
+/// A shared helper is reached from every instruction that calls it. Its
+/// preconditions live in comments and in reviewers' memory, and each new
+/// caller is a fresh chance to forget them. Late additions — emergency
+/// exits, migrations, admin tooling — are written fastest and reviewed
+/// least.
+///
+/// ## The Fix
+/// Make the precondition a type. `transfer_from_vault_approved` takes an
+/// `AdminApproval`, which only `AdminApproval::verify` can build, and only
+/// after checking the signer against `vault.admin`. A caller that skips the
+/// check doesn't compile.
+#[program]
+pub mod helper_preconditions {
+    use super::*;
+
+    pub fn init_vault(ctx: Context<InitVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.admin = ctx.accounts.admin.key();
+        vault.bump = ctx.bumps.vault;
+        Ok(())
+    }
+
+    /// The original admin withdrawal. Correct: `has_one = admin` satisfies
+    /// the helper's precondition before the handler runs.
+    pub fn withdraw_vulnerable(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        transfer_from_vault(&ctx.accounts.vault, &ctx.accounts.destination, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.admin.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: New caller of an unchecked helper
+    // ============================================================================
+    // ISSUE: `emergency_withdraw_vulnerable` was added later and reuses the
+    //        generic `VaultTransfer` accounts. Its author assumed
+    //        `transfer_from_vault` checked authority; the helper assumed its
+    //        caller did. Nobody does.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker calls emergency_withdraw_vulnerable, signing as
+    //      `authority` with their own key
+    //   2. `destination` is the attacker's wallet, `amount` the vault balance
+    //   3. transfer_from_vault moves it — there is nothing to fail
+    // ============================================================================
+    pub fn emergency_withdraw_vulnerable(ctx: Context<VaultTransfer>, amount: u64) -> Result<()> {
+        // VULNERABLE: the helper's precondition is never established
+        transfer_from_vault(&ctx.accounts.vault, &ctx.accounts.destination, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "emergency_withdraw",
+            actor = ctx.accounts.authority.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: The precondition is a value the helper demands
+    // ============================================================================
+    // FIX: `transfer_from_vault_approved` takes `&AdminApproval`. The only
+    //      way to get one is `AdminApproval::verify`, which checks the signer
+    //      against `vault.admin`. Both instructions share the unconstrained
+    //      `VaultTransfer` accounts and are still safe, because the check
+    //      moved from the accounts struct into the helper's signature.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<VaultTransfer>, amount: u64) -> Result<()> {
+        let approval = AdminApproval::verify(&ctx.accounts.vault, &ctx.accounts.authority)?;
+        transfer_from_vault_approved(
+            &approval,
+            &ctx.accounts.vault,
+            &ctx.accounts.destination,
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.authority.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    pub fn emergency_withdraw_secure(ctx: Context<VaultTransfer>, amount: u64) -> Result<()> {
+        // SECURE: no approval, no call to the helper
+        let approval = AdminApproval::verify(&ctx.accounts.vault, &ctx.accounts.authority)?;
+        transfer_from_vault_approved(
+            &approval,
+            &ctx.accounts.vault,
+            &ctx.accounts.destination,
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "emergency_withdraw",
+            actor = ctx.accounts.authority.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// Move `amount` lamports from the vault to `destination`.
+///
+/// Callers must have verified that the signer is `vault.admin`.
+fn transfer_from_vault(
+    vault: &Account<Vault>,
+    destination: &AccountInfo,
+    amount: u64,
+) -> Result<()> {
+    let vault = vault.to_account_info();
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// `transfer_from_vault`, with its precondition in the signature.
+fn transfer_from_vault_approved(
+    approval: &AdminApproval,
+    vault: &Account<Vault>,
+    destination: &AccountInfo,
+    amount: u64,
+) -> Result<()> {
+    // An approval for one vault can't be spent on another.
+    require_keys_eq!(approval.vault(), vault.key(), ErrorCode::NotAdmin);
+    transfer_from_vault(vault, destination, amount)
+}
+
+mod approval {
+    use super::*;
+
+    /// Proof that the vault's admin signed this instruction.
+    ///
+    /// The field is private to this module, so `verify` is the only
+    /// constructor anywhere in the crate.
+    pub struct AdminApproval {
+        vault: Pubkey,
+    }
+
+    impl AdminApproval {
+        pub fn verify(vault: &Account<Vault>, signer: &Signer) -> Result<Self> {
+            require_keys_eq!(vault.admin, signer.key(), ErrorCode::NotAdmin);
+            Ok(Self { vault: vault.key() })
+        }
+
+        pub fn vault(&self) -> Pubkey {
+            self.vault
+        }
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitVault<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The original withdrawal accounts - `has_one = admin` does the check
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump, has_one = admin @ ErrorCode::NotAdmin)]
+    pub vault: Account<'info, Vault>,
+    pub admin: Signer<'info>,
+    /// CHECK: Receives lamports only.
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+}
+
+/// Generic vault transfer accounts - no authority constraint
+#[derive(Accounts)]
+pub struct VaultTransfer<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+    /// CHECK: Receives lamports only.
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub admin: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Signer is not the vault admin")]
+    NotAdmin,
+    #[msg("Vault cannot cover the withdrawal")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
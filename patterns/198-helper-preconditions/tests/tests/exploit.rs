@@ -0,0 +1,157 @@
+/// # Pattern 198: Helper Preconditions — Mollusk Exploit Tests
+///
+/// The vault PDA holds 10 SOL. Every withdrawal goes through
+/// `transfer_from_vault`, which expects its caller to have checked the admin.
+///
+/// - Test 1: EXPLOIT — emergency_withdraw_vulnerable skips the check, and an
+///   attacker drains the vault.
+/// - Test 2: SANITY — the original withdraw_vulnerable still refuses the
+///   attacker; its accounts struct does the check.
+/// - Test 3: SECURE — emergency_withdraw_secure can't reach the helper
+///   without an AdminApproval, and the attacker can't get one.
+/// - Test 4: SANITY — the admin's emergency_withdraw_secure succeeds.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, system_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("JBPd3sPVcKnStbQgjWwde2z8y4Vx4rw2VghKj8hnrHT3");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const FUNDS: u64 = 10 * SOL;
+
+// Error codes
+const NOT_ADMIN: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// The vault PDA holding FUNDS above rent: [8 disc][32 admin][1 bump]
+fn vault(admin: &Pubkey) -> (Pubkey, AccountSharedData) {
+    let (address, bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+    let mut data = account_discriminator("Vault").to_vec();
+    data.extend_from_slice(admin.as_ref());
+    data.push(bump);
+    let mut account = AccountSharedData::new(RENT + FUNDS, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(&data);
+    (address, account)
+}
+
+/// Every withdrawal takes [vault (w), signer (s), destination (w)].
+fn withdraw_ix(name: &str, vault: Pubkey, signer: Pubkey, destination: Pubkey) -> Instruction {
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&FUNDS.to_le_bytes());
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(signer, true),
+            AccountMeta::new(destination, false),
+        ],
+    )
+}
+
+/// Run `name` signed by `signer`, paying the whole vault to the signer.
+fn withdraw(name: &str, admin: Pubkey, signer: Pubkey, checks: &[Check]) {
+    let mollusk = Mollusk::new(&PROGRAM_ID, "helper_preconditions");
+    let (vault, vault_account) = vault(&admin);
+
+    mollusk.process_and_validate_instruction(
+        &withdraw_ix(name, vault, signer, signer),
+        &[(vault, vault_account), (signer, system_account(RENT))],
+        checks,
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_emergency_withdraw_skips_admin_check() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker signs emergency_withdraw_vulnerable for the full
+    // 10 SOL, with their own wallet as the destination.
+    //
+    // Expected: SUCCEEDS — VaultTransfer has no authority constraint and
+    //           transfer_from_vault assumes one was checked.
+    // -----------------------------------------------------------------------
+    let admin = Pubkey::new_unique();
+    let attacker = Pubkey::new_unique();
+    let (vault, _) = vault(&admin);
+
+    withdraw(
+        "emergency_withdraw_vulnerable",
+        admin,
+        attacker,
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(RENT).build(),
+            Check::account(&attacker).lamports(RENT + FUNDS).build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_original_withdraw_checks_admin_vulnerable() {
+    // -----------------------------------------------------------------------
+    // SANITY: The same attacker tries the original withdraw_vulnerable.
+    //
+    // Expected: FAILS with NotAdmin (6000) — `has_one = admin` establishes
+    //           the helper's precondition. The helper was only ever as safe
+    //           as its callers.
+    // -----------------------------------------------------------------------
+    withdraw(
+        "withdraw_vulnerable",
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        &[Check::err(ProgramError::Custom(NOT_ADMIN))],
+    );
+}
+
+#[test]
+fn secure_emergency_withdraw_requires_approval() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker signs emergency_withdraw_secure, which uses the
+    // same unconstrained VaultTransfer accounts.
+    //
+    // Expected: FAILS with NotAdmin (6000) — AdminApproval::verify refuses
+    //           the attacker, and without an approval the handler can't call
+    //           transfer_from_vault_approved.
+    // -----------------------------------------------------------------------
+    withdraw(
+        "emergency_withdraw_secure",
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        &[Check::err(ProgramError::Custom(NOT_ADMIN))],
+    );
+}
+
+#[test]
+fn sanity_admin_emergency_withdraw_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The admin signs emergency_withdraw_secure for the full 10 SOL.
+    //
+    // Expected: SUCCEEDS — the vault keeps its rent, the admin gets the rest.
+    // -----------------------------------------------------------------------
+    let admin = Pubkey::new_unique();
+    let (vault, _) = vault(&admin);
+
+    withdraw(
+        "emergency_withdraw_secure",
+        admin,
+        admin,
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(RENT).build(),
+            Check::account(&admin).lamports(RENT + FUNDS).build(),
+        ],
+    );
+}
@@ -0,0 +1,243 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("BecRuXCM3QvN8uQRKM6CrzT77ZDJjRe8tSLJy5hruyuB");
+
+/// # Borrowed Bumps
+///
+/// This program demonstrates a SOL vault whose withdrawal signs for the vault
+/// PDA with a bump taken from the wrong account.
+///
+/// ## The Vulnerability
+/// Each pool owns a system-owned vault PDA at `[b"vault", pool]`. Moving
+/// lamports out of it means signing with those seeds and the vault's bump.
+/// The withdraw accounts struct used to declare the vault with `seeds` and
+/// `bump`, and the handler signed with `ctx.bumps.vault`. A refactor replaced
+/// the vault's seeds with `address = pool.vault`, which removed
+/// `ctx.bumps.vault`. The handler was "fixed" to use `ctx.bumps.pool`, the
+/// only bump left. It compiles. It's the bump of a different derivation.
+///
+/// ## Real-World Impact
+/// Whenever the two bumps differ, the program signs for some other address
+/// and the transfer fails: the vault is locked for that user, permanently.
+/// Whenever they match — often, for canonical bumps near 255 — it works, so
+/// the bug survives tests and reaches users. A signature for an address the
+/// program never meant to sign for is also a privilege it hands out blind.
+#[program]
+pub mod borrowed_bumps {
+    use super::*;
+
+    /// Create the signer's pool and record both canonical bumps.
+    pub fn init_pool(ctx: Context<InitPool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.owner = ctx.accounts.owner.key();
+        pool.vault = ctx.accounts.vault.key();
+        pool.bump = ctx.bumps.pool;
+        pool.vault_bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "init_pool",
+            actor = pool.owner,
+            vault_bump = pool.vault_bump
+        );
+        Ok(())
+    }
+
+    /// Move `amount` lamports from the owner into the pool's vault.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Signs for the vault with the pool's bump
+    // ============================================================================
+    // ISSUE: `vault` is no longer declared with `seeds`, so Anchor computes
+    //        no bump for it. `ctx.bumps.pool` is the bump of
+    //        `[b"pool", owner]`, not of `[b"vault", pool]`.
+    //
+    // ATTACK SCENARIO (the bug needs no attacker):
+    //   1. A user's pool bump is 254 and their vault bump is 255
+    //   2. init_pool and deposit work; neither signs for the vault
+    //   3. withdraw_vulnerable signs for [b"vault", pool, 254]. That's a
+    //      valid PDA too, but not the vault. (If it lands on the curve, the
+    //      derivation itself fails.)
+    //   4. The system program sees the vault marked as signer without a
+    //      signature: PrivilegeEscalation. Every withdrawal fails
+    //
+    // WHY IT SURVIVES REVIEW:
+    // `ctx.bumps.pool` is a real, typed field. The compiler has no idea
+    // which seeds it belongs to.
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<WithdrawVulnerable>, amount: u64) -> Result<()> {
+        let pool_key = ctx.accounts.pool.key();
+        // VULNERABLE: was `ctx.bumps.vault`; this is the bump of [b"pool", owner]
+        let bump = ctx.bumps.pool;
+        let seeds: &[&[u8]] = &[Pool::VAULT_SEED, pool_key.as_ref(), &[bump]];
+
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.owner.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            signed_bump = bump
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Signer seeds from the pool's stored state
+    // ============================================================================
+    // FIX: `init_pool` records the vault's canonical bump while its seeds
+    //      are still declared. `Pool::vault_seeds` builds the signer seeds
+    //      from that field and the same `VAULT_SEED` constant the init
+    //      constraint uses.
+    //
+    // WHY IT HOLDS UP:
+    // - The bump is a field of the account it describes, never borrowed
+    // - One accessor returns a fixed-size array, so every signer gets the
+    //   same seeds in the same order, and a missing seed is a type error
+    // - Dropping `seeds` from an accounts struct doesn't change it
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<WithdrawSecure>, amount: u64) -> Result<()> {
+        let pool_key = ctx.accounts.pool.key();
+        let seeds = ctx.accounts.pool.vault_seeds(&pool_key);
+
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.owner.to_account_info(),
+                },
+                &[&seeds],
+            ),
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            signed_bump = ctx.accounts.pool.vault_bump
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [Pool::SEED, owner.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(seeds = [Pool::VAULT_SEED, pool.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(has_one = owner, has_one = vault)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// VULNERABLE account structure - the vault's seeds were dropped in a refactor
+#[derive(Accounts)]
+pub struct WithdrawVulnerable<'info> {
+    #[account(seeds = [Pool::SEED, owner.key().as_ref()], bump, has_one = owner)]
+    pub pool: Account<'info, Pool>,
+
+    // Was: seeds = [Pool::VAULT_SEED, pool.key().as_ref()], bump
+    // The address check still holds; only `ctx.bumps.vault` went away.
+    #[account(mut, address = pool.vault)]
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// SECURE account structure - the bump comes from `pool`, not `ctx.bumps`
+#[derive(Accounts)]
+pub struct WithdrawSecure<'info> {
+    #[account(
+        seeds = [Pool::SEED, owner.key().as_ref()],
+        bump = pool.bump,
+        has_one = owner,
+        has_one = vault
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub owner: Pubkey,  // 32 bytes
+    pub vault: Pubkey,  // 32 bytes
+    pub bump: u8,       //  1 byte, of [b"pool", owner]
+    pub vault_bump: u8, //  1 byte, of [b"vault", pool]
+}
+
+impl Pool {
+    pub const SEED: &'static [u8] = b"pool";
+    pub const VAULT_SEED: &'static [u8] = b"vault";
+
+    /// Signer seeds for this pool's vault: `[VAULT_SEED, pool, vault_bump]`.
+    pub fn vault_seeds<'a>(&'a self, pool: &'a Pubkey) -> [&'a [u8]; 3] {
+        [
+            Self::VAULT_SEED,
+            pool.as_ref(),
+            std::slice::from_ref(&self.vault_bump),
+        ]
+    }
+}
@@ -0,0 +1,228 @@
+/// # Pattern 199: Borrowed Bumps — Mollusk Exploit Tests
+///
+/// Users are picked by searching deterministic keys for a particular
+/// relationship between their pool bump and their vault bump, the way a
+/// real user base contains every combination.
+///
+/// - Test 1: EXPLOIT — for a user whose pool bump is lower than their vault
+///   bump, withdraw_vulnerable signs for a different PDA and the vault's
+///   lamports can't leave.
+/// - Test 2: SANITY — a user whose two bumps happen to match withdraws
+///   through the vulnerable path, which is why the bug passes testing.
+/// - Test 3: SECURE — the locked-out user withdraws through withdraw_secure,
+///   which signs with the stored vault bump.
+/// - Test 4: SECURE — another pool's vault is rejected by `has_one = vault`.
+use mollusk_svm::{result::Check, Mollusk};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, system_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("BecRuXCM3QvN8uQRKM6CrzT77ZDJjRe8tSLJy5hruyuB");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const VAULT_BALANCE: u64 = 5 * SOL;
+const WITHDRAW: u64 = SOL;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account: [8 disc][32 owner][32 vault][1 bump][1 vault_bump]
+fn serialize_pool(owner: &Pubkey, vault: &Pubkey, bump: u8, vault_bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(74);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(vault.as_ref());
+    data.push(bump);
+    data.push(vault_bump);
+    data
+}
+
+/// One user's pool and vault PDAs with their canonical bumps.
+struct User {
+    owner: Pubkey,
+    pool: Pubkey,
+    pool_bump: u8,
+    vault: Pubkey,
+    vault_bump: u8,
+}
+
+/// Grind deterministic user keys until one satisfies `wanted`.
+fn user_where(wanted: impl Fn(&User) -> bool) -> User {
+    (0u32..)
+        .map(|i| {
+            let hash: [u8; 32] = Sha256::digest(format!("user-{i}").as_bytes()).into();
+            let owner = Pubkey::new_from_array(hash);
+            let (pool, pool_bump) =
+                Pubkey::find_program_address(&[b"pool", owner.as_ref()], &PROGRAM_ID);
+            let (vault, vault_bump) =
+                Pubkey::find_program_address(&[b"vault", pool.as_ref()], &PROGRAM_ID);
+            User {
+                owner,
+                pool,
+                pool_bump,
+                vault,
+                vault_bump,
+            }
+        })
+        .find(|user| wanted(user))
+        .unwrap()
+}
+
+/// The address withdraw_vulnerable actually signs for.
+fn signed_for(user: &User) -> Option<Pubkey> {
+    Pubkey::create_program_address(
+        &[b"vault", user.pool.as_ref(), &[user.pool_bump]],
+        &PROGRAM_ID,
+    )
+    .ok()
+}
+
+/// A withdrawal of WITHDRAW lamports from `vault`, with `user`'s pool
+/// holding VAULT_BALANCE in its own vault.
+fn withdraw(
+    name: &str,
+    user: &User,
+    vault: Pubkey,
+) -> (Instruction, Vec<(Pubkey, AccountSharedData)>) {
+    let pool_data = serialize_pool(&user.owner, &user.vault, user.pool_bump, user.vault_bump);
+    let mut pool_account = AccountSharedData::new(RENT, pool_data.len(), &PROGRAM_ID);
+    pool_account.set_data_from_slice(&pool_data);
+
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&WITHDRAW.to_le_bytes());
+
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new_readonly(user.pool, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(user.owner, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+    );
+    let accounts = vec![
+        (user.pool, pool_account),
+        (vault, system_account(VAULT_BALANCE)),
+        (user.owner, system_account(RENT)),
+        (
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ),
+    ];
+    (ix, accounts)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_borrowed_bump_locks_vault() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: A user whose pool bump is lower than their vault bump, and
+    // for whom [b"vault", pool, pool_bump] is another valid PDA, withdraws
+    // their own lamports.
+    //
+    // withdraw_vulnerable signs for that other PDA. The system program sees
+    // the vault marked as a signer with no signature behind it.
+    //
+    // Expected: FAILS with PrivilegeEscalation — for this user, on every
+    //           attempt.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "borrowed_bumps");
+    let user = user_where(|u| u.pool_bump < u.vault_bump && signed_for(u).is_some());
+    assert_ne!(signed_for(&user), Some(user.vault));
+    let (ix, accounts) = withdraw("withdraw_vulnerable", &user, user.vault);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::instruction_err(
+            InstructionError::PrivilegeEscalation,
+        )],
+    );
+}
+
+#[test]
+fn sanity_matching_bumps_pass_vulnerable() {
+    // -----------------------------------------------------------------------
+    // SANITY: A user whose pool and vault bumps are equal withdraws through
+    // withdraw_vulnerable.
+    //
+    // Expected: SUCCEEDS — the borrowed bump is right by coincidence. A test
+    //           suite with one such wallet never sees the bug.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "borrowed_bumps");
+    let user = user_where(|u| u.pool_bump == u.vault_bump);
+    let (ix, accounts) = withdraw("withdraw_vulnerable", &user, user.vault);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&user.vault)
+                .lamports(VAULT_BALANCE - WITHDRAW)
+                .build(),
+            Check::account(&user.owner)
+                .lamports(RENT + WITHDRAW)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_stored_vault_bump_withdraws() {
+    // -----------------------------------------------------------------------
+    // SECURE: The locked-out user from the exploit test withdraws through
+    // withdraw_secure.
+    //
+    // Expected: SUCCEEDS — Pool::vault_seeds signs with the stored vault
+    //           bump.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "borrowed_bumps");
+    let user = user_where(|u| u.pool_bump < u.vault_bump && signed_for(u).is_some());
+    let (ix, accounts) = withdraw("withdraw_secure", &user, user.vault);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&user.vault)
+                .lamports(VAULT_BALANCE - WITHDRAW)
+                .build(),
+            Check::account(&user.owner)
+                .lamports(RENT + WITHDRAW)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_other_vault() {
+    // -----------------------------------------------------------------------
+    // SECURE: A user passes someone else's vault to withdraw_secure.
+    //
+    // Expected: FAILS with ConstraintHasOne (2001) — pool.vault names the
+    //           user's own vault.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "borrowed_bumps");
+    let user = user_where(|u| u.pool_bump == u.vault_bump);
+    let other = user_where(|u| u.owner != user.owner);
+    let (ix, accounts) = withdraw("withdraw_secure", &user, other.vault);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(2001))],
+    );
+}
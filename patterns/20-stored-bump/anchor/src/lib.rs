@@ -0,0 +1,243 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("GYC567DqG15StDAEy1NQxepCQABDWM8Avd7VbkmsmYUS");
+
+/// # Stored Bump
+///
+/// This program demonstrates a vault that stores its canonical bump at
+/// creation and then ignores it.
+///
+/// ## The Vulnerability
+/// `open_vault` derives `[b"vault", owner]` once and saves the bump. The
+/// withdraw paths don't use it. One writes a bare `bump`, so Anchor calls
+/// `find_program_address` on every withdrawal, trying bumps from 255
+/// downward until one is off the curve. The other takes a bump from the
+/// instruction data and checks the address with whatever the client sent.
+///
+/// ## Real-World Impact
+/// Each failed try in `find_program_address` costs as much as a whole
+/// `create_program_address`. A vault whose canonical bump is 247 pays for
+/// nine derivations on every call, and owners can grind keys until their
+/// bumps are that low. A crank that processes many vaults per transaction
+/// runs out of compute on the ground ones. The stored bump is never read,
+/// so nothing catches it if it goes wrong.
+///
+/// The supplied bump makes the outcome depend on the client. An SDK that
+/// hard-codes 255 works for half of all owners and fails for the rest,
+/// even though the right bump sits in the vault they passed.
+#[program]
+pub mod stored_bump {
+    use super::*;
+
+    pub fn open_vault(ctx: Context<OpenVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.owner = ctx.accounts.owner.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "open_vault",
+            actor = vault.owner,
+            bump = vault.bump
+        );
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Re-derives the canonical bump on every call
+    // ============================================================================
+    // ISSUE: A bare `bump` in the seeds constraint makes Anchor run
+    //        `find_program_address`. It costs one derivation per bump tried,
+    //        and the vault's stored bump is never compared with the result.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker grinds owner keys until one's vault bump is 247 or lower
+    //   2. Each withdrawal from that vault tries 255, 254, ..., 247
+    //   3. A keeper batching withdrawals exceeds its compute budget whenever
+    //      the attacker's vaults are in the batch
+    // ============================================================================
+    pub fn withdraw_recompute_vulnerable(
+        ctx: Context<WithdrawRecomputeVulnerable>,
+        amount: u64,
+    ) -> Result<()> {
+        // VULNERABLE: ctx.bumps.vault was found by searching from 255
+        pay_out(&ctx.accounts.vault, &ctx.accounts.owner, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw_recompute",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            bump = ctx.bumps.vault
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Checks the vault with a bump the caller supplied
+    // ============================================================================
+    // ISSUE: `bump = bump` takes the argument over `vault.bump`. With any
+    //        value but the stored one, the seeds derive a different address
+    //        and a valid withdrawal fails.
+    //
+    // ATTACK SCENARIO:
+    //   1. A client library assumes every bump is 255
+    //   2. An owner whose vault bump is 254 withdraws through it
+    //   3. The seeds check fails, though the vault holds the right bump
+    // ============================================================================
+    pub fn withdraw_supplied_bump_vulnerable(
+        ctx: Context<WithdrawSuppliedBumpVulnerable>,
+        amount: u64,
+        bump: u8,
+    ) -> Result<()> {
+        // VULNERABLE: the address was checked against the caller's bump
+        pay_out(&ctx.accounts.vault, &ctx.accounts.owner, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw_supplied_bump",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            bump = bump
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Checks the vault with the bump stored at creation
+    // ============================================================================
+    // FIX: `bump = vault.bump` makes Anchor call `create_program_address`
+    //      once with the stored bump. `init` only ever stores the canonical
+    //      bump, so the check costs the same for every vault and the caller
+    //      has nothing to get wrong.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<WithdrawSecure>, amount: u64) -> Result<()> {
+        // SECURE: one derivation, with the bump the vault recorded
+        pay_out(&ctx.accounts.vault, &ctx.accounts.owner, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            bump = ctx.accounts.vault.bump
+        );
+        Ok(())
+    }
+}
+
+fn pay_out<'info>(vault: &Account<'info, Vault>, owner: &Signer<'info>, amount: u64) -> Result<()> {
+    let vault = vault.to_account_info();
+    let owner = owner.to_account_info();
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct OpenVault<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"vault", vault.owner.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawRecomputeVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump, // <-- find_program_address on every call
+        has_one = owner
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, bump: u8)]
+pub struct WithdrawSuppliedBumpVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump = bump, // <-- the caller's bump, not vault.bump
+        has_one = owner
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSecure<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump = vault.bump,
+        has_one = owner
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Lamports above rent are the owner's balance.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub owner: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte, canonical, set once by `init`
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Vault cannot cover the withdrawal")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,216 @@
+/// # Pattern 20: Stored Bump — Mollusk Exploit Tests
+///
+/// Each test opens with a vault that already exists at the canonical
+/// address, holding its canonical bump and 5 SOL. Owners are picked by
+/// grinding keys until their vault's bump is the one the test needs.
+///
+/// - Test 1: EXPLOIT — for a vault with a low bump, the recomputing
+///   withdrawal costs at least 1,500 CU more per bump below 255 than the
+///   stored-bump one.
+/// - Test 2: EXPLOIT — a client that passes bump 255 can't withdraw from a
+///   vault whose bump is 254.
+/// - Test 3: SECURE — the stored-bump withdrawal costs the same for a
+///   bump-255 vault and a low-bump vault.
+/// - Test 4: SANITY — the owner of a bump-254 vault withdraws through the
+///   secure path without passing a bump.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("GYC567DqG15StDAEy1NQxepCQABDWM8Avd7VbkmsmYUS");
+
+const SOL: u64 = 1_000_000_000;
+const BALANCE: u64 = 5 * SOL;
+const VAULT_LEN: usize = 41;
+
+/// What the runtime charges for one `create_program_address`, and for each
+/// bump `find_program_address` tries.
+const DERIVATION_CU: u64 = 1_500;
+/// Ground vaults have a canonical bump at or below this.
+const LOW_BUMP: u8 = 247;
+
+// Error codes
+const CONSTRAINT_SEEDS: u32 = 2006;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account: [8 disc][32 owner][1 bump]
+fn serialize_vault(owner: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(VAULT_LEN);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(owner.as_ref());
+    data.push(bump);
+    data
+}
+
+/// An owner, their vault, and the vault's canonical bump.
+struct Owner {
+    key: Pubkey,
+    vault: Pubkey,
+    bump: u8,
+}
+
+/// Generate owners until one's vault bump satisfies `wanted`, the way an
+/// attacker grinds keypairs offline.
+fn grind_owner(wanted: impl Fn(u8) -> bool) -> Owner {
+    loop {
+        let key = Pubkey::new_unique();
+        let (vault, bump) = Pubkey::find_program_address(&[b"vault", key.as_ref()], &PROGRAM_ID);
+        if wanted(bump) {
+            return Owner { key, vault, bump };
+        }
+    }
+}
+
+/// The owner's wallet and their open vault holding BALANCE.
+fn accounts(mollusk: &Mollusk, owner: &Owner) -> Vec<(Pubkey, AccountSharedData)> {
+    let rent = mollusk.sysvars.rent.minimum_balance(VAULT_LEN);
+    let data = serialize_vault(&owner.key, owner.bump);
+    let mut vault = AccountSharedData::new(rent + BALANCE, data.len(), &PROGRAM_ID);
+    vault.set_data_from_slice(&data);
+    vec![
+        (owner.vault, vault),
+        (
+            owner.key,
+            AccountSharedData::new(SOL, 0, &solana_sdk::system_program::ID),
+        ),
+    ]
+}
+
+/// Withdraw BALANCE. `bump` is appended to the instruction data for the
+/// supplied-bump variant.
+fn withdraw_ix(name: &str, owner: &Owner, bump: Option<u8>) -> Instruction {
+    let mut data = ix_discriminator(name).to_vec();
+    data.extend_from_slice(&BALANCE.to_le_bytes());
+    if let Some(bump) = bump {
+        data.push(bump);
+    }
+    Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(owner.vault, false),
+            AccountMeta::new(owner.key, true),
+        ],
+    )
+}
+
+/// Compute units a successful withdrawal consumed.
+fn withdraw_cost(mollusk: &Mollusk, name: &str, owner: &Owner) -> u64 {
+    let result = mollusk.process_and_validate_instruction(
+        &withdraw_ix(name, owner, None),
+        &accounts(mollusk, owner),
+        &[Check::success()],
+    );
+    result.compute_units_consumed
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_recompute_cost_grows_with_bump_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: An owner ground until their vault bump is 247 or lower
+    // withdraws through each path.
+    //
+    // Expected: both SUCCEED. withdraw_recompute_vulnerable costs at least
+    //           (255 - bump) × 1,500 CU more than withdraw_secure: one
+    //           extra derivation for every bump find_program_address tried
+    //           and rejected.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "stored_bump");
+    let owner = grind_owner(|bump| bump <= LOW_BUMP);
+
+    let recompute = withdraw_cost(&mollusk, "withdraw_recompute_vulnerable", &owner);
+    let stored = withdraw_cost(&mollusk, "withdraw_secure", &owner);
+
+    let wasted = (255 - owner.bump) as u64 * DERIVATION_CU;
+    assert!(
+        recompute >= stored + wasted,
+        "bump {}: recompute {recompute} CU, stored {stored} CU",
+        owner.bump
+    );
+}
+
+#[test]
+fn exploit_wrong_supplied_bump_locks_owner_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: A client that assumes bump 255 withdraws for an owner whose
+    // vault bump is 254. The vault's stored bump is 254.
+    //
+    // Expected: withdraw_supplied_bump_vulnerable(bump = 255) FAILS with
+    //           ConstraintSeeds (2006). The same call with 254 SUCCEEDS, so
+    //           the owner's access depends on what the client sent.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "stored_bump");
+    let owner = grind_owner(|bump| bump == 254);
+    let accounts = accounts(&mollusk, &owner);
+
+    mollusk.process_and_validate_instruction(
+        &withdraw_ix("withdraw_supplied_bump_vulnerable", &owner, Some(255)),
+        &accounts,
+        &[Check::err(ProgramError::Custom(CONSTRAINT_SEEDS))],
+    );
+    mollusk.process_and_validate_instruction(
+        &withdraw_ix("withdraw_supplied_bump_vulnerable", &owner, Some(254)),
+        &accounts,
+        &[Check::success()],
+    );
+}
+
+#[test]
+fn secure_stored_bump_cost_is_flat() {
+    // -----------------------------------------------------------------------
+    // SECURE: A bump-255 owner and a ground low-bump owner each withdraw
+    // through withdraw_secure.
+    //
+    // Expected: both SUCCEED, and their costs differ by less than one
+    //           derivation. The stored bump is checked with a single
+    //           create_program_address whatever its value.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "stored_bump");
+    let high = grind_owner(|bump| bump == 255);
+    let low = grind_owner(|bump| bump <= LOW_BUMP);
+
+    let high_cost = withdraw_cost(&mollusk, "withdraw_secure", &high);
+    let low_cost = withdraw_cost(&mollusk, "withdraw_secure", &low);
+
+    assert!(
+        high_cost.abs_diff(low_cost) < DERIVATION_CU,
+        "bump 255: {high_cost} CU, bump {}: {low_cost} CU",
+        low.bump
+    );
+}
+
+#[test]
+fn sanity_owner_withdraws_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The bump-254 owner withdraws their 5 SOL through
+    // withdraw_secure, with no bump in the instruction.
+    //
+    // Expected: SUCCEEDS. The owner receives 5 SOL and the vault keeps its
+    //           rent.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "stored_bump");
+    let owner = grind_owner(|bump| bump == 254);
+    let rent = mollusk.sysvars.rent.minimum_balance(VAULT_LEN);
+
+    mollusk.process_and_validate_instruction(
+        &withdraw_ix("withdraw_secure", &owner, None),
+        &accounts(&mollusk, &owner),
+        &[
+            Check::success(),
+            Check::account(&owner.key).lamports(SOL + BALANCE).build(),
+            Check::account(&owner.vault).lamports(rent).build(),
+        ],
+    );
+}
@@ -0,0 +1,242 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("59yau2pG1ckqduAjKUmmYL59grVfPZoRWrrv98cLr47m");
+
+/// # Unpersisted Mutation
+///
+/// This program demonstrates an airdrop whose double-claim fix writes to an
+/// account that Anchor never saves.
+///
+/// ## The Vulnerability
+/// Each recipient has a `ClaimRecord`. After a double-claim incident the
+/// team added a `claimed` flag: `claim` checks it, sets it, and pays out.
+/// The record was declared without `mut`. Anchor still hands the handler a
+/// deserialized copy it can change, but only serializes `mut` accounts back
+/// when the instruction ends. The flag is set in memory and thrown away.
+///
+/// ## Real-World Impact
+/// The fix compiles, passes review, and its first claim behaves exactly
+/// like a correct one. Only a second claim by the same recipient shows that
+/// nothing was recorded, and that is the one case a test suite written for
+/// the "happy path" never tries. The distributor pays every recipient as
+/// many times as they ask.
+#[program]
+pub mod unpersisted_mutation {
+    use super::*;
+
+    /// Create the distributor, paying `amount` lamports per claim.
+    pub fn init_distributor(ctx: Context<InitDistributor>, amount: u64) -> Result<()> {
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.admin = ctx.accounts.admin.key();
+        distributor.amount = amount;
+        distributor.bump = ctx.bumps.distributor;
+        demo_log!(
+            Mode::Setup,
+            "init_distributor",
+            actor = distributor.admin,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Admin: make `recipient` eligible for one claim.
+    pub fn register(ctx: Context<Register>, recipient: Pubkey) -> Result<()> {
+        let record = &mut ctx.accounts.claim_record;
+        record.distributor = ctx.accounts.distributor.key();
+        record.recipient = recipient;
+        record.claimed = false;
+        record.bump = ctx.bumps.claim_record;
+        demo_log!(
+            Mode::Setup,
+            "register",
+            actor = ctx.accounts.admin.key(),
+            recipient = recipient
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: The claimed flag is set on a read-only account
+    // ============================================================================
+    // ISSUE: `claim_record` has no `mut` in `ClaimVulnerable`. The handler
+    //        can still assign `claim_record.claimed = true`, because
+    //        `ctx.accounts` is `&mut`. Anchor's generated exit step only
+    //        serializes fields marked `mut`, so the assignment never
+    //        reaches the account.
+    //
+    // ATTACK SCENARIO:
+    //   1. Recipient claims: `claimed` is false, the handler sets it and
+    //      pays `amount`
+    //   2. The instruction ends; the record's data is unchanged
+    //   3. Recipient claims again, and again: every call sees
+    //      `claimed == false`
+    //
+    // WHY IT SURVIVES REVIEW:
+    // The diff that added the flag touches only the handler, and the
+    // handler is correct. The missing `mut` is in a struct the diff didn't
+    // show.
+    // ============================================================================
+    pub fn claim_vulnerable(ctx: Context<ClaimVulnerable>) -> Result<()> {
+        let record = &mut ctx.accounts.claim_record;
+        require!(!record.claimed, ErrorCode::AlreadyClaimed);
+        // VULNERABLE: lost when the instruction ends
+        record.claimed = true;
+
+        let amount = ctx.accounts.distributor.amount;
+        pay(
+            &ctx.accounts.distributor.to_account_info(),
+            &ctx.accounts.recipient.to_account_info(),
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "claim",
+            actor = ctx.accounts.recipient.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Every account the handler writes is `mut`
+    // ============================================================================
+    // FIX: `claim_record` is `#[account(mut, ...)]`. Anchor writes the flag
+    //      back, and the second claim fails `AlreadyClaimed`.
+    //
+    // CATCHING IT:
+    // `tools/detector` reads each handler in the `#[program]` module and
+    // reports writes through `ctx.accounts.x` to a field of the handler's
+    // accounts struct that isn't `mut`, `init`, `init_if_needed`, or `zero`.
+    // It flags `claim_vulnerable` and passes this handler.
+    // ============================================================================
+    pub fn claim_secure(ctx: Context<ClaimSecure>) -> Result<()> {
+        let record = &mut ctx.accounts.claim_record;
+        require!(!record.claimed, ErrorCode::AlreadyClaimed);
+        record.claimed = true;
+
+        let amount = ctx.accounts.distributor.amount;
+        pay(
+            &ctx.accounts.distributor.to_account_info(),
+            &ctx.accounts.recipient.to_account_info(),
+            amount,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "claim",
+            actor = ctx.accounts.recipient.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// Move `amount` lamports from the program-owned distributor.
+fn pay(distributor: &AccountInfo, recipient: &AccountInfo, amount: u64) -> Result<()> {
+    **distributor.try_borrow_mut_lamports()? = distributor
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    **recipient.try_borrow_mut_lamports()? = recipient
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitDistributor<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Distributor::INIT_SPACE,
+        seeds = [b"distributor"],
+        bump
+    )]
+    pub distributor: Account<'info, Distributor>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct Register<'info> {
+    #[account(seeds = [b"distributor"], bump = distributor.bump, has_one = admin)]
+    pub distributor: Account<'info, Distributor>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ClaimRecord::INIT_SPACE,
+        seeds = [b"claim", distributor.key().as_ref(), recipient.as_ref()],
+        bump
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// VULNERABLE account structure - `claim_record` is written but not `mut`
+#[derive(Accounts)]
+pub struct ClaimVulnerable<'info> {
+    #[account(mut, seeds = [b"distributor"], bump = distributor.bump)]
+    pub distributor: Account<'info, Distributor>,
+    #[account(
+        seeds = [b"claim", distributor.key().as_ref(), recipient.key().as_ref()],
+        bump = claim_record.bump,
+        has_one = recipient
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+}
+
+/// SECURE account structure - identical except for `mut` on `claim_record`
+#[derive(Accounts)]
+pub struct ClaimSecure<'info> {
+    #[account(mut, seeds = [b"distributor"], bump = distributor.bump)]
+    pub distributor: Account<'info, Distributor>,
+    #[account(
+        mut,
+        seeds = [b"claim", distributor.key().as_ref(), recipient.key().as_ref()],
+        bump = claim_record.bump,
+        has_one = recipient
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Distributor {
+    pub admin: Pubkey, // 32 bytes
+    pub amount: u64,   //  8 bytes, lamports per claim
+    pub bump: u8,      //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimRecord {
+    pub distributor: Pubkey, // 32 bytes
+    pub recipient: Pubkey,   // 32 bytes
+    pub claimed: bool,       //  1 byte
+    pub bump: u8,            //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("This recipient has already claimed")]
+    AlreadyClaimed,
+    #[msg("Distributor cannot cover the claim")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
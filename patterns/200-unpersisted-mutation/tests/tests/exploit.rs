@@ -0,0 +1,219 @@
+/// # Pattern 200: Unpersisted Mutation — Mollusk Exploit Tests
+///
+/// The distributor holds 100 SOL and pays 1 SOL per claim. One recipient is
+/// registered and hasn't claimed. Each test feeds resulting accounts into
+/// the next instruction, so a flag that was saved is seen by the next claim.
+///
+/// - Test 1: EXPLOIT — the recipient claims three times through
+///   claim_vulnerable; the record still says unclaimed.
+/// - Test 2: SECURE — the second claim through claim_secure fails.
+/// - Test 3: SANITY — the first claim through claim_secure pays and saves
+///   the flag.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("59yau2pG1ckqduAjKUmmYL59grVfPZoRWrrv98cLr47m");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const POOL: u64 = 100 * SOL;
+const CLAIM: u64 = SOL;
+
+// Error codes
+const ALREADY_CLAIMED: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Distributor account: [8 disc][32 admin][8 amount][1 bump]
+fn serialize_distributor(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Distributor"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&CLAIM.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a ClaimRecord account:
+/// [8 disc][32 distributor][32 recipient][1 claimed][1 bump]
+fn serialize_record(distributor: &Pubkey, recipient: &Pubkey, claimed: bool, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(74);
+    data.extend_from_slice(&account_discriminator("ClaimRecord"));
+    data.extend_from_slice(distributor.as_ref());
+    data.extend_from_slice(recipient.as_ref());
+    data.push(claimed as u8);
+    data.push(bump);
+    data
+}
+
+/// A registered recipient who hasn't claimed yet.
+struct Airdrop {
+    distributor: Pubkey,
+    record: Pubkey,
+    record_bump: u8,
+    recipient: Pubkey,
+    ledger: Ledger,
+}
+
+impl Airdrop {
+    fn new() -> Self {
+        let admin = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let (distributor, distributor_bump) =
+            Pubkey::find_program_address(&[b"distributor"], &PROGRAM_ID);
+        let (record, record_bump) = Pubkey::find_program_address(
+            &[b"claim", distributor.as_ref(), recipient.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                distributor,
+                owned_account(
+                    &serialize_distributor(&admin, distributor_bump),
+                    RENT + POOL,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                record,
+                owned_account(
+                    &serialize_record(&distributor, &recipient, false, record_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                recipient,
+                AccountSharedData::new(RENT, 0, &solana_sdk::system_program::ID),
+            ),
+        ];
+
+        Self {
+            distributor,
+            record,
+            record_bump,
+            recipient,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// A claim, with `claim_record` passed writable only when the accounts
+    /// struct asks for it, as a client built from the IDL would.
+    fn claim(&self, name: &str, record_writable: bool) -> Instruction {
+        let record = if record_writable {
+            AccountMeta::new(self.record, false)
+        } else {
+            AccountMeta::new_readonly(self.record, false)
+        };
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.distributor, false),
+                record,
+                AccountMeta::new(self.recipient, true),
+            ],
+        )
+    }
+
+    fn record_data(&self, claimed: bool) -> Vec<u8> {
+        serialize_record(
+            &self.distributor,
+            &self.recipient,
+            claimed,
+            self.record_bump,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_repeat_claims_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The recipient calls claim_vulnerable three times.
+    //
+    // Each call checks `claimed`, sets it, and pays. Anchor never writes the
+    // record back, so each call sees it unset again.
+    //
+    // Expected: all three SUCCEED. The recipient receives 3 SOL and the
+    //           record's data is byte-for-byte what it was before.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "unpersisted_mutation");
+    let mut airdrop = Airdrop::new();
+    let (record, recipient) = (airdrop.record, airdrop.recipient);
+    let ix = airdrop.claim("claim_vulnerable", false);
+    let unclaimed = airdrop.record_data(false);
+
+    for n in 1..=3 {
+        airdrop.ledger.step(
+            &mollusk,
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&record).data(&unclaimed).build(),
+                Check::account(&recipient)
+                    .lamports(RENT + n * CLAIM)
+                    .build(),
+            ],
+        );
+    }
+}
+
+#[test]
+fn secure_second_claim_rejected() {
+    // -----------------------------------------------------------------------
+    // SECURE: The recipient calls claim_secure twice.
+    //
+    // Expected: the first SUCCEEDS; the second FAILS with AlreadyClaimed
+    //           (6000), because the first one's flag was saved.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "unpersisted_mutation");
+    let mut airdrop = Airdrop::new();
+    let ix = airdrop.claim("claim_secure", true);
+
+    airdrop.ledger.step(&mollusk, &ix, &[Check::success()]);
+    airdrop.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(ALREADY_CLAIMED))],
+    );
+}
+
+#[test]
+fn sanity_first_claim_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The recipient's first claim_secure.
+    //
+    // Expected: SUCCEEDS — 1 SOL moves and the record now reads claimed.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "unpersisted_mutation");
+    let mut airdrop = Airdrop::new();
+    let (record, distributor, recipient) = (airdrop.record, airdrop.distributor, airdrop.recipient);
+    let ix = airdrop.claim("claim_secure", true);
+    let claimed = airdrop.record_data(true);
+
+    airdrop.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&record).data(&claimed).build(),
+            Check::account(&distributor)
+                .lamports(RENT + POOL - CLAIM)
+                .build(),
+            Check::account(&recipient).lamports(RENT + CLAIM).build(),
+        ],
+    );
+}
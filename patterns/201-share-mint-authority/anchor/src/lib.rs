@@ -0,0 +1,301 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("6BdVcnqVbsrYB8SbqiyuUsCstdUKvjPmFusoA32Tb96S");
+
+/// # Share Mint Authority
+///
+/// ## The Vulnerability
+/// A pool sells shares against SOL and buys them back pro rata. Shares are
+/// SPL tokens; the pool prices them from the mint's supply minus what sits
+/// unsold in its reserve. That price is only honest if nobody outside the
+/// pool can mint. The creator makes the share mint, and the launch script
+/// is supposed to hand its mint authority to the pool PDA before anyone
+/// buys. Nothing on-chain checks that it did. A creator who kept the
+/// authority mints shares to themselves through SPL Token and redeems them.
+///
+/// ## Why It Matters
+/// Every share the creator mints is a claim on SOL other people paid in.
+/// The pool's own books balance, because `total_assets` and the share
+/// supply both look consistent to it; holders only find out when their
+/// shares redeem for a fraction of what they paid.
+///
+/// ## The Fix
+/// Check `share_mint.mint_authority == Some(pool)` in every instruction
+/// that reads the mint, not once at creation: a launch-time check still
+/// trusts a script to run, and a pool whose mint escaped must stop trading.
+#[program]
+pub mod share_mint_authority {
+    use super::*;
+
+    /// Register a pool over `share_mint`, with `reserve` holding the shares
+    /// it hasn't sold. The launch script sets the mint authority to the pool
+    /// in the same transaction.
+    pub fn create_pool(ctx: Context<CreatePool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.creator = ctx.accounts.creator.key();
+        pool.share_mint = ctx.accounts.share_mint.key();
+        pool.reserve = ctx.accounts.reserve.key();
+        pool.total_assets = 0;
+        pool.bump = ctx.bumps.pool;
+
+        demo_log!(
+            Mode::Setup,
+            "create_pool",
+            actor = pool.creator,
+            mint = pool.share_mint
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Share price read from a mint anyone may hold authority over
+    // ============================================================================
+    // ISSUE: Shares are priced from `supply - reserve.amount`, and nothing
+    //        checks who can raise `supply`. The pool PDA was meant to be the
+    //        only minter; the creator's key still is.
+    //
+    // ATTACK SCENARIO:
+    //   1. Creator makes the share mint, creates the pool, and skips the
+    //      set_authority step
+    //   2. Buyers pay 100 SOL for 100 shares
+    //   3. Creator calls SPL Token mint_to: 900 shares to themselves
+    //   4. Creator redeems the 900 for 900/1,000 of the pool: 90 SOL
+    //   5. The buyers' 100 shares are now worth 10 SOL
+    // ============================================================================
+    pub fn buy_vulnerable(ctx: Context<Buy>, amount: u64) -> Result<()> {
+        // VULNERABLE: whoever holds the mint authority is never looked at
+        buy(ctx, amount, Mode::Vulnerable)
+    }
+
+    pub fn redeem_vulnerable(ctx: Context<Redeem>, shares: u64) -> Result<()> {
+        // VULNERABLE: shares minted outside the pool redeem like any other
+        redeem(ctx, shares, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Mint authority asserted wherever the mint is read
+    // ============================================================================
+    // FIX: Both instructions require `mint_authority == Some(pool)` before
+    //      pricing anything. A pool whose creator kept the mint can't sell
+    //      or redeem a single share.
+    //
+    // WHY NOT ONLY AT CREATION:
+    // Creation is where the script was supposed to act, so that's where
+    // reviewers look. Checking on every read also covers a mint whose
+    // authority is handed back later by an upgrade or an admin path.
+    // ============================================================================
+    pub fn buy_secure(ctx: Context<Buy>, amount: u64) -> Result<()> {
+        // SECURE: only the pool may have minted the shares being priced
+        assert_pool_mints(&ctx.accounts.share_mint, &ctx.accounts.pool)?;
+        buy(ctx, amount, Mode::Secure)
+    }
+
+    pub fn redeem_secure(ctx: Context<Redeem>, shares: u64) -> Result<()> {
+        // SECURE: only the pool may have minted the shares being priced
+        assert_pool_mints(&ctx.accounts.share_mint, &ctx.accounts.pool)?;
+        redeem(ctx, shares, Mode::Secure)
+    }
+}
+
+fn assert_pool_mints(share_mint: &Account<Mint>, pool: &Account<Pool>) -> Result<()> {
+    require!(
+        share_mint.mint_authority == COption::Some(pool.key()),
+        ErrorCode::MintAuthorityNotPool
+    );
+    Ok(())
+}
+
+/// Shares held outside the reserve: the ones with a claim on the pool.
+fn outstanding(share_mint: &Account<Mint>, reserve: &Account<TokenAccount>) -> Result<u64> {
+    share_mint
+        .supply
+        .checked_sub(reserve.amount)
+        .ok_or(error!(ErrorCode::Overflow))
+}
+
+/// `value * numerator / denominator` in u128, rounded down.
+fn mul_div(value: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    require!(denominator > 0, ErrorCode::EmptyPool);
+    let scaled = value as u128 * numerator as u128 / denominator as u128;
+    u64::try_from(scaled).map_err(|_| error!(ErrorCode::Overflow))
+}
+
+/// Take `amount` lamports and hand over shares from the reserve at the
+/// current price. The first buyer gets one share per lamport.
+fn buy(ctx: Context<Buy>, amount: u64, mode: Mode) -> Result<()> {
+    let outstanding = outstanding(&ctx.accounts.share_mint, &ctx.accounts.reserve)?;
+    let shares = if outstanding == 0 {
+        amount
+    } else {
+        mul_div(amount, outstanding, ctx.accounts.pool.total_assets)?
+    };
+    require!(shares > 0, ErrorCode::ZeroShares);
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.pool.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let share_mint = ctx.accounts.share_mint.key();
+    let seeds: &[&[u8]] = &[b"pool", share_mint.as_ref(), &[ctx.accounts.pool.bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.reserve.to_account_info(),
+                to: ctx.accounts.buyer_shares.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            &[seeds],
+        ),
+        shares,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.total_assets = pool
+        .total_assets
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+
+    demo_log!(
+        mode,
+        "buy",
+        actor = ctx.accounts.buyer.key(),
+        amount = amount,
+        shares = shares
+    );
+    Ok(())
+}
+
+/// Return `shares` to the reserve and pay out their pro-rata share of the
+/// pool's assets.
+fn redeem(ctx: Context<Redeem>, shares: u64, mode: Mode) -> Result<()> {
+    let outstanding = outstanding(&ctx.accounts.share_mint, &ctx.accounts.reserve)?;
+    let payout = mul_div(shares, ctx.accounts.pool.total_assets, outstanding)?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.holder_shares.to_account_info(),
+                to: ctx.accounts.reserve.to_account_info(),
+                authority: ctx.accounts.holder.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.total_assets = pool
+        .total_assets
+        .checked_sub(payout)
+        .ok_or(ErrorCode::Overflow)?;
+    **pool.to_account_info().try_borrow_mut_lamports()? -= payout;
+    **ctx.accounts.holder.try_borrow_mut_lamports()? += payout;
+
+    demo_log!(
+        mode,
+        "redeem",
+        actor = ctx.accounts.holder.key(),
+        amount = payout,
+        shares = shares,
+        outstanding = outstanding
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", share_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    pub share_mint: Account<'info, Mint>,
+    #[account(token::mint = share_mint, token::authority = pool)]
+    pub reserve: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Buy<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", share_mint.key().as_ref()],
+        bump = pool.bump,
+        has_one = share_mint,
+        has_one = reserve
+    )]
+    pub pool: Account<'info, Pool>,
+    pub share_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub reserve: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = share_mint)]
+    pub buyer_shares: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", share_mint.key().as_ref()],
+        bump = pool.bump,
+        has_one = share_mint,
+        has_one = reserve
+    )]
+    pub pool: Account<'info, Pool>,
+    pub share_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub reserve: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = share_mint, token::authority = holder)]
+    pub holder_shares: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Lamports above rent are the pool's assets, tracked in `total_assets`.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub creator: Pubkey,    // 32 bytes
+    pub share_mint: Pubkey, // 32 bytes
+    pub reserve: Pubkey,    // 32 bytes, the pool's unsold shares
+    pub total_assets: u64,  //  8 bytes
+    pub bump: u8,           //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Share mint authority is not the pool")]
+    MintAuthorityNotPool,
+    #[msg("Amount buys zero shares")]
+    ZeroShares,
+    #[msg("Pool has no outstanding shares")]
+    EmptyPool,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
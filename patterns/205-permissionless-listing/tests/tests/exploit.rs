@@ -0,0 +1,469 @@
+/// # Pattern 205: Permissionless Listing — Mollusk Exploit Tests
+///
+/// A market holds 100 SOL of lenders' liquidity. Governance trusts one
+/// price publisher and makes new listings wait two days. Two mints exist:
+/// a real one, priced at 1 SOL per token by the trusted publisher, and the
+/// attacker's junk mint, priced at 1,000 SOL per token by the attacker's
+/// own oracle. Each borrower already has collateral in custody: the
+/// attacker 1 junk token, the honest borrower 100 real tokens.
+///
+/// - Test 1: EXPLOIT — the attacker lists the junk mint at 100% LTV on
+///   their own oracle through list_asset_vulnerable and borrows all 100 SOL
+///   in the next instruction.
+/// - Test 2: SECURE — list_asset_secure signed by the attacker fails with
+///   Unauthorized.
+/// - Test 3: SECURE — even governance can't list the junk mint on the
+///   attacker's oracle: UntrustedOracle.
+/// - Test 4: SANITY — governance lists the real mint at 50% LTV. A borrow
+///   during the listing delay fails with AssetNotActive; after the delay,
+///   the honest borrower takes 50 SOL against 100 tokens.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Accounts, Ledger,
+};
+use test_harness::token::{Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("Bw8DmKmPjMNVAaNqxA66dgRK7j12KHJG29ggFGBHVSf4");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const TOKEN_RENT: u64 = 2_039_280;
+const WALLET: u64 = 10 * SOL;
+const LIQUIDITY: u64 = 100 * SOL;
+
+const NOW: i64 = 1_700_000_000;
+const LISTING_DELAY: i64 = 2 * 24 * 60 * 60;
+
+/// Prices in lamports per token (both mints have 0 decimals).
+const REAL_PRICE: u64 = SOL;
+const JUNK_PRICE: u64 = 1_000 * SOL;
+
+const ASSET_LEN: usize = 147;
+
+// Error codes
+const UNAUTHORIZED: u32 = 6000;
+const UNTRUSTED_ORACLE: u32 = 6001;
+const ASSET_NOT_ACTIVE: u32 = 6002;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Market account:
+/// [8 disc][32 governance][32 oracle_authority][8 listing_delay][1 bump]
+fn serialize_market(governance: &Pubkey, oracle_authority: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(governance.as_ref());
+    data.extend_from_slice(oracle_authority.as_ref());
+    data.extend_from_slice(&LISTING_DELAY.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an Oracle account:
+/// [8 disc][32 authority][32 mint][8 price][1 bump]
+fn serialize_oracle(authority: &Pubkey, mint: &Pubkey, price: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Oracle"));
+    data.extend_from_slice(authority.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&price.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an Asset account:
+/// [8 disc][32 mint][32 custody][32 oracle][2 ltv_bps][8 activates_at]
+/// [32 listed_by][1 bump]
+fn serialize_asset(
+    listing: &Listing,
+    ltv_bps: u16,
+    activates_at: i64,
+    listed_by: &Pubkey,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(ASSET_LEN);
+    data.extend_from_slice(&account_discriminator("Asset"));
+    data.extend_from_slice(listing.mint.as_ref());
+    data.extend_from_slice(listing.custody.as_ref());
+    data.extend_from_slice(listing.oracle.as_ref());
+    data.extend_from_slice(&ltv_bps.to_le_bytes());
+    data.extend_from_slice(&activates_at.to_le_bytes());
+    data.extend_from_slice(listed_by.as_ref());
+    data.push(listing.asset_bump);
+    data
+}
+
+/// Serialize a Position account:
+/// [8 disc][32 asset][32 owner][8 collateral][8 debt][1 bump]
+fn serialize_position(
+    asset: &Pubkey,
+    owner: &Pubkey,
+    collateral: u64,
+    debt: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(89);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(asset.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&collateral.to_le_bytes());
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with no decimals and no freeze authority.
+fn serialize_mint(authority: &Pubkey, supply: u64) -> Vec<u8> {
+    Mint {
+        mint_authority: Some(*authority),
+        supply,
+        ..Mint::default()
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "permissionless_listing");
+    mollusk.sysvars.clock.unix_timestamp = NOW;
+    mollusk
+}
+
+/// One mint that could be listed, with its custody, its oracle, and the
+/// borrower whose collateral already sits in that custody.
+struct Listing {
+    mint: Pubkey,
+    custody: Pubkey,
+    oracle: Pubkey,
+    asset: Pubkey,
+    asset_bump: u8,
+    borrower: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    collateral: u64,
+}
+
+impl Listing {
+    fn new(
+        market: &Pubkey,
+        publisher: &Pubkey,
+        price: u64,
+        borrower: Pubkey,
+        collateral: u64,
+        ledger: &mut Accounts,
+    ) -> Self {
+        let mint = Pubkey::new_unique();
+        let custody = Pubkey::new_unique();
+        let (oracle, oracle_bump) = Pubkey::find_program_address(
+            &[b"oracle", mint.as_ref(), publisher.as_ref()],
+            &PROGRAM_ID,
+        );
+        let (asset, asset_bump) =
+            Pubkey::find_program_address(&[b"asset", mint.as_ref()], &PROGRAM_ID);
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", asset.as_ref(), borrower.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        ledger.extend([
+            (
+                mint,
+                owned_account(
+                    &serialize_mint(&borrower, collateral),
+                    RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (
+                custody,
+                owned_account(
+                    &TokenAccount::new(&mint, market, collateral).pack(),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (
+                oracle,
+                owned_account(
+                    &serialize_oracle(publisher, &mint, price, oracle_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (asset, AccountSharedData::default()),
+            (
+                position,
+                owned_account(
+                    &serialize_position(&asset, &borrower, collateral, 0, position_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+        ]);
+
+        Self {
+            mint,
+            custody,
+            oracle,
+            asset,
+            asset_bump,
+            borrower,
+            position,
+            position_bump,
+            collateral,
+        }
+    }
+}
+
+/// The market with its liquidity, and the two mints waiting to be listed.
+struct Setup {
+    governance: Pubkey,
+    attacker: Pubkey,
+    market: Pubkey,
+    market_rent: u64,
+    real: Listing,
+    junk: Listing,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new(mollusk: &Mollusk) -> Self {
+        let governance = Pubkey::new_unique();
+        let oracle_authority = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let honest = Pubkey::new_unique();
+        let (market, market_bump) = Pubkey::find_program_address(&[b"market"], &PROGRAM_ID);
+
+        let market_data = serialize_market(&governance, &oracle_authority, market_bump);
+        let market_rent = mollusk.sysvars.rent.minimum_balance(market_data.len());
+        let mut ledger = vec![
+            (
+                market,
+                owned_account(&market_data, market_rent + LIQUIDITY, &PROGRAM_ID),
+            ),
+            (governance, system_account()),
+            (attacker, system_account()),
+            (honest, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        let real = Listing::new(
+            &market,
+            &oracle_authority,
+            REAL_PRICE,
+            honest,
+            100,
+            &mut ledger,
+        );
+        let junk = Listing::new(&market, &attacker, JUNK_PRICE, attacker, 1, &mut ledger);
+
+        Self {
+            governance,
+            attacker,
+            market,
+            market_rent,
+            real,
+            junk,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn list(&self, name: &str, listing: &Listing, lister: Pubkey, ltv_bps: u16) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&ltv_bps.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.market, false),
+                AccountMeta::new(listing.asset, false),
+                AccountMeta::new_readonly(listing.mint, false),
+                AccountMeta::new_readonly(listing.custody, false),
+                AccountMeta::new_readonly(listing.oracle, false),
+                AccountMeta::new(lister, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn borrow(&self, listing: &Listing, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("borrow").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.market, false),
+                AccountMeta::new_readonly(listing.asset, false),
+                AccountMeta::new_readonly(listing.oracle, false),
+                AccountMeta::new(listing.position, false),
+                AccountMeta::new(listing.borrower, true),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_self_listed_junk_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker's oracle says one junk token is worth 1,000 SOL.
+    //   1. list_asset_vulnerable(10,000 bps), signed by the attacker
+    //   2. borrow(100 SOL) against the 1 junk token in custody
+    //
+    // Expected: both SUCCEED. The asset is live immediately, and the market
+    //           is left with nothing but rent.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(&mollusk);
+    let (junk_asset, junk_position, market, attacker) =
+        (s.junk.asset, s.junk.position, s.market, s.attacker);
+    let asset_rent = mollusk.sysvars.rent.minimum_balance(ASSET_LEN);
+
+    let ix = s.list("list_asset_vulnerable", &s.junk, s.attacker, 10_000);
+    let asset_data = serialize_asset(&s.junk, 10_000, NOW, &s.attacker);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&junk_asset).data(&asset_data).build(),
+        ],
+    );
+
+    let ix = s.borrow(&s.junk, LIQUIDITY);
+    let position_data = serialize_position(
+        &s.junk.asset,
+        &s.attacker,
+        s.junk.collateral,
+        LIQUIDITY,
+        s.junk.position_bump,
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&junk_position).data(&position_data).build(),
+            Check::account(&market).lamports(s.market_rent).build(),
+            Check::account(&attacker)
+                .lamports(WALLET - asset_rent + LIQUIDITY)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_listing_rejects_non_governance() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker tries the same listing through list_asset_secure.
+    //
+    // Expected: FAILS with Unauthorized (6000). Only the market's governance
+    //           gets an AdminCap.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(&mollusk);
+
+    let ix = s.list("list_asset_secure", &s.junk, s.attacker, 8_000);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(UNAUTHORIZED))],
+    );
+}
+
+#[test]
+fn secure_listing_rejects_untrusted_oracle() {
+    // -----------------------------------------------------------------------
+    // SECURE: Governance signs a listing of the junk mint, but the oracle
+    // passed is the one the attacker published.
+    //
+    // Expected: FAILS with UntrustedOracle (6001). A governance signature
+    //           doesn't make the attacker's price trustworthy.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(&mollusk);
+
+    let ix = s.list("list_asset_secure", &s.junk, s.governance, 8_000);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(UNTRUSTED_ORACLE))],
+    );
+}
+
+#[test]
+fn sanity_governance_listing_after_delay_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: Governance lists the real mint at 50% LTV on the trusted
+    // oracle. The honest borrower has 100 tokens, worth 100 SOL.
+    //   1. list_asset_secure(5,000 bps)
+    //   2. borrow(50 SOL) in the same block
+    //   3. The listing delay passes; borrow(50 SOL) again
+    //
+    // Expected: 1 SUCCEEDS with activates_at two days out; 2 FAILS with
+    //           AssetNotActive (6002); 3 SUCCEEDS at exactly the LTV limit.
+    // -----------------------------------------------------------------------
+    let mut mollusk = new_mollusk();
+    let mut s = Setup::new(&mollusk);
+    let (real_asset, real_position, market, real_borrower) =
+        (s.real.asset, s.real.position, s.market, s.real.borrower);
+
+    let ix = s.list("list_asset_secure", &s.real, s.governance, 5_000);
+    let asset_data = serialize_asset(&s.real, 5_000, NOW + LISTING_DELAY, &s.governance);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&real_asset).data(&asset_data).build(),
+        ],
+    );
+
+    let ix = s.borrow(&s.real, 50 * SOL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(ASSET_NOT_ACTIVE))],
+    );
+
+    mollusk.sysvars.clock.unix_timestamp = NOW + LISTING_DELAY;
+    let position_data = serialize_position(
+        &s.real.asset,
+        &s.real.borrower,
+        s.real.collateral,
+        50 * SOL,
+        s.real.position_bump,
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&real_position).data(&position_data).build(),
+            Check::account(&market)
+                .lamports(s.market_rent + LIQUIDITY - 50 * SOL)
+                .build(),
+            Check::account(&real_borrower)
+                .lamports(WALLET + 50 * SOL)
+                .build(),
+        ],
+    );
+}
@@ -0,0 +1,292 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("BnHHNE3WpgqRxCxLPqvL98HVLqfn8NQbxx4otCadwZKR");
+
+/// # Nested PDA Authority
+///
+/// ## The Vulnerability
+/// A vault's owner appoints a manager, and the manager adds strategies,
+/// each run by an operator who can move the vault's SOL. Authority flows
+/// down a chain of accounts: vault → manager → strategy. Each child
+/// records its parent, and a withdrawal is allowed if the chain from the
+/// operator back up to the vault is intact. The vulnerable withdrawal
+/// checks that the manager belongs to the vault and that the operator
+/// runs the strategy, but never that the strategy belongs to the manager.
+///
+/// ## Why It Matters
+/// Anyone can open a vault, appoint themselves manager, and add a
+/// strategy they operate. Every account in that chain is real. The
+/// attacker then presents the victim's vault, the victim's manager, and
+/// their own strategy. The two hops that are checked pass, and the one
+/// that isn't is exactly where the attacker's chain was grafted on. The
+/// longer the chain, the easier it is to miss one link in review.
+///
+/// ## The Fix
+/// Check the parent field at every hop: `has_one = vault` on the manager
+/// and `has_one = manager` on the strategy, with the vault pinned by its
+/// seeds. Each check is one line; the chain is only as strong as the one
+/// that's missing.
+#[program]
+pub mod nested_pda_authority {
+    use super::*;
+
+    pub fn open_vault(ctx: Context<OpenVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.owner = ctx.accounts.owner.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(Mode::Setup, "open_vault", actor = vault.owner);
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Vault owner: delegate to `authority` as the vault's manager.
+    pub fn appoint_manager(ctx: Context<AppointManager>, authority: Pubkey) -> Result<()> {
+        let manager = &mut ctx.accounts.manager;
+        manager.vault = ctx.accounts.vault.key();
+        manager.authority = authority;
+        manager.bump = ctx.bumps.manager;
+        demo_log!(
+            Mode::Setup,
+            "appoint_manager",
+            actor = ctx.accounts.owner.key(),
+            vault = manager.vault,
+            authority = authority
+        );
+        Ok(())
+    }
+
+    /// Manager: add a strategy run by `operator`.
+    pub fn add_strategy(ctx: Context<AddStrategy>, operator: Pubkey) -> Result<()> {
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.manager = ctx.accounts.manager.key();
+        strategy.operator = operator;
+        strategy.bump = ctx.bumps.strategy;
+        demo_log!(
+            Mode::Setup,
+            "add_strategy",
+            actor = ctx.accounts.authority.key(),
+            manager = strategy.manager,
+            operator = operator
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: The strategy → manager hop is never checked
+    // ============================================================================
+    // ISSUE: The manager is checked against the vault and the operator
+    //        against the strategy, but `strategy.manager` is never compared
+    //        with the manager passed in. Any strategy the signer operates
+    //        completes the chain.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker opens their own vault, appoints themselves manager, and
+    //      adds a strategy they operate
+    //   2. Attacker calls strategy_withdraw_vulnerable with the victim's
+    //      vault, the victim's manager, and their own strategy
+    //   3. vault ← manager passes, strategy ← operator passes
+    //   4. The victim's SOL is paid to the attacker
+    // ============================================================================
+    pub fn strategy_withdraw_vulnerable(
+        ctx: Context<StrategyWithdrawVulnerable>,
+        amount: u64,
+    ) -> Result<()> {
+        // VULNERABLE: the strategy may hang off any manager
+        pay_out(
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.operator.to_account_info(),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Vulnerable,
+            "strategy_withdraw",
+            actor = ctx.accounts.operator.key(),
+            amount = amount,
+            manager = ctx.accounts.manager.key(),
+            strategy_manager = ctx.accounts.strategy.manager
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Every hop checks its parent
+    // ============================================================================
+    // FIX: `has_one = manager` on the strategy closes the missing link. With
+    //      the vault pinned by its seeds and `has_one = vault` on the
+    //      manager, the operator's authority traces back to this vault and
+    //      no other.
+    // ============================================================================
+    pub fn strategy_withdraw_secure(
+        ctx: Context<StrategyWithdrawSecure>,
+        amount: u64,
+    ) -> Result<()> {
+        // SECURE: vault ← manager ← strategy ← operator, all checked
+        pay_out(
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.operator.to_account_info(),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Secure,
+            "strategy_withdraw",
+            actor = ctx.accounts.operator.key(),
+            amount = amount,
+            manager = ctx.accounts.manager.key(),
+            strategy_manager = ctx.accounts.strategy.manager
+        );
+        Ok(())
+    }
+}
+
+fn pay_out<'info>(vault: &AccountInfo<'info>, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct OpenVault<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"vault", vault.owner.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(authority: Pubkey)]
+pub struct AppointManager<'info> {
+    #[account(seeds = [b"vault", owner.key().as_ref()], bump = vault.bump, has_one = owner)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Manager::INIT_SPACE,
+        seeds = [b"manager", vault.key().as_ref(), authority.as_ref()],
+        bump
+    )]
+    pub manager: Account<'info, Manager>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct AddStrategy<'info> {
+    #[account(has_one = authority)]
+    pub manager: Account<'info, Manager>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Strategy::INIT_SPACE,
+        seeds = [b"strategy", manager.key().as_ref(), operator.as_ref()],
+        bump
+    )]
+    pub strategy: Account<'info, Strategy>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StrategyWithdrawVulnerable<'info> {
+    #[account(mut, seeds = [b"vault", vault.owner.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(has_one = vault)]
+    pub manager: Account<'info, Manager>,
+    #[account(has_one = operator)] // <-- no has_one = manager
+    pub strategy: Account<'info, Strategy>,
+    #[account(mut)]
+    pub operator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StrategyWithdrawSecure<'info> {
+    #[account(mut, seeds = [b"vault", vault.owner.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(has_one = vault)]
+    pub manager: Account<'info, Manager>,
+    #[account(has_one = manager, has_one = operator)]
+    pub strategy: Account<'info, Strategy>,
+    #[account(mut)]
+    pub operator: Signer<'info>,
+}
+
+/// Lamports above rent are the owner's funds.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub owner: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Manager {
+    pub vault: Pubkey,     // 32 bytes, parent
+    pub authority: Pubkey, // 32 bytes
+    pub bump: u8,          //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Strategy {
+    pub manager: Pubkey,  // 32 bytes, parent
+    pub operator: Pubkey, // 32 bytes, may move the vault's SOL
+    pub bump: u8,         //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Vault cannot cover the withdrawal")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
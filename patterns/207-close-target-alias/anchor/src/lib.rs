@@ -0,0 +1,297 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("918dxFWQgbJADbxDYMaUX6aG44RDCZuodH5y49WQXTf2");
+
+/// # Close Target Alias
+///
+/// ## The Vulnerability
+/// A payout queue escrows each payment in its own ticket account. Anyone
+/// can crank `process_next`, which closes the ticket at the head of the
+/// queue with `close = recipient`, delivering the escrow and the rent in
+/// one step. The recipient is recorded when the ticket is created, and
+/// the vulnerable `enqueue` records any key at all, including the address
+/// of the ticket itself or of the queue.
+///
+/// ## Why It Matters
+/// Closing an account into itself would destroy its lamports, so Anchor
+/// rejects `close` when the target is the closed account
+/// (ConstraintClose). Closing a ticket into the queue would strand its
+/// escrow in an account nobody withdraws from, so `process_next` rejects
+/// that too. Both checks are right, and both make the close fail. A
+/// ticket whose recipient is itself can never be processed, and because
+/// the queue is FIFO, neither can any ticket behind it. One cheap
+/// enqueue freezes every later payment.
+///
+/// ## The Fix
+/// Keep the close target distinct from every account the instruction
+/// touches, and check it where it's recorded: `enqueue_secure` rejects a
+/// recipient equal to the ticket or the queue. A check at close time can
+/// only turn a bad target into a failed close.
+#[program]
+pub mod close_target_alias {
+    use super::*;
+
+    pub fn create_queue(ctx: Context<CreateQueue>) -> Result<()> {
+        let queue = &mut ctx.accounts.queue;
+        queue.head = 0;
+        queue.tail = 0;
+        queue.bump = ctx.bumps.queue;
+        demo_log!(
+            Mode::Setup,
+            "create_queue",
+            actor = ctx.accounts.payer.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Any recipient is recorded, including the ticket itself
+    // ============================================================================
+    // ISSUE: The ticket's address is known before it's created: its seeds
+    //        are the queue and the queue's tail. Recording it, or the
+    //        queue's address, as the recipient makes the ticket's close
+    //        target an account the close can never pay.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker derives the next ticket address and calls
+    //      enqueue_vulnerable with it as the recipient
+    //   2. Honest users enqueue payments behind the attacker's ticket
+    //   3. process_next on the attacker's ticket fails with ConstraintClose
+    //      every time it's tried
+    //   4. The head never advances; every escrowed payment is stuck
+    // ============================================================================
+    pub fn enqueue_vulnerable(
+        ctx: Context<EnqueueVulnerable>,
+        recipient: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        // VULNERABLE: recipient may be the ticket or the queue
+        let seq = ctx.accounts.queue.tail;
+        open_ticket(
+            &mut ctx.accounts.queue,
+            &mut ctx.accounts.ticket,
+            ctx.bumps.ticket,
+            recipient,
+            amount,
+        )?;
+        escrow(
+            &ctx.accounts.payer,
+            &ctx.accounts.ticket,
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        demo_log!(
+            Mode::Vulnerable,
+            "enqueue",
+            actor = ctx.accounts.payer.key(),
+            amount = amount,
+            seq = seq,
+            recipient = recipient
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: The recipient must be distinct from the ticket and the queue
+    // ============================================================================
+    // FIX: Constraints on `queue` and `ticket` compare the recipient with
+    //      both addresses before the ticket is recorded. Every ticket in
+    //      the queue can be closed into its recipient, so the head always
+    //      advances.
+    // ============================================================================
+    pub fn enqueue_secure(
+        ctx: Context<EnqueueSecure>,
+        recipient: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        // SECURE: the constraints kept the recipient apart from both accounts
+        let seq = ctx.accounts.queue.tail;
+        open_ticket(
+            &mut ctx.accounts.queue,
+            &mut ctx.accounts.ticket,
+            ctx.bumps.ticket,
+            recipient,
+            amount,
+        )?;
+        escrow(
+            &ctx.accounts.payer,
+            &ctx.accounts.ticket,
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        demo_log!(
+            Mode::Secure,
+            "enqueue",
+            actor = ctx.accounts.payer.key(),
+            amount = amount,
+            seq = seq,
+            recipient = recipient
+        );
+        Ok(())
+    }
+
+    /// Permissionless crank: pay out the ticket at the head of the queue by
+    /// closing it into its recipient.
+    pub fn process_next(ctx: Context<ProcessNext>) -> Result<()> {
+        let queue = &mut ctx.accounts.queue;
+        let seq = queue.head;
+        queue.head = seq.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "process_next",
+            actor = ctx.accounts.cranker.key(),
+            seq = seq,
+            recipient = ctx.accounts.recipient.key()
+        );
+        Ok(())
+    }
+}
+
+/// Record ticket `queue.tail` for `recipient` and advance the tail.
+fn open_ticket(
+    queue: &mut Account<Queue>,
+    ticket: &mut Account<Ticket>,
+    bump: u8,
+    recipient: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    ticket.seq = queue.tail;
+    ticket.recipient = recipient;
+    ticket.amount = amount;
+    ticket.bump = bump;
+    queue.tail = queue.tail.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// Move the payment into the ticket, on top of its rent.
+fn escrow<'info>(
+    payer: &Signer<'info>,
+    ticket: &Account<'info, Ticket>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            Transfer {
+                from: payer.to_account_info(),
+                to: ticket.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateQueue<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Queue::INIT_SPACE,
+        seeds = [b"queue"],
+        bump
+    )]
+    pub queue: Account<'info, Queue>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnqueueVulnerable<'info> {
+    #[account(mut, seeds = [b"queue"], bump = queue.bump)]
+    pub queue: Account<'info, Queue>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [b"ticket", queue.key().as_ref(), &queue.tail.to_le_bytes()],
+        bump
+    )] // <-- recipient never compared with ticket or queue
+    pub ticket: Account<'info, Ticket>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct EnqueueSecure<'info> {
+    #[account(
+        mut,
+        seeds = [b"queue"],
+        bump = queue.bump,
+        constraint = recipient != queue.key() @ ErrorCode::RecipientIsQueue
+    )]
+    pub queue: Account<'info, Queue>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [b"ticket", queue.key().as_ref(), &queue.tail.to_le_bytes()],
+        bump,
+        constraint = recipient != ticket.key() @ ErrorCode::RecipientIsTicket
+    )]
+    pub ticket: Account<'info, Ticket>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Anchor already rejects `recipient == ticket`; the constraint on
+/// `recipient` covers the queue, the other writable account here.
+#[derive(Accounts)]
+pub struct ProcessNext<'info> {
+    #[account(mut, seeds = [b"queue"], bump = queue.bump)]
+    pub queue: Account<'info, Queue>,
+    #[account(
+        mut,
+        seeds = [b"ticket", queue.key().as_ref(), &queue.head.to_le_bytes()],
+        bump = ticket.bump,
+        has_one = recipient,
+        close = recipient
+    )]
+    pub ticket: Account<'info, Ticket>,
+    /// CHECK: Bound by `has_one`; receives the escrow and the rent.
+    #[account(
+        mut,
+        constraint = recipient.key() != queue.key() @ ErrorCode::RecipientIsQueue
+    )]
+    pub recipient: UncheckedAccount<'info>,
+    pub cranker: Signer<'info>,
+}
+
+/// Tickets `head..tail` are waiting, paid strictly in order.
+#[account]
+#[derive(InitSpace)]
+pub struct Queue {
+    pub head: u64, // 8 bytes, next ticket to pay
+    pub tail: u64, // 8 bytes, next ticket to open
+    pub bump: u8,  // 1 byte
+}
+
+/// Lamports above rent are the escrowed payment.
+#[account]
+#[derive(InitSpace)]
+pub struct Ticket {
+    pub seq: u64,          //  8 bytes
+    pub recipient: Pubkey, // 32 bytes, close target
+    pub amount: u64,       //  8 bytes
+    pub bump: u8,          //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Recipient is the ticket being paid out")]
+    RecipientIsTicket,
+    #[msg("Recipient is the queue")]
+    RecipientIsQueue,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
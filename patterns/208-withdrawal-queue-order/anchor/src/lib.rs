@@ -0,0 +1,324 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("CMxgjjxdK775n9tV6Gh4jUxFVgXJVDPeiVDaZ2yGMoWY");
+
+/// # Withdrawal Queue Order
+///
+/// ## The Vulnerability
+/// A pool can't always pay every withdrawal at once: most of its SOL is
+/// deployed, and only what sits in the pool PDA is liquid. Members queue
+/// withdrawal requests instead, and a permissionless crank pays them as
+/// liquidity allows. Each request records its position in the queue,
+/// but the vulnerable crank pays whichever unpaid request it's handed.
+///
+/// ## Why It Matters
+/// Under stress, the order requests are paid in decides who gets paid at
+/// all. The crank is an ordinary instruction, so whoever sends it picks
+/// the order: an insider, a keeper bot, or a member who joined the queue
+/// last. They pay their own request first with liquidity that was owed
+/// to the requests ahead of them, and the earliest requesters are left
+/// waiting on funds that may never come back.
+///
+/// ## The Fix
+/// Store a sequence number in each request when it's made, keep the
+/// queue's head in the pool, and pay only the request whose sequence
+/// number is the head. If the head can't be paid, nothing can, and the
+/// crank fails until liquidity returns.
+#[program]
+pub mod withdrawal_queue_order {
+    use super::*;
+
+    pub fn create_pool(ctx: Context<CreatePool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.head = 0;
+        pool.tail = 0;
+        pool.bump = ctx.bumps.pool;
+        demo_log!(Mode::Setup, "create_pool", actor = ctx.accounts.payer.key());
+        Ok(())
+    }
+
+    pub fn join(ctx: Context<Join>) -> Result<()> {
+        let member = &mut ctx.accounts.member;
+        member.pool = ctx.accounts.pool.key();
+        member.owner = ctx.accounts.owner.key();
+        member.balance = 0;
+        member.bump = ctx.bumps.member;
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let member = &mut ctx.accounts.member;
+        member.balance = member
+            .balance
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = member.owner,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Move `amount` of the member's balance into a queued request, number
+    /// `pool.tail`.
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, amount: u64) -> Result<()> {
+        let member = &mut ctx.accounts.member;
+        member.balance = member
+            .balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let pool = &mut ctx.accounts.pool;
+        let request = &mut ctx.accounts.request;
+        request.pool = pool.key();
+        request.owner = member.owner;
+        request.seq = pool.tail;
+        request.amount = amount;
+        request.paid = false;
+        request.bump = ctx.bumps.request;
+        pool.tail = pool.tail.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "request_withdrawal",
+            actor = request.owner,
+            amount = amount,
+            seq = request.seq
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: The crank pays any unpaid request
+    // ============================================================================
+    // ISSUE: `request.seq` is stored but never compared with the queue's
+    //        head. Whoever cranks chooses the order, and when liquidity
+    //        runs out, the order decides who is paid.
+    //
+    // ATTACK SCENARIO:
+    //   1. The pool has 10 SOL liquid; Alice and Bob queue 5 SOL each
+    //   2. Mallory queues 5 SOL last, as request #2
+    //   3. Mallory cranks process_vulnerable on request #2 first and is
+    //      paid, then cranks #0 for Alice
+    //   4. Bob's request #1 fails for lack of liquidity; Mallory jumped him
+    // ============================================================================
+    pub fn process_vulnerable(ctx: Context<ProcessVulnerable>) -> Result<()> {
+        // VULNERABLE: any request, in any order
+        pay(
+            &ctx.accounts.pool,
+            &mut ctx.accounts.request,
+            &ctx.accounts.owner,
+        )?;
+        demo_log!(
+            Mode::Vulnerable,
+            "process",
+            actor = ctx.accounts.cranker.key(),
+            amount = ctx.accounts.request.amount,
+            seq = ctx.accounts.request.seq,
+            head = ctx.accounts.pool.head
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Only the request at the head of the queue can be paid
+    // ============================================================================
+    // FIX: `request.seq == pool.head` is required, and paying advances the
+    //      head. Requests are paid in the order they were made, whoever
+    //      cranks; a request that can't be paid blocks the ones behind it
+    //      instead of being skipped.
+    // ============================================================================
+    pub fn process_secure(ctx: Context<ProcessSecure>) -> Result<()> {
+        // SECURE: the constraint held this to the head of the queue
+        pay(
+            &ctx.accounts.pool,
+            &mut ctx.accounts.request,
+            &ctx.accounts.owner,
+        )?;
+        let pool = &mut ctx.accounts.pool;
+        pool.head = pool.head.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Secure,
+            "process",
+            actor = ctx.accounts.cranker.key(),
+            amount = ctx.accounts.request.amount,
+            seq = ctx.accounts.request.seq,
+            head = pool.head
+        );
+        Ok(())
+    }
+}
+
+/// Pay `request` out of the pool's liquid lamports and mark it paid.
+fn pay<'info>(
+    pool: &Account<'info, Pool>,
+    request: &mut Account<'info, Request>,
+    owner: &AccountInfo<'info>,
+) -> Result<()> {
+    let pool = pool.to_account_info();
+    let reserve = Rent::get()?.minimum_balance(pool.data_len());
+    let liquid = pool.lamports().saturating_sub(reserve);
+    require!(request.amount <= liquid, ErrorCode::InsufficientLiquidity);
+
+    pool.sub_lamports(request.amount)?;
+    owner.add_lamports(request.amount)?;
+    request.paid = true;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Join<'info> {
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Member::INIT_SPACE,
+        seeds = [b"member", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub member: Account<'info, Member>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub member: Account<'info, Member>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub member: Account<'info, Member>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Request::INIT_SPACE,
+        seeds = [b"request", pool.key().as_ref(), &pool.tail.to_le_bytes()],
+        bump
+    )]
+    pub request: Account<'info, Request>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessVulnerable<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        constraint = !request.paid @ ErrorCode::AlreadyPaid
+    )] // <-- request.seq never compared with pool.head
+    pub request: Account<'info, Request>,
+    /// CHECK: Bound by `has_one`; receives the payout.
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessSecure<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        constraint = !request.paid @ ErrorCode::AlreadyPaid,
+        constraint = request.seq == pool.head @ ErrorCode::OutOfOrder
+    )]
+    pub request: Account<'info, Request>,
+    /// CHECK: Bound by `has_one`; receives the payout.
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    pub cranker: Signer<'info>,
+}
+
+/// Requests `head..tail` are unpaid, in the order they were made. Lamports
+/// above rent are liquid; the rest of the members' balances are deployed.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub head: u64, // 8 bytes, next request to pay
+    pub tail: u64, // 8 bytes, next request to make
+    pub bump: u8,  // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Member {
+    pub pool: Pubkey,  // 32 bytes
+    pub owner: Pubkey, // 32 bytes
+    pub balance: u64,  //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Request {
+    pub pool: Pubkey,  // 32 bytes
+    pub owner: Pubkey, // 32 bytes
+    pub seq: u64,      //  8 bytes, place in the queue
+    pub amount: u64,   //  8 bytes
+    pub paid: bool,    //  1 byte
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Not enough liquid SOL to pay the request")]
+    InsufficientLiquidity,
+    #[msg("Request is not at the head of the queue")]
+    OutOfOrder,
+    #[msg("Request has already been paid")]
+    AlreadyPaid,
+    #[msg("Withdrawal exceeds the member's balance")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
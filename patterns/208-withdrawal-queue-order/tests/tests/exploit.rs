@@ -0,0 +1,376 @@
+/// # Pattern 208: Withdrawal Queue Order — Mollusk Exploit Tests
+///
+/// The pool is under stress: it has 10 SOL liquid, while Alice, Bob, and
+/// Mallory each hold a 5 SOL balance. Each test starts with all three
+/// queueing a full withdrawal, in that order, so Mallory's request is #2.
+/// Mallory also runs the keeper that cranks the queue.
+///
+/// - Test 1: EXPLOIT — the keeper pays Mallory's request first through
+///   process_vulnerable, then Alice's. Bob, who asked before Mallory,
+///   can't be paid.
+/// - Test 2: SECURE — process_secure refuses Mallory's request while #0
+///   is unpaid.
+/// - Test 3: SANITY — process_secure pays Alice and Bob in order, and
+///   Mallory's request is the one left waiting for liquidity.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, system_account,
+    Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("CMxgjjxdK775n9tV6Gh4jUxFVgXJVDPeiVDaZ2yGMoWY");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10 * SOL;
+
+/// What the pool can pay right now.
+const LIQUID: u64 = 10 * SOL;
+/// Each member's balance, all of it requested.
+const BALANCE: u64 = 5 * SOL;
+
+// Error codes
+const INSUFFICIENT_LIQUIDITY: u32 = 6000;
+const OUT_OF_ORDER: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account:
+///   [8 disc][8 head][8 tail][1 bump]
+fn serialize_pool(head: u64, tail: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(25);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(&head.to_le_bytes());
+    data.extend_from_slice(&tail.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Member account:
+///   [8 disc][32 pool][32 owner][8 balance][1 bump]
+fn serialize_member(pool: &Pubkey, owner: &Pubkey, balance: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Member"));
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&balance.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Request account:
+///   [8 disc][32 pool][32 owner][8 seq][8 amount][1 paid][1 bump]
+fn serialize_request(
+    pool: &Pubkey,
+    owner: &Pubkey,
+    seq: u64,
+    amount: u64,
+    paid: bool,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(90);
+    data.extend_from_slice(&account_discriminator("Request"));
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&seq.to_le_bytes());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(paid as u8);
+    data.push(bump);
+    data
+}
+
+/// A member: their wallet and their Member account.
+struct User {
+    key: Pubkey,
+    member: Pubkey,
+    member_bump: u8,
+}
+
+impl User {
+    fn new(pool: &Pubkey) -> Self {
+        let key = Pubkey::new_unique();
+        let (member, member_bump) =
+            Pubkey::find_program_address(&[b"member", pool.as_ref(), key.as_ref()], &PROGRAM_ID);
+        Self {
+            key,
+            member,
+            member_bump,
+        }
+    }
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    pool: Pubkey,
+    pool_bump: u8,
+    /// Request PDAs #0, #1, #2, not yet created.
+    requests: [(Pubkey, u8); 3],
+    alice: User,
+    bob: User,
+    mallory: User,
+    /// Mallory's crank bot.
+    keeper: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let (pool, pool_bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+        let requests = [0u64, 1, 2].map(|seq| {
+            Pubkey::find_program_address(
+                &[b"request", pool.as_ref(), &seq.to_le_bytes()],
+                &PROGRAM_ID,
+            )
+        });
+        let alice = User::new(&pool);
+        let bob = User::new(&pool);
+        let mallory = User::new(&pool);
+        let keeper = Pubkey::new_unique();
+
+        let mut ledger = vec![
+            (
+                pool,
+                owned_account(&serialize_pool(0, 0, pool_bump), RENT + LIQUID, &PROGRAM_ID),
+            ),
+            (keeper, system_account(WALLET)),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+        ledger.extend(
+            requests
+                .iter()
+                .map(|(request, _)| (*request, AccountSharedData::default())),
+        );
+        for user in [&alice, &bob, &mallory] {
+            ledger.extend([
+                (user.key, system_account(WALLET)),
+                (
+                    user.member,
+                    owned_account(
+                        &serialize_member(&pool, &user.key, BALANCE, user.member_bump),
+                        RENT,
+                        &PROGRAM_ID,
+                    ),
+                ),
+            ]);
+        }
+
+        let mut s = Self {
+            mollusk: Mollusk::new(&PROGRAM_ID, "withdrawal_queue_order"),
+            pool,
+            pool_bump,
+            requests,
+            alice,
+            bob,
+            mallory,
+            keeper,
+            ledger: Ledger::new(ledger),
+        };
+
+        // Alice, Bob, then Mallory queue their whole balance.
+        for seq in 0..3 {
+            let ix = s.request_withdrawal(s.user(seq), seq, BALANCE);
+            s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        }
+        let queued = s.pool_data(0, 3);
+        assert_eq!(s.ledger.data(&s.pool), queued.as_slice());
+        s
+    }
+
+    /// The user who made request `seq`.
+    fn user(&self, seq: usize) -> &User {
+        [&self.alice, &self.bob, &self.mallory][seq]
+    }
+
+    fn request_withdrawal(&self, user: &User, seq: usize, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("request_withdrawal").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(user.member, false),
+                AccountMeta::new(self.requests[seq].0, false),
+                AccountMeta::new(user.key, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// process_vulnerable / process_secure on request `seq`, cranked by
+    /// Mallory's keeper.
+    fn process(&self, name: &str, seq: usize) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(self.requests[seq].0, false),
+                AccountMeta::new(self.user(seq).key, false),
+                AccountMeta::new_readonly(self.keeper, true),
+            ],
+        )
+    }
+
+    fn pool_data(&self, head: u64, tail: u64) -> Vec<u8> {
+        serialize_pool(head, tail, self.pool_bump)
+    }
+
+    fn request_data(&self, seq: usize, paid: bool) -> Vec<u8> {
+        let (_, bump) = self.requests[seq];
+        serialize_request(
+            &self.pool,
+            &self.user(seq).key,
+            seq as u64,
+            BALANCE,
+            paid,
+            bump,
+        )
+    }
+
+    fn lamports(&self, key: &Pubkey) -> u64 {
+        self.ledger.lamports(key)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_keeper_jumps_queue_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory's keeper cranks process_vulnerable:
+    //   1. request #2 (Mallory's)
+    //   2. request #0 (Alice's)
+    //   3. request #1 (Bob's)
+    //
+    // Expected: 1 and 2 SUCCEED and pay 5 SOL each, using up the 10 SOL of
+    //           liquidity. 3 FAILS with InsufficientLiquidity (6000). Bob
+    //           asked before Mallory and is the one left unpaid.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (alice, bob, mallory) = (s.alice.key, s.bob.key, s.mallory.key);
+    let (alice_before, bob_before, mallory_before) =
+        (s.lamports(&alice), s.lamports(&bob), s.lamports(&mallory));
+
+    let mallory_request = s.requests[2].0;
+    let ix = s.process("process_vulnerable", 2);
+    let paid = s.request_data(2, true);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&mallory)
+                .lamports(mallory_before + BALANCE)
+                .build(),
+            Check::account(&mallory_request).data(&paid).build(),
+        ],
+    );
+
+    let ix = s.process("process_vulnerable", 0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&alice)
+                .lamports(alice_before + BALANCE)
+                .build(),
+        ],
+    );
+
+    let ix = s.process("process_vulnerable", 1);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_LIQUIDITY))],
+    );
+
+    assert_eq!(s.lamports(&bob), bob_before);
+    assert_eq!(
+        s.ledger.data(&s.requests[1].0),
+        s.request_data(1, false).as_slice()
+    );
+}
+
+#[test]
+fn secure_rejects_request_behind_head() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory's keeper cranks process_secure on request #2 while
+    // #0 is the head.
+    //
+    // Expected: FAILS with OutOfOrder (6001). Nothing is paid and the head
+    //           stays at 0.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory.key;
+    let mallory_before = s.lamports(&mallory);
+
+    let ix = s.process("process_secure", 2);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(OUT_OF_ORDER))]);
+
+    assert_eq!(s.lamports(&mallory), mallory_before);
+    assert_eq!(s.ledger.data(&s.pool), s.pool_data(0, 3).as_slice());
+}
+
+#[test]
+fn sanity_fifo_payout_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The keeper cranks process_secure on #0, #1, then #2.
+    //
+    // Expected: #0 and #1 SUCCEED: Alice and Bob are paid 5 SOL each and
+    //           the head moves to 2. #2 FAILS with InsufficientLiquidity
+    //           (6000): Mallory, the last to ask, waits for liquidity.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (alice, bob, mallory) = (s.alice.key, s.bob.key, s.mallory.key);
+    let (alice_before, bob_before, mallory_before) =
+        (s.lamports(&alice), s.lamports(&bob), s.lamports(&mallory));
+
+    let ix = s.process("process_secure", 0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&alice)
+                .lamports(alice_before + BALANCE)
+                .build(),
+        ],
+    );
+
+    let pool = s.pool;
+    let ix = s.process("process_secure", 1);
+    let advanced = s.pool_data(2, 3);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&bob).lamports(bob_before + BALANCE).build(),
+            Check::account(&pool).data(&advanced).build(),
+        ],
+    );
+
+    let ix = s.process("process_secure", 2);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_LIQUIDITY))],
+    );
+
+    assert_eq!(s.lamports(&mallory), mallory_before);
+}
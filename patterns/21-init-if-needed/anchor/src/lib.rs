@@ -0,0 +1,179 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("7Sb7J1abnpPKEBXM8jXNyHohoxxGHXg9xgNdXXhjV42v");
+
+/// # init_if_needed Misuse
+///
+/// This program demonstrates a treasury that is created by its first
+/// deposit, using `init_if_needed`.
+///
+/// ## The Vulnerability
+/// `init_if_needed` makes the account constraint idempotent: it creates the
+/// treasury if it doesn't exist and accepts it if it does. It does nothing
+/// to the handler. The vulnerable deposit sets the authority and resets the
+/// running total on every call, as if the body only ran on creation. The
+/// second depositor becomes the authority, and so does every depositor
+/// after them.
+///
+/// ## Real-World Impact
+/// Anyone can take the treasury with a 1-lamport deposit and withdraw
+/// everything in it. Counters written in the same block are reset too, so
+/// the accounting that should have shown the loss starts again from zero.
+/// Unlike [Pattern 4](../04-reinitialization-attack/), there's no separate
+/// init instruction to review: the setup code sits inside an instruction
+/// everyone is supposed to call.
+#[program]
+pub mod init_if_needed {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Setup fields written on every deposit
+    // ============================================================================
+    // ISSUE: The handler assumes it's only reached on creation. With
+    //        `init_if_needed`, it's reached on every deposit into an
+    //        existing treasury too.
+    //
+    // ATTACK SCENARIO:
+    //   1. Admin's first deposit creates the treasury; others deposit 50 SOL
+    //   2. Attacker deposits 1 lamport: authority = attacker, total = 1
+    //   3. Attacker withdraws the 50 SOL as the treasury's authority
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        transfer_in(&ctx, amount)?;
+
+        let treasury = &mut ctx.accounts.treasury;
+        // VULNERABLE: "initialization" that runs on every call
+        treasury.authority = ctx.accounts.depositor.key();
+        treasury.is_initialized = true;
+        treasury.total_deposited = amount;
+        treasury.bump = ctx.bumps.treasury;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount,
+            authority = treasury.authority,
+            total_deposited = treasury.total_deposited
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Setup gated on `is_initialized`
+    // ============================================================================
+    // FIX: The setup fields are written once, when `is_initialized` is still
+    //      false, which is only true for an account `init_if_needed` has just
+    //      created. Every later deposit only adds to the total.
+    //
+    // The simpler fix is to not use `init_if_needed` at all: create the
+    // treasury in its own `init` instruction and deposit with plain `mut`.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        transfer_in(&ctx, amount)?;
+
+        let treasury = &mut ctx.accounts.treasury;
+        // SECURE: only a freshly created treasury takes an authority
+        if !treasury.is_initialized {
+            treasury.authority = ctx.accounts.depositor.key();
+            treasury.is_initialized = true;
+            treasury.bump = ctx.bumps.treasury;
+        }
+        treasury.total_deposited = treasury
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount,
+            authority = treasury.authority,
+            total_deposited = treasury.total_deposited
+        );
+        Ok(())
+    }
+
+    /// The treasury's authority withdraws `amount` lamports.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let authority = ctx.accounts.authority.to_account_info();
+        **treasury.try_borrow_mut_lamports()? = treasury
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientFunds)?;
+        **authority.try_borrow_mut_lamports()? = authority
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = authority.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+fn transfer_in(ctx: &Context<Deposit>, amount: u64) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.depositor.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        init_if_needed, // <-- creates the treasury, or accepts the existing one
+        payer = depositor,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump, has_one = authority)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Lamports above rent are the treasury's funds.
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub authority: Pubkey,    // 32 bytes
+    pub is_initialized: bool, //  1 byte, false only on the call that creates it
+    pub total_deposited: u64, //  8 bytes
+    pub bump: u8,             //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Treasury cannot cover the withdrawal")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
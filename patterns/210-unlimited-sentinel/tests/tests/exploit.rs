@@ -0,0 +1,398 @@
+/// # Pattern 210: Unlimited Sentinel — Mollusk Exploit Tests
+///
+/// The treasury holds 10 SOL. The owner has approved a keeper through the
+/// vulnerable `Allowance`, where `u64::MAX` means unlimited, and through
+/// the secure `Approval`, where `None` does.
+///
+/// - Test 1: EXPLOIT — after the keeper spends 1 SOL from an unlimited
+///   allowance, the owner can't top it up or revoke it, and the keeper
+///   spends the other 9 SOL.
+/// - Test 2: SECURE — an unlimited approval stays `None` through a spend
+///   and a top-up, and revoking it with `None` stops the keeper.
+/// - Test 3: BOUNDARY — `Some(u64::MAX)` is a finite limit: it's spent
+///   down, topped back up to exactly `u64::MAX`, refuses one more
+///   lamport, and is decreased by its full value to zero.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("Hrqm5KNjHWP9NoExRDH95fJPjGfqYXTEB2r6fDqwqHMp");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10 * SOL;
+const FUNDS: u64 = 10 * SOL;
+/// The owner's routine top-up, more than the keeper has spent.
+const TOP_UP: u64 = 2 * SOL;
+
+const UNLIMITED: u64 = u64::MAX;
+
+// Error codes
+const INSUFFICIENT_ALLOWANCE: u32 = 6000;
+const DECREASE_EXCEEDS_ALLOWANCE: u32 = 6001;
+const UNLIMITED_ALLOWANCE: u32 = 6002;
+const OVERFLOW: u32 = 6003;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Borsh `Option<u64>`: `[0]` or `[1][8 value]`.
+fn option_bytes(value: Option<u64>) -> Vec<u8> {
+    match value {
+        None => vec![0],
+        Some(value) => {
+            let mut data = vec![1];
+            data.extend_from_slice(&value.to_le_bytes());
+            data
+        }
+    }
+}
+
+/// Serialize a Treasury account:
+///   [8 disc][32 owner][1 bump]
+fn serialize_treasury(owner: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(&account_discriminator("Treasury"));
+    data.extend_from_slice(owner.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize an Allowance account:
+///   [8 disc][32 treasury][32 spender][8 amount][1 bump]
+fn serialize_allowance(treasury: &Pubkey, spender: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Allowance"));
+    data.extend_from_slice(treasury.as_ref());
+    data.extend_from_slice(spender.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an Approval account, padded to its 82-byte allocation:
+///   [8 disc][32 treasury][32 spender][1 or 9 limit][1 bump][zeros]
+fn serialize_approval(
+    treasury: &Pubkey,
+    spender: &Pubkey,
+    limit: Option<u64>,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(82);
+    data.extend_from_slice(&account_discriminator("Approval"));
+    data.extend_from_slice(treasury.as_ref());
+    data.extend_from_slice(spender.as_ref());
+    data.extend_from_slice(&option_bytes(limit));
+    data.push(bump);
+    data.resize(82, 0);
+    data
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    treasury: Pubkey,
+    allowance: Pubkey,
+    allowance_bump: u8,
+    approval: Pubkey,
+    approval_bump: u8,
+    owner: Pubkey,
+    keeper: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// The funded treasury, the keeper's vulnerable allowance of `amount`,
+    /// and their secure approval with `limit`.
+    fn new(amount: u64, limit: Option<u64>) -> Self {
+        let owner = Pubkey::new_unique();
+        let keeper = Pubkey::new_unique();
+        let (treasury, treasury_bump) =
+            Pubkey::find_program_address(&[b"treasury", owner.as_ref()], &PROGRAM_ID);
+        let (allowance, allowance_bump) = Pubkey::find_program_address(
+            &[b"allowance", treasury.as_ref(), keeper.as_ref()],
+            &PROGRAM_ID,
+        );
+        let (approval, approval_bump) = Pubkey::find_program_address(
+            &[b"approval", treasury.as_ref(), keeper.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                treasury,
+                owned_account(
+                    &serialize_treasury(&owner, treasury_bump),
+                    RENT + FUNDS,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                allowance,
+                owned_account(
+                    &serialize_allowance(&treasury, &keeper, amount, allowance_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                approval,
+                owned_account(
+                    &serialize_approval(&treasury, &keeper, limit, approval_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                owner,
+                AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID),
+            ),
+            (
+                keeper,
+                AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID),
+            ),
+        ];
+
+        Self {
+            mollusk: Mollusk::new(&PROGRAM_ID, "unlimited_sentinel"),
+            treasury,
+            allowance,
+            allowance_bump,
+            approval,
+            approval_bump,
+            owner,
+            keeper,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// increase_allowance_* / decrease_allowance_*: the owner adjusts
+    /// `approval` (an Allowance or an Approval) with the encoded `args`.
+    fn adjust(&self, name: &str, approval: Pubkey, args: &[u8]) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(args);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.treasury, false),
+                AccountMeta::new(approval, false),
+                AccountMeta::new_readonly(self.owner, true),
+            ],
+        )
+    }
+
+    /// spend_vulnerable / spend_secure: the keeper takes `amount` from the
+    /// treasury under `approval`.
+    fn spend(&self, name: &str, approval: Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(approval, false),
+                AccountMeta::new(self.keeper, true),
+            ],
+        )
+    }
+
+    fn allowance_data(&self, amount: u64) -> Vec<u8> {
+        serialize_allowance(&self.treasury, &self.keeper, amount, self.allowance_bump)
+    }
+
+    fn approval_data(&self, limit: Option<u64>) -> Vec<u8> {
+        serialize_approval(&self.treasury, &self.keeper, limit, self.approval_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_spent_unlimited_allowance_cannot_be_revoked_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Keeper: spend_vulnerable(1 SOL) from an UNLIMITED allowance
+    //   2. Owner:  increase_allowance_vulnerable(2 SOL)
+    //   3. Owner:  decrease_allowance_vulnerable(UNLIMITED) to revoke
+    //   4. Keeper: spend_vulnerable(9 SOL)
+    //
+    // Expected: the spend leaves u64::MAX - 1 SOL. The top-up FAILS with
+    //           Overflow (6003) and the revoke FAILS with
+    //           DecreaseExceedsAllowance (6001). The keeper's second spend
+    //           SUCCEEDS and the treasury is empty.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(UNLIMITED, None);
+    let (allowance, treasury, keeper) = (s.allowance, s.treasury, s.keeper);
+
+    let ix = s.spend("spend_vulnerable", allowance, SOL);
+    let drifted = s.allowance_data(UNLIMITED - SOL);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&allowance).data(&drifted).build(),
+        ],
+    );
+
+    let ix = s.adjust(
+        "increase_allowance_vulnerable",
+        allowance,
+        &TOP_UP.to_le_bytes(),
+    );
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(OVERFLOW))]);
+
+    let ix = s.adjust(
+        "decrease_allowance_vulnerable",
+        allowance,
+        &UNLIMITED.to_le_bytes(),
+    );
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(DECREASE_EXCEEDS_ALLOWANCE))],
+    );
+
+    let ix = s.spend("spend_vulnerable", allowance, FUNDS - SOL);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    assert_eq!(s.ledger.lamports(&treasury), RENT);
+    assert_eq!(s.ledger.lamports(&keeper), WALLET + FUNDS);
+}
+
+#[test]
+fn secure_unlimited_approval_survives_spend_and_revokes() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same sequence against an Approval with `limit = None`.
+    //   1. Keeper: spend_secure(1 SOL)
+    //   2. Owner:  increase_allowance_secure(2 SOL)
+    //   3. Owner:  decrease_allowance_secure(Some(1 SOL))
+    //   4. Owner:  decrease_allowance_secure(None) to revoke
+    //   5. Keeper: spend_secure(1 SOL)
+    //
+    // Expected: the spend and the top-up SUCCEED and leave the limit
+    //           `None`. Decreasing an unlimited approval by an amount FAILS
+    //           with UnlimitedAllowance (6002). The revoke SUCCEEDS with
+    //           `Some(0)`, and the keeper's next spend FAILS with
+    //           InsufficientAllowance (6000).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(UNLIMITED, None);
+    let approval = s.approval;
+    let unlimited = s.approval_data(None);
+
+    let ix = s.spend("spend_secure", approval, SOL);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&approval).data(&unlimited).build(),
+        ],
+    );
+
+    let ix = s.adjust("increase_allowance_secure", approval, &TOP_UP.to_le_bytes());
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&approval).data(&unlimited).build(),
+        ],
+    );
+
+    let ix = s.adjust(
+        "decrease_allowance_secure",
+        approval,
+        &option_bytes(Some(SOL)),
+    );
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(UNLIMITED_ALLOWANCE))],
+    );
+
+    let ix = s.adjust("decrease_allowance_secure", approval, &option_bytes(None));
+    let revoked = s.approval_data(Some(0));
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&approval).data(&revoked).build(),
+        ],
+    );
+
+    let ix = s.spend("spend_secure", approval, SOL);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_ALLOWANCE))],
+    );
+}
+
+#[test]
+fn boundary_max_limit_is_finite_secure() {
+    // -----------------------------------------------------------------------
+    // BOUNDARY: An Approval with `limit = Some(u64::MAX)`.
+    //   1. Keeper: spend_secure(1 SOL)
+    //   2. Owner:  increase_allowance_secure(1 SOL)
+    //   3. Owner:  increase_allowance_secure(1)
+    //   4. Owner:  decrease_allowance_secure(Some(u64::MAX))
+    //
+    // Expected: the spend leaves Some(u64::MAX - 1 SOL), and the top-up
+    //           restores exactly Some(u64::MAX). One lamport more FAILS with
+    //           Overflow (6003). Decreasing by the full u64::MAX SUCCEEDS
+    //           and leaves Some(0).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(0, Some(u64::MAX));
+    let approval = s.approval;
+
+    let ix = s.spend("spend_secure", approval, SOL);
+    let spent = s.approval_data(Some(u64::MAX - SOL));
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&approval).data(&spent).build(),
+        ],
+    );
+
+    let ix = s.adjust("increase_allowance_secure", approval, &SOL.to_le_bytes());
+    let full = s.approval_data(Some(u64::MAX));
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&approval).data(&full).build(),
+        ],
+    );
+
+    let ix = s.adjust("increase_allowance_secure", approval, &1u64.to_le_bytes());
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(OVERFLOW))]);
+
+    let ix = s.adjust(
+        "decrease_allowance_secure",
+        approval,
+        &option_bytes(Some(u64::MAX)),
+    );
+    let zero = s.approval_data(Some(0));
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&approval).data(&zero).build(),
+        ],
+    );
+}
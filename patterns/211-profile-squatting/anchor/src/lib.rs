@@ -0,0 +1,199 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("6upnqVs1fdrS3xwyTeMn5PkFn6J3yt53PpHQuiQNXMvK");
+
+/// Share of every purchase paid to the buyer's referrer: 500 = 5%.
+pub const REFERRAL_BPS: u64 = 500;
+const BPS: u64 = 10_000;
+
+/// # Profile Squatting
+///
+/// ## The Vulnerability
+/// Each user has one profile, at a PDA derived from their key. It records
+/// who referred them, and every purchase they make pays that referrer a
+/// share. The vulnerable creation derives the profile from a `user`
+/// account that doesn't have to sign, so anyone can create anyone's
+/// profile, and pick the referrer. Profiles are created once and the
+/// referrer never changes, which is what makes a referral worth paying
+/// for.
+///
+/// ## Why It Matters
+/// An attacker walks a list of wallets and creates a profile for each,
+/// with themselves as referrer. When a victim shows up, their own signup
+/// fails because the account already exists, and every purchase they make
+/// pays the attacker. The attacker only paid rent.
+///
+/// ## The Fix
+/// The subject of a per-user account signs for its creation. The secure
+/// creation takes `user` as the `Signer` who pays, so the only profile at
+/// Alice's address is one Alice made, with the referrer she chose.
+#[program]
+pub mod profile_squatting {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Anyone can create anyone's profile
+    // ============================================================================
+    // ISSUE: The profile's seeds use `user`, but `user` doesn't sign. The
+    //        payer fills in every field, including the permanent referrer.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker calls create_profile_vulnerable(referrer = attacker) for
+    //      Alice, who hasn't signed up yet
+    //   2. Alice's own signup fails: her profile already exists
+    //   3. Every purchase Alice makes pays the attacker 5%
+    // ============================================================================
+    pub fn create_profile_vulnerable(
+        ctx: Context<CreateProfileVulnerable>,
+        referrer: Pubkey,
+    ) -> Result<()> {
+        // VULNERABLE: the payer, not the user, decides what the profile says
+        let profile = &mut ctx.accounts.profile;
+        profile.user = ctx.accounts.user.key();
+        profile.referrer = referrer;
+        profile.bump = ctx.bumps.profile;
+        demo_log!(
+            Mode::Vulnerable,
+            "create_profile",
+            actor = ctx.accounts.payer.key(),
+            user = profile.user,
+            referrer = referrer
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: The user signs for their own profile
+    // ============================================================================
+    // FIX: `user` is the `Signer` that pays for the profile. A third party
+    //      can't produce the signature, so the address stays free until the
+    //      user creates it with a referrer of their choice.
+    // ============================================================================
+    pub fn create_profile_secure(
+        ctx: Context<CreateProfileSecure>,
+        referrer: Pubkey,
+    ) -> Result<()> {
+        // SECURE: only the user can create the profile at their address
+        let profile = &mut ctx.accounts.profile;
+        profile.user = ctx.accounts.user.key();
+        profile.referrer = referrer;
+        profile.bump = ctx.bumps.profile;
+        demo_log!(
+            Mode::Secure,
+            "create_profile",
+            actor = ctx.accounts.user.key(),
+            user = profile.user,
+            referrer = referrer
+        );
+        Ok(())
+    }
+
+    /// Pay `amount` lamports: the referrer's share to the referrer, the rest
+    /// to the treasury.
+    pub fn purchase(ctx: Context<Purchase>, amount: u64) -> Result<()> {
+        let share = amount
+            .checked_mul(REFERRAL_BPS)
+            .ok_or(ErrorCode::Overflow)?
+            / BPS;
+        let accounts = &ctx.accounts;
+        pay(accounts, accounts.referrer.to_account_info(), share)?;
+        pay(
+            accounts,
+            accounts.treasury.to_account_info(),
+            amount - share,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "purchase",
+            actor = ctx.accounts.user.key(),
+            amount = amount,
+            referrer = ctx.accounts.referrer.key(),
+            share = share
+        );
+        Ok(())
+    }
+}
+
+fn pay<'info>(accounts: &Purchase<'info>, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            Transfer {
+                from: accounts.user.to_account_info(),
+                to,
+            },
+        ),
+        amount,
+    )
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateProfileVulnerable<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Profile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+    /// CHECK: Only used for its key — and it never signs.
+    pub user: UncheckedAccount<'info>, // <-- anyone's wallet
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProfileSecure<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Profile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Purchase<'info> {
+    #[account(
+        seeds = [b"profile", user.key().as_ref()],
+        bump = profile.bump,
+        has_one = user,
+        has_one = referrer
+    )]
+    pub profile: Account<'info, Profile>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub referrer: SystemAccount<'info>,
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// One per user. The referrer is set at creation and never changes.
+#[account]
+#[derive(InitSpace)]
+pub struct Profile {
+    pub user: Pubkey,     // 32 bytes
+    pub referrer: Pubkey, // 32 bytes
+    pub bump: u8,         //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
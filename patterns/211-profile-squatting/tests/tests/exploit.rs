@@ -0,0 +1,253 @@
+/// # Pattern 211: Profile Squatting — Mollusk Exploit Tests
+///
+/// Alice hasn't signed up yet: her profile address is empty. She means to
+/// sign up with her friend as referrer. The attacker has a wallet of their
+/// own and nothing else.
+///
+/// - Test 1: EXPLOIT — the attacker creates Alice's profile through
+///   create_profile_vulnerable with themselves as referrer. Alice's own
+///   signup fails, and her purchase pays the attacker.
+/// - Test 2: SECURE — create_profile_secure needs Alice's signature, so
+///   the attacker can't create it. Alice does, and her purchase pays her
+///   friend.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, system_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("6upnqVs1fdrS3xwyTeMn5PkFn6J3yt53PpHQuiQNXMvK");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10 * SOL;
+/// Alice's purchase, and the referrer's 5% of it.
+const PRICE: u64 = SOL;
+const SHARE: u64 = PRICE / 20;
+
+// Error codes
+/// System program's AccountAlreadyInUse, from `init` on an existing account.
+const ACCOUNT_ALREADY_IN_USE: u32 = 0;
+const CONSTRAINT_HAS_ONE: u32 = 2001;
+const ACCOUNT_NOT_SIGNER: u32 = 3010;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Profile account:
+///   [8 disc][32 user][32 referrer][1 bump]
+fn serialize_profile(user: &Pubkey, referrer: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Profile"));
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(referrer.as_ref());
+    data.push(bump);
+    data
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    profile: Pubkey,
+    profile_bump: u8,
+    treasury: Pubkey,
+    alice: Pubkey,
+    friend: Pubkey,
+    attacker: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let alice = Pubkey::new_unique();
+        let friend = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let (profile, profile_bump) =
+            Pubkey::find_program_address(&[b"profile", alice.as_ref()], &PROGRAM_ID);
+        let (treasury, _) = Pubkey::find_program_address(&[b"treasury"], &PROGRAM_ID);
+
+        let ledger = vec![
+            (profile, AccountSharedData::default()),
+            (treasury, system_account(RENT)),
+            (alice, system_account(WALLET)),
+            (friend, system_account(WALLET)),
+            (attacker, system_account(WALLET)),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            mollusk: Mollusk::new(&PROGRAM_ID, "profile_squatting"),
+            profile,
+            profile_bump,
+            treasury,
+            alice,
+            friend,
+            attacker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// create_profile_vulnerable: `payer` creates Alice's profile. Alice
+    /// doesn't sign.
+    fn create_profile_vulnerable(&self, payer: Pubkey, referrer: Pubkey) -> Instruction {
+        let mut data = ix_discriminator("create_profile_vulnerable").to_vec();
+        data.extend_from_slice(referrer.as_ref());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.profile, false),
+                AccountMeta::new_readonly(self.alice, false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// create_profile_secure for Alice, with or without her signature.
+    fn create_profile_secure(&self, signed: bool, referrer: Pubkey) -> Instruction {
+        let mut data = ix_discriminator("create_profile_secure").to_vec();
+        data.extend_from_slice(referrer.as_ref());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.profile, false),
+                AccountMeta::new(self.alice, signed),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// Alice buys for `PRICE`, naming `referrer` as her referrer.
+    fn purchase(&self, referrer: Pubkey) -> Instruction {
+        let mut data = ix_discriminator("purchase").to_vec();
+        data.extend_from_slice(&PRICE.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.profile, false),
+                AccountMeta::new(self.alice, true),
+                AccountMeta::new(referrer, false),
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn profile_data(&self, referrer: &Pubkey) -> Vec<u8> {
+        serialize_profile(&self.alice, referrer, self.profile_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_squatted_profile_pays_attacker_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: create_profile_vulnerable for Alice, referrer = attacker
+    //   2. Alice: create_profile_secure(referrer = friend)
+    //   3. Alice: purchase(1 SOL), naming her friend as referrer
+    //   4. Alice: purchase(1 SOL), naming the attacker
+    //
+    // Expected: step 1 SUCCEEDS and Alice's profile names the attacker.
+    //           Her signup FAILS with AccountAlreadyInUse (0), and the
+    //           purchase through her friend FAILS with ConstraintHasOne
+    //           (2001). The only purchase that works pays the attacker
+    //           0.05 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (profile, attacker, friend, treasury) = (s.profile, s.attacker, s.friend, s.treasury);
+
+    let ix = s.create_profile_vulnerable(attacker, attacker);
+    let squatted = s.profile_data(&attacker);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&profile).data(&squatted).build(),
+        ],
+    );
+
+    let ix = s.create_profile_secure(true, friend);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE))],
+    );
+
+    let ix = s.purchase(friend);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(CONSTRAINT_HAS_ONE))]);
+
+    // The attacker paid the profile's rent; the purchase only adds to that.
+    let before = s.ledger.lamports(&attacker);
+    let ix = s.purchase(attacker);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&attacker).lamports(before + SHARE).build(),
+            Check::account(&treasury)
+                .lamports(RENT + PRICE - SHARE)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_profile_requires_user_signature() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Attacker: create_profile_secure for Alice, referrer = attacker,
+    //      without Alice's signature
+    //   2. Alice: create_profile_secure(referrer = friend), signed
+    //   3. Alice: purchase(1 SOL), naming her friend
+    //
+    // Expected: step 1 FAILS with AccountNotSigner (3010) and the address
+    //           stays empty. Alice's signup SUCCEEDS with her friend as
+    //           referrer, and the purchase pays the friend 0.05 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (profile, attacker, friend, treasury) = (s.profile, s.attacker, s.friend, s.treasury);
+
+    let ix = s.create_profile_secure(false, attacker);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(ACCOUNT_NOT_SIGNER))]);
+
+    let ix = s.create_profile_secure(true, friend);
+    let chosen = s.profile_data(&friend);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&profile).data(&chosen).build(),
+        ],
+    );
+
+    let ix = s.purchase(friend);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&friend).lamports(WALLET + SHARE).build(),
+            Check::account(&treasury)
+                .lamports(RENT + PRICE - SHARE)
+                .build(),
+        ],
+    );
+}
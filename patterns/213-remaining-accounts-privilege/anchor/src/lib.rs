@@ -0,0 +1,188 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("8sLjVW1X8PWvXYpGgzEwhqFcwNydyFHsykN1aE6AePk");
+
+/// Share of every deposit kept as a protocol fee: 100 = 1%.
+pub const FEE_BPS: u64 = 100;
+const BPS: u64 = 10_000;
+
+/// # Remaining Accounts Privilege
+///
+/// ## The Vulnerability
+/// Users deposit SOL into a vault, which keeps 1% as protocol fees. One
+/// `withdraw` instruction serves two callers: users withdraw their own
+/// balance, and the admin sweeps the fees. The vulnerable version tells
+/// them apart by counting `ctx.remaining_accounts`. The admin's script
+/// passes one extra account and the frontend doesn't, so "one extra
+/// account" is taken to mean "the admin is calling". Anyone can append an
+/// account to an instruction.
+///
+/// ## Why It Matters
+/// Any user with a position becomes the admin by passing one more account
+/// of any kind. The admin path debits the fee reserve instead of the
+/// caller's balance, so they withdraw every lamport the protocol has
+/// earned.
+///
+/// ## The Fix
+/// Privilege comes from who signed, checked against the role on record,
+/// never from how many accounts arrived or where they sit. The secure
+/// withdrawal takes the admin path only when the signer is `vault.admin`,
+/// and ignores `remaining_accounts`.
+#[program]
+pub mod remaining_accounts_privilege {
+    use super::*;
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let fee = amount.checked_mul(FEE_BPS).ok_or(ErrorCode::Overflow)? / BPS;
+        let vault = &mut ctx.accounts.vault;
+        vault.fees = vault.fees.checked_add(fee).ok_or(ErrorCode::Overflow)?;
+        let position = &mut ctx.accounts.position;
+        position.balance = position
+            .balance
+            .checked_add(amount - fee)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            fee = fee
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Admin path chosen by the number of remaining accounts
+    // ============================================================================
+    // ISSUE: "One extra account means the admin's script is calling." The
+    //        caller decides how many accounts to pass, so the caller decides
+    //        which path runs.
+    //
+    // ATTACK SCENARIO:
+    //   1. The vault holds users' balances and 5 SOL of protocol fees
+    //   2. Attacker, with a 1 SOL position, calls withdraw_vulnerable(5 SOL)
+    //      with one extra account appended: any account at all
+    //   3. The admin path debits the fee reserve and pays the attacker 5 SOL
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        // VULNERABLE: counting accounts says nothing about who is calling
+        let admin_call = ctx.remaining_accounts.len() == 1;
+        debit(ctx.accounts, admin_call, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            admin_call = admin_call
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Admin path chosen by the validated signer
+    // ============================================================================
+    // FIX: The signer is compared with `vault.admin`, the role the vault
+    //      recorded. Extra accounts, their number, and their order change
+    //      nothing: a user's withdrawal always debits their own balance.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        // SECURE: privilege is a property of the signer, not of the list
+        let admin_call = ctx.accounts.owner.key() == ctx.accounts.vault.admin;
+        debit(ctx.accounts, admin_call, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            admin_call = admin_call
+        );
+        Ok(())
+    }
+}
+
+/// Debit the fee reserve for an admin call and the caller's balance
+/// otherwise, then pay the caller.
+fn debit(accounts: &mut Withdraw, admin_call: bool, amount: u64) -> Result<()> {
+    if admin_call {
+        let vault = &mut accounts.vault;
+        vault.fees = vault
+            .fees
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+    } else {
+        let position = &mut accounts.position;
+        position.balance = position
+            .balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+    }
+
+    **accounts.vault.to_account_info().try_borrow_mut_lamports()? -= amount;
+    **accounts.owner.to_account_info().try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The admin sweeps fees through this instruction too, with a position of
+/// their own.
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Holds every user's SOL plus the protocol's fees.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub admin: Pubkey, // 32 bytes
+    pub fees: u64,     //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub vault: Pubkey, // 32 bytes
+    pub owner: Pubkey, // 32 bytes
+    pub balance: u64,  //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the available balance")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
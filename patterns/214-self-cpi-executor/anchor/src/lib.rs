@@ -0,0 +1,283 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{get_stack_height, AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+use demo_log::{demo_log, Mode};
+
+declare_id!("7xnsMTGr8A9HJkGMfrQGSLWCNCbaWv95C2ZWEXiytjJe");
+
+/// # Self-CPI Executor
+///
+/// ## The Vulnerability
+/// A flash-loan pool checks repayment with the instructions sysvar: the
+/// instruction right after the borrow must be a `flash_repay` for the loan
+/// plus fee. That only works if "the current instruction" in the sysvar is
+/// the borrow itself, so the borrow refuses CPI by checking that the
+/// current top-level instruction belongs to this program. The same program
+/// also has `execute`, a multicall that CPIs whatever calls it's given,
+/// this program included. Called through `execute`, the borrow sees
+/// `execute` as the current instruction: it belongs to this program, so
+/// the check passes, and every borrow inside it finds the same repayment
+/// after it.
+///
+/// ## Why It Matters
+/// One `execute` borrows as many times as the pool allows and one repay
+/// covers only the first loan. The attacker keeps the rest. Any check that
+/// reads "is the top-level instruction mine?" to mean "I wasn't called by
+/// CPI" fails the same way once the program can call itself.
+///
+/// ## The Fix
+/// Ask the runtime how deep the call is. The secure borrow calls
+/// `cpi_guard::require_top_level`, which requires `get_stack_height()` to be
+/// `TRANSACTION_LEVEL_STACK_HEIGHT`. No CPI can satisfy that, its own
+/// program's included. Refusing to let `execute`
+/// target this program closes the same hole from the other side.
+#[program]
+pub mod self_cpi_executor {
+    use super::*;
+
+    pub fn init_pool(ctx: Context<InitPool>, fee_bps: u16) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.fee_bps = fee_bps;
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    /// Make each call in turn. A call takes the next `accounts` remaining
+    /// accounts after its program, with the signer and writable flags they
+    /// were passed with.
+    pub fn execute<'info>(
+        ctx: Context<'_, '_, '_, 'info, Execute<'info>>,
+        calls: Vec<Call>,
+    ) -> Result<()> {
+        let mut remaining = ctx.remaining_accounts;
+        for call in &calls {
+            let (program, rest) = remaining.split_first().ok_or(ErrorCode::MissingAccounts)?;
+            let count = call.accounts as usize;
+            require!(rest.len() >= count, ErrorCode::MissingAccounts);
+            let (accounts, rest) = rest.split_at(count);
+
+            let ix = Instruction {
+                program_id: program.key(),
+                accounts: accounts
+                    .iter()
+                    .map(|account| AccountMeta {
+                        pubkey: account.key(),
+                        is_signer: account.is_signer,
+                        is_writable: account.is_writable,
+                    })
+                    .collect(),
+                data: call.data.clone(),
+            };
+            invoke(&ix, accounts)?;
+            remaining = rest;
+        }
+
+        demo_log!(
+            Mode::Setup,
+            "execute",
+            actor = ctx.accounts.caller.key(),
+            calls = calls.len()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: "Not CPI" read from the top-level instruction's program
+    // ============================================================================
+    // ISSUE: The current top-level instruction belonging to this program is
+    //        taken to mean the borrow is that instruction. Through
+    //        `execute`, the top-level instruction is `execute`, which also
+    //        belongs to this program.
+    //
+    // ATTACK SCENARIO:
+    //   1. The pool holds 100 SOL
+    //   2. Attacker's transaction: execute([flash_borrow_vulnerable(50),
+    //      flash_borrow_vulnerable(50)]), then flash_repay(50 + fee)
+    //   3. Both borrows see `execute` as current and the repay right after
+    //      it, and pay out: 100 SOL
+    //   4. The repay returns 50 + fee. The attacker keeps 50 SOL less the fee
+    // ============================================================================
+    pub fn flash_borrow_vulnerable(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+        let instructions = ctx.accounts.instructions.to_account_info();
+        let index = load_current_index_checked(&instructions)? as usize;
+        let current = load_instruction_at_checked(index, &instructions)?;
+        // VULNERABLE: `execute` is this program too
+        require_keys_eq!(current.program_id, crate::ID, ErrorCode::CpiBorrow);
+
+        borrow(ctx.accounts, index, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "flash_borrow",
+            actor = ctx.accounts.borrower.key(),
+            amount = amount,
+            stack_height = get_stack_height()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: "Not CPI" read from the stack height
+    // ============================================================================
+    // FIX: A top-level instruction runs at TRANSACTION_LEVEL_STACK_HEIGHT,
+    //      and every CPI runs deeper, whichever program makes it. With the
+    //      borrow known to be top-level, the current index in the sysvar is
+    //      the borrow's own.
+    // ============================================================================
+    pub fn flash_borrow_secure(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+        // SECURE: only the runtime knows how deep this call is
+        cpi_guard::require_top_level().map_err(|_| ErrorCode::CpiBorrow)?;
+        let instructions = ctx.accounts.instructions.to_account_info();
+        let index = load_current_index_checked(&instructions)? as usize;
+
+        borrow(ctx.accounts, index, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "flash_borrow",
+            actor = ctx.accounts.borrower.key(),
+            amount = amount,
+            stack_height = get_stack_height()
+        );
+        Ok(())
+    }
+
+    /// Return a loan plus fee. The borrow checked the amount; this moves it.
+    pub fn flash_repay(ctx: Context<FlashRepay>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.borrower.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "flash_repay",
+            actor = ctx.accounts.borrower.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// Require the instruction after `index` to repay `amount` plus fee, then
+/// pay the loan out.
+fn borrow(accounts: &FlashBorrow, index: usize, amount: u64) -> Result<()> {
+    let fee = (amount as u128 * accounts.pool.fee_bps as u128 / 10_000) as u64;
+    let owed = amount.checked_add(fee).ok_or(ErrorCode::Overflow)?;
+    let instructions = accounts.instructions.to_account_info();
+    let repaid = load_instruction_at_checked(index + 1, &instructions)
+        .ok()
+        .filter(|ix| {
+            ix.program_id == crate::ID
+                && ix.data.starts_with(instruction::FlashRepay::DISCRIMINATOR)
+                && ix.accounts.first().map(|meta| meta.pubkey) == Some(accounts.pool.key())
+        })
+        .and_then(|ix| repay_amount(&ix.data));
+    require!(
+        repaid.is_some_and(|repaid| repaid >= owed),
+        ErrorCode::MissingRepayment
+    );
+
+    let pool = accounts.pool.to_account_info();
+    **pool.try_borrow_mut_lamports()? = pool
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientLiquidity)?;
+    let borrower = accounts.borrower.to_account_info();
+    **borrower.try_borrow_mut_lamports()? = borrower
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// The `amount` argument of a serialized `flash_repay`.
+fn repay_amount(data: &[u8]) -> Option<u64> {
+    let start = instruction::FlashRepay::DISCRIMINATOR.len();
+    data.get(start..start + 8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+/// One call made by `execute`: the number of accounts it takes after its
+/// program, and its instruction data.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Call {
+    pub accounts: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Every call's program and accounts follow as remaining accounts.
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    /// CHECK: Address-checked; read with the sysvar::instructions helpers.
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub fee_bps: u16, // 2 bytes
+    pub bump: u8,     // 1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The next instruction doesn't repay the loan")]
+    MissingRepayment,
+    #[msg("Flash loans must be borrowed by a top-level instruction")]
+    CpiBorrow,
+    #[msg("A call is missing its program or accounts")]
+    MissingAccounts,
+    #[msg("Pool cannot cover the loan")]
+    InsufficientLiquidity,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
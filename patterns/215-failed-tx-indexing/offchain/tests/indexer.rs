@@ -0,0 +1,149 @@
+/// # Pattern 215: Failed Transaction Indexing — indexer tests
+///
+/// Notifications are built from the log lines the runtime writes for
+/// `deposit`, so the indexer is tested without a validator. What
+/// commitment the notifications arrive at is the subscription's business,
+/// and is exercised by `indexer watch-*` (see the pattern README).
+///
+/// - Test 1: EXPLOIT — a deposit in a transaction that then fails is
+///   credited by `credit_vulnerable`
+/// - Test 2: SECURE — `credit_secure` credits nothing for it
+/// - Test 3: EXPLOIT / SECURE — a deposit line printed by another program
+///   is credited by `credit_vulnerable` only
+/// - Test 4: SANITY — a successful deposit, direct or through CPI, is
+///   credited once by `credit_secure`, however often it's notified
+use demo_log::{demo_line, Mode};
+use failed_tx_indexer::{Indexer, Notification};
+
+const PROGRAM_ID: &str = "3ognCNffxNy6zbJFEaDAN6S5Drwr6SyBh7e3hZYbrUDQ";
+const SYSTEM_PROGRAM_ID: &str = "11111111111111111111111111111111";
+/// A program the attacker deployed; it logs whatever it likes.
+const ATTACKER_PROGRAM_ID: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+const ATTACKER: &str = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+const DEPOSIT: u64 = 1_000_000_000; // 1 SOL
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn deposit_line(actor: &str, amount: u64) -> String {
+    format!(
+        "Program log: {}",
+        demo_line!(Mode::Setup, "deposit", actor = actor, amount = amount)
+    )
+}
+
+/// The deposit program's output for `deposit(amount)`, invoked at `depth`.
+fn deposit_logs(actor: &str, amount: u64, depth: u8) -> Vec<String> {
+    vec![
+        format!("Program {PROGRAM_ID} invoke [{depth}]"),
+        "Program log: Instruction: Deposit".to_string(),
+        format!("Program {SYSTEM_PROGRAM_ID} invoke [{}]", depth + 1),
+        format!("Program {SYSTEM_PROGRAM_ID} success"),
+        deposit_line(actor, amount),
+        format!("Program {PROGRAM_ID} consumed 6042 of 200000 compute units"),
+        format!("Program {PROGRAM_ID} success"),
+    ]
+}
+
+/// The system program's output for a transfer the payer can't afford.
+fn failed_transfer_logs() -> Vec<String> {
+    vec![
+        format!("Program {SYSTEM_PROGRAM_ID} invoke [1]"),
+        "Transfer: insufficient lamports 8997955000, need 18446744073709551615".to_string(),
+        format!("Program {SYSTEM_PROGRAM_ID} failed: custom program error: 0x1"),
+    ]
+}
+
+/// [deposit(1 SOL), transfer(u64::MAX)]: landed and failed at the transfer.
+fn failed_deposit() -> Notification {
+    let mut logs = deposit_logs(ATTACKER, DEPOSIT, 1);
+    logs.extend(failed_transfer_logs());
+    Notification {
+        signature: "failed-deposit".to_string(),
+        err: Some("Error processing Instruction 1: custom program error: 0x1".to_string()),
+        logs,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_failed_transaction_credited_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Attacker lands [deposit(1 SOL), transfer(u64::MAX)]
+    //
+    // Expected: the transfer fails and the deposit is reverted, but its
+    //           event is in the logs: the attacker is credited 1 SOL.
+    // -----------------------------------------------------------------------
+    let mut indexer = Indexer::new(PROGRAM_ID);
+
+    indexer.credit_vulnerable(&failed_deposit());
+    assert_eq!(indexer.balance(ATTACKER), DEPOSIT);
+}
+
+#[test]
+fn secure_skips_failed_transaction() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same notification through credit_secure.
+    //
+    // Expected: `err` is set, so nothing is credited.
+    // -----------------------------------------------------------------------
+    let mut indexer = Indexer::new(PROGRAM_ID);
+
+    indexer.credit_secure(&failed_deposit());
+    assert_eq!(indexer.balance(ATTACKER), 0);
+}
+
+#[test]
+fn spoofed_deposit_line_credited_vulnerable_only() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT / SECURE: Attacker's program prints a deposit line for
+    // 100 SOL, then CPIs a real deposit of 1 SOL so the transaction
+    // mentions the deposit program. The transaction succeeds.
+    //
+    // Expected: credit_vulnerable credits 101 SOL. credit_secure credits
+    //           the 1 SOL the deposit program logged.
+    // -----------------------------------------------------------------------
+    let mut logs = vec![
+        format!("Program {ATTACKER_PROGRAM_ID} invoke [1]"),
+        deposit_line(ATTACKER, 100 * DEPOSIT),
+    ];
+    logs.extend(deposit_logs(ATTACKER, DEPOSIT, 2));
+    logs.push(format!("Program {ATTACKER_PROGRAM_ID} success"));
+    let notification = Notification {
+        signature: "spoofed-deposit".to_string(),
+        err: None,
+        logs,
+    };
+
+    let mut vulnerable = Indexer::new(PROGRAM_ID);
+    vulnerable.credit_vulnerable(&notification);
+    assert_eq!(vulnerable.balance(ATTACKER), 101 * DEPOSIT);
+
+    let mut secure = Indexer::new(PROGRAM_ID);
+    secure.credit_secure(&notification);
+    assert_eq!(secure.balance(ATTACKER), DEPOSIT);
+}
+
+#[test]
+fn sanity_successful_deposit_credited_once_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: A successful deposit(1 SOL), notified twice.
+    //
+    // Expected: credited 1 SOL, once.
+    // -----------------------------------------------------------------------
+    let notification = Notification {
+        signature: "deposit".to_string(),
+        err: None,
+        logs: deposit_logs(ATTACKER, DEPOSIT, 1),
+    };
+    let mut indexer = Indexer::new(PROGRAM_ID);
+
+    indexer.credit_secure(&notification);
+    indexer.credit_secure(&notification);
+    assert_eq!(indexer.balance(ATTACKER), DEPOSIT);
+}
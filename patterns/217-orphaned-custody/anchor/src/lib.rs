@@ -0,0 +1,243 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{
+    self, spl_token::instruction::AuthorityType, SetAuthority, Token, TokenAccount,
+};
+use demo_log::{demo_log, Mode};
+
+declare_id!("BfwMRJNCGBxvHK4vu4R1P9vb9C2m9frgeMNkGfUEaynT");
+
+/// # Orphaned Custody
+///
+/// ## The Vulnerability
+/// A lockup vault takes custody of whole token accounts rather than their
+/// balances. To deposit, a user calls SPL Token `SetAuthority` on their own
+/// token account and makes the vault PDA its owner. To withdraw, the vault
+/// hands ownership back. The handoff happens outside the program, so the
+/// program never learns who made it. The vulnerable `withdraw` sees a token
+/// account the vault owns and gives it to whoever signed.
+///
+/// ## Why It Matters
+/// Every deposit is up for grabs between the `SetAuthority` landing and the
+/// depositor's withdrawal. The depositor's transaction is public, so
+/// anyone watching sees the handoff and claims the account first. The
+/// program can't tell the thief from the depositor, because it has no
+/// record of who the depositor was.
+///
+/// ## The Fix
+/// Custody changes hands only through the program. `deposit_secure`
+/// performs the `SetAuthority` CPI itself, with the depositor signing as
+/// the current owner, and creates a `Custody` record of the depositor in
+/// the same instruction. `withdraw_secure` releases an account only to the
+/// depositor on its record. Accounts handed over any other way have no
+/// record and can't be withdrawn through the program at all.
+#[program]
+pub mod orphaned_custody {
+    use super::*;
+
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        ctx.accounts.vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "create_vault",
+            actor = ctx.accounts.payer.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Any vault-owned account released to any caller
+    // ============================================================================
+    // ISSUE: Users deposit by calling SPL Token SetAuthority themselves. The
+    //        program has no record of who handed over which account, so it
+    //        can't tell the depositor from anyone else.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice sets the vault as the owner of her 1,000-token account
+    //   2. Mallory sees the transaction land
+    //   3. Mallory calls withdraw_vulnerable on Alice's account
+    //   4. The vault makes Mallory its owner: 1,000 tokens gone
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<WithdrawVulnerable>) -> Result<()> {
+        // VULNERABLE: the only check is that the vault owns the account
+        release(
+            &ctx.accounts.vault,
+            &ctx.accounts.custody,
+            &ctx.accounts.token_program,
+            ctx.accounts.caller.key(),
+        )?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.caller.key(),
+            amount = ctx.accounts.custody.amount,
+            custody = ctx.accounts.custody.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Custody taken and recorded in one instruction
+    // ============================================================================
+    // FIX: The program moves ownership to the vault itself, with the
+    //      depositor signing, and writes a Custody record of who they are.
+    //      There is no window in which the vault owns an account it can't
+    //      attribute.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<DepositSecure>) -> Result<()> {
+        token::set_authority(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: ctx.accounts.depositor.to_account_info(),
+                    account_or_mint: ctx.accounts.custody.to_account_info(),
+                },
+            ),
+            AuthorityType::AccountOwner,
+            Some(ctx.accounts.vault.key()),
+        )?;
+
+        let record = &mut ctx.accounts.record;
+        record.depositor = ctx.accounts.depositor.key();
+        record.custody = ctx.accounts.custody.key();
+        record.bump = ctx.bumps.record;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = record.depositor,
+            amount = ctx.accounts.custody.amount,
+            custody = record.custody
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Account released only to the depositor on record
+    // ============================================================================
+    // FIX: `has_one = depositor` on the Custody record. An account handed to
+    //      the vault outside `deposit_secure` has no record and stays put.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<WithdrawSecure>) -> Result<()> {
+        // SECURE: the record names the only signer who gets it back
+        release(
+            &ctx.accounts.vault,
+            &ctx.accounts.custody,
+            &ctx.accounts.token_program,
+            ctx.accounts.depositor.key(),
+        )?;
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.depositor.key(),
+            amount = ctx.accounts.custody.amount,
+            custody = ctx.accounts.custody.key()
+        );
+        Ok(())
+    }
+}
+
+/// Make `new_owner` the owner of `custody`, signing as the vault.
+fn release<'info>(
+    vault: &Account<'info, Vault>,
+    custody: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    new_owner: Pubkey,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[b"vault", &[vault.bump]];
+    token::set_authority(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            SetAuthority {
+                current_authority: vault.to_account_info(),
+                account_or_mint: custody.to_account_info(),
+            },
+            &[seeds],
+        ),
+        AuthorityType::AccountOwner,
+        Some(new_owner),
+    )
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVulnerable<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = vault)]
+    pub custody: Account<'info, TokenAccount>,
+    pub caller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSecure<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = depositor)]
+    pub custody: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = depositor,
+        space = 8 + Custody::INIT_SPACE,
+        seeds = [b"custody", custody.key().as_ref()],
+        bump
+    )]
+    pub record: Account<'info, Custody>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSecure<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = vault)]
+    pub custody: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = depositor,
+        seeds = [b"custody", custody.key().as_ref()],
+        bump = record.bump,
+        has_one = depositor,
+        has_one = custody
+    )]
+    pub record: Account<'info, Custody>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// The PDA that owns every token account in custody.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub bump: u8, // 1 byte
+}
+
+/// Who handed `custody` to the vault, and so who gets it back.
+#[account]
+#[derive(InitSpace)]
+pub struct Custody {
+    pub depositor: Pubkey, // 32 bytes
+    pub custody: Pubkey,   // 32 bytes
+    pub bump: u8,          //  1 byte
+}
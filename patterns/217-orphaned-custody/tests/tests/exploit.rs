@@ -0,0 +1,336 @@
+/// # Pattern 217: Orphaned Custody — Mollusk Exploit Tests
+///
+/// Alice holds two token accounts: 1,000 tokens in `tokens` and 200 in
+/// `spare`. A raw deposit is her own SPL Token SetAuthority call making the
+/// vault PDA an account's owner. Mallory watches for it.
+///
+/// - Test 1: EXPLOIT — after Alice's raw deposit, withdraw_vulnerable hands
+///   the account to Mallory.
+/// - Test 2: SECURE — withdraw_secure refuses Mallory an account Alice
+///   deposited through deposit_secure, and refuses anyone an account with
+///   no custody record.
+/// - Test 3: SANITY — Alice deposits through deposit_secure and gets the
+///   account back through withdraw_secure.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{token_account, Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("BfwMRJNCGBxvHK4vu4R1P9vb9C2m9frgeMNkGfUEaynT");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const MINT_RENT: u64 = 1_461_600;
+const WALLET: u64 = 10_000_000_000;
+
+/// Alice's balances, in raw units.
+const TOKENS: u64 = 1_000;
+const SPARE: u64 = 200;
+
+// Error codes
+const CONSTRAINT_HAS_ONE: u32 = 2001;
+const ACCOUNT_NOT_INITIALIZED: u32 = 3012;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account: [8 disc][1 bump]
+fn serialize_vault(bump: u8) -> Vec<u8> {
+    let mut data = account_discriminator("Vault").to_vec();
+    data.push(bump);
+    data
+}
+
+/// Serialize a Custody account: [8 disc][32 depositor][32 custody][1 bump]
+fn serialize_custody(depositor: &Pubkey, custody: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Custody"));
+    data.extend_from_slice(depositor.as_ref());
+    data.extend_from_slice(custody.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with no authorities and no decimals.
+fn serialize_mint(supply: u64) -> Vec<u8> {
+    Mint {
+        supply,
+        ..Mint::default()
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "orphaned_custody");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+struct Setup {
+    vault: Pubkey,
+    mint: Pubkey,
+    alice: Pubkey,
+    /// Alice's 1,000-token account.
+    tokens: Pubkey,
+    /// Alice's 200-token account.
+    spare: Pubkey,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+        let mint = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let tokens = Pubkey::new_unique();
+        let spare = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+
+        let mut ledger = vec![
+            (
+                vault,
+                owned_account(&serialize_vault(vault_bump), RENT, &PROGRAM_ID),
+            ),
+            (
+                mint,
+                owned_account(
+                    &serialize_mint(TOKENS + SPARE),
+                    MINT_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (tokens, token_account(&TOKEN_PROGRAM_ID, &mint, &alice, TOKENS)),
+            (spare, token_account(&TOKEN_PROGRAM_ID, &mint, &alice, SPARE)),
+            (alice, system_account()),
+            (mallory, system_account()),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+        for custody in [tokens, spare] {
+            ledger.push((record_address(&custody).0, AccountSharedData::default()));
+        }
+
+        Self {
+            vault,
+            mint,
+            alice,
+            tokens,
+            spare,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// Alice's raw deposit: SPL Token SetAuthority(AccountOwner) on
+    /// `custody`, making the vault its owner.
+    fn raw_deposit(&self, custody: Pubkey) -> Instruction {
+        // [6 SetAuthority][2 AccountOwner][1 Some][32 new_authority]
+        let mut data = vec![6, 2, 1];
+        data.extend_from_slice(self.vault.as_ref());
+        Instruction::new_with_bytes(
+            TOKEN_PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(custody, false),
+                AccountMeta::new_readonly(self.alice, true),
+            ],
+        )
+    }
+
+    /// `caller`: withdraw_vulnerable() on `custody`.
+    fn withdraw_vulnerable(&self, custody: Pubkey, caller: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("withdraw_vulnerable"),
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(custody, false),
+                AccountMeta::new_readonly(caller, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// Alice: deposit_secure() of `custody`.
+    fn deposit_secure(&self, custody: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("deposit_secure"),
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(custody, false),
+                AccountMeta::new(record_address(&custody).0, false),
+                AccountMeta::new(self.alice, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// `depositor`: withdraw_secure() on `custody`.
+    fn withdraw_secure(&self, custody: Pubkey, depositor: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("withdraw_secure"),
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(custody, false),
+                AccountMeta::new(record_address(&custody).0, false),
+                AccountMeta::new(depositor, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn token_data(&self, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        TokenAccount::new(&self.mint, owner, amount).pack()
+    }
+}
+
+/// The Custody record PDA for `custody`.
+fn record_address(custody: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"custody", custody.as_ref()], &PROGRAM_ID)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_first_claimer_takes_raw_deposit_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Alice: SPL Token SetAuthority, vault becomes owner of `tokens`
+    //   2. Mallory: withdraw_vulnerable() on `tokens`
+    //
+    // Expected: both SUCCEED. Mallory owns Alice's 1,000 tokens.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, tokens, mallory) = (s.vault, s.tokens, s.mallory);
+
+    let ix = s.raw_deposit(tokens);
+    let deposited = s.token_data(&vault, TOKENS);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&tokens).data(&deposited).build(),
+        ],
+    );
+
+    let ix = s.withdraw_vulnerable(tokens, mallory);
+    let stolen = s.token_data(&mallory, TOKENS);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&tokens).data(&stolen).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_withdraw_requires_recorded_depositor() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Alice: deposit_secure() of `tokens`
+    //   2. Mallory: withdraw_secure() on `tokens`
+    //   3. Alice: SPL Token SetAuthority, vault becomes owner of `spare`
+    //   4. Mallory: withdraw_secure() on `spare`
+    //
+    // Expected: step 2 FAILS with ConstraintHasOne (2001): the record names
+    //           Alice. Step 4 FAILS with AccountNotInitialized (3012):
+    //           `spare` reached the vault without a record.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (tokens, spare, mallory) = (s.tokens, s.spare, s.mallory);
+
+    let ix = s.deposit_secure(tokens);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.withdraw_secure(tokens, mallory);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(CONSTRAINT_HAS_ONE))],
+    );
+
+    let ix = s.raw_deposit(spare);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.withdraw_secure(spare, mallory);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(ACCOUNT_NOT_INITIALIZED))],
+    );
+}
+
+#[test]
+fn sanity_depositor_withdraws_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   1. Alice: deposit_secure() of `tokens`
+    //   2. Alice: withdraw_secure() on `tokens`
+    //
+    // Expected: both SUCCEED. The vault owns `tokens` and the record names
+    //           Alice; then Alice owns `tokens` again and the record is
+    //           closed.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, tokens, alice) = (s.vault, s.tokens, s.alice);
+    let (record, record_bump) = record_address(&tokens);
+
+    let ix = s.deposit_secure(tokens);
+    let deposited = s.token_data(&vault, TOKENS);
+    let recorded = serialize_custody(&alice, &tokens, record_bump);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&tokens).data(&deposited).build(),
+            Check::account(&record).data(&recorded).build(),
+        ],
+    );
+
+    let ix = s.withdraw_secure(tokens, alice);
+    let returned = s.token_data(&alice, TOKENS);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&tokens).data(&returned).build(),
+            Check::account(&record).lamports(0).build(),
+        ],
+    );
+}
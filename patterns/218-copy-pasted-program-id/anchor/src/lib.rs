@@ -0,0 +1,285 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("7EC95Ur8HmpiTvhA9MiwC33y3HSDSt8Y6mwMpM4MokwU");
+
+/// The protocol's program ids, one module per program. A marker's `id()`
+/// names the module it reads from, so the pairing is visible in review and
+/// checkable against the workspace's `program-ids.toml`.
+pub mod ids {
+    /// Allowlist: the protocol's admin adds wallets, one entry PDA each.
+    pub mod allowlist {
+        anchor_lang::declare_id!("6Ne9ZyGTDtVYGPjzrP3dteZAsjaFW1HjD51Q6sua2Cpp");
+    }
+
+    /// Waitlist: anyone can add their own wallet. Built from the same
+    /// template as the allowlist, with the same entry seeds.
+    pub mod waitlist {
+        anchor_lang::declare_id!("3tSok8R3cs3KwHu5tamQxBXf5UrZcz94cpNTgjbGhE5R");
+    }
+}
+
+/// # Copy-Pasted Program Id
+///
+/// ## The Vulnerability
+/// A drop pays a fixed allocation to every wallet on the protocol's
+/// allowlist. Membership is an entry PDA at `[b"entry", wallet]` owned by
+/// the allowlist program, and the claim checks that program with
+/// `Program<'info, T>`. The allowlist has no Anchor crate to import, so the
+/// drop declares its own marker type and implements `Id` for it. The
+/// vulnerable marker's `id()` is a pasted literal, and the id pasted was
+/// the waitlist program's.
+///
+/// ## Why It Matters
+/// `Program<'info, T>` checks the account against `T::id()`, whatever that
+/// returns. From then on every check that goes through the marker, the
+/// program account, the entry's seeds, the entry's owner, is a check
+/// against the waitlist, where anyone can add themselves. The claim reads
+/// correctly and its constraints are all there. Allowlisted wallets can't
+/// claim at all, and any wallet that joined the waitlist can.
+///
+/// ## The Fix
+/// Program ids live in one `ids` module, a `declare_id!` per program, and
+/// each marker returns the `ID` of the module it's named for. The pairing
+/// is one line to review, and the detector checks every `declare_id!` and
+/// `Id::id()` in the workspace against `program-ids.toml`.
+#[program]
+pub mod copy_pasted_program_id {
+    use super::*;
+
+    /// Create the drop, paying `allocation` lamports per claim.
+    pub fn initialize(ctx: Context<Initialize>, allocation: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.allocation = allocation;
+        config.treasury_bump = ctx.bumps.treasury;
+        config.bump = ctx.bumps.config;
+        demo_log!(
+            Mode::Setup,
+            "initialize",
+            actor = config.admin,
+            amount = allocation
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Allowlist checked through a marker with the waitlist's id
+    // ============================================================================
+    // ISSUE: `AllowlistVulnerable::id()` returns the waitlist program's id.
+    //        `Program<'info, AllowlistVulnerable>` only accepts the waitlist,
+    //        and the entry's seeds and owner are checked against it.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory, who is on no allowlist, joins the permissionless waitlist
+    //   2. Mallory calls claim_vulnerable with the waitlist program and entry
+    //   3. Every constraint holds: Mallory receives the allocation
+    //   4. Alice, who is allowlisted, is refused: the allowlist program fails
+    //      the `Program` check
+    // ============================================================================
+    pub fn claim_vulnerable(ctx: Context<ClaimVulnerable>) -> Result<()> {
+        pay(
+            &ctx.accounts.config,
+            &ctx.accounts.treasury,
+            &ctx.accounts.claimer,
+            &ctx.accounts.system_program,
+        )?;
+        let allocation = ctx.accounts.config.allocation;
+        record(
+            &mut ctx.accounts.claim,
+            ctx.accounts.claimer.key(),
+            allocation,
+            ctx.bumps.claim,
+        );
+        demo_log!(
+            Mode::Vulnerable,
+            "claim",
+            actor = ctx.accounts.claimer.key(),
+            amount = allocation,
+            list = ctx.accounts.allowlist_program.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Marker returns the id of the program it's named for
+    // ============================================================================
+    // FIX: `Allowlist::id()` returns `ids::allowlist::ID`. The handler and the
+    //      constraints are unchanged; they now check the right program.
+    // ============================================================================
+    pub fn claim_secure(ctx: Context<ClaimSecure>) -> Result<()> {
+        pay(
+            &ctx.accounts.config,
+            &ctx.accounts.treasury,
+            &ctx.accounts.claimer,
+            &ctx.accounts.system_program,
+        )?;
+        let allocation = ctx.accounts.config.allocation;
+        record(
+            &mut ctx.accounts.claim,
+            ctx.accounts.claimer.key(),
+            allocation,
+            ctx.bumps.claim,
+        );
+        demo_log!(
+            Mode::Secure,
+            "claim",
+            actor = ctx.accounts.claimer.key(),
+            amount = allocation,
+            list = ctx.accounts.allowlist_program.key()
+        );
+        Ok(())
+    }
+}
+
+/// Transfer one allocation from the treasury to `claimer`.
+fn pay<'info>(
+    config: &Account<'info, Config>,
+    treasury: &SystemAccount<'info>,
+    claimer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[b"treasury", &[config.treasury_bump]];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Transfer {
+                from: treasury.to_account_info(),
+                to: claimer.to_account_info(),
+            },
+            &[seeds],
+        ),
+        config.allocation,
+    )
+}
+
+fn record(claim: &mut Account<Claim>, claimer: Pubkey, amount: u64, bump: u8) {
+    claim.claimer = claimer;
+    claim.amount = amount;
+    claim.bump = bump;
+}
+
+// ============================================================================
+// Program Markers
+// ============================================================================
+
+/// The allowlist program, as the vulnerable claim names it.
+#[derive(Clone)]
+pub struct AllowlistVulnerable;
+
+impl Id for AllowlistVulnerable {
+    fn id() -> Pubkey {
+        // VULNERABLE: the waitlist program's id, pasted by mistake
+        pubkey!("3tSok8R3cs3KwHu5tamQxBXf5UrZcz94cpNTgjbGhE5R")
+    }
+}
+
+/// The allowlist program.
+#[derive(Clone)]
+pub struct Allowlist;
+
+impl Id for Allowlist {
+    fn id() -> Pubkey {
+        // SECURE: the id of the module this marker is named for
+        ids::allowlist::ID
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: SystemAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVulnerable<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+    #[account(
+        init,
+        payer = claimer,
+        space = 8 + Claim::INIT_SPACE,
+        seeds = [b"claim", claimer.key().as_ref()],
+        bump
+    )]
+    pub claim: Account<'info, Claim>,
+    /// CHECK: The claimer's entry, a PDA of the list program. Its seeds and
+    /// owner are checked against `allowlist_program`.
+    #[account(
+        seeds = [b"entry", claimer.key().as_ref()],
+        bump,
+        seeds::program = allowlist_program.key(),
+        owner = allowlist_program.key()
+    )]
+    pub entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+    pub allowlist_program: Program<'info, AllowlistVulnerable>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSecure<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+    #[account(
+        init,
+        payer = claimer,
+        space = 8 + Claim::INIT_SPACE,
+        seeds = [b"claim", claimer.key().as_ref()],
+        bump
+    )]
+    pub claim: Account<'info, Claim>,
+    /// CHECK: The claimer's allowlist entry. Its seeds and owner are checked
+    /// against `allowlist_program`.
+    #[account(
+        seeds = [b"entry", claimer.key().as_ref()],
+        bump,
+        seeds::program = allowlist_program.key(),
+        owner = allowlist_program.key()
+    )]
+    pub entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+    pub allowlist_program: Program<'info, Allowlist>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Drop settings. The treasury PDA itself is a system account.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,     // 32 bytes
+    pub allocation: u64,   //  8 bytes
+    pub treasury_bump: u8, //  1 byte
+    pub bump: u8,          //  1 byte
+}
+
+/// One wallet's claim; its existence stops a second one.
+#[account]
+#[derive(InitSpace)]
+pub struct Claim {
+    pub claimer: Pubkey, // 32 bytes
+    pub amount: u64,     //  8 bytes
+    pub bump: u8,        //  1 byte
+}
@@ -0,0 +1,259 @@
+// Mollusk tests for Pattern 218
+/// # Pattern 218: Copy-Pasted Program Id — Mollusk Exploit Tests
+///
+/// The drop's treasury holds 100 SOL and pays 10 SOL per claim. Alice is
+/// on the allowlist. Mallory is only on the waitlist, which anyone can
+/// join. Each list's entry for a wallet is a PDA of that list's program at
+/// `[b"entry", wallet]`.
+///
+/// - Test 1: EXPLOIT — claim_vulnerable pays Mallory with her waitlist
+///   entry, and refuses Alice's allowlist entry.
+/// - Test 2: SECURE — claim_secure refuses the waitlist program, and
+///   Mallory has no allowlist entry.
+/// - Test 3: SANITY — Alice claims through claim_secure.
+///
+/// Neither list program is invoked, so their program accounts are
+/// executable placeholders; only the drop must be built into SBF_OUT_DIR.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, system_account,
+    Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("7EC95Ur8HmpiTvhA9MiwC33y3HSDSt8Y6mwMpM4MokwU");
+const ALLOWLIST_ID: Pubkey = solana_sdk::pubkey!("6Ne9ZyGTDtVYGPjzrP3dteZAsjaFW1HjD51Q6sua2Cpp");
+const WAITLIST_ID: Pubkey = solana_sdk::pubkey!("3tSok8R3cs3KwHu5tamQxBXf5UrZcz94cpNTgjbGhE5R");
+const SYSTEM_PROGRAM_ID: Pubkey = solana_sdk::system_program::ID;
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+const TREASURY: u64 = 100 * SOL;
+const ALLOCATION: u64 = 10 * SOL;
+
+// Error codes
+const CONSTRAINT_OWNER: u32 = 2004;
+const INVALID_PROGRAM_ID: u32 = 3008;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Config account:
+///   [8 disc][32 admin][8 allocation][1 treasury_bump][1 bump]
+fn serialize_config(admin: &Pubkey, allocation: u64, treasury_bump: u8, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(50);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&allocation.to_le_bytes());
+    data.push(treasury_bump);
+    data.push(bump);
+    data
+}
+
+/// Serialize a Claim account: [8 disc][32 claimer][8 amount][1 bump]
+fn serialize_claim(claimer: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Claim"));
+    data.extend_from_slice(claimer.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID)
+}
+
+/// `wallet`'s entry PDA under the list program `list`.
+fn entry(list: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"entry", wallet.as_ref()], list).0
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    config: Pubkey,
+    treasury: Pubkey,
+    alice: Pubkey,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "copy_pasted_program_id");
+        let (config, config_bump) = pda(&[b"config"]);
+        let (treasury, treasury_bump) = pda(&[b"treasury"]);
+        let admin = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+
+        let mut ledger = vec![
+            (
+                config,
+                owned_account(
+                    &serialize_config(&admin, ALLOCATION, treasury_bump, config_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (treasury, system_account(TREASURY)),
+            (
+                ALLOWLIST_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                WAITLIST_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                SYSTEM_PROGRAM_ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+        // Alice is allowlisted; Mallory joined the waitlist. The drop never
+        // reads an entry's data, only its address and owner.
+        for (wallet, list) in [(alice, ALLOWLIST_ID), (mallory, WAITLIST_ID)] {
+            ledger.extend([
+                (wallet, system_account(WALLET)),
+                (
+                    pda(&[b"claim", wallet.as_ref()]).0,
+                    AccountSharedData::default(),
+                ),
+            ]);
+            for candidate in [ALLOWLIST_ID, WAITLIST_ID] {
+                let account = if candidate == list {
+                    owned_account(wallet.as_ref(), RENT, &list)
+                } else {
+                    AccountSharedData::default()
+                };
+                ledger.push((entry(&candidate, &wallet), account));
+            }
+        }
+
+        Self {
+            mollusk,
+            config,
+            treasury,
+            alice,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// `claimer`: claim_*, naming `list` as the allowlist program and the
+    /// claimer's entry under it.
+    fn claim(&self, name: &str, claimer: &Pubkey, list: &Pubkey) -> Instruction {
+        let (claim, _) = pda(&[b"claim", claimer.as_ref()]);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new_readonly(self.config, false),
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(claim, false),
+                AccountMeta::new_readonly(entry(list, claimer), false),
+                AccountMeta::new(*claimer, true),
+                AccountMeta::new_readonly(*list, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_waitlist_entry_claims_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Mallory: claim_vulnerable with the waitlist program and her
+    //      waitlist entry
+    //   2. Alice: claim_vulnerable with the allowlist program and her
+    //      allowlist entry
+    //
+    // Expected: step 1 SUCCEEDS: AllowlistVulnerable::id() is the
+    //           waitlist's id, so every check passes and Mallory gets
+    //           10 SOL. Step 2 FAILS with InvalidProgramId (3008).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (treasury, alice, mallory) = (s.treasury, s.alice, s.mallory);
+    let (claim, claim_bump) = pda(&[b"claim", mallory.as_ref()]);
+
+    let ix = s.claim("claim_vulnerable", &mallory, &WAITLIST_ID);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury)
+                .lamports(TREASURY - ALLOCATION)
+                .build(),
+            Check::account(&claim)
+                .data(&serialize_claim(&mallory, ALLOCATION, claim_bump))
+                .build(),
+        ],
+    );
+
+    let ix = s.claim("claim_vulnerable", &alice, &ALLOWLIST_ID);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(INVALID_PROGRAM_ID))]);
+}
+
+#[test]
+fn secure_claim_requires_allowlist_entry() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Mallory: claim_secure with the waitlist program and her
+    //      waitlist entry
+    //   2. Mallory: claim_secure with the allowlist program and the
+    //      (empty) allowlist entry address for her wallet
+    //
+    // Expected: step 1 FAILS with InvalidProgramId (3008). Step 2 FAILS
+    //           with ConstraintOwner (2004). The treasury keeps 100 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+
+    let ix = s.claim("claim_secure", &mallory, &WAITLIST_ID);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(INVALID_PROGRAM_ID))]);
+
+    let ix = s.claim("claim_secure", &mallory, &ALLOWLIST_ID);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(CONSTRAINT_OWNER))]);
+}
+
+#[test]
+fn sanity_allowlisted_wallet_claims_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   Alice: claim_secure with the allowlist program and her entry
+    //
+    // Expected: SUCCEEDS. Alice receives 10 SOL and her claim is recorded.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (treasury, alice) = (s.treasury, s.alice);
+    let (claim, claim_bump) = pda(&[b"claim", alice.as_ref()]);
+
+    let ix = s.claim("claim_secure", &alice, &ALLOWLIST_ID);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury)
+                .lamports(TREASURY - ALLOCATION)
+                .build(),
+            Check::account(&claim)
+                .data(&serialize_claim(&alice, ALLOCATION, claim_bump))
+                .build(),
+        ],
+    );
+}
@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+use rent_reserve::withdrawable_lamports;
+
+declare_id!("4ApJqG659PAoJ4yw24ZsvVBBGtYSE4XMpC7Ux7CFNRrw");
+
+/// # Rent-Exempt Withdrawal
+///
+/// ## The Vulnerability
+/// A service collects its fees in a vault PDA, which also holds the
+/// service's settings and running total. `withdraw_all` sweeps the fees to
+/// the treasury. Sweeps can only pay the treasury, so anyone may run one.
+/// The vulnerable sweep pays out `vault.lamports()`: the fees, and the
+/// rent-exempt minimum that keeps the vault's data on chain with them.
+///
+/// ## Why It Matters
+/// The runtime deletes every account left with zero lamports at the end of
+/// a transaction. After one sweep the vault is gone, with its settings, and
+/// every later `pay` fails because the account it writes to doesn't exist.
+/// Anyone can trigger it, at no cost, the moment fees arrive. The treasury
+/// gains a fraction of a cent; the service stops taking payments.
+///
+/// ## The Fix
+/// Withdraw only what the account holds above its rent-exempt minimum.
+/// `withdraw_all_secure` pays out `withdrawable_lamports(vault)` from
+/// `common/rent-reserve`, and the vault stays rent-exempt however often it
+/// is swept.
+#[program]
+pub mod rent_exempt_withdrawal {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, treasury: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.admin = ctx.accounts.admin.key();
+        vault.treasury = treasury;
+        vault.collected = 0;
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "initialize",
+            actor = vault.admin,
+            treasury = treasury
+        );
+        Ok(())
+    }
+
+    /// Pay a fee of `amount` lamports into the vault.
+    pub fn pay(ctx: Context<Pay>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let vault = &mut ctx.accounts.vault;
+        vault.collected = vault
+            .collected
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "pay",
+            actor = ctx.accounts.payer.key(),
+            amount = amount,
+            collected = vault.collected
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Sweep takes the rent-exempt minimum with the fees
+    // ============================================================================
+    // ISSUE: The sweep pays out every lamport the vault holds. The vault is
+    //        left with zero, and the runtime deletes it, data and all, when
+    //        the transaction ends.
+    //
+    // ATTACK SCENARIO:
+    //   1. The vault holds 3 SOL of fees above its rent-exempt minimum
+    //   2. Mallory calls withdraw_all_vulnerable
+    //   3. The treasury receives 3 SOL plus the minimum; the vault has 0
+    //   4. The runtime deletes the vault, and every later pay fails
+    // ============================================================================
+    pub fn withdraw_all_vulnerable(ctx: Context<WithdrawAll>) -> Result<()> {
+        // VULNERABLE: the whole balance, rent reserve included
+        let amount = ctx.accounts.vault.to_account_info().lamports();
+        sweep(ctx.accounts, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw_all",
+            actor = ctx.accounts.caller.key(),
+            amount = amount,
+            remaining = ctx.accounts.vault.to_account_info().lamports()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Sweep stops at the rent-exempt minimum
+    // ============================================================================
+    // FIX: Only the lamports above the minimum for the vault's data are
+    //      fees. The vault keeps the rest and stays alive after every sweep.
+    // ============================================================================
+    pub fn withdraw_all_secure(ctx: Context<WithdrawAll>) -> Result<()> {
+        // SECURE: the balance above the rent-exempt minimum
+        let amount = withdrawable_lamports(&ctx.accounts.vault.to_account_info())?;
+        sweep(ctx.accounts, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "withdraw_all",
+            actor = ctx.accounts.caller.key(),
+            amount = amount,
+            remaining = ctx.accounts.vault.to_account_info().lamports()
+        );
+        Ok(())
+    }
+}
+
+/// Move `amount` lamports from the vault to the treasury.
+fn sweep(accounts: &mut WithdrawAll, amount: u64) -> Result<()> {
+    accounts.vault.sub_lamports(amount)?;
+    accounts.treasury.add_lamports(amount)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Pay<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawAll<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump, has_one = treasury)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub treasury: SystemAccount<'info>,
+    /// Anyone: a sweep can only pay the treasury.
+    pub caller: Signer<'info>,
+}
+
+/// The service's settings and fees. Lamports above the rent-exempt minimum
+/// are fees waiting to be swept.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub admin: Pubkey,    // 32 bytes
+    pub treasury: Pubkey, // 32 bytes
+    pub collected: u64,   //  8 bytes: lifetime fees
+    pub bump: u8,         //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
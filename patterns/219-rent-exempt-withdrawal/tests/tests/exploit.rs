@@ -0,0 +1,269 @@
+// Mollusk tests for Pattern 219
+/// # Pattern 219: Rent-Exempt Withdrawal — Mollusk Exploit Tests
+///
+/// The fee vault holds its rent-exempt minimum plus 3 SOL of fees. Sweeps
+/// pay the treasury, and anyone can run one.
+///
+/// - Test 1: EXPLOIT — Mallory's withdraw_all_vulnerable leaves the vault
+///   with zero lamports. The runtime deletes it, and Alice's payment fails.
+/// - Test 2: SECURE — withdraw_all_secure sweeps the 3 SOL and leaves the
+///   minimum. Alice pays, and the next sweep collects her payment.
+/// - Test 3: SANITY — a sweep with no fees pays nothing and changes nothing.
+///
+/// Mollusk runs single instructions, so it doesn't delete accounts left
+/// with zero lamports. `Setup::step` does that after each instruction, as
+/// the runtime does at the end of a transaction.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, system_account,
+    Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("4ApJqG659PAoJ4yw24ZsvVBBGtYSE4XMpC7Ux7CFNRrw");
+const SYSTEM_PROGRAM_ID: Pubkey = solana_sdk::system_program::ID;
+
+const WALLET: u64 = 10_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// 8 discriminator + 32 admin + 32 treasury + 8 collected + 1 bump.
+const VAULT_LEN: usize = 81;
+const FEES: u64 = 3 * SOL;
+const PAYMENT: u64 = SOL;
+
+// Error codes
+const ACCOUNT_NOT_INITIALIZED: u32 = 3012;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// The vault's rent-exempt minimum, at Mollusk's default rent.
+fn reserve() -> u64 {
+    Rent::default().minimum_balance(VAULT_LEN)
+}
+
+/// Serialize a Vault account:
+///   [8 disc][32 admin][32 treasury][8 collected][1 bump]
+fn serialize_vault(admin: &Pubkey, treasury: &Pubkey, collected: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(VAULT_LEN);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(treasury.as_ref());
+    data.extend_from_slice(&collected.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+struct Setup {
+    vault: Pubkey,
+    admin: Pubkey,
+    treasury: Pubkey,
+    alice: Pubkey,
+    mallory: Pubkey,
+    bump: u8,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A vault holding its minimum plus `fees`, all of them collected.
+    fn new(fees: u64) -> Self {
+        let (vault, bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+        let admin = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+
+        let ledger = vec![
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(&admin, &treasury, fees, bump),
+                    reserve() + fees,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (treasury, system_account(SOL)),
+            (alice, system_account(WALLET)),
+            (mallory, system_account(WALLET)),
+            (
+                SYSTEM_PROGRAM_ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            vault,
+            admin,
+            treasury,
+            alice,
+            mallory,
+            bump,
+            ledger: Ledger::new(ledger).collecting_garbage(),
+        }
+    }
+
+    fn pay(&self, payer: &Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("pay").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn withdraw_all(&self, name: &str, caller: &Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new_readonly(*caller, true),
+            ],
+        )
+    }
+
+    fn vault_data(&self, collected: u64) -> Vec<u8> {
+        serialize_vault(&self.admin, &self.treasury, collected, self.bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_sweep_deletes_vault_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Mallory: withdraw_all_vulnerable
+    //   2. Alice:   pay(1 SOL)
+    //
+    // Expected: step 1 SUCCEEDS and pays the treasury 3 SOL plus the vault's
+    //           rent-exempt minimum. The vault is left with zero lamports
+    //           and deleted. Step 2 FAILS with AccountNotInitialized (3012).
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "rent_exempt_withdrawal");
+    let mut s = Setup::new(FEES);
+    let (vault, treasury, alice, mallory) = (s.vault, s.treasury, s.alice, s.mallory);
+
+    let ix = s.withdraw_all("withdraw_all_vulnerable", &mallory);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(0).build(),
+            Check::account(&treasury)
+                .lamports(SOL + FEES + reserve())
+                .build(),
+        ],
+    );
+    // Deleted: no lamports, no data, owned by the System Program
+    assert_eq!(s.ledger.account(&vault), &AccountSharedData::default());
+
+    let ix = s.pay(&alice, PAYMENT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(ACCOUNT_NOT_INITIALIZED))],
+    );
+    assert_eq!(s.ledger.lamports(&alice), WALLET);
+}
+
+#[test]
+fn secure_sweep_leaves_rent_reserve() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Mallory: withdraw_all_secure
+    //   2. Alice:   pay(1 SOL)
+    //   3. Mallory: withdraw_all_secure
+    //
+    // Expected: step 1 pays the treasury 3 SOL and leaves the vault at its
+    //           minimum, data intact. Step 2 SUCCEEDS. Step 3 pays the
+    //           treasury Alice's 1 SOL, and the vault is still at its minimum.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "rent_exempt_withdrawal");
+    let mut s = Setup::new(FEES);
+    let (vault, treasury, alice, mallory) = (s.vault, s.treasury, s.alice, s.mallory);
+
+    let ix = s.withdraw_all("withdraw_all_secure", &mallory);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault)
+                .lamports(reserve())
+                .data(&s.vault_data(FEES))
+                .build(),
+            Check::account(&treasury).lamports(SOL + FEES).build(),
+        ],
+    );
+
+    let ix = s.pay(&alice, PAYMENT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault)
+                .lamports(reserve() + PAYMENT)
+                .data(&s.vault_data(FEES + PAYMENT))
+                .build(),
+        ],
+    );
+
+    let ix = s.withdraw_all("withdraw_all_secure", &mallory);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(reserve()).build(),
+            Check::account(&treasury)
+                .lamports(SOL + FEES + PAYMENT)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_sweep_without_fees_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   Mallory: withdraw_all_secure on a vault holding only its minimum
+    //
+    // Expected: SUCCEEDS, moving nothing: the vault keeps its minimum and
+    //           the treasury is unchanged.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "rent_exempt_withdrawal");
+    let mut s = Setup::new(0);
+    let (vault, treasury, mallory) = (s.vault, s.treasury, s.mallory);
+
+    let ix = s.withdraw_all("withdraw_all_secure", &mallory);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault)
+                .lamports(reserve())
+                .data(&s.vault_data(0))
+                .build(),
+            Check::account(&treasury).lamports(SOL).build(),
+        ],
+    );
+}
@@ -0,0 +1,204 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("AemgJhSdZDJ3GVKdhZ9n9jQ4dLrsqXrtLUADAZ9ipsGz");
+
+/// Fees above 10% are rejected.
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// # Config Init Front-Running
+///
+/// This program demonstrates a protocol-wide config that is created by a
+/// separate instruction after the program is deployed.
+///
+/// ## The Vulnerability
+/// The config lives at the fixed PDA `[b"config"]`, and `initialize` makes
+/// whoever pays for it the admin. Deploying the program and initializing
+/// its config are two transactions. In between, the program is live and
+/// the instruction is public, so anyone who sees the deploy can initialize
+/// first and name themselves admin.
+///
+/// ## Real-World Impact
+/// Bots watch for new program deployments and send the initialize the
+/// moment one lands. The attacker's config then sets the fee recipient and
+/// every other protocol-wide parameter. Because the PDA has one address,
+/// the team can't create a second config: their own initialize fails, and
+/// the only way out is a redeploy under a new program ID. If nobody checks
+/// the config's admin before launch, users deposit into a protocol whose
+/// fees go to the attacker.
+#[program]
+pub mod config_init_frontrun {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: First caller becomes admin
+    // ============================================================================
+    // ISSUE: `init` stops a second initialize, but nothing restricts the
+    //        first one. The payer is whoever got there first.
+    //
+    // ATTACK SCENARIO:
+    //   1. Team deploys the program
+    //   2. A bot sees the deploy and sends initialize_vulnerable, naming
+    //      itself admin and fee recipient
+    //   3. Team's initialize fails: the config already exists
+    //   4. Attacker raises the fee to the maximum; every fee is theirs
+    // ============================================================================
+    pub fn initialize_vulnerable(
+        ctx: Context<InitializeVulnerable>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        // VULNERABLE: the admin is just the payer
+        let admin = ctx.accounts.payer.key();
+        write_config(
+            &mut ctx.accounts.config,
+            admin,
+            fee_bps,
+            fee_recipient,
+            ctx.bumps.config,
+            Mode::Vulnerable,
+        )
+    }
+
+    // ============================================================================
+    // SECURE: Only the upgrade authority can initialize
+    // ============================================================================
+    // FIX: The program's ProgramData account records its upgrade authority,
+    //      the key that deployed it. The instruction takes that account,
+    //      checks it belongs to this program, and requires the signer to be
+    //      its upgrade authority. A bot can still send the transaction; it
+    //      just can't sign it.
+    //
+    // A hardcoded deployer key (`address = DEPLOYER`) works the same way
+    // when the program is deployed immutable or by a multisig.
+    // ============================================================================
+    pub fn initialize_secure(
+        ctx: Context<InitializeSecure>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        // SECURE: the constraints proved the signer deployed this program
+        let admin = ctx.accounts.authority.key();
+        write_config(
+            &mut ctx.accounts.config,
+            admin,
+            fee_bps,
+            fee_recipient,
+            ctx.bumps.config,
+            Mode::Secure,
+        )
+    }
+
+    /// Admin: change the fee and where it goes.
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+        let config = &mut ctx.accounts.config;
+        config.fee_bps = fee_bps;
+        config.fee_recipient = fee_recipient;
+        demo_log!(
+            Mode::Setup,
+            "update_config",
+            actor = config.admin,
+            fee_bps = fee_bps,
+            fee_recipient = fee_recipient
+        );
+        Ok(())
+    }
+}
+
+fn write_config(
+    config: &mut Account<Config>,
+    admin: Pubkey,
+    fee_bps: u16,
+    fee_recipient: Pubkey,
+    bump: u8,
+    mode: Mode,
+) -> Result<()> {
+    require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+    config.admin = admin;
+    config.fee_bps = fee_bps;
+    config.fee_recipient = fee_recipient;
+    config.bump = bump;
+    demo_log!(
+        mode,
+        "initialize",
+        actor = admin,
+        fee_bps = fee_bps,
+        fee_recipient = fee_recipient
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeVulnerable<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub payer: Signer<'info>, // <-- anyone
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSecure<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key())
+            @ ErrorCode::WrongProgramData
+    )]
+    pub program: Program<'info, crate::program::ConfigInitFrontrun>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,         // 32 bytes
+    pub fee_recipient: Pubkey, // 32 bytes
+    pub fee_bps: u16,          //  2 bytes
+    pub bump: u8,              //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Signer is not the program's upgrade authority")]
+    NotUpgradeAuthority,
+    #[msg("ProgramData account does not belong to this program")]
+    WrongProgramData,
+    #[msg("Fee is above the maximum")]
+    InvalidFee,
+}
@@ -0,0 +1,286 @@
+/// # Pattern 22: Config Init Front-Running — Mollusk Exploit Tests
+///
+/// The program has just been deployed by the team, whose key is recorded as
+/// its upgrade authority. The config PDA doesn't exist yet. The attacker
+/// has deployed a program of their own, so they hold a genuine ProgramData
+/// account too, with themselves as upgrade authority.
+///
+/// - Test 1: EXPLOIT — the attacker's initialize_vulnerable lands first,
+///   the team's fails, and the attacker raises the fee to the maximum.
+/// - Test 2: SECURE — initialize_secure signed by the attacker fails: they
+///   aren't this program's upgrade authority.
+/// - Test 3: SECURE — passing the attacker's own ProgramData fails too: it
+///   belongs to a different program.
+/// - Test 4: SANITY — the team initializes through initialize_secure and
+///   becomes admin.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{AccountSharedData, WritableAccount},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("AemgJhSdZDJ3GVKdhZ9n9jQ4dLrsqXrtLUADAZ9ipsGz");
+
+const SOL: u64 = 1_000_000_000;
+const WALLET: u64 = 10 * SOL;
+const CONFIG_LEN: usize = 75;
+
+const TEAM_FEE_BPS: u16 = 30;
+const MAX_FEE_BPS: u16 = 1_000;
+
+// Error codes
+const NOT_UPGRADE_AUTHORITY: u32 = 6000;
+const WRONG_PROGRAM_DATA: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Config account:
+///   [8 disc][32 admin][32 fee_recipient][2 fee_bps][1 bump]
+fn serialize_config(admin: &Pubkey, fee_recipient: &Pubkey, fee_bps: u16, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(CONFIG_LEN);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(fee_recipient.as_ref());
+    data.extend_from_slice(&fee_bps.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The loader's record of a program (UpgradeableLoaderState::Program):
+///   [4 tag = 2][32 programdata_address]
+fn program_account(programdata: &Pubkey) -> AccountSharedData {
+    let mut data = vec![2, 0, 0, 0];
+    data.extend_from_slice(programdata.as_ref());
+    let mut account =
+        AccountSharedData::new(SOL, data.len(), &solana_sdk::bpf_loader_upgradeable::id());
+    account.set_data_from_slice(&data);
+    account.set_executable(true);
+    account
+}
+
+/// A program's ProgramData header (UpgradeableLoaderState::ProgramData),
+/// without the ELF that follows it:
+///   [4 tag = 3][8 slot][1 Some][32 upgrade_authority_address]
+fn program_data_account(upgrade_authority: &Pubkey) -> AccountSharedData {
+    let mut data = vec![3, 0, 0, 0];
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(upgrade_authority.as_ref());
+    let mut account =
+        AccountSharedData::new(SOL, data.len(), &solana_sdk::bpf_loader_upgradeable::id());
+    account.set_data_from_slice(&data);
+    account
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+/// A freshly deployed program with no config yet.
+struct Setup {
+    mollusk: Mollusk,
+    team: Pubkey,
+    attacker: Pubkey,
+    config: Pubkey,
+    config_bump: u8,
+    program_data: Pubkey,
+    attacker_program_data: Pubkey,
+    ledger: Ledger,
+}
+
+fn setup() -> Setup {
+    let team = Pubkey::new_unique();
+    let attacker = Pubkey::new_unique();
+    let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+    let (program_data, _) = Pubkey::find_program_address(
+        &[PROGRAM_ID.as_ref()],
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+
+    // The attacker deployed their own program; its ProgramData is real.
+    let attacker_program = Pubkey::new_unique();
+    let (attacker_program_data, _) = Pubkey::find_program_address(
+        &[attacker_program.as_ref()],
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+
+    let ledger = vec![
+        (config, AccountSharedData::default()),
+        (team, system_account()),
+        (attacker, system_account()),
+        (PROGRAM_ID, program_account(&program_data)),
+        (program_data, program_data_account(&team)),
+        (attacker_program_data, program_data_account(&attacker)),
+        (
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ),
+    ];
+
+    Setup {
+        mollusk: Mollusk::new(&PROGRAM_ID, "config_init_frontrun"),
+        team,
+        attacker,
+        config,
+        config_bump,
+        program_data,
+        attacker_program_data,
+        ledger: Ledger::new(ledger),
+    }
+}
+
+impl Setup {
+    fn initialize_vulnerable(&self, payer: Pubkey, fee_bps: u16) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &self.config_args("initialize_vulnerable", fee_bps, &payer),
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn initialize_secure(&self, authority: Pubkey, program_data: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &self.config_args("initialize_secure", TEAM_FEE_BPS, &authority),
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new(authority, true),
+                AccountMeta::new_readonly(PROGRAM_ID, false),
+                AccountMeta::new_readonly(program_data, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn update_config(&self, admin: Pubkey, fee_bps: u16) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &self.config_args("update_config", fee_bps, &admin),
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new_readonly(admin, true),
+            ],
+        )
+    }
+
+    /// Instruction data for (fee_bps: u16, fee_recipient: Pubkey).
+    fn config_args(&self, name: &str, fee_bps: u16, fee_recipient: &Pubkey) -> Vec<u8> {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&fee_bps.to_le_bytes());
+        data.extend_from_slice(fee_recipient.as_ref());
+        data
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_bot_initializes_first_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The attacker's bot sees the deploy before the team's
+    // initialize lands.
+    //   1. Attacker: initialize_vulnerable(30 bps, fee_recipient = attacker)
+    //   2. Team:     initialize_vulnerable(30 bps, fee_recipient = team)
+    //   3. Attacker: update_config(1,000 bps, fee_recipient = attacker)
+    //
+    // Expected: 1 SUCCEEDS with the attacker as admin. 2 FAILS: the config
+    //           PDA already exists. 3 SUCCEEDS: the fee is at the maximum
+    //           and goes to the attacker.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let (config, attacker, team) = (s.config, s.attacker, s.team);
+
+    let claimed = serialize_config(&attacker, &attacker, TEAM_FEE_BPS, s.config_bump);
+    let ix = s.initialize_vulnerable(attacker, TEAM_FEE_BPS);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&claimed).build(),
+        ],
+    );
+
+    let ix = s.initialize_vulnerable(team, TEAM_FEE_BPS);
+    let result = s.mollusk.process_instruction(&ix, &s.ledger.accounts_for(&ix));
+    assert!(result.program_result.is_err());
+
+    let raised = serialize_config(&attacker, &attacker, MAX_FEE_BPS, s.config_bump);
+    let ix = s.update_config(attacker, MAX_FEE_BPS);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&raised).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_init_rejects_non_upgrade_authority() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker sends initialize_secure with this program's real
+    // ProgramData account.
+    //
+    // Expected: FAILS with NotUpgradeAuthority (6000). The team is the
+    //           upgrade authority, and the attacker can't sign for it.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+
+    let ix = s.initialize_secure(s.attacker, s.program_data);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(NOT_UPGRADE_AUTHORITY))],
+    );
+}
+
+#[test]
+fn secure_init_rejects_foreign_program_data() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker passes the ProgramData of a program they deployed
+    // themselves. Its upgrade authority really is the attacker.
+    //
+    // Expected: FAILS with WrongProgramData (6001). This program's loader
+    //           account points at a different ProgramData.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+
+    let ix = s.initialize_secure(s.attacker, s.attacker_program_data);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(WRONG_PROGRAM_DATA))]);
+}
+
+#[test]
+fn sanity_upgrade_authority_initializes_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The team, as upgrade authority, sends initialize_secure.
+    //
+    // Expected: SUCCEEDS. The config exists with the team as admin and fee
+    //           recipient.
+    // -----------------------------------------------------------------------
+    let mut s = setup();
+    let (config, team) = (s.config, s.team);
+
+    let expected = serialize_config(&team, &team, TEAM_FEE_BPS, s.config_bump);
+    let ix = s.initialize_secure(team, s.program_data);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&expected).build(),
+        ],
+    );
+}
@@ -0,0 +1,420 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+use safe_math::{value_basket, Holding, TokenAmount, UsdPrice, ValuationError};
+
+declare_id!("DCpHJwjLqUBnmsVdJvssvigEHVZwBZMaeezuND6BgG8");
+
+/// Assets in a basket.
+pub const ASSETS: usize = 2;
+
+/// The decimals the vulnerable valuation assumes every mint has.
+const ASSUMED_DECIMALS: u32 = 6;
+
+/// # Basket Valuation
+///
+/// ## The Vulnerability
+/// A basket vault holds two assets and mints shares against its net asset
+/// value: a deposit worth 1% of the basket mints 1% more shares. The
+/// vulnerable NAV makes three mistakes earlier patterns make one at a time.
+/// It values raw balances as if every mint had 6 decimals (pattern 34). It
+/// uses each feed's price however old it is (pattern 14). And it reads
+/// balances from the vault's token accounts, which anyone can transfer
+/// into.
+///
+/// ## Why It Matters
+/// Each mistake misprices shares on its own. A 9-decimal asset deposited
+/// at 6-decimal scale mints 1,000 times its worth. A price frozen before a
+/// crash mints shares for value the deposit no longer has. A donation to
+/// an empty basket raises the share price so far that the next deposit
+/// mints nothing, and the donor's single share takes it. Fixing them one by
+/// one in each program that values a basket leaves the next one to repeat
+/// them.
+///
+/// ## The Fix
+/// One audited valuation, with the units in its types. `deposit_secure`
+/// builds `safe_math::TokenAmount`s, which can't exist without decimals,
+/// and `UsdPrice`s, which can't exist without a publish slot, from the
+/// vault's own record of its holdings. `safe_math::value_basket` scales
+/// each by its decimals and refuses stale prices.
+#[program]
+pub mod basket_valuation {
+    use super::*;
+
+    pub fn create_basket(ctx: Context<CreateBasket>, max_age_slots: u64) -> Result<()> {
+        let basket = &mut ctx.accounts.basket;
+        basket.admin = ctx.accounts.admin.key();
+        basket.max_age_slots = max_age_slots;
+        basket.total_shares = 0;
+        basket.bump = ctx.bumps.basket;
+        demo_log!(
+            Mode::Setup,
+            "create_basket",
+            actor = basket.admin,
+            max_age_slots = max_age_slots
+        );
+        Ok(())
+    }
+
+    /// Admin: add `mint` as asset `index`, with a vault and a price feed.
+    pub fn add_asset(ctx: Context<AddAsset>, index: u8) -> Result<()> {
+        let asset = &mut ctx.accounts.basket.assets[index as usize];
+        asset.mint = ctx.accounts.mint.key();
+        asset.vault = ctx.accounts.vault.key();
+        asset.feed = ctx.accounts.feed.key();
+        asset.decimals = ctx.accounts.mint.decimals;
+        asset.holdings = 0;
+        ctx.accounts.feed.bump = ctx.bumps.feed;
+        demo_log!(
+            Mode::Setup,
+            "add_asset",
+            actor = ctx.accounts.admin.key(),
+            index = index,
+            mint = asset.mint,
+            decimals = asset.decimals
+        );
+        Ok(())
+    }
+
+    /// Admin: publish `price`, in micro-USD per whole token.
+    pub fn set_price(ctx: Context<SetPrice>, price: u64) -> Result<()> {
+        let feed = &mut ctx.accounts.feed;
+        feed.price = price;
+        feed.slot = Clock::get()?.slot;
+        demo_log!(
+            Mode::Setup,
+            "set_price",
+            actor = ctx.accounts.admin.key(),
+            price = price,
+            slot = feed.slot
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.user = ctx.accounts.user.key();
+        position.shares = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: NAV from token balances, one assumed scale, any price
+    // ============================================================================
+    // ISSUE: Balances are read from the vault token accounts, scaled as if
+    //        every mint had 6 decimals, and priced without looking at when
+    //        the price was published.
+    //
+    // ATTACK SCENARIOS:
+    //   Decimals: SOL has 9 decimals. 1 SOL is valued as 1,000 SOL, and
+    //             mints $100,000 of shares for $100
+    //   Stale:    USDC's feed stopped at $1 while USDC traded lower. Every
+    //             deposit still mints at $1
+    //   Donation: Mallory deposits 1 raw unit of USDC into the empty basket
+    //             and transfers 1,000 USDC straight to its vault. Alice's
+    //             1,000 USDC deposit mints 0 shares
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<Deposit>, index: u8, amount: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let feeds = [&accounts.feed_a, &accounts.feed_b];
+
+        // VULNERABLE: donatable balances, assumed decimals, prices of any age
+        let mut nav: u64 = 0;
+        for (vault, feed) in [&accounts.vault_a, &accounts.vault_b]
+            .into_iter()
+            .zip(feeds)
+        {
+            nav = nav
+                .checked_add(assumed_value(vault.amount, feed.price)?)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+        let value = assumed_value(amount, feeds[index as usize].price)?;
+
+        let shares = deposit(ctx.accounts, index, amount, value, nav)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.user.key(),
+            index = index,
+            amount = amount,
+            value = value,
+            nav = nav,
+            shares = shares
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: NAV from value_basket over recorded holdings
+    // ============================================================================
+    // FIX: Each holding is a TokenAmount with its mint's decimals and a
+    //      UsdPrice with its publish slot, built from the basket's record of
+    //      what was deposited. value_basket scales every holding by its own
+    //      decimals and refuses prices older than max_age_slots.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<Deposit>, index: u8, amount: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let basket = &accounts.basket;
+        let feeds = [&accounts.feed_a, &accounts.feed_b];
+        let slot = Clock::get()?.slot;
+
+        // SECURE: recorded holdings, each with its decimals and price age
+        let holdings: Vec<Holding> = basket
+            .assets
+            .iter()
+            .zip(feeds)
+            .map(|(asset, feed)| holding(asset.holdings, asset, feed))
+            .collect();
+        let nav = value_basket(&holdings, slot, basket.max_age_slots).map_err(valuation_error)?;
+        let deposited = holding(
+            amount,
+            &basket.assets[index as usize],
+            feeds[index as usize],
+        );
+        let value =
+            value_basket(&[deposited], slot, basket.max_age_slots).map_err(valuation_error)?;
+
+        let shares = deposit(ctx.accounts, index, amount, value, nav)?;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.user.key(),
+            index = index,
+            amount = amount,
+            value = value,
+            nav = nav,
+            shares = shares
+        );
+        Ok(())
+    }
+}
+
+/// `amount × price`, scaled as if the mint had `ASSUMED_DECIMALS`.
+fn assumed_value(amount: u64, price: u64) -> Result<u64> {
+    let value = (amount as u128) * (price as u128) / 10u128.pow(ASSUMED_DECIMALS);
+    Ok(u64::try_from(value).map_err(|_| ErrorCode::Overflow)?)
+}
+
+/// `raw` units of `asset`, priced by `feed`.
+fn holding(raw: u64, asset: &Asset, feed: &PriceFeed) -> Holding {
+    Holding {
+        amount: TokenAmount::new(raw, asset.decimals),
+        price: UsdPrice::new(feed.price, feed.slot),
+    }
+}
+
+fn valuation_error(error: ValuationError) -> ErrorCode {
+    match error {
+        ValuationError::StalePrice => ErrorCode::StalePrice,
+        ValuationError::InvalidDecimals => ErrorCode::InvalidDecimals,
+        ValuationError::Overflow => ErrorCode::Overflow,
+    }
+}
+
+/// Move `amount` of asset `index` into its vault and mint shares for
+/// `value` against `nav`, the basket's value before the deposit. Returns
+/// the shares minted.
+fn deposit(accounts: &mut Deposit, index: u8, amount: u64, value: u64, nav: u64) -> Result<u64> {
+    let total = accounts.basket.total_shares;
+    let shares = if total == 0 {
+        value
+    } else {
+        let shares = (value as u128) * (total as u128) / (nav as u128).max(1);
+        u64::try_from(shares).map_err(|_| ErrorCode::Overflow)?
+    };
+
+    let vault = match index {
+        0 => &accounts.vault_a,
+        _ => &accounts.vault_b,
+    };
+    token::transfer(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.user_tokens.to_account_info(),
+                to: vault.to_account_info(),
+                authority: accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let basket = &mut accounts.basket;
+    let asset = &mut basket.assets[index as usize];
+    asset.holdings = asset
+        .holdings
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    basket.total_shares = total.checked_add(shares).ok_or(ErrorCode::Overflow)?;
+    let position = &mut accounts.position;
+    position.shares = position
+        .shares
+        .checked_add(shares)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(shares)
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateBasket<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Basket::INIT_SPACE,
+        seeds = [b"basket"],
+        bump
+    )]
+    pub basket: Account<'info, Basket>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct AddAsset<'info> {
+    #[account(
+        mut,
+        seeds = [b"basket"],
+        bump = basket.bump,
+        has_one = admin,
+        constraint = (index as usize) < ASSETS @ ErrorCode::UnknownAsset
+    )]
+    pub basket: Account<'info, Basket>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = basket
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PriceFeed::INIT_SPACE,
+        seeds = [b"feed", mint.key().as_ref()],
+        bump
+    )]
+    pub feed: Account<'info, PriceFeed>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(seeds = [b"basket"], bump = basket.bump, has_one = admin)]
+    pub basket: Account<'info, Basket>,
+    #[account(
+        mut,
+        constraint = basket.assets.iter().any(|asset| asset.feed == feed.key())
+            @ ErrorCode::UnknownAsset
+    )]
+    pub feed: Account<'info, PriceFeed>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"basket"],
+        bump = basket.bump,
+        constraint = (index as usize) < ASSETS @ ErrorCode::UnknownAsset
+    )]
+    pub basket: Account<'info, Basket>,
+    #[account(
+        mut,
+        seeds = [b"position", user.key().as_ref()],
+        bump = position.bump,
+        has_one = user
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut, token::mint = basket.assets[index as usize].mint)]
+    pub user_tokens: Account<'info, TokenAccount>,
+    #[account(mut, address = basket.assets[0].vault)]
+    pub vault_a: Account<'info, TokenAccount>,
+    #[account(mut, address = basket.assets[1].vault)]
+    pub vault_b: Account<'info, TokenAccount>,
+    #[account(address = basket.assets[0].feed)]
+    pub feed_a: Account<'info, PriceFeed>,
+    #[account(address = basket.assets[1].feed)]
+    pub feed_b: Account<'info, PriceFeed>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// One asset of the basket. `holdings` is what deposits have put in its
+/// vault; tokens sent to the vault any other way aren't counted.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Asset {
+    pub mint: Pubkey,  // 32 bytes
+    pub vault: Pubkey, // 32 bytes
+    pub feed: Pubkey,  // 32 bytes
+    pub decimals: u8,  //  1 byte
+    pub holdings: u64, //  8 bytes
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Basket {
+    pub admin: Pubkey,           //  32 bytes
+    pub max_age_slots: u64,      //   8 bytes
+    pub total_shares: u64,       //   8 bytes
+    pub assets: [Asset; ASSETS], // 210 bytes
+    pub bump: u8,                //   1 byte
+}
+
+/// An asset's price in micro-USD per whole token, and the slot it was set.
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    pub price: u64, // 8 bytes
+    pub slot: u64,  // 8 bytes
+    pub bump: u8,   // 1 byte
+}
+
+/// A user's shares of the basket.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub user: Pubkey, // 32 bytes
+    pub shares: u64,  //  8 bytes
+    pub bump: u8,     //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("A price is older than the basket allows")]
+    StalePrice,
+    #[msg("No such asset in the basket")]
+    UnknownAsset,
+    #[msg("A mint's decimals are too large")]
+    InvalidDecimals,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
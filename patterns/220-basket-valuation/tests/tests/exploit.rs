@@ -0,0 +1,491 @@
+// Mollusk tests for Pattern 220
+/// # Pattern 220: Basket Valuation — Mollusk Exploit Tests
+///
+/// The basket holds USDC (6 decimals, $1) and SOL (9 decimals, $100). It
+/// is slot 1,000, and prices may be 100 slots old. Unless a test says
+/// otherwise, Alice has deposited 10,000 USDC for 10,000,000,000 shares
+/// (one per micro-USD).
+///
+/// - Test 1: EXPLOIT (decimals) — 1 SOL through deposit_vulnerable mints
+///   shares for $100,000.
+/// - Test 2: EXPLOIT (staleness) — deposit_vulnerable accepts a USDC price
+///   published 500 slots ago.
+/// - Test 3: EXPLOIT (donation) — Mallory deposits 1 raw unit into the
+///   empty basket and donates 1,000 USDC to its vault. Alice's 1,000 USDC
+///   deposit mints 0 shares.
+/// - Tests 4–6: SECURE — deposit_secure mints 1 SOL at $100, refuses the
+///   stale price, and ignores the donation.
+/// - Test 7: SANITY — Alice's 1,000 USDC through deposit_secure mints
+///   1,000,000,000 shares.
+///
+/// The SPL Token program must be built into SBF_OUT_DIR as `spl_token.so`.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::token_account;
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("DCpHJwjLqUBnmsVdJvssvigEHVZwBZMaeezuND6BgG8");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+
+const SLOT: u64 = 1_000;
+const MAX_AGE_SLOTS: u64 = 100;
+
+/// Asset indexes, decimals, and prices in micro-USD per whole token.
+const USDC: u8 = 0;
+const SOL: u8 = 1;
+const USDC_DECIMALS: u8 = 6;
+const SOL_DECIMALS: u8 = 9;
+const USDC_PRICE: u64 = 1_000_000;
+const SOL_PRICE: u64 = 100_000_000;
+
+/// Raw amounts.
+const ONE_USDC: u64 = 1_000_000;
+const ONE_SOL: u64 = 1_000_000_000;
+const ALICE_HOLDINGS: u64 = 10_000 * ONE_USDC;
+
+// Error codes
+const STALE_PRICE: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// One basket asset: [32 mint][32 vault][32 feed][1 decimals][8 holdings]
+struct Asset {
+    mint: Pubkey,
+    vault: Pubkey,
+    feed: Pubkey,
+    feed_bump: u8,
+    decimals: u8,
+}
+
+/// Serialize a Basket account:
+///   [8 disc][32 admin][8 max_age_slots][8 total_shares][105 × 2 assets][1 bump]
+fn serialize_basket(
+    admin: &Pubkey,
+    total_shares: u64,
+    assets: &[(&Asset, u64)],
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(267);
+    data.extend_from_slice(&account_discriminator("Basket"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&MAX_AGE_SLOTS.to_le_bytes());
+    data.extend_from_slice(&total_shares.to_le_bytes());
+    for (asset, holdings) in assets {
+        data.extend_from_slice(asset.mint.as_ref());
+        data.extend_from_slice(asset.vault.as_ref());
+        data.extend_from_slice(asset.feed.as_ref());
+        data.push(asset.decimals);
+        data.extend_from_slice(&holdings.to_le_bytes());
+    }
+    data.push(bump);
+    data
+}
+
+/// Serialize a PriceFeed account: [8 disc][8 price][8 slot][1 bump]
+fn serialize_feed(price: u64, slot: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(25);
+    data.extend_from_slice(&account_discriminator("PriceFeed"));
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&slot.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account: [8 disc][32 user][8 shares][1 bump]
+fn serialize_position(user: &Pubkey, shares: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(&shares.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "basket_valuation");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk.sysvars.clock.slot = SLOT;
+    mollusk
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID)
+}
+
+/// A depositor's position and token accounts.
+#[derive(Clone, Copy)]
+struct User {
+    key: Pubkey,
+    position: Pubkey,
+    bump: u8,
+    usdc: Pubkey,
+    sol: Pubkey,
+}
+
+struct Setup {
+    basket: Pubkey,
+    usdc: Asset,
+    sol: Asset,
+    alice: User,
+    mallory: User,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A basket holding `holdings` raw USDC, all deposited by Alice at $1.
+    /// Alice and Mallory each have 2,000 USDC and 1 SOL in their wallets.
+    fn new(holdings: u64) -> Self {
+        let (basket, basket_bump) = pda(&[b"basket"]);
+        let asset = |decimals: u8| {
+            let mint = Pubkey::new_unique();
+            let (vault, _) = pda(&[b"vault", mint.as_ref()]);
+            let (feed, feed_bump) = pda(&[b"feed", mint.as_ref()]);
+            Asset {
+                mint,
+                vault,
+                feed,
+                feed_bump,
+                decimals,
+            }
+        };
+        let usdc = asset(USDC_DECIMALS);
+        let sol = asset(SOL_DECIMALS);
+
+        let mut ledger = vec![
+            (
+                basket,
+                owned_account(
+                    &serialize_basket(
+                        &Pubkey::new_unique(),
+                        holdings,
+                        &[(&usdc, holdings), (&sol, 0)],
+                        basket_bump,
+                    ),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (usdc.vault, token_account(&TOKEN_PROGRAM_ID, &usdc.mint, &basket, holdings)),
+            (sol.vault, token_account(&TOKEN_PROGRAM_ID, &sol.mint, &basket, 0)),
+            (
+                usdc.feed,
+                owned_account(
+                    &serialize_feed(USDC_PRICE, SLOT, usdc.feed_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                sol.feed,
+                owned_account(
+                    &serialize_feed(SOL_PRICE, SLOT, sol.feed_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+        let mut user = |shares: u64| {
+            let key = Pubkey::new_unique();
+            let (position, bump) = pda(&[b"position", key.as_ref()]);
+            let (usdc_tokens, sol_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+            ledger.extend([
+                (
+                    position,
+                    owned_account(&serialize_position(&key, shares, bump), RENT, &PROGRAM_ID),
+                ),
+                (
+                    usdc_tokens,
+                    token_account(&TOKEN_PROGRAM_ID, &usdc.mint, &key, 2_000 * ONE_USDC),
+                ),
+                (sol_tokens, token_account(&TOKEN_PROGRAM_ID, &sol.mint, &key, ONE_SOL)),
+                (key, system_account()),
+            ]);
+            User {
+                key,
+                position,
+                bump,
+                usdc: usdc_tokens,
+                sol: sol_tokens,
+            }
+        };
+        let alice = user(holdings);
+        let mallory = user(0);
+
+        Self {
+            basket,
+            usdc,
+            sol,
+            alice,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// Rewrite the USDC feed as published at `slot`.
+    fn set_usdc_slot(&mut self, slot: u64) {
+        let data = serialize_feed(USDC_PRICE, slot, self.usdc.feed_bump);
+        self.ledger
+            .account_mut(&self.usdc.feed)
+            .set_data_from_slice(&data);
+    }
+
+    /// `user`: deposit_*(index, amount).
+    fn deposit(&self, name: &str, user: &User, index: u8, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.push(index);
+        data.extend_from_slice(&amount.to_le_bytes());
+        let source = if index == USDC { user.usdc } else { user.sol };
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.basket, false),
+                AccountMeta::new(user.position, false),
+                AccountMeta::new(source, false),
+                AccountMeta::new(self.usdc.vault, false),
+                AccountMeta::new(self.sol.vault, false),
+                AccountMeta::new_readonly(self.usdc.feed, false),
+                AccountMeta::new_readonly(self.sol.feed, false),
+                AccountMeta::new_readonly(user.key, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// `user`: a plain SPL Token transfer of USDC into the basket's vault.
+    fn donate(&self, user: &User, amount: u64) -> Instruction {
+        let mut data = vec![3]; // Transfer
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            TOKEN_PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(user.usdc, false),
+                AccountMeta::new(self.usdc.vault, false),
+                AccountMeta::new_readonly(user.key, true),
+            ],
+        )
+    }
+}
+
+impl User {
+    /// The position account's data holding `shares`.
+    fn position_data(&self, shares: u64) -> Vec<u8> {
+        serialize_position(&self.key, shares, self.bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_sol_valued_at_usdc_decimals_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT (decimals):
+    //   Mallory: deposit_vulnerable(SOL, 1 SOL)
+    //
+    // Expected: SUCCEEDS. 1,000,000,000 raw SOL units at 6 decimals is
+    //           1,000 SOL, or $100,000. Mallory gets 100,000,000,000
+    //           shares, 91% of a basket worth $10,100.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(ALICE_HOLDINGS);
+    let mallory = s.mallory;
+
+    let ix = s.deposit("deposit_vulnerable", &mallory, SOL, ONE_SOL);
+    let data = mallory.position_data(100_000_000_000);
+    let checks = [
+        Check::success(),
+        Check::account(&mallory.position).data(&data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+}
+
+#[test]
+fn exploit_stale_price_accepted_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT (staleness):
+    //   The USDC feed was last published at slot 500, 500 slots ago
+    //   Mallory: deposit_vulnerable(USDC, 1,000 USDC)
+    //
+    // Expected: SUCCEEDS at the old $1 price: 1,000,000,000 shares, whatever
+    //           USDC has traded at since.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(ALICE_HOLDINGS);
+    let mallory = s.mallory;
+    s.set_usdc_slot(SLOT - 500);
+
+    let ix = s.deposit("deposit_vulnerable", &mallory, USDC, 1_000 * ONE_USDC);
+    let data = mallory.position_data(1_000 * ONE_USDC);
+    let checks = [
+        Check::success(),
+        Check::account(&mallory.position).data(&data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+}
+
+#[test]
+fn exploit_donation_zeroes_next_deposit_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT (donation):
+    //   1. Mallory: deposit_vulnerable(USDC, 1 raw unit) into the empty basket
+    //   2. Mallory: SPL Token transfer of 1,000 USDC to the USDC vault
+    //   3. Alice:   deposit_vulnerable(USDC, 1,000 USDC)
+    //
+    // Expected: step 1 mints Mallory 1 share. Step 3 SUCCEEDS against a NAV
+    //           that counts the donation: 1,000 USDC × 1 share / 1,000.000001
+    //           USDC rounds to 0 shares. Mallory's 1 share owns the basket.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(0);
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    let ix = s.deposit("deposit_vulnerable", &mallory, USDC, 1);
+    let data = mallory.position_data(1);
+    let checks = [
+        Check::success(),
+        Check::account(&mallory.position).data(&data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+
+    let ix = s.donate(&mallory, 1_000 * ONE_USDC);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.deposit("deposit_vulnerable", &alice, USDC, 1_000 * ONE_USDC);
+    let data = alice.position_data(0);
+    let checks = [
+        Check::success(),
+        Check::account(&alice.position).data(&data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+}
+
+#[test]
+fn secure_sol_valued_at_its_decimals() {
+    // -----------------------------------------------------------------------
+    // SECURE (decimals):
+    //   Mallory: deposit_secure(SOL, 1 SOL)
+    //
+    // Expected: SUCCEEDS at $100: 100,000,000 shares.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(ALICE_HOLDINGS);
+    let mallory = s.mallory;
+
+    let ix = s.deposit("deposit_secure", &mallory, SOL, ONE_SOL);
+    let data = mallory.position_data(100 * ONE_USDC);
+    let checks = [
+        Check::success(),
+        Check::account(&mallory.position).data(&data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+}
+
+#[test]
+fn secure_stale_price_refused() {
+    // -----------------------------------------------------------------------
+    // SECURE (staleness):
+    //   The USDC feed was last published at slot 500
+    //   Mallory: deposit_secure(SOL, 1 SOL), then deposit_secure(USDC, ...)
+    //
+    // Expected: both FAIL with StalePrice (6000). The NAV needs every price,
+    //           so a stale USDC price blocks SOL deposits too.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(ALICE_HOLDINGS);
+    let mallory = s.mallory;
+    s.set_usdc_slot(SLOT - 500);
+
+    for ix in [
+        s.deposit("deposit_secure", &mallory, SOL, ONE_SOL),
+        s.deposit("deposit_secure", &mallory, USDC, 1_000 * ONE_USDC),
+    ] {
+        s.ledger.step(
+            &mollusk,
+            &ix,
+            &[Check::err(ProgramError::Custom(STALE_PRICE))],
+        );
+    }
+}
+
+#[test]
+fn secure_donation_not_counted() {
+    // -----------------------------------------------------------------------
+    // SECURE (donation):
+    //   1. Mallory: deposit_secure(USDC, 1 raw unit) into the empty basket
+    //   2. Mallory: SPL Token transfer of 1,000 USDC to the USDC vault
+    //   3. Alice:   deposit_secure(USDC, 1,000 USDC)
+    //
+    // Expected: the NAV counts recorded holdings, 1 raw unit, so Alice's
+    //           deposit mints 1,000,000,000 shares to Mallory's 1.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(0);
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    let ix = s.deposit("deposit_secure", &mallory, USDC, 1);
+    let data = mallory.position_data(1);
+    let checks = [
+        Check::success(),
+        Check::account(&mallory.position).data(&data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+
+    let ix = s.donate(&mallory, 1_000 * ONE_USDC);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.deposit("deposit_secure", &alice, USDC, 1_000 * ONE_USDC);
+    let data = alice.position_data(1_000 * ONE_USDC);
+    let checks = [
+        Check::success(),
+        Check::account(&alice.position).data(&data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+}
+
+#[test]
+fn sanity_usdc_deposit_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   Alice: deposit_secure(USDC, 1,000 USDC)
+    //
+    // Expected: SUCCEEDS. Her 10,000,000,000 shares grow by 1,000,000,000.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(ALICE_HOLDINGS);
+    let alice = s.alice;
+
+    let ix = s.deposit("deposit_secure", &alice, USDC, 1_000 * ONE_USDC);
+    let data = alice.position_data(ALICE_HOLDINGS + 1_000 * ONE_USDC);
+    let checks = [
+        Check::success(),
+        Check::account(&alice.position).data(&data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+}
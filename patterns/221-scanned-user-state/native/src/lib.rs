@@ -0,0 +1,216 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use demo_log::{demo_log, Mode};
+
+solana_program::declare_id!("FqPDVLpR8yYu2BgJYES1PmWin6RbXz6TAjNDRdroBeMn");
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+/// # Scanned User State
+///
+/// ## The Vulnerability
+/// A custody program keeps each user's lamports in a vault it owns, and
+/// records which vault belongs to whom in the user's state account. The
+/// vulnerable withdraw finds that state by scanning the accounts it was
+/// given for one whose first 32 bytes are the signer's key. It never asks
+/// who owns the account it found, or where it lives. Anyone can create an
+/// account whose data starts with their own key and continues with any
+/// vault they like.
+///
+/// ## Why It Matters
+/// A state account is only evidence if the program wrote it. Bytes in an
+/// account owned by someone else are whatever that someone chose. Mallory
+/// passes a lookalike naming Alice's vault ahead of everything else, the
+/// scan stops at it, and Alice's vault pays Mallory.
+///
+/// ## The Fix
+/// Don't look state up by its contents. Derive the address it must have,
+/// the PDA `["state", user]`, require the account passed to be that one,
+/// and check that this program owns it before reading a byte.
+///
+/// ## Instructions
+/// `[tag: u8][amount: u64 LE]`
+/// - `0` — withdraw_vulnerable: accounts `[vault (w), user (s, w), ..candidates]`
+/// - `1` — withdraw_secure:     accounts `[vault (w), user (s, w), state]`
+///
+/// State data: `[owner: Pubkey][vault: Pubkey]`. Vaults are program-owned
+/// accounts with no data.
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let (tag, rest) = instruction_data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let amount = rest
+        .get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    match tag {
+        0 => withdraw_vulnerable(program_id, accounts, amount),
+        1 => withdraw_secure(program_id, accounts, amount),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// ============================================================================
+// VULNERABLE: User state found by scanning account contents
+// ============================================================================
+// ISSUE: Any account whose first 32 bytes equal the signer's key is taken
+//        as the signer's state. Its owner and address are never checked,
+//        so the vault it names is whatever its creator wrote.
+//
+// ATTACK SCENARIO:
+//   1. Alice's state, at her PDA, names her vault holding 5 SOL
+//   2. Mallory creates a 64-byte account, owned by her own program:
+//      [mallory][alice's vault]
+//   3. Mallory calls withdraw_vulnerable with Alice's vault, passing the
+//      lookalike as the first candidate
+//   4. The scan matches the lookalike, its vault matches, and Alice's
+//      vault pays Mallory
+// ============================================================================
+fn withdraw_vulnerable(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+    let vault = next_account_info(account_iter)?;
+    let user = next_account_info(account_iter)?;
+    if !user.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // VULNERABLE: the first account that starts with the signer's key
+    let state = account_iter
+        .find(|candidate| {
+            candidate
+                .try_borrow_data()
+                .is_ok_and(|data| data.len() >= STATE_LEN && data[..32] == user.key.to_bytes())
+        })
+        .ok_or(CustodyError::StateNotFound)?;
+
+    let state_vault = read_vault(state)?;
+    pay_out(program_id, vault, &state_vault, user, amount)?;
+    demo_log!(
+        Mode::Vulnerable,
+        "withdraw",
+        actor = user.key,
+        amount = amount,
+        vault = vault.key,
+        state = state.key
+    );
+    Ok(())
+}
+
+// ============================================================================
+// SECURE: User state resolved by its expected PDA
+// ============================================================================
+// FIX: The state must be the account at ["state", user], and this program
+//      must own it. Only this program can write there, so the vault it
+//      names is the one the program recorded for the user.
+// ============================================================================
+fn withdraw_secure(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+    let vault = next_account_info(account_iter)?;
+    let user = next_account_info(account_iter)?;
+    let state = next_account_info(account_iter)?;
+    if !user.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // SECURE: the address is derived from the signer, not found in the data
+    let (expected, _) = state_address(user.key, program_id);
+    if *state.key != expected {
+        return Err(CustodyError::InvalidState.into());
+    }
+    // SECURE: only state this program wrote is believed
+    if state.owner != program_id {
+        return Err(CustodyError::InvalidState.into());
+    }
+
+    let state_vault = read_vault(state)?;
+    pay_out(program_id, vault, &state_vault, user, amount)?;
+    demo_log!(
+        Mode::Secure,
+        "withdraw",
+        actor = user.key,
+        amount = amount,
+        vault = vault.key,
+        state = state.key
+    );
+    Ok(())
+}
+
+/// The state PDA for `user`: `["state", user]`.
+pub fn state_address(user: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"state", user.as_ref()], program_id)
+}
+
+/// The vault a state account names.
+fn read_vault(state: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    let data = state.try_borrow_data()?;
+    data.get(32..STATE_LEN)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(Pubkey::new_from_array)
+        .ok_or(CustodyError::InvalidState.into())
+}
+
+/// Move `amount` from `vault` to `user`, if `vault` is this program's and
+/// is the one the state names.
+fn pay_out(
+    program_id: &Pubkey,
+    vault: &AccountInfo,
+    state_vault: &Pubkey,
+    user: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    if vault.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if vault.key != state_vault {
+        return Err(CustodyError::WrongVault.into());
+    }
+
+    let vault_lamports = vault.lamports();
+    **vault.try_borrow_mut_lamports()? = vault_lamports
+        .checked_sub(amount)
+        .ok_or(CustodyError::InsufficientFunds)?;
+    let user_lamports = user.lamports();
+    **user.try_borrow_mut_lamports()? = user_lamports
+        .checked_add(amount)
+        .ok_or(CustodyError::Overflow)?;
+    Ok(())
+}
+
+/// State data: 32-byte owner + 32-byte vault.
+pub const STATE_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustodyError {
+    /// No candidate account starts with the signer's key
+    StateNotFound,
+    /// The state account isn't the signer's PDA, or this program doesn't own it
+    InvalidState,
+    /// The vault isn't the one the state names
+    WrongVault,
+    /// Vault holds fewer lamports than requested
+    InsufficientFunds,
+    /// Arithmetic overflow
+    Overflow,
+}
+
+impl From<CustodyError> for ProgramError {
+    fn from(e: CustodyError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
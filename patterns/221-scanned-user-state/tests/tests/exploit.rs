@@ -0,0 +1,266 @@
+// Mollusk tests for Pattern 221
+/// # Pattern 221: Scanned User State — Mollusk Exploit Tests
+///
+/// Alice's and Mallory's states sit at their PDAs, owned by the program,
+/// each naming the holder's vault. Each vault holds 5 SOL. Mallory also
+/// owns a lookalike: 64 bytes, owned by a program of her own, that start
+/// with her key and name Alice's vault.
+///
+/// - Test 1: EXPLOIT — withdraw_vulnerable finds the lookalike first and
+///   pays Mallory out of Alice's vault.
+/// - Test 2: SECURE — withdraw_secure rejects the lookalike, and Alice's
+///   real state, because neither is at Mallory's PDA.
+/// - Test 3: SECURE — Mallory's own state doesn't name Alice's vault.
+/// - Test 4: SANITY — Alice withdraws from her vault with her state.
+///
+/// This program is native (no Anchor): instruction data is
+/// `[tag: u8][amount: u64 LE]` and errors are the `CustodyError` enum values.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::owned_account;
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("FqPDVLpR8yYu2BgJYES1PmWin6RbXz6TAjNDRdroBeMn");
+
+const SOL: u64 = 1_000_000_000;
+const VAULT_LAMPORTS: u64 = 5 * SOL;
+const WALLET: u64 = SOL;
+const RENT: u64 = 2_000_000;
+
+const TAG_WITHDRAW_VULNERABLE: u8 = 0;
+const TAG_WITHDRAW_SECURE: u8 = 1;
+
+// CustodyError discriminants
+const INVALID_STATE: u32 = 1;
+const WRONG_VAULT: u32 = 2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize state data: [32 owner][32 vault]
+fn serialize_state(owner: &Pubkey, vault: &Pubkey) -> Vec<u8> {
+    let mut data = Vec::with_capacity(64);
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(vault.as_ref());
+    data
+}
+
+fn state_address(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"state", user.as_ref()], &PROGRAM_ID).0
+}
+
+fn wallet() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    alice: Pubkey,
+    alice_vault: Pubkey,
+    mallory: Pubkey,
+    /// Mallory's account shaped like a state: [mallory][alice_vault].
+    lookalike: Pubkey,
+    accounts: Vec<(Pubkey, AccountSharedData)>,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let alice = Pubkey::new_unique();
+        let alice_vault = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let mallory_vault = Pubkey::new_unique();
+        let lookalike = Pubkey::new_unique();
+        let mallory_program = Pubkey::new_unique();
+
+        let mut accounts = vec![(
+            lookalike,
+            owned_account(
+                &serialize_state(&mallory, &alice_vault),
+                RENT,
+                &mallory_program,
+            ),
+        )];
+        for (user, vault) in [(alice, alice_vault), (mallory, mallory_vault)] {
+            accounts.push((
+                state_address(&user),
+                owned_account(&serialize_state(&user, &vault), RENT, &PROGRAM_ID),
+            ));
+            accounts.push((vault, owned_account(&[], VAULT_LAMPORTS, &PROGRAM_ID)));
+            accounts.push((user, wallet()));
+        }
+
+        Self {
+            alice,
+            alice_vault,
+            mallory,
+            lookalike,
+            accounts,
+        }
+    }
+
+    /// A withdraw of `amount` from `vault` to `user`, with `states` after
+    /// them. Returns the instruction and the accounts it names.
+    fn withdraw(
+        &self,
+        tag: u8,
+        vault: &Pubkey,
+        user: &Pubkey,
+        states: &[Pubkey],
+        amount: u64,
+    ) -> (Instruction, Vec<(Pubkey, AccountSharedData)>) {
+        let mut data = vec![tag];
+        data.extend_from_slice(&amount.to_le_bytes());
+
+        let mut metas = vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*user, true),
+        ];
+        metas.extend(
+            states
+                .iter()
+                .map(|state| AccountMeta::new_readonly(*state, false)),
+        );
+        let accounts = metas
+            .iter()
+            .map(|meta| {
+                self.accounts
+                    .iter()
+                    .find(|(key, _)| *key == meta.pubkey)
+                    .cloned()
+                    .unwrap()
+            })
+            .collect();
+
+        (
+            Instruction::new_with_bytes(PROGRAM_ID, &data, metas),
+            accounts,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_lookalike_state_drains_vault() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory calls withdraw_vulnerable(5 SOL) on Alice's vault,
+    //          passing her lookalike, then her real state, as candidates
+    //
+    // Expected: SUCCEEDS. The scan stops at the lookalike, which starts
+    //           with Mallory's key and names Alice's vault. Alice's vault
+    //           is empty and Mallory has its 5 SOL.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "native_state_scan");
+    let s = Setup::new();
+    let (ix, accounts) = s.withdraw(
+        TAG_WITHDRAW_VULNERABLE,
+        &s.alice_vault,
+        &s.mallory,
+        &[s.lookalike, state_address(&s.mallory)],
+        VAULT_LAMPORTS,
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&s.alice_vault).lamports(0).build(),
+            Check::account(&s.mallory)
+                .lamports(WALLET + VAULT_LAMPORTS)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_state_resolved_by_address() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory calls withdraw_secure on Alice's vault, passing
+    //   1. her lookalike as the state
+    //   2. Alice's real state as the state
+    //
+    // Expected: both FAIL with InvalidState (1). The state must be the
+    //           account at ["state", mallory]; one that merely starts with
+    //           her key, or that names Alice's vault, isn't it.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "native_state_scan");
+    let s = Setup::new();
+
+    for state in [s.lookalike, state_address(&s.alice)] {
+        let (ix, accounts) = s.withdraw(
+            TAG_WITHDRAW_SECURE,
+            &s.alice_vault,
+            &s.mallory,
+            &[state],
+            VAULT_LAMPORTS,
+        );
+        mollusk.process_and_validate_instruction(
+            &ix,
+            &accounts,
+            &[Check::err(ProgramError::Custom(INVALID_STATE))],
+        );
+    }
+}
+
+#[test]
+fn secure_own_state_names_own_vault() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory calls withdraw_secure on Alice's vault with her own
+    //         state, at her PDA
+    //
+    // Expected: FAILS with WrongVault (2). The state the program wrote for
+    //           Mallory names Mallory's vault.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "native_state_scan");
+    let s = Setup::new();
+    let (ix, accounts) = s.withdraw(
+        TAG_WITHDRAW_SECURE,
+        &s.alice_vault,
+        &s.mallory,
+        &[state_address(&s.mallory)],
+        VAULT_LAMPORTS,
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[Check::err(ProgramError::Custom(WRONG_VAULT))],
+    );
+}
+
+#[test]
+fn sanity_owner_withdraws_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: Alice calls withdraw_secure(2 SOL) on her vault with her state
+    //
+    // Expected: SUCCEEDS. Her vault pays her 2 SOL.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "native_state_scan");
+    let s = Setup::new();
+    let (ix, accounts) = s.withdraw(
+        TAG_WITHDRAW_SECURE,
+        &s.alice_vault,
+        &s.alice,
+        &[state_address(&s.alice)],
+        2 * SOL,
+    );
+
+    mollusk.process_and_validate_instruction(
+        &ix,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&s.alice_vault)
+                .lamports(VAULT_LAMPORTS - 2 * SOL)
+                .build(),
+            Check::account(&s.alice).lamports(WALLET + 2 * SOL).build(),
+        ],
+    );
+}
@@ -0,0 +1,287 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::onchain::invoke_transfer_checked;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use demo_log::{demo_log, Mode};
+
+declare_id!("DQvca8PCbLpFPn3f1h4FEfGQSdx4MX7adUcSKveYGkX3");
+
+/// The most recipients a splitter pays.
+pub const MAX_RECIPIENTS: usize = 8;
+
+/// # Compute Budget Under Transfer Hooks
+///
+/// ## The Vulnerability
+/// A permissionless splitter holds tokens of any mint and pays an equal
+/// share to each of up to eight recipients. The vulnerable `distribute`
+/// pays all of them in one instruction. Eight was picked from a benchmark
+/// with a plain mint: eight transfers fit comfortably in the 200,000 CU a
+/// keeper asks for. But a Token-2022 mint with a transfer hook makes every
+/// transfer CPI into the hook program, and the hook's cost is whatever its
+/// author made it. At 30,000 CU a hook, eight payouts no longer fit.
+///
+/// ## Why It Matters
+/// Nothing fails for plain mints, so the benchmark never showed it. For a
+/// hooked mint, every distribution runs out of compute and reverts, and
+/// the tokens sit in the vault until someone pays for a bigger budget, if
+/// the transaction limit allows one at all. The users who brought those
+/// mints are locked out; everyone else is fine.
+///
+/// ## The Fix
+/// Keep the per-instruction work bounded by one transfer. `distribute_secure`
+/// pays a single recipient, by index, and marks it paid, so a hooked mint
+/// costs one hook call per instruction however many recipients there are.
+/// Benchmark each mint type the program accepts, not just the plain one.
+#[program]
+pub mod hook_compute_budget {
+    use super::*;
+
+    /// Open a splitter for `mint` that pays `share` to each of
+    /// `recipients`, which are token accounts.
+    pub fn create_splitter(
+        ctx: Context<CreateSplitter>,
+        recipients: Vec<Pubkey>,
+        share: u64,
+    ) -> Result<()> {
+        require!(
+            !recipients.is_empty() && recipients.len() <= MAX_RECIPIENTS,
+            ErrorCode::InvalidRecipients
+        );
+
+        let splitter = &mut ctx.accounts.splitter;
+        splitter.creator = ctx.accounts.creator.key();
+        splitter.mint = ctx.accounts.mint.key();
+        splitter.recipients = recipients;
+        splitter.share = share;
+        splitter.bump = ctx.bumps.splitter;
+        demo_log!(
+            Mode::Setup,
+            "create_splitter",
+            actor = splitter.creator,
+            recipients = splitter.recipients.len(),
+            share = share
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Every payout in one instruction
+    // ============================================================================
+    // ISSUE: The instruction's cost grows with the recipients and with the
+    //        mint's transfer hook, and only the first was benchmarked. A
+    //        hooked mint multiplies the hook's cost by eight.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice opens a splitter for a mint whose transfer hook costs
+    //      about 30,000 CU, paying eight recipients
+    //   2. The keeper calls distribute_vulnerable with its usual 200,000 CU
+    //   3. Eight transfers, eight hook calls: the budget runs out and the
+    //      instruction reverts
+    //   4. It reverts every time. Alice's recipients are never paid, while
+    //      splitters of plain mints pay out as usual
+    // ============================================================================
+    pub fn distribute_vulnerable<'info>(
+        ctx: Context<'_, '_, '_, 'info, Distribute<'info>>,
+    ) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let splitter = &accounts.splitter;
+        require!(splitter.paid == 0, ErrorCode::AlreadyPaid);
+
+        // Recipients in order, then the accounts the mint's hook needs
+        let count = splitter.recipients.len();
+        require!(
+            ctx.remaining_accounts.len() >= count,
+            ErrorCode::MissingRecipient
+        );
+        let (recipients, hook_accounts) = ctx.remaining_accounts.split_at(count);
+
+        // VULNERABLE: cost is count × (transfer + hook), unbounded by the program
+        for (recipient, expected) in recipients.iter().zip(&splitter.recipients) {
+            require_keys_eq!(recipient.key(), *expected, ErrorCode::WrongRecipient);
+            pay_share(
+                splitter,
+                &accounts.mint,
+                &accounts.vault,
+                &accounts.token_program,
+                recipient,
+                hook_accounts,
+            )?;
+        }
+
+        ctx.accounts.splitter.paid = u8::MAX >> (MAX_RECIPIENTS - count);
+        demo_log!(
+            Mode::Vulnerable,
+            "distribute",
+            splitter = ctx.accounts.splitter.key(),
+            recipients = count
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: One payout per instruction
+    // ============================================================================
+    // FIX: Each call pays the recipient at `index` and marks it paid. An
+    //      instruction costs one transfer and at most one hook call, for
+    //      any mint and any number of recipients, and anyone can crank the
+    //      rest in separate instructions or transactions.
+    // ============================================================================
+    pub fn distribute_secure<'info>(
+        ctx: Context<'_, '_, '_, 'info, DistributeShare<'info>>,
+        index: u8,
+    ) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let splitter = &accounts.splitter;
+        let expected = splitter
+            .recipients
+            .get(index as usize)
+            .ok_or(ErrorCode::InvalidIndex)?;
+        require_keys_eq!(
+            accounts.recipient.key(),
+            *expected,
+            ErrorCode::WrongRecipient
+        );
+        let bit = 1u8 << index;
+        require!(splitter.paid & bit == 0, ErrorCode::AlreadyPaid);
+
+        // SECURE: one transfer, one hook call, whatever the mint
+        pay_share(
+            splitter,
+            &accounts.mint,
+            &accounts.vault,
+            &accounts.token_program,
+            &accounts.recipient.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+
+        ctx.accounts.splitter.paid |= bit;
+        demo_log!(
+            Mode::Secure,
+            "distribute",
+            splitter = ctx.accounts.splitter.key(),
+            index = index
+        );
+        Ok(())
+    }
+}
+
+/// Move one share from the vault to `recipient`. If the mint has a
+/// transfer hook, Token-2022 calls it, so `hook_accounts` must hold the
+/// hook program and any accounts it asks for.
+fn pay_share<'info>(
+    splitter: &Account<'info, Splitter>,
+    mint: &InterfaceAccount<'info, Mint>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    recipient: &AccountInfo<'info>,
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let mint_key = mint.key();
+    let seeds: &[&[u8]] = &[
+        b"splitter",
+        splitter.creator.as_ref(),
+        mint_key.as_ref(),
+        &[splitter.bump],
+    ];
+    invoke_transfer_checked(
+        token_program.key,
+        vault.to_account_info(),
+        mint.to_account_info(),
+        recipient.clone(),
+        splitter.to_account_info(),
+        hook_accounts,
+        splitter.share,
+        mint.decimals,
+        &[seeds],
+    )?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateSplitter<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Splitter::INIT_SPACE,
+        seeds = [b"splitter", creator.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub splitter: Account<'info, Splitter>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Distribute<'info> {
+    #[account(
+        mut,
+        seeds = [b"splitter", splitter.creator.as_ref(), mint.key().as_ref()],
+        bump = splitter.bump,
+        has_one = mint
+    )]
+    pub splitter: Account<'info, Splitter>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = splitter,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeShare<'info> {
+    #[account(
+        mut,
+        seeds = [b"splitter", splitter.creator.as_ref(), mint.key().as_ref()],
+        bump = splitter.bump,
+        has_one = mint
+    )]
+    pub splitter: Account<'info, Splitter>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = splitter,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub recipient: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Pays `share` of `mint` to each recipient token account. Bit `i` of
+/// `paid` is set once recipient `i` has been paid.
+#[account]
+#[derive(InitSpace)]
+pub struct Splitter {
+    pub creator: Pubkey, // 32 bytes
+    pub mint: Pubkey,    // 32 bytes
+    #[max_len(MAX_RECIPIENTS)]
+    pub recipients: Vec<Pubkey>, // 4 + 32 * MAX_RECIPIENTS bytes
+    pub share: u64,      //  8 bytes
+    pub paid: u8,        //  1 byte
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("A splitter pays between one and eight recipients")]
+    InvalidRecipients,
+    #[msg("Not every recipient was passed")]
+    MissingRecipient,
+    #[msg("Recipient doesn't match the splitter's")]
+    WrongRecipient,
+    #[msg("No recipient at that index")]
+    InvalidIndex,
+    #[msg("Recipient already paid")]
+    AlreadyPaid,
+}
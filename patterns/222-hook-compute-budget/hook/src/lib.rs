@@ -0,0 +1,31 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, hash::hashv, pubkey::Pubkey,
+};
+
+solana_program::declare_id!("WHe91E7tyc3izo9E6Z7miJpteFFmbsyMPZGqvqvbhjd");
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+/// Hash rounds per transfer: roughly 30,000 CU.
+pub const HOOK_ROUNDS: usize = 300;
+
+/// # Compute Hook (Pattern 222)
+///
+/// A mint's transfer hook. Token-2022 calls it on every transfer of the
+/// mint, with the transfer's accounts and amount. Real hooks read
+/// allowlists, check Merkle proofs, or update fee state; this one stands
+/// in for that work by hashing `HOOK_ROUNDS` times and approving every
+/// transfer. Nothing about it is malicious. Its cost is simply its
+/// author's choice, and every program moving the mint pays it.
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let mut digest = hashv(&[instruction_data]);
+    for _ in 0..HOOK_ROUNDS {
+        digest = hashv(&[digest.as_ref()]);
+    }
+    Ok(())
+}
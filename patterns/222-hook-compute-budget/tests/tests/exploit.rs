@@ -0,0 +1,429 @@
+// Mollusk tests for Pattern 222
+/// # Pattern 222: Compute Budget Under Transfer Hooks — Mollusk Exploit Tests
+///
+/// A splitter's vault holds 800 tokens, to be paid 100 each to eight
+/// recipients. Each test builds it twice over: once for a plain Token-2022
+/// mint, and once for a mint whose transfer hook (`compute_hook`) costs
+/// about 30,000 CU per transfer. The keeper runs each instruction with a
+/// 200,000 CU budget.
+///
+/// - Test 1: EXPLOIT — distribute_vulnerable pays all eight for the plain
+///   mint, and runs out of compute for the hooked one.
+/// - Test 2: SECURE — distribute_secure pays the hooked mint's eight
+///   recipients one per instruction, each within budget.
+/// - Test 3: SECURE — a recipient distribute_secure paid can't be paid again.
+/// - Test 4: BENCHMARK — compute units for one share and for all eight,
+///   per mint type, against the keeper's budget.
+///
+/// Transfers run in Token-2022, and the hooked mint's in `compute_hook`
+/// too, so `spl_token_2022.so` and `compute_hook.so` must be in
+/// SBF_OUT_DIR (see the pattern README).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+use test_harness::token::{push_extension, Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("DQvca8PCbLpFPn3f1h4FEfGQSdx4MX7adUcSKveYGkX3");
+const HOOK_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("WHe91E7tyc3izo9E6Z7miJpteFFmbsyMPZGqvqvbhjd");
+const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// The compute units the keeper requests for every instruction.
+const KEEPER_BUDGET: u64 = 200_000;
+/// The most a transaction can request.
+const MAX_BUDGET: u64 = 1_400_000;
+
+const RECIPIENTS: usize = 8;
+const DECIMALS: u8 = 6;
+const SHARE: u64 = 100_000_000; // 100 tokens
+
+/// 8 discriminator + 32 creator + 32 mint + 4 + 32 × 8 recipients
+/// + 8 share + 1 paid + 1 bump.
+const SPLITTER_LEN: usize = 342;
+
+// Token-2022 layout constants
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+const EXTENSION_TRANSFER_HOOK_ACCOUNT: u16 = 15;
+
+// Error codes
+const ALREADY_PAID: u32 = 6004;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Splitter, zero-padded to its allocated length:
+///   [8 disc][32 creator][32 mint][4 len][32 × len recipients][8 share]
+///   [1 paid][1 bump]
+fn serialize_splitter(
+    creator: &Pubkey,
+    mint: &Pubkey,
+    recipients: &[Pubkey],
+    paid: u8,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(SPLITTER_LEN);
+    data.extend_from_slice(&account_discriminator("Splitter"));
+    data.extend_from_slice(creator.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&(recipients.len() as u32).to_le_bytes());
+    for recipient in recipients {
+        data.extend_from_slice(recipient.as_ref());
+    }
+    data.extend_from_slice(&SHARE.to_le_bytes());
+    data.push(paid);
+    data.push(bump);
+    data.resize(SPLITTER_LEN, 0);
+    data
+}
+
+/// Serialize a Token-2022 mint. A hooked mint carries a TransferHook
+/// extension naming `compute_hook`: [32 authority][32 program_id]
+fn serialize_mint(hooked: bool) -> Vec<u8> {
+    let mut data = Mint {
+        mint_authority: Some(Pubkey::new_unique()),
+        supply: RECIPIENTS as u64 * SHARE,
+        decimals: DECIMALS,
+        ..Mint::default()
+    }
+    .pack();
+    if hooked {
+        let mut hook = vec![0u8; 32]; // authority: None
+        hook.extend_from_slice(HOOK_PROGRAM_ID.as_ref());
+        push_extension(&mut data, EXTENSION_TRANSFER_HOOK, &hook);
+    }
+    data
+}
+
+/// Serialize a Token-2022 account. For a hooked mint it carries a
+/// TransferHookAccount extension, which Token-2022 flags during the hook:
+///   [1 transferring]
+fn serialize_token_account(mint: &Pubkey, owner: &Pubkey, amount: u64, hooked: bool) -> Vec<u8> {
+    let mut data = TokenAccount::new(mint, owner, amount).pack();
+    if hooked {
+        push_extension(&mut data, EXTENSION_TRANSFER_HOOK_ACCOUNT, &[0]); // transferring: false
+    }
+    data
+}
+
+fn owned_account(data: &[u8], owner: &Pubkey) -> AccountSharedData {
+    let lamports = Rent::default().minimum_balance(data.len());
+    let mut account = AccountSharedData::new(lamports, data.len(), owner);
+    account.set_data_from_slice(data);
+    account
+}
+
+/// A Mollusk that gives each instruction `budget` compute units.
+fn new_mollusk(budget: u64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "hook_compute_budget");
+    mollusk.add_program(
+        &TOKEN_2022_PROGRAM_ID,
+        "spl_token_2022",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk.add_program(
+        &HOOK_PROGRAM_ID,
+        "compute_hook",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk.compute_budget.compute_unit_limit = budget;
+    mollusk
+}
+
+struct Setup {
+    hooked: bool,
+    creator: Pubkey,
+    splitter: Pubkey,
+    splitter_bump: u8,
+    mint: Pubkey,
+    vault: Pubkey,
+    /// Recipient token accounts, in the splitter's order.
+    recipients: Vec<Pubkey>,
+    /// Who owns each recipient token account.
+    owners: Vec<Pubkey>,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A splitter holding eight shares, none paid, for a plain mint or a
+    /// hooked one.
+    fn new(hooked: bool) -> Self {
+        let creator = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (splitter, splitter_bump) = Pubkey::find_program_address(
+            &[b"splitter", creator.as_ref(), mint.as_ref()],
+            &PROGRAM_ID,
+        );
+        let vault = Pubkey::new_unique();
+        let recipients: Vec<Pubkey> = (0..RECIPIENTS).map(|_| Pubkey::new_unique()).collect();
+        let owners: Vec<Pubkey> = (0..RECIPIENTS).map(|_| Pubkey::new_unique()).collect();
+
+        let token_account = |owner: &Pubkey, amount: u64| {
+            owned_account(
+                &serialize_token_account(&mint, owner, amount, hooked),
+                &TOKEN_2022_PROGRAM_ID,
+            )
+        };
+
+        let mut ledger = vec![
+            (
+                splitter,
+                owned_account(
+                    &serialize_splitter(&creator, &mint, &recipients, 0, splitter_bump),
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                mint,
+                owned_account(&serialize_mint(hooked), &TOKEN_2022_PROGRAM_ID),
+            ),
+            (vault, token_account(&splitter, RECIPIENTS as u64 * SHARE)),
+            (TOKEN_2022_PROGRAM_ID, executable_account(&solana_sdk::bpf_loader_upgradeable::id())),
+            (HOOK_PROGRAM_ID, executable_account(&solana_sdk::bpf_loader_upgradeable::id())),
+        ];
+        for (recipient, owner) in recipients.iter().zip(&owners) {
+            ledger.push((*recipient, token_account(owner, 0)));
+        }
+
+        Self {
+            hooked,
+            creator,
+            splitter,
+            splitter_bump,
+            mint,
+            vault,
+            recipients,
+            owners,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// The accounts Token-2022 needs to call the mint's hook: the hook
+    /// program, for a hooked mint, and nothing otherwise.
+    fn hook_metas(&self) -> Vec<AccountMeta> {
+        if self.hooked {
+            vec![AccountMeta::new_readonly(HOOK_PROGRAM_ID, false)]
+        } else {
+            vec![]
+        }
+    }
+
+    /// distribute_vulnerable: every recipient, then the hook's accounts.
+    fn distribute_all(&self) -> Instruction {
+        let mut metas = vec![
+            AccountMeta::new(self.splitter, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.vault, false),
+            AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+        ];
+        metas.extend(
+            self.recipients
+                .iter()
+                .map(|recipient| AccountMeta::new(*recipient, false)),
+        );
+        metas.extend(self.hook_metas());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("distribute_vulnerable"),
+            metas,
+        )
+    }
+
+    /// distribute_secure for the recipient at `index`.
+    fn distribute_one(&self, index: u8) -> Instruction {
+        let mut data = ix_discriminator("distribute_secure").to_vec();
+        data.push(index);
+        let mut metas = vec![
+            AccountMeta::new(self.splitter, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.vault, false),
+            AccountMeta::new(self.recipients[index as usize], false),
+            AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+        ];
+        metas.extend(self.hook_metas());
+        Instruction::new_with_bytes(PROGRAM_ID, &data, metas)
+    }
+
+    /// A recipient's token account holding `amount`, as Token-2022 leaves
+    /// it.
+    fn recipient_data(&self, index: usize, amount: u64) -> Vec<u8> {
+        serialize_token_account(&self.mint, &self.owners[index], amount, self.hooked)
+    }
+
+    /// The splitter with `paid` as its paid bitmap.
+    fn splitter_data(&self, paid: u8) -> Vec<u8> {
+        serialize_splitter(
+            &self.creator,
+            &self.mint,
+            &self.recipients,
+            paid,
+            self.splitter_bump,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_hooked_mint_distribution_runs_out_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The keeper calls distribute_vulnerable with 200,000 CU
+    //   1. for the plain mint's splitter
+    //   2. for the hooked mint's splitter
+    //
+    // Expected: step 1 SUCCEEDS and pays all eight recipients. Step 2 FAILS
+    //           with ComputationalBudgetExceeded: eight hook calls don't fit.
+    //           It fails the same way every time, so the hooked mint's
+    //           recipients are never paid.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(KEEPER_BUDGET);
+
+    let mut plain = Setup::new(false);
+    let ix = plain.distribute_all();
+    let (vault, last) = (plain.vault, plain.recipients[RECIPIENTS - 1]);
+    let empty = serialize_token_account(&plain.mint, &plain.splitter, 0, false);
+    let paid = plain.recipient_data(RECIPIENTS - 1, SHARE);
+    plain.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).data(&empty).build(),
+            Check::account(&last).data(&paid).build(),
+        ],
+    );
+
+    let mut hooked = Setup::new(true);
+    let ix = hooked.distribute_all();
+    hooked.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::instruction_err(
+            InstructionError::ComputationalBudgetExceeded,
+        )],
+    );
+}
+
+#[test]
+fn secure_hooked_mint_pays_one_share_per_instruction() {
+    // -----------------------------------------------------------------------
+    // SECURE: The keeper calls distribute_secure(0) through
+    //         distribute_secure(7) for the hooked mint's splitter, each in
+    //         its own instruction with 200,000 CU
+    //
+    // Expected: all SUCCEED. Each pays one recipient its 100 tokens through
+    //           the hook and sets its bit of `paid`. In the end the vault
+    //           is empty.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(KEEPER_BUDGET);
+    let mut s = Setup::new(true);
+
+    let (splitter, vault) = (s.splitter, s.vault);
+    for index in 0..RECIPIENTS {
+        let ix = s.distribute_one(index as u8);
+        let recipient = s.recipients[index];
+        let received = s.recipient_data(index, SHARE);
+        let paid = s.splitter_data(u8::MAX >> (RECIPIENTS - 1 - index));
+        let left = (RECIPIENTS - 1 - index) as u64 * SHARE;
+        let remaining = serialize_token_account(&s.mint, &splitter, left, true);
+        s.ledger.step(
+            &mollusk,
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&recipient).data(&received).build(),
+                Check::account(&splitter).data(&paid).build(),
+                Check::account(&vault).data(&remaining).build(),
+            ],
+        );
+    }
+}
+
+#[test]
+fn secure_share_paid_once() {
+    // -----------------------------------------------------------------------
+    // SECURE: distribute_secure(3) for the plain mint's splitter, twice
+    //
+    // Expected: the first SUCCEEDS and sets bit 3 of `paid`. The second
+    //           FAILS with AlreadyPaid (6004).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(KEEPER_BUDGET);
+    let mut s = Setup::new(false);
+    let splitter = s.splitter;
+    let ix = s.distribute_one(3);
+
+    let paid = s.splitter_data(1 << 3);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&splitter).data(&paid).build(),
+        ],
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(ALREADY_PAID))],
+    );
+}
+
+#[test]
+fn benchmark_cu_per_mint_type() {
+    // -----------------------------------------------------------------------
+    // BENCHMARK: With the most compute a transaction can request, measure
+    //   - distribute_secure(0): one share
+    //   - distribute_vulnerable: all eight
+    //   for the plain mint and the hooked one
+    //
+    // Expected: all SUCCEED. Eight shares of the plain mint fit in the
+    //           keeper's 200,000 CU; eight of the hooked mint don't. One
+    //           share of either does. A benchmark that only ran the plain
+    //           mint would never have shown the difference.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(MAX_BUDGET);
+
+    let cost = |hooked: bool| {
+        let mut s = Setup::new(hooked);
+        let ix = s.distribute_one(0);
+        let one = s
+            .ledger
+            .step(&mollusk, &ix, &[Check::success()])
+            .compute_units_consumed;
+
+        let mut s = Setup::new(hooked);
+        let ix = s.distribute_all();
+        let all = s
+            .ledger
+            .step(&mollusk, &ix, &[Check::success()])
+            .compute_units_consumed;
+        (one, all)
+    };
+    let (plain_one, plain_all) = cost(false);
+    let (hooked_one, hooked_all) = cost(true);
+
+    assert!(
+        plain_all <= KEEPER_BUDGET,
+        "eight plain shares cost {plain_all} CU, over the keeper's {KEEPER_BUDGET}"
+    );
+    assert!(
+        hooked_all > KEEPER_BUDGET,
+        "eight hooked shares cost {hooked_all} CU, within the keeper's {KEEPER_BUDGET}"
+    );
+    assert!(
+        hooked_one <= KEEPER_BUDGET,
+        "one hooked share costs {hooked_one} CU, over the keeper's {KEEPER_BUDGET}"
+    );
+    assert!(
+        hooked_one > plain_one,
+        "one hooked share costs {hooked_one} CU, no more than a plain one's {plain_one}"
+    );
+}
@@ -0,0 +1,364 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("J7tnV8qYmvUarxTn4Ra9JDMogPqwmudAV8ZeuDiEVnuX");
+
+/// Debt may be at most 80% of collateral.
+pub const MAX_LTV_BPS: u128 = 8_000;
+
+/// # Position List Hiding
+///
+/// ## The Vulnerability
+/// A lending protocol keeps each borrow in its own `Position` account, so
+/// a user can hold any number of them. Health checks can't name an unknown
+/// number of accounts in the accounts struct, so the client passes the
+/// user's positions in `remaining_accounts` and the program sums their
+/// debt. The vulnerable withdraw checks that every account it is given is
+/// a genuine position of this user. It never checks that it was given all
+/// of them.
+///
+/// ## Why It Matters
+/// A borrower leaves out the positions that carry their debt, and the
+/// health check sees less debt than they owe. They withdraw the collateral
+/// backing it and walk away with both, leaving the protocol the bad debt.
+/// Liquidations, borrow limits, and rewards that sum client-supplied
+/// positions can all be gamed by omission the same way.
+///
+/// ## The Fix
+/// The user account counts the positions opened against it, and each
+/// position records its index. `withdraw_secure` requires exactly that many
+/// positions, in index order, each linked to this user, so the list it
+/// sums is the full set.
+#[program]
+pub mod position_list_hiding {
+    use super::*;
+
+    /// Open the pool borrows are paid from. Its lamports above rent are
+    /// the liquidity.
+    pub fn create_pool(ctx: Context<CreatePool>) -> Result<()> {
+        ctx.accounts.pool.bump = ctx.bumps.pool;
+        demo_log!(Mode::Setup, "create_pool", actor = ctx.accounts.payer.key());
+        Ok(())
+    }
+
+    pub fn create_user(ctx: Context<CreateUser>) -> Result<()> {
+        let user = &mut ctx.accounts.user;
+        user.owner = ctx.accounts.owner.key();
+        user.collateral = 0;
+        user.position_count = 0;
+        user.bump = ctx.bumps.user;
+        demo_log!(Mode::Setup, "create_user", actor = user.owner);
+        Ok(())
+    }
+
+    /// Add `amount` lamports of collateral, held in the user account.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let user = &mut ctx.accounts.user;
+        user.collateral = user
+            .collateral
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(Mode::Setup, "deposit", actor = user.owner, amount = amount);
+        Ok(())
+    }
+
+    /// Borrow `amount` from the pool in a new position. The user's
+    /// existing positions follow the accounts, all of them, in index order.
+    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let user = &ctx.accounts.user;
+        let debt = full_debt(ctx.remaining_accounts, user)?
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        check_health(user.collateral, debt)?;
+
+        move_lamports(
+            &ctx.accounts.pool.to_account_info(),
+            &ctx.accounts.owner.to_account_info(),
+            amount,
+        )?;
+
+        let user = &mut ctx.accounts.user;
+        let position = &mut ctx.accounts.position;
+        position.user = user.key();
+        position.index = user.position_count;
+        position.debt = amount;
+        position.bump = ctx.bumps.position;
+        user.position_count = user
+            .position_count
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "borrow",
+            actor = user.owner,
+            amount = amount,
+            index = position.index
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Health summed over whichever positions the client passed
+    // ============================================================================
+    // ISSUE: Every position passed is checked: owned by this program, a
+    //        Position, this user's. But nothing says the list is complete,
+    //        and each position left out is debt the check doesn't see.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory deposits 10 SOL and borrows 3 SOL in position 0 and
+    //      4 SOL in position 1: 7 SOL of debt, within the 8 SOL limit
+    //   2. She calls withdraw_vulnerable(6 SOL), passing only position 0
+    //   3. The check sees 3 SOL of debt against 4 SOL of collateral: fine
+    //   4. She leaves with 6 SOL of collateral and 7 SOL of loans. Position
+    //      1's 4 SOL is backed by nothing
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let user = &ctx.accounts.user;
+        let collateral = user
+            .collateral
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientFunds)?;
+
+        // VULNERABLE: sums what was passed, not what the user owes
+        let mut debt: u64 = 0;
+        for info in ctx.remaining_accounts {
+            let position = load_position(info, user)?;
+            debt = debt.checked_add(position.debt).ok_or(ErrorCode::Overflow)?;
+        }
+        check_health(collateral, debt)?;
+
+        pay_out(ctx.accounts, collateral, amount, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Health summed over every position the user has
+    // ============================================================================
+    // FIX: The user account counts its positions, and each position stores
+    //      its index. The list must be exactly `position_count` long and in
+    //      index order, with every entry linked to this user, so nothing
+    //      can be left out, repeated to fill the gap, or borrowed from
+    //      someone else.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let user = &ctx.accounts.user;
+        let collateral = user
+            .collateral
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientFunds)?;
+
+        // SECURE: the full set, checked against the user's own count
+        let debt = full_debt(ctx.remaining_accounts, user)?;
+        check_health(collateral, debt)?;
+
+        pay_out(ctx.accounts, collateral, amount, Mode::Secure)
+    }
+}
+
+/// Read a position of `user` from a raw account: owned by this program,
+/// a `Position`, and linked to this user.
+fn load_position(info: &AccountInfo, user: &Account<User>) -> Result<Position> {
+    if info.owner != &crate::ID {
+        return Err(anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    let data = info.try_borrow_data()?;
+    let position = Position::try_deserialize(&mut &data[..])?;
+    require_keys_eq!(position.user, user.key(), ErrorCode::WrongPosition);
+    Ok(position)
+}
+
+/// Total debt across all of `user`'s positions, which `positions` must be:
+/// `position_count` of them, position `i` at index `i`.
+fn full_debt(positions: &[AccountInfo], user: &Account<User>) -> Result<u64> {
+    // SECURE: a list shorter than the count is hiding something
+    require!(
+        positions.len() == user.position_count as usize,
+        ErrorCode::PositionCountMismatch
+    );
+    let mut debt: u64 = 0;
+    for (index, info) in positions.iter().enumerate() {
+        let position = load_position(info, user)?;
+        // SECURE: one slot per index, so no entry stands in for another
+        require!(position.index as usize == index, ErrorCode::WrongPosition);
+        debt = debt.checked_add(position.debt).ok_or(ErrorCode::Overflow)?;
+    }
+    Ok(debt)
+}
+
+/// `debt` must be at most MAX_LTV_BPS of `collateral`.
+fn check_health(collateral: u64, debt: u64) -> Result<()> {
+    require!(
+        debt as u128 * 10_000 <= collateral as u128 * MAX_LTV_BPS,
+        ErrorCode::Undercollateralized
+    );
+    Ok(())
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// Pay `amount` of collateral to the owner, leaving `collateral`.
+fn pay_out(accounts: &mut Withdraw, collateral: u64, amount: u64, mode: Mode) -> Result<()> {
+    move_lamports(
+        &accounts.user.to_account_info(),
+        &accounts.owner.to_account_info(),
+        amount,
+    )?;
+    accounts.user.collateral = collateral;
+    demo_log!(
+        mode,
+        "withdraw",
+        actor = accounts.owner.key(),
+        amount = amount,
+        collateral = collateral
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateUser<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + User::INIT_SPACE,
+        seeds = [b"user", owner.key().as_ref()],
+        bump
+    )]
+    pub user: Account<'info, User>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", owner.key().as_ref()],
+        bump = user.bump,
+        has_one = owner
+    )]
+    pub user: Account<'info, User>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The user's existing positions follow these accounts.
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"user", owner.key().as_ref()],
+        bump = user.bump,
+        has_one = owner
+    )]
+    pub user: Account<'info, User>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", user.key().as_ref(), &[user.position_count]],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The user's positions follow these accounts.
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", owner.key().as_ref()],
+        bump = user.bump,
+        has_one = owner
+    )]
+    pub user: Account<'info, User>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub bump: u8, //  1 byte
+}
+
+/// A borrower. `collateral` lamports are held in this account, and
+/// positions 0 to `position_count - 1` have been opened against it.
+#[account]
+#[derive(InitSpace)]
+pub struct User {
+    pub owner: Pubkey,      // 32 bytes
+    pub collateral: u64,    //  8 bytes
+    pub position_count: u8, //  1 byte
+    pub bump: u8,           //  1 byte
+}
+
+/// One borrow, at PDA [b"position", user, index].
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub user: Pubkey, // 32 bytes
+    pub index: u8,    //  1 byte
+    pub debt: u64,    //  8 bytes
+    pub bump: u8,     //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Not every one of the user's positions was passed")]
+    PositionCountMismatch,
+    #[msg("The position isn't the user's, or is out of order")]
+    WrongPosition,
+    #[msg("Debt would exceed the collateral limit")]
+    Undercollateralized,
+    #[msg("Not enough lamports")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,342 @@
+// Mollusk tests for Pattern 223
+/// # Pattern 223: Position List Hiding — Mollusk Exploit Tests
+///
+/// Mallory has deposited 10 SOL of collateral and borrowed 3 SOL in
+/// position 0 and 4 SOL in position 1. Debt may be at most 80% of
+/// collateral, so her 7 SOL is within her 8 SOL limit.
+///
+/// - Test 1: EXPLOIT — withdraw_vulnerable with only position 0 passed
+///   sees 3 SOL of debt and lets her take 6 SOL out.
+/// - Test 2: SECURE — withdraw_secure refuses a list that leaves a
+///   position out, or repeats one to make up the count.
+/// - Test 3: SECURE — with the full set, the 6 SOL withdrawal fails the
+///   health check and a 1 SOL withdrawal passes.
+/// - Test 4: SECURE — a new borrow raises the count, so the old two-entry
+///   list no longer passes.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("J7tnV8qYmvUarxTn4Ra9JDMogPqwmudAV8ZeuDiEVnuX");
+
+const WALLET: u64 = 20_000_000_000;
+const SOL: u64 = 1_000_000_000;
+const POOL_LIQUIDITY: u64 = 100 * SOL;
+
+/// 8 discriminator + 32 owner + 8 collateral + 1 count + 1 bump.
+const USER_LEN: usize = 50;
+/// 8 discriminator + 32 user + 1 index + 8 debt + 1 bump.
+const POSITION_LEN: usize = 50;
+
+const COLLATERAL: u64 = 10 * SOL;
+const DEBTS: [u64; 2] = [3 * SOL, 4 * SOL];
+
+// Error codes
+const POSITION_COUNT_MISMATCH: u32 = 6000;
+const WRONG_POSITION: u32 = 6001;
+const UNDERCOLLATERALIZED: u32 = 6002;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// [8 disc][1 bump]
+fn serialize_pool(bump: u8) -> Vec<u8> {
+    let mut data = account_discriminator("Pool").to_vec();
+    data.push(bump);
+    data
+}
+
+/// [8 disc][32 owner][8 collateral][1 position_count][1 bump]
+fn serialize_user(owner: &Pubkey, collateral: u64, position_count: u8, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(USER_LEN);
+    data.extend_from_slice(&account_discriminator("User"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&collateral.to_le_bytes());
+    data.push(position_count);
+    data.push(bump);
+    data
+}
+
+/// [8 disc][32 user][1 index][8 debt][1 bump]
+fn serialize_position(user: &Pubkey, index: u8, debt: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(POSITION_LEN);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(user.as_ref());
+    data.push(index);
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// A program-owned account holding `data`, rent-exempt plus `extra`.
+fn owned_account(data: &[u8], extra: u64) -> AccountSharedData {
+    let lamports = Rent::default().minimum_balance(data.len()) + extra;
+    let mut account = AccountSharedData::new(lamports, data.len(), &PROGRAM_ID);
+    account.set_data_from_slice(data);
+    account
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn position_address(user: &Pubkey, index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"position", user.as_ref(), &[index]], &PROGRAM_ID)
+}
+
+struct Setup {
+    pool: Pubkey,
+    user: Pubkey,
+    user_bump: u8,
+    /// Mallory's positions, by index, including any not yet opened.
+    positions: Vec<(Pubkey, u8)>,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// The pool, and Mallory's user account with positions 0 and 1.
+    /// Position 2's address is in the ledger, with no account yet.
+    fn new() -> Self {
+        let mallory = Pubkey::new_unique();
+        let (pool, pool_bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+        let (user, user_bump) =
+            Pubkey::find_program_address(&[b"user", mallory.as_ref()], &PROGRAM_ID);
+        let positions: Vec<(Pubkey, u8)> = (0..3).map(|i| position_address(&user, i)).collect();
+
+        let mut ledger = vec![
+            (
+                pool,
+                owned_account(&serialize_pool(pool_bump), POOL_LIQUIDITY),
+            ),
+            (
+                user,
+                owned_account(
+                    &serialize_user(&mallory, COLLATERAL, 2, user_bump),
+                    COLLATERAL,
+                ),
+            ),
+            (mallory, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+        for (index, (position, bump)) in positions.iter().enumerate() {
+            let account = match DEBTS.get(index) {
+                Some(&debt) => {
+                    owned_account(&serialize_position(&user, index as u8, debt, *bump), 0)
+                }
+                None => AccountSharedData::default(),
+            };
+            ledger.push((*position, account));
+        }
+
+        Self {
+            pool,
+            user,
+            user_bump,
+            positions,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// The position accounts at `indexes`, in that order, as remaining
+    /// accounts.
+    fn position_metas(&self, indexes: &[usize]) -> Vec<AccountMeta> {
+        indexes
+            .iter()
+            .map(|&i| AccountMeta::new_readonly(self.positions[i].0, false))
+            .collect()
+    }
+
+    /// withdraw_vulnerable or withdraw_secure, passing the positions at
+    /// `indexes`.
+    fn withdraw(&self, name: &str, amount: u64, indexes: &[usize]) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        let mut accounts = vec![
+            AccountMeta::new(self.user, false),
+            AccountMeta::new(self.mallory, true),
+        ];
+        accounts.extend(self.position_metas(indexes));
+        Instruction::new_with_bytes(PROGRAM_ID, &data, accounts)
+    }
+
+    /// Borrow `amount` into position `new_index`, passing the positions at
+    /// `indexes`.
+    fn borrow(&self, amount: u64, new_index: usize, indexes: &[usize]) -> Instruction {
+        let mut data = ix_discriminator("borrow").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        let mut accounts = vec![
+            AccountMeta::new(self.pool, false),
+            AccountMeta::new(self.user, false),
+            AccountMeta::new(self.positions[new_index].0, false),
+            AccountMeta::new(self.mallory, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ];
+        accounts.extend(self.position_metas(indexes));
+        Instruction::new_with_bytes(PROGRAM_ID, &data, accounts)
+    }
+
+    fn user_data(&self, collateral: u64, position_count: u8) -> Vec<u8> {
+        serialize_user(&self.mallory, collateral, position_count, self.user_bump)
+    }
+}
+
+fn mollusk() -> Mollusk {
+    Mollusk::new(&PROGRAM_ID, "position_list_hiding")
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_hidden_position_withdraw_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory calls withdraw_vulnerable(6 SOL), passing only
+    //          position 0
+    //
+    // Expected: SUCCEEDS. The check sees 3 SOL of debt against the 4 SOL
+    //           left, under the 3.2 SOL limit. Mallory holds 6 SOL of
+    //           collateral and 7 SOL of loans; position 1 is unbacked.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (user, mallory) = (s.user, s.mallory);
+
+    let ix = s.withdraw("withdraw_vulnerable", 6 * SOL, &[0]);
+    let drained = s.user_data(4 * SOL, 2);
+    s.ledger.step(
+        &mollusk(),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&user).data(&drained).build(),
+            Check::account(&mallory).lamports(WALLET + 6 * SOL).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_partial_list_rejected() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory calls withdraw_secure(6 SOL), passing
+    //   1. only position 0
+    //   2. position 0 twice, to make up the count
+    //
+    // Expected: step 1 FAILS with PositionCountMismatch (6000). Step 2
+    //           FAILS with WrongPosition (6001): the second entry isn't
+    //           position 1.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+
+    let ix = s.withdraw("withdraw_secure", 6 * SOL, &[0]);
+    s.ledger.step(
+        &mollusk(),
+        &ix,
+        &[Check::err(ProgramError::Custom(POSITION_COUNT_MISMATCH))],
+    );
+
+    let ix = s.withdraw("withdraw_secure", 6 * SOL, &[0, 0]);
+    s.ledger.step(
+        &mollusk(),
+        &ix,
+        &[Check::err(ProgramError::Custom(WRONG_POSITION))],
+    );
+}
+
+#[test]
+fn secure_full_set_enforces_health() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory calls withdraw_secure with both positions, for
+    //   1. 6 SOL
+    //   2. 1 SOL
+    //
+    // Expected: step 1 FAILS with Undercollateralized (6002): 7 SOL of debt
+    //           against 4 SOL. Step 2 SUCCEEDS: 7 SOL against 9 SOL is
+    //           within the 7.2 SOL limit.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (user, mallory) = (s.user, s.mallory);
+
+    let ix = s.withdraw("withdraw_secure", 6 * SOL, &[0, 1]);
+    s.ledger.step(
+        &mollusk(),
+        &ix,
+        &[Check::err(ProgramError::Custom(UNDERCOLLATERALIZED))],
+    );
+
+    let ix = s.withdraw("withdraw_secure", SOL, &[0, 1]);
+    let withdrawn = s.user_data(9 * SOL, 2);
+    s.ledger.step(
+        &mollusk(),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&user).data(&withdrawn).build(),
+            Check::account(&mallory).lamports(WALLET + SOL).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_borrow_grows_required_set() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory
+    //   1. borrows 0.2 SOL into position 2, passing positions 0 and 1
+    //   2. calls withdraw_secure(1 SOL), passing positions 0 and 1
+    //   3. calls withdraw_secure(1 SOL), passing positions 0, 1, and 2
+    //
+    // Expected: step 1 SUCCEEDS and the count becomes 3. Step 2 FAILS with
+    //           PositionCountMismatch (6000): the new position can't be
+    //           left out. Step 3 SUCCEEDS: 7.2 SOL of debt is exactly the
+    //           limit on 9 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (user, mallory) = (s.user, s.mallory);
+    let (position, position_bump) = s.positions[2];
+    let borrowed = SOL / 5;
+
+    let ix = s.borrow(borrowed, 2, &[0, 1]);
+    let opened = serialize_position(&user, 2, borrowed, position_bump);
+    let registered = s.user_data(COLLATERAL, 3);
+    s.ledger.step(
+        &mollusk(),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position).data(&opened).build(),
+            Check::account(&user).data(&registered).build(),
+        ],
+    );
+
+    let ix = s.withdraw("withdraw_secure", SOL, &[0, 1]);
+    s.ledger.step(
+        &mollusk(),
+        &ix,
+        &[Check::err(ProgramError::Custom(POSITION_COUNT_MISMATCH))],
+    );
+
+    let ix = s.withdraw("withdraw_secure", SOL, &[0, 1, 2]);
+    let withdrawn = s.user_data(9 * SOL, 3);
+    s.ledger.step(
+        &mollusk(),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&user).data(&withdrawn).build(),
+            Check::account(&mallory)
+                .lamports(WALLET + borrowed + SOL - Rent::default().minimum_balance(POSITION_LEN))
+                .build(),
+        ],
+    );
+}
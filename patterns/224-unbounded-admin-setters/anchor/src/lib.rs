@@ -0,0 +1,383 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+use timelock::{cancel, ready_at, take_ready, Queued, TimelockError};
+
+declare_id!("8c9dDixd3qZHqs27kGRAhqJ4At2em8QjegJJ2yNALSdR");
+
+/// Slots after `ready_at` that a queued change may still be applied in,
+/// about a day. Later it has to be cancelled and queued again.
+pub const GRACE_SLOTS: u64 = 216_000;
+
+/// # Unbounded Admin Setters
+///
+/// ## The Vulnerability
+/// A lending market's admin tunes it with setters: the liquidation bonus
+/// paid to liquidators, the oracle prices come from, and the cooldown
+/// between a user's actions. The vulnerable setters check the admin's
+/// signature and nothing else. Any value is accepted, and it applies in
+/// the same transaction: a 10,000% liquidation bonus, an oracle the caller
+/// owns, a cooldown of zero.
+///
+/// ## Why It Matters
+/// The admin key is then the whole protocol. Whoever holds it, an attacker
+/// with a stolen key or an operator with a typo, can set a parameter and
+/// exploit it in one transaction: point the oracle at their own account
+/// and borrow everything, or raise the bonus and liquidate every position
+/// for its whole collateral. Users see the change only after it has been
+/// used against them.
+///
+/// ## The Fix
+/// Bound every parameter. The limits are set once, in `initialize`, in an
+/// account no instruction can change, so a compromised admin can't widen
+/// them first. The cooldown is checked against its range and applies at
+/// once; the bonus and the oracle, which can move funds, are queued and
+/// apply only after the timelock from `common/timelock`, in public view,
+/// and before its grace period runs out.
+#[program]
+pub mod unbounded_admin_setters {
+    use super::*;
+
+    /// Create the immutable limits and the config they bound. `oracle` must
+    /// already satisfy the limits, as must the other settings.
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        bounds: Bounds,
+        liquidation_bonus_bps: u32,
+        cooldown_slots: u64,
+    ) -> Result<()> {
+        check_oracle(&bounds, &ctx.accounts.oracle)?;
+        check_bonus(&bounds, liquidation_bonus_bps)?;
+        check_cooldown(&bounds, cooldown_slots)?;
+
+        let limits = &mut ctx.accounts.limits;
+        limits.bounds = bounds;
+        limits.bump = ctx.bumps.limits;
+
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.oracle = ctx.accounts.oracle.key();
+        config.liquidation_bonus_bps = liquidation_bonus_bps;
+        config.cooldown_slots = cooldown_slots;
+        config.pending = None;
+        config.bump = ctx.bumps.config;
+        demo_log!(
+            Mode::Setup,
+            "initialize",
+            actor = config.admin,
+            oracle = config.oracle,
+            bonus_bps = liquidation_bonus_bps,
+            cooldown = cooldown_slots
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Setters that take any value, at once
+    // ============================================================================
+    // ISSUE: Each setter checks the admin signed and writes the value. No
+    //        range, no trusted-oracle check, no delay: the admin key alone
+    //        decides every parameter, effective immediately.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory steals the admin key
+    //   2. She calls set_oracle_vulnerable with an account she controls,
+    //      set_liquidation_bonus_vulnerable(1_000_000), 10,000%, and
+    //      set_cooldown_vulnerable(0)
+    //   3. Each applies in the same transaction as her borrows and
+    //      liquidations against it, before any user can react
+    // ============================================================================
+    pub fn set_liquidation_bonus_vulnerable(ctx: Context<SetParam>, bps: u32) -> Result<()> {
+        // VULNERABLE: any bonus, at once
+        ctx.accounts.config.liquidation_bonus_bps = bps;
+        demo_log!(
+            Mode::Vulnerable,
+            "set_liquidation_bonus",
+            actor = ctx.accounts.admin.key(),
+            bps = bps
+        );
+        Ok(())
+    }
+
+    pub fn set_oracle_vulnerable(ctx: Context<SetParam>, oracle: Pubkey) -> Result<()> {
+        // VULNERABLE: any key, at once
+        ctx.accounts.config.oracle = oracle;
+        demo_log!(
+            Mode::Vulnerable,
+            "set_oracle",
+            actor = ctx.accounts.admin.key(),
+            oracle = oracle
+        );
+        Ok(())
+    }
+
+    pub fn set_cooldown_vulnerable(ctx: Context<SetParam>, slots: u64) -> Result<()> {
+        // VULNERABLE: zero disables the cooldown entirely
+        ctx.accounts.config.cooldown_slots = slots;
+        demo_log!(
+            Mode::Vulnerable,
+            "set_cooldown",
+            actor = ctx.accounts.admin.key(),
+            slots = slots
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Setters bounded by the limits, sensitive ones timelocked
+    // ============================================================================
+    // FIX: Every value is checked against the immutable limits: the bonus
+    //      against its maximum, the cooldown against its range, the oracle
+    //      against the oracle program. The cooldown applies at once. The
+    //      bonus and oracle are queued and applied by execute_change once
+    //      the timelock has passed, so a stolen key buys a public, bounded
+    //      change a day later, not an instant exploit.
+    // ============================================================================
+    pub fn set_cooldown_secure(ctx: Context<SetParamSecure>, slots: u64) -> Result<()> {
+        // SECURE: within the range no admin can change
+        check_cooldown(&ctx.accounts.limits.bounds, slots)?;
+        ctx.accounts.config.cooldown_slots = slots;
+        demo_log!(
+            Mode::Secure,
+            "set_cooldown",
+            actor = ctx.accounts.admin.key(),
+            slots = slots
+        );
+        Ok(())
+    }
+
+    pub fn queue_liquidation_bonus_secure(ctx: Context<SetParamSecure>, bps: u32) -> Result<()> {
+        let accounts = ctx.accounts;
+        check_bonus(&accounts.limits.bounds, bps)?;
+        // SECURE: takes effect only after the timelock
+        queue(
+            &accounts.limits,
+            &mut accounts.config,
+            &accounts.admin,
+            Change::LiquidationBonus(bps),
+        )
+    }
+
+    pub fn queue_oracle_secure(ctx: Context<QueueOracle>) -> Result<()> {
+        let accounts = ctx.accounts;
+        check_oracle(&accounts.limits.bounds, &accounts.oracle)?;
+        // SECURE: takes effect only after the timelock
+        queue(
+            &accounts.limits,
+            &mut accounts.config,
+            &accounts.admin,
+            Change::Oracle(accounts.oracle.key()),
+        )
+    }
+
+    /// Anyone: apply the queued change once its timelock has passed, and
+    /// before its grace period has.
+    pub fn execute_change(ctx: Context<ExecuteChange>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let pending = take_ready(&mut config.pending, Clock::get()?.slot, GRACE_SLOTS)
+            .map_err(timelock_error)?;
+
+        match pending.change {
+            Change::LiquidationBonus(bps) => config.liquidation_bonus_bps = bps,
+            Change::Oracle(oracle) => config.oracle = oracle,
+        }
+        demo_log!(
+            Mode::Secure,
+            "execute_change",
+            actor = ctx.accounts.caller.key(),
+            ready_at = pending.ready_at
+        );
+        Ok(())
+    }
+
+    /// Admin: drop the queued change without applying it, such as one
+    /// whose grace period has passed.
+    pub fn cancel_change(ctx: Context<SetParam>) -> Result<()> {
+        let pending = cancel(&mut ctx.accounts.config.pending).map_err(timelock_error)?;
+        demo_log!(
+            Mode::Secure,
+            "cancel_change",
+            actor = ctx.accounts.admin.key(),
+            ready_at = pending.ready_at
+        );
+        Ok(())
+    }
+}
+
+/// Record `change` as the pending change, ready after the timelock. A
+/// change queued over another replaces it and restarts the delay.
+fn queue(limits: &Limits, config: &mut Config, admin: &Signer, change: Change) -> Result<()> {
+    let delay = limits.bounds.timelock_slots;
+    let ready_at = ready_at(Clock::get()?.slot, delay).map_err(timelock_error)?;
+    config.pending = Some(Pending { change, ready_at });
+    demo_log!(
+        Mode::Secure,
+        "queue_change",
+        actor = admin.key(),
+        ready_at = ready_at
+    );
+    Ok(())
+}
+
+fn check_bonus(bounds: &Bounds, bps: u32) -> Result<()> {
+    require!(
+        bps <= bounds.max_liquidation_bonus_bps,
+        ErrorCode::BonusOutOfBounds
+    );
+    Ok(())
+}
+
+fn check_cooldown(bounds: &Bounds, slots: u64) -> Result<()> {
+    require!(
+        (bounds.min_cooldown_slots..=bounds.max_cooldown_slots).contains(&slots),
+        ErrorCode::CooldownOutOfBounds
+    );
+    Ok(())
+}
+
+/// The oracle must be an account of the oracle program the limits name.
+fn check_oracle(bounds: &Bounds, oracle: &AccountInfo) -> Result<()> {
+    require_keys_eq!(
+        *oracle.owner,
+        bounds.oracle_program,
+        ErrorCode::UntrustedOracle
+    );
+    Ok(())
+}
+
+fn timelock_error(err: TimelockError) -> ErrorCode {
+    match err {
+        TimelockError::TooEarly => ErrorCode::ChangeNotReady,
+        TimelockError::Expired => ErrorCode::ChangeExpired,
+        TimelockError::NothingQueued => ErrorCode::NothingQueued,
+        TimelockError::Overflow => ErrorCode::Overflow,
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Limits::INIT_SPACE,
+        seeds = [b"limits"],
+        bump
+    )]
+    pub limits: Account<'info, Limits>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: owner checked against the limits' oracle program
+    pub oracle: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetParam<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetParamSecure<'info> {
+    #[account(seeds = [b"limits"], bump = limits.bump)]
+    pub limits: Account<'info, Limits>,
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueueOracle<'info> {
+    #[account(seeds = [b"limits"], bump = limits.bump)]
+    pub limits: Account<'info, Limits>,
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    /// CHECK: owner checked against the limits' oracle program
+    pub oracle: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteChange<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub caller: Signer<'info>,
+}
+
+/// What the admin may set, fixed at initialization.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct Bounds {
+    pub max_liquidation_bonus_bps: u32, //  4 bytes
+    pub min_cooldown_slots: u64,        //  8 bytes
+    pub max_cooldown_slots: u64,        //  8 bytes
+    pub oracle_program: Pubkey,         // 32 bytes
+    pub timelock_slots: u64,            //  8 bytes
+}
+
+/// The admin's bounds. No instruction writes this after `initialize`.
+#[account]
+#[derive(InitSpace)]
+pub struct Limits {
+    pub bounds: Bounds, // 60 bytes
+    pub bump: u8,       //  1 byte
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub enum Change {
+    LiquidationBonus(u32),
+    Oracle(Pubkey),
+}
+
+/// A queued change and the first slot it may be applied in.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct Pending {
+    pub change: Change, // 1 + 32 bytes
+    pub ready_at: u64,  //  8 bytes
+}
+
+impl Queued for Pending {
+    fn ready_at(&self) -> u64 {
+        self.ready_at
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,              // 32 bytes
+    pub oracle: Pubkey,             // 32 bytes
+    pub liquidation_bonus_bps: u32, //  4 bytes
+    pub cooldown_slots: u64,        //  8 bytes
+    pub pending: Option<Pending>,   // 1 + 41 bytes
+    pub bump: u8,                   //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The liquidation bonus is above the limit")]
+    BonusOutOfBounds,
+    #[msg("The cooldown is outside the limits")]
+    CooldownOutOfBounds,
+    #[msg("The oracle isn't an account of the trusted oracle program")]
+    UntrustedOracle,
+    #[msg("No change is queued")]
+    NothingQueued,
+    #[msg("The queued change's timelock hasn't passed")]
+    ChangeNotReady,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("The queued change's grace period has passed")]
+    ChangeExpired,
+}
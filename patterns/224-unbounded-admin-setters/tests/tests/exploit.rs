@@ -0,0 +1,567 @@
+// Mollusk tests for Pattern 224
+/// # Pattern 224: Unbounded Admin Setters — Mollusk Exploit Tests
+///
+/// The market is initialized with a 5% liquidation bonus, a 1,500-slot
+/// cooldown, and an oracle account of the trusted oracle program. Its
+/// limits allow a bonus of at most 20%, a cooldown of 150 to 216,000
+/// slots, and oracles of that program only, with a 216,000-slot (about a
+/// day) timelock. Whoever signs as admin may hold a stolen key.
+///
+/// - Test 1: EXPLOIT — the vulnerable setters accept a 10,000% bonus, an
+///   oracle account owned by the attacker's program, and a zero cooldown,
+///   each effective in the same slot.
+/// - Test 2: SECURE — the secure setters refuse the same three values, and
+///   accept a cooldown inside the range.
+/// - Test 3: SECURE — a bonus change within the limit is queued, can't be
+///   executed a slot early, and applies once the timelock has passed.
+/// - Test 4: SECURE — an oracle of the trusted program is queued, and
+///   anyone can apply it after the timelock.
+/// - Test 5: SECURE — a change left past its grace period can't be
+///   applied, and the admin cancels it.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("8c9dDixd3qZHqs27kGRAhqJ4At2em8QjegJJ2yNALSdR");
+
+const WALLET: u64 = 20_000_000_000;
+
+/// 8 discriminator + 60 bounds + 1 bump.
+const LIMITS_LEN: usize = 69;
+/// 8 discriminator + 32 admin + 32 oracle + 4 bonus + 8 cooldown
+/// + 42 pending + 1 bump.
+const CONFIG_LEN: usize = 127;
+
+// Bounds
+const MAX_BONUS_BPS: u32 = 2_000;
+const MIN_COOLDOWN: u64 = 150;
+const MAX_COOLDOWN: u64 = 216_000;
+const TIMELOCK_SLOTS: u64 = 216_000;
+/// The program's GRACE_SLOTS.
+const GRACE_SLOTS: u64 = 216_000;
+
+// Settings at initialization
+const BONUS_BPS: u32 = 500;
+const COOLDOWN: u64 = 1_500;
+
+/// 10,000%.
+const ABSURD_BONUS_BPS: u32 = 1_000_000;
+const QUEUED_AT: u64 = 1_000;
+
+// Error codes
+const BONUS_OUT_OF_BOUNDS: u32 = 6000;
+const COOLDOWN_OUT_OF_BOUNDS: u32 = 6001;
+const UNTRUSTED_ORACLE: u32 = 6002;
+const NOTHING_QUEUED: u32 = 6003;
+const CHANGE_NOT_READY: u32 = 6004;
+const CHANGE_EXPIRED: u32 = 6006;
+
+/// A queued change, as the program's `Change`.
+#[derive(Clone, Copy)]
+enum Change {
+    LiquidationBonus(u32),
+    Oracle(Pubkey),
+}
+
+/// The config's settings, as the tests expect to find them.
+#[derive(Clone, Copy)]
+struct Settings {
+    oracle: Pubkey,
+    bonus_bps: u32,
+    cooldown: u64,
+    pending: Option<(Change, u64)>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// [8 disc][4 max_bonus][8 min_cooldown][8 max_cooldown]
+/// [32 oracle_program][8 timelock][1 bump]
+fn serialize_limits(oracle_program: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(LIMITS_LEN);
+    data.extend_from_slice(&account_discriminator("Limits"));
+    data.extend_from_slice(&MAX_BONUS_BPS.to_le_bytes());
+    data.extend_from_slice(&MIN_COOLDOWN.to_le_bytes());
+    data.extend_from_slice(&MAX_COOLDOWN.to_le_bytes());
+    data.extend_from_slice(oracle_program.as_ref());
+    data.extend_from_slice(&TIMELOCK_SLOTS.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// [8 disc][32 admin][32 oracle][4 bonus][8 cooldown]
+/// [1 + (1 + 4 or 32) + 8 pending][1 bump], zero-padded to its allocated
+/// length: Borsh writes only the variant that is there.
+fn serialize_config(admin: &Pubkey, settings: &Settings, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(CONFIG_LEN);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(settings.oracle.as_ref());
+    data.extend_from_slice(&settings.bonus_bps.to_le_bytes());
+    data.extend_from_slice(&settings.cooldown.to_le_bytes());
+    match settings.pending {
+        None => data.push(0),
+        Some((change, ready_at)) => {
+            data.push(1);
+            match change {
+                Change::LiquidationBonus(bps) => {
+                    data.push(0);
+                    data.extend_from_slice(&bps.to_le_bytes());
+                }
+                Change::Oracle(oracle) => {
+                    data.push(1);
+                    data.extend_from_slice(oracle.as_ref());
+                }
+            }
+            data.extend_from_slice(&ready_at.to_le_bytes());
+        }
+    }
+    data.push(bump);
+    data.resize(CONFIG_LEN, 0);
+    data
+}
+
+fn owned_account(data: &[u8], owner: &Pubkey) -> AccountSharedData {
+    let lamports = Rent::default().minimum_balance(data.len());
+    let mut account = AccountSharedData::new(lamports, data.len(), owner);
+    account.set_data_from_slice(data);
+    account
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+/// A Mollusk whose clock reads `slot`.
+fn mollusk_at(slot: u64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "unbounded_admin_setters");
+    mollusk.sysvars.clock.slot = slot;
+    mollusk
+}
+
+struct Setup {
+    limits: Pubkey,
+    config: Pubkey,
+    config_bump: u8,
+    admin: Pubkey,
+    oracle: Pubkey,
+    /// Another account of the trusted oracle program.
+    new_oracle: Pubkey,
+    /// An account of the attacker's own program.
+    fake_oracle: Pubkey,
+    caller: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// The market as `initialize` leaves it.
+    fn new() -> Self {
+        let oracle_program = Pubkey::new_unique();
+        let attacker_program = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let oracle = Pubkey::new_unique();
+        let new_oracle = Pubkey::new_unique();
+        let fake_oracle = Pubkey::new_unique();
+        let caller = Pubkey::new_unique();
+        let (limits, limits_bump) = Pubkey::find_program_address(&[b"limits"], &PROGRAM_ID);
+        let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &PROGRAM_ID);
+
+        let initial = Settings {
+            oracle,
+            bonus_bps: BONUS_BPS,
+            cooldown: COOLDOWN,
+            pending: None,
+        };
+        let ledger = vec![
+            (
+                limits,
+                owned_account(&serialize_limits(&oracle_program, limits_bump), &PROGRAM_ID),
+            ),
+            (
+                config,
+                owned_account(
+                    &serialize_config(&admin, &initial, config_bump),
+                    &PROGRAM_ID,
+                ),
+            ),
+            (admin, system_account()),
+            (oracle, owned_account(&[0; 32], &oracle_program)),
+            (new_oracle, owned_account(&[0; 32], &oracle_program)),
+            (fake_oracle, owned_account(&[0; 32], &attacker_program)),
+            (caller, system_account()),
+        ];
+
+        Self {
+            limits,
+            config,
+            config_bump,
+            admin,
+            oracle,
+            new_oracle,
+            fake_oracle,
+            caller,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// The settings `initialize` left.
+    fn initial(&self) -> Settings {
+        Settings {
+            oracle: self.oracle,
+            bonus_bps: BONUS_BPS,
+            cooldown: COOLDOWN,
+            pending: None,
+        }
+    }
+
+    fn config_data(&self, settings: &Settings) -> Vec<u8> {
+        serialize_config(&self.admin, settings, self.config_bump)
+    }
+
+    /// A vulnerable setter, signed by the admin key.
+    fn set_vulnerable(&self, name: &str, arg: &[u8]) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(arg);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new_readonly(self.admin, true),
+            ],
+        )
+    }
+
+    /// set_cooldown_secure or queue_liquidation_bonus_secure, signed by the
+    /// admin key.
+    fn set_secure(&self, name: &str, arg: &[u8]) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(arg);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.limits, false),
+                AccountMeta::new(self.config, false),
+                AccountMeta::new_readonly(self.admin, true),
+            ],
+        )
+    }
+
+    fn queue_oracle_secure(&self, oracle: &Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("queue_oracle_secure"),
+            vec![
+                AccountMeta::new_readonly(self.limits, false),
+                AccountMeta::new(self.config, false),
+                AccountMeta::new_readonly(self.admin, true),
+                AccountMeta::new_readonly(*oracle, false),
+            ],
+        )
+    }
+
+    /// execute_change, by someone other than the admin.
+    fn execute_change(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("execute_change"),
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new_readonly(self.caller, true),
+            ],
+        )
+    }
+
+    /// cancel_change, signed by the admin key.
+    fn cancel_change(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("cancel_change"),
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new_readonly(self.admin, true),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_setters_apply_any_value_at_once_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: In one slot, the admin key calls
+    //   1. set_oracle_vulnerable(fake oracle)
+    //   2. set_liquidation_bonus_vulnerable(1_000_000), 10,000%
+    //   3. set_cooldown_vulnerable(0)
+    //
+    // Expected: all three SUCCEED and are in the config at once, ready to
+    //           be used in the same transaction.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let config = s.config;
+    let mollusk = mollusk_at(QUEUED_AT);
+    let mut settings = s.initial();
+
+    settings.oracle = s.fake_oracle;
+    let ix = s.set_vulnerable("set_oracle_vulnerable", s.fake_oracle.as_ref());
+    let data = s.config_data(&settings);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&data).build(),
+        ],
+    );
+
+    settings.bonus_bps = ABSURD_BONUS_BPS;
+    let ix = s.set_vulnerable(
+        "set_liquidation_bonus_vulnerable",
+        &ABSURD_BONUS_BPS.to_le_bytes(),
+    );
+    let data = s.config_data(&settings);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&data).build(),
+        ],
+    );
+
+    settings.cooldown = 0;
+    let ix = s.set_vulnerable("set_cooldown_vulnerable", &0u64.to_le_bytes());
+    let data = s.config_data(&settings);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&data).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_setters_enforce_limits() {
+    // -----------------------------------------------------------------------
+    // SECURE: The admin key calls
+    //   1. set_cooldown_secure(0)
+    //   2. queue_liquidation_bonus_secure(1_000_000)
+    //   3. queue_oracle_secure(fake oracle)
+    //   4. set_cooldown_secure(300)
+    //
+    // Expected: step 1 FAILS with CooldownOutOfBounds (6001), step 2 with
+    //           BonusOutOfBounds (6000), step 3 with UntrustedOracle (6002).
+    //           Step 4 SUCCEEDS: a cooldown in range applies at once.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let config = s.config;
+    let mollusk = mollusk_at(QUEUED_AT);
+
+    let ix = s.set_secure("set_cooldown_secure", &0u64.to_le_bytes());
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(COOLDOWN_OUT_OF_BOUNDS))],
+    );
+
+    let ix = s.set_secure(
+        "queue_liquidation_bonus_secure",
+        &ABSURD_BONUS_BPS.to_le_bytes(),
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(BONUS_OUT_OF_BOUNDS))],
+    );
+
+    let ix = s.queue_oracle_secure(&s.fake_oracle);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(UNTRUSTED_ORACLE))],
+    );
+
+    let mut settings = s.initial();
+    settings.cooldown = 300;
+    let ix = s.set_secure("set_cooldown_secure", &300u64.to_le_bytes());
+    let data = s.config_data(&settings);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&data).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_bonus_change_waits_for_timelock() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. execute_change with nothing queued
+    //   2. At slot 1,000, the admin key queues a 10% bonus
+    //   3. execute_change one slot before the timelock passes
+    //   4. execute_change at the slot it passes
+    //
+    // Expected: step 1 FAILS with NothingQueued (6003). Step 2 SUCCEEDS
+    //           and leaves the bonus at 5%, with the change pending. Step 3
+    //           FAILS with ChangeNotReady (6004). Step 4 SUCCEEDS and
+    //           applies it.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let config = s.config;
+    let ready_at = QUEUED_AT + TIMELOCK_SLOTS;
+
+    let ix = s.execute_change();
+    s.ledger.step(
+        &mollusk_at(QUEUED_AT),
+        &ix,
+        &[Check::err(ProgramError::Custom(NOTHING_QUEUED))],
+    );
+
+    let mut settings = s.initial();
+    settings.pending = Some((Change::LiquidationBonus(1_000), ready_at));
+    let ix = s.set_secure("queue_liquidation_bonus_secure", &1_000u32.to_le_bytes());
+    let data = s.config_data(&settings);
+    s.ledger.step(
+        &mollusk_at(QUEUED_AT),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&data).build(),
+        ],
+    );
+
+    let ix = s.execute_change();
+    s.ledger.step(
+        &mollusk_at(ready_at - 1),
+        &ix,
+        &[Check::err(ProgramError::Custom(CHANGE_NOT_READY))],
+    );
+
+    settings.bonus_bps = 1_000;
+    settings.pending = None;
+    let data = s.config_data(&settings);
+    s.ledger.step(
+        &mollusk_at(ready_at),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&data).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_oracle_change_applies_after_timelock() {
+    // -----------------------------------------------------------------------
+    // SECURE: At slot 1,000, the admin key queues another oracle of the
+    //         trusted program. After the timelock, someone else calls
+    //         execute_change.
+    //
+    // Expected: both SUCCEED. The oracle is unchanged while queued, and
+    //           replaced once executed.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (config, new_oracle) = (s.config, s.new_oracle);
+    let ready_at = QUEUED_AT + TIMELOCK_SLOTS;
+
+    let mut settings = s.initial();
+    settings.pending = Some((Change::Oracle(new_oracle), ready_at));
+    let ix = s.queue_oracle_secure(&new_oracle);
+    let data = s.config_data(&settings);
+    s.ledger.step(
+        &mollusk_at(QUEUED_AT),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&data).build(),
+        ],
+    );
+
+    settings.oracle = new_oracle;
+    settings.pending = None;
+    let ix = s.execute_change();
+    let data = s.config_data(&settings);
+    s.ledger.step(
+        &mollusk_at(ready_at),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&data).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_expired_change_cancelled() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. At slot 1,000, the admin key queues a 10% bonus
+    //   2. execute_change one slot after the grace period ends
+    //   3. The admin key calls cancel_change
+    //   4. execute_change, and cancel_change, again
+    //
+    // Expected: step 2 FAILS with ChangeExpired (6006), and the change
+    //           stays queued. Step 3 SUCCEEDS and clears it, leaving the
+    //           bonus at 5%. Step 4 FAILS with NothingQueued (6003) both
+    //           times.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let config = s.config;
+    let ready_at = QUEUED_AT + TIMELOCK_SLOTS;
+    let expired = ready_at + GRACE_SLOTS + 1;
+
+    let mut settings = s.initial();
+    settings.pending = Some((Change::LiquidationBonus(1_000), ready_at));
+    let ix = s.set_secure("queue_liquidation_bonus_secure", &1_000u32.to_le_bytes());
+    let queued = s.config_data(&settings);
+    s.ledger.step(
+        &mollusk_at(QUEUED_AT),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&queued).build(),
+        ],
+    );
+
+    let ix = s.execute_change();
+    s.ledger.step(
+        &mollusk_at(expired),
+        &ix,
+        &[Check::err(ProgramError::Custom(CHANGE_EXPIRED))],
+    );
+
+    let ix = s.cancel_change();
+    let data = s.config_data(&s.initial());
+    s.ledger.step(
+        &mollusk_at(expired),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&config).data(&data).build(),
+        ],
+    );
+
+    for ix in [s.execute_change(), s.cancel_change()] {
+        s.ledger.step(
+            &mollusk_at(expired),
+            &ix,
+            &[Check::err(ProgramError::Custom(NOTHING_QUEUED))],
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+use rent_reserve::withdrawable_lamports;
+
+declare_id!("867CG3VDFpEF4MDp1mUWmL2M3sZ8CNEKiSBjkMx69tAz");
+
+/// # Missing Audit Events
+///
+/// ## The Vulnerability
+/// A treasury's admin can withdraw its lamports to any account and hand
+/// the admin role to any key. The vulnerable instructions do both and
+/// record nothing: no event, no log line beyond the instruction name
+/// Anchor prints. The only trace is a balance that went down and a field
+/// that changed, in a transaction whose logs say nothing about how much,
+/// to whom, or by whose key.
+///
+/// ## Why It Matters
+/// Privileged instructions are the ones a stolen key uses. Monitoring
+/// can't alert on what isn't emitted, so the first sign of a drain is an
+/// empty treasury. Afterwards, responders have to replay every
+/// transaction that touched the program and diff account states to find
+/// which moved funds, and when the admin changed hands. Indexers, risk
+/// dashboards, and the team's own alerting are all blind by construction.
+///
+/// ## The Fix
+/// Every privileged action emits a structured event: `AdminWithdrawal`
+/// and `AdminChanged`, with the accounts, the amounts, and the slot.
+/// `emit!` writes each one to the program's logs for subscribers, and
+/// `emit_cpi!` also records it as a self-CPI, which survives log
+/// truncation and can only come from the program itself.
+#[program]
+pub mod missing_audit_events {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.admin = ctx.accounts.admin.key();
+        treasury.bump = ctx.bumps.treasury;
+        demo_log!(Mode::Setup, "initialize", actor = treasury.admin);
+        Ok(())
+    }
+
+    /// Pay `amount` lamports into the treasury.
+    pub fn fund(ctx: Context<Fund>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "fund",
+            actor = ctx.accounts.payer.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Admin withdrawal with no event
+    // ============================================================================
+    // ISSUE: The lamports move and the instruction returns. Nothing on chain
+    //        says an admin withdrawal happened, how much it took, or where
+    //        it went.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory steals the admin key
+    //   2. Mallory calls admin_withdraw_vulnerable for the whole treasury,
+    //      to an account Mallory controls
+    //   3. No alert fires: there is no event to alert on. The team learns
+    //      of it from users, and has to reconstruct it from raw balances
+    // ============================================================================
+    pub fn admin_withdraw_vulnerable(ctx: Context<AdminWithdraw>, amount: u64) -> Result<()> {
+        pay_out(&ctx.accounts.treasury, &ctx.accounts.destination, amount)?;
+        // VULNERABLE: nothing records that this happened, or who did it
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Admin withdrawal emits AdminWithdrawal
+    // ============================================================================
+    // FIX: The event names the treasury, the admin who signed, the
+    //      destination, the amount, what is left, and the slot. It is
+    //      logged for subscribers and recorded by CPI for indexers.
+    // ============================================================================
+    pub fn admin_withdraw_secure(ctx: Context<AdminWithdrawSecure>, amount: u64) -> Result<()> {
+        pay_out(&ctx.accounts.treasury, &ctx.accounts.destination, amount)?;
+        // SECURE: every withdrawal leaves a structured record
+        let event = AdminWithdrawal {
+            treasury: ctx.accounts.treasury.key(),
+            admin: ctx.accounts.admin.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+            remaining: withdrawable_lamports(&ctx.accounts.treasury.to_account_info())?,
+            slot: Clock::get()?.slot,
+        };
+        emit!(event.clone());
+        emit_cpi!(event);
+        demo_log!(
+            Mode::Secure,
+            "admin_withdraw",
+            actor = ctx.accounts.admin.key(),
+            amount = amount,
+            destination = ctx.accounts.destination.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Admin handover with no event
+    // ============================================================================
+    // ISSUE: The admin field is overwritten in silence. Whoever watches the
+    //        program can't tell the key changed hands until the new key
+    //        uses it.
+    //
+    // ATTACK SCENARIO:
+    //   1. With the stolen key, Mallory calls set_admin_vulnerable with a
+    //      second key Mallory holds
+    //   2. The team's key no longer works, and nothing says since when
+    // ============================================================================
+    pub fn set_admin_vulnerable(ctx: Context<SetAdmin>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.treasury.admin = new_admin;
+        // VULNERABLE: nothing records that this happened, or who did it
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Admin handover emits AdminChanged
+    // ============================================================================
+    // FIX: The event names the previous admin, the new one, and the slot,
+    //      logged and recorded by CPI like every privileged action.
+    // ============================================================================
+    pub fn set_admin_secure(ctx: Context<SetAdminSecure>, new_admin: Pubkey) -> Result<()> {
+        let previous_admin = ctx.accounts.treasury.admin;
+        ctx.accounts.treasury.admin = new_admin;
+        // SECURE: every handover leaves a structured record
+        let event = AdminChanged {
+            treasury: ctx.accounts.treasury.key(),
+            previous_admin,
+            new_admin,
+            slot: Clock::get()?.slot,
+        };
+        emit!(event.clone());
+        emit_cpi!(event);
+        demo_log!(
+            Mode::Secure,
+            "set_admin",
+            actor = previous_admin,
+            new_admin = new_admin
+        );
+        Ok(())
+    }
+}
+
+/// Move `amount` lamports from the treasury to `destination`, leaving the
+/// treasury's rent-exempt minimum.
+fn pay_out<'info>(
+    treasury: &Account<'info, Treasury>,
+    destination: &SystemAccount<'info>,
+    amount: u64,
+) -> Result<()> {
+    let available = withdrawable_lamports(&treasury.to_account_info())?;
+    require!(amount <= available, ErrorCode::InsufficientFunds);
+    treasury.sub_lamports(amount)?;
+    destination.add_lamports(amount)?;
+    Ok(())
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+/// An admin moved lamports out of the treasury.
+#[event]
+#[derive(Clone)]
+pub struct AdminWithdrawal {
+    pub treasury: Pubkey,
+    pub admin: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    /// Lamports left above the rent-exempt minimum.
+    pub remaining: u64,
+    pub slot: u64,
+}
+
+/// The treasury's admin role changed hands.
+#[event]
+#[derive(Clone)]
+pub struct AdminChanged {
+    pub treasury: Pubkey,
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub slot: u64,
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Fund<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminWithdraw<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump, has_one = admin)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+    pub admin: Signer<'info>,
+}
+
+/// `AdminWithdraw`, plus the event authority and program `emit_cpi!`
+/// needs.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AdminWithdrawSecure<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump, has_one = admin)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAdmin<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump, has_one = admin)]
+    pub treasury: Account<'info, Treasury>,
+    pub admin: Signer<'info>,
+}
+
+/// `SetAdmin`, plus the event authority and program `emit_cpi!` needs.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetAdminSecure<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump, has_one = admin)]
+    pub treasury: Account<'info, Treasury>,
+    pub admin: Signer<'info>,
+}
+
+/// The treasury. Lamports above the rent-exempt minimum are its funds.
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub admin: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The treasury doesn't hold that much")]
+    InsufficientFunds,
+}
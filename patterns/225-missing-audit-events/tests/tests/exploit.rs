@@ -0,0 +1,365 @@
+// Mollusk tests for Pattern 225
+/// # Pattern 225: Missing Audit Events — Mollusk Exploit Tests
+///
+/// The treasury holds 10 SOL above its rent-exempt minimum, and it is slot
+/// 1,000. The admin key is the one Mallory has stolen. Each test collects
+/// the runtime's program log lines with `demo_log::capture` while its
+/// instructions run, and reads events back from them.
+///
+/// - Test 1: EXPLOIT — admin_withdraw_vulnerable moves all 10 SOL with no
+///   event, logged or by CPI.
+/// - Test 2: SECURE — admin_withdraw_secure logs an AdminWithdrawal with
+///   every field, and records it by CPI.
+/// - Test 3: EXPLOIT — set_admin_vulnerable hands the treasury to a new
+///   key with no event.
+/// - Test 4: SECURE — set_admin_secure logs an AdminChanged, and records
+///   it by CPI.
+/// - Test 5: SECURE — an event instruction sent to the program directly,
+///   without the event authority's signature, is refused: CPI events can't
+///   be forged from outside.
+use base64::{engine::general_purpose::STANDARD, Engine};
+use demo_log::capture;
+use mollusk_svm::{result::Check, Mollusk};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("867CG3VDFpEF4MDp1mUWmL2M3sZ8CNEKiSBjkMx69tAz");
+
+const WALLET: u64 = 1_000_000_000;
+const FUNDS: u64 = 10_000_000_000;
+const SLOT: u64 = 1_000;
+
+/// 8 discriminator + 32 admin + 1 bump.
+const TREASURY_LEN: usize = 41;
+
+/// Anchor's tag for the instruction `emit_cpi!` sends to its own program.
+const EVENT_IX_TAG: u64 = 0x1d9acb512ea545e4;
+/// anchor_lang::error::ErrorCode::ConstraintSigner
+const CONSTRAINT_SIGNER: u32 = 2002;
+
+// ---------------------------------------------------------------------------
+// Logged events
+// ---------------------------------------------------------------------------
+
+/// The events in `logs`: every `Program data:` line, decoded.
+fn logged_events(logs: &[String]) -> Vec<Vec<u8>> {
+    logs.iter()
+        .filter_map(|line| line.strip_prefix("Program data: "))
+        .map(|data| STANDARD.decode(data).unwrap())
+        .collect()
+}
+
+/// Whether the program invoked itself, as `emit_cpi!` does.
+fn self_invoked(logs: &[String]) -> bool {
+    let invoke = format!("Program {PROGRAM_ID} invoke [2]");
+    logs.contains(&invoke)
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn event_discriminator(name: &str) -> [u8; 8] {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("event:{name}").as_bytes());
+    let hash = hasher.finalize();
+    let mut disc = [0u8; 8];
+    disc.copy_from_slice(&hash[..8]);
+    disc
+}
+
+/// [8 disc][32 admin][1 bump]
+fn serialize_treasury(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(TREASURY_LEN);
+    data.extend_from_slice(&account_discriminator("Treasury"));
+    data.extend_from_slice(admin.as_ref());
+    data.push(bump);
+    data
+}
+
+/// [8 disc][32 treasury][32 admin][32 destination][8 amount][8 remaining][8 slot]
+fn serialize_withdrawal(
+    treasury: &Pubkey,
+    admin: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+    remaining: u64,
+) -> Vec<u8> {
+    let mut data = event_discriminator("AdminWithdrawal").to_vec();
+    data.extend_from_slice(treasury.as_ref());
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(destination.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&remaining.to_le_bytes());
+    data.extend_from_slice(&SLOT.to_le_bytes());
+    data
+}
+
+/// [8 disc][32 treasury][32 previous_admin][32 new_admin][8 slot]
+fn serialize_admin_changed(
+    treasury: &Pubkey,
+    previous_admin: &Pubkey,
+    new_admin: &Pubkey,
+) -> Vec<u8> {
+    let mut data = event_discriminator("AdminChanged").to_vec();
+    data.extend_from_slice(treasury.as_ref());
+    data.extend_from_slice(previous_admin.as_ref());
+    data.extend_from_slice(new_admin.as_ref());
+    data.extend_from_slice(&SLOT.to_le_bytes());
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    treasury: Pubkey,
+    treasury_bump: u8,
+    event_authority: Pubkey,
+    admin: Pubkey,
+    destination: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// The treasury, holding FUNDS above its minimum, with `admin` as its
+    /// admin; and an account of Mallory's to withdraw to.
+    fn new() -> Self {
+        let mut mollusk = Mollusk::new(&PROGRAM_ID, "missing_audit_events");
+        mollusk.sysvars.clock.slot = SLOT;
+        let admin = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let (treasury, treasury_bump) = Pubkey::find_program_address(&[b"treasury"], &PROGRAM_ID);
+        let (event_authority, _) =
+            Pubkey::find_program_address(&[b"__event_authority"], &PROGRAM_ID);
+
+        let data = serialize_treasury(&admin, treasury_bump);
+        let mut treasury_account =
+            AccountSharedData::new(Self::rent() + FUNDS, TREASURY_LEN, &PROGRAM_ID);
+        treasury_account.set_data_from_slice(&data);
+
+        let ledger = vec![
+            (treasury, treasury_account),
+            (admin, system_account()),
+            (destination, system_account()),
+            (event_authority, AccountSharedData::default()),
+            (
+                PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+
+        Self {
+            mollusk,
+            treasury,
+            treasury_bump,
+            event_authority,
+            admin,
+            destination,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn rent() -> u64 {
+        Rent::default().minimum_balance(TREASURY_LEN)
+    }
+
+    fn treasury_data(&self, admin: &Pubkey) -> Vec<u8> {
+        serialize_treasury(admin, self.treasury_bump)
+    }
+
+    /// The accounts `#[event_cpi]` appends to a secure instruction's.
+    fn event_cpi_metas(&self) -> [AccountMeta; 2] {
+        [
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new_readonly(PROGRAM_ID, false),
+        ]
+    }
+
+    /// admin_withdraw_vulnerable or admin_withdraw_secure, to the
+    /// destination.
+    fn admin_withdraw(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        let mut metas = vec![
+            AccountMeta::new(self.treasury, false),
+            AccountMeta::new(self.destination, false),
+            AccountMeta::new_readonly(self.admin, true),
+        ];
+        if name.ends_with("_secure") {
+            metas.extend(self.event_cpi_metas());
+        }
+        Instruction::new_with_bytes(PROGRAM_ID, &data, metas)
+    }
+
+    /// set_admin_vulnerable or set_admin_secure.
+    fn set_admin(&self, name: &str, new_admin: &Pubkey) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(new_admin.as_ref());
+        let mut metas = vec![
+            AccountMeta::new(self.treasury, false),
+            AccountMeta::new_readonly(self.admin, true),
+        ];
+        if name.ends_with("_secure") {
+            metas.extend(self.event_cpi_metas());
+        }
+        Instruction::new_with_bytes(PROGRAM_ID, &data, metas)
+    }
+
+    /// Step the ledger through `ix` and return the program log lines the
+    /// instruction produced.
+    fn step(&mut self, ix: &Instruction, checks: &[Check]) -> Vec<String> {
+        capture::start();
+        self.ledger.step(&self.mollusk, ix, checks);
+        capture::lines()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_admin_withdraw_emits_nothing_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory, with the admin key, calls admin_withdraw_vulnerable
+    //          for all 10 SOL
+    //
+    // Expected: SUCCEEDS with no event: no `Program data:` line and no
+    //           self-CPI. The logs say an instruction ran, and nothing else.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (treasury, destination) = (s.treasury, s.destination);
+
+    let ix = s.admin_withdraw("admin_withdraw_vulnerable", FUNDS);
+    let logs = s.step(
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).lamports(Setup::rent()).build(),
+            Check::account(&destination)
+                .lamports(WALLET + FUNDS)
+                .build(),
+        ],
+    );
+
+    assert!(logged_events(&logs).is_empty());
+    assert!(!self_invoked(&logs));
+}
+
+#[test]
+fn secure_admin_withdraw_emits_event() {
+    // -----------------------------------------------------------------------
+    // SECURE: the same withdrawal through admin_withdraw_secure
+    //
+    // Expected: SUCCEEDS, logs exactly one event: AdminWithdrawal naming
+    //           the treasury, the admin, the destination, 10 SOL, 0 left,
+    //           and slot 1,000. The program also invokes itself to record
+    //           it by CPI.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (treasury, admin, destination) = (s.treasury, s.admin, s.destination);
+
+    let ix = s.admin_withdraw("admin_withdraw_secure", FUNDS);
+    let logs = s.step(
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&destination)
+                .lamports(WALLET + FUNDS)
+                .build(),
+        ],
+    );
+
+    let expected = serialize_withdrawal(&treasury, &admin, &destination, FUNDS, 0);
+    assert_eq!(logged_events(&logs), vec![expected]);
+    assert!(self_invoked(&logs));
+}
+
+#[test]
+fn exploit_admin_change_emits_nothing_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory, with the admin key, calls set_admin_vulnerable with
+    //          a second key
+    //
+    // Expected: SUCCEEDS. The treasury now answers to the new key, and
+    //           nothing was emitted.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let treasury = s.treasury;
+    let new_admin = Pubkey::new_unique();
+
+    let ix = s.set_admin("set_admin_vulnerable", &new_admin);
+    let handed_over = s.treasury_data(&new_admin);
+    let logs = s.step(
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).data(&handed_over).build(),
+        ],
+    );
+
+    assert!(logged_events(&logs).is_empty());
+    assert!(!self_invoked(&logs));
+}
+
+#[test]
+fn secure_admin_change_emits_event() {
+    // -----------------------------------------------------------------------
+    // SECURE: the same handover through set_admin_secure
+    //
+    // Expected: SUCCEEDS, logs exactly one event: AdminChanged from the
+    //           old admin to the new one at slot 1,000, also recorded by
+    //           CPI.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (treasury, admin) = (s.treasury, s.admin);
+    let new_admin = Pubkey::new_unique();
+
+    let ix = s.set_admin("set_admin_secure", &new_admin);
+    let handed_over = s.treasury_data(&new_admin);
+    let logs = s.step(
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).data(&handed_over).build(),
+        ],
+    );
+
+    let expected = serialize_admin_changed(&treasury, &admin, &new_admin);
+    assert_eq!(logged_events(&logs), vec![expected]);
+    assert!(self_invoked(&logs));
+}
+
+#[test]
+fn secure_event_cpi_cannot_be_forged() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory sends the program an event instruction directly,
+    //         carrying a made-up AdminWithdrawal, with the event authority
+    //         unsigned
+    //
+    // Expected: FAILS with ConstraintSigner (2002). Only the program can
+    //           sign for its event authority, so an indexer can trust every
+    //           CPI event it finds under it.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let forged = serialize_withdrawal(&s.treasury, &s.admin, &s.destination, 1, FUNDS);
+
+    let mut data = EVENT_IX_TAG.to_le_bytes().to_vec();
+    data.extend_from_slice(&forged);
+    let ix = Instruction::new_with_bytes(
+        PROGRAM_ID,
+        &data,
+        vec![AccountMeta::new_readonly(s.event_authority, false)],
+    );
+    s.step(&ix, &[Check::err(ProgramError::Custom(CONSTRAINT_SIGNER))]);
+}
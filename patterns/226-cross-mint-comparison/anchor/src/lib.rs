@@ -0,0 +1,429 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+use safe_math::{quote, QuoteValue, TokenAmount, UsdPrice, ValuationError};
+
+declare_id!("BjtbiS6D1qcwDsdJcPwFKKhPRt8MXBWVeNW89JLs7aXr");
+
+/// Debt may be up to 80% of the collateral's value.
+pub const MAX_LTV_BPS: u64 = 8_000;
+pub const BPS: u64 = 10_000;
+
+/// # Cross-Mint Comparison
+///
+/// ## The Vulnerability
+/// A lending market takes one token as collateral and lends another. The
+/// vulnerable borrow checks the position's debt against its collateral as
+/// raw `u64`s: `debt <= collateral × 80%`. The two numbers count units of
+/// different mints, with different decimals and different prices, so the
+/// comparison means nothing. Collateral whose raw units are cheap, like a
+/// 5-decimal memecoin, borrows a multiple of its worth.
+///
+/// ## Why It Matters
+/// The check looks right and passes review: it has a limit, a ratio, and
+/// checked math. Nothing in a `u64` says which mint it counts, so the
+/// compiler accepts it too. Every health check, liquidation threshold, and
+/// swap limit that compares amounts across mints has the same hole, and an
+/// attacker only needs the mint whose units are cheapest.
+///
+/// ## The Fix
+/// Compare values, not amounts. `borrow_secure` wraps each balance in a
+/// `safe_math::TokenAmount` with its mint's decimals and prices it with
+/// `safe_math::quote`, which returns a `QuoteValue` in micro-USD.
+/// `TokenAmount` has no ordering, so amounts of two mints can't be
+/// compared at all; `QuoteValue`s can.
+#[program]
+pub mod cross_mint_comparison {
+    use super::*;
+
+    /// Admin: a market lending `debt_mint` against `collateral_mint`, with
+    /// a vault and a price feed for each.
+    pub fn create_market(ctx: Context<CreateMarket>, max_age_slots: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let collateral = Asset {
+            mint: accounts.collateral_mint.key(),
+            vault: accounts.collateral_vault.key(),
+            feed: accounts.collateral_feed.key(),
+            decimals: accounts.collateral_mint.decimals,
+        };
+        let debt = Asset {
+            mint: accounts.debt_mint.key(),
+            vault: accounts.debt_vault.key(),
+            feed: accounts.debt_feed.key(),
+            decimals: accounts.debt_mint.decimals,
+        };
+        ctx.accounts.collateral_feed.bump = ctx.bumps.collateral_feed;
+        ctx.accounts.debt_feed.bump = ctx.bumps.debt_feed;
+
+        let market = &mut ctx.accounts.market;
+        market.admin = ctx.accounts.admin.key();
+        market.max_age_slots = max_age_slots;
+        market.collateral = collateral;
+        market.debt = debt;
+        market.bump = ctx.bumps.market;
+        demo_log!(
+            Mode::Setup,
+            "create_market",
+            actor = market.admin,
+            collateral = collateral.mint,
+            debt = debt.mint
+        );
+        Ok(())
+    }
+
+    /// Admin: publish `price`, in micro-USD per whole token.
+    pub fn set_price(ctx: Context<SetPrice>, price: u64) -> Result<()> {
+        let feed = &mut ctx.accounts.feed;
+        feed.price = price;
+        feed.slot = Clock::get()?.slot;
+        demo_log!(
+            Mode::Setup,
+            "set_price",
+            actor = ctx.accounts.admin.key(),
+            price = price,
+            slot = feed.slot
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.collateral = 0;
+        position.debt = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_tokens.to_account_info(),
+                    to: ctx.accounts.collateral_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let position = &mut ctx.accounts.position;
+        position.collateral = position
+            .collateral
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit_collateral",
+            actor = position.owner,
+            amount = amount,
+            collateral = position.collateral
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Borrow limit from raw amounts of two different mints
+    // ============================================================================
+    // ISSUE: `position.collateral` counts collateral units and `new_debt`
+    //        counts debt units. Multiplying one by 80% and comparing it to
+    //        the other ignores both decimals and both prices.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory deposits 10,000,000 BONK (5 decimals, $0.00002): raw
+    //      1,000,000,000,000, worth $200
+    //   2. borrow_vulnerable(800,000 USDC): raw 800,000,000,000 is 80% of
+    //      the raw collateral, so the check passes
+    //   3. Mallory walks away with $800,000 against $200
+    // ============================================================================
+    pub fn borrow_vulnerable(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let position = &ctx.accounts.position;
+        let new_debt = position
+            .debt
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let limit = (position.collateral as u128) * (MAX_LTV_BPS as u128) / (BPS as u128);
+
+        // VULNERABLE: collateral units against debt units
+        require!((new_debt as u128) <= limit, ErrorCode::Undercollateralized);
+
+        borrow(ctx.accounts, amount, new_debt)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            debt = new_debt,
+            limit = limit
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Borrow limit from the quote value of each side
+    // ============================================================================
+    // FIX: Each balance becomes a TokenAmount with its mint's decimals, and
+    //      `quote` prices it with a fresh UsdPrice. The check compares two
+    //      QuoteValues in micro-USD; TokenAmounts of different mints have
+    //      no ordering to compare with.
+    // ============================================================================
+    pub fn borrow_secure(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let market = &accounts.market;
+        let position = &accounts.position;
+        let new_debt = position
+            .debt
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let slot = Clock::get()?.slot;
+
+        // SECURE: both sides in one unit before they meet
+        let collateral_value = value(
+            position.collateral,
+            &market.collateral,
+            &accounts.collateral_feed,
+            slot,
+            market.max_age_slots,
+        )?;
+        let debt_value = value(
+            new_debt,
+            &market.debt,
+            &accounts.debt_feed,
+            slot,
+            market.max_age_slots,
+        )?;
+        let limit = collateral_value.bps(MAX_LTV_BPS).map_err(valuation_error)?;
+        require!(debt_value <= limit, ErrorCode::Undercollateralized);
+
+        borrow(ctx.accounts, amount, new_debt)?;
+        demo_log!(
+            Mode::Secure,
+            "borrow",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            debt_value = debt_value.micro_usd(),
+            limit = limit.micro_usd()
+        );
+        Ok(())
+    }
+}
+
+/// `raw` units of `asset`, priced by `feed` at `slot`.
+fn value(
+    raw: u64,
+    asset: &Asset,
+    feed: &PriceFeed,
+    slot: u64,
+    max_age_slots: u64,
+) -> Result<QuoteValue> {
+    let amount = TokenAmount::new(raw, asset.decimals);
+    let price = UsdPrice::new(feed.price, feed.slot);
+    Ok(quote(amount, price, slot, max_age_slots).map_err(valuation_error)?)
+}
+
+fn valuation_error(error: ValuationError) -> ErrorCode {
+    match error {
+        ValuationError::StalePrice => ErrorCode::StalePrice,
+        ValuationError::InvalidDecimals => ErrorCode::InvalidDecimals,
+        ValuationError::Overflow => ErrorCode::Overflow,
+    }
+}
+
+/// Send `amount` of the debt token from the market's vault to the owner,
+/// and record the position's debt as `new_debt`.
+fn borrow(accounts: &mut Borrow, amount: u64, new_debt: u64) -> Result<()> {
+    let bump = [accounts.market.bump];
+    let seeds: &[&[u8]] = &[b"market", &bump];
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.debt_vault.to_account_info(),
+                to: accounts.owner_tokens.to_account_info(),
+                authority: accounts.market.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+    )?;
+    accounts.position.debt = new_debt;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market"],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    pub collateral_mint: Account<'info, Mint>,
+    pub debt_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"vault", collateral_mint.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = market
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"vault", debt_mint.key().as_ref()],
+        bump,
+        token::mint = debt_mint,
+        token::authority = market
+    )]
+    pub debt_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PriceFeed::INIT_SPACE,
+        seeds = [b"feed", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub collateral_feed: Account<'info, PriceFeed>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PriceFeed::INIT_SPACE,
+        seeds = [b"feed", debt_mint.key().as_ref()],
+        bump
+    )]
+    pub debt_feed: Account<'info, PriceFeed>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(seeds = [b"market"], bump = market.bump, has_one = admin)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        constraint = feed.key() == market.collateral.feed || feed.key() == market.debt.feed
+            @ ErrorCode::UnknownAsset
+    )]
+    pub feed: Account<'info, PriceFeed>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"position", owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut, token::mint = market.collateral.mint)]
+    pub owner_tokens: Account<'info, TokenAccount>,
+    #[account(mut, address = market.collateral.vault)]
+    pub collateral_vault: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"position", owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(address = market.collateral.feed)]
+    pub collateral_feed: Account<'info, PriceFeed>,
+    #[account(address = market.debt.feed)]
+    pub debt_feed: Account<'info, PriceFeed>,
+    #[account(mut, address = market.debt.vault)]
+    pub debt_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = market.debt.mint)]
+    pub owner_tokens: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// One side of the market: a mint, the vault holding it, and its feed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Asset {
+    pub mint: Pubkey,  // 32 bytes
+    pub vault: Pubkey, // 32 bytes
+    pub feed: Pubkey,  // 32 bytes
+    pub decimals: u8,  //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub admin: Pubkey,      // 32 bytes
+    pub max_age_slots: u64, //  8 bytes
+    pub collateral: Asset,  // 97 bytes
+    pub debt: Asset,        // 97 bytes
+    pub bump: u8,           //  1 byte
+}
+
+/// An asset's price in micro-USD per whole token, and the slot it was set.
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    pub price: u64, // 8 bytes
+    pub slot: u64,  // 8 bytes
+    pub bump: u8,   // 1 byte
+}
+
+/// A borrower's collateral and debt, each in raw units of its own mint.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,   // 32 bytes
+    pub collateral: u64, //  8 bytes
+    pub debt: u64,       //  8 bytes
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Debt would exceed the collateral's borrow limit")]
+    Undercollateralized,
+    #[msg("A price is older than the market allows")]
+    StalePrice,
+    #[msg("No such asset in the market")]
+    UnknownAsset,
+    #[msg("A mint's decimals are too large")]
+    InvalidDecimals,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,348 @@
+// Mollusk tests for Pattern 226
+/// # Pattern 226: Cross-Mint Comparison — Mollusk Exploit Tests
+///
+/// The market lends USDC (6 decimals, $1) against BONK (5 decimals,
+/// $0.00002) at up to 80% loan-to-value. It is slot 1,000, and prices may
+/// be 100 slots old. Mallory has deposited 10,000,000 BONK, raw
+/// 1,000,000,000,000, worth $200. The USDC vault holds 1,000,000 USDC.
+///
+/// - Test 1: EXPLOIT — borrow_vulnerable lends Mallory 800,000 USDC: 80%
+///   of the raw collateral amount.
+/// - Test 2: SECURE — borrow_secure refuses 800,000 USDC, and 160 USDC plus
+///   one raw unit.
+/// - Test 3: SECURE — borrow_secure refuses a BONK price 500 slots old.
+/// - Test 4: SANITY — borrow_secure lends exactly 160 USDC, 80% of $200.
+///
+/// The SPL Token program must be built into SBF_OUT_DIR as `spl_token.so`.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{token_account, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("BjtbiS6D1qcwDsdJcPwFKKhPRt8MXBWVeNW89JLs7aXr");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+
+const SLOT: u64 = 1_000;
+const MAX_AGE_SLOTS: u64 = 100;
+
+/// Decimals, and prices in micro-USD per whole token.
+const BONK_DECIMALS: u8 = 5;
+const USDC_DECIMALS: u8 = 6;
+const BONK_PRICE: u64 = 20;
+const USDC_PRICE: u64 = 1_000_000;
+
+/// Raw amounts.
+const ONE_USDC: u64 = 1_000_000;
+const MALLORY_BONK: u64 = 1_000_000_000_000;
+const VAULT_USDC: u64 = 1_000_000 * ONE_USDC;
+/// 80% of the raw BONK amount, read as USDC.
+const RAW_LIMIT: u64 = 800_000 * ONE_USDC;
+/// 80% of $200.
+const VALUE_LIMIT: u64 = 160 * ONE_USDC;
+
+// Error codes
+const UNDERCOLLATERALIZED: u32 = 6000;
+const STALE_PRICE: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// One side of the market: [32 mint][32 vault][32 feed][1 decimals]
+struct Asset {
+    mint: Pubkey,
+    vault: Pubkey,
+    feed: Pubkey,
+    feed_bump: u8,
+    decimals: u8,
+}
+
+/// Serialize a Market account:
+///   [8 disc][32 admin][8 max_age_slots][97 collateral][97 debt][1 bump]
+fn serialize_market(admin: &Pubkey, collateral: &Asset, debt: &Asset, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(243);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&MAX_AGE_SLOTS.to_le_bytes());
+    for asset in [collateral, debt] {
+        data.extend_from_slice(asset.mint.as_ref());
+        data.extend_from_slice(asset.vault.as_ref());
+        data.extend_from_slice(asset.feed.as_ref());
+        data.push(asset.decimals);
+    }
+    data.push(bump);
+    data
+}
+
+/// Serialize a PriceFeed account: [8 disc][8 price][8 slot][1 bump]
+fn serialize_feed(price: u64, slot: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(25);
+    data.extend_from_slice(&account_discriminator("PriceFeed"));
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&slot.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 owner][8 collateral][8 debt][1 bump]
+fn serialize_position(owner: &Pubkey, collateral: u64, debt: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(57);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&collateral.to_le_bytes());
+    data.extend_from_slice(&debt.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "cross_mint_comparison");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk.sysvars.clock.slot = SLOT;
+    mollusk
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID)
+}
+
+struct Setup {
+    market: Pubkey,
+    bonk: Asset,
+    usdc: Asset,
+    mallory: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    mallory_usdc: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// The market with both prices published at SLOT, and Mallory's
+    /// position holding MALLORY_BONK as collateral and no debt.
+    fn new() -> Self {
+        let (market, market_bump) = pda(&[b"market"]);
+        let asset = |decimals: u8| {
+            let mint = Pubkey::new_unique();
+            let (vault, _) = pda(&[b"vault", mint.as_ref()]);
+            let (feed, feed_bump) = pda(&[b"feed", mint.as_ref()]);
+            Asset {
+                mint,
+                vault,
+                feed,
+                feed_bump,
+                decimals,
+            }
+        };
+        let bonk = asset(BONK_DECIMALS);
+        let usdc = asset(USDC_DECIMALS);
+        let mallory = Pubkey::new_unique();
+        let (position, position_bump) = pda(&[b"position", mallory.as_ref()]);
+        let mallory_usdc = Pubkey::new_unique();
+
+        let ledger = vec![
+            (
+                market,
+                owned_account(
+                    &serialize_market(&Pubkey::new_unique(), &bonk, &usdc, market_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                position,
+                owned_account(
+                    &serialize_position(&mallory, MALLORY_BONK, 0, position_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                bonk.feed,
+                owned_account(
+                    &serialize_feed(BONK_PRICE, SLOT, bonk.feed_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                usdc.feed,
+                owned_account(
+                    &serialize_feed(USDC_PRICE, SLOT, usdc.feed_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (bonk.vault, token_account(&TOKEN_PROGRAM_ID, &bonk.mint, &market, MALLORY_BONK)),
+            (usdc.vault, token_account(&TOKEN_PROGRAM_ID, &usdc.mint, &market, VAULT_USDC)),
+            (mallory_usdc, token_account(&TOKEN_PROGRAM_ID, &usdc.mint, &mallory, 0)),
+            (mallory, system_account()),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+
+        Self {
+            market,
+            bonk,
+            usdc,
+            mallory,
+            position,
+            position_bump,
+            mallory_usdc,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// Rewrite the BONK feed as published at `slot`.
+    fn set_bonk_slot(&mut self, slot: u64) {
+        let data = serialize_feed(BONK_PRICE, slot, self.bonk.feed_bump);
+        self.ledger
+            .account_mut(&self.bonk.feed)
+            .set_data_from_slice(&data);
+    }
+
+    /// Mallory: borrow_*(amount).
+    fn borrow(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.market, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.bonk.feed, false),
+                AccountMeta::new_readonly(self.usdc.feed, false),
+                AccountMeta::new(self.usdc.vault, false),
+                AccountMeta::new(self.mallory_usdc, false),
+                AccountMeta::new_readonly(self.mallory, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// Mallory's position and USDC account data after borrowing `debt`.
+    fn borrowed_data(&self, debt: u64) -> (Vec<u8>, Vec<u8>) {
+        (
+            serialize_position(&self.mallory, MALLORY_BONK, debt, self.position_bump),
+            TokenAccount::new(&self.usdc.mint, &self.mallory, debt).pack(),
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_raw_amounts_compared_across_mints_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   Mallory: borrow_vulnerable(800,000 USDC) against $200 of BONK
+    //
+    // Expected: SUCCEEDS. The limit is 80% of 1,000,000,000,000 raw BONK
+    //           units, and 800,000,000,000 raw USDC units is within it.
+    //           Mallory's wallet receives $800,000.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (position, wallet) = (s.position, s.mallory_usdc);
+
+    let ix = s.borrow("borrow_vulnerable", RAW_LIMIT);
+    let (position_data, wallet_data) = s.borrowed_data(RAW_LIMIT);
+    let checks = [
+        Check::success(),
+        Check::account(&position).data(&position_data).build(),
+        Check::account(&wallet).data(&wallet_data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+}
+
+#[test]
+fn secure_values_compared_in_quote() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   Mallory: borrow_secure(800,000 USDC), then borrow_secure(160 USDC
+    //            plus one raw unit)
+    //
+    // Expected: both FAIL with Undercollateralized (6000). The collateral
+    //           quotes at $200, so the limit is $160, and 160.000001 USDC
+    //           is over it.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+
+    for amount in [RAW_LIMIT, VALUE_LIMIT + 1] {
+        let ix = s.borrow("borrow_secure", amount);
+        s.ledger.step(
+            &mollusk,
+            &ix,
+            &[Check::err(ProgramError::Custom(UNDERCOLLATERALIZED))],
+        );
+    }
+}
+
+#[test]
+fn secure_stale_price_refused() {
+    // -----------------------------------------------------------------------
+    // SECURE (staleness):
+    //   The BONK feed was last published at slot 500, 500 slots ago
+    //   Mallory: borrow_secure(1 USDC)
+    //
+    // Expected: FAILS with StalePrice (6001): quote won't value the
+    //           collateral at a price that old.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    s.set_bonk_slot(SLOT - 500);
+
+    let ix = s.borrow("borrow_secure", ONE_USDC);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(STALE_PRICE))],
+    );
+}
+
+#[test]
+fn sanity_borrow_up_to_ltv_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   Mallory: borrow_secure(160 USDC)
+    //
+    // Expected: SUCCEEDS. $160 of debt is exactly 80% of $200.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (position, wallet) = (s.position, s.mallory_usdc);
+
+    let ix = s.borrow("borrow_secure", VALUE_LIMIT);
+    let (position_data, wallet_data) = s.borrowed_data(VALUE_LIMIT);
+    let checks = [
+        Check::success(),
+        Check::account(&position).data(&position_data).build(),
+        Check::account(&wallet).data(&wallet_data).build(),
+    ];
+    s.ledger.step(&mollusk, &ix, &checks);
+}
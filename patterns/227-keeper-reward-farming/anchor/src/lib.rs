@@ -0,0 +1,235 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+use rent_reserve::withdrawable_lamports;
+
+declare_id!("A415AphsxraW3fQinasqkqZ4jyp4uFXDZRqjKnRJ29n6");
+
+/// Index units the pool accrues per slot. The index starts at 1e9.
+pub const RATE_PER_SLOT: u64 = 10;
+/// The vulnerable reward: lamports per call, sized for a call every 100
+/// slots.
+pub const REWARD_PER_CALL: u64 = 100_000;
+/// The secure reward: lamports per slot brought up to date.
+pub const REWARD_PER_SLOT: u64 = 1_000;
+/// Slots one call is paid for at most, however long the pool went stale.
+pub const MAX_REWARD_SLOTS: u64 = 1_000;
+
+/// # Keeper Reward Farming
+///
+/// ## The Vulnerability
+/// A pool's interest index has to be brought up to date as slots pass, and
+/// anyone can do it: `do_maintenance` accrues interest for the slots since
+/// the last update and pays the caller a keeper reward out of the pool.
+/// The vulnerable version pays a flat reward per call. It never asks
+/// whether there was anything to do, so a call in a slot the pool was
+/// already updated in accrues nothing and is paid in full.
+///
+/// ## Why It Matters
+/// A bot can call it again and again in the same slot, or bundle dozens
+/// of calls in one transaction, and collect a reward for each. The
+/// incentive meant to pay for upkeep pays for spam instead, and the pool
+/// that funds it drains at the rate of the bot's transaction fees, not
+/// the rate its index actually needs updating.
+///
+/// ## The Fix
+/// Pay for work that can be measured in state. `do_maintenance_secure`
+/// refuses a call with no slots to accrue, and pays per slot it brought
+/// up to date, up to a cap. One call every 100 slots and one call every
+/// slot earn the same; a second call in the same slot earns nothing.
+#[program]
+pub mod keeper_reward_farming {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.admin = ctx.accounts.admin.key();
+        pool.index = 1_000_000_000;
+        pool.last_slot = Clock::get()?.slot;
+        pool.rewards_paid = 0;
+        pool.bump = ctx.bumps.pool;
+        demo_log!(
+            Mode::Setup,
+            "initialize",
+            actor = pool.admin,
+            slot = pool.last_slot
+        );
+        Ok(())
+    }
+
+    /// Pay `amount` lamports into the pool's keeper rewards.
+    pub fn fund(ctx: Context<Fund>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "fund",
+            actor = ctx.accounts.payer.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Keeper paid per call
+    // ============================================================================
+    // ISSUE: The reward doesn't depend on what the call did. When the pool
+    //        was already updated this slot, `elapsed` is 0, nothing
+    //        accrues, and the keeper is still paid REWARD_PER_CALL.
+    //
+    // ATTACK SCENARIO:
+    //   1. A keeper updates the pool at slot 10,000
+    //   2. Mallory calls do_maintenance_vulnerable five times in slot 10,000
+    //   3. The index doesn't move, and Mallory is paid 500,000 lamports
+    // ============================================================================
+    pub fn do_maintenance_vulnerable(ctx: Context<Maintain>) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let elapsed = slot.saturating_sub(ctx.accounts.pool.last_slot);
+        accrue(&mut ctx.accounts.pool, slot, elapsed)?;
+
+        // VULNERABLE: paid whether or not there was anything to do
+        pay_keeper(
+            &mut ctx.accounts.pool,
+            &ctx.accounts.keeper,
+            REWARD_PER_CALL,
+        )?;
+        demo_log!(
+            Mode::Vulnerable,
+            "maintain",
+            actor = ctx.accounts.keeper.key(),
+            elapsed = elapsed,
+            reward = REWARD_PER_CALL
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Keeper paid per slot brought up to date
+    // ============================================================================
+    // FIX: The work is the slots since `last_slot`, which the pool records.
+    //      A call with none is refused, and a call with some is paid
+    //      REWARD_PER_SLOT for each, up to MAX_REWARD_SLOTS. Splitting the
+    //      same slots across more calls earns nothing extra.
+    // ============================================================================
+    pub fn do_maintenance_secure(ctx: Context<Maintain>) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let elapsed = slot.saturating_sub(ctx.accounts.pool.last_slot);
+        // SECURE: no work, no reward
+        require!(elapsed > 0, ErrorCode::NothingToDo);
+        accrue(&mut ctx.accounts.pool, slot, elapsed)?;
+
+        // SECURE: paid for the work done, not for the call
+        let reward = elapsed
+            .min(MAX_REWARD_SLOTS)
+            .checked_mul(REWARD_PER_SLOT)
+            .ok_or(ErrorCode::Overflow)?;
+        pay_keeper(&mut ctx.accounts.pool, &ctx.accounts.keeper, reward)?;
+        demo_log!(
+            Mode::Secure,
+            "maintain",
+            actor = ctx.accounts.keeper.key(),
+            elapsed = elapsed,
+            reward = reward
+        );
+        Ok(())
+    }
+}
+
+/// Accrue `elapsed` slots of interest and mark the pool current at `slot`.
+fn accrue(pool: &mut Pool, slot: u64, elapsed: u64) -> Result<()> {
+    let interest = elapsed
+        .checked_mul(RATE_PER_SLOT)
+        .ok_or(ErrorCode::Overflow)?;
+    pool.index = pool
+        .index
+        .checked_add(interest)
+        .ok_or(ErrorCode::Overflow)?;
+    pool.last_slot = slot;
+    Ok(())
+}
+
+/// Pay `reward` lamports from the pool to the keeper, leaving the pool
+/// rent-exempt.
+fn pay_keeper<'info>(
+    pool: &mut Account<'info, Pool>,
+    keeper: &Signer<'info>,
+    reward: u64,
+) -> Result<()> {
+    let available = withdrawable_lamports(&pool.to_account_info())?;
+    require!(reward <= available, ErrorCode::InsufficientRewards);
+    pool.sub_lamports(reward)?;
+    keeper.add_lamports(reward)?;
+    pool.rewards_paid = pool
+        .rewards_paid
+        .checked_add(reward)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Fund<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Maintain<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    /// Anyone: maintenance is permissionless, and the reward goes here.
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+/// The pool's interest index, when it was last brought up to date, and
+/// what keepers have been paid. Keeper rewards are the lamports it holds
+/// above its rent-exempt minimum.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub admin: Pubkey,     // 32 bytes
+    pub index: u64,        //  8 bytes: accrued interest index
+    pub last_slot: u64,    //  8 bytes: slot the index is current to
+    pub rewards_paid: u64, //  8 bytes: lamports paid to keepers
+    pub bump: u8,          //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The pool is already up to date")]
+    NothingToDo,
+    #[msg("Not enough rewards left in the pool")]
+    InsufficientRewards,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,221 @@
+// Mollusk tests for Pattern 227
+/// # Pattern 227: Keeper Reward Farming — Mollusk Exploit Tests
+///
+/// It is slot 10,000. The pool holds 1 SOL of keeper rewards above its
+/// rent-exempt minimum, and its index is 1,000,000,000. Unless a test says
+/// otherwise, the pool was brought up to date this slot. Mallory is the
+/// keeper in every test.
+///
+/// - Test 1: EXPLOIT — five do_maintenance_vulnerable calls in slot 10,000
+///   accrue nothing and pay Mallory 500,000 lamports.
+/// - Test 2: SECURE — do_maintenance_secure refuses a call with nothing to
+///   do.
+/// - Test 3: SECURE — 100 stale slots pay 100,000 lamports whether Mallory
+///   updates them in one call or in a hundred.
+/// - Test 4: SECURE — a call after 5,000 stale slots is paid for 1,000.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("A415AphsxraW3fQinasqkqZ4jyp4uFXDZRqjKnRJ29n6");
+
+const WALLET: u64 = 1_000_000_000;
+const FUNDS: u64 = 1_000_000_000;
+const SLOT: u64 = 10_000;
+
+const INDEX: u64 = 1_000_000_000;
+const RATE_PER_SLOT: u64 = 10;
+const REWARD_PER_CALL: u64 = 100_000;
+const REWARD_PER_SLOT: u64 = 1_000;
+const MAX_REWARD_SLOTS: u64 = 1_000;
+
+/// 8 discriminator + 32 admin + 8 index + 8 last_slot + 8 rewards_paid +
+/// 1 bump.
+const POOL_LEN: usize = 65;
+
+// Error codes
+const NOTHING_TO_DO: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account:
+///   [8 disc][32 admin][8 index][8 last_slot][8 rewards_paid][1 bump]
+fn serialize_pool(
+    admin: &Pubkey,
+    index: u64,
+    last_slot: u64,
+    rewards_paid: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(POOL_LEN);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&index.to_le_bytes());
+    data.extend_from_slice(&last_slot.to_le_bytes());
+    data.extend_from_slice(&rewards_paid.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    pool: Pubkey,
+    admin: Pubkey,
+    bump: u8,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A funded pool last brought up to date at `last_slot`, at SLOT.
+    fn new(last_slot: u64) -> Self {
+        let mut mollusk = Mollusk::new(&PROGRAM_ID, "keeper_reward_farming");
+        mollusk.sysvars.clock.slot = SLOT;
+        let admin = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let (pool, bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+
+        let mut pool_account = AccountSharedData::new(Self::rent() + FUNDS, POOL_LEN, &PROGRAM_ID);
+        pool_account.set_data_from_slice(&serialize_pool(&admin, INDEX, last_slot, 0, bump));
+
+        let ledger = vec![(pool, pool_account), (mallory, system_account())];
+
+        Self {
+            mollusk,
+            pool,
+            admin,
+            bump,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn rent() -> u64 {
+        Rent::default().minimum_balance(POOL_LEN)
+    }
+
+    /// do_maintenance_vulnerable or do_maintenance_secure, by Mallory.
+    fn maintain(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(self.mallory, true),
+            ],
+        )
+    }
+
+    /// Assert the pool is current to `last_slot` at `index`, and has paid
+    /// Mallory `paid` lamports in all.
+    fn assert_paid(&self, index: u64, last_slot: u64, paid: u64) {
+        let pool = self.ledger.account(&self.pool);
+        assert_eq!(
+            pool.data(),
+            serialize_pool(&self.admin, index, last_slot, paid, self.bump)
+        );
+        assert_eq!(pool.lamports(), Self::rent() + FUNDS - paid);
+        assert_eq!(self.ledger.lamports(&self.mallory), WALLET + paid);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_noop_maintenance_paid_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The pool was brought up to date at slot 10,000
+    //   Mallory: do_maintenance_vulnerable × 5, all in slot 10,000
+    //
+    // Expected: all SUCCEED. The index doesn't move, and each call pays
+    //           100,000 lamports: 500,000 for no work.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(SLOT);
+
+    let ix = s.maintain("do_maintenance_vulnerable");
+    for _ in 0..5 {
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    }
+    s.assert_paid(INDEX, SLOT, 5 * REWARD_PER_CALL);
+}
+
+#[test]
+fn secure_noop_maintenance_refused() {
+    // -----------------------------------------------------------------------
+    // SECURE: The pool was brought up to date at slot 10,000
+    //   Mallory: do_maintenance_secure, in slot 10,000
+    //
+    // Expected: FAILS with NothingToDo (6000). Nothing is paid.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(SLOT);
+
+    let ix = s.maintain("do_maintenance_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(NOTHING_TO_DO))]);
+    s.assert_paid(INDEX, SLOT, 0);
+}
+
+#[test]
+fn secure_reward_same_however_work_is_split() {
+    // -----------------------------------------------------------------------
+    // SECURE: The pool was last brought up to date 100 slots ago
+    //   1. Mallory: do_maintenance_secure at slot 10,000, then again in the
+    //      same slot
+    //   2. Separately, from the same start: do_maintenance_secure once in
+    //      each of slots 9,901 to 10,000
+    //
+    // Expected: in 1, the first call is paid 100,000 for 100 slots and the
+    //           second FAILS with NothingToDo. In 2, a hundred calls are
+    //           paid 1,000 each: the same 100,000 for the same work.
+    // -----------------------------------------------------------------------
+    let start = SLOT - 100;
+    let index = INDEX + 100 * RATE_PER_SLOT;
+    let reward = 100 * REWARD_PER_SLOT;
+
+    let mut s = Setup::new(start);
+    let ix = s.maintain("do_maintenance_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(NOTHING_TO_DO))]);
+    s.assert_paid(index, SLOT, reward);
+
+    let mut s = Setup::new(start);
+    let ix = s.maintain("do_maintenance_secure");
+    for slot in start + 1..=SLOT {
+        s.mollusk.sysvars.clock.slot = slot;
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    }
+    s.assert_paid(index, SLOT, reward);
+}
+
+#[test]
+fn secure_reward_capped_after_long_gap() {
+    // -----------------------------------------------------------------------
+    // SECURE: The pool was last brought up to date 5,000 slots ago
+    //   Mallory: do_maintenance_secure
+    //
+    // Expected: SUCCEEDS. All 5,000 slots accrue, but the reward is for
+    //           1,000 of them: 1,000,000 lamports.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(SLOT - 5_000);
+
+    let ix = s.maintain("do_maintenance_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_paid(
+        INDEX + 5_000 * RATE_PER_SLOT,
+        SLOT,
+        MAX_REWARD_SLOTS * REWARD_PER_SLOT,
+    );
+}
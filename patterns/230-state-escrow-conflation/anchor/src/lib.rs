@@ -0,0 +1,366 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+use rent_reserve::withdrawable_lamports;
+
+declare_id!("4iZYAxjUHQh3KruU2YHf1gxuMFzaXkgm3QDjonJ3pcz1");
+
+/// Longest note a deal can carry.
+pub const MAX_NOTE_LEN: usize = 512;
+
+/// A deal's length with a note of `note_len` bytes: 8 disc + 32 buyer +
+/// 32 seller + 32 arbiter + 8 amount + 1 settled + 1 bump + 4 + note.
+pub const fn deal_space(note_len: usize) -> usize {
+    8 + 32 + 32 + 32 + 8 + 1 + 1 + 4 + note_len
+}
+
+/// # State/Escrow Conflation
+///
+/// ## The Vulnerability
+/// A buyer pays a seller through escrow: the buyer deposits the price, and
+/// an arbiter releases it to the seller once the goods arrive. The buyer
+/// can edit a delivery note on the deal at any time. The vulnerable deal
+/// PDA holds the deal's state and the deposit both, so its balance is
+/// rent plus escrow, and nothing but the arithmetic tells them apart.
+/// Editing the note resizes the deal with Anchor's `realloc`, which treats
+/// the whole balance as rent: shrinking refunds everything above the new
+/// minimum to the buyer, deposit included, and growing takes the new rent
+/// out of the deposit instead of charging the buyer.
+///
+/// ## Why It Matters
+/// The deposit is the seller's once the goods ship, and the buyer can take
+/// it back by shortening the note. Growing is quieter: the seller is paid
+/// short by the new rent, and the buyer reclaims that rent when the deal
+/// closes. Any lifecycle step written for an account's data, whether
+/// realloc, close, or a rent sweep, moves its lamports too, and can't know
+/// which of them belong to someone else.
+///
+/// ## The Fix
+/// Keep the deposit out of the state account. `open_secure` puts it in a
+/// system-owned escrow PDA with no data, derived from the deal, and
+/// `release_secure` pays the seller the recorded amount from it. The deal
+/// holds only its rent, so resizing or closing it touches only rent, and
+/// the escrow has no data to resize and nothing to close.
+#[program]
+pub mod state_escrow_conflation {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Deposit held in the deal's own state account
+    // ============================================================================
+    // ISSUE: The deal's balance is its rent and the deposit together, and
+    //        every instruction that resizes or closes it treats the whole
+    //        balance as rent. set_note's realloc refunds what's above the
+    //        new minimum to the buyer when the note shrinks, and spends the
+    //        deposit on rent when it grows.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory opens a 1 SOL deal with Alice, with a 64-byte note
+    //   2. Alice ships. Mallory sets the note to empty; realloc refunds
+    //      1 SOL and the note's rent to Mallory
+    //   3. The arbiter releases the deal. Nothing is above rent, and Alice
+    //      is paid 0
+    // ============================================================================
+    pub fn open_vulnerable(
+        ctx: Context<OpenVulnerable>,
+        seller: Pubkey,
+        arbiter: Pubkey,
+        amount: u64,
+        note: Vec<u8>,
+    ) -> Result<()> {
+        require!(note.len() <= MAX_NOTE_LEN, ErrorCode::NoteTooLong);
+        // VULNERABLE: the deposit goes into the account that holds state
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.deal.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let buyer = ctx.accounts.buyer.key();
+        write_deal(
+            &mut ctx.accounts.deal,
+            buyer,
+            seller,
+            arbiter,
+            amount,
+            note,
+            ctx.bumps.deal,
+        );
+        demo_log!(
+            Mode::Vulnerable,
+            "open",
+            actor = buyer,
+            seller = seller,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    pub fn release_vulnerable(ctx: Context<ReleaseVulnerable>) -> Result<()> {
+        require!(!ctx.accounts.deal.settled, ErrorCode::AlreadySettled);
+        // VULNERABLE: the escrow is whatever is above rent, and rent moves
+        let paid = withdrawable_lamports(&ctx.accounts.deal.to_account_info())?;
+        ctx.accounts.deal.sub_lamports(paid)?;
+        ctx.accounts.seller.add_lamports(paid)?;
+        ctx.accounts.deal.settled = true;
+        demo_log!(
+            Mode::Vulnerable,
+            "release",
+            actor = ctx.accounts.arbiter.key(),
+            amount = ctx.accounts.deal.amount,
+            paid = paid
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: State PDA and escrow PDA, each with one lifecycle
+    // ============================================================================
+    // FIX: The deposit goes to a system-owned escrow PDA with no data,
+    //      seeded by the deal. The deal holds only rent, so set_note and
+    //      close move only rent. release_secure pays the recorded amount
+    //      out of the escrow, whatever the deal's size.
+    // ============================================================================
+    pub fn open_secure(
+        ctx: Context<OpenSecure>,
+        seller: Pubkey,
+        arbiter: Pubkey,
+        amount: u64,
+        note: Vec<u8>,
+    ) -> Result<()> {
+        require!(note.len() <= MAX_NOTE_LEN, ErrorCode::NoteTooLong);
+        // SECURE: the deposit goes to an account with no state
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let buyer = ctx.accounts.buyer.key();
+        write_deal(
+            &mut ctx.accounts.deal,
+            buyer,
+            seller,
+            arbiter,
+            amount,
+            note,
+            ctx.bumps.deal,
+        );
+        demo_log!(
+            Mode::Secure,
+            "open",
+            actor = buyer,
+            seller = seller,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    pub fn release_secure(ctx: Context<ReleaseSecure>) -> Result<()> {
+        require!(!ctx.accounts.deal.settled, ErrorCode::AlreadySettled);
+        // SECURE: the recorded amount, from the account that holds only it
+        let amount = ctx.accounts.deal.amount;
+        let deal = ctx.accounts.deal.key();
+        let seeds: &[&[u8]] = &[b"escrow", deal.as_ref(), &[ctx.bumps.escrow]];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.seller.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        ctx.accounts.deal.settled = true;
+        demo_log!(
+            Mode::Secure,
+            "release",
+            actor = ctx.accounts.arbiter.key(),
+            amount = amount,
+            paid = amount
+        );
+        Ok(())
+    }
+
+    /// Replace the deal's note, resizing the deal to fit. Anchor's realloc
+    /// charges the buyer whatever rent the deal's balance doesn't cover,
+    /// and refunds the buyer everything above the new minimum. Both
+    /// variants share it; it is safe only when the deal holds nothing but
+    /// rent.
+    pub fn set_note(ctx: Context<SetNote>, note: Vec<u8>) -> Result<()> {
+        require!(note.len() <= MAX_NOTE_LEN, ErrorCode::NoteTooLong);
+        let deal = &mut ctx.accounts.deal;
+        let len = note.len();
+        deal.note = note;
+        demo_log!(Mode::Setup, "set_note", actor = deal.buyer, len = len);
+        Ok(())
+    }
+
+    /// Close a settled deal, returning its balance to the buyer who paid
+    /// its rent.
+    pub fn close(ctx: Context<Close>) -> Result<()> {
+        require!(ctx.accounts.deal.settled, ErrorCode::NotSettled);
+        demo_log!(
+            Mode::Setup,
+            "close",
+            actor = ctx.accounts.buyer.key(),
+            lamports = ctx.accounts.deal.get_lamports()
+        );
+        Ok(())
+    }
+}
+
+fn write_deal(
+    deal: &mut Deal,
+    buyer: Pubkey,
+    seller: Pubkey,
+    arbiter: Pubkey,
+    amount: u64,
+    note: Vec<u8>,
+    bump: u8,
+) {
+    deal.buyer = buyer;
+    deal.seller = seller;
+    deal.arbiter = arbiter;
+    deal.amount = amount;
+    deal.settled = false;
+    deal.bump = bump;
+    deal.note = note;
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(seller: Pubkey, arbiter: Pubkey, amount: u64, note: Vec<u8>)]
+pub struct OpenVulnerable<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = deal_space(note.len()),
+        seeds = [b"deal", buyer.key().as_ref()],
+        bump
+    )]
+    pub deal: Account<'info, Deal>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(seller: Pubkey, arbiter: Pubkey, amount: u64, note: Vec<u8>)]
+pub struct OpenSecure<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = deal_space(note.len()),
+        seeds = [b"deal", buyer.key().as_ref()],
+        bump
+    )]
+    pub deal: Account<'info, Deal>,
+    /// Holds the deposit, and nothing else.
+    #[account(mut, seeds = [b"escrow", deal.key().as_ref()], bump)]
+    pub escrow: SystemAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [b"deal", deal.buyer.as_ref()],
+        bump = deal.bump,
+        has_one = seller,
+        has_one = arbiter
+    )]
+    pub deal: Account<'info, Deal>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+    pub arbiter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseSecure<'info> {
+    #[account(
+        mut,
+        seeds = [b"deal", deal.buyer.as_ref()],
+        bump = deal.bump,
+        has_one = seller,
+        has_one = arbiter
+    )]
+    pub deal: Account<'info, Deal>,
+    #[account(mut, seeds = [b"escrow", deal.key().as_ref()], bump)]
+    pub escrow: SystemAccount<'info>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+    pub arbiter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(note: Vec<u8>)]
+pub struct SetNote<'info> {
+    #[account(
+        mut,
+        seeds = [b"deal", buyer.key().as_ref()],
+        bump = deal.bump,
+        has_one = buyer,
+        realloc = deal_space(note.len()),
+        realloc::payer = buyer,
+        realloc::zero = false
+    )]
+    pub deal: Account<'info, Deal>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Close<'info> {
+    #[account(
+        mut,
+        seeds = [b"deal", buyer.key().as_ref()],
+        bump = deal.bump,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub deal: Account<'info, Deal>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+/// One buyer's deal with a seller. Sized to its note, so its rent changes
+/// whenever the note does.
+#[account]
+pub struct Deal {
+    pub buyer: Pubkey,   // 32 bytes: paid the rent and the deposit
+    pub seller: Pubkey,  // 32 bytes: paid on release
+    pub arbiter: Pubkey, // 32 bytes: releases the deposit
+    pub amount: u64,     //  8 bytes: the deposit
+    pub settled: bool,   //  1 byte
+    pub bump: u8,        //  1 byte
+    pub note: Vec<u8>,   // 4 + note bytes: the buyer's, for the seller
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Note is longer than 512 bytes")]
+    NoteTooLong,
+    #[msg("Deal was already released")]
+    AlreadySettled,
+    #[msg("Deal has not been released")]
+    NotSettled,
+}
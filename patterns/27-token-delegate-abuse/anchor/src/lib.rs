@@ -0,0 +1,345 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{self, Approve, Revoke, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("HyGffsFtqfkNXPZTmLr7m9gi5bFAZLwZ448REherDEW");
+
+/// # Token Delegate Abuse
+///
+/// This program demonstrates a vault that fills deposit orders through a
+/// keeper holding an SPL Token delegate approval on the depositor's
+/// token account.
+///
+/// ## The Vulnerability
+/// The vulnerable flow records an order and lets the keeper pull the
+/// amount as the account's delegate. How much the keeper was approved for
+/// is up to the frontend, which approves `u64::MAX` so it never has to ask
+/// again. The program never compares the approval with the order and never
+/// revokes it, so the keeper can still move the depositor's tokens after
+/// the order is filled.
+///
+/// ## Real-World Impact
+/// A leaked keeper key, a rogue operator, or a malicious frontend that
+/// slipped its own key in as delegate empties every wallet that ever
+/// placed an order, long after the deposits were made.
+///
+/// ## The Fix
+/// The program makes the approval itself, for exactly the order amount,
+/// which replaces any earlier approval. The keeper can only fill an order
+/// whose approval still matches it, and filling it uses the approval up,
+/// so SPL Token clears the delegate. Cancelling revokes it.
+#[program]
+pub mod token_delegate_abuse {
+    use super::*;
+
+    pub fn create_vault(ctx: Context<CreateVault>, keeper: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.mint = ctx.accounts.mint.key();
+        vault.vault_token = ctx.accounts.vault_token.key();
+        vault.keeper = keeper;
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "create_vault",
+            actor = ctx.accounts.payer.key(),
+            keeper = keeper
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.vault = ctx.accounts.vault.key();
+        position.owner = ctx.accounts.owner.key();
+        position.deposited = 0;
+        position.pending = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Keeper fills orders with an approval nobody bounds or revokes
+    // ============================================================================
+    // ISSUE: The order only records an amount. The approval that lets the
+    //        keeper pull it is made by the frontend, for whatever it chose,
+    //        and it outlives the order.
+    //
+    // ATTACK SCENARIO:
+    //   1. Frontend approves the keeper for u64::MAX next to place_order
+    //   2. Keeper fills the 100 token order; 900 tokens stay in the wallet
+    //   3. The keeper is still the account's delegate for u64::MAX - 100
+    //   4. Keeper transfers the other 900 straight to itself through SPL Token
+    // ============================================================================
+    pub fn place_order_vulnerable(ctx: Context<PlaceOrderVulnerable>, amount: u64) -> Result<()> {
+        // VULNERABLE: relies on an approval this program never sees
+        ctx.accounts.position.pending = amount;
+        demo_log!(
+            Mode::Vulnerable,
+            "place_order",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    pub fn execute_order_vulnerable(ctx: Context<ExecuteOrderVulnerable>) -> Result<()> {
+        let amount = ctx.accounts.position.pending;
+        require!(amount > 0, ErrorCode::NoPendingOrder);
+
+        // VULNERABLE: delegated_amount is never read, and whatever the
+        // transfer leaves of it stays with the keeper
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token.to_account_info(),
+                    to: ctx.accounts.vault_token.to_account_info(),
+                    authority: ctx.accounts.keeper.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        fill(&mut ctx.accounts.position, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "execute_order",
+            actor = ctx.accounts.keeper.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Approval made by the program, bounded to the order, used up
+    // ============================================================================
+    // FIX: place_order_secure approves the keeper for exactly the order,
+    //      replacing any earlier approval. execute_order_secure requires the
+    //      approval to still match the order, so the transfer spends all of
+    //      it and SPL Token clears the delegate. cancel_order_secure revokes.
+    // ============================================================================
+    pub fn place_order_secure(ctx: Context<PlaceOrderSecure>, amount: u64) -> Result<()> {
+        // SECURE: Approve replaces the delegate and the amount outright
+        token::approve(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Approve {
+                    to: ctx.accounts.user_token.to_account_info(),
+                    delegate: ctx.accounts.keeper.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        ctx.accounts.position.pending = amount;
+        demo_log!(
+            Mode::Secure,
+            "place_order",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    pub fn execute_order_secure(ctx: Context<ExecuteOrderSecure>) -> Result<()> {
+        let amount = ctx.accounts.position.pending;
+        require!(amount > 0, ErrorCode::NoPendingOrder);
+
+        // SECURE: the transfer must use up the whole approval
+        let user_token = &ctx.accounts.user_token;
+        require!(
+            user_token.delegate == COption::Some(ctx.accounts.keeper.key())
+                && user_token.delegated_amount == amount,
+            ErrorCode::ApprovalNotBounded
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token.to_account_info(),
+                    to: ctx.accounts.vault_token.to_account_info(),
+                    authority: ctx.accounts.keeper.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        fill(&mut ctx.accounts.position, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "execute_order",
+            actor = ctx.accounts.keeper.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    pub fn cancel_order_secure(ctx: Context<CancelOrderSecure>) -> Result<()> {
+        // SECURE: a cancelled order leaves no delegate behind
+        token::revoke(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Revoke {
+                source: ctx.accounts.user_token.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ))?;
+        ctx.accounts.position.pending = 0;
+        demo_log!(
+            Mode::Secure,
+            "cancel_order",
+            actor = ctx.accounts.owner.key()
+        );
+        Ok(())
+    }
+}
+
+fn fill(position: &mut Account<Position>, amount: u64) -> Result<()> {
+    position.deposited = position
+        .deposited
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    position.pending = 0;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    pub mint: Account<'info, token::Mint>,
+    #[account(token::mint = mint, token::authority = vault)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceOrderVulnerable<'info> {
+    #[account(mut, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteOrderVulnerable<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = vault_token,
+        has_one = keeper
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.mint, token::authority = position.owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault)]
+    pub position: Account<'info, Position>,
+    pub keeper: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceOrderSecure<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = keeper
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, token::mint = vault.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    /// CHECK: Matched against `vault.keeper`; only named as the delegate.
+    pub keeper: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteOrderSecure<'info> {
+    #[account(
+        seeds = [b"vault", vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = vault_token,
+        has_one = keeper
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.mint, token::authority = position.owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault)]
+    pub position: Account<'info, Position>,
+    pub keeper: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOrderSecure<'info> {
+    #[account(mut, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// The vault PDA owns `vault_token`. Only `keeper` fills orders.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub mint: Pubkey,        // 32 bytes
+    pub vault_token: Pubkey, // 32 bytes
+    pub keeper: Pubkey,      // 32 bytes
+    pub bump: u8,            //  1 byte
+}
+
+/// Filled deposits, plus the order waiting for the keeper.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub vault: Pubkey,  // 32 bytes
+    pub owner: Pubkey,  // 32 bytes
+    pub deposited: u64, //  8 bytes
+    pub pending: u64,   //  8 bytes
+    pub bump: u8,       //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("No order is waiting to be filled")]
+    NoPendingOrder,
+    #[msg("The keeper's approval doesn't match the order")]
+    ApprovalNotBounded,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,502 @@
+/// # Pattern 27: Token Delegate Abuse — Mollusk Exploit Tests
+///
+/// The victim's wallet holds 1,000 tokens and they have an empty position
+/// in the vault. The keeper fills deposit orders and has a token account
+/// of its own for the vault's mint.
+///
+/// - Test 1: EXPLOIT — the frontend approved the keeper for u64::MAX. The
+///   keeper fills a 100 token order through execute_order_vulnerable, then
+///   transfers the other 900 straight to itself with the approval it kept.
+/// - Test 2: SECURE — place_order_secure replaces the same leftover
+///   approval with one for 100. Filling the order uses it up, and the
+///   keeper's direct transfer fails.
+/// - Test 3: SECURE — an approval widened after the order is placed is
+///   refused by execute_order_secure, and cancel_order_secure revokes it.
+///
+/// The program must be built into SBF_OUT_DIR next to `spl_token.so`.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("HyGffsFtqfkNXPZTmLr7m9gi5bFAZLwZ448REherDEW");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const TOKEN_RENT: u64 = 2_039_280;
+const MINT_RENT: u64 = 1_461_600;
+const WALLET: u64 = 10_000_000_000;
+
+const DECIMALS: u8 = 6;
+/// Tokens in the victim's wallet.
+const HELD: u64 = 1_000;
+/// The victim's deposit order.
+const ORDER: u64 = 100;
+
+// Error codes
+const APPROVAL_NOT_BOUNDED: u32 = 6001;
+/// SPL Token's OwnerMismatch: the signer is neither owner nor delegate.
+const OWNER_MISMATCH: u32 = 4;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account:
+///   [8 disc][32 mint][32 vault_token][32 keeper][1 bump]
+fn serialize_vault(mint: &Pubkey, vault_token: &Pubkey, keeper: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(105);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(vault_token.as_ref());
+    data.extend_from_slice(keeper.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 vault][32 owner][8 deposited][8 pending][1 bump]
+fn serialize_position(
+    vault: &Pubkey,
+    owner: &Pubkey,
+    deposited: u64,
+    pending: u64,
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(89);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&deposited.to_le_bytes());
+    data.extend_from_slice(&pending.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with no authorities.
+fn serialize_mint(supply: u64) -> Vec<u8> {
+    Mint {
+        supply,
+        decimals: DECIMALS,
+        ..Mint::default()
+    }
+    .pack()
+}
+
+/// Serialize an SPL Token account, with `delegate` approved for an amount.
+fn serialize_token_account(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    delegate: Option<(&Pubkey, u64)>,
+) -> Vec<u8> {
+    TokenAccount {
+        delegate: delegate.map(|(key, amount)| (*key, amount)),
+        ..TokenAccount::new(mint, owner, amount)
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "token_delegate_abuse");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// SPL Token's Transfer, signed by `authority` as owner or delegate.
+fn spl_transfer(from: &Pubkey, to: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![3]; // TokenInstruction::Transfer
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+/// SPL Token's Approve, signed by the account's owner.
+fn spl_approve(source: &Pubkey, delegate: &Pubkey, owner: &Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![4]; // TokenInstruction::Approve
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*delegate, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+    )
+}
+
+struct Setup {
+    vault: Pubkey,
+    mint: Pubkey,
+    vault_token: Pubkey,
+    keeper: Pubkey,
+    keeper_token: Pubkey,
+    victim: Pubkey,
+    victim_token: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// `approval` is what the keeper may already move out of the victim's
+    /// wallet before any order is placed.
+    fn new(approval: Option<u64>) -> Self {
+        let mint = Pubkey::new_unique();
+        let vault_token = Pubkey::new_unique();
+        let keeper = Pubkey::new_unique();
+        let keeper_token = Pubkey::new_unique();
+        let victim = Pubkey::new_unique();
+        let victim_token = Pubkey::new_unique();
+        let (vault, vault_bump) =
+            Pubkey::find_program_address(&[b"vault", mint.as_ref()], &PROGRAM_ID);
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", vault.as_ref(), victim.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let delegate = approval.map(|amount| (&keeper, amount));
+        let ledger = vec![
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(&mint, &vault_token, &keeper, vault_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                mint,
+                owned_account(&serialize_mint(HELD), MINT_RENT, &TOKEN_PROGRAM_ID),
+            ),
+            (
+                vault_token,
+                owned_account(
+                    &serialize_token_account(&mint, &vault, 0, None),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (keeper, system_account()),
+            (
+                keeper_token,
+                owned_account(
+                    &serialize_token_account(&mint, &keeper, 0, None),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (victim, system_account()),
+            (
+                victim_token,
+                owned_account(
+                    &serialize_token_account(&mint, &victim, HELD, delegate),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (
+                position,
+                owned_account(
+                    &serialize_position(&vault, &victim, 0, 0, position_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+
+        Self {
+            vault,
+            mint,
+            vault_token,
+            keeper,
+            keeper_token,
+            victim,
+            victim_token,
+            position,
+            position_bump,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn place_order_vulnerable(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("place_order_vulnerable").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.victim, true),
+            ],
+        )
+    }
+
+    fn place_order_secure(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("place_order_secure").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(self.victim_token, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.keeper, false),
+                AccountMeta::new_readonly(self.victim, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// execute_order_vulnerable / execute_order_secure, signed by the keeper.
+    fn execute_order(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(self.vault_token, false),
+                AccountMeta::new(self.victim_token, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.keeper, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn cancel_order_secure(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("cancel_order_secure"),
+            vec![
+                AccountMeta::new(self.victim_token, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.victim, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// The keeper moves `amount` out of the victim's wallet into its own,
+    /// straight through SPL Token.
+    fn keeper_sweep(&self, amount: u64) -> Instruction {
+        spl_transfer(&self.victim_token, &self.keeper_token, &self.keeper, amount)
+    }
+
+    fn victim_token_data(&self, amount: u64, approval: Option<u64>) -> Vec<u8> {
+        let delegate = approval.map(|delegated| (&self.keeper, delegated));
+        serialize_token_account(&self.mint, &self.victim, amount, delegate)
+    }
+
+    fn position_data(&self, deposited: u64, pending: u64) -> Vec<u8> {
+        serialize_position(
+            &self.vault,
+            &self.victim,
+            deposited,
+            pending,
+            self.position_bump,
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_leftover_approval_drains_wallet_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   0. Frontend approved the keeper for u64::MAX on the victim's wallet
+    //   1. Victim: place_order_vulnerable(100)
+    //   2. Keeper: execute_order_vulnerable
+    //   3. Keeper: SPL Token transfer of 900 from the victim's wallet to its
+    //      own token account
+    //
+    // Expected: all SUCCEED. After the fill the victim's position reads 100
+    //           and the keeper is still delegate for u64::MAX - 100. The
+    //           direct transfer moves the rest of the wallet to the keeper.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(Some(u64::MAX));
+    let (vault_token, victim_token, keeper_token, position) =
+        (s.vault_token, s.victim_token, s.keeper_token, s.position);
+
+    let ix = s.place_order_vulnerable(ORDER);
+    let placed = s.position_data(0, ORDER);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position).data(&placed).build(),
+        ],
+    );
+
+    let ix = s.execute_order("execute_order_vulnerable");
+    let leftover = s.victim_token_data(HELD - ORDER, Some(u64::MAX - ORDER));
+    let filled = s.position_data(ORDER, 0);
+    let vault_held = serialize_token_account(&s.mint, &s.vault, ORDER, None);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&leftover).build(),
+            Check::account(&vault_token).data(&vault_held).build(),
+            Check::account(&position).data(&filled).build(),
+        ],
+    );
+
+    let ix = s.keeper_sweep(HELD - ORDER);
+    let emptied = s.victim_token_data(0, Some(u64::MAX - HELD));
+    let stolen = serialize_token_account(&s.mint, &s.keeper, HELD - ORDER, None);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&emptied).build(),
+            Check::account(&keeper_token).data(&stolen).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_fill_uses_up_approval() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same leftover u64::MAX approval, with the order placed and
+    // filled through the secure instructions.
+    //
+    // Expected: place_order_secure replaces the approval with one for 100.
+    //           execute_order_secure SUCCEEDS and spends all of it, so SPL
+    //           Token clears the delegate. The keeper's direct transfer then
+    //           FAILS with OwnerMismatch (4).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(Some(u64::MAX));
+    let (victim_token, position) = (s.victim_token, s.position);
+
+    let ix = s.place_order_secure(ORDER);
+    let bounded = s.victim_token_data(HELD, Some(ORDER));
+    let placed = s.position_data(0, ORDER);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&bounded).build(),
+            Check::account(&position).data(&placed).build(),
+        ],
+    );
+
+    let ix = s.execute_order("execute_order_secure");
+    let cleared = s.victim_token_data(HELD - ORDER, None);
+    let filled = s.position_data(ORDER, 0);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&cleared).build(),
+            Check::account(&position).data(&filled).build(),
+        ],
+    );
+
+    let ix = s.keeper_sweep(HELD - ORDER);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(OWNER_MISMATCH))],
+    );
+}
+
+#[test]
+fn secure_rejects_widened_approval_and_cancel_revokes() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Victim: place_order_secure(100)
+    //   2. Frontend: SPL Token approve of u64::MAX for the keeper, signed
+    //      by the victim
+    //   3. Keeper: execute_order_secure
+    //   4. Victim: cancel_order_secure
+    //   5. Keeper: SPL Token transfer of the whole wallet
+    //
+    // Expected: the fill FAILS with ApprovalNotBounded (6001) and nothing
+    //           moves. The cancel SUCCEEDS, clears the order, and revokes
+    //           the delegate, so the keeper's transfer FAILS with
+    //           OwnerMismatch (4).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(None);
+    let (victim_token, position) = (s.victim_token, s.position);
+
+    let ix = s.place_order_secure(ORDER);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = spl_approve(&s.victim_token, &s.keeper, &s.victim, u64::MAX);
+    let widened = s.victim_token_data(HELD, Some(u64::MAX));
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&widened).build(),
+        ],
+    );
+
+    let ix = s.execute_order("execute_order_secure");
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(APPROVAL_NOT_BOUNDED))],
+    );
+
+    let ix = s.cancel_order_secure();
+    let revoked = s.victim_token_data(HELD, None);
+    let cancelled = s.position_data(0, 0);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&victim_token).data(&revoked).build(),
+            Check::account(&position).data(&cancelled).build(),
+        ],
+    );
+
+    let ix = s.keeper_sweep(HELD);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(OWNER_MISMATCH))],
+    );
+}
@@ -0,0 +1,272 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("7ZtU6Kr7bQSJHfWk1fXeNG9hCngo18pYzDNsbsgkrbbm");
+
+/// # Freeze Authority
+///
+/// This program demonstrates a permissionless pool that anyone can open
+/// for any SPL Token mint, holding every depositor's tokens in one pool
+/// token account.
+///
+/// ## The Vulnerability
+/// The vulnerable creation accepts the mint as it is. An SPL Token mint
+/// can carry a freeze authority, and the freeze authority can freeze any
+/// token account of that mint, including one owned by a PDA. A frozen
+/// account can't send or receive tokens.
+///
+/// ## Real-World Impact
+/// Whoever holds the mint's freeze authority freezes the pool's token
+/// account once deposits are in. Every withdrawal fails until they thaw
+/// it, which makes the deposits a hostage: the authority can demand a
+/// ransom, or simply never thaw.
+///
+/// ## The Fix
+/// Only accept mints with no freeze authority, or whose freeze authority
+/// is the pool PDA itself. The program never signs a freeze, so in both
+/// cases nobody can lock the pool.
+#[program]
+pub mod freeze_authority {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Pool opened for a mint with a live freeze authority
+    // ============================================================================
+    // ISSUE: `mint.freeze_authority` is never read. Whoever holds it can
+    //        freeze `pool_token`, which holds every depositor's tokens.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker creates a mint with themselves as freeze authority
+    //   2. Attacker opens a pool for it with create_pool_vulnerable
+    //   3. Users deposit
+    //   4. Attacker freezes pool_token; every withdrawal fails
+    // ============================================================================
+    pub fn create_pool_vulnerable(ctx: Context<CreatePool>) -> Result<()> {
+        // VULNERABLE: any mint, whoever can freeze it
+        init_pool(
+            &mut ctx.accounts.pool,
+            &ctx.accounts.mint,
+            &ctx.accounts.pool_token,
+            ctx.bumps.pool,
+        );
+        demo_log!(
+            Mode::Vulnerable,
+            "create_pool",
+            actor = ctx.accounts.payer.key(),
+            mint = ctx.accounts.mint.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Only mints nobody but the pool can freeze
+    // ============================================================================
+    // FIX: The mint must have no freeze authority, or the pool PDA as its
+    //      freeze authority. The program has no instruction that freezes,
+    //      so pool_token can't be frozen in either case.
+    // ============================================================================
+    pub fn create_pool_secure(ctx: Context<CreatePool>) -> Result<()> {
+        // SECURE: a live freeze authority held by anyone else is refused
+        let freeze_authority = ctx.accounts.mint.freeze_authority;
+        require!(
+            freeze_authority == COption::None
+                || freeze_authority == COption::Some(ctx.accounts.pool.key()),
+            ErrorCode::FreezeAuthorityLive
+        );
+
+        init_pool(
+            &mut ctx.accounts.pool,
+            &ctx.accounts.mint,
+            &ctx.accounts.pool_token,
+            ctx.bumps.pool,
+        );
+        demo_log!(
+            Mode::Secure,
+            "create_pool",
+            actor = ctx.accounts.payer.key(),
+            mint = ctx.accounts.mint.key()
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.pool = ctx.accounts.pool.key();
+        position.owner = ctx.accounts.owner.key();
+        position.amount = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token.to_account_info(),
+                    to: ctx.accounts.pool_token.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let pool = &ctx.accounts.pool;
+        let seeds: &[&[u8]] = &[b"pool", pool.mint.as_ref(), &[pool.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+fn init_pool(
+    pool: &mut Account<Pool>,
+    mint: &Account<Mint>,
+    pool_token: &Account<TokenAccount>,
+    bump: u8,
+) {
+    pool.mint = mint.key();
+    pool.pool_token = pool_token.key();
+    pool.bump = bump;
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = pool)]
+    pub pool_token: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref()],
+        bump = pool.bump,
+        has_one = pool_token
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub pool_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref()],
+        bump = pool.bump,
+        has_one = pool_token
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub pool_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.mint, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// The pool PDA owns `pool_token`, which holds every deposit.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub mint: Pubkey,       // 32 bytes
+    pub pool_token: Pubkey, // 32 bytes
+    pub bump: u8,           //  1 byte
+}
+
+/// Deposited amount, in units of `pool.mint`.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub pool: Pubkey,  // 32 bytes
+    pub owner: Pubkey, // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The mint's freeze authority could freeze the pool")]
+    FreezeAuthorityLive,
+    #[msg("Withdrawal exceeds the position")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,341 @@
+/// # Pattern 28: Freeze Authority — Mollusk Exploit Tests
+///
+/// No pool exists yet for the mint. The pool token account is already
+/// created and owned by the pool PDA. The victim holds 1,000 tokens and
+/// an empty position in the pool-to-be. Who holds the mint's freeze
+/// authority depends on the test.
+///
+/// - Test 1: EXPLOIT — the attacker, the mint's freeze authority, opens
+///   the pool through create_pool_vulnerable. The victim deposits, the
+///   attacker freezes the pool token account, and the withdrawal fails.
+/// - Test 2: SECURE — create_pool_secure rejects the same mint.
+/// - Test 3: SANITY — create_pool_secure accepts a mint with no freeze
+///   authority and one frozen only by the pool PDA. The victim deposits
+///   and withdraws.
+///
+/// The program must be built into SBF_OUT_DIR next to `spl_token.so`.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{token_account, Mint, TokenAccount, FROZEN, INITIALIZED};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("7ZtU6Kr7bQSJHfWk1fXeNG9hCngo18pYzDNsbsgkrbbm");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const MINT_RENT: u64 = 1_461_600;
+const WALLET: u64 = 10_000_000_000;
+
+const DECIMALS: u8 = 6;
+/// The victim's deposit.
+const DEPOSIT: u64 = 1_000;
+
+// Error codes
+const FREEZE_AUTHORITY_LIVE: u32 = 6000;
+/// SPL Token's AccountFrozen.
+const ACCOUNT_FROZEN: u32 = 17;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Position account:
+///   [8 disc][32 pool][32 owner][8 amount][1 bump]
+fn serialize_position(pool: &Pubkey, owner: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with no mint authority.
+fn serialize_mint(supply: u64, freeze_authority: Option<&Pubkey>) -> Vec<u8> {
+    Mint {
+        supply,
+        decimals: DECIMALS,
+        freeze_authority: freeze_authority.copied(),
+        ..Mint::default()
+    }
+    .pack()
+}
+
+/// Serialize an SPL Token account in `state`, INITIALIZED or FROZEN.
+fn serialize_token_account(mint: &Pubkey, owner: &Pubkey, amount: u64, state: u8) -> Vec<u8> {
+    TokenAccount {
+        state,
+        ..TokenAccount::new(mint, owner, amount)
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "freeze_authority");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// SPL Token's FreezeAccount, signed by the mint's freeze authority.
+fn freeze_account(account: &Pubkey, mint: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &[10], // TokenInstruction::FreezeAccount
+        vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+/// Who holds the mint's freeze authority.
+enum Freeze {
+    Nobody,
+    Attacker,
+    Pool,
+}
+
+struct Setup {
+    pool: Pubkey,
+    mint: Pubkey,
+    pool_token: Pubkey,
+    attacker: Pubkey,
+    victim: Pubkey,
+    victim_token: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new(freeze: Freeze) -> Self {
+        let mint = Pubkey::new_unique();
+        let pool_token = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let victim = Pubkey::new_unique();
+        let victim_token = Pubkey::new_unique();
+        let (pool, _) = Pubkey::find_program_address(&[b"pool", mint.as_ref()], &PROGRAM_ID);
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", pool.as_ref(), victim.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let freeze_authority = match freeze {
+            Freeze::Nobody => None,
+            Freeze::Attacker => Some(&attacker),
+            Freeze::Pool => Some(&pool),
+        };
+        let ledger = vec![
+            (pool, AccountSharedData::default()),
+            (
+                mint,
+                owned_account(
+                    &serialize_mint(DEPOSIT, freeze_authority),
+                    MINT_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (pool_token, token_account(&TOKEN_PROGRAM_ID, &mint, &pool, 0)),
+            (attacker, system_account()),
+            (victim, system_account()),
+            (victim_token, token_account(&TOKEN_PROGRAM_ID, &mint, &victim, DEPOSIT)),
+            (
+                position,
+                owned_account(
+                    &serialize_position(&pool, &victim, 0, position_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+
+        Self {
+            pool,
+            mint,
+            pool_token,
+            attacker,
+            victim,
+            victim_token,
+            position,
+            position_bump,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// create_pool_vulnerable / create_pool_secure, paid for by `payer`.
+    fn create_pool(&self, name: &str, payer: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new_readonly(self.pool_token, false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// deposit / withdraw for the victim.
+    fn ix(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.pool, false),
+                AccountMeta::new(self.pool_token, false),
+                AccountMeta::new(self.victim_token, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.victim, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn pool_token_data(&self, amount: u64, state: u8) -> Vec<u8> {
+        serialize_token_account(&self.mint, &self.pool, amount, state)
+    }
+
+    fn position_data(&self, amount: u64) -> Vec<u8> {
+        serialize_position(&self.pool, &self.victim, amount, self.position_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_freeze_authority_locks_pool_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: create_pool_vulnerable for a mint they can freeze
+    //   2. Victim: deposit(1,000)
+    //   3. Attacker: SPL Token FreezeAccount on the pool token account
+    //   4. Victim: withdraw(1,000)
+    //
+    // Expected: steps 1-3 SUCCEED and the pool token account is Frozen
+    //           with the victim's 1,000 in it. The withdrawal FAILS with
+    //           AccountFrozen (17), and the position still reads 1,000.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(Freeze::Attacker);
+    let (pool_token, position) = (s.pool_token, s.position);
+
+    let ix = s.create_pool("create_pool_vulnerable", s.attacker);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.ix("deposit", DEPOSIT);
+    let deposited = s.position_data(DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position).data(&deposited).build(),
+        ],
+    );
+
+    let ix = freeze_account(&s.pool_token, &s.mint, &s.attacker);
+    let frozen = s.pool_token_data(DEPOSIT, FROZEN);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&pool_token).data(&frozen).build(),
+        ],
+    );
+
+    let ix = s.ix("withdraw", DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(ACCOUNT_FROZEN))],
+    );
+}
+
+#[test]
+fn secure_rejects_mint_with_live_freeze_authority() {
+    // -----------------------------------------------------------------------
+    // SECURE: The attacker opens the same pool through create_pool_secure.
+    //
+    // Expected: FAILS with FreezeAuthorityLive (6000). No pool exists, so
+    //           there is nothing to deposit into.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(Freeze::Attacker);
+
+    let ix = s.create_pool("create_pool_secure", s.attacker);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(FREEZE_AUTHORITY_LIVE))],
+    );
+}
+
+#[test]
+fn sanity_unfreezable_mints_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: create_pool_secure for a mint with no freeze authority, then
+    // for a mint whose freeze authority is the pool PDA. In the first
+    // pool, the victim deposits 1,000 and withdraws 400.
+    //
+    // Expected: all SUCCEED. The pool token account stays Initialized and
+    //           holds 600, and the position reads 600.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+
+    let mut s = Setup::new(Freeze::Pool);
+    let ix = s.create_pool("create_pool_secure", s.victim);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let mut s = Setup::new(Freeze::Nobody);
+    let (pool_token, position) = (s.pool_token, s.position);
+    let ix = s.create_pool("create_pool_secure", s.victim);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.ix("deposit", DEPOSIT);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.ix("withdraw", 400);
+    let held = s.pool_token_data(DEPOSIT - 400, INITIALIZED);
+    let remaining = s.position_data(DEPOSIT - 400);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&pool_token).data(&held).build(),
+            Check::account(&position).data(&remaining).build(),
+        ],
+    );
+}
@@ -0,0 +1,188 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{self, Mint, SetAuthority, Token};
+use demo_log::{demo_log, Mode};
+
+declare_id!("H8m2YjHZ5yDPr8edUpzUuie8jqJgwnNYcaANBGJsDwGg");
+
+/// # Mint Authority
+///
+/// This program demonstrates a launchpad that lists tokens as "fixed
+/// supply" once it has checked the mint's supply.
+///
+/// ## The Vulnerability
+/// The vulnerable listing compares `mint.supply` with the supply the
+/// creator announced, and records it. It never reads `mint.mint_authority`.
+/// While the creator holds the mint authority, the supply is fixed only
+/// until they decide otherwise: `MintTo` doesn't go through the
+/// launchpad.
+///
+/// ## Real-World Impact
+/// The listing tells buyers the supply is final. After the sale, the
+/// creator mints as much as they like and sells it into the market,
+/// diluting every holder who bought on the launchpad's word.
+///
+/// ## The Fix
+/// Only list a mint whose supply nobody can change. The secure listing
+/// accepts a mint with no mint authority, or one held by the launchpad's
+/// burn PDA, which the program never signs for. If the creator still holds
+/// the authority, the listing moves it to the burn PDA in the same
+/// instruction.
+#[program]
+pub mod mint_authority {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Supply checked, mint authority ignored
+    // ============================================================================
+    // ISSUE: `mint.supply` is correct at listing time, but the creator still
+    //        holds the mint authority, so the recorded supply is a promise
+    //        the program can't keep.
+    //
+    // ATTACK SCENARIO:
+    //   1. Creator mints 1,000,000 tokens and keeps the mint authority
+    //   2. list_vulnerable(1,000,000) passes and records "fixed supply"
+    //   3. Buyers buy on the strength of the listing
+    //   4. Creator mints 9,000,000 more straight through SPL Token
+    // ============================================================================
+    pub fn list_vulnerable(ctx: Context<ListVulnerable>, supply: u64) -> Result<()> {
+        // VULNERABLE: checks the supply today, not who can change it
+        require!(
+            ctx.accounts.mint.supply == supply,
+            ErrorCode::SupplyMismatch
+        );
+        record(
+            &mut ctx.accounts.listing,
+            &ctx.accounts.mint,
+            &ctx.accounts.creator,
+            ctx.bumps.listing,
+        );
+        demo_log!(
+            Mode::Vulnerable,
+            "list",
+            actor = ctx.accounts.creator.key(),
+            supply = supply
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Nobody but the burn PDA can mint
+    // ============================================================================
+    // FIX: The mint authority must be None or the launchpad's burn PDA. A
+    //      creator who still holds it hands it to the burn PDA here, with
+    //      their signature. The program has no instruction that signs for
+    //      the burn PDA, so the supply can't change again.
+    // ============================================================================
+    pub fn list_secure(ctx: Context<ListSecure>, supply: u64) -> Result<()> {
+        require!(
+            ctx.accounts.mint.supply == supply,
+            ErrorCode::SupplyMismatch
+        );
+
+        // SECURE: the creator's authority moves to the burn PDA; any other
+        // live authority is refused
+        let burn_authority = ctx.accounts.burn_authority.key();
+        let mint_authority = ctx.accounts.mint.mint_authority;
+        if mint_authority == COption::Some(ctx.accounts.creator.key()) {
+            token::set_authority(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    SetAuthority {
+                        current_authority: ctx.accounts.creator.to_account_info(),
+                        account_or_mint: ctx.accounts.mint.to_account_info(),
+                    },
+                ),
+                AuthorityType::MintTokens,
+                Some(burn_authority),
+            )?;
+        } else {
+            require!(
+                mint_authority == COption::None || mint_authority == COption::Some(burn_authority),
+                ErrorCode::MintAuthorityLive
+            );
+        }
+
+        record(
+            &mut ctx.accounts.listing,
+            &ctx.accounts.mint,
+            &ctx.accounts.creator,
+            ctx.bumps.listing,
+        );
+        demo_log!(
+            Mode::Secure,
+            "list",
+            actor = ctx.accounts.creator.key(),
+            supply = supply
+        );
+        Ok(())
+    }
+}
+
+fn record(listing: &mut Account<Listing>, mint: &Account<Mint>, creator: &Signer, bump: u8) {
+    listing.mint = mint.key();
+    listing.creator = creator.key();
+    listing.supply = mint.supply;
+    listing.bump = bump;
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct ListVulnerable<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Listing::INIT_SPACE,
+        seeds = [b"listing", mint.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ListSecure<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Listing::INIT_SPACE,
+        seeds = [b"listing", mint.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    /// CHECK: Never signs; only its address is used, as the mint authority
+    /// nobody can exercise.
+    #[account(seeds = [b"burn", mint.key().as_ref()], bump)]
+    pub burn_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// A mint the launchpad lists, and the supply it was listed with.
+#[account]
+#[derive(InitSpace)]
+pub struct Listing {
+    pub mint: Pubkey,    // 32 bytes
+    pub creator: Pubkey, // 32 bytes
+    pub supply: u64,     //  8 bytes
+    pub bump: u8,        //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The mint's supply doesn't match the listing")]
+    SupplyMismatch,
+    #[msg("Someone other than the launchpad can still mint")]
+    MintAuthorityLive,
+}
@@ -0,0 +1,332 @@
+/// # Pattern 29: Mint Authority — Mollusk Exploit Tests
+///
+/// The creator has minted 1,000,000 tokens into their own token account
+/// and asks the launchpad to list the mint as fixed supply. No listing
+/// exists yet. Who holds the mint authority depends on the test.
+///
+/// - Test 1: EXPLOIT — the creator keeps the mint authority. The mint is
+///   listed through list_vulnerable, then the creator mints 9,000,000 more.
+/// - Test 2: SECURE — list_secure moves the creator's mint authority to
+///   the burn PDA, and the creator's next mint fails.
+/// - Test 3: SECURE — list_secure refuses a mint whose authority is held
+///   by a third party, and lists one with no mint authority.
+///
+/// The program must be built into SBF_OUT_DIR next to `spl_token.so`.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("H8m2YjHZ5yDPr8edUpzUuie8jqJgwnNYcaANBGJsDwGg");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const TOKEN_RENT: u64 = 2_039_280;
+const MINT_RENT: u64 = 1_461_600;
+const WALLET: u64 = 10_000_000_000;
+
+const DECIMALS: u8 = 6;
+/// The supply the creator lists with.
+const SUPPLY: u64 = 1_000_000;
+/// What the creator mints after listing.
+const INFLATION: u64 = 9_000_000;
+
+// Error codes
+const MINT_AUTHORITY_LIVE: u32 = 6001;
+/// SPL Token's OwnerMismatch: the signer isn't the mint authority.
+const OWNER_MISMATCH: u32 = 4;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Listing account:
+///   [8 disc][32 mint][32 creator][8 supply][1 bump]
+fn serialize_listing(mint: &Pubkey, creator: &Pubkey, supply: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Listing"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(creator.as_ref());
+    data.extend_from_slice(&supply.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with no freeze authority.
+fn serialize_mint(mint_authority: Option<&Pubkey>, supply: u64) -> Vec<u8> {
+    Mint {
+        mint_authority: mint_authority.copied(),
+        supply,
+        decimals: DECIMALS,
+        ..Mint::default()
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "mint_authority");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// SPL Token's MintTo, signed by `authority`.
+fn mint_to(mint: &Pubkey, account: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![7]; // TokenInstruction::MintTo
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+/// Who holds the mint authority when the creator lists.
+enum Authority {
+    Creator,
+    Outsider,
+    Nobody,
+}
+
+struct Setup {
+    listing: Pubkey,
+    listing_bump: u8,
+    mint: Pubkey,
+    burn_authority: Pubkey,
+    creator: Pubkey,
+    creator_token: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new(authority: Authority) -> Self {
+        let mint = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let creator_token = Pubkey::new_unique();
+        let outsider = Pubkey::new_unique();
+        let (listing, listing_bump) =
+            Pubkey::find_program_address(&[b"listing", mint.as_ref()], &PROGRAM_ID);
+        let (burn_authority, _) =
+            Pubkey::find_program_address(&[b"burn", mint.as_ref()], &PROGRAM_ID);
+
+        let mint_authority = match authority {
+            Authority::Creator => Some(&creator),
+            Authority::Outsider => Some(&outsider),
+            Authority::Nobody => None,
+        };
+        let ledger = vec![
+            (listing, AccountSharedData::default()),
+            (
+                mint,
+                owned_account(
+                    &serialize_mint(mint_authority, SUPPLY),
+                    MINT_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (burn_authority, AccountSharedData::default()),
+            (creator, system_account()),
+            (
+                creator_token,
+                owned_account(
+                    &TokenAccount::new(&mint, &creator, SUPPLY).pack(),
+                    TOKEN_RENT,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+
+        Self {
+            listing,
+            listing_bump,
+            mint,
+            burn_authority,
+            creator,
+            creator_token,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn list_vulnerable(&self) -> Instruction {
+        let mut data = ix_discriminator("list_vulnerable").to_vec();
+        data.extend_from_slice(&SUPPLY.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.listing, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.creator, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn list_secure(&self) -> Instruction {
+        let mut data = ix_discriminator("list_secure").to_vec();
+        data.extend_from_slice(&SUPPLY.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.listing, false),
+                AccountMeta::new(self.mint, false),
+                AccountMeta::new_readonly(self.burn_authority, false),
+                AccountMeta::new(self.creator, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// The creator mints `INFLATION` more into their own account.
+    fn inflate(&self) -> Instruction {
+        mint_to(&self.mint, &self.creator_token, &self.creator, INFLATION)
+    }
+
+    fn listing_data(&self) -> Vec<u8> {
+        serialize_listing(&self.mint, &self.creator, SUPPLY, self.listing_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_creator_mints_after_listing_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Creator: list_vulnerable(1,000,000), still holding the mint
+    //      authority
+    //   2. Creator: SPL Token MintTo of 9,000,000 into their own account
+    //
+    // Expected: both SUCCEED. The listing records a supply of 1,000,000;
+    //           afterwards the mint's supply is 10,000,000 and the
+    //           creator's account holds all of it.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(Authority::Creator);
+    let (listing, mint, creator_token) = (s.listing, s.mint, s.creator_token);
+
+    let ix = s.list_vulnerable();
+    let listed = s.listing_data();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&listing).data(&listed).build(),
+        ],
+    );
+
+    let ix = s.inflate();
+    let inflated = serialize_mint(Some(&s.creator), SUPPLY + INFLATION);
+    let minted = TokenAccount::new(&s.mint, &s.creator, SUPPLY + INFLATION).pack();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&mint).data(&inflated).build(),
+            Check::account(&creator_token).data(&minted).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_listing_hands_mint_authority_to_burn_pda() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Creator: list_secure(1,000,000), still holding the mint authority
+    //   2. Creator: SPL Token MintTo of 9,000,000
+    //
+    // Expected: the listing SUCCEEDS and the mint's authority is now the
+    //           burn PDA. The mint FAILS with OwnerMismatch (4), and the
+    //           supply stays 1,000,000.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new(Authority::Creator);
+    let (listing, mint) = (s.listing, s.mint);
+
+    let ix = s.list_secure();
+    let listed = s.listing_data();
+    let locked = serialize_mint(Some(&s.burn_authority), SUPPLY);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&listing).data(&listed).build(),
+            Check::account(&mint).data(&locked).build(),
+        ],
+    );
+
+    let ix = s.inflate();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(OWNER_MISMATCH))],
+    );
+}
+
+#[test]
+fn secure_rejects_outside_mint_authority() {
+    // -----------------------------------------------------------------------
+    // SECURE: list_secure for a mint whose authority is held by someone
+    // other than the creator, then for a mint with no mint authority.
+    //
+    // Expected: the first FAILS with MintAuthorityLive (6001); the creator
+    //           can't hand over an authority they don't hold. The second
+    //           SUCCEEDS without touching the mint.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+
+    let mut s = Setup::new(Authority::Outsider);
+    let ix = s.list_secure();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(MINT_AUTHORITY_LIVE))],
+    );
+
+    let mut s = Setup::new(Authority::Nobody);
+    let (listing, mint) = (s.listing, s.mint);
+    let ix = s.list_secure();
+    let listed = s.listing_data();
+    let unchanged = serialize_mint(None, SUPPLY);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&listing).data(&listed).build(),
+            Check::account(&mint).data(&unchanged).build(),
+        ],
+    );
+}
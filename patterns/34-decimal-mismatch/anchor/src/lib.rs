@@ -0,0 +1,221 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("iHqY1ntuXf8jnnvW5FaPAdFXB4kX2BgDFJZeiumjXH6");
+
+/// # Decimal Mismatch
+///
+/// This program demonstrates a peg swap: a pool holding two dollar
+/// stablecoins that swaps either one for the other at 1:1 in value.
+///
+/// ## The Vulnerability
+/// Token amounts are integers in the mint's smallest unit, and the mint's
+/// `decimals` say how many of those make one token. One dollar of a
+/// 6-decimal stablecoin is 1,000,000; one dollar of a 9-decimal one is
+/// 1,000,000,000. The vulnerable swap pays out the raw amount it was given,
+/// so every unit of the 9-decimal coin buys a unit of the 6-decimal coin,
+/// which is worth 1,000 times as much.
+///
+/// ## Real-World Impact
+/// Swapping $10 of the 9-decimal coin returns $10,000 of the 6-decimal
+/// coin, and the pool's reserve is gone in a handful of swaps. The other
+/// direction shortchanges every honest user by the same factor. The bug
+/// hides in tests that use two mints with the same decimals.
+#[program]
+pub mod decimal_mismatch {
+    use super::*;
+
+    pub fn create_pool(ctx: Context<CreatePool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.mint_a = ctx.accounts.mint_a.key();
+        pool.mint_b = ctx.accounts.mint_b.key();
+        pool.bump = ctx.bumps.pool;
+        demo_log!(
+            Mode::Setup,
+            "create_pool",
+            actor = ctx.accounts.payer.key(),
+            mint_a = pool.mint_a,
+            mint_b = pool.mint_b
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Raw amounts compared across mints
+    // ============================================================================
+    // ISSUE: "1:1" is applied to raw amounts. With 9 decimals in and 6
+    //        decimals out, each unit paid in is 1/1,000 of the value of
+    //        each unit paid out.
+    //
+    // ATTACK SCENARIO:
+    //   1. The pool holds 10,000 of each stablecoin: USDC (6 decimals) and
+    //      USDX (9 decimals)
+    //   2. Attacker calls swap_vulnerable with 10 USDX
+    //      (10,000,000,000 raw)
+    //   3. The pool pays 10,000,000,000 raw USDC: 10,000 USDC, its whole
+    //      reserve
+    // ============================================================================
+    pub fn swap_vulnerable(ctx: Context<Swap>, amount_in: u64) -> Result<()> {
+        // VULNERABLE: a unit of one mint is not a unit of the other
+        let amount_out = amount_in;
+        swap(&ctx, amount_in, amount_out)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "swap",
+            actor = ctx.accounts.user.key(),
+            amount = amount_in,
+            amount_out = amount_out
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Amounts rescaled by the mints' decimals
+    // ============================================================================
+    // FIX: Convert `amount_in` to the output mint's units with
+    //      10^(decimals difference), computed with `checked_pow`. Rounding
+    //      down favours the pool, and a swap that rounds to nothing is
+    //      refused instead of keeping the input.
+    // ============================================================================
+    pub fn swap_secure(ctx: Context<Swap>, amount_in: u64) -> Result<()> {
+        // SECURE: the same value, expressed in the output mint's units
+        let amount_out = rescale(
+            amount_in,
+            ctx.accounts.mint_in.decimals,
+            ctx.accounts.mint_out.decimals,
+        )?;
+        require!(amount_out > 0, ErrorCode::ZeroOutput);
+        swap(&ctx, amount_in, amount_out)?;
+        demo_log!(
+            Mode::Secure,
+            "swap",
+            actor = ctx.accounts.user.key(),
+            amount = amount_in,
+            amount_out = amount_out
+        );
+        Ok(())
+    }
+}
+
+/// `amount` in units of a mint with `from` decimals, converted to units of
+/// a mint with `to` decimals at equal value. Rounds down.
+fn rescale(amount: u64, from: u8, to: u8) -> Result<u64> {
+    let factor = 10u64
+        .checked_pow(u32::from(from.abs_diff(to)))
+        .ok_or(ErrorCode::Overflow)?;
+    if to >= from {
+        Ok(amount.checked_mul(factor).ok_or(ErrorCode::Overflow)?)
+    } else {
+        Ok(amount / factor)
+    }
+}
+
+/// Take `amount_in` from the user and pay `amount_out` from the pool.
+fn swap(ctx: &Context<Swap>, amount_in: u64, amount_out: u64) -> Result<()> {
+    let accounts = &ctx.accounts;
+    token::transfer(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.user_in.to_account_info(),
+                to: accounts.reserve_in.to_account_info(),
+                authority: accounts.user.to_account_info(),
+            },
+        ),
+        amount_in,
+    )?;
+
+    let pool = &accounts.pool;
+    let seeds: &[&[u8]] = &[
+        b"pool",
+        pool.mint_a.as_ref(),
+        pool.mint_b.as_ref(),
+        &[pool.bump],
+    ];
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.reserve_out.to_account_info(),
+                to: accounts.user_out.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount_out,
+    )
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    pub mint_a: Account<'info, Mint>,
+    #[account(constraint = mint_b.key() != mint_a.key() @ ErrorCode::InvalidPair)]
+    pub mint_b: Account<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// A swap in either direction: `mint_in` and `mint_out` are the pool's two
+/// mints, in either order.
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        bump = pool.bump,
+        constraint = pool.pairs(&mint_in.key(), &mint_out.key()) @ ErrorCode::InvalidPair
+    )]
+    pub pool: Account<'info, Pool>,
+    pub mint_in: Account<'info, Mint>,
+    pub mint_out: Account<'info, Mint>,
+    #[account(mut, token::mint = mint_in, token::authority = pool)]
+    pub reserve_in: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = mint_out, token::authority = pool)]
+    pub reserve_out: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = mint_in, token::authority = user)]
+    pub user_in: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = mint_out)]
+    pub user_out: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// The pool PDA owns one reserve token account for each mint.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub mint_a: Pubkey, // 32 bytes
+    pub mint_b: Pubkey, // 32 bytes
+    pub bump: u8,       //  1 byte
+}
+
+impl Pool {
+    /// Whether `mint_in` → `mint_out` is this pool's pair, either way round.
+    pub fn pairs(&self, mint_in: &Pubkey, mint_out: &Pubkey) -> bool {
+        (*mint_in == self.mint_a && *mint_out == self.mint_b)
+            || (*mint_in == self.mint_b && *mint_out == self.mint_a)
+    }
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The swap pays out nothing")]
+    ZeroOutput,
+    #[msg("The mints are not this pool's pair")]
+    InvalidPair,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,328 @@
+/// # Pattern 34: Decimal Mismatch — Mollusk Exploit Tests
+///
+/// The pool swaps USDC (6 decimals) and USDX (9 decimals) at 1:1 in value,
+/// and holds 10,000 of each. The attacker holds 10 USDX; an honest user
+/// holds 100 USDC.
+///
+/// - Test 1: EXPLOIT — swap_vulnerable pays the attacker 10,000 USDC for
+///   10 USDX, the pool's whole USDC reserve.
+/// - Test 2: SECURE — swap_secure pays 10 USDC for the same swap, and
+///   refuses a swap too small to pay out anything.
+/// - Test 3: SANITY — the honest user swaps 100 USDC for 100 USDX through
+///   swap_secure.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{token_account, Mint, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("iHqY1ntuXf8jnnvW5FaPAdFXB4kX2BgDFJZeiumjXH6");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const MINT_RENT: u64 = 1_461_600;
+const WALLET: u64 = 10_000_000_000;
+
+const USDC_DECIMALS: u8 = 6;
+const USDX_DECIMALS: u8 = 9;
+/// One whole token of each mint, in raw units.
+const USDC: u64 = 1_000_000;
+const USDX: u64 = 1_000_000_000;
+/// The pool's reserve of each, in whole tokens.
+const RESERVE: u64 = 10_000;
+
+// Error codes
+const ZERO_OUTPUT: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Pool account: [8 disc][32 mint_a][32 mint_b][1 bump]
+fn serialize_pool(mint_a: &Pubkey, mint_b: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(mint_a.as_ref());
+    data.extend_from_slice(mint_b.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize an SPL Token mint with no authorities or supply.
+fn serialize_mint(decimals: u8) -> Vec<u8> {
+    Mint {
+        decimals,
+        ..Mint::default()
+    }
+    .pack()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "decimal_mismatch");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+/// One user: a wallet and a token account for each mint.
+struct User {
+    key: Pubkey,
+    usdc: Pubkey,
+    usdx: Pubkey,
+}
+
+impl User {
+    fn new() -> Self {
+        Self {
+            key: Pubkey::new_unique(),
+            usdc: Pubkey::new_unique(),
+            usdx: Pubkey::new_unique(),
+        }
+    }
+}
+
+struct Setup {
+    pool: Pubkey,
+    usdc_mint: Pubkey,
+    usdx_mint: Pubkey,
+    reserve_usdc: Pubkey,
+    reserve_usdx: Pubkey,
+    /// Holds 10 USDX.
+    attacker: User,
+    /// Holds 100 USDC.
+    user: User,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let usdc_mint = Pubkey::new_unique();
+        let usdx_mint = Pubkey::new_unique();
+        let (pool, pool_bump) = Pubkey::find_program_address(
+            &[b"pool", usdc_mint.as_ref(), usdx_mint.as_ref()],
+            &PROGRAM_ID,
+        );
+        let reserve_usdc = Pubkey::new_unique();
+        let reserve_usdx = Pubkey::new_unique();
+        let attacker = User::new();
+        let user = User::new();
+
+        let mut ledger = vec![
+            (
+                pool,
+                owned_account(
+                    &serialize_pool(&usdc_mint, &usdx_mint, pool_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                usdc_mint,
+                owned_account(&serialize_mint(USDC_DECIMALS), MINT_RENT, &TOKEN_PROGRAM_ID),
+            ),
+            (
+                usdx_mint,
+                owned_account(&serialize_mint(USDX_DECIMALS), MINT_RENT, &TOKEN_PROGRAM_ID),
+            ),
+            (
+                reserve_usdc,
+                token_account(&TOKEN_PROGRAM_ID, &usdc_mint, &pool, RESERVE * USDC),
+            ),
+            (
+                reserve_usdx,
+                token_account(&TOKEN_PROGRAM_ID, &usdx_mint, &pool, RESERVE * USDX),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+        for (holder, usdc, usdx) in [(&attacker, 0, 10 * USDX), (&user, 100 * USDC, 0)] {
+            ledger.extend([
+                (holder.key, system_account()),
+                (holder.usdc, token_account(&TOKEN_PROGRAM_ID, &usdc_mint, &holder.key, usdc)),
+                (holder.usdx, token_account(&TOKEN_PROGRAM_ID, &usdx_mint, &holder.key, usdx)),
+            ]);
+        }
+
+        Self {
+            pool,
+            usdc_mint,
+            usdx_mint,
+            reserve_usdc,
+            reserve_usdx,
+            attacker,
+            user,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// swap_* of `amount_in` USDX for USDC.
+    fn sell_usdx(&self, name: &str, user: &User, amount_in: u64) -> Instruction {
+        self.ix(
+            name,
+            user,
+            amount_in,
+            [self.usdx_mint, self.usdc_mint],
+            [self.reserve_usdx, self.reserve_usdc],
+            [user.usdx, user.usdc],
+        )
+    }
+
+    /// swap_* of `amount_in` USDC for USDX.
+    fn sell_usdc(&self, name: &str, user: &User, amount_in: u64) -> Instruction {
+        self.ix(
+            name,
+            user,
+            amount_in,
+            [self.usdc_mint, self.usdx_mint],
+            [self.reserve_usdc, self.reserve_usdx],
+            [user.usdc, user.usdx],
+        )
+    }
+
+    /// Accounts in `Swap` order; each pair is [in, out].
+    fn ix(
+        &self,
+        name: &str,
+        user: &User,
+        amount_in: u64,
+        mints: [Pubkey; 2],
+        reserves: [Pubkey; 2],
+        user_tokens: [Pubkey; 2],
+    ) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount_in.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.pool, false),
+                AccountMeta::new_readonly(mints[0], false),
+                AccountMeta::new_readonly(mints[1], false),
+                AccountMeta::new(reserves[0], false),
+                AccountMeta::new(reserves[1], false),
+                AccountMeta::new(user_tokens[0], false),
+                AccountMeta::new(user_tokens[1], false),
+                AccountMeta::new_readonly(user.key, true),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+        )
+    }
+
+    fn usdc_data(&self, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        TokenAccount::new(&self.usdc_mint, owner, amount).pack()
+    }
+
+    fn usdx_data(&self, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        TokenAccount::new(&self.usdx_mint, owner, amount).pack()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_raw_amounts_drain_pool_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Attacker: swap_vulnerable(10 USDX) for USDC
+    //
+    // Expected: SUCCEEDS and pays 10,000,000,000 raw USDC, the same raw
+    //           amount that came in: 10,000 USDC for $10. The pool's USDC
+    //           reserve is empty.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (reserve_usdc, reserve_usdx, attacker_usdc) =
+        (s.reserve_usdc, s.reserve_usdx, s.attacker.usdc);
+
+    let ix = s.sell_usdx("swap_vulnerable", &s.attacker, 10 * USDX);
+    let drained = s.usdc_data(&s.pool, 0);
+    let received = s.usdx_data(&s.pool, (RESERVE + 10) * USDX);
+    let stolen = s.usdc_data(&s.attacker.key, RESERVE * USDC);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&reserve_usdc).data(&drained).build(),
+            Check::account(&reserve_usdx).data(&received).build(),
+            Check::account(&attacker_usdc).data(&stolen).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rescales_by_decimals() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Attacker: swap_secure(10 USDX) for USDC
+    //   2. Attacker: swap_secure(999 raw USDX), less than one raw USDC
+    //
+    // Expected: step 1 SUCCEEDS and pays 10 USDC (10,000,000 raw). Step 2
+    //           FAILS with ZeroOutput (6000) instead of taking the USDX
+    //           for nothing.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (reserve_usdc, attacker_usdc) = (s.reserve_usdc, s.attacker.usdc);
+
+    let ix = s.sell_usdx("swap_secure", &s.attacker, 10 * USDX);
+    let paid = s.usdc_data(&s.pool, (RESERVE - 10) * USDC);
+    let fair = s.usdc_data(&s.attacker.key, 10 * USDC);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&reserve_usdc).data(&paid).build(),
+            Check::account(&attacker_usdc).data(&fair).build(),
+        ],
+    );
+
+    let ix = s.sell_usdx("swap_secure", &s.attacker, USDX / USDC - 1);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(ZERO_OUTPUT))],
+    );
+}
+
+#[test]
+fn sanity_swap_into_more_decimals_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: User: swap_secure(100 USDC) for USDX
+    //
+    // Expected: SUCCEEDS and pays 100 USDX (100,000,000,000 raw).
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (user_usdc, user_usdx) = (s.user.usdc, s.user.usdx);
+
+    let ix = s.sell_usdc("swap_secure", &s.user, 100 * USDC);
+    let spent = s.usdc_data(&s.user.key, 0);
+    let bought = s.usdx_data(&s.user.key, 100 * USDX);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&user_usdc).data(&spent).build(),
+            Check::account(&user_usdx).data(&bought).build(),
+        ],
+    );
+}
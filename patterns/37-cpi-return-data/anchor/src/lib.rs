@@ -0,0 +1,367 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::Instruction,
+    program::{get_return_data, invoke},
+};
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("7irbY25aKWkGRjSbfiecVV8PVQ26xz2Pz3q2EsGncDiR");
+
+/// # Trusting CPI Return Data
+///
+/// This program demonstrates a credits vault that buys credits back for
+/// SOL at an oracle price. Prices are read through a quote interface: a
+/// `quote` instruction that takes the feed account and returns the price,
+/// in lamports per credit, as return data. The vault's own `quote`, over
+/// the admin's feed, is the trusted oracle. Redemptions take the quote
+/// program as an account, so pricing can move to another program without
+/// an upgrade.
+///
+/// ## The Vulnerability
+/// `get_return_data()` returns whatever the last program to call
+/// `set_return_data` left behind, along with that program's id. The
+/// vulnerable redemption CPIs into the quote program the caller passed,
+/// then reads the first 8 bytes of the return data as the price. It never
+/// looks at which program set them.
+///
+/// ## Real-World Impact
+/// An attacker passes their own program, which returns a price 100 times
+/// the feed's, and redeems 1 SOL of credits for the whole treasury. The
+/// same gap appears wherever return data stands in for an account read:
+/// oracle adapters, quote routers, and "view" CPIs into other protocols.
+#[program]
+pub mod cpi_return_data {
+    use super::*;
+
+    /// Create the vault, with this program's `quote` as the oracle.
+    pub fn initialize(ctx: Context<Initialize>, price: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.oracle = crate::ID;
+        config.treasury_bump = ctx.bumps.treasury;
+        config.bump = ctx.bumps.config;
+        let feed = &mut ctx.accounts.feed;
+        feed.price = price;
+        feed.bump = ctx.bumps.feed;
+        demo_log!(
+            Mode::Setup,
+            "initialize",
+            actor = config.admin,
+            price = price
+        );
+        Ok(())
+    }
+
+    /// Admin: publish a new price, in lamports per credit.
+    pub fn set_price(ctx: Context<SetPrice>, price: u64) -> Result<()> {
+        ctx.accounts.feed.price = price;
+        demo_log!(
+            Mode::Setup,
+            "set_price",
+            actor = ctx.accounts.admin.key(),
+            price = price
+        );
+        Ok(())
+    }
+
+    /// The quote interface: the feed's price, as return data.
+    pub fn quote(ctx: Context<Quote>) -> Result<u64> {
+        Ok(ctx.accounts.feed.price)
+    }
+
+    pub fn open_balance(ctx: Context<OpenBalance>) -> Result<()> {
+        let balance = &mut ctx.accounts.balance;
+        balance.user = ctx.accounts.user.key();
+        balance.credits = 0;
+        balance.bump = ctx.bumps.balance;
+        Ok(())
+    }
+
+    /// Buy `credits` at the feed's price.
+    pub fn deposit(ctx: Context<Deposit>, credits: u64) -> Result<()> {
+        let cost = credits
+            .checked_mul(ctx.accounts.feed.price)
+            .ok_or(ErrorCode::Overflow)?;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            cost,
+        )?;
+        let balance = &mut ctx.accounts.balance;
+        balance.credits = balance
+            .credits
+            .checked_add(credits)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = balance.user,
+            amount = cost,
+            credits = credits
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Return data trusted without asking who set it
+    // ============================================================================
+    // ISSUE: `quote_program` is whatever the caller passed, and the price is
+    //        the first 8 bytes of whatever return data it left. The program
+    //        id `get_return_data()` reports is thrown away.
+    //
+    // ATTACK SCENARIO:
+    //   1. The treasury holds 100 SOL; the feed prices a credit at 0.001 SOL
+    //   2. Attacker holds 1,000 credits (1 SOL) and deploys fake_quote,
+    //      which returns 0.1 SOL per credit
+    //   3. Attacker calls redeem_vulnerable(1,000) with fake_quote
+    //   4. The vault pays 1,000 × 0.1 SOL: the whole treasury
+    // ============================================================================
+    pub fn redeem_vulnerable(ctx: Context<Redeem>, credits: u64) -> Result<()> {
+        request_quote(&ctx.accounts.quote_program, &ctx.accounts.feed)?;
+
+        // VULNERABLE: any program's return data, any length
+        let (_, data) = get_return_data().ok_or(ErrorCode::NoQuote)?;
+        let price = data
+            .get(..8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ErrorCode::MalformedQuote)?;
+
+        let paid = redeem(ctx.accounts, credits, price)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "redeem",
+            actor = ctx.accounts.user.key(),
+            amount = paid,
+            credits = credits,
+            price = price
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Return data accepted only from the oracle, in its format
+    // ============================================================================
+    // FIX: The program id attached to the return data must be the configured
+    //      oracle, and the data exactly one u64. Any quote program can still
+    //      be passed (it may route to the oracle), but only the oracle's own
+    //      answer is used.
+    // ============================================================================
+    pub fn redeem_secure(ctx: Context<Redeem>, credits: u64) -> Result<()> {
+        request_quote(&ctx.accounts.quote_program, &ctx.accounts.feed)?;
+
+        // SECURE: set by the oracle, and exactly 8 bytes
+        let (setter, data) = get_return_data().ok_or(ErrorCode::NoQuote)?;
+        require_keys_eq!(
+            setter,
+            ctx.accounts.config.oracle,
+            ErrorCode::UntrustedQuote
+        );
+        let price = <[u8; 8]>::try_from(data.as_slice())
+            .map(u64::from_le_bytes)
+            .map_err(|_| ErrorCode::MalformedQuote)?;
+
+        let paid = redeem(ctx.accounts, credits, price)?;
+        demo_log!(
+            Mode::Secure,
+            "redeem",
+            actor = ctx.accounts.user.key(),
+            amount = paid,
+            credits = credits,
+            price = price
+        );
+        Ok(())
+    }
+}
+
+/// CPI `quote` on `quote_program`, passing the feed.
+fn request_quote<'info>(
+    quote_program: &UncheckedAccount<'info>,
+    feed: &Account<'info, Feed>,
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: quote_program.key(),
+        accounts: vec![AccountMeta::new_readonly(feed.key(), false)],
+        data: instruction::Quote::DISCRIMINATOR.to_vec(),
+    };
+    invoke(
+        &ix,
+        &[feed.to_account_info(), quote_program.to_account_info()],
+    )?;
+    Ok(())
+}
+
+/// Burn `credits` and pay them out of the treasury at `price`. Returns the
+/// lamports paid.
+fn redeem(accounts: &mut Redeem, credits: u64, price: u64) -> Result<u64> {
+    let balance = &mut accounts.balance;
+    balance.credits = balance
+        .credits
+        .checked_sub(credits)
+        .ok_or(ErrorCode::InsufficientCredits)?;
+    let paid = credits.checked_mul(price).ok_or(ErrorCode::Overflow)?;
+
+    let seeds: &[&[u8]] = &[b"treasury", &[accounts.config.treasury_bump]];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            accounts.system_program.to_account_info(),
+            Transfer {
+                from: accounts.treasury.to_account_info(),
+                to: accounts.user.to_account_info(),
+            },
+            &[seeds],
+        ),
+        paid,
+    )?;
+    Ok(paid)
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Feed::INIT_SPACE,
+        seeds = [b"feed"],
+        bump
+    )]
+    pub feed: Account<'info, Feed>,
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: SystemAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"feed"], bump = feed.bump)]
+    pub feed: Account<'info, Feed>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    #[account(seeds = [b"feed"], bump = feed.bump)]
+    pub feed: Account<'info, Feed>,
+}
+
+#[derive(Accounts)]
+pub struct OpenBalance<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Balance::INIT_SPACE,
+        seeds = [b"balance", user.key().as_ref()],
+        bump
+    )]
+    pub balance: Account<'info, Balance>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"feed"], bump = feed.bump)]
+    pub feed: Account<'info, Feed>,
+    #[account(
+        mut,
+        seeds = [b"balance", user.key().as_ref()],
+        bump = balance.bump,
+        has_one = user
+    )]
+    pub balance: Account<'info, Balance>,
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"feed"], bump = feed.bump)]
+    pub feed: Account<'info, Feed>,
+    #[account(
+        mut,
+        seeds = [b"balance", user.key().as_ref()],
+        bump = balance.bump,
+        has_one = user
+    )]
+    pub balance: Account<'info, Balance>,
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Any program speaking the quote interface. Unchecked in
+    /// `redeem_vulnerable`; in `redeem_secure` only the oracle's return data
+    /// is accepted, whichever program was called.
+    pub quote_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Vault settings. The treasury PDA itself is a system account.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,     // 32 bytes
+    pub oracle: Pubkey,    // 32 bytes
+    pub treasury_bump: u8, //  1 byte
+    pub bump: u8,          //  1 byte
+}
+
+/// The admin's price, read by `quote`.
+#[account]
+#[derive(InitSpace)]
+pub struct Feed {
+    pub price: u64, // 8 bytes: lamports per credit
+    pub bump: u8,   // 1 byte
+}
+
+/// A user's credits.
+#[account]
+#[derive(InitSpace)]
+pub struct Balance {
+    pub user: Pubkey, // 32 bytes
+    pub credits: u64, //  8 bytes
+    pub bump: u8,     //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Return data was not set by the oracle")]
+    UntrustedQuote,
+    #[msg("Return data is not a u64 price")]
+    MalformedQuote,
+    #[msg("The quote program returned no data")]
+    NoQuote,
+    #[msg("Redemption exceeds the user's credits")]
+    InsufficientCredits,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
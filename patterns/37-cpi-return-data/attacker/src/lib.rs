@@ -0,0 +1,30 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program::set_return_data, pubkey::Pubkey,
+};
+
+use demo_log::{demo_log, Mode};
+
+solana_program::declare_id!("GCqjy788bZhTqAxqXmqExVa93pStCT5QfDrSjYzvmpFm");
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+/// The price fake_quote reports: 0.1 SOL per credit, 100 times the feed's.
+pub const FAKE_PRICE: u64 = 100_000_000;
+
+/// # Fake Quote (Pattern 37)
+///
+/// Passed as the quote program of a vault that doesn't check who set the
+/// return data it reads. It speaks the quote interface: any data, the feed
+/// as its one account, a u64 price as return data. An honest quote program
+/// reads the feed. This one ignores it and returns `FAKE_PRICE`.
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    // ATTACKER: return data carries no proof of where a price came from
+    set_return_data(&FAKE_PRICE.to_le_bytes());
+    demo_log!(Mode::Attacker, "quote", price = FAKE_PRICE);
+    Ok(())
+}
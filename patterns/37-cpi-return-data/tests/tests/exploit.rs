@@ -0,0 +1,298 @@
+// Mollusk tests for Pattern 37
+/// # Pattern 37: Trusting CPI Return Data — Mollusk Exploit Tests
+///
+/// The treasury holds 100 SOL. The feed prices a credit at 0.001 SOL. The
+/// attacker holds 1,000 credits (1 SOL) and the user 5,000 (5 SOL). The
+/// vault's own `quote` is the oracle. The attacker's `fake_quote` returns
+/// 0.1 SOL per credit.
+///
+/// - Test 1: EXPLOIT — redeem_vulnerable(1,000) through fake_quote pays the
+///   attacker the whole treasury.
+/// - Test 2: SECURE — redeem_secure refuses fake_quote's return data. The
+///   same redemption through the oracle pays 1 SOL.
+/// - Test 3: SANITY — the user redeems 5,000 credits for 5 SOL.
+///
+/// Both programs must be built into SBF_OUT_DIR (`fake_quote.so` comes from
+/// `patterns/37-cpi-return-data/attacker`).
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, system_account,
+    Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("7irbY25aKWkGRjSbfiecVV8PVQ26xz2Pz3q2EsGncDiR");
+const FAKE_QUOTE_ID: Pubkey = solana_sdk::pubkey!("GCqjy788bZhTqAxqXmqExVa93pStCT5QfDrSjYzvmpFm");
+const SYSTEM_PROGRAM_ID: Pubkey = solana_sdk::system_program::ID;
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+const TREASURY: u64 = 100 * SOL;
+/// The feed's price: 0.001 SOL per credit.
+const PRICE: u64 = 1_000_000;
+/// fake_quote's price: 0.1 SOL per credit.
+const FAKE_PRICE: u64 = 100_000_000;
+const ATTACKER_CREDITS: u64 = 1_000;
+const USER_CREDITS: u64 = 5_000;
+
+// Error codes
+const UNTRUSTED_QUOTE: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Config account:
+///   [8 disc][32 admin][32 oracle][1 treasury_bump][1 bump]
+fn serialize_config(admin: &Pubkey, oracle: &Pubkey, treasury_bump: u8, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(74);
+    data.extend_from_slice(&account_discriminator("Config"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(oracle.as_ref());
+    data.push(treasury_bump);
+    data.push(bump);
+    data
+}
+
+/// Serialize a Feed account: [8 disc][8 price][1 bump]
+fn serialize_feed(price: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(17);
+    data.extend_from_slice(&account_discriminator("Feed"));
+    data.extend_from_slice(&price.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Balance account: [8 disc][32 user][8 credits][1 bump]
+fn serialize_balance(user: &Pubkey, credits: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Balance"));
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(&credits.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "cpi_return_data");
+    mollusk.add_program(
+        &FAKE_QUOTE_ID,
+        "fake_quote",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID)
+}
+
+struct Setup {
+    config: Pubkey,
+    feed: Pubkey,
+    treasury: Pubkey,
+    attacker: Pubkey,
+    user: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let (config, config_bump) = pda(&[b"config"]);
+        let (feed, feed_bump) = pda(&[b"feed"]);
+        let (treasury, treasury_bump) = pda(&[b"treasury"]);
+        let admin = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+
+        let mut ledger = vec![
+            (
+                config,
+                owned_account(
+                    &serialize_config(&admin, &PROGRAM_ID, treasury_bump, config_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (
+                feed,
+                owned_account(&serialize_feed(PRICE, feed_bump), RENT, &PROGRAM_ID),
+            ),
+            (treasury, system_account(TREASURY)),
+            // The vault is its own oracle, so redemptions CPI into it
+            (
+                PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                FAKE_QUOTE_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                SYSTEM_PROGRAM_ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+        for (owner, credits) in [(attacker, ATTACKER_CREDITS), (user, USER_CREDITS)] {
+            let (balance, bump) = pda(&[b"balance", owner.as_ref()]);
+            ledger.extend([
+                (
+                    balance,
+                    owned_account(&serialize_balance(&owner, credits, bump), RENT, &PROGRAM_ID),
+                ),
+                (owner, system_account(WALLET)),
+            ]);
+        }
+
+        Self {
+            config,
+            feed,
+            treasury,
+            attacker,
+            user,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// `user`: redeem_*(credits), quoted by `quote_program`.
+    fn redeem(
+        &self,
+        name: &str,
+        user: &Pubkey,
+        quote_program: &Pubkey,
+        credits: u64,
+    ) -> Instruction {
+        let (balance, _) = pda(&[b"balance", user.as_ref()]);
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&credits.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.config, false),
+                AccountMeta::new_readonly(self.feed, false),
+                AccountMeta::new(balance, false),
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(*user, true),
+                AccountMeta::new_readonly(*quote_program, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_fake_quote_prices_redemption_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   Attacker: redeem_vulnerable(1,000 credits) through fake_quote
+    //
+    // Expected: SUCCEEDS. fake_quote's return data says 0.1 SOL per credit,
+    //           and the attacker's 1 SOL of credits takes all 100 SOL.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (treasury, attacker) = (s.treasury, s.attacker);
+    let (balance, balance_bump) = pda(&[b"balance", attacker.as_ref()]);
+
+    let ix = s.redeem(
+        "redeem_vulnerable",
+        &attacker,
+        &FAKE_QUOTE_ID,
+        ATTACKER_CREDITS,
+    );
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury).lamports(0).build(),
+            Check::account(&attacker)
+                .lamports(WALLET + ATTACKER_CREDITS * FAKE_PRICE)
+                .build(),
+            Check::account(&balance)
+                .data(&serialize_balance(&attacker, 0, balance_bump))
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_only_oracle_return_data_accepted() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Attacker: redeem_secure(1,000 credits) through fake_quote
+    //   2. Attacker: redeem_secure(1,000 credits) through the oracle
+    //
+    // Expected: step 1 FAILS with UntrustedQuote (6000): the return data
+    //           was set by fake_quote. Step 2 SUCCEEDS at the feed's price:
+    //           the attacker gets 1 SOL and the treasury keeps 99 SOL.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (treasury, attacker) = (s.treasury, s.attacker);
+
+    let ix = s.redeem("redeem_secure", &attacker, &FAKE_QUOTE_ID, ATTACKER_CREDITS);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(UNTRUSTED_QUOTE))],
+    );
+
+    let ix = s.redeem("redeem_secure", &attacker, &PROGRAM_ID, ATTACKER_CREDITS);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury)
+                .lamports(TREASURY - ATTACKER_CREDITS * PRICE)
+                .build(),
+            Check::account(&attacker)
+                .lamports(WALLET + ATTACKER_CREDITS * PRICE)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_redeem_through_oracle_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   User: redeem_secure(5,000 credits) through the oracle
+    //
+    // Expected: SUCCEEDS. The user receives 5 SOL and has no credits left.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (treasury, user) = (s.treasury, s.user);
+    let (balance, balance_bump) = pda(&[b"balance", user.as_ref()]);
+
+    let ix = s.redeem("redeem_secure", &user, &PROGRAM_ID, USER_CREDITS);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&treasury)
+                .lamports(TREASURY - USER_CREDITS * PRICE)
+                .build(),
+            Check::account(&user)
+                .lamports(WALLET + USER_CREDITS * PRICE)
+                .build(),
+            Check::account(&balance)
+                .data(&serialize_balance(&user, 0, balance_bump))
+                .build(),
+        ],
+    );
+}
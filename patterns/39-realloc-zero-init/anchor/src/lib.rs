@@ -0,0 +1,303 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+use rent_reserve::withdrawable_lamports;
+use std::ops::Range;
+
+declare_id!("6VxprPsRdRqxBCX1sPUR82b1h6GmZ3bY8kQ1qXH12tmt");
+
+/// Bytes every profile version starts with: the version, then the owner.
+pub const HEADER_LEN: usize = 1 + 32;
+/// Version 1: the header and a 64-byte display name.
+pub const V1_LEN: usize = HEADER_LEN + 64;
+/// Version 2: the header, credit limit, amount borrowed, and a 32-byte name.
+pub const V2_LEN: usize = HEADER_LEN + 8 + 8 + 32;
+
+const V1_NAME: Range<usize> = HEADER_LEN..V1_LEN;
+const V2_CREDIT_LIMIT: Range<usize> = HEADER_LEN..HEADER_LEN + 8;
+const V2_BORROWED: Range<usize> = HEADER_LEN + 8..HEADER_LEN + 16;
+const V2_NAME: Range<usize> = HEADER_LEN + 16..V2_LEN;
+
+/// # Realloc Without Zero-Initialization
+///
+/// This program demonstrates a lending pool whose borrower profiles are
+/// versioned byte layouts. Version 1 held a 64-byte display name. Version
+/// 2 adds a credit limit, which only the pool admin sets, and the amount
+/// borrowed, and keeps 32 bytes of the name. `migrate` moves a profile
+/// from one to the other in place: it cuts the account back to the header
+/// both versions share, then grows it to the version 2 length.
+///
+/// ## The Vulnerability
+/// The vulnerable migration grows the account with
+/// `realloc(V2_LEN, false)`. Shrinking only changed the account's length;
+/// the version 1 bytes are still in the buffer, and growing without
+/// zero-initialization hands them back. The migration writes the version
+/// and the name, and expects the new fields to start at zero. They start
+/// as the first 16 bytes of the old name, which the owner chose.
+///
+/// ## Real-World Impact
+/// A borrower writes a credit limit into their display name, migrates,
+/// and borrows the pool empty. Any layout change that shrinks and grows in
+/// one instruction, or reuses a buffer, can turn old bytes into new fields:
+/// authorities, flags, balances. The runtime zeroes data between
+/// instructions, so tests that migrate freshly loaded accounts one step at
+/// a time may never see it.
+#[program]
+pub mod realloc_zero_init {
+    use super::*;
+
+    pub fn create_pool(ctx: Context<CreatePool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.admin = ctx.accounts.admin.key();
+        pool.bump = ctx.bumps.pool;
+        demo_log!(Mode::Setup, "create_pool", actor = pool.admin);
+        Ok(())
+    }
+
+    /// Create a version 1 profile with a 64-byte display name.
+    pub fn create_profile(ctx: Context<CreateProfile>, name: [u8; 64]) -> Result<()> {
+        let mut data = ctx.accounts.profile.try_borrow_mut_data()?;
+        data[0] = 1;
+        data[1..HEADER_LEN].copy_from_slice(ctx.accounts.user.key().as_ref());
+        data[V1_NAME].copy_from_slice(&name);
+        demo_log!(
+            Mode::Setup,
+            "create_profile",
+            actor = ctx.accounts.user.key(),
+            version = 1
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Version 2 fields grown over version 1 bytes
+    // ============================================================================
+    // ISSUE: Shrinking to the header leaves the version 1 body in the
+    //        buffer. realloc(V2_LEN, false) grows the account back over it,
+    //        and the credit limit and amount borrowed are whatever the old
+    //        name held at those offsets.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory creates a version 1 profile whose name starts with
+    //      u64::MAX and then eight zero bytes
+    //   2. Mallory calls migrate_vulnerable
+    //   3. Her version 2 profile has a credit limit of u64::MAX and has
+    //      borrowed 0
+    //   4. Mallory borrows everything the pool can lend
+    // ============================================================================
+    pub fn migrate_vulnerable(ctx: Context<Migrate>) -> Result<()> {
+        let profile = ctx.accounts.profile.to_account_info();
+        require!(
+            profile.try_borrow_data()?.first() == Some(&1),
+            ErrorCode::LayoutMismatch
+        );
+        let name = keep_name(&profile)?;
+
+        // Drop the version 1 body, keeping the header both versions share
+        #[allow(deprecated)]
+        profile.realloc(HEADER_LEN, false)?;
+        // VULNERABLE: the grown bytes are the version 1 body, not zeroes
+        #[allow(deprecated)]
+        profile.realloc(V2_LEN, false)?;
+
+        let mut data = profile.try_borrow_mut_data()?;
+        data[0] = 2;
+        data[V2_NAME].copy_from_slice(&name);
+        demo_log!(
+            Mode::Vulnerable,
+            "migrate",
+            actor = ctx.accounts.user.key(),
+            credit_limit = read_u64(&data, V2_CREDIT_LIMIT),
+            borrowed = read_u64(&data, V2_BORROWED)
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Version checked against its length, grown region zeroed
+    // ============================================================================
+    // FIX: The profile must be exactly the version 1 layout before it is
+    //      read, and exactly the version 2 layout after it is grown.
+    //      resize() zero-initializes the grown region, so every field the
+    //      migration doesn't write is 0: no credit until the admin sets it.
+    // ============================================================================
+    pub fn migrate_secure(ctx: Context<Migrate>) -> Result<()> {
+        let profile = ctx.accounts.profile.to_account_info();
+        // SECURE: the version byte and the length must agree
+        check_layout(&profile, 1, V1_LEN)?;
+        let name = keep_name(&profile)?;
+
+        profile.resize(HEADER_LEN)?;
+        // SECURE: resize zero-initializes everything it grows
+        profile.resize(V2_LEN)?;
+
+        {
+            let mut data = profile.try_borrow_mut_data()?;
+            data[0] = 2;
+            data[V2_NAME].copy_from_slice(&name);
+        }
+        // SECURE: what was written is exactly a version 2 profile
+        check_layout(&profile, 2, V2_LEN)?;
+        let data = profile.try_borrow_data()?;
+        demo_log!(
+            Mode::Secure,
+            "migrate",
+            actor = ctx.accounts.user.key(),
+            credit_limit = read_u64(&data, V2_CREDIT_LIMIT),
+            borrowed = read_u64(&data, V2_BORROWED)
+        );
+        Ok(())
+    }
+
+    /// Admin: set a version 2 profile's credit limit.
+    pub fn set_credit_limit(ctx: Context<SetCreditLimit>, limit: u64) -> Result<()> {
+        let profile = ctx.accounts.profile.to_account_info();
+        check_layout(&profile, 2, V2_LEN)?;
+        let mut data = profile.try_borrow_mut_data()?;
+        data[V2_CREDIT_LIMIT].copy_from_slice(&limit.to_le_bytes());
+        demo_log!(
+            Mode::Setup,
+            "set_credit_limit",
+            actor = ctx.accounts.admin.key(),
+            limit = limit
+        );
+        Ok(())
+    }
+
+    /// Borrow `amount` lamports from the pool, up to the profile's limit.
+    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        let profile = ctx.accounts.profile.to_account_info();
+        check_layout(&profile, 2, V2_LEN)?;
+        let mut data = profile.try_borrow_mut_data()?;
+        let borrowed = read_u64(&data, V2_BORROWED)
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            borrowed <= read_u64(&data, V2_CREDIT_LIMIT),
+            ErrorCode::CreditLimitExceeded
+        );
+        require!(
+            amount <= withdrawable_lamports(&ctx.accounts.pool.to_account_info())?,
+            ErrorCode::InsufficientLiquidity
+        );
+        data[V2_BORROWED].copy_from_slice(&borrowed.to_le_bytes());
+
+        ctx.accounts.pool.sub_lamports(amount)?;
+        ctx.accounts.user.add_lamports(amount)?;
+        demo_log!(
+            Mode::Setup,
+            "borrow",
+            actor = ctx.accounts.user.key(),
+            amount = amount,
+            borrowed = borrowed
+        );
+        Ok(())
+    }
+}
+
+/// Require `profile` to hold `version` and be exactly `len` bytes.
+fn check_layout(profile: &AccountInfo, version: u8, len: usize) -> Result<()> {
+    let data = profile.try_borrow_data()?;
+    require!(
+        data.first() == Some(&version) && data.len() == len,
+        ErrorCode::LayoutMismatch
+    );
+    Ok(())
+}
+
+/// The first 32 bytes of a version 1 profile's name: what version 2 keeps.
+fn keep_name(profile: &AccountInfo) -> Result<[u8; 32]> {
+    let data = profile.try_borrow_data()?;
+    let name = data
+        .get(V1_NAME.start..V1_NAME.start + 32)
+        .ok_or(ErrorCode::LayoutMismatch)?;
+    Ok(name.try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], range: Range<usize>) -> u64 {
+    u64::from_le_bytes(data[range].try_into().unwrap())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool"],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProfile<'info> {
+    /// CHECK: Created here with the version 1 length; its layout is
+    /// written and read by hand.
+    #[account(
+        init,
+        payer = user,
+        space = V1_LEN,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub profile: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    /// CHECK: The user's profile PDA; the handler checks its layout.
+    #[account(mut, seeds = [b"profile", user.key().as_ref()], bump)]
+    pub profile: UncheckedAccount<'info>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCreditLimit<'info> {
+    #[account(seeds = [b"pool"], bump = pool.bump, has_one = admin)]
+    pub pool: Account<'info, Pool>,
+    /// CHECK: Any profile this program owns; the handler checks its layout.
+    #[account(mut, owner = crate::ID)]
+    pub profile: UncheckedAccount<'info>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(mut, seeds = [b"pool"], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+    /// CHECK: The user's profile PDA; the handler checks its layout.
+    #[account(mut, seeds = [b"profile", user.key().as_ref()], bump)]
+    pub profile: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+/// The pool PDA holds the lamports it lends.
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub admin: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Borrowing would exceed the profile's credit limit")]
+    CreditLimitExceeded,
+    #[msg("Profile data doesn't match its version's layout")]
+    LayoutMismatch,
+    #[msg("The pool can't lend that much")]
+    InsufficientLiquidity,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
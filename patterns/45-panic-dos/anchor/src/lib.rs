@@ -0,0 +1,245 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("DrhgMirpxL26Ekmqn2fKWYxgbM18M6aTL8AAcFRUhVxH");
+
+/// Pools an order can route through.
+pub const POOLS: usize = 3;
+/// Orders the book holds at once.
+pub const MAX_ORDERS: usize = 8;
+/// Bytes in a packed order: an 8-byte amount and up to four hops.
+pub const MAX_ORDER_LEN: usize = 12;
+/// Rates are in basis points of the input.
+pub const BPS: u64 = 10_000;
+
+/// # Panic as DoS
+///
+/// This program demonstrates an order book settled by a permissionless
+/// crank. Anyone submits an order as packed bytes, an amount followed by
+/// the pools to route it through, and anyone can crank the book to settle
+/// the order at its head.
+///
+/// ## The Vulnerability
+/// The vulnerable crank decodes the head order with `unwrap()`, slice
+/// ranges, and `[i]` indexing. Each of those panics on input the crank
+/// didn't expect: an order shorter than 8 bytes, one with no hops, one
+/// naming a pool that doesn't exist, or an amount large enough to overflow.
+/// A panic aborts the whole instruction, so the order is never removed from
+/// the head of the queue. Every later crank reads the same order and
+/// panics the same way.
+///
+/// ## Real-World Impact
+/// One malformed order, costing its submitter a transaction fee, stops
+/// settlement for every order behind it, for good. Cranks, keepers,
+/// liquidators, and reward distributors all process state that other
+/// people wrote; a panic anywhere in that path turns their data into a
+/// kill switch. The runtime reports only `ProgramFailedToComplete`, so the
+/// keeper's operator doesn't even learn which check failed.
+#[program]
+pub mod panic_dos {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, rates_bps: [u64; POOLS]) -> Result<()> {
+        let book = &mut ctx.accounts.book;
+        book.admin = ctx.accounts.admin.key();
+        book.rates_bps = rates_bps;
+        book.settled = 0;
+        book.rejected = 0;
+        book.volume = 0;
+        book.orders = Vec::new();
+        book.bump = ctx.bumps.book;
+        demo_log!(Mode::Setup, "initialize", actor = book.admin);
+        Ok(())
+    }
+
+    /// Queue an order: `data` is an 8-byte little-endian amount followed
+    /// by one pool index per hop. Only its length is checked.
+    pub fn submit(ctx: Context<Submit>, data: Vec<u8>) -> Result<()> {
+        let book = &mut ctx.accounts.book;
+        require!(book.orders.len() < MAX_ORDERS, ErrorCode::QueueFull);
+        require!(data.len() <= MAX_ORDER_LEN, ErrorCode::OrderTooLong);
+        book.orders.push(Order {
+            owner: ctx.accounts.owner.key(),
+            data,
+        });
+        demo_log!(
+            Mode::Setup,
+            "submit",
+            actor = ctx.accounts.owner.key(),
+            queued = book.orders.len()
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Crank that panics on a malformed order
+    // ============================================================================
+    // ISSUE: `quote_vulnerable` slices, indexes, and unwraps bytes an
+    //        arbitrary user wrote. A panic aborts the instruction before the
+    //        order is removed, so the same order is at the head next time.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory submits a 3-byte order
+    //   2. Alice submits a valid order behind it
+    //   3. Every crank_vulnerable slices `data[..8]` out of 3 bytes and
+    //      panics: Alice's order is never settled, nor is anyone's after it
+    // ============================================================================
+    pub fn crank_vulnerable(ctx: Context<Crank>) -> Result<()> {
+        let book = &mut ctx.accounts.book;
+        // VULNERABLE: panics on an empty book, too
+        let order = book.orders.remove(0);
+        let out = quote_vulnerable(&book.rates_bps, &order.data);
+        settle(book, out)?;
+        demo_log!(Mode::Vulnerable, "crank", actor = order.owner, out = out);
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Crank that rejects a malformed order and moves on
+    // ============================================================================
+    // FIX: `quote` returns a typed error for every way an order can be
+    //      malformed, instead of panicking. A `Result` can be handled: the
+    //      crank removes the bad order, counts it as rejected, and
+    //      succeeds, so the next crank reaches the order behind it.
+    // ============================================================================
+    pub fn crank_secure(ctx: Context<Crank>) -> Result<()> {
+        let book = &mut ctx.accounts.book;
+        require!(!book.orders.is_empty(), ErrorCode::QueueEmpty);
+        let order = book.orders.remove(0);
+        // SECURE: a bad order is rejected, not fatal
+        match quote(&book.rates_bps, &order.data) {
+            Ok(out) => {
+                settle(book, out)?;
+                demo_log!(Mode::Secure, "crank", actor = order.owner, out = out);
+            }
+            Err(err) => {
+                book.rejected = book.rejected.checked_add(1).ok_or(ErrorCode::Overflow)?;
+                demo_log!(
+                    Mode::Secure,
+                    "reject",
+                    actor = order.owner,
+                    error = u32::from(err)
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The output of a packed order, assuming it is well formed.
+fn quote_vulnerable(rates_bps: &[u64; POOLS], data: &[u8]) -> u64 {
+    // VULNERABLE: slicing past the end panics
+    let amount = u64::from_le_bytes(data[..8].try_into().unwrap());
+    let route = &data[8..];
+    // VULNERABLE: an order with no hops panics here
+    let mut out = hop_vulnerable(rates_bps, amount, route[0]);
+    for &pool in &route[1..] {
+        out = hop_vulnerable(rates_bps, out, pool);
+    }
+    out
+}
+
+/// `amount` through `pool`.
+fn hop_vulnerable(rates_bps: &[u64; POOLS], amount: u64, pool: u8) -> u64 {
+    // VULNERABLE: an unknown pool panics, and so does an overflow
+    amount.checked_mul(rates_bps[pool as usize]).unwrap() / BPS
+}
+
+/// The output of a packed order, or why it has none.
+fn quote(rates_bps: &[u64; POOLS], data: &[u8]) -> std::result::Result<u64, ErrorCode> {
+    // SECURE: every fallible step is a typed error
+    let (amount, route) = data
+        .split_first_chunk::<8>()
+        .ok_or(ErrorCode::MalformedOrder)?;
+    if route.is_empty() {
+        return Err(ErrorCode::EmptyRoute);
+    }
+    let mut out = u64::from_le_bytes(*amount);
+    for &pool in route {
+        let rate = *rates_bps.get(pool as usize).ok_or(ErrorCode::UnknownPool)?;
+        out = out.checked_mul(rate).ok_or(ErrorCode::Overflow)? / BPS;
+    }
+    Ok(out)
+}
+
+/// Record a settled order with output `out`.
+fn settle(book: &mut Book, out: u64) -> Result<()> {
+    book.settled = book.settled.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    book.volume = book.volume.checked_add(out).ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Book::INIT_SPACE,
+        seeds = [b"book"],
+        bump
+    )]
+    pub book: Account<'info, Book>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Submit<'info> {
+    #[account(mut, seeds = [b"book"], bump = book.bump)]
+    pub book: Account<'info, Book>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Crank<'info> {
+    #[account(mut, seeds = [b"book"], bump = book.bump)]
+    pub book: Account<'info, Book>,
+    /// Anyone: settling the head order is permissionless.
+    pub cranker: Signer<'info>,
+}
+
+/// The pools' rates, what has been settled, and the queue of orders, head
+/// first.
+#[account]
+#[derive(InitSpace)]
+pub struct Book {
+    pub admin: Pubkey,           // 32 bytes
+    pub rates_bps: [u64; POOLS], // 24 bytes
+    pub settled: u64,            //  8 bytes: orders settled
+    pub rejected: u64,           //  8 bytes: orders rejected as malformed
+    pub volume: u64,             //  8 bytes: total output settled
+    pub bump: u8,                //  1 byte
+    #[max_len(MAX_ORDERS)]
+    pub orders: Vec<Order>, // 4 + 48 * MAX_ORDERS bytes
+}
+
+/// A queued order, packed as submitted.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Order {
+    pub owner: Pubkey, // 32 bytes
+    #[max_len(MAX_ORDER_LEN)]
+    pub data: Vec<u8>, // 4 + MAX_ORDER_LEN bytes
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The book is full")]
+    QueueFull,
+    #[msg("An order is at most 12 bytes")]
+    OrderTooLong,
+    #[msg("No order to settle")]
+    QueueEmpty,
+    #[msg("An order starts with an 8-byte amount")]
+    MalformedOrder,
+    #[msg("An order routes through at least one pool")]
+    EmptyRoute,
+    #[msg("No pool at that index")]
+    UnknownPool,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,286 @@
+// Mollusk tests for Pattern 45
+/// # Pattern 45: Panic as DoS — Mollusk Exploit Tests
+///
+/// The book starts empty, with pools 0, 1, and 2 at 99.70%, 100.50%, and
+/// 99.00%. Alice's order is 1,000,000 through pools 0 then 1. Mallory's is
+/// one of four malformed orders: 3 bytes, too short for the amount; an
+/// amount and no hops; an amount and pool 7, which doesn't exist; and an
+/// amount of u64::MAX, which overflows at the first hop.
+///
+/// - Test 1: EXPLOIT — with each of Mallory's orders ahead of Alice's,
+///   crank_vulnerable panics, and panics again on the next crank.
+/// - Test 2: SECURE — crank_secure rejects each of Mallory's orders, and
+///   the next crank settles Alice's.
+/// - Test 3: SANITY — both cranks settle Alice's order alone for 1,001,985.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("DrhgMirpxL26Ekmqn2fKWYxgbM18M6aTL8AAcFRUhVxH");
+
+const RENT: u64 = 10_000_000;
+const WALLET: u64 = 1_000_000_000;
+
+const RATES_BPS: [u64; 3] = [9_970, 10_050, 9_900];
+const AMOUNT: u64 = 1_000_000;
+/// AMOUNT through pool 0, then pool 1, rounding down at each hop.
+const ALICE_OUT: u64 = 1_001_985;
+
+/// 8 disc + 32 admin + 24 rates + 8 settled + 8 rejected + 8 volume +
+/// 1 bump + 4 + 8 * (32 owner + 4 + 12 data) orders.
+const BOOK_LEN: usize = 477;
+/// Where `settled` starts; `rejected`, `volume`, `bump`, and the order
+/// count follow.
+const SETTLED_OFFSET: usize = 64;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// [8 disc][32 admin][24 rates][8 settled][8 rejected][8 volume][1 bump]
+/// [4 orders: 0], zero-padded to BOOK_LEN.
+fn serialize_empty_book(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(BOOK_LEN);
+    data.extend_from_slice(&account_discriminator("Book"));
+    data.extend_from_slice(admin.as_ref());
+    for rate in RATES_BPS {
+        data.extend_from_slice(&rate.to_le_bytes());
+    }
+    data.extend_from_slice(&[0u8; 24]);
+    data.push(bump);
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.resize(BOOK_LEN, 0);
+    data
+}
+
+/// A packed order: the amount, then one byte per hop.
+fn order(amount: u64, route: &[u8]) -> Vec<u8> {
+    let mut data = amount.to_le_bytes().to_vec();
+    data.extend_from_slice(route);
+    data
+}
+
+fn alice_order() -> Vec<u8> {
+    order(AMOUNT, &[0, 1])
+}
+
+/// Mallory's orders, one for each way the vulnerable crank panics.
+fn malformed_orders() -> [Vec<u8>; 4] {
+    [
+        vec![1, 2, 3],
+        order(AMOUNT, &[]),
+        order(AMOUNT, &[7]),
+        order(u64::MAX, &[0]),
+    ]
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+/// The book's counters.
+#[derive(Debug, PartialEq)]
+struct Counts {
+    settled: u64,
+    rejected: u64,
+    volume: u64,
+    queued: u32,
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    book: Pubkey,
+    mallory: Pubkey,
+    alice: Pubkey,
+    cranker: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// An empty book, and Mallory, Alice, and a cranker.
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "panic_dos");
+        let admin = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let cranker = Pubkey::new_unique();
+        let (book, bump) = Pubkey::find_program_address(&[b"book"], &PROGRAM_ID);
+
+        let mut book_account = AccountSharedData::new(RENT, BOOK_LEN, &PROGRAM_ID);
+        book_account.set_data_from_slice(&serialize_empty_book(&admin, bump));
+
+        let ledger = vec![
+            (book, book_account),
+            (mallory, system_account()),
+            (alice, system_account()),
+            (cranker, system_account()),
+        ];
+
+        Self {
+            mollusk,
+            book,
+            mallory,
+            alice,
+            cranker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// submit, by `owner`.
+    fn submit(&self, owner: &Pubkey, order: &[u8]) -> Instruction {
+        let mut data = ix_discriminator("submit").to_vec();
+        data.extend_from_slice(&(order.len() as u32).to_le_bytes());
+        data.extend_from_slice(order);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.book, false),
+                AccountMeta::new_readonly(*owner, true),
+            ],
+        )
+    }
+
+    /// crank_vulnerable or crank_secure, by the cranker.
+    fn crank(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.book, false),
+                AccountMeta::new_readonly(self.cranker, true),
+            ],
+        )
+    }
+
+    /// Mallory's `order`, then Alice's behind it.
+    fn queue_behind(&mut self, order: &[u8]) {
+        let ix = self.submit(&self.mallory, order);
+        self.ledger.step(&self.mollusk, &ix, &[Check::success()]);
+        let ix = self.submit(&self.alice, &alice_order());
+        self.ledger.step(&self.mollusk, &ix, &[Check::success()]);
+    }
+
+    /// The book's counters, as the ledger has them.
+    fn counts(&self) -> Counts {
+        let data = self.ledger.data(&self.book);
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let len_offset = SETTLED_OFFSET + 25;
+        Counts {
+            settled: u64_at(SETTLED_OFFSET),
+            rejected: u64_at(SETTLED_OFFSET + 8),
+            volume: u64_at(SETTLED_OFFSET + 16),
+            queued: u32::from_le_bytes(data[len_offset..len_offset + 4].try_into().unwrap()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_malformed_order_bricks_crank_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: for each malformed order, Mallory submits it and Alice
+    //          submits hers behind it. Anyone calls crank_vulnerable, twice.
+    //
+    // Expected: both cranks FAIL with ProgramFailedToComplete. The panic
+    //           undoes the crank, so Mallory's order stays at the head and
+    //           Alice's is never reached.
+    // -----------------------------------------------------------------------
+    for malformed in malformed_orders() {
+        let mut s = Setup::new();
+        s.queue_behind(&malformed);
+
+        let ix = s.crank("crank_vulnerable");
+        for _ in 0..2 {
+            s.ledger.step(
+                &s.mollusk,
+                &ix,
+                &[Check::instruction_err(
+                    InstructionError::ProgramFailedToComplete,
+                )],
+            );
+        }
+        assert_eq!(
+            s.counts(),
+            Counts {
+                settled: 0,
+                rejected: 0,
+                volume: 0,
+                queued: 2,
+            }
+        );
+    }
+}
+
+#[test]
+fn secure_crank_rejects_malformed_order() {
+    // -----------------------------------------------------------------------
+    // SECURE: the same queues, cranked with crank_secure, twice
+    //
+    // Expected: both cranks SUCCEED. The first rejects Mallory's order and
+    //           removes it; the second settles Alice's for 1,001,985.
+    // -----------------------------------------------------------------------
+    for malformed in malformed_orders() {
+        let mut s = Setup::new();
+        s.queue_behind(&malformed);
+
+        let ix = s.crank("crank_secure");
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        assert_eq!(
+            s.counts(),
+            Counts {
+                settled: 0,
+                rejected: 1,
+                volume: 0,
+                queued: 1,
+            }
+        );
+
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        assert_eq!(
+            s.counts(),
+            Counts {
+                settled: 1,
+                rejected: 1,
+                volume: ALICE_OUT,
+                queued: 0,
+            }
+        );
+    }
+}
+
+#[test]
+fn sanity_valid_order_settles() {
+    // -----------------------------------------------------------------------
+    // SANITY: Alice's order alone, cranked with crank_vulnerable, and
+    //         separately with crank_secure
+    //
+    // Expected: SUCCEEDS both ways, settling 1,001,985.
+    // -----------------------------------------------------------------------
+    for name in ["crank_vulnerable", "crank_secure"] {
+        let mut s = Setup::new();
+        let alice = s.alice;
+        let ix = s.submit(&alice, &alice_order());
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+        let ix = s.crank(name);
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        assert_eq!(
+            s.counts(),
+            Counts {
+                settled: 1,
+                rejected: 0,
+                volume: ALICE_OUT,
+                queued: 0,
+            }
+        );
+    }
+}
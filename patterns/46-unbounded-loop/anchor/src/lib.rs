@@ -0,0 +1,249 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("AV9WkCTrqsMv9KshJbQRrXosjcD2o9xi19r3KeWgLKX2");
+
+/// Claimants `distribute_secure` credits per call.
+pub const PAGE: u64 = 256;
+/// Bytes before the first claimant: the discriminator and the header.
+pub const HEADER_LEN: usize = 8 + Registry::INIT_SPACE;
+/// Bytes per claimant.
+pub const CLAIMANT_LEN: usize = Claimant::INIT_SPACE;
+
+/// # Unbounded Loop
+///
+/// This program demonstrates a reward registry. Anyone can register as a
+/// claimant, and each round credits every claimant the same reward. The
+/// claimants are stored one after another behind the registry's header,
+/// and each registration grows the account by one entry.
+///
+/// ## The Vulnerability
+/// The vulnerable distribution credits every claimant in one instruction.
+/// Its cost grows with the registry, and the registry grows whenever
+/// anyone pays to register. An instruction can use at most 1,400,000
+/// compute units; once crediting every claimant costs more than that,
+/// `distribute_vulnerable` fails every time it runs, and no round ever
+/// completes again.
+///
+/// ## Real-World Impact
+/// Registering costs the rent on one entry and a transaction fee. For a
+/// few SOL, an attacker registers enough claimants to push the loop past
+/// the limit, and rewards stop for everyone, honest claimants included.
+/// No compute budget request helps: the limit is the runtime's, not the
+/// caller's. Distributions, liquidations over every position, and
+/// end-of-epoch settlements over every account all fail this way.
+#[program]
+pub mod unbounded_loop {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, reward: u64) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.admin = ctx.accounts.admin.key();
+        registry.reward = reward;
+        registry.round = 0;
+        registry.count = 0;
+        registry.cursor = 0;
+        registry.round_end = 0;
+        registry.bump = ctx.bumps.registry;
+        demo_log!(
+            Mode::Setup,
+            "initialize",
+            actor = registry.admin,
+            reward = reward
+        );
+        Ok(())
+    }
+
+    /// Append the signer as a claimant. The registry grows by one entry,
+    /// paid for by the signer.
+    pub fn register(ctx: Context<Register>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        let index = registry.count;
+        let claimant = Claimant {
+            owner: ctx.accounts.owner.key(),
+            accrued: 0,
+        };
+        let info = registry.to_account_info();
+        let mut data = info.try_borrow_mut_data()?;
+        let entry = entry_mut(&mut data, index)?;
+        claimant.serialize(&mut &mut entry[..])?;
+        registry.count = index.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "register",
+            actor = claimant.owner,
+            index = index
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Credits every claimant in one instruction
+    // ============================================================================
+    // ISSUE: The loop runs `registry.count` times, and anyone can raise
+    //        `registry.count` by registering. Past some size, the loop
+    //        costs more than the 1,400,000 CU an instruction can use.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice registers
+    //   2. Mallory registers 49,999 claimants, for about 14 SOL of rent
+    //   3. Every distribute_vulnerable runs out of compute partway
+    //      through: no round completes, and Alice is never credited
+    // ============================================================================
+    pub fn distribute_vulnerable(ctx: Context<Distribute>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        let info = registry.to_account_info();
+        let mut data = info.try_borrow_mut_data()?;
+        // VULNERABLE: one pass over every claimant, however many there are
+        for index in 0..registry.count {
+            credit(&mut data, index, registry.reward)?;
+        }
+        registry.round = registry.round.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "distribute",
+            actor = ctx.accounts.cranker.key(),
+            round = registry.round,
+            credited = registry.count
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Credits one page of claimants per instruction
+    // ============================================================================
+    // FIX: `registry.cursor` records where the round has got to. Each call
+    //      credits at most PAGE claimants from there and saves the cursor,
+    //      so its cost is bounded however large the registry grows. The
+    //      round's length is fixed when it starts, so registering during a
+    //      round can't keep it from ending.
+    // ============================================================================
+    pub fn distribute_secure(ctx: Context<Distribute>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        if registry.cursor == 0 {
+            registry.round_end = registry.count;
+        }
+        let start = registry.cursor;
+        // SECURE: at most PAGE claimants, whatever the registry's size
+        let end = start.saturating_add(PAGE).min(registry.round_end);
+        let info = registry.to_account_info();
+        let mut data = info.try_borrow_mut_data()?;
+        for index in start..end {
+            credit(&mut data, index, registry.reward)?;
+        }
+
+        if end == registry.round_end {
+            registry.cursor = 0;
+            registry.round = registry.round.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        } else {
+            registry.cursor = end;
+        }
+        demo_log!(
+            Mode::Secure,
+            "distribute",
+            actor = ctx.accounts.cranker.key(),
+            round = registry.round,
+            from = start,
+            to = end
+        );
+        Ok(())
+    }
+}
+
+/// Claimant `index`'s bytes within the registry's data.
+fn entry_mut(data: &mut [u8], index: u64) -> Result<&mut [u8]> {
+    let start = usize::try_from(index)
+        .ok()
+        .and_then(|index| index.checked_mul(CLAIMANT_LEN))
+        .and_then(|offset| offset.checked_add(HEADER_LEN))
+        .ok_or(ErrorCode::MissingClaimant)?;
+    let entry = data
+        .get_mut(start..start + CLAIMANT_LEN)
+        .ok_or(ErrorCode::MissingClaimant)?;
+    Ok(entry)
+}
+
+/// Add `amount` to claimant `index`'s accrued rewards.
+fn credit(data: &mut [u8], index: u64, amount: u64) -> Result<()> {
+    let entry = entry_mut(data, index)?;
+    let mut claimant = Claimant::deserialize(&mut &entry[..])?;
+    claimant.accrued = claimant
+        .accrued
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    claimant.serialize(&mut &mut entry[..])?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = HEADER_LEN,
+        seeds = [b"registry"],
+        bump
+    )]
+    pub registry: Account<'info, Registry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Register<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump,
+        realloc = HEADER_LEN + (registry.count as usize + 1) * CLAIMANT_LEN,
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub registry: Account<'info, Registry>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Distribute<'info> {
+    #[account(mut, seeds = [b"registry"], bump = registry.bump)]
+    pub registry: Account<'info, Registry>,
+    /// Anyone: distributing a round is permissionless.
+    pub cranker: Signer<'info>,
+}
+
+/// The reward per round and where the current round has got to. `count`
+/// claimants follow the header in the account's data, CLAIMANT_LEN bytes
+/// each.
+#[account]
+#[derive(InitSpace)]
+pub struct Registry {
+    pub admin: Pubkey,  // 32 bytes
+    pub reward: u64,    //  8 bytes: credited to each claimant per round
+    pub round: u64,     //  8 bytes: rounds completed
+    pub count: u64,     //  8 bytes: claimants registered
+    pub cursor: u64,    //  8 bytes: next claimant this round
+    pub round_end: u64, //  8 bytes: claimants in this round
+    pub bump: u8,       //  1 byte
+}
+
+/// One registered claimant and the rewards credited to it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Claimant {
+    pub owner: Pubkey, // 32 bytes
+    pub accrued: u64,  //  8 bytes
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("No claimant at that index")]
+    MissingClaimant,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
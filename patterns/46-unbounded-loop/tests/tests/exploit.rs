@@ -0,0 +1,320 @@
+// Mollusk tests for Pattern 46
+/// # Pattern 46: Unbounded Loop — Mollusk Exploit Tests
+///
+/// The registry credits 1,000 per claimant per round. Alice registered
+/// first, at index 0. In the large registry, Mallory has registered the
+/// other 49,999 entries.
+///
+/// - Test 1: EXPLOIT — with 50,000 claimants, distribute_vulnerable runs
+///   out of compute at the runtime's 1,400,000 CU limit, every time.
+/// - Test 2: SECURE — distribute_secure completes the same round in 196
+///   calls of at most 256 claimants, each within a keeper's 200,000 CU.
+/// - Test 3: SECURE — a claimant who registers mid-round waits for the
+///   next one, so registering can't stretch a round out.
+/// - Test 4: SANITY — with three claimants, both distributions credit
+///   everyone in one call.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("AV9WkCTrqsMv9KshJbQRrXosjcD2o9xi19r3KeWgLKX2");
+
+const WALLET: u64 = 10_000_000_000;
+/// Rent-exempt for the largest registry here, with room to grow.
+const RENT: u64 = 20_000_000_000;
+
+/// What a keeper requests per instruction, and the most anyone can.
+const KEEPER_BUDGET: u64 = 200_000;
+const MAX_BUDGET: u64 = 1_400_000;
+
+const REWARD: u64 = 1_000;
+const PAGE: usize = 256;
+const LARGE: usize = 50_000;
+
+/// 8 disc + 32 admin + 8 reward + 8 round + 8 count + 8 cursor +
+/// 8 round_end + 1 bump.
+const HEADER_LEN: usize = 81;
+/// 32 owner + 8 accrued.
+const CLAIMANT_LEN: usize = 40;
+/// Where `round` starts; `count`, `cursor`, and `round_end` follow.
+const ROUND_OFFSET: usize = 48;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// A registry at the start of round 0:
+///   [8 disc][32 admin][8 reward][8 round][8 count][8 cursor][8 round_end]
+///   [1 bump], then [32 owner][8 accrued] per claimant.
+fn serialize_registry(admin: &Pubkey, owners: &[Pubkey], bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(HEADER_LEN + owners.len() * CLAIMANT_LEN);
+    data.extend_from_slice(&account_discriminator("Registry"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&REWARD.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&(owners.len() as u64).to_le_bytes());
+    data.extend_from_slice(&[0u8; 16]);
+    data.push(bump);
+    for owner in owners {
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&0u64.to_le_bytes());
+    }
+    data
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+/// A Mollusk that gives each instruction `budget` compute units.
+fn new_mollusk(budget: u64) -> Mollusk {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "unbounded_loop");
+    mollusk.compute_budget.compute_unit_limit = budget;
+    mollusk
+}
+
+/// The registry's progress.
+#[derive(Debug, PartialEq)]
+struct Progress {
+    round: u64,
+    count: u64,
+    cursor: u64,
+    round_end: u64,
+}
+
+struct Setup {
+    registry: Pubkey,
+    bob: Pubkey,
+    cranker: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A registry of `claimants` entries: Alice's, then Mallory's. Bob and
+    /// a cranker have wallets but aren't registered.
+    fn new(claimants: usize) -> Self {
+        let (registry, bump) = Pubkey::find_program_address(&[b"registry"], &PROGRAM_ID);
+        let alice = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let cranker = Pubkey::new_unique();
+
+        let mut owners = vec![alice];
+        owners.resize(claimants, mallory);
+        let data = serialize_registry(&Pubkey::new_unique(), &owners, bump);
+        let mut registry_account = AccountSharedData::new(RENT, data.len(), &PROGRAM_ID);
+        registry_account.set_data_from_slice(&data);
+
+        let ledger = vec![
+            (registry, registry_account),
+            (bob, system_account()),
+            (cranker, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            registry,
+            bob,
+            cranker,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// register, by `owner`.
+    fn register(&self, owner: &Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator("register"),
+            vec![
+                AccountMeta::new(self.registry, false),
+                AccountMeta::new(*owner, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// distribute_vulnerable or distribute_secure, by the cranker.
+    fn distribute(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.registry, false),
+                AccountMeta::new_readonly(self.cranker, true),
+            ],
+        )
+    }
+
+    fn registry_data(&self) -> &[u8] {
+        self.ledger.data(&self.registry)
+    }
+
+    fn progress(&self) -> Progress {
+        let data = self.registry_data();
+        let u64_at = |offset: usize| {
+            let start = ROUND_OFFSET + offset;
+            u64::from_le_bytes(data[start..start + 8].try_into().unwrap())
+        };
+        Progress {
+            round: u64_at(0),
+            count: u64_at(8),
+            cursor: u64_at(16),
+            round_end: u64_at(24),
+        }
+    }
+
+    /// Rewards credited to claimant `index`.
+    fn accrued(&self, index: usize) -> u64 {
+        let start = HEADER_LEN + index * CLAIMANT_LEN + 32;
+        u64::from_le_bytes(self.registry_data()[start..start + 8].try_into().unwrap())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_large_registry_exhausts_compute_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: 50,000 claimants, 49,999 of them Mallory's. Anyone calls
+    //          distribute_vulnerable with the most compute an instruction
+    //          can have, twice.
+    //
+    // Expected: both FAIL with ComputationalBudgetExceeded. Nothing is
+    //           credited and the round never completes; no caller can ask
+    //           for more compute than this.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(MAX_BUDGET);
+    let mut s = Setup::new(LARGE);
+
+    let ix = s.distribute("distribute_vulnerable");
+    for _ in 0..2 {
+        s.ledger.step(
+            &mollusk,
+            &ix,
+            &[Check::instruction_err(
+                InstructionError::ComputationalBudgetExceeded,
+            )],
+        );
+    }
+    assert_eq!(s.progress().round, 0);
+    assert_eq!(s.accrued(0), 0);
+}
+
+#[test]
+fn secure_paginated_distribution_completes() {
+    // -----------------------------------------------------------------------
+    // SECURE: the same 50,000 claimants. A keeper calls distribute_secure
+    //         with 200,000 CU until the round completes.
+    //
+    // Expected: 196 calls SUCCEED, each crediting up to 256 claimants and
+    //           saving the cursor, and none needs more than the keeper's
+    //           200,000 CU. The round completes, Alice and the last
+    //           claimant are credited 1,000 each, and the cursor is back
+    //           at 0.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(KEEPER_BUDGET);
+    let mut s = Setup::new(LARGE);
+
+    let ix = s.distribute("distribute_secure");
+    let calls = LARGE.div_ceil(PAGE);
+    let mut most = 0;
+    for call in 1..=calls {
+        let result = s.ledger.step(&mollusk, &ix, &[Check::success()]);
+        most = most.max(result.compute_units_consumed);
+        let expected = if call < calls {
+            (call * PAGE) as u64
+        } else {
+            0
+        };
+        assert_eq!(s.progress().cursor, expected);
+    }
+    assert!(
+        most <= KEEPER_BUDGET,
+        "a page of {PAGE} used {most} CU, over the keeper's {KEEPER_BUDGET}"
+    );
+
+    assert_eq!(
+        s.progress(),
+        Progress {
+            round: 1,
+            count: LARGE as u64,
+            cursor: 0,
+            round_end: LARGE as u64,
+        }
+    );
+    assert_eq!(s.accrued(0), REWARD);
+    assert_eq!(s.accrued(LARGE - 1), REWARD);
+}
+
+#[test]
+fn secure_late_registration_waits_for_next_round() {
+    // -----------------------------------------------------------------------
+    // SECURE: 300 claimants.
+    //   1. distribute_secure credits claimants 0–255
+    //   2. Bob registers, as claimant 300
+    //   3. distribute_secure credits claimants 256–299
+    //
+    // Expected: all SUCCEED. Step 3 completes the round without Bob, whose
+    //           entry was appended after the round's length was fixed.
+    //           Bob's entry is credited in round 2.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(KEEPER_BUDGET);
+    let mut s = Setup::new(300);
+    let bob = s.bob;
+
+    let ix = s.distribute("distribute_secure");
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+    let register = s.register(&bob);
+    s.ledger.step(&mollusk, &register, &[Check::success()]);
+    assert_eq!(
+        s.progress(),
+        Progress {
+            round: 0,
+            count: 301,
+            cursor: PAGE as u64,
+            round_end: 300,
+        }
+    );
+
+    s.ledger.step(&mollusk, &ix, &[Check::success()]);
+    assert_eq!(s.progress().round, 1);
+    assert_eq!(s.accrued(299), REWARD);
+    assert_eq!(s.accrued(300), 0);
+
+    for _ in 0..2 {
+        s.ledger.step(&mollusk, &ix, &[Check::success()]);
+    }
+    assert_eq!(s.progress().round, 2);
+    assert_eq!(s.accrued(300), REWARD);
+}
+
+#[test]
+fn sanity_small_registry_distributes() {
+    // -----------------------------------------------------------------------
+    // SANITY: three claimants, distributed with distribute_vulnerable, and
+    //         separately with distribute_secure, within a keeper's budget
+    //
+    // Expected: SUCCEEDS both ways in one call, crediting all three.
+    // -----------------------------------------------------------------------
+    let mollusk = new_mollusk(KEEPER_BUDGET);
+    for name in ["distribute_vulnerable", "distribute_secure"] {
+        let mut s = Setup::new(3);
+        let ix = s.distribute(name);
+        s.ledger.step(&mollusk, &ix, &[Check::success()]);
+
+        assert_eq!(s.progress().round, 1);
+        for index in 0..3 {
+            assert_eq!(s.accrued(index), REWARD);
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("4XrXfb17yxjezQDUo6Zw5mRjsutgJ3qJL79PKzqr8mrf");
+
+/// Listings a board holds at once.
+pub const MAX_LISTINGS: usize = 16;
+/// Listings one owner can hold at once on the secure board.
+pub const MAX_PER_OWNER: u8 = 2;
+/// Lamports held per listing on the secure board, refunded on delisting.
+pub const LISTING_DEPOSIT: u64 = 100_000_000;
+
+/// # State Bloat
+///
+/// This program demonstrates a shared listing board: owners list an item
+/// at a price, and buyers browse one account to see every listing. The
+/// account was allocated for a fixed number of listings.
+///
+/// ## The Vulnerability
+/// The vulnerable board is a `Vec` that anyone can append to, as often as
+/// they like, for nothing but the transaction fee. When it holds as many
+/// listings as the account has room for, the next one no longer
+/// serializes, and `list_vulnerable` fails for everyone. Nothing makes
+/// the entries that filled it go away.
+///
+/// ## Real-World Impact
+/// For a few transaction fees, one key fills the board with listings no
+/// one will buy, and honest sellers can't list at all. Any shared account
+/// that users append to, like order books, allowlists, queues, and
+/// registries, has the same ceiling. Growing the account only raises it:
+/// the attacker fills it again, and the protocol pays the rent.
+#[program]
+pub mod state_bloat {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let board = &mut ctx.accounts.board;
+        board.listings = Vec::new();
+        board.bump = ctx.bumps.board;
+        let slot_board = &mut ctx.accounts.slot_board;
+        slot_board.slots = [Slot::default(); MAX_LISTINGS];
+        slot_board.bump = ctx.bumps.slot_board;
+        demo_log!(Mode::Setup, "initialize", actor = ctx.accounts.admin.key());
+        Ok(())
+    }
+
+    /// Create the signer's record of how many listings it holds on the
+    /// secure board.
+    pub fn open_seller(ctx: Context<OpenSeller>) -> Result<()> {
+        let seller = &mut ctx.accounts.seller;
+        seller.owner = ctx.accounts.owner.key();
+        seller.listings = 0;
+        seller.bump = ctx.bumps.seller;
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Anyone appends, for free, without limit
+    // ============================================================================
+    // ISSUE: No cap per owner and nothing at stake, so one key can take
+    //        every entry the account has room for. Once the Vec is full,
+    //        it no longer fits the account, and every list fails.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory calls list_vulnerable 16 times, paying only fees
+    //   2. Alice calls list_vulnerable: the board doesn't serialize into
+    //      its account, and her listing fails
+    //   3. It fails for every seller, for as long as Mallory's listings
+    //      stay, which is as long as Mallory wants
+    // ============================================================================
+    pub fn list_vulnerable(ctx: Context<ListVulnerable>, price: u64) -> Result<()> {
+        let board = &mut ctx.accounts.board;
+        // VULNERABLE: no cap, no cost, no reuse
+        board.listings.push(Listing {
+            owner: ctx.accounts.owner.key(),
+            price,
+        });
+        demo_log!(
+            Mode::Vulnerable,
+            "list",
+            actor = ctx.accounts.owner.key(),
+            price = price,
+            listings = board.listings.len()
+        );
+        Ok(())
+    }
+
+    pub fn delist_vulnerable(ctx: Context<DelistVulnerable>, index: u8) -> Result<()> {
+        let board = &mut ctx.accounts.board;
+        let listing = board
+            .listings
+            .get(index as usize)
+            .ok_or(ErrorCode::NoListing)?;
+        require_keys_eq!(
+            listing.owner,
+            ctx.accounts.owner.key(),
+            ErrorCode::NotListingOwner
+        );
+        board.listings.remove(index as usize);
+        demo_log!(
+            Mode::Vulnerable,
+            "delist",
+            actor = ctx.accounts.owner.key(),
+            index = index
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Fixed slots, a per-owner cap, and a deposit
+    // ============================================================================
+    // FIX: The board is MAX_LISTINGS fixed slots, and a delisted slot is
+    //      reused by the next listing, so the account never grows. Each
+    //      owner can hold at most MAX_PER_OWNER slots, and each slot holds
+    //      a LISTING_DEPOSIT until it is freed. Filling the board takes
+    //      many keys and locks up their deposits for as long as it lasts.
+    // ============================================================================
+    pub fn list_secure(ctx: Context<ListSecure>, price: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        // SECURE: a cap per owner
+        let seller = &mut ctx.accounts.seller;
+        require!(seller.listings < MAX_PER_OWNER, ErrorCode::TooManyListings);
+        // SECURE: the first free slot, reused
+        let index = ctx
+            .accounts
+            .slot_board
+            .slots
+            .iter()
+            .position(|slot| !slot.active)
+            .ok_or(ErrorCode::BoardFull)?;
+
+        // SECURE: each listing holds a deposit until it's delisted
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.slot_board.to_account_info(),
+                },
+            ),
+            LISTING_DEPOSIT,
+        )?;
+
+        ctx.accounts.slot_board.slots[index] = Slot {
+            active: true,
+            owner,
+            price,
+        };
+        let seller = &mut ctx.accounts.seller;
+        seller.listings += 1;
+        demo_log!(
+            Mode::Secure,
+            "list",
+            actor = owner,
+            price = price,
+            slot = index,
+            held = seller.listings
+        );
+        Ok(())
+    }
+
+    pub fn delist_secure(ctx: Context<DelistSecure>, index: u8) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let slot_board = &mut ctx.accounts.slot_board;
+        let slot = slot_board
+            .slots
+            .get_mut(index as usize)
+            .filter(|slot| slot.active)
+            .ok_or(ErrorCode::NoListing)?;
+        require_keys_eq!(slot.owner, owner, ErrorCode::NotListingOwner);
+        *slot = Slot::default();
+
+        slot_board.sub_lamports(LISTING_DEPOSIT)?;
+        ctx.accounts.owner.add_lamports(LISTING_DEPOSIT)?;
+        let seller = &mut ctx.accounts.seller;
+        seller.listings -= 1;
+        demo_log!(
+            Mode::Secure,
+            "delist",
+            actor = owner,
+            slot = index,
+            held = seller.listings
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Board::INIT_SPACE,
+        seeds = [b"board"],
+        bump
+    )]
+    pub board: Account<'info, Board>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + SlotBoard::INIT_SPACE,
+        seeds = [b"slot_board"],
+        bump
+    )]
+    pub slot_board: Account<'info, SlotBoard>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenSeller<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Seller::INIT_SPACE,
+        seeds = [b"seller", owner.key().as_ref()],
+        bump
+    )]
+    pub seller: Account<'info, Seller>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ListVulnerable<'info> {
+    #[account(mut, seeds = [b"board"], bump = board.bump)]
+    pub board: Account<'info, Board>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DelistVulnerable<'info> {
+    #[account(mut, seeds = [b"board"], bump = board.bump)]
+    pub board: Account<'info, Board>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ListSecure<'info> {
+    #[account(mut, seeds = [b"slot_board"], bump = slot_board.bump)]
+    pub slot_board: Account<'info, SlotBoard>,
+    #[account(
+        mut,
+        seeds = [b"seller", owner.key().as_ref()],
+        bump = seller.bump,
+        has_one = owner
+    )]
+    pub seller: Account<'info, Seller>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DelistSecure<'info> {
+    #[account(mut, seeds = [b"slot_board"], bump = slot_board.bump)]
+    pub slot_board: Account<'info, SlotBoard>,
+    #[account(
+        mut,
+        seeds = [b"seller", owner.key().as_ref()],
+        bump = seller.bump,
+        has_one = owner
+    )]
+    pub seller: Account<'info, Seller>,
+    /// Receives the listing's deposit back.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// The vulnerable board: listings in the order they were made. The
+/// account has room for MAX_LISTINGS of them.
+#[account]
+#[derive(InitSpace)]
+pub struct Board {
+    #[max_len(MAX_LISTINGS)]
+    pub listings: Vec<Listing>, // 4 + 40 * MAX_LISTINGS bytes
+    pub bump: u8, //  1 byte
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Listing {
+    pub owner: Pubkey, // 32 bytes
+    pub price: u64,    //  8 bytes
+}
+
+/// The secure board: MAX_LISTINGS slots, each free or holding a listing
+/// and its deposit.
+#[account]
+#[derive(InitSpace)]
+pub struct SlotBoard {
+    pub slots: [Slot; MAX_LISTINGS], // 41 * MAX_LISTINGS bytes
+    pub bump: u8,                    //  1 byte
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Slot {
+    pub active: bool,  //  1 byte
+    pub owner: Pubkey, // 32 bytes
+    pub price: u64,    //  8 bytes
+}
+
+/// How many slots an owner holds on the secure board.
+#[account]
+#[derive(InitSpace)]
+pub struct Seller {
+    pub owner: Pubkey, // 32 bytes
+    pub listings: u8,  //  1 byte
+    pub bump: u8,      //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Every slot on the board is taken")]
+    BoardFull,
+    #[msg("An owner can hold at most 2 listings")]
+    TooManyListings,
+    #[msg("No listing at that index")]
+    NoListing,
+    #[msg("Only the listing's owner can delist it")]
+    NotListingOwner,
+}
@@ -0,0 +1,405 @@
+// Mollusk tests for Pattern 47
+/// # Pattern 47: State Bloat — Mollusk Exploit Tests
+///
+/// Both boards have room for 16 listings and start empty unless a test
+/// says otherwise. The secure board holds a 0.1 SOL deposit per listing and
+/// lets an owner hold 2 at once. Alice and Mallory each start with 1 SOL.
+///
+/// - Test 1: EXPLOIT — Mallory lists 16 times on the vulnerable board, and
+///   Alice's listing fails.
+/// - Test 2: SECURE — Mallory's third listing on the secure board is
+///   refused, and Alice lists.
+/// - Test 3: SECURE — a full secure board refuses Alice until an owner
+///   delists; the deposit is refunded and Alice's listing reuses the slot.
+/// - Test 4: SANITY — Alice lists on both boards, and delists from the
+///   secure board with her deposit back.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("4XrXfb17yxjezQDUo6Zw5mRjsutgJ3qJL79PKzqr8mrf");
+
+const WALLET: u64 = 1_000_000_000;
+const MAX_LISTINGS: usize = 16;
+const LISTING_DEPOSIT: u64 = 100_000_000;
+const PRICE: u64 = 5_000;
+
+/// 8 disc + 4 + 16 * (32 owner + 8 price) listings + 1 bump.
+const BOARD_LEN: usize = 653;
+/// 8 disc + 16 * (1 active + 32 owner + 8 price) slots + 1 bump.
+const SLOT_BOARD_LEN: usize = 665;
+/// 8 disc + 32 owner + 1 listings + 1 bump.
+const SELLER_LEN: usize = 42;
+
+// Error codes
+const ACCOUNT_DID_NOT_SERIALIZE: u32 = 3004;
+const BOARD_FULL: u32 = 6000;
+const TOO_MANY_LISTINGS: u32 = 6001;
+const NOT_LISTING_OWNER: u32 = 6003;
+
+/// One slot of the secure board: free, or an owner and a price.
+type Slot = Option<(Pubkey, u64)>;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Board account, zero-padded to BOARD_LEN:
+///   [8 disc][4 len][len * (32 owner, 8 price)][1 bump]
+fn serialize_board(listings: &[(Pubkey, u64)], bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(BOARD_LEN);
+    data.extend_from_slice(&account_discriminator("Board"));
+    data.extend_from_slice(&(listings.len() as u32).to_le_bytes());
+    for (owner, price) in listings {
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&price.to_le_bytes());
+    }
+    data.push(bump);
+    data.resize(BOARD_LEN, 0);
+    data
+}
+
+/// Serialize a SlotBoard account:
+///   [8 disc][16 * (1 active, 32 owner, 8 price)][1 bump]
+fn serialize_slot_board(slots: &[Slot; MAX_LISTINGS], bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(SLOT_BOARD_LEN);
+    data.extend_from_slice(&account_discriminator("SlotBoard"));
+    for slot in slots {
+        let (active, owner, price) = match slot {
+            Some((owner, price)) => (1, *owner, *price),
+            None => (0, Pubkey::default(), 0),
+        };
+        data.push(active);
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&price.to_le_bytes());
+    }
+    data.push(bump);
+    data
+}
+
+/// Serialize a Seller account:
+///   [8 disc][32 owner][1 listings][1 bump]
+fn serialize_seller(owner: &Pubkey, listings: u8, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(SELLER_LEN);
+    data.extend_from_slice(&account_discriminator("Seller"));
+    data.extend_from_slice(owner.as_ref());
+    data.push(listings);
+    data.push(bump);
+    data
+}
+
+fn program_account(len: usize, data: &[u8], extra: u64) -> AccountSharedData {
+    let lamports = Rent::default().minimum_balance(len) + extra;
+    let mut account = AccountSharedData::new(lamports, len, &PROGRAM_ID);
+    account.set_data_from_slice(data);
+    account
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    board: Pubkey,
+    board_bump: u8,
+    slot_board: Pubkey,
+    slot_board_bump: u8,
+    alice: Pubkey,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// Both boards empty, and a Seller account with no listings for Alice
+    /// and for Mallory.
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "state_bloat");
+        let alice = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let (board, board_bump) = Pubkey::find_program_address(&[b"board"], &PROGRAM_ID);
+        let (slot_board, slot_board_bump) =
+            Pubkey::find_program_address(&[b"slot_board"], &PROGRAM_ID);
+
+        let ledger = vec![
+            (
+                board,
+                program_account(BOARD_LEN, &serialize_board(&[], board_bump), 0),
+            ),
+            (slot_board, AccountSharedData::default()),
+            (Self::seller(&alice).0, AccountSharedData::default()),
+            (Self::seller(&mallory).0, AccountSharedData::default()),
+            (alice, system_account()),
+            (mallory, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+        let mut s = Self {
+            mollusk,
+            board,
+            board_bump,
+            slot_board,
+            slot_board_bump,
+            alice,
+            mallory,
+            ledger: Ledger::new(ledger),
+        };
+        s.put_slot_board(&[None; MAX_LISTINGS]);
+        s.put_seller(&alice, 0);
+        s.put_seller(&mallory, 0);
+        s
+    }
+
+    fn seller(owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"seller", owner.as_ref()], &PROGRAM_ID)
+    }
+
+    /// Replace the secure board with `slots`, holding a deposit for each
+    /// taken slot.
+    fn put_slot_board(&mut self, slots: &[Slot; MAX_LISTINGS]) {
+        let account = program_account(
+            SLOT_BOARD_LEN,
+            &serialize_slot_board(slots, self.slot_board_bump),
+            Self::deposits(slots),
+        );
+        let key = self.slot_board;
+        self.ledger.set(key, account);
+    }
+
+    /// Replace `owner`'s Seller account with one holding `listings`.
+    fn put_seller(&mut self, owner: &Pubkey, listings: u8) {
+        let (seller, bump) = Self::seller(owner);
+        let account = program_account(SELLER_LEN, &serialize_seller(owner, listings, bump), 0);
+        self.ledger.set(seller, account);
+    }
+
+    fn deposits(slots: &[Slot; MAX_LISTINGS]) -> u64 {
+        slots.iter().filter(|slot| slot.is_some()).count() as u64 * LISTING_DEPOSIT
+    }
+
+    /// list_vulnerable by `owner` at `price`.
+    fn list_vulnerable(&self, owner: &Pubkey, price: u64) -> Instruction {
+        let mut data = ix_discriminator("list_vulnerable").to_vec();
+        data.extend_from_slice(&price.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.board, false),
+                AccountMeta::new_readonly(*owner, true),
+            ],
+        )
+    }
+
+    /// list_secure by `owner` at `price`.
+    fn list_secure(&self, owner: &Pubkey, price: u64) -> Instruction {
+        let mut data = ix_discriminator("list_secure").to_vec();
+        data.extend_from_slice(&price.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.slot_board, false),
+                AccountMeta::new(Self::seller(owner).0, false),
+                AccountMeta::new(*owner, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// delist_secure by `owner` of slot `index`.
+    fn delist_secure(&self, owner: &Pubkey, index: u8) -> Instruction {
+        let mut data = ix_discriminator("delist_secure").to_vec();
+        data.push(index);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.slot_board, false),
+                AccountMeta::new(Self::seller(owner).0, false),
+                AccountMeta::new(*owner, true),
+            ],
+        )
+    }
+
+    fn assert_board(&self, listings: &[(Pubkey, u64)]) {
+        assert_eq!(
+            self.ledger.data(&self.board),
+            serialize_board(listings, self.board_bump)
+        );
+    }
+
+    /// Assert the secure board holds `slots` and a deposit for each.
+    fn assert_slot_board(&self, slots: &[Slot; MAX_LISTINGS]) {
+        let account = self.ledger.account(&self.slot_board);
+        assert_eq!(
+            account.data(),
+            serialize_slot_board(slots, self.slot_board_bump)
+        );
+        assert_eq!(
+            account.lamports(),
+            Rent::default().minimum_balance(SLOT_BOARD_LEN) + Self::deposits(slots)
+        );
+    }
+
+    /// Assert `owner` holds `listings` on the secure board and has
+    /// `lamports` in its wallet.
+    fn assert_seller(&self, owner: &Pubkey, listings: u8, lamports: u64) {
+        let (seller, bump) = Self::seller(owner);
+        assert_eq!(
+            self.ledger.data(&seller),
+            serialize_seller(owner, listings, bump)
+        );
+        assert_eq!(self.ledger.lamports(owner), lamports);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_board_filled_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: The vulnerable board is empty
+    //   1. Mallory: list_vulnerable × 16, at a price of 1
+    //   2. Alice:   list_vulnerable
+    //
+    // Expected: Mallory's 16 SUCCEED, paying nothing but fees. Alice's
+    //           FAILS with AccountDidNotSerialize (3004): a 17th listing
+    //           doesn't fit the account, and never will while Mallory's
+    //           stay.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    let ix = s.list_vulnerable(&mallory, 1);
+    for _ in 0..MAX_LISTINGS {
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    }
+    let spam = [(mallory, 1); MAX_LISTINGS];
+    s.assert_board(&spam);
+    assert_eq!(s.ledger.lamports(&mallory), WALLET);
+
+    let ix = s.list_vulnerable(&alice, PRICE);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(ACCOUNT_DID_NOT_SERIALIZE))],
+    );
+    s.assert_board(&spam);
+}
+
+#[test]
+fn secure_per_owner_cap() {
+    // -----------------------------------------------------------------------
+    // SECURE: The secure board is empty
+    //   1. Mallory: list_secure × 3
+    //   2. Alice:   list_secure
+    //
+    // Expected: Mallory's first two SUCCEED, with a deposit each, and the
+    //           third FAILS with TooManyListings (6001). Alice's SUCCEEDS
+    //           in the next free slot.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    let ix = s.list_secure(&mallory, 1);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(TOO_MANY_LISTINGS))]);
+    s.assert_seller(&mallory, 2, WALLET - 2 * LISTING_DEPOSIT);
+
+    let ix = s.list_secure(&alice, PRICE);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_seller(&alice, 1, WALLET - LISTING_DEPOSIT);
+
+    let mut slots = [None; MAX_LISTINGS];
+    slots[0] = Some((mallory, 1));
+    slots[1] = Some((mallory, 1));
+    slots[2] = Some((alice, PRICE));
+    s.assert_slot_board(&slots);
+}
+
+#[test]
+fn secure_full_board_frees_and_reuses_slot() {
+    // -----------------------------------------------------------------------
+    // SECURE: Eight owners hold two slots each, and Mallory holds slots 0
+    //         and 1: the secure board is full
+    //   1. Alice:   list_secure
+    //   2. Alice:   delist_secure(0)
+    //   3. Mallory: delist_secure(1)
+    //   4. Alice:   list_secure
+    //
+    // Expected: 1 FAILS with BoardFull (6000), and 2 with NotListingOwner
+    //           (6003). 3 SUCCEEDS and refunds Mallory's deposit. 4
+    //           SUCCEEDS in slot 1, and the board is full again at the
+    //           same size.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (alice, mallory) = (s.alice, s.mallory);
+
+    let owners: Vec<Pubkey> = std::iter::once(mallory)
+        .chain((1..8).map(|_| Pubkey::new_unique()))
+        .collect();
+    let mut slots: [Slot; MAX_LISTINGS] = std::array::from_fn(|i| Some((owners[i / 2], PRICE)));
+    s.put_slot_board(&slots);
+    s.put_seller(&mallory, 2);
+
+    let ix = s.list_secure(&alice, PRICE);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(BOARD_FULL))]);
+    let ix = s.delist_secure(&alice, 0);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(NOT_LISTING_OWNER))]);
+    s.assert_slot_board(&slots);
+
+    let ix = s.delist_secure(&mallory, 1);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    slots[1] = None;
+    s.assert_slot_board(&slots);
+    s.assert_seller(&mallory, 1, WALLET + LISTING_DEPOSIT);
+
+    let ix = s.list_secure(&alice, PRICE);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    slots[1] = Some((alice, PRICE));
+    s.assert_slot_board(&slots);
+    s.assert_seller(&alice, 1, WALLET - LISTING_DEPOSIT);
+    assert_eq!(s.ledger.data(&s.slot_board).len(), SLOT_BOARD_LEN);
+}
+
+#[test]
+fn sanity_list_and_delist() {
+    // -----------------------------------------------------------------------
+    // SANITY: Both boards are empty
+    //   Alice: list_vulnerable, list_secure, then delist_secure(0)
+    //
+    // Expected: all SUCCEED. Alice's listing is on both boards, and
+    //           delisting from the secure board frees the slot and
+    //           refunds her deposit.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let alice = s.alice;
+
+    let ix = s.list_vulnerable(&alice, PRICE);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_board(&[(alice, PRICE)]);
+
+    let ix = s.list_secure(&alice, PRICE);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    let mut slots = [None; MAX_LISTINGS];
+    slots[0] = Some((alice, PRICE));
+    s.assert_slot_board(&slots);
+    s.assert_seller(&alice, 1, WALLET - LISTING_DEPOSIT);
+
+    let ix = s.delist_secure(&alice, 0);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_slot_board(&[None; MAX_LISTINGS]);
+    s.assert_seller(&alice, 0, WALLET);
+}
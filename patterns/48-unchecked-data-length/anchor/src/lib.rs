@@ -0,0 +1,302 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+use std::ops::Range;
+
+declare_id!("3d8zitbJRbS4cR84kXx1achZ8mvizMXCqoCAwuMY19t6");
+
+/// A Member account: the discriminator, authority, credits, and bump.
+pub const MEMBER_LEN: usize = 8 + 32 + 8 + 1;
+/// Longest memo a note can hold.
+pub const MAX_MEMO_LEN: usize = 32;
+
+const AUTHORITY: Range<usize> = 8..40;
+const CREDITS: Range<usize> = 40..48;
+
+/// # Unchecked Data Length
+///
+/// This program demonstrates a rewards treasury. The admin grants members
+/// credits, and members redeem them for lamports. Anyone can also post a
+/// note, an account sized to fit its memo. `redeem` reads the two fields
+/// it needs straight from the member account's bytes instead of
+/// deserializing all of it.
+///
+/// ## The Vulnerability
+/// The vulnerable redeem checks that this program owns the account, then
+/// slices `data[8..40]` for the authority and `data[40..48]` for the
+/// credits. It never checks that the account is that long, or that it is
+/// a Member at all. A note with an empty memo is 45 bytes, and the slice
+/// panics. A note with a 4-byte memo is 49 bytes, the length of a Member,
+/// and the "credits" are its memo's length prefix and its memo.
+///
+/// ## Real-World Impact
+/// Every account a program owns passes its owner check, including the
+/// ones users create and fill. Manual parsing that trusts the length
+/// aborts with a panic instead of an error on short input, and reads
+/// whatever another type keeps at that offset on long input. Here that is
+/// a memo Mallory chose, read as 18 billion SOL of credits.
+#[program]
+pub mod unchecked_data_length {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.admin = ctx.accounts.admin.key();
+        treasury.bump = ctx.bumps.treasury;
+        demo_log!(Mode::Setup, "initialize", actor = treasury.admin);
+        Ok(())
+    }
+
+    pub fn open_member(ctx: Context<OpenMember>) -> Result<()> {
+        let member = &mut ctx.accounts.member;
+        member.authority = ctx.accounts.authority.key();
+        member.credits = 0;
+        member.bump = ctx.bumps.member;
+        demo_log!(Mode::Setup, "open_member", actor = member.authority);
+        Ok(())
+    }
+
+    /// Grant a member `amount` credits, redeemable one lamport each.
+    pub fn grant(ctx: Context<Grant>, amount: u64) -> Result<()> {
+        let member = &mut ctx.accounts.member;
+        member.credits = member
+            .credits
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        demo_log!(
+            Mode::Setup,
+            "grant",
+            actor = ctx.accounts.admin.key(),
+            member = member.authority,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// Post a note, in an account sized to fit `memo`.
+    pub fn post_note(ctx: Context<PostNote>, memo: String) -> Result<()> {
+        require!(memo.len() <= MAX_MEMO_LEN, ErrorCode::MemoTooLong);
+        let note = &mut ctx.accounts.note;
+        note.author = ctx.accounts.author.key();
+        note.memo = memo;
+        note.bump = ctx.bumps.note;
+        demo_log!(Mode::Setup, "post_note", actor = note.author);
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Slices account data without checking its length or type
+    // ============================================================================
+    // ISSUE: The owner check on `member` passes for any account this
+    //        program owns, notes included. The slices assume the Member
+    //        layout: a shorter account panics, and another type of the same
+    //        length is read as a Member.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory posts a note with a 4-byte memo of 0xff bytes: 49 bytes,
+    //      the length of a Member, with Mallory as its author
+    //   2. Mallory calls redeem_vulnerable with the note as `member`
+    //   3. The authority is Mallory, and the credits are the memo's length
+    //      prefix and bytes: about 1.8e19. The treasury pays 1 SOL
+    // ============================================================================
+    pub fn redeem_vulnerable(ctx: Context<Redeem>, amount: u64) -> Result<()> {
+        let mut data = ctx.accounts.member.try_borrow_mut_data()?;
+        // VULNERABLE: no length or discriminator check before slicing
+        let authority = Pubkey::try_from(&data[AUTHORITY]).map_err(|_| ErrorCode::InvalidData)?;
+        let credits = u64::from_le_bytes(
+            data[CREDITS]
+                .try_into()
+                .map_err(|_| ErrorCode::InvalidData)?,
+        );
+
+        let left = spend(&ctx.accounts.authority, authority, credits, amount)?;
+        data[CREDITS].copy_from_slice(&left.to_le_bytes());
+        drop(data);
+
+        pay(&ctx.accounts.treasury, &ctx.accounts.authority, amount)?;
+        demo_log!(
+            Mode::Vulnerable,
+            "redeem",
+            actor = authority,
+            amount = amount,
+            credits = left
+        );
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: Length and discriminator checked before any slice
+    // ============================================================================
+    // FIX: `read_member` refuses data shorter than MEMBER_LEN with
+    //      AccountTooShort, and data that doesn't start with the Member
+    //      discriminator with WrongAccountType. Only then does it slice,
+    //      and every offset it reads is inside a Member.
+    // ============================================================================
+    pub fn redeem_secure(ctx: Context<Redeem>, amount: u64) -> Result<()> {
+        let mut data = ctx.accounts.member.try_borrow_mut_data()?;
+        // SECURE: a Member's length and discriminator, or an error
+        let (authority, credits) = read_member(&data)?;
+
+        let left = spend(&ctx.accounts.authority, authority, credits, amount)?;
+        data[CREDITS].copy_from_slice(&left.to_le_bytes());
+        drop(data);
+
+        pay(&ctx.accounts.treasury, &ctx.accounts.authority, amount)?;
+        demo_log!(
+            Mode::Secure,
+            "redeem",
+            actor = authority,
+            amount = amount,
+            credits = left
+        );
+        Ok(())
+    }
+}
+
+/// The authority and credits of a Member account's data.
+fn read_member(data: &[u8]) -> Result<(Pubkey, u64)> {
+    require!(data.len() >= MEMBER_LEN, ErrorCode::AccountTooShort);
+    require!(
+        data[..8] == *Member::DISCRIMINATOR,
+        ErrorCode::WrongAccountType
+    );
+    let authority = Pubkey::try_from(&data[AUTHORITY]).map_err(|_| ErrorCode::InvalidData)?;
+    let credits = u64::from_le_bytes(
+        data[CREDITS]
+            .try_into()
+            .map_err(|_| ErrorCode::InvalidData)?,
+    );
+    Ok((authority, credits))
+}
+
+/// Check the signer is the member's `authority`, and return its credits
+/// after spending `amount`.
+fn spend(signer: &Signer, authority: Pubkey, credits: u64, amount: u64) -> Result<u64> {
+    require_keys_eq!(signer.key(), authority, ErrorCode::NotMemberAuthority);
+    credits
+        .checked_sub(amount)
+        .ok_or(error!(ErrorCode::InsufficientCredits))
+}
+
+/// Pay `amount` lamports from the treasury to `recipient`.
+fn pay<'info>(
+    treasury: &Account<'info, Treasury>,
+    recipient: &Signer<'info>,
+    amount: u64,
+) -> Result<()> {
+    treasury.sub_lamports(amount)?;
+    recipient.add_lamports(amount)?;
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenMember<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Member::INIT_SPACE,
+        seeds = [b"member", authority.key().as_ref()],
+        bump
+    )]
+    pub member: Account<'info, Member>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Grant<'info> {
+    #[account(seeds = [b"treasury"], bump = treasury.bump, has_one = admin)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub member: Account<'info, Member>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(memo: String)]
+pub struct PostNote<'info> {
+    #[account(
+        init,
+        payer = author,
+        space = 8 + 32 + 4 + memo.len() + 1,
+        seeds = [b"note", author.key().as_ref()],
+        bump
+    )]
+    pub note: Account<'info, Note>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    /// CHECK: Any account this program owns; the handler reads its bytes.
+    #[account(mut, owner = crate::ID)]
+    pub member: UncheckedAccount<'info>,
+    /// Must be the member's authority; receives the lamports.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// The treasury PDA holds the lamports members redeem.
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub admin: Pubkey, // 32 bytes
+    pub bump: u8,      //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Member {
+    pub authority: Pubkey, // 32 bytes
+    pub credits: u64,      //  8 bytes: lamports it can redeem
+    pub bump: u8,          //  1 byte
+}
+
+/// A note, allocated to fit its memo: 45 bytes with an empty memo.
+#[account]
+pub struct Note {
+    pub author: Pubkey, // 32 bytes
+    pub memo: String,   //  4 + memo.len() bytes
+    pub bump: u8,       //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Account data is shorter than a Member")]
+    AccountTooShort,
+    #[msg("Account is not a Member")]
+    WrongAccountType,
+    #[msg("Invalid account data")]
+    InvalidData,
+    #[msg("Only the member's authority can redeem its credits")]
+    NotMemberAuthority,
+    #[msg("Not enough credits")]
+    InsufficientCredits,
+    #[msg("Memo is longer than 32 bytes")]
+    MemoTooLong,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
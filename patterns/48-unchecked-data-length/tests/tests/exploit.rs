@@ -0,0 +1,269 @@
+// Mollusk tests for Pattern 48
+/// # Pattern 48: Unchecked Data Length — Mollusk Exploit Tests
+///
+/// The treasury holds 5 SOL above its rent-exempt minimum. Alice is a
+/// member with 0.5 SOL of credits. Mallory isn't a member, but has posted a
+/// note: with an empty memo, 45 bytes, or with a memo of four 0xff bytes,
+/// 49 bytes, the length of a Member.
+///
+/// - Test 1: EXPLOIT — redeem_vulnerable with the 45-byte note panics.
+/// - Test 2: EXPLOIT — redeem_vulnerable reads the 49-byte note as a Member
+///   with Mallory's authority and pays Mallory 1 SOL.
+/// - Test 3: SECURE — redeem_secure rejects the 45-byte note as too short
+///   and the 49-byte note as the wrong type.
+/// - Test 4: SANITY — Alice redeems 0.2 SOL with each variant.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, ix_discriminator, owned_account, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("3d8zitbJRbS4cR84kXx1achZ8mvizMXCqoCAwuMY19t6");
+
+const WALLET: u64 = 1_000_000_000;
+const FUNDS: u64 = 5_000_000_000;
+const ALICE_CREDITS: u64 = 500_000_000;
+const SOL: u64 = 1_000_000_000;
+
+/// 8 disc + 32 admin + 1 bump.
+const TREASURY_LEN: usize = 41;
+/// 8 disc + 32 authority + 8 credits + 1 bump.
+const MEMBER_LEN: usize = 49;
+
+// Error codes
+const ACCOUNT_TOO_SHORT: u32 = 6000;
+const WRONG_ACCOUNT_TYPE: u32 = 6001;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Treasury account:
+///   [8 disc][32 admin][1 bump]
+fn serialize_treasury(admin: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(TREASURY_LEN);
+    data.extend_from_slice(&account_discriminator("Treasury"));
+    data.extend_from_slice(admin.as_ref());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Member account:
+///   [8 disc][32 authority][8 credits][1 bump]
+fn serialize_member(authority: &Pubkey, credits: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(MEMBER_LEN);
+    data.extend_from_slice(&account_discriminator("Member"));
+    data.extend_from_slice(authority.as_ref());
+    data.extend_from_slice(&credits.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Note account, sized to its memo:
+///   [8 disc][32 author][4 len][len memo][1 bump]
+fn serialize_note(author: &Pubkey, memo: &[u8], bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(45 + memo.len());
+    data.extend_from_slice(&account_discriminator("Note"));
+    data.extend_from_slice(author.as_ref());
+    data.extend_from_slice(&(memo.len() as u32).to_le_bytes());
+    data.extend_from_slice(memo);
+    data.push(bump);
+    data
+}
+
+fn program_account(data: &[u8], extra: u64) -> AccountSharedData {
+    let lamports = Rent::default().minimum_balance(data.len()) + extra;
+    owned_account(data, lamports, &PROGRAM_ID)
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    treasury: Pubkey,
+    alice: Pubkey,
+    alice_member: Pubkey,
+    alice_bump: u8,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A funded treasury, and Alice's Member account with ALICE_CREDITS.
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "unchecked_data_length");
+        let admin = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let (treasury, treasury_bump) = Pubkey::find_program_address(&[b"treasury"], &PROGRAM_ID);
+        let (alice_member, alice_bump) =
+            Pubkey::find_program_address(&[b"member", alice.as_ref()], &PROGRAM_ID);
+
+        let ledger = vec![
+            (
+                treasury,
+                program_account(&serialize_treasury(&admin, treasury_bump), FUNDS),
+            ),
+            (
+                alice_member,
+                program_account(&serialize_member(&alice, ALICE_CREDITS, alice_bump), 0),
+            ),
+            (alice, system_account()),
+            (mallory, system_account()),
+        ];
+
+        Self {
+            mollusk,
+            treasury,
+            alice,
+            alice_member,
+            alice_bump,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// Add Mallory's note with `memo` to the ledger, and return its key.
+    fn post_note(&mut self, memo: &[u8]) -> Pubkey {
+        let (note, bump) =
+            Pubkey::find_program_address(&[b"note", self.mallory.as_ref()], &PROGRAM_ID);
+        let account = program_account(&serialize_note(&self.mallory, memo, bump), 0);
+        self.ledger.set(note, account);
+        note
+    }
+
+    fn treasury_rent() -> u64 {
+        Rent::default().minimum_balance(TREASURY_LEN)
+    }
+
+    /// redeem_vulnerable or redeem_secure of `amount`, by `authority`, from
+    /// the account at `member`.
+    fn redeem(&self, name: &str, member: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(*member, false),
+                AccountMeta::new(*authority, true),
+            ],
+        )
+    }
+
+    /// Assert the treasury has paid out `paid` lamports, all to `recipient`.
+    fn assert_paid(&self, recipient: &Pubkey, paid: u64) {
+        assert_eq!(
+            self.ledger.lamports(&self.treasury),
+            Self::treasury_rent() + FUNDS - paid
+        );
+        assert_eq!(self.ledger.lamports(recipient), WALLET + paid);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_short_account_panics_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory's note has an empty memo: 45 bytes
+    //   Mallory: redeem_vulnerable(1 lamport), with the note as `member`
+    //
+    // Expected: FAILS with ProgramFailedToComplete. The owner check
+    //           passes, and slicing bytes 40..48 of a 45-byte account
+    //           panics before any error the program defines.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let note = s.post_note(b"");
+    assert_eq!(s.ledger.data(&note).len(), 45);
+
+    let ix = s.redeem("redeem_vulnerable", &note, &mallory, 1);
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::instruction_err(
+            InstructionError::ProgramFailedToComplete,
+        )],
+    );
+    s.assert_paid(&mallory, 0);
+}
+
+#[test]
+fn exploit_note_read_as_member_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory's note has a memo of four 0xff bytes: 49 bytes
+    //   Mallory: redeem_vulnerable(1 SOL), with the note as `member`
+    //
+    // Expected: SUCCEEDS. Bytes 8..40 are Mallory's key, and bytes 40..48
+    //           are the memo's length prefix and memo: 0xffffffff_00000004
+    //           credits. The treasury pays Mallory 1 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let note = s.post_note(&[0xff; 4]);
+    assert_eq!(s.ledger.data(&note).len(), MEMBER_LEN);
+
+    let ix = s.redeem("redeem_vulnerable", &note, &mallory, SOL);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_paid(&mallory, SOL);
+}
+
+#[test]
+fn secure_checks_length_and_type() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory's two notes, of 45 and of 49 bytes
+    //   Mallory: redeem_secure(1 SOL), with each note as `member`
+    //
+    // Expected: the 45-byte note FAILS with AccountTooShort (6000), and
+    //           the 49-byte note with WrongAccountType (6001). Nothing is
+    //           paid.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let note = s.post_note(b"");
+    let ix = s.redeem("redeem_secure", &note, &mallory, SOL);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(ACCOUNT_TOO_SHORT))]);
+    s.assert_paid(&mallory, 0);
+
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let note = s.post_note(&[0xff; 4]);
+    let ix = s.redeem("redeem_secure", &note, &mallory, SOL);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(WRONG_ACCOUNT_TYPE))]);
+    s.assert_paid(&mallory, 0);
+}
+
+#[test]
+fn sanity_member_redeems() {
+    // -----------------------------------------------------------------------
+    // SANITY: Alice is a member with 0.5 SOL of credits
+    //   Alice: redeem_vulnerable(0.2 SOL), then redeem_secure(0.2 SOL)
+    //
+    // Expected: both SUCCEED. Alice is paid 0.4 SOL and has 0.1 SOL of
+    //           credits left.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let (alice, member) = (s.alice, s.alice_member);
+    let amount = 200_000_000;
+
+    let ix = s.redeem("redeem_vulnerable", &member, &alice, amount);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    let ix = s.redeem("redeem_secure", &member, &alice, amount);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    s.assert_paid(&alice, 2 * amount);
+    assert_eq!(
+        s.ledger.data(&member),
+        serialize_member(&alice, ALICE_CREDITS - 2 * amount, s.alice_bump)
+    );
+}
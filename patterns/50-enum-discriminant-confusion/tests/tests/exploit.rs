@@ -0,0 +1,319 @@
+// Mollusk tests for Pattern 50
+/// # Pattern 50: Enum Discriminant Confusion — Mollusk Exploit Tests
+///
+/// Alice buys from Mallory for 1 SOL. The vault holds 5 SOL of deposits,
+/// Alice's among them. Each test starts from Alice's trade as a given
+/// version wrote it: version 2 numbers the statuses Open 0, Cancelled 1,
+/// Settled 2, Disputed 3; version 1 numbered them Open 0, Settled 1,
+/// Cancelled 2.
+///
+/// - Test 1: EXPLOIT — Alice disputes, and withdraw_vulnerable reads the
+///   dispute as Settled and pays Mallory.
+/// - Test 2: EXPLOIT — a trade Mallory cancelled under version 1 reads as
+///   Settled, and withdraw_vulnerable pays Mallory Alice's refund.
+/// - Test 3: SECURE — withdraw_secure refuses the dispute, refunds Alice
+///   for the version 1 cancellation, and rejects unknown bytes and
+///   versions.
+/// - Test 4: SANITY — both variants pay Mallory for a settled trade and
+///   refund Alice for a cancelled one.
+use mollusk_svm::{result::Check, Mollusk};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use test_harness::{account_discriminator, ix_discriminator, Ledger};
+
+const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("67ZZBKnoTQQmGmuPot7xkY3jfWBnsxdFc4MzKzCB47mN");
+
+const WALLET: u64 = 1_000_000_000;
+const FUNDS: u64 = 5_000_000_000;
+const PRICE: u64 = 1_000_000_000;
+
+/// 8 disc + 1 bump.
+const VAULT_LEN: usize = 9;
+/// 8 disc + 1 version + 32 buyer + 32 seller + 8 amount + 1 status + 1 bump.
+const TRADE_LEN: usize = 83;
+
+// Status bytes
+const OPEN: u8 = 0;
+const V2_CANCELLED: u8 = 1;
+const V2_SETTLED: u8 = 2;
+const V2_DISPUTED: u8 = 3;
+const V1_CANCELLED: u8 = 2;
+
+// Error codes
+const INVALID_STATUS: u32 = 6000;
+const UNSUPPORTED_VERSION: u32 = 6001;
+const NOT_FINAL: u32 = 6003;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Serialize a Vault account:
+///   [8 disc][1 bump]
+fn serialize_vault(bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(VAULT_LEN);
+    data.extend_from_slice(&account_discriminator("Vault"));
+    data.push(bump);
+    data
+}
+
+/// Serialize a Trade account:
+///   [8 disc][1 version][32 buyer][32 seller][8 amount][1 status][1 bump]
+fn serialize_trade(version: u8, buyer: &Pubkey, seller: &Pubkey, status: u8, bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(TRADE_LEN);
+    data.extend_from_slice(&account_discriminator("Trade"));
+    data.push(version);
+    data.extend_from_slice(buyer.as_ref());
+    data.extend_from_slice(seller.as_ref());
+    data.extend_from_slice(&PRICE.to_le_bytes());
+    data.push(status);
+    data.push(bump);
+    data
+}
+
+fn program_account(len: usize, data: &[u8], extra: u64) -> AccountSharedData {
+    let lamports = Rent::default().minimum_balance(len) + extra;
+    let mut account = AccountSharedData::new(lamports, len, &PROGRAM_ID);
+    account.set_data_from_slice(data);
+    account
+}
+
+fn system_account() -> AccountSharedData {
+    AccountSharedData::new(WALLET, 0, &solana_sdk::system_program::ID)
+}
+
+struct Setup {
+    mollusk: Mollusk,
+    vault: Pubkey,
+    trade: Pubkey,
+    alice: Pubkey,
+    mallory: Pubkey,
+    ledger: Ledger,
+}
+
+impl Setup {
+    /// A funded vault, and Alice's trade with Mallory as `version` wrote
+    /// it, with `status`.
+    fn new(version: u8, status: u8) -> Self {
+        let mollusk = Mollusk::new(&PROGRAM_ID, "enum_discriminant_confusion");
+        let alice = Pubkey::new_unique();
+        let mallory = Pubkey::new_unique();
+        let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM_ID);
+        let (trade, trade_bump) = Pubkey::find_program_address(
+            &[b"trade", alice.as_ref(), mallory.as_ref()],
+            &PROGRAM_ID,
+        );
+
+        let ledger = vec![
+            (
+                vault,
+                program_account(VAULT_LEN, &serialize_vault(vault_bump), FUNDS),
+            ),
+            (
+                trade,
+                program_account(
+                    TRADE_LEN,
+                    &serialize_trade(version, &alice, &mallory, status, trade_bump),
+                    0,
+                ),
+            ),
+            (alice, system_account()),
+            (mallory, system_account()),
+        ];
+
+        Self {
+            mollusk,
+            vault,
+            trade,
+            alice,
+            mallory,
+            ledger: Ledger::new(ledger),
+        }
+    }
+
+    /// settle or dispute by Alice, or cancel by Mallory.
+    fn set_status(&self, name: &str) -> Instruction {
+        let signer = if name == "cancel" {
+            self.mallory
+        } else {
+            self.alice
+        };
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.trade, false),
+                AccountMeta::new_readonly(signer, true),
+            ],
+        )
+    }
+
+    /// withdraw_vulnerable or withdraw_secure of Alice's trade.
+    fn withdraw(&self, name: &str) -> Instruction {
+        Instruction::new_with_bytes(
+            PROGRAM_ID,
+            &ix_discriminator(name),
+            vec![
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.trade, false),
+                AccountMeta::new(self.alice, false),
+                AccountMeta::new(self.mallory, false),
+            ],
+        )
+    }
+
+    /// Assert Alice's trade is closed to Alice, and the vault has paid
+    /// PRICE to `payee`.
+    fn assert_withdrawn(&self, payee: &Pubkey) {
+        let vault_rent = Rent::default().minimum_balance(VAULT_LEN);
+        let trade_rent = Rent::default().minimum_balance(TRADE_LEN);
+        let paid = |key: &Pubkey| if key == payee { PRICE } else { 0 };
+        assert_eq!(self.ledger.lamports(&self.trade), 0);
+        assert_eq!(
+            self.ledger.lamports(&self.vault),
+            vault_rent + FUNDS - PRICE
+        );
+        assert_eq!(
+            self.ledger.lamports(&self.alice),
+            WALLET + trade_rent + paid(&self.alice)
+        );
+        assert_eq!(
+            self.ledger.lamports(&self.mallory),
+            WALLET + paid(&self.mallory)
+        );
+    }
+
+    /// Assert Alice's trade is unchanged apart from `status`, and nothing
+    /// has been paid.
+    fn assert_unpaid(&self, version: u8, status: u8) {
+        let (_, bump) = Pubkey::find_program_address(
+            &[b"trade", self.alice.as_ref(), self.mallory.as_ref()],
+            &PROGRAM_ID,
+        );
+        assert_eq!(
+            self.ledger.data(&self.trade),
+            serialize_trade(version, &self.alice, &self.mallory, status, bump)
+        );
+        assert_eq!(self.ledger.lamports(&self.alice), WALLET);
+        assert_eq!(self.ledger.lamports(&self.mallory), WALLET);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn exploit_disputed_read_as_settled_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Alice's trade is open, under version 2
+    //   Alice:   dispute
+    //   Mallory: withdraw_vulnerable
+    //
+    // Expected: both SUCCEED. The trade stores Disputed (3); the decoder's
+    //           catch-all reads it as Settled, and Mallory is paid 1 SOL.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(2, OPEN);
+    let mallory = s.mallory;
+
+    let ix = s.set_status("dispute");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_unpaid(2, V2_DISPUTED);
+
+    let ix = s.withdraw("withdraw_vulnerable");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_withdrawn(&mallory);
+}
+
+#[test]
+fn exploit_legacy_cancelled_read_as_settled_vulnerable() {
+    // -----------------------------------------------------------------------
+    // EXPLOIT: Mallory cancelled Alice's trade under version 1, which
+    //          stored Cancelled as 2; Alice hasn't withdrawn her refund
+    //   Mallory: withdraw_vulnerable
+    //
+    // Expected: SUCCEEDS. The version is ignored, 2 is version 2's
+    //           Settled, and Mallory is paid Alice's refund.
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(1, V1_CANCELLED);
+    let mallory = s.mallory;
+
+    let ix = s.withdraw("withdraw_vulnerable");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_withdrawn(&mallory);
+}
+
+#[test]
+fn secure_decodes_by_version() {
+    // -----------------------------------------------------------------------
+    // SECURE: Alice's trade, disputed under version 2; cancelled under
+    //         version 1; with status 7 under version 2; and with status 0
+    //         under version 9
+    //   Anyone: withdraw_secure, for each
+    //
+    // Expected: the dispute FAILS with NotFinal (6003). The version 1
+    //           cancellation SUCCEEDS and refunds Alice. Status 7 FAILS
+    //           with InvalidStatus (6000), and version 9 with
+    //           UnsupportedVersion (6001).
+    // -----------------------------------------------------------------------
+    let mut s = Setup::new(2, V2_DISPUTED);
+    let ix = s.withdraw("withdraw_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(NOT_FINAL))]);
+    s.assert_unpaid(2, V2_DISPUTED);
+
+    let mut s = Setup::new(1, V1_CANCELLED);
+    let alice = s.alice;
+    let ix = s.withdraw("withdraw_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+    s.assert_withdrawn(&alice);
+
+    let mut s = Setup::new(2, 7);
+    let ix = s.withdraw("withdraw_secure");
+    s.ledger.step(&s.mollusk, &ix, &[Check::err(ProgramError::Custom(INVALID_STATUS))]);
+    s.assert_unpaid(2, 7);
+
+    let mut s = Setup::new(9, OPEN);
+    let ix = s.withdraw("withdraw_secure");
+    s.ledger.step(
+        &s.mollusk,
+        &ix,
+        &[Check::err(ProgramError::Custom(UNSUPPORTED_VERSION))],
+    );
+    s.assert_unpaid(9, OPEN);
+}
+
+#[test]
+fn sanity_settled_and_cancelled() {
+    // -----------------------------------------------------------------------
+    // SANITY: Alice's trade is open, under version 2
+    //   Alice settles, or Mallory cancels
+    //   Anyone: withdraw_vulnerable, or withdraw_secure
+    //
+    // Expected: all SUCCEED. A settled trade pays Mallory 1 SOL and a
+    //           cancelled one refunds Alice, with either variant.
+    // -----------------------------------------------------------------------
+    for withdraw in ["withdraw_vulnerable", "withdraw_secure"] {
+        let mut s = Setup::new(2, OPEN);
+        let mallory = s.mallory;
+        let ix = s.set_status("settle");
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        s.assert_unpaid(2, V2_SETTLED);
+        let ix = s.withdraw(withdraw);
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        s.assert_withdrawn(&mallory);
+
+        let mut s = Setup::new(2, OPEN);
+        let alice = s.alice;
+        let ix = s.set_status("cancel");
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        s.assert_unpaid(2, V2_CANCELLED);
+        let ix = s.withdraw(withdraw);
+        s.ledger.step(&s.mollusk, &ix, &[Check::success()]);
+        s.assert_withdrawn(&alice);
+    }
+}
@@ -0,0 +1,246 @@
+//! # Playground
+//!
+//! Pattern 191's lending market as plain state, so a web page can step
+//! through the vulnerable and secure handlers without a validator. The
+//! arithmetic is `risk-limits`, the same code the program runs; only the
+//! accounts and lamport moves are replaced by fields.
+//!
+//! Build with `wasm-pack build playground --target web`. The package
+//! exports [`Lending`] and its [`Config`], [`Market`], and [`Position`]
+//! state, plus the bindings of the shared modules the programs call:
+//! `risk-limits` (`checkedIncrease`, `borrowLimit`, ...), `safe-math`
+//! (`quote`, `Basket`), and `timelock` (`readyAt`, `Queue`, ...).
+//!
+//! Pattern 191 is the only pattern modelled as state. The other patterns'
+//! handlers keep their logic inline, so the browser gets the shared
+//! arithmetic they use, not their handlers.
+
+use risk_limits::{borrow_limit, checked_increase, LimitError};
+use wasm_bindgen::prelude::*;
+
+/// `risk-limits`' bindings, exported from the same package.
+pub use risk_limits::wasm as limits;
+/// `safe-math`'s bindings: patterns 220 and 226's valuations.
+pub use safe_math::wasm as valuation;
+/// `timelock`'s bindings: pattern 224's queued changes.
+pub use timelock::wasm as delays;
+
+/// Loan-to-value both borrow variants enforce, as in pattern 191.
+pub const LTV_BPS: u64 = 5_000;
+
+/// Pattern 191's error codes, by name. Thrown to JavaScript as an `Error`
+/// with the variant name as its message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketError {
+    MarketCapExceeded,
+    GlobalCapExceeded,
+    BorrowCapExceeded,
+    ExceedsBorrowLimit,
+    /// The market holds fewer lamports than the borrow pays out.
+    InsufficientLiquidity,
+    Overflow,
+}
+
+impl MarketError {
+    pub fn name(self) -> &'static str {
+        match self {
+            MarketError::MarketCapExceeded => "MarketCapExceeded",
+            MarketError::GlobalCapExceeded => "GlobalCapExceeded",
+            MarketError::BorrowCapExceeded => "BorrowCapExceeded",
+            MarketError::ExceedsBorrowLimit => "ExceedsBorrowLimit",
+            MarketError::InsufficientLiquidity => "InsufficientLiquidity",
+            MarketError::Overflow => "Overflow",
+        }
+    }
+}
+
+impl From<MarketError> for JsValue {
+    fn from(err: MarketError) -> Self {
+        JsError::new(err.name()).into()
+    }
+}
+
+fn limit_error(err: LimitError, cap_exceeded: MarketError) -> MarketError {
+    match err {
+        LimitError::CapExceeded => cap_exceeded,
+        LimitError::Overflow => MarketError::Overflow,
+    }
+}
+
+/// Protocol-wide deposit (TVL) cap.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub global_deposit_cap: u64,
+    pub total_deposits: u64,
+}
+
+/// One market. Deposits minus borrows is its lendable liquidity.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Market {
+    /// Collateral value, 10_000 = 1:1.
+    pub price_bps: u64,
+    pub deposit_cap: u64,
+    pub borrow_cap: u64,
+    pub total_deposits: u64,
+    pub total_borrows: u64,
+}
+
+/// The user's position in the market.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub deposited: u64,
+    pub borrowed: u64,
+}
+
+/// A config, one market, and one position. Each handler updates the state
+/// only when it succeeds, like a transaction.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Lending {
+    config: Config,
+    market: Market,
+    position: Position,
+}
+
+#[wasm_bindgen]
+impl Lending {
+    #[wasm_bindgen(constructor)]
+    pub fn new(global_deposit_cap: u64, deposit_cap: u64, borrow_cap: u64, price_bps: u64) -> Self {
+        Self {
+            config: Config {
+                global_deposit_cap,
+                total_deposits: 0,
+            },
+            market: Market {
+                price_bps,
+                deposit_cap,
+                borrow_cap,
+                total_deposits: 0,
+                total_borrows: 0,
+            },
+            position: Position::default(),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn market(&self) -> Market {
+        self.market
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// The oracle update. A glitch here is what the caps have to survive.
+    #[wasm_bindgen(js_name = setPrice)]
+    pub fn set_price(&mut self, price_bps: u64) {
+        self.market.price_bps = price_bps;
+    }
+
+    /// VULNERABLE: totals grow without bound.
+    #[wasm_bindgen(js_name = depositVulnerable)]
+    pub fn deposit_vulnerable(&mut self, amount: u64) -> Result<(), MarketError> {
+        let global = self
+            .config
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(MarketError::Overflow)?;
+        let market = self
+            .market
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(MarketError::Overflow)?;
+        self.credit(amount)?;
+        self.config.total_deposits = global;
+        self.market.total_deposits = market;
+        Ok(())
+    }
+
+    /// SECURE: the market and global deposit caps.
+    #[wasm_bindgen(js_name = depositSecure)]
+    pub fn deposit_secure(&mut self, amount: u64) -> Result<(), MarketError> {
+        let global = checked_increase(
+            self.config.total_deposits,
+            amount,
+            self.config.global_deposit_cap,
+        )
+        .map_err(|e| limit_error(e, MarketError::GlobalCapExceeded))?;
+        let market = checked_increase(self.market.total_deposits, amount, self.market.deposit_cap)
+            .map_err(|e| limit_error(e, MarketError::MarketCapExceeded))?;
+        self.credit(amount)?;
+        self.config.total_deposits = global;
+        self.market.total_deposits = market;
+        Ok(())
+    }
+
+    /// VULNERABLE: nothing limits the market's total exposure.
+    #[wasm_bindgen(js_name = borrowVulnerable)]
+    pub fn borrow_vulnerable(&mut self, amount: u64) -> Result<(), MarketError> {
+        let borrowed = self.check_ltv(amount)?;
+        let total = self
+            .market
+            .total_borrows
+            .checked_add(amount)
+            .ok_or(MarketError::Overflow)?;
+        self.pay_out(total)?;
+        self.market.total_borrows = total;
+        self.position.borrowed = borrowed;
+        Ok(())
+    }
+
+    /// SECURE: the market's borrow cap bounds what a glitched price costs.
+    #[wasm_bindgen(js_name = borrowSecure)]
+    pub fn borrow_secure(&mut self, amount: u64) -> Result<(), MarketError> {
+        let borrowed = self.check_ltv(amount)?;
+        let total = checked_increase(self.market.total_borrows, amount, self.market.borrow_cap)
+            .map_err(|e| limit_error(e, MarketError::BorrowCapExceeded))?;
+        self.pay_out(total)?;
+        self.market.total_borrows = total;
+        self.position.borrowed = borrowed;
+        Ok(())
+    }
+}
+
+impl Lending {
+    fn credit(&mut self, amount: u64) -> Result<(), MarketError> {
+        self.position.deposited = self
+            .position
+            .deposited
+            .checked_add(amount)
+            .ok_or(MarketError::Overflow)?;
+        Ok(())
+    }
+
+    /// Per-position LTV: both variants enforce it. Returns the position's
+    /// new borrowed total.
+    fn check_ltv(&self, amount: u64) -> Result<u64, MarketError> {
+        let limit = borrow_limit(self.position.deposited, self.market.price_bps, LTV_BPS)
+            .map_err(|_| MarketError::Overflow)?;
+        let borrowed = self
+            .position
+            .borrowed
+            .checked_add(amount)
+            .ok_or(MarketError::Overflow)?;
+        if borrowed > limit {
+            return Err(MarketError::ExceedsBorrowLimit);
+        }
+        Ok(borrowed)
+    }
+
+    /// The program's lamport debit fails once the market runs dry.
+    fn pay_out(&self, total_borrows: u64) -> Result<(), MarketError> {
+        if total_borrows > self.market.total_deposits {
+            return Err(MarketError::InsufficientLiquidity);
+        }
+        Ok(())
+    }
+}
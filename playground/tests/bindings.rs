@@ -0,0 +1,53 @@
+/// # Playground — shared module bindings
+///
+/// - A basket with a stale price refuses to value, like pattern 220's
+///   secure handler
+/// - A queued change applies only inside its window, like pattern 224's
+/// - Cancelling leaves nothing to apply
+use playground::delays::Queue;
+use playground::valuation::{quote, Basket};
+use safe_math::ValuationError;
+use timelock::TimelockError;
+
+const SLOT: u64 = 1_000;
+const MAX_AGE: u64 = 25;
+
+#[test]
+fn basket_refuses_stale_price() {
+    // 2.5 tokens of a 6-decimal mint at $4
+    assert_eq!(
+        quote(2_500_000, 6, 4_000_000, SLOT, SLOT, MAX_AGE),
+        Ok(10_000_000)
+    );
+
+    let mut basket = Basket::new();
+    basket.add(2_500_000, 6, 4_000_000, SLOT);
+    basket.add(1_000_000_000, 9, 1_000_000, SLOT);
+    assert_eq!(basket.value(SLOT, MAX_AGE), Ok(11_000_000));
+    assert_eq!(
+        basket.value(SLOT + MAX_AGE + 1, MAX_AGE),
+        Err(ValuationError::StalePrice)
+    );
+}
+
+#[test]
+fn queued_change_applies_in_window() {
+    let mut queue = Queue::new(100, 50);
+    assert_eq!(queue.apply(SLOT), Err(TimelockError::NothingQueued));
+
+    queue.queue(250, SLOT).unwrap();
+    assert_eq!(queue.pending_ready_at(), Some(SLOT + 100));
+    assert_eq!(queue.apply(SLOT + 99), Err(TimelockError::TooEarly));
+    assert_eq!(queue.apply(SLOT + 151), Err(TimelockError::Expired));
+    assert_eq!(queue.apply(SLOT + 100), Ok(250));
+    assert_eq!(queue.pending_ready_at(), None);
+}
+
+#[test]
+fn cancelled_change_is_gone() {
+    let mut queue = Queue::new(100, 50);
+    queue.queue(250, SLOT).unwrap();
+    assert_eq!(queue.cancel(), Ok(250));
+    assert_eq!(queue.apply(SLOT + 100), Err(TimelockError::NothingQueued));
+    assert_eq!(queue.cancel(), Err(TimelockError::NothingQueued));
+}
@@ -0,0 +1,62 @@
+/// # Playground — pattern 191 state transitions
+///
+/// - Deposits past the market cap pass the vulnerable handler only
+/// - A glitched price lets the vulnerable borrow take the whole market
+/// - The secure borrow stops at the borrow cap
+/// - A failed handler leaves the state unchanged
+use playground::{Lending, MarketError, Position};
+
+const PRICE_1X: u64 = 10_000;
+const GLITCHED: u64 = 1_000 * PRICE_1X;
+
+fn lending() -> Lending {
+    // global cap 1,000, market deposit cap 500, borrow cap 100
+    Lending::new(1_000, 500, 100, PRICE_1X)
+}
+
+#[test]
+fn deposit_cap_vulnerable_vs_secure() {
+    let mut l = lending();
+    assert_eq!(l.deposit_secure(400), Ok(()));
+    assert_eq!(l.deposit_secure(200), Err(MarketError::MarketCapExceeded));
+    assert_eq!(l.deposit_vulnerable(200), Ok(()));
+    assert_eq!(l.market().total_deposits, 600);
+    assert_eq!(l.config().total_deposits, 600);
+}
+
+#[test]
+fn glitched_price_drains_market_vulnerable() {
+    let mut l = lending();
+    l.deposit_secure(400).unwrap();
+    assert_eq!(
+        l.borrow_vulnerable(400),
+        Err(MarketError::ExceedsBorrowLimit)
+    );
+
+    l.set_price(GLITCHED);
+    assert_eq!(l.borrow_vulnerable(400), Ok(()));
+    assert_eq!(l.market().total_borrows, 400);
+    assert_eq!(
+        l.borrow_vulnerable(1),
+        Err(MarketError::InsufficientLiquidity)
+    );
+}
+
+#[test]
+fn borrow_cap_bounds_glitch_secure() {
+    let mut l = lending();
+    l.deposit_secure(400).unwrap();
+    l.set_price(GLITCHED);
+
+    assert_eq!(l.borrow_secure(400), Err(MarketError::BorrowCapExceeded));
+    assert_eq!(
+        l.position(),
+        Position {
+            deposited: 400,
+            borrowed: 0
+        }
+    );
+    assert_eq!(l.borrow_secure(100), Ok(()));
+    assert_eq!(l.borrow_secure(1), Err(MarketError::BorrowCapExceeded));
+    assert_eq!(l.market().total_borrows, 100);
+}
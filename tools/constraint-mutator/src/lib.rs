@@ -0,0 +1,11 @@
+//! Mutation testing for Anchor account constraints.
+//!
+//! For each `#[derive(Accounts)]` struct in a pattern's program, remove one
+//! constraint at a time (a `has_one`, a `seeds`, a `Signer`, ...), rebuild,
+//! and rerun the pattern's Mollusk tests. A test that starts failing is the
+//! one that depends on that constraint; a mutant nothing notices is a
+//! constraint no test exercises.
+
+pub mod mutate;
+pub mod report;
+pub mod runner;
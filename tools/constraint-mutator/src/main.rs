@@ -0,0 +1,145 @@
+//! Usage, from the repo root:
+//!
+//!   cargo run -p constraint-mutator -- [--list] [--out FILE] [PATTERN...]
+//!
+//! `PATTERN` filters by directory name substring (`01`, `signer`). `--list`
+//! prints the mutants without building anything.
+
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use constraint_mutator::{
+    mutate::{find_mutations, Mutation},
+    report::{markdown, PatternReport},
+    runner::{discover, Outcome, Pattern, Runner, TestResults},
+};
+
+struct Args {
+    list: bool,
+    out: Option<PathBuf>,
+    filters: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        list: false,
+        out: None,
+        filters: Vec::new(),
+    };
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--list" => args.list = true,
+            "--out" => args.out = Some(iter.next().ok_or("--out needs a path")?.into()),
+            flag if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+            _ => args.filters.push(arg),
+        }
+    }
+    Ok(args)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let root = env::current_dir().expect("current dir");
+    let patterns: Vec<Pattern> = match discover(&root) {
+        Ok(patterns) => patterns
+            .into_iter()
+            .filter(|p| args.filters.is_empty() || args.filters.iter().any(|f| p.name.contains(f)))
+            .collect(),
+        Err(err) => {
+            eprintln!("error: no patterns/ under {}: {err}", root.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if args.list {
+        for pattern in &patterns {
+            match read_mutations(pattern) {
+                Ok((_, mutations)) => {
+                    for m in mutations {
+                        println!("{}: {}.{}: {}", pattern.name, m.accounts, m.field, m.kind);
+                    }
+                }
+                Err(err) => eprintln!("{}: {err}", pattern.name),
+            }
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let runner = Runner::new(&root);
+    let mut reports = Vec::new();
+    for pattern in &patterns {
+        let results = mutate_pattern(&runner, pattern);
+        if let Err(err) = &results {
+            eprintln!("{}: {err}", pattern.name);
+        }
+        reports.push(PatternReport {
+            pattern: pattern.name.clone(),
+            results,
+        });
+    }
+
+    let report = markdown(&reports);
+    match &args.out {
+        Some(path) => {
+            if let Err(err) = fs::write(path, report) {
+                eprintln!("error: writing {}: {err}", path.display());
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{report}"),
+    }
+    ExitCode::SUCCESS
+}
+
+fn read_mutations(pattern: &Pattern) -> Result<(String, Vec<Mutation>), String> {
+    let original = fs::read_to_string(&pattern.program)
+        .map_err(|e| format!("reading {}: {e}", pattern.program.display()))?;
+    let mutations = find_mutations(&original).map_err(|e| format!("parsing program: {e}"))?;
+    Ok((original, mutations))
+}
+
+fn mutate_pattern(runner: &Runner, pattern: &Pattern) -> Result<Vec<(Mutation, Outcome)>, String> {
+    let (original, mutations) = read_mutations(pattern)?;
+
+    eprintln!("{}: baseline", pattern.name);
+    let baseline = runner.baseline(pattern)?;
+    let results = run_mutants(runner, pattern, &original, mutations, &baseline);
+    // Rebuild the original even if a mutant failed, so its binary isn't
+    // left in target/deploy for the next pattern's tests
+    let restored = runner.restore(pattern);
+    match (results, restored) {
+        (Ok(results), Ok(())) => Ok(results),
+        (Err(err), Ok(())) | (Ok(_), Err(err)) => Err(err),
+        (Err(err), Err(restore)) => Err(format!("{err}; {restore}")),
+    }
+}
+
+fn run_mutants(
+    runner: &Runner,
+    pattern: &Pattern,
+    original: &str,
+    mutations: Vec<Mutation>,
+    baseline: &TestResults,
+) -> Result<Vec<(Mutation, Outcome)>, String> {
+    let mut results = Vec::new();
+    for (i, mutation) in mutations.into_iter().enumerate() {
+        eprintln!(
+            "{}: [{}] {}.{}: {}",
+            pattern.name,
+            i + 1,
+            mutation.accounts,
+            mutation.field,
+            mutation.kind
+        );
+        let outcome = runner.run(pattern, original, &mutation, baseline)?;
+        results.push((mutation, outcome));
+    }
+    Ok(results)
+}
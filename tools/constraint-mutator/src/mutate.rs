@@ -0,0 +1,308 @@
+//! Finding and applying constraint mutations in Anchor source.
+//!
+//! Mutations are computed from `syn` spans and applied as text edits, so a
+//! mutant differs from the original only where the constraint was.
+
+use std::fmt;
+
+use proc_macro2::{LineColumn, TokenStream, TokenTree};
+use syn::{
+    punctuated::Punctuated, Attribute, Fields, Item, ItemStruct, MacroDelimiter, Meta, Type,
+};
+
+/// One kind of weakening applied to a single field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MutationKind {
+    /// Remove one `has_one = <target>`.
+    DropHasOne(String),
+    /// Remove one `constraint = <expr>`.
+    DropConstraint(String),
+    /// Remove `address = <expr>`.
+    DropAddress,
+    /// Remove `owner = <expr>`.
+    DropOwner,
+    /// Remove `seeds = [...]` together with its `bump`.
+    DropSeeds,
+    /// Replace `Signer<'info>` with an unchecked `AccountInfo<'info>`.
+    SignerToAccountInfo,
+}
+
+impl fmt::Display for MutationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MutationKind::DropHasOne(target) => write!(f, "drop `has_one = {target}`"),
+            MutationKind::DropConstraint(expr) => write!(f, "drop `constraint = {expr}`"),
+            MutationKind::DropAddress => write!(f, "drop `address`"),
+            MutationKind::DropOwner => write!(f, "drop `owner`"),
+            MutationKind::DropSeeds => write!(f, "drop `seeds` + `bump`"),
+            MutationKind::SignerToAccountInfo => write!(f, "`Signer` → `AccountInfo`"),
+        }
+    }
+}
+
+/// A byte-range replacement in the original source.
+#[derive(Debug, Clone)]
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// One mutant: a single constraint removed from a single field.
+#[derive(Debug, Clone)]
+pub struct Mutation {
+    /// The `#[derive(Accounts)]` struct.
+    pub accounts: String,
+    /// The field whose constraint was weakened.
+    pub field: String,
+    pub kind: MutationKind,
+    edits: Vec<Edit>,
+}
+
+impl Mutation {
+    /// Return `source` with this mutation applied. `source` must be the text
+    /// the mutation was found in.
+    pub fn apply(&self, source: &str) -> String {
+        let mut edits = self.edits.clone();
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+        let mut out = source.to_string();
+        for edit in edits {
+            out.replace_range(edit.start..edit.end, &edit.text);
+        }
+        out
+    }
+}
+
+/// Every single-constraint mutation in `source`, in source order.
+pub fn find_mutations(source: &str) -> syn::Result<Vec<Mutation>> {
+    let file = syn::parse_file(source)?;
+    let lines = LineIndex::new(source);
+    let mut mutations = Vec::new();
+    for item in &file.items {
+        if let Item::Struct(item) = item {
+            if derives_accounts(item) {
+                collect_struct(item, source, &lines, &mut mutations);
+            }
+        }
+    }
+    Ok(mutations)
+}
+
+fn derives_accounts(item: &ItemStruct) -> bool {
+    item.attrs.iter().any(|attr| {
+        attr.path().is_ident("derive")
+            && attr
+                .parse_args_with(Punctuated::<syn::Path, syn::Token![,]>::parse_terminated)
+                .map(|paths| {
+                    paths
+                        .iter()
+                        .any(|path| path.segments.last().is_some_and(|s| s.ident == "Accounts"))
+                })
+                .unwrap_or(false)
+    })
+}
+
+fn collect_struct(item: &ItemStruct, source: &str, lines: &LineIndex, out: &mut Vec<Mutation>) {
+    let Fields::Named(fields) = &item.fields else {
+        return;
+    };
+    let accounts = item.ident.to_string();
+
+    for field in &fields.named {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let mut push = |kind: MutationKind, edits: Vec<Edit>| {
+            out.push(Mutation {
+                accounts: accounts.clone(),
+                field: ident.to_string(),
+                kind,
+                edits,
+            })
+        };
+
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("account")) {
+            let Meta::List(list) = &attr.meta else {
+                continue;
+            };
+            let items = split_items(list.tokens.clone(), source, lines);
+
+            for (i, item) in items.iter().enumerate() {
+                let (kind, drop): (MutationKind, Vec<usize>) = match item.key.as_str() {
+                    "has_one" => (MutationKind::DropHasOne(item.value.clone()), vec![i]),
+                    "constraint" => (MutationKind::DropConstraint(item.value.clone()), vec![i]),
+                    "address" => (MutationKind::DropAddress, vec![i]),
+                    "owner" => (MutationKind::DropOwner, vec![i]),
+                    "seeds" => {
+                        let mut drop = vec![i];
+                        drop.extend(items.iter().position(|other| other.key == "bump"));
+                        (MutationKind::DropSeeds, drop)
+                    }
+                    _ => continue,
+                };
+                push(
+                    kind,
+                    vec![rewrite_attr(attr, list, &items, &drop, source, lines)],
+                );
+            }
+        }
+
+        if let Some(segment) = signer_segment(&field.ty) {
+            let name = lines.range(segment.ident.span().start(), segment.ident.span().end());
+
+            // Anchor's safety check wants a `/// CHECK:` on every AccountInfo.
+            let first = match &field.vis {
+                syn::Visibility::Public(token) => token.span.start(),
+                syn::Visibility::Restricted(vis) => vis.pub_token.span.start(),
+                syn::Visibility::Inherited => ident.span().start(),
+            };
+            let at = lines.offset(first);
+            let doc = format!("/// CHECK: constraint mutant\n{}", " ".repeat(first.column));
+
+            push(
+                MutationKind::SignerToAccountInfo,
+                vec![
+                    Edit {
+                        start: at,
+                        end: at,
+                        text: doc,
+                    },
+                    Edit {
+                        start: name.0,
+                        end: name.1,
+                        text: "AccountInfo".to_string(),
+                    },
+                ],
+            );
+        }
+    }
+}
+
+fn signer_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    path.path.segments.last().filter(|s| s.ident == "Signer")
+}
+
+/// One comma-separated item inside `#[account(...)]`.
+struct AttrItem {
+    start: usize,
+    end: usize,
+    /// Text before the first `=`, whitespace removed (`has_one`, `token::mint`).
+    key: String,
+    /// Source text after the first `=`, or empty.
+    value: String,
+}
+
+fn split_items(tokens: TokenStream, source: &str, lines: &LineIndex) -> Vec<AttrItem> {
+    let mut groups: Vec<Vec<TokenTree>> = vec![Vec::new()];
+    for tt in tokens {
+        match &tt {
+            TokenTree::Punct(p) if p.as_char() == ',' => groups.push(Vec::new()),
+            _ => groups.last_mut().unwrap().push(tt),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|group| !group.is_empty())
+        .map(|group| {
+            let start = lines.offset(group[0].span().start());
+            let end = lines.offset(group[group.len() - 1].span().end());
+            let eq = group
+                .iter()
+                .position(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == '='));
+            let key_end = eq.unwrap_or(group.len());
+            let key = group[..key_end].iter().map(|tt| tt.to_string()).collect();
+            let value = match eq.and_then(|eq| group.get(eq + 1)) {
+                Some(tt) => source[lines.offset(tt.span().start())..end].to_string(),
+                None => String::new(),
+            };
+            AttrItem {
+                start,
+                end,
+                key,
+                value,
+            }
+        })
+        .collect()
+}
+
+/// Rewrite `#[account(...)]` without the items at `drop`. If nothing is
+/// left, the whole attribute (and its line) goes.
+fn rewrite_attr(
+    attr: &Attribute,
+    list: &syn::MetaList,
+    items: &[AttrItem],
+    drop: &[usize],
+    source: &str,
+    lines: &LineIndex,
+) -> Edit {
+    let kept: Vec<&str> = items
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !drop.contains(i))
+        .map(|(_, item)| &source[item.start..item.end])
+        .collect();
+
+    if kept.is_empty() {
+        let start = lines.offset(attr.pound_token.span.start());
+        let end = lines.offset(attr.bracket_token.span.close().end());
+        let (start, end) = widen_to_lines(source, start, end);
+        return Edit {
+            start,
+            end,
+            text: String::new(),
+        };
+    }
+
+    let MacroDelimiter::Paren(paren) = &list.delimiter else {
+        unreachable!("#[account] takes parentheses")
+    };
+    Edit {
+        start: lines.offset(paren.span.open().end()),
+        end: lines.offset(paren.span.close().start()),
+        text: kept.join(", "),
+    }
+}
+
+/// Extend `start..end` to whole lines when nothing else shares them.
+fn widen_to_lines(source: &str, start: usize, end: usize) -> (usize, usize) {
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[end..]
+        .find('\n')
+        .map_or(source.len(), |i| end + i + 1);
+    if source[line_start..start].trim().is_empty() && source[end..line_end].trim().is_empty() {
+        (line_start, line_end)
+    } else {
+        (start, end)
+    }
+}
+
+/// Maps proc-macro2 line/column positions back to byte offsets.
+struct LineIndex<'a> {
+    source: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(source: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { source, starts }
+    }
+
+    fn offset(&self, at: LineColumn) -> usize {
+        let line = self.starts[at.line - 1];
+        self.source[line..]
+            .char_indices()
+            .nth(at.column)
+            .map_or(self.source.len(), |(i, _)| line + i)
+    }
+
+    fn range(&self, start: LineColumn, end: LineColumn) -> (usize, usize) {
+        (self.offset(start), self.offset(end))
+    }
+}
@@ -0,0 +1,66 @@
+//! Markdown report: one table per pattern.
+
+use std::fmt::Write;
+
+use crate::{mutate::Mutation, runner::Outcome};
+
+/// Results for one pattern.
+pub struct PatternReport {
+    pub pattern: String,
+    pub results: Result<Vec<(Mutation, Outcome)>, String>,
+}
+
+pub fn markdown(reports: &[PatternReport]) -> String {
+    let mut out = String::from("# Constraint Mutation Report\n");
+    for report in reports {
+        let _ = write!(out, "\n## {}\n\n", report.pattern);
+        let results = match &report.results {
+            Ok(results) => results,
+            Err(err) => {
+                let _ = writeln!(out, "Skipped: {err}");
+                continue;
+            }
+        };
+        if results.is_empty() {
+            out.push_str("No account constraints to mutate.\n");
+            continue;
+        }
+
+        let killed = results
+            .iter()
+            .filter(|(_, o)| matches!(o, Outcome::Killed(_)))
+            .count();
+        let survived = results
+            .iter()
+            .filter(|(_, o)| *o == Outcome::Survived)
+            .count();
+        let _ = write!(
+            out,
+            "{} mutants: {killed} killed, {survived} survived, {} did not build.\n\n",
+            results.len(),
+            results.len() - killed - survived,
+        );
+
+        out.push_str("| Accounts | Field | Mutation | Result |\n");
+        out.push_str("|----------|-------|----------|--------|\n");
+        for (mutation, outcome) in results {
+            let result = match outcome {
+                Outcome::BuildFailed => "did not build".to_string(),
+                Outcome::Survived => "**survived**".to_string(),
+                Outcome::Killed(tests) => {
+                    let tests: Vec<String> = tests.iter().map(|t| format!("`{t}`")).collect();
+                    format!("killed by {}", tests.join(", "))
+                }
+            };
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                mutation.accounts,
+                mutation.field,
+                mutation.kind.to_string().replace('|', "\\|"),
+                result
+            );
+        }
+    }
+    out
+}
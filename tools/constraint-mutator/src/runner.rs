@@ -0,0 +1,205 @@
+//! Building mutants and running a pattern's tests against them.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::mutate::Mutation;
+
+/// A pattern with an Anchor program and a Mollusk test crate.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    /// Directory name, e.g. `01-missing-signer-check`.
+    pub name: String,
+    /// `anchor/src/lib.rs`.
+    pub program: PathBuf,
+    /// `anchor/Cargo.toml`.
+    pub manifest: PathBuf,
+    /// Package name of the test crate, e.g. `test-missing-signer`.
+    pub test_crate: String,
+}
+
+/// Test name → passed.
+pub type TestResults = BTreeMap<String, bool>;
+
+/// What happened to one mutant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The mutant didn't compile; the constraint can't be removed this way.
+    BuildFailed,
+    /// Every test that passed on the original still passes.
+    Survived,
+    /// These tests passed on the original and fail on the mutant.
+    Killed(Vec<String>),
+}
+
+/// Every pattern under `root/patterns` with an `anchor/` program and a
+/// `tests/` crate, in pattern-number order.
+pub fn discover(root: &Path) -> io::Result<Vec<Pattern>> {
+    let mut patterns = Vec::new();
+    for entry in fs::read_dir(root.join("patterns"))? {
+        let dir = entry?.path();
+        let program = dir.join("anchor/src/lib.rs");
+        let tests_manifest = dir.join("tests/Cargo.toml");
+        if !program.is_file() || !tests_manifest.is_file() {
+            continue;
+        }
+        let Some(test_crate) = package_name(&fs::read_to_string(&tests_manifest)?) else {
+            continue;
+        };
+        patterns.push(Pattern {
+            name: dir.file_name().unwrap().to_string_lossy().into_owned(),
+            manifest: dir.join("anchor/Cargo.toml"),
+            program,
+            test_crate,
+        });
+    }
+    patterns.sort_by_key(|p| (pattern_number(&p.name), p.name.clone()));
+    Ok(patterns)
+}
+
+fn package_name(manifest: &str) -> Option<String> {
+    manifest
+        .lines()
+        .skip_while(|line| line.trim() != "[package]")
+        .find_map(|line| line.trim().strip_prefix("name = "))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+fn pattern_number(name: &str) -> u32 {
+    name.split('-')
+        .next()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(u32::MAX)
+}
+
+/// Parse libtest's `test <name> ... ok|FAILED` lines.
+pub fn parse_test_output(stdout: &str) -> TestResults {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let rest = line.strip_prefix("test ")?;
+            let (name, status) = rest.rsplit_once(" ... ")?;
+            match status.trim() {
+                "ok" => Some((name.to_string(), true)),
+                "FAILED" => Some((name.to_string(), false)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Compare a mutant's results against the original's.
+pub fn classify(baseline: &TestResults, mutant: &TestResults) -> Outcome {
+    let killed: Vec<String> = baseline
+        .iter()
+        .filter(|(name, passed)| **passed && mutant.get(*name) != Some(&true))
+        .map(|(name, _)| name.clone())
+        .collect();
+    if killed.is_empty() {
+        Outcome::Survived
+    } else {
+        Outcome::Killed(killed)
+    }
+}
+
+/// Builds programs into `sbf_out` and runs test crates against them.
+pub struct Runner {
+    root: PathBuf,
+    sbf_out: PathBuf,
+}
+
+impl Runner {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            sbf_out: root.join("target/deploy"),
+        }
+    }
+
+    /// Build and test the unmodified program.
+    pub fn baseline(&self, pattern: &Pattern) -> Result<TestResults, String> {
+        if !self.build(pattern)? {
+            return Err(format!("{} does not build", pattern.name));
+        }
+        let results = self.test(pattern)?;
+        if results.is_empty() {
+            return Err(format!("{} ran no tests", pattern.test_crate));
+        }
+        Ok(results)
+    }
+
+    /// Write the mutant, build it, test it, and put the original source
+    /// back. The mutant's binary stays in `sbf_out`; call [`Runner::restore`]
+    /// after the last mutant, or after any mutant that returns an error.
+    pub fn run(
+        &self,
+        pattern: &Pattern,
+        original: &str,
+        mutation: &Mutation,
+        baseline: &TestResults,
+    ) -> Result<Outcome, String> {
+        let _restore = Restore {
+            path: &pattern.program,
+            original,
+        };
+        fs::write(&pattern.program, mutation.apply(original))
+            .map_err(|e| format!("writing {}: {e}", pattern.program.display()))?;
+
+        if self.build(pattern)? {
+            Ok(classify(baseline, &self.test(pattern)?))
+        } else {
+            Ok(Outcome::BuildFailed)
+        }
+    }
+
+    /// Rebuild the original program so no mutant binary is left behind.
+    pub fn restore(&self, pattern: &Pattern) -> Result<(), String> {
+        if self.build(pattern)? {
+            Ok(())
+        } else {
+            Err(format!("{} no longer builds after restore", pattern.name))
+        }
+    }
+
+    fn build(&self, pattern: &Pattern) -> Result<bool, String> {
+        Command::new("cargo")
+            .arg("build-sbf")
+            .arg("--manifest-path")
+            .arg(&pattern.manifest)
+            .arg("--sbf-out-dir")
+            .arg(&self.sbf_out)
+            .current_dir(&self.root)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .map_err(|e| format!("running cargo build-sbf: {e}"))
+    }
+
+    fn test(&self, pattern: &Pattern) -> Result<TestResults, String> {
+        let output = Command::new("cargo")
+            .args(["test", "-p", &pattern.test_crate])
+            .env("SBF_OUT_DIR", &self.sbf_out)
+            .current_dir(&self.root)
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|e| format!("running cargo test: {e}"))?;
+        Ok(parse_test_output(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// Puts the original program source back, even if a step fails.
+struct Restore<'a> {
+    path: &'a Path,
+    original: &'a str,
+}
+
+impl Drop for Restore<'_> {
+    fn drop(&mut self) {
+        let _ = fs::write(self.path, self.original);
+    }
+}
@@ -0,0 +1,153 @@
+/// # Constraint Mutator — mutation discovery tests
+///
+/// - Each constraint kind yields one mutant, and only `Accounts` structs count
+/// - Dropping one item keeps the rest of the attribute
+/// - Dropping `seeds` takes `bump` with it
+/// - Dropping the only item removes the attribute's line
+/// - `Signer` → `AccountInfo` adds the `/// CHECK:` Anchor requires
+/// - Every mutant of every pattern in the repo still parses
+/// - libtest output parsing and kill classification
+use std::{fs, path::Path};
+
+use constraint_mutator::{
+    mutate::{find_mutations, MutationKind},
+    runner::{classify, discover, parse_test_output, Outcome},
+};
+
+const SOURCE: &str = r#"use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, has_one = authority, seeds = [b"vault", authority.key().as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(address = vault.treasury)]
+    pub treasury: SystemAccount<'info>,
+    #[account(constraint = config.paused == false)]
+    pub config: Account<'info, Config>,
+    pub authority: Signer<'info>,
+}
+
+pub struct NotAccounts<'info> {
+    #[account(has_one = authority)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+"#;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn mutant(kind: &MutationKind) -> String {
+    let mutations = find_mutations(SOURCE).unwrap();
+    let m = mutations.iter().find(|m| &m.kind == kind).unwrap();
+    let out = m.apply(SOURCE);
+    syn::parse_file(&out).expect("mutant must parse");
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn finds_each_constraint_once() {
+    let found: Vec<(String, String, MutationKind)> = find_mutations(SOURCE)
+        .unwrap()
+        .into_iter()
+        .map(|m| (m.accounts, m.field, m.kind))
+        .collect();
+
+    let expected = [
+        ("vault", MutationKind::DropHasOne("authority".into())),
+        ("vault", MutationKind::DropSeeds),
+        ("treasury", MutationKind::DropAddress),
+        (
+            "config",
+            MutationKind::DropConstraint("config.paused == false".into()),
+        ),
+        ("authority", MutationKind::SignerToAccountInfo),
+    ];
+    assert_eq!(found.len(), expected.len(), "{found:#?}");
+    for ((accounts, field, kind), (want_field, want_kind)) in found.iter().zip(expected) {
+        assert_eq!(accounts, "Withdraw");
+        assert_eq!(field, want_field);
+        assert_eq!(kind, &want_kind);
+    }
+}
+
+#[test]
+fn drop_has_one_keeps_other_items() {
+    let out = mutant(&MutationKind::DropHasOne("authority".into()));
+    assert!(out.contains(
+        r#"#[account(mut, seeds = [b"vault", authority.key().as_ref()], bump = vault.bump)]"#
+    ));
+    assert!(!out.contains("mut, has_one = authority, seeds"));
+}
+
+#[test]
+fn drop_seeds_takes_bump() {
+    let out = mutant(&MutationKind::DropSeeds);
+    assert!(out.contains("#[account(mut, has_one = authority)]\n    pub vault"));
+}
+
+#[test]
+fn drop_only_item_removes_attribute_line() {
+    let out = mutant(&MutationKind::DropAddress);
+    assert!(out.contains(
+        "    pub vault: Account<'info, Vault>,\n    pub treasury: SystemAccount<'info>,"
+    ));
+}
+
+#[test]
+fn signer_becomes_checked_account_info() {
+    let out = mutant(&MutationKind::SignerToAccountInfo);
+    assert!(out.contains(
+        "    /// CHECK: constraint mutant\n    pub authority: AccountInfo<'info>,\n}\n\npub struct NotAccounts"
+    ));
+}
+
+#[test]
+fn every_repo_mutant_parses() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let patterns = discover(&root).unwrap();
+    assert!(!patterns.is_empty());
+
+    for pattern in patterns {
+        let source = fs::read_to_string(&pattern.program).unwrap();
+        for m in find_mutations(&source).unwrap() {
+            let out = m.apply(&source);
+            assert_ne!(out, source, "{}: {} is a no-op", pattern.name, m.kind);
+            if let Err(err) = syn::parse_file(&out) {
+                panic!(
+                    "{}: {}.{} {}: {err}",
+                    pattern.name, m.accounts, m.field, m.kind
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn classifies_newly_failing_tests() {
+    let baseline = parse_test_output(
+        "running 3 tests\n\
+         test exploit_works ... ok\n\
+         test secure_rejects ... ok\n\
+         test sanity_flaky ... FAILED\n",
+    );
+    assert_eq!(baseline.len(), 3);
+
+    let same = baseline.clone();
+    assert_eq!(classify(&baseline, &same), Outcome::Survived);
+
+    let mutant = parse_test_output(
+        "test exploit_works ... ok\n\
+         test secure_rejects ... FAILED\n\
+         test sanity_flaky ... FAILED\n",
+    );
+    assert_eq!(
+        classify(&baseline, &mutant),
+        Outcome::Killed(vec!["secure_rejects".into()])
+    );
+}
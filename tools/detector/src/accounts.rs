@@ -0,0 +1,153 @@
+//! `#[derive(Accounts)]` structs and the constraints on each field.
+
+use proc_macro2::{TokenStream, TokenTree};
+use syn::{punctuated::Punctuated, spanned::Spanned, Fields, Item, ItemStruct, Meta, Type};
+
+/// An accounts struct, with its fields in declaration order.
+#[derive(Debug, Clone)]
+pub struct AccountsStruct {
+    pub name: String,
+    pub fields: Vec<AccountField>,
+}
+
+impl AccountsStruct {
+    pub fn field(&self, name: &str) -> Option<&AccountField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+}
+
+/// One field and the items of its `#[account(...)]` attributes.
+#[derive(Debug, Clone)]
+pub struct AccountField {
+    pub name: String,
+    pub line: usize,
+    /// The type's last path segment: `Account`, `Signer`, `UncheckedAccount`.
+    pub ty: String,
+    /// `(key, value)` per item: `("mut", "")`, `("has_one", "owner")`.
+    /// Keys have whitespace removed (`token::mint`); values are token text.
+    pub constraints: Vec<(String, String)>,
+}
+
+impl AccountField {
+    pub fn has(&self, key: &str) -> bool {
+        self.constraints.iter().any(|(k, _)| k == key)
+    }
+
+    /// The value of the first `key = value` item, e.g. `close`.
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.constraints
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether Anchor writes this account back when the instruction ends.
+    /// `init`, `init_if_needed`, and `zero` imply `mut`.
+    pub fn is_writable(&self) -> bool {
+        ["mut", "init", "init_if_needed", "zero"]
+            .iter()
+            .any(|key| self.has(key))
+    }
+}
+
+/// Every top-level `#[derive(Accounts)]` struct in `file`.
+pub fn collect(file: &syn::File) -> Vec<AccountsStruct> {
+    file.items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(item) if derives_accounts(item) => Some(parse_struct(item)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn derives_accounts(item: &ItemStruct) -> bool {
+    item.attrs.iter().any(|attr| {
+        attr.path().is_ident("derive")
+            && attr
+                .parse_args_with(Punctuated::<syn::Path, syn::Token![,]>::parse_terminated)
+                .map(|paths| {
+                    paths
+                        .iter()
+                        .any(|path| path.segments.last().is_some_and(|s| s.ident == "Accounts"))
+                })
+                .unwrap_or(false)
+    })
+}
+
+fn parse_struct(item: &ItemStruct) -> AccountsStruct {
+    let fields = match &item.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .filter_map(|field| {
+                let ident = field.ident.as_ref()?;
+                let constraints = field
+                    .attrs
+                    .iter()
+                    .filter(|a| a.path().is_ident("account"))
+                    .filter_map(|a| match &a.meta {
+                        Meta::List(list) => Some(split_items(list.tokens.clone())),
+                        _ => None,
+                    })
+                    .flatten()
+                    .collect();
+                Some(AccountField {
+                    name: ident.to_string(),
+                    line: field.span().start().line,
+                    ty: type_name(&field.ty),
+                    constraints,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    AccountsStruct {
+        name: item.ident.to_string(),
+        fields,
+    }
+}
+
+fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Split `#[account(...)]` tokens on top-level commas into key/value pairs.
+fn split_items(tokens: TokenStream) -> Vec<(String, String)> {
+    let mut groups: Vec<Vec<TokenTree>> = vec![Vec::new()];
+    for tt in tokens {
+        match &tt {
+            TokenTree::Punct(p) if p.as_char() == ',' => groups.push(Vec::new()),
+            _ => groups.last_mut().unwrap().push(tt),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|group| !group.is_empty())
+        .map(|group| {
+            let eq = group
+                .iter()
+                .position(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == '='));
+            let key_end = eq.unwrap_or(group.len());
+            let key = group[..key_end].iter().map(|tt| tt.to_string()).collect();
+            let value = match eq {
+                Some(eq) => group[eq + 1..]
+                    .iter()
+                    .cloned()
+                    .collect::<TokenStream>()
+                    .to_string(),
+                None => String::new(),
+            };
+            (key, value)
+        })
+        .collect()
+}
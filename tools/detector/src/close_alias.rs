@@ -0,0 +1,58 @@
+//! `close-target-alias`: a `close = target` whose target could be another
+//! writable account in the same struct.
+//!
+//! Anchor refuses to close an account into itself, but nothing stops the
+//! target from being any other account the instruction writes. If it is,
+//! the closed account's lamports land in an account the program tracks
+//! separately, and either sit there uncounted or make a later check fail.
+//!
+//! For each field with `close = target`, the rule looks at every other
+//! writable field and reports the pair unless a `constraint` in the
+//! struct compares the two keys with `!=`. Signer targets are skipped: a
+//! wallet that signs isn't one of the program's accounts.
+
+use crate::{accounts::AccountsStruct, Finding, Rule};
+
+pub fn check(structs: &[AccountsStruct]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for accounts in structs {
+        for closed in &accounts.fields {
+            let Some(target) = closed.value("close").and_then(|t| accounts.field(t)) else {
+                continue;
+            };
+            if target.ty == "Signer" {
+                continue;
+            }
+            for other in &accounts.fields {
+                if other.name == closed.name || other.name == target.name || !other.is_writable() {
+                    continue;
+                }
+                if kept_distinct(accounts, &target.name, &other.name) {
+                    continue;
+                }
+                findings.push(Finding {
+                    rule: Rule::CloseTargetAlias,
+                    line: closed.line,
+                    item: accounts.name.clone(),
+                    message: format!(
+                        "`{}` closes into `{}`, which `{}` doesn't keep distinct from writable `{}`",
+                        closed.name, target.name, accounts.name, other.name
+                    ),
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// Whether some `constraint = ...` compares `a.key()` and `b.key()` with `!=`.
+fn kept_distinct(accounts: &AccountsStruct, a: &str, b: &str) -> bool {
+    let (a, b) = (format!("{a}.key()"), format!("{b}.key()"));
+    accounts
+        .fields
+        .iter()
+        .flat_map(|f| &f.constraints)
+        .filter(|(key, _)| key == "constraint")
+        .map(|(_, value)| value.split_whitespace().collect::<String>())
+        .any(|expr| expr.contains("!=") && expr.contains(&a) && expr.contains(&b))
+}
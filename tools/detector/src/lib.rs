@@ -0,0 +1,68 @@
+//! Static checks for Anchor programs.
+//!
+//! Each rule parses a program with `syn` and looks for code that compiles
+//! and reads correctly but doesn't do what it says: a write Anchor throws
+//! away, a constraint that can't hold, a program id pasted into the wrong
+//! place. The patterns in this repo contain such code on purpose, in their
+//! vulnerable variants; a finding anywhere else is a bug.
+
+pub mod accounts;
+pub mod close_alias;
+pub mod non_mut_write;
+pub mod program_ids;
+
+use std::fmt;
+
+pub use program_ids::Manifest;
+
+/// Which check produced a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// A handler writes to an account its accounts struct doesn't mark `mut`.
+    NonMutWrite,
+    /// A `close` target nothing keeps distinct from another writable account.
+    CloseTargetAlias,
+    /// A `declare_id!` or `Id::id()` that disagrees with `program-ids.toml`.
+    ProgramIdMismatch,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::NonMutWrite => write!(f, "non-mut-write"),
+            Rule::CloseTargetAlias => write!(f, "close-target-alias"),
+            Rule::ProgramIdMismatch => write!(f, "program-id-mismatch"),
+        }
+    }
+}
+
+/// One problem at one line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: Rule,
+    /// 1-based line in the checked source.
+    pub line: usize,
+    /// The handler or accounts struct the finding is in.
+    pub item: String,
+    pub message: String,
+}
+
+impl Finding {
+    /// Whether the finding is in a pattern's deliberately vulnerable code:
+    /// a `*_vulnerable` handler or a `*Vulnerable` accounts struct or type.
+    pub fn is_vulnerable_variant(&self) -> bool {
+        self.item.ends_with("_vulnerable") || self.item.ends_with("Vulnerable")
+    }
+}
+
+/// Every finding in `source`, in line order. Program ids are checked
+/// against `manifest`; an empty one checks nothing.
+pub fn check(source: &str, manifest: &Manifest) -> syn::Result<Vec<Finding>> {
+    let file = syn::parse_file(source)?;
+    let structs = accounts::collect(&file);
+    let mut findings = non_mut_write::check(&file, &structs);
+    findings.extend(close_alias::check(&structs));
+    findings.extend(program_ids::check(&file, manifest));
+    findings.sort_by_key(|f| f.line);
+    Ok(findings)
+}
@@ -0,0 +1,103 @@
+//! Usage, from the repo root:
+//!
+//!   cargo run -p detector -- [PATTERN | FILE ...]
+//!
+//! With no arguments, checks every `patterns/*/anchor/src/lib.rs`. `PATTERN`
+//! filters by directory name substring (`200`, `mut`); an argument naming an
+//! existing `.rs` file checks that file instead. Program ids are checked
+//! against `program-ids.toml` in the current directory, if there is one.
+//! Exits non-zero if any finding is outside a vulnerable variant.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+fn main() -> ExitCode {
+    let root = env::current_dir().expect("current dir");
+    let manifest = match detector::Manifest::load(&root.join("program-ids.toml")) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            eprintln!("program-ids.toml: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let (files, filters): (Vec<String>, Vec<String>) = env::args()
+        .skip(1)
+        .partition(|arg| arg.ends_with(".rs") && Path::new(arg).is_file());
+
+    let targets = if files.is_empty() {
+        match programs(&root, &filters) {
+            Ok(targets) => targets,
+            Err(err) => {
+                eprintln!("error: no patterns/ under {}: {err}", root.display());
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        files.into_iter().map(PathBuf::from).collect()
+    };
+
+    let (mut expected, mut unexpected) = (0, 0);
+    for path in &targets {
+        let shown = path.strip_prefix(&root).unwrap_or(path).display();
+        let findings = match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| detector::check(&source, &manifest).map_err(|e| e.to_string()))
+        {
+            Ok(findings) => findings,
+            Err(err) => {
+                eprintln!("{shown}: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+        for finding in findings {
+            let note = if finding.is_vulnerable_variant() {
+                expected += 1;
+                " (vulnerable variant)"
+            } else {
+                unexpected += 1;
+                ""
+            };
+            println!(
+                "{shown}:{}: {}: {}{note}",
+                finding.line, finding.rule, finding.message
+            );
+        }
+    }
+
+    eprintln!(
+        "files: {}, findings: {}, outside vulnerable variants: {unexpected}",
+        targets.len(),
+        expected + unexpected
+    );
+    if unexpected > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// `patterns/*/anchor/src/lib.rs` whose directory matches a filter, in
+/// pattern-number order.
+fn programs(root: &Path, filters: &[String]) -> std::io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    for entry in fs::read_dir(root.join("patterns"))? {
+        let dir = entry?.path();
+        let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+        let program = dir.join("anchor/src/lib.rs");
+        if program.is_file() && (filters.is_empty() || filters.iter().any(|f| name.contains(f))) {
+            found.push((pattern_number(&name), program));
+        }
+    }
+    found.sort();
+    Ok(found.into_iter().map(|(_, program)| program).collect())
+}
+
+fn pattern_number(name: &str) -> u32 {
+    name.split('-')
+        .next()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(u32::MAX)
+}
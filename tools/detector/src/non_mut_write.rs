@@ -0,0 +1,254 @@
+//! `non-mut-write`: a handler writes to an account its accounts struct
+//! doesn't mark `mut`.
+//!
+//! Anchor serializes `Account<T>` state back only for writable fields when
+//! an instruction ends, so an assignment to any other account is silently
+//! dropped. Borrowing lamports or data mutably on one fails at runtime.
+//!
+//! The rule reads each handler in the `#[program]` module and follows
+//! `ctx.accounts.<field>` and locals bound to it (`let x = &mut
+//! ctx.accounts.x;`). It reports:
+//!
+//! - assignments, including compound ones, to anything reached that way
+//! - calls that change lamports, data, or owner
+//! - `&mut` borrows handed to other code
+//!
+//! It doesn't follow accounts into helper functions or through
+//! `remaining_accounts`.
+
+use std::collections::HashMap;
+
+use syn::{
+    spanned::Spanned,
+    visit::{self, Visit},
+    BinOp, Expr, FnArg, GenericArgument, Item, ItemFn, Pat, PathArguments, Type, UnOp,
+};
+
+use crate::{accounts::AccountsStruct, Finding, Rule};
+
+/// Methods that change an account's lamports, data, or owner.
+const MUTATING_METHODS: &[&str] = &[
+    "try_borrow_mut_lamports",
+    "try_borrow_mut_data",
+    "add_lamports",
+    "sub_lamports",
+    "realloc",
+    "resize",
+    "assign",
+];
+
+/// Methods that return the same account in another form.
+const PASS_THROUGH: &[&str] = &["to_account_info", "as_mut", "deref_mut"];
+
+pub fn check(file: &syn::File, structs: &[AccountsStruct]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for handler in handlers(file) {
+        let Some((ctx, accounts)) = context_param(handler) else {
+            continue;
+        };
+        let Some(accounts) = structs.iter().find(|s| s.name == accounts) else {
+            continue;
+        };
+
+        let mut visitor = Writes {
+            ctx,
+            aliases: HashMap::new(),
+            writes: Vec::new(),
+        };
+        visitor.visit_block(&handler.block);
+
+        let name = handler.sig.ident.to_string();
+        for write in visitor.writes {
+            let Some(field) = accounts.field(&write.account) else {
+                continue;
+            };
+            if field.is_writable() {
+                continue;
+            }
+            let finding = Finding {
+                rule: Rule::NonMutWrite,
+                line: write.line,
+                item: name.clone(),
+                message: format!(
+                    "`{name}` {} `{}`, which `{}` doesn't mark `mut`",
+                    write.action, write.account, accounts.name
+                ),
+            };
+            if !findings.contains(&finding) {
+                findings.push(finding);
+            }
+        }
+    }
+    findings
+}
+
+/// Functions inside `#[program] mod ... { }`.
+fn handlers(file: &syn::File) -> impl Iterator<Item = &ItemFn> {
+    file.items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Mod(m) if m.attrs.iter().any(|a| a.path().is_ident("program")) => {
+                m.content.as_ref()
+            }
+            _ => None,
+        })
+        .flat_map(|(_, items)| items)
+        .filter_map(|item| match item {
+            Item::Fn(f) => Some(f),
+            _ => None,
+        })
+}
+
+/// `ctx: Context<Accounts>` → (`"ctx"`, `"Accounts"`).
+fn context_param(handler: &ItemFn) -> Option<(String, String)> {
+    let Some(FnArg::Typed(param)) = handler.sig.inputs.first() else {
+        return None;
+    };
+    let Pat::Ident(ctx) = &*param.pat else {
+        return None;
+    };
+    let Type::Path(ty) = &*param.ty else {
+        return None;
+    };
+    let segment = ty.path.segments.last().filter(|s| s.ident == "Context")?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(Type::Path(accounts)) => accounts
+            .path
+            .segments
+            .last()
+            .map(|s| (ctx.ident.to_string(), s.ident.to_string())),
+        _ => None,
+    })
+}
+
+struct Write {
+    account: String,
+    line: usize,
+    action: String,
+}
+
+struct Writes {
+    ctx: String,
+    /// Local name → the account field it refers to.
+    aliases: HashMap<String, String>,
+    writes: Vec<Write>,
+}
+
+impl Writes {
+    /// The `ctx.accounts` field `expr` is, or is a place inside.
+    fn account_of(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Field(field) if self.is_ctx_accounts(&field.base) => match &field.member {
+                syn::Member::Named(ident) => Some(ident.to_string()),
+                syn::Member::Unnamed(_) => None,
+            },
+            Expr::Field(field) => self.account_of(&field.base),
+            Expr::Index(index) => self.account_of(&index.expr),
+            Expr::Paren(paren) => self.account_of(&paren.expr),
+            Expr::Reference(reference) => self.account_of(&reference.expr),
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Deref(_)) => {
+                self.account_of(&unary.expr)
+            }
+            Expr::MethodCall(call) if PASS_THROUGH.iter().any(|m| call.method == m) => {
+                self.account_of(&call.receiver)
+            }
+            Expr::Path(path) => path
+                .path
+                .get_ident()
+                .and_then(|ident| self.aliases.get(&ident.to_string()))
+                .cloned(),
+            _ => None,
+        }
+    }
+
+    fn is_ctx_accounts(&self, expr: &Expr) -> bool {
+        let Expr::Field(field) = expr else {
+            return false;
+        };
+        let syn::Member::Named(member) = &field.member else {
+            return false;
+        };
+        member == "accounts"
+            && matches!(&*field.base, Expr::Path(p) if p.path.is_ident(self.ctx.as_str()))
+    }
+
+    fn record(&mut self, target: &Expr, at: &impl Spanned, action: String) {
+        if let Some(account) = self.account_of(target) {
+            self.writes.push(Write {
+                account,
+                line: at.span().start().line,
+                action,
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Writes {
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        if let Pat::Ident(binding) = &local.pat {
+            let name = binding.ident.to_string();
+            // Only a reference or an `AccountInfo` aliases the account; a
+            // copied field value doesn't.
+            let account = local.init.as_ref().and_then(|init| match &*init.expr {
+                Expr::Reference(_) | Expr::MethodCall(_) => self.account_of(&init.expr),
+                _ => None,
+            });
+            match account {
+                Some(account) => {
+                    self.aliases.insert(name, account);
+                }
+                None => {
+                    visit::visit_local(self, local);
+                    self.aliases.remove(&name);
+                }
+            }
+            return;
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_expr_assign(&mut self, assign: &'ast syn::ExprAssign) {
+        self.record(&assign.left, assign, "writes to".into());
+        visit::visit_expr_assign(self, assign);
+    }
+
+    fn visit_expr_binary(&mut self, binary: &'ast syn::ExprBinary) {
+        if is_compound_assign(&binary.op) {
+            self.record(&binary.left, binary, "writes to".into());
+        }
+        visit::visit_expr_binary(self, binary);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if MUTATING_METHODS.iter().any(|m| call.method == m) {
+            self.record(&call.receiver, call, format!("calls `{}` on", call.method));
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_expr_reference(&mut self, reference: &'ast syn::ExprReference) {
+        if reference.mutability.is_some() {
+            self.record(&reference.expr, reference, "borrows `&mut`".into());
+        }
+        visit::visit_expr_reference(self, reference);
+    }
+}
+
+fn is_compound_assign(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::AddAssign(_)
+            | BinOp::SubAssign(_)
+            | BinOp::MulAssign(_)
+            | BinOp::DivAssign(_)
+            | BinOp::RemAssign(_)
+            | BinOp::BitXorAssign(_)
+            | BinOp::BitAndAssign(_)
+            | BinOp::BitOrAssign(_)
+            | BinOp::ShlAssign(_)
+            | BinOp::ShrAssign(_)
+    )
+}
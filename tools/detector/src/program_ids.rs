@@ -0,0 +1,338 @@
+//! `program-id-mismatch`: a `declare_id!` or `Id::id()` that disagrees
+//! with the workspace's `program-ids.toml`.
+//!
+//! `Program<'info, T>` accepts the account whose key is `T::id()`, and
+//! `declare_id!` fixes the id a program checks itself against. Both are
+//! base58 strings nobody reads, so one pasted into the wrong place passes
+//! review and every check built on it checks the wrong program.
+//!
+//! The rule collects the file's ids: `declare_id!` at the top level and in
+//! nested modules, and `const`s set with `pubkey!`. Each is checked by
+//! name against the manifest:
+//!
+//! - a top-level `declare_id!` against the `#[program]` module's name
+//! - a `declare_id!` in `mod x` against `x`
+//! - an `impl Id for T` against the longest manifest name `T` starts
+//!   with, ignoring case and underscores (`AllowlistVulnerable` is
+//!   `allowlist`)
+//!
+//! `id()` is resolved when its body is a `pubkey!` literal or a path to one
+//! of the collected ids. Names the manifest doesn't list, and ids that
+//! can't be resolved, aren't reported.
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use syn::{spanned::Spanned, Expr, ImplItem, Item, ItemMod, Macro, Stmt, Type};
+
+use crate::{Finding, Rule};
+
+/// Program ids by name: the `[programs]` table of `program-ids.toml`.
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    programs: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Parse the `[programs]` table. Other tables are ignored; only
+    /// `name = "id"` lines are accepted in it.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut programs = BTreeMap::new();
+        let mut in_programs = false;
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_programs = line == "[programs]";
+                continue;
+            }
+            if !in_programs {
+                continue;
+            }
+            let entry = line.split_once('=').and_then(|(name, id)| {
+                let id = id.trim().strip_prefix('"')?.strip_suffix('"')?;
+                Some((name.trim().to_string(), id.to_string()))
+            });
+            match entry {
+                Some((name, id)) if !name.is_empty() && !id.is_empty() => {
+                    programs.insert(name, id);
+                }
+                _ => return Err(format!("line {}: expected `name = \"id\"`", n + 1)),
+            }
+        }
+        Ok(Self { programs })
+    }
+
+    /// Read and parse `path`. A missing file is an empty manifest.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.programs.get(name).map(String::as_str)
+    }
+
+    /// The name listed for `id`, if any.
+    pub fn name_of(&self, id: &str) -> Option<&str> {
+        self.programs
+            .iter()
+            .find(|(_, listed)| *listed == id)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The longest listed name `ident` starts with, ignoring case and
+    /// underscores.
+    fn named_in(&self, ident: &str) -> Option<&str> {
+        let ident = normalize(ident);
+        self.programs
+            .keys()
+            .filter(|name| ident.starts_with(&normalize(name)))
+            .max_by_key(|name| name.len())
+            .map(String::as_str)
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// An id the file declares, keyed by its path from the crate root
+/// (`ID`, `ids::allowlist::ID`, `REWARDS_ID`).
+struct Declared {
+    path: Vec<String>,
+    id: String,
+}
+
+pub fn check(file: &syn::File, manifest: &Manifest) -> Vec<Finding> {
+    let mut declared = Vec::new();
+    let mut findings = Vec::new();
+    let program = file.items.iter().find_map(|item| match item {
+        Item::Mod(m) if m.attrs.iter().any(|a| a.path().is_ident("program")) => {
+            Some(m.ident.to_string())
+        }
+        _ => None,
+    });
+    collect(
+        &file.items,
+        &[],
+        program.as_deref(),
+        manifest,
+        &mut declared,
+        &mut findings,
+    );
+    check_impls(&file.items, &[], manifest, &declared, &mut findings);
+    findings
+}
+
+/// Record the ids in `items` (under module path `module`) and check each
+/// `declare_id!` against the manifest entry for `owner`.
+fn collect(
+    items: &[Item],
+    module: &[String],
+    owner: Option<&str>,
+    manifest: &Manifest,
+    declared: &mut Vec<Declared>,
+    findings: &mut Vec<Finding>,
+) {
+    for item in items {
+        match item {
+            Item::Macro(item) if is_macro(&item.mac, "declare_id") => {
+                let Some(id) = literal(&item.mac) else {
+                    continue;
+                };
+                if let Some(owner) = owner {
+                    if let Some(message) = mismatch(manifest, owner, &id, "`declare_id!`") {
+                        findings.push(Finding {
+                            rule: Rule::ProgramIdMismatch,
+                            line: item.span().start().line,
+                            item: owner.to_string(),
+                            message,
+                        });
+                    }
+                }
+                declared.push(Declared {
+                    path: child(module, "ID"),
+                    id,
+                });
+            }
+            Item::Const(item) => {
+                if let Expr::Macro(expr) = &*item.expr {
+                    if is_macro(&expr.mac, "pubkey") {
+                        if let Some(id) = literal(&expr.mac) {
+                            declared.push(Declared {
+                                path: child(module, &item.ident.to_string()),
+                                id,
+                            });
+                        }
+                    }
+                }
+            }
+            Item::Mod(ItemMod {
+                ident,
+                content: Some((_, items)),
+                ..
+            }) => {
+                let name = ident.to_string();
+                collect(
+                    items,
+                    &child(module, &name),
+                    Some(&name),
+                    manifest,
+                    declared,
+                    findings,
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Check every `impl Id for T` in `items` against the manifest entry `T`
+/// is named for.
+fn check_impls(
+    items: &[Item],
+    module: &[String],
+    manifest: &Manifest,
+    declared: &[Declared],
+    findings: &mut Vec<Finding>,
+) {
+    for item in items {
+        match item {
+            Item::Impl(item) => {
+                let is_id = item
+                    .trait_
+                    .as_ref()
+                    .and_then(|(_, path, _)| path.segments.last())
+                    .is_some_and(|s| s.ident == "Id");
+                if !is_id {
+                    continue;
+                }
+                let Type::Path(ty) = &*item.self_ty else {
+                    continue;
+                };
+                let Some(marker) = ty.path.segments.last().map(|s| s.ident.to_string()) else {
+                    continue;
+                };
+                let Some(name) = manifest.named_in(&marker) else {
+                    continue;
+                };
+                let Some(body) = item.items.iter().find_map(|i| match i {
+                    ImplItem::Fn(f) if f.sig.ident == "id" => f.block.stmts.last(),
+                    _ => None,
+                }) else {
+                    continue;
+                };
+                let Some(id) = resolve(body, module, declared) else {
+                    continue;
+                };
+                let what = format!("`{marker}::id()`");
+                if let Some(message) = mismatch(manifest, name, &id, &what) {
+                    findings.push(Finding {
+                        rule: Rule::ProgramIdMismatch,
+                        line: body.span().start().line,
+                        item: marker,
+                        message,
+                    });
+                }
+            }
+            Item::Mod(ItemMod {
+                ident,
+                content: Some((_, items)),
+                ..
+            }) => check_impls(
+                items,
+                &child(module, &ident.to_string()),
+                manifest,
+                declared,
+                findings,
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// Why `id`, given by `what`, is wrong for `name`, if it is.
+fn mismatch(manifest: &Manifest, name: &str, id: &str, what: &str) -> Option<String> {
+    let expected = manifest.get(name)?;
+    if expected == id {
+        return None;
+    }
+    Some(match manifest.name_of(id) {
+        Some(other) => {
+            format!("{what} is `{other}`'s id {id}, but `{name}` is {expected} in program-ids.toml")
+        }
+        None => format!("{what} is {id}, but `{name}` is {expected} in program-ids.toml"),
+    })
+}
+
+/// The id an `id()` body's final expression evaluates to: a `pubkey!`
+/// literal, or a path to a declared id.
+fn resolve(stmt: &Stmt, module: &[String], declared: &[Declared]) -> Option<String> {
+    let expr = match stmt {
+        Stmt::Expr(expr, None) => expr,
+        Stmt::Macro(stmt) if stmt.semi_token.is_none() => {
+            return is_macro(&stmt.mac, "pubkey")
+                .then(|| literal(&stmt.mac))
+                .flatten()
+        }
+        _ => return None,
+    };
+    match expr {
+        Expr::Macro(expr) if is_macro(&expr.mac, "pubkey") => literal(&expr.mac),
+        Expr::Path(expr) => {
+            let mut segments: Vec<String> = expr
+                .path
+                .segments
+                .iter()
+                .map(|s| s.ident.to_string())
+                .collect();
+            let mut base = module.to_vec();
+            match segments.first().map(String::as_str) {
+                Some("crate") => {
+                    segments.remove(0);
+                    base.clear();
+                }
+                Some("self") => {
+                    segments.remove(0);
+                }
+                Some("super") => {
+                    segments.remove(0);
+                    base.pop();
+                }
+                _ => {}
+            }
+            let relative = [base, segments.clone()].concat();
+            declared
+                .iter()
+                .find(|d| d.path == relative)
+                .or_else(|| declared.iter().find(|d| d.path == segments))
+                .map(|d| d.id.clone())
+        }
+        _ => None,
+    }
+}
+
+fn is_macro(mac: &Macro, name: &str) -> bool {
+    mac.path.segments.last().is_some_and(|s| s.ident == name)
+}
+
+/// The string literal a `declare_id!` or `pubkey!` is called with.
+fn literal(mac: &Macro) -> Option<String> {
+    mac.parse_body::<syn::LitStr>().ok().map(|lit| lit.value())
+}
+
+fn child(module: &[String], name: &str) -> Vec<String> {
+    let mut path = module.to_vec();
+    path.push(name.to_string());
+    path
+}
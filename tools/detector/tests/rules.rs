@@ -0,0 +1,387 @@
+/// # Detector — rule tests
+///
+/// - `non-mut-write` flags assignments through `ctx.accounts.x` and through
+///   a local bound to it
+/// - Compound assignments, lamport changes, and `&mut` borrows handed to a
+///   helper are writes too
+/// - `mut`, `init`, `init_if_needed`, and `zero` all make an account writable
+/// - A copied field value isn't the account, and shadowing ends an alias
+/// - `close-target-alias` flags a close target that could be another
+///   writable account, unless a `!=` constraint or a `Signer` rules it out
+/// - `program-id-mismatch` flags a `declare_id!` or `Id::id()` whose id
+///   isn't the manifest's for the program it's named for, following
+///   `pubkey!` consts and `declare_id!` modules
+/// - Only `*_vulnerable` / `*Vulnerable` items count as expected
+/// - Every finding in the repo's patterns is in a vulnerable variant
+use std::{fs, path::Path};
+
+use detector::{check, Finding, Manifest, Rule};
+
+const SOURCE: &str = r#"use anchor_lang::prelude::*;
+
+#[program]
+pub mod demo {
+    use super::*;
+
+    pub fn alias_write(ctx: Context<ReadOnly>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        state.count = 1;
+        Ok(())
+    }
+
+    pub fn direct_write(ctx: Context<ReadOnly>, amount: u64) -> Result<()> {
+        ctx.accounts.state.count += amount;
+        ctx.accounts.state.to_account_info().sub_lamports(amount)?;
+        Ok(())
+    }
+
+    pub fn helper_write(ctx: Context<ReadOnly>) -> Result<()> {
+        bump_count(&mut ctx.accounts.state);
+        Ok(())
+    }
+
+    pub fn copies_only(ctx: Context<ReadOnly>) -> Result<()> {
+        let mut count = ctx.accounts.state.count;
+        count += 1;
+        let state = &mut ctx.accounts.state;
+        let mut state = Counter::default();
+        state.count = count;
+        Ok(())
+    }
+
+    pub fn writes_writable(ctx: Context<Writable>) -> Result<()> {
+        ctx.accounts.marked.count = 1;
+        ctx.accounts.created.count = 1;
+        ctx.accounts.maybe.count = 1;
+        ctx.accounts.zeroed.count = 1;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReadOnly<'info> {
+    #[account(has_one = authority)]
+    pub state: Account<'info, Counter>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Writable<'info> {
+    #[account(mut, has_one = authority)]
+    pub marked: Account<'info, Counter>,
+    #[account(init, payer = authority, space = 16)]
+    pub created: Account<'info, Counter>,
+    #[account(init_if_needed, payer = authority, space = 16)]
+    pub maybe: Account<'info, Counter>,
+    #[account(zero)]
+    pub zeroed: Account<'info, Counter>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAliased<'info> {
+    #[account(mut)]
+    pub queue: Account<'info, Counter>,
+    #[account(mut, close = recipient)]
+    pub ticket: Account<'info, Counter>,
+    /// CHECK: any account
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    pub config: Account<'info, Counter>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDistinct<'info> {
+    #[account(mut)]
+    pub queue: Account<'info, Counter>,
+    #[account(mut, close = recipient)]
+    pub ticket: Account<'info, Counter>,
+    /// CHECK: kept apart from the queue
+    #[account(mut, constraint = recipient.key() != queue.key())]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseToSigner<'info> {
+    #[account(mut)]
+    pub queue: Account<'info, Counter>,
+    #[account(mut, close = owner)]
+    pub ticket: Account<'info, Counter>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+"#;
+
+const IDS_SOURCE: &str = r#"use anchor_lang::prelude::*;
+
+declare_id!("Prog111111111111111111111111111111111111111");
+
+pub mod ids {
+    pub mod rewards {
+        anchor_lang::declare_id!("Rwds111111111111111111111111111111111111111");
+    }
+    pub mod points {
+        anchor_lang::declare_id!("Pnts111111111111111111111111111111111111111");
+    }
+}
+
+pub const POINTS_ID: Pubkey = pubkey!("Pnts111111111111111111111111111111111111111");
+
+#[program]
+pub mod demo {
+    use super::*;
+}
+
+#[derive(Clone)]
+pub struct RewardsVulnerable;
+
+impl Id for RewardsVulnerable {
+    fn id() -> Pubkey {
+        pubkey!("Pnts111111111111111111111111111111111111111")
+    }
+}
+
+#[derive(Clone)]
+pub struct RewardsProgram;
+
+impl Id for RewardsProgram {
+    fn id() -> Pubkey {
+        POINTS_ID
+    }
+}
+
+#[derive(Clone)]
+pub struct Rewards;
+
+impl Id for Rewards {
+    fn id() -> Pubkey {
+        ids::rewards::ID
+    }
+}
+
+#[derive(Clone)]
+pub struct Unlisted;
+
+impl Id for Unlisted {
+    fn id() -> Pubkey {
+        POINTS_ID
+    }
+}
+"#;
+
+const MANIFEST: &str = r#"# known ids
+[other]
+ignored = "x"
+
+[programs]
+demo = "Prog111111111111111111111111111111111111111"
+rewards = "Rwds111111111111111111111111111111111111111"
+points = "Pnts222222222222222222222222222222222222222"
+"#;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// (item, line) for every `rule` finding in `SOURCE`.
+fn found_by(rule: Rule) -> Vec<(String, usize)> {
+    check(SOURCE, &Manifest::default())
+        .unwrap()
+        .into_iter()
+        .filter(|f| f.rule == rule)
+        .map(|f| (f.item, f.line))
+        .collect()
+}
+
+fn found() -> Vec<(String, usize)> {
+    found_by(Rule::NonMutWrite)
+}
+
+fn line_of(needle: &str) -> usize {
+    SOURCE.lines().position(|l| l.contains(needle)).unwrap() + 1
+}
+
+/// Every `program-id-mismatch` finding in `IDS_SOURCE`.
+fn id_findings() -> Vec<Finding> {
+    let manifest = Manifest::parse(MANIFEST).unwrap();
+    check(IDS_SOURCE, &manifest)
+        .unwrap()
+        .into_iter()
+        .filter(|f| f.rule == Rule::ProgramIdMismatch)
+        .collect()
+}
+
+/// Line of the first occurrence of `needle` after the line containing
+/// `after`, in `IDS_SOURCE`.
+fn ids_line_of(after: &str, needle: &str) -> usize {
+    let start = IDS_SOURCE.lines().position(|l| l.contains(after)).unwrap();
+    start
+        + IDS_SOURCE
+            .lines()
+            .skip(start)
+            .position(|l| l.contains(needle))
+            .unwrap()
+        + 1
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn flags_write_through_alias() {
+    assert!(found().contains(&("alias_write".into(), line_of("state.count = 1;"))));
+}
+
+#[test]
+fn flags_compound_assign_and_lamports() {
+    let found = found();
+    assert!(found.contains(&("direct_write".into(), line_of("count += amount"))));
+    assert!(found.contains(&("direct_write".into(), line_of("sub_lamports"))));
+}
+
+#[test]
+fn flags_mut_borrow_passed_to_helper() {
+    assert!(found().contains(&("helper_write".into(), line_of("bump_count("))));
+}
+
+#[test]
+fn ignores_copies_and_shadowed_aliases() {
+    assert!(
+        !found().iter().any(|(handler, _)| handler == "copies_only"),
+        "{:#?}",
+        found()
+    );
+}
+
+#[test]
+fn writable_constraints_pass() {
+    assert!(!found()
+        .iter()
+        .any(|(handler, _)| handler == "writes_writable"));
+}
+
+#[test]
+fn finds_exactly_the_writes() {
+    assert_eq!(found().len(), 4, "{:#?}", found());
+}
+
+#[test]
+fn flags_close_target_aliasing_writable_account() {
+    let found = found_by(Rule::CloseTargetAlias);
+    assert_eq!(
+        found,
+        vec![("CloseAliased".into(), line_of("close = recipient"))],
+        "{found:#?}"
+    );
+}
+
+#[test]
+fn close_alias_message_names_both_accounts() {
+    let finding = check(SOURCE, &Manifest::default())
+        .unwrap()
+        .into_iter()
+        .find(|f| f.rule == Rule::CloseTargetAlias)
+        .unwrap();
+    assert!(finding.message.contains("`recipient`"));
+    assert!(finding.message.contains("`queue`"));
+    assert!(!finding.message.contains("`config`"));
+}
+
+#[test]
+fn flags_id_impl_returning_another_programs_id() {
+    let found: Vec<_> = id_findings()
+        .into_iter()
+        .map(|f| (f.item, f.line, f.message))
+        .collect();
+    let (_, line, message) = found
+        .iter()
+        .find(|(item, _, _)| item == "RewardsVulnerable")
+        .unwrap();
+    assert_eq!(*line, ids_line_of("for RewardsVulnerable", "pubkey!"));
+    assert!(message.contains("`RewardsVulnerable::id()`"), "{message}");
+    assert!(message.contains("`rewards`"), "{message}");
+}
+
+#[test]
+fn resolves_consts_and_declared_modules() {
+    let items: Vec<_> = id_findings().into_iter().map(|f| f.item).collect();
+    // POINTS_ID isn't `points`'s listed id, so it names no other program
+    assert!(items.contains(&"RewardsProgram".to_string()), "{items:#?}");
+    assert!(!items.contains(&"Rewards".to_string()), "{items:#?}");
+}
+
+#[test]
+fn flags_declare_id_disagreeing_with_manifest() {
+    let found: Vec<_> = id_findings()
+        .into_iter()
+        .filter(|f| f.item == "points")
+        .collect();
+    assert_eq!(found.len(), 1, "{found:#?}");
+    assert_eq!(found[0].line, ids_line_of("pub mod points", "declare_id!"));
+    // The program's own and `rewards`'s match
+    assert!(!id_findings()
+        .iter()
+        .any(|f| f.item == "demo" || f.item == "rewards"));
+}
+
+#[test]
+fn ignores_unlisted_names_and_empty_manifest() {
+    assert!(!id_findings().iter().any(|f| f.item == "Unlisted"));
+    assert_eq!(id_findings().len(), 3, "{:#?}", id_findings());
+    assert!(check(IDS_SOURCE, &Manifest::default()).unwrap().is_empty());
+}
+
+#[test]
+fn manifest_rejects_malformed_program_lines() {
+    let manifest = Manifest::parse(MANIFEST).unwrap();
+    assert_eq!(
+        manifest.get("rewards"),
+        Some("Rwds111111111111111111111111111111111111111")
+    );
+    assert_eq!(manifest.get("ignored"), None);
+    assert!(Manifest::parse("[programs]\nrewards = Rwds111\n").is_err());
+}
+
+#[test]
+fn classifies_vulnerable_variants() {
+    let finding = |item: &str| Finding {
+        rule: Rule::NonMutWrite,
+        line: 1,
+        item: item.into(),
+        message: String::new(),
+    };
+    assert!(finding("claim_vulnerable").is_vulnerable_variant());
+    assert!(finding("ClaimVulnerable").is_vulnerable_variant());
+    assert!(!finding("claim_secure").is_vulnerable_variant());
+    assert!(!finding("vulnerable_claim").is_vulnerable_variant());
+}
+
+#[test]
+fn repo_findings_are_all_vulnerable_variants() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let manifest = Manifest::load(&root.join("program-ids.toml")).unwrap();
+    let patterns = root.join("patterns");
+    let mut total = 0;
+    for entry in fs::read_dir(patterns).unwrap() {
+        let program = entry.unwrap().path().join("anchor/src/lib.rs");
+        if !program.is_file() {
+            continue;
+        }
+        let source = fs::read_to_string(&program).unwrap();
+        for finding in check(&source, &manifest).unwrap() {
+            assert!(
+                finding.is_vulnerable_variant(),
+                "{}:{}: {}",
+                program.display(),
+                finding.line,
+                finding.message
+            );
+            total += 1;
+        }
+    }
+    // Pattern 200's claim_vulnerable and 218's AllowlistVulnerable, at least.
+    assert!(total >= 2);
+}
@@ -0,0 +1,319 @@
+//! Learning paths through the patterns.
+//!
+//! Every pattern directory has a `pattern.toml` next to its README, with
+//! how hard the pattern is and which patterns to read first:
+//!
+//! ```toml
+//! difficulty = "intermediate"
+//! prerequisites = [5, 23]
+//! estimated_minutes = 25
+//! ```
+//!
+//! [`discover`] reads them all. [`learning_path`] orders patterns so each
+//! comes after its prerequisites, and [`to_json`] exports a path for
+//! course platforms.
+//!
+//! ## Why prerequisites and not just numbers
+//!
+//! Pattern numbers record when a pattern was added, not what it builds on.
+//! Pattern 33 (permanent delegate clawback) assumes pattern 195, and
+//! pattern 38 (state after CPI) assumes pattern 183. Reading in numeric
+//! order meets both the wrong way round.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::Path,
+    str::FromStr,
+};
+
+/// How much Solana a reader needs before the pattern makes sense.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    /// One missing check in one handler.
+    Beginner,
+    /// Needs the token programs, PDAs, or a second instruction in play.
+    Intermediate,
+    /// Several programs, CPIs, or a multi-step economic attack.
+    Advanced,
+}
+
+impl Difficulty {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Intermediate => "intermediate",
+            Difficulty::Advanced => "advanced",
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beginner" => Ok(Difficulty::Beginner),
+            "intermediate" => Ok(Difficulty::Intermediate),
+            "advanced" => Ok(Difficulty::Advanced),
+            other => Err(format!(
+                "unknown difficulty `{other}`, expected beginner, intermediate, or advanced"
+            )),
+        }
+    }
+}
+
+/// The contents of one `pattern.toml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    pub difficulty: Difficulty,
+    /// Pattern numbers to read first.
+    pub prerequisites: Vec<u32>,
+    /// Roughly how long reading the pattern and running its tests takes.
+    pub estimated_minutes: u32,
+}
+
+impl Metadata {
+    /// Parse `difficulty = "..."`, `prerequisites = [n, ...]`, and
+    /// `estimated_minutes = n`. All three keys are required and nothing else
+    /// is accepted; `#` starts a comment.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (mut difficulty, mut prerequisites, mut estimated_minutes) = (None, None, None);
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected `key = value`", n + 1));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "difficulty" => {
+                    let name = value
+                        .strip_prefix('"')
+                        .and_then(|v| v.strip_suffix('"'))
+                        .ok_or_else(|| format!("line {}: difficulty must be a string", n + 1))?;
+                    difficulty = Some(name.parse().map_err(|e| format!("line {}: {e}", n + 1))?);
+                }
+                "prerequisites" => {
+                    let list = value
+                        .strip_prefix('[')
+                        .and_then(|v| v.strip_suffix(']'))
+                        .ok_or_else(|| format!("line {}: prerequisites must be a list", n + 1))?;
+                    let numbers = list
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .map(|item| {
+                            item.parse().map_err(|_| {
+                                format!("line {}: `{item}` is not a pattern number", n + 1)
+                            })
+                        })
+                        .collect::<Result<Vec<u32>, _>>()?;
+                    prerequisites = Some(numbers);
+                }
+                "estimated_minutes" => {
+                    let minutes = value.parse().ok().filter(|m| *m > 0).ok_or_else(|| {
+                        format!(
+                            "line {}: estimated_minutes must be a positive number",
+                            n + 1
+                        )
+                    })?;
+                    estimated_minutes = Some(minutes);
+                }
+                other => return Err(format!("line {}: unknown key `{other}`", n + 1)),
+            }
+        }
+        Ok(Self {
+            difficulty: difficulty.ok_or("missing `difficulty`")?,
+            prerequisites: prerequisites.ok_or("missing `prerequisites`")?,
+            estimated_minutes: estimated_minutes.ok_or("missing `estimated_minutes`")?,
+        })
+    }
+}
+
+/// A pattern directory and its metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub number: u32,
+    /// The directory name under `patterns/`, e.g. `01-missing-signer-check`.
+    pub dir: String,
+    /// From the README's `# Pattern N: Title` line.
+    pub title: String,
+    pub difficulty: Difficulty,
+    pub prerequisites: Vec<u32>,
+    pub estimated_minutes: u32,
+}
+
+/// Every pattern under `root/patterns`, sorted by number. A pattern
+/// directory without a `pattern.toml` is an error, so a new pattern can't
+/// be left out of the paths by accident.
+pub fn discover(root: &Path) -> Result<Vec<Pattern>, String> {
+    let patterns_dir = root.join("patterns");
+    let entries =
+        fs::read_dir(&patterns_dir).map_err(|e| format!("{}: {e}", patterns_dir.display()))?;
+
+    let mut patterns = Vec::new();
+    for entry in entries {
+        let dir = entry.map_err(|e| e.to_string())?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+        let Some(number) = name.split('-').next().and_then(|n| n.parse().ok()) else {
+            continue;
+        };
+        let read = |file: &str| {
+            fs::read_to_string(dir.join(file)).map_err(|e| format!("{name}/{file}: {e}"))
+        };
+        let metadata = Metadata::parse(&read("pattern.toml")?)
+            .map_err(|e| format!("{name}/pattern.toml: {e}"))?;
+        let readme = read("README.md")?;
+        let title = readme
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("# Pattern "))
+            .and_then(|line| line.split_once(": "))
+            .map(|(_, title)| title.trim().to_string())
+            .ok_or_else(|| format!("{name}/README.md: expected `# Pattern N: Title` first"))?;
+        patterns.push(Pattern {
+            number,
+            dir: name,
+            title,
+            difficulty: metadata.difficulty,
+            prerequisites: metadata.prerequisites,
+            estimated_minutes: metadata.estimated_minutes,
+        });
+    }
+    patterns.sort_by_key(|p| p.number);
+    Ok(patterns)
+}
+
+/// Order patterns so that each comes after all of its prerequisites.
+///
+/// With no `targets`, every pattern is in the path. Otherwise the path is
+/// the targets and everything they need, transitively. Among patterns
+/// whose prerequisites are all met, the easiest comes first, then the
+/// lowest number, so the order is the same on every run.
+pub fn learning_path<'a>(
+    patterns: &'a [Pattern],
+    targets: &[u32],
+) -> Result<Vec<&'a Pattern>, String> {
+    let by_number: BTreeMap<u32, &Pattern> = patterns.iter().map(|p| (p.number, p)).collect();
+    for pattern in patterns {
+        for prerequisite in &pattern.prerequisites {
+            if !by_number.contains_key(prerequisite) {
+                return Err(format!(
+                    "pattern {} lists prerequisite {prerequisite}, which doesn't exist",
+                    pattern.number
+                ));
+            }
+        }
+    }
+
+    // The patterns in the path: the targets and their prerequisites.
+    let mut included = BTreeSet::new();
+    if targets.is_empty() {
+        included.extend(by_number.keys().copied());
+    } else {
+        let mut stack = Vec::new();
+        for target in targets {
+            if !by_number.contains_key(target) {
+                return Err(format!("no pattern {target}"));
+            }
+            stack.push(*target);
+        }
+        while let Some(number) = stack.pop() {
+            if included.insert(number) {
+                stack.extend(&by_number[&number].prerequisites);
+            }
+        }
+    }
+
+    // Kahn's algorithm over the included patterns.
+    let mut waiting: BTreeMap<u32, usize> = BTreeMap::new();
+    let mut unlocks: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for number in &included {
+        let pattern = by_number[number];
+        waiting.insert(*number, pattern.prerequisites.len());
+        for prerequisite in &pattern.prerequisites {
+            unlocks.entry(*prerequisite).or_default().push(*number);
+        }
+    }
+    let mut ready: BTreeSet<(Difficulty, u32)> = waiting
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(number, _)| (by_number[number].difficulty, *number))
+        .collect();
+
+    let mut path = Vec::with_capacity(included.len());
+    while let Some((_, number)) = ready.pop_first() {
+        path.push(by_number[&number]);
+        for next in unlocks.get(&number).into_iter().flatten() {
+            let count = waiting.get_mut(next).expect("included");
+            *count -= 1;
+            if *count == 0 {
+                ready.insert((by_number[next].difficulty, *next));
+            }
+        }
+    }
+
+    if path.len() < included.len() {
+        let stuck: Vec<String> = waiting
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(number, _)| number.to_string())
+            .collect();
+        return Err(format!(
+            "prerequisites form a cycle through patterns {}",
+            stuck.join(", ")
+        ));
+    }
+    Ok(path)
+}
+
+/// A path as a JSON array, in order. Each entry has `step` (from 1),
+/// `number`, `dir`, `title`, `difficulty`, `prerequisites`, and
+/// `estimated_minutes`.
+pub fn to_json(path: &[&Pattern]) -> String {
+    let mut out = String::from("[");
+    for (i, pattern) in path.iter().enumerate() {
+        let prerequisites: Vec<String> = pattern.prerequisites.iter().map(u32::to_string).collect();
+        out.push_str(if i == 0 { "\n" } else { ",\n" });
+        out.push_str(&format!(
+            "  {{\"step\": {}, \"number\": {}, \"dir\": {}, \"title\": {}, \"difficulty\": {}, \"prerequisites\": [{}], \"estimated_minutes\": {}}}",
+            i + 1,
+            pattern.number,
+            json_string(&pattern.dir),
+            json_string(&pattern.title),
+            json_string(pattern.difficulty.as_str()),
+            prerequisites.join(", "),
+            pattern.estimated_minutes
+        ));
+    }
+    out.push_str(if path.is_empty() { "]\n" } else { "\n]\n" });
+    out
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,102 @@
+//! Usage, from the repo root:
+//!
+//!   cargo run -p patterns-cli -- path [N...]
+//!   cargo run -p patterns-cli -- export [--out FILE] [N...]
+//!
+//! `path` prints a reading order in which every pattern comes after its
+//! prerequisites. `export` writes the same order as JSON, to stdout or
+//! `FILE`. With pattern numbers, the path covers those patterns and what
+//! they need; without, every pattern.
+
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use patterns_cli::{discover, learning_path, to_json};
+
+enum Command {
+    Path,
+    Export { out: Option<PathBuf> },
+}
+
+struct Args {
+    command: Command,
+    targets: Vec<u32>,
+}
+
+const USAGE: &str = "usage: patterns-cli path [N...] | patterns-cli export [--out FILE] [N...]";
+
+fn parse_args() -> Result<Args, String> {
+    let mut iter = env::args().skip(1);
+    let mut command = match iter.next().as_deref() {
+        Some("path") => Command::Path,
+        Some("export") => Command::Export { out: None },
+        Some(other) => return Err(format!("unknown command {other}")),
+        None => return Err("missing command".into()),
+    };
+    let mut targets = Vec::new();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), &mut command) {
+            ("--out", Command::Export { out }) => {
+                *out = Some(iter.next().ok_or("--out needs a path")?.into());
+            }
+            (flag, _) if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+            (number, _) => targets.push(
+                number
+                    .parse()
+                    .map_err(|_| format!("`{number}` is not a pattern number"))?,
+            ),
+        }
+    }
+    Ok(Args { command, targets })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let root = env::current_dir().expect("current dir");
+    let patterns = match discover(&root) {
+        Ok(patterns) => patterns,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let path = match learning_path(&patterns, &args.targets) {
+        Ok(path) => path,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match args.command {
+        Command::Path => {
+            for (i, pattern) in path.iter().enumerate() {
+                println!(
+                    "{:>3}. {:>3}  {} ({}, {} min)",
+                    i + 1,
+                    pattern.number,
+                    pattern.title,
+                    pattern.difficulty,
+                    pattern.estimated_minutes
+                );
+            }
+            let minutes: u32 = path.iter().map(|p| p.estimated_minutes).sum();
+            println!("{} patterns, {minutes} min", path.len());
+        }
+        Command::Export { out: None } => print!("{}", to_json(&path)),
+        Command::Export { out: Some(out) } => {
+            if let Err(err) = fs::write(&out, to_json(&path)) {
+                eprintln!("error: {}: {err}", out.display());
+                return ExitCode::FAILURE;
+            }
+            eprintln!("{} patterns written to {}", path.len(), out.display());
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,149 @@
+/// # Patterns CLI — learning path tests
+///
+/// - `pattern.toml` parses all three keys and rejects unknown keys and values
+/// - Every pattern comes after its prerequisites; ties go to the easiest,
+///   then the lowest number
+/// - A path to a target holds the target and its prerequisites, no more
+/// - Unknown prerequisites, unknown targets, and cycles are errors
+/// - JSON export escapes strings and numbers the steps
+/// - Every pattern in the repo has metadata, and the full path orders them
+use std::path::Path;
+
+use patterns_cli::{discover, learning_path, to_json, Difficulty, Metadata, Pattern};
+
+fn pattern(number: u32, difficulty: Difficulty, prerequisites: &[u32]) -> Pattern {
+    Pattern {
+        number,
+        dir: format!("{number:02}-demo"),
+        title: format!("Demo {number}"),
+        difficulty,
+        prerequisites: prerequisites.to_vec(),
+        estimated_minutes: 20,
+    }
+}
+
+fn numbers(path: &[&Pattern]) -> Vec<u32> {
+    path.iter().map(|p| p.number).collect()
+}
+
+#[test]
+fn metadata_parses_and_rejects() {
+    let metadata = Metadata::parse(
+        "# comment\ndifficulty = \"advanced\"  # why\nprerequisites = [5, 23]\nestimated_minutes = 40\n",
+    )
+    .unwrap();
+    assert_eq!(metadata.difficulty, Difficulty::Advanced);
+    assert_eq!(metadata.prerequisites, vec![5, 23]);
+    assert_eq!(metadata.estimated_minutes, 40);
+
+    let empty =
+        Metadata::parse("difficulty = \"beginner\"\nprerequisites = []\nestimated_minutes = 15")
+            .unwrap();
+    assert!(empty.prerequisites.is_empty());
+
+    let parse = |text: &str| Metadata::parse(&format!("{text}\nestimated_minutes = 15"));
+    assert!(parse("difficulty = \"easy\"\nprerequisites = []").is_err());
+    assert!(parse("difficulty = \"beginner\"\nprerequisites = [x]").is_err());
+    assert!(parse("difficulty = \"beginner\"\nprerequisites = []\nminutes = 5").is_err());
+    assert!(parse("prerequisites = []").is_err());
+    assert!(parse("difficulty = \"beginner\"").is_err());
+
+    let minutes = |value: &str| {
+        Metadata::parse(&format!(
+            "difficulty = \"beginner\"\nprerequisites = []\nestimated_minutes = {value}"
+        ))
+    };
+    assert!(minutes("0").is_err());
+    assert!(minutes("\"15\"").is_err());
+    assert!(Metadata::parse("difficulty = \"beginner\"\nprerequisites = []").is_err());
+}
+
+#[test]
+fn prerequisites_come_first() {
+    use Difficulty::*;
+    // 3 needs 9, which is numbered later; 4 is advanced with nothing needed.
+    let patterns = [
+        pattern(1, Beginner, &[]),
+        pattern(3, Intermediate, &[9]),
+        pattern(4, Advanced, &[]),
+        pattern(9, Beginner, &[1]),
+        pattern(12, Intermediate, &[]),
+    ];
+    let path = learning_path(&patterns, &[]).unwrap();
+    assert_eq!(numbers(&path), vec![1, 9, 3, 12, 4]);
+}
+
+#[test]
+fn target_path_holds_only_what_it_needs() {
+    use Difficulty::*;
+    let patterns = [
+        pattern(1, Beginner, &[]),
+        pattern(2, Beginner, &[]),
+        pattern(5, Intermediate, &[1]),
+        pattern(8, Advanced, &[5, 2]),
+        pattern(9, Beginner, &[]),
+    ];
+    assert_eq!(
+        numbers(&learning_path(&patterns, &[8]).unwrap()),
+        vec![1, 2, 5, 8]
+    );
+    assert_eq!(
+        numbers(&learning_path(&patterns, &[5, 9]).unwrap()),
+        vec![1, 9, 5]
+    );
+}
+
+#[test]
+fn bad_graphs_are_errors() {
+    use Difficulty::*;
+    let unknown = [pattern(1, Beginner, &[7])];
+    let err = learning_path(&unknown, &[]).unwrap_err();
+    assert!(err.contains("prerequisite 7"), "{err}");
+
+    let patterns = [pattern(1, Beginner, &[])];
+    assert!(learning_path(&patterns, &[2]).is_err());
+
+    let cycle = [
+        pattern(1, Beginner, &[]),
+        pattern(2, Beginner, &[3]),
+        pattern(3, Beginner, &[2]),
+    ];
+    let err = learning_path(&cycle, &[]).unwrap_err();
+    assert!(err.ends_with("2, 3"), "{err}");
+}
+
+#[test]
+fn json_export() {
+    let mut quoted = pattern(7, Difficulty::Intermediate, &[1, 2]);
+    quoted.title = "A \"quoted\" \\ title".into();
+    let first = pattern(1, Difficulty::Beginner, &[]);
+
+    let json = to_json(&[&first, &quoted]);
+    assert_eq!(
+        json,
+        "[\n  {\"step\": 1, \"number\": 1, \"dir\": \"01-demo\", \"title\": \"Demo 1\", \
+         \"difficulty\": \"beginner\", \"prerequisites\": [], \"estimated_minutes\": 20},\n  \
+         {\"step\": 2, \"number\": 7, \"dir\": \"07-demo\", \"title\": \"A \\\"quoted\\\" \\\\ title\", \
+         \"difficulty\": \"intermediate\", \"prerequisites\": [1, 2], \"estimated_minutes\": 20}\n]\n"
+    );
+    assert_eq!(to_json(&[]), "[]\n");
+}
+
+#[test]
+fn repo_metadata_is_complete() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let patterns = discover(&root).unwrap();
+    assert!(!patterns.is_empty());
+
+    let path = learning_path(&patterns, &[]).unwrap();
+    assert_eq!(path.len(), patterns.len());
+    for (i, pattern) in path.iter().enumerate() {
+        for prerequisite in &pattern.prerequisites {
+            assert!(
+                path[..i].iter().any(|p| p.number == *prerequisite),
+                "pattern {} comes before its prerequisite {prerequisite}",
+                pattern.number
+            );
+        }
+    }
+}